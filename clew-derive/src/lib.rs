@@ -47,12 +47,39 @@ pub fn derive_identifiable(input: TokenStream) -> TokenStream {
     TokenStream::from(expanded)
 }
 
-#[proc_macro_derive(WidgetState)]
+/// Derive macro for WidgetState, `#[widget_state(serializable)]` exposes the
+/// `SerializableWidgetState` implementation of the type to the UI state snapshots.
+#[proc_macro_derive(WidgetState, attributes(widget_state))]
 pub fn derive_widget_state(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as DeriveInput);
     let name = &input.ident;
     let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
 
+    let serializable = input.attrs.iter().any(|attr| {
+        attr.path().is_ident("widget_state")
+            && attr
+                .parse_args::<syn::Ident>()
+                .is_ok_and(|ident| ident == "serializable")
+    });
+
+    let serializable_impl = if serializable {
+        quote! {
+            #[inline]
+            fn as_serializable(&self) -> Option<&dyn ::clew::snapshot::SerializableWidgetState> {
+                Some(self)
+            }
+
+            #[inline]
+            fn as_serializable_mut(
+                &mut self,
+            ) -> Option<&mut dyn ::clew::snapshot::SerializableWidgetState> {
+                Some(self)
+            }
+        }
+    } else {
+        quote! {}
+    };
+
     let expanded = quote! {
         impl #impl_generics ::clew::prelude::WidgetState for #name #ty_generics #where_clause {
             #[inline]
@@ -69,6 +96,8 @@ pub fn derive_widget_state(input: TokenStream) -> TokenStream {
             fn into_any(self: Box<Self>) -> Box<dyn std::any::Any> {
                 self
             }
+
+            #serializable_impl
         }
     };
 
@@ -0,0 +1,54 @@
+use clew::{Announcement, Politeness};
+
+/// Hands the announcement to the screen reader. Delivered only with the `accessibility`
+/// feature on macOS, where VoiceOver reads the announcements of the application without
+/// an accessibility tree. Elsewhere the screen readers only read the announcements of
+/// the elements of such a tree, the announcements are dropped there.
+#[cfg(all(feature = "accessibility", target_os = "macos"))]
+pub(crate) fn deliver(announcement: &Announcement) {
+    use objc2::{MainThreadMarker, runtime::AnyObject};
+    use objc2_app_kit::{
+        NSAccessibilityAnnouncementKey, NSAccessibilityAnnouncementRequestedNotification,
+        NSAccessibilityPostNotificationWithUserInfo, NSAccessibilityPriorityKey,
+        NSAccessibilityPriorityLevel, NSApp,
+    };
+    use objc2_foundation::{NSDictionary, NSNumber, NSString};
+
+    let Some(mtm) = MainThreadMarker::new() else {
+        return;
+    };
+
+    let priority = match announcement.politeness {
+        Politeness::Polite => NSAccessibilityPriorityLevel::Medium,
+        Politeness::Assertive => NSAccessibilityPriorityLevel::High,
+    };
+    let text = NSString::from_str(&announcement.text);
+    let priority = NSNumber::new_isize(priority.0);
+    let values: [&AnyObject; 2] = [&text, &priority];
+
+    unsafe {
+        let user_info = NSDictionary::from_slices(
+            &[NSAccessibilityAnnouncementKey, NSAccessibilityPriorityKey],
+            &values,
+        );
+
+        NSAccessibilityPostNotificationWithUserInfo(
+            &NSApp(mtm),
+            NSAccessibilityAnnouncementRequestedNotification,
+            Some(&*user_info),
+        );
+    }
+}
+
+#[cfg(not(all(feature = "accessibility", target_os = "macos")))]
+pub(crate) fn deliver(announcement: &Announcement) {
+    let politeness = match announcement.politeness {
+        Politeness::Polite => "polite",
+        Politeness::Assertive => "assertive",
+    };
+
+    log::debug!(
+        "No screen reader to deliver the {politeness} announcement to: {}",
+        announcement.text
+    );
+}
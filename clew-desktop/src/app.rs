@@ -1,26 +1,29 @@
-use std::any::{Any, TypeId};
+use std::any::TypeId;
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
 use clew::assets::Assets;
-use clew::io::{Cursor, TextInputAction};
-use clew::keyboard::{KeyCode, KeyModifiers};
-use clew::lifecycle::{finalize_cycle, init_cycle};
+use clew::io::{Cursor, InputEvent, UserInput};
+use clew::keyboard::KeyCode;
 use clew::render::Renderer;
 use clew::shortcuts::ShortcutsManager;
-use clew::text::{FontResources, StringInterner};
-use clew::widgets::builder::{ApplicationEvent, ApplicationEventLoopProxy, BuildContext};
-use clew::{PhysicalSize, Rect, ShortcutsRegistry};
+use clew::widgets::builder::{ApplicationEvent, ApplicationEventLoopProxy};
+use clew::widgets::editable_text::OsEvent;
+use clew::{ClewError, ClewHost, PhysicalSize, Resources, ShortcutsRegistry, SystemTheme, ViewId};
 
-use crate::keyboard::{from_winit_key_code, from_winit_modifiers};
+use crate::announcement;
+use crate::input::to_input_event;
+use crate::resize::FrozenResize;
 use crate::window_manager::WindowManager;
-use crate::window_manager::WindowState;
 #[cfg(target_os = "macos")]
 use winit::platform::macos::EventLoopBuilderExtMacOS;
 
 pub trait ApplicationDelegate<Event> {
     fn init_assets(&mut self, _assets: &mut Assets) {}
 
+    /// Registers the resources shared by all the windows, see [`clew::resources`].
+    fn init_resources(&mut self, _resources: &mut Resources) {}
+
     fn on_start(
         &mut self,
         window_manager: &mut WindowManager<Self, Event>,
@@ -36,31 +39,57 @@ pub trait ApplicationDelegate<Event> {
     {
     }
 
-    fn create_renderer(window: Arc<winit::window::Window>) -> Box<dyn Renderer>;
+    fn on_window_focus_changed(&mut self, _view_id: ViewId, _focused: bool) {}
+
+    /// The system has switched between dark and light, the windows are drawn in the
+    /// new theme from their next frames. The initial one is
+    /// [`WindowManager::system_theme`].
+    fn on_system_theme_changed(&mut self, _system_theme: SystemTheme) {}
+
+    /// Time without user input after which a window is idle.
+    fn idle_threshold(&self) -> Duration {
+        Duration::from_secs(60)
+    }
+
+    /// The window has had no user input for the threshold, called once until the next
+    /// input.
+    fn on_idle(&mut self, _view_id: ViewId, _threshold: Duration) {}
+
+    /// The first user input of an idle window.
+    fn on_idle_ended(&mut self, _view_id: ViewId) {}
+
+    /// Stops the animations of the windows without the focus, an unfocused window that
+    /// is idle isn't redrawn until something wakes it.
+    fn pause_when_unfocused(&self) -> bool {
+        false
+    }
+
+    /// The renderer of the view has failed to set up after it was created, see
+    /// [`Renderer::poll_ready`]. The view isn't drawn until the renderer is replaced,
+    /// e.g. with [`WindowManager::replace_renderer`].
+    fn on_renderer_error(
+        &mut self,
+        _window_manager: &mut WindowManager<Self, Event>,
+        _view_id: ViewId,
+        err: ClewError,
+    ) where
+        Self: std::marker::Sized,
+    {
+        log::error!("{err}");
+    }
+
+    fn create_renderer(window: Arc<winit::window::Window>) -> Result<Box<dyn Renderer>, ClewError>;
 }
 
 pub struct Application<'a, T: ApplicationDelegate<Event>, Event = ()> {
     app: T,
     window_manager: WindowManager<'a, T, Event>,
-    fonts: FontResources,
-    assets: Assets<'a>,
-    string_interner: StringInterner,
+    host: ClewHost<'a>,
     last_cursor: Cursor,
-    last_ime_rect: Rect,
-    ime_activated: bool,
-    ime_reset_needed: bool,
-    modifiers: Option<KeyModifiers>,
-    key_code: Option<KeyCode>,
-    key_code_repeat: Option<KeyCode>,
-    key_event_handled: bool,
-    broadcast_event_queue: Vec<Arc<dyn Any + Send>>,
-    broadcast_async_tx: tokio::sync::mpsc::UnboundedSender<Box<dyn Any + Send>>,
-    broadcast_async_rx: tokio::sync::mpsc::UnboundedReceiver<Box<dyn Any + Send>>,
-    event_loop_proxy: Arc<WinitEventLoopProxy>,
-    force_redraw: bool,
-    needs_redraw: bool,
     shortcuts_manager: ShortcutsManager,
     shortcuts_registry: ShortcutsRegistry,
+    /// Taken when the first frame is presented.
+    started: Option<Instant>,
 }
 
 pub struct WinitEventLoopProxy {
@@ -73,81 +102,28 @@ impl ApplicationEventLoopProxy for WinitEventLoopProxy {
     }
 }
 
-#[allow(clippy::too_many_arguments)]
-fn build<'a, T: ApplicationDelegate<Event>, Event: 'static>(
-    app: &mut T,
-    fonts: &mut FontResources,
-    assets: &Assets,
-    string_interner: &mut StringInterner,
-    broadcast_event_queue: &mut Vec<Arc<dyn Any + Send>>,
-    broadcast_async_tx: &mut tokio::sync::mpsc::UnboundedSender<Box<dyn Any + Send>>,
-    window_state: &mut WindowState<'a, T, Event>,
-    event_loop_proxy: Arc<WinitEventLoopProxy>,
-    force_redraw: bool,
-) -> bool {
-    init_cycle(&mut window_state.ui_state);
-
-    for event_box in window_state.ui_state.current_event_queue.iter() {
-        // Skip event processing for () type
-        if TypeId::of::<Event>() != TypeId::of::<()>()
-            && let Some(event) = event_box.downcast_ref::<Event>()
-        {
-            window_state.window.on_event(app, event);
-        }
-    }
-
-    for event_box in broadcast_event_queue.iter() {
-        window_state
-            .ui_state
-            .current_event_queue
-            .push(event_box.clone());
-    }
-
-    broadcast_event_queue.clear();
-
-    let mut build_context = BuildContext::new(
-        &mut window_state.ui_state,
-        &mut window_state.texts,
-        fonts,
-        broadcast_event_queue,
-        broadcast_async_tx,
-        event_loop_proxy,
-        window_state.delta_time_timer.elapsed().as_secs_f32(),
-    );
-
-    window_state.delta_time_timer = Instant::now();
-
-    window_state.window.build(app, &mut build_context);
-
-    let redraw = clew::render(
-        &mut window_state.ui_state,
-        &mut window_state.texts,
-        fonts,
-        assets,
-        string_interner,
-        &mut window_state.strings,
-        force_redraw,
-    );
-
-    finalize_cycle(&mut window_state.ui_state);
-
-    redraw
-}
-
 impl<T: ApplicationDelegate<Event>, Event: 'static>
     winit::application::ApplicationHandler<ApplicationEvent> for Application<'_, T, Event>
 {
     fn resumed(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
+        self.window_manager
+            .set_system_theme(event_loop.system_theme());
         self.window_manager
             .with_event_loop(event_loop, |window_manager| {
                 self.app
                     .on_start(window_manager, &mut self.shortcuts_registry);
             });
 
+        if self.window_manager.error.is_some() {
+            event_loop.exit();
+            return;
+        }
+
         for window in self.window_manager.windows.values_mut() {
             window
-                .ui_state
+                .instance
+                .ui_state()
                 .shortcuts_registry()
                 .merge_with(&self.shortcuts_registry);
         }
@@ -158,17 +134,114 @@ impl<T: ApplicationDelegate<Event>, Event: 'static>
             ApplicationEvent::Wake { view_id } => {
                 self.window_manager.request_view_redraw(view_id);
             }
+            ApplicationEvent::RunOnMainThread(task) => {
+                task.run();
+            }
+            ApplicationEvent::AssetLoaded => {
+                self.window_manager.request_redraw_all();
+            }
         }
     }
 
     fn about_to_wait(&mut self, event_loop: &winit::event_loop::ActiveEventLoop) {
         event_loop.set_control_flow(winit::event_loop::ControlFlow::Poll);
 
+        // The render commands of an asset don't change once it's loaded, repaint
+        // everything in place of its placeholder.
+        if self.host.assets.receive_loaded() {
+            for window in self.window_manager.windows.values_mut() {
+                window.instance.ui_state().render_state.invalidate_layers();
+                window.instance.invalidate();
+            }
+        }
+
+        // All the events of this iteration have been received, the windows with new
+        // input are drawn right away rather than in the next iteration, along with the
+        // windows that are moving, so their frames are laid out at the same time.
+        let mut frame_windows: Vec<_> = self
+            .window_manager
+            .windows
+            .iter()
+            .filter(|(_, window)| window.instance.has_pending_input())
+            .map(|(id, _)| *id)
+            .collect();
+        let input_windows = frame_windows.len();
+
+        let idle_threshold = self.app.idle_threshold();
+        let mut redraw_requested = !frame_windows.is_empty();
+        let now = Instant::now();
+        let mut wake_at: Option<Instant> = None;
+        let mut wake_at_earliest = |at: Instant| {
+            wake_at = Some(wake_at.map_or(at, |other: Instant| other.min(at)));
+        };
+
         // Request redraw for all windows that need it
-        for (_, window) in self.window_manager.windows.iter_mut() {
-            // if self.needs_redraw {
-            window.winit_window.request_redraw();
-            // }
+        for (id, window) in self.window_manager.windows.iter_mut() {
+            let ui_state = window.instance.ui_state();
+            let user_input = &mut ui_state.user_input;
+
+            let idle_started = user_input.idle_timer.check(idle_threshold);
+
+            if idle_started {
+                self.app.on_idle(ui_state.view.id, idle_threshold);
+            }
+
+            // A window resized with a frozen frame is redrawn by its resize events, and
+            // once they stop to build the frame for the final size.
+            if let Some(frozen) = &window.frozen_resize {
+                if frozen.is_settled(now) {
+                    window.winit_window.request_redraw();
+                    redraw_requested = true;
+                } else {
+                    wake_at_earliest(frozen.settles_at());
+                }
+
+                continue;
+            }
+
+            if is_paused(&self.app, user_input) && user_input.idle_timer.is_idle() {
+                continue;
+            }
+
+            if frame_windows[..input_windows].contains(id) {
+                continue;
+            }
+
+            // The frames of a window that has settled are repainted without building
+            // them, until it's asked for a frame or gets input. The app may have changed
+            // its state once the window went idle.
+            if idle_started || window.instance.needs_build(&self.host) {
+                frame_windows.push(*id);
+                redraw_requested = true;
+            } else {
+                if window.instance.repaint().is_some() {
+                    window
+                        .instance
+                        .render(&self.host, window.renderer.as_mut(), window.fill_color);
+                }
+
+                if let Some(at) = window.instance.next_repaint() {
+                    wake_at_earliest(at);
+                }
+
+                let user_input = &window.instance.ui_state().user_input;
+
+                if !user_input.idle_timer.is_idle() {
+                    wake_at_earliest(
+                        now + idle_threshold.saturating_sub(user_input.idle_timer.idle_duration()),
+                    );
+                }
+            }
+        }
+
+        self.frames(&frame_windows);
+
+        // Every window is paused or settled, wait for an event rather than spin.
+        if !redraw_requested {
+            event_loop.set_control_flow(match wake_at {
+                Some(at) => winit::event_loop::ControlFlow::WaitUntil(at),
+                None => winit::event_loop::ControlFlow::Wait,
+            });
         }
     }
 
@@ -178,12 +251,7 @@ impl<T: ApplicationDelegate<Event>, Event: 'static>
         window_id: winit::window::WindowId,
         event: winit::event::WindowEvent,
     ) {
-        // Collect async events
-        while let Ok(event) = self.broadcast_async_rx.try_recv() {
-            self.broadcast_event_queue.push(event.into());
-        }
-
-        for event_box in self.broadcast_event_queue.iter() {
+        for event_box in self.host.broadcast_events() {
             if let Some(event) = event_box.downcast_ref::<Event>() {
                 self.app.on_event(&mut self.window_manager, event);
 
@@ -194,15 +262,14 @@ impl<T: ApplicationDelegate<Event>, Event: 'static>
         }
 
         if !matches!(event, winit::event::WindowEvent::RedrawRequested) {
-            self.broadcast_event_queue.clear();
+            self.host.clear_broadcast_events();
         }
 
         let window = self.window_manager.get_mut_window(window_id).unwrap();
-        let input_cursor = window.ui_state.user_input.cursor;
+        let view_id = window.instance.view().id;
+        let input_cursor = window.instance.ui_state().user_input.cursor;
 
-        if self.last_cursor != input_cursor
-        /* || ui_state.parameters.should_update_cursor_each_frame*/
-        {
+        if self.last_cursor != input_cursor {
             let cursor = match input_cursor {
                 Cursor::Default => winit::window::CursorIcon::Default,
                 Cursor::Pointer => winit::window::CursorIcon::Pointer,
@@ -219,245 +286,313 @@ impl<T: ApplicationDelegate<Event>, Event: 'static>
             self.last_cursor = input_cursor;
         }
 
+        if let Some(input) = to_input_event(&event) {
+            if let winit::event::WindowEvent::Focused(focused) = event {
+                window.winit_window.request_redraw();
+                self.app.on_window_focus_changed(view_id, focused);
+
+                if !focused {
+                    window.winit_window.set_cursor(winit::window::Cursor::Icon(
+                        winit::window::CursorIcon::Default,
+                    ));
+                    self.last_cursor = Cursor::Default;
+                }
+            }
+
+            if let InputEvent::Key {
+                key_code: Some(KeyCode::Escape),
+                pressed: true,
+                ..
+            } = input
+            {
+                event_loop.exit();
+            }
+
+            if window.instance.push_event(input) {
+                self.app.on_idle_ended(view_id);
+            }
+
+            return;
+        }
+
         match event {
             winit::event::WindowEvent::CloseRequested => {
+                self.window_manager.remember_geometry(window_id);
                 event_loop.exit();
             }
             winit::event::WindowEvent::Resized(size) => {
-                window.ui_state.view.size = PhysicalSize::new(size.width, size.height);
-                self.force_redraw = true;
-
-                window.ui_state.user_input.mouse_left_pressed = false;
-                window.ui_state.user_input.mouse_right_pressed = false;
-                window.ui_state.user_input.mouse_middle_pressed = false;
-                window.ui_state.user_input.mouse_left_released = false;
-                window.ui_state.user_input.mouse_right_released = false;
-                window.ui_state.user_input.mouse_middle_released = false;
-                window.ui_state.user_input.mouse_pressed = false;
-                window.ui_state.user_input.mouse_released = false;
-                window.ui_state.user_input.mouse_x = -1.;
-                window.ui_state.user_input.mouse_y = -1.;
-                window.ui_state.user_input.mouse_wheel_delta_x = 0.;
-                window.ui_state.user_input.mouse_wheel_delta_y = 0.;
-                window.ui_state.user_input.mouse_left_click_count = 0;
+                // The frame built before the resize is drawn until the resize ends.
+                let built = !window
+                    .instance
+                    .ui_state()
+                    .render_state
+                    .commands()
+                    .is_empty();
+
+                if window.frozen_resize.is_none() && built {
+                    let view = window.instance.view();
+
+                    window.frozen_resize = FrozenResize::start(
+                        window.resize_policy,
+                        view.size.clone(),
+                        view.scale_factor,
+                    );
+                }
 
+                if let Some(frozen) = &mut window.frozen_resize {
+                    frozen.on_resize();
+                }
+
+                window
+                    .instance
+                    .resize(PhysicalSize::new(size.width, size.height));
                 self.window_manager.request_redraw(window_id);
+                self.window_manager.remember_geometry(window_id);
+            }
+            winit::event::WindowEvent::Moved(_) => {
+                self.window_manager.remember_geometry(window_id);
+            }
+            winit::event::WindowEvent::ThemeChanged(theme) => {
+                if let Some(system_theme) = self.window_manager.set_system_theme(Some(theme)) {
+                    self.app.on_system_theme_changed(system_theme);
+                }
             }
             winit::event::WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
-                window.ui_state.view.scale_factor = scale_factor as f32;
-                window
-                    .texts
-                    .update_view(&window.ui_state.view, &mut self.fonts);
+                window.instance.set_scale_factor(scale_factor as f32);
+
+                if let Some(frozen) = &mut window.frozen_resize {
+                    frozen.on_resize();
+                }
 
-                self.force_redraw = true;
+                self.window_manager.request_redraw(window_id);
+            }
+            // Frames only repaint what has changed, the content of an uncovered window
+            // may be gone.
+            winit::event::WindowEvent::Occluded(false) => {
+                window.instance.invalidate();
                 self.window_manager.request_redraw(window_id);
             }
             winit::event::WindowEvent::RedrawRequested => {
-                self.key_code_repeat = None;
-                self.key_event_handled = true;
-                self.key_code = None;
-
-                // println!("{:?}", window.ui_state.user_input.key_pressed);
-
-                let need_to_redraw = build(
-                    &mut self.app,
-                    &mut self.fonts,
-                    &self.assets,
-                    &mut self.string_interner,
-                    &mut self.broadcast_event_queue,
-                    &mut self.broadcast_async_tx,
-                    window,
-                    self.event_loop_proxy.clone(),
-                    self.force_redraw,
-                );
-
-                window.ui_state.user_input.key_pressed.clear();
-                window.ui_state.user_input.key_pressed_repeat.clear();
-
-                if need_to_redraw {
-                    window.renderer.process_commands(
-                        &window.ui_state.view,
-                        &window.ui_state.render_state,
-                        window.fill_color,
-                        &mut self.fonts,
-                        &mut window.texts,
-                        &self.assets,
-                    );
+                self.frame(window_id);
+            }
+            _ => (),
+        }
+    }
+}
 
-                    window.winit_window.request_redraw();
-                    self.force_redraw = false;
+impl<T: ApplicationDelegate<Event>, Event: 'static> Application<'_, T, Event> {
+    /// Builds and renders a frame of the window, the input received since its previous
+    /// frame is applied first.
+    fn frame(&mut self, window_id: winit::window::WindowId) {
+        self.frames(&[window_id]);
+    }
+
+    /// Builds the frames of the windows one after another on this thread, the app is
+    /// only touched here and the broadcasts reach the windows in the order they're
+    /// sent. Then lays them out and collects their render commands on a thread for
+    /// each window, see [`clew::ClewInstance::layout_frame`], and renders them.
+    ///
+    /// The event loop waits for the layouts of all of the windows, so a slow window
+    /// still holds up the others, and a single window is laid out on this thread.
+    fn frames(&mut self, window_ids: &[winit::window::WindowId]) {
+        let built: Vec<_> = window_ids
+            .iter()
+            .copied()
+            .filter(|window_id| self.build_frame(*window_id))
+            .collect();
+
+        let assets = &self.host.assets;
+        let mut instances: Vec<_> = self
+            .window_manager
+            .windows
+            .iter_mut()
+            .filter(|(id, _)| built.contains(id))
+            .map(|(_, window)| &mut window.instance)
+            .collect();
+
+        // The last window is laid out on this thread while it waits for the others.
+        if let Some(last) = instances.pop() {
+            std::thread::scope(|scope| {
+                for instance in instances {
+                    scope.spawn(move || {
+                        instance.layout_frame(assets);
+                    });
                 }
+
+                last.layout_frame(assets);
+            });
+        }
+
+        for window_id in built {
+            self.present_frame(window_id);
+        }
+
+        // Resource changes requested during the build are applied in between
+        // the frames, any window may depend on them.
+        if self.host.resources.apply_updates() {
+            self.window_manager.request_redraw_all();
+        }
+    }
+
+    /// Applies the input of the window and builds its frame, returns false if the
+    /// window isn't drawn this time.
+    fn build_frame(&mut self, window_id: winit::window::WindowId) -> bool {
+        let Some(window) = self.window_manager.get_mut_window(window_id) else {
+            return false;
+        };
+
+        // Nothing is built while the renderer warms up, the input and the resizes wait
+        // for the first frame it draws.
+        match window.renderer.poll_ready() {
+            Ok(true) => {}
+            Ok(false) => {
+                window
+                    .renderer
+                    .present_fill(window.instance.view(), window.fill_color);
+
+                return false;
             }
-            winit::event::WindowEvent::MouseInput {
-                state: btn_state,
-                button,
-                ..
-            } => {
-                // window.winit_window.request_redraw();
-                self.needs_redraw = true;
-
-                window.ui_state.user_input.mouse_pressed =
-                    btn_state == winit::event::ElementState::Pressed;
-                window.ui_state.user_input.mouse_released =
-                    btn_state == winit::event::ElementState::Released;
-
-                match button {
-                    winit::event::MouseButton::Left => {
-                        window.ui_state.user_input.mouse_left_pressed =
-                            window.ui_state.user_input.mouse_pressed;
-                        window.ui_state.user_input.mouse_left_released =
-                            window.ui_state.user_input.mouse_released;
-                    }
-                    winit::event::MouseButton::Right => {
-                        window.ui_state.user_input.mouse_right_pressed =
-                            window.ui_state.user_input.mouse_pressed;
-                        window.ui_state.user_input.mouse_right_released =
-                            window.ui_state.user_input.mouse_released;
-                    }
-                    winit::event::MouseButton::Middle => {
-                        window.ui_state.user_input.mouse_middle_pressed =
-                            window.ui_state.user_input.mouse_pressed;
-                        window.ui_state.user_input.mouse_middle_released =
-                            window.ui_state.user_input.mouse_released;
-                    }
-                    _ => {}
-                }
+            Err(err) => {
+                let view_id = window.instance.view().id;
+
+                self.app
+                    .on_renderer_error(&mut self.window_manager, view_id, err);
+                return false;
             }
+        }
 
-            // Mouse wheel scrolling
-            winit::event::WindowEvent::MouseWheel { delta, .. } => {
-                self.needs_redraw = true;
-                // window.winit_window.request_redraw();
+        // The surface follows the size of the view while the frozen frame is drawn into
+        // it, the input waits for the frame built once the resize ends.
+        if let Some(frozen) = &mut window.frozen_resize {
+            if !frozen.is_settled(Instant::now()) {
+                if frozen.dirty {
+                    frozen.dirty = false;
 
-                match delta {
-                    winit::event::MouseScrollDelta::LineDelta(x, y) => {
-                        // Scale line delta
-                        window.ui_state.user_input.mouse_wheel_delta_x = x * 20.0;
-                        window.ui_state.user_input.mouse_wheel_delta_y = y * 20.0;
-                    }
-                    winit::event::MouseScrollDelta::PixelDelta(pos) => {
-                        window.ui_state.user_input.mouse_wheel_delta_x = pos.x as f32;
-                        window.ui_state.user_input.mouse_wheel_delta_y = pos.y as f32;
-                    }
+                    let view = window.instance.view();
+                    let transform = frozen.transform(&view.size, view.scale_factor);
+
+                    window.instance.render_frozen(
+                        &self.host,
+                        window.renderer.as_mut(),
+                        window.fill_color,
+                        transform,
+                    );
                 }
+
+                return false;
             }
 
-            // Mouse movement
-            winit::event::WindowEvent::CursorMoved { position, .. } => {
-                // window.winit_window.request_redraw();
-                self.needs_redraw = true;
+            window.frozen_resize = None;
+            window.instance.invalidate();
+        }
+
+        let paused = is_paused(&self.app, &window.instance.ui_state().user_input);
+        window.instance.set_paused(paused);
 
-                window.ui_state.user_input.mouse_x = position.x as f32;
-                window.ui_state.user_input.mouse_y = position.y as f32;
+        for event_box in window.instance.begin_frame() {
+            // Skip event processing for () type
+            if TypeId::of::<Event>() != TypeId::of::<()>()
+                && let Some(event) = event_box.downcast_ref::<Event>()
+            {
+                window.window.on_event(&mut self.app, event);
             }
+        }
 
-            // Focus events
-            winit::event::WindowEvent::Focused(focused) => {
-                window.winit_window.request_redraw();
+        // The rest of a split batch goes to the next frame.
+        if window.instance.has_pending_input() {
+            window.winit_window.request_redraw();
+        }
 
-                if !focused {
-                    // Clear input state when window loses focus
-                    // input.keys_pressed.clear();
-                    window.ui_state.user_input.mouse_left_pressed = false;
-                    window.ui_state.user_input.mouse_right_pressed = false;
-                    window.ui_state.user_input.mouse_middle_pressed = false;
+        let app = &mut self.app;
+        let content = &mut window.window;
 
-                    window.winit_window.set_cursor(winit::window::Cursor::Icon(
-                        winit::window::CursorIcon::Default,
-                    ));
-                    self.last_cursor = Cursor::Default;
-                    window.ui_state.user_input.cursor = Cursor::Default;
+        window
+            .instance
+            .build_widgets(&mut self.host, &mut |ctx| content.build(app, ctx));
+
+        true
+    }
+
+    /// Hands the requests of the laid out frame to the platform and renders it.
+    fn present_frame(&mut self, window_id: winit::window::WindowId) {
+        let Some(window) = self.window_manager.get_mut_window(window_id) else {
+            return;
+        };
+
+        for event in window.instance.take_os_events() {
+            match event {
+                OsEvent::FocusWindow => window.winit_window.focus_window(),
+                OsEvent::ActivateIme => {
+                    window.winit_window.set_ime_allowed(true);
+                    window.ime_activated = true;
                 }
-            }
-            winit::event::WindowEvent::ModifiersChanged(new_modifiers) => {
-                self.modifiers = from_winit_modifiers(new_modifiers.state());
-            }
-            winit::event::WindowEvent::KeyboardInput {
-                event:
-                    winit::event::KeyEvent {
-                        physical_key: winit::keyboard::PhysicalKey::Code(code),
-                        logical_key,
-                        state,
-                        repeat,
-                        ..
-                    },
-                ..
-            } => {
-                window.ui_state.user_input.is_key_pressed =
-                    state == winit::event::ElementState::Pressed;
-                window.ui_state.user_input.is_key_released =
-                    state == winit::event::ElementState::Released;
-
-                if state == winit::event::ElementState::Pressed {
-                    if !repeat {
-                        self.key_code = from_winit_key_code(code);
-                    } else {
-                        self.key_code_repeat = from_winit_key_code(code);
-                    }
+                OsEvent::DeactivateIme => {
+                    window.winit_window.set_ime_allowed(false);
+                    window.ime_activated = false;
                 }
-
-                match logical_key {
-                    winit::keyboard::Key::Character(ref text) => {
-                        if state.is_pressed() {
-                            window.ui_state.user_input.text_input.push_str(text);
-                            window
-                                .ui_state
-                                .user_input
-                                .text_input_actions
-                                .push(TextInputAction::Insert);
-                        }
+                // The composition is in the text already, turning the input method off
+                // and on drops it there.
+                OsEvent::CommitIme => {
+                    if window.ime_activated {
+                        window.winit_window.set_ime_allowed(false);
+                        window.winit_window.set_ime_allowed(true);
                     }
-                    winit::keyboard::Key::Named(winit::keyboard::NamedKey::Space) => {
-                        if state.is_pressed() {
-                            // if ui_state.parameters.should_use_wide_space {
-                            // ui_state.input.text_input.push('\u{3000}');
-                            // } else {
-                            // ui_state.input.text_input.push(' ');
-                            // }
-
-                            window
-                                .ui_state
-                                .user_input
-                                .text_input_actions
-                                .push(TextInputAction::Insert);
-                        }
-                    }
-                    _ => {}
                 }
+            }
+        }
 
-                if let (winit::keyboard::KeyCode::Escape, true) = (code, state.is_pressed()) {
-                    event_loop.exit()
-                }
+        for announcement in window.instance.take_announcements() {
+            announcement::deliver(&announcement);
+        }
 
-                let _ = state;
+        let dropped_files = window.instance.take_unclaimed_dropped_files();
 
-                // self.handle_key_events(window_id);
-                window
-                    .ui_state
-                    .user_input
-                    .key_pressed
-                    .push((self.modifiers, self.key_code));
+        if !dropped_files.is_empty() {
+            window.window.on_files_dropped(&mut self.app, dropped_files);
+        }
 
-                window
-                    .ui_state
-                    .user_input
-                    .key_pressed_repeat
-                    .push((self.modifiers, self.key_code_repeat));
+        let rendered =
+            window
+                .instance
+                .render(&self.host, window.renderer.as_mut(), window.fill_color);
+
+        // The candidate window of the input method follows the caret.
+        let ime_cursor_rect = window.instance.ime_cursor_rect();
+
+        if window.ime_activated && ime_cursor_rect != window.ime_cursor_rect {
+            window.winit_window.set_ime_cursor_area(
+                winit::dpi::PhysicalPosition::new(ime_cursor_rect.x, ime_cursor_rect.y),
+                winit::dpi::PhysicalSize::new(ime_cursor_rect.width, ime_cursor_rect.height),
+            );
+            window.ime_cursor_rect = ime_cursor_rect;
+        }
+
+        if rendered {
+            window.winit_window.request_redraw();
+
+            if let Some(started) = self.started.take() {
+                log::debug!("Presented the first frame in {:?}", started.elapsed());
             }
-            _ => (),
         }
     }
-}
 
-impl<T: ApplicationDelegate<Event>, Event: 'static> Application<'_, T, Event> {
     pub fn run_application(mut delegate: T) -> anyhow::Result<()> {
-        let (broadcast_async_tx, broadcast_async_rx) = tokio::sync::mpsc::unbounded_channel();
+        let started = Instant::now();
 
         let mut assets = Assets::new();
 
+        let phase = Instant::now();
         delegate.init_assets(&mut assets);
+        log::debug!("Initialized assets in {:?}", phase.elapsed());
+
+        let mut resources = Resources::new();
+        let phase = Instant::now();
+        delegate.init_resources(&mut resources);
+        log::debug!("Initialized resources in {:?}", phase.elapsed());
 
+        let phase = Instant::now();
         let fonts = assets.create_font_resources();
+        log::debug!("Loaded fonts in {:?}", phase.elapsed());
 
         #[cfg(target_os = "macos")]
         let event_loop = winit::event_loop::EventLoop::with_user_event()
@@ -468,33 +603,37 @@ impl<T: ApplicationDelegate<Event>, Event: 'static> Application<'_, T, Event> {
         let event_loop = winit::event_loop::EventLoop::with_user_event().build()?;
 
         let event_proxy = event_loop.create_proxy();
+        let assets_proxy = event_loop.create_proxy();
+
+        assets.on_loaded(move || {
+            let _ = assets_proxy.send_event(ApplicationEvent::AssetLoaded);
+        });
 
         let mut application = Application {
             app: delegate,
-            window_manager: WindowManager::new(T::create_renderer),
-            fonts,
-            string_interner: StringInterner::new(),
+            window_manager: WindowManager::new(T::create_renderer, fonts),
+            host: ClewHost::new(
+                assets,
+                resources,
+                Arc::new(WinitEventLoopProxy { proxy: event_proxy }),
+            ),
             last_cursor: Cursor::Default,
-            broadcast_event_queue: Vec::new(),
-            broadcast_async_rx,
-            broadcast_async_tx,
-            force_redraw: false,
-            needs_redraw: false,
-            event_loop_proxy: Arc::new(WinitEventLoopProxy { proxy: event_proxy }),
-            assets,
             shortcuts_manager: ShortcutsManager::default(),
             shortcuts_registry: ShortcutsRegistry::default(),
-            last_ime_rect: Rect::default(),
-            ime_activated: false,
-            ime_reset_needed: false,
-            modifiers: None,
-            key_code: None,
-            key_code_repeat: None,
-            key_event_handled: false,
+            started: Some(started),
         };
 
         event_loop.run_app(&mut application)?;
 
+        if let Some(err) = application.window_manager.error.take() {
+            return Err(err.into());
+        }
+
         Ok(())
     }
 }
+
+/// Whether the animations of the window are stopped.
+fn is_paused<T: ApplicationDelegate<Event>, Event>(app: &T, user_input: &UserInput) -> bool {
+    app.pause_when_unfocused() && !user_input.window_focused
+}
@@ -0,0 +1,227 @@
+use clew::io::{ImeClause, InputEvent, MouseButton, TouchPhase};
+use winit::event::{ElementState, Ime, MouseScrollDelta, Touch, WindowEvent};
+
+use crate::keyboard::{from_winit_key_code, from_winit_modifiers};
+
+/// Scroll distance of a line of the mouse wheel.
+const WHEEL_LINE_DELTA: f64 = 20.;
+
+/// Translates the input events of a window, the other events are handled by the shell
+/// itself.
+pub(crate) fn to_input_event(event: &WindowEvent) -> Option<InputEvent> {
+    match event {
+        WindowEvent::CursorMoved { position, .. } => Some(InputEvent::PointerMoved {
+            x: position.x as f32,
+            y: position.y as f32,
+        }),
+        WindowEvent::MouseInput { state, button, .. } => Some(InputEvent::MouseButton {
+            button: match button {
+                winit::event::MouseButton::Left => MouseButton::Left,
+                winit::event::MouseButton::Right => MouseButton::Right,
+                winit::event::MouseButton::Middle => MouseButton::Middle,
+                _ => MouseButton::Other,
+            },
+            pressed: *state == ElementState::Pressed,
+        }),
+        WindowEvent::MouseWheel { delta, .. } => {
+            let (delta_x, delta_y) = match delta {
+                MouseScrollDelta::LineDelta(x, y) => {
+                    (*x as f64 * WHEEL_LINE_DELTA, *y as f64 * WHEEL_LINE_DELTA)
+                }
+                MouseScrollDelta::PixelDelta(position) => (position.x, position.y),
+            };
+
+            Some(InputEvent::MouseWheel { delta_x, delta_y })
+        }
+        WindowEvent::Touch(Touch {
+            id,
+            phase,
+            location,
+            ..
+        }) => Some(InputEvent::Touch {
+            id: *id,
+            phase: match phase {
+                winit::event::TouchPhase::Started => TouchPhase::Started,
+                winit::event::TouchPhase::Moved => TouchPhase::Moved,
+                winit::event::TouchPhase::Ended => TouchPhase::Ended,
+                winit::event::TouchPhase::Cancelled => TouchPhase::Cancelled,
+            },
+            x: location.x as f32,
+            y: location.y as f32,
+        }),
+        WindowEvent::PinchGesture { delta, .. } => Some(InputEvent::Pinch {
+            delta: *delta as f32,
+        }),
+        WindowEvent::ModifiersChanged(modifiers) => Some(InputEvent::ModifiersChanged(
+            from_winit_modifiers(modifiers.state()).unwrap_or_default(),
+        )),
+        WindowEvent::KeyboardInput {
+            event:
+                winit::event::KeyEvent {
+                    physical_key: winit::keyboard::PhysicalKey::Code(code),
+                    logical_key,
+                    state,
+                    repeat,
+                    ..
+                },
+            ..
+        } => Some(InputEvent::Key {
+            key_code: from_winit_key_code(*code),
+            pressed: state.is_pressed(),
+            repeat: *repeat,
+            text: match logical_key {
+                winit::keyboard::Key::Character(text) => Some(text.to_string()),
+                winit::keyboard::Key::Named(winit::keyboard::NamedKey::Space) => {
+                    Some(" ".to_string())
+                }
+                _ => None,
+            },
+        }),
+        WindowEvent::Ime(Ime::Enabled) => Some(InputEvent::ImeEnabled),
+        WindowEvent::Ime(Ime::Preedit(text, cursor)) => Some(InputEvent::ImePreedit {
+            text: text.clone(),
+            cursor: *cursor,
+            // winit doesn't tell the clauses, the one being converted comes as the
+            // range of the cursor.
+            clauses: cursor
+                .filter(|(start, end)| start < end)
+                .map(|(start, end)| ImeClause {
+                    range: start..end,
+                    selected: true,
+                })
+                .into_iter()
+                .collect(),
+        }),
+        WindowEvent::Ime(Ime::Commit(text)) => Some(InputEvent::ImeCommit(text.clone())),
+        WindowEvent::Ime(Ime::Disabled) => Some(InputEvent::ImeDisabled),
+        WindowEvent::Focused(focused) => Some(InputEvent::Focused(*focused)),
+        // winit doesn't tell where the files are dragged or dropped.
+        WindowEvent::HoveredFile(path) => Some(InputEvent::FileHovered {
+            path: path.clone(),
+            position: None,
+        }),
+        WindowEvent::DroppedFile(path) => Some(InputEvent::FileDropped {
+            path: path.clone(),
+            position: None,
+        }),
+        WindowEvent::HoveredFileCancelled => Some(InputEvent::FileHoverCancelled),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use winit::{dpi::PhysicalPosition, event::DeviceId, event::TouchPhase};
+
+    use super::*;
+
+    #[test]
+    fn test_wheel_lines_are_scrolled_in_pixels() {
+        let line = WindowEvent::MouseWheel {
+            device_id: DeviceId::dummy(),
+            delta: MouseScrollDelta::LineDelta(0., -2.),
+            phase: TouchPhase::Moved,
+        };
+        let pixels = WindowEvent::MouseWheel {
+            device_id: DeviceId::dummy(),
+            delta: MouseScrollDelta::PixelDelta(PhysicalPosition::new(3., 4.)),
+            phase: TouchPhase::Moved,
+        };
+
+        assert_eq!(
+            to_input_event(&line),
+            Some(InputEvent::MouseWheel {
+                delta_x: 0.,
+                delta_y: -40.,
+            })
+        );
+        assert_eq!(
+            to_input_event(&pixels),
+            Some(InputEvent::MouseWheel {
+                delta_x: 3.,
+                delta_y: 4.,
+            })
+        );
+    }
+
+    #[test]
+    fn test_touches_keep_their_finger() {
+        let touch = WindowEvent::Touch(Touch {
+            device_id: DeviceId::dummy(),
+            phase: TouchPhase::Ended,
+            location: PhysicalPosition::new(12., 34.),
+            force: None,
+            id: 3,
+        });
+
+        assert_eq!(
+            to_input_event(&touch),
+            Some(InputEvent::Touch {
+                id: 3,
+                phase: clew::io::TouchPhase::Ended,
+                x: 12.,
+                y: 34.,
+            })
+        );
+    }
+
+    #[test]
+    fn test_dragged_files_have_no_position() {
+        let path = std::path::PathBuf::from("/tmp/notes.txt");
+
+        assert_eq!(
+            to_input_event(&WindowEvent::HoveredFile(path.clone())),
+            Some(InputEvent::FileHovered {
+                path: path.clone(),
+                position: None,
+            })
+        );
+        assert_eq!(
+            to_input_event(&WindowEvent::DroppedFile(path.clone())),
+            Some(InputEvent::FileDropped {
+                path,
+                position: None,
+            })
+        );
+        assert_eq!(
+            to_input_event(&WindowEvent::HoveredFileCancelled),
+            Some(InputEvent::FileHoverCancelled)
+        );
+    }
+
+    #[test]
+    fn test_converted_ime_range_is_the_selected_clause() {
+        let preedit = |cursor| {
+            to_input_event(&WindowEvent::Ime(Ime::Preedit(
+                "にほんご".to_string(),
+                cursor,
+            )))
+        };
+
+        assert_eq!(
+            preedit(Some((0, 6))),
+            Some(InputEvent::ImePreedit {
+                text: "にほんご".to_string(),
+                cursor: Some((0, 6)),
+                clauses: vec![ImeClause {
+                    range: 0..6,
+                    selected: true,
+                }],
+            })
+        );
+        assert_eq!(
+            preedit(Some((12, 12))),
+            Some(InputEvent::ImePreedit {
+                text: "にほんご".to_string(),
+                cursor: Some((12, 12)),
+                clauses: Vec::new(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_window_events_are_not_input() {
+        assert_eq!(to_input_event(&WindowEvent::CloseRequested), None);
+        assert_eq!(to_input_event(&WindowEvent::RedrawRequested), None);
+    }
+}
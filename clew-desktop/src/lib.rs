@@ -1,6 +1,12 @@
+mod announcement;
 pub mod app;
 // #[cfg(feature = "async")]
 pub mod async_support;
+mod input;
 mod keyboard;
+pub mod material;
+pub mod placement;
+pub mod resize;
+mod theme;
 pub mod window;
 pub mod window_manager;
@@ -0,0 +1,151 @@
+use clew::render::Renderer;
+
+/// Material the system draws behind the window, seen through the parts of the UI that
+/// aren't painted, see [`crate::window_manager::WindowDescriptor::background_material`].
+/// Applied only with the `window-effects` feature on Windows 11 and macOS, and only when
+/// the renderer can present transparent frames, the window is filled with its fill
+/// color otherwise.
+///
+/// Each platform has its own materials, the closest one is picked where the asked one
+/// isn't available: Windows draws Mica for [`Material::Mica`] and Acrylic for the others,
+/// macOS draws the vibrancy of the window background for all of them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Material {
+    /// Opaque material tinted with the desktop wallpaper, for the main windows.
+    Mica,
+    /// Translucent blurred material, for the transient windows such as popups.
+    Acrylic,
+    /// Translucent blurred material of the macOS windows.
+    Vibrancy,
+}
+
+#[cfg(all(feature = "window-effects", target_os = "macos"))]
+fn apply_to_window(window: &winit::window::Window, _material: Material) -> bool {
+    use objc2::MainThreadMarker;
+    use objc2_app_kit::{
+        NSAutoresizingMaskOptions, NSView, NSVisualEffectBlendingMode, NSVisualEffectMaterial,
+        NSVisualEffectState, NSVisualEffectView, NSWindowOrderingMode,
+    };
+    use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+    let Some(mtm) = MainThreadMarker::new() else {
+        return false;
+    };
+    let Ok(handle) = window.window_handle() else {
+        return false;
+    };
+    let RawWindowHandle::AppKit(handle) = handle.as_raw() else {
+        return false;
+    };
+
+    let view = unsafe { handle.ns_view.cast::<NSView>().as_ref() };
+
+    // Placed behind the view rather than in it, the frames are drawn into the layer
+    // of the view and would be covered by a subview.
+    let Some(superview) = (unsafe { view.superview() }) else {
+        return false;
+    };
+
+    let effect = NSVisualEffectView::initWithFrame(mtm.alloc(), view.frame());
+    effect.setMaterial(NSVisualEffectMaterial::UnderWindowBackground);
+    effect.setBlendingMode(NSVisualEffectBlendingMode::BehindWindow);
+    effect.setState(NSVisualEffectState::FollowsWindowActiveState);
+    effect.setAutoresizingMask(
+        NSAutoresizingMaskOptions::ViewWidthSizable | NSAutoresizingMaskOptions::ViewHeightSizable,
+    );
+
+    superview.addSubview_positioned_relativeTo(&effect, NSWindowOrderingMode::Below, Some(view));
+
+    true
+}
+
+#[cfg(all(feature = "window-effects", target_os = "windows"))]
+fn apply_to_window(window: &winit::window::Window, material: Material) -> bool {
+    use windows_sys::Win32::{
+        Foundation::HWND,
+        Graphics::Dwm::{
+            DWMSBT_MAINWINDOW, DWMSBT_TRANSIENTWINDOW, DWMWA_SYSTEMBACKDROP_TYPE,
+            DwmExtendFrameIntoClientArea, DwmSetWindowAttribute,
+        },
+        UI::Controls::MARGINS,
+    };
+    use winit::raw_window_handle::{HasWindowHandle, RawWindowHandle};
+
+    let Ok(handle) = window.window_handle() else {
+        return false;
+    };
+    let RawWindowHandle::Win32(handle) = handle.as_raw() else {
+        return false;
+    };
+
+    let hwnd = handle.hwnd.get() as HWND;
+    let backdrop = match material {
+        Material::Mica => DWMSBT_MAINWINDOW,
+        Material::Acrylic | Material::Vibrancy => DWMSBT_TRANSIENTWINDOW,
+    };
+    // The backdrop is drawn only under the frame, extended over the whole window.
+    let margins = MARGINS {
+        cxLeftWidth: -1,
+        cxRightWidth: -1,
+        cyTopHeight: -1,
+        cyBottomHeight: -1,
+    };
+
+    // Windows before 11 22H2 don't know the attribute and fail.
+    unsafe {
+        DwmExtendFrameIntoClientArea(hwnd, &margins) >= 0
+            && DwmSetWindowAttribute(
+                hwnd,
+                DWMWA_SYSTEMBACKDROP_TYPE as u32,
+                (&backdrop as *const i32).cast(),
+                std::mem::size_of::<i32>() as u32,
+            ) >= 0
+    }
+}
+
+#[cfg(not(all(
+    feature = "window-effects",
+    any(target_os = "windows", target_os = "macos")
+)))]
+fn apply_to_window(_window: &winit::window::Window, _material: Material) -> bool {
+    false
+}
+
+/// Applies the material to the window created transparent and makes the renderer draw
+/// transparent frames, returns false and leaves the renderer opaque when either of them
+/// can't do it.
+pub(crate) fn apply(
+    window: &winit::window::Window,
+    renderer: &mut dyn Renderer,
+    material: Material,
+) -> bool {
+    if !supported() {
+        log::debug!("Background materials aren't supported, filling the window instead");
+
+        return false;
+    }
+
+    if !renderer.set_transparent_background(true) {
+        log::warn!("The renderer can't draw transparent frames, filling the window instead");
+
+        return false;
+    }
+
+    if !apply_to_window(window, material) {
+        log::warn!("Couldn't apply {material:?} to the window, filling it instead");
+        renderer.set_transparent_background(false);
+
+        return false;
+    }
+
+    true
+}
+
+/// Whether the materials can be applied at all, the windows with one are created
+/// transparent then.
+pub(crate) fn supported() -> bool {
+    cfg!(all(
+        feature = "window-effects",
+        any(target_os = "windows", target_os = "macos")
+    ))
+}
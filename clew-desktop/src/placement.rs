@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use clew::ViewId;
+use winit::{
+    dpi::{LogicalPosition, LogicalSize, PhysicalPosition, PhysicalSize},
+    monitor::MonitorHandle,
+};
+
+/// Where a window is opened, see [`crate::window_manager::WindowDescriptor::position`].
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum WindowPlacement {
+    /// Left to the system.
+    #[default]
+    Default,
+    CenteredOnPrimary,
+    /// Centered on the monitor the window of the view is on now.
+    CenteredOnMonitorOf(ViewId),
+    /// At the position in the logical coordinates of the desktop, see
+    /// [`MonitorInfo::position`].
+    At(LogicalPosition<f64>),
+    /// Where the window with the key was left, restored from the
+    /// [`WindowGeometryStore`] of the window manager. Opened with the default
+    /// placement when nothing is saved or the monitor it was on is gone.
+    Remembered(String),
+}
+
+/// Monitor of the desktop.
+///
+/// The monitors keep their physical positions in the logical coordinates of the desktop
+/// and their sizes are divided by their own scale factors, so the monitors with
+/// different scales don't overlap and a logical position maps to one physical pixel.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MonitorInfo {
+    pub name: Option<String>,
+    pub position: LogicalPosition<f64>,
+    pub size: LogicalSize<f64>,
+    pub physical_position: PhysicalPosition<i32>,
+    pub physical_size: PhysicalSize<u32>,
+    pub scale_factor: f64,
+    pub is_primary: bool,
+}
+
+/// Geometry of a window relative to the monitor it's on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WindowGeometry {
+    pub monitor: String,
+    /// Outer position from the top left corner of the monitor.
+    pub position: LogicalPosition<f64>,
+    pub size: LogicalSize<f64>,
+}
+
+/// Keeps the geometry of the windows opened with [`WindowPlacement::Remembered`], e.g.
+/// in the settings of the app. The map keeps it for the session.
+pub trait WindowGeometryStore {
+    fn load(&self, key: &str) -> Option<WindowGeometry>;
+
+    fn save(&mut self, key: &str, geometry: WindowGeometry);
+}
+
+impl WindowGeometryStore for HashMap<String, WindowGeometry> {
+    fn load(&self, key: &str) -> Option<WindowGeometry> {
+        self.get(key).cloned()
+    }
+
+    fn save(&mut self, key: &str, geometry: WindowGeometry) {
+        self.insert(key.to_string(), geometry);
+    }
+}
+
+impl MonitorInfo {
+    pub(crate) fn new(handle: &MonitorHandle, primary: Option<&MonitorHandle>) -> Self {
+        let scale_factor = handle.scale_factor();
+        let physical_position = handle.position();
+        let physical_size = handle.size();
+
+        Self {
+            name: handle.name(),
+            position: LogicalPosition::new(physical_position.x as f64, physical_position.y as f64),
+            size: physical_size.to_logical(scale_factor),
+            physical_position,
+            physical_size,
+            scale_factor,
+            is_primary: primary.is_some_and(|primary| primary == handle),
+        }
+    }
+
+    pub fn contains(&self, position: LogicalPosition<f64>) -> bool {
+        position.x >= self.position.x
+            && position.y >= self.position.y
+            && position.x < self.position.x + self.size.width
+            && position.y < self.position.y + self.size.height
+    }
+
+    /// Physical position of the logical position of the desktop, scaled from the top
+    /// left corner of the monitor.
+    pub fn to_physical(&self, position: LogicalPosition<f64>) -> PhysicalPosition<i32> {
+        PhysicalPosition::new(
+            self.physical_position.x
+                + ((position.x - self.position.x) * self.scale_factor).round() as i32,
+            self.physical_position.y
+                + ((position.y - self.position.y) * self.scale_factor).round() as i32,
+        )
+    }
+
+    /// Position that centers the window of the size on the monitor.
+    pub fn centered(&self, size: LogicalSize<f64>) -> LogicalPosition<f64> {
+        LogicalPosition::new(
+            self.position.x + (self.size.width - size.width) / 2.,
+            self.position.y + (self.size.height - size.height) / 2.,
+        )
+    }
+}
+
+/// Monitor the logical position is on, the primary one or the first one when it's
+/// outside of all of them.
+pub(crate) fn monitor_at(
+    monitors: &[MonitorInfo],
+    position: LogicalPosition<f64>,
+) -> Option<&MonitorInfo> {
+    monitors
+        .iter()
+        .find(|monitor| monitor.contains(position))
+        .or_else(|| monitors.iter().find(|monitor| monitor.is_primary))
+        .or_else(|| monitors.first())
+}
+
+/// Outer position and inner size of a window opened with the geometry, `None` when its
+/// monitor is gone.
+pub(crate) fn restore_geometry(
+    monitors: &[MonitorInfo],
+    geometry: &WindowGeometry,
+) -> Option<(PhysicalPosition<i32>, PhysicalSize<u32>)> {
+    let monitor = monitors
+        .iter()
+        .find(|monitor| monitor.name.as_deref() == Some(geometry.monitor.as_str()))?;
+    let position = LogicalPosition::new(
+        monitor.position.x + geometry.position.x,
+        monitor.position.y + geometry.position.y,
+    );
+
+    Some((
+        monitor.to_physical(position),
+        geometry.size.to_physical(monitor.scale_factor),
+    ))
+}
+
+/// Geometry of the window on the monitor from its outer position and inner size.
+pub(crate) fn save_geometry(
+    monitor: &MonitorInfo,
+    position: PhysicalPosition<i32>,
+    size: PhysicalSize<u32>,
+) -> Option<WindowGeometry> {
+    Some(WindowGeometry {
+        monitor: monitor.name.clone()?,
+        position: LogicalPosition::new(
+            (position.x - monitor.physical_position.x) as f64 / monitor.scale_factor,
+            (position.y - monitor.physical_position.y) as f64 / monitor.scale_factor,
+        ),
+        size: size.to_logical(monitor.scale_factor),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 1x monitor with a 2x one on its right.
+    fn monitors() -> [MonitorInfo; 2] {
+        [
+            MonitorInfo {
+                name: Some("left".to_string()),
+                position: LogicalPosition::new(0., 0.),
+                size: LogicalSize::new(1920., 1080.),
+                physical_position: PhysicalPosition::new(0, 0),
+                physical_size: PhysicalSize::new(1920, 1080),
+                scale_factor: 1.,
+                is_primary: true,
+            },
+            MonitorInfo {
+                name: Some("right".to_string()),
+                position: LogicalPosition::new(1920., 0.),
+                size: LogicalSize::new(1920., 1080.),
+                physical_position: PhysicalPosition::new(1920, 0),
+                physical_size: PhysicalSize::new(3840, 2160),
+                scale_factor: 2.,
+                is_primary: false,
+            },
+        ]
+    }
+
+    #[test]
+    fn test_geometry_is_restored_at_scale_of_its_monitor() {
+        let monitors = monitors();
+        let right = &monitors[1];
+
+        let centered = right.centered(LogicalSize::new(400., 300.));
+        assert_eq!(centered, LogicalPosition::new(2680., 390.));
+        assert_eq!(
+            right.to_physical(centered),
+            PhysicalPosition::new(3440, 780)
+        );
+
+        let geometry = save_geometry(
+            right,
+            PhysicalPosition::new(3440, 780),
+            PhysicalSize::new(800, 600),
+        )
+        .unwrap();
+        assert_eq!(geometry.position, LogicalPosition::new(760., 390.));
+        assert_eq!(geometry.size, LogicalSize::new(400., 300.));
+        assert_eq!(
+            restore_geometry(&monitors, &geometry),
+            Some((
+                PhysicalPosition::new(3440, 780),
+                PhysicalSize::new(800, 600)
+            ))
+        );
+
+        // The monitor is gone.
+        assert_eq!(restore_geometry(&monitors[..1], &geometry), None);
+    }
+}
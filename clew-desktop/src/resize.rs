@@ -0,0 +1,176 @@
+use std::time::{Duration, Instant};
+
+use clew::{PhysicalSize, Transform, Vec2};
+
+/// Time without resize events after which an interactive resize has ended, winit
+/// doesn't report its start and end on every platform.
+const RESIZE_SETTLE: Duration = Duration::from_millis(150);
+
+/// How the content of a window follows an interactive resize.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ResizePolicy {
+    /// A frame is built for every size.
+    #[default]
+    Live,
+    /// The last frame is drawn at the new size until the resize ends, then a frame is
+    /// built for the final size once. For windows whose build is too slow to keep up.
+    Freeze { anchor: FrozenFrameAnchor },
+    /// The last frame is drawn at the top left until no resize has come for `ms`
+    /// milliseconds, then a frame is built.
+    Debounced { ms: u64 },
+}
+
+/// Where the last frame is drawn in a window resized while it's frozen.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FrozenFrameAnchor {
+    #[default]
+    TopLeft,
+    Center,
+    /// Scaled to the new size, by the smaller of the horizontal and the vertical ratio
+    /// so it keeps its aspect.
+    Stretch,
+}
+
+impl ResizePolicy {
+    fn freeze(&self) -> Option<(FrozenFrameAnchor, Duration)> {
+        match *self {
+            ResizePolicy::Live => None,
+            ResizePolicy::Freeze { anchor } => Some((anchor, RESIZE_SETTLE)),
+            ResizePolicy::Debounced { ms } => {
+                Some((FrozenFrameAnchor::TopLeft, Duration::from_millis(ms)))
+            }
+        }
+    }
+}
+
+/// Interactive resize of a window with a frozen frame.
+pub(crate) struct FrozenResize {
+    anchor: FrozenFrameAnchor,
+    settle: Duration,
+    last_event: Instant,
+    /// Size and scale factor the frozen frame has been built at.
+    built_size: PhysicalSize,
+    built_scale_factor: f32,
+    /// Whether the size has changed since the frozen frame was drawn.
+    pub(crate) dirty: bool,
+}
+
+impl FrozenResize {
+    /// Starts freezing the frame built at the size and scale factor, `None` if the
+    /// policy builds frames live.
+    pub(crate) fn start(
+        policy: ResizePolicy,
+        built_size: PhysicalSize,
+        built_scale_factor: f32,
+    ) -> Option<Self> {
+        let (anchor, settle) = policy.freeze()?;
+
+        Some(Self {
+            anchor,
+            settle,
+            last_event: Instant::now(),
+            built_size,
+            built_scale_factor,
+            dirty: true,
+        })
+    }
+
+    pub(crate) fn on_resize(&mut self) {
+        self.last_event = Instant::now();
+        self.dirty = true;
+    }
+
+    /// Whether the resize has ended and a frame has to be built.
+    pub(crate) fn is_settled(&self, now: Instant) -> bool {
+        now.duration_since(self.last_event) >= self.settle
+    }
+
+    /// Time the resize ends at if no other resize comes.
+    pub(crate) fn settles_at(&self) -> Instant {
+        self.last_event + self.settle
+    }
+
+    /// Transform of the frozen frame drawn into a view of the size and scale factor.
+    pub(crate) fn transform(&self, size: &PhysicalSize, scale_factor: f32) -> Transform {
+        frozen_transform(
+            self.anchor,
+            &self.built_size,
+            self.built_scale_factor,
+            size,
+            scale_factor,
+        )
+    }
+}
+
+fn frozen_transform(
+    anchor: FrozenFrameAnchor,
+    built_size: &PhysicalSize,
+    built_scale_factor: f32,
+    size: &PhysicalSize,
+    scale_factor: f32,
+) -> Transform {
+    let built = Vec2::new(built_size.width as f32, built_size.height as f32);
+    let size = Vec2::new(size.width as f32, size.height as f32);
+
+    if built.x <= 0. || built.y <= 0. {
+        return Transform::IDENTITY;
+    }
+
+    // The frame keeps its logical size when the window moves to another display.
+    let scale = scale_factor / built_scale_factor;
+
+    match anchor {
+        FrozenFrameAnchor::TopLeft => Transform::new(Vec2::ZERO, scale),
+        FrozenFrameAnchor::Center => Transform::new(
+            Vec2::new(
+                (size.x - built.x * scale) / 2.,
+                (size.y - built.y * scale) / 2.,
+            ),
+            scale,
+        ),
+        FrozenFrameAnchor::Stretch => {
+            Transform::new(Vec2::ZERO, (size.x / built.x).min(size.y / built.y))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frozen_frame_follows_size_and_scale_factor() {
+        let built = PhysicalSize::new(800, 600);
+
+        assert_eq!(
+            frozen_transform(
+                FrozenFrameAnchor::TopLeft,
+                &built,
+                1.,
+                &PhysicalSize::new(1000, 600),
+                2.
+            ),
+            Transform::new(Vec2::ZERO, 2.)
+        );
+        assert_eq!(
+            frozen_transform(
+                FrozenFrameAnchor::Center,
+                &built,
+                1.,
+                &PhysicalSize::new(1000, 800),
+                1.
+            ),
+            Transform::new(Vec2::new(100., 100.), 1.)
+        );
+        assert_eq!(
+            frozen_transform(
+                FrozenFrameAnchor::Stretch,
+                &built,
+                1.,
+                &PhysicalSize::new(1600, 900),
+                1.
+            ),
+            Transform::new(Vec2::ZERO, 1.5)
+        );
+    }
+}
@@ -0,0 +1,149 @@
+use clew::{Appearance, ColorRgba, SystemTheme};
+use winit::window::Theme;
+
+/// System theme of the appearance winit reports, `None` where it reports none. The
+/// accent color is only read with the `accent-color` feature, on Windows and macOS. The
+/// reduced motion preference is read with the `reduced-motion` one there, and from the
+/// GNOME settings on Linux.
+pub(crate) fn system_theme(theme: Option<Theme>) -> SystemTheme {
+    SystemTheme {
+        appearance: match theme {
+            Some(Theme::Dark) => Appearance::Dark,
+            Some(Theme::Light) | None => Appearance::Light,
+        },
+        accent_color: accent_color(),
+        reduced_motion: reduced_motion(),
+    }
+}
+
+/// Theme asked for the chrome of a new window. Windows and macOS switch the chrome of
+/// the windows that don't ask for one along with the system, and only report the
+/// switches to them, elsewhere the chrome gets the appearance read from the system.
+pub(crate) fn window_theme(theme: Option<Theme>) -> Option<Theme> {
+    if cfg!(any(target_os = "windows", target_os = "macos")) {
+        None
+    } else {
+        theme
+    }
+}
+
+#[cfg(all(feature = "accent-color", target_os = "windows"))]
+fn accent_color() -> Option<ColorRgba> {
+    use windows_sys::Win32::{
+        Foundation::ERROR_SUCCESS,
+        System::Registry::{HKEY_CURRENT_USER, RRF_RT_REG_DWORD, RegGetValueW},
+    };
+
+    let key: Vec<u16> = "Software\\Microsoft\\Windows\\DWM\0"
+        .encode_utf16()
+        .collect();
+    let value: Vec<u16> = "AccentColor\0".encode_utf16().collect();
+    let mut color = 0u32;
+    let mut size = std::mem::size_of::<u32>() as u32;
+
+    let status = unsafe {
+        RegGetValueW(
+            HKEY_CURRENT_USER,
+            key.as_ptr(),
+            value.as_ptr(),
+            RRF_RT_REG_DWORD,
+            std::ptr::null_mut(),
+            (&mut color as *mut u32).cast(),
+            &mut size,
+        )
+    };
+
+    if status != ERROR_SUCCESS {
+        return None;
+    }
+
+    // Stored as 0xAABBGGRR, the alpha isn't meaningful.
+    let [r, g, b, _] = color.to_le_bytes();
+
+    Some(ColorRgba::from_hex(u32::from_be_bytes([0xFF, r, g, b])))
+}
+
+#[cfg(all(feature = "accent-color", target_os = "macos"))]
+fn accent_color() -> Option<ColorRgba> {
+    use objc2_app_kit::{NSColor, NSColorSpace};
+
+    let color = NSColor::controlAccentColor();
+    let color = color.colorUsingColorSpace(&NSColorSpace::sRGBColorSpace())?;
+
+    Some(ColorRgba::new(
+        color.redComponent() as f32,
+        color.greenComponent() as f32,
+        color.blueComponent() as f32,
+        1.,
+    ))
+}
+
+#[cfg(not(all(
+    feature = "accent-color",
+    any(target_os = "windows", target_os = "macos")
+)))]
+fn accent_color() -> Option<ColorRgba> {
+    None
+}
+
+#[cfg(all(feature = "reduced-motion", target_os = "windows"))]
+fn reduced_motion() -> bool {
+    use windows_sys::Win32::{
+        Foundation::FALSE,
+        UI::WindowsAndMessaging::{SPI_GETCLIENTAREAANIMATION, SystemParametersInfoW},
+    };
+
+    let mut animations = 1i32;
+
+    let status = unsafe {
+        SystemParametersInfoW(
+            SPI_GETCLIENTAREAANIMATION,
+            0,
+            (&mut animations as *mut i32).cast(),
+            0,
+        )
+    };
+
+    status != FALSE && animations == FALSE
+}
+
+#[cfg(all(feature = "reduced-motion", target_os = "macos"))]
+fn reduced_motion() -> bool {
+    use objc2_app_kit::NSWorkspace;
+
+    NSWorkspace::sharedWorkspace().accessibilityDisplayShouldReduceMotion()
+}
+
+/// Animations turned off in the GNOME settings, which the other desktops that follow
+/// the GTK settings share. Reads nothing without `gsettings` or the schema.
+#[cfg(target_os = "linux")]
+fn reduced_motion() -> bool {
+    std::process::Command::new("gsettings")
+        .args(["get", "org.gnome.desktop.interface", "enable-animations"])
+        .output()
+        .is_ok_and(|output| {
+            output.status.success() && String::from_utf8_lossy(&output.stdout).trim() == "false"
+        })
+}
+
+#[cfg(not(any(
+    all(
+        feature = "reduced-motion",
+        any(target_os = "windows", target_os = "macos")
+    ),
+    target_os = "linux"
+)))]
+fn reduced_motion() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unknown_theme_reads_as_light() {
+        assert_eq!(system_theme(None).appearance, Appearance::Light);
+        assert_eq!(system_theme(Some(Theme::Dark)).appearance, Appearance::Dark);
+    }
+}
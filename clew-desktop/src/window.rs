@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use clew::{ShortcutsRegistry, shortcuts::ShortcutsManager, widgets::builder::BuildContext};
 
 pub trait Window<App, Event = ()> {
@@ -7,5 +9,11 @@ pub trait Window<App, Event = ()> {
 
     fn on_shortcut(&mut self, _shortcuts_manager: &ShortcutsManager) {}
 
+    /// Files dropped onto the window that no [`clew::file_drop_target`] has taken,
+    /// called after the frame they were dropped in. winit doesn't report where the
+    /// files are dropped, the targets get them only on the platforms that move the
+    /// pointer with the drag.
+    fn on_files_dropped(&mut self, _app: &mut App, _files: Vec<PathBuf>) {}
+
     fn build(&mut self, app: &mut App, ctx: &mut BuildContext);
 }
@@ -1,13 +1,24 @@
-use std::{collections::HashMap, sync::Arc, time::Instant};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use clew::{
-    ColorRgb, EdgeInsets, PhysicalSize, View, ViewId,
-    render::Renderer,
-    state::UiState,
-    text::{StringId, TextId, TextsResources},
+    ClewError, ClewInstance, ColorRgb, EdgeInsets, LayoutDirection, PhysicalSize, Rect,
+    SystemTheme, View, ViewId, render::Renderer, text::FontResources,
 };
 
-use crate::window::Window;
+use crate::{
+    material::{self, Material},
+    placement::{
+        MonitorInfo, WindowGeometryStore, WindowPlacement, monitor_at, restore_geometry,
+        save_geometry,
+    },
+    resize::{FrozenResize, ResizePolicy},
+    theme,
+    window::Window,
+};
 
 #[derive(Debug, Clone)]
 pub struct WindowDescriptor {
@@ -16,6 +27,11 @@ pub struct WindowDescriptor {
     pub height: u32,
     pub resizable: bool,
     pub fill_color: ColorRgb,
+    /// Material drawn behind the window instead of the fill color, see [`Material`].
+    /// The window is filled with the fill color where the material isn't available.
+    pub background_material: Option<Material>,
+    pub position: WindowPlacement,
+    pub resize_policy: ResizePolicy,
 }
 
 impl Default for WindowDescriptor {
@@ -26,6 +42,9 @@ impl Default for WindowDescriptor {
             height: 600,
             resizable: true,
             fill_color: ColorRgb::from_hex(0x000000),
+            background_material: None,
+            position: WindowPlacement::Default,
+            resize_policy: ResizePolicy::Live,
         }
     }
 }
@@ -33,29 +52,176 @@ impl Default for WindowDescriptor {
 pub(crate) struct WindowState<'a, App, Event> {
     pub(crate) window: Box<dyn Window<App, Event>>,
     pub(crate) winit_window: Arc<winit::window::Window>,
-    pub(crate) texts: TextsResources<'a>,
-    pub(crate) strings: HashMap<StringId, TextId>,
-    pub(crate) ui_state: UiState,
+    /// Every window shapes its texts with its own font system, so the frame of a window
+    /// doesn't touch the state of the other windows apart from the application.
+    pub(crate) instance: ClewInstance<'a>,
     pub(crate) renderer: Box<dyn Renderer>,
     pub(crate) fill_color: ColorRgb,
-    pub(crate) delta_time_timer: Instant,
+    /// Whether a background material has been applied, the renderers of the window
+    /// draw transparent frames then.
+    pub(crate) transparent: bool,
+    /// Key of [`WindowPlacement::Remembered`] the geometry of the window is saved under.
+    pub(crate) geometry_key: Option<String>,
+    pub(crate) resize_policy: ResizePolicy,
+    /// Set while the window is resized with a frozen frame.
+    pub(crate) frozen_resize: Option<FrozenResize>,
+    /// Whether the input method is turned on for a focused text field of the window.
+    pub(crate) ime_activated: bool,
+    /// Caret the candidate window of the input method was last put next to.
+    pub(crate) ime_cursor_rect: Rect,
 }
 
 pub struct WindowManager<'a, App, Event> {
     pub(crate) windows: HashMap<winit::window::WindowId, WindowState<'a, App, Event>>,
     event_loop: Option<*const winit::event_loop::ActiveEventLoop>,
-    renderer_factory: fn(Arc<winit::window::Window>) -> Box<dyn Renderer>,
+    renderer_factory: RendererFactory,
+    /// Fonts of the assets and of the system, loaded once. Every spawned window gets a
+    /// fork of them, so a family has the same id in all windows.
+    fonts: FontResources,
     // TODO(sysint64): Implement proper id manager
     next_view_id: usize,
+    /// Set when a window couldn't be spawned, the application stops and returns it.
+    pub(crate) error: Option<ClewError>,
+    geometry_store: Option<Box<dyn WindowGeometryStore>>,
+    /// Theme of the system the windows are drawn in, and the appearance it's read from,
+    /// `None` where winit doesn't report it.
+    system_theme: SystemTheme,
+    window_theme: Option<winit::window::Theme>,
 }
 
+pub type RendererFactory = fn(Arc<winit::window::Window>) -> Result<Box<dyn Renderer>, ClewError>;
+
 impl<'a, App, Event> WindowManager<'a, App, Event> {
-    pub fn new(renderer_factory: fn(Arc<winit::window::Window>) -> Box<dyn Renderer>) -> Self {
+    pub fn new(renderer_factory: RendererFactory, fonts: FontResources) -> Self {
         Self {
             windows: HashMap::new(),
             event_loop: None,
             renderer_factory,
+            fonts,
             next_view_id: 0,
+            error: None,
+            geometry_store: None,
+            // Read before the event loop reports the appearance, so the preferences that
+            // don't depend on it are right from the start.
+            system_theme: theme::system_theme(None),
+            window_theme: None,
+        }
+    }
+
+    /// Store of the geometry of the windows opened with [`WindowPlacement::Remembered`],
+    /// without one they are opened with the default placement.
+    pub fn set_geometry_store(&mut self, store: impl WindowGeometryStore + 'static) {
+        self.geometry_store = Some(Box::new(store));
+    }
+
+    /// Monitors of the desktop, empty when there is neither the event loop nor a
+    /// window to ask.
+    pub fn monitors(&self) -> Vec<MonitorInfo> {
+        if let Some(event_loop) = self.event_loop {
+            let event_loop = unsafe { &*event_loop };
+            let primary = event_loop.primary_monitor();
+
+            return event_loop
+                .available_monitors()
+                .map(|monitor| MonitorInfo::new(&monitor, primary.as_ref()))
+                .collect();
+        }
+
+        let Some(window) = self.windows.values().next() else {
+            return Vec::new();
+        };
+        let primary = window.winit_window.primary_monitor();
+
+        window
+            .winit_window
+            .available_monitors()
+            .map(|monitor| MonitorInfo::new(&monitor, primary.as_ref()))
+            .collect()
+    }
+
+    /// Monitor the window of the view is on now.
+    pub fn monitor_of(&self, id: ViewId) -> Option<MonitorInfo> {
+        let window = self
+            .windows
+            .values()
+            .find(|window| window.instance.view().id == id)?;
+        let primary = window.winit_window.primary_monitor();
+
+        window
+            .winit_window
+            .current_monitor()
+            .map(|monitor| MonitorInfo::new(&monitor, primary.as_ref()))
+    }
+
+    /// Adds the placement to the attributes. The size is converted with the scale of the
+    /// monitor the window is opened on, it's left to the system for the default
+    /// placement.
+    fn place(
+        &self,
+        mut attributes: winit::window::WindowAttributes,
+        descriptor: &WindowDescriptor,
+    ) -> winit::window::WindowAttributes {
+        let size = winit::dpi::LogicalSize::new(descriptor.width as f64, descriptor.height as f64);
+        let monitors = self.monitors();
+
+        let monitor = match &descriptor.position {
+            WindowPlacement::Default => None,
+            WindowPlacement::CenteredOnPrimary => monitors
+                .iter()
+                .find(|monitor| monitor.is_primary)
+                .or_else(|| monitors.first())
+                .map(|monitor| (monitor.clone(), monitor.centered(size))),
+            WindowPlacement::CenteredOnMonitorOf(id) => self
+                .monitor_of(*id)
+                .map(|monitor| (monitor.clone(), monitor.centered(size))),
+            WindowPlacement::At(position) => {
+                monitor_at(&monitors, *position).map(|monitor| (monitor.clone(), *position))
+            }
+            WindowPlacement::Remembered(key) => {
+                let restored = self
+                    .geometry_store
+                    .as_ref()
+                    .and_then(|store| store.load(key))
+                    .and_then(|geometry| restore_geometry(&monitors, &geometry));
+
+                if let Some((position, size)) = restored {
+                    return attributes.with_position(position).with_inner_size(size);
+                }
+
+                None
+            }
+        };
+
+        if let Some((monitor, position)) = monitor {
+            attributes = attributes
+                .with_position(monitor.to_physical(position))
+                .with_inner_size(size.to_physical::<u32>(monitor.scale_factor));
+        }
+
+        attributes
+    }
+
+    /// Saves the geometry of the window if it was opened with
+    /// [`WindowPlacement::Remembered`].
+    pub(crate) fn remember_geometry(&mut self, id: winit::window::WindowId) {
+        let Some(window) = self.windows.get(&id) else {
+            return;
+        };
+        let (Some(key), Some(store)) = (&window.geometry_key, &mut self.geometry_store) else {
+            return;
+        };
+        let Some(monitor) = window.winit_window.current_monitor() else {
+            return;
+        };
+        let Ok(position) = window.winit_window.outer_position() else {
+            return;
+        };
+        let primary = window.winit_window.primary_monitor();
+        let monitor = MonitorInfo::new(&monitor, primary.as_ref());
+
+        if let Some(geometry) = save_geometry(&monitor, position, window.winit_window.inner_size())
+        {
+            store.save(key, geometry);
         }
     }
 
@@ -79,12 +245,17 @@ impl<'a, App, Event> WindowManager<'a, App, Event> {
     ) {
         if let Some(event_loop) = self.event_loop {
             let attributes = winit::window::WindowAttributes::default()
-                .with_title(descriptor.title)
+                .with_title(descriptor.title.clone())
                 .with_inner_size(winit::dpi::LogicalSize::new(
                     descriptor.width,
                     descriptor.height,
                 ))
-                .with_resizable(descriptor.resizable);
+                .with_resizable(descriptor.resizable)
+                .with_theme(theme::window_theme(self.window_theme))
+                .with_transparent(
+                    descriptor.background_material.is_some() && material::supported(),
+                );
+            let attributes = self.place(attributes, &descriptor);
 
             let event_loop = unsafe { &*event_loop };
             match event_loop.create_window(attributes) {
@@ -93,14 +264,36 @@ impl<'a, App, Event> WindowManager<'a, App, Event> {
                     let id = winit_window.id();
                     let scale_factor = winit_window.scale_factor();
                     let inner_size = winit_window.inner_size();
-                    let renderer = (self.renderer_factory)(winit_window.clone());
-                    let mut ui_state = UiState::new(View {
-                        id: ViewId(self.next_view_id),
-                        size: PhysicalSize::new(inner_size.width, inner_size.height),
-                        scale_factor: scale_factor as f32,
-                        safe_area: EdgeInsets::ZERO,
+                    let started = Instant::now();
+                    let mut renderer = match (self.renderer_factory)(winit_window.clone()) {
+                        Ok(renderer) => renderer,
+                        Err(err) => {
+                            log::error!("{err}");
+                            self.error = Some(err);
+                            return;
+                        }
+                    };
+                    log::debug!("Created renderer in {:?}", started.elapsed());
+
+                    let transparent = descriptor.background_material.is_some_and(|material| {
+                        material::apply(&winit_window, renderer.as_mut(), material)
                     });
+
+                    let mut instance = ClewInstance::new(
+                        View {
+                            id: ViewId(self.next_view_id),
+                            size: PhysicalSize::new(inner_size.width, inner_size.height),
+                            scale_factor: scale_factor as f32,
+                            safe_area: EdgeInsets::ZERO,
+                        },
+                        self.fonts.fork(),
+                    );
                     self.next_view_id += 1;
+                    instance.set_system_theme(self.system_theme);
+
+                    let ui_state = instance.ui_state();
+                    ui_state.latency_probe = std::env::var_os("CLEW_LATENCY_PROBE").is_some();
+                    ui_state.user_input.window_focused = winit_window.has_focus();
 
                     window.on_init(ui_state.shortcuts_registry());
 
@@ -109,12 +302,18 @@ impl<'a, App, Event> WindowManager<'a, App, Event> {
                         WindowState {
                             window: Box::new(window),
                             winit_window,
-                            texts: TextsResources::new(),
-                            strings: HashMap::new(),
-                            ui_state,
+                            instance,
                             renderer,
                             fill_color: descriptor.fill_color,
-                            delta_time_timer: Instant::now(),
+                            transparent,
+                            geometry_key: match descriptor.position {
+                                WindowPlacement::Remembered(key) => Some(key),
+                                _ => None,
+                            },
+                            resize_policy: descriptor.resize_policy,
+                            frozen_resize: None,
+                            ime_activated: false,
+                            ime_cursor_rect: Rect::default(),
                         },
                     );
 
@@ -138,7 +337,88 @@ impl<'a, App, Event> WindowManager<'a, App, Event> {
 
     pub fn request_view_redraw(&self, id: ViewId) {
         for window in self.windows.values() {
-            if window.ui_state.view.id == id {
+            if window.instance.view().id == id {
+                window.winit_window.request_redraw();
+            }
+        }
+    }
+
+    /// Draws the view with a renderer of the factory from now on, e.g. to compare the
+    /// backends side by side. The view keeps its renderer if the new one fails.
+    pub fn replace_renderer(
+        &mut self,
+        id: ViewId,
+        renderer_factory: RendererFactory,
+    ) -> Result<(), ClewError> {
+        for window in self.windows.values_mut() {
+            if window.instance.view().id == id {
+                let mut renderer = renderer_factory(window.winit_window.clone())?;
+
+                // The material stays behind the window, a renderer that can't draw
+                // transparent frames covers it with the fill color.
+                if window.transparent && !renderer.set_transparent_background(true) {
+                    log::warn!("The renderer can't draw transparent frames");
+                }
+
+                window.renderer = renderer;
+                window.instance.invalidate();
+                window.winit_window.request_redraw();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Theme of the system the windows are drawn in, see [`clew::theme`].
+    pub fn system_theme(&self) -> SystemTheme {
+        self.system_theme
+    }
+
+    /// Reads the system theme of the appearance and passes it to the windows, returns
+    /// it if it has changed.
+    pub(crate) fn set_system_theme(
+        &mut self,
+        appearance: Option<winit::window::Theme>,
+    ) -> Option<SystemTheme> {
+        let system_theme = theme::system_theme(appearance);
+        self.window_theme = appearance;
+
+        if system_theme == self.system_theme {
+            return None;
+        }
+
+        self.system_theme = system_theme;
+
+        for window in self.windows.values_mut() {
+            window.instance.set_system_theme(system_theme);
+            window.winit_window.request_redraw();
+        }
+
+        Some(system_theme)
+    }
+
+    pub fn set_layout_direction(&mut self, id: ViewId, layout_direction: LayoutDirection) {
+        for window in self.windows.values_mut() {
+            if window.instance.view().id == id {
+                window.instance.set_layout_direction(layout_direction);
+                window.winit_window.request_redraw();
+            }
+        }
+    }
+
+    /// Reports the part of the view covered by the platform, e.g. by an on-screen
+    /// keyboard or an IME candidate panel, see [`clew::ClewInstance::set_obscured_insets`].
+    /// Winit doesn't tell the size of those on the desktop, the application passes on
+    /// what it learns from the platform or a value of its own.
+    pub fn set_obscured_insets(
+        &mut self,
+        id: ViewId,
+        insets: EdgeInsets,
+        animation: Option<Duration>,
+    ) {
+        for window in self.windows.values_mut() {
+            if window.instance.view().id == id {
+                window.instance.set_obscured_insets(insets, animation);
                 window.winit_window.request_redraw();
             }
         }
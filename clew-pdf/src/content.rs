@@ -0,0 +1,240 @@
+use std::fmt::Write;
+
+use clew::{BorderRadius, ColorRgba, PathSegment, Rect};
+
+use crate::document::Num;
+
+/// Magic constant for circle/ellipse approximation with bezier curves.
+const KAPPA: f32 = 0.552_284_8;
+
+/// Operators of a content stream, the drawing of a page or of a form.
+#[derive(Default)]
+pub(crate) struct Content {
+    ops: String,
+}
+
+impl Content {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.ops.into_bytes()
+    }
+
+    fn op(&mut self, operands: &[f32], operator: &str) {
+        for operand in operands {
+            write!(self.ops, "{} ", Num(*operand)).unwrap();
+        }
+
+        self.ops.push_str(operator);
+        self.ops.push('\n');
+    }
+
+    pub(crate) fn save(&mut self) {
+        self.op(&[], "q");
+    }
+
+    pub(crate) fn restore(&mut self) {
+        self.op(&[], "Q");
+    }
+
+    /// Concatenates the matrix to the current transform, in the order of PDF.
+    pub(crate) fn transform(&mut self, matrix: [f32; 6]) {
+        self.op(&matrix, "cm");
+    }
+
+    pub(crate) fn move_to(&mut self, x: f32, y: f32) {
+        self.op(&[x, y], "m");
+    }
+
+    pub(crate) fn line_to(&mut self, x: f32, y: f32) {
+        self.op(&[x, y], "l");
+    }
+
+    pub(crate) fn cubic_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.op(&[x1, y1, x2, y2, x, y], "c");
+    }
+
+    pub(crate) fn close(&mut self) {
+        self.op(&[], "h");
+    }
+
+    pub(crate) fn rect(&mut self, rect: Rect) {
+        self.op(&[rect.x, rect.y, rect.width, rect.height], "re");
+    }
+
+    /// Rect with the corners rounded the way the other renderers do, the radii are
+    /// clamped to half of the sides.
+    pub(crate) fn rounded_rect(&mut self, rect: Rect, border_radius: &BorderRadius) {
+        let max_radius = (rect.width / 2.).min(rect.height / 2.);
+        let tl = border_radius.top_left.min(max_radius).max(0.);
+        let tr = border_radius.top_right.min(max_radius).max(0.);
+        let br = border_radius.bottom_right.min(max_radius).max(0.);
+        let bl = border_radius.bottom_left.min(max_radius).max(0.);
+        let (left, top, right, bottom) = (rect.left(), rect.top(), rect.right(), rect.bottom());
+
+        self.move_to(left + tl, top);
+        self.line_to(right - tr, top);
+        self.cubic_to(
+            right - tr * (1. - KAPPA),
+            top,
+            right,
+            top + tr * (1. - KAPPA),
+            right,
+            top + tr,
+        );
+        self.line_to(right, bottom - br);
+        self.cubic_to(
+            right,
+            bottom - br * (1. - KAPPA),
+            right - br * (1. - KAPPA),
+            bottom,
+            right - br,
+            bottom,
+        );
+        self.line_to(left + bl, bottom);
+        self.cubic_to(
+            left + bl * (1. - KAPPA),
+            bottom,
+            left,
+            bottom - bl * (1. - KAPPA),
+            left,
+            bottom - bl,
+        );
+        self.line_to(left, top + tl);
+        self.cubic_to(
+            left,
+            top + tl * (1. - KAPPA),
+            left + tl * (1. - KAPPA),
+            top,
+            left + tl,
+            top,
+        );
+        self.close();
+    }
+
+    pub(crate) fn oval(&mut self, rect: Rect) {
+        let (cx, cy) = (rect.x + rect.width / 2., rect.y + rect.height / 2.);
+        let (rx, ry) = (rect.width / 2., rect.height / 2.);
+        let (ox, oy) = (rx * KAPPA, ry * KAPPA);
+
+        self.move_to(cx - rx, cy);
+        self.cubic_to(cx - rx, cy - oy, cx - ox, cy - ry, cx, cy - ry);
+        self.cubic_to(cx + ox, cy - ry, cx + rx, cy - oy, cx + rx, cy);
+        self.cubic_to(cx + rx, cy + oy, cx + ox, cy + ry, cx, cy + ry);
+        self.cubic_to(cx - ox, cy + ry, cx - rx, cy + oy, cx - rx, cy);
+        self.close();
+    }
+
+    pub(crate) fn outline(&mut self, outline: &[PathSegment]) {
+        for segment in outline {
+            match *segment {
+                PathSegment::MoveTo(x, y) => self.move_to(x, y),
+                PathSegment::LineTo(x, y) => self.line_to(x, y),
+                PathSegment::CubicTo((x1, y1), (x2, y2), (x, y)) => {
+                    self.cubic_to(x1, y1, x2, y2, x, y)
+                }
+                PathSegment::Close => self.close(),
+            }
+        }
+    }
+
+    pub(crate) fn fill(&mut self, even_odd: bool) {
+        self.op(&[], if even_odd { "f*" } else { "f" });
+    }
+
+    pub(crate) fn stroke(&mut self) {
+        self.op(&[], "S");
+    }
+
+    /// Intersects the clip with the path and ends the path without painting it.
+    pub(crate) fn clip(&mut self, even_odd: bool) {
+        self.op(&[], if even_odd { "W* n" } else { "W n" });
+    }
+
+    /// The alpha is set separately with a graphics state.
+    pub(crate) fn fill_color(&mut self, color: ColorRgba) {
+        self.op(&[color.r, color.g, color.b], "rg");
+    }
+
+    pub(crate) fn stroke_color(&mut self, color: ColorRgba) {
+        self.op(&[color.r, color.g, color.b], "RG");
+    }
+
+    pub(crate) fn line_width(&mut self, width: f32) {
+        self.op(&[width], "w");
+    }
+
+    /// 0 is butt, 1 is round and 2 is square.
+    pub(crate) fn line_cap(&mut self, cap: u8) {
+        self.op(&[cap as f32], "J");
+    }
+
+    /// 0 is miter, 1 is round and 2 is bevel.
+    pub(crate) fn line_join(&mut self, join: u8) {
+        self.op(&[join as f32], "j");
+    }
+
+    pub(crate) fn miter_limit(&mut self, limit: f32) {
+        self.op(&[limit], "M");
+    }
+
+    pub(crate) fn dash(&mut self, pattern: &[f32], offset: f32) {
+        self.ops.push('[');
+
+        for (index, length) in pattern.iter().enumerate() {
+            if index > 0 {
+                self.ops.push(' ');
+            }
+
+            write!(self.ops, "{}", Num(*length)).unwrap();
+        }
+
+        self.ops.push_str("] ");
+        self.op(&[offset], "d");
+    }
+
+    /// Applies a graphics state of the resources, e.g. the alpha.
+    pub(crate) fn graphics_state(&mut self, name: &str) {
+        writeln!(self.ops, "/{name} gs").unwrap();
+    }
+
+    /// Paints the shading of the resources over the clip.
+    pub(crate) fn shading(&mut self, name: &str) {
+        writeln!(self.ops, "/{name} sh").unwrap();
+    }
+
+    /// Draws the image or form of the resources into the unit square.
+    pub(crate) fn x_object(&mut self, name: &str) {
+        writeln!(self.ops, "/{name} Do").unwrap();
+    }
+
+    pub(crate) fn begin_text(&mut self) {
+        self.op(&[], "BT");
+    }
+
+    pub(crate) fn end_text(&mut self) {
+        self.op(&[], "ET");
+    }
+
+    pub(crate) fn font(&mut self, name: &str, size: f32) {
+        writeln!(self.ops, "/{name} {} Tf", Num(size)).unwrap();
+    }
+
+    /// 0 fills the glyphs, 1 strokes them.
+    pub(crate) fn text_rendering_mode(&mut self, mode: u8) {
+        self.op(&[mode as f32], "Tr");
+    }
+
+    /// Places the next glyph with its origin at the point, upright on the pages drawn
+    /// from the top.
+    pub(crate) fn text_position(&mut self, x: f32, y: f32) {
+        self.op(&[1., 0., 0., -1., x, y], "Tm");
+    }
+
+    /// Shows the glyph of the current font by its id.
+    pub(crate) fn glyph(&mut self, glyph_id: u16) {
+        writeln!(self.ops, "<{glyph_id:04X}> Tj").unwrap();
+    }
+}
@@ -0,0 +1,146 @@
+use std::fmt::{self, Display, Write};
+
+/// Reference to an indirect object of the [`Document`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub(crate) struct ObjectId(u32);
+
+impl Display for ObjectId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} 0 R", self.0)
+    }
+}
+
+/// Objects of a PDF file, written out with the cross-reference table by
+/// [`Document::finish`]. The ids are reserved first so the objects can refer to the
+/// ones written after them, e.g. the pages to their parent.
+#[derive(Default)]
+pub(crate) struct Document {
+    objects: Vec<Option<Vec<u8>>>,
+}
+
+impl Document {
+    pub(crate) fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn reserve(&mut self) -> ObjectId {
+        self.objects.push(None);
+
+        ObjectId(self.objects.len() as u32)
+    }
+
+    /// Writes the object with the id, the body is a dictionary or any other object.
+    pub(crate) fn write(&mut self, id: ObjectId, body: impl Display) {
+        self.objects[id.0 as usize - 1] = Some(body.to_string().into_bytes());
+    }
+
+    pub(crate) fn add(&mut self, body: impl Display) -> ObjectId {
+        let id = self.reserve();
+        self.write(id, body);
+
+        id
+    }
+
+    /// Writes a stream compressed with its dictionary, the entries are added to the
+    /// length and the filter.
+    pub(crate) fn write_stream(&mut self, id: ObjectId, entries: &str, data: &[u8]) {
+        let data = miniz_oxide::deflate::compress_to_vec_zlib(data, 6);
+        let mut body = format!(
+            "<< /Length {} /Filter /FlateDecode {entries} >>\nstream\n",
+            data.len()
+        )
+        .into_bytes();
+
+        body.extend_from_slice(&data);
+        body.extend_from_slice(b"\nendstream");
+
+        self.objects[id.0 as usize - 1] = Some(body);
+    }
+
+    pub(crate) fn add_stream(&mut self, entries: &str, data: &[u8]) -> ObjectId {
+        let id = self.reserve();
+        self.write_stream(id, entries, data);
+
+        id
+    }
+
+    /// The file with the catalog as its root. The reserved objects that were never
+    /// written are left out as free entries.
+    pub(crate) fn finish(self, catalog: ObjectId) -> Vec<u8> {
+        // The binary comment tells the transfer tools that the file isn't text.
+        let mut out = b"%PDF-1.7\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+
+        for (index, object) in self.objects.iter().enumerate() {
+            let Some(object) = object else {
+                offsets.push(None);
+                continue;
+            };
+
+            offsets.push(Some(out.len()));
+            out.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = out.len();
+        let mut table = format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1);
+
+        for offset in offsets {
+            match offset {
+                Some(offset) => writeln!(table, "{offset:010} 00000 n ").unwrap(),
+                None => writeln!(table, "0000000000 00000 f ").unwrap(),
+            }
+        }
+
+        write!(
+            table,
+            "trailer\n<< /Size {} /Root {catalog} >>\nstartxref\n{xref}\n%%EOF\n",
+            self.objects.len() + 1
+        )
+        .unwrap();
+
+        out.extend_from_slice(table.as_bytes());
+
+        out
+    }
+}
+
+/// Number written with at most 3 decimals, which is 1/72000 of an inch on the page.
+#[derive(Clone, Copy)]
+pub(crate) struct Num(pub f32);
+
+impl Display for Num {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = if self.0.is_finite() { self.0 } else { 0. };
+        let rounded = (value * 1000.).round() / 1000.;
+
+        if rounded == rounded.trunc() {
+            // Also turns -0 into 0.
+            write!(f, "{}", rounded as i64)
+        } else {
+            let text = format!("{rounded:.3}");
+
+            f.write_str(text.trim_end_matches('0'))
+        }
+    }
+}
+
+/// Name object with the characters outside of the regular ones escaped.
+pub(crate) struct Name<'a>(pub &'a str);
+
+impl Display for Name<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('/')?;
+
+        for byte in self.0.bytes() {
+            if byte.is_ascii_alphanumeric() || b"-_.+".contains(&byte) {
+                f.write_char(byte as char)?;
+            } else {
+                write!(f, "#{byte:02X}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
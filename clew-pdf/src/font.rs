@@ -0,0 +1,516 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    sync::Arc,
+};
+
+use cosmic_text::ttf_parser::{self, Tag, name_id};
+
+use crate::document::{Document, Name, Num, ObjectId};
+
+/// Font whose glyphs are drawn in the document, embedded at the end with only the glyphs
+/// that were drawn.
+///
+/// The glyphs are written by their ids in the font, so the shaping done by cosmic-text is
+/// kept as is, including the ligatures and the glyphs of the complex scripts that don't
+/// map to a single character. The text they came from is written alongside, so the
+/// document can be searched and copied from.
+pub(crate) struct EmbeddedFont {
+    font: Arc<cosmic_text::Font>,
+    index: u32,
+    /// Glyphs drawn with the font and the text of their clusters.
+    glyphs: BTreeMap<u16, String>,
+}
+
+impl EmbeddedFont {
+    /// `None` when the font can't be parsed or has outlines PDF doesn't know, e.g. the
+    /// CFF2 ones of the variable fonts, its glyphs are left out then.
+    pub(crate) fn new(font: Arc<cosmic_text::Font>, index: u32) -> Option<Self> {
+        let face = ttf_parser::RawFace::parse(font.data(), index).ok()?;
+
+        if face.table(Tag::from_bytes(b"glyf")).is_none()
+            && face.table(Tag::from_bytes(b"CFF ")).is_none()
+        {
+            return None;
+        }
+
+        Some(Self {
+            font,
+            index,
+            glyphs: BTreeMap::new(),
+        })
+    }
+
+    /// Marks the glyph as drawn, the text is kept for the first cluster it's drawn for.
+    pub(crate) fn use_glyph(&mut self, glyph_id: u16, text: &str) {
+        let entry = self.glyphs.entry(glyph_id).or_default();
+
+        if entry.is_empty() {
+            entry.push_str(text);
+        }
+    }
+
+    /// Writes the font with its descendant and returns the font to refer to from the
+    /// resources, the glyphs are encoded by their ids in two bytes.
+    pub(crate) fn write(&self, document: &mut Document) -> ObjectId {
+        let face = ttf_parser::Face::parse(self.font.data(), self.index)
+            .expect("The font is checked when it's created");
+        let raw = face.raw_face();
+        let scale = 1000. / face.units_per_em() as f32;
+        let glyph_ids: BTreeSet<u16> = self.glyphs.keys().copied().collect();
+
+        let base_font = format!("{}+{}", subset_tag(&glyph_ids), postscript_name(&face));
+
+        // The glyf outlines are cut down to the drawn glyphs, the CFF ones are embedded
+        // whole.
+        let (program, descendant_type) = match subset_glyf(raw, &glyph_ids) {
+            Some(program) => (
+                document.add_stream(&format!("/Length1 {}", program.len()), &program),
+                "CIDFontType2",
+            ),
+            None => {
+                let program = copy_face(raw, u32::from_be_bytes(*b"OTTO"));
+
+                (
+                    document.add_stream("/Subtype /OpenType", &program),
+                    "CIDFontType0",
+                )
+            }
+        };
+
+        let bbox = face.global_bounding_box();
+        let mut flags = 1 << 2;
+
+        if face.is_monospaced() {
+            flags |= 1;
+        }
+
+        if face.is_italic() {
+            flags |= 1 << 6;
+        }
+
+        let descriptor = document.add(format!(
+            "<< /Type /FontDescriptor /FontName {} /Flags {flags} /FontBBox [{} {} {} {}] \
+             /ItalicAngle {} /Ascent {} /Descent {} /CapHeight {} /StemV 80 /{} {program} >>",
+            Name(&base_font),
+            Num(bbox.x_min as f32 * scale),
+            Num(bbox.y_min as f32 * scale),
+            Num(bbox.x_max as f32 * scale),
+            Num(bbox.y_max as f32 * scale),
+            Num(face.italic_angle().unwrap_or(0.)),
+            Num(face.ascender() as f32 * scale),
+            Num(face.descender() as f32 * scale),
+            Num(face.capital_height().unwrap_or(face.ascender()) as f32 * scale),
+            if descendant_type == "CIDFontType2" {
+                "FontFile2"
+            } else {
+                "FontFile3"
+            },
+        ));
+
+        let mut widths = String::new();
+
+        for glyph_id in &glyph_ids {
+            let advance = face
+                .glyph_hor_advance(ttf_parser::GlyphId(*glyph_id))
+                .unwrap_or(0);
+
+            write!(widths, "{glyph_id} [{}] ", Num(advance as f32 * scale)).unwrap();
+        }
+
+        let cid_to_gid = if descendant_type == "CIDFontType2" {
+            "/CIDToGIDMap /Identity "
+        } else {
+            ""
+        };
+        let descendant = document.add(format!(
+            "<< /Type /Font /Subtype /{descendant_type} /BaseFont {} \
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (Identity) /Supplement 0 >> \
+             /FontDescriptor {descriptor} {cid_to_gid}/DW 0 /W [{widths}] >>",
+            Name(&base_font),
+        ));
+
+        let to_unicode = document.add_stream("", self.to_unicode().as_bytes());
+
+        document.add(format!(
+            "<< /Type /Font /Subtype /Type0 /BaseFont {} /Encoding /Identity-H \
+             /DescendantFonts [{descendant}] /ToUnicode {to_unicode} >>",
+            Name(&base_font),
+        ))
+    }
+
+    /// CMap from the glyph ids to the text of their clusters.
+    fn to_unicode(&self) -> String {
+        let mut cmap = String::from(
+            "/CIDInit /ProcSet findresource begin\n12 dict begin\nbegincmap\n\
+             /CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def\n\
+             /CMapName /Adobe-Identity-UCS def\n/CMapType 2 def\n\
+             1 begincodespacerange\n<0000> <FFFF>\nendcodespacerange\n",
+        );
+        let mapped: Vec<_> = self
+            .glyphs
+            .iter()
+            .filter(|(_, text)| !text.is_empty())
+            .collect();
+
+        // A section can't have more than 100 entries.
+        for chunk in mapped.chunks(100) {
+            writeln!(cmap, "{} beginbfchar", chunk.len()).unwrap();
+
+            for (glyph_id, text) in chunk {
+                write!(cmap, "<{glyph_id:04X}> <").unwrap();
+
+                for unit in text.encode_utf16() {
+                    write!(cmap, "{unit:04X}").unwrap();
+                }
+
+                cmap.push_str(">\n");
+            }
+
+            cmap.push_str("endbfchar\n");
+        }
+
+        cmap.push_str("endcmap\nCMapName currentdict /CMap defineresource pop\nend\nend\n");
+
+        cmap
+    }
+}
+
+/// Six capital letters naming the subset, the same glyphs give the same tag.
+fn subset_tag(glyph_ids: &BTreeSet<u16>) -> String {
+    // FNV-1a, the tag only has to differ between the subsets of a document.
+    let mut hash: u32 = 0x811C_9DC5;
+
+    for glyph_id in glyph_ids {
+        for byte in glyph_id.to_be_bytes() {
+            hash = (hash ^ byte as u32).wrapping_mul(0x0100_0193);
+        }
+    }
+
+    (0..6)
+        .map(|_| {
+            let letter = (b'A' + (hash % 26) as u8) as char;
+            hash /= 26;
+
+            letter
+        })
+        .collect()
+}
+
+fn postscript_name(face: &ttf_parser::Face) -> String {
+    let name: String = face
+        .names()
+        .into_iter()
+        .filter(|name| name.name_id == name_id::POST_SCRIPT_NAME)
+        // The Macintosh names can't be decoded, the same name follows in UTF-16.
+        .find_map(|name| name.to_string())
+        .unwrap_or_default()
+        .chars()
+        .filter(|char| char.is_ascii_graphic() && !"[](){}<>/%".contains(*char))
+        .collect();
+
+    if name.is_empty() {
+        "Font".to_string()
+    } else {
+        name
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// TrueType font with the outlines of the glyphs other than the given ones emptied.
+/// The glyphs keep their ids, the text is written with them. `None` when the font has
+/// no glyf outlines.
+fn subset_glyf(raw: &ttf_parser::RawFace, glyph_ids: &BTreeSet<u16>) -> Option<Vec<u8>> {
+    let table = |tag: &[u8; 4]| raw.table(Tag::from_bytes(tag));
+
+    let glyf = table(b"glyf")?;
+    let loca = table(b"loca")?;
+    let head = table(b"head")?;
+    let num_glyphs = read_u16(table(b"maxp")?, 4)? as usize;
+    let long_loca = read_u16(head, 50)? == 1;
+
+    let glyph_range = |glyph_id: usize| -> Option<std::ops::Range<usize>> {
+        let (start, end) = if long_loca {
+            (
+                read_u32(loca, glyph_id * 4)? as usize,
+                read_u32(loca, glyph_id * 4 + 4)? as usize,
+            )
+        } else {
+            (
+                read_u16(loca, glyph_id * 2)? as usize * 2,
+                read_u16(loca, glyph_id * 2 + 2)? as usize * 2,
+            )
+        };
+
+        (start <= end && end <= glyf.len()).then_some(start..end)
+    };
+
+    // The glyph drawn for the missing ones is always kept, and so are the parts of the
+    // composite glyphs.
+    let mut kept = BTreeSet::new();
+    let mut pending: Vec<u16> = glyph_ids.iter().copied().chain([0]).collect();
+
+    while let Some(glyph_id) = pending.pop() {
+        if glyph_id as usize >= num_glyphs || !kept.insert(glyph_id) {
+            continue;
+        }
+
+        if let Some(range) = glyph_range(glyph_id as usize) {
+            pending.extend(
+                composite_components(&glyf[range])
+                    .into_iter()
+                    .filter(|component| !kept.contains(component)),
+            );
+        }
+    }
+
+    let mut new_glyf = Vec::new();
+    let mut new_loca = Vec::with_capacity((num_glyphs + 1) * 4);
+
+    for glyph_id in 0..num_glyphs {
+        new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+
+        if kept.contains(&(glyph_id as u16))
+            && let Some(range) = glyph_range(glyph_id)
+        {
+            new_glyf.extend_from_slice(&glyf[range]);
+
+            while new_glyf.len() % 4 != 0 {
+                new_glyf.push(0);
+            }
+        }
+    }
+
+    new_loca.extend_from_slice(&(new_glyf.len() as u32).to_be_bytes());
+
+    let mut new_head = head.to_vec();
+    // The offsets are written in the long format.
+    new_head
+        .get_mut(50..52)?
+        .copy_from_slice(&1u16.to_be_bytes());
+
+    let mut tables = vec![
+        (*b"head", new_head),
+        (*b"loca", new_loca),
+        (*b"glyf", new_glyf),
+    ];
+
+    // Metrics and the hinting programs the glyphs rely on.
+    for tag in [b"hhea", b"hmtx", b"maxp", b"cvt ", b"fpgm", b"prep"] {
+        if let Some(data) = table(tag) {
+            tables.push((*tag, data.to_vec()));
+        }
+    }
+
+    Some(write_sfnt(0x0001_0000, tables))
+}
+
+/// Ids of the glyphs a composite glyph is made of, none for a simple glyph.
+fn composite_components(glyph: &[u8]) -> Vec<u16> {
+    const ARG_1_AND_2_ARE_WORDS: u16 = 0x0001;
+    const WE_HAVE_A_SCALE: u16 = 0x0008;
+    const MORE_COMPONENTS: u16 = 0x0020;
+    const WE_HAVE_AN_X_AND_Y_SCALE: u16 = 0x0040;
+    const WE_HAVE_A_TWO_BY_TWO: u16 = 0x0080;
+
+    let mut components = Vec::new();
+
+    // Composite glyphs have a negative number of contours.
+    if read_u16(glyph, 0).is_none_or(|contours| (contours as i16) >= 0) {
+        return components;
+    }
+
+    let mut offset = 10;
+
+    while let (Some(flags), Some(glyph_id)) = (read_u16(glyph, offset), read_u16(glyph, offset + 2))
+    {
+        components.push(glyph_id);
+
+        offset += if flags & ARG_1_AND_2_ARE_WORDS != 0 {
+            8
+        } else {
+            6
+        };
+
+        if flags & WE_HAVE_A_SCALE != 0 {
+            offset += 2;
+        } else if flags & WE_HAVE_AN_X_AND_Y_SCALE != 0 {
+            offset += 4;
+        } else if flags & WE_HAVE_A_TWO_BY_TWO != 0 {
+            offset += 8;
+        }
+
+        if flags & MORE_COMPONENTS == 0 {
+            break;
+        }
+    }
+
+    components
+}
+
+/// The face on its own, e.g. taken out of a collection.
+fn copy_face(raw: &ttf_parser::RawFace, version: u32) -> Vec<u8> {
+    let tables = raw
+        .table_records
+        .into_iter()
+        .filter_map(|record| {
+            let data = raw.table(record.tag)?;
+
+            Some((record.tag.to_bytes(), data.to_vec()))
+        })
+        .collect();
+
+    write_sfnt(version, tables)
+}
+
+fn checksum(data: &[u8]) -> u32 {
+    data.chunks(4).fold(0u32, |sum, chunk| {
+        let mut word = [0; 4];
+        word[..chunk.len()].copy_from_slice(chunk);
+
+        sum.wrapping_add(u32::from_be_bytes(word))
+    })
+}
+
+/// Font file with the tables, the checksums are computed for them.
+fn write_sfnt(version: u32, mut tables: Vec<([u8; 4], Vec<u8>)>) -> Vec<u8> {
+    tables.sort_by_key(|(tag, _)| *tag);
+
+    let count = tables.len() as u16;
+    let entry_selector = 15 - count.max(1).leading_zeros() as u16;
+    let search_range: u16 = (1 << entry_selector) * 16;
+
+    let mut out = Vec::new();
+    out.extend_from_slice(&version.to_be_bytes());
+    out.extend_from_slice(&count.to_be_bytes());
+    out.extend_from_slice(&search_range.to_be_bytes());
+    out.extend_from_slice(&entry_selector.to_be_bytes());
+    out.extend_from_slice(&(count * 16 - search_range).to_be_bytes());
+
+    let mut offset = 12 + tables.len() * 16;
+    let mut head_offset = None;
+
+    for (tag, data) in &mut tables {
+        if tag == b"head" && data.len() >= 12 {
+            // Computed over the whole file once it's written.
+            data[8..12].fill(0);
+            head_offset = Some(offset);
+        }
+
+        out.extend_from_slice(tag);
+        out.extend_from_slice(&checksum(data).to_be_bytes());
+        out.extend_from_slice(&(offset as u32).to_be_bytes());
+        out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+        offset += data.len().next_multiple_of(4);
+    }
+
+    for (_, data) in &tables {
+        out.extend_from_slice(data);
+        out.resize(out.len().next_multiple_of(4), 0);
+    }
+
+    if let Some(head_offset) = head_offset {
+        let adjustment = 0xB1B0_AFBAu32.wrapping_sub(checksum(&out));
+
+        out[head_offset + 8..head_offset + 12].copy_from_slice(&adjustment.to_be_bytes());
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FONT: &[u8] = include_bytes!("../../test-fonts/DejaVuSansMono.ttf");
+
+    /// Outline of the glyph as the segments it's drawn with, `None` for an empty one.
+    fn outline(face: &ttf_parser::Face, glyph_id: u16) -> Option<Vec<String>> {
+        struct Segments(Vec<String>);
+
+        impl ttf_parser::OutlineBuilder for Segments {
+            fn move_to(&mut self, x: f32, y: f32) {
+                self.0.push(format!("M {x} {y}"));
+            }
+
+            fn line_to(&mut self, x: f32, y: f32) {
+                self.0.push(format!("L {x} {y}"));
+            }
+
+            fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+                self.0.push(format!("Q {x1} {y1} {x} {y}"));
+            }
+
+            fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+                self.0.push(format!("C {x1} {y1} {x2} {y2} {x} {y}"));
+            }
+
+            fn close(&mut self) {
+                self.0.push("Z".to_string());
+            }
+        }
+
+        let mut segments = Segments(Vec::new());
+        face.outline_glyph(ttf_parser::GlyphId(glyph_id), &mut segments)?;
+
+        Some(segments.0)
+    }
+
+    #[test]
+    fn test_subset_keeps_drawn_and_composite_glyphs() {
+        let face = ttf_parser::Face::parse(FONT, 0).unwrap();
+        let glyph = |char| face.glyph_index(char).unwrap().0;
+        let (e_acute, e, a, b) = (glyph('é'), glyph('e'), glyph('A'), glyph('B'));
+
+        // é is made of e and the accent.
+        let raw = face.raw_face();
+        let glyf = raw.table(Tag::from_bytes(b"glyf")).unwrap();
+        let loca = raw.table(Tag::from_bytes(b"loca")).unwrap();
+        let long_loca = read_u16(raw.table(Tag::from_bytes(b"head")).unwrap(), 50) == Some(1);
+        let offset = |glyph_id: u16| {
+            if long_loca {
+                read_u32(loca, glyph_id as usize * 4).unwrap() as usize
+            } else {
+                read_u16(loca, glyph_id as usize * 2).unwrap() as usize * 2
+            }
+        };
+        let components = composite_components(&glyf[offset(e_acute)..offset(e_acute + 1)]);
+
+        assert_eq!(components.len(), 2);
+        assert!(components.contains(&e));
+        assert!(composite_components(&glyf[offset(a)..offset(a + 1)]).is_empty());
+
+        let program = subset_glyf(raw, &BTreeSet::from([e_acute, a])).unwrap();
+        let subset = ttf_parser::Face::parse(&program, 0).unwrap();
+
+        // The glyphs keep their ids, the missing glyph and the components are kept.
+        assert_eq!(subset.number_of_glyphs(), face.number_of_glyphs());
+
+        for glyph_id in [0, e_acute, a].into_iter().chain(components) {
+            assert!(outline(&face, glyph_id).is_some());
+            assert_eq!(outline(&subset, glyph_id), outline(&face, glyph_id));
+            assert_eq!(
+                subset.glyph_hor_advance(ttf_parser::GlyphId(glyph_id)),
+                face.glyph_hor_advance(ttf_parser::GlyphId(glyph_id))
+            );
+        }
+
+        assert!(outline(&face, b).is_some());
+        assert_eq!(outline(&subset, b), None);
+
+        // The checksum adjustment of the head makes the whole file sum up to the magic.
+        assert_eq!(checksum(&program), 0xB1B0_AFBA);
+    }
+}
@@ -0,0 +1,301 @@
+//! PDF export of the views, e.g. to print a report with the same widgets as the screens.
+//!
+//! [`export_pdf`] lays the content out at the width of the page and splits it into pages
+//! between the widgets. [`PdfRenderer`] can also be used as the renderer of a view, each
+//! rendered frame is a page of the document.
+//!
+//! Shapes, solid colors, linear and radial gradients and the SVGs made of plain paths
+//! are written as vectors, the texts with the glyphs of their fonts embedded. The sweep
+//! gradients are filled with their average color and the alpha of a gradient is averaged
+//! over its stops. The SVGs with images, filters, masks, clip paths or gradients are
+//! rasterized at twice their size.
+
+use std::sync::Arc;
+
+use clew::{
+    ClewHost, ClewInstance, ColorRgb, EdgeInsets, PhysicalSize, Rect, Resources, View, ViewId,
+    assets::Assets,
+    text::FontResources,
+    widgets::builder::{BuildContext, NoopEventLoopProxy},
+};
+
+mod content;
+mod document;
+mod font;
+mod renderer;
+mod svg;
+
+pub use renderer::PdfRenderer;
+
+use renderer::Pagination;
+
+/// Height of the view the content is laid out in, so nothing is cut by its bottom.
+const LAYOUT_HEIGHT: u32 = 1 << 20;
+
+/// Size of a page in points, 1/72 of an inch. One logical pixel of the content is one
+/// point.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageSize {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl PageSize {
+    pub const A4: PageSize = PageSize::new(595.28, 841.89);
+    pub const LETTER: PageSize = PageSize::new(612., 792.);
+
+    pub const fn new(width: f32, height: f32) -> Self {
+        Self { width, height }
+    }
+}
+
+/// Builds the content once and returns it as a PDF document of the pages it takes, with
+/// the system fonts and no assets. See [`export_pdf_with`] to use the assets and the
+/// fonts of an application.
+pub fn export_pdf(
+    page_size: PageSize,
+    margins: EdgeInsets,
+    build: impl FnMut(&mut BuildContext),
+) -> Vec<u8> {
+    let mut host = ClewHost::new(
+        Assets::new(),
+        Resources::new(),
+        Arc::new(NoopEventLoopProxy),
+    );
+
+    export_pdf_with(&mut host, FontResources::new(), page_size, margins, build)
+}
+
+/// Builds the content once at the width of the page inside of the margins and returns
+/// it as a PDF document. The pages end above the widgets that would be cut by their
+/// bottom. A widget taller than a page starts a page and is clipped by its bottom, the
+/// next page continues below it.
+///
+/// The content is built with the animations paused, the async results that aren't ready
+/// yet, e.g. the images still loading, are left out.
+pub fn export_pdf_with(
+    host: &mut ClewHost,
+    fonts: FontResources,
+    page_size: PageSize,
+    margins: EdgeInsets,
+    mut build: impl FnMut(&mut BuildContext),
+) -> Vec<u8> {
+    let content_width = (page_size.width - margins.left - margins.right).max(1.);
+    let content_height = (page_size.height - margins.top - margins.bottom).max(1.);
+    let view = View {
+        id: ViewId(0),
+        size: PhysicalSize::new(content_width.round() as u32, LAYOUT_HEIGHT),
+        scale_factor: 1.,
+        safe_area: EdgeInsets::ZERO,
+    };
+
+    let mut instance = ClewInstance::new(view, fonts);
+    instance.set_paused(true);
+    instance.frame(host, &mut build);
+
+    let rects: Vec<Rect> = instance
+        .ui_state()
+        .placed_widgets()
+        .map(|(_, rect)| rect)
+        // The widgets filling the view only end at its bottom.
+        .filter(|rect| rect.height < LAYOUT_HEIGHT as f32)
+        .collect();
+
+    let content_bottom = rects
+        .iter()
+        .fold(0., |bottom: f32, rect| bottom.max(rect.bottom()));
+
+    for rect in tall_leaves(&rects, content_height) {
+        log::warn!(
+            "A widget of {} points is taller than the page of {content_height} points, it's clipped by the bottom of its page",
+            rect.height
+        );
+    }
+
+    let mut renderer = PdfRenderer::new();
+    renderer.pagination = Some(Pagination {
+        page_size,
+        margins,
+        slices: page_breaks(&rects, content_bottom, content_height),
+    });
+
+    instance.render(host, &mut renderer, ColorRgb::from_hex(0xFFFFFF));
+
+    renderer.finish()
+}
+
+/// Widgets taller than a page with no other widgets inside of them, these can't be moved
+/// to a page of their own.
+fn tall_leaves(rects: &[Rect], page_height: f32) -> Vec<Rect> {
+    rects
+        .iter()
+        .enumerate()
+        .filter(|(index, rect)| {
+            rect.height > page_height
+                && !rects.iter().enumerate().any(|(other, inner)| {
+                    other != *index && inner != *rect && rect.contains_rect(*inner)
+                })
+        })
+        .map(|(_, rect)| *rect)
+        .collect()
+}
+
+/// Vertical ranges of the pages, each ends above the widgets that its bottom would cut.
+/// A widget taller than a page starts a page clipped at the page height, the next one
+/// starts at the bottom of the widget or at the first widget below the clipped page. An
+/// empty content still takes a page.
+fn page_breaks(rects: &[Rect], content_bottom: f32, page_height: f32) -> Vec<(f32, f32)> {
+    const EPSILON: f32 = 0.01;
+
+    let tall_leaves = tall_leaves(rects, page_height);
+    let mut slices = Vec::new();
+    let mut top = 0.;
+
+    while top < content_bottom - EPSILON || slices.is_empty() {
+        let mut bottom = top + page_height;
+
+        if bottom >= content_bottom {
+            slices.push((top, content_bottom.max(top)));
+            break;
+        }
+
+        // Moving the break up to a widget can cut the ones around it, e.g. its parent.
+        while let Some(widget_top) = rects
+            .iter()
+            .filter(|rect| {
+                (rect.height <= page_height || tall_leaves.contains(rect))
+                    && rect.top() > top + EPSILON
+                    && rect.top() < bottom - EPSILON
+                    && rect.bottom() > bottom + EPSILON
+            })
+            .map(|rect| rect.top())
+            .min_by(f32::total_cmp)
+        {
+            bottom = widget_top;
+        }
+
+        slices.push((top, bottom));
+
+        let clipped_bottom = tall_leaves
+            .iter()
+            .filter(|rect| rect.top() <= top + EPSILON && rect.bottom() > bottom + EPSILON)
+            .map(|rect| rect.bottom())
+            .max_by(f32::total_cmp);
+
+        top = match clipped_bottom {
+            // The rest of the clipped widgets is skipped, the widgets beside them aren't.
+            Some(clipped_bottom) => rects
+                .iter()
+                .map(|rect| rect.top())
+                .filter(|widget_top| *widget_top >= bottom - EPSILON)
+                .fold(clipped_bottom, f32::min),
+            None => bottom,
+        };
+    }
+
+    slices
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::{ColorRgba, decorated_box, vstack, widgets::builder::WidgetBuilder};
+
+    use super::*;
+
+    #[test]
+    fn test_two_page_document_is_a_parseable_pdf() {
+        let pdf = export_pdf(PageSize::new(200., 300.), EdgeInsets::all(10.), |ctx| {
+            vstack().spacing(0.).build(ctx, |ctx| {
+                for _ in 0..3 {
+                    decorated_box()
+                        .color(ColorRgba::from_hex(0xFF336699))
+                        .width(100.)
+                        .height(120.)
+                        .build(ctx);
+                }
+            });
+        });
+        assert!(pdf.starts_with(b"%PDF-1.7"));
+
+        // The streams are binary, the table and the trailer after them are text.
+        let startxref = pdf
+            .windows(10)
+            .rposition(|window| window == b"startxref\n")
+            .unwrap();
+        let trailer = std::str::from_utf8(&pdf[startxref + 10..]).unwrap();
+        let xref: usize = trailer.lines().next().unwrap().parse().unwrap();
+        assert!(trailer.trim_end().ends_with("%%EOF"));
+
+        // The cross-reference table points at each of the objects.
+        let table = std::str::from_utf8(&pdf[xref..]).unwrap();
+        let mut lines = table.lines();
+        assert_eq!(lines.next(), Some("xref"));
+
+        let count: usize = lines
+            .next()
+            .unwrap()
+            .split(' ')
+            .nth(1)
+            .unwrap()
+            .parse()
+            .unwrap();
+
+        for (id, entry) in lines.take(count).enumerate().skip(1) {
+            if entry.ends_with("n ") {
+                let offset: usize = entry[..10].parse().unwrap();
+                assert!(pdf[offset..].starts_with(format!("{id} 0 obj").as_bytes()));
+            }
+        }
+
+        let text = String::from_utf8_lossy(&pdf);
+
+        // The third box doesn't fit under the first two and starts the second page.
+        assert!(text.contains("/Count 2"));
+        assert_eq!(text.matches("/Type /Page ").count(), 2);
+    }
+
+    #[test]
+    fn test_page_breaks_dont_cut_through_widgets() {
+        let rects = [
+            Rect::new(0., 0., 100., 80.),
+            Rect::new(0., 80., 100., 80.),
+            Rect::new(0., 160., 100., 80.),
+            // A parent of the last two.
+            Rect::new(0., 80., 100., 160.),
+        ];
+
+        assert_eq!(
+            page_breaks(&rects, 240., 200.),
+            vec![(0., 80.), (80., 240.)]
+        );
+
+        // Taller than a page, so clipped.
+        let rects = [Rect::new(0., 0., 100., 500.)];
+        assert_eq!(page_breaks(&rects, 500., 200.), vec![(0., 200.)]);
+
+        // Starts a page of its own, the next page starts below it.
+        let rects = [
+            Rect::new(0., 0., 100., 80.),
+            Rect::new(0., 80., 100., 300.),
+            Rect::new(0., 380., 100., 80.),
+            // A parent of all of them.
+            Rect::new(0., 0., 100., 460.),
+        ];
+        assert_eq!(
+            page_breaks(&rects, 460., 200.),
+            vec![(0., 80.), (80., 280.), (380., 460.)]
+        );
+
+        // The widget beside the clipped one starts the next page.
+        let rects = [
+            Rect::new(0., 0., 50., 300.),
+            Rect::new(50., 0., 50., 150.),
+            Rect::new(50., 250., 50., 100.),
+        ];
+        assert_eq!(
+            page_breaks(&rects, 350., 200.),
+            vec![(0., 200.), (250., 350.)]
+        );
+        assert_eq!(page_breaks(&[], 0., 200.), vec![(0., 0.)]);
+    }
+}
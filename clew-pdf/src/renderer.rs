@@ -0,0 +1,1059 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Write,
+    sync::Arc,
+};
+
+use clew::{
+    ColorRgb, ColorRgba, ColorStop, EdgeInsets, Gradient, Rect, TintMode, Transform, Vec2, View,
+    assets::{Assets, MISSING_ASSET_COLOR},
+    render::{Fill, RenderCommand, RenderState, Renderer, TextStroke, is_run_visible},
+    text::{FontResources, TextId, TextsResources, is_visible_cluster},
+};
+
+use crate::{
+    PageSize,
+    content::Content,
+    document::{Document, Num, ObjectId},
+    font::EmbeddedFont,
+    svg,
+};
+
+/// Renders the frames into a PDF document, each processed frame is a page of the size of
+/// the view, see [`crate::export_pdf`] for the documents of several pages. The document
+/// is taken with [`PdfRenderer::finish`].
+///
+/// Everything is drawn as vectors in the points of PDF, one logical pixel of the view
+/// is one point. The texts are written with their fonts embedded, see [`crate`] for
+/// what is approximated.
+pub struct PdfRenderer {
+    document: Document,
+    pages_id: ObjectId,
+    resources_id: ObjectId,
+    pages: Vec<ObjectId>,
+    resources: Resources,
+    /// Splits the next processed frame into these pages instead.
+    pub(crate) pagination: Option<Pagination>,
+}
+
+/// Pages cut from a frame laid out at the width of their content.
+pub(crate) struct Pagination {
+    pub(crate) page_size: PageSize,
+    pub(crate) margins: EdgeInsets,
+    /// Vertical ranges of the frame drawn on each of the pages, at most as tall as the
+    /// content of a page.
+    pub(crate) slices: Vec<(f32, f32)>,
+}
+
+impl PdfRenderer {
+    pub fn new() -> Self {
+        let mut document = Document::new();
+        let pages_id = document.reserve();
+        let resources_id = document.reserve();
+
+        Self {
+            document,
+            pages_id,
+            resources_id,
+            pages: Vec::new(),
+            resources: Resources::default(),
+            pagination: None,
+        }
+    }
+
+    pub fn page_count(&self) -> usize {
+        self.pages.len()
+    }
+
+    /// Adds a page of the size in points with the commands drawn on it, the commands are
+    /// in the physical pixels of the scale factor.
+    #[allow(clippy::too_many_arguments)]
+    pub fn add_page(
+        &mut self,
+        page_size: PageSize,
+        scale_factor: f32,
+        background: Option<ColorRgb>,
+        commands: &[RenderCommand],
+        fonts: &mut FontResources,
+        text: &mut TextsResources,
+        assets: &Assets,
+    ) {
+        let mut content = Content::new();
+
+        // The pages are drawn from the top like the views.
+        content.transform([1., 0., 0., -1., 0., page_size.height]);
+
+        if let Some(background) = background {
+            content.fill_color(background.with_alpha(1.));
+            content.rect(Rect::new(0., 0., page_size.width, page_size.height));
+            content.fill(false);
+        }
+
+        if scale_factor != 1. {
+            content.transform([1. / scale_factor, 0., 0., 1. / scale_factor, 0., 0.]);
+        }
+
+        let page = Rect::new(
+            0.,
+            0.,
+            page_size.width * scale_factor,
+            page_size.height * scale_factor,
+        );
+
+        CommandsWriter {
+            content: &mut content,
+            resources: &mut self.resources,
+            document: &mut self.document,
+            fonts,
+            text,
+            assets,
+        }
+        .write(commands, page);
+
+        let contents = self.document.add_stream("", &content.into_bytes());
+        let page = self.document.add(format!(
+            "<< /Type /Page /Parent {} /MediaBox [0 0 {} {}] /Resources {} /Contents {contents} >>",
+            self.pages_id,
+            Num(page_size.width),
+            Num(page_size.height),
+            self.resources_id,
+        ));
+
+        self.pages.push(page);
+    }
+
+    /// The document with the pages added so far, the fonts are embedded with the glyphs
+    /// drawn on all of them.
+    pub fn finish(mut self) -> Vec<u8> {
+        let resources = self.resources.write(&mut self.document);
+        self.document.write(self.resources_id, resources);
+
+        let kids = self
+            .pages
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        self.document.write(
+            self.pages_id,
+            format!(
+                "<< /Type /Pages /Kids [{kids}] /Count {} >>",
+                self.pages.len()
+            ),
+        );
+
+        let catalog = self
+            .document
+            .add(format!("<< /Type /Catalog /Pages {} >>", self.pages_id));
+
+        self.document.finish(catalog)
+    }
+}
+
+impl Default for PdfRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer for PdfRenderer {
+    fn process_commands(
+        &mut self,
+        view: &View,
+        state: &RenderState,
+        fill_color: ColorRgb,
+        fonts: &mut FontResources,
+        text: &mut TextsResources,
+        assets: &Assets,
+    ) {
+        let Some(pagination) = self.pagination.take() else {
+            self.add_page(
+                PageSize::new(
+                    view.size.width as f32 / view.scale_factor,
+                    view.size.height as f32 / view.scale_factor,
+                ),
+                view.scale_factor,
+                Some(fill_color),
+                state.commands(),
+                fonts,
+                text,
+                assets,
+            );
+
+            return;
+        };
+
+        let margins = pagination.margins;
+        let width = pagination.page_size.width - margins.left - margins.right;
+
+        for (top, bottom) in pagination.slices {
+            // The slice is moved under the top margin and cut to its height, so a widget
+            // straddling its end is only drawn on the next page.
+            let mut commands = Vec::with_capacity(state.commands().len() + 4);
+            commands.push(RenderCommand::PushClip {
+                rect: Rect::new(margins.left, margins.top, width, bottom - top),
+                shape: clew::ClipShape::Rect,
+            });
+            commands.push(RenderCommand::PushTransform {
+                transform: Transform::new(Vec2::new(margins.left, margins.top - top), 1.),
+            });
+            commands.extend_from_slice(state.commands());
+            commands.push(RenderCommand::PopTransform);
+            commands.push(RenderCommand::PopClip);
+
+            self.add_page(
+                pagination.page_size,
+                view.scale_factor,
+                Some(fill_color),
+                &commands,
+                fonts,
+                text,
+                assets,
+            );
+        }
+    }
+}
+
+/// What the pages refer to by name, shared by all of them.
+#[derive(Default)]
+pub(crate) struct Resources {
+    /// Index in the embedded fonts, `None` for the fonts that can't be embedded.
+    fonts: HashMap<cosmic_text::fontdb::ID, Option<usize>>,
+    embedded_fonts: Vec<EmbeddedFont>,
+    /// Alphas of the graphics states, in 1/255.
+    alphas: BTreeSet<u8>,
+    /// Index of each shading dictionary, the same gradients share it.
+    shadings: HashMap<String, usize>,
+    images: Vec<ObjectId>,
+    bitmaps: HashMap<(Arc<str>, Option<[u8; 4]>), usize>,
+}
+
+impl Resources {
+    /// Name of the graphics state with the alpha for both fills and strokes, `None` for
+    /// the opaque ones.
+    pub(crate) fn alpha(&mut self, alpha: f32) -> Option<String> {
+        let alpha = (alpha.clamp(0., 1.) * 255.).round() as u8;
+
+        if alpha == u8::MAX {
+            return None;
+        }
+
+        self.alphas.insert(alpha);
+
+        Some(format!("A{alpha}"))
+    }
+
+    fn shading(&mut self, dictionary: String) -> String {
+        let count = self.shadings.len();
+        let index = *self.shadings.entry(dictionary).or_insert(count);
+
+        format!("Sh{index}")
+    }
+
+    /// Name of an image of the pixels in rows from the top with straight alpha, tinted
+    /// with the tint in its mode.
+    fn image(
+        &mut self,
+        document: &mut Document,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        tint: Option<(ColorRgba, TintMode)>,
+    ) -> String {
+        let tint = tint.map(|(tint, tint_mode)| (tint.to_rgba8(), tint_mode));
+        let multiply = |channel: u8, factor: u8| (channel as u16 * factor as u16 / 255) as u8;
+        let mut colors = Vec::with_capacity(pixels.len() / 4 * 3);
+        let mut alphas = Vec::with_capacity(pixels.len() / 4);
+
+        for pixel in pixels.chunks_exact(4) {
+            match tint {
+                Some(([r, g, b, a], tint_mode)) => {
+                    match tint_mode {
+                        TintMode::Replace => colors.extend_from_slice(&[r, g, b]),
+                        TintMode::Multiply => colors.extend_from_slice(&[
+                            multiply(pixel[0], r),
+                            multiply(pixel[1], g),
+                            multiply(pixel[2], b),
+                        ]),
+                    }
+
+                    alphas.push(multiply(pixel[3], a));
+                }
+                None => {
+                    colors.extend_from_slice(&pixel[..3]);
+                    alphas.push(pixel[3]);
+                }
+            }
+        }
+
+        let mut entries = format!(
+            "/Type /XObject /Subtype /Image /Width {width} /Height {height} \
+             /ColorSpace /DeviceRGB /BitsPerComponent 8 /Interpolate true"
+        );
+
+        if alphas.iter().any(|alpha| *alpha < u8::MAX) {
+            let mask = document.add_stream(
+                &format!(
+                    "/Type /XObject /Subtype /Image /Width {width} /Height {height} \
+                     /ColorSpace /DeviceGray /BitsPerComponent 8 /Interpolate true"
+                ),
+                &alphas,
+            );
+
+            write!(entries, " /SMask {mask}").unwrap();
+        }
+
+        self.images.push(document.add_stream(&entries, &colors));
+
+        format!("Im{}", self.images.len() - 1)
+    }
+
+    /// Name of the font and the font to mark its glyphs in, `None` when the font can't
+    /// be embedded.
+    fn font(
+        &mut self,
+        fonts: &mut FontResources,
+        id: cosmic_text::fontdb::ID,
+    ) -> Option<(String, &mut EmbeddedFont)> {
+        let index = match self.fonts.get(&id) {
+            Some(index) => *index,
+            None => {
+                let index = embed_font(fonts, id).map(|font| {
+                    self.embedded_fonts.push(font);
+
+                    self.embedded_fonts.len() - 1
+                });
+
+                self.fonts.insert(id, index);
+
+                index
+            }
+        }?;
+
+        Some((format!("F{index}"), &mut self.embedded_fonts[index]))
+    }
+
+    /// The resource dictionary, the fonts are written with it.
+    fn write(&self, document: &mut Document) -> String {
+        let mut resources = String::from("<< /ProcSet [/PDF /Text /ImageB /ImageC]");
+
+        resources.push_str(" /Font <<");
+
+        for (index, font) in self.embedded_fonts.iter().enumerate() {
+            write!(resources, " /F{index} {}", font.write(document)).unwrap();
+        }
+
+        resources.push_str(" >> /ExtGState <<");
+
+        for alpha in &self.alphas {
+            let value = Num(*alpha as f32 / 255.);
+
+            write!(resources, " /A{alpha} << /ca {value} /CA {value} >>").unwrap();
+        }
+
+        resources.push_str(" >> /Shading <<");
+
+        let mut shadings: Vec<_> = self.shadings.iter().collect();
+        shadings.sort_by_key(|(_, index)| **index);
+
+        for (dictionary, index) in shadings {
+            write!(resources, " /Sh{index} {dictionary}").unwrap();
+        }
+
+        resources.push_str(" >> /XObject <<");
+
+        for (index, image) in self.images.iter().enumerate() {
+            write!(resources, " /Im{index} {image}").unwrap();
+        }
+
+        resources.push_str(" >> >>");
+
+        resources
+    }
+}
+
+/// Enclosing clip and transform of the commands.
+#[derive(Clone, Copy)]
+struct Scope {
+    /// From the coordinates of the commands to the ones of the page.
+    transform: Transform,
+    /// Part of the page the commands can paint, in its coordinates.
+    clip: Rect,
+}
+
+struct CommandsWriter<'a, 'b, 'c> {
+    content: &'a mut Content,
+    resources: &'a mut Resources,
+    document: &'a mut Document,
+    fonts: &'a mut FontResources,
+    text: &'a mut TextsResources<'b>,
+    assets: &'a Assets<'c>,
+}
+
+impl CommandsWriter<'_, '_, '_> {
+    fn write(&mut self, commands: &[RenderCommand], page: Rect) {
+        let mut stack = vec![Scope {
+            transform: Transform::IDENTITY,
+            clip: page,
+        }];
+
+        for command in commands {
+            let scope = *stack.last().expect("The page scope is never popped");
+
+            // The pages of a long document all get its commands, most of them are
+            // on the other pages.
+            if let Some(bounds) = command.bounds() {
+                let bounds = scope.transform.apply_rect(bounds).intersect(scope.clip);
+
+                if bounds.width <= 0. || bounds.height <= 0. {
+                    continue;
+                }
+            }
+
+            match command {
+                RenderCommand::Rect {
+                    boundary,
+                    fill,
+                    border_radius,
+                    border,
+                    border_fill,
+                } => {
+                    let path = |content: &mut Content| match border_radius {
+                        Some(border_radius) => content.rounded_rect(*boundary, border_radius),
+                        None => content.rect(*boundary),
+                    };
+
+                    if let Some(fill) = fill {
+                        self.paint(fill, *boundary, false, path);
+                    }
+
+                    if let Some(border) = border {
+                        for piece in border.resolve(*boundary, border_radius.as_ref()) {
+                            let color = Fill::Color(piece.color);
+
+                            self.paint(
+                                border_fill.as_ref().unwrap_or(&color),
+                                *boundary,
+                                true,
+                                |content| content.outline(&piece.outline),
+                            );
+                        }
+                    }
+                }
+                RenderCommand::Oval {
+                    boundary,
+                    fill,
+                    border,
+                    border_fill,
+                } => {
+                    if let Some(fill) = fill {
+                        self.paint(fill, *boundary, false, |content| content.oval(*boundary));
+                    }
+
+                    if let Some(side) = border
+                        && side.width > 0.
+                    {
+                        let color = Fill::Color(side.color);
+
+                        // The stroke is centered on the outline, it's filled as a ring so
+                        // a gradient can paint it too.
+                        self.paint(
+                            border_fill.as_ref().unwrap_or(&color),
+                            *boundary,
+                            true,
+                            |content| {
+                                content.oval(boundary.expand(side.width / 2.));
+
+                                let inner = boundary.shrink(side.width / 2.);
+
+                                if inner.width > 0. && inner.height > 0. {
+                                    content.oval(inner);
+                                }
+                            },
+                        );
+                    }
+                }
+                // PDF has no blur, the shadows are drawn with a sharp edge.
+                RenderCommand::Shadow {
+                    boundary,
+                    border_radius,
+                    color,
+                    ..
+                } => {
+                    self.paint(
+                        &Fill::Color(*color),
+                        *boundary,
+                        false,
+                        |content| match border_radius {
+                            Some(border_radius) => content.rounded_rect(*boundary, border_radius),
+                            None => content.rect(*boundary),
+                        },
+                    );
+                }
+                RenderCommand::Text {
+                    x,
+                    y,
+                    text_id,
+                    tint_color,
+                    visible_band,
+                    clip,
+                    stroke,
+                } => {
+                    let visible = scope.transform.apply_inverse_rect(scope.clip);
+                    let band = match visible_band {
+                        Some(band) => {
+                            band.start.max(visible.top() - y)..band.end.min(visible.bottom() - y)
+                        }
+                        None => visible.top() - y..visible.bottom() - y,
+                    };
+
+                    self.text(
+                        Vec2::new(*x, *y),
+                        *text_id,
+                        *tint_color,
+                        band,
+                        *clip,
+                        *stroke,
+                    );
+                }
+                RenderCommand::PushClip { rect, shape } => {
+                    self.content.save();
+
+                    match shape {
+                        clew::ClipShape::Rect => self.content.rect(*rect),
+                        clew::ClipShape::RoundedRect { border_radius } => {
+                            self.content.rounded_rect(*rect, border_radius)
+                        }
+                        clew::ClipShape::Oval => self.content.oval(*rect),
+                    }
+
+                    self.content.clip(false);
+
+                    stack.push(Scope {
+                        transform: scope.transform,
+                        clip: scope.clip.intersect(scope.transform.apply_rect(*rect)),
+                    });
+                }
+                RenderCommand::PushTransform { transform } => {
+                    self.content.save();
+                    self.content.transform([
+                        transform.scale.x,
+                        0.,
+                        0.,
+                        transform.scale.y,
+                        transform.translation.x,
+                        transform.translation.y,
+                    ]);
+
+                    stack.push(Scope {
+                        transform: transform.then(scope.transform),
+                        clip: scope.clip,
+                    });
+                }
+                RenderCommand::PopClip | RenderCommand::PopTransform => {
+                    if stack.len() > 1 {
+                        stack.pop();
+                        self.content.restore();
+                    }
+                }
+                // The pages are written once, there is nothing to reuse the layers for.
+                RenderCommand::PushLayer { .. } | RenderCommand::PopLayer => {}
+                RenderCommand::Svg {
+                    boundary,
+                    asset_id,
+                    tint_color,
+                    tint_mode,
+                } => {
+                    let tint = tint_color.map(|tint| (tint, *tint_mode));
+                    let Some(tree) = self.assets.find_svg_tree(asset_id) else {
+                        if !self.assets.is_svg_pending(asset_id) {
+                            self.missing_asset(*boundary);
+                        }
+
+                        continue;
+                    };
+
+                    if svg::is_vector(tree.root()) {
+                        self.content.save();
+                        self.content.rect(*boundary);
+                        self.content.clip(false);
+                        self.content.transform([
+                            boundary.width / tree.size().width(),
+                            0.,
+                            0.,
+                            boundary.height / tree.size().height(),
+                            boundary.x,
+                            boundary.y,
+                        ]);
+
+                        svg::write_group(self.content, self.resources, tree.root(), 1., tint);
+
+                        self.content.restore();
+                    } else if let Some((width, height, pixels)) = svg::rasterize(tree, *boundary) {
+                        let name =
+                            self.resources
+                                .image(self.document, width, height, &pixels, tint);
+
+                        self.draw_image(&name, *boundary, *boundary, 1.);
+                    }
+                }
+                RenderCommand::Image {
+                    boundary,
+                    asset_id,
+                    tint_color,
+                    fit,
+                    opacity,
+                } => {
+                    let Some(bitmap) = self.assets.get_bitmap(asset_id) else {
+                        self.missing_asset(*boundary);
+
+                        continue;
+                    };
+
+                    let key = (asset_id.clone(), tint_color.map(|tint| tint.to_rgba8()));
+                    let index = match self.resources.bitmaps.get(&key) {
+                        Some(index) => *index,
+                        None => {
+                            self.resources.image(
+                                self.document,
+                                bitmap.width(),
+                                bitmap.height(),
+                                bitmap.pixels(),
+                                tint_color.map(|tint| (tint, TintMode::Replace)),
+                            );
+
+                            let index = self.resources.images.len() - 1;
+                            self.resources.bitmaps.insert(key, index);
+
+                            index
+                        }
+                    };
+
+                    let rect = fit.rect(*boundary, bitmap.width() as f32, bitmap.height() as f32);
+
+                    self.draw_image(&format!("Im{index}"), rect, *boundary, *opacity);
+                }
+            }
+        }
+
+        // Unbalanced commands still leave a valid page.
+        for _ in 1..stack.len() {
+            self.content.restore();
+        }
+    }
+
+    /// Paints the path with the fill, a gradient is resolved against the boundary.
+    fn paint(&mut self, fill: &Fill, boundary: Rect, even_odd: bool, path: impl Fn(&mut Content)) {
+        let content = &mut *self.content;
+
+        match fill {
+            Fill::None => {}
+            Fill::Color(color) => {
+                content.save();
+
+                if let Some(alpha) = self.resources.alpha(color.a) {
+                    content.graphics_state(&alpha);
+                }
+
+                content.fill_color(*color);
+                path(content);
+                content.fill(even_odd);
+                content.restore();
+            }
+            Fill::Gradient(gradient) => {
+                let stops = gradient_stops(gradient);
+                let alpha =
+                    stops.iter().map(|stop| stop.color.a).sum::<f32>() / stops.len().max(1) as f32;
+
+                content.save();
+
+                if let Some(alpha) = self.resources.alpha(alpha) {
+                    content.graphics_state(&alpha);
+                }
+
+                match shading(gradient, boundary) {
+                    Some((dictionary, matrix)) => {
+                        let name = self.resources.shading(dictionary);
+
+                        path(content);
+                        content.clip(even_odd);
+
+                        if let Some(matrix) = matrix {
+                            content.transform(matrix);
+                        }
+
+                        content.shading(&name);
+                    }
+                    None => {
+                        content.fill_color(average_color(stops));
+                        path(content);
+                        content.fill(even_odd);
+                    }
+                }
+
+                content.restore();
+            }
+        }
+    }
+
+    fn missing_asset(&mut self, boundary: Rect) {
+        self.paint(
+            &Fill::Color(MISSING_ASSET_COLOR),
+            boundary,
+            false,
+            |content| content.rect(boundary),
+        );
+    }
+
+    /// Draws the image into the rect, cut to the boundary.
+    fn draw_image(&mut self, name: &str, rect: Rect, boundary: Rect, opacity: f32) {
+        self.content.save();
+        self.content.rect(boundary);
+        self.content.clip(false);
+
+        if let Some(alpha) = self.resources.alpha(opacity) {
+            self.content.graphics_state(&alpha);
+        }
+
+        // The first row of an image is at the top of the unit square, the pages are
+        // drawn from the top.
+        self.content
+            .transform([rect.width, 0., 0., -rect.height, rect.x, rect.bottom()]);
+        self.content.x_object(name);
+        self.content.restore();
+    }
+
+    /// Writes the glyphs of the layout runs in the band, relative to the origin of the
+    /// text.
+    fn text(
+        &mut self,
+        origin: Vec2,
+        text_id: TextId,
+        tint_color: Option<ColorRgba>,
+        band: std::ops::Range<f32>,
+        clip: Option<Rect>,
+        stroke: Option<TextStroke>,
+    ) {
+        let Self {
+            content,
+            resources,
+            fonts,
+            text,
+            ..
+        } = self;
+
+        content.save();
+
+        if let Some(clip) = clip {
+            content.rect(clip);
+            content.clip(false);
+        }
+
+        content.begin_text();
+
+        // The outlines of all the glyphs are drawn first, so none of them covers the fill
+        // of a neighbour.
+        let passes = [stroke, None];
+        let passes = if stroke.is_some() {
+            &passes[..]
+        } else {
+            &passes[1..]
+        };
+
+        let text = text.get(text_id);
+
+        for pass in passes {
+            match pass {
+                Some(stroke) => {
+                    content.text_rendering_mode(1);
+                    content.line_join(1);
+                    // Centered on the outline, the inner half is under the fill.
+                    content.line_width(stroke.width * 2.);
+                    content.stroke_color(stroke.color);
+
+                    if let Some(alpha) = resources.alpha(stroke.color.a) {
+                        content.graphics_state(&alpha);
+                    }
+                }
+                None => content.text_rendering_mode(0),
+            }
+
+            let mut current_font = None;
+            let mut current_color = None;
+
+            for run in text.layout_runs() {
+                if !is_run_visible(Some(&band), &run) {
+                    continue;
+                }
+
+                for glyph in run.glyphs.iter() {
+                    let cluster = &run.text[glyph.start..glyph.end];
+
+                    if !is_visible_cluster(cluster) {
+                        continue;
+                    }
+
+                    let Some((name, font)) = resources.font(fonts, glyph.font_id) else {
+                        continue;
+                    };
+
+                    font.use_glyph(glyph.glyph_id, cluster);
+
+                    if current_font.as_ref() != Some(&(name.clone(), glyph.font_size)) {
+                        content.font(&name, glyph.font_size);
+                        current_font = Some((name, glyph.font_size));
+                    }
+
+                    if pass.is_none() {
+                        let color = glyph_color(glyph.color_opt, tint_color);
+
+                        if current_color != Some(color) {
+                            content.fill_color(color);
+
+                            // The opaque state resets the alpha of the previous glyph.
+                            let alpha = resources.alpha(color.a);
+                            content.graphics_state(&alpha.unwrap_or_else(|| {
+                                resources.alphas.insert(u8::MAX);
+
+                                format!("A{}", u8::MAX)
+                            }));
+
+                            current_color = Some(color);
+                        }
+                    }
+
+                    content.text_position(
+                        origin.x + glyph.x + glyph.x_offset * glyph.font_size,
+                        origin.y + run.line_y + glyph.y - glyph.y_offset * glyph.font_size,
+                    );
+                    content.glyph(glyph.glyph_id);
+                }
+            }
+        }
+
+        content.end_text();
+        content.restore();
+    }
+}
+
+fn embed_font(fonts: &mut FontResources, id: cosmic_text::fontdb::ID) -> Option<EmbeddedFont> {
+    let index = fonts.font_system.db().face(id)?.index;
+    let font = fonts.font_system.get_font(id)?;
+    let embedded = EmbeddedFont::new(font, index);
+
+    if embedded.is_none() {
+        log::warn!("Font {id} can't be embedded into the PDF, its glyphs are left out");
+    }
+
+    embedded
+}
+
+/// Color of a glyph, the tint replaces the color of the glyph keeping its alpha.
+fn glyph_color(color: Option<cosmic_text::Color>, tint: Option<ColorRgba>) -> ColorRgba {
+    let alpha = color.map_or(1., |color| color.a() as f32 / 255.);
+
+    match (tint, color) {
+        (Some(tint), _) => tint.with_opacity(tint.a * alpha),
+        (None, Some(color)) => ColorRgba {
+            r: color.r() as f32 / 255.,
+            g: color.g() as f32 / 255.,
+            b: color.b() as f32 / 255.,
+            a: alpha,
+        },
+        (None, None) => ColorRgba::from_hex(0xFF000000),
+    }
+}
+
+fn gradient_stops(gradient: &Gradient) -> &[ColorStop] {
+    match gradient {
+        Gradient::Linear(linear) => &linear.stops,
+        Gradient::Radial(radial) => &radial.stops,
+        Gradient::Sweep(sweep) => &sweep.stops,
+    }
+}
+
+pub(crate) fn average_color(stops: &[ColorStop]) -> ColorRgba {
+    let count = stops.len().max(1) as f32;
+    let sum = stops.iter().fold([0.; 3], |sum, stop| {
+        [
+            sum[0] + stop.color.r,
+            sum[1] + stop.color.g,
+            sum[2] + stop.color.b,
+        ]
+    });
+
+    ColorRgba {
+        r: sum[0] / count,
+        g: sum[1] / count,
+        b: sum[2] / count,
+        a: 1.,
+    }
+}
+
+/// Shading dictionary of the gradient and the matrix it's painted through, `None` for
+/// the sweep gradients PDF can't draw, they are filled with their average color.
+///
+/// The colors are interpolated between the stops, the alpha of the stops is averaged
+/// for the whole gradient and the repeated and mirrored gradients are extended with
+/// their end colors.
+fn shading(gradient: &Gradient, boundary: Rect) -> Option<(String, Option<[f32; 6]>)> {
+    match gradient {
+        Gradient::Linear(linear) => {
+            let ((x0, y0), (x1, y1)) = linear.resolve(boundary);
+
+            Some((
+                format!(
+                    "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [{} {} {} {}] \
+                     /Function {} /Extend [true true] >>",
+                    Num(x0),
+                    Num(y0),
+                    Num(x1),
+                    Num(y1),
+                    stops_function(&linear.stops),
+                ),
+                None,
+            ))
+        }
+        Gradient::Radial(radial) => {
+            let geometry = radial.resolve(boundary)?;
+
+            // The end circle is centered at the origin, the ellipse is made by the matrix.
+            Some((
+                format!(
+                    "<< /ShadingType 3 /ColorSpace /DeviceRGB /Coords [{} {} {} 0 0 {}] \
+                     /Function {} /Extend [true true] >>",
+                    Num(geometry.focal.0),
+                    Num(geometry.focal.1),
+                    Num(geometry.focal_radius),
+                    Num(geometry.radius),
+                    stops_function(&radial.stops),
+                ),
+                Some([
+                    1.,
+                    0.,
+                    0.,
+                    geometry.scale_y,
+                    geometry.center.0,
+                    geometry.center.1,
+                ]),
+            ))
+        }
+        Gradient::Sweep(_) => None,
+    }
+}
+
+/// Function from the offset along a gradient to its color, the segments between the
+/// stops stitched together.
+fn stops_function(stops: &[ColorStop]) -> String {
+    let mut stops: Vec<(f32, ColorRgba)> = stops
+        .iter()
+        .map(|stop| (stop.offset.clamp(0., 1.), stop.color))
+        .collect();
+    stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+    let (Some(first), Some(last)) = (stops.first().copied(), stops.last().copied()) else {
+        return segment_function(
+            ColorRgba::from_hex(0xFF000000),
+            ColorRgba::from_hex(0xFF000000),
+        );
+    };
+
+    if first.0 > 0. {
+        stops.insert(0, (0., first.1));
+    }
+
+    if last.0 < 1. {
+        stops.push((1., last.1));
+    }
+
+    // A hard stop is the end of a segment and the start of the next one, the empty
+    // segment between them is dropped.
+    let segments: Vec<_> = stops
+        .windows(2)
+        .filter(|pair| pair[1].0 > pair[0].0)
+        .map(|pair| (pair[0].0, segment_function(pair[0].1, pair[1].1)))
+        .collect();
+
+    match segments.len() {
+        0 => segment_function(first.1, first.1),
+        1 => segments[0].1.clone(),
+        _ => {
+            let functions: Vec<_> = segments
+                .iter()
+                .map(|(_, function)| function.as_str())
+                .collect();
+            let bounds: Vec<_> = segments[1..]
+                .iter()
+                .map(|(offset, _)| Num(*offset).to_string())
+                .collect();
+
+            format!(
+                "<< /FunctionType 3 /Domain [0 1] /Functions [{}] /Bounds [{}] /Encode [{}] >>",
+                functions.join(" "),
+                bounds.join(" "),
+                vec!["0 1"; segments.len()].join(" "),
+            )
+        }
+    }
+}
+
+fn segment_function(start: ColorRgba, end: ColorRgba) -> String {
+    format!(
+        "<< /FunctionType 2 /Domain [0 1] /C0 [{} {} {}] /C1 [{} {} {}] /N 1 >>",
+        Num(start.r),
+        Num(start.g),
+        Num(start.b),
+        Num(end.r),
+        Num(end.g),
+        Num(end.b),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::BorderRadius;
+
+    use super::*;
+
+    /// Operators the commands are written with on a page of 200 by 200.
+    fn write(commands: &[RenderCommand]) -> String {
+        let mut content = Content::new();
+
+        CommandsWriter {
+            content: &mut content,
+            resources: &mut Resources::default(),
+            document: &mut Document::new(),
+            fonts: &mut FontResources::new(),
+            text: &mut TextsResources::new(),
+            assets: &Assets::new(),
+        }
+        .write(commands, Rect::new(0., 0., 200., 200.));
+
+        String::from_utf8(content.into_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_shadows_are_filled_sharp_in_their_rounded_shape() {
+        let shadow = |boundary, radius, color| RenderCommand::Shadow {
+            boundary,
+            border_radius: Some(BorderRadius::all(radius)),
+            blur_radius: 8.,
+            color: ColorRgba::from_hex(color),
+        };
+        let operators = write(&[
+            shadow(Rect::new(8., 8., 104., 54.), 10., 0xFF0000FF),
+            shadow(Rect::new(10., 14., 100., 50.), 8., 0x80FF0000),
+        ]);
+
+        // In the order of the commands, each one a single fill of its rounded rect.
+        let blue = operators.find("0 0 1 rg\n18 8 m\n").unwrap();
+        let red = operators.find("1 0 0 rg\n18 14 m\n").unwrap();
+        assert!(blue < red);
+        assert_eq!(operators.matches(" c\n").count(), 8);
+        assert_eq!(operators.matches("f\n").count(), 2);
+
+        // The translucent one through a graphics state of its alpha.
+        assert!(operators[..red].ends_with("gs\n"));
+    }
+}
@@ -0,0 +1,210 @@
+use clew::{ColorRgba, Rect, TintMode};
+use resvg::tiny_skia;
+use usvg::{Group, Node, Paint, tiny_skia_path::PathSegment};
+
+use crate::{content::Content, renderer::Resources};
+
+/// Largest side of a rasterized svg, in pixels.
+const MAX_RASTER_SIZE: f32 = 4096.;
+
+/// Whether the group can be written as PDF paths, the trees with images, filters, masks,
+/// clip paths, gradients or patterns are rasterized instead.
+pub(crate) fn is_vector(group: &Group) -> bool {
+    if !group.filters().is_empty() || group.mask().is_some() || group.clip_path().is_some() {
+        return false;
+    }
+
+    group.children().iter().all(|node| match node {
+        Node::Group(group) => is_vector(group),
+        Node::Path(path) => {
+            let is_color = |paint: &Paint| matches!(paint, Paint::Color(_));
+
+            path.fill().is_none_or(|fill| is_color(fill.paint()))
+                && path.stroke().is_none_or(|stroke| is_color(stroke.paint()))
+        }
+        Node::Image(_) => false,
+        Node::Text(text) => is_vector(text.flattened()),
+    })
+}
+
+/// Writes the paths of the group in the coordinates of the tree, tinted with the tint
+/// in its mode.
+pub(crate) fn write_group(
+    content: &mut Content,
+    resources: &mut Resources,
+    group: &Group,
+    opacity: f32,
+    tint: Option<(ColorRgba, TintMode)>,
+) {
+    // The opacity of a group is applied to each of its paths, overlapping paths of a
+    // translucent group show through each other.
+    let opacity = opacity * group.opacity().get();
+
+    for node in group.children() {
+        match node {
+            Node::Group(group) => write_group(content, resources, group, opacity, tint),
+            Node::Text(text) => write_group(content, resources, text.flattened(), opacity, tint),
+            Node::Path(path) if path.is_visible() => {
+                let transform = path.abs_transform();
+                let fill = path.fill().map(|fill| {
+                    (
+                        color(fill.paint(), fill.opacity().get() * opacity, tint),
+                        fill.rule() == usvg::FillRule::EvenOdd,
+                    )
+                });
+                let stroke = path.stroke();
+
+                let write_fill = |content: &mut Content, resources: &mut Resources| {
+                    if let Some((color, even_odd)) = fill {
+                        content.save();
+
+                        if let Some(alpha) = resources.alpha(color.a) {
+                            content.graphics_state(&alpha);
+                        }
+
+                        content.fill_color(color);
+                        write_path(content, path.data());
+                        content.fill(even_odd);
+                        content.restore();
+                    }
+                };
+
+                content.save();
+                content.transform([
+                    transform.sx,
+                    transform.ky,
+                    transform.kx,
+                    transform.sy,
+                    transform.tx,
+                    transform.ty,
+                ]);
+
+                if path.paint_order() == usvg::PaintOrder::FillAndStroke {
+                    write_fill(content, resources);
+                }
+
+                if let Some(stroke) = stroke {
+                    let color = color(stroke.paint(), stroke.opacity().get() * opacity, tint);
+
+                    content.save();
+
+                    if let Some(alpha) = resources.alpha(color.a) {
+                        content.graphics_state(&alpha);
+                    }
+
+                    content.stroke_color(color);
+                    content.line_width(stroke.width().get());
+                    content.line_cap(match stroke.linecap() {
+                        usvg::LineCap::Butt => 0,
+                        usvg::LineCap::Round => 1,
+                        usvg::LineCap::Square => 2,
+                    });
+                    content.line_join(match stroke.linejoin() {
+                        usvg::LineJoin::Miter | usvg::LineJoin::MiterClip => 0,
+                        usvg::LineJoin::Round => 1,
+                        usvg::LineJoin::Bevel => 2,
+                    });
+                    content.miter_limit(stroke.miterlimit().get());
+
+                    if let Some(dasharray) = stroke.dasharray() {
+                        content.dash(dasharray, stroke.dashoffset());
+                    }
+
+                    write_path(content, path.data());
+                    content.stroke();
+                    content.restore();
+                }
+
+                if path.paint_order() == usvg::PaintOrder::StrokeAndFill {
+                    write_fill(content, resources);
+                }
+
+                content.restore();
+            }
+            Node::Path(_) | Node::Image(_) => {}
+        }
+    }
+}
+
+fn color(paint: &Paint, opacity: f32, tint: Option<(ColorRgba, TintMode)>) -> ColorRgba {
+    let color = match paint {
+        Paint::Color(color) => ColorRgba {
+            r: color.red as f32 / 255.,
+            g: color.green as f32 / 255.,
+            b: color.blue as f32 / 255.,
+            a: opacity,
+        },
+        // Only the trees without them are written as paths, see `is_vector`.
+        _ => ColorRgba::from_hex(0xFF000000).with_opacity(opacity),
+    };
+
+    match tint {
+        Some((tint, tint_mode)) => tint_mode.apply(color, tint),
+        None => color,
+    }
+}
+
+fn write_path(content: &mut Content, path: &usvg::tiny_skia_path::Path) {
+    let mut last = (0., 0.);
+
+    for segment in path.segments() {
+        match segment {
+            PathSegment::MoveTo(point) => {
+                content.move_to(point.x, point.y);
+                last = (point.x, point.y);
+            }
+            PathSegment::LineTo(point) => {
+                content.line_to(point.x, point.y);
+                last = (point.x, point.y);
+            }
+            PathSegment::QuadTo(control, point) => {
+                // The same curve as a cubic one, PDF has no quadratic curves.
+                content.cubic_to(
+                    last.0 + (control.x - last.0) * 2. / 3.,
+                    last.1 + (control.y - last.1) * 2. / 3.,
+                    point.x + (control.x - point.x) * 2. / 3.,
+                    point.y + (control.y - point.y) * 2. / 3.,
+                    point.x,
+                    point.y,
+                );
+                last = (point.x, point.y);
+            }
+            PathSegment::CubicTo(control1, control2, point) => {
+                content.cubic_to(
+                    control1.x, control1.y, control2.x, control2.y, point.x, point.y,
+                );
+                last = (point.x, point.y);
+            }
+            PathSegment::Close => content.close(),
+        }
+    }
+}
+
+/// Pixels of the tree drawn into the boundary at twice its resolution, in rows from the
+/// top with straight alpha.
+pub(crate) fn rasterize(tree: &usvg::Tree, boundary: Rect) -> Option<(u32, u32, Vec<u8>)> {
+    let width = (boundary.width * 2.).clamp(1., MAX_RASTER_SIZE).ceil() as u32;
+    let height = (boundary.height * 2.).clamp(1., MAX_RASTER_SIZE).ceil() as u32;
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)?;
+
+    resvg::render(
+        tree,
+        tiny_skia::Transform::from_scale(
+            width as f32 / tree.size().width(),
+            height as f32 / tree.size().height(),
+        ),
+        &mut pixmap.as_mut(),
+    );
+
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let color = pixel.demultiply();
+
+            [color.red(), color.green(), color.blue(), color.alpha()]
+        })
+        .collect();
+
+    Some((width, height, pixels))
+}
@@ -0,0 +1,104 @@
+//! CPU time taken by an idle window with a focused text field, drawn headless. The
+//! caret blinks twice a second: once with the frame built and laid out for every
+//! blink, and once the way a host drives a settled view, with the blinks repainted
+//! over the last built frame.
+//!
+//! `cargo bench -p clew-tiny-skia --bench idle_caret`
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use clew::{
+    ClewHost, ClewInstance, ColorRgb, EdgeInsets, PhysicalSize, Resources, TextData, View, ViewId,
+    assets::Assets,
+    prelude::*,
+    text::FontResources,
+    widgets::builder::{BuildContext, NoopEventLoopProxy},
+};
+use clew_tiny_skia::HeadlessRenderer;
+
+const RUN: Duration = Duration::from_secs(3);
+
+/// A form of a few hundred labels with a field focused in the first frame.
+fn build(ctx: &mut BuildContext, field: &mut TextData, focused: &mut bool) {
+    clew::vstack().build(ctx, |ctx| {
+        let response = clew::editable_text(field).build(ctx);
+
+        if !*focused {
+            ctx.focus_with_keyboard(response.id);
+            *focused = true;
+        }
+
+        for i in 0..300 {
+            clew::text("Label").id(i).build(ctx);
+        }
+    });
+}
+
+/// Runs the view for a while, returns the time spent in clew and the renderer.
+fn run(repaint: bool) -> Duration {
+    let mut host = ClewHost::new(
+        Assets::new(),
+        Resources::new(),
+        Arc::new(NoopEventLoopProxy),
+    );
+    let view = View {
+        id: ViewId(0),
+        size: PhysicalSize::new(800, 600),
+        scale_factor: 1.,
+        safe_area: EdgeInsets::ZERO,
+    };
+    let mut instance = ClewInstance::new(view, FontResources::new());
+    let mut renderer = HeadlessRenderer::new();
+    let mut field = TextData::from("Name");
+    let mut focused = false;
+    let fill_color = ColorRgb::from_hex(0x121212);
+
+    let mut frame = |instance: &mut ClewInstance<'static>, host: &mut ClewHost<'static>| {
+        instance.frame(host, &mut |ctx| build(ctx, &mut field, &mut focused));
+    };
+
+    // Settles the view before it's measured.
+    while instance.needs_build(&host) {
+        frame(&mut instance, &mut host);
+        instance.render(&host, &mut renderer, fill_color);
+    }
+
+    let started = Instant::now();
+    let mut busy = Duration::ZERO;
+
+    while started.elapsed() < RUN {
+        let Some(next) = instance.next_repaint() else {
+            break;
+        };
+
+        std::thread::sleep(next.saturating_duration_since(Instant::now()));
+
+        let work = Instant::now();
+
+        if !repaint || instance.needs_build(&host) {
+            frame(&mut instance, &mut host);
+        } else {
+            instance.repaint();
+        }
+
+        instance.render(&host, &mut renderer, fill_color);
+        busy += work.elapsed();
+    }
+
+    busy
+}
+
+fn main() {
+    for (name, repaint) in [("built every blink", false), ("repainted", true)] {
+        let busy = run(repaint);
+
+        println!(
+            "{name:>17}: {:>6.2} ms of CPU per second, {:.2}% of a core",
+            busy.as_secs_f64() * 1000. / RUN.as_secs_f64(),
+            busy.as_secs_f64() * 100. / RUN.as_secs_f64(),
+        );
+    }
+}
@@ -1,10 +1,13 @@
 use std::{num::NonZeroU32, slice};
 
 use clew::{
-    Border, BorderRadius, BorderSide, ColorRgb, ColorRgba, Gradient, Rect, TileMode, View,
-    assets::Assets,
-    render::{Fill, RenderCommand, RenderState, Renderer},
-    text::{FontResources, TextsResources},
+    Border, BorderRadius, BorderSide, ClewError, ClipShape, ColorRgb, ColorRgba, Gradient,
+    PathSegment, Rect, SweepGradient, TileMode, TintMode, View,
+    assets::{Assets, Bitmap, MISSING_ASSET_COLOR},
+    render::{
+        Damage, Fill, RenderCommand, RenderState, Renderer, RgbaImage, TextStroke, is_run_visible,
+    },
+    text::{FontResources, TextsResources, is_visible_cluster},
 };
 use cosmic_text::SwashCache;
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
@@ -18,20 +21,28 @@ pub struct TinySkiaRenderer<D, W> {
 }
 
 impl<D: HasDisplayHandle, W: HasWindowHandle> TinySkiaRenderer<D, W> {
-    pub fn new(display: D, window: W) -> Self {
-        let context = softbuffer::Context::new(display).unwrap();
-        let surface = softbuffer::Surface::new(&context, window).unwrap();
+    pub fn new(display: D, window: W) -> Result<Self, ClewError> {
+        let context =
+            softbuffer::Context::new(display).map_err(|err| ClewError::Surface(err.to_string()))?;
+        let surface = softbuffer::Surface::new(&context, window)
+            .map_err(|err| ClewError::Surface(err.to_string()))?;
 
-        Self {
+        Ok(Self {
             surface,
             current_width: 0,
             current_height: 0,
             swash_cache: SwashCache::new(),
-        }
+        })
     }
 }
 
 impl<D: HasDisplayHandle, W: HasWindowHandle> Renderer for TinySkiaRenderer<D, W> {
+    // Softbuffer presents the pixels as opaque on every platform, the alpha is ignored,
+    // so the background materials of the windows are disabled with this renderer.
+    fn set_transparent_background(&mut self, _transparent: bool) -> bool {
+        false
+    }
+
     fn process_commands(
         &mut self,
         view: &View,
@@ -47,181 +58,679 @@ impl<D: HasDisplayHandle, W: HasWindowHandle> Renderer for TinySkiaRenderer<D, W
             let width = view.size.width;
             let height = view.size.height;
 
+            // Minimized window, nothing to draw into.
+            let (Some(non_zero_width), Some(non_zero_height)) =
+                (NonZeroU32::new(width), NonZeroU32::new(height))
+            else {
+                return;
+            };
+
             if self.current_width != width || self.current_height != height {
-                self.surface
-                    .resize(
-                        NonZeroU32::new(width).unwrap(),
-                        NonZeroU32::new(height).unwrap(),
-                    )
-                    .unwrap();
+                if let Err(err) = self.surface.resize(non_zero_width, non_zero_height) {
+                    log::error!("Failed to resize surface: {err}");
+                    return;
+                }
 
                 self.current_width = width;
                 self.current_height = height;
             }
 
-            let mut surface_buffer = self.surface.buffer_mut().unwrap();
+            let mut surface_buffer = match self.surface.buffer_mut() {
+                Ok(surface_buffer) => surface_buffer,
+                Err(err) => {
+                    log::error!("Failed to get surface buffer: {err}");
+                    return;
+                }
+            };
+            // Only the damaged rect is repainted while the buffer still holds the previous
+            // frame, otherwise its content is undefined and everything is drawn again.
+            let damage = match state.damage() {
+                Damage::Rect(rect) if surface_buffer.age() == 1 => damage_rect(rect, width, height),
+                _ => None,
+            };
             let surface_buffer_u8 = unsafe {
                 slice::from_raw_parts_mut(
                     surface_buffer.as_mut_ptr() as *mut u8,
                     surface_buffer.len() * 4,
                 )
             };
-            let mut pixmap = PixmapMut::from_bytes(surface_buffer_u8, width, height).unwrap();
-            pixmap.fill(convert_rgb_color(&fill_color));
-
-            let clip_stack: Vec<tiny_skia::Mask> = Vec::new();
-
-            for command in state.commands() {
-                let current_clip = clip_stack.last();
-
-                match command {
-                    RenderCommand::Rect {
-                        boundary,
-                        fill,
-                        border_radius,
-                        border,
-                        ..
-                    } => {
-                        render_rect(
-                            &mut pixmap,
-                            *boundary,
-                            fill.as_ref(),
-                            border_radius.as_ref(),
-                            border.as_ref(),
-                            current_clip,
-                        );
-                    }
-                    RenderCommand::Oval {
-                        boundary,
-                        fill,
-                        border,
-                        ..
-                    } => {
-                        render_oval(
-                            &mut pixmap,
-                            *boundary,
-                            fill.as_ref(),
-                            border.as_ref(),
-                            current_clip,
-                        );
-                    }
-                    RenderCommand::Text {
-                        x: text_position_x,
-                        y: text_position_y,
-                        text_id,
-                        tint_color,
-                        ..
-                    } => {
-                        let mut paint = Paint {
-                            anti_alias: false,
+            let Some(mut pixmap) = PixmapMut::from_bytes(surface_buffer_u8, width, height) else {
+                log::error!("Surface buffer doesn't match the view size");
+                return;
+            };
+
+            match damage {
+                Some(rect) => {
+                    let Some(mut region) = tiny_skia::Pixmap::new(rect.width(), rect.height())
+                    else {
+                        return;
+                    };
+
+                    region.fill(convert_rgb_color(&fill_color));
+                    draw_commands(
+                        &mut region.as_mut(),
+                        &mut self.swash_cache,
+                        state.commands(),
+                        tiny_skia::Transform::from_translate(-rect.x() as f32, -rect.y() as f32),
+                        tiny_skia::Rect::from_xywh(
+                            0.,
+                            0.,
+                            rect.width() as f32,
+                            rect.height() as f32,
+                        ),
+                        fonts,
+                        text,
+                        assets,
+                    );
+                    pixmap.draw_pixmap(
+                        rect.x(),
+                        rect.y(),
+                        region.as_ref(),
+                        &tiny_skia::PixmapPaint {
+                            blend_mode: tiny_skia::BlendMode::Source,
                             ..Default::default()
-                        };
-
-                        text.get_mut(*text_id).with_buffer_mut(|buffer| {
-                            buffer.draw(
-                                &mut fonts.font_system,
-                                &mut self.swash_cache,
-                                tint_color.unwrap_or(ColorRgba::from_hex(0xFF000000)).into(),
-                                |x, y, w, h, color| {
-                                    let opacity = color.a() as f32 / 255.;
-                                    let color = tint_color
-                                        .map(|c| c.with_opacity(opacity * c.a).into())
-                                        .unwrap_or(color);
-
-                                    // Note: due to softbuffer and tiny_skia having incompatible internal color representations we swap
-                                    // the red and blue channels here
-                                    paint.set_color_rgba8(
-                                        color.b(),
-                                        color.g(),
-                                        color.r(),
-                                        color.a(),
-                                    );
-                                    pixmap.fill_rect(
-                                        tiny_skia::Rect::from_xywh(
-                                            text_position_x + x as f32,
-                                            text_position_y + y as f32,
-                                            w as f32,
-                                            h as f32,
-                                        )
-                                        .unwrap(),
-                                        &paint,
-                                        tiny_skia::Transform::identity(),
-                                        None,
-                                    );
-                                },
-                            );
-                        });
+                        },
+                        tiny_skia::Transform::identity(),
+                        None,
+                    );
+                }
+                None => {
+                    pixmap.fill(convert_rgb_color(&fill_color));
+                    draw_commands(
+                        &mut pixmap,
+                        &mut self.swash_cache,
+                        state.commands(),
+                        tiny_skia::Transform::identity(),
+                        None,
+                        fonts,
+                        text,
+                        assets,
+                    );
+                }
+            }
+
+            (surface_buffer, damage)
+        };
+
+        {
+            profiling::scope!("clew :: Tiny Skia - Softbuffer Preset");
+
+            let (surface_buffer, damage) = surface_buffer;
+            let result = match damage {
+                Some(rect) => surface_buffer.present_with_damage(&[softbuffer::Rect {
+                    x: rect.x() as u32,
+                    y: rect.y() as u32,
+                    width: NonZeroU32::new(rect.width()).unwrap_or(NonZeroU32::MIN),
+                    height: NonZeroU32::new(rect.height()).unwrap_or(NonZeroU32::MIN),
+                }]),
+                None => surface_buffer.present(),
+            };
+
+            if let Err(err) = result {
+                log::error!("Failed to present surface buffer: {err}");
+            }
+        }
+
+        tracy_client::frame_mark();
+    }
+
+    fn render_image(
+        &mut self,
+        commands: &[RenderCommand],
+        width: u32,
+        height: u32,
+        background: Option<ColorRgba>,
+        fonts: &mut FontResources,
+        text: &mut TextsResources,
+        assets: &Assets,
+    ) -> Result<RgbaImage, ClewError> {
+        draw_image(
+            &mut self.swash_cache,
+            commands,
+            width,
+            height,
+            background,
+            fonts,
+            text,
+            assets,
+        )
+    }
+}
+
+/// Draws the frames into an image in memory rather than a window, e.g. to render
+/// screenshots of the views without a display.
+pub struct HeadlessRenderer {
+    swash_cache: SwashCache,
+    frame: Option<RgbaImage>,
+}
+
+impl HeadlessRenderer {
+    pub fn new() -> Self {
+        Self {
+            swash_cache: SwashCache::new(),
+            frame: None,
+        }
+    }
+
+    /// The last processed frame.
+    pub fn frame(&self) -> Option<&RgbaImage> {
+        self.frame.as_ref()
+    }
+}
+
+impl Default for HeadlessRenderer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Renderer for HeadlessRenderer {
+    fn process_commands(
+        &mut self,
+        view: &View,
+        state: &RenderState,
+        fill_color: ColorRgb,
+        fonts: &mut FontResources,
+        text: &mut TextsResources,
+        assets: &Assets,
+    ) {
+        profiling::scope!("clew :: Tiny Skia - Headless Render");
+
+        // There is no previous frame to repaint the damage over, the frame is drawn
+        // entirely.
+        match draw_image(
+            &mut self.swash_cache,
+            state.commands(),
+            view.size.width,
+            view.size.height,
+            Some(fill_color.with_alpha(1.)),
+            fonts,
+            text,
+            assets,
+        ) {
+            Ok(frame) => self.frame = Some(frame),
+            Err(err) => log::error!("Failed to render frame: {err}"),
+        }
+    }
+
+    fn render_image(
+        &mut self,
+        commands: &[RenderCommand],
+        width: u32,
+        height: u32,
+        background: Option<ColorRgba>,
+        fonts: &mut FontResources,
+        text: &mut TextsResources,
+        assets: &Assets,
+    ) -> Result<RgbaImage, ClewError> {
+        draw_image(
+            &mut self.swash_cache,
+            commands,
+            width,
+            height,
+            background,
+            fonts,
+            text,
+            assets,
+        )
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn draw_image(
+    swash_cache: &mut SwashCache,
+    commands: &[RenderCommand],
+    width: u32,
+    height: u32,
+    background: Option<ColorRgba>,
+    fonts: &mut FontResources,
+    text: &mut TextsResources,
+    assets: &Assets,
+) -> Result<RgbaImage, ClewError> {
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)
+        .ok_or_else(|| ClewError::Capture(format!("Can't allocate {width}x{height} pixels")))?;
+
+    if let Some(background) = background {
+        pixmap.fill(convert_rgba_color(&background));
+    }
+
+    draw_commands(
+        &mut pixmap.as_mut(),
+        swash_cache,
+        commands,
+        tiny_skia::Transform::identity(),
+        None,
+        fonts,
+        text,
+        assets,
+    );
+
+    // The pixmap is premultiplied and in the softbuffer order, see `convert_rgba_color`.
+    let pixels = pixmap
+        .pixels()
+        .iter()
+        .flat_map(|pixel| {
+            let pixel = pixel.demultiply();
+
+            [pixel.blue(), pixel.green(), pixel.red(), pixel.alpha()]
+        })
+        .collect();
+
+    Ok(RgbaImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+/// Draws the commands through `base_transform`, the commands entirely outside of
+/// `cull` are skipped.
+#[allow(clippy::too_many_arguments)]
+fn draw_commands(
+    pixmap: &mut PixmapMut,
+    swash_cache: &mut SwashCache,
+    commands: &[RenderCommand],
+    base_transform: tiny_skia::Transform,
+    cull: Option<tiny_skia::Rect>,
+    fonts: &mut FontResources,
+    text: &mut TextsResources,
+    assets: &Assets,
+) {
+    // Clip masks hold the coverage of the anti-aliased clip paths, nested clips multiply
+    // it so the edges of all of them stay smooth.
+    let mut clip_stack: Vec<tiny_skia::Mask> = Vec::new();
+    // Transforms are applied per draw call, so zoomed in content is rasterized
+    // at the screen resolution rather than scaled up from a bitmap.
+    let mut transform_stack: Vec<tiny_skia::Transform> = Vec::new();
+
+    for command in commands {
+        let current_clip = clip_stack.last();
+        let current_transform = transform_stack.last().copied().unwrap_or(base_transform);
+
+        if let Some(cull) = cull
+            && let Some(bounds) = command.bounds()
+            && is_outside(bounds, current_transform, cull)
+        {
+            continue;
+        }
+
+        match command {
+            RenderCommand::Rect {
+                boundary,
+                fill,
+                border_radius,
+                border,
+                border_fill,
+            } => {
+                render_rect(
+                    pixmap,
+                    *boundary,
+                    fill.as_ref(),
+                    border_radius.as_ref(),
+                    border.as_ref(),
+                    border_fill.as_ref(),
+                    current_clip,
+                    current_transform,
+                );
+            }
+            RenderCommand::Oval {
+                boundary,
+                fill,
+                border,
+                border_fill,
+            } => {
+                render_oval(
+                    pixmap,
+                    *boundary,
+                    fill.as_ref(),
+                    border.as_ref(),
+                    border_fill.as_ref(),
+                    current_clip,
+                    current_transform,
+                );
+            }
+            RenderCommand::Shadow {
+                boundary,
+                border_radius,
+                blur_radius,
+                color,
+            } => {
+                render_shadow(
+                    pixmap,
+                    *boundary,
+                    border_radius.as_ref(),
+                    *blur_radius,
+                    color,
+                    current_clip,
+                    current_transform,
+                );
+            }
+            RenderCommand::Text {
+                x: text_position_x,
+                y: text_position_y,
+                text_id,
+                tint_color,
+                visible_band,
+                clip,
+                stroke,
+            } => {
+                let mut paint = Paint {
+                    anti_alias: false,
+                    ..Default::default()
+                };
+                let default_color: cosmic_text::Color =
+                    tint_color.unwrap_or(ColorRgba::from_hex(0xFF000000)).into();
+                let cull_band = cull.and_then(|cull| {
+                    current_transform
+                        .invert()
+                        .and_then(|inverse| cull.transform(inverse))
+                });
+
+                // The outlines of all the glyphs are drawn first, so none of them covers
+                // the fill of a neighbour.
+                let passes = [*stroke, None];
+                let passes = if stroke.is_some() {
+                    &passes[..]
+                } else {
+                    &passes[1..]
+                };
+
+                // The glyphs are rasterized at the scale they're drawn at, e.g. in a
+                // scaled capture, and drawn in the pixels of that scale.
+                let glyph_scale = raster_scale(current_transform);
+                let glyph_transform =
+                    current_transform.pre_scale(1. / glyph_scale, 1. / glyph_scale);
+                let origin_x = text_position_x * glyph_scale;
+                let origin_y = text_position_y * glyph_scale;
+                let glyph_clip = clip.map(|clip| clip * glyph_scale);
+                let glyph_stroke = stroke.map(|stroke| TextStroke {
+                    width: stroke.width * glyph_scale,
+                    ..stroke
+                });
+
+                let text = text.get(*text_id);
+
+                for (pass, run) in passes
+                    .iter()
+                    .flat_map(|pass| text.layout_runs().map(move |run| (pass, run)))
+                {
+                    if !is_run_visible(visible_band.as_ref(), &run) {
+                        continue;
                     }
-                    RenderCommand::PushClip { .. } => {
-                        // TODO
+
+                    if let Some(band) = cull_band
+                        && (text_position_y + run.line_top + run.line_height < band.top()
+                            || text_position_y + run.line_top > band.bottom())
+                    {
+                        continue;
                     }
-                    RenderCommand::PopClip => {
-                        // TODO
+
+                    if let Some(clip) = clip
+                        && (text_position_y + run.line_top + run.line_height < clip.top()
+                            || text_position_y + run.line_top > clip.bottom())
+                    {
+                        continue;
                     }
-                    RenderCommand::Svg {
-                        boundary,
-                        asset_id,
-                        tint_color,
-                        ..
-                    } => {
-                        let tree = assets
-                            .get_svg_tree(asset_id)
-                            .unwrap_or_else(|| panic!("SVG with ID = {asset_id} has not found"));
-
-                        let svg_pixmap = tiny_skia::Pixmap::new(
-                            boundary.width.ceil() as u32,
-                            boundary.height.ceil() as u32,
-                        );
 
-                        if let Some(mut svg_pixmap) = svg_pixmap {
-                            let sx = boundary.width / tree.size().width();
-                            let sy = boundary.height / tree.size().height();
+                    let line_y = (run.line_y * glyph_scale) as i32;
 
-                            resvg::render(
-                                tree,
-                                tiny_skia::Transform::from_scale(sx, sy),
-                                &mut svg_pixmap.as_mut(),
-                            );
+                    for glyph in run.glyphs.iter() {
+                        if !is_visible_cluster(&run.text[glyph.start..glyph.end]) {
+                            continue;
+                        }
 
-                            if let Some(tint) = tint_color {
-                                tint_pixmap(&mut svg_pixmap, convert_rgba_color(tint));
-                            }
+                        if let Some(clip) = clip
+                            && (text_position_x + glyph.x + glyph.w < clip.left()
+                                || text_position_x + glyph.x > clip.right())
+                        {
+                            continue;
+                        }
 
-                            pixmap.draw_pixmap(
-                                boundary.x.round() as i32,
-                                boundary.y.round() as i32,
-                                svg_pixmap.as_ref(),
-                                &tiny_skia::PixmapPaint::default(),
-                                tiny_skia::Transform::identity(),
-                                None,
+                        let physical_glyph = glyph.physical((0., 0.), glyph_scale);
+                        let glyph_color = glyph.color_opt.unwrap_or(default_color);
+
+                        if pass.is_some()
+                            && let Some(stroke) = &glyph_stroke
+                        {
+                            stroke_glyph(
+                                pixmap,
+                                swash_cache,
+                                fonts,
+                                physical_glyph.cache_key,
+                                (
+                                    origin_x + physical_glyph.x as f32,
+                                    origin_y + (line_y + physical_glyph.y) as f32,
+                                ),
+                                stroke,
+                                glyph_clip,
+                                glyph_transform,
+                                current_clip,
                             );
-                        } else {
-                            log::warn!("Failed to render svg: {asset_id}");
+                            continue;
                         }
+
+                        swash_cache.with_pixels(
+                            &mut fonts.font_system,
+                            physical_glyph.cache_key,
+                            glyph_color,
+                            |x, y, color| {
+                                let x = physical_glyph.x + x;
+                                let y = line_y + physical_glyph.y + y;
+
+                                // The glyphs are drawn pixel by pixel, so the straddlers
+                                // are clipped by the centers of their pixels.
+                                if let Some(clip) = glyph_clip {
+                                    let center_x = origin_x + x as f32 + 0.5;
+                                    let center_y = origin_y + y as f32 + 0.5;
+
+                                    if center_x < clip.left()
+                                        || center_x >= clip.right()
+                                        || center_y < clip.top()
+                                        || center_y >= clip.bottom()
+                                    {
+                                        return;
+                                    }
+                                }
+
+                                let opacity = color.a() as f32 / 255.;
+                                let color = tint_color
+                                    .map(|c| c.with_opacity(opacity * c.a).into())
+                                    .unwrap_or(color);
+
+                                // Note: due to softbuffer and tiny_skia having incompatible internal color representations we swap
+                                // the red and blue channels here
+                                paint.set_color_rgba8(color.b(), color.g(), color.r(), color.a());
+                                pixmap.fill_rect(
+                                    tiny_skia::Rect::from_xywh(
+                                        origin_x + x as f32,
+                                        origin_y + y as f32,
+                                        1.,
+                                        1.,
+                                    )
+                                    .unwrap(),
+                                    &paint,
+                                    glyph_transform,
+                                    current_clip,
+                                );
+                            },
+                        );
                     }
                 }
             }
+            RenderCommand::PushClip { rect, shape } => {
+                let mask = clip_mask(
+                    pixmap.width(),
+                    pixmap.height(),
+                    current_clip,
+                    *rect,
+                    shape,
+                    current_transform,
+                );
 
-            surface_buffer
-        };
+                if let Some(mask) = mask {
+                    clip_stack.push(mask);
+                }
+            }
+            RenderCommand::PopClip => {
+                clip_stack.pop();
+            }
+            RenderCommand::PushTransform { transform } => {
+                transform_stack.push(current_transform.pre_concat(tiny_skia::Transform::from_row(
+                    transform.scale.x,
+                    0.,
+                    0.,
+                    transform.scale.y,
+                    transform.translation.x,
+                    transform.translation.y,
+                )));
+            }
+            RenderCommand::PopTransform => {
+                transform_stack.pop();
+            }
+            // Only the damaged part is repainted anyway, so layers are drawn in place.
+            RenderCommand::PushLayer { .. } | RenderCommand::PopLayer => {}
+            RenderCommand::Svg {
+                boundary,
+                asset_id,
+                tint_color,
+                tint_mode,
+            } => {
+                let Some(tree) = assets.find_svg_tree(asset_id) else {
+                    if assets.is_svg_pending(asset_id) {
+                        continue;
+                    }
 
-        {
-            profiling::scope!("clew :: Tiny Skia - Softbuffer Preset");
+                    render_rect(
+                        pixmap,
+                        *boundary,
+                        Some(&Fill::Color(MISSING_ASSET_COLOR)),
+                        None,
+                        None,
+                        None,
+                        current_clip,
+                        current_transform,
+                    );
+
+                    continue;
+                };
+
+                // Rasterized at the scale it's drawn at, like the glyphs.
+                let svg_scale = raster_scale(current_transform);
+                let svg_pixmap = tiny_skia::Pixmap::new(
+                    (boundary.width * svg_scale).ceil() as u32,
+                    (boundary.height * svg_scale).ceil() as u32,
+                );
 
-            surface_buffer.present().unwrap();
-        }
+                if let Some(mut svg_pixmap) = svg_pixmap {
+                    let sx = boundary.width * svg_scale / tree.size().width();
+                    let sy = boundary.height * svg_scale / tree.size().height();
+
+                    resvg::render(
+                        tree,
+                        tiny_skia::Transform::from_scale(sx, sy),
+                        &mut svg_pixmap.as_mut(),
+                    );
+                    swap_red_blue(&mut svg_pixmap);
+
+                    if let Some(tint) = tint_color {
+                        match tint_mode {
+                            TintMode::Replace => {
+                                tint_pixmap(&mut svg_pixmap, convert_rgba_color(tint))
+                            }
+                            TintMode::Multiply => {
+                                multiply_pixmap(&mut svg_pixmap, convert_rgba_color(tint))
+                            }
+                        }
+                    }
 
-        tracy_client::frame_mark();
+                    pixmap.draw_pixmap(
+                        (boundary.x * svg_scale).round() as i32,
+                        (boundary.y * svg_scale).round() as i32,
+                        svg_pixmap.as_ref(),
+                        &tiny_skia::PixmapPaint::default(),
+                        current_transform.pre_scale(1. / svg_scale, 1. / svg_scale),
+                        current_clip,
+                    );
+                } else {
+                    log::warn!("Failed to render svg: {asset_id}");
+                }
+            }
+            RenderCommand::Image {
+                boundary,
+                asset_id,
+                tint_color,
+                fit,
+                opacity,
+            } => {
+                let Some(bitmap) = assets.get_bitmap(asset_id) else {
+                    render_rect(
+                        pixmap,
+                        *boundary,
+                        Some(&Fill::Color(MISSING_ASSET_COLOR)),
+                        None,
+                        None,
+                        None,
+                        current_clip,
+                        current_transform,
+                    );
+
+                    continue;
+                };
+
+                let Some(mut image) = bitmap_pixmap(bitmap) else {
+                    continue;
+                };
+
+                if let Some(tint) = tint_color {
+                    tint_pixmap(&mut image, convert_rgba_color(tint));
+                }
+
+                let rect = fit.rect(*boundary, bitmap.width() as f32, bitmap.height() as f32);
+
+                // The image covering the boundary is cut to it.
+                let Some(visible) = tiny_skia::Rect::from_ltrb(
+                    rect.x.max(boundary.x),
+                    rect.y.max(boundary.y),
+                    (rect.x + rect.width).min(boundary.x + boundary.width),
+                    (rect.y + rect.height).min(boundary.y + boundary.height),
+                ) else {
+                    continue;
+                };
+
+                let paint = tiny_skia::Paint {
+                    shader: tiny_skia::Pattern::new(
+                        image.as_ref(),
+                        tiny_skia::SpreadMode::Pad,
+                        tiny_skia::FilterQuality::Bilinear,
+                        *opacity,
+                        tiny_skia::Transform::from_row(
+                            rect.width / bitmap.width() as f32,
+                            0.,
+                            0.,
+                            rect.height / bitmap.height() as f32,
+                            rect.x,
+                            rect.y,
+                        ),
+                    ),
+                    ..Default::default()
+                };
+
+                pixmap.fill_rect(visible, &paint, current_transform, current_clip);
+            }
+        }
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn render_rect(
     pixmap: &mut PixmapMut,
     boundary: Rect,
     fill: Option<&Fill>,
     border_radius: Option<&BorderRadius>,
     border: Option<&Border>,
+    border_fill: Option<&Fill>,
     clip_mask: Option<&tiny_skia::Mask>,
+    transform: tiny_skia::Transform,
 ) {
     let path = if let Some(border_radius) = border_radius {
         Some(create_rounded_rect_path(boundary, border_radius))
@@ -241,9 +750,9 @@ fn render_rect(
             if let Some(paint) = create_paint_from_fill(fill, boundary) {
                 pixmap.fill_path(
                     &path,
-                    &paint,
+                    &paint.paint(),
                     tiny_skia::FillRule::Winding,
-                    tiny_skia::Transform::identity(),
+                    transform,
                     clip_mask,
                 );
             }
@@ -251,7 +760,16 @@ fn render_rect(
 
         if let Some(border) = border {
             // Render border
-            render_border(pixmap, &path, border, clip_mask);
+            render_border(
+                pixmap,
+                &path,
+                boundary,
+                border_radius,
+                border,
+                border_fill,
+                clip_mask,
+                transform,
+            );
         }
     }
 }
@@ -261,41 +779,20 @@ fn render_oval(
     boundary: Rect,
     fill: Option<&Fill>,
     border: Option<&BorderSide>,
+    border_fill: Option<&Fill>,
     clip_mask: Option<&tiny_skia::Mask>,
+    transform: tiny_skia::Transform,
 ) {
-    let cx = boundary.x + boundary.width / 2.0;
-    let cy = boundary.y + boundary.height / 2.0;
-    let rx = boundary.width / 2.0;
-    let ry = boundary.height / 2.0;
-
-    let path = {
-        let mut pb = tiny_skia::PathBuilder::new();
-        // Create ellipse using cubic bezier curves
-        // Magic constant for circle/ellipse approximation with bezier curves
-        // const KAPPA: f32 = 0.5522847498;
-        const KAPPA: f32 = 0.552_284_8;
-
-        let ox = rx * KAPPA; // control point offset x
-        let oy = ry * KAPPA; // control point offset y
-
-        pb.move_to(cx - rx, cy);
-        pb.cubic_to(cx - rx, cy - oy, cx - ox, cy - ry, cx, cy - ry);
-        pb.cubic_to(cx + ox, cy - ry, cx + rx, cy - oy, cx + rx, cy);
-        pb.cubic_to(cx + rx, cy + oy, cx + ox, cy + ry, cx, cy + ry);
-        pb.cubic_to(cx - ox, cy + ry, cx - rx, cy + oy, cx - rx, cy);
-        pb.close();
-
-        pb.finish().unwrap()
-    };
+    let path = create_oval_path(boundary);
 
     if let Some(fill) = fill {
         // Render fill
         if let Some(paint) = create_paint_from_fill(fill, boundary) {
             pixmap.fill_path(
                 &path,
-                &paint,
+                &paint.paint(),
                 tiny_skia::FillRule::Winding,
-                tiny_skia::Transform::identity(),
+                transform,
                 clip_mask,
             );
         }
@@ -311,18 +808,218 @@ fn render_oval(
             dash: None,
         };
 
-        let mut paint = tiny_skia::Paint::default();
-        paint.set_color(convert_rgba_color(&border_side.color));
-        paint.anti_alias = true;
+        if let Some(paint) = create_border_paint(border_fill, border_side.color, boundary) {
+            pixmap.stroke_path(&path, &paint.paint(), &stroke, transform, clip_mask);
+        }
+    }
+}
 
-        pixmap.stroke_path(
-            &path,
-            &paint,
-            &stroke,
-            tiny_skia::Transform::identity(),
+/// Draws the shape into a mask, blurs it and draws the mask in the color. The mask is
+/// drawn without the transform and then transformed, it's blurry anyway. The whole
+/// shape is shaded, also under the box that casts the shadow.
+#[allow(clippy::too_many_arguments)]
+fn render_shadow(
+    pixmap: &mut PixmapMut,
+    boundary: Rect,
+    border_radius: Option<&BorderRadius>,
+    blur_radius: f32,
+    color: &ColorRgba,
+    clip_mask: Option<&tiny_skia::Mask>,
+    transform: tiny_skia::Transform,
+) {
+    let fill = Fill::Color(*color);
+
+    if blur_radius <= 0. {
+        render_rect(
+            pixmap,
+            boundary,
+            Some(&fill),
+            border_radius,
+            None,
+            None,
             clip_mask,
+            transform,
         );
+
+        return;
+    }
+
+    // The blur radius is the one of CSS, twice the standard deviation, the gaussian is
+    // negligible past three of them.
+    let sigma = blur_radius / 2.;
+    let padding = (sigma * 3.).ceil();
+    let left = (boundary.x - padding).floor();
+    let top = (boundary.y - padding).floor();
+    let width = ((boundary.x + boundary.width + padding).ceil() - left) as u32;
+    let height = ((boundary.y + boundary.height + padding).ceil() - top) as u32;
+
+    let path = match border_radius {
+        Some(border_radius) => Some(create_rounded_rect_path(boundary, border_radius)),
+        None => tiny_skia::Rect::from_xywh(boundary.x, boundary.y, boundary.width, boundary.height)
+            .map(tiny_skia::PathBuilder::from_rect),
+    };
+
+    let (Some(path), Some(mut mask), Some(mut shadow)) = (
+        path,
+        tiny_skia::Mask::new(width, height),
+        tiny_skia::Pixmap::new(width, height),
+    ) else {
+        return;
+    };
+
+    mask.fill_path(
+        &path,
+        tiny_skia::FillRule::Winding,
+        true,
+        tiny_skia::Transform::from_translate(-left, -top),
+    );
+    blur_mask(mask.data_mut(), width as usize, height as usize, sigma);
+
+    let color = convert_rgba_color(color).premultiply().to_color_u8();
+    let multiply = |channel: u8, coverage: u8| (channel as u16 * coverage as u16 / 255) as u8;
+
+    for (pixel, &coverage) in shadow.pixels_mut().iter_mut().zip(mask.data()) {
+        if let Some(shaded) = tiny_skia::PremultipliedColorU8::from_rgba(
+            multiply(color.red(), coverage),
+            multiply(color.green(), coverage),
+            multiply(color.blue(), coverage),
+            multiply(color.alpha(), coverage),
+        ) {
+            *pixel = shaded;
+        }
+    }
+
+    pixmap.draw_pixmap(
+        0,
+        0,
+        shadow.as_ref(),
+        &tiny_skia::PixmapPaint::default(),
+        transform.pre_translate(left, top),
+        clip_mask,
+    );
+}
+
+/// Approximates a gaussian blur with three box blurs in each direction.
+fn blur_mask(data: &mut [u8], width: usize, height: usize, sigma: f32) {
+    // Three boxes of `2 * radius + 1` pixels have the variance of the gaussian.
+    let radius = (((4. * sigma * sigma + 1.).sqrt() - 1.) / 2.).round() as usize;
+
+    if radius == 0 {
+        return;
+    }
+
+    let mut line = Vec::new();
+
+    for _ in 0..3 {
+        for y in 0..height {
+            box_blur_line(data, y * width, 1, width, radius, &mut line);
+        }
+
+        for x in 0..width {
+            box_blur_line(data, x, width, height, radius, &mut line);
+        }
+    }
+}
+
+/// Blurs `len` values of `data` from `start` a `step` apart, the values past the ends
+/// are zero.
+fn box_blur_line(
+    data: &mut [u8],
+    start: usize,
+    step: usize,
+    len: usize,
+    radius: usize,
+    line: &mut Vec<u8>,
+) {
+    line.clear();
+    line.extend((0..len).map(|index| data[start + index * step]));
+
+    let size = (2 * radius + 1) as u32;
+    // The sum of the window around the first value.
+    let mut sum: u32 = line.iter().take(radius).map(|&value| value as u32).sum();
+
+    for index in 0..len {
+        if let Some(&entering) = line.get(index + radius) {
+            sum += entering as u32;
+        }
+
+        data[start + index * step] = ((sum + size / 2) / size) as u8;
+
+        if index >= radius {
+            sum -= line[index - radius] as u32;
+        }
+    }
+}
+
+/// Paint of a border, the fill when it's set and the color of the side otherwise.
+fn create_border_paint(fill: Option<&Fill>, color: ColorRgba, boundary: Rect) -> Option<FillPaint> {
+    match fill {
+        Some(fill) => create_paint_from_fill(fill, boundary),
+        None => create_paint_from_fill(&Fill::Color(color), boundary),
+    }
+}
+
+fn create_oval_path(boundary: Rect) -> tiny_skia::Path {
+    let cx = boundary.x + boundary.width / 2.0;
+    let cy = boundary.y + boundary.height / 2.0;
+    let rx = boundary.width / 2.0;
+    let ry = boundary.height / 2.0;
+
+    let mut pb = tiny_skia::PathBuilder::new();
+    // Create ellipse using cubic bezier curves
+    // Magic constant for circle/ellipse approximation with bezier curves
+    // const KAPPA: f32 = 0.5522847498;
+    const KAPPA: f32 = 0.552_284_8;
+
+    let ox = rx * KAPPA; // control point offset x
+    let oy = ry * KAPPA; // control point offset y
+
+    pb.move_to(cx - rx, cy);
+    pb.cubic_to(cx - rx, cy - oy, cx - ox, cy - ry, cx, cy - ry);
+    pb.cubic_to(cx + ox, cy - ry, cx + rx, cy - oy, cx + rx, cy);
+    pb.cubic_to(cx + rx, cy + oy, cx + ox, cy + ry, cx, cy + ry);
+    pb.cubic_to(cx - ox, cy + ry, cx - rx, cy + oy, cx - rx, cy);
+    pb.close();
+
+    pb.finish().unwrap()
+}
+
+/// Coverage of the clip shape intersected with the enclosing clip, `None` only for an
+/// empty surface. A degenerate shape covers nothing.
+fn clip_mask(
+    width: u32,
+    height: u32,
+    parent: Option<&tiny_skia::Mask>,
+    rect: Rect,
+    shape: &ClipShape,
+    transform: tiny_skia::Transform,
+) -> Option<tiny_skia::Mask> {
+    let path = match shape {
+        ClipShape::Rect => tiny_skia::Rect::from_xywh(rect.x, rect.y, rect.width, rect.height)
+            .map(tiny_skia::PathBuilder::from_rect),
+        ClipShape::RoundedRect { border_radius } => {
+            Some(create_rounded_rect_path(rect, border_radius))
+        }
+        ClipShape::Oval => Some(create_oval_path(rect)),
+    };
+
+    let mut mask = match parent {
+        Some(parent) => parent.clone(),
+        None => {
+            let mut mask = tiny_skia::Mask::new(width, height)?;
+            mask.data_mut().fill(u8::MAX);
+            mask
+        }
+    };
+
+    match path {
+        Some(path) => {
+            mask.intersect_path(&path, tiny_skia::FillRule::Winding, true, transform);
+        }
+        None => mask.clear(),
     }
+
+    Some(mask)
 }
 
 fn create_rounded_rect_path(rect: Rect, border_radius: &BorderRadius) -> tiny_skia::Path {
@@ -385,14 +1082,49 @@ fn create_rounded_rect_path(rect: Rect, border_radius: &BorderRadius) -> tiny_sk
     pb.finish().unwrap()
 }
 
-fn create_paint_from_fill(fill: &Fill, rect: Rect) -> Option<tiny_skia::Paint<'static>> {
+/// Paint of a fill. tiny-skia has no sweep gradients, they are rasterized over the
+/// boundary and drawn as a pattern.
+enum FillPaint {
+    Paint(tiny_skia::Paint<'static>),
+    Pattern {
+        pixmap: tiny_skia::Pixmap,
+        transform: tiny_skia::Transform,
+    },
+}
+
+impl FillPaint {
+    fn paint(&self) -> tiny_skia::Paint<'_> {
+        match self {
+            FillPaint::Paint(paint) => paint.clone(),
+            FillPaint::Pattern { pixmap, transform } => tiny_skia::Paint {
+                shader: tiny_skia::Pattern::new(
+                    pixmap.as_ref(),
+                    tiny_skia::SpreadMode::Pad,
+                    tiny_skia::FilterQuality::Bilinear,
+                    1.0,
+                    *transform,
+                ),
+                blend_mode: tiny_skia::BlendMode::default(),
+                anti_alias: true,
+                force_hq_pipeline: false,
+            },
+        }
+    }
+}
+
+fn create_paint_from_fill(fill: &Fill, rect: Rect) -> Option<FillPaint> {
     match fill {
         Fill::None => None,
         Fill::Color(color) => {
             let mut paint = tiny_skia::Paint::default();
             paint.set_color(convert_rgba_color(color));
             paint.anti_alias = true;
-            Some(paint)
+            Some(FillPaint::Paint(paint))
+        }
+        Fill::Gradient(Gradient::Sweep(sweep)) => {
+            let (pixmap, transform) = create_sweep_gradient_pixmap(sweep, rect)?;
+
+            Some(FillPaint::Pattern { pixmap, transform })
         }
         Fill::Gradient(gradient) => {
             let shader = create_gradient_shader(gradient, rect)?;
@@ -404,7 +1136,7 @@ fn create_paint_from_fill(fill: &Fill, rect: Rect) -> Option<tiny_skia::Paint<'s
                 force_hq_pipeline: false,
             };
 
-            Some(paint)
+            Some(FillPaint::Paint(paint))
         }
     }
 }
@@ -420,11 +1152,7 @@ fn create_gradient_shader(gradient: &Gradient, rect: Rect) -> Option<tiny_skia::
                 })
                 .collect();
 
-            // Convert normalized coordinates to absolute coordinates
-            let start_x = rect.x + linear.start.0 * rect.width;
-            let start_y = rect.y + linear.start.1 * rect.height;
-            let end_x = rect.x + linear.end.0 * rect.width;
-            let end_y = rect.y + linear.end.1 * rect.height;
+            let ((start_x, start_y), (end_x, end_y)) = linear.resolve(rect);
 
             tiny_skia::LinearGradient::new(
                 tiny_skia::Point::from_xy(start_x, start_y),
@@ -443,36 +1171,104 @@ fn create_gradient_shader(gradient: &Gradient, rect: Rect) -> Option<tiny_skia::
                 })
                 .collect();
 
-            // Convert normalized coordinates to absolute coordinates
-            let center_x = rect.x + radial.center.0 * rect.width;
-            let center_y = rect.y + radial.center.1 * rect.height;
-            let radius = radial.radius * rect.width.max(rect.height);
-
-            // Use focal point if provided, otherwise use center
-            let (focal_x, focal_y) = if let Some(focal) = radial.focal {
-                (
-                    rect.x + focal.0 * rect.width,
-                    rect.y + focal.1 * rect.height,
-                )
-            } else {
-                (center_x, center_y)
-            };
+            let geometry = radial.resolve(rect)?;
 
+            // The gradient starts at the focal point and ends at the circle, the ellipse
+            // is made by the transform of the shader.
             tiny_skia::RadialGradient::new(
-                tiny_skia::Point::from_xy(center_x, center_y),
-                tiny_skia::Point::from_xy(focal_x, focal_y),
-                radius,
+                tiny_skia::Point::from_xy(geometry.focal.0, geometry.focal.1),
+                tiny_skia::Point::from_xy(0., 0.),
+                geometry.radius,
                 stops,
                 convert_tile_mode(&radial.tile_mode),
-                tiny_skia::Transform::identity(),
+                tiny_skia::Transform::from_scale(1., geometry.scale_y)
+                    .post_translate(geometry.center.0, geometry.center.1),
             )
         }
-        Gradient::Sweep(_sweep) => {
-            // tiny-skia doesn't have native sweep gradient support
-            // You could implement it using a custom shader or fall back to radial
-            None
-        }
+        // Drawn as a pattern, see `create_sweep_gradient_pixmap`.
+        Gradient::Sweep(_) => None,
+    }
+}
+
+/// Pixmap of the sweep gradient over the rect and the transform that places it there.
+/// Each pixel takes the color at its angle around the center the way vello computes it:
+/// the angle goes clockwise from the right from 0 to 1 of a turn, the start angle is at
+/// 0 of the gradient and the end angle at 1, the tile mode extends it to the angles
+/// before and after them.
+fn create_sweep_gradient_pixmap(
+    sweep: &SweepGradient,
+    rect: Rect,
+) -> Option<(tiny_skia::Pixmap, tiny_skia::Transform)> {
+    const MAX_SIZE: f32 = 2048.;
+    const RAMP_SIZE: usize = 256;
+
+    let width = rect.width.ceil().clamp(1., MAX_SIZE) as u32;
+    let height = rect.height.ceil().clamp(1., MAX_SIZE) as u32;
+    let mut pixmap = tiny_skia::Pixmap::new(width, height)?;
+
+    let mut stops = sweep.stops.clone();
+    stops.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+
+    let first = stops.first()?;
+    let last = stops.last()?;
+    let color_at = |t: f32| {
+        let color = match stops.iter().position(|stop| stop.offset > t) {
+            None => last.color,
+            Some(0) => first.color,
+            Some(index) => {
+                let (from, to) = (&stops[index - 1], &stops[index]);
+                let amount = (t - from.offset) / (to.offset - from.offset);
+                let mix = |a: f32, b: f32| a + (b - a) * amount;
+
+                ColorRgba {
+                    r: mix(from.color.r, to.color.r),
+                    g: mix(from.color.g, to.color.g),
+                    b: mix(from.color.b, to.color.b),
+                    a: mix(from.color.a, to.color.a),
+                }
+            }
+        };
+
+        convert_rgba_color(&color).premultiply().to_color_u8()
+    };
+    let ramp: Vec<_> = (0..RAMP_SIZE)
+        .map(|index| color_at(index as f32 / (RAMP_SIZE - 1) as f32))
+        .collect();
+
+    let turn = std::f32::consts::TAU;
+    let t0 = sweep.start_angle / turn;
+    let t1 = sweep.end_angle / turn;
+    let scale = if t1 != t0 { 1. / (t1 - t0) } else { 0. };
+
+    let (scale_x, scale_y) = (rect.width / width as f32, rect.height / height as f32);
+    let center_x = sweep.center.0 * rect.width;
+    let center_y = sweep.center.1 * rect.height;
+    let transparent = tiny_skia::PremultipliedColorU8::TRANSPARENT;
+
+    for (index, pixel) in pixmap.pixels_mut().iter_mut().enumerate() {
+        let x = (index as u32 % width) as f32 + 0.5;
+        let y = (index as u32 / width) as f32 + 0.5;
+        let angle = (y * scale_y - center_y).atan2(x * scale_x - center_x) / turn;
+        let t = (angle.rem_euclid(1.) - t0) * scale;
+
+        let t = match sweep.tile_mode {
+            TileMode::Clamp => t.clamp(0., 1.),
+            TileMode::Repeat => t.rem_euclid(1.),
+            TileMode::Mirror => 1. - (t.rem_euclid(2.) - 1.).abs(),
+            TileMode::Decal if !(0. ..=1.).contains(&t) => {
+                *pixel = transparent;
+                continue;
+            }
+            TileMode::Decal => t,
+        };
+
+        *pixel = ramp[(t * (RAMP_SIZE - 1) as f32).round() as usize];
     }
+
+    let transform =
+        tiny_skia::Transform::from_scale(scale_x, scale_y).post_translate(rect.x, rect.y);
+
+    Some((pixmap, transform))
 }
 
 fn convert_tile_mode(tile_mode: &TileMode) -> tiny_skia::SpreadMode {
@@ -484,69 +1280,249 @@ fn convert_tile_mode(tile_mode: &TileMode) -> tiny_skia::SpreadMode {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn render_border(
     pixmap: &mut PixmapMut,
     path: &tiny_skia::Path,
+    boundary: Rect,
+    border_radius: Option<&BorderRadius>,
     border: &Border,
+    border_fill: Option<&Fill>,
     clip_mask: Option<&tiny_skia::Mask>,
+    transform: tiny_skia::Transform,
 ) {
-    // For uniform borders, we can stroke once
-    // For non-uniform borders, we'd need to stroke each side separately
-    // This is a simplified implementation that strokes all sides with the same width
-
-    // Find the maximum border width to use
-    let max_width = [
-        border.top.as_ref().map(|s| s.width).unwrap_or(0.0),
-        border.right.as_ref().map(|s| s.width).unwrap_or(0.0),
-        border.bottom.as_ref().map(|s| s.width).unwrap_or(0.0),
-        border.left.as_ref().map(|s| s.width).unwrap_or(0.0),
-    ]
-    .into_iter()
-    .fold(0.0f32, f32::max);
-
-    if max_width > 0.0 {
-        // Use the first available border side's color
-        let color = border
-            .top
-            .as_ref()
-            .or(border.right.as_ref())
-            .or(border.bottom.as_ref())
-            .or(border.left.as_ref())
-            .map(|s| s.color)
-            .unwrap_or(ColorRgba::TRANSPARENT);
+    // The same sides are stroked along the outline at once.
+    if let Some(side) = border.uniform() {
+        if side.width <= 0.0 {
+            return;
+        }
 
         let stroke = tiny_skia::Stroke {
-            width: max_width,
+            width: side.width,
             miter_limit: 4.0,
             line_cap: tiny_skia::LineCap::default(),
             line_join: tiny_skia::LineJoin::default(),
             dash: None,
         };
 
-        let mut paint = tiny_skia::Paint::default();
-        paint.set_color(convert_rgba_color(&color));
-        paint.anti_alias = true;
+        if let Some(paint) = create_border_paint(border_fill, side.color, boundary) {
+            pixmap.stroke_path(path, &paint.paint(), &stroke, transform, clip_mask);
+        }
 
-        pixmap.stroke_path(
-            path,
-            &paint,
-            &stroke,
-            tiny_skia::Transform::identity(),
-            clip_mask,
-        );
+        return;
+    }
+
+    for piece in border.resolve(boundary, border_radius) {
+        let Some(path) = create_outline_path(&piece.outline) else {
+            continue;
+        };
+
+        if let Some(paint) = create_border_paint(border_fill, piece.color, boundary) {
+            pixmap.fill_path(
+                &path,
+                &paint.paint(),
+                tiny_skia::FillRule::EvenOdd,
+                transform,
+                clip_mask,
+            );
+        }
+    }
+}
+
+fn create_outline_path(outline: &[PathSegment]) -> Option<tiny_skia::Path> {
+    let mut pb = tiny_skia::PathBuilder::new();
+
+    for segment in outline {
+        match *segment {
+            PathSegment::MoveTo(x, y) => pb.move_to(x, y),
+            PathSegment::LineTo(x, y) => pb.line_to(x, y),
+            PathSegment::CubicTo((x1, y1), (x2, y2), (x, y)) => pb.cubic_to(x1, y1, x2, y2, x, y),
+            PathSegment::Close => pb.close(),
+        }
+    }
+
+    pb.finish()
+}
+
+/// Outline of a glyph approximated by its coverage dilated by the width of the stroke,
+/// the glyph image is at `origin`.
+#[allow(clippy::too_many_arguments)]
+fn stroke_glyph(
+    pixmap: &mut PixmapMut,
+    swash_cache: &mut SwashCache,
+    fonts: &mut FontResources,
+    cache_key: cosmic_text::CacheKey,
+    origin: (f32, f32),
+    stroke: &TextStroke,
+    clip: Option<Rect>,
+    transform: tiny_skia::Transform,
+    mask: Option<&tiny_skia::Mask>,
+) {
+    let mut coverage = Vec::new();
+
+    swash_cache.with_pixels(
+        &mut fonts.font_system,
+        cache_key,
+        cosmic_text::Color::rgba(0, 0, 0, 255),
+        |x, y, color| {
+            if color.a() > 0 {
+                coverage.push((x, y, color.a()));
+            }
+        },
+    );
+
+    let (Some(left), Some(top)) = (
+        coverage.iter().map(|(x, ..)| *x).min(),
+        coverage.iter().map(|(_, y, _)| *y).min(),
+    ) else {
+        return;
+    };
+    let right = coverage.iter().map(|(x, ..)| *x).max().unwrap_or(left);
+    let bottom = coverage.iter().map(|(_, y, _)| *y).max().unwrap_or(top);
+
+    let radius = stroke.width.ceil() as i32;
+    let width = (right - left + 1 + radius * 2) as usize;
+    let height = (bottom - top + 1 + radius * 2) as usize;
+    let mut dilated = vec![0f32; width * height];
+
+    for (x, y, alpha) in coverage {
+        let alpha = alpha as f32 / 255.;
+
+        for dy in -radius..=radius {
+            for dx in -radius..=radius {
+                let distance = ((dx * dx + dy * dy) as f32).sqrt();
+                // The edge of the disk is anti-aliased over a pixel.
+                let edge = (stroke.width + 0.5 - distance).clamp(0., 1.);
+
+                if edge > 0. {
+                    let index = (y - top + radius + dy) as usize * width
+                        + (x - left + radius + dx) as usize;
+
+                    dilated[index] = dilated[index].max(alpha * edge);
+                }
+            }
+        }
+    }
+
+    let Some(mut outline) = tiny_skia::Pixmap::new(width as u32, height as u32) else {
+        return;
+    };
+    let x = origin.0 + (left - radius) as f32;
+    let y = origin.1 + (top - radius) as f32;
+
+    for ((index, alpha), pixel) in dilated.into_iter().enumerate().zip(outline.pixels_mut()) {
+        if alpha <= 0. {
+            continue;
+        }
+
+        // The outline is drawn as an image, so the straddlers are clipped by the
+        // centers of their pixels like the fill of the glyphs.
+        if let Some(clip) = clip {
+            let center_x = x + (index % width) as f32 + 0.5;
+            let center_y = y + (index / width) as f32 + 0.5;
+
+            if center_x < clip.left()
+                || center_x >= clip.right()
+                || center_y < clip.top()
+                || center_y >= clip.bottom()
+            {
+                continue;
+            }
+        }
+
+        let color: cosmic_text::Color = stroke.color.with_opacity(alpha * stroke.color.a).into();
+
+        // Swapped like the fill of the glyphs, see the text command.
+        *pixel =
+            tiny_skia::ColorU8::from_rgba(color.b(), color.g(), color.r(), color.a()).premultiply();
     }
+
+    pixmap.draw_pixmap(
+        0,
+        0,
+        outline.as_ref(),
+        &tiny_skia::PixmapPaint::default(),
+        transform.pre_translate(x, y),
+        mask,
+    );
 }
 
+/// Scale the glyphs and the SVGs are rasterized at under the transform, the uniform
+/// scale of a transform without rotation or skew and 1 otherwise.
+fn raster_scale(transform: tiny_skia::Transform) -> f32 {
+    if transform.is_scale_translate() && transform.sx == transform.sy && transform.sx > 0. {
+        transform.sx
+    } else {
+        1.
+    }
+}
+
+/// The damaged rect in whole pixels, clipped to the surface.
+fn damage_rect(rect: Rect, width: u32, height: u32) -> Option<tiny_skia::IntRect> {
+    tiny_skia::Rect::from_xywh(rect.x, rect.y, rect.width, rect.height)?
+        .round_out()?
+        .intersect(&tiny_skia::IntRect::from_xywh(0, 0, width, height)?)
+}
+
+/// Whether the area drawn in `bounds` is entirely outside of `cull`.
+fn is_outside(bounds: Rect, transform: tiny_skia::Transform, cull: tiny_skia::Rect) -> bool {
+    tiny_skia::Rect::from_xywh(bounds.x, bounds.y, bounds.width, bounds.height)
+        .and_then(|bounds| bounds.transform(transform))
+        .is_some_and(|bounds| {
+            bounds.right() < cull.left()
+                || bounds.left() > cull.right()
+                || bounds.bottom() < cull.top()
+                || bounds.top() > cull.bottom()
+        })
+}
+
+// The colors are quantized the same way as in the other renderers and written into
+// the buffer as is, softbuffer presents them without a color conversion.
 fn convert_rgba_color(color: &ColorRgba) -> tiny_skia::Color {
+    let [r, g, b, a] = color.to_rgba8();
+
     // Note: due to softbuffer and tiny_skia having incompatible internal color representations we swap
     // the red and blue channels here
-    tiny_skia::Color::from_rgba(color.b, color.g, color.r, color.a).unwrap()
+    tiny_skia::Color::from_rgba8(b, g, r, a)
 }
 
 fn convert_rgb_color(color: &ColorRgb) -> tiny_skia::Color {
+    let [r, g, b] = color.to_rgb8();
+
     // Note: due to softbuffer and tiny_skia having incompatible internal color representations we swap
     // the red and blue channels here
-    tiny_skia::Color::from_rgba(color.b, color.g, color.r, 1.).unwrap()
+    tiny_skia::Color::from_rgba8(b, g, r, 255)
+}
+
+/// Premultiplied copy of the bitmap with the red and blue channels swapped, see
+/// [`convert_rgba_color`].
+fn bitmap_pixmap(bitmap: &Bitmap) -> Option<tiny_skia::Pixmap> {
+    let mut pixmap = tiny_skia::Pixmap::new(bitmap.width(), bitmap.height())?;
+
+    for (pixel, rgba) in pixmap
+        .pixels_mut()
+        .iter_mut()
+        .zip(bitmap.pixels().chunks_exact(4))
+    {
+        *pixel = tiny_skia::ColorU8::from_rgba(rgba[2], rgba[1], rgba[0], rgba[3]).premultiply();
+    }
+
+    Some(pixmap)
+}
+
+/// Swaps the red and blue channels of a pixmap resvg rasterized into, it draws them in
+/// the order of the colors rather than the softbuffer one, see [`convert_rgba_color`].
+fn swap_red_blue(pixmap: &mut tiny_skia::Pixmap) {
+    for pixel in pixmap.pixels_mut() {
+        if let Some(swapped) = tiny_skia::PremultipliedColorU8::from_rgba(
+            pixel.blue(),
+            pixel.green(),
+            pixel.red(),
+            pixel.alpha(),
+        ) {
+            *pixel = swapped;
+        }
+    }
 }
 
 fn tint_pixmap(pixmap: &mut tiny_skia::Pixmap, color: tiny_skia::Color) {
@@ -569,3 +1545,938 @@ fn tint_pixmap(pixmap: &mut tiny_skia::Pixmap, color: tiny_skia::Color) {
         None,
     );
 }
+
+/// Multiplies the colors of the pixels by the color, the alpha included.
+fn multiply_pixmap(pixmap: &mut tiny_skia::Pixmap, color: tiny_skia::Color) {
+    let color = color.premultiply();
+    let multiply = |channel: u8, factor: f32| (channel as f32 * factor).round() as u8;
+
+    for pixel in pixmap.pixels_mut() {
+        // The channels of a premultiplied tint are at most its alpha, so the result
+        // stays premultiplied.
+        if let Some(multiplied) = tiny_skia::PremultipliedColorU8::from_rgba(
+            multiply(pixel.red(), color.red()),
+            multiply(pixel.green(), color.green()),
+            multiply(pixel.blue(), color.blue()),
+            multiply(pixel.alpha(), color.alpha()),
+        ) {
+            *pixel = multiplied;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use clew::{
+        ClewHost, ClewInstance, ColorStop, ImageFit, LinearGradient, RadialGradient,
+        RadialGradientGeometry, Resources, SubtreeImageOptions, WidgetType, prelude::*, testing,
+        text::SystemFonts, widgets::builder::NoopEventLoopProxy,
+    };
+
+    use super::*;
+
+    /// Channels of the pixel in the order of `ColorRgba::to_rgba8`, the pixmap holds them
+    /// in the softbuffer order.
+    fn rgba8(pixmap: &tiny_skia::Pixmap, x: u32, y: u32) -> [u8; 4] {
+        let pixel = pixmap.pixel(x, y).unwrap();
+
+        [pixel.blue(), pixel.green(), pixel.red(), pixel.alpha()]
+    }
+
+    /// The shipped DejaVu Sans Mono, so the text is shaped the same on every system.
+    fn test_fonts() -> FontResources {
+        FontResources::with_fonts(
+            SystemFonts::Lazy,
+            [(
+                "DejaVu Sans Mono",
+                include_bytes!("../../test-fonts/DejaVuSansMono.ttf").as_slice(),
+            )],
+        )
+    }
+
+    #[test]
+    fn test_colors_are_drawn_with_srgb_values() {
+        let fill_color = ColorRgb::from_hex(0x2F2F2F);
+        let color = ColorRgba::from_hex(0xFF2F80C4);
+        let commands = [RenderCommand::Rect {
+            boundary: Rect::new(0., 0., 2., 4.),
+            fill: Some(Fill::Color(color)),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        }];
+        let mut pixmap = tiny_skia::Pixmap::new(4, 4).unwrap();
+        pixmap.fill(convert_rgb_color(&fill_color));
+
+        draw_commands(
+            &mut pixmap.as_mut(),
+            &mut SwashCache::new(),
+            &commands,
+            tiny_skia::Transform::identity(),
+            None,
+            &mut FontResources::new(),
+            &mut TextsResources::new(),
+            &Assets::new(),
+        );
+
+        // The vello renderer is given the same values, see `convert_rgba_color` there.
+        let close = |rendered: [u8; 4], expected: [u8; 4]| {
+            rendered
+                .iter()
+                .zip(expected)
+                .all(|(rendered, expected)| rendered.abs_diff(expected) <= 1)
+        };
+
+        assert!(close(rgba8(&pixmap, 1, 1), [0x2F, 0x80, 0xC4, 0xFF]));
+        assert!(close(rgba8(&pixmap, 3, 1), [0x2F, 0x2F, 0x2F, 0xFF]));
+    }
+
+    #[test]
+    fn test_image_is_straight_rgba() {
+        let color = ColorRgba::from_hex(0x802F80C4);
+        let commands = [RenderCommand::Rect {
+            boundary: Rect::new(0., 0., 2., 2.),
+            fill: Some(Fill::Color(color)),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        }];
+
+        let image = draw_image(
+            &mut SwashCache::new(),
+            &commands,
+            4,
+            2,
+            None,
+            &mut FontResources::new(),
+            &mut TextsResources::new(),
+            &Assets::new(),
+        )
+        .unwrap();
+
+        assert_eq!((image.width, image.height), (4, 2));
+        assert!(
+            image.pixels[..4]
+                .iter()
+                .zip([0x2F, 0x80, 0xC4, 0x80])
+                .all(|(pixel, expected)| pixel.abs_diff(expected) <= 1)
+        );
+        assert_eq!(image.pixels[12..16], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_headless_frame_is_rendered_at_view_scale() {
+        let mut host = testing::host();
+        let view = View {
+            scale_factor: 2.,
+            ..testing::view(8, 8)
+        };
+        let mut instance = ClewInstance::new(view, FontResources::new());
+        let mut renderer = HeadlessRenderer::new();
+        let fill_color = ColorRgb::from_hex(0x121212);
+
+        instance.frame(&mut host, &mut |ctx| {
+            clew::zstack()
+                .width(2.)
+                .height(2.)
+                .background(
+                    clew::decoration()
+                        .color(ColorRgba::from_hex(0xFF2F80C4))
+                        .build(ctx),
+                )
+                .build(ctx, |_| {});
+        });
+
+        assert!(instance.render(&host, &mut renderer, fill_color));
+
+        let image = instance
+            .render_image(&host, &mut renderer, fill_color)
+            .unwrap();
+        let pixel = |x: usize, y: usize| {
+            let offset = (y * image.width as usize + x) * 4;
+
+            &image.pixels[offset..offset + 4]
+        };
+
+        assert_eq!(renderer.frame(), Some(&image));
+        // The box of 2 is 4 pixels wide at the scale.
+        assert_eq!(pixel(3, 3), [0x2F, 0x80, 0xC4, 0xFF]);
+        assert_eq!(pixel(4, 4), [0x12, 0x12, 0x12, 0xFF]);
+    }
+
+    #[test]
+    fn test_bitmap_is_stretched_to_boundary() {
+        let mut source = tiny_skia::Pixmap::new(1, 1).unwrap();
+        source.fill(tiny_skia::Color::from_rgba8(0x2F, 0x80, 0xC4, 0xFF));
+
+        let mut assets = Assets::new();
+        assets
+            .load_bitmap("pixel", &source.encode_png().unwrap())
+            .unwrap();
+
+        let commands = [RenderCommand::Image {
+            boundary: Rect::new(0., 0., 2., 2.),
+            asset_id: "pixel".into(),
+            tint_color: None,
+            fit: ImageFit::Fill,
+            opacity: 0.5,
+        }];
+
+        let image = draw_image(
+            &mut SwashCache::new(),
+            &commands,
+            4,
+            2,
+            None,
+            &mut FontResources::new(),
+            &mut TextsResources::new(),
+            &assets,
+        )
+        .unwrap();
+
+        assert!(
+            image.pixels[20..24]
+                .iter()
+                .zip([0x2F, 0x80, 0xC4, 0x80])
+                .all(|(pixel, expected)| pixel.abs_diff(expected) <= 2)
+        );
+        assert_eq!(image.pixels[12..16], [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_contained_bitmap_is_tinted_inside_of_its_rect() {
+        let mut source = tiny_skia::Pixmap::new(2, 1).unwrap();
+        source.fill(tiny_skia::Color::from_rgba8(0x2F, 0x80, 0xC4, 0xFF));
+
+        let mut assets = Assets::new();
+        assets
+            .load_bitmap("wide", &source.encode_png().unwrap())
+            .unwrap();
+
+        let commands = [RenderCommand::Image {
+            boundary: Rect::new(0., 0., 4., 4.),
+            asset_id: "wide".into(),
+            tint_color: Some(ColorRgba::from_hex(0xFFE04040)),
+            fit: ImageFit::Contain,
+            opacity: 1.,
+        }];
+
+        let image = draw_image(
+            &mut SwashCache::new(),
+            &commands,
+            4,
+            4,
+            None,
+            &mut FontResources::new(),
+            &mut TextsResources::new(),
+            &assets,
+        )
+        .unwrap();
+        let pixel = |x: usize, y: usize| &image.pixels[(y * 4 + x) * 4..(y * 4 + x) * 4 + 4];
+
+        // Fitted into the rows 1 and 2, the rows above and below stay empty.
+        assert_eq!(pixel(1, 0), [0, 0, 0, 0]);
+        assert_eq!(pixel(1, 3), [0, 0, 0, 0]);
+        assert_eq!(pixel(1, 1), [0xE0, 0x40, 0x40, 0xFF]);
+        assert_eq!(pixel(2, 2), [0xE0, 0x40, 0x40, 0xFF]);
+    }
+
+    #[test]
+    fn test_subtree_image_is_rasterized_at_its_scale() {
+        let mut assets = Assets::new();
+        assets
+            .load_svg(
+                "dot",
+                br##"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16">
+                    <circle cx="8" cy="8" r="7" fill="#FFFFFF"/>
+                </svg>"##,
+            )
+            .unwrap();
+
+        let mut host = ClewHost::new(assets, Resources::new(), Arc::new(NoopEventLoopProxy));
+        let view = testing::view(96, 32);
+        let mut instance = ClewInstance::new(view, test_fonts());
+        let mut renderer = HeadlessRenderer::new();
+        let fill_color = ColorRgb::from_hex(0x121212);
+
+        instance.frame(&mut host, &mut |ctx| {
+            clew::hstack().build(ctx, |ctx| {
+                clew::text("Hg").font_size(24.).build(ctx);
+                clew::svg("dot").width(16.).height(16.).build(ctx);
+            });
+        });
+        instance.render(&host, &mut renderer, fill_color);
+
+        let mut placed = |widget_type| {
+            instance
+                .ui_state()
+                .placed_widgets()
+                .find(|(widget_ref, _)| widget_ref.widget_type == widget_type)
+                .unwrap()
+        };
+        let placements = [
+            placed(WidgetType::of::<clew::widgets::text::TextWidget>()),
+            placed(WidgetType::of::<clew::widgets::svg::State>()),
+        ];
+
+        for (widget_ref, rect) in placements {
+            let image = instance
+                .subtree_image(
+                    &host,
+                    &mut renderer,
+                    widget_ref.id,
+                    SubtreeImageOptions::new().scale(2.),
+                    fill_color,
+                )
+                .unwrap();
+            let pixel = |x: u32, y: u32| {
+                let offset = ((y * image.width + x) * 4) as usize;
+
+                &image.pixels[offset..offset + 4]
+            };
+
+            assert_eq!(
+                (image.width, image.height),
+                (
+                    (rect.width * 2.).ceil() as u32,
+                    (rect.height * 2.).ceil() as u32
+                )
+            );
+
+            // Stretched from the pixels of the view, the image would be made of blocks
+            // of 2x2 pixels of one color, the edges rasterized at the scale aren't.
+            let sharp = (0..image.height / 2).any(|y| {
+                (0..image.width / 2).any(|x| {
+                    let (x, y) = (x * 2, y * 2);
+
+                    [(x + 1, y), (x, y + 1), (x + 1, y + 1)]
+                        .into_iter()
+                        .any(|(x, y)| pixel(x, y) != pixel(x & !1, y & !1))
+                })
+            });
+            assert!(sharp);
+        }
+    }
+
+    #[test]
+    fn test_svg_keeps_its_colors_untinted_and_multiplied() {
+        let mut assets = Assets::new();
+        assets
+            .load_svg(
+                "orange",
+                br##"<svg xmlns="http://www.w3.org/2000/svg" width="1" height="1">
+                    <rect width="1" height="1" fill="#FF8040"/>
+                </svg>"##,
+            )
+            .unwrap();
+
+        let tint = ColorRgba::from_hex(0xFFC0A040);
+        let draw = |tint_color| {
+            draw_image(
+                &mut SwashCache::new(),
+                &[RenderCommand::Svg {
+                    boundary: Rect::new(0., 0., 1., 1.),
+                    asset_id: "orange",
+                    tint_color,
+                    tint_mode: TintMode::Multiply,
+                }],
+                1,
+                1,
+                None,
+                &mut FontResources::new(),
+                &mut TextsResources::new(),
+                &assets,
+            )
+            .unwrap()
+        };
+        let close = |pixel: &[u8], expected: ColorRgba| {
+            pixel
+                .iter()
+                .zip(expected.to_rgba8())
+                .all(|(pixel, expected)| pixel.abs_diff(expected) <= 2)
+        };
+        let orange = ColorRgba::from_hex(0xFFFF8040);
+
+        assert!(close(&draw(None).pixels, orange));
+        assert!(close(
+            &draw(Some(tint)).pixels,
+            TintMode::Multiply.apply(orange, tint)
+        ));
+    }
+
+    #[test]
+    fn test_svg_is_tinted_in_its_tint_mode() {
+        let mut assets = Assets::new();
+        assets
+            .load_svg(
+                "halves",
+                br##"<svg xmlns="http://www.w3.org/2000/svg" width="2" height="1">
+                    <rect width="1" height="1" fill="#FFFFFF"/>
+                    <rect x="1" width="1" height="1" fill="#808080"/>
+                </svg>"##,
+            )
+            .unwrap();
+
+        let tint = ColorRgba::from_hex(0x80E04040);
+        let draw = |tint_mode| {
+            draw_image(
+                &mut SwashCache::new(),
+                &[RenderCommand::Svg {
+                    boundary: Rect::new(0., 0., 2., 1.),
+                    asset_id: "halves",
+                    tint_color: Some(tint),
+                    tint_mode,
+                }],
+                2,
+                1,
+                None,
+                &mut FontResources::new(),
+                &mut TextsResources::new(),
+                &assets,
+            )
+            .unwrap()
+        };
+        let close = |pixel: &[u8], expected: ColorRgba| {
+            pixel
+                .iter()
+                .zip(expected.to_rgba8())
+                .all(|(pixel, expected)| pixel.abs_diff(expected) <= 2)
+        };
+        let gray = ColorRgba::from_hex(0xFF808080);
+
+        let replaced = draw(TintMode::Replace);
+        assert!(close(&replaced.pixels[..4], tint));
+        assert!(close(&replaced.pixels[4..], tint));
+
+        // The white half takes the tint, the gray one is darker.
+        let multiplied = draw(TintMode::Multiply);
+        assert!(close(&multiplied.pixels[..4], tint));
+        assert!(close(
+            &multiplied.pixels[4..],
+            TintMode::Multiply.apply(gray, tint)
+        ));
+    }
+
+    #[test]
+    fn test_rounded_clip_is_anti_aliased() {
+        let clip = RenderCommand::PushClip {
+            rect: Rect::new(0., 0., 16., 16.),
+            shape: ClipShape::RoundedRect {
+                border_radius: BorderRadius::all(4.),
+            },
+        };
+        let child = RenderCommand::Rect {
+            boundary: Rect::new(0., 0., 16., 16.),
+            fill: Some(Fill::Color(ColorRgba::from_hex(0xFFFFFFFF))),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        };
+        let draw = |commands: &[RenderCommand]| {
+            let mut pixmap = tiny_skia::Pixmap::new(16, 16).unwrap();
+
+            draw_commands(
+                &mut pixmap.as_mut(),
+                &mut SwashCache::new(),
+                commands,
+                tiny_skia::Transform::identity(),
+                None,
+                &mut FontResources::new(),
+                &mut TextsResources::new(),
+                &Assets::new(),
+            );
+
+            pixmap
+        };
+        let corner_alpha = |pixmap: &tiny_skia::Pixmap| {
+            (0..4)
+                .flat_map(|y| (0..4).map(move |x| (x, y)))
+                .map(|(x, y)| rgba8(pixmap, x, y)[3])
+                .collect::<Vec<_>>()
+        };
+
+        let single = draw(&[clip.clone(), child.clone(), RenderCommand::PopClip]);
+        let single_corner = corner_alpha(&single);
+
+        assert_eq!(rgba8(&single, 0, 0)[3], 0);
+        assert_eq!(rgba8(&single, 8, 8), [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(
+            single_corner
+                .iter()
+                .any(|alpha| *alpha > 0 && *alpha < 0xFF)
+        );
+
+        // The coverage of nested clips is multiplied, partially covered pixels get
+        // lighter while the covered ones stay opaque.
+        let nested = draw(&[
+            clip.clone(),
+            clip,
+            child,
+            RenderCommand::PopClip,
+            RenderCommand::PopClip,
+        ]);
+        let nested_corner = corner_alpha(&nested);
+
+        assert_eq!(rgba8(&nested, 8, 8), [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert!(
+            single_corner
+                .iter()
+                .zip(&nested_corner)
+                .all(|(single, nested)| nested <= single)
+        );
+        assert!(
+            single_corner
+                .iter()
+                .zip(&nested_corner)
+                .any(|(single, nested)| nested < single)
+        );
+    }
+
+    #[test]
+    fn test_selected_text_is_drawn_between_selection_and_caret() {
+        let mut fonts = test_fonts();
+        let view = testing::view(96, 24);
+        let mut texts = TextsResources::new();
+        let text_id = texts.add_text(&view, &mut fonts, 20., 24., |fonts, text| {
+            text.set_text(fonts, "HHHH")
+        });
+        texts.shape_as_needed(text_id, &mut fonts.font_system, false);
+
+        let selection_start = texts
+            .get(text_id)
+            .buffer()
+            .layout_runs()
+            .next()
+            .unwrap()
+            .glyphs[2]
+            .x
+            .round();
+        let selection = Rect::new(selection_start, 0., 96. - selection_start, 24.);
+
+        let text_color = [0xFF, 0, 0, 0xFF];
+        let selected_text_color = [0, 0xFF, 0, 0xFF];
+        let selection_color = [0, 0, 0xFF, 0xFF];
+        let caret_color = [0xFF, 0xFF, 0xFF, 0xFF];
+        let text = |tint: u32| RenderCommand::Text {
+            x: 0.,
+            y: 0.,
+            text_id,
+            tint_color: Some(ColorRgba::from_hex(tint)),
+            visible_band: None,
+            clip: None,
+            stroke: None,
+        };
+        let rect = |boundary: Rect, color: u32| RenderCommand::Rect {
+            boundary,
+            fill: Some(Fill::Color(ColorRgba::from_hex(color))),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        };
+
+        // The order the editable text emits its commands in.
+        let commands = [
+            rect(selection, 0xFF0000FF),
+            text(0xFFFF0000),
+            RenderCommand::PushClip {
+                rect: selection,
+                shape: ClipShape::Rect,
+            },
+            text(0xFF00FF00),
+            RenderCommand::PopClip,
+            rect(Rect::new(selection_start, 0., 1., 24.), 0xFFFFFFFF),
+        ];
+
+        let mut pixmap = tiny_skia::Pixmap::new(96, 24).unwrap();
+
+        draw_commands(
+            &mut pixmap.as_mut(),
+            &mut SwashCache::new(),
+            &commands,
+            tiny_skia::Transform::identity(),
+            None,
+            &mut fonts,
+            &mut texts,
+            &Assets::new(),
+        );
+
+        let column = |x: u32| (0..24).map(|y| rgba8(&pixmap, x, y)).collect::<Vec<_>>();
+        let before = (0..selection_start as u32 - 1)
+            .flat_map(column)
+            .collect::<Vec<_>>();
+        let inside = (selection_start as u32 + 2..96)
+            .flat_map(column)
+            .collect::<Vec<_>>();
+
+        assert!(before.contains(&text_color));
+        assert!(!before.contains(&selected_text_color));
+        assert!(!before.contains(&selection_color));
+
+        // The selected glyphs are over the selection and only in their own color.
+        assert!(inside.contains(&selected_text_color));
+        assert!(inside.contains(&selection_color));
+        assert!(!inside.contains(&text_color));
+
+        assert!(
+            column(selection_start as u32)
+                .iter()
+                .all(|pixel| *pixel == caret_color)
+        );
+    }
+
+    #[test]
+    fn test_text_is_clipped_to_its_rect() {
+        let mut fonts = test_fonts();
+        let view = testing::view(96, 24);
+        let mut texts = TextsResources::new();
+        let text_id = texts.add_text(&view, &mut fonts, 20., 24., |fonts, text| {
+            text.set_text(fonts, "HHHHHHHH")
+        });
+        texts.shape_as_needed(text_id, &mut fonts.font_system, false);
+
+        let draw = |clip: Option<Rect>, fonts: &mut FontResources, texts: &mut TextsResources| {
+            let mut pixmap = tiny_skia::Pixmap::new(96, 24).unwrap();
+
+            draw_commands(
+                &mut pixmap.as_mut(),
+                &mut SwashCache::new(),
+                &[RenderCommand::Text {
+                    x: 0.,
+                    y: 0.,
+                    text_id,
+                    tint_color: Some(ColorRgba::from_hex(0xFFFFFFFF)),
+                    visible_band: None,
+                    clip,
+                    stroke: None,
+                }],
+                tiny_skia::Transform::identity(),
+                None,
+                fonts,
+                texts,
+                &Assets::new(),
+            );
+
+            (0..96)
+                .filter(|x| (0..24).any(|y| rgba8(&pixmap, *x, y)[3] != 0))
+                .max()
+        };
+
+        let overflowing = draw(None, &mut fonts, &mut texts).unwrap();
+        let clipped = draw(Some(Rect::new(0., 0., 40., 24.)), &mut fonts, &mut texts).unwrap();
+
+        assert!(overflowing >= 40);
+        assert!(clipped < 40);
+    }
+
+    #[test]
+    fn test_text_stroke_surrounds_the_glyphs() {
+        let mut fonts = test_fonts();
+        let view = testing::view(48, 32);
+        let mut texts = TextsResources::new();
+        let text_id = texts.add_text(&view, &mut fonts, 20., 24., |fonts, text| {
+            text.set_text(fonts, "H")
+        });
+        texts.shape_as_needed(text_id, &mut fonts.font_system, false);
+
+        let mut pixmap = tiny_skia::Pixmap::new(48, 32).unwrap();
+
+        draw_commands(
+            &mut pixmap.as_mut(),
+            &mut SwashCache::new(),
+            &[RenderCommand::Text {
+                x: 8.,
+                y: 4.,
+                text_id,
+                tint_color: Some(ColorRgba::from_hex(0xFFFFFFFF)),
+                visible_band: None,
+                clip: None,
+                stroke: Some(TextStroke {
+                    width: 3.,
+                    color: ColorRgba::from_hex(0xFF000000),
+                }),
+            }],
+            tiny_skia::Transform::identity(),
+            None,
+            &mut fonts,
+            &mut texts,
+            &Assets::new(),
+        );
+
+        let leftmost = |pixel: fn([u8; 4]) -> bool| {
+            (0..48).find(|x| (0..32).any(|y| pixel(rgba8(&pixmap, *x, y))))
+        };
+        let painted = leftmost(|pixel| pixel[3] != 0).unwrap();
+        let fill = leftmost(|pixel| pixel[0] > 200 && pixel[3] == 255).unwrap();
+
+        // The outline is under the fill and sticks out by its width.
+        assert!(fill - painted >= 2);
+    }
+
+    /// Draws a black to white gradient filling the rect at the origin and checks its
+    /// pixels against the resolved geometry, which the vello renderer is given too.
+    fn check_radial_gradient(gradient: RadialGradient, width: u32, height: u32) {
+        let rect = Rect::new(0., 0., width as f32, height as f32);
+        let geometry = gradient.resolve(rect).unwrap();
+        let commands = [RenderCommand::Rect {
+            boundary: rect,
+            fill: Some(Fill::Gradient(Gradient::Radial(gradient))),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        }];
+        let mut pixmap = tiny_skia::Pixmap::new(width, height).unwrap();
+
+        draw_commands(
+            &mut pixmap.as_mut(),
+            &mut SwashCache::new(),
+            &commands,
+            tiny_skia::Transform::identity(),
+            None,
+            &mut FontResources::new(),
+            &mut TextsResources::new(),
+            &Assets::new(),
+        );
+
+        for y in 0..height {
+            for x in 0..width {
+                let offset = gradient_offset(&geometry, x as f32 + 0.5, y as f32 + 0.5);
+                let expected = (offset.min(1.) * 255.).round() as u8;
+                let rendered = rgba8(&pixmap, x, y)[0];
+
+                assert!(
+                    rendered.abs_diff(expected) <= 3,
+                    "pixel ({x}, {y}) is {rendered}, expected {expected}"
+                );
+            }
+        }
+    }
+
+    /// Offset of the gradient at the point, for a focal point without a radius.
+    fn gradient_offset(geometry: &RadialGradientGeometry, x: f32, y: f32) -> f32 {
+        let point = (
+            x - geometry.center.0,
+            (y - geometry.center.1) / geometry.scale_y,
+        );
+        let (focal_x, focal_y) = geometry.focal;
+        let (delta_x, delta_y) = (point.0 - focal_x, point.1 - focal_y);
+
+        // The point is on the circle `focal + t * (-focal)` of radius `t * radius`.
+        let a = focal_x * focal_x + focal_y * focal_y - geometry.radius * geometry.radius;
+        let b = delta_x * focal_x + delta_y * focal_y;
+        let c = delta_x * delta_x + delta_y * delta_y;
+
+        (-b - (b * b - a * c).sqrt()) / a
+    }
+
+    fn black_to_white() -> Vec<ColorStop> {
+        vec![
+            ColorStop::new(0., ColorRgba::from_hex(0xFF000000)),
+            ColorStop::new(1., ColorRgba::from_hex(0xFFFFFFFF)),
+        ]
+    }
+
+    #[test]
+    fn test_elliptical_radial_gradient() {
+        // A quarter of the height, the gradient spans the rect vertically.
+        let gradient = RadialGradient::ellipse((0.5, 0.5), 0.5, 0.125, black_to_white());
+
+        check_radial_gradient(gradient.clone(), 64, 16);
+
+        let geometry = gradient.resolve(Rect::new(0., 0., 64., 16.)).unwrap();
+        assert_eq!(geometry.radius, 32.);
+        assert_eq!(geometry.scale_y, 0.25);
+    }
+
+    #[test]
+    fn test_radial_gradient_with_focal_point() {
+        let gradient =
+            RadialGradient::new((0.5, 0.5), 0.5, black_to_white()).with_focal((0.25, 0.5));
+
+        check_radial_gradient(gradient.clone(), 32, 32);
+
+        // The gradient starts at the focal point rather than at the center.
+        let geometry = gradient.resolve(Rect::new(0., 0., 32., 32.)).unwrap();
+        assert_eq!(geometry.focal, (-8., 0.));
+        assert!((gradient_offset(&geometry, 16., 16.) - 1. / 3.).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_gradient_border_lines_up_with_gradient_fill() {
+        let boundary = Rect::new(4., 4., 56., 24.);
+        let gradient = Fill::Gradient(Gradient::Linear(LinearGradient::horizontal((
+            ColorRgba::from_hex(0xFFFF0000),
+            ColorRgba::from_hex(0xFF0000FF),
+        ))));
+        let draw = |command: RenderCommand| {
+            let mut pixmap = tiny_skia::Pixmap::new(64, 32).unwrap();
+
+            draw_commands(
+                &mut pixmap.as_mut(),
+                &mut SwashCache::new(),
+                &[command],
+                tiny_skia::Transform::identity(),
+                None,
+                &mut FontResources::new(),
+                &mut TextsResources::new(),
+                &Assets::new(),
+            );
+
+            pixmap
+        };
+
+        let filled = draw(RenderCommand::Rect {
+            boundary,
+            fill: Some(gradient.clone()),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        });
+        let outlined = draw(RenderCommand::Rect {
+            boundary,
+            fill: None,
+            border_radius: None,
+            border: Some(Border::all(BorderSide::new(4., ColorRgba::TRANSPARENT))),
+            border_fill: Some(gradient),
+        });
+
+        // The top row of the boundary is covered by both, the inside only by the fill.
+        for x in 4..60 {
+            assert_eq!(rgba8(&outlined, x, 4), rgba8(&filled, x, 4), "x = {x}");
+        }
+
+        assert_eq!(rgba8(&outlined, 32, 16), [0, 0, 0, 0]);
+        assert_ne!(rgba8(&filled, 32, 16), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_sweep_gradient_goes_clockwise_from_the_start_angle() {
+        let mut pixmap = tiny_skia::Pixmap::new(32, 32).unwrap();
+        let gradient = SweepGradient::full(vec![
+            ColorRgba::from_hex(0xFFFF0000),
+            ColorRgba::from_hex(0xFF0000FF),
+        ]);
+
+        draw_commands(
+            &mut pixmap.as_mut(),
+            &mut SwashCache::new(),
+            &[RenderCommand::Rect {
+                boundary: Rect::new(0., 0., 32., 32.),
+                fill: Some(Fill::Gradient(Gradient::Sweep(gradient))),
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            }],
+            tiny_skia::Transform::identity(),
+            None,
+            &mut FontResources::new(),
+            &mut TextsResources::new(),
+            &Assets::new(),
+        );
+
+        // A quarter of the turn below the center, three quarters above it.
+        let [r, _, b, a] = rgba8(&pixmap, 16, 28);
+        assert!(r.abs_diff(191) <= 8 && b.abs_diff(64) <= 8, "{r}, {b}");
+        assert_eq!(a, 255);
+
+        let [r, _, b, _] = rgba8(&pixmap, 16, 3);
+        assert!(r.abs_diff(64) <= 8 && b.abs_diff(191) <= 8, "{r}, {b}");
+    }
+
+    #[test]
+    fn test_sides_of_a_border_are_drawn_with_their_widths_and_colors() {
+        let red = ColorRgba::from_hex(0xFFFF0000);
+        let green = ColorRgba::from_hex(0xFF00FF00);
+        let blue = ColorRgba::from_hex(0xFF0000FF);
+        let draw = |border_radius: Option<BorderRadius>, border: Border| {
+            let mut pixmap = tiny_skia::Pixmap::new(64, 32).unwrap();
+
+            draw_commands(
+                &mut pixmap.as_mut(),
+                &mut SwashCache::new(),
+                &[RenderCommand::Rect {
+                    boundary: Rect::new(8., 8., 48., 16.),
+                    fill: None,
+                    border_radius,
+                    border: Some(border),
+                    border_fill: None,
+                }],
+                tiny_skia::Transform::identity(),
+                None,
+                &mut FontResources::new(),
+                &mut TextsResources::new(),
+                &Assets::new(),
+            );
+
+            pixmap
+        };
+
+        let pixmap = draw(
+            None,
+            Border::new(
+                Some(BorderSide::new(2., red)),
+                None,
+                Some(BorderSide::new(4., blue)),
+                Some(BorderSide::new(0., green)),
+            ),
+        );
+
+        // Each side is centered on its edge, the sides without a width aren't drawn.
+        assert_eq!(rgba8(&pixmap, 32, 7), [255, 0, 0, 255]);
+        assert_eq!(rgba8(&pixmap, 32, 8), [255, 0, 0, 255]);
+        assert_eq!(rgba8(&pixmap, 32, 9), [0, 0, 0, 0]);
+        assert_eq!(rgba8(&pixmap, 32, 22), [0, 0, 255, 255]);
+        assert_eq!(rgba8(&pixmap, 32, 25), [0, 0, 255, 255]);
+        assert_eq!(rgba8(&pixmap, 32, 26), [0, 0, 0, 0]);
+        assert_eq!(rgba8(&pixmap, 8, 16), [0, 0, 0, 0]);
+        assert_eq!(rgba8(&pixmap, 55, 16), [0, 0, 0, 0]);
+
+        let pixmap = draw(
+            Some(BorderRadius::all(6.)),
+            Border::new(
+                Some(BorderSide::new(2., red)),
+                None,
+                None,
+                Some(BorderSide::new(6., green)),
+            ),
+        );
+
+        // The wider side takes most of the rounded corner they share.
+        assert_eq!(rgba8(&pixmap, 32, 8), [255, 0, 0, 255]);
+        assert_eq!(rgba8(&pixmap, 8, 16), [0, 255, 0, 255]);
+        assert_eq!(rgba8(&pixmap, 9, 9), [0, 255, 0, 255]);
+        assert_eq!(rgba8(&pixmap, 20, 16), [0, 0, 0, 0]);
+        assert_eq!(rgba8(&pixmap, 55, 16), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_shadow_fades_out_over_its_blur_radius() {
+        let mut pixmap = tiny_skia::Pixmap::new(64, 64).unwrap();
+
+        draw_commands(
+            &mut pixmap.as_mut(),
+            &mut SwashCache::new(),
+            &[RenderCommand::Shadow {
+                boundary: Rect::new(16., 16., 32., 32.),
+                border_radius: Some(BorderRadius::all(4.)),
+                blur_radius: 8.,
+                color: ColorRgba::from_hex(0xFF000000),
+            }],
+            tiny_skia::Transform::identity(),
+            None,
+            &mut FontResources::new(),
+            &mut TextsResources::new(),
+            &Assets::new(),
+        );
+
+        let alpha = |x: u32, y: u32| rgba8(&pixmap, x, y)[3];
+
+        // Opaque inside, half way on the edge and fading out past it.
+        assert!(alpha(32, 32) >= 253);
+        assert!((100..=160).contains(&alpha(16, 32)));
+        assert!(alpha(10, 32) > alpha(4, 32) && alpha(10, 32) < alpha(16, 32));
+        assert!(alpha(2, 32) <= 2);
+        assert_eq!(rgba8(&pixmap, 32, 32)[..3], [0, 0, 0]);
+    }
+}
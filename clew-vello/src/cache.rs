@@ -0,0 +1,238 @@
+use std::{
+    collections::HashMap,
+    hash::{DefaultHasher, Hash, Hasher},
+    sync::Arc,
+};
+
+use clew::assets::Bitmap;
+use cosmic_text::FontSystem;
+use vello::peniko::{Blob, FontData, ImageAlphaType, ImageData, ImageFormat};
+
+/// Font files the glyphs are drawn with. The files are kept by their content, so a
+/// file registered under several fontdb IDs, e.g. the faces of a collection or a font
+/// loaded twice, is kept once.
+pub(crate) struct FontCache {
+    fonts: HashMap<cosmic_text::fontdb::ID, (u64, FontData)>,
+    files: HashMap<u64, CachedFile>,
+}
+
+struct CachedFile {
+    blob: Blob<u8>,
+    bytes: usize,
+    last_used: u64,
+}
+
+impl FontCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            fonts: HashMap::new(),
+            files: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn get_or_insert(
+        &mut self,
+        font_id: cosmic_text::fontdb::ID,
+        font_system: &mut FontSystem,
+        frame: u64,
+    ) -> Option<&FontData> {
+        if !self.fonts.contains_key(&font_id) {
+            let font = font_system.get_font(font_id)?;
+            let index = font_system.db().face(font_id).map_or(0, |face| face.index);
+            let data = font.data();
+            let hash = content_hash(data);
+            let file = self.files.entry(hash).or_insert_with(|| CachedFile {
+                blob: Blob::new(Arc::new(data.to_vec())),
+                bytes: data.len(),
+                last_used: frame,
+            });
+
+            self.fonts
+                .insert(font_id, (hash, FontData::new(file.blob.clone(), index)));
+        }
+
+        let (hash, font) = self.fonts.get(&font_id)?;
+
+        if let Some(file) = self.files.get_mut(hash) {
+            file.last_used = frame;
+        }
+
+        Some(font)
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.files.values().map(|file| file.bytes).sum()
+    }
+
+    /// Files not used in the `frame`, with the frame they were last used in.
+    fn unused(&self, frame: u64) -> impl Iterator<Item = (u64, usize, CacheKey)> + '_ {
+        self.files
+            .iter()
+            .filter(move |(_, file)| file.last_used < frame)
+            .map(|(hash, file)| (file.last_used, file.bytes, CacheKey::Font(*hash)))
+    }
+
+    fn remove(&mut self, hash: u64) {
+        self.files.remove(&hash);
+        self.fonts.retain(|_, (file, _)| *file != hash);
+    }
+}
+
+fn content_hash(data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    data.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// Image data of the bitmaps, kept so the atlas of vello finds the images it has
+/// uploaded by their blobs rather than uploading them every frame.
+pub(crate) struct ImageCache {
+    pub(crate) cache: HashMap<Arc<str>, CachedImage>,
+}
+
+pub(crate) struct CachedImage {
+    pixels: Arc<[u8]>,
+    image: ImageData,
+    last_used: u64,
+}
+
+impl ImageCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            cache: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn get_or_insert(
+        &mut self,
+        key: &Arc<str>,
+        bitmap: &Bitmap,
+        frame: u64,
+    ) -> &ImageData {
+        let stale = self
+            .cache
+            .get(key)
+            .is_none_or(|cached| !Arc::ptr_eq(&cached.pixels, bitmap.pixels()));
+
+        if stale {
+            let image = ImageData {
+                data: Blob::new(Arc::new(bitmap.pixels().clone())),
+                format: ImageFormat::Rgba8,
+                alpha_type: ImageAlphaType::Alpha,
+                width: bitmap.width(),
+                height: bitmap.height(),
+            };
+
+            self.cache.insert(
+                key.clone(),
+                CachedImage {
+                    pixels: bitmap.pixels().clone(),
+                    image,
+                    last_used: frame,
+                },
+            );
+        }
+
+        let cached = self.cache.get_mut(key).unwrap();
+        cached.last_used = frame;
+
+        &cached.image
+    }
+
+    pub(crate) fn bytes(&self) -> usize {
+        self.cache.values().map(|cached| cached.pixels.len()).sum()
+    }
+
+    fn unused(&self, frame: u64) -> impl Iterator<Item = (u64, usize, CacheKey)> + '_ {
+        self.cache
+            .iter()
+            .filter(move |(_, cached)| cached.last_used < frame)
+            .map(|(key, cached)| {
+                (
+                    cached.last_used,
+                    cached.pixels.len(),
+                    CacheKey::Image(key.clone()),
+                )
+            })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CacheKey {
+    Font(u64),
+    Image(Arc<str>),
+}
+
+/// Evicts the least recently used fonts and images until the caches fit into the
+/// `budget` along with the `other_bytes` of the caches that can't be evicted. What has
+/// been used in the `frame` is kept even over the budget. Returns the number of
+/// evicted entries.
+pub(crate) fn evict_over_budget(
+    fonts: &mut FontCache,
+    images: &mut ImageCache,
+    other_bytes: usize,
+    budget: usize,
+    frame: u64,
+) -> u32 {
+    let total = fonts.bytes() + images.bytes() + other_bytes;
+
+    if total <= budget {
+        return 0;
+    }
+
+    let candidates = fonts.unused(frame).chain(images.unused(frame)).collect();
+    let evicted = least_recently_used(candidates, total - budget);
+
+    for key in &evicted {
+        match key {
+            CacheKey::Font(hash) => fonts.remove(*hash),
+            CacheKey::Image(key) => {
+                images.cache.remove(key);
+            }
+        }
+    }
+
+    evicted.len() as u32
+}
+
+/// Evicts everything not used in the `frame`, e.g. before an allocation that has failed
+/// is tried again.
+pub(crate) fn evict_unused(fonts: &mut FontCache, images: &mut ImageCache, frame: u64) -> u32 {
+    evict_over_budget(fonts, images, 0, 0, frame)
+}
+
+/// Keys of the oldest entries that add up to at least `excess` bytes, or all of them.
+fn least_recently_used<K>(mut candidates: Vec<(u64, usize, K)>, excess: usize) -> Vec<K> {
+    candidates.sort_by_key(|(last_used, ..)| *last_used);
+
+    let mut freed = 0;
+
+    candidates
+        .into_iter()
+        .take_while(|(_, bytes, _)| {
+            let take = freed < excess;
+            freed += bytes;
+
+            take
+        })
+        .map(|(.., key)| key)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_least_recently_used_are_evicted_first() {
+        let candidates = vec![(5, 100, 'a'), (1, 100, 'b'), (3, 100, 'c')];
+
+        assert_eq!(
+            least_recently_used(candidates.clone(), 0),
+            Vec::<char>::new()
+        );
+        assert_eq!(least_recently_used(candidates.clone(), 150), vec!['b', 'c']);
+        assert_eq!(least_recently_used(candidates, 1000), vec!['b', 'c', 'a']);
+    }
+}
@@ -0,0 +1,219 @@
+//! Rectangular clips carried as bounds instead of clip layers.
+//!
+//! Every clip layer is composited separately, which adds up with deeply nested scroll
+//! areas. An axis aligned rect clip doesn't need a layer as long as nothing drawn under
+//! it crosses its edges: the commands outside of it are skipped and the ones inside of
+//! it are drawn as is. The layer is pushed only when a command straddles the edge, and
+//! then stays until the clip is popped, so there are never more layers than without
+//! carrying.
+
+use clew::Rect;
+
+/// What has to be done to draw a command with the given bounds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum Visibility {
+    /// The command is entirely outside of the clip.
+    Hidden,
+    Visible,
+    /// The command crosses the edge of a carried clip, a clip layer with the rect has to
+    /// be pushed before the command is drawn.
+    NeedsLayer(Rect),
+}
+
+struct ClipEntry {
+    /// Intersection with the enclosing clips of the same scene.
+    bounds: Rect,
+    /// The content is clipped to the bounds by a pushed layer.
+    layer: bool,
+}
+
+pub(crate) struct ClipStack {
+    entries: Vec<ClipEntry>,
+    /// First entry of the current scene, the bounds of the entries below it are in the
+    /// coordinates of an enclosing scene.
+    base: usize,
+    carry_rects: bool,
+}
+
+impl ClipStack {
+    pub(crate) fn new(carry_rects: bool) -> Self {
+        Self {
+            entries: Vec::new(),
+            base: 0,
+            carry_rects,
+        }
+    }
+
+    pub(crate) fn set_carry_rects(&mut self, carry_rects: bool) {
+        self.carry_rects = carry_rects;
+    }
+
+    pub(crate) fn clear(&mut self) {
+        self.entries.clear();
+        self.base = 0;
+    }
+
+    fn top(&self) -> Option<&ClipEntry> {
+        self.entries[self.base..].last()
+    }
+
+    fn clipped(&self, rect: Rect) -> Rect {
+        self.top()
+            .map_or(rect, |parent| parent.bounds.intersect(rect))
+    }
+
+    /// Pushes a rect clip, returns the rect of the clip layer to push if it can't be
+    /// carried.
+    pub(crate) fn push_rect(&mut self, rect: Rect) -> Option<Rect> {
+        let bounds = self.clipped(rect);
+        let layer = !self.carry_rects;
+
+        self.entries.push(ClipEntry { bounds, layer });
+
+        layer.then_some(rect)
+    }
+
+    /// Pushes a clip with a shape other than a rect, which always gets its own layer.
+    /// Returns the rect of the clip layer to push for the enclosing carried clip first,
+    /// when the shape crosses its edge.
+    pub(crate) fn push_shape(&mut self, rect: Rect) -> Option<Rect> {
+        let enclosing = self.materialize_if(|bounds| !bounds.contains_rect(rect));
+        let bounds = self.clipped(rect);
+
+        self.entries.push(ClipEntry {
+            bounds,
+            layer: true,
+        });
+
+        enclosing
+    }
+
+    /// Returns true if a layer has been pushed for the clip and has to be popped.
+    pub(crate) fn pop(&mut self) -> bool {
+        if self.entries.len() <= self.base {
+            return false;
+        }
+
+        self.entries.pop().is_some_and(|entry| entry.layer)
+    }
+
+    pub(crate) fn visibility(&mut self, bounds: Rect) -> Visibility {
+        if !self.carry_rects {
+            return Visibility::Visible;
+        }
+
+        let Some(top) = self.top() else {
+            return Visibility::Visible;
+        };
+
+        let overlap = top.bounds.intersect(bounds);
+
+        if overlap.width <= 0. || overlap.height <= 0. {
+            return Visibility::Hidden;
+        }
+
+        match self.materialize_if(|clip| !clip.contains_rect(bounds)) {
+            Some(rect) => Visibility::NeedsLayer(rect),
+            None => Visibility::Visible,
+        }
+    }
+
+    /// Starts a scene that is appended to the current one, e.g. through a transform.
+    /// Its content can't be checked against the bounds of the current scene, so the
+    /// carried clip becomes a layer. Returns the rect of the layer to push before the
+    /// scene starts and the base to restore with [`ClipStack::leave_scene`].
+    pub(crate) fn enter_scene(&mut self) -> (Option<Rect>, usize) {
+        let layer = self.materialize_if(|_| true);
+        let base = std::mem::replace(&mut self.base, self.entries.len());
+
+        (layer, base)
+    }
+
+    pub(crate) fn leave_scene(&mut self, base: usize) {
+        self.entries.truncate(self.base);
+        self.base = base;
+    }
+
+    /// Marks the innermost clip as pushed as a layer if it's carried and `condition`
+    /// holds for its bounds, returns the rect of the layer to push.
+    fn materialize_if(&mut self, condition: impl FnOnce(Rect) -> bool) -> Option<Rect> {
+        let top = self.entries[self.base..].last_mut()?;
+
+        if top.layer || !condition(top.bounds) {
+            return None;
+        }
+
+        top.layer = true;
+
+        Some(top.bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nested_rect_clips_get_a_layer_only_when_crossed() {
+        let mut clips = ClipStack::new(true);
+
+        assert_eq!(clips.push_rect(Rect::new(0., 0., 100., 100.)), None);
+        assert_eq!(clips.push_rect(Rect::new(50., 0., 100., 50.)), None);
+
+        assert_eq!(
+            clips.visibility(Rect::new(60., 10., 10., 10.)),
+            Visibility::Visible
+        );
+        assert_eq!(
+            clips.visibility(Rect::new(0., 10., 10., 10.)),
+            Visibility::Hidden
+        );
+
+        // The layer gets the bounds of both clips and is pushed once.
+        assert_eq!(
+            clips.visibility(Rect::new(90., 40., 20., 20.)),
+            Visibility::NeedsLayer(Rect::new(50., 0., 50., 50.))
+        );
+        assert_eq!(
+            clips.visibility(Rect::new(90., 40., 20., 20.)),
+            Visibility::Visible
+        );
+
+        assert!(clips.pop());
+        assert!(!clips.pop());
+    }
+
+    #[test]
+    fn test_clips_of_an_enclosing_scene_become_layers() {
+        let mut clips = ClipStack::new(true);
+
+        clips.push_rect(Rect::new(0., 0., 100., 100.));
+
+        let (layer, base) = clips.enter_scene();
+        assert_eq!(layer, Some(Rect::new(0., 0., 100., 100.)));
+
+        // Inside of the scene nothing is culled by the enclosing clip.
+        assert_eq!(
+            clips.visibility(Rect::new(200., 200., 10., 10.)),
+            Visibility::Visible
+        );
+
+        clips.leave_scene(base);
+        assert!(clips.pop());
+    }
+
+    #[test]
+    fn test_disabled_carrying_pushes_a_layer_per_clip() {
+        let mut clips = ClipStack::new(false);
+
+        assert_eq!(
+            clips.push_rect(Rect::new(0., 0., 10., 10.)),
+            Some(Rect::new(0., 0., 10., 10.))
+        );
+        assert_eq!(
+            clips.visibility(Rect::new(20., 20., 10., 10.)),
+            Visibility::Visible
+        );
+        assert!(clips.pop());
+    }
+}
@@ -1,74 +1,111 @@
 use clew::{
-    Border, BorderRadius, BorderSide, ClipShape, ColorRgb, ColorRgba, Gradient, Rect, View,
-    assets::Assets,
-    render::{Fill, RenderCommand, RenderState, Renderer},
-    text::{FontResources, TextsResources},
+    Border, BorderRadius, BorderSide, ClewError, ClipShape, ColorRgb, ColorRgba, Gradient,
+    ImageFit, PathSegment, Rect, TintMode, Transform, View, WidgetId,
+    assets::{Assets, MISSING_ASSET_COLOR},
+    render::{Fill, FrameStats, RenderCommand, RenderState, Renderer, RgbaImage, is_run_visible},
+    text::{FontResources, Text, TextsResources, is_visible_cluster},
 };
 use cosmic_text::{Buffer, FontSystem};
 use raw_window_handle::{HasDisplayHandle, HasWindowHandle};
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    num::NonZeroU32,
+    ops::Range,
+    sync::{Arc, mpsc},
+};
 use vello::{
     AaConfig, Glyph, RenderParams, RendererOptions, Scene,
-    kurbo::{Affine, RoundedRect, RoundedRectRadii, Stroke},
+    kurbo::{Affine, BezPath, RoundedRect, RoundedRectRadii, Stroke},
     peniko::{
-        self, Blob, Brush, Color, Fill as VelloFill, FontData, Gradient as VelloGradient, StyleRef,
+        self, Brush, Color, Fill as VelloFill, Gradient as VelloGradient, ImageBrush, ImageSampler,
+        StyleRef,
     },
     util::RenderContext,
-    wgpu,
 };
 use vello_svg::usvg;
 
-/// Cache for FontData to avoid repeated allocations
-struct FontCache {
-    cache: HashMap<cosmic_text::fontdb::ID, FontData>,
-}
-
-impl FontCache {
-    fn new() -> Self {
-        Self {
-            cache: HashMap::new(),
-        }
-    }
+use crate::cache::{FontCache, ImageCache, evict_over_budget, evict_unused};
+use crate::clip::{ClipStack, Visibility};
 
-    fn get_or_insert(
-        &mut self,
-        font_id: cosmic_text::fontdb::ID,
-        font_system: &mut FontSystem,
-    ) -> Option<&FontData> {
-        if let std::collections::hash_map::Entry::Vacant(e) = self.cache.entry(font_id)
-            && let Some(font) = font_system.get_font(font_id)
-        {
-            let font_data = FontData::new(Blob::new(Arc::new(font.data().to_vec())), 0);
-            e.insert(font_data);
-        }
+mod cache;
+mod clip;
 
-        self.cache.get(&font_id)
-    }
+/// The wgpu the renderer is built with, the device and the target given to
+/// [`VelloRenderer::with_device`] have to come from it.
+pub use vello::wgpu;
 
-    fn _clear(&mut self) {
-        self.cache.clear();
-    }
-}
+const DEFAULT_FRAME_LATENCY: u32 = 3;
+const DEFAULT_CACHE_BUDGET: usize = 256 * 1024 * 1024;
+/// Approximate bytes a command takes in the encoding of a recorded cache layer.
+const LAYER_COMMAND_BYTES: usize = 256;
 
-impl Default for FontCache {
-    fn default() -> Self {
-        Self::new()
-    }
+/// Recorded commands of a cache layer.
+struct CachedLayer {
+    version: u64,
+    scene: Scene,
+    bytes: usize,
 }
 
 pub struct VelloRenderer {
     render_cx: RenderContext,
     surface: Option<vello::util::RenderSurface<'static>>,
+    /// Device the scenes are rendered with, the one of the surface if there is one.
+    dev_id: usize,
+    /// Device of the application the view is embedded into, used instead of the ones
+    /// of the render context, see [`VelloRenderer::with_device`].
+    host_device: Option<HostDevice>,
+    /// Texture the frames are drawn into when there is no surface.
+    target: Option<wgpu::TextureView>,
     renderer: Option<vello::Renderer>,
+    /// Set up of the device on a background thread, see [`VelloRenderer::warming_up`].
+    warm_up: Option<mpsc::Receiver<Result<WarmedUp, ClewError>>>,
+    /// Fills the window until the device is set up, see [`Renderer::present_fill`].
+    warm_up_fill: Option<FillFallback>,
     scene: Scene,
+    /// Enclosing scenes of the open transforms, the current scene is appended to the
+    /// enclosing one through the transform when the transform is popped.
+    transform_stack: Vec<(Scene, Affine, usize)>,
+    /// Enclosing scenes of the cache layers being recorded, with the number of the
+    /// commands drawn before them.
+    layer_stack: Vec<(WidgetId, u64, Scene, usize, usize)>,
+    clip_stack: ClipStack,
+    layer_cache: HashMap<WidgetId, CachedLayer>,
+    seen_layers: HashSet<WidgetId>,
+    frame_stats: FrameStats,
     font_cache: FontCache,
+    image_cache: ImageCache,
+    cache_budget: usize,
+    /// Frames begun so far, the caches tell the recently used entries by it.
+    frame: u64,
+    /// Commands drawn so far in the frame, the size of the layers is estimated by it.
+    drawn_commands: usize,
+    frame_latency: u32,
+    /// Whether the frames are cleared to transparent, see
+    /// [`Renderer::set_transparent_background`].
+    transparent_background: bool,
 
     current_width: u32,
     current_height: u32,
 }
 
+struct HostDevice {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+}
+
+/// Fills a window of the given size with the color on the CPU.
+type FillFallback = Box<dyn FnMut(u32, u32, &ColorRgb)>;
+
+/// Device, surface and renderer of a window, set up in the background by
+/// [`VelloRenderer::warming_up`].
+struct WarmedUp {
+    render_cx: RenderContext,
+    surface: vello::util::RenderSurface<'static>,
+    renderer: vello::Renderer,
+}
+
 impl VelloRenderer {
-    pub async fn new<W>(window: Arc<W>, width: u32, height: u32) -> Self
+    pub async fn new<W>(window: Arc<W>, width: u32, height: u32) -> Result<Self, ClewError>
     where
         W: HasWindowHandle + HasDisplayHandle + Send + Sync + 'static,
     {
@@ -78,40 +115,281 @@ impl VelloRenderer {
         let surface = render_cx
             .create_surface(window.clone(), width, height, wgpu::PresentMode::Fifo)
             .await
-            .expect("Failed to create surface");
-
-        #[cfg(target_os = "macos")]
-        #[allow(invalid_reference_casting)]
-        unsafe {
-            if let Some(hal_surface) = surface.surface.as_hal::<wgpu::hal::api::Metal>() {
-                let raw = (&*hal_surface) as *const wgpu::hal::metal::Surface
-                    as *mut wgpu::hal::metal::Surface;
-                (*raw).present_with_transaction = true;
-            }
-        }
+            .map_err(|err| ClewError::Surface(err.to_string()))?;
+
+        let ready = finish_setup(render_cx, surface)?;
+        let dev_id = ready.surface.dev_id;
+
+        Ok(Self::from_parts(
+            ready.render_cx,
+            Some(ready.surface),
+            dev_id,
+            Some(ready.renderer),
+            width,
+            height,
+        ))
+    }
+
+    /// Renderer that sets up the device and compiles the shaders on a background thread
+    /// and returns right away, so the event loop doesn't stall on them. The frames are
+    /// skipped until it's ready, see [`Renderer::poll_ready`], the window is filled with
+    /// the fill color by the CPU meanwhile and the view is drawn at the size it has by
+    /// then. `on_ready` is called from the background thread once the setup is over,
+    /// e.g. to request a redraw of the window.
+    ///
+    /// ```ignore
+    /// fn create_renderer(window: Arc<Window>) -> Result<Box<dyn Renderer>, ClewError> {
+    ///     let size = window.inner_size();
+    ///     let redraw = window.clone();
+    ///
+    ///     Ok(Box::new(VelloRenderer::warming_up(
+    ///         window,
+    ///         size.width,
+    ///         size.height,
+    ///         move || redraw.request_redraw(),
+    ///     )?))
+    /// }
+    /// ```
+    pub fn warming_up<W>(
+        window: Arc<W>,
+        width: u32,
+        height: u32,
+        on_ready: impl FnOnce() + Send + 'static,
+    ) -> Result<Self, ClewError>
+    where
+        W: HasWindowHandle + HasDisplayHandle + Send + Sync + 'static,
+    {
+        let mut render_cx = RenderContext::new();
+        // Some platforms only allow the surface of a window on its thread, it's cheap to
+        // create, unlike the device.
+        let surface = render_cx
+            .instance
+            .create_surface(window.clone())
+            .map_err(|err| ClewError::Surface(err.to_string()))?;
+        // After the surface, which may replace the layer of the view the fill is drawn
+        // into on macOS.
+        let fill = fill_fallback(window);
+        let (sender, receiver) = mpsc::channel();
+
+        std::thread::Builder::new()
+            .name("clew-vello-setup".to_string())
+            .spawn(move || {
+                let ready = block_on(render_cx.create_render_surface(
+                    surface,
+                    width,
+                    height,
+                    wgpu::PresentMode::Fifo,
+                ))
+                .map_err(|err| ClewError::Surface(err.to_string()))
+                .and_then(|surface| finish_setup(render_cx, surface));
+
+                let _ = sender.send(ready);
+                on_ready();
+            })
+            .map_err(|err| ClewError::Renderer(err.to_string()))?;
+
+        Ok(Self::waiting_for(receiver, fill, width, height))
+    }
+
+    /// Renderer that isn't ready until the setup is received from the background thread.
+    fn waiting_for(
+        warm_up: mpsc::Receiver<Result<WarmedUp, ClewError>>,
+        fill: Option<FillFallback>,
+        width: u32,
+        height: u32,
+    ) -> Self {
+        // Stands in for the context of the background thread until it's ready.
+        let mut renderer = Self::from_parts(RenderContext::new(), None, 0, None, width, height);
+        renderer.warm_up = Some(warm_up);
+        renderer.warm_up_fill = fill;
+
+        renderer
+    }
 
-        let device = &render_cx.devices[surface.dev_id].device;
+    /// Renderer without a window, it only draws offscreen with
+    /// [`Renderer::render_image`], e.g. to render screenshots of the views.
+    pub async fn headless() -> Result<Self, ClewError> {
+        let mut render_cx = RenderContext::new();
+        let dev_id = render_cx
+            .device(None)
+            .await
+            .ok_or_else(|| ClewError::Renderer("No compatible GPU device".to_string()))?;
+        let device = &render_cx.devices[dev_id].device;
 
-        // Create Vello renderer
         let renderer = vello::Renderer::new(device, RendererOptions::default())
-            .expect("Failed to create Vello renderer");
+            .map_err(|err| ClewError::Renderer(err.to_string()))?;
+
+        Ok(Self::from_parts(
+            render_cx,
+            None,
+            dev_id,
+            Some(renderer),
+            0,
+            0,
+        ))
+    }
+
+    /// Renderer that draws with the device of the application the view is embedded
+    /// into, rather than setting up its own. It has no surface, the frames are drawn into
+    /// the texture set with [`VelloRenderer::set_target`] and the application presents
+    /// them, e.g. blitted into its own surface or composited with its scene.
+    pub fn with_device(device: wgpu::Device, queue: wgpu::Queue) -> Result<Self, ClewError> {
+        let renderer = vello::Renderer::new(&device, RendererOptions::default())
+            .map_err(|err| ClewError::Renderer(err.to_string()))?;
+        let mut this = Self::from_parts(RenderContext::new(), None, 0, Some(renderer), 0, 0);
+        this.host_device = Some(HostDevice { device, queue });
+
+        Ok(this)
+    }
 
-        let mut config = surface.config.clone();
-        config.desired_maximum_frame_latency = 3;
-        surface.surface.configure(device, &config);
+    /// Texture the frames are drawn into when the renderer has no surface, of the size
+    /// of the view. Vello writes it from a compute shader, it has to be created with the
+    /// `Rgba8Unorm` format and the `STORAGE_BINDING` usage.
+    pub fn set_target(&mut self, target: Option<wgpu::TextureView>) {
+        self.target = target;
+    }
 
+    fn from_parts(
+        render_cx: RenderContext,
+        surface: Option<vello::util::RenderSurface<'static>>,
+        dev_id: usize,
+        renderer: Option<vello::Renderer>,
+        width: u32,
+        height: u32,
+    ) -> Self {
         Self {
             render_cx,
-            surface: Some(surface),
-            renderer: Some(renderer),
+            surface,
+            dev_id,
+            host_device: None,
+            target: None,
+            renderer,
+            warm_up: None,
+            warm_up_fill: None,
             scene: Scene::new(),
+            transform_stack: Vec::new(),
+            layer_stack: Vec::new(),
+            clip_stack: ClipStack::new(true),
+            layer_cache: HashMap::new(),
+            seen_layers: HashSet::new(),
+            frame_stats: FrameStats::default(),
             font_cache: FontCache::new(),
+            image_cache: ImageCache::new(),
+            cache_budget: DEFAULT_CACHE_BUDGET,
+            frame: 0,
+            drawn_commands: 0,
+            frame_latency: DEFAULT_FRAME_LATENCY,
+            transparent_background: false,
 
             current_width: width,
             current_height: height,
         }
     }
 
+    /// Takes over what the background thread has set up, the surface is resized to the
+    /// size the renderer has been resized to meanwhile.
+    fn install(&mut self, ready: WarmedUp) {
+        let (width, height) = (self.current_width, self.current_height);
+
+        // Dropped before the first frame, so its layer doesn't cover the surface.
+        self.warm_up_fill = None;
+        self.current_width = ready.surface.config.width;
+        self.current_height = ready.surface.config.height;
+        self.dev_id = ready.surface.dev_id;
+        self.render_cx = ready.render_cx;
+        self.surface = Some(ready.surface);
+        self.renderer = Some(ready.renderer);
+
+        self.set_frame_latency(self.frame_latency);
+
+        if self.transparent_background && !self.configure_alpha_mode() {
+            log::warn!("The surface can't be composited with alpha, drawn opaque instead");
+            self.transparent_background = false;
+        }
+
+        self.resize(width, height);
+    }
+
+    /// Configures the surface to be composited with the alpha of the frames when the
+    /// background is transparent, returns false when the surface doesn't support it.
+    /// Vello stores the colors with straight alpha, the premultiplied compositing is
+    /// only the fallback.
+    fn configure_alpha_mode(&mut self) -> bool {
+        let Some(surface) = &mut self.surface else {
+            return false;
+        };
+        let device = &self.render_cx.devices[surface.dev_id];
+        let alpha_modes = surface
+            .surface
+            .get_capabilities(device.adapter())
+            .alpha_modes;
+
+        let alpha_mode = if !self.transparent_background {
+            wgpu::CompositeAlphaMode::Auto
+        } else if let Some(mode) = [
+            wgpu::CompositeAlphaMode::PostMultiplied,
+            wgpu::CompositeAlphaMode::PreMultiplied,
+        ]
+        .into_iter()
+        .find(|mode| alpha_modes.contains(mode))
+        {
+            mode
+        } else {
+            return false;
+        };
+
+        surface.config.alpha_mode = alpha_mode;
+        surface.surface.configure(&device.device, &surface.config);
+
+        true
+    }
+
+    /// Frames the GPU may queue ahead of the one on the screen, 3 by default. Fewer
+    /// frames bring what is drawn closer to the input, e.g. a dragged widget to the
+    /// cursor, at the cost of stalls when a frame takes long.
+    pub fn with_frame_latency(mut self, frames: u32) -> Self {
+        self.set_frame_latency(frames);
+
+        self
+    }
+
+    pub fn set_frame_latency(&mut self, frames: u32) {
+        self.frame_latency = frames.max(1);
+
+        if let Some(surface) = &mut self.surface {
+            let device = &self.render_cx.devices[surface.dev_id].device;
+
+            surface.config.desired_maximum_frame_latency = self.frame_latency;
+            surface.surface.configure(device, &surface.config);
+        }
+    }
+
+    /// Rect clips are carried as bounds and get a clip layer only when something drawn
+    /// under them crosses their edges, enabled by default. Disabling it pushes a layer
+    /// for every clip, which helps to tell whether a clipping issue comes from carrying.
+    pub fn with_rect_clip_carrying(mut self, enabled: bool) -> Self {
+        self.set_rect_clip_carrying(enabled);
+
+        self
+    }
+
+    pub fn set_rect_clip_carrying(&mut self, enabled: bool) {
+        self.clip_stack.set_carry_rects(enabled);
+    }
+
+    /// Approximate bytes the caches of fonts, images and layers may take, 256 MiB by
+    /// default. Over the budget the least recently used fonts and images are evicted
+    /// after the frame, the ones drawn in the frame are kept even if they don't fit.
+    pub fn with_cache_budget(mut self, bytes: usize) -> Self {
+        self.set_cache_budget(bytes);
+
+        self
+    }
+
+    pub fn set_cache_budget(&mut self, bytes: usize) {
+        self.cache_budget = bytes;
+    }
+
     /// Resize the renderer surface
     pub fn resize(&mut self, width: u32, height: u32) {
         if width == 0 || height == 0 {
@@ -133,13 +411,124 @@ impl VelloRenderer {
     /// Begin a new frame
     pub fn begin_frame(&mut self) {
         self.scene.reset();
+        self.transform_stack.clear();
+        self.layer_stack.clear();
+        self.clip_stack.clear();
+        self.seen_layers.clear();
+        self.frame_stats = FrameStats::default();
+        self.frame += 1;
+        self.drawn_commands = 0;
+    }
+
+    fn push_transform(&mut self, transform: Transform) {
+        let translation = transform.translation;
+        let scale = transform.scale;
+        let affine = Affine::translate((translation.x as f64, translation.y as f64))
+            * Affine::scale_non_uniform(scale.x as f64, scale.y as f64);
+        let clip_base = self.enter_clip_scene();
+        let outer = std::mem::replace(&mut self.scene, Scene::new());
+
+        self.transform_stack.push((outer, affine, clip_base));
+    }
+
+    fn pop_transform(&mut self) {
+        if let Some((outer, affine, clip_base)) = self.transform_stack.pop() {
+            let inner = std::mem::replace(&mut self.scene, outer);
+
+            self.scene.append(&inner, Some(affine));
+            self.clip_stack.leave_scene(clip_base);
+        }
+    }
+
+    /// Returns true when the layer has been drawn from the cache and its commands
+    /// should be skipped, otherwise starts recording it.
+    fn push_layer(&mut self, id: WidgetId, version: u64) -> bool {
+        self.seen_layers.insert(id);
+
+        // Cached scenes are replayed in the next frames with other clips, so nothing in
+        // them may be culled by the current ones.
+        let clip_base = self.enter_clip_scene();
+
+        if let Some(cached) = self.layer_cache.get(&id)
+            && cached.version == version
+        {
+            self.scene.append(&cached.scene, None);
+            self.frame_stats.layer_cache_hits += 1;
+            self.clip_stack.leave_scene(clip_base);
+
+            return true;
+        }
+
+        self.frame_stats.layer_cache_misses += 1;
+        let outer = std::mem::replace(&mut self.scene, Scene::new());
+        self.layer_stack
+            .push((id, version, outer, clip_base, self.drawn_commands));
+
+        false
+    }
+
+    fn pop_layer(&mut self) {
+        if let Some((id, version, outer, clip_base, drawn_before)) = self.layer_stack.pop() {
+            let scene = std::mem::replace(&mut self.scene, outer);
+            let bytes = (self.drawn_commands - drawn_before) * LAYER_COMMAND_BYTES;
+
+            self.scene.append(&scene, None);
+            self.layer_cache.insert(
+                id,
+                CachedLayer {
+                    version,
+                    scene,
+                    bytes,
+                },
+            );
+            self.clip_stack.leave_scene(clip_base);
+        }
+    }
+
+    /// Pushes the layer of the carried clip before the commands go to another scene,
+    /// returns the clip stack base to restore when the scene ends.
+    fn enter_clip_scene(&mut self) -> usize {
+        let (layer, base) = self.clip_stack.enter_scene();
+
+        if let Some(rect) = layer {
+            self.push_clip_rect(rect);
+        }
+
+        base
+    }
+
+    fn push_clip_rect(&mut self, rect: Rect) {
+        self.scene
+            .push_clip_layer(Affine::IDENTITY, &convert_rect(rect));
+        self.frame_stats.clip_layers += 1;
+    }
+
+    /// Prepares the clip for a command with the given bounds, returns false when the
+    /// command is clipped out entirely and can be skipped.
+    fn clip_command(&mut self, bounds: Rect) -> bool {
+        match self.clip_stack.visibility(bounds) {
+            Visibility::Hidden => {
+                self.frame_stats.culled_commands += 1;
+
+                false
+            }
+            Visibility::Visible => true,
+            Visibility::NeedsLayer(rect) => {
+                self.push_clip_rect(rect);
+
+                true
+            }
+        }
     }
 
     /// End frame and present
     pub fn end_frame(&mut self, fill_color: &ColorRgb) {
         profiling::scope!("end_frame");
 
-        let Some(surface) = &self.surface else { return };
+        let Some(surface) = &self.surface else {
+            self.render_to_target(fill_color);
+            return;
+        };
         let Some(renderer) = &mut self.renderer else {
             return;
         };
@@ -147,8 +536,13 @@ impl VelloRenderer {
         let device = &self.render_cx.devices[surface.dev_id].device;
         let queue = &self.render_cx.devices[surface.dev_id].queue;
 
+        let base_color = if self.transparent_background {
+            Color::TRANSPARENT
+        } else {
+            convert_rgb_color(fill_color)
+        };
         let render_params = RenderParams {
-            base_color: convert_rgb_color(fill_color),
+            base_color,
             width: self.current_width,
             height: self.current_height,
             antialiasing_method: AaConfig::Msaa16,
@@ -156,24 +550,51 @@ impl VelloRenderer {
 
         {
             profiling::scope!("render_to_texture");
-            renderer
-                .render_to_texture(
-                    device,
-                    queue,
-                    &self.scene,
-                    &surface.target_view,
-                    &render_params,
-                )
-                .expect("Failed to render to surface");
+            let render = |renderer: &mut vello::Renderer| {
+                catch_out_of_memory(device, || {
+                    renderer.render_to_texture(
+                        device,
+                        queue,
+                        &self.scene,
+                        &surface.target_view,
+                        &render_params,
+                    )
+                })
+            };
+
+            let mut rendered = render(renderer);
+
+            if let Err(err) = &rendered {
+                log::warn!("Out of GPU memory, evicting the caches: {err}");
+                self.layer_cache.clear();
+                evict_unused(&mut self.font_cache, &mut self.image_cache, self.frame);
+                rendered = render(renderer);
+            }
+
+            match rendered {
+                Ok(Ok(())) => {}
+                Ok(Err(err)) => {
+                    log::error!("Failed to render to surface: {err}");
+                    return;
+                }
+                Err(err) => {
+                    // The frame on the screen stays until the next one fits.
+                    log::error!("Out of GPU memory, the frame is dropped: {err}");
+                    return;
+                }
+            }
         }
 
         let surface_texture = {
             profiling::scope!("get_current_texture");
 
-            surface
-                .surface
-                .get_current_texture()
-                .expect("Failed to get surface texture")
+            match surface.surface.get_current_texture() {
+                Ok(surface_texture) => surface_texture,
+                Err(err) => {
+                    log::error!("Failed to get surface texture: {err}");
+                    return;
+                }
+            }
         };
 
         {
@@ -194,7 +615,9 @@ impl VelloRenderer {
             surface_texture.present();
         }
 
-        device.poll(wgpu::PollType::Poll).unwrap();
+        if let Err(err) = device.poll(wgpu::PollType::Poll) {
+            log::error!("Failed to poll device: {err}");
+        }
 
         // {
         //     profiling::scope!("device_poll");
@@ -202,6 +625,45 @@ impl VelloRenderer {
         // }
     }
 
+    /// Draws the frame into the texture set with [`VelloRenderer::set_target`], the
+    /// application presents it.
+    fn render_to_target(&mut self, fill_color: &ColorRgb) {
+        let (Some(target), Some(renderer)) = (&self.target, &mut self.renderer) else {
+            return;
+        };
+        let (device, queue) = gpu(&self.render_cx, self.dev_id, &self.host_device);
+        let render_params = RenderParams {
+            base_color: if self.transparent_background {
+                Color::TRANSPARENT
+            } else {
+                convert_rgb_color(fill_color)
+            },
+            width: self.current_width,
+            height: self.current_height,
+            antialiasing_method: AaConfig::Msaa16,
+        };
+        let render = |renderer: &mut vello::Renderer| {
+            catch_out_of_memory(device, || {
+                renderer.render_to_texture(device, queue, &self.scene, target, &render_params)
+            })
+        };
+
+        let mut rendered = render(renderer);
+
+        if let Err(err) = &rendered {
+            log::warn!("Out of GPU memory, evicting the caches: {err}");
+            self.layer_cache.clear();
+            evict_unused(&mut self.font_cache, &mut self.image_cache, self.frame);
+            rendered = render(renderer);
+        }
+
+        match rendered {
+            Ok(Ok(())) => {}
+            Ok(Err(err)) => log::error!("Failed to render to the target: {err}"),
+            Err(err) => log::error!("Out of GPU memory, the frame is dropped: {err}"),
+        }
+    }
+
     /// Draw a filled rectangle with optional border
     pub fn draw_rect(
         &mut self,
@@ -209,6 +671,7 @@ impl VelloRenderer {
         fill: Option<&Fill>,
         border_radius: Option<&BorderRadius>,
         border: Option<&Border>,
+        border_fill: Option<&Fill>,
     ) {
         let rect = vello::kurbo::Rect::new(
             boundary.x as f64,
@@ -233,34 +696,70 @@ impl VelloRenderer {
 
         // Draw fill
         if let Some(fill) = fill
-            && let Some(brush) = create_brush_from_fill(fill, boundary)
+            && let Some((brush, brush_transform)) = create_brush_from_fill(fill, boundary)
         {
-            self.scene
-                .fill(VelloFill::NonZero, Affine::IDENTITY, &brush, None, &shape);
+            self.scene.fill(
+                VelloFill::NonZero,
+                Affine::IDENTITY,
+                &brush,
+                brush_transform,
+                &shape,
+            );
         }
 
         // Draw border
         if let Some(border) = border {
-            self.draw_border(&shape, border);
+            self.draw_border(&shape, boundary, border_radius, border, border_fill);
         }
     }
 
     /// Draw border for a shape
-    fn draw_border(&mut self, shape: &RoundedRect, border: &Border) {
-        // Get the maximum border width and color
-        let (max_width, color) = get_border_params(border);
+    fn draw_border(
+        &mut self,
+        shape: &RoundedRect,
+        boundary: Rect,
+        border_radius: Option<&BorderRadius>,
+        border: &Border,
+        border_fill: Option<&Fill>,
+    ) {
+        // The same sides are stroked along the outline at once.
+        if let Some(side) = border.uniform() {
+            if side.width > 0.0
+                && let Some((brush, brush_transform)) =
+                    create_border_brush(border_fill, side.color, boundary)
+            {
+                let stroke = Stroke::new(side.width as f64);
 
-        if max_width > 0.0 {
-            let stroke = Stroke::new(max_width as f64);
-            let brush = Brush::Solid(convert_rgba_color(&color));
+                self.scene
+                    .stroke(&stroke, Affine::IDENTITY, &brush, brush_transform, shape);
+            }
 
-            self.scene
-                .stroke(&stroke, Affine::IDENTITY, &brush, None, shape);
+            return;
+        }
+
+        for piece in border.resolve(boundary, border_radius) {
+            if let Some((brush, brush_transform)) =
+                create_border_brush(border_fill, piece.color, boundary)
+            {
+                self.scene.fill(
+                    VelloFill::EvenOdd,
+                    Affine::IDENTITY,
+                    &brush,
+                    brush_transform,
+                    &create_outline_path(&piece.outline),
+                );
+            }
         }
     }
 
     /// Draw an oval/ellipse with optional border
-    pub fn draw_oval(&mut self, boundary: Rect, fill: Option<&Fill>, border: Option<&BorderSide>) {
+    pub fn draw_oval(
+        &mut self,
+        boundary: Rect,
+        fill: Option<&Fill>,
+        border: Option<&BorderSide>,
+        border_fill: Option<&Fill>,
+    ) {
         let ellipse = vello::kurbo::Ellipse::new(
             (
                 (boundary.x + boundary.width / 2.0) as f64,
@@ -275,21 +774,68 @@ impl VelloRenderer {
 
         // Draw fill
         if let Some(fill) = fill
-            && let Some(brush) = create_brush_from_fill(fill, boundary)
+            && let Some((brush, brush_transform)) = create_brush_from_fill(fill, boundary)
         {
-            self.scene
-                .fill(VelloFill::NonZero, Affine::IDENTITY, &brush, None, &ellipse);
+            self.scene.fill(
+                VelloFill::NonZero,
+                Affine::IDENTITY,
+                &brush,
+                brush_transform,
+                &ellipse,
+            );
         }
 
         // Draw border
         if let Some(border_side) = border
             && border_side.width > 0.0
+            && let Some((brush, brush_transform)) =
+                create_border_brush(border_fill, border_side.color, boundary)
         {
             let stroke = Stroke::new(border_side.width as f64);
-            let brush = Brush::Solid(convert_rgba_color(&border_side.color));
             self.scene
-                .stroke(&stroke, Affine::IDENTITY, &brush, None, &ellipse);
+                .stroke(&stroke, Affine::IDENTITY, &brush, brush_transform, &ellipse);
+        }
+    }
+
+    /// Draw a blurred rounded rect, vello blurs a single radius for all of the corners
+    /// so the largest one is used.
+    pub fn draw_shadow(
+        &mut self,
+        boundary: Rect,
+        border_radius: Option<&BorderRadius>,
+        blur_radius: f32,
+        color: ColorRgba,
+    ) {
+        if blur_radius <= 0. {
+            self.draw_rect(
+                boundary,
+                Some(&Fill::Color(color)),
+                border_radius,
+                None,
+                None,
+            );
+            return;
         }
+
+        let radius = border_radius.map_or(0., |br| {
+            br.top_left
+                .max(br.top_right)
+                .max(br.bottom_right)
+                .max(br.bottom_left)
+        });
+
+        // The blur radius is the one of CSS, twice the standard deviation. Vello's
+        // `std_dev` is the one of Raph Levien's approximation, `erf(d / std_dev)`, that's
+        // the standard deviation times √2.
+        let sigma = blur_radius as f64 / 2.;
+
+        self.scene.draw_blurred_rounded_rect(
+            PIXEL_CENTER,
+            convert_rect(boundary),
+            convert_rgba_color(&color),
+            radius as f64,
+            sigma * std::f64::consts::SQRT_2,
+        );
     }
 
     /// Draw text from a cosmic_text Buffer
@@ -328,7 +874,10 @@ impl VelloRenderer {
 
         // Render each font's glyphs in a batch
         for (font_id, glyphs) in font_glyphs {
-            if let Some(vello_font) = self.font_cache.get_or_insert(font_id, font_system) {
+            if let Some(vello_font) =
+                self.font_cache
+                    .get_or_insert(font_id, font_system, self.frame)
+            {
                 // Assuming uniform font size within a run (common case)
                 let font_size = glyphs.first().map(|(_, s)| *s).unwrap_or(16.0);
                 let glyph_iter = glyphs.into_iter().map(|(g, _)| g);
@@ -342,208 +891,323 @@ impl VelloRenderer {
         }
     }
 
-    /// Draw an SVG asset
-    pub fn draw_svg(&mut self, tree: &usvg::Tree, boundary: Rect, tint_color: Option<ColorRgba>) {
+    /// Draw an SVG asset, tinted with the tint in its mode.
+    pub fn draw_svg(
+        &mut self,
+        tree: &usvg::Tree,
+        boundary: Rect,
+        tint: Option<(ColorRgba, TintMode)>,
+    ) {
         let sx = boundary.width / tree.size().width();
         let sy = boundary.height / tree.size().height();
 
-        // let transform = Affine::translate((boundary.x as f64, boundary.y as f64))
-        // .then_scale_non_uniform(sx as f64, sy as f64);
-
         let transform = Affine::scale_non_uniform(sx as f64, sy as f64)
             .then_translate((boundary.x as f64, boundary.y as f64).into());
 
-        // Use vello_svg to render the SVG
-        // vello_svg::render_tree(&mut self.scene, tree, transform);
         let svg_scene = vello_svg::render_tree(tree);
 
-        // Note: Tinting would require post-processing or modifying the SVG tree
-        // For now, tint_color is not applied
-        if let Some(tint) = tint_color {
-            // For tinting, we use a layer with SrcIn blend mode
-            // 1. Push a layer to isolate the SVG
-            // 2. Draw the SVG
-            // 3. Draw a rect with the tint color using SrcIn blend
-            // 4. Pop the layer
-
-            let clip_rect = vello::kurbo::Rect::new(
-                boundary.x as f64,
-                boundary.y as f64,
-                (boundary.x + boundary.width) as f64,
-                (boundary.y + boundary.height) as f64,
-            );
-
-            // Push a layer for compositing
-            self.scene.push_layer(
-                peniko::BlendMode::default(),
-                1.0,
-                Affine::IDENTITY,
-                &clip_rect,
-            );
-
-            // Draw the SVG
+        let Some((tint, tint_mode)) = tint else {
             self.scene.append(&svg_scene, Some(transform));
 
-            // Draw tint color with SourceIn blend mode
-            // SourceIn: shows source (tint) only where destination (SVG) has alpha
-            let tint_brush = Brush::Solid(convert_rgba_color(&tint));
-            self.scene.push_layer(
-                peniko::BlendMode::new(peniko::Mix::Normal, peniko::Compose::SrcIn),
-                1.0,
-                Affine::IDENTITY,
-                &clip_rect,
-            );
-            self.scene.fill(
-                VelloFill::NonZero,
-                Affine::IDENTITY,
-                &tint_brush,
-                None,
-                &clip_rect,
-            );
-            self.scene.pop_layer();
+            return;
+        };
 
-            // Pop the outer layer
-            self.scene.pop_layer();
-        } else {
-            self.scene.append(&svg_scene, Some(transform));
+        let clip_rect = vello::kurbo::Rect::new(
+            boundary.x as f64,
+            boundary.y as f64,
+            (boundary.x + boundary.width) as f64,
+            (boundary.y + boundary.height) as f64,
+        );
+
+        match tint_mode {
+            TintMode::Replace => {
+                // The SVG is isolated in a layer and the tint is drawn over it with
+                // SrcIn, so it only shows where the SVG has alpha.
+                self.scene.push_layer(
+                    peniko::BlendMode::default(),
+                    1.0,
+                    Affine::IDENTITY,
+                    &clip_rect,
+                );
+                self.scene.append(&svg_scene, Some(transform));
+                self.scene.push_layer(
+                    peniko::BlendMode::new(peniko::Mix::Normal, peniko::Compose::SrcIn),
+                    1.0,
+                    Affine::IDENTITY,
+                    &clip_rect,
+                );
+                self.scene.fill(
+                    VelloFill::NonZero,
+                    Affine::IDENTITY,
+                    &Brush::Solid(convert_rgba_color(&tint)),
+                    None,
+                    &clip_rect,
+                );
+                self.scene.pop_layer();
+                self.scene.pop_layer();
+            }
+            TintMode::Multiply => {
+                // The SVG is multiplied with the opaque tint under it and cut to its own
+                // alpha with SrcIn. Over an opaque backdrop the product isn't faded with
+                // the alpha of the SVG, the alpha of the tint is the opacity of the layer.
+                self.scene.push_layer(
+                    peniko::BlendMode::default(),
+                    tint.a,
+                    Affine::IDENTITY,
+                    &clip_rect,
+                );
+                self.scene.fill(
+                    VelloFill::NonZero,
+                    Affine::IDENTITY,
+                    &Brush::Solid(convert_rgba_color(&tint.with_opacity(1.))),
+                    None,
+                    &clip_rect,
+                );
+                self.scene.push_layer(
+                    peniko::BlendMode::new(peniko::Mix::Multiply, peniko::Compose::SrcIn),
+                    1.0,
+                    Affine::IDENTITY,
+                    &clip_rect,
+                );
+                self.scene.append(&svg_scene, Some(transform));
+                self.scene.pop_layer();
+                self.scene.pop_layer();
+            }
         }
     }
-}
 
-impl Renderer for VelloRenderer {
-    fn process_commands(
+    /// Draws the commands into the current scene. `default_font_size` is in physical
+    /// pixels.
+    fn draw_commands(
         &mut self,
-        view: &View,
-        state: &RenderState,
-        fill_color: ColorRgb,
+        commands: &[RenderCommand],
+        default_font_size: f32,
         fonts: &mut FontResources,
         text: &mut TextsResources,
         assets: &Assets,
     ) {
-        profiling::scope!("clew :: Vello - Render");
+        // Depth of the cache layers inside of a layer drawn from the cache.
+        let mut skip_depth = 0;
+
+        for command in commands {
+            if skip_depth > 0 {
+                match command {
+                    RenderCommand::PushLayer { id, .. } => {
+                        // Nested layers are in the cached scene, keep them cached.
+                        self.seen_layers.insert(*id);
+                        skip_depth += 1;
+                    }
+                    RenderCommand::PopLayer => skip_depth -= 1,
+                    _ => {}
+                }
 
-        let width = view.size.width;
-        let height = view.size.height;
+                continue;
+            }
 
-        self.resize(width, height);
-        self.begin_frame();
+            self.drawn_commands += 1;
 
-        for command in state.commands() {
             match command {
                 RenderCommand::Rect {
                     boundary,
                     fill,
                     border_radius,
                     border,
-                    ..
+                    border_fill,
                 } => {
-                    self.draw_rect(
-                        *boundary,
-                        fill.as_ref(),
-                        border_radius.as_ref(),
-                        border.as_ref(),
-                    );
+                    let stroke = border.as_ref().map_or(0., Border::max_width);
+
+                    if self.clip_command(boundary.expand(stroke / 2.)) {
+                        self.draw_rect(
+                            *boundary,
+                            fill.as_ref(),
+                            border_radius.as_ref(),
+                            border.as_ref(),
+                            border_fill.as_ref(),
+                        );
+                    }
                 }
                 RenderCommand::Oval {
                     boundary,
                     fill,
                     border,
-                    ..
+                    border_fill,
+                } => {
+                    let stroke = border.as_ref().map_or(0., |border| border.width);
+
+                    if self.clip_command(boundary.expand(stroke / 2.)) {
+                        self.draw_oval(
+                            *boundary,
+                            fill.as_ref(),
+                            border.as_ref(),
+                            border_fill.as_ref(),
+                        );
+                    }
+                }
+                RenderCommand::Shadow {
+                    boundary,
+                    border_radius,
+                    blur_radius,
+                    color,
                 } => {
-                    self.draw_oval(*boundary, fill.as_ref(), border.as_ref());
+                    if self.clip_command(boundary.expand(blur_radius * 1.5)) {
+                        self.draw_shadow(*boundary, border_radius.as_ref(), *blur_radius, *color);
+                    }
                 }
                 RenderCommand::Text {
                     x,
                     y,
                     text_id,
                     tint_color,
-                    ..
+                    visible_band,
+                    clip,
+                    stroke,
                 } => {
+                    let stroke_width = stroke.map_or(0., |stroke| stroke.width);
+
+                    let text = text.get(*text_id);
+                    let bounds = text_bounds(text, *x, *y, visible_band.as_ref())
+                        .map(|bounds| bounds.expand(stroke_width));
+
+                    // Only the text straddling its clip gets a clip, carried like the
+                    // clips of the containers so it's a layer only when it has to be.
+                    let clip = clip
+                        .filter(|clip| bounds.is_some_and(|bounds| !clip.contains_rect(bounds)));
+
+                    if let Some(clip) = clip
+                        && let Some(rect) = self.clip_stack.push_rect(clip)
+                    {
+                        self.push_clip_rect(rect);
+                    }
+
+                    if !bounds.is_some_and(|bounds| self.clip_command(bounds)) {
+                        if clip.is_some() && self.clip_stack.pop() {
+                            self.scene.pop_layer();
+                        }
+
+                        continue;
+                    }
+
                     let color = tint_color
                         .map(|c| convert_rgba_color(&c))
                         .unwrap_or_else(|| Color::from_rgba8(0, 0, 0, 255));
 
-                    text.get_mut(*text_id).with_buffer_mut(|buffer| {
-                        let brush = Brush::Solid(color);
-
-                        for run in buffer.layout_runs() {
-                            let line_y = y + run.line_y.round();
-
-                            // Group by font
-                            let mut font_glyphs: HashMap<
-                                cosmic_text::fontdb::ID,
-                                Vec<(Glyph, f32)>,
-                            > = HashMap::new();
-
-                            for glyph in run.glyphs.iter() {
-                                let physical = glyph.physical((*x, line_y), 1.0);
-                                let font_size = f32::from_bits(physical.cache_key.font_size_bits);
-
-                                // Use raw floating-point positions for smooth subpixel rendering
-                                // This prevents jiggling with justified text during resize
-                                let vello_glyph = Glyph {
-                                    id: physical.cache_key.glyph_id as u32,
-                                    x: x + glyph.x + glyph.x_offset,
-                                    y: glyph.y - glyph.y_offset + line_y,
-                                };
-
-                                font_glyphs
-                                    .entry(glyph.font_id)
-                                    .or_default()
-                                    .push((vello_glyph, font_size));
+                    let brush = Brush::Solid(color);
+
+                    for run in text.layout_runs() {
+                        if !is_run_visible(visible_band.as_ref(), &run) {
+                            continue;
+                        }
+
+                        // Truncated like tiny-skia does, so the baselines fall on the
+                        // same row of pixels in both.
+                        let line_y = y + run.line_y.floor();
+
+                        // Group by font
+                        let mut font_glyphs: HashMap<cosmic_text::fontdb::ID, Vec<(Glyph, f32)>> =
+                            HashMap::new();
+
+                        for glyph in run.glyphs.iter() {
+                            if !is_visible_cluster(&run.text[glyph.start..glyph.end]) {
+                                continue;
                             }
 
-                            // Render glyphs for each font
-                            for (font_id, glyphs) in font_glyphs {
-                                if let Some(vello_font) = self
-                                    .font_cache
-                                    .get_or_insert(font_id, &mut fonts.font_system)
-                                {
-                                    let font_size = glyphs
-                                        .first()
-                                        .map(|(_, s)| *s)
-                                        .unwrap_or(12.0 * view.scale_factor);
-                                    let glyph_iter = glyphs.into_iter().map(|(g, _)| g);
+                            if let Some(clip) = clip
+                                && (x + glyph.x + glyph.w < clip.left()
+                                    || x + glyph.x > clip.right())
+                            {
+                                continue;
+                            }
+
+                            let physical = glyph.physical((*x, line_y), 1.0);
+                            let font_size = f32::from_bits(physical.cache_key.font_size_bits);
+
+                            // Use raw floating-point positions for smooth subpixel rendering
+                            // This prevents jiggling with justified text during resize
+                            let vello_glyph = Glyph {
+                                id: physical.cache_key.glyph_id as u32,
+                                x: x + glyph.x + glyph.x_offset,
+                                y: glyph.y - glyph.y_offset + line_y,
+                            };
+
+                            font_glyphs
+                                .entry(glyph.font_id)
+                                .or_default()
+                                .push((vello_glyph, font_size));
+                        }
 
+                        // Render glyphs for each font
+                        for (font_id, glyphs) in font_glyphs {
+                            if let Some(vello_font) = self.font_cache.get_or_insert(
+                                font_id,
+                                &mut fonts.font_system,
+                                self.frame,
+                            ) {
+                                let font_size =
+                                    glyphs.first().map(|(_, s)| *s).unwrap_or(default_font_size);
+                                let glyph_iter = glyphs.iter().map(|(g, _)| *g);
+
+                                // The stroke is centered on the outlines, the fill
+                                // covers its inner half.
+                                if let Some(stroke) = stroke {
                                     self.scene
                                         .draw_glyphs(vello_font)
                                         .font_size(font_size)
-                                        .brush(&brush)
-                                        .draw(StyleRef::Fill(peniko::Fill::NonZero), glyph_iter);
+                                        .brush(&Brush::Solid(convert_rgba_color(&stroke.color)))
+                                        .draw(
+                                            StyleRef::Stroke(&Stroke::new(
+                                                (stroke.width * 2.) as f64,
+                                            )),
+                                            glyph_iter.clone(),
+                                        );
                                 }
+
+                                self.scene
+                                    .draw_glyphs(vello_font)
+                                    .font_size(font_size)
+                                    .brush(&brush)
+                                    .draw(StyleRef::Fill(peniko::Fill::NonZero), glyph_iter);
                             }
                         }
-                    });
+                    }
+
+                    if clip.is_some() && self.clip_stack.pop() {
+                        self.scene.pop_layer();
+                    }
                 }
                 RenderCommand::PushClip { rect, shape, .. } => match shape {
                     ClipShape::Rect => {
+                        if let Some(rect) = self.clip_stack.push_rect(*rect) {
+                            self.push_clip_rect(rect);
+                        }
+                    }
+                    ClipShape::RoundedRect { border_radius } => {
+                        if let Some(enclosing) = self.clip_stack.push_shape(*rect) {
+                            self.push_clip_rect(enclosing);
+                        }
+
+                        self.frame_stats.clip_layers += 1;
                         self.scene.push_clip_layer(
                             Affine::IDENTITY,
-                            &vello::kurbo::Rect::new(
+                            &vello::kurbo::RoundedRect::new(
                                 rect.x as f64,
                                 rect.y as f64,
                                 (rect.x + rect.width) as f64,
                                 (rect.y + rect.height) as f64,
+                                RoundedRectRadii {
+                                    top_left: border_radius.top_left as f64,
+                                    top_right: border_radius.top_right as f64,
+                                    bottom_right: border_radius.bottom_right as f64,
+                                    bottom_left: border_radius.bottom_left as f64,
+                                },
                             ),
                         );
                     }
-                    ClipShape::RoundedRect { border_radius } => self.scene.push_clip_layer(
-                        Affine::IDENTITY,
-                        &vello::kurbo::RoundedRect::new(
-                            rect.x as f64,
-                            rect.y as f64,
-                            (rect.x + rect.width) as f64,
-                            (rect.y + rect.height) as f64,
-                            RoundedRectRadii {
-                                top_left: border_radius.top_left as f64,
-                                top_right: border_radius.top_right as f64,
-                                bottom_right: border_radius.bottom_right as f64,
-                                bottom_left: border_radius.bottom_left as f64,
-                            },
-                        ),
-                    ),
                     ClipShape::Oval => {
-                        let center = vello::kurbo::Point::new(
+                        if let Some(enclosing) = self.clip_stack.push_shape(*rect) {
+                            self.push_clip_rect(enclosing);
+                        }
+
+                        let center = vello::kurbo::Point::new(
                             (rect.x + rect.width / 2.0) as f64,
                             (rect.y + rect.height / 2.0) as f64,
                         );
@@ -552,6 +1216,7 @@ impl Renderer for VelloRenderer {
                             (rect.height / 2.0) as f64,
                         );
 
+                        self.frame_stats.clip_layers += 1;
                         self.scene.push_clip_layer(
                             Affine::IDENTITY,
                             &vello::kurbo::Ellipse::new(center, radii, 0.0),
@@ -559,62 +1224,612 @@ impl Renderer for VelloRenderer {
                     }
                 },
                 RenderCommand::PopClip => {
-                    self.scene.pop_layer();
+                    if self.clip_stack.pop() {
+                        self.scene.pop_layer();
+                    }
+                }
+                RenderCommand::PushTransform { transform } => {
+                    self.push_transform(*transform);
+                }
+                RenderCommand::PopTransform => {
+                    self.pop_transform();
+                }
+                RenderCommand::PushLayer { id, version } => {
+                    if self.push_layer(*id, *version) {
+                        skip_depth = 1;
+                    }
+                }
+                RenderCommand::PopLayer => {
+                    self.pop_layer();
                 }
                 RenderCommand::Svg {
                     boundary,
                     asset_id,
                     tint_color,
-                    ..
+                    tint_mode,
+                } => {
+                    if !self.clip_command(*boundary) {
+                        continue;
+                    }
+
+                    if let Some(tree) = assets.find_svg_tree(asset_id) {
+                        self.draw_svg(tree, *boundary, tint_color.map(|tint| (tint, *tint_mode)));
+                    } else if !assets.is_svg_pending(asset_id) {
+                        self.draw_rect(
+                            *boundary,
+                            Some(&Fill::Color(MISSING_ASSET_COLOR)),
+                            None,
+                            None,
+                            None,
+                        );
+                    }
+                }
+                RenderCommand::Image {
+                    boundary,
+                    asset_id,
+                    tint_color,
+                    fit,
+                    opacity,
                 } => {
-                    if let Some(tree) = assets.get_svg_tree(asset_id) {
-                        self.draw_svg(tree, *boundary, *tint_color);
-                    } else {
-                        log::warn!("SVG with ID = {} not found", asset_id);
+                    if !self.clip_command(*boundary) {
+                        continue;
+                    }
+
+                    let Some(bitmap) = assets.get_bitmap(asset_id) else {
+                        self.draw_rect(
+                            *boundary,
+                            Some(&Fill::Color(MISSING_ASSET_COLOR)),
+                            None,
+                            None,
+                            None,
+                        );
+
+                        continue;
+                    };
+
+                    let image = self.image_cache.get_or_insert(asset_id, bitmap, self.frame);
+                    let rect = fit.rect(*boundary, bitmap.width() as f32, bitmap.height() as f32);
+                    let transform = Affine::scale_non_uniform(
+                        rect.width as f64 / bitmap.width() as f64,
+                        rect.height as f64 / bitmap.height() as f64,
+                    )
+                    .then_translate((rect.x as f64, rect.y as f64).into());
+
+                    // The image covering the boundary is cut to it, and the tint is
+                    // composed with the image alone.
+                    let layer = *fit == ImageFit::Cover || tint_color.is_some();
+
+                    if layer {
+                        self.scene.push_layer(
+                            peniko::BlendMode::default(),
+                            1.0,
+                            Affine::IDENTITY,
+                            &convert_rect(*boundary),
+                        );
+                    }
+
+                    self.scene.draw_image(
+                        ImageBrush {
+                            image,
+                            sampler: ImageSampler::default().with_alpha(*opacity),
+                        },
+                        transform,
+                    );
+
+                    if let Some(tint) = tint_color {
+                        self.scene.push_layer(
+                            peniko::BlendMode::new(peniko::Mix::Normal, peniko::Compose::SrcIn),
+                            1.0,
+                            Affine::IDENTITY,
+                            &convert_rect(*boundary),
+                        );
+                        self.scene.fill(
+                            VelloFill::NonZero,
+                            Affine::IDENTITY,
+                            &Brush::Solid(convert_rgba_color(tint)),
+                            None,
+                            &convert_rect(*boundary),
+                        );
+                        self.scene.pop_layer();
+                    }
+
+                    if layer {
+                        self.scene.pop_layer();
                     }
                 }
             }
         }
+    }
+}
+
+impl Renderer for VelloRenderer {
+    fn process_commands(
+        &mut self,
+        view: &View,
+        state: &RenderState,
+        fill_color: ColorRgb,
+        fonts: &mut FontResources,
+        text: &mut TextsResources,
+        assets: &Assets,
+    ) {
+        profiling::scope!("clew :: Vello - Render");
+
+        let width = view.size.width;
+        let height = view.size.height;
+
+        self.resize(width, height);
+        self.begin_frame();
+
+        // The surface can't present a part of the frame, so the damage is ignored and the
+        // whole scene is drawn. Frames without damage aren't rendered at all.
+        self.draw_commands(
+            state.commands(),
+            12.0 * view.scale_factor,
+            fonts,
+            text,
+            assets,
+        );
+
+        let seen_layers = &self.seen_layers;
+        self.layer_cache.retain(|id, _| seen_layers.contains(id));
+        self.image_cache
+            .cache
+            .retain(|key, _| assets.get_bitmap(key).is_some());
+
+        let layer_bytes = self.layer_cache.values().map(|layer| layer.bytes).sum();
+
+        self.frame_stats.cache_evictions = evict_over_budget(
+            &mut self.font_cache,
+            &mut self.image_cache,
+            layer_bytes,
+            self.cache_budget,
+            self.frame,
+        );
+        self.frame_stats.cache_bytes =
+            (self.font_cache.bytes() + self.image_cache.bytes() + layer_bytes) as u64;
+
+        tracy_client::plot!(
+            "clew :: Layer cache hits",
+            self.frame_stats.layer_cache_hits as f64
+        );
+        tracy_client::plot!(
+            "clew :: Layer cache misses",
+            self.frame_stats.layer_cache_misses as f64
+        );
+        tracy_client::plot!("clew :: Clip layers", self.frame_stats.clip_layers as f64);
+        tracy_client::plot!("clew :: Cache bytes", self.frame_stats.cache_bytes as f64);
+        tracy_client::plot!(
+            "clew :: Culled commands",
+            self.frame_stats.culled_commands as f64
+        );
 
         self.end_frame(&fill_color);
         tracy_client::frame_mark();
     }
+
+    fn set_transparent_background(&mut self, transparent: bool) -> bool {
+        self.transparent_background = transparent;
+
+        // The surface is configured once it's set up when it's warming up.
+        if self.warm_up.is_some() {
+            return true;
+        }
+
+        if !self.configure_alpha_mode() && transparent {
+            self.transparent_background = false;
+            self.configure_alpha_mode();
+
+            return false;
+        }
+
+        true
+    }
+
+    fn poll_ready(&mut self) -> Result<bool, ClewError> {
+        let Some(warm_up) = &self.warm_up else {
+            return Ok(self.renderer.is_some());
+        };
+
+        match warm_up.try_recv() {
+            Ok(Ok(ready)) => {
+                self.warm_up = None;
+                self.install(ready);
+
+                Ok(true)
+            }
+            Ok(Err(err)) => {
+                self.warm_up = None;
+
+                Err(err)
+            }
+            Err(mpsc::TryRecvError::Empty) => Ok(false),
+            Err(mpsc::TryRecvError::Disconnected) => {
+                self.warm_up = None;
+
+                Err(ClewError::Renderer(
+                    "The setup thread has stopped".to_string(),
+                ))
+            }
+        }
+    }
+
+    fn present_fill(&mut self, view: &View, fill_color: ColorRgb) {
+        // Kept for the surface, which is resized to it once it's set up.
+        self.resize(view.size.width, view.size.height);
+
+        if self.renderer.is_none()
+            && let Some(fill) = &mut self.warm_up_fill
+        {
+            fill(self.current_width, self.current_height, &fill_color);
+        }
+    }
+
+    fn frame_stats(&self) -> FrameStats {
+        self.frame_stats
+    }
+
+    fn render_image(
+        &mut self,
+        commands: &[RenderCommand],
+        width: u32,
+        height: u32,
+        background: Option<ColorRgba>,
+        fonts: &mut FontResources,
+        text: &mut TextsResources,
+        assets: &Assets,
+    ) -> Result<RgbaImage, ClewError> {
+        profiling::scope!("clew :: Vello - Render Image");
+
+        if self.renderer.is_none() {
+            return Err(ClewError::Capture("The renderer isn't ready".to_string()));
+        }
+
+        let max_size = gpu(&self.render_cx, self.dev_id, &self.host_device)
+            .0
+            .limits()
+            .max_texture_dimension_2d;
+
+        if width > max_size || height > max_size {
+            return Err(ClewError::Capture(format!(
+                "{width}x{height} pixels exceed the texture size limit of {max_size}"
+            )));
+        }
+
+        // The frame stats and the scene belong to the frame on the screen.
+        let frame_stats = self.frame_stats;
+        let frame_scene = std::mem::replace(&mut self.scene, Scene::new());
+
+        self.transform_stack.clear();
+        self.layer_stack.clear();
+        self.clip_stack.clear();
+        self.draw_commands(commands, 12.0, fonts, text, assets);
+
+        let scene = std::mem::replace(&mut self.scene, frame_scene);
+        self.frame_stats = frame_stats;
+
+        let Some(renderer) = &mut self.renderer else {
+            return Err(ClewError::Capture("The renderer isn't ready".to_string()));
+        };
+        let (device, queue) = gpu(&self.render_cx, self.dev_id, &self.host_device);
+
+        let size = wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        };
+        let render = |renderer: &mut vello::Renderer| {
+            catch_out_of_memory(device, || {
+                let texture = device.create_texture(&wgpu::TextureDescriptor {
+                    label: Some("Image Capture"),
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: wgpu::TextureDimension::D2,
+                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC,
+                    view_formats: &[],
+                });
+
+                renderer
+                    .render_to_texture(
+                        device,
+                        queue,
+                        &scene,
+                        &texture.create_view(&wgpu::TextureViewDescriptor::default()),
+                        &RenderParams {
+                            base_color: background
+                                .map_or(Color::TRANSPARENT, |color| convert_rgba_color(&color)),
+                            width,
+                            height,
+                            antialiasing_method: AaConfig::Msaa16,
+                        },
+                    )
+                    .map(|()| texture)
+            })
+        };
+
+        let mut rendered = render(renderer);
+
+        // Large images may not fit next to what the caches hold, they are freed and the
+        // image is tried once more.
+        if let Err(err) = &rendered {
+            log::warn!("Out of GPU memory, evicting the caches: {err}");
+            self.layer_cache.clear();
+            evict_unused(&mut self.font_cache, &mut self.image_cache, self.frame);
+            rendered = render(renderer);
+        }
+
+        let texture = rendered
+            .map_err(|err| ClewError::Capture(format!("Out of GPU memory: {err}")))?
+            .map_err(|err| ClewError::Capture(err.to_string()))?;
+
+        // Rows of the copy are padded to the alignment wgpu requires.
+        let row_size = width * 4;
+        let padded_row_size = row_size.next_multiple_of(wgpu::COPY_BYTES_PER_ROW_ALIGNMENT);
+        let buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Image Capture Readback"),
+            size: padded_row_size as u64 * height as u64,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor {
+            label: Some("Image Capture Readback"),
+        });
+        encoder.copy_texture_to_buffer(
+            texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(padded_row_size),
+                    rows_per_image: None,
+                },
+            },
+            size,
+        );
+        queue.submit([encoder.finish()]);
+
+        let slice = buffer.slice(..);
+        let (tx, rx) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = tx.send(result);
+        });
+
+        device
+            .poll(wgpu::PollType::Wait)
+            .map_err(|err| ClewError::Capture(err.to_string()))?;
+        rx.recv()
+            .map_err(|err| ClewError::Capture(err.to_string()))?
+            .map_err(|err| ClewError::Capture(err.to_string()))?;
+
+        // Vello writes the colors unpremultiplied, so the pixels are copied as is.
+        let mut pixels = Vec::with_capacity(row_size as usize * height as usize);
+
+        {
+            let mapped = slice.get_mapped_range();
+
+            for row in mapped.chunks(padded_row_size as usize) {
+                pixels.extend_from_slice(&row[..row_size as usize]);
+            }
+        }
+
+        buffer.unmap();
+
+        Ok(RgbaImage {
+            width,
+            height,
+            pixels,
+        })
+    }
+
+    fn on_scale_factor_update(&mut self, _scale_factor: f32) {
+        self.layer_cache.clear();
+    }
 }
 
 // Helper functions
 
+/// Draws the fill color with softbuffer while the device is set up, without it the
+/// window stays blank, or shows garbage, until the first frame.
+fn fill_fallback<W>(window: Arc<W>) -> Option<FillFallback>
+where
+    W: HasWindowHandle + HasDisplayHandle + 'static,
+{
+    let surface = softbuffer::Context::new(window.clone())
+        .and_then(|context| softbuffer::Surface::new(&context, window));
+
+    let mut surface = match surface {
+        Ok(surface) => surface,
+        Err(err) => {
+            log::warn!("The window isn't filled while the renderer warms up: {err}");
+            return None;
+        }
+    };
+
+    Some(Box::new(
+        move |width: u32, height: u32, fill_color: &ColorRgb| {
+            let (Some(width), Some(height)) = (NonZeroU32::new(width), NonZeroU32::new(height))
+            else {
+                return;
+            };
+
+            let result = surface.resize(width, height).and_then(|_| {
+                let mut buffer = surface.buffer_mut()?;
+                buffer.fill(fill_color.to_hex());
+                buffer.present()
+            });
+
+            if let Err(err) = result {
+                log::error!("Failed to fill the window: {err}");
+            }
+        },
+    ))
+}
+
+/// Creates the renderer for the device of the surface.
+fn finish_setup(
+    render_cx: RenderContext,
+    mut surface: vello::util::RenderSurface<'static>,
+) -> Result<WarmedUp, ClewError> {
+    #[cfg(target_os = "macos")]
+    #[allow(invalid_reference_casting)]
+    unsafe {
+        if let Some(hal_surface) = surface.surface.as_hal::<wgpu::hal::api::Metal>() {
+            let raw = (&*hal_surface) as *const wgpu::hal::metal::Surface
+                as *mut wgpu::hal::metal::Surface;
+            (*raw).present_with_transaction = true;
+        }
+    }
+
+    let device = &render_cx.devices[surface.dev_id].device;
+
+    // Create Vello renderer
+    let renderer = vello::Renderer::new(device, RendererOptions::default())
+        .map_err(|err| ClewError::Renderer(err.to_string()))?;
+
+    // Kept in the config of the surface so it survives the resizes.
+    surface.config.desired_maximum_frame_latency = DEFAULT_FRAME_LATENCY;
+    surface.surface.configure(device, &surface.config);
+
+    Ok(WarmedUp {
+        render_cx,
+        surface,
+        renderer,
+    })
+}
+
+/// Device and queue the scenes are rendered with, the ones of the application if the
+/// renderer draws with its device.
+fn gpu<'a>(
+    render_cx: &'a RenderContext,
+    dev_id: usize,
+    host_device: &'a Option<HostDevice>,
+) -> (&'a wgpu::Device, &'a wgpu::Queue) {
+    match host_device {
+        Some(host) => (&host.device, &host.queue),
+        None => {
+            let handle = &render_cx.devices[dev_id];
+
+            (&handle.device, &handle.queue)
+        }
+    }
+}
+
+/// Runs `f` in an error scope of the out of memory errors of the device, the failed
+/// allocations in it are returned as the error instead of being raised.
+fn catch_out_of_memory<T>(device: &wgpu::Device, f: impl FnOnce() -> T) -> Result<T, wgpu::Error> {
+    device.push_error_scope(wgpu::ErrorFilter::OutOfMemory);
+    let result = f();
+
+    match block_on(device.pop_error_scope()) {
+        Some(err) => Err(err),
+        None => Ok(result),
+    }
+}
+
+/// The futures of wgpu on the native backends, e.g. the error scopes and the requests of
+/// the devices, resolve right away, so the future is polled without an executor.
+fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = std::pin::pin!(future);
+    let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+
+    loop {
+        if let std::task::Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+            return output;
+        }
+
+        std::thread::yield_now();
+    }
+}
+
+// The colors are sRGB, the same as the colors of vello. It renders into an `Rgba8Unorm`
+// texture and blits it into an `Rgba8Unorm` or a `Bgra8Unorm` surface, neither of them
+// sRGB, so the values aren't converted on the way.
 fn convert_rgba_color(color: &ColorRgba) -> Color {
-    Color::from_rgba8(
-        (color.r * 255.) as u8,
-        (color.g * 255.) as u8,
-        (color.b * 255.) as u8,
-        (color.a * 255.) as u8,
+    let [r, g, b, a] = color.to_rgba8();
+
+    Color::from_rgba8(r, g, b, a)
+}
+
+fn convert_rect(rect: Rect) -> vello::kurbo::Rect {
+    vello::kurbo::Rect::new(
+        rect.x as f64,
+        rect.y as f64,
+        (rect.x + rect.width) as f64,
+        (rect.y + rect.height) as f64,
     )
 }
 
+/// Bounds of the glyphs in the visible runs. The outlines may go past the advances and
+/// the line box, so a margin of half a line is added around.
+fn text_bounds(text: &Text, x: f32, y: f32, visible_band: Option<&Range<f32>>) -> Option<Rect> {
+    let mut bounds: Option<Rect> = None;
+
+    for run in text.layout_runs() {
+        if !is_run_visible(visible_band, &run) {
+            continue;
+        }
+
+        let left = run.glyphs.iter().map(|glyph| glyph.x).reduce(f32::min);
+        let right = run
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.x + glyph.w)
+            .reduce(f32::max);
+
+        if let (Some(left), Some(right)) = (left, right) {
+            let margin = run.line_height / 2.;
+            let rect = Rect::new(
+                x + left - margin,
+                y + run.line_top - margin,
+                right - left + margin * 2.,
+                run.line_height + margin * 2.,
+            );
+
+            bounds = Some(bounds.map_or(rect, |bounds| bounds.union(rect)));
+        }
+    }
+
+    bounds
+}
+
 fn convert_rgb_color(color: &ColorRgb) -> Color {
-    Color::from_rgb8(
-        (color.r * 255.) as u8,
-        (color.g * 255.) as u8,
-        (color.b * 255.) as u8,
-    )
+    let [r, g, b] = color.to_rgb8();
+
+    Color::from_rgb8(r, g, b)
 }
 
-fn create_brush_from_fill(fill: &Fill, rect: Rect) -> Option<Brush> {
+/// The brush with the transform to draw it with, if any.
+fn create_brush_from_fill(fill: &Fill, rect: Rect) -> Option<(Brush, Option<Affine>)> {
     match fill {
         Fill::None => None,
-        Fill::Color(color) => Some(Brush::Solid(convert_rgba_color(color))),
+        Fill::Color(color) => Some((Brush::Solid(convert_rgba_color(color)), None)),
         Fill::Gradient(gradient) => create_gradient_brush(gradient, rect),
     }
 }
 
-fn create_gradient_brush(gradient: &Gradient, rect: Rect) -> Option<Brush> {
+/// Brush of a border, the fill when it's set and the color of the side otherwise.
+fn create_border_brush(
+    fill: Option<&Fill>,
+    color: ColorRgba,
+    boundary: Rect,
+) -> Option<(Brush, Option<Affine>)> {
+    match fill {
+        Some(fill) => create_brush_from_fill(fill, boundary),
+        None => create_brush_from_fill(&Fill::Color(color), boundary),
+    }
+}
+
+/// Vello samples the gradients and the blurred rects at the corners of the pixels,
+/// tiny-skia at their centers, they're moved by half a pixel so the backends agree on
+/// the steep ones.
+const PIXEL_CENTER: Affine = Affine::new([1., 0., 0., 1., -0.5, -0.5]);
+
+fn create_gradient_brush(gradient: &Gradient, rect: Rect) -> Option<(Brush, Option<Affine>)> {
     match gradient {
         Gradient::Linear(linear) => {
-            let start_x = rect.x + linear.start.0 * rect.width;
-            let start_y = rect.y + linear.start.1 * rect.height;
-            let end_x = rect.x + linear.end.0 * rect.width;
-            let end_y = rect.y + linear.end.1 * rect.height;
+            let ((start_x, start_y), (end_x, end_y)) = linear.resolve(rect);
 
             let stops: Vec<peniko::ColorStop> = linear
                 .stops
@@ -631,12 +1846,10 @@ fn create_gradient_brush(gradient: &Gradient, rect: Rect) -> Option<Brush> {
             )
             .with_stops(stops.as_slice());
 
-            Some(Brush::Gradient(grad))
+            Some((Brush::Gradient(grad), Some(PIXEL_CENTER)))
         }
         Gradient::Radial(radial) => {
-            let center_x = rect.x + radial.center.0 * rect.width;
-            let center_y = rect.y + radial.center.1 * rect.height;
-            let radius = radial.radius * rect.width.max(rect.height);
+            let geometry = radial.resolve(rect)?;
 
             let stops: Vec<peniko::ColorStop> = radial
                 .stops
@@ -647,10 +1860,19 @@ fn create_gradient_brush(gradient: &Gradient, rect: Rect) -> Option<Brush> {
                 })
                 .collect();
 
-            let grad = VelloGradient::new_radial((center_x, center_y), radius)
-                .with_stops(stops.as_slice());
+            // The gradient starts at the focal circle and ends at the circle, the
+            // ellipse is made by the transform of the brush.
+            let grad = VelloGradient::new_two_point_radial(
+                (geometry.focal.0 as f64, geometry.focal.1 as f64),
+                geometry.focal_radius,
+                (0., 0.),
+                geometry.radius,
+            )
+            .with_stops(stops.as_slice());
+            let transform = Affine::translate((geometry.center.0 as f64, geometry.center.1 as f64))
+                * Affine::scale_non_uniform(1., geometry.scale_y as f64);
 
-            Some(Brush::Gradient(grad))
+            Some((Brush::Gradient(grad), Some(PIXEL_CENTER * transform)))
         }
         Gradient::Sweep(sweep) => {
             let center_x = rect.x + sweep.center.0 * rect.width;
@@ -669,29 +1891,749 @@ fn create_gradient_brush(gradient: &Gradient, rect: Rect) -> Option<Brush> {
                 VelloGradient::new_sweep((center_x, center_y), sweep.start_angle, sweep.end_angle)
                     .with_stops(stops.as_slice());
 
-            Some(Brush::Gradient(grad))
+            Some((Brush::Gradient(grad), Some(PIXEL_CENTER)))
         }
     }
 }
 
-fn get_border_params(border: &Border) -> (f32, ColorRgba) {
-    let max_width = [
-        border.top.as_ref().map(|s| s.width).unwrap_or(0.0),
-        border.right.as_ref().map(|s| s.width).unwrap_or(0.0),
-        border.bottom.as_ref().map(|s| s.width).unwrap_or(0.0),
-        border.left.as_ref().map(|s| s.width).unwrap_or(0.0),
-    ]
-    .into_iter()
-    .fold(0.0f32, f32::max);
-
-    let color = border
-        .top
-        .as_ref()
-        .or(border.right.as_ref())
-        .or(border.bottom.as_ref())
-        .or(border.left.as_ref())
-        .map(|s| s.color)
-        .unwrap_or(ColorRgba::TRANSPARENT);
-
-    (max_width, color)
+fn create_outline_path(outline: &[PathSegment]) -> BezPath {
+    let mut path = BezPath::new();
+
+    for segment in outline {
+        match *segment {
+            PathSegment::MoveTo(x, y) => path.move_to((x as f64, y as f64)),
+            PathSegment::LineTo(x, y) => path.line_to((x as f64, y as f64)),
+            PathSegment::CubicTo((x1, y1), (x2, y2), (x, y)) => path.curve_to(
+                (x1 as f64, y1 as f64),
+                (x2 as f64, y2 as f64),
+                (x as f64, y as f64),
+            ),
+            PathSegment::Close => path.close_path(),
+        }
+    }
+
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::text::SystemFonts;
+    use clew::{ColorStop, EdgeInsets, LinearGradient, PhysicalSize, RadialGradient, Vec2, ViewId};
+    use pollster::FutureExt;
+    use std::{cell::RefCell, rc::Rc};
+
+    use super::*;
+
+    /// Headless renderer to compare with tiny-skia. The tests that take it are ignored by
+    /// default, since they need a GPU, run them with `cargo test -- --ignored` on the
+    /// machines that have one.
+    fn headless() -> VelloRenderer {
+        VelloRenderer::headless()
+            .block_on()
+            .expect("the vello comparison tests need a GPU")
+    }
+
+    /// Pixel of a rendered image with its color multiplied by its alpha, the colors of
+    /// the nearly transparent pixels say little in straight alpha.
+    fn premultiplied(pixel: &[u8]) -> [u8; 4] {
+        let channel = |value: u8| (value as u16 * pixel[3] as u16 / 255) as u8;
+
+        [
+            channel(pixel[0]),
+            channel(pixel[1]),
+            channel(pixel[2]),
+            pixel[3],
+        ]
+    }
+
+    /// The shipped DejaVu Sans Mono, the one the tiny-skia tests shape with.
+    fn test_fonts() -> FontResources {
+        FontResources::with_fonts(
+            SystemFonts::Lazy,
+            [(
+                "DejaVu Sans Mono",
+                include_bytes!("../../test-fonts/DejaVuSansMono.ttf").as_slice(),
+            )],
+        )
+    }
+
+    /// An opaque half and a translucent half, aligned to the pixels so the edges don't
+    /// depend on the anti-aliasing.
+    const SVG: &[u8] = br##"<svg xmlns="http://www.w3.org/2000/svg" width="16" height="16">
+        <rect width="16" height="8" fill="#FF8040"/>
+        <rect y="8" width="16" height="8" fill="#4080FF" fill-opacity="0.5"/>
+    </svg>"##;
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_colors_match_tiny_skia() {
+        let mut vello = headless();
+        let mut tiny_skia = clew_tiny_skia::HeadlessRenderer::new();
+        let commands = [
+            RenderCommand::Rect {
+                boundary: Rect::new(0., 0., 8., 8.),
+                fill: Some(Fill::Color(ColorRgba::from_hex(0xFF2F80C4))),
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            },
+            // Outside of 0..1, both renderers clamp it.
+            RenderCommand::Rect {
+                boundary: Rect::new(8., 0., 8., 8.),
+                fill: Some(Fill::Color(ColorRgba::new(1.2, 0.5, -0.1, 1.))),
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            },
+            // Outside of the sRGB gamut.
+            RenderCommand::Rect {
+                boundary: Rect::new(16., 0., 8., 8.),
+                fill: Some(Fill::Color(ColorRgba::from_display_p3(0., 1., 0., 1.))),
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            },
+        ];
+        let render = |renderer: &mut dyn Renderer| {
+            renderer
+                .render_image(
+                    &commands,
+                    24,
+                    8,
+                    None,
+                    &mut FontResources::new(),
+                    &mut TextsResources::new(),
+                    &Assets::new(),
+                )
+                .unwrap()
+        };
+        let expected = render(&mut tiny_skia);
+        let rendered = render(&mut vello);
+
+        for (index, (expected, rendered)) in expected
+            .pixels
+            .chunks_exact(4)
+            .zip(rendered.pixels.chunks_exact(4))
+            .enumerate()
+        {
+            assert!(
+                expected
+                    .iter()
+                    .zip(rendered)
+                    .all(|(expected, rendered)| expected.abs_diff(*rendered) <= 1),
+                "pixel {index} is {rendered:?}, expected {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_gradient_border_matches_tiny_skia() {
+        let mut vello = headless();
+        let mut tiny_skia = clew_tiny_skia::HeadlessRenderer::new();
+        let gradient = Fill::Gradient(Gradient::Linear(LinearGradient::horizontal((
+            ColorRgba::from_hex(0xFFFF4020),
+            ColorRgba::from_hex(0xFF2040FF),
+        ))));
+        // Aligned to the pixels, so the edges don't depend on the anti-aliasing. The
+        // second rect is filled with the same gradient, its border has to line up.
+        let commands = [
+            RenderCommand::Rect {
+                boundary: Rect::new(0., 0., 32., 32.),
+                fill: None,
+                border_radius: None,
+                border: Some(Border::all(BorderSide::new(
+                    4.,
+                    ColorRgba::from_hex(0xFF00FF00),
+                ))),
+                border_fill: Some(gradient.clone()),
+            },
+            RenderCommand::Rect {
+                boundary: Rect::new(32., 0., 32., 32.),
+                fill: Some(gradient.clone()),
+                border_radius: None,
+                border: Some(Border::all(BorderSide::new(
+                    6.,
+                    ColorRgba::from_hex(0xFF00FF00),
+                ))),
+                border_fill: Some(gradient),
+            },
+        ];
+        let render = |renderer: &mut dyn Renderer| {
+            renderer
+                .render_image(
+                    &commands,
+                    64,
+                    32,
+                    None,
+                    &mut FontResources::new(),
+                    &mut TextsResources::new(),
+                    &Assets::new(),
+                )
+                .unwrap()
+        };
+        let expected = render(&mut tiny_skia);
+        let rendered = render(&mut vello);
+
+        for (index, (expected, rendered)) in expected
+            .pixels
+            .chunks_exact(4)
+            .zip(rendered.pixels.chunks_exact(4))
+            .enumerate()
+        {
+            assert!(
+                expected
+                    .iter()
+                    .zip(rendered)
+                    .all(|(expected, rendered)| expected.abs_diff(*rendered) <= 3),
+                "pixel {index} is {rendered:?}, expected {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_carried_clips_draw_like_clip_layers() {
+        let mut carrying = headless();
+        let layers = headless();
+        let mut layers = layers.with_rect_clip_carrying(false);
+        let rect = |x: f32, y: f32, width: f32, height: f32, color: u32| RenderCommand::Rect {
+            boundary: Rect::new(x, y, width, height),
+            fill: Some(Fill::Color(ColorRgba::from_hex(color))),
+            border_radius: Some(BorderRadius::all(3.)),
+            border: None,
+            border_fill: None,
+        };
+        let push_clip =
+            |x: f32, y: f32, width: f32, height: f32, shape: ClipShape| RenderCommand::PushClip {
+                rect: Rect::new(x, y, width, height),
+                shape,
+            };
+        // Nested scroll areas: commands inside of the clips, outside of them and across
+        // their edges, a rounded clip inside of a carried one and a scrolled content.
+        let commands = [
+            push_clip(4., 4., 56., 56., ClipShape::Rect),
+            rect(8., 40., 16., 8., 0xFFFF4020),
+            rect(80., 80., 10., 10., 0xFF2040FF),
+            // Inside of the outer clip, but only partly: carried.
+            push_clip(32., 0., 64., 30., ClipShape::Rect),
+            rect(36., 10., 8., 8., 0xFF20C0FF),
+            rect(70., 10., 10., 10., 0xFFFFC020),
+            RenderCommand::PopClip,
+            // A straddling rect gets a layer for this clip only.
+            push_clip(8., 8., 20., 20., ClipShape::Rect),
+            rect(20., 20., 20., 20., 0xFF20FF40),
+            RenderCommand::PopClip,
+            push_clip(
+                8.,
+                32.,
+                24.,
+                20.,
+                ClipShape::RoundedRect {
+                    border_radius: BorderRadius::all(8.),
+                },
+            ),
+            rect(0., 30., 40., 30., 0xFFC020FF),
+            RenderCommand::PopClip,
+            // Crosses the outer clip, which gets its layer when the transform starts.
+            RenderCommand::PushTransform {
+                transform: Transform::new(Vec2::new(30., 50.), 1.),
+            },
+            rect(0., 0., 40., 8., 0xFF40FFFF),
+            RenderCommand::PopTransform,
+            RenderCommand::PopClip,
+        ];
+        // The image capture keeps the stats of the frame on the screen, they're taken
+        // from the commands encoded as a frame.
+        let render = |renderer: &mut VelloRenderer| {
+            let mut fonts = FontResources::new();
+            let mut texts = TextsResources::new();
+            let assets = Assets::new();
+
+            renderer.begin_frame();
+            renderer.draw_commands(&commands, 12., &mut fonts, &mut texts, &assets);
+
+            let stats = renderer.frame_stats();
+            let image = renderer
+                .render_image(&commands, 64, 64, None, &mut fonts, &mut texts, &assets)
+                .unwrap();
+
+            (image, stats)
+        };
+        let (expected, layer_stats) = render(&mut layers);
+        let (rendered, carrying_stats) = render(&mut carrying);
+
+        // Composited through a layer or drawn directly may round differently.
+        for (index, (expected, rendered)) in
+            expected.pixels.iter().zip(&rendered.pixels).enumerate()
+        {
+            assert!(
+                expected.abs_diff(*rendered) <= 1,
+                "byte {index}: {expected} with clip layers, {rendered} carried",
+            );
+        }
+
+        // A layer per clip without carrying, carried the one with everything inside of
+        // it doesn't need one and the two rects outside of the clips are skipped.
+        assert_eq!(layer_stats.clip_layers, 4);
+        assert_eq!(layer_stats.culled_commands, 0);
+        assert_eq!(carrying_stats.clip_layers, 3);
+        assert_eq!(carrying_stats.culled_commands, 2);
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_radial_gradients_match_tiny_skia() {
+        let mut vello = headless();
+        let mut tiny_skia = clew_tiny_skia::HeadlessRenderer::new();
+        let stops = vec![
+            ColorStop::new(0., ColorRgba::from_hex(0xFFFF4020)),
+            ColorStop::new(1., ColorRgba::from_hex(0xFF2040FF)),
+        ];
+        let gradients = [
+            // Spans the wide rect horizontally and a quarter of its width vertically.
+            RadialGradient::ellipse((0.5, 0.5), 0.5, 0.125, stops.clone()),
+            RadialGradient::new((0.5, 0.5), 0.5, stops.clone()).with_focal((0.25, 0.5)),
+            RadialGradient::ellipse((0.5, 0.5), 0.5, 0.25, stops).with_focal((0.75, 0.25)),
+        ];
+
+        for gradient in gradients {
+            let commands = [RenderCommand::Rect {
+                boundary: Rect::new(0., 0., 64., 32.),
+                fill: Some(Fill::Gradient(Gradient::Radial(gradient.clone()))),
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            }];
+            let render = |renderer: &mut dyn Renderer| {
+                renderer
+                    .render_image(
+                        &commands,
+                        64,
+                        32,
+                        None,
+                        &mut FontResources::new(),
+                        &mut TextsResources::new(),
+                        &Assets::new(),
+                    )
+                    .unwrap()
+            };
+            let expected = render(&mut tiny_skia);
+            let rendered = render(&mut vello);
+
+            for (index, (expected, rendered)) in expected
+                .pixels
+                .chunks_exact(4)
+                .zip(rendered.pixels.chunks_exact(4))
+                .enumerate()
+            {
+                assert!(
+                    expected
+                        .iter()
+                        .zip(rendered)
+                        .all(|(expected, rendered)| expected.abs_diff(*rendered) <= 3),
+                    "{gradient:?} pixel {index} is {rendered:?}, expected {expected:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_shadows_match_tiny_skia() {
+        let mut vello = headless();
+        let mut tiny_skia = clew_tiny_skia::HeadlessRenderer::new();
+        // Two overlapping shadows of a rounded box, a translucent one over an opaque one.
+        // Vello blurs a single radius, so the corners are all the same.
+        let commands = [
+            RenderCommand::Shadow {
+                boundary: Rect::new(14., 14., 36., 36.),
+                border_radius: Some(BorderRadius::all(10.)),
+                blur_radius: 8.,
+                color: ColorRgba::from_hex(0xFF2040FF),
+            },
+            RenderCommand::Shadow {
+                boundary: Rect::new(16., 20., 32., 32.),
+                border_radius: Some(BorderRadius::all(8.)),
+                blur_radius: 12.,
+                color: ColorRgba::from_hex(0x80FF4020),
+            },
+        ];
+        let render = |renderer: &mut dyn Renderer| {
+            renderer
+                .render_image(
+                    &commands,
+                    64,
+                    64,
+                    None,
+                    &mut FontResources::new(),
+                    &mut TextsResources::new(),
+                    &Assets::new(),
+                )
+                .unwrap()
+        };
+        let expected = render(&mut tiny_skia);
+        let rendered = render(&mut vello);
+
+        // Tiny-skia approximates the gaussian with box blurs, vello computes it, the
+        // falloffs differ by a few levels. Compared premultiplied, the edge of the blur is
+        // nearly transparent.
+        for (index, (expected, rendered)) in expected
+            .pixels
+            .chunks_exact(4)
+            .zip(rendered.pixels.chunks_exact(4))
+            .enumerate()
+        {
+            let expected = premultiplied(expected);
+            let rendered = premultiplied(rendered);
+
+            assert!(
+                expected
+                    .iter()
+                    .zip(rendered)
+                    .all(|(expected, rendered)| expected.abs_diff(rendered) <= 12),
+                "pixel {index} is {rendered:?}, expected {expected:?}"
+            );
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_svg_tint_matches_tiny_skia() {
+        let mut vello = headless();
+        let mut tiny_skia = clew_tiny_skia::HeadlessRenderer::new();
+        let mut assets = Assets::new();
+        assets.load_svg("tinted", SVG).unwrap();
+
+        for tint_mode in [TintMode::Replace, TintMode::Multiply] {
+            let commands = [RenderCommand::Svg {
+                boundary: Rect::new(0., 0., 32., 32.),
+                asset_id: "tinted",
+                tint_color: Some(ColorRgba::from_hex(0xB3C0A040)),
+                tint_mode,
+            }];
+            let render = |renderer: &mut dyn Renderer| {
+                renderer
+                    .render_image(
+                        &commands,
+                        32,
+                        32,
+                        None,
+                        &mut FontResources::new(),
+                        &mut TextsResources::new(),
+                        &assets,
+                    )
+                    .unwrap()
+            };
+            let expected = render(&mut tiny_skia);
+            let rendered = render(&mut vello);
+
+            for (index, (expected, rendered)) in expected
+                .pixels
+                .chunks_exact(4)
+                .zip(rendered.pixels.chunks_exact(4))
+                .enumerate()
+            {
+                // The colors of the translucent pixels are less precise with straight
+                // alpha.
+                let expected = premultiplied(expected);
+                let rendered = premultiplied(rendered);
+
+                assert!(
+                    expected
+                        .iter()
+                        .zip(rendered)
+                        .all(|(expected, rendered)| expected.abs_diff(rendered) <= 3),
+                    "{tint_mode:?} pixel {index} is {rendered:?}, expected {expected:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_text_is_clipped_to_its_rect() {
+        let mut vello = headless();
+        let mut fonts = test_fonts();
+        let view = view_of_size(96, 24);
+        let mut texts = TextsResources::new();
+        let text_id = texts.add_text(&view, &mut fonts, 20., 24., |fonts, text| {
+            text.set_text(fonts, "HHHHHHHH")
+        });
+        texts.shape_as_needed(text_id, &mut fonts.font_system, false);
+
+        let mut draw = |clip: Option<Rect>| {
+            let image = vello
+                .render_image(
+                    &[RenderCommand::Text {
+                        x: 0.,
+                        y: 0.,
+                        text_id,
+                        tint_color: Some(ColorRgba::from_hex(0xFFFFFFFF)),
+                        visible_band: None,
+                        clip,
+                        stroke: None,
+                    }],
+                    96,
+                    24,
+                    None,
+                    &mut fonts,
+                    &mut texts,
+                    &Assets::new(),
+                )
+                .unwrap();
+            let columns = (0..96)
+                .filter(|x| (0..24).any(|y| image.pixels[((y * 96 + x) * 4 + 3) as usize] != 0))
+                .collect::<Vec<_>>();
+
+            (image.pixels, columns)
+        };
+
+        let (overflowing, overflowing_columns) = draw(None);
+        let (_, clipped_columns) = draw(Some(Rect::new(0., 0., 41., 24.)));
+        let (inside, _) = draw(Some(Rect::new(0., 0., 96., 24.)));
+
+        assert!(overflowing_columns.last().is_some_and(|x| *x >= 41));
+        assert!(clipped_columns.last().is_some_and(|x| *x < 41));
+        // The glyph across the edge is cut rather than skipped.
+        assert!(clipped_columns.len() > overflowing_columns.iter().filter(|x| **x < 36).count());
+        // A clip the text fits into changes nothing.
+        assert_eq!(inside, overflowing);
+    }
+
+    fn view_of_size(width: u32, height: u32) -> View {
+        View {
+            id: ViewId(0),
+            size: PhysicalSize::new(width, height),
+            scale_factor: 1.,
+            safe_area: EdgeInsets::ZERO,
+        }
+    }
+
+    #[test]
+    fn test_window_is_filled_at_its_latest_size_while_warming_up() {
+        let (_sender, receiver) = mpsc::channel();
+        let fills = Rc::new(RefCell::new(Vec::new()));
+        let fill: FillFallback = {
+            let fills = fills.clone();
+
+            Box::new(move |width: u32, height: u32, color: &ColorRgb| {
+                fills.borrow_mut().push((width, height, color.to_hex()));
+            })
+        };
+        let mut renderer = VelloRenderer::waiting_for(receiver, Some(fill), 100, 80);
+        let fill_color = ColorRgb::from_hex(0x202124);
+
+        assert_eq!(renderer.poll_ready(), Ok(false));
+
+        renderer.present_fill(&view_of_size(100, 80), fill_color);
+        renderer.present_fill(&view_of_size(120, 90), fill_color);
+
+        assert_eq!(*fills.borrow(), [(100, 80, 0x202124), (120, 90, 0x202124)]);
+        // The surface is set up at the size of the view by then.
+        assert_eq!((renderer.current_width, renderer.current_height), (120, 90));
+    }
+
+    #[test]
+    fn test_failed_warm_up_is_reported_once() {
+        let (sender, receiver) = mpsc::channel();
+        let mut renderer = VelloRenderer::waiting_for(receiver, None, 100, 80);
+
+        sender
+            .send(Err(ClewError::Renderer(
+                "No compatible GPU device".to_string(),
+            )))
+            .unwrap();
+
+        assert_eq!(
+            renderer.poll_ready(),
+            Err(ClewError::Renderer("No compatible GPU device".to_string()))
+        );
+        assert_eq!(renderer.poll_ready(), Ok(false));
+    }
+
+    #[test]
+    fn test_stopped_warm_up_is_reported() {
+        let (sender, receiver) = mpsc::channel();
+        let mut renderer = VelloRenderer::waiting_for(receiver, None, 100, 80);
+
+        drop(sender);
+
+        assert!(matches!(renderer.poll_ready(), Err(ClewError::Renderer(_))));
+        assert_eq!(renderer.poll_ready(), Ok(false));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_text_stroke_surrounds_the_glyphs() {
+        let mut vello = headless();
+        let mut fonts = test_fonts();
+        let view = view_of_size(48, 32);
+        let mut texts = TextsResources::new();
+        let text_id = texts.add_text(&view, &mut fonts, 20., 24., |fonts, text| {
+            text.set_text(fonts, "H")
+        });
+        texts.shape_as_needed(text_id, &mut fonts.font_system, false);
+
+        let image = vello
+            .render_image(
+                &[RenderCommand::Text {
+                    x: 8.,
+                    y: 4.,
+                    text_id,
+                    tint_color: Some(ColorRgba::from_hex(0xFFFFFFFF)),
+                    visible_band: None,
+                    clip: None,
+                    stroke: Some(clew::render::TextStroke {
+                        width: 3.,
+                        color: ColorRgba::from_hex(0xFFFF0000),
+                    }),
+                }],
+                48,
+                32,
+                None,
+                &mut fonts,
+                &mut texts,
+                &Assets::new(),
+            )
+            .unwrap();
+
+        let pixel = |x: u32, y: u32| {
+            let index = ((y * 48 + x) * 4) as usize;
+            [0, 1, 2, 3].map(|channel| image.pixels[index + channel])
+        };
+        let leftmost =
+            |matches: fn([u8; 4]) -> bool| (0..48).find(|x| (0..32).any(|y| matches(pixel(*x, y))));
+        let painted = leftmost(|pixel| pixel[3] != 0).unwrap();
+        let fill = leftmost(|pixel| pixel[1] > 200 && pixel[3] == 255).unwrap();
+        let outline = leftmost(|pixel| pixel[0] > 200 && pixel[1] < 50 && pixel[3] == 255);
+
+        // The outline is under the fill and sticks out by its width.
+        assert!(fill - painted >= 2);
+        assert!(outline.is_some_and(|x| x < fill));
+    }
+
+    #[test]
+    #[ignore = "needs a GPU"]
+    fn test_text_selection_matches_tiny_skia() {
+        let mut vello = headless();
+        let mut tiny_skia = clew_tiny_skia::HeadlessRenderer::new();
+        let mut fonts = test_fonts();
+        let view = view_of_size(96, 24);
+        let mut texts = TextsResources::new();
+        let text_id = texts.add_text(&view, &mut fonts, 20., 24., |fonts, text| {
+            text.set_text(fonts, "HHHH")
+        });
+        texts.shape_as_needed(text_id, &mut fonts.font_system, false);
+
+        let selection_start = texts
+            .get(text_id)
+            .buffer()
+            .layout_runs()
+            .next()
+            .unwrap()
+            .glyphs[2]
+            .x
+            .round();
+        let selection = Rect::new(selection_start, 0., 96. - selection_start, 24.);
+        let text = |tint: u32| RenderCommand::Text {
+            x: 0.,
+            y: 0.,
+            text_id,
+            tint_color: Some(ColorRgba::from_hex(tint)),
+            visible_band: None,
+            clip: None,
+            stroke: None,
+        };
+        let rect = |boundary: Rect, color: u32| RenderCommand::Rect {
+            boundary,
+            fill: Some(Fill::Color(ColorRgba::from_hex(color))),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        };
+
+        // The order the editable text emits its commands in: the selection, the text,
+        // the selected text over it and the caret on top.
+        let commands = [
+            rect(selection, 0xFF0000FF),
+            text(0xFFFF0000),
+            RenderCommand::PushClip {
+                rect: selection,
+                shape: ClipShape::Rect,
+            },
+            text(0xFF00FF00),
+            RenderCommand::PopClip,
+            rect(Rect::new(selection_start, 0., 1., 24.), 0xFFFFFFFF),
+        ];
+        let mut render = |renderer: &mut dyn Renderer| {
+            renderer
+                .render_image(
+                    &commands,
+                    96,
+                    24,
+                    None,
+                    &mut fonts,
+                    &mut texts,
+                    &Assets::new(),
+                )
+                .unwrap()
+        };
+        let expected = render(&mut tiny_skia);
+        let rendered = render(&mut vello);
+        let pixel = |image: &RgbaImage, x: u32, y: u32| {
+            let index = ((y * image.width + x) * 4) as usize;
+
+            [
+                image.pixels[index] as i32,
+                image.pixels[index + 1] as i32,
+                image.pixels[index + 2] as i32,
+                image.pixels[index + 3] as i32,
+            ]
+        };
+
+        // The caret isn't covered by the glyphs in either.
+        for y in 0..24 {
+            assert_eq!(
+                pixel(&rendered, selection_start as u32, y),
+                [0xFF, 0xFF, 0xFF, 0xFF],
+                "caret pixel at {y}"
+            );
+        }
+
+        // Tiny-skia draws the glyphs hinted and vello doesn't, their edges move by a
+        // fraction of a pixel. The colors are compared premultiplied and averaged over
+        // blocks of 4x4, a glyph drawn over the selection or under it would be off by far
+        // more.
+        for block_y in (0..24).step_by(4) {
+            for block_x in (0..96).step_by(4) {
+                let average = |image: &RgbaImage| {
+                    let mut sum = [0; 4];
+
+                    for y in block_y..block_y + 4 {
+                        for x in block_x..block_x + 4 {
+                            let index = ((y * image.width + x) * 4) as usize;
+                            let pixel = premultiplied(&image.pixels[index..index + 4]);
+
+                            for (sum, channel) in sum.iter_mut().zip(pixel) {
+                                *sum += channel as i32;
+                            }
+                        }
+                    }
+
+                    sum.map(|sum| sum / 16)
+                };
+                let expected = average(&expected);
+                let rendered = average(&rendered);
+
+                assert!(
+                    expected
+                        .iter()
+                        .zip(rendered)
+                        .all(|(expected, rendered)| expected.abs_diff(rendered) <= 32),
+                    "block at {block_x}, {block_y} is {rendered:?}, expected {expected:?}"
+                );
+            }
+        }
+    }
 }
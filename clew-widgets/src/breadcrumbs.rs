@@ -0,0 +1,455 @@
+use std::ops::Range;
+
+use clew::io::UserInput;
+use clew::keyboard::KeyCode;
+use clew::prelude::*;
+use clew::stateful::StatefulWidget;
+use clew::text::MeasureTextStyle;
+use clew::{
+    AlignY, Border, BorderRadius, BorderSide, Clip, CrossAxisAlignment, EdgeInsets,
+    LayoutDirection, Rect, View, WidgetTheme, curves, widgets::*,
+};
+use clew_derive::{WidgetBuilder, WidgetState};
+
+use crate::{HOVER_TRANSITION, is_key_pressed};
+
+const CRUMB_PADDING_X: f32 = 6.;
+const CRUMB_PADDING_Y: f32 = 4.;
+const SEPARATOR_PADDING_X: f32 = 2.;
+const OVERFLOW_LABEL: &str = "…";
+const MENU_ROW_HEIGHT: f32 = 24.;
+const MENU_PADDING: f32 = 4.;
+/// Space between the overflow button and its menu.
+const MENU_SPACING: f32 = 4.;
+
+/// Segment of a [`breadcrumbs`] trail, a plain `&str` is a crumb with the label.
+#[derive(Debug, Clone, Copy)]
+pub struct Crumb<'a> {
+    label: &'a str,
+}
+
+impl<'a> Crumb<'a> {
+    pub fn new(label: &'a str) -> Self {
+        Self { label }
+    }
+}
+
+impl<'a> From<&'a str> for Crumb<'a> {
+    fn from(label: &'a str) -> Self {
+        Crumb::new(label)
+    }
+}
+
+#[derive(WidgetBuilder)]
+pub struct BreadcrumbsBuilder<'a> {
+    frame: FrameBuilder,
+    crumbs: Vec<Crumb<'a>>,
+}
+
+pub struct BreadcrumbsResponse {
+    clicked: Option<usize>,
+}
+
+impl BreadcrumbsResponse {
+    /// Index of the crumb clicked in this frame, in the trail or in the overflow menu.
+    pub fn clicked(&self) -> Option<usize> {
+        self.clicked
+    }
+}
+
+impl<'a> BreadcrumbsBuilder<'a> {
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> BreadcrumbsResponse {
+        let mut data = BreadcrumbsData {
+            labels: self.crumbs.iter().map(|it| it.label.to_string()).collect(),
+            clicked: None,
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<Breadcrumbs>().frame(self.frame).build(ctx);
+        });
+
+        BreadcrumbsResponse {
+            clicked: data.clicked,
+        }
+    }
+}
+
+/// Horizontal trail of the crumbs separated by chevrons, the last crumb is the current
+/// one and can't be clicked. When the trail doesn't fit into its width, the crumbs
+/// after the first one are collapsed into an overflow menu, starting with the farthest
+/// from the current crumb. The trail follows the layout direction of the view.
+#[track_caller]
+pub fn breadcrumbs<'a, C>(crumbs: &'a [C]) -> BreadcrumbsBuilder<'a>
+where
+    C: Into<Crumb<'a>> + Copy,
+{
+    BreadcrumbsBuilder {
+        frame: FrameBuilder::new(),
+        crumbs: crumbs.iter().map(|it| (*it).into()).collect(),
+    }
+}
+
+/// Labels the trail is built from this frame. The state measures them to collapse the
+/// trail and sets `clicked` to the crumb picked in the trail or in the overflow menu.
+#[derive(Default)]
+struct BreadcrumbsData {
+    labels: Vec<String>,
+    clicked: Option<usize>,
+}
+
+#[derive(WidgetState, Default)]
+struct Breadcrumbs {
+    /// Width the trail got in the last layout.
+    available_width: Option<f32>,
+    menu_open: bool,
+    overflow_rect: Option<Rect>,
+    menu_rect: Option<Rect>,
+    mouse_was_pressed: bool,
+}
+
+/// Crumbs moved into the overflow menu so the trail of the `widths` fits into
+/// `available`, empty if it fits as it is. The first and the last crumb always stay,
+/// the crumbs right after the first go first, so the ones closest to the current crumb
+/// stay the longest.
+fn collapsed_crumbs(widths: &[f32], separator: f32, overflow: f32, available: f32) -> Range<usize> {
+    let count = widths.len();
+    let total = widths.iter().sum::<f32>() + separator * count.saturating_sub(1) as f32;
+
+    if total <= available || count < 3 {
+        return 0..0;
+    }
+
+    // The overflow button with its separator takes the place of the collapsed crumbs.
+    let mut width = total + overflow + separator;
+
+    for end in 2..count {
+        width -= widths[end - 1] + separator;
+
+        if width <= available {
+            return 1..end;
+        }
+    }
+
+    1..count - 1
+}
+
+fn chevron(layout_direction: LayoutDirection) -> &'static str {
+    match layout_direction {
+        LayoutDirection::LTR => "›",
+        LayoutDirection::RTL => "‹",
+    }
+}
+
+impl Breadcrumbs {
+    fn collapsed(&self, ctx: &mut BuildContext, data: &BreadcrumbsData) -> Range<usize> {
+        let Some(available) = self.available_width else {
+            return 0..0;
+        };
+
+        let widths = data
+            .labels
+            .iter()
+            .map(|label| {
+                ctx.measure_text(label, MeasureTextStyle::default()).x + CRUMB_PADDING_X * 2.
+            })
+            .collect::<Vec<_>>();
+        let separator = ctx
+            .measure_text(chevron(ctx.layout_direction()), MeasureTextStyle::default())
+            .x
+            + SEPARATOR_PADDING_X * 2.;
+        let overflow = ctx
+            .measure_text(OVERFLOW_LABEL, MeasureTextStyle::default())
+            .x
+            + CRUMB_PADDING_X * 2.;
+
+        collapsed_crumbs(&widths, separator, overflow, available)
+    }
+
+    /// Closes the menu on Escape and on a press outside of it and its button.
+    fn handle_menu_input(&mut self, input: &UserInput, view: &View) {
+        let mouse = (
+            input.mouse_x / view.scale_factor,
+            input.mouse_y / view.scale_factor,
+        );
+        let pressed = input.mouse_left_pressed && !self.mouse_was_pressed;
+        let inside = |rect: Option<Rect>| {
+            rect.is_some_and(|rect| {
+                mouse.0 >= rect.left()
+                    && mouse.0 < rect.right()
+                    && mouse.1 >= rect.top()
+                    && mouse.1 < rect.bottom()
+            })
+        };
+
+        if (pressed && !inside(self.overflow_rect) && !inside(self.menu_rect))
+            || is_key_pressed(input, KeyCode::Escape)
+        {
+            self.close_menu();
+        }
+    }
+
+    fn close_menu(&mut self) {
+        self.menu_open = false;
+        self.menu_rect = None;
+    }
+}
+
+impl StatefulWidget for Breadcrumbs {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut BuildContext, mut frame: FrameBuilder) {
+        let Some(mut data) = ctx.of_mut::<BreadcrumbsData>().map(std::mem::take) else {
+            return;
+        };
+
+        let theme = ctx.widget_theme();
+        let collapsed = self.collapsed(ctx, &data);
+
+        if collapsed.is_empty() {
+            self.close_menu();
+        }
+
+        if self.menu_open {
+            self.handle_menu_input(ctx.input(), ctx.view());
+        }
+
+        self.mouse_was_pressed = ctx.input().mouse_left_pressed;
+
+        frame.build(ctx, |ctx| {
+            // Doesn't fit even collapsed, the trail is cut off at the end.
+            let rect = measure()
+                .fill_max_width()
+                .clip(Clip::Rect)
+                .build(ctx, |ctx| {
+                    hstack()
+                        .rtl_aware(true)
+                        .spacing(0.)
+                        .cross_axis_alignment(CrossAxisAlignment::Center)
+                        .build(ctx, |ctx| {
+                            self.build_trail(ctx, &mut data, &theme, collapsed.clone());
+                        });
+                });
+
+            self.available_width = rect.map(|rect| rect.width);
+        });
+
+        if self.menu_open
+            && let Some(overflow_rect) = self.overflow_rect
+        {
+            self.build_menu(ctx, &mut data, &theme, collapsed, overflow_rect);
+        }
+
+        if let Some(shared) = ctx.of_mut::<BreadcrumbsData>() {
+            *shared = data;
+        }
+    }
+}
+
+impl Breadcrumbs {
+    fn build_trail(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &mut BreadcrumbsData,
+        theme: &WidgetTheme,
+        collapsed: Range<usize>,
+    ) {
+        let count = data.labels.len();
+        let separator = chevron(ctx.layout_direction());
+
+        for index in 0..count {
+            if collapsed.contains(&index) && index != collapsed.start {
+                continue;
+            }
+
+            // The crumbs are built at the same place, the scope keeps the states of
+            // their widgets apart.
+            scope(index).build(ctx, |ctx| {
+                if index > 0 {
+                    text(separator)
+                        .color(theme.secondary_text)
+                        .padding(EdgeInsets::symmetric(SEPARATOR_PADDING_X, 0.))
+                        .build(ctx);
+                }
+
+                if index == collapsed.start && !collapsed.is_empty() {
+                    self.overflow_rect = measure().build(ctx, |ctx| {
+                        if crumb_button(ctx, theme, OVERFLOW_LABEL, false, false) {
+                            self.menu_open = !self.menu_open;
+                        }
+                    });
+                } else {
+                    let current = index + 1 == count;
+
+                    if crumb_button(ctx, theme, &data.labels[index], current, false) {
+                        data.clicked = Some(index);
+                    }
+                }
+            });
+        }
+    }
+
+    fn build_menu(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &mut BreadcrumbsData,
+        theme: &WidgetTheme,
+        collapsed: Range<usize>,
+        overflow_rect: Rect,
+    ) {
+        let width = data.labels[collapsed.clone()]
+            .iter()
+            .map(|label| ctx.measure_text(label, MeasureTextStyle::default()).x)
+            .fold(0., f32::max)
+            + (CRUMB_PADDING_X + MENU_PADDING) * 2.;
+        let height = collapsed.len() as f32 * MENU_ROW_HEIGHT + MENU_PADDING * 2.;
+        let x = match ctx.layout_direction() {
+            LayoutDirection::LTR => overflow_rect.left(),
+            LayoutDirection::RTL => overflow_rect.right() - width,
+        };
+        let menu_rect = Rect::new(x, overflow_rect.bottom() + MENU_SPACING, width, height);
+        self.menu_rect = Some(menu_rect);
+
+        // The menu escapes the clips of the ancestors of the trail and takes the
+        // pointer before the widgets it covers.
+        portal().rect(menu_rect).build(ctx, |ctx| {
+            vstack()
+                .fill_max_size()
+                .spacing(0.)
+                .padding(EdgeInsets::all(MENU_PADDING))
+                .background(
+                    decoration()
+                        .color(theme.surface)
+                        .border_radius(BorderRadius::all(3.))
+                        .border(Border::all(BorderSide::new(1., theme.border)))
+                        .build(ctx),
+                )
+                .build(ctx, |ctx| {
+                    for index in collapsed {
+                        scope(index).build(ctx, |ctx| {
+                            if crumb_button(ctx, theme, &data.labels[index], false, true) {
+                                data.clicked = Some(index);
+                                self.close_menu();
+                            }
+                        });
+                    }
+                });
+        });
+    }
+}
+
+/// Crumb that is clicked with the pointer, or with Enter while focused. Returns whether
+/// it has been clicked. The `current` crumb is only a label.
+fn crumb_button(
+    ctx: &mut BuildContext,
+    theme: &WidgetTheme,
+    label: &str,
+    current: bool,
+    menu_row: bool,
+) -> bool {
+    let gesture = gesture_detector()
+        .clickable(!current)
+        .focusable(!current)
+        .build(ctx, |ctx| {
+            let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+            let color = if current || gesture.is_hot() {
+                theme.text
+            } else {
+                theme.secondary_text
+            };
+            let background = if gesture.is_hot() && !current {
+                theme.border.with_opacity(0.5)
+            } else {
+                theme.border.with_opacity(0.)
+            };
+
+            let mut crumb = text(label)
+                .color(color)
+                .text_vertical_align(AlignY::Center)
+                .padding(EdgeInsets::symmetric(CRUMB_PADDING_X, CRUMB_PADDING_Y))
+                .background(
+                    decoration()
+                        .color(background)
+                        .border_radius(BorderRadius::all(3.))
+                        .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                        .build(ctx),
+                );
+
+            if menu_row {
+                crumb = crumb.fill_max_width().height(MENU_ROW_HEIGHT);
+            }
+
+            if gesture.is_focus_visible() {
+                crumb = crumb.foreground(
+                    decoration()
+                        .border_radius(BorderRadius::all(3.))
+                        .border(Border::all(BorderSide::new(2., theme.accent)))
+                        .build(ctx),
+                );
+            }
+
+            crumb.build(ctx);
+        });
+
+    gesture.clicked() || (gesture.is_focused() && is_key_pressed(ctx.input(), KeyCode::Enter))
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::io::InputEvent;
+    use clew::testing;
+
+    use super::*;
+
+    #[test]
+    fn test_collapsed_crumb_is_clicked_in_the_overflow_menu() {
+        let (mut host, mut instance) = testing::instance(300, 200);
+        let labels = ["Home", "Documents", "Projects", "Clew", "Readme"];
+        let mut frame = |key_code: Option<KeyCode>| {
+            if let Some(key_code) = key_code {
+                instance.push_event(InputEvent::Key {
+                    key_code: Some(key_code),
+                    pressed: true,
+                    repeat: false,
+                    text: None,
+                });
+            }
+
+            let mut clicked = None;
+
+            instance.frame(&mut host, &mut |ctx| {
+                clicked = breadcrumbs(&labels).width(100.).build(ctx).clicked();
+            });
+
+            clicked
+        };
+
+        // Collapsed once the trail has been laid out.
+        frame(None);
+        frame(None);
+
+        // The first crumb, then the overflow button.
+        frame(Some(KeyCode::Tab));
+        frame(Some(KeyCode::Tab));
+        assert_eq!(frame(Some(KeyCode::Enter)), None);
+
+        // Only the first and the current crumb fit, the first row of the menu.
+        frame(Some(KeyCode::Tab));
+        assert_eq!(frame(Some(KeyCode::Enter)), Some(1));
+    }
+
+    #[test]
+    fn test_collapsed_crumbs_keep_the_first_and_the_closest() {
+        let widths = [50., 50., 50., 50., 50.];
+
+        // 5 crumbs and 4 separators of 10.
+        assert_eq!(collapsed_crumbs(&widths, 10., 20., 290.), 0..0);
+        // Without the second crumb and with the overflow button: 260.
+        assert_eq!(collapsed_crumbs(&widths, 10., 20., 280.), 1..2);
+        assert_eq!(collapsed_crumbs(&widths, 10., 20., 220.), 1..3);
+        // Doesn't fit even collapsed, everything but the first and the last goes.
+        assert_eq!(collapsed_crumbs(&widths, 10., 20., 10.), 1..4);
+        // Nothing to collapse in between.
+        assert_eq!(collapsed_crumbs(&widths[..2], 10., 20., 10.), 0..0);
+    }
+}
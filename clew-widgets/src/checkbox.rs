@@ -0,0 +1,341 @@
+use clew::assets::Assets;
+use clew::keyboard::KeyCode;
+use clew::prelude::*;
+use clew::widgets::shortcuts::shortcut_scope;
+use clew::{
+    AlignX, AlignY, Border, BorderRadius, BorderSide, ColorRgba, CrossAxisAlignment, KeyBinding,
+    ShortcutsRegistry, curves, widgets::*,
+};
+use clew_derive::{ShortcutId, ShortcutScopeId, WidgetBuilder};
+
+use crate::HOVER_TRANSITION;
+
+const CHECKBOX_SIZE: f32 = 14.;
+
+/// Asset id of the tick, see [`add_checkbox_assets`].
+pub const CHECKMARK_SVG: &str = "clew_widgets::checkmark";
+
+/// White tick on a box of the checkbox size, it's tinted when drawn.
+const CHECKMARK: &[u8] = br##"<svg xmlns="http://www.w3.org/2000/svg" width="14" height="14" viewBox="0 0 14 14">
+<path d="M3.4 7.3 L5.9 9.8 L10.6 4.5" fill="none" stroke="#FFFFFF" stroke-width="2" stroke-linecap="round" stroke-linejoin="round"/>
+</svg>"##;
+
+#[derive(WidgetBuilder)]
+pub struct CheckboxBuilder<'a> {
+    frame: FrameBuilder,
+    checked: &'a mut bool,
+    label: Option<&'a str>,
+    disabled: bool,
+}
+
+pub struct CheckboxResponse {
+    changed: bool,
+}
+
+impl CheckboxResponse {
+    /// The checkbox was toggled in this frame by a click or the keyboard.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+#[derive(ShortcutScopeId)]
+pub struct ShortcutScopeCheckbox;
+
+#[derive(ShortcutId)]
+pub enum CheckboxShortcut {
+    Toggle,
+}
+
+/// Binds Space to toggle the focused checkbox. Adding a shortcut with the same id
+/// afterwards replaces the default binding.
+pub fn add_checkbox_shortcuts(registry: &mut ShortcutsRegistry) {
+    registry
+        .scope(ShortcutScopeCheckbox)
+        .add(CheckboxShortcut::Toggle, KeyBinding::new(KeyCode::Space));
+}
+
+/// Loads the tick of the checked checkboxes, without it the box is drawn filled but
+/// empty.
+pub fn add_checkbox_assets(assets: &mut Assets) {
+    assets
+        .load_svg(CHECKMARK_SVG, CHECKMARK)
+        .expect("The checkmark SVG is valid");
+}
+
+impl<'a> CheckboxBuilder<'a> {
+    /// Text after the box, a click on it toggles the checkbox too.
+    pub fn label(mut self, label: &'a str) -> Self {
+        self.label = Some(label);
+
+        self
+    }
+
+    /// A disabled checkbox can't be toggled or focused and is drawn dimmed.
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> CheckboxResponse {
+        let Self {
+            mut frame,
+            checked,
+            label,
+            disabled,
+        } = self;
+        let mut changed = false;
+
+        frame.build(ctx, |ctx| {
+            gesture_detector()
+                .clickable(!disabled)
+                .focusable(!disabled)
+                .build(ctx, |ctx| {
+                    let response = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+
+                    shortcut_scope(ShortcutScopeCheckbox)
+                        .active(response.is_focused())
+                        .build(ctx, |ctx| {
+                            if !disabled
+                                && (response.clicked() || ctx.is_shortcut(CheckboxShortcut::Toggle))
+                            {
+                                *checked = !*checked;
+                                changed = true;
+                            }
+
+                            build_checkbox(ctx, label, *checked, disabled, &response);
+                        });
+                });
+        });
+
+        CheckboxResponse { changed }
+    }
+}
+
+/// Checkbox with an optional label that toggles `checked`, Space toggles it while it's
+/// focused once the shortcuts are added with [`add_checkbox_shortcuts`]. The tick is
+/// loaded with [`add_checkbox_assets`].
+#[track_caller]
+pub fn checkbox(checked: &mut bool) -> CheckboxBuilder<'_> {
+    CheckboxBuilder {
+        frame: FrameBuilder::new(),
+        checked,
+        label: None,
+        disabled: false,
+    }
+}
+
+fn build_checkbox(
+    ctx: &mut BuildContext,
+    label: Option<&str>,
+    checked: bool,
+    disabled: bool,
+    response: &GestureDetectorResponse,
+) {
+    let theme = ctx.widget_theme();
+    let hot = !disabled && response.is_hot();
+    let border = if response.is_focus_visible() {
+        BorderSide::new(2., theme.accent)
+    } else if disabled {
+        BorderSide::new(1., theme.border.with_opacity(0.5))
+    } else if hot {
+        BorderSide::new(1., theme.secondary_text)
+    } else {
+        BorderSide::new(1., theme.border)
+    };
+    let background = match (checked, disabled) {
+        (true, false) => theme.accent,
+        (true, true) => theme.border,
+        (false, _) => theme.surface,
+    };
+    let text_color = if disabled {
+        theme.secondary_text
+    } else {
+        theme.text
+    };
+
+    hstack()
+        .spacing(8.)
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .build(ctx, |ctx| {
+            zstack()
+                .width(CHECKBOX_SIZE)
+                .height(CHECKBOX_SIZE)
+                .align_x(AlignX::Center)
+                .align_y(AlignY::Center)
+                .background(
+                    decoration()
+                        .color(background)
+                        .border_radius(BorderRadius::all(3.))
+                        .border(Border::all(border))
+                        .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                        .build(ctx),
+                )
+                .build(ctx, |ctx| {
+                    if checked {
+                        let tick = if disabled {
+                            theme.secondary_text
+                        } else {
+                            ColorRgba::from_hex(0xFFFFFFFF)
+                        };
+
+                        svg(CHECKMARK_SVG).size(CHECKBOX_SIZE).tint(tick).build(ctx);
+                    }
+                });
+
+            if let Some(label) = label {
+                text(label)
+                    .color(text_color)
+                    .text_vertical_align(AlignY::Center)
+                    .build(ctx);
+            }
+        });
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::authoring::{Fill, RenderCommand};
+    use clew::io::{InputEvent, MouseButton};
+    use clew::testing;
+    use clew::{ClewHost, ClewInstance, WidgetTheme};
+
+    use super::*;
+
+    fn instance<'a>() -> (ClewHost<'a>, ClewInstance<'a>) {
+        testing::instance(200, 100)
+    }
+
+    fn click(x: f32, y: f32) -> [Option<InputEvent>; 3] {
+        [
+            Some(InputEvent::PointerMoved { x, y }),
+            Some(InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: true,
+            }),
+            Some(InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: false,
+            }),
+        ]
+    }
+
+    fn key(key_code: KeyCode) -> Option<InputEvent> {
+        Some(InputEvent::Key {
+            key_code: Some(key_code),
+            pressed: true,
+            repeat: false,
+            text: None,
+        })
+    }
+
+    #[test]
+    fn test_click_on_box_or_label_toggles_unless_disabled() {
+        let (mut host, mut instance) = instance();
+        let mut checked = false;
+        let mut disabled = false;
+        let mut changes = 0;
+        let mut frame = |event: Option<InputEvent>, disabled: bool| {
+            if let Some(event) = event {
+                instance.push_event(event);
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                if checkbox(&mut checked)
+                    .label("Remember me")
+                    .disabled(disabled)
+                    .build(ctx)
+                    .changed()
+                {
+                    changes += 1;
+                }
+            });
+
+            checked
+        };
+
+        frame(None, disabled);
+
+        // The box, then the label.
+        for event in click(7., 7.) {
+            frame(event, disabled);
+        }
+        assert!(frame(None, disabled));
+
+        for event in click(40., 7.) {
+            frame(event, disabled);
+        }
+        assert!(!frame(None, disabled));
+
+        disabled = true;
+        for event in click(7., 7.) {
+            frame(event, disabled);
+        }
+        assert!(!frame(None, disabled));
+        assert_eq!(changes, 2);
+    }
+
+    #[test]
+    fn test_space_toggles_the_focused_checkbox() {
+        let (mut host, mut instance) = instance();
+        let mut checked = false;
+        let mut changes = 0;
+
+        add_checkbox_shortcuts(instance.ui_state().shortcuts_registry());
+
+        let mut frame = |event: Option<InputEvent>| {
+            if let Some(event) = event {
+                instance.push_event(event);
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                if checkbox(&mut checked).build(ctx).changed() {
+                    changes += 1;
+                }
+            });
+
+            checked
+        };
+
+        frame(None);
+        // Space does nothing until the checkbox has the focus.
+        frame(key(KeyCode::Space));
+        assert!(!frame(None));
+
+        frame(key(KeyCode::Tab));
+        frame(key(KeyCode::Space));
+        assert!(frame(None));
+
+        frame(key(KeyCode::Space));
+        assert!(!frame(None));
+        assert_eq!(changes, 2);
+    }
+
+    #[test]
+    fn test_checked_box_and_tick_take_the_theme_colors() {
+        let (mut host, mut instance) = instance();
+        let theme = WidgetTheme::light(ColorRgba::from_hex(0xFF2E7D32));
+        let mut checked = true;
+
+        add_checkbox_assets(&mut host.assets);
+        host.resources.insert(theme);
+
+        let render_state = instance
+            .frame(&mut host, &mut |ctx| {
+                checkbox(&mut checked).build(ctx);
+            })
+            .unwrap();
+        let commands = render_state.commands();
+
+        assert!(commands.iter().any(|command| matches!(
+            command,
+            RenderCommand::Rect { fill: Some(Fill::Color(color)), .. } if *color == theme.accent
+        )));
+        assert!(commands.iter().any(|command| matches!(
+            command,
+            RenderCommand::Svg { asset_id: CHECKMARK_SVG, tint_color: Some(color), .. }
+                if *color == ColorRgba::from_hex(0xFFFFFFFF)
+        )));
+    }
+}
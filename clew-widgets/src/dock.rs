@@ -0,0 +1,1094 @@
+use clew::prelude::*;
+use clew::{
+    Border, BorderSide, Clip, CrossAxisAlignment, EdgeInsets, Rect, SizeConstraint, StateValue,
+    Vec2, WidgetTheme, curves, widgets::*,
+};
+use clew_derive::WidgetBuilder;
+
+use crate::HOVER_TRANSITION;
+
+const TAB_BAR_HEIGHT: f32 = 26.;
+const TAB_PADDING_X: f32 = 10.;
+const DIVIDER_SIZE: f32 = 4.;
+/// Part of a group along each of its edges where a drop docks the panel beside it.
+const EDGE_ZONE: f32 = 0.25;
+/// Smallest part of a split either side can be resized to.
+const MIN_RATIO: f32 = 0.1;
+/// Distance the pointer has to move with a tab held before the panel is dragged, so
+/// clicking a tab doesn't move it.
+const DRAG_THRESHOLD: f32 = 6.;
+
+/// How the two sides of a split are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DockAxis {
+    /// Side by side.
+    Horizontal,
+    /// One above the other.
+    Vertical,
+}
+
+/// Where a panel goes relative to the group it's dropped on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DockZone {
+    Left,
+    Right,
+    Top,
+    Bottom,
+    /// Into the group, as one more tab.
+    Center,
+}
+
+impl DockZone {
+    fn name(self) -> &'static str {
+        match self {
+            DockZone::Left => "left",
+            DockZone::Right => "right",
+            DockZone::Top => "top",
+            DockZone::Bottom => "bottom",
+            DockZone::Center => "center",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "left" => DockZone::Left,
+            "right" => DockZone::Right,
+            "top" => DockZone::Top,
+            "bottom" => DockZone::Bottom,
+            "center" => DockZone::Center,
+            _ => return None,
+        })
+    }
+}
+
+/// Node of the tree of a [`DockLayout`], the panels are told apart by their names, each
+/// one is in the tree at most once.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DockNode {
+    /// Two nodes sharing the space, `ratio` is the part of it the first one takes.
+    Split {
+        axis: DockAxis,
+        ratio: f32,
+        first: Box<DockNode>,
+        second: Box<DockNode>,
+    },
+    /// Group of panels shown one at a time under a bar of their tabs.
+    Tabs { panels: Vec<String>, active: usize },
+}
+
+impl DockNode {
+    pub fn tabs<S: Into<String>>(panels: impl IntoIterator<Item = S>) -> Self {
+        DockNode::Tabs {
+            panels: panels.into_iter().map(Into::into).collect(),
+            active: 0,
+        }
+    }
+
+    pub fn split(axis: DockAxis, ratio: f32, first: DockNode, second: DockNode) -> Self {
+        DockNode::Split {
+            axis,
+            ratio: ratio.clamp(MIN_RATIO, 1. - MIN_RATIO),
+            first: Box::new(first),
+            second: Box::new(second),
+        }
+    }
+
+    fn contains(&self, panel: &str) -> bool {
+        match self {
+            DockNode::Split { first, second, .. } => {
+                first.contains(panel) || second.contains(panel)
+            }
+            DockNode::Tabs { panels, .. } => panels.iter().any(|it| it == panel),
+        }
+    }
+
+    fn first_panel(&self) -> Option<&str> {
+        match self {
+            DockNode::Split { first, .. } => first.first_panel(),
+            DockNode::Tabs { panels, .. } => panels.first().map(String::as_str),
+        }
+    }
+
+    /// The group with the panel.
+    fn group_mut(&mut self, panel: &str) -> Option<&mut DockNode> {
+        match self {
+            DockNode::Split { first, second, .. } => {
+                first.group_mut(panel).or_else(|| second.group_mut(panel))
+            }
+            DockNode::Tabs { panels, .. } => {
+                if panels.iter().any(|it| it == panel) {
+                    Some(self)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// Node at the path of the sides of the splits taken from this one, `true` for the
+    /// second side.
+    fn at_mut(&mut self, path: &[bool]) -> Option<&mut DockNode> {
+        match (self, path.split_first()) {
+            (node, None) => Some(node),
+            (DockNode::Split { first, second, .. }, Some((side, rest))) => {
+                if *side { second } else { first }.at_mut(rest)
+            }
+            (DockNode::Tabs { .. }, Some(_)) => None,
+        }
+    }
+
+    /// The node without the panel, `None` when nothing is left of it. A split with an
+    /// empty side is replaced with the other one.
+    fn without(self, panel: &str) -> Option<DockNode> {
+        match self {
+            DockNode::Split {
+                axis,
+                ratio,
+                first,
+                second,
+            } => match (first.without(panel), second.without(panel)) {
+                (Some(first), Some(second)) => Some(DockNode::Split {
+                    axis,
+                    ratio,
+                    first: Box::new(first),
+                    second: Box::new(second),
+                }),
+                (Some(node), None) | (None, Some(node)) => Some(node),
+                (None, None) => None,
+            },
+            DockNode::Tabs {
+                mut panels,
+                mut active,
+            } => {
+                if let Some(index) = panels.iter().position(|it| it == panel) {
+                    panels.remove(index);
+
+                    if index < active {
+                        active -= 1;
+                    }
+                }
+
+                if panels.is_empty() {
+                    None
+                } else {
+                    Some(DockNode::Tabs {
+                        active: active.min(panels.len() - 1),
+                        panels,
+                    })
+                }
+            }
+        }
+    }
+
+    /// Panel and zone that put the panel back where it is, the next tab of its group or
+    /// the side of the split next to it when it's alone in the group.
+    fn anchor(&self, panel: &str) -> Option<(String, DockZone)> {
+        match self {
+            DockNode::Split {
+                axis,
+                first,
+                second,
+                ..
+            } => {
+                let (inner, other, zone) = if first.contains(panel) {
+                    let zone = match axis {
+                        DockAxis::Horizontal => DockZone::Left,
+                        DockAxis::Vertical => DockZone::Top,
+                    };
+
+                    (first, second, zone)
+                } else if second.contains(panel) {
+                    let zone = match axis {
+                        DockAxis::Horizontal => DockZone::Right,
+                        DockAxis::Vertical => DockZone::Bottom,
+                    };
+
+                    (second, first, zone)
+                } else {
+                    return None;
+                };
+
+                inner.anchor(panel).or_else(|| {
+                    other
+                        .first_panel()
+                        .map(|neighbor| (neighbor.to_string(), zone))
+                })
+            }
+            DockNode::Tabs { panels, .. } => panels
+                .iter()
+                .find(|it| *it != panel)
+                .map(|neighbor| (neighbor.clone(), DockZone::Center)),
+        }
+    }
+
+    fn save(&self) -> StateValue {
+        match self {
+            DockNode::Split {
+                axis,
+                ratio,
+                first,
+                second,
+            } => StateValue::List(vec![
+                StateValue::String("split".to_string()),
+                StateValue::String(
+                    match axis {
+                        DockAxis::Horizontal => "horizontal",
+                        DockAxis::Vertical => "vertical",
+                    }
+                    .to_string(),
+                ),
+                StateValue::Float(*ratio as f64),
+                first.save(),
+                second.save(),
+            ]),
+            DockNode::Tabs { panels, active } => StateValue::List(vec![
+                StateValue::String("tabs".to_string()),
+                StateValue::Int(*active as i64),
+                StateValue::List(panels.iter().cloned().map(StateValue::String).collect()),
+            ]),
+        }
+    }
+
+    fn load(value: &StateValue) -> Option<Self> {
+        match value.as_list()? {
+            [kind, axis, ratio, first, second] if kind.as_str() == Some("split") => {
+                let axis = match axis.as_str()? {
+                    "horizontal" => DockAxis::Horizontal,
+                    "vertical" => DockAxis::Vertical,
+                    _ => return None,
+                };
+
+                Some(DockNode::split(
+                    axis,
+                    ratio.as_f64()? as f32,
+                    DockNode::load(first)?,
+                    DockNode::load(second)?,
+                ))
+            }
+            [kind, active, panels] if kind.as_str() == Some("tabs") => {
+                let panels = panels
+                    .as_list()?
+                    .iter()
+                    .map(|it| it.as_str().map(str::to_string))
+                    .collect::<Option<Vec<_>>>()?;
+
+                if panels.is_empty() {
+                    return None;
+                }
+
+                Some(DockNode::Tabs {
+                    active: usize::try_from(active.as_i64()?)
+                        .ok()?
+                        .min(panels.len() - 1),
+                    panels,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Closed panel and where it goes back when it's restored.
+#[derive(Debug, Clone, PartialEq)]
+struct ClosedPanel {
+    panel: String,
+    anchor: Option<(String, DockZone)>,
+}
+
+/// Arrangement of the panels of a [`dock_space`], kept by the app so it can be changed
+/// from the code and saved with [`DockLayout::save`].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DockLayout {
+    root: Option<DockNode>,
+    closed: Vec<ClosedPanel>,
+    /// Panel dragged by its tab, once it has moved far enough. Not saved.
+    drag: Option<String>,
+}
+
+impl DockLayout {
+    pub fn new(root: DockNode) -> Self {
+        Self {
+            root: Some(root),
+            ..Default::default()
+        }
+    }
+
+    pub fn root(&self) -> Option<&DockNode> {
+        self.root.as_ref()
+    }
+
+    /// The panel is shown in the layout, it could be behind another tab of its group.
+    pub fn contains(&self, panel: &str) -> bool {
+        self.root.as_ref().is_some_and(|root| root.contains(panel))
+    }
+
+    /// Panels closed with [`DockLayout::close`] or their close buttons, in the order
+    /// they were closed.
+    pub fn closed_panels(&self) -> impl Iterator<Item = &str> {
+        self.closed.iter().map(|it| it.panel.as_str())
+    }
+
+    /// Moves the panel to the zone of the group with the `target` panel, a panel not in
+    /// the layout yet is added. Returns false when the target isn't in the layout or
+    /// the move would leave the panel where it is.
+    pub fn dock(&mut self, panel: &str, target: &str, zone: DockZone) -> bool {
+        let Some(root) = &mut self.root else {
+            return false;
+        };
+
+        if panel == target {
+            return false;
+        }
+
+        match root.group_mut(target) {
+            Some(DockNode::Tabs { panels, .. }) => {
+                if zone == DockZone::Center && panels.iter().any(|it| it == panel) {
+                    return false;
+                }
+            }
+            _ => return false,
+        }
+
+        let root = self.root.take().and_then(|root| root.without(panel));
+        self.closed.retain(|it| it.panel != panel);
+        self.root = root;
+
+        let Some(group) = self.root.as_mut().and_then(|root| root.group_mut(target)) else {
+            return false;
+        };
+
+        match zone {
+            DockZone::Center => {
+                if let DockNode::Tabs { panels, active } = group {
+                    panels.push(panel.to_string());
+                    *active = panels.len() - 1;
+                }
+            }
+            DockZone::Left | DockZone::Right | DockZone::Top | DockZone::Bottom => {
+                let axis = match zone {
+                    DockZone::Left | DockZone::Right => DockAxis::Horizontal,
+                    _ => DockAxis::Vertical,
+                };
+                let existing = std::mem::replace(group, DockNode::tabs(Vec::<String>::new()));
+                let added = DockNode::tabs([panel]);
+                let (first, second) = match zone {
+                    DockZone::Left | DockZone::Top => (added, existing),
+                    _ => (existing, added),
+                };
+
+                *group = DockNode::split(axis, 0.5, first, second);
+            }
+        }
+
+        true
+    }
+
+    /// Shows the panel in its group. Returns false when it's already shown or isn't in
+    /// the layout.
+    pub fn activate(&mut self, panel: &str) -> bool {
+        if let Some(DockNode::Tabs { panels, active }) =
+            self.root.as_mut().and_then(|root| root.group_mut(panel))
+            && let Some(index) = panels.iter().position(|it| it == panel)
+            && *active != index
+        {
+            *active = index;
+
+            return true;
+        }
+
+        false
+    }
+
+    /// Takes the panel out of the layout, the space of a group left empty goes to the
+    /// one next to it. Returns false when the panel isn't in the layout.
+    pub fn close(&mut self, panel: &str) -> bool {
+        let Some(root) = self.root.take() else {
+            return false;
+        };
+
+        if !root.contains(panel) {
+            self.root = Some(root);
+
+            return false;
+        }
+
+        let anchor = root.anchor(panel);
+
+        self.root = root.without(panel);
+        self.closed.push(ClosedPanel {
+            panel: panel.to_string(),
+            anchor,
+        });
+
+        true
+    }
+
+    /// Puts the closed panel back next to the panel it was closed beside, when that one
+    /// is gone it goes into the first group. Returns false when the panel isn't closed.
+    pub fn restore(&mut self, panel: &str) -> bool {
+        let Some(index) = self.closed.iter().position(|it| it.panel == panel) else {
+            return false;
+        };
+        let closed = self.closed.remove(index);
+
+        if let Some((neighbor, zone)) = &closed.anchor
+            && self.dock(panel, neighbor, *zone)
+        {
+            return true;
+        }
+
+        match self.root.as_ref().and_then(DockNode::first_panel) {
+            Some(first) => {
+                let first = first.to_string();
+
+                self.dock(panel, &first, DockZone::Center)
+            }
+            None => {
+                self.root = Some(DockNode::tabs([panel]));
+
+                true
+            }
+        }
+    }
+
+    /// Layout as a value to save with the rest of the state of the app, the closed
+    /// panels are saved with it.
+    pub fn save(&self) -> StateValue {
+        StateValue::List(vec![
+            StateValue::List(self.root.iter().map(DockNode::save).collect()),
+            StateValue::List(
+                self.closed
+                    .iter()
+                    .map(|closed| {
+                        let mut value = vec![StateValue::String(closed.panel.clone())];
+
+                        if let Some((neighbor, zone)) = &closed.anchor {
+                            value.push(StateValue::String(neighbor.clone()));
+                            value.push(StateValue::String(zone.name().to_string()));
+                        }
+
+                        StateValue::List(value)
+                    })
+                    .collect(),
+            ),
+        ])
+    }
+
+    /// Layout saved with [`DockLayout::save`], `None` when the value isn't one.
+    pub fn load(value: &StateValue) -> Option<Self> {
+        let [root, closed] = value.as_list()? else {
+            return None;
+        };
+        let root = match root.as_list()? {
+            [] => None,
+            [root] => Some(DockNode::load(root)?),
+            _ => return None,
+        };
+        let closed = closed
+            .as_list()?
+            .iter()
+            .map(|closed| match closed.as_list()? {
+                [panel] => Some(ClosedPanel {
+                    panel: panel.as_str()?.to_string(),
+                    anchor: None,
+                }),
+                [panel, neighbor, zone] => Some(ClosedPanel {
+                    panel: panel.as_str()?.to_string(),
+                    anchor: Some((
+                        neighbor.as_str()?.to_string(),
+                        DockZone::from_name(zone.as_str()?)?,
+                    )),
+                }),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(Self {
+            root,
+            closed,
+            drag: None,
+        })
+    }
+}
+
+#[derive(WidgetBuilder)]
+pub struct DockSpaceBuilder<'a> {
+    frame: FrameBuilder,
+    layout: &'a mut DockLayout,
+    closable: bool,
+}
+
+pub struct DockSpaceResponse {
+    changed: bool,
+}
+
+impl DockSpaceResponse {
+    /// The layout was changed in this frame by a drop, a close, a resize or a switch of
+    /// the tabs, e.g. to save it.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+/// Change made in the frame, applied to the layout once it's built.
+enum DockAction {
+    Activate(String),
+    Close(String),
+    /// New ratio of the split at the path.
+    Resize(Vec<bool>, f32),
+}
+
+/// Tab held in the frame.
+struct TabDrag {
+    panel: String,
+    state: DragState,
+    start: Vec2,
+    pointer: Vec2,
+}
+
+/// What the traversal of the tree collects for the end of the frame.
+struct DockPass<'c> {
+    closable: bool,
+    theme: WidgetTheme,
+    content: &'c mut dyn FnMut(&mut BuildContext, &str),
+    actions: Vec<DockAction>,
+    /// Panels of the groups with their rects from the previous layout.
+    groups: Vec<(Vec<String>, Rect)>,
+    drag: Option<TabDrag>,
+}
+
+impl<'a> DockSpaceBuilder<'a> {
+    /// Shows a close button on the tabs, true by default.
+    pub fn closable(mut self, closable: bool) -> Self {
+        self.closable = closable;
+        self
+    }
+
+    /// Builds the active panel of each group with `content`, which gets the name of the
+    /// panel. The content of a panel is built in a scope of its name, so its state is
+    /// kept when it's moved to another group.
+    #[profiling::function]
+    pub fn build(
+        self,
+        ctx: &mut BuildContext,
+        mut content: impl FnMut(&mut BuildContext, &str),
+    ) -> DockSpaceResponse {
+        let Self {
+            mut frame,
+            layout,
+            closable,
+        } = self;
+
+        let root = layout.root.take();
+        let mut pass = DockPass {
+            closable,
+            theme: ctx.widget_theme(),
+            content: &mut content,
+            actions: Vec::new(),
+            groups: Vec::new(),
+            drag: None,
+        };
+
+        frame.build(ctx, |ctx| {
+            if let Some(root) = &root {
+                build_node(ctx, root, &mut Vec::new(), &mut pass);
+            }
+        });
+
+        layout.root = root;
+
+        let mut changed = false;
+
+        match pass.drag {
+            Some(drag) if matches!(drag.state, DragState::Start | DragState::Update) => {
+                let offset = drag.pointer - drag.start;
+
+                if layout.drag.is_some() || offset.x.hypot(offset.y) >= DRAG_THRESHOLD {
+                    let target = drop_target(&pass.groups, &drag.panel, drag.pointer);
+
+                    build_drop_preview(
+                        ctx,
+                        &pass.theme,
+                        &drag.panel,
+                        drag.pointer,
+                        target.map(|(_, _, rect)| rect),
+                    );
+                    layout.drag = Some(drag.panel);
+                }
+            }
+            Some(drag) if drag.state == DragState::End => {
+                if layout.drag.take().is_some()
+                    && let Some((target, zone, _)) =
+                        drop_target(&pass.groups, &drag.panel, drag.pointer)
+                {
+                    changed |= layout.dock(&drag.panel, &target, zone);
+                }
+            }
+            _ => layout.drag = None,
+        }
+
+        for action in pass.actions {
+            changed |= match action {
+                DockAction::Activate(panel) => layout.activate(&panel),
+                DockAction::Close(panel) => layout.close(&panel),
+                DockAction::Resize(path, ratio) => {
+                    match layout.root.as_mut().and_then(|root| root.at_mut(&path)) {
+                        Some(DockNode::Split { ratio: current, .. }) if *current != ratio => {
+                            *current = ratio;
+
+                            true
+                        }
+                        _ => false,
+                    }
+                }
+            };
+        }
+
+        DockSpaceResponse { changed }
+    }
+}
+
+/// Panels in tabbed groups split by draggable dividers, like the tool windows of an
+/// IDE. A panel is dragged by its tab: dropped on the tab bar or the middle of a group
+/// it joins it as a tab, dropped near an edge of a group it splits the group. The part
+/// of the group it would take is highlighted while it's dragged.
+///
+/// Floating panels, in the view or in windows of their own, aren't supported.
+#[track_caller]
+pub fn dock_space(layout: &mut DockLayout) -> DockSpaceBuilder<'_> {
+    DockSpaceBuilder {
+        frame: FrameBuilder::new().fill_max_size(),
+        layout,
+        closable: true,
+    }
+}
+
+fn build_node(ctx: &mut BuildContext, node: &DockNode, path: &mut Vec<bool>, pass: &mut DockPass) {
+    match node {
+        DockNode::Split {
+            axis,
+            ratio,
+            first,
+            second,
+        } => {
+            // The nodes are built from the same place, they're told apart by their panels.
+            let key = ("dock-split", first.first_panel(), second.first_panel());
+
+            measure()
+                .id(key)
+                .fill_max_size()
+                .build_with_rect(ctx, |ctx, rect| {
+                    let children = |ctx: &mut BuildContext| {
+                        build_side(ctx, first, *axis, *ratio, false, path, pass);
+                        ctx.scope(key, |ctx| build_divider(ctx, *axis, rect, path, pass));
+                        build_side(ctx, second, *axis, 1. - ratio, true, path, pass);
+                    };
+
+                    match axis {
+                        DockAxis::Horizontal => {
+                            hstack().spacing(0.).fill_max_size().build(ctx, children)
+                        }
+                        DockAxis::Vertical => {
+                            vstack().spacing(0.).fill_max_size().build(ctx, children)
+                        }
+                    };
+                });
+        }
+        DockNode::Tabs { panels, active } => build_group(ctx, panels, *active, pass),
+    }
+}
+
+fn build_side(
+    ctx: &mut BuildContext,
+    node: &DockNode,
+    axis: DockAxis,
+    flex: f32,
+    second: bool,
+    path: &mut Vec<bool>,
+    pass: &mut DockPass,
+) {
+    let side = match axis {
+        DockAxis::Horizontal => zstack().width(SizeConstraint::Fill(flex)).fill_max_height(),
+        DockAxis::Vertical => zstack().fill_max_width().height(SizeConstraint::Fill(flex)),
+    };
+
+    side.clip(Clip::Rect).build(ctx, |ctx| {
+        path.push(second);
+        build_node(ctx, node, path, pass);
+        path.pop();
+    });
+}
+
+fn build_divider(
+    ctx: &mut BuildContext,
+    axis: DockAxis,
+    split: Option<Rect>,
+    path: &[bool],
+    pass: &mut DockPass,
+) {
+    gesture_detector().dragable(true).build(ctx, |ctx| {
+        let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+
+        if let Some(split) = split
+            && matches!(gesture.drag_state, DragState::Start | DragState::Update)
+        {
+            let pointer = match gesture.drag_state {
+                DragState::Start => Vec2::new(gesture.drag_start_x, gesture.drag_start_y),
+                _ => Vec2::new(gesture.drag_x, gesture.drag_y),
+            };
+
+            if let Some(ratio) = divider_ratio(split, axis, pointer) {
+                pass.actions.push(DockAction::Resize(path.to_vec(), ratio));
+            }
+        }
+
+        let color = if gesture.is_active() || gesture.is_hot() {
+            pass.theme.accent
+        } else {
+            pass.theme.border
+        };
+        let divider = decorated_box().color(color);
+
+        match axis {
+            DockAxis::Horizontal => divider.width(DIVIDER_SIZE).fill_max_height(),
+            DockAxis::Vertical => divider.fill_max_width().height(DIVIDER_SIZE),
+        }
+        .build(ctx);
+    });
+}
+
+/// Ratio of the split that puts the middle of its divider at the pointer.
+fn divider_ratio(split: Rect, axis: DockAxis, pointer: Vec2) -> Option<f32> {
+    let (start, length, pointer) = match axis {
+        DockAxis::Horizontal => (split.x, split.width, pointer.x),
+        DockAxis::Vertical => (split.y, split.height, pointer.y),
+    };
+    let space = length - DIVIDER_SIZE;
+
+    if space <= 0. {
+        return None;
+    }
+
+    Some(((pointer - start - DIVIDER_SIZE / 2.) / space).clamp(MIN_RATIO, 1. - MIN_RATIO))
+}
+
+fn build_group(ctx: &mut BuildContext, panels: &[String], active: usize, pass: &mut DockPass) {
+    let Some(active_panel) = panels.get(active).or(panels.first()) else {
+        return;
+    };
+    let theme = pass.theme;
+
+    let rect = measure()
+        .id(("dock-group", &panels[0]))
+        .fill_max_size()
+        .build(ctx, |ctx| {
+            vstack().spacing(0.).fill_max_size().build(ctx, |ctx| {
+                hstack()
+                    .spacing(0.)
+                    .fill_max_width()
+                    .height(TAB_BAR_HEIGHT)
+                    .background(decoration().color(theme.surface).build(ctx))
+                    .build(ctx, |ctx| {
+                        for panel in panels {
+                            ctx.scope(("dock-tab", panel), |ctx| {
+                                build_tab(ctx, panel, panel == active_panel, pass);
+                            });
+                        }
+                    });
+
+                zstack()
+                    .fill_max_size()
+                    .clip(Clip::Rect)
+                    .background(decoration().color(theme.background).build(ctx))
+                    .build(ctx, |ctx| {
+                        ctx.scope(("dock-panel", active_panel), |ctx| {
+                            (pass.content)(ctx, active_panel);
+                        });
+                    });
+            });
+        });
+
+    if let Some(rect) = rect {
+        pass.groups.push((panels.to_vec(), rect));
+    }
+}
+
+fn build_tab(ctx: &mut BuildContext, panel: &str, active: bool, pass: &mut DockPass) {
+    let theme = pass.theme;
+
+    gesture_detector()
+        .clickable(true)
+        .dragable(true)
+        .build(ctx, |ctx| {
+            let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+
+            if gesture.clicked() && !active {
+                pass.actions.push(DockAction::Activate(panel.to_string()));
+            }
+
+            if gesture.drag_state != DragState::None {
+                let start = Vec2::new(gesture.drag_start_x, gesture.drag_start_y);
+
+                pass.drag = Some(TabDrag {
+                    panel: panel.to_string(),
+                    state: gesture.drag_state,
+                    start,
+                    pointer: match gesture.drag_state {
+                        DragState::Start => start,
+                        _ => Vec2::new(gesture.drag_x, gesture.drag_y),
+                    },
+                });
+            }
+
+            let background = if active {
+                theme.background
+            } else if gesture.is_hot() {
+                theme.border
+            } else {
+                theme.surface
+            };
+
+            hstack()
+                .fill_max_height()
+                .cross_axis_alignment(CrossAxisAlignment::Center)
+                .background(
+                    decoration()
+                        .color(background)
+                        .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                        .build(ctx),
+                )
+                .padding(EdgeInsets::new().left(TAB_PADDING_X).right(TAB_PADDING_X))
+                .build(ctx, |ctx| {
+                    text(panel)
+                        .color(if active {
+                            theme.text
+                        } else {
+                            theme.secondary_text
+                        })
+                        .build(ctx);
+
+                    if pass.closable {
+                        build_close_button(ctx, panel, pass);
+                    }
+                });
+        });
+}
+
+fn build_close_button(ctx: &mut BuildContext, panel: &str, pass: &mut DockPass) {
+    let theme = pass.theme;
+
+    gesture_detector().clickable(true).build(ctx, |ctx| {
+        let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+
+        if gesture.clicked() {
+            pass.actions.push(DockAction::Close(panel.to_string()));
+        }
+
+        text("×")
+            .color(if gesture.is_hot() {
+                theme.text
+            } else {
+                theme.secondary_text
+            })
+            .padding(EdgeInsets::new().left(6.))
+            .build(ctx);
+    });
+}
+
+/// Zone of the group under the pointer and the part of the group the panel would take
+/// there. The tab bar is the center.
+fn zone_at(group: Rect, pointer: Vec2) -> (DockZone, Rect) {
+    if pointer.y < group.y + TAB_BAR_HEIGHT || group.width <= 0. || group.height <= 0. {
+        return (DockZone::Center, group);
+    }
+
+    let x = (pointer.x - group.x) / group.width;
+    let y = (pointer.y - group.y) / group.height;
+    let (distance, zone) = [
+        (x, DockZone::Left),
+        (1. - x, DockZone::Right),
+        (y, DockZone::Top),
+        (1. - y, DockZone::Bottom),
+    ]
+    .into_iter()
+    .fold((f32::INFINITY, DockZone::Center), |closest, edge| {
+        if edge.0 < closest.0 { edge } else { closest }
+    });
+
+    if distance >= EDGE_ZONE {
+        return (DockZone::Center, group);
+    }
+
+    let (half_width, half_height) = (group.width / 2., group.height / 2.);
+    let rect = match zone {
+        DockZone::Left => Rect::new(group.x, group.y, half_width, group.height),
+        DockZone::Right => Rect::new(group.x + half_width, group.y, half_width, group.height),
+        DockZone::Top => Rect::new(group.x, group.y, group.width, half_height),
+        DockZone::Bottom => Rect::new(group.x, group.y + half_height, group.width, half_height),
+        DockZone::Center => group,
+    };
+
+    (zone, rect)
+}
+
+/// Panel to dock the dragged panel next to, the zone and the highlighted rect, `None`
+/// when the drop would leave the panel where it is.
+fn drop_target(
+    groups: &[(Vec<String>, Rect)],
+    panel: &str,
+    pointer: Vec2,
+) -> Option<(String, DockZone, Rect)> {
+    let (panels, rect) = groups.iter().find(|(_, rect)| {
+        pointer.x >= rect.left()
+            && pointer.x < rect.right()
+            && pointer.y >= rect.top()
+            && pointer.y < rect.bottom()
+    })?;
+    let target = panels.iter().find(|it| *it != panel)?;
+    let (zone, rect) = zone_at(*rect, pointer);
+
+    if zone == DockZone::Center && panels.iter().any(|it| it == panel) {
+        return None;
+    }
+
+    Some((target.clone(), zone, rect))
+}
+
+fn build_drop_preview(
+    ctx: &mut BuildContext,
+    theme: &WidgetTheme,
+    panel: &str,
+    pointer: Vec2,
+    target: Option<Rect>,
+) {
+    let view = ctx.view();
+    let view_rect = Rect::new(
+        0.,
+        0.,
+        view.size.width as f32 / view.scale_factor,
+        view.size.height as f32 / view.scale_factor,
+    );
+
+    // Lets the pointer through to the groups under it, so the tab keeps the drag.
+    portal().rect(view_rect).build(ctx, |ctx| {
+        gesture_detector().hover_through(true).build(ctx, |ctx| {
+            zstack().fill_max_size().build(ctx, |ctx| {
+                if let Some(target) = target {
+                    decorated_box()
+                        .color(theme.accent.with_opacity(0.25))
+                        .border(Border::all(BorderSide::new(2., theme.accent)))
+                        .width(target.width)
+                        .height(target.height)
+                        .offset(target.x, target.y)
+                        .build(ctx);
+                }
+
+                text(panel)
+                    .color(theme.text)
+                    .background(
+                        decoration()
+                            .color(theme.surface.with_opacity(0.8))
+                            .border(Border::all(BorderSide::new(1., theme.border)))
+                            .build(ctx),
+                    )
+                    .padding(EdgeInsets::new().left(8.).right(8.).top(4.).bottom(4.))
+                    .offset(pointer.x + 12., pointer.y + 12.)
+                    .build(ctx);
+            });
+        });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layout() -> DockLayout {
+        DockLayout::new(DockNode::split(
+            DockAxis::Horizontal,
+            0.3,
+            DockNode::tabs(["Files", "Search"]),
+            DockNode::tabs(["Editor"]),
+        ))
+    }
+
+    #[test]
+    fn test_dock_close_and_restore() {
+        let mut layout = layout();
+
+        assert!(layout.dock("Search", "Editor", DockZone::Bottom));
+        assert_eq!(
+            layout.root(),
+            Some(&DockNode::split(
+                DockAxis::Horizontal,
+                0.3,
+                DockNode::tabs(["Files"]),
+                DockNode::split(
+                    DockAxis::Vertical,
+                    0.5,
+                    DockNode::tabs(["Editor"]),
+                    DockNode::tabs(["Search"]),
+                ),
+            ))
+        );
+
+        // Alone in its group, the space goes to the editor.
+        assert!(layout.close("Search"));
+        assert!(!layout.contains("Search"));
+        assert_eq!(layout.closed_panels().collect::<Vec<_>>(), ["Search"]);
+
+        assert!(layout.restore("Search"));
+        assert!(layout.contains("Search"));
+        assert_eq!(
+            layout.root().and_then(|root| match root {
+                DockNode::Split { second, .. } => Some(second.as_ref().clone()),
+                _ => None,
+            }),
+            Some(DockNode::split(
+                DockAxis::Vertical,
+                0.5,
+                DockNode::tabs(["Editor"]),
+                DockNode::tabs(["Search"]),
+            ))
+        );
+
+        // Already there.
+        assert!(!layout.dock("Editor", "Editor", DockZone::Left));
+        assert!(!layout.dock("Search", "Search", DockZone::Center));
+    }
+
+    #[test]
+    fn test_layout_saves_and_loads() {
+        let mut layout = layout();
+
+        layout.activate("Search");
+        layout.close("Editor");
+
+        let loaded = DockLayout::load(&layout.save());
+
+        assert_eq!(loaded, Some(layout));
+        assert_eq!(DockLayout::load(&StateValue::Int(1)), None);
+    }
+
+    #[test]
+    fn test_zone_at_edges_and_tab_bar() {
+        let group = Rect::new(0., 0., 400., 300.);
+
+        assert_eq!(
+            zone_at(group, Vec2::new(20., 150.)),
+            (DockZone::Left, Rect::new(0., 0., 200., 300.))
+        );
+        assert_eq!(
+            zone_at(group, Vec2::new(200., 290.)),
+            (DockZone::Bottom, Rect::new(0., 150., 400., 150.))
+        );
+        assert_eq!(
+            zone_at(group, Vec2::new(200., 150.)),
+            (DockZone::Center, group)
+        );
+        assert_eq!(
+            zone_at(group, Vec2::new(5., 10.)),
+            (DockZone::Center, group)
+        );
+    }
+}
@@ -0,0 +1,731 @@
+use std::f32::consts::TAU;
+use std::ops::RangeInclusive;
+
+use clew::io::UserInput;
+use clew::keyboard::{KeyCode, KeyModifiers};
+use clew::stateful::StatefulWidget;
+use clew::{
+    AlignY, Border, BorderSide, BoxShape, ColorRgba, ColorStop, CrossAxisAlignment, EdgeInsets,
+    Gradient, Lerp, LinearGradient, Rect, SweepGradient, TextData, View, curves, widgets::*,
+};
+use clew::{TextAlign, prelude::*};
+use clew_derive::{WidgetBuilder, WidgetState};
+
+use crate::{HOVER_TRANSITION, RangeKey, is_key_pressed, range_key};
+
+/// Where the value arc starts, in turns clockwise from the right, the arc goes
+/// through the top and leaves a gap at the bottom.
+const START_TURN: f32 = 0.375;
+const SWEEP_TURNS: f32 = 0.75;
+/// Vertical drag distance that goes through the whole range.
+const DRAG_RANGE_PX: f32 = 200.;
+/// Multiplier of the drag and wheel changes while Shift is held.
+const FINE_FACTOR: f32 = 0.1;
+/// Wheel delta of one notch, matches the line delta scale of the desktop shell.
+const WHEEL_NOTCH: f32 = 20.;
+const RING_WIDTH: f32 = 4.;
+const INDICATOR_SIZE: f32 = 6.;
+const READOUT_HEIGHT: f32 = 20.;
+
+/// How dragging the [`knob`] changes its value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KnobMode {
+    /// Dragging up increases the value and dragging down decreases it, regardless of
+    /// where the drag happens.
+    #[default]
+    Vertical,
+    /// The value follows the angle between the pointer and the center of the knob.
+    Angular,
+}
+
+#[derive(WidgetBuilder)]
+pub struct KnobBuilder<'a> {
+    frame: FrameBuilder,
+    value: &'a mut f32,
+    range: RangeInclusive<f32>,
+    step: Option<f32>,
+    default_value: Option<f32>,
+    mode: KnobMode,
+    diameter: f32,
+    precision: usize,
+}
+
+pub struct KnobResponse {
+    changed: bool,
+    drag_state: DragState,
+}
+
+impl KnobResponse {
+    /// The value was changed in this frame by dragging, the wheel, the keys, a reset or
+    /// the readout.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Phase of the drag on the knob, a drag that resets the value isn't reported.
+    pub fn drag_state(&self) -> DragState {
+        self.drag_state
+    }
+}
+
+impl<'a> KnobBuilder<'a> {
+    /// Range of the value, `0.0..=1.0` by default.
+    ///
+    /// # Panics
+    ///
+    /// If the start of the range is above its end, or either of them is NaN.
+    pub fn range(mut self, range: RangeInclusive<f32>) -> Self {
+        assert!(
+            range.start() <= range.end(),
+            "the start of the knob range {range:?} has to be at most its end"
+        );
+
+        self.range = range;
+        self
+    }
+
+    /// Snaps the value to multiples of `step` from the start of the range.
+    pub fn step(mut self, step: f32) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// Value restored by a double click on the knob, the start of the range by default.
+    pub fn default_value(mut self, default_value: f32) -> Self {
+        self.default_value = Some(default_value);
+        self
+    }
+
+    pub fn mode(mut self, mode: KnobMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn diameter(mut self, diameter: f32) -> Self {
+        self.diameter = diameter;
+        self
+    }
+
+    /// Number of decimal places shown in the readout.
+    pub fn precision(mut self, precision: usize) -> Self {
+        self.precision = precision;
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> KnobResponse {
+        let Self {
+            frame,
+            value,
+            range,
+            step,
+            default_value,
+            mode,
+            diameter,
+            precision,
+        } = self;
+
+        let (min, max) = (*range.start(), *range.end());
+        let mut data = KnobData {
+            value: *value,
+            min,
+            max,
+            step: step.filter(|it| *it > 0.),
+            default_value: default_value.unwrap_or(min),
+            mode,
+            diameter,
+            precision,
+            drag_state: DragState::None,
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<Knob>().frame(frame).build(ctx);
+        });
+
+        let changed = data.value != *value;
+        *value = data.value;
+
+        KnobResponse {
+            changed,
+            drag_state: data.drag_state,
+        }
+    }
+}
+
+/// Rotary control for a value in a range. Dragging changes the value vertically or by
+/// the angle, see [`KnobMode`], and while the knob is focused the arrows step it and
+/// Home and End go to the ends of the range. Shift slows the drag, the wheel and the
+/// arrows down for fine adjustment, a double click on the knob resets the value to the
+/// default and a double click on the readout below the knob allows to type the value in.
+#[track_caller]
+pub fn knob(value: &mut f32) -> KnobBuilder<'_> {
+    KnobBuilder {
+        frame: FrameBuilder::new(),
+        value,
+        range: 0.0..=1.0,
+        step: None,
+        default_value: None,
+        mode: KnobMode::default(),
+        diameter: 48.,
+        precision: 2,
+    }
+}
+
+/// Parameters of the current frame passed down to the knob state, and the updated
+/// value passed back.
+#[derive(Default)]
+struct KnobData {
+    value: f32,
+    min: f32,
+    max: f32,
+    step: Option<f32>,
+    default_value: f32,
+    mode: KnobMode,
+    diameter: f32,
+    precision: usize,
+    drag_state: DragState,
+}
+
+impl KnobData {
+    fn span(&self) -> f32 {
+        self.max - self.min
+    }
+
+    fn snap(&self, value: f32) -> f32 {
+        let value = value.clamp(self.min, self.max);
+
+        match self.step {
+            Some(step) => {
+                (self.min + ((value - self.min) / step).round() * step).clamp(self.min, self.max)
+            }
+            None => value,
+        }
+    }
+
+    fn progress(&self) -> f32 {
+        if self.span() > 0. {
+            (self.value - self.min) / self.span()
+        } else {
+            0.
+        }
+    }
+
+    fn readout(&self) -> String {
+        format!("{:.*}", self.precision, self.value)
+    }
+}
+
+#[derive(WidgetState, Default)]
+struct Knob {
+    /// Value before snapping to the step, so slow drags and fine wheel notches add up.
+    raw: f32,
+    /// Turn of the pointer around the center in the previous frame of an angular drag.
+    last_turn: f32,
+    /// The drag started with a double click that reset the value.
+    resetting: bool,
+    editing: bool,
+    field: TextData,
+    dial_rect: Option<Rect>,
+    readout_rect: Option<Rect>,
+    mouse_was_pressed: bool,
+}
+
+impl Knob {
+    fn set_raw(&mut self, data: &mut KnobData, raw: f32) {
+        self.raw = raw.clamp(data.min, data.max);
+        data.value = data.snap(self.raw);
+    }
+
+    fn handle_dial_input(
+        &mut self,
+        input: &UserInput,
+        gesture: &GestureDetectorResponse,
+        data: &mut KnobData,
+    ) {
+        let fine = input.modifiers.contains(KeyModifiers::SHIFT);
+        let factor = if fine { FINE_FACTOR } else { 1. };
+        let pointer = match gesture.drag_state {
+            DragState::Start => (gesture.drag_start_x, gesture.drag_start_y),
+            _ => (gesture.drag_x, gesture.drag_y),
+        };
+        let turn = self.dial_rect.map(|rect| {
+            let center_x = rect.x + rect.width / 2.;
+            let center_y = rect.y + rect.height / 2.;
+
+            ((pointer.1 - center_y).atan2(pointer.0 - center_x) / TAU).rem_euclid(1.)
+        });
+
+        // The arc goes clockwise in every layout direction, so the arrows aren't mirrored.
+        if gesture.is_focused()
+            && let Some(key) = range_key(input, false)
+        {
+            let increment = data.step.unwrap_or(data.span() / 100.) * factor;
+
+            match key {
+                RangeKey::Increase => self.set_raw(data, self.raw + increment),
+                RangeKey::Decrease => self.set_raw(data, self.raw - increment),
+                RangeKey::Start => self.set_raw(data, data.min),
+                RangeKey::End => self.set_raw(data, data.max),
+            }
+        }
+
+        match gesture.drag_state {
+            DragState::Start => {
+                self.resetting = input.mouse_left_click_count >= 2;
+
+                if self.resetting {
+                    self.set_raw(data, data.default_value);
+                } else if data.mode == KnobMode::Angular
+                    && let Some(turn) = turn
+                {
+                    self.last_turn = turn;
+
+                    if !fine {
+                        self.set_raw(data, data.min + turn_to_progress(turn) * data.span());
+                    }
+                }
+            }
+            DragState::Update if !self.resetting => match data.mode {
+                KnobMode::Vertical => {
+                    let delta = -gesture.drag_delta_y / DRAG_RANGE_PX * data.span() * factor;
+
+                    self.set_raw(data, self.raw + delta);
+                }
+                KnobMode::Angular => {
+                    let Some(turn) = turn else {
+                        return;
+                    };
+
+                    if fine {
+                        let delta = (turn - self.last_turn + 0.5).rem_euclid(1.) - 0.5;
+
+                        self.set_raw(data, self.raw + delta / SWEEP_TURNS * data.span() * factor);
+                    } else {
+                        self.set_raw(data, data.min + turn_to_progress(turn) * data.span());
+                    }
+
+                    self.last_turn = turn;
+                }
+            },
+            _ => {
+                if gesture.is_hot() && input.mouse_wheel_delta_y != 0. {
+                    let increment = data.step.unwrap_or(data.span() / 100.);
+                    let delta = input.mouse_wheel_delta_y as f32 / WHEEL_NOTCH * increment * factor;
+
+                    self.set_raw(data, self.raw + delta);
+                }
+            }
+        }
+
+        if !self.resetting {
+            data.drag_state = gesture.drag_state;
+        }
+    }
+
+    /// Handles the keyboard and the presses outside of the readout while it's edited,
+    /// the typed text goes to the field and the field only shows it.
+    fn handle_field_input(
+        &mut self,
+        input: &UserInput,
+        text_input: &str,
+        view: &View,
+        data: &mut KnobData,
+    ) {
+        let mouse = (
+            input.mouse_x / view.scale_factor,
+            input.mouse_y / view.scale_factor,
+        );
+        let pressed = input.mouse_left_pressed && !self.mouse_was_pressed;
+        let pressed_outside = pressed
+            && !self.readout_rect.is_some_and(|rect| {
+                mouse.0 >= rect.left()
+                    && mouse.0 < rect.right()
+                    && mouse.1 >= rect.top()
+                    && mouse.1 < rect.bottom()
+            });
+
+        if is_key_pressed(input, KeyCode::Escape) {
+            self.editing = false;
+            return;
+        }
+
+        if pressed_outside
+            || is_key_pressed(input, KeyCode::Enter)
+            || is_key_pressed(input, KeyCode::NumpadEnter)
+        {
+            if let Ok(value) = self.field.get_text().trim().parse::<f32>() {
+                self.set_raw(data, value);
+            }
+
+            self.editing = false;
+            return;
+        }
+
+        let mut text = self.field.get_text();
+        let previous = text.clone();
+
+        if is_key_pressed(input, KeyCode::Backspace) {
+            text.pop();
+        }
+
+        text.push_str(text_input);
+
+        if text != previous {
+            self.field.set_text(&text);
+        }
+    }
+}
+
+/// Position of the value along the arc for a turn of the pointer around the center,
+/// turns in the gap at the bottom go to the closest end of the arc.
+fn turn_to_progress(turn: f32) -> f32 {
+    let progress = (turn - START_TURN).rem_euclid(1.) / SWEEP_TURNS;
+
+    if progress <= 1. {
+        progress
+    } else if progress < (1. / SWEEP_TURNS + 1.) / 2. {
+        1.
+    } else {
+        0.
+    }
+}
+
+/// Stops of a full sweep gradient that paint `color` on the arc going clockwise from
+/// the `from` turn to the `to` turn and leave the rest transparent.
+fn arc_stops(from: f32, to: f32, color: ColorRgba) -> Vec<ColorStop> {
+    let from = from.rem_euclid(1.);
+    let to = to.rem_euclid(1.);
+    let transparent = color.with_opacity(0.);
+
+    if from <= to {
+        vec![
+            ColorStop::new(0., transparent),
+            ColorStop::new(from, transparent),
+            ColorStop::new(from, color),
+            ColorStop::new(to, color),
+            ColorStop::new(to, transparent),
+            ColorStop::new(1., transparent),
+        ]
+    } else {
+        vec![
+            ColorStop::new(0., color),
+            ColorStop::new(to, color),
+            ColorStop::new(to, transparent),
+            ColorStop::new(from, transparent),
+            ColorStop::new(from, color),
+            ColorStop::new(1., color),
+        ]
+    }
+}
+
+impl StatefulWidget for Knob {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut BuildContext, mut frame: FrameBuilder) {
+        let Some(mut data) = ctx.of_mut::<KnobData>().map(std::mem::take) else {
+            return;
+        };
+
+        // The value has been changed outside of the knob.
+        if data.snap(self.raw) != data.value {
+            self.raw = data.value;
+        }
+
+        if self.editing {
+            // Taken, so the focused field doesn't insert the text once more.
+            let text_input = ctx.take_text_input();
+
+            self.handle_field_input(ctx.input(), &text_input, ctx.view(), &mut data);
+        }
+
+        self.mouse_was_pressed = ctx.input().mouse_left_pressed;
+
+        frame.build(ctx, |ctx| {
+            vstack()
+                .spacing(4.)
+                .cross_axis_alignment(CrossAxisAlignment::Center)
+                .build(ctx, |ctx| {
+                    self.dial_rect = measure().build(ctx, |ctx| self.build_dial(ctx, &mut data));
+                    self.readout_rect = measure()
+                        .width(data.diameter.max(48.))
+                        .height(READOUT_HEIGHT)
+                        .build(ctx, |ctx| self.build_readout(ctx, &data));
+                });
+        });
+
+        if let Some(shared) = ctx.of_mut::<KnobData>() {
+            *shared = data;
+        }
+    }
+}
+
+impl Knob {
+    fn build_dial(&mut self, ctx: &mut BuildContext, data: &mut KnobData) {
+        gesture_detector()
+            .dragable(true)
+            .clickable(true)
+            .focusable(true)
+            .build(ctx, |ctx| {
+                let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+
+                self.handle_dial_input(ctx.input(), &gesture, data);
+
+                let diameter = data.diameter;
+                let progress = data.progress();
+                let value_turn = START_TURN + progress * SWEEP_TURNS;
+                let theme = ctx.widget_theme();
+                let accent = if gesture.is_active() || gesture.is_hot() {
+                    theme.accent.lerp(ColorRgba::from_hex(0xFFFFFFFF), 0.12)
+                } else {
+                    theme.accent
+                };
+                let border_color = if gesture.is_hot() {
+                    theme.border.lerp(theme.text, 0.2)
+                } else {
+                    theme.border
+                };
+
+                // The indicator sits inside the body, on the line from the center to
+                // the end of the value arc.
+                let angle = value_turn * TAU;
+                let radius = diameter / 2. - RING_WIDTH - 2. - INDICATOR_SIZE;
+                let indicator_x = diameter / 2. + radius * angle.cos() - INDICATOR_SIZE / 2.;
+                let indicator_y = diameter / 2. + radius * angle.sin() - INDICATOR_SIZE / 2.;
+
+                let mut dial = zstack().width(diameter).height(diameter);
+
+                if gesture.is_focus_visible() {
+                    dial = dial.foreground(
+                        decoration()
+                            .shape(BoxShape::Oval)
+                            .border(Border::all(BorderSide::new(2., theme.accent)))
+                            .outset(2.)
+                            .build(ctx),
+                    );
+                }
+
+                dial.build(ctx, |ctx| {
+                    decorated_box()
+                        .shape(BoxShape::Oval)
+                        .add_gradient(Gradient::Sweep(SweepGradient::new(
+                            (0.5, 0.5),
+                            0.,
+                            TAU,
+                            arc_stops(START_TURN, START_TURN + SWEEP_TURNS, theme.border),
+                        )))
+                        .fill_max_size()
+                        .build(ctx);
+
+                    if progress > 0. {
+                        decorated_box()
+                            .shape(BoxShape::Oval)
+                            .add_gradient(Gradient::Sweep(SweepGradient::new(
+                                (0.5, 0.5),
+                                0.,
+                                TAU,
+                                arc_stops(START_TURN, value_turn, accent),
+                            )))
+                            .fill_max_size()
+                            .build(ctx);
+                    }
+
+                    decorated_box()
+                        .shape(BoxShape::Oval)
+                        .add_linear_gradient(LinearGradient::vertical((
+                            theme.raised(0.06),
+                            theme.surface,
+                        )))
+                        .border(Border::all(BorderSide::new(1., border_color)))
+                        .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                        .padding(EdgeInsets::all(RING_WIDTH + 2.))
+                        .fill_max_size()
+                        .build(ctx);
+
+                    decorated_box()
+                        .shape(BoxShape::Oval)
+                        .color(theme.text)
+                        .width(INDICATOR_SIZE)
+                        .height(INDICATOR_SIZE)
+                        .offset_x(indicator_x)
+                        .offset_y(indicator_y)
+                        .build(ctx);
+                });
+            });
+    }
+
+    fn build_readout(&mut self, ctx: &mut BuildContext, data: &KnobData) {
+        let theme = ctx.widget_theme();
+
+        if self.editing {
+            editable_text(&mut self.field)
+                .color(theme.text)
+                .selection_color(theme.accent.with_opacity(0.4))
+                .selected_text_color(theme.text)
+                .text_align(TextAlign::Center)
+                .text_vertical_align(AlignY::Center)
+                .fill_max_size()
+                .background(
+                    decoration()
+                        .color(theme.sunken(0.5))
+                        .border(Border::all(BorderSide::new(1., theme.accent)))
+                        .build(ctx),
+                )
+                .build(ctx);
+
+            return;
+        }
+
+        let response = gesture_detector().clickable(true).build(ctx, |ctx| {
+            text(&data.readout())
+                .color(theme.secondary_text)
+                .text_align(TextAlign::Center)
+                .text_vertical_align(AlignY::Center)
+                .fill_max_size()
+                .build(ctx);
+        });
+
+        if response.clicked() && ctx.input().mouse_left_click_count >= 2 {
+            self.editing = true;
+            self.field.set_text(&data.readout());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::io::{InputEvent, MouseButton};
+    use clew::testing;
+
+    use super::*;
+
+    #[test]
+    fn test_text_typed_in_focused_field_is_inserted_once() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let mut value = 5.;
+        let mut frame = |event: Option<InputEvent>| {
+            if let Some(event) = event {
+                instance.push_event(event);
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                knob(&mut value).range(0.0..=100.0).precision(0).build(ctx);
+            });
+
+            value
+        };
+        let click = |x: f32, y: f32| {
+            [
+                Some(InputEvent::PointerMoved { x, y }),
+                Some(InputEvent::MouseButton {
+                    button: MouseButton::Left,
+                    pressed: true,
+                }),
+                Some(InputEvent::MouseButton {
+                    button: MouseButton::Left,
+                    pressed: false,
+                }),
+                None,
+            ]
+        };
+        let key = |key_code: KeyCode, text: Option<&str>| {
+            Some(InputEvent::Key {
+                key_code: Some(key_code),
+                pressed: true,
+                repeat: false,
+                text: text.map(str::to_string),
+            })
+        };
+        let readout_y = 48. + 4. + READOUT_HEIGHT / 2.;
+
+        frame(None);
+
+        // A double click on the readout edits it, a click on the field focuses it.
+        for _ in 0..3 {
+            for event in click(24., readout_y) {
+                frame(event);
+            }
+        }
+
+        frame(key(KeyCode::Digit7, Some("7")));
+        assert_eq!(frame(key(KeyCode::Enter, None)), 57.);
+    }
+
+    #[test]
+    fn test_focused_knob_is_stepped_with_the_keys() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let mut value = 5.;
+        let mut frame = |key_code: KeyCode, modifiers: KeyModifiers| {
+            instance.push_event(InputEvent::ModifiersChanged(modifiers));
+            instance.push_event(InputEvent::Key {
+                key_code: Some(key_code),
+                pressed: true,
+                repeat: false,
+                text: None,
+            });
+            instance.frame(&mut host, &mut |ctx| {
+                knob(&mut value).range(0.0..=10.0).build(ctx);
+            });
+
+            value
+        };
+
+        // The arrows do nothing until the knob has the focus.
+        assert_eq!(frame(KeyCode::ArrowUp, KeyModifiers::empty()), 5.);
+
+        frame(KeyCode::Tab, KeyModifiers::empty());
+        assert_eq!(frame(KeyCode::ArrowUp, KeyModifiers::empty()), 5.1);
+        assert_eq!(frame(KeyCode::ArrowLeft, KeyModifiers::empty()), 5.);
+        assert_eq!(frame(KeyCode::ArrowRight, KeyModifiers::SHIFT), 5.01);
+        assert_eq!(frame(KeyCode::End, KeyModifiers::empty()), 10.);
+        assert_eq!(frame(KeyCode::Home, KeyModifiers::empty()), 0.);
+    }
+
+    #[test]
+    fn test_turn_to_progress() {
+        assert_eq!(turn_to_progress(START_TURN), 0.);
+        assert_eq!(turn_to_progress(0.75), 0.5);
+        assert!((turn_to_progress(0.125) - 1.).abs() < 1e-6);
+        // The gap at the bottom goes to the closest end.
+        assert_eq!(turn_to_progress(0.2), 1.);
+        assert_eq!(turn_to_progress(0.3), 0.);
+    }
+
+    #[test]
+    #[should_panic(expected = "the start of the knob range 1.0..=0.0 has to be at most its end")]
+    fn test_reversed_range_is_rejected() {
+        let mut value = 0.5;
+
+        let _ = knob(&mut value).range(1.0..=0.0);
+    }
+
+    #[test]
+    fn test_arc_stops_wrap_around() {
+        let color = ColorRgba::from_hex(0xFFFFFFFF);
+        let offsets = |stops: Vec<ColorStop>| -> Vec<(f32, f32)> {
+            stops.iter().map(|it| (it.offset, it.color.a)).collect()
+        };
+
+        assert_eq!(
+            offsets(arc_stops(0.25, 0.5, color)),
+            vec![
+                (0., 0.),
+                (0.25, 0.),
+                (0.25, 1.),
+                (0.5, 1.),
+                (0.5, 0.),
+                (1., 0.)
+            ]
+        );
+        assert_eq!(
+            offsets(arc_stops(0.75, 1.25, color)),
+            vec![
+                (0., 1.),
+                (0.25, 1.),
+                (0.25, 0.),
+                (0.75, 0.),
+                (0.75, 1.),
+                (1., 1.)
+            ]
+        );
+    }
+}
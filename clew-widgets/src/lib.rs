@@ -1,16 +1,111 @@
-use clew::stateful::{StatefulWidget, StatefulWidgetBuilder};
+use std::time::Duration;
+
+use clew::io::UserInput;
+use clew::keyboard::{KeyCode, KeyModifiers};
+use clew::stateful::StatefulWidget;
 use clew::widgets::shortcuts::shortcut_scope;
 use clew::{
-    AlignX, AlignY, Border, BorderRadius, BorderSide, ColorRgba, Constraints, EdgeInsets,
-    LinearGradient, widgets::*,
+    AlignX, AlignY, Border, BorderRadius, BorderSide, ColorRgba, Constraints, EdgeInsets, Lerp,
+    LinearGradient, curves, widgets::*,
 };
 use clew::{TextAlign, prelude::*};
 use clew_derive::{ShortcutId, ShortcutScopeId, WidgetBuilder, WidgetState};
 
+mod breadcrumbs;
+mod checkbox;
+mod dock;
+mod knob;
+mod listbox;
+mod material;
+mod menu_bar;
+mod mnemonic;
+mod pagination;
+mod palette_grid;
+mod radio_group;
+mod roving;
+mod segmented;
+mod select;
+mod skeleton;
+mod slider;
+
+pub use breadcrumbs::{BreadcrumbsBuilder, BreadcrumbsResponse, Crumb, breadcrumbs};
+pub use checkbox::{
+    CHECKMARK_SVG, CheckboxBuilder, CheckboxResponse, CheckboxShortcut, ShortcutScopeCheckbox,
+    add_checkbox_assets, add_checkbox_shortcuts, checkbox,
+};
+pub use dock::{
+    DockAxis, DockLayout, DockNode, DockSpaceBuilder, DockSpaceResponse, DockZone, dock_space,
+};
+pub use knob::{KnobBuilder, KnobMode, KnobResponse, knob};
+pub use listbox::{ListboxBuilder, ListboxResponse, listbox};
+pub use material::{MaterialAwareBackgroundBuilder, material_aware_background};
+pub use menu_bar::{
+    Menu, MenuBarBuilder, MenuBarResponse, MenuItem, ShortcutScopeMenuBar, menu_bar,
+};
+pub use mnemonic::{
+    Mnemonic, MnemonicLabelBuilder, MnemonicMatch, is_alt_held, match_mnemonic, mnemonic_label,
+    pressed_mnemonic_key,
+};
+pub use pagination::{PaginationBuilder, PaginationResponse, pagination};
+pub use palette_grid::{PaletteGridBuilder, PaletteGridResponse, palette_grid};
+pub use radio_group::{RadioGroupBuilder, RadioGroupResponse, radio_group};
+pub use segmented::{
+    Segment, SegmentWidth, SegmentedControlBuilder, SegmentedControlResponse, segmented_control,
+};
+pub use select::{SelectBuilder, SelectOption, SelectResponse, select};
+pub use skeleton::{
+    SkeletonBuilder, SkeletonOfBuilder, SkeletonParagraphBuilder, SkeletonStyle, skeleton_block,
+    skeleton_circle, skeleton_line, skeleton_of, skeleton_paragraph,
+};
+pub use slider::{SliderBuilder, SliderResponse, slider};
+
+const HOVER_TRANSITION: Duration = Duration::from_millis(120);
+
+fn is_key_pressed(input: &UserInput, key: KeyCode) -> bool {
+    input
+        .key_pressed
+        .iter()
+        .chain(input.key_pressed_repeat.iter())
+        .any(|(_, it)| *it == Some(key))
+}
+
+/// Key pressed to change the value of a focused control in a range, e.g. a slider.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RangeKey {
+    Increase,
+    Decrease,
+    Start,
+    End,
+}
+
+/// Up and the arrow toward the end of the range increase the value, Down and the other
+/// arrow decrease it, Home and End go to the ends of the range. The end of a mirrored
+/// range is on the left.
+fn range_key(input: &UserInput, mirrored: bool) -> Option<RangeKey> {
+    let (forward, backward) = if mirrored {
+        (KeyCode::ArrowLeft, KeyCode::ArrowRight)
+    } else {
+        (KeyCode::ArrowRight, KeyCode::ArrowLeft)
+    };
+
+    if is_key_pressed(input, forward) || is_key_pressed(input, KeyCode::ArrowUp) {
+        Some(RangeKey::Increase)
+    } else if is_key_pressed(input, backward) || is_key_pressed(input, KeyCode::ArrowDown) {
+        Some(RangeKey::Decrease)
+    } else if is_key_pressed(input, KeyCode::Home) {
+        Some(RangeKey::Start)
+    } else if is_key_pressed(input, KeyCode::End) {
+        Some(RangeKey::End)
+    } else {
+        None
+    }
+}
+
 #[derive(WidgetBuilder)]
 pub struct ButtonBuilder<'a> {
     frame: FrameBuilder,
     text: &'a str,
+    disabled: bool,
 }
 
 pub struct ButtonResponse {
@@ -32,43 +127,41 @@ pub enum ButtonShortcut {
 }
 
 impl<'a> ButtonBuilder<'a> {
+    /// A disabled button can't be clicked or focused.
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+
+        self
+    }
+
     #[profiling::function]
     pub fn build(mut self, ctx: &mut BuildContext) -> ButtonResponse {
         let layout = self.frame.take_layout();
+        let disabled = self.disabled;
         let response = self.frame.build(ctx, |ctx| {
             gesture_detector()
-                .clickable(true)
-                .focusable(true)
+                .clickable(!disabled)
+                .focusable(!disabled)
                 .build(ctx, |ctx| {
                     let response = ctx.of::<GestureDetectorResponse>().unwrap();
+                    let hot = !disabled && response.is_hot();
+                    let theme = ctx.widget_theme();
 
                     let gradient = {
-                        if response.is_active() && response.is_hot() {
-                            LinearGradient::vertical((
-                                ColorRgba::from_hex(0xFF1C1C1C),
-                                ColorRgba::from_hex(0xFF212121),
-                            ))
-                        } else if response.is_hot() {
-                            LinearGradient::vertical((
-                                ColorRgba::from_hex(0xFF383838),
-                                ColorRgba::from_hex(0xFF2E2E2E),
-                            ))
+                        if response.is_active() && hot {
+                            LinearGradient::vertical((theme.sunken(0.6), theme.sunken(0.4)))
+                        } else if hot {
+                            LinearGradient::vertical((theme.raised(0.1), theme.raised(0.05)))
                         } else {
-                            LinearGradient::vertical((
-                                ColorRgba::from_hex(0xFF2F2F2F),
-                                ColorRgba::from_hex(0xFF272727),
-                            ))
+                            LinearGradient::vertical((theme.raised(0.03), theme.surface))
                         }
                     };
 
-                    let border_color = if response.is_focused() {
-                        ColorRgba::from_hex(0xFF357CCE)
-                    } else if response.is_active() && response.is_hot() {
-                        ColorRgba::from_hex(0xFF414141)
-                    } else if response.is_hot() {
-                        ColorRgba::from_hex(0xFF616161)
+                    let focus_visible = response.is_focus_visible();
+                    let border_color = if hot && !response.is_active() {
+                        theme.border.lerp(theme.text, 0.2)
                     } else {
-                        ColorRgba::from_hex(0xFF414141)
+                        theme.border
                     };
 
                     shortcut_scope(ShortcutScopeButton)
@@ -82,14 +175,32 @@ impl<'a> ButtonBuilder<'a> {
                                 // gesture_detector_click(response.id);
                             }
 
-                            text(self.text)
-                                .background(
+                            let mut label = text(self.text).background(
+                                decoration()
+                                    .border_radius(BorderRadius::all(3.))
+                                    .add_linear_gradient(gradient)
+                                    .border(Border::all(BorderSide::new(1., border_color)))
+                                    .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                                    .build(ctx),
+                            );
+
+                            // Drawn outside of the button, so the label stays in place.
+                            if focus_visible {
+                                label = label.foreground(
                                     decoration()
-                                        .border_radius(BorderRadius::all(3.))
-                                        .add_linear_gradient(gradient)
-                                        .border(Border::all(BorderSide::new(1., border_color)))
+                                        .border_radius(BorderRadius::all(5.))
+                                        .border(Border::all(BorderSide::new(2., theme.accent)))
+                                        .outset(2.)
                                         .build(ctx),
-                                )
+                                );
+                            }
+
+                            label
+                                .color(if disabled {
+                                    theme.secondary_text.with_opacity(0.5)
+                                } else {
+                                    theme.text
+                                })
                                 .text_align(TextAlign::Center)
                                 .text_vertical_align(AlignY::Center)
                                 .size(layout.size)
@@ -116,6 +227,194 @@ pub fn button(text: &str) -> ButtonBuilder<'_> {
             max_height: f32::INFINITY,
         }),
         text,
+        disabled: false,
+    }
+}
+
+/// What happens when the scroll bar track (outside of the thumb) is pressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TrackClickBehavior {
+    /// Scroll by one page toward the pointer, repeating while the track is held.
+    #[default]
+    Page,
+    /// Move the thumb so it's centered at the pointer.
+    JumpToPosition,
+}
+
+const TRACK_REPEAT_DELAY: f32 = 0.3;
+const TRACK_REPEAT_INTERVAL: f32 = 0.05;
+
+#[derive(Debug, PartialEq)]
+enum TrackAction {
+    None,
+    Page(f64),
+    Jump(f64),
+}
+
+/// Positions along the track, `pointer` and `thumb_offset` are relative to its start.
+struct TrackGeometry {
+    pointer: f64,
+    thumb_offset: f64,
+    thumb_length: f64,
+    track_length: f64,
+}
+
+#[derive(Default)]
+struct TrackPress {
+    repeat_timer: f32,
+    jump: bool,
+}
+
+impl TrackPress {
+    fn update(
+        &mut self,
+        ctx: &BuildContext,
+        gesture: &GestureDetectorResponse,
+        behavior: TrackClickBehavior,
+        geometry: TrackGeometry,
+    ) -> TrackAction {
+        self.step(
+            gesture.drag_state,
+            ctx.input().modifiers.contains(KeyModifiers::ALT),
+            ctx.delta_time(),
+            behavior,
+            geometry,
+        )
+    }
+
+    /// Holding Alt (Option on macOS) swaps the page and jump behaviors.
+    fn step(
+        &mut self,
+        drag_state: DragState,
+        alt: bool,
+        delta_time: f32,
+        behavior: TrackClickBehavior,
+        geometry: TrackGeometry,
+    ) -> TrackAction {
+        let TrackGeometry {
+            pointer,
+            thumb_offset,
+            thumb_length,
+            track_length,
+        } = geometry;
+
+        let page = match drag_state {
+            DragState::Start => {
+                self.jump = (behavior == TrackClickBehavior::JumpToPosition) != alt;
+                self.repeat_timer = TRACK_REPEAT_DELAY;
+
+                true
+            }
+            DragState::Update => {
+                // Build runs every frame while the track is held, so the timer just
+                // accumulates the frame delta.
+                self.repeat_timer -= delta_time;
+
+                if self.repeat_timer <= 0. {
+                    self.repeat_timer += TRACK_REPEAT_INTERVAL;
+
+                    true
+                } else {
+                    false
+                }
+            }
+            DragState::None | DragState::End => return TrackAction::None,
+        };
+
+        if self.jump {
+            let range = track_length - thumb_length;
+
+            if range <= 0. {
+                return TrackAction::None;
+            }
+
+            TrackAction::Jump(((pointer - thumb_length / 2.) / range).clamp(0., 1.))
+        } else if !page {
+            TrackAction::None
+        } else if pointer < thumb_offset {
+            TrackAction::Page(-1.)
+        } else if pointer > thumb_offset + thumb_length {
+            TrackAction::Page(1.)
+        } else {
+            // The thumb has reached the pointer.
+            TrackAction::None
+        }
+    }
+}
+
+/// Thickness of the thumb and the space between it and the edge of the scroll area.
+fn thumb_thickness(style: &ScrollBarStyle, active: bool) -> (f32, f32) {
+    let thickness = if active {
+        style.expanded_thickness
+    } else {
+        style.thickness
+    };
+
+    (thickness, f32::max(0., (style.extent() - thickness) / 2.))
+}
+
+fn thumb_color(style: &ScrollBarStyle, gesture: &GestureDetectorResponse) -> ColorRgba {
+    if gesture.is_active() {
+        style.thumb_active_color
+    } else if gesture.is_hot() {
+        style.thumb_hover_color
+    } else {
+        style.thumb_color
+    }
+}
+
+fn track_color(style: &ScrollBarStyle, track: &GestureDetectorResponse) -> ColorRgba {
+    if track.is_hot() || track.is_active() {
+        style.track_hover_color
+    } else {
+        style.track_color
+    }
+}
+
+#[derive(WidgetBuilder)]
+pub struct HorizontalScrollBarBuilder {
+    frame: FrameBuilder,
+    style: Option<ScrollBarStyle>,
+    track_click: TrackClickBehavior,
+}
+
+/// Scroll bar along the bottom of a scroll area, expects a [`ScrollAreaResponse`] to be
+/// provided. It's styled by [`ScrollBarStyle::of`] unless the style is set.
+///
+/// Returns [`HorizontalScrollBarBuilder`] in place of the former
+/// `impl StatefulWidgetBuilder`, so `.build(ctx)` is still called on it but the value
+/// can no longer be passed where a `StatefulWidgetBuilder` is expected.
+pub fn horizontal_scroll_bar() -> HorizontalScrollBarBuilder {
+    HorizontalScrollBarBuilder {
+        frame: FrameBuilder::new(),
+        style: None,
+        track_click: TrackClickBehavior::default(),
+    }
+}
+
+impl HorizontalScrollBarBuilder {
+    pub fn style(mut self, style: ScrollBarStyle) -> Self {
+        self.style = Some(style);
+
+        self
+    }
+
+    pub fn track_click(mut self, behavior: TrackClickBehavior) -> Self {
+        self.track_click = behavior;
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) {
+        let style = self.style.unwrap_or_else(|| ScrollBarStyle::of(ctx));
+
+        stateful::<HorizontalScrollBar>()
+            .frame(self.frame)
+            .update_state_and_build(ctx, |state| {
+                state.style = style;
+                state.track_click = self.track_click;
+            });
     }
 }
 
@@ -123,6 +422,9 @@ pub fn button(text: &str) -> ButtonBuilder<'_> {
 pub struct HorizontalScrollBar {
     offset: f64,
     last_offset: f64,
+    style: ScrollBarStyle,
+    track: TrackPress,
+    track_click: TrackClickBehavior,
 }
 
 impl StatefulWidget for HorizontalScrollBar {
@@ -134,26 +436,66 @@ impl StatefulWidget for HorizontalScrollBar {
                 .fill_max_size()
                 .align_y(AlignY::Bottom)
                 .build(ctx, |ctx| {
+                    let response = ctx.of::<ScrollAreaResponse>().unwrap().clone();
+                    let style = self.style;
+                    let end_margin = style.end_margin as f64;
+                    let mut scroll_area_width = response.width - end_margin * 2.;
+
+                    // Leaves the corner to the vertical bar.
+                    if response.overflow_y {
+                        scroll_area_width -= style.expanded_thickness as f64;
+                    }
+
+                    let bar_width = f64::max(
+                        style.min_thumb_length as f64,
+                        scroll_area_width * response.fraction_x,
+                    );
+
+                    // The track goes first so the thumb is on top of it and wins the press.
                     gesture_detector().dragable(true).build(ctx, |ctx| {
-                        let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
-
-                        let color = ColorRgba::from_hex(0xFFFFFFFF).with_opacity(
-                            if gesture.is_hot() || gesture.is_active() {
-                                0.5
-                            } else {
-                                0.4
+                        let track = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+                        let pointer = match track.drag_state {
+                            DragState::Start => track.drag_start_x,
+                            _ => track.drag_x,
+                        } as f64
+                            - response.x
+                            - end_margin;
+
+                        let action = self.track.update(
+                            ctx,
+                            &track,
+                            self.track_click,
+                            TrackGeometry {
+                                pointer,
+                                thumb_offset: self.offset,
+                                thumb_length: bar_width,
+                                track_length: scroll_area_width,
                             },
                         );
 
-                        let response = ctx.of::<ScrollAreaResponse>().unwrap().clone();
-                        let horizontal_padding = 16.;
-                        let mut scroll_area_width = response.width - horizontal_padding;
+                        match action {
+                            TrackAction::None => {}
+                            TrackAction::Page(direction) => {
+                                let offset = -response.offset_x + direction * response.width;
 
-                        if response.overflow_y {
-                            scroll_area_width -= 8.;
+                                set_scroll_offset_x(ctx, response.id, offset);
+                            }
+                            TrackAction::Jump(progress_x) => {
+                                set_scroll_progress_x(ctx, response.id, progress_x);
+                            }
                         }
 
-                        let bar_width = f64::max(16., scroll_area_width * response.fraction_x);
+                        decorated_box()
+                            .color(track_color(&style, &track))
+                            .fill_max_width()
+                            .height(style.extent())
+                            .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                            .build(ctx);
+                    });
+
+                    gesture_detector().dragable(true).build(ctx, |ctx| {
+                        let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+                        let (thickness, margin) = thumb_thickness(&style, gesture.is_active());
 
                         if gesture.drag_state == DragState::None
                             || gesture.drag_state == DragState::End
@@ -172,20 +514,17 @@ impl StatefulWidget for HorizontalScrollBar {
                         }
 
                         decorated_box()
-                            .color(color)
+                            .color(thumb_color(&style, &gesture))
                             .border_radius(BorderRadius::all(if gesture.is_active() {
                                 0.
                             } else {
                                 2.
                             }))
+                            .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
                             .width(bar_width)
-                            .height(if gesture.is_active() { 8. } else { 4. })
+                            .height(thickness)
                             .offset_x(self.offset as f32)
-                            .padding(if gesture.is_active() {
-                                EdgeInsets::symmetric(8., 6.)
-                            } else {
-                                EdgeInsets::all(8.)
-                            })
+                            .padding(EdgeInsets::symmetric(style.end_margin, margin))
                             .build(ctx);
                     });
                 });
@@ -193,34 +532,63 @@ impl StatefulWidget for HorizontalScrollBar {
     }
 }
 
-pub fn horizontal_scroll_bar() -> impl StatefulWidgetBuilder {
-    stateful::<HorizontalScrollBar>()
-}
-
 #[derive(WidgetBuilder)]
 pub struct VerticalScrollBarBuilder {
     frame: FrameBuilder,
-    thinkness: f32,
+    style: Option<ScrollBarStyle>,
+    thickness: Option<f32>,
+    track_click: TrackClickBehavior,
 }
 
+/// Scroll bar along the end of a scroll area, expects a [`ScrollAreaResponse`] to be
+/// provided. It's styled by [`ScrollBarStyle::of`] unless the style is set.
 pub fn vertical_scroll_bar() -> VerticalScrollBarBuilder {
     VerticalScrollBarBuilder {
         frame: FrameBuilder::new(),
-        thinkness: 4.,
+        style: None,
+        thickness: None,
+        track_click: TrackClickBehavior::default(),
     }
 }
 
 impl VerticalScrollBarBuilder {
-    pub fn thinkness(mut self, thinkness: f32) -> Self {
-        self.thinkness = thinkness;
+    pub fn style(mut self, style: ScrollBarStyle) -> Self {
+        self.style = Some(style);
+
+        self
+    }
+
+    /// Overrides the thickness of the thumb of the style.
+    pub fn thickness(mut self, thickness: f32) -> Self {
+        self.thickness = Some(thickness);
+        self
+    }
+
+    #[deprecated(note = "Use `thickness`")]
+    pub fn thinkness(self, thickness: f32) -> Self {
+        self.thickness(thickness)
+    }
+
+    pub fn track_click(mut self, behavior: TrackClickBehavior) -> Self {
+        self.track_click = behavior;
+
         self
     }
 
     #[profiling::function]
     pub fn build(self, ctx: &mut BuildContext) {
+        let mut style = self.style.unwrap_or_else(|| ScrollBarStyle::of(ctx));
+
+        if let Some(thickness) = self.thickness {
+            style.thickness = thickness;
+        }
+
         stateful::<VerticalScrollBar>()
             .frame(self.frame)
-            .update_state_and_build(ctx, |state| state.thinkness = self.thinkness);
+            .update_state_and_build(ctx, |state| {
+                state.style = style;
+                state.track_click = self.track_click;
+            });
     }
 }
 
@@ -228,7 +596,9 @@ impl VerticalScrollBarBuilder {
 pub struct VerticalScrollBar {
     offset: f64,
     last_offset: f64,
-    thinkness: f32,
+    style: ScrollBarStyle,
+    track: TrackPress,
+    track_click: TrackClickBehavior,
 }
 
 impl StatefulWidget for VerticalScrollBar {
@@ -240,26 +610,66 @@ impl StatefulWidget for VerticalScrollBar {
                 .fill_max_size()
                 .align_x(AlignX::Right)
                 .build(ctx, |ctx| {
+                    let response = ctx.of::<ScrollAreaResponse>().unwrap().clone();
+                    let style = self.style;
+                    let end_margin = style.end_margin as f64;
+                    let mut scroll_area_height = response.height - end_margin * 2.;
+
+                    // Leaves the corner to the horizontal bar.
+                    if response.overflow_x {
+                        scroll_area_height -= style.expanded_thickness as f64;
+                    }
+
+                    let bar_height = f64::max(
+                        style.min_thumb_length as f64,
+                        scroll_area_height * response.fraction_y,
+                    );
+
+                    // The track goes first so the thumb is on top of it and wins the press.
                     gesture_detector().dragable(true).build(ctx, |ctx| {
-                        let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
-
-                        let color = ColorRgba::from_hex(0xFFFFFFFF).with_opacity(
-                            if gesture.is_hot() || gesture.is_active() {
-                                0.5
-                            } else {
-                                0.4
+                        let track = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+                        let pointer = match track.drag_state {
+                            DragState::Start => track.drag_start_y,
+                            _ => track.drag_y,
+                        } as f64
+                            - response.y
+                            - end_margin;
+
+                        let action = self.track.update(
+                            ctx,
+                            &track,
+                            self.track_click,
+                            TrackGeometry {
+                                pointer,
+                                thumb_offset: self.offset,
+                                thumb_length: bar_height,
+                                track_length: scroll_area_height,
                             },
                         );
 
-                        let response = ctx.of::<ScrollAreaResponse>().unwrap().clone();
-                        let vertical_padding = 16.;
-                        let mut scroll_area_height = response.height - vertical_padding;
+                        match action {
+                            TrackAction::None => {}
+                            TrackAction::Page(direction) => {
+                                let offset = -response.offset_y + direction * response.height;
 
-                        if response.overflow_x {
-                            scroll_area_height -= 8.;
+                                set_scroll_offset_y(ctx, response.id, offset);
+                            }
+                            TrackAction::Jump(progress_y) => {
+                                set_scroll_progress_y(ctx, response.id, progress_y);
+                            }
                         }
 
-                        let bar_height = f64::max(16., scroll_area_height * response.fraction_y);
+                        decorated_box()
+                            .color(track_color(&style, &track))
+                            .width(style.extent())
+                            .fill_max_height()
+                            .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                            .build(ctx);
+                    });
+
+                    gesture_detector().dragable(true).build(ctx, |ctx| {
+                        let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+                        let (thickness, margin) = thumb_thickness(&style, gesture.is_active());
 
                         if gesture.drag_state == DragState::None
                             || gesture.drag_state == DragState::End
@@ -278,23 +688,269 @@ impl StatefulWidget for VerticalScrollBar {
                         }
 
                         decorated_box()
-                            .color(color)
+                            .color(thumb_color(&style, &gesture))
                             .border_radius(BorderRadius::all(if gesture.is_active() {
                                 0.
                             } else {
                                 2.
                             }))
-                            .width(if gesture.is_active() { 8. } else { 4. })
+                            .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                            .width(thickness)
                             .height(bar_height)
                             .offset_y(self.offset as f32)
-                            .padding(if gesture.is_active() {
-                                EdgeInsets::symmetric(6., 8.)
-                            } else {
-                                EdgeInsets::all(8.)
-                            })
+                            .padding(EdgeInsets::symmetric(margin, style.end_margin))
                             .build(ctx);
                     });
                 });
         });
     }
 }
+
+#[derive(WidgetBuilder)]
+pub struct PanZoomIndicatorsBuilder {
+    frame: FrameBuilder,
+}
+
+/// Scroll bars overlaid on top of a pan zoom area that show which part of the
+/// content is visible, expects a [`PanZoomAreaResponse`] to be provided.
+pub fn pan_zoom_indicators() -> PanZoomIndicatorsBuilder {
+    PanZoomIndicatorsBuilder {
+        frame: FrameBuilder::new(),
+    }
+}
+
+impl PanZoomIndicatorsBuilder {
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) {
+        let response = ctx.of::<PanZoomAreaResponse>().unwrap().clone();
+        let visible = response.visible_content_rect();
+        let content = response.content_rect();
+        let color = ctx.widget_theme().text.with_opacity(0.4);
+        let padding = 16.;
+
+        // The range covers both the content and the viewport, so the thumb stays inside
+        // the track when the content is panned out of the view.
+        let indicator = |visible_start: f32, visible_size: f32, content_size: f32, length: f32| {
+            let start = f32::min(visible_start, 0.);
+            let end = f32::max(visible_start + visible_size, content_size);
+
+            if visible_start <= 0. && visible_start + visible_size >= content_size {
+                return None;
+            }
+
+            let range = end - start;
+
+            Some((
+                (visible_start - start) / range * length,
+                f32::max(16., visible_size / range * length),
+            ))
+        };
+
+        let horizontal = indicator(
+            visible.x,
+            visible.width,
+            content.width,
+            response.width - padding,
+        );
+        let vertical = indicator(
+            visible.y,
+            visible.height,
+            content.height,
+            response.height - padding,
+        );
+
+        self.frame.fill_max_size().build(ctx, |ctx| {
+            if let Some((offset, length)) = horizontal {
+                zstack()
+                    .fill_max_size()
+                    .align_y(AlignY::Bottom)
+                    .build(ctx, |ctx| {
+                        decorated_box()
+                            .color(color)
+                            .border_radius(BorderRadius::all(2.))
+                            .width(length)
+                            .height(4.)
+                            .offset_x(offset)
+                            .padding(EdgeInsets::all(8.))
+                            .build(ctx);
+                    });
+            }
+
+            if let Some((offset, length)) = vertical {
+                zstack()
+                    .fill_max_size()
+                    .align_x(AlignX::Right)
+                    .build(ctx, |ctx| {
+                        decorated_box()
+                            .color(color)
+                            .border_radius(BorderRadius::all(2.))
+                            .width(4.)
+                            .height(length)
+                            .offset_y(offset)
+                            .padding(EdgeInsets::all(8.))
+                            .build(ctx);
+                    });
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::testing;
+
+    use super::*;
+
+    fn geometry(pointer: f64, thumb_offset: f64) -> TrackGeometry {
+        TrackGeometry {
+            pointer,
+            thumb_offset,
+            thumb_length: 20.,
+            track_length: 120.,
+        }
+    }
+
+    #[test]
+    fn test_track_press_pages_toward_the_pointer() {
+        let mut track = TrackPress::default();
+        let page = TrackClickBehavior::Page;
+
+        assert_eq!(
+            track.step(DragState::Start, false, 0., page, geometry(10., 50.)),
+            TrackAction::Page(-1.)
+        );
+        assert_eq!(
+            track.step(DragState::Start, false, 0., page, geometry(100., 50.)),
+            TrackAction::Page(1.)
+        );
+        assert_eq!(
+            track.step(DragState::Start, false, 0., page, geometry(60., 50.)),
+            TrackAction::None
+        );
+    }
+
+    #[test]
+    fn test_track_press_repeats_until_the_thumb_reaches_the_pointer() {
+        let mut track = TrackPress::default();
+        let page = TrackClickBehavior::Page;
+
+        assert_eq!(
+            track.step(DragState::Start, false, 0., page, geometry(100., 0.)),
+            TrackAction::Page(1.)
+        );
+
+        // Waits for the initial delay, then repeats with the shorter interval.
+        assert_eq!(
+            track.step(DragState::Update, false, 0.25, page, geometry(100., 20.)),
+            TrackAction::None
+        );
+        assert_eq!(
+            track.step(DragState::Update, false, 0.075, page, geometry(100., 20.)),
+            TrackAction::Page(1.)
+        );
+        assert_eq!(
+            track.step(DragState::Update, false, 0.01, page, geometry(100., 40.)),
+            TrackAction::None
+        );
+        assert_eq!(
+            track.step(DragState::Update, false, 0.02, page, geometry(100., 40.)),
+            TrackAction::Page(1.)
+        );
+
+        // The thumb is under the pointer now.
+        assert_eq!(
+            track.step(DragState::Update, false, 0.05, page, geometry(100., 90.)),
+            TrackAction::None
+        );
+        assert_eq!(
+            track.step(DragState::End, false, 0.05, page, geometry(100., 90.)),
+            TrackAction::None
+        );
+    }
+
+    #[test]
+    fn test_track_press_jumps_to_the_pointer() {
+        let mut track = TrackPress::default();
+        let jump = TrackClickBehavior::JumpToPosition;
+
+        assert_eq!(
+            track.step(DragState::Start, false, 0., jump, geometry(60., 0.)),
+            TrackAction::Jump(0.5)
+        );
+        // The thumb follows the pointer while the track is held.
+        assert_eq!(
+            track.step(DragState::Update, false, 0.01, jump, geometry(5., 50.)),
+            TrackAction::Jump(0.)
+        );
+        assert_eq!(
+            track.step(DragState::Update, false, 0.01, jump, geometry(200., 0.)),
+            TrackAction::Jump(1.)
+        );
+
+        // Alt swaps the behaviors.
+        assert_eq!(
+            track.step(DragState::Start, true, 0., jump, geometry(100., 0.)),
+            TrackAction::Page(1.)
+        );
+        assert_eq!(
+            track.step(
+                DragState::Start,
+                true,
+                0.,
+                TrackClickBehavior::Page,
+                geometry(60., 0.)
+            ),
+            TrackAction::Jump(0.5)
+        );
+    }
+
+    #[test]
+    fn test_button_takes_the_theme_colors() {
+        use clew::authoring::RenderCommand;
+        use clew::{Appearance, ClewHost, ClewInstance, SystemTheme, WidgetTheme};
+
+        fn label_color<'a>(
+            host: &mut ClewHost<'a>,
+            instance: &mut ClewInstance<'a>,
+        ) -> Option<ColorRgba> {
+            let render_state = instance
+                .frame(host, &mut |ctx| {
+                    button("Save").build(ctx);
+                })
+                .unwrap();
+
+            render_state
+                .commands()
+                .iter()
+                .find_map(|command| match command {
+                    RenderCommand::Text { tint_color, .. } => *tint_color,
+                    _ => None,
+                })
+        }
+
+        let (mut host, mut instance) = testing::instance(200, 100);
+
+        // Derived from the system theme, switched along with it.
+        assert_eq!(
+            label_color(&mut host, &mut instance),
+            Some(WidgetTheme::default().text)
+        );
+
+        instance.set_system_theme(SystemTheme {
+            appearance: Appearance::Dark,
+            ..SystemTheme::default()
+        });
+        assert_eq!(
+            label_color(&mut host, &mut instance),
+            Some(WidgetTheme::dark(ColorRgba::from_hex(WidgetTheme::DEFAULT_ACCENT)).text)
+        );
+
+        // The registered theme wins over the system.
+        let theme = WidgetTheme {
+            text: ColorRgba::from_hex(0xFF102030),
+            ..WidgetTheme::light(ColorRgba::from_hex(0xFF2E7D32))
+        };
+        host.resources.insert(theme);
+        assert_eq!(label_color(&mut host, &mut instance), Some(theme.text));
+    }
+}
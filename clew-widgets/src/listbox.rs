@@ -0,0 +1,105 @@
+use clew::widgets::*;
+use clew::{FocusAxis, prelude::*};
+use clew_derive::WidgetBuilder;
+
+use crate::SelectOption;
+use crate::roving::{RovingData, RovingGroup, RovingKind, option_key};
+
+#[derive(WidgetBuilder)]
+pub struct ListboxBuilder<'a, T: SelectOption> {
+    frame: FrameBuilder,
+    selected: &'a mut Option<T::Id>,
+    items: &'a [T],
+    disabled: Option<&'a dyn Fn(&T) -> bool>,
+    selection_follows_focus: bool,
+}
+
+pub struct ListboxResponse {
+    changed: bool,
+}
+
+impl ListboxResponse {
+    /// The selection was changed in this frame by a click or the keyboard, or cleared
+    /// because the selected item is gone.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl<'a, T: SelectOption> ListboxBuilder<'a, T>
+where
+    T::Id: PartialEq,
+{
+    /// Items that can't be selected and are skipped by the focus.
+    pub fn disabled(mut self, disabled: &'a dyn Fn(&T) -> bool) -> Self {
+        self.disabled = Some(disabled);
+        self
+    }
+
+    /// Whether moving the focus with the keyboard selects the item, on by default.
+    /// Otherwise only Space selects the focused item.
+    pub fn selection_follows_focus(mut self, value: bool) -> Self {
+        self.selection_follows_focus = value;
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> ListboxResponse {
+        let Self {
+            frame,
+            selected,
+            items,
+            disabled,
+            selection_follows_focus,
+        } = self;
+
+        let current = items
+            .iter()
+            .position(|it| selected.as_ref() == Some(&it.id()));
+        let mut data = RovingData {
+            kind: RovingKind::Listbox,
+            keys: items.iter().map(|it| option_key(&it.id())).collect(),
+            labels: items.iter().map(|it| it.label().to_string()).collect(),
+            disabled: items
+                .iter()
+                .map(|it| disabled.is_some_and(|disabled| disabled(it)))
+                .collect(),
+            selected: current,
+            axis: FocusAxis::Vertical,
+            selection_follows_focus,
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<RovingGroup>().frame(frame).build(ctx);
+        });
+
+        // The selected item has been removed from the list.
+        let gone = current.is_none() && selected.is_some();
+        let changed = data.selected != current || gone;
+
+        if changed {
+            *selected = data.selected.map(|index| items[index].id());
+        }
+
+        ListboxResponse { changed }
+    }
+}
+
+/// List that picks one of the `items`, the list is a single Tab stop. Tab enters it on
+/// the item focused last or the selected one, Up and Down move the focus and the
+/// selection between the items, Home and End to the first and the last one, and
+/// typing jumps to the item whose label starts with the typed letters. A selected item
+/// that is removed from the list is unselected.
+#[track_caller]
+pub fn listbox<'a, T: SelectOption>(
+    selected: &'a mut Option<T::Id>,
+    items: &'a [T],
+) -> ListboxBuilder<'a, T> {
+    ListboxBuilder {
+        frame: FrameBuilder::new(),
+        selected,
+        items,
+        disabled: None,
+        selection_follows_focus: true,
+    }
+}
@@ -0,0 +1,55 @@
+use clew::prelude::*;
+use clew::{BorderRadius, widgets::*};
+use clew_derive::WidgetBuilder;
+
+#[derive(WidgetBuilder)]
+pub struct MaterialAwareBackgroundBuilder {
+    frame: FrameBuilder,
+    opacity: f32,
+    border_radius: BorderRadius,
+}
+
+/// Tint of the background color of the theme painted behind the content, so the text
+/// stays readable over the background material of the window whatever is behind the
+/// window. Over an opaque window it only shades the fill color slightly.
+///
+/// ```ignore
+/// material_aware_background()
+///     .padding(EdgeInsets::all(12.))
+///     .build(ctx, |ctx| settings_form(ctx, &mut app.settings));
+/// ```
+pub fn material_aware_background() -> MaterialAwareBackgroundBuilder {
+    MaterialAwareBackgroundBuilder {
+        frame: FrameBuilder::new(),
+        opacity: 0.6,
+        border_radius: BorderRadius::all(0.),
+    }
+}
+
+impl MaterialAwareBackgroundBuilder {
+    /// Opacity of the tint, 0.6 by default.
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+
+        self
+    }
+
+    pub fn border_radius(mut self, border_radius: BorderRadius) -> Self {
+        self.border_radius = border_radius;
+
+        self
+    }
+
+    pub fn build<F, T>(self, ctx: &mut BuildContext, content: F) -> T
+    where
+        F: FnOnce(&mut BuildContext) -> T,
+    {
+        let theme = ctx.widget_theme();
+        let tint = decoration()
+            .color(theme.background.with_opacity(self.opacity))
+            .border_radius(self.border_radius)
+            .build(ctx);
+
+        self.background(tint).frame.build(ctx, content)
+    }
+}
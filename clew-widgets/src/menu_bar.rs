@@ -0,0 +1,842 @@
+use clew::io::UserInput;
+use clew::keyboard::{KeyCode, KeyModifiers};
+use clew::prelude::*;
+use clew::stateful::StatefulWidget;
+use clew::text::MeasureTextStyle;
+use clew::widgets::shortcuts::shortcut_scope;
+use clew::{
+    AlignY, Border, BorderRadius, BorderSide, ColorRgba, CrossAxisAlignment, EdgeInsets,
+    LayoutDirection, Rect, View, WidgetTheme, curves, widgets::*,
+};
+use clew_derive::{ShortcutScopeId, WidgetBuilder, WidgetState};
+
+use crate::mnemonic::{
+    Mnemonic, MnemonicMatch, is_alt_held, match_mnemonic, mnemonic_label, pressed_mnemonic_key,
+};
+use crate::{HOVER_TRANSITION, is_key_pressed};
+
+const TITLE_PADDING_X: f32 = 8.;
+const TITLE_PADDING_Y: f32 = 4.;
+const ITEM_HEIGHT: f32 = 24.;
+const ITEM_PADDING_X: f32 = 8.;
+const SEPARATOR_HEIGHT: f32 = 9.;
+const MENU_PADDING: f32 = 4.;
+const MENU_MIN_WIDTH: f32 = 160.;
+/// Space between the label of an item and its shortcut hint.
+const HINT_SPACING: f32 = 24.;
+
+/// Scope of the shortcuts of a [`menu_bar`] while it has the keyboard. It's exclusive,
+/// so the shortcuts of the rest of the view don't fire under an open menu.
+#[derive(ShortcutScopeId)]
+pub struct ShortcutScopeMenuBar;
+
+/// Row of a [`Menu`], its label marks the mnemonic letter with an `&`, see
+/// [`Mnemonic::parse`].
+#[derive(Debug, Clone)]
+pub struct MenuItem {
+    label: Mnemonic,
+    shortcut_hint: Option<String>,
+    separator: bool,
+}
+
+impl MenuItem {
+    pub fn new(label: &str) -> Self {
+        Self {
+            label: Mnemonic::parse(label),
+            shortcut_hint: None,
+            separator: false,
+        }
+    }
+
+    /// Line between the groups of the items, it's skipped by the keyboard.
+    pub fn separator() -> Self {
+        Self {
+            separator: true,
+            ..Self::new("")
+        }
+    }
+
+    /// Shown at the end of the row, e.g. `"Ctrl+S"`. It's only a hint, the shortcut
+    /// itself is registered in a scope as usual.
+    pub fn shortcut_hint(mut self, hint: &str) -> Self {
+        self.shortcut_hint = Some(hint.to_string());
+        self
+    }
+}
+
+/// Title of a [`menu_bar`] with the items of its popup.
+#[derive(Debug, Clone)]
+pub struct Menu {
+    title: Mnemonic,
+    items: Vec<MenuItem>,
+}
+
+impl Menu {
+    pub fn new(title: &str, items: Vec<MenuItem>) -> Self {
+        Self {
+            title: Mnemonic::parse(title),
+            items,
+        }
+    }
+}
+
+#[derive(WidgetBuilder)]
+pub struct MenuBarBuilder<'a> {
+    frame: FrameBuilder,
+    menus: &'a [Menu],
+}
+
+pub struct MenuBarResponse {
+    activated: Option<(usize, usize)>,
+}
+
+impl MenuBarResponse {
+    /// Index of the menu and of its item activated in this frame.
+    pub fn activated(&self) -> Option<(usize, usize)> {
+        self.activated
+    }
+}
+
+impl MenuBarBuilder<'_> {
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> MenuBarResponse {
+        let mut data = MenuBarData {
+            menus: self.menus.to_vec(),
+            activated: None,
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<MenuBar>().frame(self.frame).build(ctx);
+        });
+
+        MenuBarResponse {
+            activated: data.activated,
+        }
+    }
+}
+
+/// Row of the menu titles, as in the windows of Windows and Linux. A menu is opened
+/// with a click or with Alt and the marked letter of its title, e.g. Alt+F for
+/// `"&File"`. In an open menu the letters activate the items, a letter shared by
+/// several titles or items moves between them and Enter picks the highlighted one.
+/// The arrows move between the titles and the items, Escape backs out one level: from
+/// the menu to the bar and from the bar to the content.
+///
+/// Build it before the content it's over, the letters it handles are taken from the
+/// typed text, so the focused widgets built after it don't get them.
+///
+/// ```ignore
+/// let menus = [
+///     Menu::new("&File", vec![
+///         MenuItem::new("&Open").shortcut_hint("Ctrl+O"),
+///         MenuItem::separator(),
+///         MenuItem::new("E&xit"),
+///     ]),
+///     Menu::new("&Edit", vec![MenuItem::new("&Copy"), MenuItem::new("C&ut")]),
+/// ];
+///
+/// if let Some((menu, item)) = menu_bar(&menus).build(ctx).activated() {
+///     handle_command(menu, item);
+/// }
+/// ```
+#[track_caller]
+pub fn menu_bar(menus: &[Menu]) -> MenuBarBuilder<'_> {
+    MenuBarBuilder {
+        frame: FrameBuilder::new(),
+        menus,
+    }
+}
+
+/// Menus of the current frame passed down to the bar state, and the activated item
+/// passed back.
+#[derive(Default)]
+struct MenuBarData {
+    menus: Vec<Menu>,
+    activated: Option<(usize, usize)>,
+}
+
+/// Key of the menu bar pressed in a frame, the arrows are already mapped to the layout
+/// direction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MenuKey {
+    Mnemonic { key: char, alt: bool },
+    Previous,
+    Next,
+    Up,
+    Down,
+    Enter,
+    Escape,
+}
+
+/// Keys of the bar pressed in this frame. Alt with a letter only counts as such while
+/// the chord is `alt_unbound`, i.e. no shortcut of the enclosing scopes takes it.
+fn menu_keys(
+    input: &UserInput,
+    layout_direction: LayoutDirection,
+    alt_unbound: bool,
+) -> Vec<MenuKey> {
+    let (left, right) = match layout_direction {
+        LayoutDirection::LTR => (MenuKey::Previous, MenuKey::Next),
+        LayoutDirection::RTL => (MenuKey::Next, MenuKey::Previous),
+    };
+    let mut keys = [
+        (KeyCode::ArrowLeft, left),
+        (KeyCode::ArrowRight, right),
+        (KeyCode::ArrowUp, MenuKey::Up),
+        (KeyCode::ArrowDown, MenuKey::Down),
+        (KeyCode::Enter, MenuKey::Enter),
+        (KeyCode::NumpadEnter, MenuKey::Enter),
+        (KeyCode::Escape, MenuKey::Escape),
+    ]
+    .into_iter()
+    .filter(|(key_code, _)| is_key_pressed(input, *key_code))
+    .map(|(_, key)| key)
+    .collect::<Vec<_>>();
+
+    if let Some(key) = pressed_mnemonic_key(input) {
+        keys.push(MenuKey::Mnemonic {
+            key,
+            alt: is_alt_held(input) && alt_unbound,
+        });
+    }
+
+    keys
+}
+
+/// Item after or before the `highlighted` one, wrapping around and skipping the
+/// separators.
+fn next_item(items: &[MenuItem], highlighted: Option<usize>, forward: bool) -> Option<usize> {
+    let count = items.len();
+    let last = count.checked_sub(1)?;
+    let step = if forward { 1 } else { last };
+    let start = highlighted.unwrap_or(if forward { last } else { 0 });
+
+    (1..=count)
+        .map(|offset| (start + offset * step) % count)
+        .find(|it| !items[*it].separator)
+}
+
+#[derive(WidgetState, Default)]
+struct MenuBar {
+    /// Menu whose items are shown.
+    open: Option<usize>,
+    /// Title the keyboard points at, the bar has the keyboard while it's set.
+    highlighted_menu: Option<usize>,
+    /// Item of the open menu the keyboard points at.
+    highlighted_item: Option<usize>,
+    /// The bar was entered with the keyboard, the mnemonics stay underlined after Alt
+    /// is released.
+    keyboard: bool,
+    title_rects: Vec<Option<Rect>>,
+    menu_rect: Option<Rect>,
+    mouse_was_pressed: bool,
+    last_mouse: (f32, f32),
+}
+
+impl MenuBar {
+    fn has_keyboard(&self) -> bool {
+        self.open.is_some() || self.highlighted_menu.is_some()
+    }
+
+    fn open_menu(&mut self, menu: usize, data: &MenuBarData, keyboard: bool) {
+        self.open = Some(menu);
+        self.highlighted_menu = Some(menu);
+        self.highlighted_item = if keyboard {
+            data.menus[menu].items.iter().position(|it| !it.separator)
+        } else {
+            None
+        };
+        self.keyboard |= keyboard;
+    }
+
+    /// Gives the keyboard back to the content.
+    fn leave(&mut self) {
+        self.open = None;
+        self.highlighted_menu = None;
+        self.highlighted_item = None;
+        self.keyboard = false;
+        self.menu_rect = None;
+    }
+
+    fn activate(&mut self, menu: usize, item: usize, data: &mut MenuBarData) {
+        data.activated = Some((menu, item));
+        self.leave();
+    }
+
+    /// Handles a key of the bar, returns whether it was taken. Closed, only Alt with
+    /// the letter of a title is.
+    fn handle_key(&mut self, key: MenuKey, data: &mut MenuBarData) -> bool {
+        match (self.open, self.highlighted_menu, key) {
+            (Some(menu), _, key) => {
+                self.handle_menu_key(menu, key, data);
+                true
+            }
+            (None, Some(menu), key) => {
+                self.handle_bar_key(menu, key, data);
+                true
+            }
+            (None, None, MenuKey::Mnemonic { key, alt: true }) => self.pick_title(key, data),
+            _ => false,
+        }
+    }
+
+    fn pick_title(&mut self, key: char, data: &MenuBarData) -> bool {
+        let titles = data
+            .menus
+            .iter()
+            .map(|it| it.title.clone())
+            .collect::<Vec<_>>();
+
+        match match_mnemonic(&titles, key, self.highlighted_menu) {
+            Some(MnemonicMatch::Activate(menu)) => self.open_menu(menu, data, true),
+            Some(MnemonicMatch::Highlight(menu)) => {
+                self.highlighted_menu = Some(menu);
+                self.keyboard = true;
+            }
+            None => return false,
+        }
+
+        true
+    }
+
+    fn handle_bar_key(&mut self, menu: usize, key: MenuKey, data: &MenuBarData) {
+        let count = data.menus.len();
+
+        match key {
+            MenuKey::Previous => self.highlighted_menu = Some((menu + count - 1) % count),
+            MenuKey::Next => self.highlighted_menu = Some((menu + 1) % count),
+            MenuKey::Down | MenuKey::Enter => self.open_menu(menu, data, true),
+            MenuKey::Mnemonic { key, .. } => {
+                self.pick_title(key, data);
+            }
+            MenuKey::Escape => self.leave(),
+            MenuKey::Up => {}
+        }
+    }
+
+    fn handle_menu_key(&mut self, menu: usize, key: MenuKey, data: &mut MenuBarData) {
+        let count = data.menus.len();
+        let items = &data.menus[menu].items;
+
+        match key {
+            MenuKey::Up => self.highlighted_item = next_item(items, self.highlighted_item, false),
+            MenuKey::Down => self.highlighted_item = next_item(items, self.highlighted_item, true),
+            MenuKey::Previous => self.open_menu((menu + count - 1) % count, data, true),
+            MenuKey::Next => self.open_menu((menu + 1) % count, data, true),
+            MenuKey::Enter => {
+                if let Some(item) = self.highlighted_item {
+                    self.activate(menu, item, data);
+                }
+            }
+            MenuKey::Mnemonic { key, .. } => {
+                let labels = items.iter().map(|it| it.label.clone()).collect::<Vec<_>>();
+
+                match match_mnemonic(&labels, key, self.highlighted_item) {
+                    Some(MnemonicMatch::Activate(item)) => self.activate(menu, item, data),
+                    Some(MnemonicMatch::Highlight(item)) => self.highlighted_item = Some(item),
+                    None => {}
+                }
+            }
+            MenuKey::Escape => {
+                self.open = None;
+                self.highlighted_item = None;
+                self.keyboard = true;
+            }
+        }
+    }
+
+    /// Gives the keyboard back on a press outside of the bar and the open menu.
+    fn handle_pointer(&mut self, input: &UserInput, view: &View) {
+        let mouse = (
+            input.mouse_x / view.scale_factor,
+            input.mouse_y / view.scale_factor,
+        );
+        let pressed = input.mouse_left_pressed && !self.mouse_was_pressed;
+        let inside = |rect: &Option<Rect>| {
+            rect.is_some_and(|rect| {
+                mouse.0 >= rect.left()
+                    && mouse.0 < rect.right()
+                    && mouse.1 >= rect.top()
+                    && mouse.1 < rect.bottom()
+            })
+        };
+
+        if pressed && !self.title_rects.iter().any(inside) && !inside(&self.menu_rect) {
+            self.leave();
+        }
+    }
+}
+
+impl StatefulWidget for MenuBar {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut BuildContext, mut frame: FrameBuilder) {
+        let Some(mut data) = ctx.of_mut::<MenuBarData>().map(std::mem::take) else {
+            return;
+        };
+
+        let theme = ctx.widget_theme();
+        let input = ctx.input();
+        let mouse_moved = (input.mouse_x, input.mouse_y) != self.last_mouse;
+        self.last_mouse = (input.mouse_x, input.mouse_y);
+
+        if self.open.is_some_and(|it| it >= data.menus.len())
+            || self
+                .highlighted_menu
+                .is_some_and(|it| it >= data.menus.len())
+        {
+            self.leave();
+        }
+
+        if self.has_keyboard() {
+            self.handle_pointer(ctx.input(), ctx.view());
+        }
+
+        self.mouse_was_pressed = ctx.input().mouse_left_pressed;
+
+        self.title_rects.resize(data.menus.len(), None);
+
+        frame.build(ctx, |ctx| {
+            // Reads the keys regardless of the focus, the chords bound to the shortcuts
+            // of the enclosing scopes are left out of its events.
+            gesture_detector().global_keys(true).build(ctx, |ctx| {
+                let alt_unbound = ctx
+                    .keyboard()
+                    .events()
+                    .any(|event| event.pressed && event.modifiers.contains(KeyModifiers::ALT));
+                let mut taken = false;
+
+                for key in menu_keys(ctx.input(), ctx.layout_direction(), alt_unbound) {
+                    taken |= self.handle_key(key, &mut data);
+                }
+
+                if taken {
+                    ctx.take_text_input();
+                }
+
+                let underline = self.keyboard || is_alt_held(ctx.input());
+
+                shortcut_scope(ShortcutScopeMenuBar)
+                    .active(self.has_keyboard())
+                    .exclusive(true)
+                    .build(ctx, |ctx| {
+                        hstack()
+                            .rtl_aware(true)
+                            .spacing(0.)
+                            .cross_axis_alignment(CrossAxisAlignment::Center)
+                            .build(ctx, |ctx| {
+                                for index in 0..data.menus.len() {
+                                    scope(index).build(ctx, |ctx| {
+                                        self.build_title(ctx, &data, &theme, index, underline);
+                                    });
+                                }
+                            });
+
+                        if let Some(menu) = self.open
+                            && let Some(title_rect) = self.title_rects[menu]
+                        {
+                            self.build_menu(
+                                ctx,
+                                &mut data,
+                                &theme,
+                                menu,
+                                title_rect,
+                                underline,
+                                mouse_moved,
+                            );
+                        }
+                    });
+            });
+        });
+
+        if let Some(shared) = ctx.of_mut::<MenuBarData>() {
+            *shared = data;
+        }
+    }
+}
+
+impl MenuBar {
+    fn build_title(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &MenuBarData,
+        theme: &WidgetTheme,
+        index: usize,
+        underline: bool,
+    ) {
+        let gesture = gesture_detector().clickable(true).build(ctx, |ctx| {
+            let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+
+            // With a menu open the others open on hover.
+            if gesture.is_hot() && self.open.is_some_and(|open| open != index) {
+                self.open_menu(index, data, false);
+            }
+
+            let highlighted = self.open == Some(index) || self.highlighted_menu == Some(index);
+            let background = if highlighted {
+                theme.accent
+            } else if gesture.is_hot() {
+                theme.border.with_opacity(0.5)
+            } else {
+                theme.border.with_opacity(0.)
+            };
+            let color = if highlighted {
+                ColorRgba::from_hex(0xFFFFFFFF)
+            } else {
+                theme.text
+            };
+
+            self.title_rects[index] = measure().build(ctx, |ctx| {
+                mnemonic_label(&data.menus[index].title)
+                    .color(color)
+                    .underline(underline)
+                    .padding(EdgeInsets::symmetric(TITLE_PADDING_X, TITLE_PADDING_Y))
+                    .background(
+                        decoration()
+                            .color(background)
+                            .border_radius(BorderRadius::all(3.))
+                            .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                            .build(ctx),
+                    )
+                    .build(ctx);
+            });
+        });
+
+        if gesture.clicked() {
+            if self.open == Some(index) {
+                self.leave();
+            } else {
+                self.open_menu(index, data, false);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn build_menu(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &mut MenuBarData,
+        theme: &WidgetTheme,
+        menu: usize,
+        title_rect: Rect,
+        underline: bool,
+        mouse_moved: bool,
+    ) {
+        let items = &data.menus[menu].items;
+        let label_width = items
+            .iter()
+            .map(|it| {
+                ctx.measure_text(it.label.text(), MeasureTextStyle::default())
+                    .x
+            })
+            .fold(0., f32::max);
+        let hint_width = items
+            .iter()
+            .filter_map(|it| it.shortcut_hint.as_deref())
+            .map(|hint| ctx.measure_text(hint, MeasureTextStyle::default()).x + HINT_SPACING)
+            .fold(0., f32::max);
+        let width =
+            (label_width + hint_width + (ITEM_PADDING_X + MENU_PADDING) * 2.).max(MENU_MIN_WIDTH);
+        let height = items
+            .iter()
+            .map(|it| {
+                if it.separator {
+                    SEPARATOR_HEIGHT
+                } else {
+                    ITEM_HEIGHT
+                }
+            })
+            .sum::<f32>()
+            + MENU_PADDING * 2.;
+        let x = match ctx.layout_direction() {
+            LayoutDirection::LTR => title_rect.left(),
+            LayoutDirection::RTL => title_rect.right() - width,
+        };
+        let menu_rect = Rect::new(x, title_rect.bottom(), width, height);
+        self.menu_rect = Some(menu_rect);
+
+        let mut clicked = None;
+
+        // The menu escapes the clips of the ancestors of the bar and takes the pointer
+        // before the content it covers.
+        portal().rect(menu_rect).build(ctx, |ctx| {
+            vstack()
+                .fill_max_size()
+                .spacing(0.)
+                .padding(EdgeInsets::all(MENU_PADDING))
+                .background(
+                    decoration()
+                        .color(theme.surface)
+                        .border_radius(BorderRadius::all(3.))
+                        .border(Border::all(BorderSide::new(1., theme.border)))
+                        .build(ctx),
+                )
+                .build(ctx, |ctx| {
+                    for (index, item) in data.menus[menu].items.iter().enumerate() {
+                        scope(index).build(ctx, |ctx| {
+                            if item.separator {
+                                zstack()
+                                    .fill_max_width()
+                                    .height(SEPARATOR_HEIGHT)
+                                    .align_y(AlignY::Center)
+                                    .build(ctx, |ctx| {
+                                        decorated_box()
+                                            .color(theme.border)
+                                            .fill_max_width()
+                                            .height(1.)
+                                            .build(ctx);
+                                    });
+
+                                return;
+                            }
+
+                            let gesture = gesture_detector().clickable(true).build(ctx, |ctx| {
+                                let gesture = ctx.of::<GestureDetectorResponse>().unwrap();
+
+                                // Hovering moves the highlight, unless the pointer rests
+                                // on an item while it's moved with the keyboard.
+                                if gesture.is_hot() && mouse_moved {
+                                    self.highlighted_item = Some(index);
+                                }
+
+                                let highlighted = self.highlighted_item == Some(index);
+                                let (background, color) = if highlighted {
+                                    (theme.accent, ColorRgba::from_hex(0xFFFFFFFF))
+                                } else {
+                                    (ColorRgba::TRANSPARENT, theme.text)
+                                };
+
+                                hstack()
+                                    .rtl_aware(true)
+                                    .fill_max_width()
+                                    .height(ITEM_HEIGHT)
+                                    .padding(EdgeInsets::symmetric(ITEM_PADDING_X, 0.))
+                                    .cross_axis_alignment(CrossAxisAlignment::Center)
+                                    .background(
+                                        decoration()
+                                            .color(background)
+                                            .border_radius(BorderRadius::all(2.))
+                                            .build(ctx),
+                                    )
+                                    .build(ctx, |ctx| {
+                                        mnemonic_label(&item.label)
+                                            .color(color)
+                                            .underline(underline)
+                                            .build(ctx);
+
+                                        spacer().build(ctx);
+
+                                        if let Some(hint) = &item.shortcut_hint {
+                                            text(hint)
+                                                .color(if highlighted {
+                                                    color
+                                                } else {
+                                                    theme.secondary_text
+                                                })
+                                                .build(ctx);
+                                        }
+                                    });
+                            });
+
+                            if gesture.clicked() {
+                                clicked = Some(index);
+                            }
+                        });
+                    }
+                });
+        });
+
+        if let Some(item) = clicked {
+            self.activate(menu, item, data);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::io::InputEvent;
+    use clew::testing;
+    use clew::{ClewHost, ClewInstance, KeyBinding, ShortcutId, ShortcutScopeId};
+
+    use super::*;
+
+    const EDITOR: ShortcutScopeId = ShortcutScopeId("editor");
+
+    fn menus() -> Vec<Menu> {
+        vec![
+            Menu::new(
+                "&File",
+                vec![
+                    MenuItem::new("&New"),
+                    MenuItem::new("&Open").shortcut_hint("Ctrl+O"),
+                    MenuItem::separator(),
+                    MenuItem::new("E&xit"),
+                ],
+            ),
+            Menu::new(
+                "&Edit",
+                vec![
+                    MenuItem::new("&Copy"),
+                    MenuItem::new("&Cut"),
+                    MenuItem::new("&Paste"),
+                ],
+            ),
+        ]
+    }
+
+    fn alt(key: char) -> MenuKey {
+        MenuKey::Mnemonic { key, alt: true }
+    }
+
+    fn letter(key: char) -> MenuKey {
+        MenuKey::Mnemonic { key, alt: false }
+    }
+
+    #[test]
+    fn test_alt_letter_opens_the_menu_and_letters_activate_items() {
+        let mut data = MenuBarData {
+            menus: menus(),
+            activated: None,
+        };
+        let mut bar = MenuBar::default();
+
+        // Without Alt the letters stay with the content.
+        assert!(!bar.handle_key(letter('f'), &mut data));
+        assert_eq!(bar.open, None);
+
+        assert!(bar.handle_key(alt('f'), &mut data));
+        assert_eq!((bar.open, bar.highlighted_item), (Some(0), Some(0)));
+
+        // The separator is skipped.
+        bar.handle_key(MenuKey::Up, &mut data);
+        assert_eq!(bar.highlighted_item, Some(3));
+
+        bar.handle_key(letter('x'), &mut data);
+        assert_eq!(data.activated, Some((0, 3)));
+        assert!(!bar.has_keyboard());
+    }
+
+    #[test]
+    fn test_shared_letter_moves_between_items() {
+        let mut data = MenuBarData {
+            menus: menus(),
+            activated: None,
+        };
+        let mut bar = MenuBar::default();
+
+        bar.handle_key(alt('e'), &mut data);
+        assert_eq!(bar.open, Some(1));
+
+        // Copy and Cut share the letter, it only highlights them.
+        bar.handle_key(letter('c'), &mut data);
+        assert_eq!(bar.highlighted_item, Some(1));
+        bar.handle_key(letter('c'), &mut data);
+        assert_eq!(bar.highlighted_item, Some(0));
+        assert_eq!(data.activated, None);
+
+        bar.handle_key(MenuKey::Enter, &mut data);
+        assert_eq!(data.activated, Some((1, 0)));
+    }
+
+    #[test]
+    fn test_escape_backs_out_one_level() {
+        let mut data = MenuBarData {
+            menus: menus(),
+            activated: None,
+        };
+        let mut bar = MenuBar::default();
+
+        bar.handle_key(alt('f'), &mut data);
+        bar.handle_key(MenuKey::Next, &mut data);
+        assert_eq!(bar.open, Some(1));
+
+        bar.handle_key(MenuKey::Escape, &mut data);
+        assert_eq!((bar.open, bar.highlighted_menu), (None, Some(1)));
+
+        // The bar keeps the keyboard, the arrows move between the titles.
+        bar.handle_key(MenuKey::Next, &mut data);
+        assert_eq!(bar.highlighted_menu, Some(0));
+        bar.handle_key(MenuKey::Down, &mut data);
+        assert_eq!(bar.open, Some(0));
+
+        bar.handle_key(MenuKey::Escape, &mut data);
+        bar.handle_key(MenuKey::Escape, &mut data);
+        assert!(!bar.has_keyboard());
+        assert_eq!(data.activated, None);
+    }
+
+    /// Presses the key producing the text and returns the activated item and the text
+    /// left over for the content built after the bar.
+    fn press<'a>(
+        host: &mut ClewHost<'a>,
+        instance: &mut ClewInstance<'a>,
+        key_code: KeyCode,
+        text: &str,
+    ) -> (Option<(usize, usize)>, String) {
+        let menus = menus();
+
+        instance.push_event(InputEvent::Key {
+            key_code: Some(key_code),
+            pressed: true,
+            repeat: false,
+            text: Some(text.to_string()),
+        });
+
+        let mut activated = None;
+        let mut left_over = String::new();
+
+        instance.frame(host, &mut |ctx| {
+            shortcut_scope(EDITOR).build(ctx, |ctx| {
+                activated = menu_bar(&menus).build(ctx).activated();
+                left_over = ctx.input().text_input.clone();
+            });
+        });
+
+        (activated, left_over)
+    }
+
+    #[test]
+    fn test_typed_letters_go_to_the_open_menu() {
+        let (mut host, mut instance) = testing::instance(400, 300);
+
+        instance.push_event(InputEvent::ModifiersChanged(KeyModifiers::ALT));
+        assert_eq!(
+            press(&mut host, &mut instance, KeyCode::KeyF, "f"),
+            (None, String::new())
+        );
+
+        instance.push_event(InputEvent::ModifiersChanged(KeyModifiers::empty()));
+        // The letter is matched by the character it produces, not by the key.
+        assert_eq!(
+            press(&mut host, &mut instance, KeyCode::KeyO, "x"),
+            (Some((0, 3)), String::new())
+        );
+
+        // Closed, the letters reach the content.
+        assert_eq!(
+            press(&mut host, &mut instance, KeyCode::KeyF, "f"),
+            (None, "f".to_string())
+        );
+    }
+
+    #[test]
+    fn test_alt_shortcut_bound_elsewhere_is_left_to_it() {
+        let (mut host, mut instance) = testing::instance(400, 300);
+
+        instance.ui_state().shortcuts_registry().scope(EDITOR).add(
+            ShortcutId("find"),
+            KeyBinding::new(KeyCode::KeyF).with_alt(),
+        );
+
+        instance.push_event(InputEvent::ModifiersChanged(KeyModifiers::ALT));
+        assert_eq!(
+            press(&mut host, &mut instance, KeyCode::KeyF, "f"),
+            (None, "f".to_string())
+        );
+        // Alt+E is free, it opens the menu.
+        assert_eq!(
+            press(&mut host, &mut instance, KeyCode::KeyE, "e"),
+            (None, String::new())
+        );
+    }
+}
@@ -0,0 +1,229 @@
+use std::ops::Range;
+
+use clew::io::UserInput;
+use clew::keyboard::KeyModifiers;
+use clew::{ColorRgba, widgets::*};
+use clew_derive::WidgetBuilder;
+
+/// Label with the letter that activates its item marked by an `&`, e.g. `"&File"` is
+/// `"File"` activated by F. A `&&` stands for a plain `&`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mnemonic {
+    text: String,
+    /// Byte range of the marked letter in the text.
+    range: Option<(usize, usize)>,
+}
+
+impl Mnemonic {
+    pub fn parse(label: &str) -> Self {
+        let mut text = String::with_capacity(label.len());
+        let mut range = None;
+        let mut chars = label.chars();
+
+        while let Some(char) = chars.next() {
+            if char != '&' {
+                text.push(char);
+                continue;
+            }
+
+            match chars.next() {
+                Some('&') => text.push('&'),
+                Some(marked) => {
+                    // Only the first marked letter counts, the rest are plain letters.
+                    if range.is_none() && !marked.is_whitespace() {
+                        range = Some((text.len(), text.len() + marked.len_utf8()));
+                    }
+
+                    text.push(marked);
+                }
+                None => text.push('&'),
+            }
+        }
+
+        Self { text, range }
+    }
+
+    /// Text of the label without the marks.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Marked letter in lowercase, `None` if the label has none.
+    pub fn key(&self) -> Option<char> {
+        let (start, end) = self.range?;
+
+        self.text[start..end].chars().next()?.to_lowercase().next()
+    }
+
+    /// Byte range of the marked letter in the text.
+    pub fn range(&self) -> Option<Range<usize>> {
+        self.range.map(|(start, end)| start..end)
+    }
+}
+
+/// Item a mnemonic letter picks, see [`match_mnemonic`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MnemonicMatch {
+    /// The only item with the letter, it's activated right away.
+    Activate(usize),
+    /// The next of the items sharing the letter, it's highlighted and the same letter
+    /// moves on to the one after it.
+    Highlight(usize),
+}
+
+/// Item of the `mnemonics` the typed `key` picks. When the letter is shared by several
+/// items it cycles through them starting after the `highlighted` one.
+pub fn match_mnemonic(
+    mnemonics: &[Mnemonic],
+    key: char,
+    highlighted: Option<usize>,
+) -> Option<MnemonicMatch> {
+    let key = key.to_lowercase().next()?;
+    let matches = || {
+        mnemonics
+            .iter()
+            .enumerate()
+            .filter(move |(_, mnemonic)| mnemonic.key() == Some(key))
+            .map(|(index, _)| index)
+    };
+
+    let first = matches().next()?;
+
+    if matches().nth(1).is_none() {
+        return Some(MnemonicMatch::Activate(first));
+    }
+
+    let next = highlighted
+        .and_then(|highlighted| matches().find(|index| *index > highlighted))
+        .unwrap_or(first);
+
+    Some(MnemonicMatch::Highlight(next))
+}
+
+/// Letter or digit typed in this frame that may pick a mnemonic, in lowercase. It's the
+/// character the key produces in the keyboard layout, so the mnemonics follow the
+/// layout rather than the position of the keys.
+pub fn pressed_mnemonic_key(input: &UserInput) -> Option<char> {
+    input
+        .text_input
+        .chars()
+        .find(|char| char.is_alphanumeric())
+        .and_then(|char| char.to_lowercase().next())
+}
+
+/// Whether Alt is held, the mnemonics of the labels are underlined only while it is.
+pub fn is_alt_held(input: &UserInput) -> bool {
+    input.modifiers.contains(KeyModifiers::ALT)
+}
+
+#[derive(WidgetBuilder)]
+pub struct MnemonicLabelBuilder<'a> {
+    frame: FrameBuilder,
+    mnemonic: &'a Mnemonic,
+    color: ColorRgba,
+    underline: Option<bool>,
+}
+
+impl MnemonicLabelBuilder<'_> {
+    pub fn color(mut self, color: ColorRgba) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Underlines the marked letter regardless of Alt, e.g. in a menu opened with the
+    /// keyboard.
+    pub fn underline(mut self, underline: bool) -> Self {
+        self.underline = Some(underline);
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) {
+        let Self {
+            mut frame,
+            mnemonic,
+            color,
+            underline,
+        } = self;
+        let underline = underline.unwrap_or_else(|| is_alt_held(ctx.input()));
+
+        frame.build(ctx, |ctx| {
+            let mut label = text(mnemonic.text()).color(color);
+
+            if underline && let Some(range) = mnemonic.range() {
+                label = label.underline(range);
+            }
+
+            label.build(ctx);
+        });
+    }
+}
+
+/// Text of a [`Mnemonic`] with its marked letter underlined while Alt is held, as in
+/// the menu bars of Windows and Linux. The titles and the items of a
+/// [`crate::menu_bar`] are labeled with it.
+///
+/// ```ignore
+/// let file = Mnemonic::parse("&File");
+///
+/// mnemonic_label(&file).build(ctx);
+///
+/// if is_alt_held(ctx.input())
+///     && let Some(key) = pressed_mnemonic_key(ctx.input())
+///     && let Some(MnemonicMatch::Activate(index)) = match_mnemonic(&menus, key, None)
+/// {
+///     open_menu(index);
+/// }
+/// ```
+#[track_caller]
+pub fn mnemonic_label(mnemonic: &Mnemonic) -> MnemonicLabelBuilder<'_> {
+    MnemonicLabelBuilder {
+        frame: FrameBuilder::new(),
+        mnemonic,
+        color: ColorRgba::from_hex(0xFFFFFFFF),
+        underline: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_marks() {
+        let file = Mnemonic::parse("&File");
+        assert_eq!(file.text(), "File");
+        assert_eq!(file.key(), Some('f'));
+
+        let save = Mnemonic::parse("Save && E&xit");
+        assert_eq!(save.text(), "Save & Exit");
+        assert_eq!(save.key(), Some('x'));
+        assert_eq!(save.range(), Some(8..9));
+
+        assert_eq!(Mnemonic::parse("Plain").key(), None);
+        assert_eq!(Mnemonic::parse("Trailing&").text(), "Trailing&");
+    }
+
+    #[test]
+    fn test_shared_letters_cycle() {
+        let items = ["&Copy", "&Cut", "&Paste", "Select &All"].map(Mnemonic::parse);
+
+        assert_eq!(
+            match_mnemonic(&items, 'P', None),
+            Some(MnemonicMatch::Activate(2))
+        );
+        assert_eq!(
+            match_mnemonic(&items, 'c', None),
+            Some(MnemonicMatch::Highlight(0))
+        );
+        assert_eq!(
+            match_mnemonic(&items, 'c', Some(0)),
+            Some(MnemonicMatch::Highlight(1))
+        );
+        assert_eq!(
+            match_mnemonic(&items, 'c', Some(1)),
+            Some(MnemonicMatch::Highlight(0))
+        );
+        assert_eq!(match_mnemonic(&items, 'z', None), None);
+    }
+}
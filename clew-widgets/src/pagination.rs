@@ -0,0 +1,673 @@
+use clew::io::UserInput;
+use clew::keyboard::KeyCode;
+use clew::stateful::StatefulWidget;
+use clew::{
+    AlignY, Border, BorderRadius, BorderSide, ColorRgba, Constraints, CrossAxisAlignment,
+    EdgeInsets, LayoutDirection, Rect, TextData, View, WidgetId, WidgetTheme, curves, widgets::*,
+};
+use clew::{TextAlign, prelude::*};
+use clew_derive::{WidgetBuilder, WidgetState};
+
+use crate::{HOVER_TRANSITION, is_key_pressed};
+
+const ITEM_SIZE: f32 = 28.;
+const ITEM_PADDING_X: f32 = 6.;
+const ITEM_SPACING: f32 = 4.;
+const FIELD_WIDTH: f32 = 44.;
+
+#[derive(WidgetBuilder)]
+pub struct PaginationBuilder<'a> {
+    frame: FrameBuilder,
+    page: &'a mut usize,
+    total_pages: usize,
+    compact: bool,
+    siblings: usize,
+}
+
+pub struct PaginationResponse {
+    changed: bool,
+}
+
+impl PaginationResponse {
+    /// The page was changed in this frame by a click, the keyboard or the page field.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl<'a> PaginationBuilder<'a> {
+    /// Shows only the current page of the total, e.g. "3 / 42", between the previous
+    /// and the next buttons. Clicking the page edits it, Enter goes to the typed page
+    /// and Escape cancels.
+    pub fn compact(mut self, compact: bool) -> Self {
+        self.compact = compact;
+        self
+    }
+
+    /// Pages shown on each side of the current one, 1 by default. The pages farther
+    /// away are left out except for the first and the last one.
+    pub fn siblings(mut self, siblings: usize) -> Self {
+        self.siblings = siblings;
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> PaginationResponse {
+        let Self {
+            frame,
+            page,
+            total_pages,
+            compact,
+            siblings,
+        } = self;
+
+        let current = (*page).min(total_pages.saturating_sub(1));
+        let mut data = PaginationData {
+            page: current,
+            total_pages,
+            compact,
+            siblings,
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<Pagination>().frame(frame).build(ctx);
+        });
+
+        let changed = data.page != current;
+
+        if changed {
+            *page = data.page;
+        }
+
+        PaginationResponse { changed }
+    }
+}
+
+/// Buttons that go to the first, the previous, the next and the last page, with the
+/// numbered pages around the current one in between and the gaps of the pages left
+/// out. The `page` is counted from zero. Left and Right go to the previous and the
+/// next page while it's focused, Home and End to the first and the last one. The
+/// buttons follow the layout direction of the view.
+#[track_caller]
+pub fn pagination(page: &mut usize, total_pages: usize) -> PaginationBuilder<'_> {
+    PaginationBuilder {
+        frame: FrameBuilder::new(),
+        page,
+        total_pages,
+        compact: false,
+        siblings: 1,
+    }
+}
+
+/// Page and the way the pages are shown, given by the builder each frame. The state
+/// moves `page` when an item, a key or the page field goes to another one.
+#[derive(Default)]
+struct PaginationData {
+    page: usize,
+    total_pages: usize,
+    compact: bool,
+    siblings: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PageItem {
+    First,
+    Previous,
+    Page(usize),
+    /// Pages left out between the numbered ones.
+    Gap,
+    /// Current page of the compact pagination, edited with a click.
+    Field,
+    Next,
+    Last,
+}
+
+impl PageItem {
+    /// Page the item goes to from the `page`, `None` if it's disabled or doesn't go
+    /// anywhere.
+    fn target(self, page: usize, total_pages: usize) -> Option<usize> {
+        let target = match self {
+            PageItem::First => 0,
+            PageItem::Previous => page.checked_sub(1)?,
+            PageItem::Page(target) => target,
+            PageItem::Next => page + 1,
+            PageItem::Last => total_pages.checked_sub(1)?,
+            PageItem::Gap | PageItem::Field => return None,
+        };
+
+        (target < total_pages && target != page).then_some(target)
+    }
+
+    /// Arrows point the way the pages go, they are mirrored from right to left.
+    fn label(self, layout_direction: LayoutDirection) -> String {
+        let rtl = layout_direction == LayoutDirection::RTL;
+
+        match self {
+            PageItem::First => if rtl { "»" } else { "«" }.to_string(),
+            PageItem::Previous => if rtl { "›" } else { "‹" }.to_string(),
+            PageItem::Next => if rtl { "‹" } else { "›" }.to_string(),
+            PageItem::Last => if rtl { "«" } else { "»" }.to_string(),
+            PageItem::Page(page) => (page + 1).to_string(),
+            PageItem::Gap => "…".to_string(),
+            PageItem::Field => String::new(),
+        }
+    }
+}
+
+/// Numbered pages around the current one with the first and the last page, and the
+/// gaps of the pages left out. The number of items stays the same wherever the current
+/// page is, a gap of a single page shows the page instead.
+fn page_items(page: usize, total_pages: usize, siblings: usize) -> Vec<PageItem> {
+    // The first and the last page, the current one and two gaps.
+    let slots = siblings * 2 + 5;
+
+    if total_pages <= slots {
+        return (0..total_pages).map(PageItem::Page).collect();
+    }
+
+    let last = total_pages - 1;
+    let start = page.saturating_sub(siblings).max(1);
+    let end = (page + siblings).min(last - 1);
+    let start_gap = start > 2;
+    let end_gap = end < last - 2;
+    // Pages on the side without a gap, they take the place of the gap.
+    let side = slots - 2;
+
+    let mut items = Vec::with_capacity(slots);
+
+    match (start_gap, end_gap) {
+        (false, _) => {
+            items.extend((0..side).map(PageItem::Page));
+            items.push(PageItem::Gap);
+            items.push(PageItem::Page(last));
+        }
+        (true, false) => {
+            items.push(PageItem::Page(0));
+            items.push(PageItem::Gap);
+            items.extend((total_pages - side..total_pages).map(PageItem::Page));
+        }
+        (true, true) => {
+            items.push(PageItem::Page(0));
+            items.push(PageItem::Gap);
+            items.extend((start..=end).map(PageItem::Page));
+            items.push(PageItem::Gap);
+            items.push(PageItem::Page(last));
+        }
+    }
+
+    items
+}
+
+fn items(data: &PaginationData) -> Vec<PageItem> {
+    if data.compact {
+        return vec![PageItem::Previous, PageItem::Field, PageItem::Next];
+    }
+
+    let mut items = vec![PageItem::First, PageItem::Previous];
+    items.extend(page_items(data.page, data.total_pages, data.siblings));
+    items.extend([PageItem::Next, PageItem::Last]);
+
+    items
+}
+
+#[derive(WidgetState, Default)]
+struct Pagination {
+    item_rects: Vec<(PageItem, Option<Rect>)>,
+    /// Text of the page field while the page of the compact pagination is edited.
+    editing: Option<TextData>,
+    /// The field has just been opened and takes the focus once it's built.
+    focus_field: bool,
+    field_focused: bool,
+}
+
+impl Pagination {
+    fn item_at(&self, input: &UserInput, view: &View) -> Option<PageItem> {
+        let x = input.mouse_x / view.scale_factor;
+        let y = input.mouse_y / view.scale_factor;
+
+        self.item_rects.iter().find_map(|(item, rect)| {
+            rect.is_some_and(|rect| {
+                x >= rect.left() && x < rect.right() && y >= rect.top() && y < rect.bottom()
+            })
+            .then_some(*item)
+        })
+    }
+
+    fn handle_input(
+        &mut self,
+        input: &UserInput,
+        view: &View,
+        layout_direction: LayoutDirection,
+        gesture: &GestureDetectorResponse,
+        data: &mut PaginationData,
+    ) {
+        if gesture.clicked()
+            && let Some(item) = self.item_at(input, view)
+        {
+            if let Some(target) = item.target(data.page, data.total_pages) {
+                data.page = target;
+            } else if item == PageItem::Field && self.editing.is_none() {
+                self.editing = Some(TextData::from(&(data.page + 1).to_string()));
+                self.focus_field = true;
+            }
+        }
+
+        if !gesture.is_focused() {
+            return;
+        }
+
+        // The arrows move visually, the pages go from right to left in RTL.
+        let (forward, backward) = match layout_direction {
+            LayoutDirection::LTR => (KeyCode::ArrowRight, KeyCode::ArrowLeft),
+            LayoutDirection::RTL => (KeyCode::ArrowLeft, KeyCode::ArrowRight),
+        };
+
+        let item = if is_key_pressed(input, forward) {
+            Some(PageItem::Next)
+        } else if is_key_pressed(input, backward) {
+            Some(PageItem::Previous)
+        } else if is_key_pressed(input, KeyCode::Home) {
+            Some(PageItem::First)
+        } else if is_key_pressed(input, KeyCode::End) {
+            Some(PageItem::Last)
+        } else {
+            None
+        };
+
+        if let Some(target) = item.and_then(|item| item.target(data.page, data.total_pages)) {
+            data.page = target;
+        }
+    }
+}
+
+impl StatefulWidget for Pagination {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut BuildContext, mut frame: FrameBuilder) {
+        let Some(mut data) = ctx.of_mut::<PaginationData>().map(std::mem::take) else {
+            return;
+        };
+
+        if !data.compact {
+            self.editing = None;
+        }
+
+        let theme = ctx.widget_theme();
+
+        frame.build(ctx, |ctx| {
+            gesture_detector()
+                .clickable(true)
+                .focusable(true)
+                .build(ctx, |ctx| {
+                    let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+                    let layout_direction = ctx.layout_direction();
+
+                    self.handle_input(
+                        ctx.input(),
+                        ctx.view(),
+                        layout_direction,
+                        &gesture,
+                        &mut data,
+                    );
+
+                    self.build_items(ctx, &mut data, &theme, &gesture);
+                });
+        });
+
+        if let Some(shared) = ctx.of_mut::<PaginationData>() {
+            *shared = data;
+        }
+    }
+}
+
+impl Pagination {
+    fn build_items(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &mut PaginationData,
+        theme: &WidgetTheme,
+        gesture: &GestureDetectorResponse,
+    ) {
+        let items = items(data);
+        let hovered = if gesture.is_hot() {
+            self.item_at(ctx.input(), ctx.view())
+        } else {
+            None
+        };
+
+        self.item_rects.resize(items.len(), (PageItem::Gap, None));
+
+        let mut row = hstack()
+            .rtl_aware(true)
+            .spacing(ITEM_SPACING)
+            .cross_axis_alignment(CrossAxisAlignment::Center);
+
+        if gesture.is_focus_visible() && self.editing.is_none() {
+            row = row.foreground(
+                decoration()
+                    .border_radius(BorderRadius::all(5.))
+                    .border(Border::all(BorderSide::new(2., theme.accent)))
+                    .outset(2.)
+                    .build(ctx),
+            );
+        }
+
+        row.build(ctx, |ctx| {
+            for (index, item) in items.iter().copied().enumerate() {
+                // The items are built at the same place, the scope keeps the states of
+                // their widgets apart.
+                let rect = scope(index).build(ctx, |ctx| {
+                    measure().build(ctx, |ctx| {
+                        if item == PageItem::Field {
+                            self.build_field(ctx, data, theme, gesture.id, hovered == Some(item));
+                        } else {
+                            build_item(ctx, data, theme, item, hovered == Some(item));
+                        }
+                    })
+                });
+
+                self.item_rects[index] = (item, rect);
+
+                if item == PageItem::Field {
+                    text(&format!("/ {}", data.total_pages))
+                        .color(theme.secondary_text)
+                        .text_vertical_align(AlignY::Center)
+                        .build(ctx);
+                }
+            }
+        });
+    }
+
+    /// Current page of the compact pagination, or the field it's edited in.
+    fn build_field(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &mut PaginationData,
+        theme: &WidgetTheme,
+        control: WidgetId,
+        hovered: bool,
+    ) {
+        let Some(editing) = &mut self.editing else {
+            let label = (data.page + 1).to_string();
+
+            build_label(
+                ctx,
+                &label,
+                theme.text,
+                item_background(theme, hovered),
+                FIELD_WIDTH,
+            );
+
+            return;
+        };
+
+        let mut response = None;
+
+        zstack()
+            .width(FIELD_WIDTH)
+            .height(ITEM_SIZE)
+            .align_y(AlignY::Center)
+            .padding(EdgeInsets::symmetric(ITEM_PADDING_X, 0.))
+            .background(
+                decoration()
+                    .color(theme.surface)
+                    .border_radius(BorderRadius::all(3.))
+                    .border(Border::all(BorderSide::new(1., theme.accent)))
+                    .build(ctx),
+            )
+            .build(ctx, |ctx| {
+                response = Some(
+                    editable_text(editing)
+                        .color(theme.text)
+                        .max_lines(1)
+                        .fill_max_width()
+                        .build(ctx),
+                );
+            });
+
+        let Some(response) = response else {
+            return;
+        };
+
+        if std::mem::take(&mut self.focus_field) {
+            ctx.focus_with_keyboard(response.id);
+        }
+
+        let focused = ctx.focused() == Some(response.id);
+        let input = ctx.input();
+        let commit = if focused {
+            if is_key_pressed(input, KeyCode::Enter) || is_key_pressed(input, KeyCode::NumpadEnter)
+            {
+                Some(true)
+            } else if is_key_pressed(input, KeyCode::Escape) {
+                Some(false)
+            } else {
+                None
+            }
+        } else {
+            // The focus has left the field.
+            self.field_focused.then_some(true)
+        };
+
+        self.field_focused = focused;
+
+        let Some(commit) = commit else {
+            return;
+        };
+
+        if commit
+            && let Ok(page) = editing.get_text().trim().parse::<usize>()
+            && data.total_pages > 0
+        {
+            data.page = page.clamp(1, data.total_pages) - 1;
+        }
+
+        self.editing = None;
+        self.field_focused = false;
+
+        // The arrows go on with the pages.
+        if focused {
+            ctx.focus_with_keyboard(control);
+        }
+    }
+}
+
+fn item_background(theme: &WidgetTheme, hovered: bool) -> ColorRgba {
+    if hovered {
+        theme.border.with_opacity(0.5)
+    } else {
+        theme.border.with_opacity(0.)
+    }
+}
+
+fn build_label(
+    ctx: &mut BuildContext,
+    label: &str,
+    color: ColorRgba,
+    background: ColorRgba,
+    min_width: f32,
+) {
+    let background = decoration()
+        .color(background)
+        .border_radius(BorderRadius::all(3.))
+        .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+        .build(ctx);
+
+    text(label)
+        .color(color)
+        .text_align(TextAlign::Center)
+        .text_vertical_align(AlignY::Center)
+        .height(ITEM_SIZE)
+        .constraints(Constraints {
+            min_width,
+            min_height: 0.,
+            max_width: f32::INFINITY,
+            max_height: f32::INFINITY,
+        })
+        .padding(EdgeInsets::symmetric(ITEM_PADDING_X, 0.))
+        .background(background)
+        .build(ctx);
+}
+
+fn build_item(
+    ctx: &mut BuildContext,
+    data: &PaginationData,
+    theme: &WidgetTheme,
+    item: PageItem,
+    hovered: bool,
+) {
+    let label = item.label(ctx.layout_direction());
+    let current = item == PageItem::Page(data.page);
+    let enabled = item.target(data.page, data.total_pages).is_some();
+
+    let (color, background) = if current {
+        (ColorRgba::from_hex(0xFFFFFFFF), theme.accent)
+    } else if item == PageItem::Gap {
+        (theme.secondary_text, item_background(theme, false))
+    } else if !enabled {
+        (
+            theme.secondary_text.with_opacity(0.5),
+            item_background(theme, false),
+        )
+    } else {
+        (theme.text, item_background(theme, hovered))
+    };
+
+    build_label(ctx, &label, color, background, ITEM_SIZE);
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::io::{InputEvent, MouseButton};
+    use clew::testing;
+
+    use super::*;
+
+    fn click(x: f32, y: f32) -> [Option<InputEvent>; 4] {
+        [
+            Some(InputEvent::PointerMoved { x, y }),
+            Some(InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: true,
+            }),
+            Some(InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: false,
+            }),
+            None,
+        ]
+    }
+
+    fn key(key_code: KeyCode, text: Option<&str>) -> Option<InputEvent> {
+        Some(InputEvent::Key {
+            key_code: Some(key_code),
+            pressed: true,
+            repeat: false,
+            text: text.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_pages_are_picked_with_clicks_and_keys() {
+        let (mut host, mut instance) = testing::instance(600, 100);
+        let mut page = 0;
+        let mut frame = |event: Option<InputEvent>| {
+            if let Some(event) = event {
+                instance.push_event(event);
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                pagination(&mut page, 10).build(ctx);
+            });
+
+            page
+        };
+
+        let item = ITEM_SIZE + ITEM_PADDING_X * 2. + ITEM_SPACING;
+
+        frame(None);
+
+        // The first, the previous and the first page, then the second page.
+        for event in click(item * 3. + 4., ITEM_SIZE / 2.) {
+            frame(event);
+        }
+        assert_eq!(frame(None), 1);
+
+        assert_eq!(frame(key(KeyCode::ArrowRight, None)), 2);
+        assert_eq!(frame(key(KeyCode::End, None)), 9);
+        assert_eq!(frame(key(KeyCode::ArrowRight, None)), 9);
+        assert_eq!(frame(key(KeyCode::Home, None)), 0);
+    }
+
+    #[test]
+    fn test_compact_page_is_typed_in_the_field() {
+        let (mut host, mut instance) = testing::instance(600, 100);
+        let mut page = 0;
+        let mut frame = |event: Option<InputEvent>| {
+            if let Some(event) = event {
+                instance.push_event(event);
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                pagination(&mut page, 100).compact(true).build(ctx);
+            });
+
+            page
+        };
+
+        let field_x = ITEM_SIZE + ITEM_PADDING_X * 2. + ITEM_SPACING + FIELD_WIDTH / 2.;
+
+        frame(None);
+
+        // The field after the previous button.
+        for event in click(field_x, ITEM_SIZE / 2.) {
+            frame(event);
+        }
+
+        // Typed in front of the current page, "1".
+        frame(None);
+        frame(key(KeyCode::Digit4, Some("4")));
+        assert_eq!(frame(key(KeyCode::Enter, None)), 40);
+
+        // Escape leaves the page as it is.
+        for event in click(field_x, ITEM_SIZE / 2.) {
+            frame(event);
+        }
+
+        frame(None);
+        frame(key(KeyCode::Digit3, Some("3")));
+        assert_eq!(frame(key(KeyCode::Escape, None)), 40);
+    }
+
+    use PageItem::{Gap, Page};
+
+    #[test]
+    fn test_page_items_leave_out_the_far_pages() {
+        assert_eq!(page_items(0, 3, 1), [Page(0), Page(1), Page(2)]);
+
+        // The gap of a single page shows it instead.
+        assert_eq!(
+            page_items(3, 10, 1),
+            [Page(0), Page(1), Page(2), Page(3), Page(4), Gap, Page(9)]
+        );
+        assert_eq!(
+            page_items(4, 10, 1),
+            [Page(0), Gap, Page(3), Page(4), Page(5), Gap, Page(9)]
+        );
+        assert_eq!(
+            page_items(9, 10, 1),
+            [Page(0), Gap, Page(5), Page(6), Page(7), Page(8), Page(9)]
+        );
+    }
+
+    #[test]
+    fn test_arrows_stop_at_the_ends() {
+        assert_eq!(PageItem::Previous.target(0, 5), None);
+        assert_eq!(PageItem::First.target(0, 5), None);
+        assert_eq!(PageItem::Next.target(4, 5), None);
+        assert_eq!(PageItem::Next.target(3, 5), Some(4));
+        assert_eq!(PageItem::Last.target(1, 5), Some(4));
+        assert_eq!(PageItem::Gap.target(1, 5), None);
+        assert_eq!(PageItem::Next.target(0, 0), None);
+    }
+}
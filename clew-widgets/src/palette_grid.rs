@@ -0,0 +1,636 @@
+use clew::io::UserInput;
+use clew::keyboard::KeyCode;
+use clew::prelude::*;
+use clew::stateful::StatefulWidget;
+use clew::{
+    Border, BorderRadius, BorderSide, Clip, ColorRgba, EdgeInsets, LayoutDirection, Rect, View,
+    curves, widgets::*,
+};
+use clew_derive::{WidgetBuilder, WidgetState};
+
+use crate::{HOVER_TRANSITION, is_key_pressed};
+
+const SWATCH_RADIUS: f32 = 4.;
+const RING_WIDTH: f32 = 2.;
+const CHECKER_SIZE: f32 = 4.;
+const TOOLTIP_SPACING: f32 = 4.;
+
+#[derive(WidgetBuilder)]
+pub struct PaletteGridBuilder<'a> {
+    frame: FrameBuilder,
+    color: &'a mut ColorRgba,
+    colors: &'a [ColorRgba],
+    swatch_size: f32,
+    spacing: f32,
+    columns: Option<usize>,
+    recent_capacity: usize,
+}
+
+pub struct PaletteGridResponse {
+    changed: bool,
+    double_clicked: bool,
+    confirmed: bool,
+}
+
+impl PaletteGridResponse {
+    /// The color was changed in this frame by a click or the arrows.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    /// A swatch was double clicked in this frame, e.g. to apply the color and close the
+    /// picker.
+    pub fn double_clicked(&self) -> bool {
+        self.double_clicked
+    }
+
+    /// A swatch was double clicked or Enter was pressed while the grid is focused.
+    pub fn confirmed(&self) -> bool {
+        self.confirmed
+    }
+}
+
+impl<'a> PaletteGridBuilder<'a> {
+    pub fn swatch_size(mut self, swatch_size: f32) -> Self {
+        self.swatch_size = swatch_size;
+        self
+    }
+
+    /// Space between the swatches, both between the columns and the rows.
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Fixes the number of columns, by default the swatches wrap to the width of the
+    /// grid.
+    pub fn columns(mut self, columns: usize) -> Self {
+        self.columns = Some(columns.max(1));
+        self
+    }
+
+    /// Shows the last `capacity` picked colors above the palette, the most recent
+    /// first. The colors are kept by the grid across frames.
+    pub fn recent_colors(mut self, capacity: usize) -> Self {
+        self.recent_capacity = capacity;
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> PaletteGridResponse {
+        let Self {
+            frame,
+            color,
+            colors,
+            swatch_size,
+            spacing,
+            columns,
+            recent_capacity,
+        } = self;
+
+        let mut data = PaletteGridData {
+            colors: colors.to_vec(),
+            color: *color,
+            swatch_size,
+            spacing,
+            columns,
+            recent_capacity,
+            double_clicked: false,
+            confirmed: false,
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<PaletteGrid>().frame(frame).build(ctx);
+        });
+
+        let changed = data.color != *color;
+        *color = data.color;
+
+        PaletteGridResponse {
+            changed,
+            double_clicked: data.double_clicked,
+            confirmed: data.confirmed,
+        }
+    }
+}
+
+/// Grid of color swatches that picks one of the `colors`, the picked one is marked by a
+/// ring and the hovered one shows its hex value. While the grid is focused the arrows
+/// move the selection through the swatches in the order they are seen in and wrap
+/// around at the ends, Enter confirms the color. The swatches wrap into rows at the width
+/// of the grid.
+#[track_caller]
+pub fn palette_grid<'a>(
+    color: &'a mut ColorRgba,
+    colors: &'a [ColorRgba],
+) -> PaletteGridBuilder<'a> {
+    PaletteGridBuilder {
+        frame: FrameBuilder::new().fill_max_width(),
+        color,
+        colors,
+        swatch_size: 20.,
+        spacing: 4.,
+        columns: None,
+        recent_capacity: 0,
+    }
+}
+
+/// Arguments of [`palette_grid`] handed to the retained [`PaletteGrid`] through the
+/// context. The grid writes the picked color and the confirmations into it for the
+/// response.
+#[derive(Default)]
+struct PaletteGridData {
+    colors: Vec<ColorRgba>,
+    color: ColorRgba,
+    swatch_size: f32,
+    spacing: f32,
+    columns: Option<usize>,
+    recent_capacity: usize,
+    double_clicked: bool,
+    confirmed: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Move {
+    Forward,
+    Backward,
+    Up,
+    Down,
+}
+
+#[derive(WidgetState, Default)]
+struct PaletteGrid {
+    recent: Vec<ColorRgba>,
+    /// Rects of the recent swatches followed by the ones of the palette.
+    swatch_rects: Vec<Option<Rect>>,
+    /// Swatch the arrows move from, the same color may be in the palette more than once.
+    cursor: Option<usize>,
+}
+
+impl PaletteGrid {
+    /// Colors of the swatches in the order they are seen in.
+    fn swatches(&self, data: &PaletteGridData) -> Vec<ColorRgba> {
+        self.recent
+            .iter()
+            .chain(data.colors.iter())
+            .copied()
+            .collect()
+    }
+
+    /// Lengths of the rows the swatches have been laid out in, a row ends where the
+    /// next swatch is placed lower.
+    fn row_lengths(&self) -> Vec<usize> {
+        let mut lengths: Vec<usize> = Vec::new();
+        let mut last_top = None;
+
+        for rect in &self.swatch_rects {
+            let top = rect.map(|rect| rect.top());
+
+            match (last_top, top, lengths.last_mut()) {
+                (Some(last_top), Some(top), Some(length)) if top - last_top < 0.5 => {
+                    *length += 1;
+                }
+                _ => lengths.push(1),
+            }
+
+            last_top = top;
+        }
+
+        lengths
+    }
+
+    fn swatch_at(&self, input: &UserInput, view: &View) -> Option<usize> {
+        let x = input.mouse_x / view.scale_factor;
+        let y = input.mouse_y / view.scale_factor;
+
+        self.swatch_rects.iter().position(|rect| {
+            rect.is_some_and(|rect| {
+                x >= rect.left() && x < rect.right() && y >= rect.top() && y < rect.bottom()
+            })
+        })
+    }
+
+    /// Keeps the cursor at the swatch of the picked color, the palette goes before the
+    /// recent colors when the color has been picked elsewhere.
+    fn update_cursor(&mut self, swatches: &[ColorRgba], data: &PaletteGridData) {
+        if let Some(cursor) = self.cursor
+            && swatches.get(cursor) == Some(&data.color)
+        {
+            return;
+        }
+
+        let recent = self.recent.len();
+
+        self.cursor = swatches[recent..]
+            .iter()
+            .position(|color| *color == data.color)
+            .map(|index| recent + index)
+            .or_else(|| {
+                swatches[..recent]
+                    .iter()
+                    .position(|color| *color == data.color)
+            });
+    }
+
+    fn push_recent(&mut self, color: ColorRgba, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+
+        self.recent.retain(|it| *it != color);
+        self.recent.insert(0, color);
+        self.recent.truncate(capacity);
+    }
+
+    fn handle_input(
+        &mut self,
+        input: &UserInput,
+        view: &View,
+        layout_direction: LayoutDirection,
+        gesture: &GestureDetectorResponse,
+        data: &mut PaletteGridData,
+    ) {
+        let swatches = self.swatches(data);
+        self.update_cursor(&swatches, data);
+
+        if gesture.clicked()
+            && let Some(index) = self.swatch_at(input, view)
+        {
+            data.color = swatches[index];
+            self.cursor = Some(index);
+            self.push_recent(data.color, data.recent_capacity);
+            // The recent colors have moved the swatches.
+            self.update_cursor(&self.swatches(data), data);
+        }
+
+        // The first click of a double click has picked the swatch.
+        if input.mouse_left_pressed
+            && input.mouse_left_click_count >= 2
+            && gesture.is_hot()
+            && let Some(index) = self.swatch_at(input, view)
+            && self.cursor == Some(index)
+        {
+            data.double_clicked = true;
+            data.confirmed = true;
+        }
+
+        if !gesture.is_focused() {
+            return;
+        }
+
+        if is_key_pressed(input, KeyCode::Enter) || is_key_pressed(input, KeyCode::NumpadEnter) {
+            data.confirmed = true;
+            self.push_recent(data.color, data.recent_capacity);
+            return;
+        }
+
+        // The arrows move visually, the swatches go from right to left in RTL.
+        let (forward, backward) = match layout_direction {
+            LayoutDirection::LTR => (KeyCode::ArrowRight, KeyCode::ArrowLeft),
+            LayoutDirection::RTL => (KeyCode::ArrowLeft, KeyCode::ArrowRight),
+        };
+
+        let direction = if is_key_pressed(input, forward) {
+            Some(Move::Forward)
+        } else if is_key_pressed(input, backward) {
+            Some(Move::Backward)
+        } else if is_key_pressed(input, KeyCode::ArrowUp) {
+            Some(Move::Up)
+        } else if is_key_pressed(input, KeyCode::ArrowDown) {
+            Some(Move::Down)
+        } else {
+            None
+        };
+
+        if let Some(direction) = direction
+            && let Some(target) = navigate(&self.row_lengths(), self.cursor, direction)
+        {
+            data.color = swatches[target];
+            self.cursor = Some(target);
+        }
+    }
+}
+
+/// Swatch the arrow moves to from `from` in the rows of the lengths, it wraps around at
+/// the ends of the grid. Moving between the rows keeps the column if the row is long
+/// enough. Without a swatch to move from it goes to the first one.
+fn navigate(row_lengths: &[usize], from: Option<usize>, direction: Move) -> Option<usize> {
+    let count: usize = row_lengths.iter().sum();
+
+    if count == 0 {
+        return None;
+    }
+
+    let Some(from) = from.filter(|from| *from < count) else {
+        return Some(0);
+    };
+
+    let (row, column) = {
+        let mut start = 0;
+        let mut row = 0;
+
+        while from >= start + row_lengths[row] {
+            start += row_lengths[row];
+            row += 1;
+        }
+
+        (row, from - start)
+    };
+    let row_start = |row: usize| row_lengths[..row].iter().sum::<usize>();
+
+    let target = match direction {
+        Move::Forward => (from + 1) % count,
+        Move::Backward => (from + count - 1) % count,
+        Move::Up | Move::Down => {
+            let rows = row_lengths.len();
+            let target_row = match direction {
+                Move::Up => (row + rows - 1) % rows,
+                _ => (row + 1) % rows,
+            };
+
+            row_start(target_row) + column.min(row_lengths[target_row] - 1)
+        }
+    };
+
+    Some(target)
+}
+
+impl StatefulWidget for PaletteGrid {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut BuildContext, mut frame: FrameBuilder) {
+        let Some(mut data) = ctx.of_mut::<PaletteGridData>().map(std::mem::take) else {
+            return;
+        };
+
+        self.recent.truncate(data.recent_capacity);
+
+        frame.build(ctx, |ctx| {
+            gesture_detector()
+                .clickable(true)
+                .focusable(true)
+                .build(ctx, |ctx| {
+                    let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+                    let layout_direction = ctx.layout_direction();
+
+                    self.handle_input(
+                        ctx.input(),
+                        ctx.view(),
+                        layout_direction,
+                        &gesture,
+                        &mut data,
+                    );
+
+                    self.build_grid(ctx, &data, &gesture);
+                });
+        });
+
+        if let Some(shared) = ctx.of_mut::<PaletteGridData>() {
+            *shared = data;
+        }
+    }
+}
+
+impl PaletteGrid {
+    fn build_grid(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &PaletteGridData,
+        gesture: &GestureDetectorResponse,
+    ) {
+        let swatches = self.swatches(data);
+        let hovered = if gesture.is_hot() {
+            self.swatch_at(ctx.input(), ctx.view())
+        } else {
+            None
+        };
+
+        self.swatch_rects.resize(swatches.len(), None);
+
+        // A fixed number of columns makes the rows as wide as them.
+        let width = data
+            .columns
+            .map(|columns| columns as f32 * data.swatch_size + (columns - 1) as f32 * data.spacing);
+        let groups = [0..self.recent.len(), self.recent.len()..swatches.len()];
+
+        vstack()
+            .spacing(data.spacing)
+            .fill_max_width()
+            .build(ctx, |ctx| {
+                for range in groups.into_iter().filter(|range| !range.is_empty()) {
+                    let mut grid = flow()
+                        .rtl_aware(true)
+                        .spacing(data.spacing)
+                        .run_spacing(data.spacing);
+
+                    grid = match width {
+                        Some(width) => grid.width(width),
+                        None => grid.fill_max_width(),
+                    };
+
+                    grid.build(ctx, |ctx| {
+                        for index in range {
+                            let swatch = Swatch {
+                                color: swatches[index],
+                                size: data.swatch_size,
+                                selected: self.cursor == Some(index),
+                                hovered: hovered == Some(index),
+                                focus_visible: gesture.is_focus_visible(),
+                            };
+
+                            // The swatches are built at the same place, the scope keeps
+                            // the states of their widgets apart.
+                            self.swatch_rects[index] = scope(index)
+                                .build(ctx, |ctx| measure().build(ctx, |ctx| swatch.build(ctx)));
+                        }
+                    });
+                }
+            });
+    }
+}
+
+struct Swatch {
+    color: ColorRgba,
+    size: f32,
+    selected: bool,
+    hovered: bool,
+    focus_visible: bool,
+}
+
+impl Swatch {
+    fn build(&self, ctx: &mut BuildContext) {
+        let theme = ctx.widget_theme();
+        let border_radius = BorderRadius::all(SWATCH_RADIUS);
+        let border_color = if self.hovered {
+            theme.text.with_opacity(0.4)
+        } else {
+            theme.text.with_opacity(0.15)
+        };
+
+        zstack()
+            .width(self.size)
+            .height(self.size)
+            .clip(Clip::RoundedRect { border_radius })
+            .build(ctx, |ctx| {
+                if self.color.a < 1. {
+                    self.build_checkerboard(ctx);
+                }
+
+                decorated_box()
+                    .color(self.color)
+                    .border_radius(border_radius)
+                    .border(Border::all(BorderSide::new(1., border_color)))
+                    .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                    .fill_max_size()
+                    .build(ctx);
+
+                if self.selected {
+                    let ring_color = if self.focus_visible {
+                        theme.accent
+                    } else {
+                        theme.text
+                    };
+
+                    decorated_box()
+                        .border_radius(border_radius)
+                        .border(Border::all(BorderSide::new(RING_WIDTH, ring_color)))
+                        .fill_max_size()
+                        .build(ctx);
+                }
+            });
+
+        if self.hovered {
+            portal()
+                .offset(0., self.size + TOOLTIP_SPACING)
+                .build(ctx, |ctx| {
+                    text(&hex_label(self.color))
+                        .color(theme.text)
+                        .padding(EdgeInsets::symmetric(6., 3.))
+                        .background(
+                            decoration()
+                                .color(theme.surface)
+                                .border_radius(BorderRadius::all(4.))
+                                .border(Border::all(BorderSide::new(1., theme.border)))
+                                .build(ctx),
+                        )
+                        .build(ctx);
+                });
+        }
+    }
+
+    /// Light and dark squares under the transparent colors, clipped by the swatch.
+    fn build_checkerboard(&self, ctx: &mut BuildContext) {
+        let cells = (self.size / CHECKER_SIZE).ceil() as usize;
+
+        decorated_box()
+            .color(ColorRgba::from_hex(0xFFFFFFFF))
+            .fill_max_size()
+            .build(ctx);
+
+        for row in 0..cells {
+            for column in (row % 2..cells).step_by(2) {
+                scope((row, column)).build(ctx, |ctx| {
+                    decorated_box()
+                        .color(ColorRgba::from_hex(0xFFCCCCCC))
+                        .width(CHECKER_SIZE)
+                        .height(CHECKER_SIZE)
+                        .offset(column as f32 * CHECKER_SIZE, row as f32 * CHECKER_SIZE)
+                        .build(ctx);
+                });
+            }
+        }
+    }
+}
+
+/// `#RRGGBB`, or `#RRGGBBAA` for the transparent colors.
+fn hex_label(color: ColorRgba) -> String {
+    let hex = color.to_hex();
+    let rgb = hex & 0xFFFFFF;
+    let alpha = hex >> 24;
+
+    if alpha == 0xFF {
+        format!("#{rgb:06X}")
+    } else {
+        format!("#{rgb:06X}{alpha:02X}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::io::{InputEvent, MouseButton};
+    use clew::testing;
+
+    use super::*;
+
+    #[test]
+    fn test_swatches_wrap_at_grid_width_in_first_frame() {
+        // Rows of 4 swatches of 20 with the spacing of 4 fit in the 100 of the view.
+        let (mut host, mut instance) = testing::instance(100, 100);
+        let colors: Vec<ColorRgba> = (0..6)
+            .map(|index| ColorRgba::from_hex(0xFF000000 + index))
+            .collect();
+        let mut color = colors[0];
+        let mut frame = |event: Option<InputEvent>| {
+            if let Some(event) = event {
+                instance.push_event(event);
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                palette_grid(&mut color, &colors).build(ctx);
+            });
+
+            color
+        };
+        let key = |key_code: KeyCode| {
+            Some(InputEvent::Key {
+                key_code: Some(key_code),
+                pressed: true,
+                repeat: false,
+                text: None,
+            })
+        };
+
+        frame(None);
+
+        // The first swatch of the second row.
+        frame(Some(InputEvent::PointerMoved { x: 10., y: 34. }));
+        frame(Some(InputEvent::MouseButton {
+            button: MouseButton::Left,
+            pressed: true,
+        }));
+        frame(Some(InputEvent::MouseButton {
+            button: MouseButton::Left,
+            pressed: false,
+        }));
+        assert_eq!(frame(None), colors[4]);
+
+        // The arrows follow the rows the swatches have been laid out in.
+        assert_eq!(frame(key(KeyCode::ArrowUp)), colors[0]);
+        assert_eq!(frame(key(KeyCode::ArrowRight)), colors[1]);
+        assert_eq!(frame(key(KeyCode::ArrowDown)), colors[5]);
+        assert_eq!(frame(key(KeyCode::ArrowDown)), colors[1]);
+    }
+
+    #[test]
+    fn test_navigate_wraps_around_the_grid() {
+        // A recent row of 2 above rows of 3, 3 and 1.
+        let rows = [2, 3, 3, 1];
+
+        assert_eq!(navigate(&rows, None, Move::Down), Some(0));
+        assert_eq!(navigate(&rows, Some(1), Move::Forward), Some(2));
+        assert_eq!(navigate(&rows, Some(8), Move::Forward), Some(0));
+        assert_eq!(navigate(&rows, Some(0), Move::Backward), Some(8));
+        // The column is kept if the row is long enough.
+        assert_eq!(navigate(&rows, Some(4), Move::Down), Some(7));
+        assert_eq!(navigate(&rows, Some(7), Move::Down), Some(8));
+        assert_eq!(navigate(&rows, Some(4), Move::Up), Some(1));
+        assert_eq!(navigate(&rows, Some(1), Move::Up), Some(8));
+        assert_eq!(navigate(&[], None, Move::Forward), None);
+    }
+
+    #[test]
+    fn test_hex_label() {
+        assert_eq!(hex_label(ColorRgba::from_hex(0xFF357CCE)), "#357CCE");
+        assert_eq!(hex_label(ColorRgba::from_hex(0x80357CCE)), "#357CCE80");
+    }
+}
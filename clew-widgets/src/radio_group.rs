@@ -0,0 +1,121 @@
+use clew::widgets::*;
+use clew::{FocusAxis, prelude::*};
+use clew_derive::WidgetBuilder;
+
+use crate::roving::{RovingData, RovingGroup, RovingKind, option_key};
+
+#[derive(WidgetBuilder)]
+pub struct RadioGroupBuilder<'a, T> {
+    frame: FrameBuilder,
+    selected: &'a mut T,
+    options: Vec<(T, &'a str, bool)>,
+    axis: FocusAxis,
+    selection_follows_focus: bool,
+}
+
+pub struct RadioGroupResponse {
+    changed: bool,
+}
+
+impl RadioGroupResponse {
+    /// The selection was changed in this frame by a click or the keyboard.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl<'a, T: PartialEq + Clone> RadioGroupBuilder<'a, T> {
+    pub fn option(mut self, value: T, label: &'a str) -> Self {
+        self.options.push((value, label, false));
+        self
+    }
+
+    /// Option that can't be selected and is skipped by the focus.
+    pub fn disabled_option(mut self, value: T, label: &'a str) -> Self {
+        self.options.push((value, label, true));
+        self
+    }
+
+    /// Arrow keys that move between the options, the options of the horizontal group
+    /// are laid out in a row.
+    pub fn axis(mut self, axis: FocusAxis) -> Self {
+        self.axis = axis;
+        self
+    }
+
+    /// Whether moving the focus with the keyboard selects the option, on by default.
+    /// Otherwise only Space selects the focused option.
+    pub fn selection_follows_focus(mut self, value: bool) -> Self {
+        self.selection_follows_focus = value;
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> RadioGroupResponse {
+        let Self {
+            frame,
+            selected,
+            options,
+            axis,
+            selection_follows_focus,
+        } = self;
+
+        let current = options.iter().position(|(value, ..)| value == selected);
+        let mut data = RovingData {
+            kind: RovingKind::Radio,
+            keys: option_keys(&options),
+            labels: options
+                .iter()
+                .map(|(_, label, _)| label.to_string())
+                .collect(),
+            disabled: options.iter().map(|(.., disabled)| *disabled).collect(),
+            selected: current,
+            axis,
+            selection_follows_focus,
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<RovingGroup>().frame(frame).build(ctx);
+        });
+
+        let changed = data.selected != current;
+
+        if changed && let Some(index) = data.selected {
+            *selected = options[index].0.clone();
+        }
+
+        RadioGroupResponse { changed }
+    }
+}
+
+/// Keys of the options by their labels, the values only have to be comparable. Options
+/// with the same label are told apart by their order among them.
+fn option_keys<T>(options: &[(T, &str, bool)]) -> Vec<u64> {
+    options
+        .iter()
+        .enumerate()
+        .map(|(index, (_, label, _))| {
+            let repeat = options[..index]
+                .iter()
+                .filter(|(_, it, _)| it == label)
+                .count();
+
+            option_key(&(label, repeat))
+        })
+        .collect()
+}
+
+/// Radio buttons that pick one of the options, the group is a single Tab stop. Tab
+/// enters it on the option focused last or the selected one, the arrows move the focus
+/// and the selection between the options, Home and End to the first and the last one,
+/// and typing jumps to the option whose label starts with the typed letters.
+#[track_caller]
+pub fn radio_group<T: PartialEq + Clone>(selected: &mut T) -> RadioGroupBuilder<'_, T> {
+    RadioGroupBuilder {
+        frame: FrameBuilder::new(),
+        selected,
+        options: Vec::new(),
+        axis: FocusAxis::Both,
+        selection_follows_focus: true,
+    }
+}
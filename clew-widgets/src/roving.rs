@@ -0,0 +1,583 @@
+use std::hash::{DefaultHasher, Hash, Hasher};
+
+use clew::io::UserInput;
+use clew::keyboard::KeyCode;
+use clew::prelude::*;
+use clew::stateful::StatefulWidget;
+use clew::{
+    AlignX, AlignY, Border, BorderRadius, BorderSide, ColorRgba, CrossAxisAlignment, EdgeInsets,
+    FocusAxis, WidgetId, curves, widgets::*,
+};
+use clew_derive::WidgetState;
+
+use crate::select::{TYPE_AHEAD_TIMEOUT, type_ahead_match};
+use crate::{HOVER_TRANSITION, is_key_pressed};
+
+const RADIO_SIZE: f32 = 14.;
+const RADIO_DOT_SIZE: f32 = 6.;
+const ROW_HEIGHT: f32 = 24.;
+const LIST_PADDING: f32 = 4.;
+
+/// How the options of a group are drawn.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum RovingKind {
+    #[default]
+    Radio,
+    Listbox,
+}
+
+/// What a [`crate::radio_group`] or a [`crate::listbox`] hands to the shared
+/// [`RovingGroup`] state in a frame: its options and their selection, which the group
+/// moves with the keyboard and the pointer before the wrapper reads it back.
+#[derive(Default)]
+pub(crate) struct RovingData {
+    pub(crate) kind: RovingKind,
+    /// Keys the options are told apart by between frames.
+    pub(crate) keys: Vec<u64>,
+    pub(crate) labels: Vec<String>,
+    pub(crate) disabled: Vec<bool>,
+    pub(crate) selected: Option<usize>,
+    pub(crate) axis: FocusAxis,
+    pub(crate) selection_follows_focus: bool,
+}
+
+impl RovingData {
+    fn enabled(&self) -> Vec<usize> {
+        (0..self.keys.len())
+            .filter(|index| !self.disabled[*index])
+            .collect()
+    }
+
+    fn position(&self, key: u64) -> Option<usize> {
+        self.keys.iter().position(|it| *it == key)
+    }
+}
+
+/// Group of options that is a single Tab stop, the focus roves between the options
+/// with the arrows, see [`crate::radio_group`] and [`crate::listbox`].
+#[derive(WidgetState, Default)]
+pub(crate) struct RovingGroup {
+    /// Keys of the options built in the last frame and the ids of their widgets.
+    ids: Vec<(u64, WidgetId)>,
+    /// Option that had the focus last, Tab enters the group on it.
+    last_focused: Option<u64>,
+    type_ahead: String,
+    type_ahead_timer: f32,
+}
+
+impl RovingGroup {
+    fn id_of(&self, key: u64) -> Option<WidgetId> {
+        self.ids
+            .iter()
+            .find(|(it, _)| *it == key)
+            .map(|(_, id)| *id)
+    }
+
+    /// Moves the focus and the selection with the keyboard while an option is focused.
+    /// The arrows have already moved the focus to another option at the start of the
+    /// frame, here it's only followed by the selection.
+    fn handle_input(&mut self, ctx: &mut BuildContext, data: &mut RovingData) {
+        let focused_key = ctx.focused().and_then(|focused| {
+            self.ids
+                .iter()
+                .find(|(_, id)| *id == focused)
+                .map(|(key, _)| *key)
+        });
+
+        let Some(focused_key) = focused_key else {
+            return;
+        };
+
+        let enabled = data.enabled();
+
+        let Some(focused) = data.position(focused_key) else {
+            // The focused option is gone, the focus goes to the option at its place
+            // rather than out of the group.
+            let index = self.ids.iter().position(|(key, _)| *key == focused_key);
+            let target = index.and_then(|index| {
+                enabled
+                    .iter()
+                    .find(|it| **it >= index)
+                    .or(enabled.last())
+                    .copied()
+            });
+
+            if let Some(target) = target
+                && let Some(id) = self.id_of(data.keys[target])
+            {
+                ctx.focus_with_keyboard(id);
+                self.last_focused = Some(data.keys[target]);
+            }
+
+            return;
+        };
+
+        let input = ctx.input();
+        let moved_with_arrows = self.last_focused != Some(focused_key)
+            && arrows(data.axis)
+                .into_iter()
+                .any(|key| is_key_pressed(input, key));
+
+        let target = if is_key_pressed(input, KeyCode::Home) {
+            enabled.first().copied()
+        } else if is_key_pressed(input, KeyCode::End) {
+            enabled.last().copied()
+        } else {
+            self.type_ahead(input, data, &enabled, focused)
+        };
+
+        let mut focused = focused;
+
+        if let Some(target) = target
+            && target != focused
+            && let Some(id) = self.id_of(data.keys[target])
+        {
+            ctx.focus_with_keyboard(id);
+            focused = target;
+        }
+
+        if data.selection_follows_focus && (moved_with_arrows || target.is_some()) {
+            data.selected = Some(focused);
+        }
+
+        if is_key_pressed(ctx.input(), KeyCode::Space) {
+            data.selected = Some(focused);
+        }
+
+        self.last_focused = Some(data.keys[focused]);
+    }
+
+    /// Option whose label starts with the letters typed in a quick succession.
+    fn type_ahead(
+        &mut self,
+        input: &UserInput,
+        data: &RovingData,
+        enabled: &[usize],
+        focused: usize,
+    ) -> Option<usize> {
+        // Space selects the focused option.
+        if input.text_input.is_empty() || is_key_pressed(input, KeyCode::Space) {
+            return None;
+        }
+
+        if self.type_ahead_timer <= 0. {
+            self.type_ahead.clear();
+        }
+
+        self.type_ahead.push_str(&input.text_input);
+        self.type_ahead_timer = TYPE_AHEAD_TIMEOUT;
+
+        type_ahead_match(&data.labels, enabled, Some(focused), &self.type_ahead)
+    }
+
+    /// Member of the focus group Tab enters it on: the option focused last, then the
+    /// selected one, then the first one. Disabled options aren't members.
+    fn primary(&self, data: &RovingData) -> usize {
+        let enabled = data.enabled();
+        let entry = self
+            .last_focused
+            .and_then(|key| data.position(key))
+            .filter(|index| enabled.contains(index))
+            .or(data.selected.filter(|index| enabled.contains(index)));
+
+        entry
+            .and_then(|entry| enabled.iter().position(|it| *it == entry))
+            .unwrap_or(0)
+    }
+}
+
+/// Key an option is told apart by between frames, so the focus and the selection stay
+/// with the option when the options before it change.
+pub(crate) fn option_key(value: &impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// Arrow keys that move the focus along the axis.
+fn arrows(axis: FocusAxis) -> Vec<KeyCode> {
+    match axis {
+        FocusAxis::Horizontal => vec![KeyCode::ArrowLeft, KeyCode::ArrowRight],
+        FocusAxis::Vertical => vec![KeyCode::ArrowUp, KeyCode::ArrowDown],
+        FocusAxis::Both => vec![
+            KeyCode::ArrowLeft,
+            KeyCode::ArrowRight,
+            KeyCode::ArrowUp,
+            KeyCode::ArrowDown,
+        ],
+    }
+}
+
+impl StatefulWidget for RovingGroup {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut BuildContext, mut frame: FrameBuilder) {
+        let Some(mut data) = ctx.of_mut::<RovingData>().map(std::mem::take) else {
+            return;
+        };
+
+        self.type_ahead_timer -= ctx.delta_time();
+        self.handle_input(ctx, &mut data);
+
+        let selected = data.selected;
+        let primary = self.primary(&data);
+        let mut ids = Vec::with_capacity(data.keys.len());
+
+        frame.build(ctx, |ctx| {
+            focus_group()
+                .primary(primary)
+                .axis(data.axis)
+                .build(ctx, |ctx| match (data.kind, data.axis) {
+                    (RovingKind::Radio, FocusAxis::Horizontal) => {
+                        hstack()
+                            .spacing(16.)
+                            .build(ctx, |ctx| build_options(ctx, &mut data, &mut ids));
+                    }
+                    (RovingKind::Radio, _) => {
+                        vstack()
+                            .spacing(6.)
+                            .build(ctx, |ctx| build_options(ctx, &mut data, &mut ids));
+                    }
+                    (RovingKind::Listbox, _) => {
+                        let theme = ctx.widget_theme();
+
+                        vstack()
+                            .fill_max_width()
+                            .spacing(0.)
+                            .padding(EdgeInsets::all(LIST_PADDING))
+                            .background(
+                                decoration()
+                                    .color(theme.sunken(0.5))
+                                    .border_radius(BorderRadius::all(3.))
+                                    .border(Border::all(BorderSide::new(1., theme.border)))
+                                    .build(ctx),
+                            )
+                            .build(ctx, |ctx| build_options(ctx, &mut data, &mut ids));
+                    }
+                });
+        });
+
+        self.ids = ids;
+
+        // A click is only seen when the option is built, the options before it are
+        // drawn with the old selection.
+        if data.selected != selected {
+            ctx.request_frame();
+        }
+
+        if let Some(shared) = ctx.of_mut::<RovingData>() {
+            *shared = data;
+        }
+    }
+}
+
+fn build_options(ctx: &mut BuildContext, data: &mut RovingData, ids: &mut Vec<(u64, WidgetId)>) {
+    for index in 0..data.keys.len() {
+        let key = data.keys[index];
+        let disabled = data.disabled[index];
+
+        // The options are built at the same place, the scope keeps the states of
+        // their widgets apart and with the option when the list changes.
+        let response = scope(key).build(ctx, |ctx| {
+            gesture_detector()
+                .clickable(!disabled)
+                .focusable(!disabled)
+                .build(ctx, |ctx| {
+                    let response = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+                    let selected = data.selected == Some(index);
+
+                    match data.kind {
+                        RovingKind::Radio => {
+                            build_radio(ctx, &data.labels[index], selected, disabled, &response)
+                        }
+                        RovingKind::Listbox => {
+                            build_row(ctx, &data.labels[index], selected, disabled, &response)
+                        }
+                    }
+                })
+        });
+
+        ids.push((key, response.id));
+
+        if response.clicked() {
+            data.selected = Some(index);
+        }
+    }
+}
+
+fn build_radio(
+    ctx: &mut BuildContext,
+    label: &str,
+    selected: bool,
+    disabled: bool,
+    response: &GestureDetectorResponse,
+) {
+    let theme = ctx.widget_theme();
+    let hot = !disabled && response.is_hot();
+    let border = if response.is_focus_visible() {
+        BorderSide::new(2., theme.accent)
+    } else if hot {
+        BorderSide::new(1., theme.secondary_text)
+    } else {
+        BorderSide::new(1., theme.border)
+    };
+    let text_color = if disabled {
+        theme.secondary_text
+    } else {
+        theme.text
+    };
+
+    hstack()
+        .spacing(8.)
+        .cross_axis_alignment(CrossAxisAlignment::Center)
+        .build(ctx, |ctx| {
+            zstack()
+                .width(RADIO_SIZE)
+                .height(RADIO_SIZE)
+                .align_x(AlignX::Center)
+                .align_y(AlignY::Center)
+                .background(
+                    decoration()
+                        .color(theme.surface)
+                        .border_radius(BorderRadius::all(RADIO_SIZE / 2.))
+                        .border(Border::all(border))
+                        .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                        .build(ctx),
+                )
+                .build(ctx, |ctx| {
+                    if selected {
+                        decorated_box()
+                            .color(if disabled { theme.border } else { theme.accent })
+                            .border_radius(BorderRadius::all(RADIO_DOT_SIZE / 2.))
+                            .width(RADIO_DOT_SIZE)
+                            .height(RADIO_DOT_SIZE)
+                            .build(ctx);
+                    }
+                });
+
+            text(label)
+                .color(text_color)
+                .text_vertical_align(AlignY::Center)
+                .build(ctx);
+        });
+}
+
+fn build_row(
+    ctx: &mut BuildContext,
+    label: &str,
+    selected: bool,
+    disabled: bool,
+    response: &GestureDetectorResponse,
+) {
+    let theme = ctx.widget_theme();
+    let background = if selected {
+        theme.accent
+    } else if !disabled && response.is_hot() {
+        theme.raised(0.05)
+    } else {
+        ColorRgba::TRANSPARENT
+    };
+    let text_color = if disabled {
+        theme.secondary_text
+    } else if selected {
+        ColorRgba::from_hex(0xFFFFFFFF)
+    } else {
+        theme.text
+    };
+
+    let mut row = text(label)
+        .color(text_color)
+        .text_vertical_align(AlignY::Center)
+        .fill_max_width()
+        .height(ROW_HEIGHT)
+        .padding(EdgeInsets::symmetric(8., 0.))
+        .background(
+            decoration()
+                .color(background)
+                .border_radius(BorderRadius::all(2.))
+                .build(ctx),
+        );
+
+    if response.is_focus_visible() {
+        row = row.foreground(
+            decoration()
+                .border_radius(BorderRadius::all(2.))
+                .border(Border::all(BorderSide::new(
+                    1.,
+                    if selected {
+                        ColorRgba::from_hex(0xFFFFFFFF)
+                    } else {
+                        theme.accent
+                    },
+                )))
+                .build(ctx),
+        );
+    }
+
+    row.build(ctx);
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::io::InputEvent;
+    use clew::testing;
+
+    use super::*;
+
+    use crate::{SelectOption, listbox, radio_group};
+
+    struct Item(usize);
+
+    impl Identifiable for Item {
+        type Id = usize;
+
+        fn id(&self) -> usize {
+            self.0
+        }
+    }
+
+    impl SelectOption for Item {
+        fn label(&self) -> &str {
+            "Item"
+        }
+    }
+
+    fn key(key: Option<KeyCode>, text: Option<&str>) -> Option<InputEvent> {
+        Some(InputEvent::Key {
+            key_code: Some(key?),
+            pressed: true,
+            repeat: false,
+            text: text.map(str::to_string),
+        })
+    }
+
+    #[test]
+    fn test_radio_group_is_one_tab_stop_and_arrows_select() {
+        let (mut host, mut instance) = testing::instance(300, 300);
+        let mut selected = "b";
+        let mut press = |key_code: Option<KeyCode>, text| {
+            if let Some(event) = key(key_code, text) {
+                instance.push_event(event);
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                radio_group(&mut selected)
+                    .option("a", "Alpha")
+                    .disabled_option("x", "Disabled")
+                    .option("b", "Beta")
+                    .option("c", "Gamma")
+                    .build(ctx);
+            });
+
+            selected
+        };
+
+        press(None, None);
+        // Tab enters on the selected option without changing it.
+        assert_eq!(press(Some(KeyCode::Tab), None), "b");
+        assert_eq!(press(Some(KeyCode::ArrowDown), None), "c");
+        // The disabled option is skipped.
+        assert_eq!(press(Some(KeyCode::ArrowUp), None), "b");
+        assert_eq!(press(Some(KeyCode::ArrowUp), None), "a");
+        assert_eq!(press(Some(KeyCode::End), None), "c");
+        assert_eq!(press(Some(KeyCode::KeyA), Some("a")), "a");
+    }
+
+    #[test]
+    fn test_radio_group_keeps_focus_when_options_change() {
+        let (mut host, mut instance) = testing::instance(300, 300);
+        let mut selected = "b";
+        let mut press = |key_code: Option<KeyCode>, options: &[(&'static str, &str)]| {
+            if let Some(event) = key(key_code, None) {
+                instance.push_event(event);
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                let mut group = radio_group(&mut selected);
+
+                for (value, label) in options {
+                    group = group.option(*value, label);
+                }
+
+                group.build(ctx);
+            });
+
+            selected
+        };
+        let options = [("a", "Alpha"), ("b", "Beta"), ("c", "Gamma")];
+
+        press(None, &options);
+        assert_eq!(press(Some(KeyCode::Tab), &options), "b");
+
+        // An option is inserted before the focused one, the focus stays on Beta.
+        let options = [
+            ("x", "Extra"),
+            ("a", "Alpha"),
+            ("b", "Beta"),
+            ("c", "Gamma"),
+        ];
+        assert_eq!(press(None, &options), "b");
+        assert_eq!(press(Some(KeyCode::ArrowDown), &options), "c");
+        assert_eq!(press(Some(KeyCode::ArrowUp), &options), "b");
+    }
+
+    #[test]
+    fn test_listbox_keeps_focus_and_selection_when_items_change() {
+        let (mut host, mut instance) = testing::instance(300, 300);
+        let mut selected = Some(2usize);
+        let mut press = |key_code: Option<KeyCode>, items: &[usize]| {
+            let items: Vec<_> = items.iter().copied().map(Item).collect();
+
+            if let Some(event) = key(key_code, Some(" ")) {
+                instance.push_event(event);
+            }
+
+            let mut changed = false;
+
+            instance.frame(&mut host, &mut |ctx| {
+                changed = listbox(&mut selected, &items)
+                    .selection_follows_focus(false)
+                    .build(ctx)
+                    .changed();
+            });
+
+            (changed, selected)
+        };
+
+        press(None, &[1, 2, 3]);
+        press(Some(KeyCode::Tab), &[1, 2, 3]);
+        // An item is inserted before the focused and selected one.
+        assert_eq!(press(None, &[0, 1, 2, 3]), (false, Some(2)));
+        assert_eq!(
+            press(Some(KeyCode::ArrowDown), &[0, 1, 2, 3]),
+            (false, Some(2))
+        );
+        assert_eq!(press(Some(KeyCode::Space), &[0, 1, 2, 3]), (true, Some(3)));
+        // The selected item is removed, the focus moves to the item at its place.
+        assert_eq!(press(None, &[0, 1, 2]), (true, None));
+        assert_eq!(press(Some(KeyCode::Space), &[0, 1, 2]), (true, Some(2)));
+    }
+
+    #[test]
+    fn test_tab_enters_on_last_focused_then_selected_option() {
+        let data = RovingData {
+            keys: vec![10, 11, 12, 13],
+            labels: vec![String::new(); 4],
+            disabled: vec![true, false, false, false],
+            selected: Some(3),
+            ..Default::default()
+        };
+        let mut group = RovingGroup::default();
+
+        // Members are the enabled options, the selected one is the third of them.
+        assert_eq!(group.primary(&data), 2);
+
+        group.last_focused = Some(12);
+        assert_eq!(group.primary(&data), 1);
+
+        // The option focused last is gone.
+        group.last_focused = Some(20);
+        assert_eq!(group.primary(&data), 2);
+    }
+}
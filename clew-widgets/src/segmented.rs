@@ -0,0 +1,479 @@
+use std::time::Duration;
+
+use clew::io::UserInput;
+use clew::keyboard::KeyCode;
+use clew::stateful::StatefulWidget;
+use clew::{
+    AlignY, Border, BorderRadius, BorderSide, ColorRgba, CrossAxisAlignment, EdgeInsets,
+    LayoutDirection, Lerp, LinearGradient, MainAxisAlignment, Rect, View, curves, widgets::*,
+};
+use clew::{AnimationStatus, TextAlign, Tween, prelude::*};
+use clew_derive::{WidgetBuilder, WidgetState};
+
+use crate::{HOVER_TRANSITION, is_key_pressed};
+
+const TRACK_PADDING: f32 = 2.;
+const SEGMENT_PADDING_X: f32 = 12.;
+const SEGMENT_PADDING_Y: f32 = 5.;
+const ICON_SIZE: f32 = 14.;
+const ICON_SPACING: f32 = 6.;
+const INDICATOR_TRANSITION: Duration = Duration::from_millis(180);
+
+/// Segment of a [`segmented_control`], a plain `&str` is a segment with the label.
+#[derive(Debug, Clone, Copy)]
+pub struct Segment<'a> {
+    label: &'a str,
+    icon: Option<&'static str>,
+    disabled: bool,
+}
+
+impl<'a> Segment<'a> {
+    pub fn new(label: &'a str) -> Self {
+        Self {
+            label,
+            icon: None,
+            disabled: false,
+        }
+    }
+
+    /// Svg asset shown before the label.
+    pub fn icon(mut self, asset_id: &'static str) -> Self {
+        self.icon = Some(asset_id);
+        self
+    }
+
+    /// A disabled segment can't be selected by a click or the arrows.
+    pub fn disabled(mut self, disabled: bool) -> Self {
+        self.disabled = disabled;
+        self
+    }
+}
+
+impl<'a> From<&'a str> for Segment<'a> {
+    fn from(label: &'a str) -> Self {
+        Segment::new(label)
+    }
+}
+
+/// How the width of the segments of a [`segmented_control`] is chosen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SegmentWidth {
+    /// Every segment is as wide as the widest one.
+    #[default]
+    Equal,
+    /// Every segment is as wide as its content.
+    Content,
+}
+
+#[derive(WidgetBuilder)]
+pub struct SegmentedControlBuilder<'a> {
+    frame: FrameBuilder,
+    selected: &'a mut usize,
+    segments: Vec<Segment<'a>>,
+    segment_width: SegmentWidth,
+}
+
+pub struct SegmentedControlResponse {
+    changed: bool,
+}
+
+impl SegmentedControlResponse {
+    /// The selection was changed in this frame by a click or the arrows.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+}
+
+impl<'a> SegmentedControlBuilder<'a> {
+    pub fn segment_width(mut self, segment_width: SegmentWidth) -> Self {
+        self.segment_width = segment_width;
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> SegmentedControlResponse {
+        let Self {
+            frame,
+            selected,
+            segments,
+            segment_width,
+        } = self;
+
+        let mut data = SegmentedControlData {
+            labels: segments.iter().map(|it| it.label.to_string()).collect(),
+            icons: segments.iter().map(|it| it.icon).collect(),
+            disabled: segments.iter().map(|it| it.disabled).collect(),
+            selected: *selected,
+            segment_width,
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<SegmentedControl>().frame(frame).build(ctx);
+        });
+
+        let changed = data.selected != *selected;
+        *selected = data.selected;
+
+        SegmentedControlResponse { changed }
+    }
+}
+
+/// Row of mutually exclusive segments, the selected one is raised by an indicator that
+/// slides to the segment picked with a click, or with the Left and Right arrows while
+/// the control is focused. The segments follow the layout direction of the view.
+#[track_caller]
+pub fn segmented_control<'a, S>(
+    selected: &'a mut usize,
+    segments: &'a [S],
+) -> SegmentedControlBuilder<'a>
+where
+    S: Into<Segment<'a>> + Copy,
+{
+    SegmentedControlBuilder {
+        frame: FrameBuilder::new(),
+        selected,
+        segments: segments.iter().map(|it| (*it).into()).collect(),
+        segment_width: SegmentWidth::default(),
+    }
+}
+
+/// Segments of the current frame passed down to the control state, and the updated
+/// selection passed back.
+#[derive(Default)]
+struct SegmentedControlData {
+    labels: Vec<String>,
+    icons: Vec<Option<&'static str>>,
+    disabled: Vec<bool>,
+    selected: usize,
+    segment_width: SegmentWidth,
+}
+
+#[derive(WidgetState, Default)]
+struct SegmentedControl {
+    track_rect: Option<Rect>,
+    segment_rects: Vec<Option<Rect>>,
+    /// Width of the icon and the label of every segment.
+    content_widths: Vec<f32>,
+    indicator_x: Tween<f32>,
+    indicator_width: Tween<f32>,
+    /// Segment the indicator is at or moving to, `None` until it's placed.
+    indicator_segment: Option<usize>,
+}
+
+impl SegmentedControl {
+    fn segment_at(&self, input: &UserInput, view: &View) -> Option<usize> {
+        let x = input.mouse_x / view.scale_factor;
+        let y = input.mouse_y / view.scale_factor;
+
+        self.segment_rects.iter().position(|rect| {
+            rect.is_some_and(|rect| {
+                x >= rect.left() && x < rect.right() && y >= rect.top() && y < rect.bottom()
+            })
+        })
+    }
+
+    fn handle_input(
+        &mut self,
+        input: &UserInput,
+        view: &View,
+        layout_direction: LayoutDirection,
+        gesture: &GestureDetectorResponse,
+        data: &mut SegmentedControlData,
+    ) {
+        if gesture.clicked()
+            && let Some(index) = self.segment_at(input, view)
+            && !data.disabled[index]
+        {
+            data.selected = index;
+        }
+
+        if !gesture.is_focused() {
+            return;
+        }
+
+        // The arrows move visually, the segments go from right to left in RTL.
+        let forward = match layout_direction {
+            LayoutDirection::LTR => KeyCode::ArrowRight,
+            LayoutDirection::RTL => KeyCode::ArrowLeft,
+        };
+        let backward = match layout_direction {
+            LayoutDirection::LTR => KeyCode::ArrowLeft,
+            LayoutDirection::RTL => KeyCode::ArrowRight,
+        };
+
+        let target = if is_key_pressed(input, forward) {
+            next_enabled(&data.disabled, data.selected, true)
+        } else if is_key_pressed(input, backward) {
+            next_enabled(&data.disabled, data.selected, false)
+        } else {
+            None
+        };
+
+        if let Some(target) = target {
+            data.selected = target;
+        }
+    }
+
+    /// Moves the indicator to the selected segment, it's placed without the animation
+    /// the first time.
+    fn update_indicator(&mut self, selected: usize) {
+        let Some(track) = self.track_rect else {
+            return;
+        };
+        let Some(Some(rect)) = self.segment_rects.get(selected) else {
+            return;
+        };
+
+        let x = rect.x - track.x;
+
+        if self.indicator_segment.is_none() {
+            self.indicator_x = Tween::new(x)
+                .duration(INDICATOR_TRANSITION)
+                .curve(curves::f32::ease_out_cubic);
+            self.indicator_width = Tween::new(rect.width)
+                .duration(INDICATOR_TRANSITION)
+                .curve(curves::f32::ease_out_cubic);
+        } else if self.indicator_segment != Some(selected) {
+            self.indicator_x.tween_to(x);
+            self.indicator_width.tween_to(rect.width);
+        } else if self.indicator_x.status() == AnimationStatus::Idle {
+            // The segments have been resized.
+            self.indicator_x.set(x);
+            self.indicator_width.set(rect.width);
+        }
+
+        self.indicator_segment = Some(selected);
+    }
+
+    fn segment_width(&self, data: &SegmentedControlData) -> Option<f32> {
+        match data.segment_width {
+            SegmentWidth::Equal => self
+                .content_widths
+                .iter()
+                .copied()
+                .reduce(f32::max)
+                .map(|width| width + SEGMENT_PADDING_X * 2.),
+            SegmentWidth::Content => None,
+        }
+    }
+}
+
+/// Closest segment that isn't disabled after `from`, or before it if `forward` is
+/// false.
+fn next_enabled(disabled: &[bool], from: usize, forward: bool) -> Option<usize> {
+    if forward {
+        (from + 1..disabled.len()).find(|index| !disabled[*index])
+    } else {
+        (0..from.min(disabled.len()))
+            .rev()
+            .find(|index| !disabled[*index])
+    }
+}
+
+impl StatefulWidget for SegmentedControl {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut BuildContext, mut frame: FrameBuilder) {
+        let Some(mut data) = ctx.of_mut::<SegmentedControlData>().map(std::mem::take) else {
+            return;
+        };
+
+        let count = data.labels.len();
+        self.segment_rects.resize(count, None);
+        self.content_widths.resize(count, 0.);
+
+        frame.build(ctx, |ctx| {
+            gesture_detector()
+                .clickable(true)
+                .focusable(true)
+                .build(ctx, |ctx| {
+                    let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+                    let layout_direction = ctx.layout_direction();
+
+                    self.handle_input(
+                        ctx.input(),
+                        ctx.view(),
+                        layout_direction,
+                        &gesture,
+                        &mut data,
+                    );
+                    self.update_indicator(data.selected);
+
+                    self.build_track(ctx, &data, &gesture);
+                });
+        });
+
+        if let Some(shared) = ctx.of_mut::<SegmentedControlData>() {
+            *shared = data;
+        }
+    }
+}
+
+impl SegmentedControl {
+    fn build_track(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &SegmentedControlData,
+        gesture: &GestureDetectorResponse,
+    ) {
+        let theme = ctx.widget_theme();
+        let border_color = if gesture.is_focus_visible() {
+            theme.accent
+        } else {
+            theme.border
+        };
+        let hovered = if gesture.is_hot() {
+            self.segment_at(ctx.input(), ctx.view())
+        } else {
+            None
+        };
+        let segment_width = self.segment_width(data);
+
+        zstack()
+            .background(
+                decoration()
+                    .color(theme.sunken(0.5))
+                    .border_radius(BorderRadius::all(5.))
+                    .border(Border::all(BorderSide::new(1., border_color)))
+                    .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                    .build(ctx),
+            )
+            .build(ctx, |ctx| {
+                if self.indicator_segment.is_some()
+                    && let Some(track) = self.track_rect
+                {
+                    let x = self.indicator_x.resolve(ctx);
+                    let width = self.indicator_width.resolve(ctx);
+
+                    decorated_box()
+                        .add_linear_gradient(LinearGradient::vertical((
+                            theme.raised(0.1),
+                            theme.raised(0.05),
+                        )))
+                        .border_radius(BorderRadius::all(4.))
+                        .border(Border::all(BorderSide::new(
+                            1.,
+                            theme.border.lerp(theme.text, 0.2),
+                        )))
+                        .width(width)
+                        .height(track.height - TRACK_PADDING * 2.)
+                        .offset(x, TRACK_PADDING)
+                        .build(ctx);
+                }
+
+                self.track_rect = measure().build(ctx, |ctx| {
+                    hstack()
+                        .rtl_aware(true)
+                        .spacing(0.)
+                        .padding(EdgeInsets::all(TRACK_PADDING))
+                        .build(ctx, |ctx| {
+                            for index in 0..data.labels.len() {
+                                let color = if data.disabled[index] {
+                                    theme.secondary_text.with_opacity(0.5)
+                                } else if index == data.selected {
+                                    theme.text
+                                } else if hovered == Some(index) {
+                                    theme.secondary_text.lerp(theme.text, 0.5)
+                                } else {
+                                    theme.secondary_text
+                                };
+
+                                // The segments are built at the same place, the scope
+                                // keeps the states of their widgets apart.
+                                self.segment_rects[index] = scope(index).build(ctx, |ctx| {
+                                    measure().build(ctx, |ctx| {
+                                        self.build_segment(ctx, data, index, segment_width, color)
+                                    })
+                                });
+                            }
+                        });
+                });
+            });
+    }
+
+    fn build_segment(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &SegmentedControlData,
+        index: usize,
+        width: Option<f32>,
+        color: ColorRgba,
+    ) {
+        let mut segment = hstack()
+            .spacing(0.)
+            .main_axis_alignment(MainAxisAlignment::Center)
+            .padding(EdgeInsets::symmetric(SEGMENT_PADDING_X, SEGMENT_PADDING_Y));
+
+        if let Some(width) = width {
+            segment = segment.width(width);
+        }
+
+        segment.build(ctx, |ctx| {
+            let content = measure().build(ctx, |ctx| {
+                hstack()
+                    .rtl_aware(true)
+                    .spacing(ICON_SPACING)
+                    .cross_axis_alignment(CrossAxisAlignment::Center)
+                    .build(ctx, |ctx| {
+                        if let Some(icon) = data.icons[index] {
+                            svg(icon)
+                                .color(color)
+                                .width(ICON_SIZE)
+                                .height(ICON_SIZE)
+                                .build(ctx);
+                        }
+
+                        text(&data.labels[index])
+                            .color(color)
+                            .text_align(TextAlign::Center)
+                            .text_vertical_align(AlignY::Center)
+                            .build(ctx);
+                    });
+            });
+
+            if let Some(content) = content {
+                self.content_widths[index] = content.width;
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::render::RenderCommand;
+    use clew::testing;
+
+    use super::*;
+
+    #[test]
+    fn test_next_enabled_skips_disabled_segments() {
+        let disabled = [false, true, false, true];
+
+        assert_eq!(next_enabled(&disabled, 0, true), Some(2));
+        assert_eq!(next_enabled(&disabled, 2, true), None);
+        assert_eq!(next_enabled(&disabled, 2, false), Some(0));
+        assert_eq!(next_enabled(&disabled, 0, false), None);
+    }
+
+    #[test]
+    fn test_each_segment_keeps_its_own_label() {
+        let (mut host, mut instance) = testing::instance(300, 100);
+        let mut selected = 0;
+        let render_state = instance
+            .frame(&mut host, &mut |ctx| {
+                segmented_control(&mut selected, &["Day", "Week", "Month"]).build(ctx);
+            })
+            .unwrap();
+        let mut text_ids = render_state
+            .commands()
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::Text { text_id, .. } => Some(*text_id),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        text_ids.dedup();
+        assert_eq!(text_ids.len(), 3);
+    }
+}
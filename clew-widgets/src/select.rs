@@ -0,0 +1,853 @@
+use clew::io::UserInput;
+use clew::keyboard::KeyCode;
+use clew::stateful::StatefulWidget;
+use clew::{
+    AlignY, Border, BorderRadius, BorderSide, Clip, ColorRgba, Constraints, EdgeInsets, Lerp,
+    LinearGradient, Rect, Size, TextData, View, curves, widgets::*,
+};
+use clew::{TextAlign, prelude::*};
+use clew_derive::{WidgetBuilder, WidgetState};
+
+use crate::{HOVER_TRANSITION, is_key_pressed, vertical_scroll_bar};
+
+const ROW_HEIGHT: f32 = 24.;
+const SEPARATOR_HEIGHT: f32 = 9.;
+const SEARCH_HEIGHT: f32 = 32.;
+const POPUP_PADDING: f32 = 4.;
+/// Space between the trigger and the popup, and between the popup and the window edge.
+const POPUP_SPACING: f32 = 4.;
+const POPUP_MAX_HEIGHT: f32 = 320.;
+/// Seconds after the last typed letter when the type-ahead starts over.
+pub(crate) const TYPE_AHEAD_TIMEOUT: f32 = 1.;
+
+/// Option of a [`select`] or a [`crate::listbox`].
+pub trait SelectOption: Identifiable {
+    fn label(&self) -> &str;
+
+    /// Consecutive options of the same group are listed under its title, groups are
+    /// separated with a line.
+    fn group(&self) -> Option<&str> {
+        None
+    }
+}
+
+#[derive(WidgetBuilder)]
+pub struct SelectBuilder<'a, T: SelectOption> {
+    frame: FrameBuilder,
+    selected: &'a mut Option<T::Id>,
+    options: &'a [T],
+    placeholder: &'a str,
+    searchable: bool,
+}
+
+pub struct SelectResponse<Id> {
+    changed: Option<Id>,
+}
+
+impl<Id> SelectResponse<Id> {
+    /// Id of the option picked in this frame, `None` if the selection hasn't changed.
+    pub fn changed(&self) -> Option<&Id> {
+        self.changed.as_ref()
+    }
+}
+
+impl<'a, T: SelectOption> SelectBuilder<'a, T>
+where
+    T::Id: PartialEq,
+{
+    /// Shown in the trigger when nothing is selected.
+    pub fn placeholder(mut self, placeholder: &'a str) -> Self {
+        self.placeholder = placeholder;
+        self
+    }
+
+    /// Adds a search box at the top of the popup that filters the options by label.
+    pub fn searchable(mut self, searchable: bool) -> Self {
+        self.searchable = searchable;
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> SelectResponse<T::Id> {
+        let Self {
+            frame,
+            selected,
+            options,
+            placeholder,
+            searchable,
+        } = self;
+
+        let mut data = SelectData {
+            labels: options.iter().map(|it| it.label().to_string()).collect(),
+            groups: options
+                .iter()
+                .map(|it| it.group().map(str::to_string))
+                .collect(),
+            selected: options
+                .iter()
+                .position(|it| selected.as_ref() == Some(&it.id())),
+            placeholder: placeholder.to_string(),
+            searchable,
+            committed: None,
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<Select>().frame(frame).build(ctx);
+        });
+
+        let committed = data.committed.filter(|index| Some(*index) != data.selected);
+
+        if let Some(index) = committed {
+            *selected = Some(options[index].id());
+        }
+
+        SelectResponse {
+            changed: committed.map(|index| options[index].id()),
+        }
+    }
+}
+
+/// Dropdown that picks one of the `options`, the popup is navigated with the arrows,
+/// Enter commits the highlighted option, Escape closes the popup and typing jumps to
+/// the matching option, or filters the options if the select is searchable.
+///
+/// The popup is built in a [`portal`], above the rest of the view.
+#[track_caller]
+pub fn select<'a, T: SelectOption>(
+    selected: &'a mut Option<T::Id>,
+    options: &'a [T],
+) -> SelectBuilder<'a, T> {
+    SelectBuilder {
+        frame: FrameBuilder::new().constraints(Constraints {
+            min_width: 120.,
+            min_height: 0.,
+            max_width: f32::INFINITY,
+            max_height: f32::INFINITY,
+        }),
+        selected,
+        options,
+        placeholder: "",
+        searchable: false,
+    }
+}
+
+/// Options of the current frame passed down to the select state, and the option
+/// committed in the popup passed back.
+#[derive(Default)]
+struct SelectData {
+    labels: Vec<String>,
+    groups: Vec<Option<String>>,
+    selected: Option<usize>,
+    placeholder: String,
+    searchable: bool,
+    committed: Option<usize>,
+}
+
+enum Row {
+    Separator,
+    /// Title of the group of the option.
+    Header(usize),
+    Option(usize),
+}
+
+impl Row {
+    fn height(&self) -> f32 {
+        match self {
+            Row::Separator => SEPARATOR_HEIGHT,
+            Row::Header(_) | Row::Option(_) => ROW_HEIGHT,
+        }
+    }
+}
+
+#[derive(WidgetState, Default)]
+struct Select {
+    open: bool,
+    /// Index of the option the keyboard points at.
+    highlighted: Option<usize>,
+    query: TextData,
+    type_ahead: String,
+    type_ahead_timer: f32,
+    scroll_to_highlighted: bool,
+    trigger_rect: Option<Rect>,
+    popup_rect: Option<Rect>,
+    mouse_was_pressed: bool,
+    last_mouse: (f32, f32),
+}
+
+impl Select {
+    fn open(&mut self, data: &SelectData) {
+        self.open = true;
+        self.highlighted = data.selected;
+        self.query.clear();
+        self.type_ahead.clear();
+        self.scroll_to_highlighted = true;
+    }
+
+    fn close(&mut self) {
+        self.open = false;
+        self.popup_rect = None;
+    }
+
+    /// Handles the keyboard and the presses outside of the select while the popup is
+    /// open. The popup keeps the keyboard, the typed text goes to the search query and
+    /// the search box only shows it.
+    fn handle_popup_input(
+        &mut self,
+        input: &UserInput,
+        text_input: &str,
+        view: &View,
+        data: &mut SelectData,
+    ) {
+        let mouse = (
+            input.mouse_x / view.scale_factor,
+            input.mouse_y / view.scale_factor,
+        );
+        let pressed = input.mouse_left_pressed && !self.mouse_was_pressed;
+        let inside = |rect: Option<Rect>| {
+            rect.is_some_and(|rect| {
+                mouse.0 >= rect.left()
+                    && mouse.0 < rect.right()
+                    && mouse.1 >= rect.top()
+                    && mouse.1 < rect.bottom()
+            })
+        };
+
+        if pressed && !inside(self.trigger_rect) && !inside(self.popup_rect) {
+            self.close();
+            return;
+        }
+
+        if is_key_pressed(input, KeyCode::Escape) {
+            self.close();
+            return;
+        }
+
+        let visible = visible_options(data, &self.query.get_text());
+
+        if is_key_pressed(input, KeyCode::Enter) || is_key_pressed(input, KeyCode::NumpadEnter) {
+            if let Some(highlighted) = self.highlighted
+                && visible.contains(&highlighted)
+            {
+                data.committed = Some(highlighted);
+            }
+
+            self.close();
+            return;
+        }
+
+        let position = self
+            .highlighted
+            .and_then(|highlighted| visible.iter().position(|it| *it == highlighted));
+
+        let target = if is_key_pressed(input, KeyCode::ArrowDown) {
+            Some(position.map_or(0, |position| position + 1))
+        } else if is_key_pressed(input, KeyCode::ArrowUp) {
+            Some(
+                position.map_or(visible.len().saturating_sub(1), |position| {
+                    position.saturating_sub(1)
+                }),
+            )
+        } else if is_key_pressed(input, KeyCode::Home) {
+            Some(0)
+        } else if is_key_pressed(input, KeyCode::End) {
+            Some(visible.len().saturating_sub(1))
+        } else {
+            None
+        };
+
+        if let Some(target) = target
+            && let Some(option) = visible.get(target.min(visible.len().saturating_sub(1)))
+        {
+            self.highlighted = Some(*option);
+            self.scroll_to_highlighted = true;
+        }
+
+        if data.searchable {
+            let mut query = self.query.get_text();
+            let previous_len = query.len();
+
+            if is_key_pressed(input, KeyCode::Backspace) {
+                query.pop();
+            }
+
+            query.push_str(text_input);
+
+            if query.len() != previous_len {
+                self.query.set_text(&query);
+
+                let visible = visible_options(data, &query);
+
+                if !self.highlighted.is_some_and(|it| visible.contains(&it)) {
+                    self.highlighted = visible.first().copied();
+                }
+
+                self.scroll_to_highlighted = true;
+            }
+        } else {
+            if !text_input.is_empty() {
+                if self.type_ahead_timer <= 0. {
+                    self.type_ahead.clear();
+                }
+
+                self.type_ahead.push_str(text_input);
+                self.type_ahead_timer = TYPE_AHEAD_TIMEOUT;
+
+                if let Some(option) =
+                    type_ahead_match(&data.labels, &visible, self.highlighted, &self.type_ahead)
+                {
+                    self.highlighted = Some(option);
+                    self.scroll_to_highlighted = true;
+                }
+            }
+        }
+    }
+}
+
+/// Options whose label contains the query, ignoring case.
+fn visible_options(data: &SelectData, query: &str) -> Vec<usize> {
+    if !data.searchable || query.is_empty() {
+        return (0..data.labels.len()).collect();
+    }
+
+    let query = query.to_lowercase();
+
+    data.labels
+        .iter()
+        .enumerate()
+        .filter(|(_, label)| label.to_lowercase().contains(&query))
+        .map(|(index, _)| index)
+        .collect()
+}
+
+/// First visible option from the highlighted one whose label starts with `prefix`,
+/// ignoring case. Repeating the same letter cycles through the options starting with it.
+pub(crate) fn type_ahead_match(
+    labels: &[String],
+    visible: &[usize],
+    highlighted: Option<usize>,
+    prefix: &str,
+) -> Option<usize> {
+    let prefix = prefix.to_lowercase();
+    let mut chars = prefix.chars();
+    let first = chars.next()?;
+    let cycle = chars.all(|it| it == first);
+
+    let prefix = if cycle { first.to_string() } else { prefix };
+
+    let position =
+        highlighted.and_then(|highlighted| visible.iter().position(|it| *it == highlighted));
+    let start = match position {
+        Some(position) if cycle => position + 1,
+        Some(position) => position,
+        None => 0,
+    };
+
+    (0..visible.len())
+        .map(|offset| visible[(start + offset) % visible.len()])
+        .find(|index| labels[*index].to_lowercase().starts_with(&prefix))
+}
+
+/// Rows of the popup, a group title goes before the first option of every group.
+fn rows(data: &SelectData, visible: &[usize]) -> Vec<Row> {
+    let mut rows = Vec::with_capacity(visible.len());
+    let mut current_group = None;
+
+    for (position, index) in visible.iter().enumerate() {
+        let group = data.groups[*index].as_deref();
+
+        if position == 0 || group != current_group {
+            if position > 0 {
+                rows.push(Row::Separator);
+            }
+
+            if group.is_some() {
+                rows.push(Row::Header(*index));
+            }
+
+            current_group = group;
+        }
+
+        rows.push(Row::Option(*index));
+    }
+
+    rows
+}
+
+impl StatefulWidget for Select {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut BuildContext, mut frame: FrameBuilder) {
+        let Some(mut data) = ctx.of_mut::<SelectData>().map(std::mem::take) else {
+            return;
+        };
+
+        let input = ctx.input();
+        let mouse_moved = (input.mouse_x, input.mouse_y) != self.last_mouse;
+        self.last_mouse = (input.mouse_x, input.mouse_y);
+
+        if self.open {
+            self.type_ahead_timer -= ctx.delta_time();
+
+            // Taken, so a focused search box doesn't insert the text once more.
+            let text_input = ctx.take_text_input();
+
+            self.handle_popup_input(ctx.input(), &text_input, ctx.view(), &mut data);
+        }
+
+        self.mouse_was_pressed = ctx.input().mouse_left_pressed;
+
+        let layout = frame.take_layout();
+
+        frame.build(ctx, |ctx| {
+            zstack()
+                .size(layout.size)
+                .constraints(layout.constraints)
+                .build(ctx, |ctx| {
+                    self.trigger_rect = measure()
+                        .size(layout.size)
+                        .constraints(layout.constraints)
+                        .build(ctx, |ctx| self.build_trigger(ctx, &data, layout.size));
+
+                    if self.open
+                        && let Some(trigger_rect) = self.trigger_rect
+                    {
+                        self.build_popup(ctx, &mut data, trigger_rect, mouse_moved);
+                    }
+                });
+        });
+
+        if let Some(shared) = ctx.of_mut::<SelectData>() {
+            *shared = data;
+        }
+    }
+}
+
+impl Select {
+    fn build_trigger(&mut self, ctx: &mut BuildContext, data: &SelectData, size: Size) {
+        let response = gesture_detector()
+            .clickable(true)
+            .focusable(true)
+            .build(ctx, |ctx| {
+                let response = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+                let theme = ctx.widget_theme();
+
+                let gradient = if response.is_active() && response.is_hot() {
+                    LinearGradient::vertical((theme.sunken(0.6), theme.sunken(0.4)))
+                } else if response.is_hot() || self.open {
+                    LinearGradient::vertical((theme.raised(0.1), theme.raised(0.05)))
+                } else {
+                    LinearGradient::vertical((theme.raised(0.03), theme.surface))
+                };
+
+                let border_color = if response.is_focus_visible() {
+                    theme.accent
+                } else if response.is_hot() {
+                    theme.border.lerp(theme.text, 0.2)
+                } else {
+                    theme.border
+                };
+
+                if !self.open
+                    && response.is_focused()
+                    && [
+                        KeyCode::ArrowDown,
+                        KeyCode::ArrowUp,
+                        KeyCode::Enter,
+                        KeyCode::Space,
+                    ]
+                    .into_iter()
+                    .any(|key| is_key_pressed(ctx.input(), key))
+                {
+                    self.open(data);
+                }
+
+                let (label, color) = match data.selected {
+                    Some(selected) => (data.labels[selected].as_str(), theme.text),
+                    None => (data.placeholder.as_str(), theme.secondary_text),
+                };
+
+                hstack()
+                    .size(size)
+                    .spacing(8.)
+                    .padding(EdgeInsets::symmetric(12., 8.))
+                    .background(
+                        decoration()
+                            .border_radius(BorderRadius::all(3.))
+                            .add_linear_gradient(gradient)
+                            .border(Border::all(BorderSide::new(1., border_color)))
+                            .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                            .build(ctx),
+                    )
+                    .build(ctx, |ctx| {
+                        text(label)
+                            .color(color)
+                            .text_vertical_align(AlignY::Center)
+                            .build(ctx);
+
+                        spacer().build(ctx);
+
+                        text("▾")
+                            .color(theme.secondary_text)
+                            .text_vertical_align(AlignY::Center)
+                            .build(ctx);
+                    });
+            });
+
+        if response.clicked() {
+            if self.open {
+                self.close();
+            } else {
+                self.open(data);
+            }
+        }
+    }
+
+    fn build_popup(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &mut SelectData,
+        trigger_rect: Rect,
+        mouse_moved: bool,
+    ) {
+        let query = self.query.get_text();
+        let visible = visible_options(data, &query);
+        let rows = rows(data, &visible);
+
+        let search_height = if data.searchable { SEARCH_HEIGHT } else { 0. };
+        let content_height = rows.iter().map(Row::height).sum::<f32>() + POPUP_PADDING * 2.;
+        let wanted_height = (search_height + content_height).min(POPUP_MAX_HEIGHT);
+
+        // Flip above the trigger when the popup doesn't fit below and there is more
+        // space above, either way it's shrunk to the space it has.
+        let view_height = ctx.view().size.height as f32 / ctx.view().scale_factor;
+        let space_below = view_height - trigger_rect.bottom() - POPUP_SPACING * 2.;
+        let space_above = trigger_rect.top() - POPUP_SPACING * 2.;
+        let flip = wanted_height > space_below && space_above > space_below;
+        let height = wanted_height
+            .min(if flip { space_above } else { space_below })
+            .max(search_height + ROW_HEIGHT);
+        let offset_y = if flip {
+            -(height + POPUP_SPACING)
+        } else {
+            trigger_rect.height + POPUP_SPACING
+        };
+
+        let popup_rect = Rect::new(
+            trigger_rect.x,
+            trigger_rect.y + offset_y,
+            trigger_rect.width,
+            height,
+        );
+        self.popup_rect = Some(popup_rect);
+
+        // The popup escapes the clips of the ancestors of the select and takes the
+        // pointer before the widgets it covers.
+        let theme = ctx.widget_theme();
+
+        portal().rect(popup_rect).build(ctx, |ctx| {
+            vstack()
+                .fill_max_size()
+                .clip(Clip::RoundedRect {
+                    border_radius: BorderRadius::all(3.),
+                })
+                .background(
+                    decoration()
+                        .color(theme.surface)
+                        .border_radius(BorderRadius::all(3.))
+                        .border(Border::all(BorderSide::new(1., theme.border)))
+                        .build(ctx),
+                )
+                .build(ctx, |ctx| {
+                    if data.searchable {
+                        self.build_search(ctx, query.is_empty());
+                    }
+
+                    zstack().fill_max_size().build(ctx, |ctx| {
+                        let list = scroll_area().fill_max_size().scroll_bars(true);
+                        let response = list.build(ctx, |ctx| {
+                            self.scroll_highlighted_into_view(ctx, &rows);
+
+                            vstack()
+                                .fill_max_width()
+                                .padding(EdgeInsets::all(POPUP_PADDING))
+                                .build(ctx, |ctx| {
+                                    for row in &rows {
+                                        self.build_row(ctx, data, row, mouse_moved);
+                                    }
+                                });
+                        });
+
+                        if response.overflow_y {
+                            ctx.provide(response.clone(), |ctx| {
+                                vertical_scroll_bar().build(ctx);
+                            });
+                        }
+                    });
+                });
+        });
+    }
+
+    fn build_search(&mut self, ctx: &mut BuildContext, is_empty: bool) {
+        let theme = ctx.widget_theme();
+
+        zstack()
+            .fill_max_width()
+            .height(SEARCH_HEIGHT)
+            .align_y(AlignY::Center)
+            .padding(EdgeInsets::symmetric(12., 0.))
+            .background(
+                decoration()
+                    .border(Border::new(
+                        None,
+                        None,
+                        Some(BorderSide::new(1., theme.border)),
+                        None,
+                    ))
+                    .build(ctx),
+            )
+            .build(ctx, |ctx| {
+                if is_empty {
+                    text("Search").color(theme.secondary_text).build(ctx);
+                }
+
+                editable_text(&mut self.query)
+                    .color(theme.text)
+                    .selection_color(theme.accent.with_opacity(0.4))
+                    .selected_text_color(theme.text)
+                    .fill_max_width()
+                    .build(ctx);
+            });
+    }
+
+    fn scroll_highlighted_into_view(&mut self, ctx: &mut BuildContext, rows: &[Row]) {
+        let response = ctx.of::<ScrollAreaResponse>().unwrap().clone();
+
+        // The scroll area is laid out a frame after the popup is opened.
+        if !self.scroll_to_highlighted || response.height <= 0. {
+            return;
+        }
+
+        self.scroll_to_highlighted = false;
+
+        let mut top = POPUP_PADDING as f64;
+
+        for row in rows {
+            let bottom = top + row.height() as f64;
+
+            if let Row::Option(index) = row
+                && Some(*index) == self.highlighted
+            {
+                let scroll = -response.offset_y;
+
+                if top - (POPUP_PADDING as f64) < scroll {
+                    set_scroll_offset_y(ctx, response.id, top - POPUP_PADDING as f64);
+                } else if bottom + POPUP_PADDING as f64 > scroll + response.height {
+                    set_scroll_offset_y(
+                        ctx,
+                        response.id,
+                        bottom + POPUP_PADDING as f64 - response.height,
+                    );
+                }
+
+                return;
+            }
+
+            top = bottom;
+        }
+    }
+
+    fn build_row(
+        &mut self,
+        ctx: &mut BuildContext,
+        data: &mut SelectData,
+        row: &Row,
+        mouse_moved: bool,
+    ) {
+        let theme = ctx.widget_theme();
+
+        match row {
+            Row::Separator => {
+                zstack()
+                    .fill_max_width()
+                    .height(SEPARATOR_HEIGHT)
+                    .align_y(AlignY::Center)
+                    .build(ctx, |ctx| {
+                        decorated_box()
+                            .color(theme.border)
+                            .fill_max_width()
+                            .height(1.)
+                            .build(ctx);
+                    });
+            }
+            Row::Header(index) => {
+                text(data.groups[*index].as_deref().unwrap_or_default())
+                    .color(theme.secondary_text)
+                    .text_vertical_align(AlignY::Center)
+                    .padding(EdgeInsets::symmetric(8., 0.))
+                    .fill_max_width()
+                    .height(ROW_HEIGHT)
+                    .build(ctx);
+            }
+            Row::Option(index) => {
+                let index = *index;
+
+                let response = scope(index).build(ctx, |ctx| {
+                    gesture_detector().clickable(true).build(ctx, |ctx| {
+                        let response = ctx.of::<GestureDetectorResponse>().unwrap();
+
+                        // Hovering moves the highlight, unless the pointer rests on a row
+                        // while the highlight is moved with the keyboard.
+                        if response.is_hot() && mouse_moved {
+                            self.highlighted = Some(index);
+                        }
+
+                        let (color, text_color) = if self.highlighted == Some(index) {
+                            (theme.accent, ColorRgba::from_hex(0xFFFFFFFF))
+                        } else {
+                            (ColorRgba::TRANSPARENT, theme.text)
+                        };
+
+                        hstack()
+                            .fill_max_width()
+                            .height(ROW_HEIGHT)
+                            .padding(EdgeInsets::symmetric(8., 0.))
+                            .background(
+                                decoration()
+                                    .color(color)
+                                    .border_radius(BorderRadius::all(2.))
+                                    .build(ctx),
+                            )
+                            .build(ctx, |ctx| {
+                                text(if data.selected == Some(index) {
+                                    "✓"
+                                } else {
+                                    ""
+                                })
+                                .color(text_color)
+                                .text_align(TextAlign::Center)
+                                .text_vertical_align(AlignY::Center)
+                                .width(16.)
+                                .fill_max_height()
+                                .build(ctx);
+
+                                text(&data.labels[index])
+                                    .color(text_color)
+                                    .text_vertical_align(AlignY::Center)
+                                    .fill_max_height()
+                                    .build(ctx);
+                            });
+                    })
+                });
+
+                if response.clicked() {
+                    data.committed = Some(index);
+                    self.close();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::io::{InputEvent, MouseButton};
+    use clew::testing;
+
+    use super::*;
+
+    struct Fruit(usize, &'static str);
+
+    impl Identifiable for Fruit {
+        type Id = usize;
+
+        fn id(&self) -> usize {
+            self.0
+        }
+    }
+
+    impl SelectOption for Fruit {
+        fn label(&self) -> &str {
+            self.1
+        }
+    }
+
+    #[test]
+    fn test_text_typed_in_focused_search_is_inserted_once() {
+        let (mut host, mut instance) = testing::instance(300, 300);
+        let options = [Fruit(0, "Apple"), Fruit(1, "Banana"), Fruit(2, "Cherry")];
+        let mut selected = None;
+        let mut frame = |event: Option<InputEvent>| {
+            if let Some(event) = event {
+                instance.push_event(event);
+            }
+
+            let mut changed = None;
+
+            instance.frame(&mut host, &mut |ctx| {
+                changed = select(&mut selected, &options)
+                    .searchable(true)
+                    .height(24.)
+                    .build(ctx)
+                    .changed()
+                    .copied();
+            });
+
+            changed
+        };
+        let click = |x: f32, y: f32| {
+            [
+                Some(InputEvent::PointerMoved { x, y }),
+                Some(InputEvent::MouseButton {
+                    button: MouseButton::Left,
+                    pressed: true,
+                }),
+                Some(InputEvent::MouseButton {
+                    button: MouseButton::Left,
+                    pressed: false,
+                }),
+                None,
+            ]
+        };
+        let key = |key_code: KeyCode, text: Option<&str>| {
+            Some(InputEvent::Key {
+                key_code: Some(key_code),
+                pressed: true,
+                repeat: false,
+                text: text.map(str::to_string),
+            })
+        };
+
+        frame(None);
+
+        for event in click(10., 12.) {
+            frame(event);
+        }
+
+        // Focuses the search box.
+        for event in click(40., 24. + POPUP_SPACING + SEARCH_HEIGHT / 2.) {
+            frame(event);
+        }
+
+        // "bb" would match none of the options.
+        frame(key(KeyCode::KeyB, Some("b")));
+        assert_eq!(frame(key(KeyCode::Enter, None)), Some(1));
+    }
+
+    #[test]
+    fn test_type_ahead_cycles_through_options_with_same_letter() {
+        let labels = ["Apple", "Banana", "Apricot", "avocado", "Cherry"]
+            .map(String::from)
+            .to_vec();
+        let visible = (0..labels.len()).collect::<Vec<_>>();
+
+        assert_eq!(type_ahead_match(&labels, &visible, None, "a"), Some(0));
+        assert_eq!(type_ahead_match(&labels, &visible, Some(0), "a"), Some(2));
+        assert_eq!(type_ahead_match(&labels, &visible, Some(2), "aa"), Some(3));
+        assert_eq!(type_ahead_match(&labels, &visible, Some(3), "a"), Some(0));
+
+        // A longer prefix keeps the highlighted option while it still matches.
+        assert_eq!(type_ahead_match(&labels, &visible, Some(2), "apr"), Some(2));
+        assert_eq!(type_ahead_match(&labels, &visible, Some(0), "ch"), Some(4));
+        assert_eq!(type_ahead_match(&labels, &visible, Some(0), "x"), None);
+    }
+}
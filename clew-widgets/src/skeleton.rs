@@ -0,0 +1,525 @@
+use std::time::Duration;
+
+use clew::prelude::*;
+use clew::{
+    BorderRadius, BoxShape, ColorRgba, LinearGradient, Size, SizeConstraint, WidgetId, WidgetTheme,
+    widgets::*,
+};
+use clew_derive::{WidgetBuilder, WidgetState};
+
+/// Longest step of the shimmer in a frame. The first frame after the frames have
+/// stopped, e.g. while the window wasn't focused, goes on from where the band was
+/// rather than jumping by the time in between.
+const MAX_SHIMMER_STEP: f32 = 1. / 30.;
+
+/// Colors of the skeletons, provided with [`BuildContext::provide`] or registered for
+/// the whole application as a resource, see [`SkeletonStyle::of`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SkeletonStyle {
+    pub color: ColorRgba,
+    /// Color of the band sweeping over the shapes.
+    pub highlight_color: ColorRgba,
+    pub border_radius: f32,
+    /// Height of a [`skeleton_line`] unless it's set.
+    pub line_height: f32,
+    /// Time the band takes to sweep over a shape.
+    pub period: Duration,
+}
+
+impl Default for SkeletonStyle {
+    fn default() -> Self {
+        Self::from_theme(&WidgetTheme::default())
+    }
+}
+
+impl SkeletonStyle {
+    /// Shapes a shade off the surface of the theme.
+    pub fn from_theme(theme: &WidgetTheme) -> Self {
+        Self {
+            color: theme.raised(0.02),
+            highlight_color: theme.raised(0.1),
+            border_radius: 4.,
+            line_height: 12.,
+            period: Duration::from_millis(1500),
+        }
+    }
+
+    /// Style provided with [`BuildContext::provide`], then the one registered as a
+    /// resource, then the one of [`BuildContext::widget_theme`].
+    pub fn of(context: &BuildContext) -> Self {
+        context
+            .of::<SkeletonStyle>()
+            .or_else(|| context.get::<SkeletonStyle>())
+            .copied()
+            .unwrap_or_else(|| Self::from_theme(&context.widget_theme()))
+    }
+
+    /// Gradient of the shapes at the phase of the shimmer. The phase comes from the
+    /// [`ShimmerClock`] of the view, so all the skeletons on the screen shimmer in sync,
+    /// and stands still while the window isn't focused. Under reduced motion the shapes
+    /// are drawn without the shimmer.
+    fn shimmer(&self, context: &mut BuildContext) -> LinearGradient {
+        let period = self.period.as_secs_f32();
+
+        if period <= 0. || context.reduced_motion() {
+            return LinearGradient::horizontal((self.color, self.color));
+        }
+
+        let focused = context.window_focused();
+
+        if focused {
+            context.request_frame();
+        }
+
+        let time = ShimmerClock::tick(context, focused);
+
+        // The band starts and ends outside of the shape.
+        let phase = time % period / period;
+        let start = phase * 2. - 1.;
+
+        LinearGradient {
+            start: (start, 0.),
+            end: (start + 1., 1.),
+            ..LinearGradient::horizontal([self.color, self.highlight_color, self.color])
+        }
+    }
+}
+
+/// Time the shimmer has run for, shared by the skeletons of the view. It's advanced by
+/// the frames built while the window is focused, a step at most [`MAX_SHIMMER_STEP`].
+#[derive(WidgetState, Default)]
+struct ShimmerClock {
+    frame: Option<u64>,
+    time: f32,
+}
+
+impl ShimmerClock {
+    /// Advances the clock once a frame, returns the time in seconds.
+    fn tick(context: &mut BuildContext, focused: bool) -> f32 {
+        let frame = context.frame_index();
+        let delta_time = context.delta_time().min(MAX_SHIMMER_STEP);
+        // Not in a scope, the same for every skeleton of the view.
+        let clock = context.state::<ShimmerClock>(WidgetId::auto());
+
+        if clock.frame != Some(frame) {
+            if focused && clock.frame.is_some() {
+                clock.time += delta_time;
+            }
+
+            clock.frame = Some(frame);
+        }
+
+        clock.time
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum SkeletonShape {
+    /// Line as wide as the fraction of the width it can take.
+    Line(f32),
+    Circle,
+    Block,
+}
+
+#[derive(WidgetBuilder)]
+pub struct SkeletonBuilder {
+    frame: FrameBuilder,
+    shape: SkeletonShape,
+    style: Option<SkeletonStyle>,
+}
+
+impl SkeletonBuilder {
+    pub fn style(mut self, style: SkeletonStyle) -> Self {
+        self.style = Some(style);
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(mut self, ctx: &mut BuildContext) {
+        let style = self.style.unwrap_or_else(|| SkeletonStyle::of(ctx));
+        let gradient = style.shimmer(ctx);
+        let layout = self.frame.take_layout();
+        let shape = self.shape;
+
+        self.frame.build(ctx, |ctx| match shape {
+            SkeletonShape::Line(fraction) => {
+                let height = match layout.size.height {
+                    SizeConstraint::Wrap => SizeConstraint::Fixed(style.line_height),
+                    height => height,
+                };
+                let fraction = fraction.clamp(0., 1.);
+
+                hstack()
+                    .width(layout.size.width)
+                    .constraints(layout.constraints)
+                    .build(ctx, |ctx| {
+                        decorated_box()
+                            .width(SizeConstraint::Fill(fraction))
+                            .height(height)
+                            .add_linear_gradient(gradient)
+                            .border_radius(BorderRadius::all(style.border_radius))
+                            .build(ctx);
+
+                        if fraction < 1. {
+                            spacer().flex(1. - fraction).build(ctx);
+                        }
+                    });
+            }
+            SkeletonShape::Circle => {
+                decorated_box()
+                    .size(layout.size)
+                    .constraints(layout.constraints)
+                    .add_linear_gradient(gradient)
+                    .shape(BoxShape::Oval)
+                    .build(ctx);
+            }
+            SkeletonShape::Block => {
+                decorated_box()
+                    .size(layout.size)
+                    .constraints(layout.constraints)
+                    .add_linear_gradient(gradient)
+                    .border_radius(BorderRadius::all(style.border_radius))
+                    .build(ctx);
+            }
+        });
+    }
+}
+
+/// Placeholder of a line of text, as wide as the fraction of the width it can take,
+/// e.g. `0.6` for the last line of a paragraph.
+#[track_caller]
+pub fn skeleton_line(width_fraction: f32) -> SkeletonBuilder {
+    SkeletonBuilder {
+        frame: FrameBuilder::new().fill_max_width(),
+        shape: SkeletonShape::Line(width_fraction),
+        style: None,
+    }
+}
+
+/// Placeholder of a round widget, e.g. an avatar.
+#[track_caller]
+pub fn skeleton_circle(diameter: f32) -> SkeletonBuilder {
+    SkeletonBuilder {
+        frame: FrameBuilder::new().size(Size::square(diameter)),
+        shape: SkeletonShape::Circle,
+        style: None,
+    }
+}
+
+/// Placeholder of a box, e.g. an image.
+#[track_caller]
+pub fn skeleton_block(size: impl Into<Size>) -> SkeletonBuilder {
+    SkeletonBuilder {
+        frame: FrameBuilder::new().size(size),
+        shape: SkeletonShape::Block,
+        style: None,
+    }
+}
+
+#[derive(WidgetBuilder)]
+pub struct SkeletonParagraphBuilder {
+    frame: FrameBuilder,
+    lines: usize,
+    spacing: f32,
+    style: Option<SkeletonStyle>,
+}
+
+impl SkeletonParagraphBuilder {
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+
+        self
+    }
+
+    pub fn style(mut self, style: SkeletonStyle) -> Self {
+        self.style = Some(style);
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) {
+        let Self {
+            mut frame,
+            lines,
+            spacing,
+            style,
+        } = self;
+        let style = style.unwrap_or_else(|| SkeletonStyle::of(ctx));
+        let mut rng = ctx.stable_rng(("skeleton_paragraph", lines));
+
+        frame.build(ctx, |ctx| {
+            vstack()
+                .fill_max_width()
+                .spacing(spacing)
+                .build(ctx, |ctx| {
+                    for line in 0..lines {
+                        let width = if line + 1 < lines {
+                            rng.range_f32(0.8..1.)
+                        } else {
+                            rng.range_f32(0.4..0.7)
+                        };
+
+                        skeleton_line(width).style(style).build(ctx);
+                    }
+                });
+        });
+    }
+}
+
+/// Placeholder of a paragraph of the number of lines, the last one is the shortest.
+/// The widths of the lines are picked with [`BuildContext::stable_rng`], they stay the
+/// same from frame to frame, and the paragraphs built in different scopes differ.
+#[track_caller]
+pub fn skeleton_paragraph(lines: usize) -> SkeletonParagraphBuilder {
+    SkeletonParagraphBuilder {
+        frame: FrameBuilder::new().fill_max_width(),
+        lines,
+        spacing: 8.,
+        style: None,
+    }
+}
+
+#[derive(WidgetBuilder)]
+pub struct SkeletonOfBuilder {
+    frame: FrameBuilder,
+    style: Option<SkeletonStyle>,
+}
+
+impl SkeletonOfBuilder {
+    pub fn style(mut self, style: SkeletonStyle) -> Self {
+        self.style = Some(style);
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build<F>(mut self, ctx: &mut BuildContext, content: F)
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        let style = self.style.unwrap_or_else(|| SkeletonStyle::of(ctx));
+        let gradient = style.shimmer(ctx);
+
+        self.frame.build(ctx, |ctx| {
+            silhouette().build(ctx, content, |ctx, shapes| {
+                for (index, shape) in shapes.iter().enumerate() {
+                    let rect = shape.rect();
+                    let border_radius = match shape {
+                        SilhouetteShape::Line(rect) => style.border_radius.min(rect.height / 2.),
+                        _ => style.border_radius,
+                    };
+
+                    decorated_box()
+                        .id(index)
+                        .offset(rect.x, rect.y)
+                        .width(rect.width)
+                        .height(rect.height)
+                        .add_linear_gradient(gradient.clone())
+                        .border_radius(BorderRadius::all(border_radius))
+                        .shape(match shape {
+                            SilhouetteShape::Oval(_) => BoxShape::Oval,
+                            _ => BoxShape::Rect,
+                        })
+                        .build(ctx);
+                }
+            });
+        });
+    }
+}
+
+/// Skeleton of the layout of the content, e.g. of a card built with placeholder data
+/// while the real one loads. The content is laid out without being drawn, and its
+/// text lines and boxes are drawn as skeletons at their places, so the layout doesn't
+/// jump when the content arrives.
+///
+/// ```ignore
+/// match &profile {
+///     Some(profile) => profile_card(ctx, profile),
+///     None => skeleton_of().build(ctx, |ctx| profile_card(ctx, &Profile::placeholder())),
+/// }
+/// ```
+#[track_caller]
+pub fn skeleton_of() -> SkeletonOfBuilder {
+    SkeletonOfBuilder {
+        frame: FrameBuilder::new(),
+        style: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::io::InputEvent;
+    use clew::render::{Fill, RenderCommand};
+    use clew::testing;
+    use clew::{ClewHost, ClewInstance, Gradient, Rect};
+
+    use super::*;
+
+    /// Rects drawn in the last frame with their gradients.
+    fn skeleton_rects(instance: &mut ClewInstance) -> Vec<(Rect, LinearGradient)> {
+        instance
+            .ui_state()
+            .render_state
+            .commands()
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::Rect {
+                    boundary,
+                    fill: Some(Fill::Gradient(Gradient::Linear(gradient))),
+                    ..
+                } => Some((*boundary, gradient.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Builds a frame of a skeleton line, returns the start of the shimmer band.
+    fn band_start<'a>(host: &mut ClewHost<'a>, instance: &mut ClewInstance<'a>) -> f32 {
+        instance.frame(host, &mut |ctx| {
+            skeleton_line(1.).build(ctx);
+        });
+
+        skeleton_rects(instance)[0].1.start.0
+    }
+
+    #[test]
+    fn test_paragraph_lines_keep_their_widths_across_frames() {
+        let (mut host, mut instance) = testing::instance(300, 300);
+        let mut line_widths = |heading: bool, key: u32| {
+            instance.frame(&mut host, &mut |ctx| {
+                vstack().fill_max_width().build(ctx, |ctx| {
+                    if heading {
+                        text("Heading").build(ctx);
+                    }
+
+                    scope(key).build(ctx, |ctx| {
+                        skeleton_paragraph(4).build(ctx);
+                    });
+                });
+            });
+
+            instance
+                .ui_state()
+                .render_state
+                .commands()
+                .iter()
+                .filter_map(|command| match command {
+                    RenderCommand::Rect { boundary, .. } => Some(boundary.width),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let widths = line_widths(false, 0);
+
+        assert_eq!(widths.len(), 4);
+        assert!(widths[3] < widths[0] && widths[3] < widths[2]);
+
+        // Widgets built around the paragraph don't change it.
+        assert_eq!(line_widths(true, 0), widths);
+        assert_eq!(line_widths(false, 0), widths);
+        assert_ne!(line_widths(false, 1), widths);
+    }
+
+    #[test]
+    fn test_shimmer_goes_on_from_where_it_stopped() {
+        let (mut host, mut instance) = testing::instance(300, 100);
+
+        instance.push_event(InputEvent::Focused(true));
+        band_start(&mut host, &mut instance);
+        let start = band_start(&mut host, &mut instance);
+
+        // No frames for a while, e.g. the view was hidden.
+        std::thread::sleep(Duration::from_millis(200));
+
+        let next = band_start(&mut host, &mut instance);
+        let max_step = MAX_SHIMMER_STEP / SkeletonStyle::default().period.as_secs_f32() * 2.;
+
+        assert!(next > start);
+        assert!(next - start <= max_step + f32::EPSILON);
+    }
+
+    #[test]
+    fn test_shimmer_stands_still_while_unfocused() {
+        let (mut host, mut instance) = testing::instance(300, 100);
+
+        instance.push_event(InputEvent::Focused(true));
+        band_start(&mut host, &mut instance);
+        std::thread::sleep(Duration::from_millis(20));
+
+        let start = band_start(&mut host, &mut instance);
+
+        assert!(start > -1.);
+
+        instance.push_event(InputEvent::Focused(false));
+        band_start(&mut host, &mut instance);
+
+        std::thread::sleep(Duration::from_millis(20));
+        assert_eq!(band_start(&mut host, &mut instance), start);
+
+        instance.push_event(InputEvent::Focused(true));
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(band_start(&mut host, &mut instance) > start);
+    }
+
+    #[test]
+    fn test_reduced_motion_draws_without_shimmer() {
+        let (mut host, mut instance) = testing::instance(300, 100);
+        let style = SkeletonStyle::default();
+
+        instance.set_reduced_motion(Some(true));
+        instance.frame(&mut host, &mut |ctx| {
+            skeleton_block(20.).build(ctx);
+        });
+
+        let rects = skeleton_rects(&mut instance);
+
+        assert_eq!(rects.len(), 1);
+        assert!(
+            rects[0]
+                .1
+                .stops
+                .iter()
+                .all(|stop| stop.color == style.color)
+        );
+    }
+
+    #[test]
+    fn test_skeleton_of_draws_the_boxes_of_the_content() {
+        let (mut host, mut instance) = testing::instance(300, 200);
+
+        // The shapes are taken from the layout of the first frame.
+        for _ in 0..2 {
+            instance.frame(&mut host, &mut |ctx| {
+                skeleton_of().build(ctx, |ctx| {
+                    vstack().spacing(10.).build(ctx, |ctx| {
+                        decorated_box()
+                            .width(40.)
+                            .height(30.)
+                            .color(ColorRgba::from_hex(0xFF202020))
+                            .build(ctx);
+                        decorated_box()
+                            .width(60.)
+                            .height(20.)
+                            .color(ColorRgba::from_hex(0xFF202020))
+                            .build(ctx);
+                    });
+                });
+            });
+        }
+
+        let rects = skeleton_rects(&mut instance);
+
+        // Only the skeletons are drawn, not the content.
+        assert_eq!(
+            rects.iter().map(|(rect, _)| *rect).collect::<Vec<_>>(),
+            [Rect::new(0., 0., 40., 30.), Rect::new(0., 40., 60., 20.)]
+        );
+        assert_eq!(
+            instance.ui_state().render_state.commands().len(),
+            rects.len()
+        );
+    }
+}
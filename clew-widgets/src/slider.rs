@@ -0,0 +1,562 @@
+use std::ops::RangeInclusive;
+
+use clew::prelude::*;
+use clew::stateful::StatefulWidget;
+use clew::{
+    AlignX, AlignY, Border, BorderRadius, BorderSide, BoxShape, ColorRgba, LayoutDirection, Lerp,
+    Rect, Vec2, curves, widgets::*,
+};
+use clew_derive::{WidgetBuilder, WidgetState};
+
+use crate::{HOVER_TRANSITION, RangeKey, range_key};
+
+const THUMB_SIZE: f32 = 14.;
+const TRACK_HEIGHT: f32 = 4.;
+/// Distance between the track and the preview above it.
+const PREVIEW_SPACING: f32 = 8.;
+
+type PreviewFn<'a> = Box<dyn FnOnce(&mut BuildContext, f32) + 'a>;
+
+#[derive(WidgetBuilder)]
+pub struct SliderBuilder<'a> {
+    frame: FrameBuilder,
+    value: &'a mut f32,
+    range: RangeInclusive<f32>,
+    step: Option<f32>,
+    preview: Option<PreviewFn<'a>>,
+}
+
+pub struct SliderResponse {
+    changed: bool,
+    drag_state: DragState,
+    hover_value: Option<f32>,
+}
+
+impl SliderResponse {
+    /// The value was changed in this frame by a click or a drag on the track, or by the
+    /// keys.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    pub fn drag_state(&self) -> DragState {
+        self.drag_state
+    }
+
+    /// Value under the pointer while it's over the track, snapped to the step, the one
+    /// a click would seek to. While the thumb is dragged it's the dragged value, a finger
+    /// doesn't hover so with touch there is only the dragged one.
+    pub fn hover_value(&self) -> Option<f32> {
+        self.hover_value
+    }
+}
+
+impl<'a> SliderBuilder<'a> {
+    pub fn range(mut self, range: RangeInclusive<f32>) -> Self {
+        self.range = range;
+        self
+    }
+
+    /// Snaps the value to multiples of `step` from the start of the range.
+    pub fn step(mut self, step: f32) -> Self {
+        self.step = Some(step);
+        self
+    }
+
+    /// Content shown above the track at the hovered value while the pointer is over the
+    /// track or the thumb is dragged, e.g. the timestamp a seek bar would jump to. With
+    /// touch it's shown only while the finger drags the thumb. It's kept inside the view
+    /// and lets the pointer through to the track.
+    pub fn preview(mut self, preview: impl FnOnce(&mut BuildContext, f32) + 'a) -> Self {
+        self.preview = Some(Box::new(preview));
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(self, ctx: &mut BuildContext) -> SliderResponse {
+        let Self {
+            frame,
+            value,
+            range,
+            step,
+            preview,
+        } = self;
+
+        let mut data = SliderData {
+            value: *value,
+            min: *range.start(),
+            max: *range.end(),
+            step: step.filter(|it| *it > 0.),
+            ..Default::default()
+        };
+
+        ctx.scoped(&mut data, |ctx| {
+            stateful::<Slider>().frame(frame).build(ctx);
+        });
+
+        let changed = data.value != *value;
+        *value = data.value;
+
+        if let (Some(preview), Some(hover_value), Some(track_rect)) =
+            (preview, data.hover_value, data.track_rect)
+        {
+            build_preview(ctx, track_rect, data.hover_x, hover_value, preview);
+        }
+
+        SliderResponse {
+            changed,
+            drag_state: data.drag_state,
+            hover_value: data.hover_value,
+        }
+    }
+}
+
+/// Horizontal control for a value in a range, a click on the track seeks to the value
+/// under the pointer and dragging the thumb follows it. While it's focused the arrows
+/// step the value and Home and End go to the ends of the range. The start of the range
+/// is on the right in the right to left layouts.
+#[track_caller]
+pub fn slider(value: &mut f32) -> SliderBuilder<'_> {
+    SliderBuilder {
+        frame: FrameBuilder::new(),
+        value,
+        range: 0.0..=1.0,
+        step: None,
+        preview: None,
+    }
+}
+
+/// Value and range the slider is built with, the track fills in the new value, the
+/// hovered value and where the preview goes.
+#[derive(Default)]
+struct SliderData {
+    value: f32,
+    min: f32,
+    max: f32,
+    step: Option<f32>,
+    drag_state: DragState,
+    hover_value: Option<f32>,
+    /// Center of the thumb at the hovered value, in the view.
+    hover_x: f32,
+    track_rect: Option<Rect>,
+}
+
+impl SliderData {
+    fn span(&self) -> f32 {
+        self.max - self.min
+    }
+
+    fn snap(&self, value: f32) -> f32 {
+        let value = value.clamp(self.min, self.max);
+
+        match self.step {
+            Some(step) => {
+                (self.min + ((value - self.min) / step).round() * step).clamp(self.min, self.max)
+            }
+            None => value,
+        }
+    }
+
+    fn progress(&self, value: f32) -> f32 {
+        if self.span() > 0. {
+            (value - self.min) / self.span()
+        } else {
+            0.
+        }
+    }
+}
+
+#[derive(WidgetState, Default)]
+struct Slider {
+    track_rect: Option<Rect>,
+}
+
+/// Progress along the track of the pointer at `x`, the center of the thumb goes from
+/// the start of the track at 0 to its end at 1.
+fn track_progress(x: f32, track: Rect, rtl: bool) -> f32 {
+    let travel = track.width - THUMB_SIZE;
+
+    if travel <= 0. {
+        return 0.;
+    }
+
+    let progress = ((x - track.x - THUMB_SIZE / 2.) / travel).clamp(0., 1.);
+
+    if rtl { 1. - progress } else { progress }
+}
+
+/// Center of the thumb at the progress, the inverse of [`track_progress`].
+fn track_x(progress: f32, track: Rect, rtl: bool) -> f32 {
+    let progress = if rtl { 1. - progress } else { progress };
+
+    track.x + THUMB_SIZE / 2. + progress * (track.width - THUMB_SIZE).max(0.)
+}
+
+/// Top left corner of a preview of the size centered above the thumb at `x`, it goes
+/// below the track when there is no space above it and it's moved into the view.
+fn preview_origin(x: f32, track: Rect, size: Vec2, view_size: Vec2) -> Vec2 {
+    let above = track.top() - PREVIEW_SPACING - size.y;
+    let y = if above >= 0. {
+        above
+    } else {
+        track.bottom() + PREVIEW_SPACING
+    };
+
+    Vec2::new(
+        (x - size.x / 2.).clamp(0., (view_size.x - size.x).max(0.)),
+        y.clamp(0., (view_size.y - size.y).max(0.)),
+    )
+}
+
+fn build_preview(ctx: &mut BuildContext, track: Rect, x: f32, value: f32, preview: PreviewFn) {
+    let view = ctx.view();
+    let view_size = Vec2::new(
+        view.size.width as f32 / view.scale_factor,
+        view.size.height as f32 / view.scale_factor,
+    );
+
+    // The preview is on top of the track, the pointer has to go through it so the
+    // hovered value keeps following the pointer.
+    portal()
+        .rect(Rect::new(0., 0., view_size.x, view_size.y))
+        .build(ctx, |ctx| {
+            gesture_detector().hover_through(true).build(ctx, |ctx| {
+                measure().build_with_rect(ctx, |ctx, rect| {
+                    // Centered once its size is known from the first layout.
+                    if rect.is_none() {
+                        ctx.request_frame();
+                    }
+
+                    let size = rect.map(|rect| rect.size()).unwrap_or(Vec2::ZERO);
+                    let origin = preview_origin(x, track, size, view_size);
+
+                    zstack()
+                        .offset(origin.x, origin.y)
+                        .build(ctx, |ctx| preview(ctx, value));
+                });
+            });
+        });
+}
+
+impl StatefulWidget for Slider {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut BuildContext, mut frame: FrameBuilder) {
+        let Some(mut data) = ctx.of_mut::<SliderData>().map(std::mem::take) else {
+            return;
+        };
+
+        frame.build(ctx, |ctx| {
+            self.track_rect = measure()
+                .fill_max_width()
+                .height(THUMB_SIZE)
+                .build(ctx, |ctx| self.build_track(ctx, &mut data));
+        });
+
+        data.track_rect = self.track_rect;
+
+        if let Some(shared) = ctx.of_mut::<SliderData>() {
+            *shared = data;
+        }
+    }
+}
+
+impl Slider {
+    fn build_track(&mut self, ctx: &mut BuildContext, data: &mut SliderData) {
+        let rtl = ctx.layout_direction() == LayoutDirection::RTL;
+
+        gesture_detector()
+            .dragable(true)
+            .clickable(true)
+            .focusable(true)
+            .build(ctx, |ctx| {
+                let gesture = ctx.of::<GestureDetectorResponse>().unwrap().clone();
+
+                if gesture.is_focused()
+                    && let Some(key) = range_key(ctx.input(), rtl)
+                {
+                    let increment = data.step.unwrap_or(data.span() / 100.);
+
+                    data.value = data.snap(match key {
+                        RangeKey::Increase => data.value + increment,
+                        RangeKey::Decrease => data.value - increment,
+                        RangeKey::Start => data.min,
+                        RangeKey::End => data.max,
+                    });
+                }
+
+                if let Some(track) = self.track_rect {
+                    let pointer_x = match gesture.drag_state {
+                        DragState::Start => gesture.drag_start_x,
+                        DragState::Update => gesture.drag_x,
+                        _ => ctx.input().mouse_x / ctx.view().scale_factor,
+                    };
+                    let value =
+                        data.snap(data.min + track_progress(pointer_x, track, rtl) * data.span());
+
+                    if matches!(gesture.drag_state, DragState::Start | DragState::Update) {
+                        data.value = value;
+                    }
+
+                    // The pointer stays where the finger was lifted, it isn't a hover.
+                    let hovered = gesture.is_hot() && !ctx.input().touch;
+
+                    if hovered || gesture.is_active() {
+                        data.hover_value = Some(value);
+                        data.hover_x = track_x(data.progress(value), track, rtl);
+                    }
+                }
+
+                data.drag_state = gesture.drag_state;
+
+                let progress = data.progress(data.value);
+                let track_width = self.track_rect.map(|rect| rect.width).unwrap_or(0.);
+                let theme = ctx.widget_theme();
+                let accent = if gesture.is_active() || gesture.is_hot() {
+                    theme.accent.lerp(ColorRgba::from_hex(0xFFFFFFFF), 0.12)
+                } else {
+                    theme.accent
+                };
+                let thumb_align = if rtl { 1. - progress } else { progress };
+
+                zstack()
+                    .fill_max_size()
+                    .align_x(AlignX::Start)
+                    .align_y(AlignY::Center)
+                    .build(ctx, |ctx| {
+                        decorated_box()
+                            .color(theme.border)
+                            .border_radius(BorderRadius::all(TRACK_HEIGHT / 2.))
+                            .fill_max_width()
+                            .height(TRACK_HEIGHT)
+                            .build(ctx);
+
+                        // Reaches the center of the thumb.
+                        decorated_box()
+                            .color(accent)
+                            .border_radius(BorderRadius::all(TRACK_HEIGHT / 2.))
+                            .width(THUMB_SIZE / 2. + progress * (track_width - THUMB_SIZE).max(0.))
+                            .height(TRACK_HEIGHT)
+                            .build(ctx);
+
+                        positioned()
+                            .align(AlignX::Fraction(thumb_align * 2. - 1.), AlignY::Center)
+                            .build(ctx, |ctx| {
+                                let mut thumb = zstack();
+
+                                if gesture.is_focus_visible() {
+                                    thumb = thumb.foreground(
+                                        decoration()
+                                            .shape(BoxShape::Oval)
+                                            .border(Border::all(BorderSide::new(2., theme.accent)))
+                                            .outset(2.)
+                                            .build(ctx),
+                                    );
+                                }
+
+                                thumb.build(ctx, |ctx| {
+                                    decorated_box()
+                                        .shape(BoxShape::Oval)
+                                        .color(ColorRgba::from_hex(0xFFFFFFFF))
+                                        .border(Border::all(BorderSide::new(1., accent)))
+                                        .transition(HOVER_TRANSITION, curves::f32::ease_out_quad)
+                                        .width(THUMB_SIZE)
+                                        .height(THUMB_SIZE)
+                                        .build(ctx);
+                                });
+                            });
+                    });
+            });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clew::io::{InputEvent, MouseButton, TouchPhase};
+    use clew::keyboard::KeyCode;
+    use clew::render::RenderCommand;
+    use clew::{ClewInstance, testing};
+
+    use super::*;
+
+    /// Builds a slider of 0 to 1 in steps of 0.1 over the width of the view, returns
+    /// the value, the hovered value and the value a preview was built at in the frame
+    /// after each event, when the interaction of the event's frame is known.
+    fn slider_frames(events: &[InputEvent]) -> Vec<(f32, Option<f32>, Option<f32>)> {
+        let (mut host, mut instance) = testing::instance(300, 100);
+        let mut value = 0.;
+        let mut frame = |event: Option<&InputEvent>| {
+            if let Some(event) = event {
+                instance.push_event(event.clone());
+            }
+
+            let mut hover_value = None;
+            let mut preview_value = None;
+
+            instance.frame(&mut host, &mut |ctx| {
+                let response = slider(&mut value)
+                    .step(0.1)
+                    .fill_max_width()
+                    .preview(|_, value| preview_value = Some(value))
+                    .build(ctx);
+
+                hover_value = response.hover_value();
+            });
+
+            (value, hover_value, preview_value)
+        };
+
+        frame(None);
+
+        events
+            .iter()
+            .map(|event| {
+                frame(Some(event));
+                frame(None)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_preview_follows_hovering_pointer_and_click_seeks() {
+        let left = |pressed| InputEvent::MouseButton {
+            button: MouseButton::Left,
+            pressed,
+        };
+        // The center of the thumb goes from 7 to 293 over the track of 300.
+        let frames = slider_frames(&[
+            InputEvent::PointerMoved { x: 150., y: 7. },
+            InputEvent::PointerMoved { x: 64.2, y: 7. },
+            left(true),
+            left(false),
+            InputEvent::PointerMoved { x: 150., y: 60. },
+        ]);
+
+        assert_eq!(frames[0], (0., Some(0.5), Some(0.5)));
+        assert_eq!(frames[1], (0., Some(0.2), Some(0.2)));
+        assert_eq!(frames[2].0, 0.2);
+        assert_eq!(frames[3], (0.2, Some(0.2), Some(0.2)));
+        // Off the track.
+        assert_eq!(frames[4], (0.2, None, None));
+    }
+
+    #[test]
+    fn test_touch_shows_preview_only_while_dragging() {
+        let touch = |phase, x| InputEvent::Touch {
+            id: 1,
+            phase,
+            x,
+            y: 7.,
+        };
+        let frames = slider_frames(&[
+            touch(TouchPhase::Started, 64.2),
+            touch(TouchPhase::Moved, 150.),
+            touch(TouchPhase::Moved, 236.),
+            touch(TouchPhase::Ended, 236.),
+        ]);
+
+        assert_eq!(frames[0], (0.2, Some(0.2), Some(0.2)));
+        assert_eq!(frames[1], (0.5, Some(0.5), Some(0.5)));
+        assert_eq!(frames[2], (0.8, Some(0.8), Some(0.8)));
+        // The lifted finger leaves the pointer over the track.
+        assert_eq!(frames[3], (0.8, None, None));
+    }
+
+    /// Whether the frame drew the focus ring, the only oval with a border of 2.
+    fn has_focus_ring(instance: &mut ClewInstance) -> bool {
+        instance
+            .ui_state()
+            .render_state
+            .commands()
+            .iter()
+            .any(|command| match command {
+                RenderCommand::Oval {
+                    border: Some(border),
+                    ..
+                } => border.width == 2.,
+                _ => false,
+            })
+    }
+
+    #[test]
+    fn test_focused_slider_is_stepped_with_the_keys() {
+        let (mut host, mut instance) = testing::instance(300, 100);
+        let mut value = 0.;
+        let mut frame = |key_code: Option<KeyCode>, rtl: bool| {
+            if let Some(key_code) = key_code {
+                instance.push_event(InputEvent::Key {
+                    key_code: Some(key_code),
+                    pressed: true,
+                    repeat: false,
+                    text: None,
+                });
+            }
+
+            instance.set_layout_direction(if rtl {
+                LayoutDirection::RTL
+            } else {
+                LayoutDirection::LTR
+            });
+            instance.frame(&mut host, &mut |ctx| {
+                slider(&mut value).step(0.1).fill_max_width().build(ctx);
+            });
+
+            (value, has_focus_ring(&mut instance))
+        };
+
+        assert_eq!(frame(None, false), (0., false));
+        // The arrows do nothing until the slider has the focus.
+        assert_eq!(frame(Some(KeyCode::ArrowRight), false), (0., false));
+
+        frame(Some(KeyCode::Tab), false);
+        assert_eq!(frame(None, false), (0., true));
+        assert_eq!(frame(Some(KeyCode::ArrowRight), false), (0.1, true));
+        assert_eq!(frame(Some(KeyCode::ArrowUp), false).0, 0.2);
+        assert_eq!(frame(Some(KeyCode::End), false).0, 1.);
+        assert_eq!(frame(Some(KeyCode::ArrowUp), false).0, 1.);
+        assert!((frame(Some(KeyCode::ArrowDown), false).0 - 0.9).abs() < 1e-6);
+        assert_eq!(frame(Some(KeyCode::Home), false).0, 0.);
+        // The end of the range is on the left in RTL.
+        assert_eq!(frame(Some(KeyCode::ArrowLeft), true).0, 0.1);
+    }
+
+    #[test]
+    fn test_track_progress_mirrors_right_to_left() {
+        let track = Rect::new(100., 0., 214., THUMB_SIZE);
+
+        assert_eq!(track_progress(107., track, false), 0.);
+        assert_eq!(track_progress(157., track, false), 0.25);
+        assert_eq!(track_progress(157., track, true), 0.75);
+        assert_eq!(track_progress(400., track, false), 1.);
+        assert_eq!(track_x(0.25, track, false), 157.);
+        assert_eq!(track_x(0.75, track, true), 157.);
+    }
+
+    #[test]
+    fn test_preview_stays_in_the_view() {
+        let track = Rect::new(0., 100., 400., THUMB_SIZE);
+        let size = Vec2::new(60., 20.);
+        let view_size = Vec2::new(400., 300.);
+
+        assert_eq!(
+            preview_origin(200., track, size, view_size),
+            Vec2::new(170., 72.)
+        );
+        assert_eq!(
+            preview_origin(10., track, size, view_size),
+            Vec2::new(0., 72.)
+        );
+        assert_eq!(
+            preview_origin(395., track, size, view_size),
+            Vec2::new(340., 72.)
+        );
+
+        // No space above the track.
+        let track = Rect::new(0., 10., 400., THUMB_SIZE);
+
+        assert_eq!(
+            preview_origin(200., track, size, view_size),
+            Vec2::new(170., 32.)
+        );
+    }
+}
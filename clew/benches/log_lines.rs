@@ -0,0 +1,101 @@
+//! Time per frame of 200 visible lines of a build log, laid out by their columns with
+//! `simple_monospace` against the full shaping. Run with `cargo bench --bench log_lines`.
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use clew::prelude::*;
+use clew::{
+    self as ui, ClewHost, ClewInstance, EdgeInsets, PhysicalSize, Resources, View, ViewId,
+    assets::Assets,
+    text::{FontResources, SystemFonts},
+    widgets::builder::NoopEventLoopProxy,
+};
+
+const VISIBLE_LINES: usize = 200;
+const FRAMES: usize = 200;
+
+fn fonts() -> FontResources {
+    let mut fonts = FontResources::with_fonts(
+        SystemFonts::Lazy,
+        [(
+            "DejaVu Sans Mono",
+            include_bytes!("../../test-fonts/DejaVuSansMono.ttf").as_slice(),
+        )],
+    );
+    fonts
+        .font_system
+        .db_mut()
+        .set_monospace_family("DejaVu Sans Mono");
+
+    fonts
+}
+
+fn log_line(index: usize) -> String {
+    format!(
+        "[{:>6}] Compiling crate-{} v0.{}.{} (/build/src/crate-{}) target/debug/deps",
+        index,
+        index % 97,
+        index % 13,
+        index % 7,
+        index % 97,
+    )
+}
+
+/// Mean time of a frame, the log scrolled by `step` lines every frame.
+fn frame_time(simple_monospace: bool, step: usize) -> Duration {
+    let mut host = ClewHost::new(
+        Assets::new(),
+        Resources::new(),
+        Arc::new(NoopEventLoopProxy),
+    );
+    let view = View {
+        id: ViewId(0),
+        size: PhysicalSize::new(1200, VISIBLE_LINES as u32 * 16),
+        scale_factor: 1.,
+        safe_area: EdgeInsets::ZERO,
+    };
+    let mut instance = ClewInstance::new(view, fonts());
+    let lines: Vec<String> = (0..VISIBLE_LINES + FRAMES * step).map(log_line).collect();
+    let mut build = |first_line: usize| {
+        instance.frame(&mut host, &mut |ctx| {
+            ui::vstack().fill_max_size().build(ctx, |ctx| {
+                for (index, line) in lines[first_line..first_line + VISIBLE_LINES]
+                    .iter()
+                    .enumerate()
+                {
+                    ctx.scope(index, |ctx| {
+                        ui::text(line)
+                            .height(16.)
+                            .simple_monospace(simple_monospace)
+                            .build(ctx);
+                    });
+                }
+            });
+        });
+    };
+
+    // The fonts are loaded and the widgets are made.
+    build(0);
+
+    let started = Instant::now();
+
+    for frame in 0..FRAMES {
+        build(frame * step);
+    }
+
+    started.elapsed() / FRAMES as u32
+}
+
+fn main() {
+    for (name, step) in [("scrolling", 1), ("still", 0)] {
+        let simple = frame_time(true, step);
+        let shaped = frame_time(false, step);
+
+        println!(
+            "{VISIBLE_LINES} lines, {name}: simple monospace {simple:?}, shaped {shaped:?} per frame"
+        );
+    }
+}
@@ -0,0 +1,135 @@
+//! Header collapsing and a reading progress bar driven by the position of the scroll
+//! area below them, read through a scroll observer before the area is built.
+
+use clew as ui;
+use clew::prelude::*;
+use clew_desktop::{
+    app::{Application, ApplicationDelegate},
+    window::Window,
+    window_manager::{WindowDescriptor, WindowManager},
+};
+use clew_vello::VelloRenderer;
+
+const EXPANDED_HEIGHT: f32 = 180.;
+const COLLAPSED_HEIGHT: f32 = 56.;
+const PARAGRAPHS: usize = 40;
+
+struct CollapsingHeaderApplication;
+
+impl ApplicationDelegate<()> for CollapsingHeaderApplication {
+    fn on_start(
+        &mut self,
+        window_manager: &mut WindowManager<Self, ()>,
+        _: &mut ui::ShortcutsRegistry,
+    ) where
+        Self: std::marker::Sized,
+    {
+        window_manager.spawn_window(
+            MainWindow,
+            WindowDescriptor {
+                title: "Collapsing header".to_string(),
+                width: 800,
+                height: 600,
+                resizable: true,
+                fill_color: ui::ColorRgb::from_hex(0x121212),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn create_renderer(
+        window: std::sync::Arc<winit::window::Window>,
+    ) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+        let size = window.inner_size();
+        let redraw = window.clone();
+
+        Ok(Box::new(VelloRenderer::warming_up(
+            window,
+            size.width,
+            size.height,
+            move || redraw.request_redraw(),
+        )?))
+    }
+}
+
+pub struct MainWindow;
+
+impl Window<CollapsingHeaderApplication, ()> for MainWindow {
+    fn build(&mut self, _: &mut CollapsingHeaderApplication, ctx: &mut ui::BuildContext) {
+        let article = ui::scroll_area().fill_max_size();
+        let observer = article.observer(ctx);
+
+        // Collapses over the height it loses, the subtitle fades out over the first half.
+        let collapse = ui::scroll_effect(
+            &observer,
+            0.0..EXPANDED_HEIGHT - COLLAPSED_HEIGHT,
+            ui::curves::f32::ease_out_cubic,
+        );
+        let fade = ui::scroll_effect(
+            &observer,
+            0.0..(EXPANDED_HEIGHT - COLLAPSED_HEIGHT) / 2.,
+            |t| t,
+        );
+        let width = ctx.view().size.width as f32 / ctx.view().scale_factor;
+
+        ui::vstack().fill_max_size().spacing(0.).build(ctx, |ctx| {
+            ui::vstack()
+                .fill_max_width()
+                .height(EXPANDED_HEIGHT - (EXPANDED_HEIGHT - COLLAPSED_HEIGHT) * collapse)
+                .padding(ui::EdgeInsets::symmetric(24., 12.))
+                .spacing(4.)
+                .background(
+                    ui::decoration()
+                        .color(ui::ColorRgba::from_hex(0xFF1E2A44))
+                        .build(ctx),
+                )
+                .build(ctx, |ctx| {
+                    ui::text("Scroll-linked effects")
+                        .font_size(32. - 12. * collapse)
+                        .color(ui::ColorRgba::from_hex(0xFFFFFFFF))
+                        .build(ctx);
+
+                    if fade < 1. {
+                        ui::text("The header follows the article below it")
+                            .color(ui::ColorRgba::from_hex(0xFFB0BCD8).with_opacity(1. - fade))
+                            .build(ctx);
+                    }
+                });
+
+            ui::decorated_box()
+                .width(width * observer.progress_y() as f32)
+                .height(3.)
+                .color(ui::ColorRgba::from_hex(0xFF3A6FF8))
+                .build(ctx);
+
+            article.build(ctx, |ctx| {
+                ui::vstack()
+                    .fill_max_width()
+                    .padding(ui::EdgeInsets::all(24.))
+                    .spacing(16.)
+                    .build(ctx, |ctx| {
+                        ui::for_each(0..PARAGRAPHS).build(ctx, |ctx, paragraph| {
+                            ui::text(&format!(
+                                "{}. Scrolling the article collapses the header above \
+                                 it and fills the progress bar, both read the position \
+                                 of the scroll area before it's built.",
+                                paragraph + 1
+                            ))
+                            .build(ctx);
+                        });
+                    });
+            });
+        });
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::new()
+        .filter(None, log::LevelFilter::Info)
+        .init();
+
+    Application::run_application(CollapsingHeaderApplication)?;
+
+    Ok(())
+}
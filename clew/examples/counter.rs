@@ -6,7 +6,6 @@ use clew_desktop::{
     window_manager::{WindowDescriptor, WindowManager},
 };
 use clew_vello::VelloRenderer;
-use pollster::FutureExt;
 
 struct CounterApplication;
 
@@ -26,19 +25,23 @@ impl ApplicationDelegate<()> for CounterApplication {
                 height: 600,
                 resizable: true,
                 fill_color: ui::ColorRgb::from_hex(0x121212),
+                ..Default::default()
             },
         );
     }
 
-    fn create_renderer(window: std::sync::Arc<winit::window::Window>) -> Box<dyn ui::Renderer> {
-        Box::new(
-            VelloRenderer::new(
-                window.clone(),
-                window.inner_size().width,
-                window.inner_size().height,
-            )
-            .block_on(),
-        )
+    fn create_renderer(
+        window: std::sync::Arc<winit::window::Window>,
+    ) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+        let size = window.inner_size();
+        let redraw = window.clone();
+
+        Ok(Box::new(VelloRenderer::warming_up(
+            window,
+            size.width,
+            size.height,
+            move || redraw.request_redraw(),
+        )?))
     }
 }
 
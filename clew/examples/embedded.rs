@@ -0,0 +1,344 @@
+//! Clew embedded into an application that runs its own winit event loop and owns the
+//! wgpu device, without the application and the window manager of `clew-desktop`. The
+//! host translates the events of the window, builds a frame when there is input or clew
+//! asks for one, has it drawn into a texture with its device and blits the texture into
+//! its surface. It waits for events in between rather than redrawing all the time.
+
+use std::sync::Arc;
+
+use clew as ui;
+use clew::io::{InputEvent, MouseButton};
+use clew::prelude::*;
+use clew::widgets::builder::{ApplicationEvent, ApplicationEventLoopProxy};
+use clew_vello::{VelloRenderer, wgpu};
+use pollster::FutureExt;
+use winit::{
+    application::ApplicationHandler,
+    event::{ElementState, MouseScrollDelta, WindowEvent},
+    event_loop::{ActiveEventLoop, ControlFlow, EventLoop, EventLoopProxy},
+    window::{Window, WindowId},
+};
+
+const FILL_COLOR: u32 = 0x121212;
+
+struct WakeProxy(EventLoopProxy<ApplicationEvent>);
+
+impl ApplicationEventLoopProxy for WakeProxy {
+    fn send_event(&self, event: ApplicationEvent) {
+        let _ = self.0.send_event(event);
+    }
+}
+
+struct Embedded {
+    host: ui::ClewHost<'static>,
+    window: Option<EmbeddedWindow>,
+    counter: i32,
+}
+
+/// The device, the surface and the pipelines are the application's, clew only gets the
+/// device and the texture to draw into.
+struct Gpu {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    surface: wgpu::Surface<'static>,
+    config: wgpu::SurfaceConfiguration,
+    blitter: wgpu::util::TextureBlitter,
+    /// Texture clew draws into, blitted into the surface.
+    target: wgpu::Texture,
+}
+
+impl Gpu {
+    fn new(window: Arc<Window>) -> Result<Self, Box<dyn std::error::Error>> {
+        let size = window.inner_size();
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let surface = instance.create_surface(window)?;
+        let adapter = instance
+            .request_adapter(&wgpu::RequestAdapterOptions {
+                compatible_surface: Some(&surface),
+                ..Default::default()
+            })
+            .block_on()?;
+        let (device, queue) = adapter
+            .request_device(&wgpu::DeviceDescriptor::default())
+            .block_on()?;
+        let mut config = surface
+            .get_default_config(&adapter, size.width.max(1), size.height.max(1))
+            .ok_or("The surface isn't supported by the adapter")?;
+
+        // The blitter writes the colors as they are, an sRGB surface would encode them
+        // once more.
+        config.format = config.format.remove_srgb_suffix();
+        surface.configure(&device, &config);
+
+        let blitter = wgpu::util::TextureBlitter::new(&device, config.format);
+        let target = create_target(&device, config.width, config.height);
+
+        Ok(Self {
+            device,
+            queue,
+            surface,
+            config,
+            blitter,
+            target,
+        })
+    }
+
+    fn resize(&mut self, width: u32, height: u32) {
+        self.config.width = width.max(1);
+        self.config.height = height.max(1);
+        self.surface.configure(&self.device, &self.config);
+        self.target = create_target(&self.device, self.config.width, self.config.height);
+    }
+
+    fn present(&self) {
+        let frame = match self.surface.get_current_texture() {
+            Ok(frame) => frame,
+            Err(err) => {
+                log::error!("Failed to get the surface texture: {err}");
+                return;
+            }
+        };
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        self.blitter.copy(
+            &self.device,
+            &mut encoder,
+            &self
+                .target
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+            &frame
+                .texture
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+        );
+        self.queue.submit([encoder.finish()]);
+        frame.present();
+    }
+}
+
+/// Vello writes the frame from a compute shader, so the texture is a storage one.
+fn create_target(device: &wgpu::Device, width: u32, height: u32) -> wgpu::Texture {
+    device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Clew Target"),
+        size: wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: wgpu::TextureDimension::D2,
+        format: wgpu::TextureFormat::Rgba8Unorm,
+        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING,
+        view_formats: &[],
+    })
+}
+
+struct EmbeddedWindow {
+    window: Arc<Window>,
+    gpu: Gpu,
+    instance: ui::ClewInstance<'static>,
+    renderer: VelloRenderer,
+}
+
+impl EmbeddedWindow {
+    /// Builds the frame and draws it, the surface is presented only if it has changed.
+    fn redraw(&mut self, host: &mut ui::ClewHost<'static>, counter: &mut i32) {
+        self.instance.frame(host, &mut |ctx| build(ctx, counter));
+        self.render(host);
+        host.resources.apply_updates();
+        host.clear_broadcast_events();
+    }
+
+    fn render(&mut self, host: &ui::ClewHost<'static>) {
+        self.renderer.set_target(Some(
+            self.gpu
+                .target
+                .create_view(&wgpu::TextureViewDescriptor::default()),
+        ));
+
+        if self
+            .instance
+            .render(host, &mut self.renderer, ui::ColorRgb::from_hex(FILL_COLOR))
+        {
+            self.gpu.present();
+        }
+    }
+}
+
+impl ApplicationHandler<ApplicationEvent> for Embedded {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        let window = Arc::new(
+            event_loop
+                .create_window(Window::default_attributes().with_title("Embedded"))
+                .expect("Failed to create a window"),
+        );
+        let gpu = Gpu::new(window.clone()).expect("Failed to set up the device");
+        let renderer = VelloRenderer::with_device(gpu.device.clone(), gpu.queue.clone())
+            .expect("Failed to create a renderer");
+
+        let size = window.inner_size();
+        let view = ui::View {
+            id: ui::ViewId(0),
+            size: ui::PhysicalSize::new(size.width, size.height),
+            scale_factor: window.scale_factor() as f32,
+            safe_area: ui::EdgeInsets::ZERO,
+        };
+        let instance = ui::ClewInstance::new(view, self.host.assets.create_font_resources());
+
+        window.request_redraw();
+
+        self.window = Some(EmbeddedWindow {
+            window,
+            gpu,
+            instance,
+            renderer,
+        });
+    }
+
+    fn user_event(&mut self, _: &ActiveEventLoop, event: ApplicationEvent) {
+        match event {
+            ApplicationEvent::RunOnMainThread(task) => task.run(),
+            // A widget asked for a frame, e.g. its animation goes on.
+            ApplicationEvent::Wake { .. } => {
+                if let Some(window) = &self.window {
+                    window.window.request_redraw();
+                }
+            }
+            // Picked up in `about_to_wait`.
+            ApplicationEvent::AssetLoaded => {}
+        }
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _: WindowId, event: WindowEvent) {
+        let Some(window) = &mut self.window else {
+            return;
+        };
+
+        let input = match event {
+            WindowEvent::CloseRequested => {
+                event_loop.exit();
+                None
+            }
+            WindowEvent::Resized(size) => {
+                window.gpu.resize(size.width, size.height);
+                window
+                    .instance
+                    .resize(ui::PhysicalSize::new(size.width, size.height));
+                window.window.request_redraw();
+                None
+            }
+            WindowEvent::ScaleFactorChanged { scale_factor, .. } => {
+                window.instance.set_scale_factor(scale_factor as f32);
+                window.window.request_redraw();
+                None
+            }
+            WindowEvent::CursorMoved { position, .. } => Some(InputEvent::PointerMoved {
+                x: position.x as f32,
+                y: position.y as f32,
+            }),
+            WindowEvent::MouseInput { state, button, .. } => Some(InputEvent::MouseButton {
+                button: match button {
+                    winit::event::MouseButton::Left => MouseButton::Left,
+                    winit::event::MouseButton::Right => MouseButton::Right,
+                    winit::event::MouseButton::Middle => MouseButton::Middle,
+                    _ => MouseButton::Other,
+                },
+                pressed: state == ElementState::Pressed,
+            }),
+            WindowEvent::MouseWheel {
+                delta: MouseScrollDelta::PixelDelta(position),
+                ..
+            } => Some(InputEvent::MouseWheel {
+                delta_x: position.x,
+                delta_y: position.y,
+            }),
+            WindowEvent::Focused(focused) => Some(InputEvent::Focused(focused)),
+            WindowEvent::RedrawRequested => {
+                window.redraw(&mut self.host, &mut self.counter);
+                None
+            }
+            _ => None,
+        };
+
+        // The events of an iteration are handled by a single frame, see `about_to_wait`.
+        if let Some(input) = input {
+            window.instance.push_event(input);
+        }
+    }
+
+    fn about_to_wait(&mut self, event_loop: &ActiveEventLoop) {
+        let Some(window) = &mut self.window else {
+            return;
+        };
+
+        if self.host.assets.receive_loaded() {
+            window.instance.ui_state().render_state.invalidate_layers();
+            window.instance.invalidate();
+        }
+
+        // A frame is built for new input and while clew asks for them, e.g. during an
+        // animation. Otherwise only the blinking caret may change, it's repainted
+        // without a build and the loop sleeps until its next blink.
+        if window.instance.needs_build(&self.host) {
+            window.window.request_redraw();
+            event_loop.set_control_flow(ControlFlow::Wait);
+
+            return;
+        }
+
+        if window.instance.repaint().is_some() {
+            window.render(&self.host);
+        }
+
+        event_loop.set_control_flow(match window.instance.next_repaint() {
+            Some(at) => ControlFlow::WaitUntil(at),
+            None => ControlFlow::Wait,
+        });
+    }
+}
+
+fn build(ctx: &mut ui::BuildContext, counter: &mut i32) {
+    ui::zstack()
+        .fill_max_size()
+        .align_x(ui::AlignX::Center)
+        .align_y(ui::AlignY::Center)
+        .build(ctx, |ctx| {
+            ui::vstack()
+                .spacing(12.)
+                .cross_axis_alignment(ui::CrossAxisAlignment::Center)
+                .build(ctx, |ctx| {
+                    ui::text(&format!("Counter: {counter}")).build(ctx);
+
+                    ui::hstack().build(ctx, |ctx| {
+                        if clew_widgets::button("+").build(ctx).clicked() {
+                            *counter += 1;
+                        }
+
+                        if clew_widgets::button("-").build(ctx).clicked() {
+                            *counter -= 1;
+                        }
+                    });
+                });
+        });
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::new()
+        .filter(None, log::LevelFilter::Info)
+        .init();
+
+    let event_loop = EventLoop::with_user_event().build()?;
+    let proxy = Arc::new(WakeProxy(event_loop.create_proxy()));
+
+    let mut app = Embedded {
+        host: ui::ClewHost::new(ui::assets::Assets::new(), ui::Resources::new(), proxy),
+        window: None,
+        counter: 0,
+    };
+
+    event_loop.run_app(&mut app)?;
+
+    Ok(())
+}
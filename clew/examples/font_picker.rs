@@ -0,0 +1,164 @@
+use clew as ui;
+use clew::prelude::*;
+use clew::text::FamilyInfo;
+use clew_desktop::{
+    app::{Application, ApplicationDelegate},
+    window::Window,
+    window_manager::{WindowDescriptor, WindowManager},
+};
+use clew_vello::VelloRenderer;
+use pollster::FutureExt;
+
+struct DemoApplication;
+
+impl ApplicationDelegate<()> for DemoApplication {
+    fn on_start(
+        &mut self,
+        window_manager: &mut WindowManager<Self, ()>,
+        _: &mut ui::ShortcutsRegistry,
+    ) where
+        Self: std::marker::Sized,
+    {
+        window_manager.spawn_window(
+            MainWindow {
+                families: Vec::new(),
+                selected: 0,
+            },
+            WindowDescriptor {
+                title: "Font Picker".to_string(),
+                width: 800,
+                height: 600,
+                resizable: true,
+                fill_color: ui::ColorRgb::from_hex(0x121212),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn create_renderer(
+        window: std::sync::Arc<winit::window::Window>,
+    ) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+        Ok(Box::new(
+            VelloRenderer::new(
+                window.clone(),
+                window.inner_size().width,
+                window.inner_size().height,
+            )
+            .block_on()?,
+        ))
+    }
+}
+
+pub struct MainWindow {
+    families: Vec<(&'static str, FamilyInfo)>,
+    selected: usize,
+}
+
+impl Window<DemoApplication, ()> for MainWindow {
+    fn build(&mut self, _: &mut DemoApplication, ctx: &mut ui::BuildContext) {
+        if self.families.is_empty() {
+            // Text attributes borrow the family name for the lifetime of the text resources,
+            // the list of families is loaded once, so it's fine to keep the names around.
+            self.families = ctx
+                .fonts()
+                .families()
+                .into_iter()
+                .map(|family| {
+                    let name: &'static str = Box::leak(family.name.clone().into_boxed_str());
+
+                    (name, family)
+                })
+                .collect();
+        }
+
+        ui::vstack()
+            .fill_max_size()
+            .spacing(8.)
+            .padding(ui::EdgeInsets::all(16.))
+            .build(ctx, |ctx| {
+                if let Some((name, family)) = self.families.get(self.selected) {
+                    ui::text("The quick brown fox jumps over the lazy dog")
+                        .font_family(name)
+                        .height(48.)
+                        .fill_max_width()
+                        .text_vertical_align(ui::AlignY::Center)
+                        .build(ctx);
+
+                    ui::text(&format!(
+                        "{} — weights: {:?}{}",
+                        family.name,
+                        family.weights,
+                        if family.monospaced {
+                            ", monospaced"
+                        } else {
+                            ""
+                        },
+                    ))
+                    .color(ui::ColorRgba::from_hex(0xFF999999))
+                    .build(ctx);
+                }
+
+                // Items builder is a `Fn` closure, so the selection is tracked in a cell.
+                let selected = std::cell::Cell::new(self.selected);
+
+                ui::zstack().fill_max_size().build(ctx, |ctx| {
+                    let response = ui::virtual_list()
+                        .fill_max_size()
+                        .items_count(self.families.len() as u64)
+                        .item_size(32.)
+                        .build(ctx, |ctx, index| {
+                            let index = index as usize;
+                            let (name, _) = self.families[index];
+
+                            let response =
+                                ui::gesture_detector().clickable(true).build(ctx, |ctx| {
+                                    let response = ctx.of::<ui::GestureDetectorResponse>().unwrap();
+
+                                    let color = if index == selected.get() {
+                                        ui::ColorRgba::from_hex(0xFF357CCE)
+                                    } else if response.is_hot() {
+                                        ui::ColorRgba::from_hex(0xFF2E2E2E)
+                                    } else {
+                                        ui::ColorRgba::TRANSPARENT
+                                    };
+
+                                    ui::text(name)
+                                        .font_family(name)
+                                        .text_vertical_align(ui::AlignY::Center)
+                                        .padding(ui::EdgeInsets::symmetric(16., 0.))
+                                        .background(ui::decoration().color(color).build(ctx))
+                                        .height(32.)
+                                        .fill_max_width()
+                                        .build(ctx);
+                                });
+
+                            if response.clicked() {
+                                selected.set(index);
+                            }
+                        });
+
+                    if response.overflow_y {
+                        ctx.provide(response.clone(), |ctx| {
+                            clew_widgets::vertical_scroll_bar().build(ctx);
+                        });
+                    }
+                });
+
+                self.selected = selected.get();
+            });
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracy_client::Client::start();
+
+    env_logger::Builder::new()
+        .filter(None, log::LevelFilter::Info)
+        .init();
+
+    log::info!("Starting app");
+    Application::run_application(DemoApplication)?;
+
+    Ok(())
+}
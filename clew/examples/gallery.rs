@@ -0,0 +1,952 @@
+//! Every widget and visual feature of clew on its own page, with the renderer and the
+//! layout direction switchable at runtime.
+//!
+//! The gallery doubles as a fixture for visual regressions: with `--screenshot <dir>`
+//! it renders every page without a window at the scales of 1 and 2 with both renderers
+//! and writes them to the directory as PNGs, e.g.
+//!
+//! ```text
+//! cargo run --example gallery -- --screenshot target/gallery
+//! ```
+//!
+//! The images are named `<page>@<scale>x-<renderer>.png`. The pages are rendered with
+//! the shipped DejaVu Sans, so they don't depend on the fonts of the machine. With
+//! `--compare` the pages are rendered again and each renderer's are compared with its
+//! goldens checked in at `clew/examples/gallery/goldens`, or with the ones in the given
+//! directory:
+//!
+//! ```text
+//! cargo run --example gallery -- --compare
+//! ```
+//!
+//! Without a GPU the vello pages are skipped, the summary says so, unless
+//! `CLEW_REQUIRE_GPU` is set, then it fails. After an intended change of the pages, the
+//! goldens are updated by writing the screenshots over them:
+//!
+//! ```text
+//! cargo run --example gallery -- --screenshot clew/examples/gallery/goldens
+//! ```
+//!
+//! The vello goldens are rendered by a GPU and other GPUs round a little differently,
+//! they're compared by the averages of blocks of pixels rather than pixel by pixel.
+
+use std::{path::Path, sync::Arc};
+
+use clew as ui;
+use clew::prelude::*;
+use clew::render::RgbaImage;
+use clew::text::SystemFonts;
+use clew::widgets::builder::NoopEventLoopProxy;
+use clew_desktop::{
+    app::{Application, ApplicationDelegate},
+    window::Window,
+    window_manager::{WindowDescriptor, WindowManager},
+};
+use clew_tiny_skia::{HeadlessRenderer, TinySkiaRenderer};
+use clew_vello::VelloRenderer;
+use clew_widgets::{DockAxis, DockLayout, DockNode, Segment, SegmentWidth, SelectOption};
+use pollster::FutureExt;
+
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 600;
+const FILL_COLOR: u32 = 0x121212;
+
+/// Shipped font the screenshots are rendered with.
+const SCREENSHOT_FONT: &[u8] = include_bytes!("../../test-fonts/DejaVuSans.ttf");
+
+/// Screenshots of the pages by both renderers the fresh renders are compared with.
+const GOLDENS_DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/examples/gallery/goldens");
+
+/// Size of the blocks of pixels the vello screenshots are compared with the goldens by,
+/// and how much their averages may differ.
+const GOLDEN_BLOCK: u32 = 4;
+const GOLDEN_BLOCK_TOLERANCE: u32 = 12;
+
+/// Frames built before a page is captured, the widgets placed from the measures of the
+/// previous layouts, like the indicator of a segmented control, take a few to settle.
+const SETTLE_FRAMES: usize = 8;
+
+const PAGES: [&str; 12] = [
+    "Buttons",
+    "Inputs",
+    "Scroll",
+    "Gradients",
+    "SVG",
+    "Clipping",
+    "Knob",
+    "Slider",
+    "Select",
+    "Segments",
+    "Dock",
+    "Navigation",
+];
+
+/// Length of the clip the slider page seeks in, in seconds.
+const DURATION: f32 = 754.;
+
+const LOGO: &str = "logo";
+const STAR: &str = "star";
+const STAR_SVG: &str = r##"<svg width="24" height="24" viewBox="0 0 24 24" xmlns="http://www.w3.org/2000/svg"><path d="M12 2l3 6.5 7 .8-5.2 4.8 1.4 7L12 17.6 5.8 21.1l1.4-7L2 9.3l7-.8z" fill="#000"/></svg>"##;
+
+#[derive(Clone, Copy)]
+enum GalleryEvent {
+    SetRenderer(ui::ViewId, Backend),
+    SetLayoutDirection(ui::ViewId, ui::LayoutDirection),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Backend {
+    Vello,
+    TinySkia,
+}
+
+impl Backend {
+    const ALL: [Backend; 2] = [Backend::Vello, Backend::TinySkia];
+
+    fn name(self) -> &'static str {
+        match self {
+            Backend::Vello => "vello",
+            Backend::TinySkia => "tiny-skia",
+        }
+    }
+}
+
+fn create_vello_renderer(
+    window: Arc<winit::window::Window>,
+) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+    Ok(Box::new(
+        VelloRenderer::new(
+            window.clone(),
+            window.inner_size().width,
+            window.inner_size().height,
+        )
+        .block_on()?,
+    ))
+}
+
+fn create_tiny_skia_renderer(
+    window: Arc<winit::window::Window>,
+) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+    Ok(Box::new(TinySkiaRenderer::new(window.clone(), window)?))
+}
+
+fn load_assets(assets: &mut ui::assets::Assets) {
+    let logo = include_bytes!("../../images/logo.svg");
+
+    for (name, data) in [(LOGO, logo.as_slice()), (STAR, STAR_SVG.as_bytes())] {
+        if let Err(err) = assets.load_svg(name, data) {
+            log::error!("{err}");
+        }
+    }
+}
+
+struct GalleryApplication;
+
+impl ApplicationDelegate<GalleryEvent> for GalleryApplication {
+    fn init_assets(&mut self, assets: &mut ui::assets::Assets) {
+        load_assets(assets);
+    }
+
+    fn on_start(
+        &mut self,
+        window_manager: &mut WindowManager<Self, GalleryEvent>,
+        _: &mut ui::ShortcutsRegistry,
+    ) where
+        Self: std::marker::Sized,
+    {
+        window_manager.spawn_window(
+            MainWindow::default(),
+            WindowDescriptor {
+                title: "Gallery".to_string(),
+                width: WIDTH,
+                height: HEIGHT,
+                resizable: true,
+                fill_color: ui::ColorRgb::from_hex(FILL_COLOR),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn on_event(
+        &mut self,
+        window_manager: &mut WindowManager<Self, GalleryEvent>,
+        event: &GalleryEvent,
+    ) {
+        match *event {
+            GalleryEvent::SetRenderer(view_id, backend) => {
+                let factory = match backend {
+                    Backend::Vello => create_vello_renderer,
+                    Backend::TinySkia => create_tiny_skia_renderer,
+                };
+
+                if let Err(err) = window_manager.replace_renderer(view_id, factory) {
+                    log::error!("Failed to switch to {}: {err}", backend.name());
+                }
+            }
+            GalleryEvent::SetLayoutDirection(view_id, layout_direction) => {
+                window_manager.set_layout_direction(view_id, layout_direction);
+            }
+        }
+    }
+
+    fn create_renderer(
+        window: Arc<winit::window::Window>,
+    ) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+        create_vello_renderer(window)
+    }
+}
+
+struct Fruit {
+    id: usize,
+    name: &'static str,
+}
+
+impl Identifiable for Fruit {
+    type Id = usize;
+
+    fn id(&self) -> usize {
+        self.id
+    }
+}
+
+impl SelectOption for Fruit {
+    fn label(&self) -> &str {
+        self.name
+    }
+}
+
+const FRUITS: [Fruit; 4] = [
+    Fruit {
+        id: 0,
+        name: "Apple",
+    },
+    Fruit {
+        id: 1,
+        name: "Banana",
+    },
+    Fruit {
+        id: 2,
+        name: "Cherry",
+    },
+    Fruit {
+        id: 3,
+        name: "Date",
+    },
+];
+
+/// What the pages show, built the same way in the window and for the screenshots.
+#[derive(Default)]
+pub struct Gallery {
+    page: usize,
+    backend: usize,
+    rtl: bool,
+    counter: i32,
+    volume: f32,
+    position: f32,
+    fruit: Option<usize>,
+    alignment: usize,
+    name: ui::TextData,
+    search: ui::TextData,
+    notes: ui::TextData,
+    dock: Option<DockLayout>,
+    results_page: usize,
+}
+
+impl Gallery {
+    fn build(&mut self, ctx: &mut ui::BuildContext) {
+        ui::vstack()
+            .fill_max_size()
+            .spacing(24.)
+            .padding(ui::EdgeInsets::all(24.))
+            .cross_axis_alignment(ui::CrossAxisAlignment::Center)
+            .build(ctx, |ctx| {
+                self.build_toolbar(ctx);
+
+                clew_widgets::segmented_control(&mut self.page, &PAGES)
+                    .segment_width(SegmentWidth::Content)
+                    .build(ctx);
+
+                ui::vstack()
+                    .spacing(12.)
+                    .cross_axis_alignment(ui::CrossAxisAlignment::Center)
+                    .build(ctx, |ctx| match self.page {
+                        0 => self.build_buttons(ctx),
+                        1 => self.build_inputs(ctx),
+                        2 => build_nested_scroll(ctx),
+                        3 => build_gradients(ctx),
+                        4 => build_svgs(ctx),
+                        5 => build_clipping(ctx),
+                        6 => self.build_knob(ctx),
+                        7 => self.build_slider(ctx),
+                        8 => self.build_select(ctx),
+                        9 => self.build_segments(ctx),
+                        10 => self.build_dock(ctx),
+                        _ => self.build_navigation(ctx),
+                    });
+            });
+    }
+
+    fn build_toolbar(&mut self, ctx: &mut ui::BuildContext) {
+        let view_id = ctx.view().id;
+
+        ui::hstack().spacing(12.).build(ctx, |ctx| {
+            let backends = Backend::ALL.map(|backend| backend.name());
+
+            if clew_widgets::segmented_control(&mut self.backend, &backends)
+                .build(ctx)
+                .changed()
+            {
+                ctx.broadcast(GalleryEvent::SetRenderer(
+                    view_id,
+                    Backend::ALL[self.backend],
+                ));
+            }
+
+            let label = if self.rtl {
+                "Left to right"
+            } else {
+                "Right to left"
+            };
+
+            if clew_widgets::button(label).build(ctx).clicked() {
+                self.rtl = !self.rtl;
+
+                let layout_direction = if self.rtl {
+                    ui::LayoutDirection::RTL
+                } else {
+                    ui::LayoutDirection::LTR
+                };
+
+                ctx.broadcast(GalleryEvent::SetLayoutDirection(view_id, layout_direction));
+            }
+        });
+    }
+
+    fn build_buttons(&mut self, ctx: &mut ui::BuildContext) {
+        ui::text(&format!("Counter: {}", self.counter)).build(ctx);
+
+        ui::hstack().build(ctx, |ctx| {
+            if clew_widgets::button("+").build(ctx).clicked() {
+                self.counter += 1;
+            }
+
+            if clew_widgets::button("-").build(ctx).clicked() {
+                self.counter -= 1;
+            }
+
+            clew_widgets::button("Disabled").disabled(true).build(ctx);
+        });
+
+        ui::text("Hover, press and tab to the buttons for the other states")
+            .color(ui::ColorRgba::from_hex(0xFF999999))
+            .build(ctx);
+    }
+
+    fn build_inputs(&mut self, ctx: &mut ui::BuildContext) {
+        build_text_field(ctx, &mut self.name, "Name", 1);
+        build_text_field(ctx, &mut self.search, "Search", 1);
+        build_text_field(ctx, &mut self.notes, "Notes", 4);
+    }
+
+    fn build_knob(&mut self, ctx: &mut ui::BuildContext) {
+        clew_widgets::knob(&mut self.volume)
+            .range(0.0..=100.)
+            .step(1.)
+            .precision(0)
+            .build(ctx);
+    }
+
+    fn build_slider(&mut self, ctx: &mut ui::BuildContext) {
+        let response = clew_widgets::slider(&mut self.position)
+            .range(0.0..=DURATION)
+            .step(1.)
+            .preview(|ctx, value| {
+                ui::text(&timestamp(value))
+                    .color(ui::ColorRgba::from_hex(0xFFE0E0E0))
+                    .padding(ui::EdgeInsets::symmetric(6., 3.))
+                    .background(
+                        ui::decoration()
+                            .color(ui::ColorRgba::from_hex(0xFF1C1C1C))
+                            .border_radius(ui::BorderRadius::all(4.))
+                            .build(ctx),
+                    )
+                    .build(ctx);
+            })
+            .width(400.)
+            .build(ctx);
+
+        let label = match response.hover_value() {
+            Some(value) => format!("{} / {}", timestamp(self.position), timestamp(value)),
+            None => timestamp(self.position),
+        };
+
+        ui::text(&label)
+            .color(ui::ColorRgba::from_hex(0xFF999999))
+            .build(ctx);
+    }
+
+    fn build_select(&mut self, ctx: &mut ui::BuildContext) {
+        clew_widgets::select(&mut self.fruit, &FRUITS)
+            .placeholder("Pick a fruit")
+            .searchable(true)
+            .width(200.)
+            .build(ctx);
+
+        // The same selection as a list, Tab into it and pick with the arrows.
+        clew_widgets::listbox(&mut self.fruit, &FRUITS)
+            .width(200.)
+            .build(ctx);
+    }
+
+    fn build_segments(&mut self, ctx: &mut ui::BuildContext) {
+        let labels = ["Left", "Center", "Right", "Justify"];
+        let segments = [
+            Segment::new(labels[0]),
+            Segment::new(labels[1]),
+            Segment::new(labels[2]),
+            Segment::new(labels[3]).disabled(true),
+        ];
+
+        let response = clew_widgets::segmented_control(&mut self.alignment, &segments)
+            .segment_width(SegmentWidth::Content)
+            .build(ctx);
+
+        if response.changed() {
+            log::info!("Alignment: {}", labels[self.alignment]);
+        }
+
+        clew_widgets::radio_group(&mut self.alignment)
+            .option(0, labels[0])
+            .option(1, labels[1])
+            .option(2, labels[2])
+            .disabled_option(3, labels[3])
+            .axis(ui::FocusAxis::Horizontal)
+            .build(ctx);
+    }
+
+    fn build_dock(&mut self, ctx: &mut ui::BuildContext) {
+        let layout = self.dock.get_or_insert_with(|| {
+            DockLayout::new(DockNode::split(
+                DockAxis::Horizontal,
+                0.3,
+                DockNode::tabs(["Files", "Outline"]),
+                DockNode::split(
+                    DockAxis::Vertical,
+                    0.7,
+                    DockNode::tabs(["Editor"]),
+                    DockNode::tabs(["Console"]),
+                ),
+            ))
+        });
+
+        clew_widgets::dock_space(layout)
+            .width(640.)
+            .height(360.)
+            .build(ctx, |ctx, panel| {
+                ui::text(panel)
+                    .color(ui::ColorRgba::from_hex(0xFF999999))
+                    .padding(ui::EdgeInsets::all(12.))
+                    .build(ctx);
+            });
+
+        let closed = layout
+            .closed_panels()
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+
+        ui::hstack().spacing(8.).build(ctx, |ctx| {
+            for panel in closed {
+                ctx.scope(&panel, |ctx| {
+                    if clew_widgets::button(&format!("Restore {panel}"))
+                        .build(ctx)
+                        .clicked()
+                    {
+                        layout.restore(&panel);
+                    }
+                });
+            }
+        });
+    }
+
+    fn build_navigation(&mut self, ctx: &mut ui::BuildContext) {
+        let path = [
+            "Home",
+            "Documents",
+            "Projects",
+            "Clew",
+            "Examples",
+            "gallery.rs",
+        ];
+
+        // The narrow trail collapses the middle folders into the overflow menu.
+        for width in [520_u32, 240] {
+            ctx.scope(width, |ctx| {
+                let response = clew_widgets::breadcrumbs(&path)
+                    .width(width as f32)
+                    .build(ctx);
+
+                if let Some(index) = response.clicked() {
+                    log::info!("Navigate to {}", path[index]);
+                }
+            });
+        }
+
+        clew_widgets::pagination(&mut self.results_page, 42).build(ctx);
+
+        // Click the page number to type the page to go to.
+        clew_widgets::pagination(&mut self.results_page, 42)
+            .compact(true)
+            .build(ctx);
+    }
+}
+
+/// Field with the hint shown while it's empty. The fields are built by the same code,
+/// so each one is scoped by its hint to keep their states apart.
+fn build_text_field(ctx: &mut ui::BuildContext, text: &mut ui::TextData, hint: &str, lines: u32) {
+    let empty = text.is_empty();
+
+    ui::scope(hint).build(ctx, |ctx| {
+        ui::zstack()
+            .width(320.)
+            .padding(ui::EdgeInsets::symmetric(8., 6.))
+            .background(
+                ui::decoration()
+                    .color(ui::ColorRgba::from_hex(0xFF1E1E1E))
+                    .border_radius(ui::BorderRadius::all(4.))
+                    .border(ui::Border::all(ui::BorderSide::new(
+                        1.,
+                        ui::ColorRgba::from_hex(0xFF414141),
+                    )))
+                    .build(ctx),
+            )
+            .build(ctx, |ctx| {
+                ui::editable_text(text)
+                    .min_lines(lines)
+                    .max_lines(lines)
+                    .fill_max_width()
+                    .build(ctx);
+
+                if empty {
+                    ui::text(hint)
+                        .color(ui::ColorRgba::from_hex(0xFF777777))
+                        .build(ctx);
+                }
+            })
+    });
+}
+
+/// Minutes and seconds of a position in the clip.
+fn timestamp(seconds: f32) -> String {
+    let seconds = seconds as u32;
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn build_nested_scroll(ctx: &mut ui::BuildContext) {
+    ui::scroll_area()
+        .width(480.)
+        .height(320.)
+        .build(ctx, |ctx| {
+            ui::vstack().spacing(8.).build(ctx, |ctx| {
+                ui::for_each(0..6).build(ctx, |ctx, row| {
+                    ui::scroll_area()
+                        .scroll_direction(ui::ScrollDirection::Horizontal)
+                        .width(480.)
+                        .height(120.)
+                        .build(ctx, |ctx| {
+                            ui::hstack().spacing(8.).build(ctx, |ctx| {
+                                ui::for_each(0..8).build(ctx, |ctx, card| {
+                                    build_card(ctx, row, card);
+                                });
+                            });
+                        });
+                });
+            });
+        });
+}
+
+fn build_card(ctx: &mut ui::BuildContext, row: usize, card: usize) {
+    ui::scroll_area()
+        .width(140.)
+        .height(120.)
+        .background(
+            ui::decoration()
+                .color(ui::ColorRgba::from_hex(0xFF2E2E2E))
+                .border_radius(ui::BorderRadius::all(8.))
+                .build(ctx),
+        )
+        .build(ctx, |ctx| {
+            ui::vstack()
+                .padding(ui::EdgeInsets::all(8.))
+                .build(ctx, |ctx| {
+                    ui::for_each(0..10).build(ctx, |ctx, line| {
+                        ui::text(&format!("{row}.{card} line {line}")).build(ctx);
+                    });
+                });
+        });
+}
+
+fn build_swatch(ctx: &mut ui::BuildContext, label: &str, gradient: ui::Gradient) {
+    ui::scope(label).build(ctx, |ctx| {
+        ui::vstack()
+            .spacing(4.)
+            .cross_axis_alignment(ui::CrossAxisAlignment::Center)
+            .build(ctx, |ctx| {
+                ui::zstack()
+                    .width(120.)
+                    .height(80.)
+                    .background(
+                        ui::decoration()
+                            .border_radius(ui::BorderRadius::all(6.))
+                            .add_gradient(gradient)
+                            .build(ctx),
+                    )
+                    .build(ctx, |_| {});
+
+                ui::text(label).build(ctx);
+            })
+    });
+}
+
+fn build_gradients(ctx: &mut ui::BuildContext) {
+    let blue = ui::ColorRgba::from_hex(0xFF357CCE);
+    let pink = ui::ColorRgba::from_hex(0xFFE0457B);
+    let yellow = ui::ColorRgba::from_hex(0xFFF2C94C);
+
+    ui::hstack().spacing(12.).build(ctx, |ctx| {
+        build_swatch(
+            ctx,
+            "Vertical",
+            ui::Gradient::Linear(ui::LinearGradient::vertical((blue, pink))),
+        );
+        build_swatch(
+            ctx,
+            "Angled",
+            ui::Gradient::Linear(ui::LinearGradient::angled(45., (blue, pink, yellow))),
+        );
+        build_swatch(
+            ctx,
+            "Radial",
+            ui::Gradient::Radial(ui::RadialGradient::circle(vec![yellow, pink, blue])),
+        );
+        build_swatch(
+            ctx,
+            "Sweep",
+            ui::Gradient::Sweep(ui::SweepGradient::full(vec![blue, pink, yellow, blue])),
+        );
+    });
+}
+
+fn build_svgs(ctx: &mut ui::BuildContext) {
+    ui::svg(LOGO).width(160.).height(133.).build(ctx);
+
+    ui::hstack().spacing(12.).build(ctx, |ctx| {
+        for color in [0xFFFFFFFF, 0xFF357CCE, 0xFFE0457B, 0xFFF2C94C] {
+            ui::svg(STAR)
+                .id(color)
+                .color(ui::ColorRgba::from_hex(color))
+                .width(32.)
+                .height(32.)
+                .build(ctx);
+        }
+    });
+}
+
+fn build_clipping(ctx: &mut ui::BuildContext) {
+    let clips = [
+        ("Rect", ui::Clip::Rect),
+        (
+            "Rounded",
+            ui::Clip::RoundedRect {
+                border_radius: ui::BorderRadius::all(24.),
+            },
+        ),
+        ("Oval", ui::Clip::Oval),
+    ];
+
+    ui::hstack().spacing(24.).build(ctx, |ctx| {
+        for (label, clip) in clips {
+            ui::scope(label).build(ctx, |ctx| {
+                ui::vstack()
+                    .spacing(4.)
+                    .cross_axis_alignment(ui::CrossAxisAlignment::Center)
+                    .build(ctx, |ctx| {
+                        // The content is larger than the clip, so only the clip shape shows.
+                        ui::zstack()
+                            .width(100.)
+                            .height(100.)
+                            .clip(clip)
+                            .build(ctx, |ctx| {
+                                ui::zstack()
+                                    .width(140.)
+                                    .height(140.)
+                                    .background(
+                                        ui::decoration()
+                                            .add_linear_gradient(ui::LinearGradient::horizontal((
+                                                ui::ColorRgba::from_hex(0xFF357CCE),
+                                                ui::ColorRgba::from_hex(0xFFE0457B),
+                                            )))
+                                            .build(ctx),
+                                    )
+                                    .build(ctx, |_| {});
+                            });
+
+                        ui::text(label).build(ctx);
+                    })
+            });
+        }
+    });
+}
+
+#[derive(Default)]
+pub struct MainWindow {
+    gallery: Gallery,
+}
+
+impl Window<GalleryApplication, GalleryEvent> for MainWindow {
+    fn build(&mut self, _: &mut GalleryApplication, ctx: &mut ui::BuildContext) {
+        self.gallery.build(ctx);
+    }
+}
+
+/// Renders every page with both renderers at the scales of 1 and 2, the images are
+/// passed on with the name of their page and scale, e.g. `buttons@2x`. Returns why the
+/// vello pages were skipped, if they were.
+fn render_pages(
+    mut output: impl FnMut(&str, Backend, &RgbaImage) -> Result<(), Box<dyn std::error::Error>>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let mut renderers: Vec<(Backend, Box<dyn ui::Renderer>)> =
+        vec![(Backend::TinySkia, Box::new(HeadlessRenderer::new()))];
+    let mut skipped = None;
+
+    match VelloRenderer::headless().block_on() {
+        Ok(renderer) => renderers.push((Backend::Vello, Box::new(renderer))),
+        // Machines that are expected to have a GPU can't skip them.
+        Err(err) if std::env::var_os("CLEW_REQUIRE_GPU").is_some() => return Err(err.into()),
+        Err(err) => skipped = Some(format!("no GPU: {err}")),
+    }
+
+    let mut assets = ui::assets::Assets::new();
+    load_assets(&mut assets);
+
+    // The shipped font rather than the installed ones, so the pages look the same on
+    // every machine.
+    assets.set_system_fonts(SystemFonts::Lazy);
+    assets.load_font("DejaVu Sans", SCREENSHOT_FONT);
+
+    let mut host = ui::ClewHost::new(assets, ui::Resources::new(), Arc::new(NoopEventLoopProxy));
+
+    for scale in [1, 2] {
+        for (page, name) in PAGES.iter().enumerate() {
+            let view = ui::View {
+                id: ui::ViewId(0),
+                size: ui::PhysicalSize::new(WIDTH * scale, HEIGHT * scale),
+                scale_factor: scale as f32,
+                safe_area: ui::EdgeInsets::ZERO,
+            };
+            let mut instance = ui::ClewInstance::new(view, host.assets.create_font_resources());
+            let mut gallery = Gallery {
+                page,
+                ..Default::default()
+            };
+
+            // No time passes, so the transitions are captured at rest.
+            instance.set_paused(true);
+
+            for _ in 0..SETTLE_FRAMES {
+                instance.frame(&mut host, &mut |ctx| gallery.build(ctx));
+                host.clear_broadcast_events();
+            }
+
+            for (backend, renderer) in &mut renderers {
+                let image = instance.render_image(
+                    &host,
+                    renderer.as_mut(),
+                    ui::ColorRgb::from_hex(FILL_COLOR),
+                )?;
+
+                output(
+                    &format!("{}@{scale}x", name.to_lowercase()),
+                    *backend,
+                    &image,
+                )?;
+            }
+        }
+    }
+
+    Ok(skipped)
+}
+
+/// Writes the pages into the directory as `<page>@<scale>x-<renderer>.png`.
+fn render_screenshots(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    std::fs::create_dir_all(dir)?;
+
+    let skipped = render_pages(|name, backend, image| {
+        let path = dir.join(format!("{name}-{}.png", backend.name()));
+
+        image::save_buffer(
+            &path,
+            &image.pixels,
+            image.width,
+            image.height,
+            image::ExtendedColorType::Rgba8,
+        )?;
+        log::info!("Wrote {}", path.display());
+
+        Ok(())
+    })?;
+
+    if let Some(reason) = skipped {
+        log::warn!("Skipped the vello screenshots, {reason}");
+    }
+
+    Ok(())
+}
+
+/// Renders the pages with both renderers again and compares them with the goldens of
+/// their renderer in the directory, fails if any of them differs or has no golden. The summary
+/// lists how many pages of each renderer were compared, or why they were skipped.
+fn compare_screenshots(dir: &Path) -> Result<(), Box<dyn std::error::Error>> {
+    let mut failures = Vec::new();
+    let mut compared = [0; Backend::ALL.len()];
+
+    let skipped = render_pages(|name, backend, image| {
+        let path = dir.join(format!("{name}-{}.png", backend.name()));
+        let Ok(golden) = image::open(&path) else {
+            failures.push(format!("{}: no golden", path.display()));
+
+            return Ok(());
+        };
+
+        compared[backend as usize] += 1;
+
+        if let Some(failure) = golden_mismatch(&golden.to_rgba8(), backend, image) {
+            failures.push(format!("{name} ({}): {failure}", backend.name()));
+        }
+
+        Ok(())
+    })?;
+
+    for failure in &failures {
+        log::error!("{failure}");
+    }
+
+    log::info!("Compared with the goldens in {}:", dir.display());
+
+    for backend in Backend::ALL {
+        match &skipped {
+            Some(reason) if backend == Backend::Vello => {
+                log::warn!("  {}: SKIPPED, {reason}", backend.name());
+            }
+            _ => log::info!(
+                "  {}: {} pages compared",
+                backend.name(),
+                compared[backend as usize]
+            ),
+        }
+    }
+
+    if failures.is_empty() {
+        Ok(())
+    } else {
+        Err(format!("{} pages differ from the goldens", failures.len()).into())
+    }
+}
+
+/// Where the image differs from the golden of its renderer more than it's allowed to.
+/// Tiny-skia draws the same pixels everywhere, only the rounding may differ. The vello
+/// golden is from one GPU, the anti-aliased edges of the others differ by a few levels,
+/// it's compared by the averages of blocks of pixels.
+fn golden_mismatch(
+    golden: &image::RgbaImage,
+    backend: Backend,
+    image: &RgbaImage,
+) -> Option<String> {
+    if golden.dimensions() != (image.width, image.height) {
+        return Some(format!(
+            "the size is {}x{}, expected {}x{}",
+            image.width,
+            image.height,
+            golden.width(),
+            golden.height()
+        ));
+    }
+
+    let (block, tolerance) = match backend {
+        Backend::TinySkia => (1, 2),
+        Backend::Vello => (GOLDEN_BLOCK, GOLDEN_BLOCK_TOLERANCE),
+    };
+    let average = |pixel: &dyn Fn(u32, u32) -> [u8; 4], x: u32, y: u32| {
+        let mut sum = [0u32; 4];
+        let mut count = 0;
+
+        for y in y..(y + block).min(image.height) {
+            for x in x..(x + block).min(image.width) {
+                for (sum, channel) in sum.iter_mut().zip(pixel(x, y)) {
+                    *sum += channel as u32;
+                }
+                count += 1;
+            }
+        }
+
+        sum.map(|sum| sum / count)
+    };
+    let expected = |x, y| golden.get_pixel(x, y).0;
+    let rendered = |x, y| {
+        let index = ((y * image.width + x) * 4) as usize;
+
+        [
+            image.pixels[index],
+            image.pixels[index + 1],
+            image.pixels[index + 2],
+            image.pixels[index + 3],
+        ]
+    };
+
+    for y in (0..image.height).step_by(block as usize) {
+        for x in (0..image.width).step_by(block as usize) {
+            let expected = average(&expected, x, y);
+            let rendered = average(&rendered, x, y);
+
+            if expected
+                .iter()
+                .zip(rendered)
+                .any(|(expected, rendered)| expected.abs_diff(rendered) > tolerance)
+            {
+                return Some(format!("{rendered:?} at {x}, {y}, expected {expected:?}"));
+            }
+        }
+    }
+
+    None
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::new()
+        .filter(None, log::LevelFilter::Info)
+        .init();
+
+    let args: Vec<String> = std::env::args().collect();
+
+    if let Some(index) = args.iter().position(|arg| arg == "--screenshot") {
+        let Some(dir) = args.get(index + 1) else {
+            return Err("--screenshot needs a directory".into());
+        };
+
+        return render_screenshots(Path::new(dir));
+    }
+
+    if let Some(index) = args.iter().position(|arg| arg == "--compare") {
+        let dir = args.get(index + 1).map_or(GOLDENS_DIR, String::as_str);
+
+        return compare_screenshots(Path::new(dir));
+    }
+
+    tracy_client::Client::start();
+
+    log::info!("Starting app");
+    Application::run_application(GalleryApplication)?;
+
+    Ok(())
+}
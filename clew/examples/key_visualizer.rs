@@ -0,0 +1,136 @@
+use std::collections::VecDeque;
+
+use clew as ui;
+use clew::prelude::*;
+use clew_desktop::{
+    app::{Application, ApplicationDelegate},
+    window::Window,
+    window_manager::{WindowDescriptor, WindowManager},
+};
+use clew_vello::VelloRenderer;
+
+const HISTORY: usize = 12;
+
+struct KeyVisualizerApplication;
+
+impl ApplicationDelegate<()> for KeyVisualizerApplication {
+    fn on_start(
+        &mut self,
+        window_manager: &mut WindowManager<Self, ()>,
+        _: &mut ui::ShortcutsRegistry,
+    ) where
+        Self: std::marker::Sized,
+    {
+        window_manager.spawn_window(
+            MainWindow {
+                history: VecDeque::new(),
+            },
+            WindowDescriptor {
+                title: "Key visualizer".to_string(),
+                width: 800,
+                height: 600,
+                resizable: true,
+                fill_color: ui::ColorRgb::from_hex(0x121212),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn create_renderer(
+        window: std::sync::Arc<winit::window::Window>,
+    ) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+        let size = window.inner_size();
+        let redraw = window.clone();
+
+        Ok(Box::new(VelloRenderer::warming_up(
+            window,
+            size.width,
+            size.height,
+            move || redraw.request_redraw(),
+        )?))
+    }
+}
+
+pub struct MainWindow {
+    history: VecDeque<String>,
+}
+
+impl Window<KeyVisualizerApplication, ()> for MainWindow {
+    fn build(&mut self, _: &mut KeyVisualizerApplication, ctx: &mut ui::BuildContext) {
+        // Reads the keys of the whole window, not only while it's focused.
+        ui::gesture_detector().global_keys(true).build(ctx, |ctx| {
+            let keyboard = ctx.keyboard();
+
+            for event in keyboard.events() {
+                let edge = match (event.pressed, event.repeat) {
+                    (true, false) => "down",
+                    (true, true) => "repeat",
+                    (false, _) => "up",
+                };
+
+                self.history
+                    .push_front(format!("{:?} {edge} {:?}", event.key, event.modifiers));
+                self.history.truncate(HISTORY);
+            }
+
+            let down = ctx
+                .input()
+                .keys_down
+                .iter()
+                .copied()
+                .filter(|key| keyboard.is_down(*key))
+                .collect::<Vec<_>>();
+            let text = keyboard.text_this_frame().to_string();
+
+            ui::vstack()
+                .fill_max_size()
+                .padding(ui::EdgeInsets::all(24.))
+                .spacing(16.)
+                .build(ctx, |ctx| {
+                    ui::hstack().spacing(8.).build(ctx, |ctx| {
+                        if down.is_empty() {
+                            ui::text("Press any key")
+                                .color(ui::ColorRgba::from_hex(0x80FFFFFF))
+                                .build(ctx);
+                        }
+
+                        for key in &down {
+                            ui::text(&format!("{key:?}"))
+                                .id(*key as u32)
+                                .font_size(16.)
+                                .padding(ui::EdgeInsets::symmetric(10., 6.))
+                                .background(
+                                    ui::decoration()
+                                        .color(ui::ColorRgba::from_hex(0xFF3A6FF8))
+                                        .border_radius(ui::BorderRadius::all(6.))
+                                        .build(ctx),
+                                )
+                                .build(ctx);
+                        }
+                    });
+
+                    if !text.is_empty() {
+                        ui::text(&format!("Typed: {text:?}")).build(ctx);
+                    }
+
+                    for (i, line) in self.history.iter().enumerate() {
+                        ui::text(line)
+                            .id(i)
+                            .color(ui::ColorRgba::from_hex(0xB0FFFFFF))
+                            .build(ctx);
+                    }
+                });
+        });
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    env_logger::Builder::new()
+        .filter(None, log::LevelFilter::Info)
+        .init();
+
+    Application::run_application(KeyVisualizerApplication)?;
+
+    Ok(())
+}
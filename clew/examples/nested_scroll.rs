@@ -0,0 +1,191 @@
+//! Stress scene for the clipping of nested scroll areas. The clip layers and the culled
+//! commands are plotted in tracy, set `CLEW_NO_CLIP_CARRYING` to compare with a clip
+//! layer per clip.
+//!
+//! `--measure` renders the scene headlessly with the rect clips carried and with a clip
+//! layer per clip, and prints the clip layers, the culled commands and the average time
+//! to render an image of the scene of both.
+
+use std::{sync::Arc, time::Instant};
+
+use clew as ui;
+use clew::prelude::*;
+use clew::render::Renderer;
+use clew::widgets::builder::NoopEventLoopProxy;
+use clew_desktop::{
+    app::{Application, ApplicationDelegate},
+    window::Window,
+    window_manager::{WindowDescriptor, WindowManager},
+};
+use clew_vello::VelloRenderer;
+use pollster::FutureExt;
+
+const ROWS: usize = 40;
+const CARDS: usize = 24;
+const LINES: usize = 12;
+const WIDTH: u32 = 800;
+const HEIGHT: u32 = 600;
+const MEASURED_FRAMES: u32 = 50;
+
+struct DemoApplication;
+
+impl ApplicationDelegate<()> for DemoApplication {
+    fn on_start(
+        &mut self,
+        window_manager: &mut WindowManager<Self, ()>,
+        _: &mut ui::ShortcutsRegistry,
+    ) where
+        Self: std::marker::Sized,
+    {
+        window_manager.spawn_window(
+            MainWindow,
+            WindowDescriptor {
+                title: "Nested Scroll Areas".to_string(),
+                width: WIDTH,
+                height: HEIGHT,
+                resizable: true,
+                fill_color: ui::ColorRgb::from_hex(0x121212),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn create_renderer(
+        window: std::sync::Arc<winit::window::Window>,
+    ) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+        Ok(Box::new(
+            VelloRenderer::new(
+                window.clone(),
+                window.inner_size().width,
+                window.inner_size().height,
+            )
+            .block_on()?
+            .with_rect_clip_carrying(std::env::var_os("CLEW_NO_CLIP_CARRYING").is_none()),
+        ))
+    }
+}
+
+pub struct MainWindow;
+
+impl Window<DemoApplication, ()> for MainWindow {
+    fn build(&mut self, _: &mut DemoApplication, ctx: &mut ui::BuildContext) {
+        build_scene(ctx);
+    }
+}
+
+fn build_scene(ctx: &mut ui::BuildContext) {
+    ui::scroll_area().fill_max_size().build(ctx, |ctx| {
+        ui::vstack()
+            .fill_max_width()
+            .spacing(8.)
+            .padding(ui::EdgeInsets::all(8.))
+            .build(ctx, |ctx| {
+                ui::for_each(0..ROWS).build(ctx, |ctx, row| {
+                    ui::scroll_area()
+                        .scroll_direction(ui::ScrollDirection::Horizontal)
+                        .fill_max_width()
+                        .height(140.)
+                        .build(ctx, |ctx| {
+                            ui::hstack().spacing(8.).build(ctx, |ctx| {
+                                ui::for_each(0..CARDS).build(ctx, |ctx, card| {
+                                    build_card(ctx, row, card);
+                                });
+                            });
+                        });
+                });
+            });
+    });
+}
+
+fn build_card(ctx: &mut ui::BuildContext, row: usize, card: usize) {
+    ui::scroll_area()
+        .width(160.)
+        .height(140.)
+        .background(
+            ui::decoration()
+                .color(ui::ColorRgba::from_hex(0xFF2E2E2E))
+                .border_radius(ui::BorderRadius::all(8.))
+                .build(ctx),
+        )
+        .build(ctx, |ctx| {
+            ui::vstack()
+                .padding(ui::EdgeInsets::all(8.))
+                .build(ctx, |ctx| {
+                    ui::for_each(0..LINES).build(ctx, |ctx, line| {
+                        ui::text(&format!("Row {row}, card {card}, line {line}")).build(ctx);
+                    });
+                });
+        });
+}
+
+/// Renders the scene with and without carrying the rect clips, the first image warms
+/// up the caches and isn't timed.
+fn measure() -> Result<(), Box<dyn std::error::Error>> {
+    let mut host = ui::ClewHost::new(
+        ui::assets::Assets::new(),
+        ui::Resources::new(),
+        Arc::new(NoopEventLoopProxy),
+    );
+    let view = ui::View {
+        id: ui::ViewId(0),
+        size: ui::PhysicalSize::new(WIDTH, HEIGHT),
+        scale_factor: 1.,
+        safe_area: ui::EdgeInsets::ZERO,
+    };
+    let fill_color = ui::ColorRgb::from_hex(0x121212);
+
+    for carrying in [true, false] {
+        let mut renderer = VelloRenderer::headless()
+            .block_on()?
+            .with_rect_clip_carrying(carrying);
+        let mut instance = ui::ClewInstance::new(view.clone(), host.assets.create_font_resources());
+
+        instance.set_paused(true);
+        instance.frame(&mut host, &mut build_scene);
+
+        // Encodes the commands as a frame for the stats, without a surface nothing is
+        // presented.
+        instance.render(&host, &mut renderer, fill_color);
+
+        let stats = renderer.frame_stats();
+
+        instance.render_image(&host, &mut renderer, fill_color)?;
+
+        let start = Instant::now();
+
+        for _ in 0..MEASURED_FRAMES {
+            instance.render_image(&host, &mut renderer, fill_color)?;
+        }
+
+        let frame_time = start.elapsed() / MEASURED_FRAMES;
+
+        println!(
+            "{}: {} clip layers, {} culled commands, {:.2} ms per image",
+            if carrying { "carried" } else { "layers" },
+            stats.clip_layers,
+            stats.culled_commands,
+            frame_time.as_secs_f64() * 1000.,
+        );
+    }
+
+    Ok(())
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    // The renderer plots the clip stats of the measured frames too.
+    tracy_client::Client::start();
+
+    if std::env::args().any(|arg| arg == "--measure") {
+        return measure();
+    }
+
+    env_logger::Builder::new()
+        .filter(None, log::LevelFilter::Info)
+        .init();
+
+    log::info!("Starting app");
+    Application::run_application(DemoApplication)?;
+
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use clew as ui;
+use clew::prelude::*;
+use clew_desktop::{
+    app::{Application, ApplicationDelegate},
+    window::Window,
+    window_manager::{WindowDescriptor, WindowManager},
+};
+use clew_vello::VelloRenderer;
+use pollster::FutureExt;
+
+struct DemoApplication;
+
+impl ApplicationDelegate<()> for DemoApplication {
+    fn on_start(
+        &mut self,
+        window_manager: &mut WindowManager<Self, ()>,
+        _: &mut ui::ShortcutsRegistry,
+    ) where
+        Self: std::marker::Sized,
+    {
+        window_manager.spawn_window(
+            MainWindow,
+            WindowDescriptor {
+                title: "Pan Zoom".to_string(),
+                width: 800,
+                height: 600,
+                resizable: true,
+                fill_color: ui::ColorRgb::from_hex(0x121212),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn create_renderer(
+        window: std::sync::Arc<winit::window::Window>,
+    ) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+        Ok(Box::new(
+            VelloRenderer::new(
+                window.clone(),
+                window.inner_size().width,
+                window.inner_size().height,
+            )
+            .block_on()?,
+        ))
+    }
+}
+
+pub struct MainWindow;
+
+impl Window<DemoApplication, ()> for MainWindow {
+    fn build(&mut self, _: &mut DemoApplication, ctx: &mut ui::BuildContext) {
+        ui::vstack().fill_max_size().build(ctx, |ctx| {
+            let fit = clew_widgets::button("Fit to content").build(ctx).clicked();
+
+            ui::zstack().fill_max_size().build(ctx, |ctx| {
+                let response = ui::pan_zoom_area()
+                    .fill_max_size()
+                    .zoom_range(0.25, 4.)
+                    .snap_to_actual_size(true)
+                    .build(ctx, |ctx| {
+                        ui::vstack().spacing(16.).build(ctx, |ctx| {
+                            for row in 0..8 {
+                                ui::hstack().spacing(16.).build(ctx, |ctx| {
+                                    for column in 0..8 {
+                                        let response = ui::gesture_detector()
+                                            .clickable(true)
+                                            .build(ctx, |ctx| {
+                                                let response = ctx
+                                                    .of::<ui::GestureDetectorResponse>()
+                                                    .unwrap();
+
+                                                ui::text(&format!("{row}:{column}"))
+                                                    .width(96.)
+                                                    .height(64.)
+                                                    .text_align(ui::TextAlign::Center)
+                                                    .text_vertical_align(ui::AlignY::Center)
+                                                    .background(
+                                                        ui::decoration()
+                                                            .color(if response.is_hot() {
+                                                                ui::ColorRgba::from_hex(0xFF357CCE)
+                                                            } else {
+                                                                ui::ColorRgba::from_hex(0xFF2E2E2E)
+                                                            })
+                                                            .border_radius(ui::BorderRadius::all(
+                                                                8.,
+                                                            ))
+                                                            .build(ctx),
+                                                    )
+                                                    .build(ctx);
+                                            });
+
+                                        if response.clicked() {
+                                            log::info!("Clicked {row}:{column}");
+                                        }
+                                    }
+                                });
+                            }
+                        });
+                    });
+
+                if fit {
+                    ui::fit_to_content(ctx, response.id, response.content_rect());
+                }
+
+                ctx.provide(response, |ctx| {
+                    clew_widgets::pan_zoom_indicators().build(ctx);
+                });
+            });
+        });
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracy_client::Client::start();
+
+    env_logger::Builder::new()
+        .filter(None, log::LevelFilter::Info)
+        .init();
+
+    log::info!("Starting app");
+    Application::run_application(DemoApplication)?;
+
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use std::cell::Cell;
+
+use clew as ui;
+use clew::prelude::*;
+use clew_desktop::{
+    app::{Application, ApplicationDelegate},
+    window::Window,
+    window_manager::{WindowDescriptor, WindowManager},
+};
+use clew_vello::VelloRenderer;
+
+const MESSAGES: [(&str, &str); 5] = [
+    (
+        "Weekly report",
+        "The numbers for this week are in the attached sheet.",
+    ),
+    ("Lunch", "Are we still on for lunch on Friday?"),
+    (
+        "Release",
+        "The release branch is cut, please hold off on merges.",
+    ),
+    (
+        "Design review",
+        "Moved the design review to Thursday afternoon.",
+    ),
+    ("Welcome", "Welcome aboard! Your accounts are ready."),
+];
+
+struct ResponsiveApplication;
+
+impl ApplicationDelegate<()> for ResponsiveApplication {
+    fn init_resources(&mut self, resources: &mut ui::Resources) {
+        // Two panes need a bit more room than the default medium breakpoint.
+        resources.insert(ui::Breakpoints {
+            medium: 640.,
+            expanded: 960.,
+        });
+    }
+
+    fn on_start(
+        &mut self,
+        window_manager: &mut WindowManager<Self, ()>,
+        _: &mut ui::ShortcutsRegistry,
+    ) where
+        Self: std::marker::Sized,
+    {
+        window_manager.spawn_window(
+            MainWindow { selected: None },
+            WindowDescriptor {
+                title: "Responsive".to_string(),
+                width: 900,
+                height: 600,
+                resizable: true,
+                fill_color: ui::ColorRgb::from_hex(0x121212),
+                ..Default::default()
+            },
+        );
+    }
+
+    fn create_renderer(
+        window: std::sync::Arc<winit::window::Window>,
+    ) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+        let size = window.inner_size();
+        let redraw = window.clone();
+
+        Ok(Box::new(VelloRenderer::warming_up(
+            window,
+            size.width,
+            size.height,
+            move || redraw.request_redraw(),
+        )?))
+    }
+}
+
+pub struct MainWindow {
+    selected: Option<usize>,
+}
+
+fn list(ctx: &mut ui::BuildContext, selected: &Cell<Option<usize>>) {
+    ui::vstack().spacing(0.).fill_max_width().build(ctx, |ctx| {
+        for (index, (subject, _)) in MESSAGES.iter().enumerate() {
+            ui::scope(index).build(ctx, |ctx| {
+                let response = ui::gesture_detector().clickable(true).build(ctx, |ctx| {
+                    let is_selected = selected.get() == Some(index);
+
+                    ui::text(subject)
+                        .fill_max_width()
+                        .padding(ui::EdgeInsets::symmetric(12., 10.))
+                        .background(
+                            ui::decoration()
+                                .color(if is_selected {
+                                    ui::ColorRgba::from_hex(0xFF2D3F5C)
+                                } else {
+                                    ui::ColorRgba::from_hex(0x00000000)
+                                })
+                                .build(ctx),
+                        )
+                        .build(ctx);
+                });
+
+                if response.clicked() {
+                    selected.set(Some(index));
+                }
+            });
+        }
+    });
+}
+
+fn detail(ctx: &mut ui::BuildContext, index: usize) {
+    let (subject, body) = MESSAGES[index];
+
+    ui::vstack()
+        .spacing(12.)
+        .padding(ui::EdgeInsets::all(16.))
+        .build(ctx, |ctx| {
+            ui::text(subject).build(ctx);
+            ui::text(body).build(ctx);
+        });
+}
+
+impl Window<ResponsiveApplication, ()> for MainWindow {
+    fn build(&mut self, _: &mut ResponsiveApplication, ctx: &mut ui::BuildContext) {
+        // Both layouts change the selection, the closures can't borrow the window
+        // mutably at the same time.
+        let selected = Cell::new(self.selected);
+
+        ui::responsive()
+            // Master and detail side by side.
+            .medium(|ctx| {
+                ui::hstack().spacing(0.).fill_max_size().build(ctx, |ctx| {
+                    ui::vstack()
+                        .width(260.)
+                        .fill_max_height()
+                        .build(ctx, |ctx| {
+                            list(ctx, &selected);
+                        });
+
+                    match selected.get() {
+                        Some(index) => detail(ctx, index),
+                        None => {
+                            ui::text("Select a message").build(ctx);
+                        }
+                    }
+                });
+            })
+            // A navigation stack, the detail replaces the list and goes back to it.
+            .compact(|ctx| {
+                ui::vstack()
+                    .fill_max_size()
+                    .build(ctx, |ctx| match selected.get() {
+                        Some(index) => {
+                            if clew_widgets::button("Back").build(ctx).clicked() {
+                                selected.set(None);
+                            }
+
+                            detail(ctx, index);
+                        }
+                        None => list(ctx, &selected),
+                    });
+            })
+            .build(ctx);
+
+        self.selected = selected.get();
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracy_client::Client::start();
+
+    env_logger::Builder::new()
+        .filter(None, log::LevelFilter::Info)
+        .init();
+
+    log::info!("Starting app");
+    Application::run_application(ResponsiveApplication)?;
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+//! Micro-benchmark of the lookups of the retained widget states. Builds frames of 10k
+//! widgets with states, a gesture detector with a stateful widget and a decorated box
+//! in each and a text field in every tenth, without a window or a renderer, and prints
+//! the average time of the build phase and of the whole frame. Run it in release:
+//!
+//! ```text
+//! cargo run --release --example state_lookups
+//! ```
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use clew as ui;
+use clew::prelude::*;
+use clew::stateful::StatefulWidget;
+use clew::widgets::builder::NoopEventLoopProxy;
+use clew_derive::WidgetState;
+
+const WIDGETS: usize = 10_000;
+const FIELD_EVERY: usize = 10;
+const WARMUP_FRAMES: usize = 10;
+const FRAMES: usize = 100;
+
+#[derive(WidgetState, Default)]
+struct Cell {
+    builds: u32,
+}
+
+impl StatefulWidget for Cell {
+    type Event = ();
+
+    fn build(&mut self, ctx: &mut ui::BuildContext, mut frame: ui::FrameBuilder) {
+        self.builds += 1;
+
+        frame.build(ctx, |ctx| {
+            ui::decorated_box()
+                .color(ui::ColorRgba::from_hex(0xFF357CCE))
+                .width(4.)
+                .height(4.)
+                .build(ctx);
+        });
+    }
+}
+
+fn build(ctx: &mut ui::BuildContext, fields: &mut [ui::TextData]) {
+    ui::vstack().spacing(0.).build(ctx, |ctx| {
+        for index in 0..WIDGETS {
+            ui::scope(index).build(ctx, |ctx| {
+                ui::gesture_detector().clickable(true).build(ctx, |ctx| {
+                    ui::stateful::<Cell>().build(ctx);
+                });
+
+                if index % FIELD_EVERY == 0 {
+                    ui::editable_text(&mut fields[index / FIELD_EVERY]).build(ctx);
+                }
+            });
+        }
+    });
+}
+
+fn main() {
+    let mut host = ui::ClewHost::new(
+        ui::assets::Assets::new(),
+        ui::Resources::new(),
+        Arc::new(NoopEventLoopProxy),
+    );
+    let mut instance = ui::ClewInstance::new(
+        ui::View {
+            id: ui::ViewId(0),
+            size: ui::PhysicalSize::new(800, 600),
+            scale_factor: 1.,
+            safe_area: ui::EdgeInsets::ZERO,
+        },
+        ui::text::FontResources::new(),
+    );
+
+    let mut fields = (0..WIDGETS / FIELD_EVERY)
+        .map(|index| ui::TextData::from(&format!("Field {index}")))
+        .collect::<Vec<_>>();
+    let mut build_time = Duration::ZERO;
+    let mut frame_time = Duration::ZERO;
+
+    for frame in 0..WARMUP_FRAMES + FRAMES {
+        let started = Instant::now();
+        let mut built = Duration::ZERO;
+
+        instance.frame(&mut host, &mut |ctx| {
+            let started = Instant::now();
+            build(ctx, &mut fields);
+            built = started.elapsed();
+        });
+
+        if frame >= WARMUP_FRAMES {
+            build_time += built;
+            frame_time += started.elapsed();
+        }
+    }
+
+    println!(
+        "{WIDGETS} widgets, {FRAMES} frames: build {:.3} ms, frame {:.3} ms",
+        build_time.as_secs_f64() * 1000. / FRAMES as f64,
+        frame_time.as_secs_f64() * 1000. / FRAMES as f64,
+    );
+}
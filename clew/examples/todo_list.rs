@@ -160,6 +160,8 @@ impl ApplicationDelegate<()> for TodoApplication {
                 ui::keyboard::KeyModifiers::super_key(),
             );
 
+        ui::add_edit_command_shortcuts(shortcuts_registry);
+
         window_manager.spawn_window(
             MainWindow {
                 task_name: ui::TextData::from("Test"),
@@ -170,19 +172,22 @@ impl ApplicationDelegate<()> for TodoApplication {
                 height: 600,
                 resizable: true,
                 fill_color: ui::ColorRgb::from_hex(0x121212),
+                ..Default::default()
             },
         );
     }
 
-    fn create_renderer(window: std::sync::Arc<winit::window::Window>) -> Box<dyn ui::Renderer> {
-        Box::new(
+    fn create_renderer(
+        window: std::sync::Arc<winit::window::Window>,
+    ) -> Result<Box<dyn ui::Renderer>, ui::ClewError> {
+        Ok(Box::new(
             VelloRenderer::new(
                 window.clone(),
                 window.inner_size().width,
                 window.inner_size().height,
             )
-            .block_on(),
-        )
+            .block_on()?,
+        ))
     }
 }
 
@@ -0,0 +1,112 @@
+//! Scripted fling over a virtual list of 50k items, without a window or a renderer.
+//! Every item costs some time the first time it's built, like a row that shapes its
+//! text or decodes an image, and the frames have a build budget. Prints how many frames
+//! had items drawn as placeholders, i.e. blank items, and how many items were, without
+//! the prefetch and with it. Run it in release:
+//!
+//! ```text
+//! cargo run --release --example virtual_list_fling
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::HashSet,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use clew as ui;
+use clew::prelude::*;
+use clew::widgets::builder::NoopEventLoopProxy;
+
+const ITEMS: u64 = 50_000;
+const ITEM_SIZE: f32 = 20.;
+const FRAMES: usize = 240;
+const FRAME_BUDGET: Duration = Duration::from_millis(4);
+const COLD_BUILD: Duration = Duration::from_micros(400);
+/// Scroll speed at the start of the fling, in pixels a frame.
+const FLING_SPEED: f64 = 400.;
+const FLING_DECAY: f64 = 0.98;
+
+#[derive(Default)]
+struct Report {
+    frames_with_placeholders: usize,
+    placeholder_items: u32,
+}
+
+fn fling(max_prefetch: u64) -> Report {
+    let mut host = ui::ClewHost::new(
+        ui::assets::Assets::new(),
+        ui::Resources::new(),
+        Arc::new(NoopEventLoopProxy),
+    );
+    let mut instance = ui::ClewInstance::new(
+        ui::View {
+            id: ui::ViewId(0),
+            size: ui::PhysicalSize::new(200, 200),
+            scale_factor: 1.,
+            safe_area: ui::EdgeInsets::ZERO,
+        },
+        ui::text::FontResources::new(),
+    );
+    instance.set_frame_budget(Some(FRAME_BUDGET));
+
+    let built = RefCell::new(HashSet::new());
+    let mut report = Report::default();
+    let mut list_id = None;
+    let mut offset = 0.;
+    let mut speed = FLING_SPEED;
+
+    for _ in 0..FRAMES {
+        let stats = instance
+            .frame(&mut host, &mut |ctx| {
+                if let Some(id) = list_id {
+                    ui::set_scroll_offset_y(ctx, id, offset);
+                }
+
+                let response = ui::virtual_list()
+                    .fill_max_size()
+                    .item_size(ITEM_SIZE)
+                    .items_count(ITEMS)
+                    .max_prefetch(max_prefetch)
+                    .placeholder(|_, _| {})
+                    .build(ctx, |ctx, i| {
+                        if built.borrow_mut().insert(i) {
+                            let started = Instant::now();
+                            while started.elapsed() < COLD_BUILD {}
+                        }
+
+                        ui::decorated_box()
+                            .color(ui::ColorRgba::from_hex(0xFF357CCE))
+                            .fill_max_width()
+                            .height(ITEM_SIZE)
+                            .build(ctx);
+                    });
+                list_id = Some(response.id);
+            })
+            .map(|render_state| render_state.stats())
+            .unwrap_or_default();
+
+        if stats.placeholder_items > 0 {
+            report.frames_with_placeholders += 1;
+        }
+
+        report.placeholder_items += stats.placeholder_items;
+        offset += speed;
+        speed *= FLING_DECAY;
+    }
+
+    report
+}
+
+fn main() {
+    for max_prefetch in [0, 8] {
+        let report = fling(max_prefetch);
+
+        println!(
+            "{ITEMS} items, {FRAMES} frames, prefetch {max_prefetch}: \
+             {} frames with placeholders, {} placeholder items",
+            report.frames_with_placeholders, report.placeholder_items,
+        );
+    }
+}
@@ -1,6 +1,9 @@
 use std::time::Duration;
 
-use crate::{ColorOkLab, ColorRgb, ColorRgba, EdgeInsets, Value};
+use crate::{
+    Border, BorderRadius, BorderSide, BoxShadow, ColorOkLab, ColorRgb, ColorRgba, ColorStop,
+    EdgeInsets, Gradient, LinearGradient, RadialGradient, SweepGradient, Value,
+};
 
 #[derive(Debug, Clone)]
 pub struct Tween<V> {
@@ -35,6 +38,9 @@ pub enum AnimationStatus {
 }
 
 pub trait Animation {
+    /// Advances the animation by the time in seconds. Stepped by an infinite time, e.g.
+    /// under reduced motion (see [`crate::BuildContext::animation_delta_time`]), it
+    /// jumps to its end, repeats included, and ends in the same step.
     fn step(&mut self, delta_time: f32);
 
     fn in_progress(&self) -> bool;
@@ -116,6 +122,152 @@ impl Lerp for ColorRgba {
     }
 }
 
+impl Lerp for (f32, f32) {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        (f32::lerp(self.0, to.0, t), f32::lerp(self.1, to.1, t))
+    }
+}
+
+impl<V: Lerp> Lerp for Option<V> {
+    /// Missing values don't interpolate, switches to `to` once the animation ends.
+    fn lerp(self, to: Self, t: f32) -> Self {
+        match (self, to) {
+            (Some(from), Some(to)) => Some(from.lerp(to, t)),
+            (from, to) => {
+                if t < 1. {
+                    from
+                } else {
+                    to
+                }
+            }
+        }
+    }
+}
+
+impl Lerp for BorderRadius {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        BorderRadius {
+            top_left: f32::lerp(self.top_left, to.top_left, t),
+            top_right: f32::lerp(self.top_right, to.top_right, t),
+            bottom_left: f32::lerp(self.bottom_left, to.bottom_left, t),
+            bottom_right: f32::lerp(self.bottom_right, to.bottom_right, t),
+        }
+    }
+}
+
+impl Lerp for BorderSide {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        BorderSide {
+            width: f32::lerp(self.width, to.width, t),
+            color: self.color.lerp(to.color, t),
+        }
+    }
+}
+
+impl Lerp for BoxShadow {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        BoxShadow {
+            offset: self.offset + (to.offset - self.offset) * t,
+            blur_radius: f32::lerp(self.blur_radius, to.blur_radius, t),
+            spread: f32::lerp(self.spread, to.spread, t),
+            color: self.color.lerp(to.color, t),
+        }
+    }
+}
+
+impl Lerp for Border {
+    /// Missing sides fade in or out from a transparent side of zero width.
+    fn lerp(self, to: Self, t: f32) -> Self {
+        fn side(from: Option<BorderSide>, to: Option<BorderSide>, t: f32) -> Option<BorderSide> {
+            let hidden = |side: BorderSide| BorderSide::new(0., side.color.with_opacity(0.));
+
+            match (from, to) {
+                (Some(from), Some(to)) => Some(from.lerp(to, t)),
+                (Some(from), None) if t < 1. => Some(from.lerp(hidden(from), t)),
+                (None, Some(to)) => Some(hidden(to).lerp(to, t)),
+                _ => None,
+            }
+        }
+
+        Border {
+            top: side(self.top, to.top, t),
+            right: side(self.right, to.right, t),
+            bottom: side(self.bottom, to.bottom, t),
+            left: side(self.left, to.left, t),
+        }
+    }
+}
+
+impl Lerp for ColorStop {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        ColorStop {
+            offset: f32::lerp(self.offset, to.offset, t),
+            color: self.color.lerp(to.color, t),
+        }
+    }
+}
+
+fn lerp_stops<S>(from: &S, to: &S, t: f32) -> Option<S>
+where
+    S: AsRef<[ColorStop]> + FromIterator<ColorStop>,
+{
+    let (from, to) = (from.as_ref(), to.as_ref());
+
+    (from.len() == to.len()).then(|| {
+        from.iter()
+            .zip(to)
+            .map(|(from, to)| from.lerp(*to, t))
+            .collect()
+    })
+}
+
+impl Lerp for Gradient {
+    /// Interpolates stop by stop, gradients of different kinds or with a different
+    /// number of stops switch to `to` right away.
+    fn lerp(self, to: Self, t: f32) -> Self {
+        match (&self, &to) {
+            (Gradient::Linear(from), Gradient::Linear(target)) => {
+                match lerp_stops(&from.stops, &target.stops, t) {
+                    Some(stops) => Gradient::Linear(LinearGradient {
+                        start: from.start.lerp(target.start, t),
+                        end: from.end.lerp(target.end, t),
+                        stops,
+                        tile_mode: target.tile_mode,
+                    }),
+                    None => to,
+                }
+            }
+            (Gradient::Radial(from), Gradient::Radial(target)) => {
+                match lerp_stops(&from.stops, &target.stops, t) {
+                    Some(stops) => Gradient::Radial(RadialGradient {
+                        center: from.center.lerp(target.center, t),
+                        radius_x: f32::lerp(from.radius_x, target.radius_x, t),
+                        radius_y: f32::lerp(from.radius_y, target.radius_y, t),
+                        focal: from.focal.lerp(target.focal, t),
+                        focal_radius: from.focal_radius.lerp(target.focal_radius, t),
+                        stops,
+                        tile_mode: target.tile_mode,
+                    }),
+                    None => to,
+                }
+            }
+            (Gradient::Sweep(from), Gradient::Sweep(target)) => {
+                match lerp_stops(&from.stops, &target.stops, t) {
+                    Some(stops) => Gradient::Sweep(SweepGradient {
+                        center: from.center.lerp(target.center, t),
+                        start_angle: f32::lerp(from.start_angle, target.start_angle, t),
+                        end_angle: f32::lerp(from.end_angle, target.end_angle, t),
+                        stops,
+                        tile_mode: target.tile_mode,
+                    }),
+                    None => to,
+                }
+            }
+            _ => to,
+        }
+    }
+}
+
 impl<V> Default for Tween<V>
 where
     V: Default,
@@ -276,7 +428,7 @@ where
 
         self.cycles_done += 1;
 
-        if !self.should_continue() {
+        if !self.should_continue() || delta_time.is_infinite() {
             self.status = AnimationStatus::Ended;
             return;
         }
@@ -402,7 +554,7 @@ where
 
         let distance = self.current_value.difference(&self.target_value);
 
-        if distance < self.threshold {
+        if distance < self.threshold || delta_time.is_infinite() {
             self.current_value = self.target_value.clone();
             self.status = AnimationStatus::Ended;
         } else {
@@ -1080,6 +1232,10 @@ where
             self.current_value = self.frames.last().unwrap().value.clone();
             self.on_cycle_end();
 
+            if delta_time.is_infinite() {
+                self.status = AnimationStatus::Ended;
+            }
+
             return;
         }
 
@@ -1091,7 +1247,9 @@ where
             self.current_value = self.eval(self.elapsed);
             self.on_cycle_end();
 
-            if self.status != AnimationStatus::Ended {
+            if delta_time.is_infinite() {
+                self.status = AnimationStatus::Ended;
+            } else if self.status != AnimationStatus::Ended {
                 self.current_value = self.eval(self.elapsed);
             }
         } else {
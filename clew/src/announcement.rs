@@ -0,0 +1,86 @@
+//! Messages for the screen readers about the changes the user didn't cause by moving
+//! the focus, e.g. a toast showing up or a validation error.
+//!
+//! Widgets queue them with [`crate::BuildContext::announce`], the texts of a
+//! [`crate::widgets::live_region`] are queued when they change and the values of the
+//! focused widgets once they stop changing, see [`crate::BuildContext::announce_value`].
+//! The shell takes them after the frame with [`crate::ClewInstance::take_announcements`]
+//! to hand them to the accessibility adapter of the platform. The ones the shell
+//! doesn't take, e.g. when it has no adapter, are dropped when the next frame begins, so
+//! the callers don't need to know whether anything reads them.
+
+use std::time::{Duration, Instant};
+
+use crate::WidgetId;
+
+/// How long the value of the focused widget has to stay the same to be announced, so
+/// typing doesn't read out every keystroke.
+pub const VALUE_ANNOUNCEMENT_DELAY: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Politeness {
+    /// Read once the screen reader is done with what it's reading.
+    #[default]
+    Polite,
+    /// Interrupts what the screen reader is reading, for the urgent messages like the
+    /// errors.
+    Assertive,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Announcement {
+    pub text: String,
+    pub politeness: Politeness,
+}
+
+/// Announcements of a view, those of the last frame and those still to come.
+#[derive(Default)]
+pub(crate) struct Announcements {
+    /// Queued in the last frame for the shell.
+    pub(crate) queued: Vec<Announcement>,
+    /// Last value of the focused widget, announced once it's due.
+    pending_value: Option<PendingValue>,
+    /// Texts built so far in the innermost live region being built.
+    pub(crate) live_texts: Option<Vec<String>>,
+}
+
+struct PendingValue {
+    id: WidgetId,
+    text: String,
+    due: Instant,
+}
+
+impl Announcements {
+    pub(crate) fn queue(&mut self, text: String, politeness: Politeness) {
+        self.queued.push(Announcement { text, politeness });
+    }
+
+    /// Replaces the pending value, it's due [`VALUE_ANNOUNCEMENT_DELAY`] from now.
+    pub(crate) fn queue_value(&mut self, id: WidgetId, text: String) {
+        self.pending_value = Some(PendingValue {
+            id,
+            text,
+            due: Instant::now() + VALUE_ANNOUNCEMENT_DELAY,
+        });
+    }
+
+    /// When the pending value is due, the view has to be built by then.
+    pub(crate) fn value_deadline(&self) -> Option<Instant> {
+        self.pending_value.as_ref().map(|value| value.due)
+    }
+
+    /// Queues the pending value once it's due, it's dropped if its widget has lost the
+    /// focus meanwhile.
+    pub(crate) fn flush_value(&mut self, focused: Option<WidgetId>, now: Instant) {
+        let Some(value) = self
+            .pending_value
+            .take_if(|value| focused != Some(value.id) || value.due <= now)
+        else {
+            return;
+        };
+
+        if focused == Some(value.id) {
+            self.queue(value.text, Politeness::Polite);
+        }
+    }
+}
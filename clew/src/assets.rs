@@ -1,18 +1,133 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    fmt::Display,
+    num::NonZeroUsize,
+    sync::{Arc, Mutex, Weak, mpsc},
+    thread,
+    time::Instant,
+};
 
-use crate::text::FontResources;
+use rustc_hash::{FxHashMap, FxHashSet};
+
+use crate::{
+    ClewError, ColorRgba, Vec2,
+    text::{FontResources, SystemFonts},
+};
+
+/// Size used to lay out an SVG that hasn't been registered.
+pub const MISSING_SVG_SIZE: Vec2 = Vec2 { x: 16., y: 16. };
+
+/// Renderers fill the boundary of a missing asset with this color.
+pub const MISSING_ASSET_COLOR: ColorRgba = ColorRgba {
+    r: 1.,
+    g: 0.,
+    b: 1.,
+    a: 0.5,
+};
+
+type ParsedSvg = (&'static str, Result<usvg::Tree, ClewError>);
+
+type DecodedBitmap = (Arc<str>, Result<Bitmap, ClewError>);
+
+type LoadedCallback = Arc<Mutex<Option<Box<dyn Fn() + Send>>>>;
+
+/// Decoded raster image.
+#[derive(Clone)]
+pub struct Bitmap {
+    width: u32,
+    height: u32,
+    pixels: Arc<[u8]>,
+}
+
+impl Bitmap {
+    /// Decodes PNG or JPEG data.
+    pub fn decode(key: &str, data: &[u8]) -> Result<Self, ClewError> {
+        let started = Instant::now();
+        let image = image::load_from_memory(data)
+            .map_err(|err| ClewError::InvalidImage {
+                key: key.to_string(),
+                message: err.to_string(),
+            })?
+            .into_rgba8();
+
+        log::trace!("Decoded image \"{key}\" in {:?}", started.elapsed());
+
+        Ok(Self {
+            width: image.width(),
+            height: image.height(),
+            pixels: image.into_raw().into(),
+        })
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// RGBA with 8 bits per channel and straight alpha, row by row from the top.
+    pub fn pixels(&self) -> &Arc<[u8]> {
+        &self.pixels
+    }
+}
+
+/// Load of a bitmap in flight, shared by everyone waiting for it. The load is canceled
+/// once the last of them drops it, see [`Assets::load_bitmap_async`].
+pub struct BitmapLoad {
+    task: tokio::task::AbortHandle,
+}
+
+impl Drop for BitmapLoad {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
 
-#[derive(Default)]
 pub struct Assets<'a> {
     fonts: HashMap<&'static str, &'a [u8]>,
+    system_fonts: SystemFonts,
     svg: HashMap<&'static str, usvg::Tree>,
+    /// SVGs that are still parsed by the loading threads.
+    pending_svg: FxHashSet<&'static str>,
+    parsed_svg_tx: mpsc::Sender<ParsedSvg>,
+    parsed_svg_rx: Mutex<mpsc::Receiver<ParsedSvg>>,
+    bitmaps: FxHashMap<Arc<str>, Bitmap>,
+    /// Errors of the last loads of the bitmaps that have failed.
+    failed_bitmaps: FxHashMap<Arc<str>, ClewError>,
+    bitmap_loads: FxHashMap<Arc<str>, Weak<BitmapLoad>>,
+    decoded_bitmap_tx: mpsc::Sender<DecodedBitmap>,
+    decoded_bitmap_rx: Mutex<mpsc::Receiver<DecodedBitmap>>,
+    on_loaded: LoadedCallback,
+    reported_missing: Mutex<FxHashSet<String>>,
+}
+
+impl Default for Assets<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<'a> Assets<'a> {
     pub fn new() -> Self {
+        let (parsed_svg_tx, parsed_svg_rx) = mpsc::channel();
+        let (decoded_bitmap_tx, decoded_bitmap_rx) = mpsc::channel();
+
         Self {
             fonts: HashMap::new(),
+            system_fonts: SystemFonts::default(),
             svg: HashMap::new(),
+            pending_svg: FxHashSet::default(),
+            parsed_svg_tx,
+            parsed_svg_rx: Mutex::new(parsed_svg_rx),
+            bitmaps: FxHashMap::default(),
+            failed_bitmaps: FxHashMap::default(),
+            bitmap_loads: FxHashMap::default(),
+            decoded_bitmap_tx,
+            decoded_bitmap_rx: Mutex::new(decoded_bitmap_rx),
+            on_loaded: Arc::new(Mutex::new(None)),
+            reported_missing: Mutex::new(FxHashSet::default()),
         }
     }
 
@@ -20,25 +135,317 @@ impl<'a> Assets<'a> {
         self.fonts.insert(name, data);
     }
 
-    pub fn load_svg(&mut self, name: &'static str, data: &[u8]) {
-        let opt = usvg::Options::default();
-        let rtree = usvg::Tree::from_data(data, &opt).expect("Invalid SVG");
+    /// When the fonts installed in the system are loaded into the font resources created
+    /// from the assets, [`SystemFonts::Eager`] by default.
+    pub fn set_system_fonts(&mut self, system_fonts: SystemFonts) {
+        self.system_fonts = system_fonts;
+    }
+
+    pub fn system_fonts(&self) -> SystemFonts {
+        self.system_fonts
+    }
 
+    pub fn load_svg(&mut self, name: &'static str, data: &[u8]) -> Result<(), ClewError> {
+        let rtree = parse_svg(name, data)?;
+
+        self.pending_svg.remove(name);
         self.svg.insert(name, rtree);
+
+        Ok(())
+    }
+
+    /// Parses the SVGs on worker threads and returns right away. Until an SVG is
+    /// received with [`Assets::receive_loaded`] it's laid out with [`MISSING_SVG_SIZE`]
+    /// unless the widget has its own size, and renderers leave its boundary empty. SVGs
+    /// that fail to parse are logged and then drawn as missing.
+    pub fn load_svg_batch(
+        &mut self,
+        items: impl IntoIterator<Item = (&'static str, &'static [u8])>,
+    ) {
+        let items: Vec<_> = items.into_iter().collect();
+
+        if items.is_empty() {
+            return;
+        }
+
+        self.pending_svg.extend(items.iter().map(|(name, _)| *name));
+
+        let workers = thread::available_parallelism()
+            .map_or(1, NonZeroUsize::get)
+            .min(items.len());
+
+        for chunk in items.chunks(items.len().div_ceil(workers)) {
+            let chunk = chunk.to_vec();
+            let parsed_svg_tx = self.parsed_svg_tx.clone();
+            let on_loaded = self.on_loaded.clone();
+
+            thread::Builder::new()
+                .name("clew-svg-loader".to_string())
+                .spawn(move || {
+                    for (name, data) in chunk {
+                        if parsed_svg_tx.send((name, parse_svg(name, data))).is_err() {
+                            return;
+                        }
+
+                        notify_loaded(&on_loaded);
+                    }
+                })
+                .expect("Failed to spawn the SVG loading thread");
+        }
+    }
+
+    /// Decodes the bitmap and caches it under the key, the previous one is replaced.
+    pub fn load_bitmap(&mut self, key: &str, data: &[u8]) -> Result<(), ClewError> {
+        let bitmap = Bitmap::decode(key, data)?;
+        let key: Arc<str> = key.into();
+
+        self.failed_bitmaps.remove(&key);
+        self.bitmaps.insert(key, bitmap);
+
+        Ok(())
+    }
+
+    /// Fetches the data of the bitmap with the future returned by the source and decodes
+    /// it on a blocking thread of the tokio runtime. The bitmap is cached under the key
+    /// once received with [`Assets::receive_loaded`], or its error is kept until
+    /// [`Assets::forget_bitmap_error`].
+    ///
+    /// The load of the key that is still in flight is joined rather than started again,
+    /// the source isn't called then.
+    pub(crate) fn load_bitmap_async<F, E>(
+        &mut self,
+        key: &str,
+        source: impl FnOnce() -> F,
+    ) -> Arc<BitmapLoad>
+    where
+        F: Future<Output = Result<Vec<u8>, E>> + Send + 'static,
+        E: Display,
+    {
+        if let Some(load) = self.bitmap_loads.get(key).and_then(Weak::upgrade) {
+            return load;
+        }
+
+        self.bitmap_loads.retain(|_, load| load.strong_count() > 0);
+
+        let key: Arc<str> = key.into();
+        let data = source();
+        let decoded_bitmap_tx = self.decoded_bitmap_tx.clone();
+        let on_loaded = self.on_loaded.clone();
+        let task_key = key.clone();
+
+        let task = tokio::spawn(async move {
+            let key = task_key;
+            let result = match data.await.map_err(|err| err.to_string()) {
+                Ok(data) => {
+                    let decode_key = key.clone();
+
+                    tokio::task::spawn_blocking(move || Bitmap::decode(&decode_key, &data))
+                        .await
+                        .unwrap_or_else(|err| {
+                            Err(ClewError::InvalidImage {
+                                key: key.to_string(),
+                                message: err.to_string(),
+                            })
+                        })
+                }
+                Err(message) => Err(ClewError::ImageSource {
+                    key: key.to_string(),
+                    message,
+                }),
+            };
+
+            if decoded_bitmap_tx.send((key, result)).is_ok() {
+                notify_loaded(&on_loaded);
+            }
+        });
+
+        let load = Arc::new(BitmapLoad {
+            task: task.abort_handle(),
+        });
+        self.bitmap_loads.insert(key, Arc::downgrade(&load));
+
+        load
+    }
+
+    pub fn get_bitmap(&self, key: &str) -> Option<&Bitmap> {
+        self.bitmaps.get(key)
+    }
+
+    /// Error of the last load of the bitmap if it has failed.
+    pub fn bitmap_error(&self, key: &str) -> Option<&ClewError> {
+        self.failed_bitmaps.get(key)
+    }
+
+    /// Lets the bitmap that has failed to load be loaded again.
+    pub fn forget_bitmap_error(&mut self, key: &str) {
+        self.failed_bitmaps.remove(key);
+    }
+
+    /// Drops the bitmap from the cache, the next request loads it again.
+    pub fn remove_bitmap(&mut self, key: &str) {
+        self.bitmaps.remove(key);
+    }
+
+    /// Called from the loading threads every time an asset is loaded, e.g. to wake up
+    /// the event loop so the next frame calls [`Assets::receive_loaded`].
+    pub fn on_loaded(&mut self, callback: impl Fn() + Send + 'static) {
+        *self.on_loaded.lock().unwrap_or_else(|err| err.into_inner()) = Some(Box::new(callback));
+    }
+
+    /// Takes the SVGs and the bitmaps loaded so far, returns whether there were any, then
+    /// the frame has to be redrawn.
+    pub fn receive_loaded(&mut self) -> bool {
+        let parsed_svg_rx = self
+            .parsed_svg_rx
+            .get_mut()
+            .unwrap_or_else(|err| err.into_inner());
+        let mut received = false;
+
+        for (name, result) in parsed_svg_rx.try_iter() {
+            received = true;
+            self.pending_svg.remove(name);
+
+            match result {
+                Ok(tree) => {
+                    self.svg.insert(name, tree);
+                }
+                Err(err) => log::error!("{err}"),
+            }
+        }
+
+        let decoded_bitmap_rx = self
+            .decoded_bitmap_rx
+            .get_mut()
+            .unwrap_or_else(|err| err.into_inner());
+
+        for (key, result) in decoded_bitmap_rx.try_iter() {
+            received = true;
+            self.bitmap_loads.remove(&key);
+
+            match result {
+                Ok(bitmap) => {
+                    self.failed_bitmaps.remove(&key);
+                    self.bitmaps.insert(key, bitmap);
+                }
+                Err(err) => {
+                    log::error!("{err}");
+                    self.failed_bitmaps.insert(key, err);
+                }
+            }
+        }
+
+        received
+    }
+
+    /// Whether the SVG is still parsed by the loading threads.
+    pub fn is_svg_pending(&self, name: &str) -> bool {
+        self.pending_svg.contains(name)
     }
 
     pub fn get_svg_tree(&self, name: &str) -> Option<&usvg::Tree> {
         self.svg.get(name)
     }
 
+    /// Same as [`Assets::get_svg_tree`], but warns the first time an unknown id is requested,
+    /// callers are expected to draw a placeholder instead.
+    pub fn find_svg_tree(&self, name: &str) -> Option<&usvg::Tree> {
+        let tree = self.svg.get(name);
+
+        if tree.is_none() && !self.is_svg_pending(name) {
+            self.report_missing(name);
+        }
+
+        tree
+    }
+
+    fn report_missing(&self, name: &str) {
+        let mut reported = self
+            .reported_missing
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+
+        if reported.insert(name.to_string()) {
+            log::warn!(
+                "{}",
+                ClewError::AssetNotFound {
+                    id: name.to_string()
+                }
+            );
+        }
+    }
+
+    /// Fonts registered with [`Assets::load_font`], by name.
+    pub fn fonts(&self) -> impl Iterator<Item = (&'static str, &'a [u8])> + '_ {
+        self.fonts.iter().map(|(name, data)| (*name, *data))
+    }
+
     pub fn create_font_resources(&self) -> FontResources {
-        let mut fonts = FontResources::new();
+        FontResources::with_fonts(self.system_fonts, self.fonts())
+    }
+}
+
+fn notify_loaded(on_loaded: &LoadedCallback) {
+    let on_loaded = on_loaded.lock().unwrap_or_else(|err| err.into_inner());
+
+    if let Some(callback) = on_loaded.as_ref() {
+        callback();
+    }
+}
+
+fn parse_svg(name: &'static str, data: &[u8]) -> Result<usvg::Tree, ClewError> {
+    let started = Instant::now();
+    let opt = usvg::Options::default();
+    let rtree = usvg::Tree::from_data(data, &opt).map_err(|err| ClewError::InvalidSvg {
+        name,
+        message: err.to_string(),
+    })?;
+
+    log::trace!("Parsed SVG \"{name}\" in {:?}", started.elapsed());
+
+    Ok(rtree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_invalid_svg_returns_error() {
+        let mut assets = Assets::new();
+        let result = assets.load_svg("broken", b"not an svg");
+
+        assert!(matches!(
+            result,
+            Err(ClewError::InvalidSvg { name: "broken", .. })
+        ));
+        assert!(assets.find_svg_tree("broken").is_none());
+    }
+
+    #[test]
+    fn test_svg_batch_is_pending_until_received() {
+        const ICON: &[u8] = br#"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="12"/>"#;
+
+        let mut assets = Assets::new();
+        let (loaded_tx, loaded_rx) = mpsc::channel();
+        assets.on_loaded(move || {
+            let _ = loaded_tx.send(());
+        });
+        assets.load_svg_batch([("icon", ICON), ("broken", b"not an svg".as_slice())]);
+
+        assert!(assets.is_svg_pending("icon"));
+        assert!(assets.find_svg_tree("icon").is_none());
 
-        for (name, data) in self.fonts.iter() {
-            log::debug!("Load font: {name}");
-            fonts.load_font(name, data);
+        for _ in 0..2 {
+            loaded_rx.recv().unwrap();
         }
 
-        fonts
+        assert!(assets.receive_loaded());
+        assert!(!assets.is_svg_pending("icon"));
+        assert!(!assets.is_svg_pending("broken"));
+        assert_eq!(
+            assets.find_svg_tree("icon").map(|tree| tree.size().width()),
+            Some(24.)
+        );
+        assert!(assets.find_svg_tree("broken").is_none());
     }
 }
@@ -0,0 +1,43 @@
+//! Everything a widget crate needs beyond composing the widgets of clew, in one place.
+//!
+//! A widget is built by pushing layout commands to the [`BuildContext`]:
+//!
+//! - [`BuildContext::push_leaf`] pushes a widget without children with the size,
+//!   padding, decorations, etc. of a [`FrameBuilder`], and
+//!   [`BuildContext::push_container`] pushes a container of a [`ContainerKind`] around
+//!   the widgets built by a callback. [`BuildContext::push_layout_command`] takes any
+//!   [`LayoutCommand`] for the rest.
+//! - [`BuildContext::state`] keeps a state of the widget between the frames, a leaf of
+//!   the type of the state is drawn by it with [`WidgetState::render`].
+//! - [`GestureDetectorBuilder::register`] detects the clicks, the hover, the drags and
+//!   the focus on the next widget pushed.
+//!
+//! [`mod@crate::widgets::svg`] is built with these only.
+//!
+//! # Stability
+//!
+//! The builders, the methods of [`BuildContext`] listed above, [`WidgetState`] and the
+//! responses follow semver: while clew is below 1.0 a breaking change to them only
+//! comes with a bump of the minor version. Widgets built with
+//! [`BuildContext::push_leaf`] and [`BuildContext::push_container`] keep compiling.
+//!
+//! [`LayoutCommand`], [`RenderCommand`] and [`WidgetPlacement`] are exported for what
+//! the methods above don't cover, and aren't a part of that promise: the fields of
+//! their variants may change in any release, and [`RenderCommand`] isn't
+//! non-exhaustive, so the renderers can match all of its variants. New variants of
+//! [`LayoutCommand`], [`ContainerKind`] and [`DeriveWrapSize`] aren't breaking,
+//! they're non-exhaustive.
+
+pub use smallvec::SmallVec;
+
+pub use crate::{
+    WidgetId, WidgetRef, WidgetType,
+    layout::{ContainerKind, DeriveWrapSize, LayoutCommand, WidgetPlacement},
+    render::{Fill, PixelExtension, RenderCommand, RenderContext},
+    state::WidgetState,
+    widgets::{
+        FrameBuilder,
+        builder::{BuildContext, WidgetBuilder},
+        gesture_detector::{GestureDetectorBuilder, GestureDetectorResponse, gesture_detector},
+    },
+};
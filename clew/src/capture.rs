@@ -0,0 +1,354 @@
+//! Copying what a part of the UI draws to the clipboard as an image.
+//!
+//! The part is the content of a [`crate::widgets::cache_layer`] or the area any other
+//! widget is placed in, it's drawn again offscreen from the render commands of the last
+//! frame, moved to the origin and at the scale of the image, so the texts and the SVGs
+//! are rasterized at that scale rather than stretched. The copy happens after the frame
+//! is rendered, the returned future is meant to be spawned so the result comes back as
+//! a regular async event:
+//!
+//! ```ignore
+//! struct Copied(Result<(), ClewError>);
+//!
+//! let layer = cache_layer().id("chart");
+//! let layer_id = layer.resolve_id(ctx);
+//!
+//! layer.build(ctx, |ctx| chart(&self.points).build(ctx));
+//!
+//! if copy_clicked {
+//!     let copy = ctx.copy_subtree_as_image(layer_id, SubtreeImageOptions::new().scale(2.));
+//!
+//!     ctx.spawn(async move { Copied(copy.await) });
+//! }
+//! ```
+
+use std::{borrow::Cow, future::Future};
+
+use crate::{
+    ClewError, ClipShape, ColorRgb, ColorRgba, Rect, Transform, Vec2, WidgetId,
+    assets::Assets,
+    render::{RenderCommand, Renderer, RgbaImage},
+    state::UiState,
+    text::{FontResources, TextId, TextsResources},
+    widgets::builder::BuildContext,
+};
+
+/// What is under the content in the image.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum ImageBackground {
+    #[default]
+    Transparent,
+    /// The fill color of the window.
+    Window,
+    Color(ColorRgba),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SubtreeImageOptions {
+    pub scale: f32,
+    pub background: ImageBackground,
+}
+
+impl Default for SubtreeImageOptions {
+    fn default() -> Self {
+        Self {
+            scale: 1.,
+            background: ImageBackground::default(),
+        }
+    }
+}
+
+impl SubtreeImageOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pixels of the image per physical pixel of the view.
+    pub fn scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    pub fn background(mut self, background: ImageBackground) -> Self {
+        self.background = background;
+        self
+    }
+}
+
+pub(crate) struct ImageCapture {
+    id: WidgetId,
+    options: SubtreeImageOptions,
+    result: tokio::sync::oneshot::Sender<Result<(), ClewError>>,
+}
+
+impl BuildContext<'_, '_> {
+    /// Puts what the widget with the id draws on the clipboard as an image, once the
+    /// frame is rendered. The image of a cache layer has only its content, the image of
+    /// other widgets has everything the frame draws in the area of the widget, e.g. the
+    /// background of its parent too.
+    pub fn copy_subtree_as_image(
+        &mut self,
+        id: WidgetId,
+        options: SubtreeImageOptions,
+    ) -> impl Future<Output = Result<(), ClewError>> + Send + 'static {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        self.image_captures.push(ImageCapture {
+            id,
+            options,
+            result: tx,
+        });
+
+        async move {
+            rx.await.unwrap_or_else(|_| {
+                Err(ClewError::Capture(
+                    "The view was closed before the image was drawn".to_string(),
+                ))
+            })
+        }
+    }
+}
+
+/// Draws the requested subtrees and puts them on the clipboard, the shell calls it
+/// after the frame is rendered.
+pub fn process_image_captures(
+    state: &mut UiState,
+    renderer: &mut dyn Renderer,
+    fill_color: ColorRgb,
+    fonts: &mut FontResources,
+    texts: &mut TextsResources,
+    assets: &Assets,
+) {
+    for capture in std::mem::take(&mut state.image_captures) {
+        let result = subtree_image(
+            state,
+            renderer,
+            capture.id,
+            capture.options,
+            fill_color,
+            fonts,
+            texts,
+            assets,
+        )
+        .and_then(|image| {
+            let mut clipboard =
+                arboard::Clipboard::new().map_err(|err| ClewError::Clipboard(err.to_string()))?;
+
+            clipboard
+                .set_image(arboard::ImageData {
+                    width: image.width as usize,
+                    height: image.height as usize,
+                    bytes: Cow::Owned(image.pixels),
+                })
+                .map_err(|err| ClewError::Clipboard(err.to_string()))
+        });
+
+        if let Err(err) = &result {
+            log::warn!("{err}");
+        }
+
+        let _ = capture.result.send(result);
+    }
+}
+
+/// Draws the widget with the id offscreen like [`BuildContext::copy_subtree_as_image`]
+/// and returns the image rather than putting it on the clipboard.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn subtree_image(
+    state: &UiState,
+    renderer: &mut dyn Renderer,
+    id: WidgetId,
+    options: SubtreeImageOptions,
+    fill_color: ColorRgb,
+    fonts: &mut FontResources,
+    texts: &mut TextsResources,
+    assets: &Assets,
+) -> Result<RgbaImage, ClewError> {
+    let (commands, bounds) = match state.render_state.layer_commands(id) {
+        Some(commands) => {
+            let bounds = subtree_bounds(commands, texts)
+                .ok_or_else(|| ClewError::Capture("The subtree draws nothing".to_string()))?;
+
+            (Cow::Borrowed(commands), bounds)
+        }
+        None => {
+            let rect = widget_rect(state, id).ok_or_else(|| {
+                ClewError::Capture("No widget with the id was rendered".to_string())
+            })?;
+            let frame = state.render_state.commands();
+            let mut commands = Vec::with_capacity(frame.len() + 2);
+
+            commands.push(RenderCommand::PushClip {
+                rect,
+                shape: ClipShape::Rect,
+            });
+            commands.extend_from_slice(frame);
+            commands.push(RenderCommand::PopClip);
+
+            (Cow::Owned(commands), rect)
+        }
+    };
+
+    let width = (bounds.width * options.scale).ceil() as u32;
+    let height = (bounds.height * options.scale).ceil() as u32;
+
+    if width == 0 || height == 0 {
+        return Err(ClewError::Capture("The subtree draws nothing".to_string()));
+    }
+
+    let background = match options.background {
+        ImageBackground::Transparent => None,
+        ImageBackground::Window => Some(fill_color.with_alpha(1.)),
+        ImageBackground::Color(color) => Some(color),
+    };
+
+    renderer.render_image(
+        &offscreen_commands(&commands, bounds, options.scale),
+        width,
+        height,
+        background,
+        fonts,
+        texts,
+        assets,
+    )
+}
+
+/// Area of the widget in the physical pixels of the view, the one of its placement or,
+/// for the containers without one, the measured one.
+fn widget_rect(state: &UiState, id: WidgetId) -> Option<Rect> {
+    let rect = state
+        .placed_widgets()
+        .find(|(widget_ref, _)| widget_ref.id == id)
+        .map(|(_, rect)| rect)
+        .or_else(|| {
+            state
+                .widgets_states
+                .layout_measures
+                .get(id)
+                .map(|measure| Rect::new(measure.x, measure.y, measure.width, measure.height))
+        })?;
+
+    Some(rect * state.view.scale_factor)
+}
+
+/// Area the commands paint in the coordinates of the view.
+fn subtree_bounds(commands: &[RenderCommand], texts: &TextsResources) -> Option<Rect> {
+    let mut transform_stack = vec![Transform::IDENTITY];
+    let mut bounds: Option<Rect> = None;
+
+    for command in commands {
+        let transform = *transform_stack.last().unwrap_or(&Transform::IDENTITY);
+
+        let command_bounds = match command {
+            RenderCommand::PushTransform { transform: inner } => {
+                transform_stack.push(inner.then(transform));
+                continue;
+            }
+            RenderCommand::PopTransform => {
+                transform_stack.pop();
+                continue;
+            }
+            RenderCommand::Text {
+                x,
+                y,
+                text_id,
+                clip,
+                stroke,
+                ..
+            } => text_bounds(texts, *text_id).map(|rect| {
+                let rect = rect
+                    .offset(*x, *y)
+                    .expand(stroke.map_or(0., |stroke| stroke.width));
+
+                clip.map_or(rect, |clip| rect.intersect(clip))
+            }),
+            _ => command.bounds(),
+        };
+
+        if let Some(command_bounds) = command_bounds {
+            let command_bounds = transform.apply_rect(command_bounds);
+
+            bounds = Some(match bounds {
+                Some(bounds) => bounds.union(command_bounds),
+                None => command_bounds,
+            });
+        }
+    }
+
+    bounds
+}
+
+/// Extent of the glyphs relative to the text origin.
+fn text_bounds(texts: &TextsResources, text_id: TextId) -> Option<Rect> {
+    let mut bounds: Option<Rect> = None;
+
+    for run in texts.get(text_id).layout_runs() {
+        for glyph in run.glyphs {
+            let rect = Rect::new(glyph.x, run.line_top, glyph.w, run.line_height);
+
+            bounds = Some(match bounds {
+                Some(bounds) => bounds.union(rect),
+                None => rect,
+            });
+        }
+    }
+
+    bounds
+}
+
+/// The commands moved so the top left corner of `bounds` is at the origin and scaled,
+/// with the whole text visible.
+fn offscreen_commands(commands: &[RenderCommand], bounds: Rect, scale: f32) -> Vec<RenderCommand> {
+    let mut offscreen = Vec::with_capacity(commands.len() + 2);
+
+    offscreen.push(RenderCommand::PushTransform {
+        transform: Transform::new(Vec2::new(-bounds.x, -bounds.y) * scale, scale),
+    });
+
+    for command in commands {
+        let mut command = command.clone();
+
+        if let RenderCommand::Text { visible_band, .. } = &mut command {
+            *visible_band = None;
+        }
+
+        offscreen.push(command);
+    }
+
+    offscreen.push(RenderCommand::PopTransform);
+
+    offscreen
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::Fill;
+
+    fn rect(x: f32, y: f32, width: f32, height: f32) -> RenderCommand {
+        RenderCommand::Rect {
+            boundary: Rect::new(x, y, width, height),
+            fill: Some(Fill::Color(ColorRgba::from_hex(0xFFFFFFFF))),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        }
+    }
+
+    #[test]
+    fn test_subtree_bounds_follow_transforms() {
+        let commands = [
+            rect(10., 20., 30., 10.),
+            RenderCommand::PushTransform {
+                transform: Transform::new(Vec2::new(100., 0.), 2.),
+            },
+            rect(0., 0., 10., 10.),
+            RenderCommand::PopTransform,
+        ];
+
+        let bounds = subtree_bounds(&commands, &TextsResources::new()).unwrap();
+
+        // Rect bounds include a pixel for the antialiasing.
+        assert_eq!(bounds, Rect::new(9., -2., 113., 33.));
+    }
+}
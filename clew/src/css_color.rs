@@ -0,0 +1,461 @@
+//! Colors written the CSS way, for the themes and the config files: `#RGB`, `#RGBA`,
+//! `#RRGGBB`, `#RRGGBBAA`, `rgb()`/`rgba()` and the named colors.
+//!
+//! Note that in CSS the alpha goes last while [`ColorRgba::from_hex`] takes it first,
+//! `#3366CC80` is `0x803366CC`.
+
+use crate::{ClewError, ColorRgb, ColorRgba};
+
+impl ColorRgba {
+    /// Parses a CSS color, the case and the surrounding whitespace don't matter and the
+    /// out of range components are clamped.
+    ///
+    /// ```
+    /// # use clew::ColorRgba;
+    /// assert_eq!(
+    ///     ColorRgba::from_css("#3366CC").unwrap(),
+    ///     ColorRgba::from_hex(0xFF3366CC)
+    /// );
+    /// assert_eq!(
+    ///     ColorRgba::from_css("rgba(51, 102, 204, 50%)").unwrap().to_css_hex(),
+    ///     "#3366cc80"
+    /// );
+    /// ```
+    pub fn from_css(css: &str) -> Result<Self, ClewError> {
+        parse(css).map_err(|reason| ClewError::InvalidColor {
+            input: css.to_string(),
+            reason,
+        })
+    }
+
+    /// `#rrggbbaa`, the form the colors are serialized with.
+    pub fn to_css_hex(&self) -> String {
+        let [r, g, b, a] = self.to_rgba8();
+
+        format!("#{r:02x}{g:02x}{b:02x}{a:02x}")
+    }
+
+    /// `rgba(r, g, b, a)` with the 8-bit components and the alpha in 0..1.
+    pub fn to_css_rgba(&self) -> String {
+        let [r, g, b, _] = self.to_rgba8();
+        let a = (self.a.clamp(0., 1.) * 1000.).round() / 1000.;
+
+        format!("rgba({r}, {g}, {b}, {a})")
+    }
+}
+
+impl ColorRgb {
+    /// Parses a CSS color the same way as [`ColorRgba::from_css`], the translucent
+    /// colors are rejected.
+    pub fn from_css(css: &str) -> Result<Self, ClewError> {
+        let color = ColorRgba::from_css(css)?;
+
+        if color.a < 1. {
+            return Err(ClewError::InvalidColor {
+                input: css.to_string(),
+                reason: "the color is translucent",
+            });
+        }
+
+        Ok(color.to_rgb())
+    }
+
+    /// `#rrggbb`, the form the colors are serialized with.
+    pub fn to_css_hex(&self) -> String {
+        let [r, g, b] = self.to_rgb8();
+
+        format!("#{r:02x}{g:02x}{b:02x}")
+    }
+
+    /// `rgb(r, g, b)` with the 8-bit components.
+    pub fn to_css_rgb(&self) -> String {
+        let [r, g, b] = self.to_rgb8();
+
+        format!("rgb({r}, {g}, {b})")
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ColorRgba {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_css_hex())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ColorRgba {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let css = String::deserialize(deserializer)?;
+
+        Self::from_css(&css).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for ColorRgb {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_css_hex())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ColorRgb {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let css = String::deserialize(deserializer)?;
+
+        Self::from_css(&css).map_err(serde::de::Error::custom)
+    }
+}
+
+fn parse(css: &str) -> Result<ColorRgba, &'static str> {
+    let css = css.trim().to_ascii_lowercase();
+
+    if let Some(hex) = css.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+
+    if let Some(args) = css.strip_prefix("rgba").or_else(|| css.strip_prefix("rgb")) {
+        let args = args
+            .trim_start()
+            .strip_prefix('(')
+            .and_then(|args| args.strip_suffix(')'))
+            .ok_or("expected the components in parentheses")?;
+
+        return parse_rgb_args(args);
+    }
+
+    NAMED_COLORS
+        .binary_search_by_key(&css.as_str(), |(name, _)| name)
+        .map(|index| ColorRgba::from_hex(NAMED_COLORS[index].1))
+        .map_err(|_| "unknown color name")
+}
+
+fn parse_hex(hex: &str) -> Result<ColorRgba, &'static str> {
+    if !hex.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+        return Err("invalid hex digit");
+    }
+
+    let digit = |index: usize| u32::from_str_radix(&hex[index..index + 1], 16).unwrap();
+    let byte = |index: usize| u32::from_str_radix(&hex[index..index + 2], 16).unwrap();
+
+    // The short forms repeat each digit, `#f80` is `#ff8800`.
+    let [r, g, b, a] = match hex.len() {
+        3 => [digit(0) * 17, digit(1) * 17, digit(2) * 17, 0xFF],
+        4 => [digit(0) * 17, digit(1) * 17, digit(2) * 17, digit(3) * 17],
+        6 => [byte(0), byte(2), byte(4), 0xFF],
+        8 => [byte(0), byte(2), byte(4), byte(6)],
+        _ => return Err("expected 3, 4, 6 or 8 hex digits"),
+    };
+
+    Ok(ColorRgba::from_hex((a << 24) | (r << 16) | (g << 8) | b))
+}
+
+/// Both the legacy `51, 102, 204, 0.5` and the modern `51 102 204 / 0.5` syntaxes.
+fn parse_rgb_args(args: &str) -> Result<ColorRgba, &'static str> {
+    let (rgb, alpha) = match args.split_once('/') {
+        Some((rgb, alpha)) => (rgb, Some(alpha.trim())),
+        None => (args, None),
+    };
+
+    let mut components: Vec<&str> = if rgb.contains(',') {
+        rgb.split(',').map(str::trim).collect()
+    } else {
+        rgb.split_whitespace().collect()
+    };
+
+    let alpha = match (alpha, components.len()) {
+        (Some(_), 4) => return Err("the alpha is given twice"),
+        (Some(alpha), _) => Some(alpha),
+        (None, 4) => components.pop(),
+        (None, _) => None,
+    };
+
+    let [r, g, b] = components[..] else {
+        return Err("expected 3 color components");
+    };
+
+    let channel = |value: &str| parse_component(value, 255.);
+
+    Ok(ColorRgba::new(
+        channel(r)? / 255.,
+        channel(g)? / 255.,
+        channel(b)? / 255.,
+        alpha.map_or(Ok(1.), |alpha| parse_component(alpha, 1.))?,
+    ))
+}
+
+/// A number in `0..=max` or a percentage of `max`, clamped to the range.
+fn parse_component(value: &str, max: f32) -> Result<f32, &'static str> {
+    let number = match value.strip_suffix('%') {
+        Some(percentage) => percentage.trim().parse::<f32>().map(|it| it / 100. * max),
+        None => value.parse::<f32>(),
+    };
+
+    match number {
+        Ok(number) if number.is_finite() => Ok(number.clamp(0., max)),
+        _ => Err("invalid number"),
+    }
+}
+
+/// The CSS named colors as `0xAARRGGBB`, sorted by the name.
+const NAMED_COLORS: &[(&str, u32)] = &[
+    ("aliceblue", 0xFFF0F8FF),
+    ("antiquewhite", 0xFFFAEBD7),
+    ("aqua", 0xFF00FFFF),
+    ("aquamarine", 0xFF7FFFD4),
+    ("azure", 0xFFF0FFFF),
+    ("beige", 0xFFF5F5DC),
+    ("bisque", 0xFFFFE4C4),
+    ("black", 0xFF000000),
+    ("blanchedalmond", 0xFFFFEBCD),
+    ("blue", 0xFF0000FF),
+    ("blueviolet", 0xFF8A2BE2),
+    ("brown", 0xFFA52A2A),
+    ("burlywood", 0xFFDEB887),
+    ("cadetblue", 0xFF5F9EA0),
+    ("chartreuse", 0xFF7FFF00),
+    ("chocolate", 0xFFD2691E),
+    ("coral", 0xFFFF7F50),
+    ("cornflowerblue", 0xFF6495ED),
+    ("cornsilk", 0xFFFFF8DC),
+    ("crimson", 0xFFDC143C),
+    ("cyan", 0xFF00FFFF),
+    ("darkblue", 0xFF00008B),
+    ("darkcyan", 0xFF008B8B),
+    ("darkgoldenrod", 0xFFB8860B),
+    ("darkgray", 0xFFA9A9A9),
+    ("darkgreen", 0xFF006400),
+    ("darkgrey", 0xFFA9A9A9),
+    ("darkkhaki", 0xFFBDB76B),
+    ("darkmagenta", 0xFF8B008B),
+    ("darkolivegreen", 0xFF556B2F),
+    ("darkorange", 0xFFFF8C00),
+    ("darkorchid", 0xFF9932CC),
+    ("darkred", 0xFF8B0000),
+    ("darksalmon", 0xFFE9967A),
+    ("darkseagreen", 0xFF8FBC8F),
+    ("darkslateblue", 0xFF483D8B),
+    ("darkslategray", 0xFF2F4F4F),
+    ("darkslategrey", 0xFF2F4F4F),
+    ("darkturquoise", 0xFF00CED1),
+    ("darkviolet", 0xFF9400D3),
+    ("deeppink", 0xFFFF1493),
+    ("deepskyblue", 0xFF00BFFF),
+    ("dimgray", 0xFF696969),
+    ("dimgrey", 0xFF696969),
+    ("dodgerblue", 0xFF1E90FF),
+    ("firebrick", 0xFFB22222),
+    ("floralwhite", 0xFFFFFAF0),
+    ("forestgreen", 0xFF228B22),
+    ("fuchsia", 0xFFFF00FF),
+    ("gainsboro", 0xFFDCDCDC),
+    ("ghostwhite", 0xFFF8F8FF),
+    ("gold", 0xFFFFD700),
+    ("goldenrod", 0xFFDAA520),
+    ("gray", 0xFF808080),
+    ("green", 0xFF008000),
+    ("greenyellow", 0xFFADFF2F),
+    ("grey", 0xFF808080),
+    ("honeydew", 0xFFF0FFF0),
+    ("hotpink", 0xFFFF69B4),
+    ("indianred", 0xFFCD5C5C),
+    ("indigo", 0xFF4B0082),
+    ("ivory", 0xFFFFFFF0),
+    ("khaki", 0xFFF0E68C),
+    ("lavender", 0xFFE6E6FA),
+    ("lavenderblush", 0xFFFFF0F5),
+    ("lawngreen", 0xFF7CFC00),
+    ("lemonchiffon", 0xFFFFFACD),
+    ("lightblue", 0xFFADD8E6),
+    ("lightcoral", 0xFFF08080),
+    ("lightcyan", 0xFFE0FFFF),
+    ("lightgoldenrodyellow", 0xFFFAFAD2),
+    ("lightgray", 0xFFD3D3D3),
+    ("lightgreen", 0xFF90EE90),
+    ("lightgrey", 0xFFD3D3D3),
+    ("lightpink", 0xFFFFB6C1),
+    ("lightsalmon", 0xFFFFA07A),
+    ("lightseagreen", 0xFF20B2AA),
+    ("lightskyblue", 0xFF87CEFA),
+    ("lightslategray", 0xFF778899),
+    ("lightslategrey", 0xFF778899),
+    ("lightsteelblue", 0xFFB0C4DE),
+    ("lightyellow", 0xFFFFFFE0),
+    ("lime", 0xFF00FF00),
+    ("limegreen", 0xFF32CD32),
+    ("linen", 0xFFFAF0E6),
+    ("magenta", 0xFFFF00FF),
+    ("maroon", 0xFF800000),
+    ("mediumaquamarine", 0xFF66CDAA),
+    ("mediumblue", 0xFF0000CD),
+    ("mediumorchid", 0xFFBA55D3),
+    ("mediumpurple", 0xFF9370DB),
+    ("mediumseagreen", 0xFF3CB371),
+    ("mediumslateblue", 0xFF7B68EE),
+    ("mediumspringgreen", 0xFF00FA9A),
+    ("mediumturquoise", 0xFF48D1CC),
+    ("mediumvioletred", 0xFFC71585),
+    ("midnightblue", 0xFF191970),
+    ("mintcream", 0xFFF5FFFA),
+    ("mistyrose", 0xFFFFE4E1),
+    ("moccasin", 0xFFFFE4B5),
+    ("navajowhite", 0xFFFFDEAD),
+    ("navy", 0xFF000080),
+    ("oldlace", 0xFFFDF5E6),
+    ("olive", 0xFF808000),
+    ("olivedrab", 0xFF6B8E23),
+    ("orange", 0xFFFFA500),
+    ("orangered", 0xFFFF4500),
+    ("orchid", 0xFFDA70D6),
+    ("palegoldenrod", 0xFFEEE8AA),
+    ("palegreen", 0xFF98FB98),
+    ("paleturquoise", 0xFFAFEEEE),
+    ("palevioletred", 0xFFDB7093),
+    ("papayawhip", 0xFFFFEFD5),
+    ("peachpuff", 0xFFFFDAB9),
+    ("peru", 0xFFCD853F),
+    ("pink", 0xFFFFC0CB),
+    ("plum", 0xFFDDA0DD),
+    ("powderblue", 0xFFB0E0E6),
+    ("purple", 0xFF800080),
+    ("rebeccapurple", 0xFF663399),
+    ("red", 0xFFFF0000),
+    ("rosybrown", 0xFFBC8F8F),
+    ("royalblue", 0xFF4169E1),
+    ("saddlebrown", 0xFF8B4513),
+    ("salmon", 0xFFFA8072),
+    ("sandybrown", 0xFFF4A460),
+    ("seagreen", 0xFF2E8B57),
+    ("seashell", 0xFFFFF5EE),
+    ("sienna", 0xFFA0522D),
+    ("silver", 0xFFC0C0C0),
+    ("skyblue", 0xFF87CEEB),
+    ("slateblue", 0xFF6A5ACD),
+    ("slategray", 0xFF708090),
+    ("slategrey", 0xFF708090),
+    ("snow", 0xFFFFFAFA),
+    ("springgreen", 0xFF00FF7F),
+    ("steelblue", 0xFF4682B4),
+    ("tan", 0xFFD2B48C),
+    ("teal", 0xFF008080),
+    ("thistle", 0xFFD8BFD8),
+    ("tomato", 0xFFFF6347),
+    ("transparent", 0x00000000),
+    ("turquoise", 0xFF40E0D0),
+    ("violet", 0xFFEE82EE),
+    ("wheat", 0xFFF5DEB3),
+    ("white", 0xFFFFFFFF),
+    ("whitesmoke", 0xFFF5F5F5),
+    ("yellow", 0xFFFFFF00),
+    ("yellowgreen", 0xFF9ACD32),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn css(css: &str) -> [u8; 4] {
+        ColorRgba::from_css(css).unwrap().to_rgba8()
+    }
+
+    fn reason(css: &str) -> &'static str {
+        match ColorRgba::from_css(css) {
+            Err(ClewError::InvalidColor { reason, .. }) => reason,
+            other => panic!("{css:?} parsed as {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_named_colors_are_sorted() {
+        assert!(NAMED_COLORS.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(NAMED_COLORS.len(), 149);
+    }
+
+    #[test]
+    fn test_hex_forms() {
+        assert_eq!(css("#f80"), [0xFF, 0x88, 0x00, 0xFF]);
+        assert_eq!(css("#f808"), [0xFF, 0x88, 0x00, 0x88]);
+        assert_eq!(css("#3366cc"), [0x33, 0x66, 0xCC, 0xFF]);
+        assert_eq!(css("#3366cc80"), [0x33, 0x66, 0xCC, 0x80]);
+        assert_eq!(css("  #3366CC\n"), [0x33, 0x66, 0xCC, 0xFF]);
+    }
+
+    #[test]
+    fn test_hex_agrees_with_from_hex() {
+        assert_eq!(
+            ColorRgba::from_css("#3366CC80").unwrap(),
+            ColorRgba::from_hex(0x803366CC)
+        );
+        assert_eq!(
+            ColorRgb::from_css("#3366CC").unwrap(),
+            ColorRgb::from_hex(0x3366CC)
+        );
+        assert_eq!(ColorRgba::from_hex(0x803366CC).to_css_hex(), "#3366cc80");
+        assert_eq!(ColorRgb::from_hex(0x3366CC).to_css_hex(), "#3366cc");
+    }
+
+    #[test]
+    fn test_rgb_functions() {
+        assert_eq!(css("rgb(51, 102, 204)"), [51, 102, 204, 255]);
+        assert_eq!(css("RGBA(51,102,204,0.5)"), [51, 102, 204, 128]);
+        assert_eq!(css("rgb(51 102 204 / 50%)"), [51, 102, 204, 128]);
+        assert_eq!(css("rgba( 20% , 40% , 80% )"), [51, 102, 204, 255]);
+        assert_eq!(css("rgb (0 0 0)"), [0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn test_out_of_range_components_are_clamped() {
+        assert_eq!(css("rgb(300, -20, 128)"), [255, 0, 128, 255]);
+        assert_eq!(css("rgba(0, 0, 0, 2)"), [0, 0, 0, 255]);
+        assert_eq!(css("rgb(150% 0% 0% / -1)"), [255, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_named_colors() {
+        assert_eq!(css("RebeccaPurple"), [0x66, 0x33, 0x99, 0xFF]);
+        assert_eq!(css(" white "), [0xFF, 0xFF, 0xFF, 0xFF]);
+        assert_eq!(css("transparent"), [0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_invalid_colors() {
+        assert_eq!(reason("#12"), "expected 3, 4, 6 or 8 hex digits");
+        assert_eq!(reason("#12345g"), "invalid hex digit");
+        assert_eq!(reason("#"), "expected 3, 4, 6 or 8 hex digits");
+        assert_eq!(reason("rgb(1, 2)"), "expected 3 color components");
+        assert_eq!(reason("rgb(1 2 3 4 5)"), "expected 3 color components");
+        assert_eq!(
+            reason("rgb(1, 2, 3, 4) / 1"),
+            "expected the components in parentheses"
+        );
+        assert_eq!(reason("rgb(1 2 3 4 / 1)"), "the alpha is given twice");
+        assert_eq!(reason("rgb(1, x, 3)"), "invalid number");
+        assert_eq!(reason("rgb(1, 2, NaN)"), "invalid number");
+        assert_eq!(
+            reason("rgb 1, 2, 3"),
+            "expected the components in parentheses"
+        );
+        assert_eq!(reason("blurple"), "unknown color name");
+        assert_eq!(reason(""), "unknown color name");
+        assert!(matches!(
+            ColorRgb::from_css("#3366cc80"),
+            Err(ClewError::InvalidColor { .. })
+        ));
+    }
+
+    #[test]
+    fn test_formatting() {
+        let color = ColorRgba::from_hex(0x803366CC);
+
+        assert_eq!(color.to_css_rgba(), "rgba(51, 102, 204, 0.502)");
+        assert_eq!(css(&color.to_css_rgba()), color.to_rgba8());
+        assert_eq!(
+            ColorRgb::from_hex(0x3366CC).to_css_rgb(),
+            "rgb(51, 102, 204)"
+        );
+        assert_eq!(
+            ColorRgba::from_hex(0xFF000000).to_css_rgba(),
+            "rgba(0, 0, 0, 1)"
+        );
+    }
+}
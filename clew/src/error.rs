@@ -0,0 +1,88 @@
+use std::fmt;
+
+use crate::text::FontLoadError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ClewError {
+    /// The asset with the given id hasn't been registered in [`crate::assets::Assets`].
+    AssetNotFound {
+        id: String,
+    },
+    /// The SVG data couldn't be parsed.
+    InvalidSvg {
+        name: &'static str,
+        message: String,
+    },
+    /// The image data couldn't be decoded.
+    InvalidImage {
+        key: String,
+        message: String,
+    },
+    /// The source of an image failed to provide its data, see
+    /// [`crate::widgets::async_image`].
+    ImageSource {
+        key: String,
+        message: String,
+    },
+    FontLoad(FontLoadError),
+    /// The window surface couldn't be created or configured.
+    Surface(String),
+    /// The rendering backend couldn't be initialized.
+    Renderer(String),
+    /// The UI state snapshot was taken by an incompatible version of the library.
+    IncompatibleSnapshot {
+        version: u32,
+    },
+    /// The widget couldn't be drawn into an image.
+    Capture(String),
+    /// The system clipboard is unavailable or refused the content.
+    Clipboard(String),
+    /// The string isn't a CSS color, see [`crate::ColorRgba::from_css`].
+    InvalidColor {
+        input: String,
+        reason: &'static str,
+    },
+}
+
+impl fmt::Display for ClewError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClewError::AssetNotFound { id } => write!(f, "Asset with ID = {id} has not found"),
+            ClewError::InvalidSvg { name, message } => {
+                write!(f, "Failed to parse SVG \"{name}\": {message}")
+            }
+            ClewError::InvalidImage { key, message } => {
+                write!(f, "Failed to decode image \"{key}\": {message}")
+            }
+            ClewError::ImageSource { key, message } => {
+                write!(f, "Failed to load image \"{key}\": {message}")
+            }
+            ClewError::FontLoad(err) => write!(f, "{err}"),
+            ClewError::Surface(message) => write!(f, "Failed to create surface: {message}"),
+            ClewError::Renderer(message) => write!(f, "Failed to create renderer: {message}"),
+            ClewError::IncompatibleSnapshot { version } => {
+                write!(f, "UI state snapshot version {version} is not supported")
+            }
+            ClewError::Capture(message) => write!(f, "Failed to capture image: {message}"),
+            ClewError::Clipboard(message) => write!(f, "Failed to access clipboard: {message}"),
+            ClewError::InvalidColor { input, reason } => {
+                write!(f, "Invalid color \"{input}\": {reason}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ClewError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClewError::FontLoad(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<FontLoadError> for ClewError {
+    fn from(value: FontLoadError) -> Self {
+        ClewError::FontLoad(value)
+    }
+}
@@ -0,0 +1,256 @@
+//! Native file dialogs, enabled with the `file-dialogs` feature.
+//!
+//! The dialogs don't block the UI thread, the returned futures are meant to be
+//! spawned so the result comes back as a regular async event:
+//!
+//! ```ignore
+//! struct FileOpened(Option<PathBuf>);
+//!
+//! if open_clicked {
+//!     let dialog = ctx.pick_file(FileDialogOptions::new().filter("Images", &["png", "jpg"]));
+//!
+//!     ctx.spawn(async move { FileOpened(dialog.await) });
+//! }
+//! ```
+//!
+//! Input of the view is suppressed until the dialog is closed.
+
+use std::{future::Future, path::PathBuf};
+
+use crate::widgets::builder::BuildContext;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileFilter {
+    pub name: String,
+    pub extensions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FileDialogOptions {
+    pub title: Option<String>,
+    pub filters: Vec<FileFilter>,
+    pub directory: Option<PathBuf>,
+    pub file_name: Option<String>,
+}
+
+impl FileDialogOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    pub fn filter(mut self, name: impl Into<String>, extensions: &[&str]) -> Self {
+        self.filters.push(FileFilter {
+            name: name.into(),
+            extensions: extensions.iter().map(|it| it.to_string()).collect(),
+        });
+        self
+    }
+
+    /// Directory the dialog starts in.
+    pub fn directory(mut self, directory: impl Into<PathBuf>) -> Self {
+        self.directory = Some(directory.into());
+        self
+    }
+
+    /// Default file name of the save dialog.
+    pub fn file_name(mut self, file_name: impl Into<String>) -> Self {
+        self.file_name = Some(file_name.into());
+        self
+    }
+
+    fn into_dialog(self) -> rfd::AsyncFileDialog {
+        let mut dialog = rfd::AsyncFileDialog::new();
+
+        if let Some(title) = self.title {
+            dialog = dialog.set_title(title);
+        }
+
+        for filter in self.filters {
+            dialog = dialog.add_filter(filter.name, &filter.extensions);
+        }
+
+        if let Some(directory) = self.directory {
+            dialog = dialog.set_directory(directory);
+        }
+
+        if let Some(file_name) = self.file_name {
+            dialog = dialog.set_file_name(file_name);
+        }
+
+        dialog
+    }
+}
+
+impl BuildContext<'_, '_> {
+    pub fn pick_file(
+        &self,
+        options: FileDialogOptions,
+    ) -> impl Future<Output = Option<PathBuf>> + Send + 'static + use<> {
+        let dialog = self.open_dialog(move || options.into_dialog().pick_file());
+
+        async move { dialog.await.flatten().map(PathBuf::from) }
+    }
+
+    pub fn pick_files(
+        &self,
+        options: FileDialogOptions,
+    ) -> impl Future<Output = Option<Vec<PathBuf>>> + Send + 'static + use<> {
+        let dialog = self.open_dialog(move || options.into_dialog().pick_files());
+
+        async move {
+            dialog
+                .await
+                .flatten()
+                .map(|files| files.into_iter().map(PathBuf::from).collect())
+        }
+    }
+
+    pub fn pick_folder(
+        &self,
+        options: FileDialogOptions,
+    ) -> impl Future<Output = Option<PathBuf>> + Send + 'static + use<> {
+        let dialog = self.open_dialog(move || options.into_dialog().pick_folder());
+
+        async move { dialog.await.flatten().map(PathBuf::from) }
+    }
+
+    pub fn save_file(
+        &self,
+        options: FileDialogOptions,
+    ) -> impl Future<Output = Option<PathBuf>> + Send + 'static + use<> {
+        let dialog = self.open_dialog(move || options.into_dialog().save_file());
+
+        async move { dialog.await.flatten().map(PathBuf::from) }
+    }
+
+    /// Keeps the view modal until the dialog is closed. `None` means the dialog couldn't
+    /// be shown.
+    fn open_dialog<T, D, F>(
+        &self,
+        show: F,
+    ) -> impl Future<Output = Option<T>> + Send + 'static + use<T, D, F>
+    where
+        T: Send + 'static,
+        D: Future<Output = T> + Send + 'static,
+        F: FnOnce() -> D + Send + 'static,
+    {
+        let guard = self.begin_modal_dialog();
+
+        // AppKit panels have to be created on the main thread, the dialog itself is
+        // awaited wherever the future is spawned.
+        #[cfg(target_os = "macos")]
+        let dialog = self.run_on_main_thread(show);
+
+        #[cfg(not(target_os = "macos"))]
+        let dialog = std::future::ready(Some(show()));
+
+        async move {
+            let _guard = guard;
+
+            Some(dialog.await?.await)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        ClewHost, ClewInstance, Resources,
+        assets::Assets,
+        io::{InputEvent, MouseButton},
+        testing,
+        widgets::builder::{ApplicationEvent, ApplicationEventLoopProxy},
+    };
+
+    /// Runs the main thread tasks right away, the dialogs are shown on the thread of the
+    /// test.
+    struct MainThreadProxy;
+
+    impl ApplicationEventLoopProxy for MainThreadProxy {
+        fn send_event(&self, event: ApplicationEvent) {
+            if let ApplicationEvent::RunOnMainThread(task) = event {
+                task.run();
+            }
+        }
+    }
+
+    fn host<'a>() -> ClewHost<'a> {
+        ClewHost::new(Assets::new(), Resources::new(), Arc::new(MainThreadProxy))
+    }
+
+    /// Opens a dialog that is closed with the value sent to the returned sender.
+    fn open_stub_dialog<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+    ) -> (
+        tokio::sync::oneshot::Sender<&'static str>,
+        impl Future<Output = Option<&'static str>> + use<>,
+    ) {
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let mut receiver = Some(receiver);
+        let mut dialog = None;
+
+        instance.frame(host, &mut |ctx| {
+            let receiver = receiver.take().unwrap();
+
+            dialog = Some(ctx.open_dialog(move || async move { receiver.await.unwrap() }));
+        });
+
+        (sender, dialog.unwrap())
+    }
+
+    /// Whether a press of the left button reaches the build of the next frame.
+    fn press_reaches_build<'a>(instance: &mut ClewInstance<'a>, host: &mut ClewHost<'a>) -> bool {
+        let mut pressed = false;
+
+        instance.push_event(InputEvent::PointerMoved { x: 10., y: 10. });
+        instance.push_event(InputEvent::MouseButton {
+            button: MouseButton::Left,
+            pressed: true,
+        });
+        instance.frame(host, &mut |ctx| pressed = ctx.input().mouse_left_pressed);
+
+        instance.push_event(InputEvent::MouseButton {
+            button: MouseButton::Left,
+            pressed: false,
+        });
+        instance.frame(host, &mut |_| {});
+
+        pressed
+    }
+
+    #[test]
+    fn test_input_is_suppressed_until_the_dialog_is_closed() {
+        let mut host = host();
+        let mut instance = ClewInstance::new(testing::view(100, 100), Default::default());
+
+        assert!(press_reaches_build(&mut instance, &mut host));
+
+        let (sender, dialog) = open_stub_dialog(&mut instance, &mut host);
+        assert!(!press_reaches_build(&mut instance, &mut host));
+        assert!(!instance.has_pending_input());
+
+        sender.send("picked").unwrap();
+        assert_eq!(pollster::block_on(dialog), Some("picked"));
+        assert!(press_reaches_build(&mut instance, &mut host));
+    }
+
+    #[test]
+    fn test_input_is_released_when_the_dialog_is_dropped_unfinished() {
+        let mut host = host();
+        let mut instance = ClewInstance::new(testing::view(100, 100), Default::default());
+
+        let (_sender, dialog) = open_stub_dialog(&mut instance, &mut host);
+        assert!(!press_reaches_build(&mut instance, &mut host));
+
+        drop(dialog);
+        assert!(press_reaches_build(&mut instance, &mut host));
+    }
+}
@@ -0,0 +1,355 @@
+use smallvec::SmallVec;
+
+use crate::{
+    WidgetId, WidgetIdMap,
+    interaction::InteractionState,
+    io::{TextInputAction, UserInput},
+    keyboard::{KeyCode, KeyModifiers},
+};
+
+/// Arrow keys that move the focus between the members of a focus group.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum FocusAxis {
+    /// Left and right arrows.
+    Horizontal,
+    /// Up and down arrows.
+    Vertical,
+    #[default]
+    Both,
+}
+
+type GroupPath = SmallVec<[WidgetId; 4]>;
+
+struct FocusStop {
+    id: WidgetId,
+    /// Enclosing groups, the outermost first.
+    groups: GroupPath,
+    /// Tab is left to the widget once it's focused, e.g. to type a tab into a text field.
+    accepts_tab: bool,
+}
+
+struct FocusGroup {
+    /// Index of the member focused when the group is entered.
+    primary: usize,
+    axis: FocusAxis,
+    ancestors: GroupPath,
+}
+
+/// Something the focus moves to inside a group, or at the top level, a nested group
+/// counts as a single item.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FocusItem {
+    Stop(WidgetId),
+    Group(WidgetId),
+}
+
+/// Focusable widgets in the build order together with the focus groups they are in.
+/// Collected during the build and used to move the focus at the start of the next
+/// frame.
+#[derive(Default)]
+pub(crate) struct FocusChain {
+    stops: Vec<FocusStop>,
+    groups: WidgetIdMap<FocusGroup>,
+    group_stack: GroupPath,
+}
+
+impl FocusChain {
+    pub(crate) fn clear(&mut self) {
+        self.stops.clear();
+        self.groups.clear();
+        self.group_stack.clear();
+    }
+
+    pub(crate) fn push_stop(&mut self, id: WidgetId) {
+        self.stops.push(FocusStop {
+            id,
+            groups: self.group_stack.clone(),
+            accepts_tab: false,
+        });
+    }
+
+    /// Stop that is reached with Tab like the others, but keeps Tab once it's focused.
+    pub(crate) fn push_stop_accepting_tab(&mut self, id: WidgetId) {
+        self.stops.push(FocusStop {
+            id,
+            groups: self.group_stack.clone(),
+            accepts_tab: true,
+        });
+    }
+
+    pub(crate) fn begin_group(&mut self, id: WidgetId, primary: usize, axis: FocusAxis) {
+        self.groups.insert(
+            id,
+            FocusGroup {
+                primary,
+                axis,
+                ancestors: self.group_stack.clone(),
+            },
+        );
+        self.group_stack.push(id);
+    }
+
+    pub(crate) fn end_group(&mut self) {
+        self.group_stack.pop();
+    }
+
+    /// Groups the focused widget is in and its item in the innermost of them.
+    fn locate(&self, focused: WidgetId) -> Option<(&[WidgetId], FocusItem)> {
+        if let Some(group) = self.groups.get(&focused) {
+            return Some((&group.ancestors, FocusItem::Group(focused)));
+        }
+
+        self.stops
+            .iter()
+            .find(|stop| stop.id == focused)
+            .map(|stop| (&stop.groups[..], FocusItem::Stop(focused)))
+    }
+
+    /// Items directly inside the innermost group of the path, in the build order.
+    fn items(&self, path: &[WidgetId]) -> Vec<FocusItem> {
+        let mut items = Vec::new();
+
+        for stop in &self.stops {
+            if !stop.groups.starts_with(path) {
+                continue;
+            }
+
+            let item = match stop.groups.get(path.len()) {
+                Some(group) => FocusItem::Group(*group),
+                None => FocusItem::Stop(stop.id),
+            };
+
+            if items.last() != Some(&item) {
+                items.push(item);
+            }
+        }
+
+        items
+    }
+
+    /// Widget that receives the focus when the item is reached, groups pass it on to
+    /// their primary member.
+    fn target(&self, item: FocusItem) -> Option<WidgetId> {
+        match item {
+            FocusItem::Stop(id) => Some(id),
+            FocusItem::Group(id) => {
+                let group = self.groups.get(&id)?;
+                let mut path = group.ancestors.clone();
+                path.push(id);
+
+                let items = self.items(&path);
+                let item = items.get(group.primary).or(items.first())?;
+
+                self.target(*item)
+            }
+        }
+    }
+
+    /// Moves through the top level items, the focus enters a group at its primary
+    /// member and leaves it entirely.
+    fn tab(&self, focused: Option<WidgetId>, backward: bool) -> Option<WidgetId> {
+        if let Some(focused) = focused
+            && self
+                .stops
+                .iter()
+                .any(|stop| stop.id == focused && stop.accepts_tab)
+        {
+            return None;
+        }
+
+        let current = match focused {
+            Some(focused) => {
+                let (path, item) = self.locate(focused)?;
+
+                Some(path.first().map_or(item, |group| FocusItem::Group(*group)))
+            }
+            None => None,
+        };
+        let items = self.items(&[]);
+
+        if items.is_empty() {
+            return None;
+        }
+
+        let index = current.and_then(|current| items.iter().position(|item| *item == current));
+        let next = match (index, backward) {
+            (None, false) => 0,
+            (None, true) => items.len() - 1,
+            (Some(index), false) => (index + 1) % items.len(),
+            (Some(index), true) => (index + items.len() - 1) % items.len(),
+        };
+
+        self.target(items[next])
+    }
+
+    /// Moves to the next or the previous item of the innermost group, stops at the ends.
+    fn arrow(&self, focused: WidgetId, key: KeyCode) -> Option<WidgetId> {
+        let (path, item) = self.locate(focused)?;
+        let group = self.groups.get(path.last()?)?;
+
+        let forward = match (key, group.axis) {
+            (KeyCode::ArrowRight, FocusAxis::Horizontal | FocusAxis::Both)
+            | (KeyCode::ArrowDown, FocusAxis::Vertical | FocusAxis::Both) => true,
+            (KeyCode::ArrowLeft, FocusAxis::Horizontal | FocusAxis::Both)
+            | (KeyCode::ArrowUp, FocusAxis::Vertical | FocusAxis::Both) => false,
+            _ => return None,
+        };
+
+        let items = self.items(path);
+        let index = items.iter().position(|it| *it == item)?;
+        let next = if forward {
+            index + 1
+        } else {
+            index.checked_sub(1)?
+        };
+
+        self.target(*items.get(next)?)
+    }
+
+    /// The group the focused widget is in.
+    fn escape(&self, focused: WidgetId) -> Option<WidgetId> {
+        let (path, _) = self.locate(focused)?;
+
+        path.last().copied()
+    }
+
+    /// Primary member of the focused group.
+    fn enter(&self, focused: WidgetId) -> Option<WidgetId> {
+        if self.groups.contains_key(&focused) {
+            self.target(FocusItem::Group(focused))
+        } else {
+            None
+        }
+    }
+}
+
+/// Moves the keyboard focus with Tab and Shift+Tab between the focusable widgets and
+/// the focus groups, with the arrows inside of the groups, Escape moves it from the
+/// member to its group and Enter back to the primary member.
+///
+/// The focus is only moved from the widgets that are in the chain, Tab from the ones
+/// accepting it, e.g. a code editor, is left to them. The tab typed with the Tab that
+/// moved the focus is dropped, so the text field it moved to doesn't get it.
+pub(crate) fn handle_focus_navigation(
+    user_input: &mut UserInput,
+    interaction_state: &mut InteractionState,
+    chain: &FocusChain,
+) {
+    let mut tabbed = false;
+
+    for (modifiers, key) in user_input
+        .key_pressed
+        .iter()
+        .chain(user_input.key_pressed_repeat.iter())
+    {
+        let Some(key) = *key else {
+            continue;
+        };
+        let modifiers = modifiers.unwrap_or_default();
+        let focused = interaction_state.focused;
+
+        let target = match key {
+            KeyCode::Tab if (modifiers - KeyModifiers::SHIFT).is_empty() => {
+                chain.tab(focused, modifiers.contains(KeyModifiers::SHIFT))
+            }
+            _ if !modifiers.is_empty() => None,
+            KeyCode::ArrowLeft | KeyCode::ArrowRight | KeyCode::ArrowUp | KeyCode::ArrowDown => {
+                focused.and_then(|focused| chain.arrow(focused, key))
+            }
+            KeyCode::Escape => focused.and_then(|focused| chain.escape(focused)),
+            KeyCode::Enter | KeyCode::NumpadEnter => {
+                focused.and_then(|focused| chain.enter(focused))
+            }
+            _ => None,
+        };
+
+        if let Some(target) = target {
+            interaction_state.focus_with_keyboard(&target);
+            tabbed |= key == KeyCode::Tab;
+        }
+    }
+
+    if tabbed {
+        user_input.text_input.retain(|c| c != '\t');
+
+        if user_input.text_input.is_empty() {
+            user_input
+                .text_input_actions
+                .retain(|action| !matches!(action, TextInputAction::Insert));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn id(seed: u64) -> WidgetId {
+        WidgetId::auto_with_seed(seed)
+    }
+
+    /// `a`, a group of `b`, `c` with `c` as the primary member, and `d`.
+    fn chain() -> FocusChain {
+        let mut chain = FocusChain::default();
+
+        chain.push_stop(id(1));
+        chain.begin_group(id(10), 1, FocusAxis::Horizontal);
+        chain.push_stop(id(2));
+        chain.push_stop(id(3));
+        chain.end_group();
+        chain.push_stop(id(4));
+
+        chain
+    }
+
+    #[test]
+    fn test_tab_treats_group_as_single_stop() {
+        let chain = chain();
+
+        assert_eq!(chain.tab(None, false), Some(id(1)));
+        assert_eq!(chain.tab(Some(id(1)), false), Some(id(3)));
+        assert_eq!(chain.tab(Some(id(2)), false), Some(id(4)));
+        assert_eq!(chain.tab(Some(id(4)), true), Some(id(3)));
+        assert_eq!(chain.tab(Some(id(4)), false), Some(id(1)));
+    }
+
+    #[test]
+    fn test_tab_is_kept_by_stop_accepting_it() {
+        let mut chain = chain();
+        chain.push_stop_accepting_tab(id(5));
+
+        assert_eq!(chain.tab(Some(id(4)), false), Some(id(5)));
+        assert_eq!(chain.tab(Some(id(1)), true), Some(id(5)));
+        assert_eq!(chain.tab(Some(id(5)), false), None);
+        assert_eq!(chain.tab(Some(id(5)), true), None);
+    }
+
+    #[test]
+    fn test_arrows_move_inside_group() {
+        let chain = chain();
+
+        assert_eq!(chain.arrow(id(3), KeyCode::ArrowLeft), Some(id(2)));
+        assert_eq!(chain.arrow(id(2), KeyCode::ArrowLeft), None);
+        assert_eq!(chain.arrow(id(2), KeyCode::ArrowDown), None);
+        assert_eq!(chain.arrow(id(1), KeyCode::ArrowRight), None);
+    }
+
+    #[test]
+    fn test_escape_returns_to_group() {
+        let mut chain = chain();
+        chain.begin_group(id(20), 0, FocusAxis::Both);
+        chain.push_stop(id(5));
+        chain.begin_group(id(21), 0, FocusAxis::Both);
+        chain.push_stop(id(6));
+        chain.end_group();
+        chain.end_group();
+
+        assert_eq!(chain.escape(id(3)), Some(id(10)));
+        assert_eq!(chain.enter(id(10)), Some(id(3)));
+        assert_eq!(chain.escape(id(6)), Some(id(21)));
+        assert_eq!(chain.escape(id(21)), Some(id(20)));
+        assert_eq!(chain.arrow(id(21), KeyCode::ArrowUp), Some(id(5)));
+        assert_eq!(chain.tab(Some(id(21)), false), Some(id(1)));
+        assert_eq!(chain.escape(id(1)), None);
+    }
+}
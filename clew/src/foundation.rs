@@ -1,4 +1,5 @@
 use smallvec::{SmallVec, smallvec};
+use std::f32::consts::{FRAC_PI_2, PI};
 use std::ops::{Add, AddAssign, Div, DivAssign, Mul, MulAssign, Neg, Sub, SubAssign};
 
 pub trait Value<V> {
@@ -42,6 +43,72 @@ pub enum ClipShape {
     Oval,
 }
 
+/// How an image is fitted into the boundary of its widget.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ImageFit {
+    /// Stretched to the boundary.
+    #[default]
+    Fill,
+    /// Scaled to fit inside of the boundary keeping its aspect ratio, centered.
+    Contain,
+    /// Scaled to cover the boundary keeping its aspect ratio, centered and cut to it.
+    Cover,
+}
+
+impl ImageFit {
+    /// Rect the image of the size is drawn into, the part of it outside of the
+    /// boundary is cut by the renderers.
+    pub fn rect(self, boundary: Rect, width: f32, height: f32) -> Rect {
+        if width <= 0. || height <= 0. {
+            return boundary;
+        }
+
+        let scale_x = boundary.width / width;
+        let scale_y = boundary.height / height;
+        let scale = match self {
+            ImageFit::Fill => return boundary,
+            ImageFit::Contain => scale_x.min(scale_y),
+            ImageFit::Cover => scale_x.max(scale_y),
+        };
+        let width = width * scale;
+        let height = height * scale;
+
+        Rect::new(
+            boundary.x + (boundary.width - width) / 2.,
+            boundary.y + (boundary.height - height) / 2.,
+            width,
+            height,
+        )
+    }
+}
+
+/// How the tint of an SVG is applied to its pixels. The alpha of the pixels is
+/// multiplied by the alpha of the tint in both modes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum TintMode {
+    /// The colors are replaced with the tint keeping their alpha, e.g. for icons.
+    #[default]
+    Replace,
+    /// The colors are multiplied by the tint, so the white parts take the tint and the
+    /// black ones stay black.
+    Multiply,
+}
+
+impl TintMode {
+    /// Color of a pixel tinted with the tint, both with straight alpha.
+    pub fn apply(self, color: ColorRgba, tint: ColorRgba) -> ColorRgba {
+        match self {
+            TintMode::Replace => tint.with_opacity(tint.a * color.a),
+            TintMode::Multiply => ColorRgba {
+                r: color.r * tint.r,
+                g: color.g * tint.g,
+                b: color.b * tint.b,
+                a: color.a * tint.a,
+            },
+        }
+    }
+}
+
 impl Clip {
     pub fn to_shape(self) -> Option<ClipShape> {
         match self {
@@ -53,7 +120,7 @@ impl Clip {
     }
 }
 
-#[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash)]
 pub enum LayoutDirection {
     #[default]
     LTR,
@@ -189,6 +256,30 @@ pub enum TextAlign {
     Justified,
 }
 
+/// Base direction of the paragraphs of a text.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TextDirection {
+    /// Each paragraph takes the direction of its first strong directional character,
+    /// paragraphs without one follow the layout direction.
+    #[default]
+    Auto,
+    Ltr,
+    Rtl,
+}
+
+impl TextDirection {
+    /// Base direction of a single paragraph.
+    pub fn resolve(self, paragraph: &str, layout_direction: LayoutDirection) -> LayoutDirection {
+        match self {
+            TextDirection::Auto => {
+                crate::text::paragraph_direction(paragraph).unwrap_or(layout_direction)
+            }
+            TextDirection::Ltr => LayoutDirection::LTR,
+            TextDirection::Rtl => LayoutDirection::RTL,
+        }
+    }
+}
+
 impl TextAlign {
     pub(crate) fn to_align_x(self) -> AlignX {
         match self {
@@ -438,6 +529,16 @@ impl EdgeInsets {
     pub fn vertical(&self) -> f32 {
         self.top + self.bottom
     }
+
+    /// The larger of the insets on each side.
+    pub fn max(self, other: EdgeInsets) -> Self {
+        Self {
+            top: self.top.max(other.top),
+            left: self.left.max(other.left),
+            right: self.right.max(other.right),
+            bottom: self.bottom.max(other.bottom),
+        }
+    }
 }
 
 impl Add<EdgeInsets> for EdgeInsets {
@@ -453,6 +554,47 @@ impl Add<EdgeInsets> for EdgeInsets {
     }
 }
 
+/// Where a background or foreground is drawn relative to the rect of the decorated
+/// widget, the layout of the widget itself stays the same.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct DecoratorPlacement {
+    /// Shrinks the decorated rect, negative values grow it outside of the widget.
+    pub insets: EdgeInsets,
+    /// Fixed sizes are aligned inside the inset rect, other constraints fill it.
+    pub size: Option<Size>,
+    pub align: (AlignX, AlignY),
+}
+
+impl DecoratorPlacement {
+    pub fn resolve(&self, rect: Rect, layout_direction: LayoutDirection) -> Rect {
+        let area = Rect::new(
+            rect.x + self.insets.left,
+            rect.y + self.insets.top,
+            rect.width - self.insets.horizontal(),
+            rect.height - self.insets.vertical(),
+        );
+
+        let Some(size) = self.size else {
+            return area;
+        };
+
+        let extent = |constraint: SizeConstraint, available: f32| match constraint {
+            SizeConstraint::Fixed(value) => value,
+            SizeConstraint::Fill(_) | SizeConstraint::Wrap => available,
+        };
+        let width = extent(size.width, area.width);
+        let height = extent(size.height, area.height);
+        let (align_x, align_y) = self.align;
+
+        Rect::new(
+            area.x + align_x.position(layout_direction, area.width, width),
+            area.y + align_y.position(area.height, height),
+            width,
+            height,
+        )
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct Constraints {
     pub min_width: f32,
@@ -745,6 +887,113 @@ impl Rect {
             height: self.height,
         }
     }
+
+    /// Returns the overlapping area of two rects. The result has zero width or height
+    /// if they don't overlap.
+    pub fn intersect(&self, other: Rect) -> Rect {
+        let left = self.left().max(other.left());
+        let top = self.top().max(other.top());
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+
+        Rect {
+            x: left,
+            y: top,
+            width: (right - left).max(0.),
+            height: (bottom - top).max(0.),
+        }
+    }
+
+    /// Returns the smallest rect containing both rects.
+    pub fn union(&self, other: Rect) -> Rect {
+        let left = self.left().min(other.left());
+        let top = self.top().min(other.top());
+        let right = self.right().max(other.right());
+        let bottom = self.bottom().max(other.bottom());
+
+        Rect::new(left, top, right - left, bottom - top)
+    }
+
+    pub fn contains_rect(&self, other: Rect) -> bool {
+        self.left() <= other.left()
+            && self.top() <= other.top()
+            && self.right() >= other.right()
+            && self.bottom() >= other.bottom()
+    }
+}
+
+/// Scale of each axis followed by a translation, maps local coordinates to the parent
+/// space.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vec2,
+    pub scale: Vec2,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec2::ZERO,
+        scale: Vec2 { x: 1., y: 1. },
+    };
+
+    /// Scales both axes by `scale`.
+    pub fn new(translation: Vec2, scale: f32) -> Self {
+        Self {
+            translation,
+            scale: Vec2::new(scale, scale),
+        }
+    }
+
+    /// Scales the axes by the factors of `scale`, e.g. to stretch along one of them.
+    pub fn non_uniform(translation: Vec2, scale: Vec2) -> Self {
+        Self { translation, scale }
+    }
+
+    pub fn apply(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            point.x * self.scale.x + self.translation.x,
+            point.y * self.scale.y + self.translation.y,
+        )
+    }
+
+    pub fn apply_inverse(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            (point.x - self.translation.x) / self.scale.x,
+            (point.y - self.translation.y) / self.scale.y,
+        )
+    }
+
+    pub fn apply_rect(&self, rect: Rect) -> Rect {
+        let size = rect.size();
+
+        Rect::from_pos_size(
+            self.apply(rect.position()),
+            Vec2::new(size.x * self.scale.x, size.y * self.scale.y),
+        )
+    }
+
+    pub fn apply_inverse_rect(&self, rect: Rect) -> Rect {
+        let size = rect.size();
+
+        Rect::from_pos_size(
+            self.apply_inverse(rect.position()),
+            Vec2::new(size.x / self.scale.x, size.y / self.scale.y),
+        )
+    }
+
+    /// Transform that applies `self` first and `outer` after it.
+    pub fn then(&self, outer: Transform) -> Transform {
+        Transform {
+            translation: outer.apply(self.translation),
+            scale: Vec2::new(self.scale.x * outer.scale.x, self.scale.y * outer.scale.y),
+        }
+    }
 }
 
 pub fn point_with_rect_hit_test(point: Vec2, rect: Rect) -> bool {
@@ -766,6 +1015,8 @@ pub fn rect_contains_boundary(boundary: Rect, rect: Rect) -> bool {
         || point_with_rect_hit_test(right_bottom, rect)
 }
 
+/// Color in the sRGB color space, the components are sRGB encoded values in 0..1 the
+/// same way as in CSS, so `0xFF2F2F2F` is drawn as exactly these 8-bit values.
 #[derive(Debug, Clone, PartialEq, Copy)]
 pub struct ColorRgb {
     pub r: f32,
@@ -773,6 +1024,9 @@ pub struct ColorRgb {
     pub b: f32,
 }
 
+/// sRGB color with a straight alpha, see [`ColorRgb`]. Colors outside of the sRGB
+/// gamut, e.g. from [`ColorRgba::from_display_p3`], have components beyond 0..1 and
+/// are clipped to the gamut when drawn.
 #[derive(Default, Debug, Clone, PartialEq, Copy)]
 pub struct ColorRgba {
     pub r: f32,
@@ -789,14 +1043,16 @@ pub struct ColorOkLab {
 }
 
 impl ColorRgb {
-    pub fn new(r: f32, b: f32, g: f32) -> Self {
+    /// Takes the channels in the (r, g, b) order, earlier versions took them as (r, b, g).
+    pub fn new(r: f32, g: f32, b: f32) -> Self {
         ColorRgb { r, g, b }
     }
 
     pub fn with_alpha(&self, a: f32) -> ColorRgba {
-        ColorRgba::new(self.r, self.b, self.g, a)
+        ColorRgba::new(self.r, self.g, self.b, a)
     }
 
+    /// `0xRRGGBB`, see [`ColorRgb::from_css`] for the strings.
     pub fn from_hex(hex: u32) -> Self {
         Self {
             r: ((hex & 0xFF0000) >> 16) as f32 / 255.,
@@ -806,13 +1062,20 @@ impl ColorRgb {
     }
 
     pub fn to_hex(&self) -> u32 {
-        let r = (self.r * 255.) as u32;
-        let g = (self.g * 255.) as u32;
-        let b = (self.b * 255.) as u32;
+        let [r, g, b] = self.to_rgb8().map(u32::from);
 
         (r << 16) | (g << 8) | b
     }
 
+    /// 8-bit sRGB values the renderers draw the color with.
+    pub fn to_rgb8(&self) -> [u8; 3] {
+        [
+            component_to_u8(self.r),
+            component_to_u8(self.g),
+            component_to_u8(self.b),
+        ]
+    }
+
     /// Source: https://bottosson.github.io/posts/oklab/
     pub fn to_oklab(&self) -> ColorOkLab {
         let r = self.r as f64;
@@ -847,10 +1110,29 @@ impl ColorRgba {
         ColorRgba::new(0., 0., 0., 0.)
     }
 
-    pub fn new(r: f32, b: f32, g: f32, a: f32) -> Self {
+    /// Takes the channels in the (r, g, b, a) order, earlier versions took them as
+    /// (r, b, g, a).
+    pub fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
         ColorRgba { r, g, b, a }
     }
 
+    /// Converts a color from the Display P3 color space, the one of the wide gamut
+    /// displays, the colors outside of the sRGB gamut keep the components beyond 0..1.
+    /// There's no wide gamut output yet, both renderers draw into 8-bit sRGB targets
+    /// and clip these colors to the sRGB gamut.
+    pub fn from_display_p3(r: f32, g: f32, b: f32, a: f32) -> Self {
+        let r = srgb_to_linear(r);
+        let g = srgb_to_linear(g);
+        let b = srgb_to_linear(b);
+
+        Self {
+            r: linear_to_srgb(1.224_940_2 * r - 0.224_940_4 * g),
+            g: linear_to_srgb(-0.042_056_955 * r + 1.042_057_1 * g),
+            b: linear_to_srgb(-0.019_637_555 * r - 0.078_636_05 * g + 1.098_273_6 * b),
+            a,
+        }
+    }
+
     pub fn to_rgb(&self) -> ColorRgb {
         ColorRgb {
             r: self.r,
@@ -863,6 +1145,8 @@ impl ColorRgba {
         [self.r, self.g, self.b, self.a]
     }
 
+    /// `0xAARRGGBB`, the alpha goes first unlike in the `#RRGGBBAA` of CSS, see
+    /// [`ColorRgba::from_css`].
     pub fn from_hex(hex: u32) -> Self {
         Self {
             r: ((hex & 0x00FF0000) >> 16) as f32 / 255.,
@@ -873,14 +1157,21 @@ impl ColorRgba {
     }
 
     pub fn to_hex(&self) -> u32 {
-        let r = (self.r * 255.) as u32;
-        let g = (self.g * 255.) as u32;
-        let b = (self.b * 255.) as u32;
-        let a = (self.a * 255.) as u32;
+        let [r, g, b, a] = self.to_rgba8().map(u32::from);
 
         (a << 24) | (r << 16) | (g << 8) | b
     }
 
+    /// 8-bit sRGB values with a straight alpha the renderers draw the color with.
+    pub fn to_rgba8(&self) -> [u8; 4] {
+        [
+            component_to_u8(self.r),
+            component_to_u8(self.g),
+            component_to_u8(self.b),
+            component_to_u8(self.a),
+        ]
+    }
+
     pub fn with_opacity(&self, opacity: f32) -> Self {
         Self {
             r: self.r,
@@ -891,6 +1182,35 @@ impl ColorRgba {
     }
 }
 
+/// Rounds to the nearest value, truncation would turn e.g. `0x2F` into `0x2E` when
+/// the value comes back from the float slightly below it.
+fn component_to_u8(value: f32) -> u8 {
+    (value.clamp(0., 1.) * 255.).round() as u8
+}
+
+/// sRGB transfer function, extended to the negative values by symmetry.
+fn srgb_to_linear(value: f32) -> f32 {
+    let abs = value.abs();
+    let linear = if abs <= 0.04045 {
+        abs / 12.92
+    } else {
+        ((abs + 0.055) / 1.055).powf(2.4)
+    };
+
+    linear.copysign(value)
+}
+
+fn linear_to_srgb(value: f32) -> f32 {
+    let abs = value.abs();
+    let encoded = if abs <= 0.003_130_8 {
+        abs * 12.92
+    } else {
+        1.055 * abs.powf(1. / 2.4) - 0.055
+    };
+
+    encoded.copysign(value)
+}
+
 impl ColorOkLab {
     /// Source: https://bottosson.github.io/posts/oklab/
     pub fn to_rgb(&self) -> ColorRgb {
@@ -932,6 +1252,20 @@ pub struct BorderSide {
     pub color: ColorRgba,
 }
 
+/// Shadow cast by a box, e.g. by a floating panel. It's drawn behind the box in its
+/// shape, rounded by the same radii. The shadow isn't cut out under the box, a box with
+/// a translucent color shows the shadow through it.
+#[derive(Debug, Clone, PartialEq, Copy)]
+pub struct BoxShadow {
+    pub offset: Vec2,
+    /// Distance the edges of the shadow fade over, as in CSS. Zero draws a sharp
+    /// shadow.
+    pub blur_radius: f32,
+    /// Grows the shadow on every side before it's blurred, negative values shrink it.
+    pub spread: f32,
+    pub color: ColorRgba,
+}
+
 impl BorderRadius {
     pub const ZERO: Self = Self {
         top_left: 0.,
@@ -1021,6 +1355,17 @@ impl BorderRadius {
     }
 }
 
+impl BoxShadow {
+    pub fn new(offset: Vec2, blur_radius: f32, spread: f32, color: ColorRgba) -> Self {
+        Self {
+            offset,
+            blur_radius,
+            spread,
+            color,
+        }
+    }
+}
+
 impl Border {
     /// Creates a Border with individual sides
     pub fn new(
@@ -1116,6 +1461,278 @@ impl Border {
             left: None,
         }
     }
+
+    /// Width of the widest side.
+    pub fn max_width(&self) -> f32 {
+        [self.top, self.right, self.bottom, self.left]
+            .into_iter()
+            .flatten()
+            .fold(0., |width, side| width.max(side.width))
+    }
+
+    /// The side when all four sides are the same, the border is a single stroke along
+    /// the edges of the rect then.
+    pub fn uniform(&self) -> Option<BorderSide> {
+        let side = self.top?;
+
+        [self.right, self.bottom, self.left]
+            .iter()
+            .all(|it| *it == Some(side))
+            .then_some(side)
+    }
+
+    /// Pieces of the border around the rect, filled with the even-odd rule. Each side is
+    /// centered on its edge like a stroke, and a rounded corner is split between the
+    /// adjacent sides in proportion to their widths, the side without a width gets none
+    /// of it. When the visible sides have one color the border is a single piece, so no
+    /// seams show between the sides, otherwise each visible side is a piece of its own.
+    pub fn resolve(&self, rect: Rect, border_radius: Option<&BorderRadius>) -> Vec<BorderPiece> {
+        let sides = [self.top, self.right, self.bottom, self.left];
+        let visible: SmallVec<[BorderSide; 4]> = sides
+            .into_iter()
+            .flatten()
+            .filter(|side| side.width > 0.)
+            .collect();
+
+        let Some(first) = visible.first() else {
+            return Vec::new();
+        };
+
+        let corners = BorderCorner::resolve(rect, sides, border_radius);
+
+        if visible.iter().all(|side| side.color == first.color) {
+            let mut outline = Vec::new();
+
+            for inner in [false, true] {
+                for (index, corner) in corners.iter().enumerate() {
+                    let ellipse = if inner { &corner.inner } else { &corner.outer };
+                    let start = ellipse.point(corner.start);
+
+                    outline.push(if index == 0 {
+                        PathSegment::MoveTo(start.0, start.1)
+                    } else {
+                        PathSegment::LineTo(start.0, start.1)
+                    });
+                    ellipse.arc(&mut outline, corner.start, corner.start + FRAC_PI_2);
+                }
+
+                outline.push(PathSegment::Close);
+            }
+
+            return vec![BorderPiece {
+                color: first.color,
+                outline,
+            }];
+        }
+
+        let mut pieces = Vec::new();
+
+        for (index, side) in sides.into_iter().enumerate() {
+            let Some(side) = side.filter(|side| side.width > 0.) else {
+                continue;
+            };
+
+            // The side runs from the corner of the same index to the next one.
+            let from = &corners[index];
+            let to = &corners[(index + 1) % 4];
+            let (from_end, to_start) = (from.start + FRAC_PI_2, to.start);
+            let mut outline = Vec::new();
+
+            let start = from.outer.point(from.split);
+            outline.push(PathSegment::MoveTo(start.0, start.1));
+            from.outer.arc(&mut outline, from.split, from_end);
+
+            let point = to.outer.point(to_start);
+            outline.push(PathSegment::LineTo(point.0, point.1));
+            to.outer.arc(&mut outline, to_start, to.split);
+
+            let point = to.inner.point(to.split);
+            outline.push(PathSegment::LineTo(point.0, point.1));
+            to.inner.arc(&mut outline, to.split, to_start);
+
+            let point = from.inner.point(from_end);
+            outline.push(PathSegment::LineTo(point.0, point.1));
+            from.inner.arc(&mut outline, from_end, from.split);
+            outline.push(PathSegment::Close);
+
+            pieces.push(BorderPiece {
+                color: side.color,
+                outline,
+            });
+        }
+
+        pieces
+    }
+}
+
+/// Segment of an outline resolved by clew, the backends build their paths of them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PathSegment {
+    MoveTo(f32, f32),
+    LineTo(f32, f32),
+    /// Two control points and the end point.
+    CubicTo((f32, f32), (f32, f32), (f32, f32)),
+    Close,
+}
+
+/// Part of a border painted with one color, see [`Border::resolve`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BorderPiece {
+    pub color: ColorRgba,
+    pub outline: Vec<PathSegment>,
+}
+
+/// Quarter of an ellipse at a corner of a border, the radii are 0 for a square corner.
+struct CornerEllipse {
+    center: (f32, f32),
+    radii: (f32, f32),
+}
+
+impl CornerEllipse {
+    fn point(&self, angle: f32) -> (f32, f32) {
+        (
+            self.center.0 + self.radii.0 * angle.cos(),
+            self.center.1 + self.radii.1 * angle.sin(),
+        )
+    }
+
+    /// Appends the arc from the point at `from` to the one at `to`, at most a quarter
+    /// of the ellipse, as a single cubic.
+    fn arc(&self, outline: &mut Vec<PathSegment>, from: f32, to: f32) {
+        if self.radii.0 <= 0. || self.radii.1 <= 0. || from == to {
+            return;
+        }
+
+        let k = 4. / 3. * ((to - from) / 4.).tan();
+        let tangent = |angle: f32| {
+            (
+                -self.radii.0 * angle.sin() * k,
+                self.radii.1 * angle.cos() * k,
+            )
+        };
+        let (start, end) = (self.point(from), self.point(to));
+        let (start_tangent, end_tangent) = (tangent(from), tangent(to));
+
+        outline.push(PathSegment::CubicTo(
+            (start.0 + start_tangent.0, start.1 + start_tangent.1),
+            (end.0 - end_tangent.0, end.1 - end_tangent.1),
+            end,
+        ));
+    }
+}
+
+/// Corner of a border between the outer and the inner edges of the sides meeting in it.
+/// The corner spans the angles from `start` to a quarter turn after it, the side before
+/// it clockwise takes the angles up to `split` and the side after it the rest.
+struct BorderCorner {
+    outer: CornerEllipse,
+    inner: CornerEllipse,
+    start: f32,
+    split: f32,
+}
+
+impl BorderCorner {
+    /// Corners from the top left one clockwise, the sides are from the top one.
+    fn resolve(
+        rect: Rect,
+        sides: [Option<BorderSide>; 4],
+        border_radius: Option<&BorderRadius>,
+    ) -> [BorderCorner; 4] {
+        let [top, right, bottom, left] =
+            sides.map(|side| side.map_or(0., |side| side.width.max(0.)) / 2.);
+        let radius = border_radius.copied().unwrap_or_default();
+        let max_radius = (rect.width.min(rect.height) / 2.).max(0.);
+        let radii = [
+            radius.top_left,
+            radius.top_right,
+            radius.bottom_right,
+            radius.bottom_left,
+        ]
+        .map(|radius| radius.clamp(0., max_radius));
+
+        // The inner edges don't cross when the sides are wider than the rect.
+        let inner_x = clamp_span(rect.x + left, rect.x + rect.width - right);
+        let inner_y = clamp_span(rect.y + top, rect.y + rect.height - bottom);
+
+        let corners = [
+            (
+                (-1., -1.),
+                (rect.x, rect.y),
+                (inner_x.0, inner_y.0),
+                (left, top),
+                PI,
+            ),
+            (
+                (1., -1.),
+                (rect.x + rect.width, rect.y),
+                (inner_x.1, inner_y.0),
+                (right, top),
+                PI + FRAC_PI_2,
+            ),
+            (
+                (1., 1.),
+                (rect.x + rect.width, rect.y + rect.height),
+                (inner_x.1, inner_y.1),
+                (right, bottom),
+                0.,
+            ),
+            (
+                (-1., 1.),
+                (rect.x, rect.y + rect.height),
+                (inner_x.0, inner_y.1),
+                (left, bottom),
+                FRAC_PI_2,
+            ),
+        ];
+
+        std::array::from_fn(|index| {
+            let ((sign_x, sign_y), point, inner_point, (half_x, half_y), start) = corners[index];
+            let radius = radii[index];
+            let outer_radii = if radius > 0. {
+                (radius + half_x, radius + half_y)
+            } else {
+                (0., 0.)
+            };
+            let inner_radii = ((radius - half_x).max(0.), (radius - half_y).max(0.));
+            // The side before the corner clockwise is the vertical one at the top left
+            // and the bottom right corners, the horizontal one at the others.
+            let (before, after) = if index % 2 == 0 {
+                (half_x, half_y)
+            } else {
+                (half_y, half_x)
+            };
+
+            BorderCorner {
+                outer: CornerEllipse {
+                    center: (
+                        point.0 + sign_x * (half_x - outer_radii.0),
+                        point.1 + sign_y * (half_y - outer_radii.1),
+                    ),
+                    radii: outer_radii,
+                },
+                inner: CornerEllipse {
+                    center: (
+                        inner_point.0 - sign_x * inner_radii.0,
+                        inner_point.1 - sign_y * inner_radii.1,
+                    ),
+                    radii: inner_radii,
+                },
+                start,
+                split: start + before.atan2(after),
+            }
+        })
+    }
+}
+
+/// Start and end of a span, met in the middle when the start is past the end.
+fn clamp_span(start: f32, end: f32) -> (f32, f32) {
+    if start <= end {
+        (start, end)
+    } else {
+        let middle = (start + end) / 2.;
+
+        (middle, middle)
+    }
 }
 
 impl BorderSide {
@@ -1148,11 +1765,14 @@ pub enum Gradient {
 pub struct RadialGradient {
     /// Center point (normalized 0.0 to 1.0)
     pub center: (f32, f32),
-    /// Radius (normalized, typically 0.0 to 1.0)
-    pub radius: f32,
-    /// Optional focal point for elliptical gradients
+    /// Horizontal radius (normalized to the larger side of the rect, typically 0.0 to 1.0)
+    pub radius_x: f32,
+    /// Vertical radius, normalized like `radius_x`
+    pub radius_y: f32,
+    /// Point where the gradient starts (normalized 0.0 to 1.0), the center if not set
     pub focal: Option<(f32, f32)>,
-    /// Optional focal radius
+    /// Radius of the circle around the focal point where the gradient starts, normalized
+    /// like `radius_x`. Not supported by tiny-skia.
     pub focal_radius: Option<f32>,
     /// Color stops
     pub stops: Vec<ColorStop>,
@@ -1304,6 +1924,20 @@ impl LinearGradient {
         self
     }
 
+    /// Start and end points of the gradient filling the rect.
+    pub fn resolve(&self, rect: Rect) -> ((f32, f32), (f32, f32)) {
+        (
+            (
+                rect.x + self.start.0 * rect.width,
+                rect.y + self.start.1 * rect.height,
+            ),
+            (
+                rect.x + self.end.0 * rect.width,
+                rect.y + self.end.1 * rect.height,
+            ),
+        )
+    }
+
     fn even_stops(colors: Vec<ColorRgba>) -> Vec<ColorStop> {
         let count = colors.len();
         if count == 0 {
@@ -1404,7 +2038,8 @@ impl RadialGradient {
     pub fn circle(colors: Vec<ColorRgba>) -> Self {
         Self {
             center: (0.5, 0.5),
-            radius: 0.5,
+            radius_x: 0.5,
+            radius_y: 0.5,
             focal: None,
             focal_radius: None,
             stops: LinearGradient::even_stops(colors),
@@ -1413,15 +2048,93 @@ impl RadialGradient {
     }
 
     pub fn new(center: (f32, f32), radius: f32, stops: Vec<ColorStop>) -> Self {
+        Self::ellipse(center, radius, radius, stops)
+    }
+
+    pub fn ellipse(
+        center: (f32, f32),
+        radius_x: f32,
+        radius_y: f32,
+        stops: Vec<ColorStop>,
+    ) -> Self {
         Self {
             center,
-            radius,
+            radius_x,
+            radius_y,
             focal: None,
             focal_radius: None,
             stops,
             tile_mode: TileMode::Clamp,
         }
     }
+
+    /// Sets both radii, making the gradient a circle.
+    pub fn with_radius(mut self, radius: f32) -> Self {
+        self.radius_x = radius;
+        self.radius_y = radius;
+        self
+    }
+
+    pub fn with_focal(mut self, focal: (f32, f32)) -> Self {
+        self.focal = Some(focal);
+        self
+    }
+
+    pub fn with_focal_radius(mut self, radius: f32) -> Self {
+        self.focal_radius = Some(radius);
+        self
+    }
+
+    pub fn with_tile_mode(mut self, mode: TileMode) -> Self {
+        self.tile_mode = mode;
+        self
+    }
+
+    /// Geometry of the gradient filling the rect. Returns `None` if either radius is
+    /// empty.
+    pub fn resolve(&self, rect: Rect) -> Option<RadialGradientGeometry> {
+        let extent = rect.width.max(rect.height);
+        let radius = self.radius_x * extent;
+        let radius_y = self.radius_y * extent;
+
+        if radius <= 0. || radius_y <= 0. {
+            return None;
+        }
+
+        let center = (
+            rect.x + self.center.0 * rect.width,
+            rect.y + self.center.1 * rect.height,
+        );
+        let scale_y = radius_y / radius;
+        let focal = self.focal.map_or((0., 0.), |focal| {
+            (
+                rect.x + focal.0 * rect.width - center.0,
+                (rect.y + focal.1 * rect.height - center.1) / scale_y,
+            )
+        });
+
+        Some(RadialGradientGeometry {
+            center,
+            scale_y,
+            radius,
+            focal,
+            focal_radius: self.focal_radius.map_or(0., |radius| radius * extent),
+        })
+    }
+}
+
+/// A radial gradient resolved against the rect it fills. The gradient is circular in its
+/// own space, which is scaled by `scale_y` vertically and moved to `center` to make the
+/// ellipse, so the backends draw the circle with that transform.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RadialGradientGeometry {
+    pub center: (f32, f32),
+    pub scale_y: f32,
+    /// Radius of the end circle, centered at the origin.
+    pub radius: f32,
+    /// Center of the start circle.
+    pub focal: (f32, f32),
+    pub focal_radius: f32,
 }
 
 impl SweepGradient {
@@ -1471,12 +2184,9 @@ impl From<cosmic_text::Color> for ColorRgba {
 
 impl From<ColorRgba> for cosmic_text::Color {
     fn from(value: ColorRgba) -> Self {
-        Self::rgba(
-            (value.r * 255.).clamp(0., 255.) as u8,
-            (value.g * 255.).clamp(0., 255.) as u8,
-            (value.b * 255.).clamp(0., 255.) as u8,
-            (value.a * 255.).clamp(0., 255.) as u8,
-        )
+        let [r, g, b, a] = value.to_rgba8();
+
+        Self::rgba(r, g, b, a)
     }
 }
 
@@ -0,0 +1,213 @@
+//! Ids of the widgets built in a frame, to check that a refactoring of the build code
+//! keeps the ids of the widgets and so their retained state, e.g. scroll offsets and
+//! the content of text fields.
+//!
+//! A map is captured by [`crate::BuildContext::capture_id_map`], two maps are compared
+//! by [`IdMap::diff`], and [`crate::ClewInstance::id_stability_report`] does both for
+//! the old and the new build code.
+
+use std::{fmt, panic::Location};
+
+use rustc_hash::FxHashSet;
+
+use crate::{WidgetId, WidgetType, widget_id::WidgetIdInfo};
+
+/// Widget with a retained state built in the frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdEntry {
+    pub id: WidgetId,
+    pub widget_type: WidgetType,
+    /// See [`crate::WidgetKey::key_path`].
+    pub key_path: Option<u64>,
+    pub location: Option<&'static Location<'static>>,
+}
+
+impl IdEntry {
+    pub(crate) fn new(id: WidgetId, widget_type: WidgetType, info: Option<WidgetIdInfo>) -> Self {
+        Self {
+            id,
+            widget_type,
+            key_path: info.and_then(|info| info.key_path),
+            location: info.map(|info| info.location),
+        }
+    }
+
+    /// Whether the entries are likely the same logical widget.
+    fn same_widget(&self, other: &IdEntry) -> bool {
+        self.widget_type == other.widget_type && self.key_path == other.key_path
+    }
+}
+
+impl fmt::Display for IdEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.widget_type.name())?;
+
+        if let Some(key_path) = self.key_path {
+            write!(f, " key {key_path:016x}")?;
+        }
+
+        match self.location {
+            Some(location) => write!(f, " at {location}"),
+            None => write!(f, " at an unknown location"),
+        }
+    }
+}
+
+/// Ids of the widgets built in a frame, in the order their states were created.
+#[derive(Debug, Default, Clone)]
+pub struct IdMap {
+    entries: Vec<IdEntry>,
+}
+
+impl IdMap {
+    pub(crate) fn push(&mut self, entry: IdEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn entries(&self) -> &[IdEntry] {
+        &self.entries
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Compares the map of the old build code to the one of the `new` code. The entries
+    /// whose ids are gone are matched to the new entries of the same type and key path
+    /// in the order they were built, the ones left over are lost or added.
+    pub fn diff(&self, new: &IdMap) -> IdMapDiff {
+        let old_ids = self
+            .entries
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<FxHashSet<_>>();
+        let new_ids = new
+            .entries
+            .iter()
+            .map(|entry| entry.id)
+            .collect::<FxHashSet<_>>();
+
+        let mut diff = IdMapDiff::default();
+        let mut added = new
+            .entries
+            .iter()
+            .filter(|entry| !old_ids.contains(&entry.id))
+            .copied()
+            .collect::<Vec<_>>();
+
+        for entry in &self.entries {
+            if new_ids.contains(&entry.id) {
+                diff.stable.push(*entry);
+            } else if let Some(index) = added.iter().position(|new| entry.same_widget(new)) {
+                diff.moved.push((*entry, added.remove(index)));
+            } else {
+                diff.lost.push(*entry);
+            }
+        }
+
+        diff.added = added;
+
+        diff
+    }
+}
+
+/// Result of [`IdMap::diff`], its [`fmt::Display`] is a report to read.
+#[derive(Debug, Default, Clone)]
+pub struct IdMapDiff {
+    /// Widgets with the same id, they keep their state.
+    pub stable: Vec<IdEntry>,
+    /// Old and new entries with the same type and key path but another id, e.g. the
+    /// builder has been moved to another line. Their state is reset.
+    pub moved: Vec<(IdEntry, IdEntry)>,
+    /// Old widgets nothing in the new build matches, their state is dropped.
+    pub lost: Vec<IdEntry>,
+    /// New widgets nothing in the old build matches.
+    pub added: Vec<IdEntry>,
+}
+
+impl IdMapDiff {
+    /// Whether every old widget keeps its state.
+    pub fn is_stable(&self) -> bool {
+        self.moved.is_empty() && self.lost.is_empty()
+    }
+}
+
+impl fmt::Display for IdMapDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} stable, {} moved, {} lost, {} added",
+            self.stable.len(),
+            self.moved.len(),
+            self.lost.len(),
+            self.added.len()
+        )?;
+
+        for (old, new) in &self.moved {
+            writeln!(f, "  moved: {old}")?;
+            writeln!(f, "     to: {new}")?;
+        }
+
+        for entry in &self.lost {
+            writeln!(f, "   lost: {entry}")?;
+        }
+
+        for entry in &self.added {
+            writeln!(f, "  added: {entry}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing,
+        widgets::{
+            builder::{BuildContext, WidgetBuilder},
+            text::text,
+        },
+    };
+
+    fn header(ctx: &mut BuildContext) {
+        text("Title").build(ctx);
+    }
+
+    #[test]
+    fn test_refactoring_is_reported() {
+        let (mut host, mut instance) = testing::instance(200, 100);
+
+        let diff = instance.id_stability_report(
+            &mut host,
+            &mut |ctx| {
+                header(ctx);
+                text("Name").id("name").build(ctx);
+                text("Gone").build(ctx);
+            },
+            &mut |ctx| {
+                header(ctx);
+                text("Name").id("name").build(ctx);
+                text("New").id("new").build(ctx);
+            },
+        );
+
+        assert!(!diff.is_stable());
+        assert_eq!(diff.stable.len(), 1);
+        assert_eq!(diff.moved.len(), 1);
+        assert_eq!(diff.lost.len(), 1);
+        assert_eq!(diff.added.len(), 1);
+
+        let (old, new) = diff.moved[0];
+        assert_eq!(old.key_path, new.key_path);
+        assert_ne!(old.location, new.location);
+        assert!(
+            diff.to_string()
+                .starts_with("1 stable, 1 moved, 1 lost, 1 added")
+        );
+    }
+}
@@ -0,0 +1,1339 @@
+//! Driving clew from an application that owns the event loop and the renderer.
+//!
+//! The host translates the events of its platform to [`InputEvent`]s and pushes them to
+//! the [`ClewInstance`] of the view, then builds a frame whenever it's about to draw
+//! and hands it to any [`Renderer`]. The views of the host share a [`ClewHost`].
+//!
+//! A [`ClewHost`] owns what is the same for every view: the assets, the resources and
+//! the events broadcast to all of the views. A [`ClewInstance`] owns one view: its
+//! [`UiState`], fonts, texts, interned strings, widget states and pending input. The
+//! instances borrow the host only for the length of a frame, so a host can drive any
+//! number of them. The windows, the surfaces, the clipboard, the IME and the cursor
+//! icons belong to the platform and stay with the host, `clew-desktop` is such a host
+//! for winit.
+//!
+//! A frame of a view goes as follows:
+//!
+//! 1. [`ClewInstance::push_event`] for each event of the platform, as they come.
+//! 2. [`ClewInstance::begin_frame`], which applies the input and returns the events
+//!    emitted to the view.
+//! 3. [`ClewInstance::build_frame`] with the build code of the view. It's
+//!    [`ClewInstance::build_widgets`], which needs the host and the app, followed by
+//!    [`ClewInstance::layout_frame`], which only reads the assets. The host may lay out
+//!    the views it has built on threads of their own, see [`ClewInstance::layout_frame`].
+//! 4. [`ClewInstance::take_os_events`] to apply the requests to the platform, e.g. to
+//!    turn the IME on.
+//! 5. [`ClewInstance::render`] with the renderer of the view.
+//!
+//! In between the frames the host applies the resource changes requested during the
+//! builds with [`Resources::apply_updates`], and hands [`ClewHost::broadcast_events`]
+//! to its own code before it clears them with [`ClewHost::clear_broadcast_events`].
+//! [`ClewInstance::frame`] does the steps 2 and 3 at once.
+//!
+//! A view that has settled doesn't have to be built for every frame, see
+//! [`ClewInstance::needs_build`], [`ClewInstance::repaint`] keeps it going in between,
+//! e.g. blinks the caret.
+
+use std::{
+    any::Any,
+    collections::HashMap,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use smallvec::SmallVec;
+
+use crate::{
+    Animation, ClewError, ColorRgb, EdgeInsets, LayoutDirection, PhysicalSize, Rect, Resources,
+    Transform, Vec2, View, WidgetId, WidgetIdMap,
+    announcement::Announcement,
+    assets::Assets,
+    capture::{SubtreeImageOptions, process_image_captures, subtree_image},
+    id_map::{IdMap, IdMapDiff},
+    io::{Cursor, ExternalFile, InputEvent, KeyEvent, MouseButton, TextInputAction, TouchPhase},
+    keyboard::{KeyCode, KeyModifiers},
+    lifecycle::{finalize_cycle, init_cycle},
+    render::{RenderState, Renderer, RgbaImage},
+    state::UiState,
+    text::{FontResources, StringId, StringInterner, TextId, TextsResources, TextsStats},
+    theme::SystemTheme,
+    widgets::{
+        builder::{ApplicationEvent, ApplicationEventLoopProxy, AsyncSender, BuildContext},
+        editable_text::OsEvent,
+        scroll_area,
+    },
+};
+
+type AsyncReceiver = tokio::sync::mpsc::UnboundedReceiver<Box<dyn Any + Send + Sync>>;
+
+/// What the views of an application share.
+pub struct ClewHost<'a> {
+    pub assets: Assets<'a>,
+    /// See [`crate::resources`], the updates requested during the builds are applied by
+    /// the host in between the frames with [`Resources::apply_updates`].
+    pub resources: Resources,
+    broadcast_event_queue: Vec<Arc<dyn Any + Send + Sync>>,
+    broadcast_async_tx: AsyncSender,
+    broadcast_async_rx: AsyncReceiver,
+    event_loop_proxy: Arc<dyn ApplicationEventLoopProxy>,
+}
+
+impl<'a> ClewHost<'a> {
+    /// The proxy wakes the event loop of the host, see
+    /// [`crate::widgets::builder::ApplicationEvent`].
+    pub fn new(
+        assets: Assets<'a>,
+        resources: Resources,
+        event_loop_proxy: Arc<dyn ApplicationEventLoopProxy>,
+    ) -> Self {
+        let (broadcast_async_tx, broadcast_async_rx) = tokio::sync::mpsc::unbounded_channel();
+
+        Self {
+            assets,
+            resources,
+            broadcast_event_queue: Vec::new(),
+            broadcast_async_tx,
+            broadcast_async_rx,
+            event_loop_proxy,
+        }
+    }
+
+    /// Events broadcast since they were last cleared, including the ones sent by the
+    /// async tasks until now. They are handed to the next built view.
+    pub fn broadcast_events(&mut self) -> &[Arc<dyn Any + Send + Sync>] {
+        while let Ok(event) = self.broadcast_async_rx.try_recv() {
+            self.broadcast_event_queue.push(event.into());
+        }
+
+        &self.broadcast_event_queue
+    }
+
+    pub fn clear_broadcast_events(&mut self) {
+        self.broadcast_event_queue.clear();
+    }
+}
+
+/// A view driven by the host, see the [module docs](self).
+pub struct ClewInstance<'a> {
+    ui_state: UiState,
+    texts: TextsResources<'a>,
+    fonts: FontResources,
+    string_interner: StringInterner,
+    strings: HashMap<StringId, TextId>,
+    pending_input: PendingInput,
+    modifiers: Option<KeyModifiers>,
+    key_code: Option<KeyCode>,
+    key_code_repeat: Option<KeyCode>,
+    /// Finger that drives the pointer until it's lifted.
+    touch_id: Option<u64>,
+    delta_time_timer: Instant,
+    paused: bool,
+    force_redraw: bool,
+    /// The last built frame has changed and hasn't been rendered yet.
+    needs_render: bool,
+    /// Something was moving in the last built frame, the next one is built too.
+    needs_build: bool,
+}
+
+impl<'a> ClewInstance<'a> {
+    /// The fonts are usually created with [`Assets::create_font_resources`] once and
+    /// forked for every view with [`FontResources::fork`], every view shapes its texts
+    /// with its own font system.
+    pub fn new(view: View, fonts: FontResources) -> Self {
+        Self {
+            ui_state: UiState::new(view),
+            texts: TextsResources::new(),
+            fonts,
+            string_interner: StringInterner::new(),
+            strings: HashMap::new(),
+            pending_input: PendingInput::default(),
+            modifiers: None,
+            key_code: None,
+            key_code_repeat: None,
+            touch_id: None,
+            delta_time_timer: Instant::now(),
+            paused: false,
+            force_redraw: false,
+            needs_render: false,
+            needs_build: true,
+        }
+    }
+
+    pub fn view(&self) -> &View {
+        &self.ui_state.view
+    }
+
+    pub fn ui_state(&mut self) -> &mut UiState {
+        &mut self.ui_state
+    }
+
+    /// Number and estimated size of the text buffers of the view, e.g. for a debug
+    /// overlay. The texts of the widgets that are gone are released after each frame.
+    pub fn texts_stats(&self) -> TextsStats {
+        self.texts.stats()
+    }
+
+    /// Takes the requests to the platform made by the widgets since the last call, e.g.
+    /// to commit the IME composition of a text field that has lost the focus.
+    pub fn take_os_events(&mut self) -> SmallVec<[OsEvent; 4]> {
+        std::mem::take(&mut self.ui_state.os_events)
+    }
+
+    /// Caret of the focused text field in physical pixels as of the last drawn frame,
+    /// the platform shows the candidate window of the input method next to it.
+    pub fn ime_cursor_rect(&self) -> Rect {
+        self.ui_state.view_config.ime_cursor_rect
+    }
+
+    /// Takes the announcements for the screen readers queued in the last frame, see
+    /// [`crate::announcement`].
+    pub fn take_announcements(&mut self) -> Vec<Announcement> {
+        std::mem::take(&mut self.ui_state.announcements.queued)
+    }
+
+    /// Takes the files dropped onto the view in the last frame outside of the drop
+    /// targets, or without a position on the platforms that don't report it, see
+    /// [`crate::file_drop_target`].
+    pub fn take_unclaimed_dropped_files(&mut self) -> Vec<PathBuf> {
+        std::mem::take(&mut self.ui_state.file_drops.unclaimed)
+    }
+
+    /// Queues the input for the next frame, the events received before a frame are
+    /// applied together right before it's built. Returns true if the view was idle
+    /// until this input.
+    ///
+    /// While a native modal dialog is up the input is dropped, so nothing reacts to the
+    /// clicks and keys meant for the dialog.
+    pub fn push_event(&mut self, event: InputEvent) -> bool {
+        let user_input = &mut self.ui_state.user_input;
+
+        if self.ui_state.modal_dialogs.is_open() && !is_state_event(&event) {
+            user_input.mouse_left_pressed = false;
+            user_input.mouse_right_pressed = false;
+            user_input.mouse_middle_pressed = false;
+            user_input.mouse_x = -1.;
+            user_input.mouse_y = -1.;
+            user_input.keys_down.clear();
+            self.pending_input = PendingInput::default();
+
+            return false;
+        }
+
+        let idle_ended =
+            !matches!(event, InputEvent::Focused(_)) && user_input.idle_timer.on_input();
+
+        self.pending_input.push(event);
+
+        idle_ended
+    }
+
+    /// Whether there is input waiting for a frame.
+    pub fn has_pending_input(&self) -> bool {
+        !self.pending_input.is_empty()
+    }
+
+    pub fn resize(&mut self, size: PhysicalSize) {
+        self.ui_state.view.size = size;
+        self.force_redraw = true;
+
+        let user_input = &mut self.ui_state.user_input;
+
+        user_input.mouse_left_pressed = false;
+        user_input.mouse_right_pressed = false;
+        user_input.mouse_middle_pressed = false;
+        user_input.mouse_left_released = false;
+        user_input.mouse_right_released = false;
+        user_input.mouse_middle_released = false;
+        user_input.mouse_pressed = false;
+        user_input.mouse_released = false;
+        user_input.mouse_x = -1.;
+        user_input.mouse_y = -1.;
+        user_input.mouse_wheel_delta_x = 0.;
+        user_input.mouse_wheel_delta_y = 0.;
+        user_input.mouse_left_click_count = 0;
+    }
+
+    pub fn set_scale_factor(&mut self, scale_factor: f32) {
+        self.ui_state.view.scale_factor = scale_factor;
+        self.texts.update_view(&self.ui_state.view, &mut self.fonts);
+        self.force_redraw = true;
+    }
+
+    /// Lays the view out in the direction from the next frame, e.g. right to left for
+    /// the languages written that way.
+    pub fn set_layout_direction(&mut self, layout_direction: LayoutDirection) {
+        self.ui_state.layout_direction = layout_direction;
+        self.ui_state.view_config.layout_direction = layout_direction;
+        self.force_redraw = true;
+    }
+
+    /// Draws the view in the system theme from the next frame, see [`crate::theme`].
+    pub fn set_system_theme(&mut self, system_theme: SystemTheme) {
+        if self.ui_state.system_theme != system_theme {
+            self.ui_state.system_theme = system_theme;
+            self.force_redraw = true;
+        }
+    }
+
+    /// Overrides the reduced motion preference of the system from the next frame,
+    /// `None` follows the system again, see [`crate::BuildContext::reduced_motion`].
+    pub fn set_reduced_motion(&mut self, reduced_motion: Option<bool>) {
+        if self.ui_state.view_config.reduced_motion != reduced_motion {
+            self.ui_state.view_config.reduced_motion = reduced_motion;
+            self.force_redraw = true;
+        }
+    }
+
+    /// Time the build of a frame may take, widgets that can put work off to the next
+    /// frames do it once it's over, see [`crate::BuildContext::deadline`]. `None`, the
+    /// default, builds everything in every frame.
+    pub fn set_frame_budget(&mut self, budget: Option<Duration>) {
+        self.ui_state.view_config.frame_budget = budget;
+    }
+
+    /// Reports the part of the view the platform covers, in logical pixels, e.g. the
+    /// height of an on-screen keyboard at the bottom. The layout moves to the new insets
+    /// over the duration of the platform's animation, or at once without one. When they
+    /// grow, the focused widget is scrolled into the part of the view left uncovered.
+    pub fn set_obscured_insets(&mut self, insets: EdgeInsets, animation: Option<Duration>) {
+        let view_config = &mut self.ui_state.view_config;
+        let previous = view_config.obscured_insets;
+
+        if insets == previous {
+            return;
+        }
+
+        view_config.obscured_insets = insets;
+
+        match animation {
+            Some(duration) if !duration.is_zero() => {
+                let tween = std::mem::take(&mut view_config.obscured_insets_animation);
+
+                view_config.obscured_insets_animation = tween.duration(duration);
+                view_config.obscured_insets_animation.tween_to(insets);
+            }
+            _ => view_config.obscured_insets_animation.set(insets),
+        }
+
+        let grew = insets.top > previous.top
+            || insets.left > previous.left
+            || insets.right > previous.right
+            || insets.bottom > previous.bottom;
+
+        if grew && let Some(focused) = self.ui_state.interaction_state.focused {
+            self.ui_state.interaction_state.scroll_into_view = Some(focused);
+        }
+
+        self.force_redraw = true;
+    }
+
+    /// Renders the next frame entirely, e.g. when the content of the surface may be
+    /// gone. Frames only repaint what has changed otherwise.
+    pub fn invalidate(&mut self) {
+        self.force_redraw = true;
+    }
+
+    /// Stops the animations, the frames are built with no time passed.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+    }
+
+    /// Whether the next frame has to be built. Otherwise there is no input or event
+    /// waiting for the view, nothing was animating or being dragged, the last built
+    /// frame didn't change, no shortcut has fired, no shortcut sequence has timed out
+    /// and no callback waits for the next build, see [`crate::phases`], so the view
+    /// would come out the same and [`ClewInstance::repaint`] can take the place of the build.
+    ///
+    /// The host still builds the frames it's asked for through its event loop, e.g. by
+    /// [`crate::BuildContext::request_frame`] or after the app has changed its state.
+    pub fn needs_build(&self, host: &ClewHost<'a>) -> bool {
+        self.needs_build
+            || self.force_redraw
+            || self.has_pending_input()
+            || !host.broadcast_event_queue.is_empty()
+            || !self.ui_state.next_event_queue.is_empty()
+            || self.chord_timed_out()
+            || self.value_announcement_due()
+            || self.ui_state.shortcuts_manager.has_resolved_shortcuts()
+            || !self.ui_state.phases.before_build.is_empty()
+    }
+
+    /// The pending shortcut sequence has waited for its next chord long enough, the
+    /// shortcuts it's a prefix of fire in the next build.
+    fn chord_timed_out(&self) -> bool {
+        self.ui_state
+            .shortcuts_manager
+            .chord_deadline()
+            .is_some_and(|deadline| deadline <= Instant::now())
+    }
+
+    /// The value of the focused widget has stayed the same long enough to be announced
+    /// in the next build, see [`crate::BuildContext::announce_value`].
+    fn value_announcement_due(&self) -> bool {
+        self.ui_state
+            .announcements
+            .value_deadline()
+            .is_some_and(|deadline| deadline <= Instant::now())
+    }
+
+    /// Updates the last built frame for the time passed without building it or laying
+    /// it out, for the frames [`ClewInstance::needs_build`] says don't have to be built.
+    /// Only the blinking commands change, e.g. the caret of the focused text field.
+    /// Returns the render state if the frame has changed, it's drawn with
+    /// [`ClewInstance::render`] as usual.
+    pub fn repaint(&mut self) -> Option<&RenderState> {
+        let time = self.time_since_start();
+        let changed = crate::render::repaint(&mut self.ui_state.render_state, time, &self.texts);
+
+        if changed {
+            self.needs_render = true;
+        }
+
+        changed.then_some(&self.ui_state.render_state)
+    }
+
+    /// When [`ClewInstance::repaint`] changes the last built frame next, or a pending
+    /// shortcut sequence times out or a value announcement is due and
+    /// [`ClewInstance::needs_build`] says so, the host can wait until then while the
+    /// frames don't have to be built.
+    pub fn next_repaint(&self) -> Option<Instant> {
+        let time = self.time_since_start();
+        let blink = self
+            .ui_state
+            .render_state
+            .next_blink(time)
+            .map(|next| Instant::now() + next.saturating_sub(time));
+        let chord_deadline = self.ui_state.shortcuts_manager.chord_deadline();
+        let value_deadline = self.ui_state.announcements.value_deadline();
+
+        [blink, chord_deadline, value_deadline]
+            .into_iter()
+            .flatten()
+            .min()
+    }
+
+    /// Time of the view by now, the frames haven't been built since the last one.
+    fn time_since_start(&self) -> Duration {
+        if self.paused {
+            self.ui_state.time_since_start
+        } else {
+            self.ui_state.time_since_start + self.delta_time_timer.elapsed()
+        }
+    }
+
+    /// Builds a frame, returns its render state if it has changed since the last one.
+    pub fn frame(
+        &mut self,
+        host: &mut ClewHost<'a>,
+        build: &mut dyn FnMut(&mut BuildContext),
+    ) -> Option<&RenderState> {
+        self.begin_frame();
+        self.build_frame(host, build)
+    }
+
+    /// Builds a frame with the `old` build code and then one with the `new` code on the
+    /// state the first has left, returns which widgets keep their ids and so their state,
+    /// e.g. for a test that a refactoring doesn't reset the scroll offsets or the text
+    /// fields. The comparison is printed readably with its [`std::fmt::Display`].
+    pub fn id_stability_report(
+        &mut self,
+        host: &mut ClewHost<'a>,
+        old: &mut dyn FnMut(&mut BuildContext),
+        new: &mut dyn FnMut(&mut BuildContext),
+    ) -> IdMapDiff {
+        let mut capture = |instance: &mut Self, build: &mut dyn FnMut(&mut BuildContext)| {
+            let mut map = IdMap::default();
+
+            instance.ui_state.widgets_states.id_infos = Some(WidgetIdMap::default());
+            instance.frame(host, &mut |ctx| {
+                build(ctx);
+                map = ctx.capture_id_map();
+            });
+            instance.ui_state.widgets_states.id_infos = None;
+
+            map
+        };
+
+        let old = capture(self, old);
+        let new = capture(self, new);
+
+        old.diff(&new)
+    }
+
+    /// Applies the pending input and starts the frame, returns the events emitted to
+    /// the view since its last frame. Has to be followed by [`ClewInstance::build_frame`].
+    pub fn begin_frame(&mut self) -> &[Arc<dyn Any + Send + Sync>] {
+        self.key_code = None;
+        self.key_code_repeat = None;
+
+        for event in self.pending_input.take_frame() {
+            self.apply_event(event);
+        }
+
+        // Not taken by the shell, there is nothing to read them.
+        self.ui_state.announcements.queued.clear();
+
+        init_cycle(&mut self.ui_state);
+
+        &self.ui_state.current_event_queue
+    }
+
+    /// Builds and lays out the frame started with [`ClewInstance::begin_frame`], the
+    /// events broadcast by the host are handed to the view first. Same as
+    /// [`ClewInstance::build_widgets`] followed by [`ClewInstance::layout_frame`].
+    pub fn build_frame(
+        &mut self,
+        host: &mut ClewHost<'a>,
+        build: &mut dyn FnMut(&mut BuildContext),
+    ) -> Option<&RenderState> {
+        self.build_widgets(host, build);
+        self.layout_frame(&host.assets)
+    }
+
+    /// Builds the widgets of the frame started with [`ClewInstance::begin_frame`]
+    /// without laying them out. Only the build touches the host and the app, the rest
+    /// of the frame is done by [`ClewInstance::layout_frame`], which the host may run
+    /// on another thread, e.g. for each of its views at once.
+    pub fn build_widgets(
+        &mut self,
+        host: &mut ClewHost<'a>,
+        build: &mut dyn FnMut(&mut BuildContext),
+    ) {
+        for event in host.broadcast_event_queue.drain(..) {
+            self.ui_state.current_event_queue.push(event);
+        }
+
+        let delta_time = if self.paused {
+            0.
+        } else {
+            self.delta_time_timer.elapsed().as_secs_f32()
+        };
+
+        self.ui_state.time_since_start += Duration::from_secs_f32(delta_time);
+
+        // Under reduced motion the insets jump to the reported ones, like the animations
+        // of the widgets.
+        let insets_delta_time = if self.ui_state.reduced_motion() {
+            f32::INFINITY
+        } else {
+            delta_time
+        };
+        let obscured_insets = &mut self.ui_state.view_config.obscured_insets_animation;
+
+        if obscured_insets.in_progress() {
+            obscured_insets.step(insets_delta_time);
+
+            // Laid out again until the insets settle.
+            host.event_loop_proxy.send_event(ApplicationEvent::Wake {
+                view_id: self.ui_state.view.id,
+            });
+        }
+
+        scroll_area::observe(&mut self.ui_state.widgets_states.scroll_area, delta_time);
+
+        let mut build_context = BuildContext::new(
+            &mut self.ui_state,
+            &mut self.texts,
+            &mut self.fonts,
+            &mut host.resources,
+            &mut host.assets,
+            &mut host.broadcast_event_queue,
+            &mut host.broadcast_async_tx,
+            host.event_loop_proxy.clone(),
+            delta_time,
+        );
+
+        self.delta_time_timer = Instant::now();
+
+        build_context.run_before_build();
+        build(&mut build_context);
+
+        // Queued in the frame it's due in, for the shell to take after it.
+        self.ui_state
+            .announcements
+            .flush_value(self.ui_state.interaction_state.focused, Instant::now());
+    }
+
+    /// Lays out the frame built by [`ClewInstance::build_widgets`] and collects its
+    /// render commands, returns its render state if it has changed since the last one.
+    /// Reads nothing of the host but the assets, the instance is [`Send`] and the
+    /// assets are [`Sync`], so the views of a host can be laid out on threads of their
+    /// own in between their builds and their renders.
+    pub fn layout_frame(&mut self, assets: &Assets) -> Option<&RenderState> {
+        let redraw = crate::render(
+            &mut self.ui_state,
+            &mut self.texts,
+            &mut self.fonts,
+            assets,
+            &mut self.string_interner,
+            &mut self.strings,
+            self.force_redraw,
+        );
+
+        finalize_cycle(&mut self.ui_state);
+        self.ui_state.frame_index += 1;
+
+        // A frame that changed may be in the middle of a transition, and the animations
+        // and drags go on without input.
+        self.needs_build = redraw
+            || !self.ui_state.animations_stepped_this_frame.is_empty()
+            || self.ui_state.interaction_state.active.is_some();
+
+        self.ui_state.user_input.key_pressed.clear();
+        self.ui_state.user_input.key_pressed_repeat.clear();
+        self.ui_state.user_input.key_events.clear();
+        self.ui_state.user_input.dropped_files.clear();
+
+        if redraw {
+            self.force_redraw = false;
+            self.needs_render = true;
+        }
+
+        redraw.then_some(&self.ui_state.render_state)
+    }
+
+    /// Draws the last built frame with the renderer if it has changed, and the images
+    /// of the subtrees requested during the build. Returns true if the frame was drawn.
+    pub fn render(
+        &mut self,
+        host: &ClewHost<'a>,
+        renderer: &mut dyn Renderer,
+        fill_color: ColorRgb,
+    ) -> bool {
+        let rendered = std::mem::take(&mut self.needs_render);
+
+        if rendered {
+            renderer.process_commands(
+                &self.ui_state.view,
+                &self.ui_state.render_state,
+                fill_color,
+                &mut self.fonts,
+                &mut self.texts,
+                &host.assets,
+            );
+        }
+
+        process_image_captures(
+            &mut self.ui_state,
+            renderer,
+            fill_color,
+            &mut self.fonts,
+            &mut self.texts,
+            &host.assets,
+        );
+
+        rendered
+    }
+
+    /// Draws what the widget with the id drew in the last rendered frame offscreen, like
+    /// [`BuildContext::copy_subtree_as_image`] without the clipboard, e.g. to save it to
+    /// a file.
+    pub fn subtree_image(
+        &mut self,
+        host: &ClewHost<'a>,
+        renderer: &mut dyn Renderer,
+        id: WidgetId,
+        options: SubtreeImageOptions,
+        fill_color: ColorRgb,
+    ) -> Result<RgbaImage, ClewError> {
+        subtree_image(
+            &self.ui_state,
+            renderer,
+            id,
+            options,
+            fill_color,
+            &mut self.fonts,
+            &mut self.texts,
+            &host.assets,
+        )
+    }
+
+    /// Draws the last built frame again through the transform without building a frame,
+    /// at the current size of the view, e.g. while the window is being resized. The
+    /// commands are in the physical pixels of the scale factor the frame was built at.
+    pub fn render_frozen(
+        &mut self,
+        host: &ClewHost<'a>,
+        renderer: &mut dyn Renderer,
+        fill_color: ColorRgb,
+        transform: Transform,
+    ) {
+        let state = self.ui_state.render_state.frozen(transform);
+
+        renderer.process_commands(
+            &self.ui_state.view,
+            &state,
+            fill_color,
+            &mut self.fonts,
+            &mut self.texts,
+            &host.assets,
+        );
+    }
+
+    /// Draws the last built frame offscreen at the size of the view, e.g. to compare it
+    /// with a golden image. The renderer has to be able to draw offscreen, see
+    /// [`Renderer::render_image`], it doesn't need a window then.
+    pub fn render_image(
+        &mut self,
+        host: &ClewHost<'a>,
+        renderer: &mut dyn Renderer,
+        fill_color: ColorRgb,
+    ) -> Result<RgbaImage, ClewError> {
+        let size = &self.ui_state.view.size;
+
+        renderer.render_image(
+            self.ui_state.render_state.commands(),
+            size.width,
+            size.height,
+            Some(fill_color.with_alpha(1.)),
+            &mut self.fonts,
+            &mut self.texts,
+            &host.assets,
+        )
+    }
+
+    fn apply_event(&mut self, event: InputEvent) {
+        let user_input = &mut self.ui_state.user_input;
+
+        match event {
+            InputEvent::PointerMoved { x, y } => {
+                user_input.mouse_x = x;
+                user_input.mouse_y = y;
+
+                // Some platforms move the pointer with the drag instead of reporting the
+                // positions of the hover.
+                for file in &mut user_input.hovered_files {
+                    file.position = Some(Vec2::new(x, y));
+                }
+            }
+            InputEvent::MouseButton { button, pressed } => {
+                if pressed && button == MouseButton::Left {
+                    user_input.touch = false;
+                }
+
+                user_input.mouse_pressed = pressed;
+                user_input.mouse_released = !pressed;
+
+                match button {
+                    MouseButton::Left => {
+                        user_input.mouse_left_pressed = pressed;
+                        user_input.mouse_left_released = !pressed;
+                    }
+                    MouseButton::Right => {
+                        user_input.mouse_right_pressed = pressed;
+                        user_input.mouse_right_released = !pressed;
+                    }
+                    MouseButton::Middle => {
+                        user_input.mouse_middle_pressed = pressed;
+                        user_input.mouse_middle_released = !pressed;
+                    }
+                    MouseButton::Other => {}
+                }
+            }
+            InputEvent::Touch { id, phase, x, y } => {
+                if phase == TouchPhase::Started && self.touch_id.is_none() {
+                    self.touch_id = Some(id);
+                }
+
+                if self.touch_id != Some(id) {
+                    return;
+                }
+
+                user_input.mouse_x = x;
+                user_input.mouse_y = y;
+
+                if phase == TouchPhase::Moved {
+                    return;
+                }
+
+                let pressed = phase == TouchPhase::Started;
+
+                if pressed {
+                    user_input.touch = true;
+                } else {
+                    self.touch_id = None;
+                }
+
+                user_input.mouse_pressed = pressed;
+                user_input.mouse_released = !pressed;
+                user_input.mouse_left_pressed = pressed;
+                user_input.mouse_left_released = !pressed;
+            }
+            InputEvent::MouseWheel { delta_x, delta_y } => {
+                user_input.mouse_wheel_delta_x += delta_x;
+                user_input.mouse_wheel_delta_y += delta_y;
+            }
+            InputEvent::Pinch { delta } => {
+                if delta.is_finite() {
+                    user_input.pinch_delta += delta;
+                }
+            }
+            InputEvent::ModifiersChanged(modifiers) => {
+                self.modifiers = Some(modifiers);
+                user_input.modifiers = modifiers;
+            }
+            InputEvent::Key {
+                key_code,
+                pressed,
+                repeat,
+                text,
+            } => {
+                user_input.is_key_pressed = pressed;
+                user_input.is_key_released = !pressed;
+
+                if pressed {
+                    if repeat {
+                        self.key_code_repeat = key_code;
+                    } else {
+                        self.key_code = key_code;
+                    }
+                }
+
+                if let Some(key_code) = key_code {
+                    user_input.key_events.push(KeyEvent {
+                        key: key_code,
+                        modifiers: self.modifiers.unwrap_or_default(),
+                        pressed,
+                        repeat,
+                    });
+
+                    if pressed {
+                        if !user_input.keys_down.contains(&key_code) {
+                            user_input.keys_down.push(key_code);
+                        }
+                    } else {
+                        user_input.keys_down.retain(|key| *key != key_code);
+                    }
+                }
+
+                if pressed && let Some(text) = text {
+                    user_input.text_input.push_str(&text);
+                    user_input.text_input_actions.push(TextInputAction::Insert);
+                }
+
+                user_input.key_pressed.push((self.modifiers, self.key_code));
+                user_input
+                    .key_pressed_repeat
+                    .push((self.modifiers, self.key_code_repeat));
+            }
+            InputEvent::ImeEnabled => {
+                user_input
+                    .text_input_actions
+                    .push(TextInputAction::ImeEnable);
+            }
+            InputEvent::ImePreedit {
+                text,
+                cursor,
+                clauses,
+            } => {
+                user_input.ime_preedit = text;
+                user_input.ime_cursor_range = cursor;
+                user_input.ime_clauses = clauses;
+                user_input
+                    .text_input_actions
+                    .push(TextInputAction::ImePreedit);
+            }
+            InputEvent::ImeCommit(text) => {
+                user_input.ime_preedit.clear();
+                user_input.ime_cursor_range = None;
+                user_input.ime_clauses.clear();
+                user_input
+                    .text_input_actions
+                    .push(TextInputAction::ImeCommit);
+
+                // The composition is taken out of the text first, then the committed
+                // text is typed in its place.
+                user_input.text_input.push_str(&text);
+                user_input.text_input_actions.push(TextInputAction::Insert);
+            }
+            InputEvent::ImeDisabled => {
+                user_input.ime_preedit.clear();
+                user_input.ime_cursor_range = None;
+                user_input.ime_clauses.clear();
+                user_input
+                    .text_input_actions
+                    .push(TextInputAction::ImeDisable);
+            }
+            InputEvent::Focused(focused) => {
+                user_input.window_focused = focused;
+
+                if !focused {
+                    user_input.mouse_left_pressed = false;
+                    user_input.mouse_right_pressed = false;
+                    user_input.mouse_middle_pressed = false;
+                    user_input.keys_down.clear();
+                    user_input.cursor = Cursor::Default;
+                }
+            }
+            InputEvent::FileHovered { path, position } => {
+                // Without a reported position the drag is where the pointer was last.
+                let position = position
+                    .or_else(|| {
+                        user_input
+                            .hovered_files
+                            .iter()
+                            .find_map(|file| file.position)
+                    })
+                    .unwrap_or(Vec2::new(user_input.mouse_x, user_input.mouse_y));
+
+                if !user_input
+                    .hovered_files
+                    .iter()
+                    .any(|file| file.path == path)
+                {
+                    user_input.hovered_files.push(ExternalFile {
+                        path,
+                        position: None,
+                    });
+                }
+
+                // The files are dragged together.
+                for file in &mut user_input.hovered_files {
+                    file.position = Some(position);
+                }
+            }
+            InputEvent::FileDropped { path, position } => {
+                let hovered = user_input
+                    .hovered_files
+                    .iter()
+                    .position(|file| file.path == path)
+                    .map(|index| user_input.hovered_files.remove(index));
+
+                // The platforms that report only the drop have no position of the hover
+                // either, winit reports none at all, the drop is where the pointer was last.
+                let position = position
+                    .or(hovered.and_then(|file| file.position))
+                    .unwrap_or(Vec2::new(user_input.mouse_x, user_input.mouse_y));
+
+                user_input.dropped_files.push(ExternalFile {
+                    path,
+                    position: Some(position),
+                });
+            }
+            InputEvent::FileHoverCancelled => {
+                user_input.hovered_files.clear();
+            }
+        }
+    }
+}
+
+/// Whether the event tells the state of the window rather than being the input of the
+/// user.
+fn is_state_event(event: &InputEvent) -> bool {
+    matches!(
+        event,
+        InputEvent::ModifiersChanged(_) | InputEvent::Focused(_) | InputEvent::FileHoverCancelled
+    )
+}
+
+/// Input events of a view received since its last frame. They are applied together
+/// right before the frame is built, so the frame reacts to all the input that arrived
+/// before it rather than to the input of the previous one.
+#[derive(Default)]
+struct PendingInput {
+    events: Vec<InputEvent>,
+}
+
+impl PendingInput {
+    fn push(&mut self, event: InputEvent) {
+        // Only the latest position matters, the moves in between are dropped.
+        if let InputEvent::PointerMoved { .. } = event
+            && let Some(last @ InputEvent::PointerMoved { .. }) = self.events.last_mut()
+        {
+            *last = event;
+            return;
+        }
+
+        if let InputEvent::Touch {
+            id,
+            phase: TouchPhase::Moved,
+            ..
+        } = event
+            && let Some(last) = self.events.last_mut()
+            && matches!(
+                last,
+                InputEvent::Touch { id: last_id, phase: TouchPhase::Moved, .. } if *last_id == id
+            )
+        {
+            *last = event;
+            return;
+        }
+
+        self.events.push(event);
+    }
+
+    fn is_empty(&self) -> bool {
+        self.events.is_empty()
+    }
+
+    /// Events for the next frame. A button released after being pressed in the same
+    /// batch is left for the frame after, so a fast click is seen as a press and then
+    /// a release instead of being lost.
+    fn take_frame(&mut self) -> Vec<InputEvent> {
+        let mut pressed = Vec::new();
+        let end = self
+            .events
+            .iter()
+            .position(|event| match event {
+                InputEvent::MouseButton {
+                    button,
+                    pressed: true,
+                } => {
+                    pressed.push(*button);
+                    false
+                }
+                InputEvent::MouseButton {
+                    button,
+                    pressed: false,
+                } => pressed.contains(button),
+                // A touch holds the left button.
+                InputEvent::Touch {
+                    phase: TouchPhase::Started,
+                    ..
+                } => {
+                    pressed.push(MouseButton::Left);
+                    false
+                }
+                InputEvent::Touch {
+                    phase: TouchPhase::Ended | TouchPhase::Cancelled,
+                    ..
+                } => pressed.contains(&MouseButton::Left),
+                _ => false,
+            })
+            .unwrap_or(self.events.len());
+
+        self.events.drain(..end).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{EdgeInsets, ViewId, prelude::*, testing};
+
+    use super::*;
+
+    fn cursor_moved(x: f32) -> InputEvent {
+        InputEvent::PointerMoved { x, y: 0. }
+    }
+
+    fn mouse_input(pressed: bool) -> InputEvent {
+        InputEvent::MouseButton {
+            button: MouseButton::Left,
+            pressed,
+        }
+    }
+
+    fn cursor_x(event: &InputEvent) -> Option<f32> {
+        match event {
+            InputEvent::PointerMoved { x, .. } => Some(*x),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_cursor_moves_are_coalesced() {
+        let mut input = PendingInput::default();
+        input.push(cursor_moved(1.));
+        input.push(cursor_moved(2.));
+        input.push(mouse_input(true));
+        input.push(cursor_moved(3.));
+        input.push(cursor_moved(4.));
+
+        let events = input.take_frame();
+
+        assert_eq!(events.len(), 3);
+        assert_eq!(cursor_x(&events[0]), Some(2.));
+        assert_eq!(cursor_x(&events[2]), Some(4.));
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_click_is_split_between_frames() {
+        let mut input = PendingInput::default();
+        input.push(mouse_input(true));
+        input.push(cursor_moved(1.));
+        input.push(mouse_input(false));
+
+        assert_eq!(input.take_frame().len(), 2);
+        assert_eq!(input.take_frame().len(), 1);
+        assert!(input.is_empty());
+    }
+
+    #[test]
+    fn test_frame_applies_the_input_pushed_before_it() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+
+        instance.push_event(InputEvent::PointerMoved { x: 10., y: 20. });
+        instance.push_event(mouse_input(true));
+
+        let mut pointer = None;
+        let render_state = instance.frame(&mut host, &mut |ctx| {
+            pointer = Some((ctx.input().mouse_x, ctx.input().mouse_y));
+            crate::text("Hello").build(ctx);
+        });
+
+        assert!(render_state.is_some());
+        assert_eq!(pointer, Some((10., 20.)));
+        assert!(instance.ui_state().user_input.mouse_left_pressed);
+        assert!(!instance.has_pending_input());
+    }
+
+    #[test]
+    fn test_announcements_not_taken_are_dropped() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        let mut announce = |instance: &mut ClewInstance<'static>, text: &str| {
+            instance.frame(&mut host, &mut |ctx| {
+                ctx.announce(text, crate::Politeness::Assertive);
+            });
+        };
+
+        announce(&mut instance, "Saved");
+        assert_eq!(
+            instance.take_announcements(),
+            vec![crate::Announcement {
+                text: "Saved".to_string(),
+                politeness: crate::Politeness::Assertive,
+            }]
+        );
+
+        announce(&mut instance, "First");
+        announce(&mut instance, "Second");
+
+        let announcements = instance.take_announcements();
+        assert_eq!(announcements.len(), 1);
+        assert_eq!(announcements[0].text, "Second");
+    }
+
+    #[test]
+    fn test_animation_under_reduced_motion_ends_in_its_first_frame() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        instance.set_reduced_motion(Some(true));
+
+        let mut fade = crate::Tween::new(0.).duration(Duration::from_secs(1));
+        let mut pulse = crate::Tween::new(0.)
+            .duration(Duration::from_secs(1))
+            .repeat(crate::Repeat::Loop);
+        fade.tween_to(1.);
+        pulse.tween_to(1.);
+
+        let mut values = None;
+        instance.frame(&mut host, &mut |ctx| {
+            values = Some((fade.resolve(ctx), pulse.resolve(ctx)));
+        });
+
+        assert_eq!(values, Some((1., 1.)));
+        assert_eq!(fade.status(), crate::AnimationStatus::Ended);
+        assert_eq!(pulse.status(), crate::AnimationStatus::Ended);
+    }
+
+    #[test]
+    fn test_obscured_insets_jump_under_reduced_motion() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        instance.set_reduced_motion(Some(true));
+        instance.set_obscured_insets(EdgeInsets::ZERO.bottom(80.), Some(Duration::from_secs(1)));
+
+        let mut insets = None;
+        instance.frame(&mut host, &mut |ctx| {
+            insets = Some(ctx.safe_area_insets());
+        });
+
+        assert_eq!(insets, Some(EdgeInsets::ZERO.bottom(80.)));
+    }
+
+    #[test]
+    fn test_layout_direction_reaches_the_view_config() {
+        let mut instance = ClewInstance::new(testing::view(100, 100), FontResources::new());
+        instance.set_layout_direction(LayoutDirection::RTL);
+
+        assert_eq!(instance.ui_state().layout_direction, LayoutDirection::RTL);
+        assert_eq!(
+            instance.ui_state().view_config.layout_direction,
+            LayoutDirection::RTL
+        );
+    }
+
+    #[test]
+    fn test_pending_chord_wakes_the_view_when_it_times_out() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        let key = crate::KeyBinding::new(KeyCode::KeyK);
+
+        instance
+            .ui_state()
+            .shortcuts_registry()
+            .scope(crate::ShortcutScopeId("editor"))
+            .add(crate::ShortcutId("kill"), key)
+            .add_sequence(
+                crate::ShortcutId("comment"),
+                &[key, crate::KeyBinding::new(KeyCode::KeyC)],
+            );
+
+        let timeout = Duration::from_millis(200);
+        instance
+            .ui_state()
+            .shortcuts_manager()
+            .set_chord_timeout(timeout);
+
+        let mut killed = false;
+        let frame = |instance: &mut ClewInstance<'static>,
+                     host: &mut ClewHost<'static>,
+                     killed: &mut bool| {
+            instance.frame(host, &mut |ctx| {
+                crate::shortcut_scope(crate::ShortcutScopeId("editor")).build(ctx, |ctx| {
+                    *killed |= ctx.is_shortcut(crate::ShortcutId("kill"));
+                });
+            });
+        };
+
+        instance.push_event(InputEvent::Key {
+            key_code: Some(KeyCode::KeyK),
+            pressed: true,
+            repeat: false,
+            text: None,
+        });
+        let pressed = Instant::now();
+
+        while instance.needs_build(&host) && pressed.elapsed() < timeout {
+            frame(&mut instance, &mut host, &mut killed);
+        }
+
+        // Settled with the chord pending, the host waits until it times out.
+        assert!(!instance.needs_build(&host));
+        assert!(
+            instance
+                .next_repaint()
+                .is_some_and(|at| at > Instant::now() && at <= Instant::now() + timeout)
+        );
+        assert!(!killed);
+
+        std::thread::sleep(timeout);
+
+        while instance.needs_build(&host) {
+            frame(&mut instance, &mut host, &mut killed);
+        }
+
+        assert!(killed);
+        assert_eq!(
+            instance.ui_state().shortcuts_manager().pending_chord(),
+            None
+        );
+    }
+
+    /// Builds a view that shows the broadcasts it has received and broadcasts `sent`.
+    fn build_broadcasts<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        sent: &[&'static str],
+        received: &mut Vec<&'static str>,
+    ) {
+        instance.begin_frame();
+        instance.build_widgets(host, &mut |ctx| {
+            received.extend(
+                ctx.event_queue
+                    .iter()
+                    .filter_map(|event| event.downcast_ref::<&'static str>()),
+            );
+
+            crate::text(&received.join(" ")).build(ctx);
+
+            for event in sent {
+                ctx.broadcast(*event);
+            }
+        });
+    }
+
+    #[test]
+    fn test_views_laid_out_on_threads_match_the_ones_laid_out_in_order() {
+        fn view(id: usize) -> View {
+            View {
+                id: ViewId(id),
+                size: PhysicalSize::new(200, 100),
+                scale_factor: 1.,
+                safe_area: EdgeInsets::ZERO,
+            }
+        }
+
+        let mut frames = Vec::new();
+
+        for threaded in [false, true] {
+            let mut host = testing::host();
+            let mut first = ClewInstance::new(view(0), FontResources::new());
+            let mut second = ClewInstance::new(view(1), FontResources::new());
+            let mut received = Vec::new();
+
+            for _ in 0..3 {
+                // The builds stay in order on this thread, the broadcasts of the first
+                // view reach the second one in the order they've been sent.
+                build_broadcasts(&mut first, &mut host, &["one", "two"], &mut Vec::new());
+                build_broadcasts(&mut second, &mut host, &[], &mut received);
+
+                let assets = &host.assets;
+
+                if threaded {
+                    std::thread::scope(|scope| {
+                        for instance in [&mut first, &mut second] {
+                            scope.spawn(move || {
+                                instance.layout_frame(assets);
+                            });
+                        }
+                    });
+                } else {
+                    first.layout_frame(assets);
+                    second.layout_frame(assets);
+                }
+            }
+
+            assert_eq!(received, ["one", "two", "one", "two", "one", "two"]);
+
+            frames.push([
+                first.ui_state.render_state.commands.clone(),
+                second.ui_state.render_state.commands.clone(),
+            ]);
+        }
+
+        assert!(!frames[0][1].is_empty());
+        assert_eq!(frames[0], frames[1]);
+    }
+
+    /// A title, then a field and a thousand items of the round.
+    fn build_items<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        data: &mut crate::TextData,
+        round: Option<usize>,
+    ) {
+        instance.frame(host, &mut |ctx| {
+            crate::text("Title").build(ctx);
+
+            if let Some(round) = round {
+                crate::editable_text(data).build(ctx);
+
+                for i in 0..1000 {
+                    crate::text("Item").id(round * 1000 + i).build(ctx);
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn test_texts_of_dropped_widgets_are_released() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        let mut data = crate::TextData::from("Field");
+
+        build_items(&mut instance, &mut host, &mut data, None);
+        let baseline = instance.texts_stats();
+
+        for round in 0..10 {
+            build_items(&mut instance, &mut host, &mut data, Some(round));
+            assert_eq!(instance.texts_stats().buffers, baseline.buffers + 1001);
+        }
+
+        build_items(&mut instance, &mut host, &mut data, None);
+        assert_eq!(instance.texts_stats(), baseline);
+
+        // The field gets a new text when it's built again.
+        build_items(&mut instance, &mut host, &mut data, Some(0));
+        assert_eq!(instance.texts_stats().editors, 1);
+        assert_eq!(data.widgets.len(), 1);
+    }
+}
@@ -1,33 +1,50 @@
-use rustc_hash::FxHashSet;
-
 use crate::{
-    Vec2, View, WidgetId,
+    Transform, Vec2, View, WidgetId, WidgetIdSet,
     io::UserInput,
-    layout::LayoutItem,
+    keyboard::KeyCode,
+    layout::{LayoutItem, WidgetPlacement},
     point_with_rect_hit_test,
     text::{FontResources, TextsResources},
 };
 
 #[derive(Default, Clone, PartialEq)]
 pub struct InteractionState {
-    pub(crate) hover: FxHashSet<WidgetId>,
+    pub(crate) hover: WidgetIdSet,
     pub(crate) hot: Option<WidgetId>,
     pub(crate) active: Option<WidgetId>,
     pub(crate) focused: Option<WidgetId>,
     pub(crate) was_focused: Option<WidgetId>,
     pub(crate) block_hover: bool,
+    /// Whether the last focus change came from the keyboard, follows the
+    /// "focus-visible" convention.
+    pub(crate) focus_visible: bool,
+    /// Widget to bring into the view of its scroll areas after the next layout.
+    pub(crate) scroll_into_view: Option<WidgetId>,
 }
 
-#[derive(Default, Clone, PartialEq)]
+#[derive(Default, Clone, Copy, PartialEq)]
 pub struct WidgetInteractionState {
     pub is_hover: bool,
     pub is_hot: bool,
     pub is_active: bool,
     pub is_focused: bool,
+    pub is_focus_visible: bool,
     pub was_focused: bool,
 }
 
 impl InteractionState {
+    /// How the user interacts with the widget, read once when it's built.
+    pub(crate) fn widget(&self, id: &WidgetId) -> WidgetInteractionState {
+        WidgetInteractionState {
+            is_hover: self.is_hover(id),
+            is_hot: self.is_hot(id),
+            is_active: self.is_active(id),
+            is_focused: self.is_focused(id),
+            is_focus_visible: self.is_focus_visible(id),
+            was_focused: self.was_focused(id),
+        }
+    }
+
     pub fn is_hover(&self, id: &WidgetId) -> bool {
         self.hover.contains(id)
     }
@@ -44,6 +61,32 @@ impl InteractionState {
         self.focused == Some(*id)
     }
 
+    pub(crate) fn is_focus_visible(&self, id: &WidgetId) -> bool {
+        self.focus_visible && self.is_focused(id)
+    }
+
+    /// Focus the widget after a pointer interaction, the focus ring stays hidden
+    /// until keyboard navigation resumes.
+    pub(crate) fn focus_with_pointer(&mut self, id: &WidgetId) {
+        self.focused = Some(*id);
+        self.focus_visible = false;
+    }
+
+    /// Focus the widget from the keyboard, the focus ring is shown and the widget is
+    /// scrolled into view.
+    pub(crate) fn focus_with_keyboard(&mut self, id: &WidgetId) {
+        self.focused = Some(*id);
+        self.focus_visible = true;
+        self.scroll_into_view = Some(*id);
+    }
+
+    /// Takes the focus from the widget, if it has it.
+    pub(crate) fn unfocus(&mut self, id: &WidgetId) {
+        if self.is_focused(id) {
+            self.focused = None;
+        }
+    }
+
     pub(crate) fn was_focused(&self, id: &WidgetId) -> bool {
         self.was_focused == Some(*id)
     }
@@ -63,7 +106,7 @@ impl InteractionState {
 pub fn handle_interaction(
     user_input: &mut UserInput,
     interaction_state: &mut InteractionState,
-    non_interactable: &FxHashSet<WidgetId>,
+    non_interactable: &WidgetIdSet,
     view: &View,
     _text: &mut TextsResources,
     _fonts: &mut FontResources,
@@ -82,29 +125,262 @@ pub fn handle_interaction(
 
     let mouse_point = Vec2::new(unscaled_mouse_x, unscaled_mouse_y);
 
+    if user_input
+        .key_pressed
+        .iter()
+        .any(|(_, key)| *key == Some(KeyCode::Tab))
+    {
+        interaction_state.focus_visible = true;
+    }
+
     interaction_state.hot = None;
     interaction_state.hover.clear();
 
-    for layout_item in layout_items.iter() {
-        if let LayoutItem::Placement(placement) = layout_item
-            && point_with_rect_hit_test(mouse_point, placement.boundary)
-        {
-            interaction_state.hover.insert(placement.widget_ref.id);
-        }
+    // Placements inside transformed containers are in content space, map the pointer
+    // through the inverse transform before testing.
+    let local_mouse_point = |transform: Option<Transform>| {
+        transform
+            .map(|transform| transform.apply_inverse(mouse_point))
+            .unwrap_or(mouse_point)
+    };
+
+    let is_hit = |placement: &WidgetPlacement| {
+        let point = local_mouse_point(placement.transform);
+
+        point_with_rect_hit_test(point, placement.boundary)
+            && placement
+                .clip_rect
+                .is_none_or(|clip| point_with_rect_hit_test(point, clip))
+    };
+
+    let mut placements = Vec::new();
+    paint_order(layout_items, &mut placements);
+
+    for placement in placements.iter().filter(|placement| is_hit(placement)) {
+        interaction_state.hover.insert(placement.widget_ref.id);
     }
 
-    for layout_item in layout_items.iter().rev() {
-        if let LayoutItem::Placement(placement) = layout_item
-            && !non_interactable.contains(&placement.widget_ref.id)
-            && (!interaction_state.block_hover
-                || interaction_state.active.is_none()
-                || interaction_state.active == Some(placement.widget_ref.id))
-            && point_with_rect_hit_test(mouse_point, placement.boundary)
-        {
-            interaction_state.hot = Some(placement.widget_ref.id);
+    // Only the topmost widget under the pointer is hot, the ones that are covered by
+    // it aren't, unless it lets the pointer through.
+    interaction_state.hot = placements
+        .iter()
+        .rev()
+        .filter(|placement| is_hit(placement))
+        .map(|placement| placement.widget_ref.id)
+        .find(|id| {
+            !non_interactable.contains(id)
+                && (!interaction_state.block_hover
+                    || interaction_state.active.is_none()
+                    || interaction_state.active == Some(*id))
+        });
+
+    true
+}
+
+enum PaintItem<'a> {
+    Placement(&'a WidgetPlacement),
+    Group(&'a [LayoutItem]),
+}
+
+fn group_zindex(item: &LayoutItem) -> Option<i32> {
+    match item {
+        LayoutItem::PushClip { zindex, .. }
+        | LayoutItem::PushTransform { zindex, .. }
+        | LayoutItem::BeginGroup { zindex }
+        | LayoutItem::BeginLayer { zindex, .. } => Some(*zindex),
+        _ => None,
+    }
+}
+
+fn is_group_end(item: &LayoutItem) -> bool {
+    matches!(
+        item,
+        LayoutItem::PopClip
+            | LayoutItem::PopTransform
+            | LayoutItem::EndGroup
+            | LayoutItem::EndLayer
+    )
+}
+
+/// Collects the placements in the order they are painted, the same order the render
+/// commands are sorted in: the siblings by zindex, in the build order when it's equal,
+/// with the clips, transforms and groups sorted as a whole.
+fn paint_order<'a>(items: &'a [LayoutItem], output: &mut Vec<&'a WidgetPlacement>) {
+    let mut siblings = Vec::new();
+    let mut i = 0;
+
+    while i < items.len() {
+        if let Some(zindex) = group_zindex(&items[i]) {
+            let content_start = i + 1;
+            let mut depth = 1;
+            i += 1;
+
+            while i < items.len() && depth > 0 {
+                if group_zindex(&items[i]).is_some() {
+                    depth += 1;
+                } else if is_group_end(&items[i]) {
+                    depth -= 1;
+                }
+                i += 1;
+            }
+
+            let content_end = if depth == 0 { i - 1 } else { i };
+            siblings.push((zindex, PaintItem::Group(&items[content_start..content_end])));
+        } else if is_group_end(&items[i]) {
             break;
+        } else {
+            if let LayoutItem::Placement(placement) = &items[i] {
+                siblings.push((placement.zindex, PaintItem::Placement(placement)));
+            }
+            i += 1;
         }
     }
 
-    true
+    siblings.sort_by_key(|(zindex, _)| *zindex);
+
+    for (_, item) in siblings {
+        match item {
+            PaintItem::Placement(placement) => output.push(placement),
+            PaintItem::Group(items) => paint_order(items, output),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Rect, WidgetRef, WidgetType, testing};
+
+    #[test]
+    fn test_pointer_focus_hides_focus_ring() {
+        let mut state = InteractionState::default();
+        let id = WidgetId::auto();
+
+        state.focus_with_pointer(&id);
+
+        assert!(state.is_focused(&id));
+        assert!(!state.is_focus_visible(&id));
+    }
+
+    #[test]
+    fn test_keyboard_focus_shows_focus_ring() {
+        let mut state = InteractionState::default();
+        let id = WidgetId::auto();
+
+        state.focus_with_keyboard(&id);
+
+        assert!(state.is_focused(&id));
+        assert!(state.is_focus_visible(&id));
+    }
+
+    #[test]
+    fn test_toggle_between_pointer_and_keyboard_focus() {
+        let mut state = InteractionState::default();
+        let first = WidgetId::auto();
+        let second = WidgetId::auto();
+
+        state.focus_with_keyboard(&first);
+        assert!(state.is_focus_visible(&first));
+
+        state.focus_with_pointer(&second);
+        assert!(!state.is_focus_visible(&first));
+        assert!(!state.is_focus_visible(&second));
+        assert!(state.is_focused(&second));
+
+        state.focus_with_keyboard(&first);
+        assert!(state.is_focus_visible(&first));
+        assert!(!state.is_focused(&second));
+    }
+
+    #[test]
+    fn test_tab_press_resumes_focus_ring() {
+        let mut state = InteractionState::default();
+        let mut input = UserInput::default();
+        let view = testing::view(100, 100);
+        let id = WidgetId::auto();
+
+        state.focus_with_pointer(&id);
+        assert!(!state.is_focus_visible(&id));
+
+        input.key_pressed.push((None, Some(KeyCode::Tab)));
+        handle_interaction(
+            &mut input,
+            &mut state,
+            &WidgetIdSet::default(),
+            &view,
+            &mut TextsResources::new(),
+            &mut FontResources::new(),
+            &[],
+        );
+
+        assert!(state.is_focus_visible(&id));
+    }
+
+    fn placement(id: WidgetId, zindex: i32, boundary: Rect) -> LayoutItem {
+        LayoutItem::Placement(WidgetPlacement {
+            widget_ref: WidgetRef::new(WidgetType::of::<()>(), id),
+            zindex,
+            boundary,
+            rect: boundary,
+            clip_rect: None,
+            transform: None,
+        })
+    }
+
+    fn hover(layout_items: &[LayoutItem], non_interactable: &WidgetIdSet) -> InteractionState {
+        let mut state = InteractionState::default();
+        let mut input = UserInput {
+            mouse_x: 15.,
+            mouse_y: 15.,
+            ..Default::default()
+        };
+        let view = testing::view(100, 100);
+
+        handle_interaction(
+            &mut input,
+            &mut state,
+            non_interactable,
+            &view,
+            &mut TextsResources::new(),
+            &mut FontResources::new(),
+            layout_items,
+        );
+
+        state
+    }
+
+    #[test]
+    fn test_only_topmost_overlapping_widget_is_hot() {
+        let tooltip = WidgetId::auto();
+        let button = WidgetId::auto();
+        // The tooltip is built first but painted over the button.
+        let layout_items = [
+            LayoutItem::BeginGroup { zindex: 10 },
+            placement(tooltip, 0, Rect::new(10., 10., 40., 20.)),
+            LayoutItem::EndGroup,
+            placement(button, 0, Rect::new(0., 0., 30., 30.)),
+        ];
+
+        let state = hover(&layout_items, &WidgetIdSet::default());
+
+        assert!(state.is_hot(&tooltip));
+        assert!(!state.is_hot(&button));
+        assert!(state.is_hover(&tooltip) && state.is_hover(&button));
+    }
+
+    #[test]
+    fn test_hover_through_overlay_does_not_block_hot() {
+        let overlay = WidgetId::auto();
+        let button = WidgetId::auto();
+        let layout_items = [
+            placement(button, 0, Rect::new(0., 0., 30., 30.)),
+            placement(overlay, 1, Rect::new(0., 0., 100., 100.)),
+        ];
+        let non_interactable = WidgetIdSet::from_iter([overlay]);
+
+        let state = hover(&layout_items, &non_interactable);
+
+        assert!(state.is_hot(&button));
+        assert!(!state.is_hot(&overlay));
+    }
 }
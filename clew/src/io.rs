@@ -1,8 +1,16 @@
-use std::time::{Duration, Instant};
+use std::{
+    ops::Range,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
 
 use smallvec::SmallVec;
 
-use crate::keyboard::{KeyCode, KeyModifiers};
+use crate::{
+    Vec2,
+    keyboard::{KeyCode, KeyModifiers},
+    shortcuts::{KeyBinding, ShortcutsManager, ShortcutsRegistry},
+};
 
 #[derive(Default, Debug, Clone)]
 pub struct UserInput {
@@ -19,13 +27,28 @@ pub struct UserInput {
     pub mouse_released: bool,
     pub mouse_x: f32,
     pub mouse_y: f32,
-    pub mouse_wheel_delta_x: f32,
-    pub mouse_wheel_delta_y: f32,
+    /// Scroll since the last frame, kept in double precision so the small deltas of a
+    /// trackpad add up without drift.
+    pub mouse_wheel_delta_x: f64,
+    pub mouse_wheel_delta_y: f64,
+    /// Magnification of the touchpad pinch gesture since the last frame, positive
+    /// values zoom in.
+    pub pinch_delta: f32,
     pub mouse_left_click_count: u32,
+    /// The last press of the left button was a touch, the pointer follows the finger
+    /// that touched first until it's lifted.
+    pub touch: bool,
 
     // Keyboard state
     pub key_pressed: SmallVec<[(Option<KeyModifiers>, Option<KeyCode>); 4]>,
     pub key_pressed_repeat: SmallVec<[(Option<KeyModifiers>, Option<KeyCode>); 4]>,
+    /// Modifiers that are currently held down.
+    pub modifiers: KeyModifiers,
+    /// Keys that are currently held down.
+    pub keys_down: SmallVec<[KeyCode; 4]>,
+    /// Presses, repeats and releases of the keys since the last frame, in the order
+    /// they happened.
+    pub key_events: SmallVec<[KeyEvent; 4]>,
 
     pub is_key_pressed: bool,
     pub is_key_released: bool,
@@ -35,12 +58,43 @@ pub struct UserInput {
     pub text_input: String,
     pub ime_preedit: String,
     pub ime_last_preedit: String,
+    /// Byte range of the caret in the preedit, `None` hides the caret.
     pub ime_cursor_range: Option<(usize, usize)>,
+    pub ime_clauses: Vec<ImeClause>,
+
+    // Files dragged from other applications
+    /// Files dragged over the window, until they are dropped or the drag leaves it.
+    pub hovered_files: Vec<ExternalFile>,
+    /// Files dropped onto the window since the last frame.
+    pub dropped_files: Vec<ExternalFile>,
+
+    /// Whether the window of the view has the keyboard focus.
+    pub window_focused: bool,
+    pub idle_timer: IdleTimer,
 
     pub(crate) mouse_left_click_tracker: ClickTracker,
     pub(crate) last_click_time: Option<Instant>,
 }
 
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct KeyEvent {
+    pub key: KeyCode,
+    /// Modifiers held when the key was pressed or released.
+    pub modifiers: KeyModifiers,
+    pub pressed: bool,
+    /// Sent by the platform while the key is held down.
+    pub repeat: bool,
+}
+
+/// File dragged onto the window from another application, e.g. the file manager.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExternalFile {
+    pub path: PathBuf,
+    /// Position of the drag in physical pixels. On the platforms that don't report it
+    /// it's the last position of the pointer, which not all of them move with the drag.
+    pub position: Option<Vec2>,
+}
+
 #[derive(Default, Debug, Copy, Clone, Eq, PartialEq)]
 pub enum Cursor {
     #[default]
@@ -53,6 +107,104 @@ pub enum Cursor {
     NwseResize, // Northwest-Southeast diagonal
 }
 
+/// Input of a view, translated by the shell from the events of its platform, see
+/// [`crate::instance::ClewInstance::push_event`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum InputEvent {
+    /// Position of the pointer in physical pixels.
+    PointerMoved {
+        x: f32,
+        y: f32,
+    },
+    MouseButton {
+        button: MouseButton,
+        pressed: bool,
+    },
+    /// Scroll in physical pixels, fractional for the high resolution trackpads.
+    MouseWheel {
+        delta_x: f64,
+        delta_y: f64,
+    },
+    /// A finger touched, moved on or left the screen, at a position in physical pixels.
+    /// The first finger moves the pointer and holds the left button, the other ones are
+    /// ignored.
+    Touch {
+        id: u64,
+        phase: TouchPhase,
+        x: f32,
+        y: f32,
+    },
+    /// Magnification of the touchpad pinch gesture, positive values zoom in.
+    Pinch {
+        delta: f32,
+    },
+    ModifiersChanged(KeyModifiers),
+    Key {
+        /// `None` for the keys clew doesn't know.
+        key_code: Option<KeyCode>,
+        pressed: bool,
+        repeat: bool,
+        /// Text typed with the key.
+        text: Option<String>,
+    },
+    /// The input method was enabled for the view, e.g. for a focused text field.
+    ImeEnabled,
+    /// Text being composed with the input method, it replaces the previous one and
+    /// isn't part of the edited text until it's committed. `cursor` is the byte range
+    /// of the caret in the text, `None` hides the caret.
+    ImePreedit {
+        text: String,
+        cursor: Option<(usize, usize)>,
+        /// Empty when the platform doesn't tell the clauses.
+        clauses: Vec<ImeClause>,
+    },
+    /// Text the composition was finished with.
+    ImeCommit(String),
+    ImeDisabled,
+    /// The window of the view gained or lost the keyboard focus.
+    Focused(bool),
+    /// A file dragged from another application is over the window, sent for each of
+    /// the dragged files and again when the drag moves. Position in physical pixels.
+    FileHovered {
+        path: PathBuf,
+        position: Option<Vec2>,
+    },
+    /// A file has been dropped onto the window, sent for each of the dropped files.
+    FileDropped {
+        path: PathBuf,
+        position: Option<Vec2>,
+    },
+    /// The dragged files have left the window without being dropped.
+    FileHoverCancelled,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TouchPhase {
+    Started,
+    Moved,
+    Ended,
+    /// The platform took the touch over, e.g. for a system gesture.
+    Cancelled,
+}
+
+/// Segment of the text composed with the input method that is converted as a unit,
+/// e.g. a word of a Japanese sentence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImeClause {
+    /// Byte range in the preedit.
+    pub range: Range<usize>,
+    /// The clause being converted, the candidates of the input method are for it.
+    pub selected: bool,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MouseButton {
+    Left,
+    Right,
+    Middle,
+    Other,
+}
+
 #[derive(Default, Copy, Clone, Debug)]
 pub enum TextInputAction {
     #[default]
@@ -64,6 +216,53 @@ pub enum TextInputAction {
     Insert,
 }
 
+/// Time since the last user input of a view, it tells that the user has been away.
+#[derive(Debug, Clone)]
+pub struct IdleTimer {
+    last_input: Instant,
+    idle: bool,
+}
+
+impl Default for IdleTimer {
+    fn default() -> Self {
+        Self {
+            last_input: Instant::now(),
+            idle: false,
+        }
+    }
+}
+
+impl IdleTimer {
+    /// Restarts the timer, returns whether the view was idle until now.
+    pub fn on_input(&mut self) -> bool {
+        self.last_input = Instant::now();
+
+        std::mem::take(&mut self.idle)
+    }
+
+    pub fn idle_duration(&self) -> Duration {
+        self.last_input.elapsed()
+    }
+
+    /// Whether the threshold passed to [`IdleTimer::check`] was reached since the last
+    /// input.
+    pub fn is_idle(&self) -> bool {
+        self.idle
+    }
+
+    /// Marks the view idle once the time since the last input reaches the threshold,
+    /// returns `true` only the first time it's reached.
+    pub fn check(&mut self, threshold: Duration) -> bool {
+        if self.idle || self.idle_duration() < threshold {
+            return false;
+        }
+
+        self.idle = true;
+
+        true
+    }
+}
+
 #[derive(Default, Debug, Clone)]
 pub(crate) struct ClickTracker {
     click_count: u32,
@@ -118,10 +317,15 @@ impl UserInput {
     pub fn clear_frame_events(&mut self) {
         self.mouse_wheel_delta_x = 0.0;
         self.mouse_wheel_delta_y = 0.0;
+        self.pinch_delta = 0.0;
 
         self.text_input.clear();
     }
 
+    pub fn is_key_down(&self, key: KeyCode) -> bool {
+        self.keys_down.contains(&key)
+    }
+
     pub fn get_text_input(&self) -> &str {
         &self.text_input
     }
@@ -130,3 +334,115 @@ impl UserInput {
         &self.ime_preedit
     }
 }
+
+/// Read-only state of the keyboard for the widgets that handle the keys themselves,
+/// e.g. a game viewport or a piano keyboard, see [`crate::BuildContext::keyboard`].
+/// Outside of the focused widgets it reads as if no key was touched, so two widgets
+/// don't both react to the same keys.
+pub struct Keyboard<'a> {
+    input: &'a UserInput,
+    shortcuts: &'a ShortcutsManager,
+    registry: &'a ShortcutsRegistry,
+    active: bool,
+    shortcut_keys: bool,
+}
+
+impl<'a> Keyboard<'a> {
+    pub(crate) fn new(
+        input: &'a UserInput,
+        shortcuts: &'a ShortcutsManager,
+        registry: &'a ShortcutsRegistry,
+        active: bool,
+        shortcut_keys: bool,
+    ) -> Self {
+        Self {
+            input,
+            shortcuts,
+            registry,
+            active,
+            shortcut_keys,
+        }
+    }
+
+    /// Whether the widget reads the keyboard, i.e. it's focused or reads the keys
+    /// regardless of the focus.
+    pub fn is_active(&self) -> bool {
+        self.active
+    }
+
+    pub fn is_down(&self, key: KeyCode) -> bool {
+        self.active && self.input.is_key_down(key)
+    }
+
+    /// The key went down in this frame, without the repeats of a held key.
+    pub fn pressed(&self, key: KeyCode) -> bool {
+        self.events()
+            .any(|event| event.key == key && event.pressed && !event.repeat)
+    }
+
+    /// The key is held and the platform has repeated it in this frame.
+    pub fn repeated(&self, key: KeyCode) -> bool {
+        self.events()
+            .any(|event| event.key == key && event.pressed && event.repeat)
+    }
+
+    pub fn released(&self, key: KeyCode) -> bool {
+        self.events()
+            .any(|event| event.key == key && !event.pressed)
+    }
+
+    /// Key events of this frame. The presses of the keys that take part in the
+    /// shortcuts of the enclosing scopes go to the shortcuts and are left out, unless
+    /// the widget opts into them with
+    /// [`crate::widgets::gesture_detector::GestureDetectorBuilder::shortcut_keys`].
+    pub fn events(&self) -> impl Iterator<Item = &'a KeyEvent> + '_ {
+        self.input
+            .key_events
+            .iter()
+            .filter(|_| self.active)
+            .filter(|event| {
+                !event.pressed
+                    || self.shortcut_keys
+                    || !self.shortcuts.is_bound(
+                        self.registry,
+                        KeyBinding::new(event.key).with_modifiers(event.modifiers),
+                    )
+            })
+    }
+
+    /// Text typed in this frame.
+    pub fn text_this_frame(&self) -> &'a str {
+        if self.active {
+            &self.input.text_input
+        } else {
+            ""
+        }
+    }
+
+    pub fn modifiers(&self) -> KeyModifiers {
+        if self.active {
+            self.input.modifiers
+        } else {
+            KeyModifiers::empty()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_idle_is_reported_once_until_input() {
+        let mut timer = IdleTimer::default();
+
+        assert!(!timer.check(Duration::from_secs(60)));
+        assert!(timer.check(Duration::ZERO));
+        assert!(!timer.check(Duration::ZERO));
+        assert!(timer.is_idle());
+
+        assert!(timer.on_input());
+        assert!(!timer.is_idle());
+        assert!(!timer.on_input());
+    }
+}
@@ -1,16 +1,22 @@
+use std::ops::Range;
+
 use crate::{
-    AlignX, AlignY, Axis, Clip, Constraints, CrossAxisAlignment, DebugBoundary, EdgeInsets,
-    LayoutDirection, MainAxisAlignment, Rect, Size, SizeConstraint, Vec2, View, WidgetId,
-    WidgetRef, WidgetType,
-    assets::Assets,
+    AlignX, AlignY, Axis, Clip, Constraints, CrossAxisAlignment, DebugBoundary, DecoratorPlacement,
+    EdgeInsets, LayoutDirection, MainAxisAlignment, Rect, Size, SizeConstraint, Transform, Vec2,
+    View, WidgetId, WidgetIdMap, WidgetIdSet, WidgetRef, WidgetType,
+    assets::{Assets, MISSING_SVG_SIZE},
     rect_contains_boundary,
+    render::RenderCommand,
     state::TypedWidgetStates,
     text::{TextId, TextsResources},
+    widgets::{rich_text::placeholder_position, scroll_area::ScrollAreaWidget},
 };
 use smallvec::SmallVec;
 
 pub(crate) const RENDER_CONTAINER_DEBUG_BOUNDARIES: bool = false;
 pub(crate) const RENDER_CHILD_DEBUG_BOUNDARIES: bool = false;
+/// How much a row of a flow may overflow its width from the rounding of the sizes.
+const FLOW_TOLERANCE: f32 = 0.001;
 
 #[derive(Debug)]
 pub struct WidgetPlacement {
@@ -18,18 +24,43 @@ pub struct WidgetPlacement {
     pub zindex: i32,
     pub boundary: Rect,
     pub rect: Rect,
+    /// Intersection of all clip regions enclosing this placement.
+    pub clip_rect: Option<Rect>,
+    /// Accumulated transform from the placement coordinates to the view space.
+    pub transform: Option<Transform>,
 }
 
 #[derive(Debug)]
 pub enum LayoutItem {
     Placement(WidgetPlacement),
-    PushClip { rect: Rect, clip: Clip, zindex: i32 },
+    PushClip {
+        rect: Rect,
+        clip: Clip,
+        zindex: i32,
+    },
     PopClip,
-    BeginGroup { zindex: i32 },
+    PushTransform {
+        transform: Transform,
+        zindex: i32,
+    },
+    PopTransform,
+    BeginGroup {
+        zindex: i32,
+    },
     EndGroup,
+    BeginLayer {
+        id: WidgetId,
+        zindex: i32,
+    },
+    EndLayer,
+    Command {
+        zindex: i32,
+        command: Box<RenderCommand>,
+    },
 }
 
 #[derive(Debug, Clone)]
+#[non_exhaustive]
 pub enum LayoutCommand {
     BeginContainer {
         backgrounds: SmallVec<[WidgetRef; 8]>,
@@ -48,6 +79,55 @@ pub enum LayoutCommand {
         offset_y: f32,
     },
     EndOffset,
+    /// Offset of the enclosed widgets to the position of the inline box with the
+    /// placeholder in the text, see [`crate::widgets::rich_text`], plus the given
+    /// offset. Ends with [`LayoutCommand::EndOffset`].
+    BeginInlineOffset {
+        text_id: TextId,
+        placeholder: usize,
+        offset_x: f32,
+        offset_y: f32,
+    },
+    /// Offset of the content of the scroll area with the id, clamped to the range the
+    /// content allows once it's laid out. Ends with [`LayoutCommand::EndOffset`].
+    BeginScrollOffset {
+        id: WidgetId,
+        offset_x: f32,
+        offset_y: f32,
+    },
+    /// Renders and hit-tests the enclosed widgets through the transform, positions are
+    /// in the view space.
+    BeginTransform {
+        transform: Transform,
+    },
+    EndTransform,
+    /// Renders the enclosed widgets through the transform but places and hit-tests them
+    /// without it, e.g. for the overscroll stretch of
+    /// [`crate::widgets::scroll_area::OverscrollIndicator`].
+    BeginRenderTransform {
+        transform: Transform,
+    },
+    EndRenderTransform,
+    /// The enclosed widgets are drawn into a cache layer, see
+    /// [`crate::render::RenderCommand::PushLayer`].
+    BeginLayer {
+        id: WidgetId,
+    },
+    EndLayer,
+    /// The enclosed widgets are laid out but neither drawn nor hit-tested, their
+    /// placements are kept for the silhouette with the id, see
+    /// [`crate::widgets::silhouette`].
+    BeginHidden {
+        id: WidgetId,
+    },
+    EndHidden,
+    /// Aligns the enclosed widgets placed right in a z-stack by the given alignment
+    /// instead of the one of the stack, see [`crate::widgets::positioned`].
+    BeginAlign {
+        align_x: AlignX,
+        align_y: AlignY,
+    },
+    EndAlign,
     Leaf {
         widget_ref: WidgetRef,
         backgrounds: SmallVec<[WidgetRef; 8]>,
@@ -63,17 +143,32 @@ pub enum LayoutCommand {
     Spacer {
         constraints: Constraints,
         size: Size,
+        /// Main axis size a stack gap compresses down to when the stack is too tight.
+        main_axis_min: Option<f32>,
+        /// Main axis size a stack gap grows up to when the stack has free space.
+        main_axis_max: Option<f32>,
     },
 }
 
 #[derive(Debug, Clone, Copy)]
+#[non_exhaustive]
 pub enum DeriveWrapSize {
     Constraints,
     Text(TextId),
+    /// Height of the text clamped to `min_lines..=max_lines` visual lines, the rest is
+    /// scrolled by the widget.
+    TextLines {
+        text_id: TextId,
+        min_lines: u32,
+        max_lines: u32,
+    },
     Svg(&'static str),
+    /// Size known by the widget at build time, e.g. of a decoded bitmap.
+    Fixed(Vec2),
 }
 
 #[derive(Debug, Default, Clone, Copy)]
+#[non_exhaustive]
 pub enum ContainerKind {
     #[default]
     None,
@@ -115,6 +210,14 @@ enum StackAxis {
     Vertical {
         spacing: f32,
     },
+    /// Rows broken at `width`, the ones above the current `row` are summed up in `rows`.
+    Flow {
+        spacing: f32,
+        run_spacing: f32,
+        width: f32,
+        row: Option<Vec2>,
+        rows: Vec2,
+    },
 }
 
 #[derive(Default, Debug, Clone, Copy)]
@@ -140,6 +243,16 @@ enum StackAxisPass2 {
         _main_axis_alignment: MainAxisAlignment,
         cross_axis_alignment: CrossAxisAlignment,
     },
+    Flow {
+        rtl_aware: bool,
+        spacing: f32,
+        run_spacing: f32,
+        /// Where the rows start and how wide they are.
+        start_x: f32,
+        width: f32,
+        /// Height of the current row, `None` until a widget is placed in it.
+        row_height: Option<f32>,
+    },
 }
 
 #[derive(Debug, Default, Clone, Copy)]
@@ -171,6 +284,7 @@ struct Pass2LayoutContainer {
 pub(crate) struct TextLayout {
     pub(crate) width: f32,
     pub(crate) text_id: TextId,
+    pub(crate) visible_height: Option<f32>,
 }
 
 #[derive(Debug, Clone)]
@@ -196,6 +310,13 @@ pub(crate) struct LayoutState {
     flex_y: Vec<f32>,
     flex_sum_x: Vec<f32>,
     flex_sum_y: Vec<f32>,
+    /// Main axis wrap size of the flexible children of a stack.
+    flex_wrap_sizes: Vec<Vec2>,
+    /// How much a gap can compress and grow along the main axis of its stack.
+    gap_shrinks: Vec<f32>,
+    gap_grows: Vec<f32>,
+    gap_shrink_sums: Vec<f32>,
+    gap_grow_sums: Vec<f32>,
     constraints: Vec<Constraints>,
     margins: Vec<EdgeInsets>,
 
@@ -204,7 +325,7 @@ pub(crate) struct LayoutState {
 
     containers_stack_cursor: usize,
     pass_2_containers_stack_cursor: usize,
-    layout_direction: LayoutDirection,
+    pub(crate) layout_direction: LayoutDirection,
     parent_container: LayoutContainer,
     pass2_parent_container: Pass2LayoutContainer,
     containers_stack: Vec<LayoutContainer>,
@@ -213,10 +334,168 @@ pub(crate) struct LayoutState {
     offsets_stack_cursor: usize,
     offsets_stack: Vec<Vec2>,
 
+    clip_stack: Vec<Rect>,
+    /// Accumulated transforms and whether a clip has been pushed along with them.
+    transform_stack: Vec<(Transform, bool)>,
+
     pub(crate) texts: Vec<TextLayout>,
+
+    /// Scroll areas enclosing the current container, `None` for the other containers.
+    scroll_areas_stack: Vec<Option<WidgetId>>,
+    /// Widget whose rect and enclosing scroll areas are looked up during the layout.
+    pub(crate) scroll_into_view_target: Option<WidgetId>,
+    pub(crate) scroll_into_view: Option<ScrollIntoView>,
+    /// Scroll areas that keep a widget of their content in place.
+    pub(crate) scroll_anchors: WidgetIdMap<ScrollAnchor>,
+    /// Space the scroll areas reserve for their scroll bars.
+    pub(crate) scroll_gutters: WidgetIdMap<ScrollGutter>,
+
+    /// Alignments overriding the one of the z-stack, with the index of the z-stack
+    /// whose children they apply to.
+    align_stack: Vec<(usize, AlignX, AlignY)>,
+
+    /// Hidden widgets being laid out and where their items start.
+    hidden_stack: Vec<(WidgetId, usize)>,
+    /// Placements of the hidden widgets of each silhouette.
+    pub(crate) hidden: WidgetIdMap<Vec<WidgetPlacement>>,
+
+    /// Measured containers being placed and where their items start, `None` for the
+    /// other containers.
+    groups_stack: Vec<Option<(WidgetId, usize)>>,
+    /// Items of each measured container placed in this layout.
+    pub(crate) groups: WidgetIdMap<ItemsGroup>,
+    /// Measured containers moved after the layout, their content is placed even off the
+    /// screen since it may be moved into it.
+    pub(crate) moved_groups: WidgetIdSet,
+    moved_depth: usize,
+
+    /// Widths the flows have been placed with by their index, the next layout breaks
+    /// their rows at them.
+    flow_widths: Vec<f32>,
+}
+
+/// Layout items of a measured container, from its backgrounds to its foregrounds.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ItemsGroup {
+    pub(crate) items: Range<usize>,
+    /// Index of the item that ends the group of its content.
+    pub(crate) content_end: usize,
+}
+
+/// Space next to the content of a scroll area taken by its scroll bars, see
+/// [`crate::widgets::scroll_area::ScrollBarMode::Gutter`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct ScrollGutter {
+    pub(crate) size: f32,
+    /// The horizontal bar is shown below the content.
+    pub(crate) x: bool,
+    /// The vertical bar is shown at the end of the content.
+    pub(crate) y: bool,
+}
+
+impl ScrollGutter {
+    fn insets(&self) -> EdgeInsets {
+        EdgeInsets {
+            top: 0.,
+            left: 0.,
+            right: if self.y { self.size } else { 0. },
+            bottom: if self.x { self.size } else { 0. },
+        }
+    }
+}
+
+/// Widgets of the content of a scroll area that preserves its anchor, see
+/// [`crate::widgets::scroll_area::ScrollAreaBuilder::preserve_anchor`].
+#[derive(Debug)]
+pub(crate) struct ScrollAnchor {
+    /// Widget kept in place, picked in the previous frame.
+    pub(crate) widget: Option<WidgetId>,
+    /// Offset of the content that replaces the one the scroll area has been built with.
+    pub(crate) offset: Option<Vec2>,
+    /// Offset the content has been laid out with.
+    pub(crate) applied_offset: Vec2,
+    /// Where the widget has been placed.
+    pub(crate) placed: Option<Rect>,
+    /// First widget of the content that is fully inside the viewport.
+    pub(crate) first_visible: Option<(WidgetId, Rect)>,
+    viewport: Rect,
+    /// Depth of the offsets inside the content, the widgets above it belong to the
+    /// scroll area itself.
+    content_depth: usize,
+}
+
+impl ScrollAnchor {
+    pub(crate) fn new(widget: Option<WidgetId>) -> Self {
+        Self {
+            widget,
+            offset: None,
+            applied_offset: Vec2::ZERO,
+            placed: None,
+            first_visible: None,
+            viewport: Rect::ZERO,
+            content_depth: usize::MAX,
+        }
+    }
+}
+
+/// Where the widget requested to be scrolled into view has been placed.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ScrollIntoView {
+    pub(crate) rect: Rect,
+    /// Ids of the enclosing scroll areas, the innermost is the last one.
+    pub(crate) scroll_areas: SmallVec<[WidgetId; 4]>,
 }
 
 impl LayoutState {
+    /// Padding of the container along with the gutter its scroll area reserves.
+    fn container_padding(&self, kind: &ContainerKind, padding: EdgeInsets) -> EdgeInsets {
+        match kind {
+            ContainerKind::Measure { id } => match self.scroll_gutters.get(id) {
+                Some(gutter) => padding + gutter.insets(),
+                None => padding,
+            },
+            _ => padding,
+        }
+    }
+
+    /// Placements are looked up regardless of whether they're visible, the target is
+    /// usually outside of the view.
+    fn track_scroll_into_view(&mut self, id: WidgetId, rect: Rect) {
+        if self.scroll_into_view_target == Some(id) {
+            self.scroll_into_view = Some(ScrollIntoView {
+                rect,
+                scroll_areas: self.scroll_areas_stack.iter().flatten().copied().collect(),
+            });
+        }
+    }
+
+    /// Placements are looked up regardless of whether they're visible, the content
+    /// above the viewport may have shrunk past the anchor.
+    fn track_scroll_anchor(&mut self, id: WidgetId, rect: Rect) {
+        if self.scroll_anchors.is_empty() {
+            return;
+        }
+
+        let Some(scroll_area) = self.scroll_areas_stack.iter().rev().flatten().next() else {
+            return;
+        };
+        let Some(anchor) = self.scroll_anchors.get_mut(scroll_area) else {
+            return;
+        };
+
+        if self.offsets_stack_cursor < anchor.content_depth {
+            return;
+        }
+
+        if anchor.widget == Some(id) && anchor.placed.is_none() {
+            anchor.placed = Some(rect);
+        }
+
+        if anchor.first_visible.is_none() && anchor.viewport.contains_rect(rect) {
+            anchor.first_visible = Some((id, rect));
+        }
+    }
+
     #[inline]
     fn current_idx(&self) -> usize {
         self.cursor - 1
@@ -269,6 +548,11 @@ impl LayoutState {
             self.flex_y.push(0.);
             self.flex_sum_x.push(0.);
             self.flex_sum_y.push(0.);
+            self.flex_wrap_sizes.push(Vec2::ZERO);
+            self.gap_shrinks.push(0.);
+            self.gap_grows.push(0.);
+            self.gap_shrink_sums.push(0.);
+            self.gap_grow_sums.push(0.);
             self.constraints.push(Constraints::default());
             self.margins.push(EdgeInsets::ZERO);
         } else {
@@ -281,6 +565,11 @@ impl LayoutState {
             self.flex_y[self.cursor] = 0.;
             self.flex_sum_x[self.cursor] = 0.;
             self.flex_sum_y[self.cursor] = 0.;
+            self.flex_wrap_sizes[self.cursor] = Vec2::ZERO;
+            self.gap_shrinks[self.cursor] = 0.;
+            self.gap_grows[self.cursor] = 0.;
+            self.gap_shrink_sums[self.cursor] = 0.;
+            self.gap_grow_sums[self.cursor] = 0.;
             self.constraints[self.cursor] = Constraints::default();
             self.margins.push(EdgeInsets::ZERO);
         }
@@ -371,6 +660,73 @@ impl LayoutState {
         self.pass_2_containers_stack[self.pass_2_containers_stack_cursor].clone()
     }
 
+    #[inline]
+    fn push_clip(&mut self, rect: Rect) {
+        let rect = match self.clip_stack.last() {
+            Some(current) => current.intersect(rect),
+            None => rect,
+        };
+
+        self.clip_stack.push(rect);
+    }
+
+    #[inline]
+    fn pop_clip(&mut self) {
+        self.clip_stack.pop();
+    }
+
+    #[inline]
+    fn should_render(&self, rect: Rect, root_size: Vec2) -> bool {
+        self.moved_depth > 0
+            || rect_contains_boundary(rect, Rect::from_pos_size(Vec2::ZERO, root_size))
+    }
+
+    #[inline]
+    fn current_clip(&self) -> Option<Rect> {
+        self.clip_stack.last().copied()
+    }
+
+    /// Clip rects are kept in the local space of the current transform, so the
+    /// enclosing clip is mapped into the new space as well.
+    fn push_transform(&mut self, transform: Transform) {
+        let clip = self.current_clip();
+
+        if let Some(clip) = clip {
+            self.clip_stack.push(transform.apply_inverse_rect(clip));
+        }
+
+        let transform = match self.current_transform() {
+            Some(current) => transform.then(current),
+            None => transform,
+        };
+
+        self.transform_stack.push((transform, clip.is_some()));
+    }
+
+    fn pop_transform(&mut self) {
+        if let Some((_, clip_pushed)) = self.transform_stack.pop()
+            && clip_pushed
+        {
+            self.clip_stack.pop();
+        }
+    }
+
+    #[inline]
+    fn current_transform(&self) -> Option<Transform> {
+        self.transform_stack.last().map(|(transform, _)| *transform)
+    }
+
+    /// Alignment of the widgets placed in the current container, the one of the parent
+    /// z-stack unless a [`LayoutCommand::BeginAlign`] right in it overrides it.
+    fn stack_align(&self, align_x: AlignX, align_y: AlignY) -> (AlignX, AlignY) {
+        match self.align_stack.last() {
+            Some((idx, align_x, align_y)) if *idx == self.pass2_parent_container.idx => {
+                (*align_x, *align_y)
+            }
+            _ => (align_x, align_y),
+        }
+    }
+
     #[inline]
     fn clear(&mut self) {
         self.parent_container = LayoutContainer {
@@ -383,6 +739,9 @@ impl LayoutState {
         self.containers_stack_cursor = 0;
         self.offsets_stack_cursor = 0;
 
+        self.clip_stack.clear();
+        self.transform_stack.clear();
+        self.align_stack.clear();
         self.texts.clear();
     }
 
@@ -415,6 +774,54 @@ impl LayoutState {
         }
     }
 
+    fn add_gap_slack(&mut self, size: Size, actual_size: Vec2, min: Option<f32>, max: Option<f32>) {
+        let (main_size, preferred) = match self.parent_container.axis {
+            StackAxis::Horizontal { .. } => (size.width, actual_size.x),
+            StackAxis::Vertical { .. } => (size.height, actual_size.y),
+            StackAxis::None | StackAxis::Flow { .. } => return,
+        };
+
+        if let SizeConstraint::Fill(_) = main_size {
+            return;
+        }
+
+        let shrink = min.map_or(0., |min| (preferred - min).max(0.));
+        let grow = max.map_or(0., |max| (max - preferred).max(0.));
+
+        self.gap_shrinks[self.cursor - 1] = shrink;
+        self.gap_grows[self.cursor - 1] = grow;
+        self.gap_shrink_sums[self.parent_container.idx] += shrink;
+        self.gap_grow_sums[self.parent_container.idx] += grow;
+    }
+
+    /// How much the gaps of a stack compress (negative) or grow given the main axis
+    /// space `free` left for the flexible children. Gaps compress before the flexible
+    /// children get less than their wrap size and grow only if there are none of them.
+    fn gaps_delta(&self, container_idx: usize, flex_sum: f32, flex_wrap: f32, free: f32) -> f32 {
+        if free < flex_wrap {
+            -(flex_wrap - free).min(self.gap_shrink_sums[container_idx])
+        } else if flex_sum == 0. {
+            free.min(self.gap_grow_sums[container_idx])
+        } else {
+            0.
+        }
+    }
+
+    fn gap_size(&self, idx: usize, preferred: f32, delta: f32) -> f32 {
+        let container_idx = self.pass2_parent_container.idx;
+        let (slack, slack_sum) = if delta < 0. {
+            (self.gap_shrinks[idx], self.gap_shrink_sums[container_idx])
+        } else {
+            (self.gap_grows[idx], self.gap_grow_sums[container_idx])
+        };
+
+        if slack > 0. {
+            preferred + delta * slack / slack_sum
+        } else {
+            preferred
+        }
+    }
+
     fn add_container_size(&mut self, size: Size, wrap_size: Vec2) -> Vec2 {
         Vec2::new(
             self.add_width(size.width, wrap_size.x, 0.),
@@ -422,6 +829,75 @@ impl LayoutState {
         )
     }
 
+    /// Adds a widget of the size to the rows of the flow it's in.
+    fn add_flow_size(&mut self, size: Vec2) {
+        let StackAxis::Flow {
+            spacing,
+            run_spacing,
+            width,
+            row,
+            rows,
+        } = &mut self.parent_container.axis
+        else {
+            return;
+        };
+
+        *row = Some(match *row {
+            Some(current) if current.x + *spacing + size.x > *width + FLOW_TOLERANCE => {
+                rows.x = rows.x.max(current.x);
+                rows.y += current.y + *run_spacing;
+
+                size
+            }
+            Some(current) => Vec2::new(current.x + *spacing + size.x, current.y.max(size.y)),
+            None => size,
+        });
+    }
+
+    /// Where the next widget of the flow goes, at the start of a new row if it doesn't
+    /// fit in the current one.
+    fn place_in_flow(&mut self, position: Vec2, size: Vec2) -> Vec2 {
+        let StackAxisPass2::Flow {
+            rtl_aware,
+            run_spacing,
+            start_x,
+            width,
+            row_height,
+            ..
+        } = &mut self.pass2_parent_container.axis
+        else {
+            return position;
+        };
+
+        let rtl = *rtl_aware && self.layout_direction == LayoutDirection::RTL;
+        let used = if rtl {
+            *start_x - position.x
+        } else {
+            position.x - *start_x
+        };
+        let mut position = position;
+
+        if let Some(height) = *row_height
+            && used + size.x > *width + FLOW_TOLERANCE
+        {
+            position.x = *start_x;
+            position.y += height + *run_spacing;
+            *row_height = None;
+        }
+
+        *row_height = Some(row_height.unwrap_or(0.).max(size.y));
+
+        position
+    }
+
+    fn set_flow_width(&mut self, idx: usize, width: f32) {
+        if self.flow_widths.len() <= idx {
+            self.flow_widths.resize(idx + 1, f32::INFINITY);
+        }
+
+        self.flow_widths[idx] = width;
+    }
+
     fn add_size(
         &mut self,
         size: Size,
@@ -453,7 +929,7 @@ impl LayoutState {
                 let value = value + insets;
 
                 match self.parent_container.axis {
-                    StackAxis::None => {
+                    StackAxis::None | StackAxis::Flow { .. } => {
                         wrap_size.x = wrap_size.x.max(value);
                         flex_sizes.x = flex_sizes.x.max(value);
                     }
@@ -473,7 +949,7 @@ impl LayoutState {
                 let wrap_width = wrap_width + insets;
 
                 match self.parent_container.axis {
-                    StackAxis::None => {
+                    StackAxis::None | StackAxis::Flow { .. } => {
                         wrap_size.x = wrap_size.x.max(wrap_width);
                         flex_sizes.x = flex_sizes.x.max(wrap_width);
                     }
@@ -493,12 +969,13 @@ impl LayoutState {
                 let wrap_width = wrap_width + insets;
 
                 match self.parent_container.axis {
-                    StackAxis::None => {
+                    StackAxis::None | StackAxis::Flow { .. } => {
                         wrap_size.x = wrap_size.x.max(wrap_width);
                     }
                     StackAxis::Horizontal { spacing, .. } => {
                         wrap_size.x += wrap_width + spacing;
                         flex_sizes.x += spacing;
+                        self.flex_wrap_sizes[self.parent_container.idx].x += wrap_width;
                     }
                     StackAxis::Vertical { .. } => {
                         wrap_size.x = wrap_size.x.max(wrap_width);
@@ -519,7 +996,7 @@ impl LayoutState {
                 let value = value + insets;
 
                 match self.parent_container.axis {
-                    StackAxis::None => {
+                    StackAxis::None | StackAxis::Flow { .. } => {
                         wrap_size.y = wrap_size.y.max(value);
                         flex_sizes.y = flex_sizes.y.max(value);
                     }
@@ -539,7 +1016,7 @@ impl LayoutState {
                 let wrap_height = wrap_height + insets;
 
                 match self.parent_container.axis {
-                    StackAxis::None => {
+                    StackAxis::None | StackAxis::Flow { .. } => {
                         wrap_size.y = wrap_size.y.max(wrap_height);
                         flex_sizes.y = flex_sizes.y.max(wrap_height);
                     }
@@ -559,7 +1036,7 @@ impl LayoutState {
                 let wrap_height = wrap_height + insets;
 
                 match self.parent_container.axis {
-                    StackAxis::None => {
+                    StackAxis::None | StackAxis::Flow { .. } => {
                         wrap_size.y = wrap_size.y.max(wrap_height);
                     }
                     StackAxis::Horizontal { .. } => {
@@ -568,6 +1045,7 @@ impl LayoutState {
                     StackAxis::Vertical { spacing } => {
                         wrap_size.y += wrap_height + spacing;
                         flex_sizes.y += spacing;
+                        self.flex_wrap_sizes[self.parent_container.idx].y += wrap_height;
                     }
                 }
 
@@ -607,12 +1085,45 @@ fn apply_constraints(size: Vec2, constraints: Constraints) -> Vec2 {
     )
 }
 
+/// Size left inside the insets, empty rather than negative when they don't fit.
+#[inline]
+fn inset_size(size: Vec2, insets: EdgeInsets) -> Vec2 {
+    Vec2::new(
+        (size.x - insets.horizontal()).max(0.),
+        (size.y - insets.vertical()).max(0.),
+    )
+}
+
+/// Rounds the translation of scrolled content to the pixels the view is rendered with,
+/// so the content is drawn sharp. The scroll offsets themselves keep their fractions.
+/// At a fractional scale the result is fractional in logical pixels, e.g. a multiple of
+/// 0.8 at 1.25.
+#[inline]
+pub(crate) fn snap_to_pixels(value: f32, scale_factor: f32) -> f32 {
+    (value * scale_factor).round() / scale_factor
+}
+
+/// Rect of a background or foreground, the decorated rect unless the decoration has a
+/// placement of its own.
+fn placed_decorator_rect(
+    decorator_placements: &TypedWidgetStates<DecoratorPlacement>,
+    widget_ref: &WidgetRef,
+    rect: Rect,
+    layout_direction: LayoutDirection,
+) -> Rect {
+    decorator_placements
+        .get(widget_ref.id)
+        .map_or(rect, |placement| placement.resolve(rect, layout_direction))
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn layout(
     layout_state: &mut LayoutState,
     view: &View,
     commands: &[LayoutCommand],
     layout_items: &mut Vec<LayoutItem>,
     layout_measures: &mut TypedWidgetStates<LayoutMeasure>,
+    decorator_placements: &TypedWidgetStates<DecoratorPlacement>,
     text: &mut TextsResources,
     assets: &Assets,
 ) {
@@ -641,7 +1152,7 @@ pub fn layout(
                 layout_state.set_constraints(*constraints);
                 layout_state.set_margin(*margin);
 
-                let insets = *padding + *margin;
+                let insets = layout_state.container_padding(kind, *padding) + *margin;
 
                 // layout_state.set_offset(Vec2::new(padding.left, padding.top));
                 layout_state.set_resize(Vec2::new(-insets.horizontal(), -insets.vertical()));
@@ -725,13 +1236,30 @@ pub fn layout(
                         };
                     }
                     ContainerKind::Flow {
-                        spacing, rtl_aware, ..
+                        spacing,
+                        run_spacing,
+                        ..
                     } => {
+                        let idx = layout_state.current_idx();
+                        // Without a fixed width the rows are broken at the width of the
+                        // previous layout, the texts are wrapped the same way.
+                        let width = match size.width {
+                            SizeConstraint::Fixed(width) => width - insets.horizontal(),
+                            _ => layout_state
+                                .flow_widths
+                                .get(idx)
+                                .copied()
+                                .unwrap_or(f32::INFINITY),
+                        };
+
                         layout_state.parent_container = LayoutContainer {
-                            idx: layout_state.current_idx(),
-                            axis: StackAxis::Horizontal {
+                            idx,
+                            axis: StackAxis::Flow {
                                 spacing: *spacing,
-                                _rtl_aware: *rtl_aware,
+                                run_spacing: *run_spacing,
+                                width,
+                                row: None,
+                                rows: Vec2::ZERO,
                             },
                             command: LayoutContainerCommand {
                                 kind: *kind,
@@ -761,11 +1289,13 @@ pub fn layout(
                         wrap_size.x -= spacing;
                         wrap_size.x = wrap_size.x.max(0.);
                     }
-                    ContainerKind::Flow { spacing, .. } => {
-                        wrap_size.x -= spacing;
-                        wrap_size.x = wrap_size.x.max(0.);
-                        wrap_size.y -= spacing;
-                        wrap_size.y = wrap_size.y.max(0.);
+                    ContainerKind::Flow { .. } => {
+                        if let StackAxis::Flow { row, rows, .. } =
+                            layout_state.parent_container.axis
+                        {
+                            let row = row.unwrap_or(Vec2::ZERO);
+                            *wrap_size = Vec2::new(rows.x.max(row.x), rows.y + row.y);
+                        }
                     }
                     ContainerKind::ZStack { .. } => {}
                     ContainerKind::None => {}
@@ -785,8 +1315,9 @@ pub fn layout(
                 layout_state.parent_container = layout_state.pop_container();
 
                 let size = layout_state.add_container_size(size, wrap_size);
-                layout_state.actual_sizes[current_container_idx] =
-                    apply_constraints(size, constraints);
+                let size = apply_constraints(size, constraints);
+                layout_state.actual_sizes[current_container_idx] = size;
+                layout_state.add_flow_size(size);
             }
             LayoutCommand::Leaf {
                 constraints,
@@ -814,25 +1345,56 @@ pub fn layout(
 
                         text_size / view.scale_factor
                     }
-                    DeriveWrapSize::Svg(asset_id) => {
-                        let tree = assets
-                            .get_svg_tree(asset_id)
-                            .unwrap_or_else(|| panic!("SVG with ID = {asset_id} has not found"));
+                    DeriveWrapSize::TextLines {
+                        text_id,
+                        min_lines,
+                        max_lines,
+                    } => {
+                        let width = text.get_mut(*text_id).layout().x;
+                        let height = text.get(*text_id).lines_height(*min_lines, *max_lines);
 
-                        Vec2::new(tree.size().width(), tree.size().height())
+                        Vec2::new(width, height) / view.scale_factor
                     }
+                    DeriveWrapSize::Svg(asset_id) => match assets.find_svg_tree(asset_id) {
+                        Some(tree) => Vec2::new(tree.size().width(), tree.size().height()),
+                        None => MISSING_SVG_SIZE,
+                    },
+                    DeriveWrapSize::Fixed(size) => *size,
                 };
                 // };
 
-                layout_state.add_size(*size, *constraints, wrap_size, *padding + *margin);
+                let size =
+                    layout_state.add_size(*size, *constraints, wrap_size, *padding + *margin);
+                layout_state.add_flow_size(size);
             }
-            LayoutCommand::Spacer { constraints, size } => {
+            LayoutCommand::Spacer {
+                constraints,
+                size,
+                main_axis_min,
+                main_axis_max,
+            } => {
                 layout_state.push_boundary();
                 layout_state.set_constraints(*constraints);
                 layout_state.add_flex_sum(*size);
-                layout_state.add_size(*size, *constraints, Vec2::ZERO, EdgeInsets::ZERO);
+                let actual_size =
+                    layout_state.add_size(*size, *constraints, Vec2::ZERO, EdgeInsets::ZERO);
+                layout_state.add_gap_slack(*size, actual_size, *main_axis_min, *main_axis_max);
+                layout_state.add_flow_size(actual_size);
             }
-            LayoutCommand::BeginOffset { .. } | LayoutCommand::EndOffset => {
+            LayoutCommand::BeginOffset { .. }
+            | LayoutCommand::BeginInlineOffset { .. }
+            | LayoutCommand::BeginScrollOffset { .. }
+            | LayoutCommand::EndOffset
+            | LayoutCommand::BeginTransform { .. }
+            | LayoutCommand::EndTransform
+            | LayoutCommand::BeginRenderTransform { .. }
+            | LayoutCommand::EndRenderTransform
+            | LayoutCommand::BeginLayer { .. }
+            | LayoutCommand::EndLayer
+            | LayoutCommand::BeginHidden { .. }
+            | LayoutCommand::EndHidden
+            | LayoutCommand::BeginAlign { .. }
+            | LayoutCommand::EndAlign => {
                 // No-op
             }
         }
@@ -850,6 +1412,19 @@ pub fn layout(
     let mut current_position = Vec2::ZERO;
 
     layout_items.clear();
+    layout_state.hidden_stack.clear();
+    layout_state.hidden.clear();
+    layout_state.groups_stack.clear();
+    layout_state.groups.clear();
+    layout_state.moved_depth = 0;
+    layout_state.scroll_areas_stack.clear();
+    layout_state.scroll_into_view = None;
+
+    for anchor in layout_state.scroll_anchors.values_mut() {
+        anchor.placed = None;
+        anchor.first_visible = None;
+        anchor.content_depth = usize::MAX;
+    }
 
     layout_state.push_position(current_position);
     layout_state.pass2_parent_container = Pass2LayoutContainer {
@@ -871,10 +1446,8 @@ pub fn layout(
 
         let container_position = layout_state.positions[layout_state.position_cursor - 1];
         let container_resize = layout_state.resizes[container_idx];
-        let container_margin = layout_state.margins[container_idx];
         let container_size_resized = layout_state.actual_sizes[container_idx] + container_resize;
         let container_size = layout_state.actual_sizes[container_idx];
-        let container_wrap_size = layout_state.wrap_sizes[container_idx];
 
         let flex_x = layout_state.flex_x[current_idx];
         let flex_y = layout_state.flex_y[current_idx];
@@ -889,14 +1462,22 @@ pub fn layout(
                 }
                 StackAxisPass2::Align { .. } => container_size_resized.x,
                 StackAxisPass2::Horizontal { spacing, .. } => {
-                    let flex_sum_x = layout_state.flex_sum_x[container_idx].max(1.);
-                    let available_width =
-                        (container_size_resized.x - container_flex_size.x + spacing).max(0.);
-                    let per_flex = available_width / flex_sum_x;
+                    let flex_sum_x = layout_state.flex_sum_x[container_idx];
+                    let free_width = container_size_resized.x - container_flex_size.x + spacing;
+                    let gaps_delta = layout_state.gaps_delta(
+                        container_idx,
+                        flex_sum_x,
+                        layout_state.flex_wrap_sizes[container_idx].x,
+                        free_width,
+                    );
+                    let available_width = (free_width - gaps_delta).max(0.);
+                    let per_flex = available_width / flex_sum_x.max(1.);
 
                     flex_x * per_flex
                 }
-                StackAxisPass2::Vertical { .. } => container_size_resized.x,
+                StackAxisPass2::Vertical { .. } | StackAxisPass2::Flow { .. } => {
+                    container_size_resized.x
+                }
             };
 
             // let wrap_size = layout_state.wrap_sizes[current_idx].x;
@@ -913,12 +1494,20 @@ pub fn layout(
                     container_size_resized.y
                 }
                 StackAxisPass2::Align { .. } => container_size_resized.y,
-                StackAxisPass2::Horizontal { .. } => container_size_resized.y,
+                StackAxisPass2::Horizontal { .. } | StackAxisPass2::Flow { .. } => {
+                    container_size_resized.y
+                }
                 StackAxisPass2::Vertical { spacing, .. } => {
-                    let flex_sum_y = layout_state.flex_sum_y[container_idx].max(1.);
-                    let available_height =
-                        (container_size_resized.y - container_flex_size.y + spacing).max(0.);
-                    let per_flex = available_height / flex_sum_y;
+                    let flex_sum_y = layout_state.flex_sum_y[container_idx];
+                    let free_height = container_size_resized.y - container_flex_size.y + spacing;
+                    let gaps_delta = layout_state.gaps_delta(
+                        container_idx,
+                        flex_sum_y,
+                        layout_state.flex_wrap_sizes[container_idx].y,
+                        free_height,
+                    );
+                    let available_height = (free_height - gaps_delta).max(0.);
+                    let per_flex = available_height / flex_sum_y.max(1.);
 
                     flex_y * per_flex
                 }
@@ -931,41 +1520,43 @@ pub fn layout(
 
         let mut widget_size = layout_state.actual_sizes[current_idx];
 
+        if let LayoutCommand::BeginContainer { .. }
+        | LayoutCommand::Leaf { .. }
+        | LayoutCommand::Spacer { .. } = command
+        {
+            current_position = layout_state.place_in_flow(current_position, widget_size);
+        }
+
         let (boundary_position, boundary_size) = match layout_state.pass2_parent_container.axis {
             StackAxisPass2::None | StackAxisPass2::Passthrough { .. } => (
                 container_position,
-                Vec2::new(
-                    container_size.x - layout_state.pass2_parent_container.padding.horizontal(),
-                    container_size.y - layout_state.pass2_parent_container.padding.vertical(),
-                ),
+                inset_size(container_size, layout_state.pass2_parent_container.padding),
             ),
             StackAxisPass2::Align { .. } => (
                 container_position,
-                Vec2::new(
-                    container_size.x - layout_state.pass2_parent_container.padding.horizontal(),
-                    container_size.y - layout_state.pass2_parent_container.padding.vertical(),
-                ),
+                inset_size(container_size, layout_state.pass2_parent_container.padding),
             ),
             StackAxisPass2::Horizontal { .. } => (
                 Vec2::new(current_position.x, current_position.y),
                 Vec2::new(
                     widget_size.x,
-                    container_size.y - layout_state.pass2_parent_container.padding.vertical(),
+                    inset_size(container_size, layout_state.pass2_parent_container.padding).y,
                 ),
             ),
             StackAxisPass2::Vertical { .. } => (
                 Vec2::new(current_position.x, current_position.y),
                 Vec2::new(
-                    container_size.x - layout_state.pass2_parent_container.padding.horizontal(),
+                    inset_size(container_size, layout_state.pass2_parent_container.padding).x,
                     widget_size.y,
                 ),
             ),
+            StackAxisPass2::Flow { .. } => (current_position, widget_size),
         };
 
         let mut boundary = Rect::from_pos_size(boundary_position, boundary_size);
         let mut position = current_position;
 
-        if let StackAxisPass2::Horizontal { rtl_aware, .. } =
+        if let StackAxisPass2::Horizontal { rtl_aware, .. } | StackAxisPass2::Flow { rtl_aware, .. } =
             layout_state.pass2_parent_container.axis
             && rtl_aware
             && layout_state.layout_direction == LayoutDirection::RTL
@@ -977,6 +1568,7 @@ pub fn layout(
         match layout_state.pass2_parent_container.axis {
             StackAxisPass2::None => {}
             StackAxisPass2::Align { .. } => {}
+            StackAxisPass2::Flow { .. } => {}
             StackAxisPass2::Horizontal {
                 cross_axis_alignment,
                 ..
@@ -1016,10 +1608,118 @@ pub fn layout(
                 layout_state.push_offset(Vec2::new(*offset_x, *offset_y));
                 continue;
             }
+            LayoutCommand::BeginInlineOffset {
+                text_id,
+                placeholder,
+                offset_x,
+                offset_y,
+            } => {
+                let position =
+                    placeholder_position(text.get(*text_id), *placeholder).unwrap_or(Vec2::ZERO);
+
+                layout_state
+                    .push_offset(position / view.scale_factor + Vec2::new(*offset_x, *offset_y));
+                continue;
+            }
+            LayoutCommand::BeginScrollOffset {
+                id,
+                offset_x,
+                offset_y,
+            } => {
+                let anchor = layout_state.scroll_anchors.get_mut(id);
+                let offset = anchor
+                    .as_ref()
+                    .and_then(|it| it.offset)
+                    .unwrap_or(Vec2::new(*offset_x, *offset_y));
+                let mut offset = Vec2::new(
+                    snap_to_pixels(offset.x, view.scale_factor),
+                    snap_to_pixels(offset.y, view.scale_factor),
+                );
+
+                // The content may have shrunk since the scroll area has been built.
+                if let Some(measure) = layout_measures.get(*id) {
+                    offset.x = offset
+                        .x
+                        .clamp(f32::min(0., -(measure.wrap_width - measure.width)), 0.);
+                    offset.y = offset
+                        .y
+                        .clamp(f32::min(0., -(measure.wrap_height - measure.height)), 0.);
+                }
+
+                if let Some(anchor) = anchor {
+                    anchor.applied_offset = offset;
+                    anchor.content_depth = layout_state.offsets_stack_cursor + 1;
+                }
+
+                layout_state.push_offset(offset);
+                continue;
+            }
             LayoutCommand::EndOffset => {
                 layout_state.pop_offset();
                 continue;
             }
+            LayoutCommand::BeginAlign { align_x, align_y } => {
+                let idx = layout_state.pass2_parent_container.idx;
+                layout_state.align_stack.push((idx, *align_x, *align_y));
+                continue;
+            }
+            LayoutCommand::EndAlign => {
+                layout_state.align_stack.pop();
+                continue;
+            }
+            LayoutCommand::BeginTransform { transform } => {
+                layout_state.push_transform(*transform);
+                layout_items.push(LayoutItem::PushTransform {
+                    transform: *transform,
+                    zindex: layout_state.pass2_parent_container.zindex,
+                });
+                continue;
+            }
+            LayoutCommand::EndTransform => {
+                layout_state.pop_transform();
+                layout_items.push(LayoutItem::PopTransform);
+                continue;
+            }
+            LayoutCommand::BeginRenderTransform { transform } => {
+                layout_items.push(LayoutItem::PushTransform {
+                    transform: *transform,
+                    zindex: layout_state.pass2_parent_container.zindex,
+                });
+                continue;
+            }
+            LayoutCommand::EndRenderTransform => {
+                layout_items.push(LayoutItem::PopTransform);
+                continue;
+            }
+            LayoutCommand::BeginLayer { id } => {
+                layout_items.push(LayoutItem::BeginLayer {
+                    id: *id,
+                    zindex: layout_state.pass2_parent_container.zindex,
+                });
+                continue;
+            }
+            LayoutCommand::EndLayer => {
+                layout_items.push(LayoutItem::EndLayer);
+                continue;
+            }
+            LayoutCommand::BeginHidden { id } => {
+                layout_state.hidden_stack.push((*id, layout_items.len()));
+                continue;
+            }
+            LayoutCommand::EndHidden => {
+                if let Some((id, start)) = layout_state.hidden_stack.pop() {
+                    let placements = layout_items
+                        .drain(start..)
+                        .filter_map(|item| match item {
+                            LayoutItem::Placement(placement) => Some(placement),
+                            _ => None,
+                        })
+                        .collect();
+
+                    layout_state.hidden.insert(id, placements);
+                }
+                continue;
+            }
             LayoutCommand::BeginContainer {
                 kind,
                 zindex,
@@ -1030,6 +1730,7 @@ pub fn layout(
                 clip,
                 ..
             } => {
+                let padding = &layout_state.container_padding(kind, *padding);
                 let parent_container_axis = layout_state.pass2_parent_container.axis;
 
                 layout_state.push_position(current_position);
@@ -1041,8 +1742,12 @@ pub fn layout(
 
                 let align_x = match layout_state.pass2_parent_container.axis {
                     StackAxisPass2::None | StackAxisPass2::Passthrough { .. } => AlignX::Start,
-                    StackAxisPass2::Align { align_x, .. } => align_x,
-                    StackAxisPass2::Horizontal { .. } => AlignX::Start,
+                    StackAxisPass2::Align { align_x, align_y } => {
+                        layout_state.stack_align(align_x, align_y).0
+                    }
+                    StackAxisPass2::Horizontal { .. } | StackAxisPass2::Flow { .. } => {
+                        AlignX::Start
+                    }
                     StackAxisPass2::Vertical {
                         rtl_aware,
                         cross_axis_alignment,
@@ -1072,7 +1777,9 @@ pub fn layout(
 
                 let align_y = match layout_state.pass2_parent_container.axis {
                     StackAxisPass2::None | StackAxisPass2::Passthrough { .. } => AlignY::Top,
-                    StackAxisPass2::Align { align_y, .. } => align_y,
+                    StackAxisPass2::Align { align_x, align_y } => {
+                        layout_state.stack_align(align_x, align_y).1
+                    }
                     StackAxisPass2::Horizontal {
                         cross_axis_alignment,
                         ..
@@ -1085,7 +1792,7 @@ pub fn layout(
                             todo!()
                         }
                     },
-                    StackAxisPass2::Vertical { .. } => AlignY::Top,
+                    StackAxisPass2::Vertical { .. } | StackAxisPass2::Flow { .. } => AlignY::Top,
                 };
 
                 current_position += Vec2::new(
@@ -1104,6 +1811,8 @@ pub fn layout(
                         zindex: i32::MAX,
                         boundary: Rect::ZERO,
                         rect: Rect::from_pos_size(current_container_position, widget_size),
+                        clip_rect: None,
+                        transform: None,
                     }));
 
                     layout_items.push(LayoutItem::Placement(WidgetPlacement {
@@ -1114,27 +1823,66 @@ pub fn layout(
                         zindex: i32::MAX,
                         boundary: Rect::ZERO,
                         rect: Rect::from_pos_size(boundary.position() + offset, boundary.size()),
+                        clip_rect: None,
+                        transform: None,
                     }));
                 }
 
-                let inside_size = widget_size - Vec2::new(margin.horizontal(), margin.vertical());
+                let inside_size = inset_size(widget_size, *margin);
                 let decorator_rect = Rect::from_pos_size(current_position + offset, inside_size);
 
+                for widget_ref in backgrounds.iter().chain(foregrounds) {
+                    layout_state.track_scroll_into_view(widget_ref.id, decorator_rect);
+                    layout_state.track_scroll_anchor(widget_ref.id, decorator_rect);
+                }
+
+                // Scroll areas are measured containers with their own background.
+                let scroll_area = match kind {
+                    ContainerKind::Measure { id } => backgrounds
+                        .iter()
+                        .any(|it| {
+                            it.id == *id && it.widget_type == WidgetType::of::<ScrollAreaWidget>()
+                        })
+                        .then_some(*id),
+                    _ => None,
+                };
+                layout_state.scroll_areas_stack.push(scroll_area);
+                layout_state.groups_stack.push(match kind {
+                    ContainerKind::Measure { id } => Some((*id, layout_items.len())),
+                    _ => None,
+                });
+
+                if let ContainerKind::Measure { id } = kind
+                    && layout_state.moved_groups.contains(id)
+                {
+                    layout_state.moved_depth += 1;
+                }
+
                 for widget_ref in backgrounds {
-                    if rect_contains_boundary(
+                    if layout_state.should_render(
                         Rect::from_pos_size(position + offset, inside_size),
-                        Rect::from_pos_size(Vec2::ZERO, root_size),
+                        root_size,
                     ) {
+                        let rect = placed_decorator_rect(
+                            decorator_placements,
+                            widget_ref,
+                            decorator_rect,
+                            layout_state.layout_direction,
+                        );
+
                         layout_items.push(LayoutItem::Placement(WidgetPlacement {
                             widget_ref: *widget_ref,
                             zindex: *zindex,
-                            boundary: decorator_rect,
-                            rect: decorator_rect,
+                            boundary: rect,
+                            rect,
+                            clip_rect: layout_state.current_clip(),
+                            transform: layout_state.current_transform(),
                         }));
                     }
                 }
 
                 if *clip != Clip::None {
+                    layout_state.push_clip(decorator_rect);
                     layout_items.push(LayoutItem::PushClip {
                         rect: decorator_rect,
                         clip: *clip,
@@ -1200,7 +1948,38 @@ pub fn layout(
                         current_idx += 1;
                         go_next = false;
                     }
-                    ContainerKind::Flow { .. } => todo!(),
+                    ContainerKind::Flow {
+                        spacing,
+                        run_spacing,
+                        rtl_aware,
+                    } => {
+                        let width = inset_size(widget_size, *margin + *padding).x;
+                        layout_state.set_flow_width(current_idx, width);
+
+                        if *rtl_aware && layout_state.layout_direction == LayoutDirection::RTL {
+                            current_position.x += width;
+                        }
+
+                        layout_state.pass2_parent_container = Pass2LayoutContainer {
+                            idx: current_idx,
+                            clipping,
+                            padding: *padding,
+                            zindex: *zindex,
+                            decorator_rect,
+                            foregrounds: foregrounds.clone(),
+                            axis: StackAxisPass2::Flow {
+                                rtl_aware: *rtl_aware,
+                                spacing: *spacing,
+                                run_spacing: *run_spacing,
+                                start_x: current_position.x,
+                                width,
+                                row_height: None,
+                            },
+                        };
+
+                        current_idx += 1;
+                        go_next = false;
+                    }
                     ContainerKind::ZStack { align_x, align_y } => {
                         layout_state.pass2_parent_container = Pass2LayoutContainer {
                             padding: *padding,
@@ -1244,7 +2023,8 @@ pub fn layout(
                                 stretch: match parent_container_axis {
                                     StackAxisPass2::None
                                     | StackAxisPass2::Passthrough { .. }
-                                    | StackAxisPass2::Align { .. } => None,
+                                    | StackAxisPass2::Align { .. }
+                                    | StackAxisPass2::Flow { .. } => None,
                                     StackAxisPass2::Horizontal {
                                         cross_axis_alignment,
                                         ..
@@ -1283,17 +2063,25 @@ pub fn layout(
                             axis: StackAxisPass2::None,
                         };
 
-                        layout_measures.set(
-                            *id,
-                            LayoutMeasure {
-                                x: current_container_position.x + margin.left,
-                                y: current_container_position.y + margin.top,
-                                width: widget_size.x - margin.horizontal(),
-                                height: widget_size.y - margin.vertical(),
-                                wrap_width: container_wrap_size.x - container_margin.horizontal(),
-                                wrap_height: container_wrap_size.y - container_margin.vertical(),
-                            },
-                        );
+                        let size = inset_size(widget_size, *margin);
+                        // The wrap size of the content, not of the column it's in with
+                        // e.g. fixed headers.
+                        let wrap_size = layout_state.wrap_sizes[current_idx];
+                        let measure = LayoutMeasure {
+                            x: current_container_position.x + margin.left,
+                            y: current_container_position.y + margin.top,
+                            width: size.x,
+                            height: size.y,
+                            wrap_width: wrap_size.x,
+                            wrap_height: wrap_size.y,
+                        };
+
+                        if let Some(anchor) = layout_state.scroll_anchors.get_mut(id) {
+                            anchor.viewport =
+                                Rect::new(measure.x, measure.y, measure.width, measure.height);
+                        }
+
+                        layout_measures.set(*id, measure);
 
                         current_idx += 1;
                         go_next = false;
@@ -1305,21 +2093,55 @@ pub fn layout(
                 let container = layout_state.pass2_parent_container.clone();
                 layout_state.pass2_parent_container = layout_state.pop_pass2_container();
                 current_position = layout_state.pop_position();
+                layout_state.scroll_areas_stack.pop();
+
+                let content_end = layout_items.len();
 
                 if container.clipping {
+                    layout_state.pop_clip();
                     layout_items.push(LayoutItem::PopClip);
                 } else {
                     layout_items.push(LayoutItem::EndGroup);
                 }
 
                 for widget_ref in &container.foregrounds {
+                    let rect = placed_decorator_rect(
+                        decorator_placements,
+                        widget_ref,
+                        container.decorator_rect,
+                        layout_state.layout_direction,
+                    );
+
                     layout_items.push(LayoutItem::Placement(WidgetPlacement {
                         widget_ref: *widget_ref,
                         zindex: container.zindex,
-                        boundary: container.decorator_rect,
-                        rect: container.decorator_rect,
+                        boundary: rect,
+                        rect,
+                        clip_rect: layout_state.current_clip(),
+                        transform: layout_state.current_transform(),
                     }));
                 }
+
+                let group = layout_state.groups_stack.pop().flatten();
+
+                if let Some((id, _)) = group
+                    && layout_state.moved_groups.contains(&id)
+                {
+                    layout_state.moved_depth -= 1;
+                }
+
+                // The items of the hidden widgets are gone by the time they're used.
+                if let Some((id, start)) = group
+                    && layout_state.hidden_stack.is_empty()
+                {
+                    layout_state.groups.insert(
+                        id,
+                        ItemsGroup {
+                            items: start..layout_items.len(),
+                            content_end,
+                        },
+                    );
+                }
             }
             LayoutCommand::Leaf {
                 widget_ref,
@@ -1330,12 +2152,17 @@ pub fn layout(
                 margin,
                 derive_wrap_size,
                 clip,
+                size,
                 ..
             } => {
                 let align_x = match layout_state.pass2_parent_container.axis {
                     StackAxisPass2::None | StackAxisPass2::Passthrough { .. } => AlignX::Start,
-                    StackAxisPass2::Align { align_x, .. } => align_x,
-                    StackAxisPass2::Horizontal { .. } => AlignX::Start,
+                    StackAxisPass2::Align { align_x, align_y } => {
+                        layout_state.stack_align(align_x, align_y).0
+                    }
+                    StackAxisPass2::Horizontal { .. } | StackAxisPass2::Flow { .. } => {
+                        AlignX::Start
+                    }
                     StackAxisPass2::Vertical {
                         rtl_aware,
                         cross_axis_alignment,
@@ -1365,7 +2192,9 @@ pub fn layout(
 
                 let align_y = match layout_state.pass2_parent_container.axis {
                     StackAxisPass2::None | StackAxisPass2::Passthrough { .. } => AlignY::Top,
-                    StackAxisPass2::Align { align_y, .. } => align_y,
+                    StackAxisPass2::Align { align_x, align_y } => {
+                        layout_state.stack_align(align_x, align_y).1
+                    }
                     StackAxisPass2::Horizontal {
                         cross_axis_alignment,
                         ..
@@ -1378,7 +2207,7 @@ pub fn layout(
                             todo!()
                         }
                     },
-                    StackAxisPass2::Vertical { .. } => AlignY::Top,
+                    StackAxisPass2::Vertical { .. } | StackAxisPass2::Flow { .. } => AlignY::Top,
                 };
 
                 let decorators_rect = Rect::from_pos_size(
@@ -1393,35 +2222,47 @@ pub fn layout(
                             align_y.position(boundary.height, widget_size.y),
                         )
                         + offset,
-                    widget_size - Vec2::new(margin.horizontal(), margin.vertical()),
+                    inset_size(widget_size, *margin),
                 );
 
                 let boundary = Rect::from_pos_size(boundary.position() + offset, boundary.size());
 
+                for widget_ref in backgrounds.iter().chain(foregrounds).chain([widget_ref]) {
+                    layout_state.track_scroll_into_view(widget_ref.id, decorators_rect);
+                    layout_state.track_scroll_anchor(widget_ref.id, decorators_rect);
+                }
+
                 // Don't render anything outside the screen view
-                let should_render = rect_contains_boundary(
-                    decorators_rect,
-                    Rect::from_pos_size(Vec2::ZERO, root_size),
-                );
+                let should_render = layout_state.should_render(decorators_rect, root_size);
 
                 if should_render {
                     for widget_ref in backgrounds {
+                        let rect = placed_decorator_rect(
+                            decorator_placements,
+                            widget_ref,
+                            decorators_rect,
+                            layout_state.layout_direction,
+                        );
+
                         layout_items.push(LayoutItem::Placement(WidgetPlacement {
                             widget_ref: *widget_ref,
                             zindex: *zindex,
-                            boundary: decorators_rect,
-                            rect: decorators_rect,
+                            boundary: rect,
+                            rect,
+                            clip_rect: layout_state.current_clip(),
+                            transform: layout_state.current_transform(),
                         }));
                     }
                 }
 
                 let rect = Rect::from_pos_size(
                     decorators_rect.position() + Vec2::new(padding.left, padding.top),
-                    decorators_rect.size() - Vec2::new(padding.horizontal(), padding.vertical()),
+                    inset_size(decorators_rect.size(), *padding),
                 );
 
                 if should_render {
                     if *clip != Clip::None {
+                        layout_state.push_clip(decorators_rect);
                         layout_items.push(LayoutItem::PushClip {
                             rect: decorators_rect,
                             clip: *clip,
@@ -1434,28 +2275,53 @@ pub fn layout(
                         zindex: *zindex,
                         boundary: decorators_rect,
                         rect,
+                        clip_rect: layout_state.current_clip(),
+                        transform: layout_state.current_transform(),
                     }));
 
                     if *clip != Clip::None {
+                        layout_state.pop_clip();
                         layout_items.push(LayoutItem::PopClip);
                     }
                 }
 
                 if should_render {
                     for widget_ref in foregrounds {
+                        let rect = placed_decorator_rect(
+                            decorator_placements,
+                            widget_ref,
+                            decorators_rect,
+                            layout_state.layout_direction,
+                        );
+
                         layout_items.push(LayoutItem::Placement(WidgetPlacement {
                             widget_ref: *widget_ref,
                             zindex: *zindex,
-                            boundary: decorators_rect,
-                            rect: decorators_rect,
+                            boundary: rect,
+                            rect,
+                            clip_rect: layout_state.current_clip(),
+                            transform: layout_state.current_transform(),
                         }));
                     }
                 }
 
-                if let DeriveWrapSize::Text(text_id) = derive_wrap_size {
+                if let DeriveWrapSize::Text(text_id) | DeriveWrapSize::TextLines { text_id, .. } =
+                    derive_wrap_size
+                {
+                    // Static texts with a known height don't need lines below the visible
+                    // band to be shaped.
+                    let visible_height = if size.height.constrained() && text.is_static(*text_id) {
+                        layout_state
+                            .current_clip()
+                            .map(|clip| (clip.bottom() - rect.top()).max(0.) * view.scale_factor)
+                    } else {
+                        None
+                    };
+
                     layout_state.texts.push(TextLayout {
                         width: rect.width * view.scale_factor,
                         text_id: *text_id,
+                        visible_height,
                     });
                 };
 
@@ -1468,6 +2334,8 @@ pub fn layout(
                         zindex: i32::MAX,
                         boundary: Rect::ZERO,
                         rect: boundary,
+                        clip_rect: None,
+                        transform: None,
                     }));
 
                     layout_items.push(LayoutItem::Placement(WidgetPlacement {
@@ -1478,6 +2346,8 @@ pub fn layout(
                         zindex: i32::MAX,
                         boundary: Rect::ZERO,
                         rect,
+                        clip_rect: None,
+                        transform: None,
                     }));
 
                     layout_items.push(LayoutItem::Placement(WidgetPlacement {
@@ -1488,12 +2358,45 @@ pub fn layout(
                         zindex: i32::MAX,
                         boundary: Rect::ZERO,
                         rect: decorators_rect,
+                        clip_rect: None,
+                        transform: None,
                     }));
                 }
 
                 current_idx += 1;
             }
             LayoutCommand::Spacer { .. } => {
+                let container_flex_size = layout_state.flex_sizes[container_idx];
+
+                match layout_state.pass2_parent_container.axis {
+                    StackAxisPass2::Horizontal { spacing, .. } => {
+                        let gaps_delta = layout_state.gaps_delta(
+                            container_idx,
+                            layout_state.flex_sum_x[container_idx],
+                            layout_state.flex_wrap_sizes[container_idx].x,
+                            container_size_resized.x - container_flex_size.x + spacing,
+                        );
+
+                        widget_size.x =
+                            layout_state.gap_size(current_idx, widget_size.x, gaps_delta);
+                    }
+                    StackAxisPass2::Vertical { spacing, .. } => {
+                        let gaps_delta = layout_state.gaps_delta(
+                            container_idx,
+                            layout_state.flex_sum_y[container_idx],
+                            layout_state.flex_wrap_sizes[container_idx].y,
+                            container_size_resized.y - container_flex_size.y + spacing,
+                        );
+
+                        widget_size.y =
+                            layout_state.gap_size(current_idx, widget_size.y, gaps_delta);
+                    }
+                    StackAxisPass2::None
+                    | StackAxisPass2::Align { .. }
+                    | StackAxisPass2::Passthrough { .. }
+                    | StackAxisPass2::Flow { .. } => {}
+                }
+
                 current_idx += 1;
             }
         }
@@ -1502,6 +2405,9 @@ pub fn layout(
             match layout_state.pass2_parent_container.axis {
                 StackAxisPass2::Horizontal {
                     spacing, rtl_aware, ..
+                }
+                | StackAxisPass2::Flow {
+                    spacing, rtl_aware, ..
                 } => {
                     if rtl_aware && layout_state.layout_direction == LayoutDirection::RTL {
                         current_position.x -= widget_size.x + spacing
@@ -1521,3 +2427,428 @@ pub fn layout(
 
     debug_assert!(layout_state.containers_stack_cursor == 0);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    struct Button;
+
+    /// Ids are derived from the call site, so all of them are created here.
+    fn id(name: &str) -> WidgetId {
+        WidgetId::auto_with_seed(name)
+    }
+
+    fn leaf(name: &str, width: f32) -> LayoutCommand {
+        LayoutCommand::Leaf {
+            widget_ref: WidgetRef {
+                widget_type: WidgetType::of::<Button>(),
+                id: id(name),
+            },
+            backgrounds: SmallVec::new(),
+            foregrounds: SmallVec::new(),
+            constraints: Constraints::default(),
+            padding: EdgeInsets::ZERO,
+            margin: EdgeInsets::ZERO,
+            size: Size::new(SizeConstraint::Fixed(width), SizeConstraint::Fixed(32.)),
+            derive_wrap_size: DeriveWrapSize::Constraints,
+            zindex: 0,
+            clip: Clip::None,
+        }
+    }
+
+    fn spacer() -> LayoutCommand {
+        LayoutCommand::Spacer {
+            constraints: Constraints::default(),
+            size: Size::new(SizeConstraint::Fill(1.), SizeConstraint::Fill(1.)),
+            main_axis_min: None,
+            main_axis_max: None,
+        }
+    }
+
+    fn gap(width: f32, min: Option<f32>, max: Option<f32>) -> LayoutCommand {
+        LayoutCommand::Spacer {
+            constraints: Constraints::default(),
+            size: Size::new(SizeConstraint::Fixed(width), SizeConstraint::Fixed(0.)),
+            main_axis_min: min,
+            main_axis_max: max,
+        }
+    }
+
+    fn hstack(width: f32, children: impl IntoIterator<Item = LayoutCommand>) -> Vec<LayoutCommand> {
+        let mut commands = vec![LayoutCommand::BeginContainer {
+            backgrounds: SmallVec::new(),
+            foregrounds: SmallVec::new(),
+            kind: ContainerKind::HStack {
+                spacing: 0.,
+                main_axis_alignment: MainAxisAlignment::Start,
+                cross_axis_alignment: CrossAxisAlignment::Start,
+                rtl_aware: true,
+            },
+            constraints: Constraints::default(),
+            size: Size::new(SizeConstraint::Fixed(width), SizeConstraint::Fixed(32.)),
+            zindex: 0,
+            padding: EdgeInsets::ZERO,
+            margin: EdgeInsets::ZERO,
+            clip: Clip::None,
+        }];
+        commands.extend(children);
+        commands.push(LayoutCommand::EndContainer);
+
+        commands
+    }
+
+    /// `[title, spacer, gap, close]`, the gap is 16 wide and compresses down to 4.
+    fn toolbar(width: f32) -> Vec<LayoutCommand> {
+        hstack(
+            width,
+            [
+                leaf("title", 100.),
+                spacer(),
+                gap(16., Some(4.), None),
+                leaf("close", 24.),
+            ],
+        )
+    }
+
+    /// X positions of the placed leaves.
+    fn layout_x(commands: &[LayoutCommand], direction: LayoutDirection) -> Vec<(WidgetId, f32)> {
+        let mut layout_state = LayoutState {
+            layout_direction: direction,
+            ..Default::default()
+        };
+        let view = testing::view(800, 600);
+        let mut layout_items = Vec::new();
+
+        layout(
+            &mut layout_state,
+            &view,
+            commands,
+            &mut layout_items,
+            &mut TypedWidgetStates::default(),
+            &TypedWidgetStates::default(),
+            &mut TextsResources::new(),
+            &Assets::new(),
+        );
+
+        layout_items
+            .iter()
+            .filter_map(|item| match item {
+                LayoutItem::Placement(placement) => {
+                    Some((placement.widget_ref.id, placement.rect.x))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn x_of(placements: &[(WidgetId, f32)], name: &str) -> f32 {
+        let id = id(name);
+
+        placements.iter().find(|(it, _)| *it == id).unwrap().1
+    }
+
+    fn scroll_area(
+        name: &str,
+        children: impl IntoIterator<Item = LayoutCommand>,
+    ) -> Vec<LayoutCommand> {
+        let mut commands = vec![LayoutCommand::BeginContainer {
+            backgrounds: SmallVec::from_iter([WidgetRef {
+                widget_type: WidgetType::of::<ScrollAreaWidget>(),
+                id: id(name),
+            }]),
+            foregrounds: SmallVec::new(),
+            kind: ContainerKind::Measure { id: id(name) },
+            constraints: Constraints::default(),
+            size: Size::new(SizeConstraint::Fixed(200.), SizeConstraint::Fixed(100.)),
+            zindex: 0,
+            padding: EdgeInsets::ZERO,
+            margin: EdgeInsets::ZERO,
+            clip: Clip::Rect,
+        }];
+        commands.push(LayoutCommand::BeginOffset {
+            offset_x: 0.,
+            offset_y: -20.,
+        });
+        commands.extend(children);
+        commands.push(LayoutCommand::EndOffset);
+        commands.push(LayoutCommand::EndContainer);
+
+        commands
+    }
+
+    #[test]
+    fn test_scroll_into_view_finds_enclosing_scroll_areas() {
+        let mut commands = scroll_area("outer", scroll_area("inner", [leaf("field", 50.)]));
+        commands.extend(scroll_area("sibling", []));
+
+        let mut layout_state = LayoutState {
+            scroll_into_view_target: Some(id("field")),
+            ..Default::default()
+        };
+        let view = testing::view(800, 600);
+
+        layout(
+            &mut layout_state,
+            &view,
+            &commands,
+            &mut Vec::new(),
+            &mut TypedWidgetStates::default(),
+            &TypedWidgetStates::default(),
+            &mut TextsResources::new(),
+            &Assets::new(),
+        );
+
+        let request = layout_state.scroll_into_view.unwrap();
+
+        assert_eq!(request.rect, Rect::new(0., -40., 50., 32.));
+        assert_eq!(request.scroll_areas.as_slice(), [id("outer"), id("inner")]);
+    }
+
+    #[test]
+    fn test_toolbar_spacer_shrinks_before_gap() {
+        // Wide: the spacer takes the free space and the gap keeps its size.
+        let placements = layout_x(&toolbar(400.), LayoutDirection::LTR);
+        assert_eq!(x_of(&placements, "title"), 0.);
+        assert_eq!(x_of(&placements, "close"), 376.);
+
+        // The spacer is gone first, then the gap compresses by the overflow.
+        let placements = layout_x(&toolbar(130.), LayoutDirection::LTR);
+        assert_eq!(x_of(&placements, "title"), 0.);
+        assert_eq!(x_of(&placements, "close"), 106.);
+
+        // Below its minimum the gap stops compressing and the content overflows.
+        let placements = layout_x(&toolbar(100.), LayoutDirection::LTR);
+        assert_eq!(x_of(&placements, "close"), 104.);
+    }
+
+    #[test]
+    fn test_toolbar_in_rtl_hstack() {
+        let placements = layout_x(&toolbar(400.), LayoutDirection::RTL);
+        assert_eq!(x_of(&placements, "title"), 300.);
+        assert_eq!(x_of(&placements, "close"), 0.);
+
+        let placements = layout_x(&toolbar(130.), LayoutDirection::RTL);
+        assert_eq!(x_of(&placements, "title"), 30.);
+        assert_eq!(x_of(&placements, "close"), 0.);
+    }
+
+    #[test]
+    fn test_gap_grows_up_to_max_without_flexible_children() {
+        let commands = hstack(
+            400.,
+            [
+                leaf("title", 100.),
+                gap(16., Some(4.), Some(32.)),
+                leaf("close", 24.),
+            ],
+        );
+
+        let placements = layout_x(&commands, LayoutDirection::LTR);
+        assert_eq!(x_of(&placements, "close"), 132.);
+    }
+
+    fn container(
+        kind: ContainerKind,
+        size: Size,
+        children: impl IntoIterator<Item = LayoutCommand>,
+    ) -> Vec<LayoutCommand> {
+        let mut commands = vec![LayoutCommand::BeginContainer {
+            backgrounds: SmallVec::new(),
+            foregrounds: SmallVec::new(),
+            kind,
+            constraints: Constraints::default(),
+            size,
+            zindex: 0,
+            padding: EdgeInsets::ZERO,
+            margin: EdgeInsets::ZERO,
+            clip: Clip::None,
+        }];
+        commands.extend(children);
+        commands.push(LayoutCommand::EndContainer);
+
+        commands
+    }
+
+    /// Swatches of 40 in a flow above a footer, all in a column of 100.
+    fn flow_column(flow_width: SizeConstraint) -> Vec<LayoutCommand> {
+        let flow = container(
+            ContainerKind::Flow {
+                spacing: 10.,
+                run_spacing: 5.,
+                rtl_aware: true,
+            },
+            Size::new(flow_width, SizeConstraint::Wrap),
+            [leaf("a", 40.), leaf("b", 40.), leaf("c", 40.)],
+        );
+
+        container(
+            ContainerKind::VStack {
+                spacing: 0.,
+                main_axis_alignment: MainAxisAlignment::Start,
+                cross_axis_alignment: CrossAxisAlignment::Start,
+                rtl_aware: true,
+            },
+            Size::new(SizeConstraint::Fixed(100.), SizeConstraint::Wrap),
+            flow.into_iter().chain([leaf("footer", 100.)]),
+        )
+    }
+
+    fn layout_rects(
+        layout_state: &mut LayoutState,
+        commands: &[LayoutCommand],
+    ) -> Vec<(WidgetId, Rect)> {
+        let view = testing::view(800, 600);
+        let mut layout_items = Vec::new();
+
+        layout(
+            layout_state,
+            &view,
+            commands,
+            &mut layout_items,
+            &mut TypedWidgetStates::default(),
+            &TypedWidgetStates::default(),
+            &mut TextsResources::new(),
+            &Assets::new(),
+        );
+
+        layout_items
+            .iter()
+            .filter_map(|item| match item {
+                LayoutItem::Placement(placement) => Some((placement.widget_ref.id, placement.rect)),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn rect_of(rects: &[(WidgetId, Rect)], name: &str) -> Rect {
+        let id = id(name);
+
+        rects.iter().find(|(it, _)| *it == id).unwrap().1
+    }
+
+    #[test]
+    fn test_snapped_translation_lands_on_physical_pixels() {
+        for scale_factor in [1., 1.25, 1.5, 2.] {
+            for value in [0.3, -0.6, 7.45, -123.7] {
+                let snapped = snap_to_pixels(value, scale_factor);
+                let physical = snapped * scale_factor;
+
+                assert!(
+                    (physical - physical.round()).abs() < 1e-4,
+                    "{value} at {scale_factor} is {snapped}"
+                );
+                assert!((snapped - value).abs() <= 0.5 / scale_factor + 1e-4);
+            }
+        }
+
+        assert_eq!(snap_to_pixels(0.5, 1.25), 0.8);
+        assert_eq!(snap_to_pixels(0.5, 1.5), 2. / 3.);
+    }
+
+    #[test]
+    fn test_flow_breaks_rows_at_its_width() {
+        let commands = flow_column(SizeConstraint::Fixed(100.));
+        let rects = layout_rects(&mut LayoutState::default(), &commands);
+
+        assert_eq!(rect_of(&rects, "a"), Rect::new(0., 0., 40., 32.));
+        assert_eq!(rect_of(&rects, "b"), Rect::new(50., 0., 40., 32.));
+        assert_eq!(rect_of(&rects, "c"), Rect::new(0., 37., 40., 32.));
+        // The flow is as tall as its rows.
+        assert_eq!(rect_of(&rects, "footer").y, 69.);
+
+        let mut layout_state = LayoutState {
+            layout_direction: LayoutDirection::RTL,
+            ..Default::default()
+        };
+        let rects = layout_rects(&mut layout_state, &commands);
+        // The column is at the end of the view.
+        let start = rect_of(&rects, "footer").x;
+
+        assert_eq!(rect_of(&rects, "a").x - start, 60.);
+        assert_eq!(rect_of(&rects, "b").x - start, 10.);
+        assert_eq!(rect_of(&rects, "c").x - start, 60.);
+        assert_eq!(rect_of(&rects, "c").y, 37.);
+    }
+
+    #[test]
+    fn test_filling_flow_breaks_rows_at_the_width_it_is_placed_with() {
+        let commands = flow_column(SizeConstraint::Fill(1.));
+        let mut layout_state = LayoutState::default();
+
+        // The frame is laid out twice, the second layout breaks the rows at the width
+        // the first one has placed the flow with.
+        layout_rects(&mut layout_state, &commands);
+        let rects = layout_rects(&mut layout_state, &commands);
+
+        assert_eq!(rect_of(&rects, "c"), Rect::new(0., 37., 40., 32.));
+        assert_eq!(rect_of(&rects, "footer").y, 69.);
+    }
+
+    #[test]
+    fn test_decorators_are_placed_inside_and_outside_of_the_widget() {
+        let decorator = |name| WidgetRef {
+            widget_type: WidgetType::of::<Button>(),
+            id: id(name),
+        };
+        let mut tab = leaf("tab", 100.);
+
+        if let LayoutCommand::Leaf {
+            backgrounds,
+            foregrounds,
+            ..
+        } = &mut tab
+        {
+            backgrounds.push(decorator("underline"));
+            foregrounds.push(decorator("ring"));
+        }
+
+        let mut decorator_placements = TypedWidgetStates::default();
+        decorator_placements.set(
+            id("underline"),
+            DecoratorPlacement {
+                size: Some(Size::new(SizeConstraint::Wrap, SizeConstraint::Fixed(2.))),
+                align: (AlignX::Start, AlignY::Bottom),
+                ..Default::default()
+            },
+        );
+        decorator_placements.set(
+            id("ring"),
+            DecoratorPlacement {
+                insets: EdgeInsets::all(-2.),
+                ..Default::default()
+            },
+        );
+
+        let view = testing::view(800, 600);
+        let mut layout_items = Vec::new();
+
+        layout(
+            &mut LayoutState::default(),
+            &view,
+            &hstack(200., [leaf("title", 20.), tab]),
+            &mut layout_items,
+            &mut TypedWidgetStates::default(),
+            &decorator_placements,
+            &mut TextsResources::new(),
+            &Assets::new(),
+        );
+
+        let rect_of = |name| {
+            layout_items
+                .iter()
+                .find_map(|item| match item {
+                    LayoutItem::Placement(placement) if placement.widget_ref.id == id(name) => {
+                        Some(placement.rect)
+                    }
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        assert_eq!(rect_of("tab"), Rect::new(20., 0., 100., 32.));
+        assert_eq!(rect_of("underline"), Rect::new(20., 30., 100., 2.));
+        assert_eq!(rect_of("ring"), Rect::new(18., -2., 104., 36.));
+    }
+}
@@ -1,29 +1,61 @@
 extern crate self as clew;
 
 pub mod animation;
+pub mod announcement;
 pub mod assets;
+pub mod authoring;
+pub mod capture;
+mod css_color;
+mod error;
+#[cfg(feature = "file-dialogs")]
+pub mod file_dialogs;
+mod focus;
 mod foundation;
+pub mod id_map;
 pub mod identifiable;
+pub mod instance;
 mod interaction;
 pub mod io;
 pub mod keyboard;
 mod layout;
 pub mod lifecycle;
+pub mod phases;
+mod random;
 pub mod render;
+pub mod resources;
 pub mod shortcuts;
+pub mod size_class;
+pub mod snapshot;
 pub mod state;
+#[doc(hidden)]
+pub mod testing;
 pub mod text;
 pub mod text_data;
 pub mod text_history;
+pub mod text_sync;
+pub mod theme;
 mod widget_id;
 pub mod widgets;
 
 pub use animation::*;
+pub use announcement::{Announcement, Politeness};
+pub use capture::{ImageBackground, SubtreeImageOptions};
+pub use error::ClewError;
+#[cfg(feature = "file-dialogs")]
+pub use file_dialogs::{FileDialogOptions, FileFilter};
+pub use focus::FocusAxis;
 pub use foundation::*;
+pub use id_map::{IdEntry, IdMap, IdMapDiff};
+pub use instance::{ClewHost, ClewInstance};
 pub use interaction::WidgetInteractionState;
+pub use random::StableRng;
 pub use render::{Renderer, render};
+pub use resources::Resources;
 pub use shortcuts::*;
+pub use size_class::{Breakpoints, SizeClass, ViewSizeClass};
+pub use snapshot::{SerializableWidgetState, StateValue, UiStateSnapshot};
 pub use text_data::*;
+pub use theme::{Appearance, SystemTheme, WidgetTheme};
 pub use widget_id::*;
 pub use widgets::*;
 
@@ -1,13 +1,27 @@
-use crate::{io::Cursor, state::UiState};
+use crate::{focus::handle_focus_navigation, io::Cursor, state::UiState};
 
 pub fn init_cycle(state: &mut UiState) {
     state.layout_commands.clear();
-    state.render_state.commands.clear();
     state.widget_placements.clear();
     state.layout_items.clear();
     state.non_interactable.clear();
+
+    // The chain is from the last frame, the focus changes before the widgets are built.
+    handle_focus_navigation(
+        &mut state.user_input,
+        &mut state.interaction_state,
+        &state.focus_chain,
+    );
+    state.focus_chain.clear();
+
     state.user_input.cursor = Cursor::Default;
 
+    state.file_drops.init_cycle(
+        &state.user_input,
+        &state.view,
+        &state.widgets_states.layout_measures,
+    );
+
     state.shortcuts_manager.init_cycle(&state.user_input);
 
     std::mem::swap(&mut state.current_event_queue, &mut state.next_event_queue);
@@ -21,4 +35,5 @@ pub fn init_cycle(state: &mut UiState) {
 
 pub fn finalize_cycle(state: &mut UiState) {
     state.shortcuts_manager.finalize_cycle();
+    state.file_drops.finalize_cycle();
 }
@@ -0,0 +1,340 @@
+//! Points of a frame widgets can run code at besides the build.
+//!
+//! A frame is built, laid out, hit-tested and then its render commands are collected.
+//! Widgets that need the rects of the frame, e.g. a header that sticks to the top of a
+//! scroll area, would read them on the next build and lag a frame behind the content.
+//! Instead they register callbacks that run at the other phases of the frame:
+//!
+//! - After the layout, with [`BuildContext::after_layout`]. The callbacks run in the
+//!   order they've been registered once the layout has settled, before the input is
+//!   hit-tested and the render commands are collected. They read the rects of the frame
+//!   with [`LayoutAccess`] and may move the content of their measured container or draw
+//!   on top of it with [`RenderMutator`], nothing else. The widget states, resources and
+//!   the layout of the other widgets can't be touched, what depends on them is deferred
+//!   to the next build with [`RenderMutator::before_next_build`].
+//! - Before the next build, with [`BuildContext::before_next_build`]. The callbacks run
+//!   in the order they've been registered before the root of the next frame is built.
+//!   They may change the widget states and resources, e.g. move a scroll offset, but
+//!   may not build widgets.
+//!
+//! Each callback runs once, in the frame it's been registered in, and only sees what the
+//! build and the callbacks before it have produced, so the same input always ends up
+//! in the same frame.
+
+use crate::{
+    Rect, Vec2, View, WidgetId, WidgetIdMap,
+    layout::{ItemsGroup, LayoutItem, LayoutMeasure, ScrollIntoView},
+    render::RenderCommand,
+    state::{TypedWidgetStates, UiState},
+    widgets::builder::BuildContext,
+};
+
+pub(crate) type AfterLayoutFn = Box<dyn FnOnce(&LayoutAccess, &mut RenderMutator) + Send>;
+pub(crate) type BeforeBuildFn = Box<dyn FnOnce(&mut BuildContext) + Send>;
+
+/// Callbacks registered for the phases of the frames.
+#[derive(Default)]
+pub(crate) struct FramePhases {
+    /// The group of the widget is adjusted by the callback, `None` if it only reads.
+    pub(crate) after_layout: Vec<(Option<WidgetId>, AfterLayoutFn)>,
+    pub(crate) before_build: Vec<BeforeBuildFn>,
+}
+
+/// Layout of the frame seen by the callbacks registered with
+/// [`BuildContext::after_layout`].
+pub struct LayoutAccess<'a> {
+    items: &'a [LayoutItem],
+    /// Index of the first placement of each widget in the items.
+    placements: &'a WidgetIdMap<usize>,
+    measures: &'a TypedWidgetStates<LayoutMeasure>,
+    view: &'a View,
+    pub(crate) scroll_into_view: Option<&'a ScrollIntoView>,
+}
+
+impl LayoutAccess<'_> {
+    /// Rect in the view of the measured container with the id, e.g. of a
+    /// [`crate::widgets::measure`] or a scroll area, or else of the widget with the id.
+    /// `None` if it hasn't been built this frame.
+    pub fn rect(&self, id: WidgetId) -> Option<Rect> {
+        if self.measures.is_accessed(id)
+            && let Some(measure) = self.measures.get(id)
+        {
+            return Some(Rect::new(
+                measure.x,
+                measure.y,
+                measure.width,
+                measure.height,
+            ));
+        }
+
+        match self.items.get(*self.placements.get(&id)?) {
+            Some(LayoutItem::Placement(placement)) => Some(placement.rect),
+            _ => None,
+        }
+    }
+
+    pub fn view(&self) -> &View {
+        self.view
+    }
+}
+
+/// Adjustments of the measured container a callback registered with
+/// [`BuildContext::after_layout`] has been registered for, applied once it returns.
+pub struct RenderMutator {
+    offset: Vec2,
+    commands: Vec<(i32, RenderCommand)>,
+    before_next_build: Vec<BeforeBuildFn>,
+}
+
+impl RenderMutator {
+    /// Moves the content of the container, it's drawn and hit-tested at the new place.
+    /// The clips around the container stay where they are.
+    pub fn offset(&mut self, x: f32, y: f32) {
+        self.offset += Vec2::new(x, y);
+    }
+
+    /// Draws the command in the group of the container after its content, in physical
+    /// pixels like the commands of the widgets.
+    pub fn push(&mut self, zindex: i32, command: RenderCommand) {
+        self.commands.push((zindex, command));
+    }
+
+    /// Defers the changes of the widget states to the next frame, see
+    /// [`BuildContext::before_next_build`].
+    pub fn before_next_build(&mut self, callback: impl FnOnce(&mut BuildContext) + Send + 'static) {
+        self.before_next_build.push(Box::new(callback));
+    }
+}
+
+impl BuildContext<'_, '_> {
+    /// Runs the callback after the layout of this frame, see [`crate::phases`]. The
+    /// adjustments of the mutator apply to the measured container with the id, the
+    /// callback can only read the layout if there is none.
+    pub fn after_layout(
+        &mut self,
+        id: WidgetId,
+        callback: impl FnOnce(&LayoutAccess, &mut RenderMutator) + Send + 'static,
+    ) {
+        self.phases
+            .after_layout
+            .push((Some(id), Box::new(callback)));
+    }
+
+    /// Runs the callback before the next frame is built, see [`crate::phases`].
+    pub fn before_next_build(&mut self, callback: impl FnOnce(&mut BuildContext) + Send + 'static) {
+        self.phases.before_build.push(Box::new(callback));
+    }
+
+    pub(crate) fn run_before_build(&mut self) {
+        let callbacks = std::mem::take(&mut self.phases.before_build);
+        let commands = self.layout_commands.len();
+
+        for callback in callbacks {
+            callback(self);
+        }
+
+        debug_assert_eq!(
+            commands,
+            self.layout_commands.len(),
+            "Widgets can't be built before the build"
+        );
+    }
+}
+
+pub(crate) fn run_after_layout(state: &mut UiState) {
+    let callbacks = std::mem::take(&mut state.phases.after_layout);
+
+    if callbacks.is_empty() {
+        return;
+    }
+
+    let mut placements = WidgetIdMap::default();
+
+    for (index, item) in state.layout_items.iter().enumerate() {
+        if let LayoutItem::Placement(placement) = item {
+            placements.entry(placement.widget_ref.id).or_insert(index);
+        }
+    }
+
+    for (id, callback) in callbacks {
+        let mut mutator = RenderMutator {
+            offset: Vec2::ZERO,
+            commands: Vec::new(),
+            before_next_build: Vec::new(),
+        };
+
+        callback(
+            &LayoutAccess {
+                items: &state.layout_items,
+                placements: &placements,
+                measures: &state.widgets_states.layout_measures,
+                view: &state.view,
+                scroll_into_view: state.layout_state.scroll_into_view.as_ref(),
+            },
+            &mut mutator,
+        );
+
+        state
+            .phases
+            .before_build
+            .append(&mut mutator.before_next_build);
+
+        let Some(id) = id else {
+            continue;
+        };
+        let Some(group) = state.layout_state.groups.get(&id).cloned() else {
+            continue;
+        };
+
+        if mutator.offset != Vec2::ZERO {
+            offset_items(&mut state.layout_items[group.items], mutator.offset);
+
+            if let Some(measure) = state.widgets_states.layout_measures.get_mut(id) {
+                measure.x += mutator.offset.x;
+                measure.y += mutator.offset.y;
+            }
+        }
+
+        if !mutator.commands.is_empty() {
+            let count = mutator.commands.len();
+
+            for index in placements.values_mut() {
+                if *index >= group.content_end {
+                    *index += count;
+                }
+            }
+
+            insert_commands(
+                &mut state.layout_items,
+                state.layout_state.groups.values_mut(),
+                group.content_end,
+                mutator.commands,
+            );
+        }
+    }
+}
+
+/// Moves the placements and the clips, the placements inside of the moved clips are
+/// clipped at the new place.
+fn offset_items(items: &mut [LayoutItem], offset: Vec2) {
+    let mut clips = 0;
+
+    for item in items {
+        match item {
+            LayoutItem::Placement(placement) => {
+                placement.rect = placement.rect.offset(offset.x, offset.y);
+                placement.boundary = placement.boundary.offset(offset.x, offset.y);
+
+                if clips > 0 {
+                    placement.clip_rect = placement
+                        .clip_rect
+                        .map(|rect| rect.offset(offset.x, offset.y));
+                }
+            }
+            LayoutItem::PushClip { rect, .. } => {
+                *rect = rect.offset(offset.x, offset.y);
+                clips += 1;
+            }
+            LayoutItem::PopClip => clips -= 1,
+            _ => {}
+        }
+    }
+}
+
+/// Inserts the commands at the index, the groups after it are shifted to keep up.
+fn insert_commands<'a>(
+    items: &mut Vec<LayoutItem>,
+    groups: impl Iterator<Item = &'a mut ItemsGroup>,
+    index: usize,
+    commands: Vec<(i32, RenderCommand)>,
+) {
+    let count = commands.len();
+
+    items.splice(
+        index..index,
+        commands
+            .into_iter()
+            .map(|(zindex, command)| LayoutItem::Command {
+                zindex,
+                command: Box::new(command),
+            }),
+    );
+
+    let shift = |value: &mut usize| {
+        if *value >= index {
+            *value += count;
+        }
+    };
+
+    for group in groups {
+        shift(&mut group.items.start);
+        shift(&mut group.items.end);
+        shift(&mut group.content_end);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Clip, WidgetRef, WidgetType, layout::WidgetPlacement};
+
+    fn placement(id: WidgetId, rect: Rect) -> LayoutItem {
+        LayoutItem::Placement(WidgetPlacement {
+            widget_ref: WidgetRef::new(WidgetType::of::<()>(), id),
+            zindex: 0,
+            boundary: rect,
+            rect,
+            clip_rect: Some(Rect::new(0., 0., 100., 100.)),
+            transform: None,
+        })
+    }
+
+    #[test]
+    fn test_commands_are_inserted_into_group() {
+        let outer = WidgetId::auto();
+        let inner = WidgetId::auto();
+        let mut items = vec![
+            LayoutItem::BeginGroup { zindex: 0 },
+            LayoutItem::PushClip {
+                rect: Rect::new(0., 0., 10., 10.),
+                clip: Clip::Rect,
+                zindex: 0,
+            },
+            placement(inner, Rect::new(0., 0., 10., 10.)),
+            LayoutItem::PopClip,
+            placement(outer, Rect::new(0., 20., 10., 10.)),
+            LayoutItem::EndGroup,
+        ];
+        let mut inner_group = ItemsGroup {
+            items: 1..4,
+            content_end: 3,
+        };
+        let mut outer_group = ItemsGroup {
+            items: 0..6,
+            content_end: 5,
+        };
+
+        offset_items(&mut items[1..4], Vec2::new(0., 5.));
+
+        let LayoutItem::Placement(moved) = &items[2] else {
+            unreachable!()
+        };
+        assert_eq!(moved.rect, Rect::new(0., 5., 10., 10.));
+        // Inside of the moved clip.
+        assert_eq!(moved.clip_rect, Some(Rect::new(0., 5., 100., 100.)));
+
+        insert_commands(
+            &mut items,
+            [&mut inner_group, &mut outer_group].into_iter(),
+            3,
+            vec![(1, RenderCommand::PopClip)],
+        );
+
+        assert!(matches!(items[3], LayoutItem::Command { zindex: 1, .. }));
+        assert!(matches!(items[4], LayoutItem::PopClip));
+        assert_eq!(inner_group.items, 1..5);
+        assert_eq!(inner_group.content_end, 4);
+        assert_eq!(outer_group.items, 0..7);
+        assert_eq!(outer_group.content_end, 6);
+    }
+}
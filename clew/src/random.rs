@@ -0,0 +1,138 @@
+use std::ops::Range;
+
+/// Pseudo-random generator that yields the same values for the same seed on every
+/// platform and in every run of the program, see [`crate::BuildContext::stable_rng`].
+/// It's meant for the looks of the widgets and is not fit for anything secret.
+#[derive(Debug, Clone)]
+pub struct StableRng {
+    state: u64,
+}
+
+impl StableRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        // SplitMix64.
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        z ^ (z >> 31)
+    }
+
+    /// Uniformly in `0.0..1.0`.
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    pub fn range_f32(&mut self, range: Range<f32>) -> f32 {
+        range.start + (range.end - range.start) * self.next_f32()
+    }
+
+    /// Panics if the range is empty.
+    pub fn range_u64(&mut self, range: Range<u64>) -> u64 {
+        assert!(!range.is_empty(), "The range is empty");
+
+        range.start + self.next_u64() % (range.end - range.start)
+    }
+
+    /// Item of the slice, e.g. a color of a palette, `None` if the slice is empty.
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+
+        items.get(self.range_u64(0..items.len() as u64) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        testing,
+        widgets::{hstack, scope, text, vstack},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_sequence_matches_splitmix64() {
+        let mut rng = StableRng::new(1234567);
+
+        assert_eq!(
+            [rng.next_u64(), rng.next_u64(), rng.next_u64()],
+            [
+                6457827717110365317,
+                3203168211198807973,
+                9817491932198370423
+            ]
+        );
+    }
+
+    #[test]
+    fn test_values_stay_in_range() {
+        let mut rng = StableRng::new(0);
+
+        for _ in 0..1000 {
+            assert!((0.0..1.0).contains(&rng.next_f32()));
+            assert!((-2.0..3.0).contains(&rng.range_f32(-2.0..3.0)));
+            assert!((10..13).contains(&rng.range_u64(10..13)));
+        }
+
+        assert_eq!(rng.pick::<u32>(&[]), None);
+    }
+
+    #[test]
+    fn test_stable_values_survive_rebuilds() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+
+        let mut frame = |rows: &[u32], extra_text: bool| {
+            let mut values = Vec::new();
+
+            instance.frame(&mut host, &mut |ctx| {
+                if extra_text {
+                    text("Header").build(ctx);
+                }
+
+                for row in rows {
+                    scope(*row).build(ctx, |ctx| {
+                        values.push((
+                            ctx.stable_hash("avatar"),
+                            ctx.stable_rng("delay").next_f32(),
+                        ));
+                    });
+                }
+            });
+
+            values
+        };
+
+        let first = frame(&[1, 2, 3], false);
+        let rebuilt = frame(&[0, 3, 2, 1], true);
+
+        assert_eq!(rebuilt[1..], [first[2], first[1], first[0]]);
+        assert_ne!(first[0], first[1]);
+    }
+
+    #[test]
+    fn test_stable_hash_in_containers_is_the_same_in_every_run() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let mut hash = 0;
+
+        instance.frame(&mut host, &mut |ctx| {
+            vstack().build(ctx, |ctx| {
+                hstack().build(ctx, |ctx| {
+                    scope("row").build(ctx, |ctx| hash = ctx.stable_hash("avatar"));
+                });
+            });
+        });
+
+        // The seeds of the containers come from their call sites, hashed by the name of
+        // the file, so the value doesn't move with the addresses of the program.
+        assert_eq!(hash, 4070508420979285441);
+    }
+}
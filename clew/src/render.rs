@@ -1,12 +1,14 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, ops::Range, sync::Arc, time::Duration};
 
 use crate::{
-    Border, BorderRadius, BorderSide, ClipShape, ColorRgb, ColorRgba, DebugBoundary, Gradient,
-    LayoutDirection, Rect, Vec2, View, WidgetType,
+    Border, BorderRadius, BorderSide, BoxShadow, ClewError, ClipShape, ColorRgb, ColorRgba,
+    DebugBoundary, Gradient, ImageFit, LayoutDirection, Rect, TintMode, Transform, Vec2, View,
+    WidgetId, WidgetIdMap, WidgetType,
     assets::Assets,
     interaction::{InteractionState, handle_interaction},
     io::UserInput,
     layout::{LayoutItem, WidgetPlacement, layout},
+    phases,
     state::UiState,
     text::{FontResources, StringId, StringInterner, TextId, TextsResources},
     widgets,
@@ -16,12 +18,145 @@ use crate::{
 pub struct RenderState {
     pub(crate) commands: Vec<RenderCommand>,
     pub(crate) unsorted_commands: Vec<RenderCommandUnsorted>,
+    pub(crate) last_commands: Vec<RenderCommand>,
+    /// Rects requested with [`crate::BuildContext::invalidate_rect`] during the build.
+    pub(crate) invalidated_rects: Vec<Rect>,
+    pub(crate) damage: Damage,
+    /// Commands of the frame pushed with [`RenderContext::push_blinking_command`].
+    pub(crate) blinks: Vec<BlinkingCommand>,
+    /// Versions of the cache layers drawn in the last frame.
+    pub(crate) layer_versions: WidgetIdMap<u64>,
+    pub(crate) next_layer_version: u64,
+    pub(crate) stats: RenderStats,
 }
 
 impl RenderState {
     pub fn commands(&self) -> &[RenderCommand] {
         &self.commands
     }
+
+    /// The part of the view that changed since the previous frame, the rest of the
+    /// previous frame can be presented as is.
+    pub fn damage(&self) -> Damage {
+        self.damage
+    }
+
+    /// Time after `time` when a blinking command of the frame shows or hides next.
+    pub(crate) fn next_blink(&self, time: Duration) -> Option<Duration> {
+        self.blinks
+            .iter()
+            .map(|blinking| blinking.blink.next_change(time))
+            .min()
+    }
+
+    /// Makes every cache layer record its content again in the next frame, e.g. after
+    /// the assets it draws have been loaded.
+    pub fn invalidate_layers(&mut self) {
+        self.layer_versions.clear();
+    }
+
+    /// Commands inside the cache layer with the id, without the layer bracket itself.
+    pub fn layer_commands(&self, id: WidgetId) -> Option<&[RenderCommand]> {
+        layer_ranges(&self.commands)
+            .find(|(layer_id, _)| *layer_id == id)
+            .map(|(_, range)| &self.commands[range])
+    }
+
+    /// What the commands of the last built frame consist of.
+    pub fn stats(&self) -> RenderStats {
+        self.stats
+    }
+
+    /// Checks that the commands can be drawn as they are, see [`validate_commands`].
+    pub fn validate(&self, text: &TextsResources, assets: &Assets) -> Vec<RenderViolation> {
+        validate_commands(&self.commands, text, assets)
+    }
+
+    /// The last frame drawn again in whole through the transform, e.g. stretched to the
+    /// new size of the view while the frames aren't built.
+    pub(crate) fn frozen(&self, transform: Transform) -> RenderState {
+        let mut commands = Vec::with_capacity(self.commands.len() + 2);
+        commands.push(RenderCommand::PushTransform { transform });
+        commands.extend_from_slice(&self.commands);
+        commands.push(RenderCommand::PopTransform);
+
+        RenderState {
+            commands,
+            damage: Damage::Full,
+            layer_versions: self.layer_versions.clone(),
+            next_layer_version: self.next_layer_version,
+            stats: self.stats,
+            ..Default::default()
+        }
+    }
+}
+
+/// Region of the view to repaint, in physical pixels.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum Damage {
+    #[default]
+    None,
+    Rect(Rect),
+    Full,
+}
+
+impl Damage {
+    fn add(self, rect: Rect) -> Damage {
+        match self {
+            Damage::None => Damage::Rect(rect),
+            Damage::Rect(damage) => Damage::Rect(damage.union(rect)),
+            Damage::Full => Damage::Full,
+        }
+    }
+}
+
+/// Visibility of a blinking command, e.g. a caret. It's shown for `interval` from
+/// `since` on, then hidden for as long, and so on.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Blink {
+    pub since: Duration,
+    pub interval: Duration,
+}
+
+impl Blink {
+    pub fn is_visible(&self, time: Duration) -> bool {
+        let interval = self.interval.as_nanos().max(1);
+
+        (time.saturating_sub(self.since).as_nanos() / interval).is_multiple_of(2)
+    }
+
+    /// Time after `time` when the command shows or hides next.
+    pub fn next_change(&self, time: Duration) -> Duration {
+        if time < self.since || self.interval.is_zero() {
+            return self.since.max(time);
+        }
+
+        let interval = self.interval.as_nanos();
+        let phases = (time - self.since).as_nanos() / interval + 1;
+
+        self.since + Duration::from_nanos((phases * interval) as u64)
+    }
+}
+
+/// Command of the frame that blinks, drawn as an empty rect over its bounds while it's
+/// hidden so the commands keep their positions.
+#[derive(Debug, Clone)]
+pub(crate) struct BlinkingCommand {
+    /// Position in the sorted commands, found once they are sorted.
+    pub(crate) index: usize,
+    command: RenderCommand,
+    hidden: RenderCommand,
+    blink: Blink,
+}
+
+impl BlinkingCommand {
+    fn command_at(&self, time: Duration) -> &RenderCommand {
+        if self.blink.is_visible(time) {
+            &self.command
+        } else {
+            &self.hidden
+        }
+    }
 }
 
 pub trait Renderer {
@@ -29,6 +164,30 @@ pub trait Renderer {
 
     fn on_scale_factor_update(&mut self, _scale_factor: f32) {}
 
+    /// Whether the renderer can draw. A renderer that sets its device up in the
+    /// background isn't ready at first and the frames wait for it, the error of a failed
+    /// setup is returned once.
+    fn poll_ready(&mut self) -> Result<bool, ClewError> {
+        Ok(true)
+    }
+
+    /// Fills the view with the fill color while the renderer isn't ready, so the window
+    /// isn't blank until the first frame. The size of the view is the one the first
+    /// frame is drawn at.
+    fn present_fill(&mut self, _view: &View, _fill_color: ColorRgb) {}
+
+    /// Clears the frames to transparent instead of the fill color, so a background
+    /// material of the window shows through where the UI doesn't paint. Returns false
+    /// when the renderer can't present a transparent frame, the fill color is used then.
+    fn set_transparent_background(&mut self, _transparent: bool) -> bool {
+        false
+    }
+
+    /// Statistics of the last processed frame.
+    fn frame_stats(&self) -> FrameStats {
+        FrameStats::default()
+    }
+
     fn process_commands(
         &mut self,
         view: &View,
@@ -38,6 +197,168 @@ pub trait Renderer {
         text: &mut TextsResources,
         assets: &Assets,
     );
+
+    /// Draws the commands into an offscreen image of the given size in physical pixels,
+    /// filled with `background` first. The frame on the screen stays as it is.
+    #[allow(clippy::too_many_arguments)]
+    fn render_image(
+        &mut self,
+        _commands: &[RenderCommand],
+        _width: u32,
+        _height: u32,
+        _background: Option<ColorRgba>,
+        _fonts: &mut FontResources,
+        _text: &mut TextsResources,
+        _assets: &Assets,
+    ) -> Result<RgbaImage, ClewError> {
+        Err(ClewError::Capture(
+            "The renderer can't draw offscreen".to_string(),
+        ))
+    }
+}
+
+/// Pixels in rows from the top, 4 bytes per pixel in the RGBA order with straight alpha.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RgbaImage {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// What a renderer did in a frame, renderers fill in what they support.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct FrameStats {
+    /// Cache layers drawn from what has been recorded in the previous frames.
+    pub layer_cache_hits: u32,
+    /// Cache layers recorded again because they have changed or are new.
+    pub layer_cache_misses: u32,
+    /// Clip layers pushed, without the clips the renderer could apply otherwise.
+    pub clip_layers: u32,
+    /// Commands skipped because they are entirely outside of the clip.
+    pub culled_commands: u32,
+    /// Approximate bytes the caches of the renderer take after the frame, e.g. of the
+    /// fonts, images and cache layers.
+    pub cache_bytes: u64,
+    /// Cached entries evicted after the frame to keep the caches in the budget.
+    pub cache_evictions: u32,
+}
+
+/// Counts of the render commands in a frame and of the texts updated to build them, the
+/// same for every renderer.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct RenderStats {
+    pub rects: u32,
+    pub ovals: u32,
+    pub shadows: u32,
+    pub texts: u32,
+    pub svgs: u32,
+    pub images: u32,
+    pub clips: u32,
+    pub transforms: u32,
+    pub layers: u32,
+    /// Deepest nesting of the clips.
+    pub max_clip_depth: u32,
+    /// Laid out lines of all the text commands.
+    pub text_runs: u32,
+    /// Texts set and shaped again in the build because their content or style changed.
+    pub texts_updated: u32,
+    /// Texts kept as they were in the build because nothing they're shaped with changed.
+    pub texts_skipped: u32,
+    /// Items of the virtual lists drawn as their placeholders because the build ran past
+    /// its deadline, a frame with any is a frame with blank items.
+    pub placeholder_items: u32,
+    /// Commands dropped because they can't draw anything, see
+    /// [`RenderCommand::is_degenerate`].
+    pub dropped_commands: u32,
+}
+
+impl RenderStats {
+    pub fn collect(commands: &[RenderCommand], text: &TextsResources) -> Self {
+        let mut stats = RenderStats::default();
+        let mut clip_depth = 0;
+
+        for command in commands {
+            match command {
+                RenderCommand::Rect { .. } => stats.rects += 1,
+                RenderCommand::Oval { .. } => stats.ovals += 1,
+                RenderCommand::Shadow { .. } => stats.shadows += 1,
+                RenderCommand::Text { text_id, .. } => {
+                    stats.texts += 1;
+
+                    if text.contains(*text_id) {
+                        stats.text_runs += text.get(*text_id).layout_runs().count() as u32;
+                    }
+                }
+                RenderCommand::Svg { .. } => stats.svgs += 1,
+                RenderCommand::Image { .. } => stats.images += 1,
+                RenderCommand::PushClip { .. } => {
+                    stats.clips += 1;
+                    clip_depth += 1;
+                    stats.max_clip_depth = stats.max_clip_depth.max(clip_depth);
+                }
+                RenderCommand::PopClip => clip_depth = clip_depth.saturating_sub(1),
+                RenderCommand::PushTransform { .. } => stats.transforms += 1,
+                RenderCommand::PushLayer { .. } => stats.layers += 1,
+                RenderCommand::PopTransform | RenderCommand::PopLayer => {}
+            }
+        }
+
+        stats
+    }
+}
+
+/// A command that renderers can't draw as it is, found by [`validate_commands`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderViolation {
+    /// Position of the command in the list.
+    pub index: usize,
+    /// Innermost cache layer around the command, the only widget id commands carry.
+    pub layer: Option<WidgetId>,
+    pub kind: RenderViolationKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderViolationKind {
+    /// A pop that doesn't close the innermost push, e.g. `PopClip` inside a transform.
+    MismatchedPop {
+        expected: Option<&'static str>,
+    },
+    /// A push that is still open at the end of the list.
+    UnclosedPush(&'static str),
+    UnknownText(TextId),
+    UnknownSvg(&'static str),
+    UnknownImage(Arc<str>),
+    NonFinite,
+}
+
+impl std::fmt::Display for RenderViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Render command #{}", self.index)?;
+
+        if let Some(layer) = self.layer {
+            write!(f, " in layer {layer:?}")?;
+        }
+
+        match &self.kind {
+            RenderViolationKind::MismatchedPop {
+                expected: Some(expected),
+            } => write!(f, ": pop doesn't match the open {expected}"),
+            RenderViolationKind::MismatchedPop { expected: None } => {
+                write!(f, ": pop without a push")
+            }
+            RenderViolationKind::UnclosedPush(push) => write!(f, ": {push} is never popped"),
+            RenderViolationKind::UnknownText(text_id) => {
+                write!(f, ": text {text_id:?} has been removed")
+            }
+            RenderViolationKind::UnknownSvg(asset_id) => {
+                write!(f, ": svg \"{asset_id}\" isn't loaded")
+            }
+            RenderViolationKind::UnknownImage(asset_id) => {
+                write!(f, ": image \"{asset_id}\" isn't loaded")
+            }
+            RenderViolationKind::NonFinite => write!(f, ": coordinates aren't finite"),
+        }
+    }
 }
 
 pub struct RenderContext<'a, 'b> {
@@ -50,46 +371,213 @@ pub struct RenderContext<'a, 'b> {
     pub strings: &'a mut HashMap<StringId, TextId>,
     pub layout_direction: LayoutDirection,
     unsorted_commands: &'a mut Vec<RenderCommandUnsorted>,
+    /// Commands left out by [`RenderContext::push_command`] in this frame.
+    dropped_commands: &'a mut u32,
+    blinks: &'a mut Vec<BlinkingCommand>,
+    /// Time of the frame, see [`crate::BuildContext::time_since_start`].
+    time: Duration,
 }
 
 impl RenderContext<'_, '_> {
+    /// Adds the command to the frame, unless it's degenerate, see
+    /// [`RenderCommand::is_degenerate`].
     pub fn push_command(&mut self, zindex: i32, command: RenderCommand) {
+        if command.is_degenerate() {
+            *self.dropped_commands += 1;
+            return;
+        }
+
         self.unsorted_commands
             .push(RenderCommandUnsorted::RenderCommand { zindex, command });
     }
+
+    /// Adds the command to the frame shown only while the blink is visible. The frames
+    /// in between the builds follow the blink without building the view again, see
+    /// [`crate::ClewInstance::repaint`]. Commands without [`RenderCommand::bounds`]
+    /// don't blink.
+    pub fn push_blinking_command(&mut self, zindex: i32, command: RenderCommand, blink: Blink) {
+        let Some(bounds) = command.bounds().filter(|_| !command.is_degenerate()) else {
+            self.push_command(zindex, command);
+            return;
+        };
+
+        let blinking = BlinkingCommand {
+            index: usize::MAX,
+            command,
+            hidden: RenderCommand::Rect {
+                boundary: bounds,
+                fill: None,
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            },
+            blink,
+        };
+
+        self.push_command(zindex, blinking.command_at(self.time).clone());
+        self.blinks.push(blinking);
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum RenderCommand {
     Rect {
         boundary: Rect,
         fill: Option<Fill>,
         border_radius: Option<BorderRadius>,
         border: Option<Border>,
+        /// Paints the border instead of the colors of its sides, see [`Fill`].
+        border_fill: Option<Fill>,
     },
     Oval {
         boundary: Rect,
         fill: Option<Fill>,
         border: Option<BorderSide>,
+        /// Paints the border instead of the color of the side, see [`Fill`].
+        border_fill: Option<Fill>,
+    },
+    /// Blurred shadow of a rounded rect, drawn before the rect that casts it. The
+    /// boundary and the radii are the ones of the shadow, moved and spread already, the
+    /// blur fades out past them. The renderers fill the whole shape, not only the part
+    /// outside of the rect.
+    Shadow {
+        boundary: Rect,
+        border_radius: Option<BorderRadius>,
+        /// Distance the edges fade over, twice the standard deviation of the blur.
+        blur_radius: f32,
+        color: ColorRgba,
     },
     Text {
         x: f32,
         y: f32,
         text_id: TextId,
         tint_color: Option<ColorRgba>,
+        /// Vertical range relative to the text origin that is visible inside the
+        /// current clip. Layout runs outside of it can be skipped.
+        visible_band: Option<Range<f32>>,
+        /// Rect the glyphs are clipped to, e.g. the content rect of the widget the text
+        /// overflows. `None` lets them paint past it.
+        clip: Option<Rect>,
+        /// Outline drawn around the glyphs under their fill.
+        stroke: Option<TextStroke>,
     },
     Svg {
         boundary: Rect,
         asset_id: &'static str,
         tint_color: Option<ColorRgba>,
+        tint_mode: TintMode,
+    },
+    /// Bitmap cached in [`Assets`] under the key, fitted into the boundary and cut
+    /// to it.
+    Image {
+        boundary: Rect,
+        asset_id: Arc<str>,
+        /// Replaces the colors of the pixels keeping their alpha, e.g. for icons.
+        tint_color: Option<ColorRgba>,
+        fit: ImageFit,
+        opacity: f32,
     },
     PushClip {
         rect: Rect,
         shape: ClipShape,
     },
     PopClip,
+    /// Commands until the matching [`RenderCommand::PopTransform`] are drawn through
+    /// the transform, it's composed with the enclosing ones.
+    PushTransform {
+        transform: Transform,
+    },
+    PopTransform,
+    /// Commands until the matching [`RenderCommand::PopLayer`] belong to a cache layer.
+    /// The version changes whenever they draw something different, so renderers can
+    /// record the layer once and reuse the recording while the version stays the same.
+    /// Renderers that don't cache draw the commands as usual.
+    ///
+    /// The commands are in view space like all the others, not relative to the layer,
+    /// so a layer that moves gets a new version every frame it moves.
+    PushLayer {
+        id: WidgetId,
+        version: u64,
+    },
+    PopLayer,
 }
 
+/// Outline around the glyphs of a text, e.g. to keep a caption over an image readable.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TextStroke {
+    /// Thickness of the outline outside of the glyphs, in physical pixels.
+    pub width: f32,
+    pub color: ColorRgba,
+}
+
+impl RenderCommand {
+    /// Area the command paints, in the coordinates of the enclosing transform. `None`
+    /// for text, which doesn't know its extent, and the clip and transform commands.
+    pub fn bounds(&self) -> Option<Rect> {
+        match self {
+            RenderCommand::Rect {
+                boundary, border, ..
+            } => {
+                let border_width = border.map_or(0., |border| {
+                    [border.top, border.right, border.bottom, border.left]
+                        .into_iter()
+                        .flatten()
+                        .fold(0., |width, side| side.width.max(width))
+                });
+
+                Some(boundary.expand(border_width + 1.))
+            }
+            RenderCommand::Oval {
+                boundary, border, ..
+            } => Some(boundary.expand(border.map_or(0., |side| side.width) + 1.)),
+            // The blur fades out within three standard deviations.
+            RenderCommand::Shadow {
+                boundary,
+                blur_radius,
+                ..
+            } => Some(boundary.expand(blur_radius * 1.5 + 1.)),
+            RenderCommand::Svg { boundary, .. } | RenderCommand::Image { boundary, .. } => {
+                Some(boundary.expand(1.))
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the command can't draw anything, its boundary is empty or its coordinates
+    /// aren't finite. Such commands are dropped before they reach the renderers, which
+    /// would otherwise each handle them differently, e.g. a gradient over an empty rect
+    /// resolves to NaN coordinates.
+    pub fn is_degenerate(&self) -> bool {
+        let degenerate = |rect: &Rect| {
+            !(rect.x.is_finite()
+                && rect.y.is_finite()
+                && rect.width.is_finite()
+                && rect.height.is_finite())
+                || rect.width <= 0.
+                || rect.height <= 0.
+        };
+
+        match self {
+            RenderCommand::Rect { boundary, .. }
+            | RenderCommand::Oval { boundary, .. }
+            | RenderCommand::Shadow { boundary, .. }
+            | RenderCommand::Svg { boundary, .. }
+            | RenderCommand::Image { boundary, .. } => degenerate(boundary),
+            RenderCommand::Text { x, y, .. } => !x.is_finite() || !y.is_finite(),
+            _ => false,
+        }
+    }
+
+    /// Whether both commands draw the same, the versions of cache layers aside.
+    fn draws_same(&self, other: &RenderCommand) -> bool {
+        match (self, other) {
+            (RenderCommand::PushLayer { id, .. }, RenderCommand::PushLayer { id: other, .. }) => {
+                id == other
+            }
+            _ => self == other,
+        }
+    }
+}
 #[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum RenderCommandUnsorted {
@@ -110,7 +598,10 @@ impl RenderCommandUnsorted {
     }
 }
 
-#[derive(Debug, Clone)]
+/// Paint of the inside of a shape or of its outline. Gradients are resolved against the
+/// boundary of the shape in both cases, so a gradient border lines up with the same
+/// gradient filling the shape.
+#[derive(Debug, Clone, PartialEq)]
 pub enum Fill {
     None,
     Color(ColorRgba),
@@ -123,7 +614,7 @@ pub trait PixelExtension<T> {
 
 impl PixelExtension<f32> for f32 {
     fn px(self, ctx: &RenderContext) -> f32 {
-        self * ctx.view.scale_factor.ceil()
+        self * ctx.view.scale_factor
     }
 }
 
@@ -135,7 +626,7 @@ impl PixelExtension<Vec2> for Vec2 {
 
 impl PixelExtension<Rect> for Rect {
     fn px(self, ctx: &RenderContext) -> Rect {
-        self * ctx.view.scale_factor.ceil()
+        self * ctx.view.scale_factor
     }
 }
 
@@ -170,6 +661,17 @@ impl PixelExtension<Border> for Border {
     }
 }
 
+impl PixelExtension<BoxShadow> for BoxShadow {
+    fn px(self, ctx: &RenderContext) -> BoxShadow {
+        BoxShadow {
+            offset: self.offset * ctx.view.scale_factor,
+            blur_radius: self.blur_radius * ctx.view.scale_factor,
+            spread: self.spread * ctx.view.scale_factor,
+            color: self.color,
+        }
+    }
+}
+
 impl PixelExtension<ClipShape> for ClipShape {
     fn px(self, ctx: &RenderContext) -> ClipShape {
         match self {
@@ -182,27 +684,6 @@ impl PixelExtension<ClipShape> for ClipShape {
     }
 }
 
-#[derive(Clone, Copy)]
-enum GroupKind {
-    Clip,
-    Group,
-}
-
-impl GroupKind {
-    fn matches_end(&self, cmd: &RenderCommandUnsorted) -> bool {
-        matches!(
-            (self, cmd),
-            (
-                GroupKind::Clip,
-                RenderCommandUnsorted::RenderCommand {
-                    command: RenderCommand::PopClip,
-                    ..
-                }
-            ) | (GroupKind::Group, RenderCommandUnsorted::EndGroup)
-        )
-    }
-}
-
 fn get_zindex(cmd: &RenderCommandUnsorted) -> i32 {
     match cmd {
         RenderCommandUnsorted::RenderCommand { zindex, .. } => *zindex,
@@ -213,26 +694,28 @@ fn get_zindex(cmd: &RenderCommandUnsorted) -> i32 {
     }
 }
 
-fn group_start(cmd: &RenderCommandUnsorted) -> Option<GroupKind> {
-    match cmd {
+/// Clips, transforms and groups are properly nested, so any end marker closes the
+/// innermost open group.
+fn is_group_start(cmd: &RenderCommandUnsorted) -> bool {
+    matches!(
+        cmd,
         RenderCommandUnsorted::RenderCommand {
-            command: RenderCommand::PushClip { .. },
+            command: RenderCommand::PushClip { .. }
+                | RenderCommand::PushTransform { .. }
+                | RenderCommand::PushLayer { .. },
             ..
-        } => Some(GroupKind::Clip),
-        RenderCommandUnsorted::BeginGroup { .. } => Some(GroupKind::Group),
-        _ => None,
-    }
+        } | RenderCommandUnsorted::BeginGroup { .. }
+    )
 }
 
-fn group_end(cmd: &RenderCommandUnsorted) -> Option<GroupKind> {
-    match cmd {
+fn is_group_end(cmd: &RenderCommandUnsorted) -> bool {
+    matches!(
+        cmd,
         RenderCommandUnsorted::RenderCommand {
-            command: RenderCommand::PopClip,
+            command: RenderCommand::PopClip | RenderCommand::PopTransform | RenderCommand::PopLayer,
             ..
-        } => Some(GroupKind::Clip),
-        RenderCommandUnsorted::EndGroup => Some(GroupKind::Group),
-        _ => None,
-    }
+        } | RenderCommandUnsorted::EndGroup
+    )
 }
 
 pub fn sort_render_commands(
@@ -256,23 +739,23 @@ fn sort_segment(commands: &mut [RenderCommandUnsorted], start: usize, end: usize
     let mut i = start;
 
     while i < end {
-        if let Some(kind) = group_start(&commands[i]) {
+        if is_group_start(&commands[i]) {
             let group_start_idx = i;
             let group_zindex = get_zindex(&commands[i]);
             let mut depth = 1;
             i += 1;
 
             while i < end && depth > 0 {
-                if group_start(&commands[i]).is_some() {
+                if is_group_start(&commands[i]) {
                     depth += 1;
-                } else if kind.matches_end(&commands[i]) {
+                } else if is_group_end(&commands[i]) {
                     depth -= 1;
                 }
                 i += 1;
             }
 
             items.push((group_start_idx, i, group_zindex));
-        } else if group_end(&commands[i]).is_some() {
+        } else if is_group_end(&commands[i]) {
             break;
         } else {
             items.push((i, i + 1, get_zindex(&commands[i])));
@@ -298,15 +781,15 @@ fn sort_segment(commands: &mut [RenderCommandUnsorted], start: usize, end: usize
     // Recursively sort inside each group
     let mut i = start;
     while i < end {
-        if let Some(kind) = group_start(&commands[i]) {
+        if is_group_start(&commands[i]) {
             let content_start = i + 1;
             let mut depth = 1;
             i += 1;
 
             while i < end && depth > 0 {
-                if group_start(&commands[i]).is_some() {
+                if is_group_start(&commands[i]) {
                     depth += 1;
-                } else if kind.matches_end(&commands[i]) {
+                } else if is_group_end(&commands[i]) {
                     depth -= 1;
                 }
                 i += 1;
@@ -319,6 +802,193 @@ fn sort_segment(commands: &mut [RenderCommandUnsorted], start: usize, end: usize
     }
 }
 
+/// Checks that every push is closed by the matching pop in the nesting order, that the
+/// texts, svgs and images the commands draw exist, and that the coordinates are finite.
+pub fn validate_commands(
+    commands: &[RenderCommand],
+    text: &TextsResources,
+    assets: &Assets,
+) -> Vec<RenderViolation> {
+    let mut validator = Validator::new(text, assets);
+
+    for (index, command) in commands.iter().enumerate() {
+        validator.command(index, command);
+    }
+
+    validator.finish()
+}
+
+/// Same as [`validate_commands`] but also pairs the groups, which only exist before the
+/// commands are sorted.
+fn validate_unsorted_commands(
+    commands: &[RenderCommandUnsorted],
+    text: &TextsResources,
+    assets: &Assets,
+) -> Vec<RenderViolation> {
+    let mut validator = Validator::new(text, assets);
+
+    for (index, command) in commands.iter().enumerate() {
+        match command {
+            RenderCommandUnsorted::RenderCommand { command, .. } => {
+                validator.command(index, command)
+            }
+            RenderCommandUnsorted::BeginGroup { .. } => validator.push(index, "group", None),
+            RenderCommandUnsorted::EndGroup => validator.pop(index, "group"),
+        }
+    }
+
+    validator.finish()
+}
+
+struct Validator<'a, 'b> {
+    text: &'a TextsResources<'b>,
+    assets: &'a Assets<'a>,
+    /// Open pushes with their positions and the layer ids of the layer pushes.
+    stack: Vec<(usize, &'static str, Option<WidgetId>)>,
+    violations: Vec<RenderViolation>,
+}
+
+impl<'a, 'b> Validator<'a, 'b> {
+    fn new(text: &'a TextsResources<'b>, assets: &'a Assets<'a>) -> Self {
+        Self {
+            text,
+            assets,
+            stack: Vec::new(),
+            violations: Vec::new(),
+        }
+    }
+
+    fn layer(&self) -> Option<WidgetId> {
+        self.stack.iter().rev().find_map(|(_, _, layer)| *layer)
+    }
+
+    fn report(&mut self, index: usize, kind: RenderViolationKind) {
+        self.violations.push(RenderViolation {
+            index,
+            layer: self.layer(),
+            kind,
+        });
+    }
+
+    fn push(&mut self, index: usize, kind: &'static str, layer: Option<WidgetId>) {
+        self.stack.push((index, kind, layer));
+    }
+
+    fn pop(&mut self, index: usize, kind: &'static str) {
+        match self.stack.last() {
+            Some((_, open, _)) if *open == kind => {
+                self.stack.pop();
+            }
+            open => {
+                let expected = open.map(|(_, open, _)| *open);
+                self.report(index, RenderViolationKind::MismatchedPop { expected });
+            }
+        }
+    }
+
+    fn command(&mut self, index: usize, command: &RenderCommand) {
+        let finite = |rect: &Rect| {
+            rect.x.is_finite()
+                && rect.y.is_finite()
+                && rect.width.is_finite()
+                && rect.height.is_finite()
+        };
+
+        let is_finite = match command {
+            RenderCommand::Rect { boundary, .. }
+            | RenderCommand::Oval { boundary, .. }
+            | RenderCommand::Shadow { boundary, .. }
+            | RenderCommand::Svg { boundary, .. }
+            | RenderCommand::Image { boundary, .. } => finite(boundary),
+            RenderCommand::Text { x, y, .. } => x.is_finite() && y.is_finite(),
+            RenderCommand::PushClip { rect, .. } => finite(rect),
+            RenderCommand::PushTransform { transform } => {
+                transform.translation.x.is_finite()
+                    && transform.translation.y.is_finite()
+                    && transform.scale.x.is_finite()
+                    && transform.scale.y.is_finite()
+            }
+            _ => true,
+        };
+
+        if !is_finite {
+            self.report(index, RenderViolationKind::NonFinite);
+        }
+
+        match command {
+            RenderCommand::Text { text_id, .. } if !self.text.contains(*text_id) => {
+                self.report(index, RenderViolationKind::UnknownText(*text_id));
+            }
+            RenderCommand::Svg { asset_id, .. }
+                if self.assets.get_svg_tree(asset_id).is_none()
+                    && !self.assets.is_svg_pending(asset_id) =>
+            {
+                self.report(index, RenderViolationKind::UnknownSvg(asset_id));
+            }
+            RenderCommand::Image { asset_id, .. } if self.assets.get_bitmap(asset_id).is_none() => {
+                self.report(index, RenderViolationKind::UnknownImage(asset_id.clone()));
+            }
+            RenderCommand::PushClip { .. } => self.push(index, "clip", None),
+            RenderCommand::PopClip => self.pop(index, "clip"),
+            RenderCommand::PushTransform { .. } => self.push(index, "transform", None),
+            RenderCommand::PopTransform => self.pop(index, "transform"),
+            RenderCommand::PushLayer { id, .. } => self.push(index, "layer", Some(*id)),
+            RenderCommand::PopLayer => self.pop(index, "layer"),
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> Vec<RenderViolation> {
+        while let Some((index, kind, layer)) = self.stack.pop() {
+            self.violations.push(RenderViolation {
+                index,
+                layer: layer.or_else(|| self.layer()),
+                kind: RenderViolationKind::UnclosedPush(kind),
+            });
+        }
+
+        self.violations
+    }
+}
+
+/// Lays out the commands, the second layout wraps the texts at the widths of the first.
+fn layout_with_texts(
+    state: &mut UiState,
+    text: &mut TextsResources,
+    fonts: &mut FontResources,
+    assets: &Assets,
+) {
+    layout(
+        &mut state.layout_state,
+        &state.view,
+        &state.layout_commands,
+        &mut state.layout_items,
+        &mut state.widgets_states.layout_measures,
+        &state.widgets_states.decorator_placements,
+        text,
+        assets,
+    );
+
+    for layout_text in &state.layout_state.texts {
+        text.get_mut(layout_text.text_id).set_size(
+            &mut fonts.font_system,
+            Some(layout_text.width),
+            layout_text.visible_height,
+        );
+    }
+
+    layout(
+        &mut state.layout_state,
+        &state.view,
+        &state.layout_commands,
+        &mut state.layout_items,
+        &mut state.widgets_states.layout_measures,
+        &state.widgets_states.decorator_placements,
+        text,
+        assets,
+    );
+}
+
 pub fn render(
     state: &mut UiState,
     text: &mut TextsResources,
@@ -334,35 +1004,106 @@ pub fn render(
     {
         profiling::scope!("clew :: Layout");
 
-        layout(
-            &mut state.layout_state,
-            &state.view,
-            &state.layout_commands,
-            &mut state.layout_items,
-            &mut state.widgets_states.layout_measures,
-            text,
-            assets,
+        state.layout_state.scroll_into_view_target =
+            state.interaction_state.scroll_into_view.take();
+
+        state.layout_state.moved_groups.clear();
+        state
+            .layout_state
+            .moved_groups
+            .extend(state.phases.after_layout.iter().filter_map(|(id, _)| *id));
+
+        if state.layout_state.scroll_into_view_target.is_some() {
+            let obscured_insets = state.view_config.obscured_insets;
+
+            state.phases.after_layout.push((
+                None,
+                Box::new(move |layout, mutator| {
+                    widgets::scroll_area::scroll_into_view_after_layout(
+                        layout,
+                        mutator,
+                        obscured_insets,
+                    )
+                }),
+            ));
+        }
+
+        state.layout_state.layout_direction = state.layout_direction;
+
+        widgets::portal::append_portals(
+            &mut state.layout_commands,
+            &mut state.portals,
+            &mut state.portal_order,
+        );
+        widgets::scroll_area::track_anchors(
+            &state.widgets_states.scroll_area,
+            &mut state.layout_state.scroll_anchors,
         );
 
-        for layout_text in &state.layout_state.texts {
-            let text = text.get_mut(layout_text.text_id);
+        widgets::scroll_area::track_gutters(
+            &state.widgets_states.scroll_area,
+            &mut state.layout_state.scroll_gutters,
+        );
 
-            text.with_buffer_mut(|buffer| {
-                buffer.set_size(&mut fonts.font_system, Some(layout_text.width), None);
-            });
+        layout_with_texts(state, text, fonts, assets);
+
+        // A gutter taken or freed changes the room of the content, which can change
+        // whether it overflows, the second round can only take the gutters.
+        for free in [true, false] {
+            if !widgets::scroll_area::settle_gutters(
+                &mut state.widgets_states.scroll_area,
+                &state.widgets_states.layout_measures,
+                &mut state.layout_state.scroll_gutters,
+                free,
+            ) {
+                break;
+            }
+
+            layout_with_texts(state, text, fonts, assets);
+        }
+
+        // The content has changed size around the anchors, it's laid out again with
+        // the offsets that keep them in place.
+        if widgets::scroll_area::keep_anchors(
+            &mut state.widgets_states.scroll_area,
+            &state.widgets_states.layout_measures,
+            &mut state.layout_state.scroll_anchors,
+        ) {
+            layout(
+                &mut state.layout_state,
+                &state.view,
+                &state.layout_commands,
+                &mut state.layout_items,
+                &mut state.widgets_states.layout_measures,
+                &state.widgets_states.decorator_placements,
+                text,
+                assets,
+            );
         }
 
-        layout(
-            &mut state.layout_state,
-            &state.view,
-            &state.layout_commands,
-            &mut state.layout_items,
-            &mut state.widgets_states.layout_measures,
+        widgets::scroll_area::update_anchors(
+            &mut state.widgets_states.scroll_area,
+            &state.widgets_states.layout_measures,
+            &state.layout_state.scroll_anchors,
+        );
+
+        widgets::silhouette::update_shapes(
+            &mut state.widgets_states,
+            &state.layout_state.hidden,
             text,
-            assets,
+            state.view.scale_factor,
         );
     }
 
+    {
+        profiling::scope!("clew :: After layout");
+
+        phases::run_after_layout(state);
+
+        state.layout_state.scroll_into_view_target = None;
+        state.layout_state.scroll_into_view = None;
+    }
+
     tracy_client::plot!(
         "clew :: Layout commands",
         state.layout_commands.len() as f64
@@ -383,15 +1124,23 @@ pub fn render(
                 &state.layout_items,
             );
 
-        need_to_redraw = need_to_redraw || state.interaction_state != state.last_interaction_state;
+        need_to_redraw = need_to_redraw
+            || state.interaction_state != state.last_interaction_state
+            || state.latency_probe;
         state.last_interaction_state = state.interaction_state.clone();
     }
 
     state.render_state.unsorted_commands.clear();
 
+    let mut dropped_commands = 0;
+
     if force_redraw || need_to_redraw {
         profiling::scope!("clew :: Collect Render Commands");
 
+        state.render_state.blinks.clear();
+
+        let mut ime_cursor_rect = None;
+
         for layout_item in &state.layout_items {
             let mut render_context = RenderContext {
                 interaction: &state.interaction_state,
@@ -403,6 +1152,9 @@ pub fn render(
                 strings,
                 layout_direction: state.layout_direction,
                 unsorted_commands: &mut state.render_state.unsorted_commands,
+                dropped_commands: &mut dropped_commands,
+                blinks: &mut state.render_state.blinks,
+                time: state.time_since_start,
             };
 
             match layout_item {
@@ -421,6 +1173,20 @@ pub fn render(
                         );
                     }
 
+                    if placement.widget_ref.widget_type
+                        == WidgetType::of::<widgets::rich_text::RichTextWidget>()
+                    {
+                        widgets::rich_text::render(
+                            &mut render_context,
+                            placement,
+                            state
+                                .widgets_states
+                                .rich_text
+                                .get(placement.widget_ref.id)
+                                .unwrap(),
+                        );
+                    }
+
                     if placement.widget_ref.widget_type
                         == WidgetType::of::<widgets::decorated_box::DecoratedBox>()
                     {
@@ -440,27 +1206,37 @@ pub fn render(
                     }
 
                     if placement.widget_ref.widget_type
-                        == WidgetType::of::<widgets::svg::SvgWidget>()
+                        == WidgetType::of::<widgets::image::ImageWidget>()
                     {
-                        widgets::svg::render(
+                        widgets::image::render(
                             &mut render_context,
                             placement,
                             state
                                 .widgets_states
-                                .svg
+                                .image
+                                .get(placement.widget_ref.id)
+                                .unwrap(),
+                        );
+                    }
+
+                    if placement.widget_ref.widget_type
+                        == WidgetType::of::<widgets::async_image::AsyncImageWidget>()
+                    {
+                        widgets::async_image::render(
+                            &mut render_context,
+                            placement,
+                            state
+                                .widgets_states
+                                .async_image
                                 .get(placement.widget_ref.id)
                                 .unwrap(),
-                            // state
-                            //     .widgets_states
-                            //     .get_mut::<widgets::svg::State>(placement.widget_ref.id)
-                            //     .unwrap(),
                         );
                     }
 
                     if placement.widget_ref.widget_type
                         == WidgetType::of::<widgets::editable_text::EditableTextWidget>()
                     {
-                        widgets::editable_text::render(
+                        let caret = widgets::editable_text::render(
                             &mut render_context,
                             placement,
                             state
@@ -469,11 +1245,23 @@ pub fn render(
                                 .get(placement.widget_ref.id)
                                 .unwrap(),
                         );
+
+                        ime_cursor_rect = ime_cursor_rect.or(caret);
                     }
 
                     if placement.widget_ref.widget_type == WidgetType::of::<DebugBoundary>() {
                         render_debug_boundary(&mut render_context, placement);
                     }
+
+                    // The leaves of the widgets with a state of their own, see
+                    // [`crate::state::WidgetState::render`].
+                    if let Some(Some(widget_state)) =
+                        state.widgets_states.custom.get(placement.widget_ref.id)
+                        && WidgetType::of_state(widget_state.as_ref())
+                            == placement.widget_ref.widget_type
+                    {
+                        widget_state.render(&mut render_context, placement);
+                    }
                 }
                 LayoutItem::PushClip { rect, clip, zindex } => {
                     let shape = clip
@@ -498,12 +1286,56 @@ pub fn render(
                         },
                     );
                 }
+                LayoutItem::PushTransform { transform, zindex } => {
+                    let transform = Transform {
+                        translation: transform.translation.px(&render_context),
+                        scale: transform.scale,
+                    };
+
+                    state.render_state.unsorted_commands.push(
+                        RenderCommandUnsorted::RenderCommand {
+                            zindex: *zindex,
+                            command: RenderCommand::PushTransform { transform },
+                        },
+                    );
+                }
+                LayoutItem::PopTransform => {
+                    state.render_state.unsorted_commands.push(
+                        RenderCommandUnsorted::RenderCommand {
+                            zindex: 0,
+                            command: RenderCommand::PopTransform,
+                        },
+                    );
+                }
                 LayoutItem::BeginGroup { zindex } => {
                     state
                         .render_state
                         .unsorted_commands
                         .push(RenderCommandUnsorted::BeginGroup { zindex: *zindex });
                 }
+                LayoutItem::BeginLayer { id, zindex } => {
+                    // The version is set once the commands are sorted.
+                    state.render_state.unsorted_commands.push(
+                        RenderCommandUnsorted::RenderCommand {
+                            zindex: *zindex,
+                            command: RenderCommand::PushLayer {
+                                id: *id,
+                                version: 0,
+                            },
+                        },
+                    );
+                }
+                LayoutItem::EndLayer => {
+                    state.render_state.unsorted_commands.push(
+                        RenderCommandUnsorted::RenderCommand {
+                            zindex: 0,
+                            command: RenderCommand::PopLayer,
+                        },
+                    );
+                }
+                LayoutItem::Command { zindex, command } => {
+                    render_context.push_command(*zindex, (**command).clone());
+                }
                 LayoutItem::EndGroup => {
                     // state.render_state.commands.push(RenderCommand::EndGroup);
 
@@ -515,6 +1347,22 @@ pub fn render(
             }
         }
 
+        if let Some(rect) = ime_cursor_rect {
+            state.view_config.ime_cursor_rect = rect;
+        }
+
+        // The pointer is outside of the view at negative coordinates.
+        if state.latency_probe && state.user_input.mouse_x >= 0. {
+            state
+                .render_state
+                .unsorted_commands
+                .push(latency_probe_command(&state.user_input));
+        }
+
+        if dropped_commands > 0 {
+            log::debug!("Dropped {dropped_commands} render commands that draw nothing");
+        }
+
         tracy_client::plot!("clew :: Layout Items", state.layout_items.len() as f64);
 
         tracy_client::plot!(
@@ -523,21 +1371,91 @@ pub fn render(
         );
     }
 
+    // The focused widget hasn't been built, e.g. it has been removed or scrolled out of
+    // a list, it loses the focus the same way as when another widget takes it, before
+    // its state is collected.
+    if let Some(focused) = state.interaction_state.focused
+        && state.widgets_states.is_dropped(focused)
+    {
+        state.interaction_state.unfocus(&focused);
+
+        if let Some(editable_text) = state.widgets_states.editable_text.get_mut(focused) {
+            widgets::editable_text::interaction::on_focus_lost(
+                editable_text,
+                &mut state.user_input,
+                &mut state.os_events,
+                text,
+                fonts,
+                &mut state.view_config,
+            );
+        }
+    }
+
+    // Same for the widget being pressed or dragged, otherwise the pointer stays
+    // captured by a widget that isn't there anymore, e.g. after switching layouts.
+    if let Some(active) = state.interaction_state.active
+        && state.widgets_states.is_dropped(active)
+    {
+        state.interaction_state.set_inactive(&active);
+    }
+
     state.widgets_states.sweep();
-    state.user_input.clear_frame_events();
 
     {
+        profiling::scope!("clew :: Release texts");
+
+        let mut live_text_ids = state.widgets_states.live_text_ids();
+        live_text_ids.extend(strings.values().copied());
+        text.retain_only(&live_text_ids);
+    }
+
+    state.user_input.clear_frame_events();
+
+    if force_redraw || need_to_redraw {
         profiling::scope!("clew :: Sort commands by zindex");
 
+        std::mem::swap(
+            &mut state.render_state.last_commands,
+            &mut state.render_state.commands,
+        );
+
         // println!("Before sort:");
         // for (i, cmd) in state.render_state.commands.iter().enumerate() {
         //     println!("  {}: {:?}", i, cmd);
         // }
 
+        #[cfg(any(debug_assertions, feature = "validate-render"))]
+        for violation in
+            validate_unsorted_commands(&state.render_state.unsorted_commands, text, assets)
+        {
+            log::error!("{violation}");
+        }
+
         sort_render_commands(
             &mut state.render_state.unsorted_commands,
             &mut state.render_state.commands,
         );
+        find_blinks(&mut state.render_state, state.time_since_start);
+
+        let text_updates = std::mem::take(&mut state.widgets_states.text_updates);
+
+        state.render_state.stats = RenderStats {
+            texts_updated: text_updates.updated,
+            texts_skipped: text_updates.skipped,
+            placeholder_items: std::mem::take(&mut state.widgets_states.placeholder_items),
+            dropped_commands,
+            ..RenderStats::collect(&state.render_state.commands, text)
+        };
+
+        tracy_client::plot!(
+            "clew :: Max clip depth",
+            state.render_state.stats.max_clip_depth as f64
+        );
+
+        tracy_client::plot!(
+            "clew :: Text runs",
+            state.render_state.stats.text_runs as f64
+        );
 
         // println!("After sort:");
         // for (i, cmd) in state.render_state.commands.iter().enumerate() {
@@ -551,12 +1469,296 @@ pub fn render(
         //     .sort_by_key(|cmd| cmd.zindex().unwrap_or(i32::MAX));
     }
 
+    {
+        profiling::scope!("clew :: Damage");
+
+        let render_state = &mut state.render_state;
+        let invalidated = render_state
+            .invalidated_rects
+            .drain(..)
+            .map(|rect| rect * state.view.scale_factor)
+            .reduce(|damage, rect| damage.union(rect));
+
+        if force_redraw || need_to_redraw {
+            update_layer_versions(render_state, text);
+        }
+
+        render_state.damage = if force_redraw {
+            Damage::Full
+        } else if need_to_redraw {
+            damage(
+                &render_state.last_commands,
+                &render_state.commands,
+                invalidated,
+                text,
+            )
+        } else {
+            Damage::None
+        };
+
+        for command in &render_state.commands {
+            if let RenderCommand::Text { text_id, .. } = command {
+                text.get_mut(*text_id)
+                    .with_buffer_mut(|buffer| buffer.set_redraw(false));
+            }
+        }
+    }
+
     {
         profiling::scope!("clew :: Reset phase allocator");
         state.phase_allocator.reset();
     }
 
-    force_redraw || need_to_redraw
+    state.render_state.damage != Damage::None
+}
+
+/// Finds the blinking commands among the sorted ones.
+fn find_blinks(render_state: &mut RenderState, time: Duration) {
+    let RenderState {
+        commands, blinks, ..
+    } = render_state;
+
+    blinks.retain_mut(|blinking| {
+        let command = blinking.command_at(time);
+
+        match commands.iter().position(|other| other == command) {
+            Some(index) => {
+                blinking.index = index;
+                true
+            }
+            None => false,
+        }
+    });
+}
+
+/// Shows the blinking commands of the last built frame as they are at `time`, without
+/// building it again. Returns whether the frame has changed, the damage covers the
+/// commands that did.
+pub(crate) fn repaint(
+    render_state: &mut RenderState,
+    time: Duration,
+    text: &TextsResources,
+) -> bool {
+    let changes: Vec<(usize, RenderCommand)> = render_state
+        .blinks
+        .iter()
+        .map(|blinking| (blinking.index, blinking.command_at(time)))
+        .filter(|(index, command)| render_state.commands[*index] != **command)
+        .map(|(index, command)| (index, command.clone()))
+        .collect();
+
+    if changes.is_empty() {
+        render_state.damage = Damage::None;
+        return false;
+    }
+
+    render_state
+        .last_commands
+        .clone_from(&render_state.commands);
+
+    for (index, command) in changes {
+        render_state.commands[index] = command;
+    }
+
+    update_layer_versions(render_state, text);
+    render_state.damage = damage(
+        &render_state.last_commands,
+        &render_state.commands,
+        None,
+        text,
+    );
+
+    true
+}
+
+/// Compares the commands with the ones of the previous frame and returns what has to be
+/// repainted. Drawings inside `invalidated` may come and go, otherwise the lists have to
+/// line up command by command, anything else repaints the whole view.
+fn damage(
+    last_commands: &[RenderCommand],
+    commands: &[RenderCommand],
+    invalidated: Option<Rect>,
+    text: &TextsResources,
+) -> Damage {
+    let last_commands = placed_commands(last_commands, invalidated);
+    let commands = placed_commands(commands, invalidated);
+
+    if last_commands.len() != commands.len() {
+        return Damage::Full;
+    }
+
+    let mut damage = invalidated.map_or(Damage::None, Damage::Rect);
+
+    for ((last_command, last_bounds), (command, bounds)) in last_commands.into_iter().zip(commands)
+    {
+        // Text content changes without changing the command.
+        if let RenderCommand::Text { text_id, .. } = command
+            && text.get(*text_id).buffer().redraw()
+        {
+            return Damage::Full;
+        }
+
+        if last_command.draws_same(command) {
+            continue;
+        }
+
+        match (last_bounds, bounds) {
+            (Some(last_bounds), Some(bounds)) => damage = damage.add(last_bounds.union(bounds)),
+            _ => return Damage::Full,
+        }
+    }
+
+    damage
+}
+
+/// Sets the versions of the cache layers, a layer gets a new version when its commands
+/// differ from the previous frame or draw a text whose content has changed.
+fn update_layer_versions(render_state: &mut RenderState, text: &TextsResources) {
+    let last_layers: WidgetIdMap<Range<usize>> =
+        layer_ranges(&render_state.last_commands).collect();
+    let mut versions = WidgetIdMap::default();
+    let mut updates = Vec::new();
+
+    for (id, range) in layer_ranges(&render_state.commands) {
+        let commands = &render_state.commands[range.clone()];
+        let text_changed = commands.iter().any(|command| {
+            matches!(command, RenderCommand::Text { text_id, .. }
+                if text.get(*text_id).buffer().redraw())
+        });
+        let last_version = render_state.layer_versions.get(&id).copied();
+        let unchanged = !text_changed
+            && last_version.is_some()
+            && last_layers.get(&id).is_some_and(|last_range| {
+                let last_commands = &render_state.last_commands[last_range.clone()];
+
+                last_commands.len() == commands.len()
+                    && last_commands
+                        .iter()
+                        .zip(commands)
+                        .all(|(last, command)| last.draws_same(command))
+            });
+
+        let version = match last_version {
+            Some(version) if unchanged => version,
+            _ => {
+                render_state.next_layer_version += 1;
+                render_state.next_layer_version
+            }
+        };
+
+        versions.insert(id, version);
+        updates.push((range.start - 1, version));
+    }
+
+    for (index, new_version) in updates {
+        if let RenderCommand::PushLayer { version, .. } = &mut render_state.commands[index] {
+            *version = new_version;
+        }
+    }
+
+    render_state.layer_versions = versions;
+}
+
+/// Ids of the cache layers with the range of the commands inside of them.
+fn layer_ranges(commands: &[RenderCommand]) -> impl Iterator<Item = (WidgetId, Range<usize>)> {
+    let mut open = Vec::new();
+
+    commands
+        .iter()
+        .enumerate()
+        .filter_map(move |(index, command)| match command {
+            RenderCommand::PushLayer { id, .. } => {
+                open.push((*id, index + 1));
+                None
+            }
+            RenderCommand::PopLayer => open.pop().map(|(id, start)| (id, start..index)),
+            _ => None,
+        })
+}
+
+/// Pairs the commands with the area they paint in the view space, leaving out the
+/// drawings inside `invalidated`.
+fn placed_commands(
+    commands: &[RenderCommand],
+    invalidated: Option<Rect>,
+) -> Vec<(&RenderCommand, Option<Rect>)> {
+    let mut transforms: Vec<Transform> = Vec::new();
+    let mut placed = Vec::with_capacity(commands.len());
+
+    for command in commands {
+        match command {
+            RenderCommand::PushTransform { transform } => {
+                let outer = transforms.last().copied().unwrap_or(Transform::IDENTITY);
+                transforms.push(transform.then(outer));
+            }
+            RenderCommand::PopTransform => {
+                transforms.pop();
+            }
+            _ => {}
+        }
+
+        let bounds = command.bounds().map(|bounds| {
+            transforms
+                .last()
+                .map_or(bounds, |transform| transform.apply_rect(bounds))
+        });
+
+        if let (Some(bounds), Some(invalidated)) = (bounds, invalidated)
+            && invalidated.contains_rect(bounds)
+        {
+            continue;
+        }
+
+        placed.push((command, bounds));
+    }
+
+    placed
+}
+
+/// Returns the part of `rect` visible inside `clip_rect` as a vertical range relative to
+/// `origin_y`, or `None` if the whole rect is visible.
+pub(crate) fn visible_band(
+    rect: Rect,
+    clip_rect: Option<Rect>,
+    origin_y: f32,
+) -> Option<Range<f32>> {
+    let clip_rect = clip_rect?;
+
+    if clip_rect.top() <= rect.top() && clip_rect.bottom() >= rect.bottom() {
+        return None;
+    }
+
+    let visible = rect.intersect(clip_rect);
+
+    Some(visible.top() - origin_y..visible.bottom() - origin_y)
+}
+
+/// Whether a layout run of a text is at least partly inside the visible band of its
+/// [`RenderCommand::Text`], the renderers skip the runs that aren't.
+pub fn is_run_visible(visible_band: Option<&Range<f32>>, run: &cosmic_text::LayoutRun) -> bool {
+    visible_band
+        .is_none_or(|band| run.line_top + run.line_height >= band.start && run.line_top <= band.end)
+}
+
+/// Square centered at the position of the pointer, drawn above everything else.
+fn latency_probe_command(input: &UserInput) -> RenderCommandUnsorted {
+    const SIZE: f32 = 12.;
+
+    RenderCommandUnsorted::RenderCommand {
+        zindex: i32::MAX,
+        command: RenderCommand::Rect {
+            boundary: Rect::new(
+                input.mouse_x - SIZE / 2.,
+                input.mouse_y - SIZE / 2.,
+                SIZE,
+                SIZE,
+            ),
+            fill: Some(Fill::Color(ColorRgba::from_hex(0xFFFF00FF))),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        },
+    }
 }
 
 fn render_debug_boundary(ctx: &mut RenderContext, placement: &WidgetPlacement) {
@@ -570,6 +1772,464 @@ fn render_debug_boundary(ctx: &mut RenderContext, placement: &WidgetPlacement) {
                 2.,
                 ColorRgba::from_hex(0xFFFF0000),
             ))),
+            border_fill: None,
         },
     );
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ClewInstance, Clip, EdgeInsets, LinearGradient, View, testing,
+        widgets::{
+            builder::{BuildContext, WidgetBuilder},
+            decorated_box::decorated_box,
+            hstack,
+            text::text,
+            vstack, zstack,
+        },
+    };
+
+    use super::*;
+
+    fn rect(zindex: i32, x: f32) -> RenderCommandUnsorted {
+        RenderCommandUnsorted::RenderCommand {
+            zindex,
+            command: RenderCommand::Rect {
+                boundary: Rect::new(x, 0., 1., 1.),
+                fill: None,
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_sort_mixed_nested_groups() {
+        let mut commands = vec![
+            RenderCommandUnsorted::RenderCommand {
+                zindex: 0,
+                command: RenderCommand::PushClip {
+                    rect: Rect::ZERO,
+                    shape: ClipShape::Rect,
+                },
+            },
+            RenderCommandUnsorted::RenderCommand {
+                zindex: 0,
+                command: RenderCommand::PushTransform {
+                    transform: Transform::IDENTITY,
+                },
+            },
+            RenderCommandUnsorted::BeginGroup { zindex: 0 },
+            rect(0, 1.),
+            RenderCommandUnsorted::EndGroup,
+            RenderCommandUnsorted::RenderCommand {
+                zindex: 0,
+                command: RenderCommand::PopTransform,
+            },
+            RenderCommandUnsorted::RenderCommand {
+                zindex: 0,
+                command: RenderCommand::PopClip,
+            },
+            rect(-1, 2.),
+        ];
+        let mut output = Vec::new();
+
+        sort_render_commands(&mut commands, &mut output);
+
+        assert!(matches!(output[0], RenderCommand::Rect { boundary, .. } if boundary.x == 2.));
+        assert!(matches!(output[1], RenderCommand::PushClip { .. }));
+        assert!(matches!(output[2], RenderCommand::PushTransform { .. }));
+        assert!(matches!(output[3], RenderCommand::Rect { boundary, .. } if boundary.x == 1.));
+        assert!(matches!(output[4], RenderCommand::PopTransform));
+        assert!(matches!(output[5], RenderCommand::PopClip));
+    }
+
+    fn fill_rect(boundary: Rect, color: u32) -> RenderCommand {
+        RenderCommand::Rect {
+            boundary,
+            fill: Some(Fill::Color(ColorRgba::from_hex(color))),
+            border_radius: None,
+            border: None,
+            border_fill: None,
+        }
+    }
+
+    #[test]
+    fn test_blink_alternates_from_its_start() {
+        let blink = Blink {
+            since: Duration::from_millis(200),
+            interval: Duration::from_millis(500),
+        };
+
+        assert!(blink.is_visible(Duration::ZERO));
+        assert!(blink.is_visible(Duration::from_millis(699)));
+        assert!(!blink.is_visible(Duration::from_millis(700)));
+        assert!(blink.is_visible(Duration::from_millis(1200)));
+
+        assert_eq!(
+            blink.next_change(Duration::ZERO),
+            Duration::from_millis(200)
+        );
+        assert_eq!(
+            blink.next_change(Duration::from_millis(700)),
+            Duration::from_millis(1200)
+        );
+    }
+
+    #[test]
+    fn test_damage_covers_changed_and_invalidated_commands() {
+        let text = TextsResources::new();
+        let background = fill_rect(Rect::new(0., 0., 100., 100.), 0xFFFFFFFF);
+        let button = Rect::new(10., 10., 20., 10.);
+        let caret = Rect::new(50., 50., 2., 10.);
+
+        let last = vec![background.clone(), fill_rect(button, 0xFF000000)];
+        let hovered = vec![background.clone(), fill_rect(button, 0xFF333333)];
+        assert_eq!(
+            damage(&last, &hovered, None, &text),
+            Damage::Rect(button.expand(1.))
+        );
+
+        let with_caret = vec![
+            background.clone(),
+            fill_rect(button, 0xFF000000),
+            fill_rect(caret, 0xFF000000),
+        ];
+        assert_eq!(damage(&last, &with_caret, None, &text), Damage::Full);
+
+        let invalidated = caret.expand(2.);
+        assert_eq!(
+            damage(&last, &with_caret, Some(invalidated), &text),
+            Damage::Rect(invalidated)
+        );
+        assert_eq!(damage(&last, &last, None, &text), Damage::None);
+    }
+
+    #[test]
+    fn test_layer_version_changes_with_content() {
+        let text = TextsResources::new();
+        let id = WidgetId::auto();
+        let content = Rect::new(10., 10., 20., 10.);
+        let layer = |color| {
+            vec![
+                RenderCommand::PushLayer { id, version: 0 },
+                fill_rect(content, color),
+                RenderCommand::PopLayer,
+            ]
+        };
+        let version = |render_state: &RenderState| match render_state.commands[0] {
+            RenderCommand::PushLayer { version, .. } => version,
+            _ => unreachable!(),
+        };
+        let mut render_state = RenderState {
+            commands: layer(0xFF000000),
+            ..Default::default()
+        };
+
+        update_layer_versions(&mut render_state, &text);
+        let first = version(&render_state);
+
+        render_state.last_commands = std::mem::take(&mut render_state.commands);
+        render_state.commands = layer(0xFF000000);
+        update_layer_versions(&mut render_state, &text);
+        assert_eq!(version(&render_state), first);
+
+        render_state.last_commands = std::mem::take(&mut render_state.commands);
+        render_state.commands = layer(0xFF333333);
+        update_layer_versions(&mut render_state, &text);
+        assert_ne!(version(&render_state), first);
+    }
+
+    #[test]
+    fn test_validator_reports_unbalanced_and_unknown_commands() {
+        let text = TextsResources::new();
+        let assets = Assets::new();
+        let clip = RenderCommand::PushClip {
+            rect: Rect::ZERO,
+            shape: ClipShape::Rect,
+        };
+        let commands = vec![
+            RenderCommandUnsorted::BeginGroup { zindex: 0 },
+            RenderCommandUnsorted::RenderCommand {
+                zindex: 0,
+                command: clip.clone(),
+            },
+            rect(0, f32::NAN),
+            RenderCommandUnsorted::RenderCommand {
+                zindex: 0,
+                command: RenderCommand::Svg {
+                    boundary: Rect::ZERO,
+                    asset_id: "missing",
+                    tint_color: None,
+                    tint_mode: TintMode::Replace,
+                },
+            },
+            RenderCommandUnsorted::EndGroup,
+            RenderCommandUnsorted::RenderCommand {
+                zindex: 0,
+                command: RenderCommand::PopClip,
+            },
+        ];
+
+        let kinds = validate_unsorted_commands(&commands, &text, &assets)
+            .into_iter()
+            .map(|violation| (violation.index, violation.kind))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            kinds,
+            vec![
+                (2, RenderViolationKind::NonFinite),
+                (3, RenderViolationKind::UnknownSvg("missing")),
+                (
+                    4,
+                    RenderViolationKind::MismatchedPop {
+                        expected: Some("clip")
+                    }
+                ),
+                (0, RenderViolationKind::UnclosedPush("group")),
+            ]
+        );
+
+        let commands = vec![
+            clip.clone(),
+            clip,
+            RenderCommand::PopClip,
+            RenderCommand::PopClip,
+        ];
+        let stats = RenderStats::collect(&commands, &text);
+
+        assert!(validate_commands(&commands, &text, &assets).is_empty());
+        assert_eq!(stats.clips, 2);
+        assert_eq!(stats.max_clip_depth, 2);
+    }
+
+    /// Xorshift, so the trees are the same in every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn pick<T: Copy>(&mut self, values: &[T]) -> T {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+
+            values[(self.0 % values.len() as u64) as usize]
+        }
+
+        fn insets(&mut self) -> EdgeInsets {
+            EdgeInsets {
+                top: self.pick(&EXTREMES),
+                left: self.pick(&EXTREMES),
+                right: self.pick(&EXTREMES),
+                bottom: self.pick(&EXTREMES),
+            }
+        }
+    }
+
+    const EXTREMES: [f32; 8] = [0., 0.5, 10., 150., 1e6, 1e30, f32::MAX, f32::INFINITY];
+
+    fn build_tree(ctx: &mut BuildContext, rng: &mut Rng, ids: &mut u64, depth: u32) {
+        *ids += 1;
+        let id = *ids;
+
+        if depth == 0 {
+            match rng.pick(&[0, 1, 2]) {
+                0 => decorated_box()
+                    .id(id)
+                    .width(rng.pick(&EXTREMES))
+                    .height(rng.pick(&EXTREMES))
+                    .padding(rng.insets())
+                    .margin(rng.insets())
+                    .add_linear_gradient(LinearGradient::vertical((
+                        ColorRgba::from_hex(0xFF000000),
+                        ColorRgba::from_hex(0xFFFFFFFF),
+                    )))
+                    .build(ctx),
+                1 => text("").id(id).margin(rng.insets()).build(ctx),
+                _ => text("Text")
+                    .id(id)
+                    .padding(rng.insets())
+                    .max_width(rng.pick(&EXTREMES))
+                    .build(ctx),
+            }
+
+            return;
+        }
+
+        let seed = rng.0 ^ id;
+        let children = |ctx: &mut BuildContext| {
+            let mut rng = Rng(seed);
+
+            for _ in 0..rng.pick(&[1, 2, 3]) {
+                build_tree(ctx, &mut rng, ids, depth - 1);
+            }
+        };
+
+        match rng.pick(&[0, 1, 2]) {
+            0 => vstack()
+                .id(id)
+                .padding(rng.insets())
+                .margin(rng.insets())
+                .min_height(rng.pick(&EXTREMES))
+                .build(ctx, children),
+            1 => hstack()
+                .id(id)
+                .fill_max_size()
+                .padding(rng.insets())
+                .max_width(rng.pick(&EXTREMES))
+                .build(ctx, children),
+            _ => zstack()
+                .id(id)
+                .height(rng.pick(&EXTREMES))
+                .padding(rng.insets())
+                .margin(rng.insets())
+                .build(ctx, children),
+        }
+    }
+
+    #[test]
+    fn test_extreme_insets_and_constraints_draw_nothing_degenerate() {
+        // Only the coordinates are checked, the texts don't have to be known.
+        let (text, assets) = (TextsResources::new(), Assets::new());
+        let mut host = testing::host();
+
+        for seed in 1..=64 {
+            let mut instance =
+                ClewInstance::new(testing::view(seed % 3 * 200, 200), FontResources::new());
+
+            instance.frame(&mut host, &mut |ctx| {
+                build_tree(ctx, &mut Rng(seed as u64), &mut 0, 3);
+            });
+
+            let commands = &instance.ui_state().render_state.commands;
+
+            assert!(!commands.iter().any(RenderCommand::is_degenerate));
+            assert!(
+                validate_commands(commands, &text, &assets)
+                    .iter()
+                    .all(|violation| violation.kind != RenderViolationKind::NonFinite),
+                "seed {seed}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_decoration_border_is_drawn_once_with_its_fill() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let gradient = Gradient::Linear(LinearGradient::horizontal((
+            ColorRgba::from_hex(0xFFFF0000),
+            ColorRgba::from_hex(0xFF0000FF),
+        )));
+        let border = Border::all(BorderSide::new(2., ColorRgba::TRANSPARENT));
+
+        instance.frame(&mut host, &mut |ctx| {
+            vstack().build(ctx, |ctx| {
+                decorated_box()
+                    .border(border)
+                    .border_fill(Fill::Gradient(gradient.clone()))
+                    .width(40.)
+                    .height(20.)
+                    .build(ctx);
+
+                decorated_box()
+                    .color(ColorRgba::from_hex(0xFF000000))
+                    .add_gradient(gradient.clone())
+                    .border(border)
+                    .width(40.)
+                    .height(20.)
+                    .build(ctx);
+            });
+        });
+
+        let rects = instance
+            .ui_state()
+            .render_state
+            .commands
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::Rect {
+                    fill,
+                    border,
+                    border_fill,
+                    ..
+                } => Some((fill.is_some(), border.is_some(), border_fill.is_some())),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // The outline alone, then the color under the gradient with the border.
+        assert_eq!(
+            rects,
+            vec![
+                (false, true, true),
+                (true, false, false),
+                (true, true, false)
+            ]
+        );
+    }
+
+    /// Keeps the lines of every text it's given to draw that aren't skipped.
+    #[derive(Default)]
+    struct VisibleLinesRenderer {
+        texts: Vec<(String, Vec<usize>)>,
+    }
+
+    impl Renderer for VisibleLinesRenderer {
+        fn process_commands(
+            &mut self,
+            _: &View,
+            state: &RenderState,
+            _: ColorRgb,
+            _: &mut FontResources,
+            text: &mut TextsResources,
+            _: &Assets,
+        ) {
+            for command in state.commands() {
+                if let RenderCommand::Text {
+                    text_id,
+                    visible_band,
+                    ..
+                } = command
+                {
+                    text.get(*text_id).with_buffer(|buffer| {
+                        let content = buffer.lines[0].text().to_string();
+                        let lines = buffer
+                            .layout_runs()
+                            .filter(|run| is_run_visible(visible_band.as_ref(), run))
+                            .map(|run| run.line_i)
+                            .collect();
+
+                        self.texts.push((content, lines));
+                    });
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_text_runs_outside_of_clip_are_skipped() {
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), crate::text::test_fonts());
+        let mut renderer = VisibleLinesRenderer::default();
+
+        // Lines of 12 pixels in a clip of 30, the first text is inside of it, the second
+        // crosses its bottom edge and the last one is below it.
+        instance.frame(&mut host, &mut |ctx| {
+            zstack().height(30.).clip(Clip::Rect).build(ctx, |ctx| {
+                vstack().build(ctx, |ctx| {
+                    text("top").build(ctx);
+                    text("0\n1\n2\n3\n4\n5").build(ctx);
+                    text("below").build(ctx);
+                });
+            });
+        });
+        instance.render(&host, &mut renderer, ColorRgb::from_hex(0x000000));
+
+        assert_eq!(
+            renderer.texts,
+            vec![("top".to_string(), vec![0]), ("0".to_string(), vec![0, 1])]
+        );
+    }
+}
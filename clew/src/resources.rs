@@ -0,0 +1,138 @@
+//! Typed resources shared by all the views of an application.
+//!
+//! The application registers resources once, in `init_resources` of the shell or with
+//! [`crate::BuildContext::init_resource`], and any widget or component reads them with
+//! [`crate::BuildContext::get`], so a component that only needs the settings doesn't
+//! have to get the whole `&mut App` threaded through.
+//!
+//! Changes requested with [`crate::BuildContext::update`] are queued and applied by the
+//! shell after the view is built, never in the middle of a build, so they can't alias
+//! the `&mut App` passed to `build`. Every applied change bumps the revision of the
+//! resource, [`crate::BuildContext::revision`] can be used as a dependency to rebuild
+//! cached content when the resource changes.
+//!
+//! Resources don't replace the component events: the state owned by the application is
+//! still changed with events handled in `on_event`, resources fit values that many
+//! unrelated widgets read, like settings or a theme.
+
+use std::any::{Any, TypeId, type_name};
+
+use rustc_hash::FxHashMap;
+
+struct ResourceEntry {
+    value: Box<dyn Any>,
+    revision: u64,
+}
+
+type ResourceUpdate = Box<dyn FnOnce(&mut Resources) -> bool>;
+
+#[derive(Default)]
+pub struct Resources {
+    entries: FxHashMap<TypeId, ResourceEntry>,
+    updates: Vec<ResourceUpdate>,
+}
+
+impl Resources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers the resource, replaces the value if it's already registered.
+    pub fn insert<T: 'static>(&mut self, value: T) {
+        let revision = self.revision::<T>().map_or(0, |revision| revision + 1);
+
+        self.entries.insert(
+            TypeId::of::<T>(),
+            ResourceEntry {
+                value: Box::new(value),
+                revision,
+            },
+        );
+    }
+
+    pub fn contains<T: 'static>(&self) -> bool {
+        self.entries.contains_key(&TypeId::of::<T>())
+    }
+
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.entries
+            .get(&TypeId::of::<T>())
+            .and_then(|entry| entry.value.downcast_ref())
+    }
+
+    /// Increases every time the resource changes, `None` if it isn't registered.
+    pub fn revision<T: 'static>(&self) -> Option<u64> {
+        self.entries
+            .get(&TypeId::of::<T>())
+            .map(|entry| entry.revision)
+    }
+
+    /// Queues a change of the resource, it's applied with [`Resources::apply_updates`].
+    pub fn update<T: 'static>(&mut self, update: impl FnOnce(&mut T) + 'static) {
+        self.updates.push(Box::new(move |resources| {
+            let Some(entry) = resources.entries.get_mut(&TypeId::of::<T>()) else {
+                log::warn!(
+                    "Skipping update of unregistered resource {}",
+                    type_name::<T>()
+                );
+                return false;
+            };
+
+            if let Some(value) = entry.value.downcast_mut() {
+                update(value);
+                entry.revision += 1;
+            }
+
+            true
+        }));
+    }
+
+    /// Applies the queued changes in the order they were requested, returns `true` if
+    /// any resource has changed.
+    pub fn apply_updates(&mut self) -> bool {
+        let mut changed = false;
+
+        for update in std::mem::take(&mut self.updates) {
+            changed |= update(self);
+        }
+
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Settings {
+        font_size: f32,
+    }
+
+    #[test]
+    fn test_updates_are_deferred_until_applied() {
+        let mut resources = Resources::new();
+        resources.insert(Settings { font_size: 12. });
+
+        resources.update::<Settings>(|settings| settings.font_size += 1.);
+        resources.update::<Settings>(|settings| settings.font_size *= 2.);
+        assert_eq!(resources.get::<Settings>().unwrap().font_size, 12.);
+        assert_eq!(resources.revision::<Settings>(), Some(0));
+
+        assert!(resources.apply_updates());
+        assert_eq!(resources.get::<Settings>().unwrap().font_size, 26.);
+        assert_eq!(resources.revision::<Settings>(), Some(2));
+
+        assert!(!resources.apply_updates());
+    }
+
+    #[test]
+    fn test_update_of_unregistered_resource_is_skipped() {
+        let mut resources = Resources::new();
+
+        resources.update::<Settings>(|settings| settings.font_size = 14.);
+        assert!(!resources.apply_updates());
+        assert_eq!(resources.get::<Settings>(), None);
+        assert_eq!(resources.revision::<Settings>(), None);
+    }
+}
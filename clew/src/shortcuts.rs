@@ -38,6 +38,14 @@ fn remove_modifiers(sequence: &[KeyBinding], modifiers: KeyModifiers) -> Vec<Key
 }
 
 impl KeyBinding {
+    pub fn key(&self) -> KeyCode {
+        self.key
+    }
+
+    pub fn modifiers(&self) -> KeyModifiers {
+        self.modifiers
+    }
+
     pub fn new(key: KeyCode) -> Self {
         Self {
             modifiers: KeyModifiers::empty(),
@@ -68,6 +76,12 @@ impl KeyBinding {
 
         self
     }
+
+    pub fn with_modifiers(mut self, modifiers: KeyModifiers) -> Self {
+        self.modifiers |= modifiers;
+
+        self
+    }
 }
 
 pub const SHORTCUTS_ROOT_SCOPE_ID: ShortcutScopeId = ShortcutScopeId("root");
@@ -198,12 +212,24 @@ impl ShortcutScope {
 }
 
 pub struct ShortcutsManager {
+    /// Chords of the sequence typed so far.
     last_sequence: Vec<KeyBinding>,
-    last_found_candidate: Option<Instant>,
+    /// Chords of the sequence typed before this cycle, the ones pressed in it follow.
+    pending_len: usize,
+    /// When the last chord of an unfinished sequence was pressed.
+    pending_since: Option<Instant>,
     chord_timeout: Duration,
     candidates: u32,
+    /// Whether the shortcuts are resolved in this cycle.
+    resolving: bool,
+    /// The pending sequence has timed out, shortcuts that are its prefix were delayed
+    /// by the longer ones and fire in this cycle.
+    flushing: bool,
 
     pub(crate) current_path: SmallVec<[ShortcutScopeId; 4]>,
+    /// Path of the exclusive scope built in this cycle, only its shortcuts and the ones
+    /// of the scopes nested in it fire.
+    exclusive_path: Option<SmallVec<[ShortcutScopeId; 4]>>,
     pub(crate) active_path: SmallVec<[ShortcutScopeId; 4]>,
     pub(crate) branches: SmallVec<[SmallVec<[ShortcutScopeId; 4]>; 4]>,
     pub(crate) depth_before_pop: usize,
@@ -219,17 +245,21 @@ impl Default for ShortcutsManager {
     fn default() -> Self {
         Self {
             chord_timeout: Duration::from_secs(2),
-            last_found_candidate: Default::default(),
+            pending_since: None,
             current_path: smallvec![SHORTCUTS_ROOT_SCOPE_ID],
+            exclusive_path: None,
             active_path: SmallVec::new(),
             branches: SmallVec::new(),
             depth_before_pop: 1,
             last_sequence: Default::default(),
+            pending_len: 0,
             current_active_shortcuts: Default::default(),
             next_active_shortcuts: Default::default(),
             current_active_modifiers: Default::default(),
             next_active_modifiers: Default::default(),
             candidates: 0,
+            resolving: false,
+            flushing: false,
         }
     }
 }
@@ -257,12 +287,79 @@ impl ShortcutsManager {
         }
     }
 
+    /// Chords of a sequence that has been started but not finished yet.
+    pub fn pending_chord(&self) -> Option<&[KeyBinding]> {
+        self.pending_since.map(|_| self.last_sequence.as_slice())
+    }
+
+    /// How long an unfinished sequence waits for the next chord, 2 seconds by default.
+    pub fn chord_timeout(&self) -> Duration {
+        self.chord_timeout
+    }
+
+    pub fn set_chord_timeout(&mut self, timeout: Duration) {
+        self.chord_timeout = timeout;
+    }
+
+    /// When the pending sequence times out, the shortcuts it's a prefix of fire in the
+    /// first cycle after it.
+    pub(crate) fn chord_deadline(&self) -> Option<Instant> {
+        self.pending_since.map(|since| since + self.chord_timeout)
+    }
+
+    /// Shortcuts resolved in the last cycle, the widgets see them in the next one.
+    pub(crate) fn has_resolved_shortcuts(&self) -> bool {
+        !self.next_active_shortcuts.is_empty()
+    }
+
+    /// Whether a shortcut of the scopes being built consumes the chord, i.e. its
+    /// sequence goes on with the chord after the pending ones. The raw readers of the
+    /// keyboard leave such presses to the shortcuts. Outside of the exclusive scope the
+    /// shortcuts of that scope are the ones that consume the chords.
+    pub(crate) fn is_bound(&self, registry: &ShortcutsRegistry, binding: KeyBinding) -> bool {
+        let scopes = match &self.exclusive_path {
+            Some(exclusive_path) if !self.current_path.starts_with(exclusive_path) => {
+                exclusive_path
+            }
+            _ => &self.current_path,
+        };
+        let scopes: SmallVec<[&ShortcutScope; 4]> = scopes
+            .iter()
+            .filter_map(|scope| registry.scopes.get(scope))
+            .collect();
+
+        // The modifiers of the scopes are dropped from the chords the way they're
+        // resolved.
+        let scope_modifiers = scopes
+            .iter()
+            .flat_map(|scope| scope.modifiers.values())
+            .filter(|modifiers| binding.modifiers.contains(**modifiers))
+            .fold(KeyModifiers::empty(), |all, modifiers| all | *modifiers);
+
+        let mut chords = self.last_sequence[..self.pending_len].to_vec();
+        chords.push(binding);
+        let stripped = remove_modifiers(&chords, scope_modifiers);
+
+        scopes
+            .iter()
+            .flat_map(|scope| scope.shortcuts.values())
+            .any(|config| {
+                config.sequence.starts_with(&chords) || config.sequence.starts_with(&stripped)
+            })
+    }
+
     #[inline]
     pub(crate) fn push_scope<T: Into<ShortcutScopeId>>(&mut self, scope: T) {
         self.current_path.push(scope.into());
         self.depth_before_pop = self.current_path.len();
     }
 
+    /// Makes the scope just pushed exclusive for this cycle, the shortcuts resolved
+    /// outside of it are dropped when the cycle is finalized.
+    pub(crate) fn set_exclusive(&mut self) {
+        self.exclusive_path = Some(self.current_path.clone());
+    }
+
     #[inline]
     pub(crate) fn pop_scope(&mut self, user_input: &UserInput, registry: &ShortcutsRegistry) {
         if self.current_path.len() == self.depth_before_pop {
@@ -288,6 +385,21 @@ impl ShortcutsManager {
     ) -> Option<ShortcutId> {
         let mut shortcut_id = None;
 
+        if self.flushing {
+            let (_, resolved_shortcut_id, active_path) = Self::resolve(
+                registry,
+                user_input.modifiers,
+                &self.current_path,
+                &mut self.next_active_modifiers,
+                &self.last_sequence,
+                false,
+                true,
+            );
+
+            shortcut_id = resolved_shortcut_id;
+            self.active_path = active_path;
+        }
+
         for (modifiers, _) in user_input.key_pressed.iter() {
             let modifiers = modifiers.unwrap_or_default();
 
@@ -298,6 +410,7 @@ impl ShortcutsManager {
                 &mut self.next_active_modifiers,
                 &self.last_sequence,
                 false,
+                false,
             );
 
             shortcut_id = resolved_shortcut_id;
@@ -321,6 +434,7 @@ impl ShortcutsManager {
                             key: *key,
                         }],
                         true,
+                        true,
                     );
 
                     shortcut_id = resolved_shortcut_id;
@@ -338,46 +452,70 @@ impl ShortcutsManager {
         self.current_active_modifiers = std::mem::take(&mut self.next_active_modifiers);
 
         self.branches.clear();
+        self.exclusive_path = None;
         self.next_active_shortcuts.clear();
         self.next_active_modifiers.clear();
 
-        for (modifiers, key) in user_input.key_pressed.iter() {
-            let modifiers = modifiers.unwrap_or_default();
+        self.candidates = 0;
+        self.resolving = !user_input.key_pressed.is_empty();
+        self.flushing = false;
 
-            self.candidates = 0;
+        let timed_out = self
+            .pending_since
+            .is_some_and(|time| time.elapsed() > self.chord_timeout);
 
-            if let Some(time) = self.last_found_candidate {
-                let duration = time.elapsed();
+        if timed_out {
+            self.pending_since = None;
 
-                if duration > self.chord_timeout {
-                    self.last_sequence.clear();
-                }
-            } else {
+            // A key pressed after the timeout starts a new sequence instead.
+            if self.resolving {
                 self.last_sequence.clear();
+            } else {
+                self.flushing = true;
+                self.resolving = true;
             }
+        }
+
+        if self.pending_since.is_none() && !self.flushing {
+            self.last_sequence.clear();
+        }
 
+        self.pending_len = self.last_sequence.len();
+
+        for (modifiers, key) in user_input.key_pressed.iter() {
             if let Some(key) = key {
                 self.last_sequence.push(KeyBinding {
-                    modifiers,
+                    modifiers: modifiers.unwrap_or_default(),
                     key: *key,
                 });
             }
         }
     }
 
+    /// Keeps the sequence pending while it's a prefix of longer shortcuts, otherwise
+    /// it's done: either a shortcut has matched or the input is dropped.
     pub(crate) fn finalize_cycle(&mut self) {
-        let has_not_active_shortcut = self.next_active_shortcuts.is_empty();
+        if let Some(exclusive_path) = &self.exclusive_path {
+            self.next_active_shortcuts
+                .retain(|path, _| path.starts_with(exclusive_path));
+        }
 
-        if has_not_active_shortcut && self.candidates == 0 {
-            self.last_sequence.clear();
-            self.last_found_candidate = None;
-        } else if has_not_active_shortcut && self.candidates > 0 {
-            self.last_found_candidate = Some(Instant::now());
+        if !self.resolving {
+            return;
+        }
+
+        let has_active_shortcut = !self.next_active_shortcuts.is_empty();
+
+        if !has_active_shortcut && self.candidates > 0 && !self.flushing {
+            self.pending_since = Some(Instant::now());
         } else {
+            self.pending_since = None;
             self.last_sequence.clear();
         }
     }
 
+    /// A shortcut that is a prefix of longer ones in the same or a nested scope only
+    /// matches once the sequence is `complete`, i.e. no more chords will follow.
     pub(crate) fn resolve(
         registry: &ShortcutsRegistry,
         modifiers: KeyModifiers,
@@ -388,6 +526,7 @@ impl ShortcutsManager {
         >,
         chords: &[KeyBinding],
         repeat: bool,
+        complete: bool,
     ) -> (u32, Option<ShortcutId>, SmallVec<[ShortcutScopeId; 4]>) {
         let mut shortcut_id = None;
         let mut candidates = 0;
@@ -413,57 +552,37 @@ impl ShortcutsManager {
         // Resolve keybinding
         for scope_id in scopes.iter().rev() {
             let scope = registry.scopes.get(scope_id);
+            let mut matched = None;
 
             if let Some(scope) = scope {
-                let mut found_in_scope = false;
-
-                for (id, key_bindings) in scope.shortcuts.iter() {
-                    if repeat && repeat != key_bindings.repeat {
-                        continue;
-                    }
-
-                    // FIRST: Try exact match with all modifiers
-                    if key_bindings.sequence == chords {
-                        shortcut_id = Some(*id);
-                        found_in_scope = true;
-                        break;
-                    }
-
-                    // Check for chord candidate (exact modifiers)
-                    if key_bindings.sequence.starts_with(chords) {
-                        candidates += 1;
-                    }
-                }
+                // FIRST: Try exact match with all modifiers
+                let (exact, longer) = Self::match_sequence(scope, chords, repeat);
+                matched = exact;
+                candidates += longer;
 
                 // SECOND: If no exact match found, try with modifiers removed
-                if !found_in_scope {
+                if matched.is_none() {
                     let chords_stripped = remove_modifiers(chords, resolved_modifiers);
 
                     // Only check if we actually removed something
                     if chords_stripped != chords {
-                        for (id, key_bindings) in scope.shortcuts.iter() {
-                            if repeat && repeat != key_bindings.repeat {
-                                continue;
-                            }
-
-                            if key_bindings.sequence == chords_stripped {
-                                shortcut_id = Some(*id);
-                                break;
-                            }
-
-                            if key_bindings.sequence.starts_with(&chords_stripped) {
-                                candidates += 1;
-                            }
-                        }
+                        let (exact, longer) = Self::match_sequence(scope, &chords_stripped, repeat);
+                        matched = exact;
+                        candidates += longer;
                     }
                 }
 
-                if shortcut_id.is_none() {
+                if matched.is_none() {
                     active_path.pop();
                 }
             }
 
-            if shortcut_id.is_some() {
+            if matched.is_some() {
+                // Delayed until the longer sequences are ruled out.
+                if complete || candidates == 0 {
+                    shortcut_id = matched;
+                }
+
                 break;
             }
         }
@@ -473,11 +592,239 @@ impl ShortcutsManager {
         (candidates, shortcut_id, active_path)
     }
 
+    /// The shortcut of the scope bound to exactly `chords` and the number of the longer
+    /// sequences that start with them.
+    fn match_sequence(
+        scope: &ShortcutScope,
+        chords: &[KeyBinding],
+        repeat: bool,
+    ) -> (Option<ShortcutId>, u32) {
+        let mut matched = None;
+        let mut longer = 0;
+
+        if chords.is_empty() {
+            return (None, 0);
+        }
+
+        for (id, config) in scope.shortcuts.iter() {
+            if repeat && repeat != config.repeat {
+                continue;
+            }
+
+            if config.sequence == chords {
+                matched = Some(*id);
+            } else if config.sequence.starts_with(chords) {
+                longer += 1;
+            }
+        }
+
+        (matched, longer)
+    }
+
     pub(crate) fn reset(&mut self) {
         self.current_active_shortcuts.clear();
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EDITOR: ShortcutScopeId = ShortcutScopeId("editor");
+
+    fn ctrl(key: KeyCode) -> KeyBinding {
+        KeyBinding::new(key).with_ctrl()
+    }
+
+    fn registry() -> ShortcutsRegistry {
+        let mut registry = ShortcutsRegistry::default();
+        registry
+            .scope(EDITOR)
+            .add(ShortcutId("kill"), ctrl(KeyCode::KeyK))
+            .add(ShortcutId("save"), ctrl(KeyCode::KeyS))
+            .add_sequence(
+                ShortcutId("comment"),
+                &[ctrl(KeyCode::KeyK), ctrl(KeyCode::KeyC)],
+            );
+
+        registry
+    }
+
+    /// Runs a cycle with the editor scope and returns the shortcut it resolved.
+    fn press(
+        manager: &mut ShortcutsManager,
+        registry: &ShortcutsRegistry,
+        chord: Option<KeyBinding>,
+    ) -> Option<ShortcutId> {
+        let mut user_input = UserInput::default();
+
+        if let Some(chord) = chord {
+            user_input
+                .key_pressed
+                .push((Some(chord.modifiers()), Some(chord.key())));
+        }
+
+        manager.init_cycle(&user_input);
+        manager.push_scope(EDITOR);
+        manager.pop_scope(&user_input, registry);
+        manager.finalize_cycle();
+
+        manager.next_active_shortcuts.values().next().copied()
+    }
+
+    #[test]
+    fn test_prefix_of_sequence_waits_for_the_next_chord() {
+        let registry = registry();
+        let mut manager = ShortcutsManager::default();
+
+        assert_eq!(
+            press(&mut manager, &registry, Some(ctrl(KeyCode::KeyK))),
+            None
+        );
+        assert_eq!(manager.pending_chord(), Some(&[ctrl(KeyCode::KeyK)][..]));
+
+        // Frames without key presses keep the sequence pending.
+        assert_eq!(press(&mut manager, &registry, None), None);
+
+        assert_eq!(
+            press(&mut manager, &registry, Some(ctrl(KeyCode::KeyC))),
+            Some(ShortcutId("comment"))
+        );
+        assert_eq!(manager.pending_chord(), None);
+
+        assert_eq!(
+            press(&mut manager, &registry, Some(ctrl(KeyCode::KeyS))),
+            Some(ShortcutId("save"))
+        );
+
+        // Unmatched input drops the sequence, the chord doesn't start a new one.
+        press(&mut manager, &registry, Some(ctrl(KeyCode::KeyK)));
+        assert_eq!(
+            press(&mut manager, &registry, Some(ctrl(KeyCode::KeyX))),
+            None
+        );
+        assert_eq!(manager.pending_chord(), None);
+        assert_eq!(
+            press(&mut manager, &registry, Some(ctrl(KeyCode::KeyC))),
+            None
+        );
+    }
+
+    #[test]
+    fn test_pending_sequence_times_out_into_the_prefix_shortcut() {
+        let registry = registry();
+        let mut manager = ShortcutsManager::default();
+        manager.set_chord_timeout(Duration::ZERO);
+
+        assert_eq!(
+            press(&mut manager, &registry, Some(ctrl(KeyCode::KeyK))),
+            None
+        );
+        assert!(manager.pending_chord().is_some());
+
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert_eq!(
+            press(&mut manager, &registry, None),
+            Some(ShortcutId("kill"))
+        );
+        assert_eq!(manager.pending_chord(), None);
+
+        // A chord after the timeout starts over.
+        press(&mut manager, &registry, Some(ctrl(KeyCode::KeyK)));
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert_eq!(
+            press(&mut manager, &registry, Some(ctrl(KeyCode::KeyC))),
+            None
+        );
+        assert_eq!(manager.pending_chord(), None);
+    }
+
+    #[test]
+    fn test_only_chords_going_on_with_pending_sequence_are_bound() {
+        let registry = registry();
+        let mut manager = ShortcutsManager::default();
+        let is_bound = |manager: &mut ShortcutsManager, chord: KeyBinding| {
+            let mut user_input = UserInput::default();
+            user_input
+                .key_pressed
+                .push((Some(chord.modifiers()), Some(chord.key())));
+
+            manager.init_cycle(&user_input);
+            let outside = manager.is_bound(&registry, chord);
+            manager.push_scope(EDITOR);
+            let bound = manager.is_bound(&registry, chord);
+            manager.pop_scope(&user_input, &registry);
+            manager.finalize_cycle();
+
+            // The shortcuts of the editor don't take the keys of the other scopes.
+            assert!(!outside);
+
+            bound
+        };
+
+        // The second chord of a sequence alone isn't taken.
+        assert!(!is_bound(&mut manager, ctrl(KeyCode::KeyC)));
+        assert!(is_bound(&mut manager, ctrl(KeyCode::KeyS)));
+
+        assert!(is_bound(&mut manager, ctrl(KeyCode::KeyK)));
+        assert!(is_bound(&mut manager, ctrl(KeyCode::KeyC)));
+
+        // A chord that doesn't go on with the pending sequence drops it.
+        assert!(is_bound(&mut manager, ctrl(KeyCode::KeyK)));
+        assert!(!is_bound(&mut manager, ctrl(KeyCode::KeyS)));
+    }
+
+    #[test]
+    fn test_exclusive_scope_takes_priority() {
+        const MENU: ShortcutScopeId = ShortcutScopeId("menu");
+
+        let mut registry = registry();
+        registry
+            .scope(MENU)
+            .add(ShortcutId("activate"), KeyBinding::new(KeyCode::Enter));
+
+        let mut manager = ShortcutsManager::default();
+        let mut cycle = |chord: KeyBinding, exclusive: bool| {
+            let mut user_input = UserInput::default();
+            user_input
+                .key_pressed
+                .push((Some(chord.modifiers()), Some(chord.key())));
+
+            manager.init_cycle(&user_input);
+            manager.push_scope(EDITOR);
+            manager.pop_scope(&user_input, &registry);
+            manager.push_scope(MENU);
+
+            if exclusive {
+                manager.set_exclusive();
+            }
+
+            manager.pop_scope(&user_input, &registry);
+            manager.finalize_cycle();
+
+            manager
+                .next_active_shortcuts
+                .values()
+                .copied()
+                .collect::<FxHashSet<_>>()
+        };
+
+        assert_eq!(
+            cycle(ctrl(KeyCode::KeyS), false),
+            FxHashSet::from_iter([ShortcutId("save")])
+        );
+
+        // The editor is built outside of the open menu, its shortcuts wait.
+        assert!(cycle(ctrl(KeyCode::KeyS), true).is_empty());
+        assert_eq!(
+            cycle(KeyBinding::new(KeyCode::Enter), true),
+            FxHashSet::from_iter([ShortcutId("activate")])
+        );
+    }
+}
+
 // #[cfg(test)]
 // mod tests {
 //     use super::*;
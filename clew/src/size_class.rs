@@ -0,0 +1,77 @@
+//! Classes of the size of the view, to switch between layouts made for different
+//! window widths, e.g. a single column on a phone and two panes on a desktop.
+//!
+//! Widgets read the class with [`crate::BuildContext::size_class`], or build one of the
+//! layouts with [`crate::widgets::responsive`]. The widths the classes start at come
+//! from [`Breakpoints`] registered as a resource (see [`crate::resources`]), otherwise
+//! the defaults are used.
+
+/// Range of widths of the view a layout is made for.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SizeClass {
+    /// A phone held upright or a narrow window, one column of content.
+    #[default]
+    Compact,
+    /// A tablet or a small window.
+    Medium,
+    /// A desktop window, room for panes next to each other.
+    Expanded,
+}
+
+/// Widths in logical pixels where the larger size classes start.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Breakpoints {
+    pub medium: f32,
+    pub expanded: f32,
+}
+
+impl Default for Breakpoints {
+    fn default() -> Self {
+        Self {
+            medium: 600.,
+            expanded: 840.,
+        }
+    }
+}
+
+impl Breakpoints {
+    pub fn classify(&self, width: f32) -> SizeClass {
+        if width >= self.expanded {
+            SizeClass::Expanded
+        } else if width >= self.medium {
+            SizeClass::Medium
+        } else {
+            SizeClass::Compact
+        }
+    }
+}
+
+/// Size of the view in logical pixels with its class.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewSizeClass {
+    pub width: f32,
+    pub height: f32,
+    pub class: SizeClass,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breakpoints_start_the_classes() {
+        let breakpoints = Breakpoints::default();
+
+        assert_eq!(breakpoints.classify(0.), SizeClass::Compact);
+        assert_eq!(breakpoints.classify(599.5), SizeClass::Compact);
+        assert_eq!(breakpoints.classify(600.), SizeClass::Medium);
+        assert_eq!(breakpoints.classify(840.), SizeClass::Expanded);
+
+        let breakpoints = Breakpoints {
+            medium: 400.,
+            expanded: 400.,
+        };
+
+        assert_eq!(breakpoints.classify(400.), SizeClass::Expanded);
+    }
+}
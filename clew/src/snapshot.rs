@@ -0,0 +1,316 @@
+//! Snapshots of the retained widget state, e.g. to restore scroll offsets along with
+//! the application state on undo.
+//!
+//! Widget states opt in by implementing [`SerializableWidgetState`], the states built
+//! inside of a [`crate::scope`] are captured by [`crate::BuildContext::snapshot_ui_state`]
+//! with the same key.
+
+use smallvec::SmallVec;
+
+use crate::{WidgetId, WidgetIdMap};
+
+/// Version of the snapshot layout and of the ids in it, snapshots with another version
+/// are rejected. Since version 2 the ids are single hashes, and the ones of the widgets
+/// with explicit keys include the keys of the enclosing scopes.
+pub const UI_STATE_SNAPSHOT_VERSION: u32 = 2;
+
+/// Serializable representation of a widget state.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StateValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    String(String),
+    List(Vec<StateValue>),
+}
+
+impl StateValue {
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            StateValue::Bool(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            StateValue::Int(value) => Some(*value),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            StateValue::Float(value) => Some(*value),
+            StateValue::Int(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            StateValue::String(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn as_list(&self) -> Option<&[StateValue]> {
+        match self {
+            StateValue::List(values) => Some(values),
+            _ => None,
+        }
+    }
+}
+
+/// Widget state that can be captured into a [`UiStateSnapshot`].
+///
+/// States that hold resources that can't be serialized save a stable subset, e.g. the
+/// text editor saves the cursor and scroll position but not the content.
+pub trait SerializableWidgetState {
+    /// Stable name of the state type, used to match the saved value on restore.
+    fn state_kind(&self) -> &'static str;
+
+    /// Bump it when the saved value changes its shape, values saved with another
+    /// version are skipped on restore.
+    fn state_version(&self) -> u32 {
+        1
+    }
+
+    fn save_state(&self) -> StateValue;
+
+    /// Returns `false` if the value couldn't be applied.
+    fn restore_state(&mut self, value: &StateValue) -> bool;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateSnapshotEntry {
+    pub id: WidgetId,
+    pub kind: String,
+    pub version: u32,
+    pub value: StateValue,
+}
+
+/// Captured states of the widgets built inside of a scope.
+///
+/// Widget ids are derived from the call sites in the running binary, so a snapshot
+/// should be restored by the same process that took it.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UiStateSnapshot {
+    pub version: u32,
+    pub scope: u64,
+    pub entries: Vec<StateSnapshotEntry>,
+}
+
+impl UiStateSnapshot {
+    pub(crate) fn new(scope: u64) -> Self {
+        Self {
+            version: UI_STATE_SNAPSHOT_VERSION,
+            scope,
+            entries: Vec::new(),
+        }
+    }
+
+    pub(crate) fn push(&mut self, id: WidgetId, state: &dyn SerializableWidgetState) {
+        self.entries.push(StateSnapshotEntry {
+            id,
+            kind: state.state_kind().to_string(),
+            version: state.state_version(),
+            value: state.save_state(),
+        });
+    }
+}
+
+/// Tracks which scopes the serializable states belong to and holds the restored values
+/// until the widgets pick them up.
+#[derive(Default)]
+pub(crate) struct SnapshotsState {
+    scopes_stack: Vec<u64>,
+    scopes: WidgetIdMap<SmallVec<[u64; 2]>>,
+    pending: WidgetIdMap<StateSnapshotEntry>,
+    /// Frames left until the restored values that weren't picked up are dropped.
+    pending_frames: u8,
+}
+
+impl SnapshotsState {
+    pub(crate) fn push_scope(&mut self, scope: u64) {
+        self.scopes_stack.push(scope);
+    }
+
+    pub(crate) fn pop_scope(&mut self) {
+        self.scopes_stack.pop();
+    }
+
+    pub(crate) fn in_scope(&self, id: WidgetId, scope: u64) -> bool {
+        self.scopes
+            .get(&id)
+            .is_some_and(|scopes| scopes.contains(&scope))
+    }
+
+    /// Called by widgets with serializable states every time they're built, applies
+    /// the restored value if there is one.
+    pub(crate) fn track(&mut self, id: WidgetId, state: &mut dyn SerializableWidgetState) {
+        if self.scopes_stack.is_empty() {
+            self.scopes.remove(&id);
+        } else {
+            self.scopes
+                .insert(id, SmallVec::from_slice(&self.scopes_stack));
+        }
+
+        if let Some(entry) = self.pending.remove(&id) {
+            if entry.kind != state.state_kind() || entry.version != state.state_version() {
+                log::warn!(
+                    "Skipping restored state of {} v{} for {} v{}",
+                    entry.kind,
+                    entry.version,
+                    state.state_kind(),
+                    state.state_version(),
+                );
+            } else if !state.restore_state(&entry.value) {
+                log::warn!("Failed to restore state of {}", entry.kind);
+            }
+        }
+    }
+
+    /// Replaces the values left from the previous restore. The snapshot is restored
+    /// during a build, so the widgets built before it get their values in the next
+    /// frame, the values of widgets that aren't built by then are dropped.
+    pub(crate) fn restore(&mut self, snapshot: UiStateSnapshot) {
+        self.pending.clear();
+
+        for entry in snapshot.entries {
+            self.pending.insert(entry.id, entry);
+        }
+
+        self.pending_frames = 2;
+    }
+
+    /// Drops the scopes of the states that weren't built this frame and the restored
+    /// values nobody picked up.
+    pub(crate) fn retain(&mut self, f: impl Fn(&WidgetId) -> bool) {
+        self.scopes.retain(|id, _| f(id));
+
+        if self.pending_frames > 0 {
+            self.pending_frames -= 1;
+
+            if self.pending_frames == 0 {
+                self.pending.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Offset(f64);
+
+    impl SerializableWidgetState for Offset {
+        fn state_kind(&self) -> &'static str {
+            "offset"
+        }
+
+        fn save_state(&self) -> StateValue {
+            StateValue::Float(self.0)
+        }
+
+        fn restore_state(&mut self, value: &StateValue) -> bool {
+            match value.as_f64() {
+                Some(value) => {
+                    self.0 = value;
+                    true
+                }
+                None => false,
+            }
+        }
+    }
+
+    #[test]
+    fn test_restore_applies_matching_entries_once() {
+        let id = WidgetId::auto();
+        let mut snapshots = SnapshotsState::default();
+        let mut snapshot = UiStateSnapshot::new(0);
+        snapshot.push(id, &Offset(42.));
+
+        snapshots.restore(snapshot);
+
+        let mut state = Offset(0.);
+        snapshots.track(id, &mut state);
+        assert_eq!(state.0, 42.);
+
+        state.0 = 10.;
+        snapshots.track(id, &mut state);
+        assert_eq!(state.0, 10.);
+    }
+
+    #[test]
+    fn test_restore_skips_other_versions() {
+        let id = WidgetId::auto();
+        let mut snapshots = SnapshotsState::default();
+        let mut snapshot = UiStateSnapshot::new(0);
+        snapshot.push(id, &Offset(42.));
+        snapshot.entries[0].version = 0;
+
+        snapshots.restore(snapshot);
+
+        let mut state = Offset(0.);
+        snapshots.track(id, &mut state);
+        assert_eq!(state.0, 0.);
+    }
+
+    #[test]
+    fn test_restore_drops_entries_that_are_not_picked_up() {
+        let built = WidgetId::auto();
+        let late = WidgetId::auto();
+        let gone = WidgetId::auto();
+        let mut snapshots = SnapshotsState::default();
+        let mut snapshot = UiStateSnapshot::new(0);
+        snapshot.push(built, &Offset(1.));
+        snapshot.push(late, &Offset(2.));
+        snapshot.push(gone, &Offset(3.));
+
+        snapshots.restore(snapshot);
+
+        let mut state = Offset(0.);
+        snapshots.track(built, &mut state);
+        assert_eq!(state.0, 1.);
+        snapshots.retain(|_| true);
+
+        // Built before the restore in its frame, it's picked up in the next one.
+        let mut state = Offset(0.);
+        snapshots.track(late, &mut state);
+        assert_eq!(state.0, 2.);
+        snapshots.retain(|_| true);
+
+        assert!(snapshots.pending.is_empty());
+
+        let mut state = Offset(0.);
+        snapshots.track(gone, &mut state);
+        assert_eq!(state.0, 0.);
+    }
+
+    #[test]
+    fn test_restore_replaces_leftover_entries() {
+        let first = WidgetId::auto();
+        let second = WidgetId::auto();
+        let mut snapshots = SnapshotsState::default();
+
+        let mut snapshot = UiStateSnapshot::new(0);
+        snapshot.push(first, &Offset(1.));
+        snapshots.restore(snapshot);
+
+        let mut snapshot = UiStateSnapshot::new(0);
+        snapshot.push(second, &Offset(2.));
+        snapshots.restore(snapshot);
+
+        let mut state = Offset(0.);
+        snapshots.track(first, &mut state);
+        assert_eq!(state.0, 0.);
+
+        snapshots.track(second, &mut state);
+        assert_eq!(state.0, 2.);
+    }
+}
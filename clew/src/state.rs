@@ -1,10 +1,38 @@
-use std::{any::Any, sync::Arc};
+use std::{
+    any::Any,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
 
-use rustc_hash::{FxHashMap, FxHashSet};
+use rustc_hash::FxHashSet;
 use smallvec::SmallVec;
 
+use crate::announcement::Announcements;
+use crate::snapshot::{SerializableWidgetState, SnapshotsState, UiStateSnapshot};
+use crate::theme::SystemTheme;
 use crate::{
-    LayoutDirection, Rect, ShortcutsRegistry, View, WidgetId, WidgetRef, editable_text, interaction::InteractionState, io::UserInput, layout::{LayoutCommand, LayoutItem, LayoutMeasure, LayoutState, WidgetPlacement}, render::RenderState, shortcuts::ShortcutsManager, widgets::{decorated_box, gesture_detector, scroll_area, svg, text}
+    DebugBoundary, DecoratorPlacement, EdgeInsets, LayoutDirection, Rect, ShortcutsRegistry, Tween,
+    View, WidgetId, WidgetIdMap, WidgetIdSet, WidgetRef, WidgetType,
+    capture::ImageCapture,
+    editable_text,
+    focus::FocusChain,
+    id_map::{IdEntry, IdMap},
+    interaction::{InteractionState, WidgetInteractionState},
+    io::UserInput,
+    layout::{LayoutCommand, LayoutItem, LayoutMeasure, LayoutState, WidgetPlacement},
+    phases::FramePhases,
+    render::{RenderContext, RenderState},
+    shortcuts::ShortcutsManager,
+    text::TextId,
+    widget_id::WidgetIdInfo,
+    widgets::{
+        async_image, decorated_box, editable_text::OsEvent, file_drop_target::FileDrops,
+        gesture_detector, image, pan_zoom_area, portal::Portal, rich_text, scroll_area, silhouette,
+        text, virtual_list,
+    },
 };
 
 pub trait WidgetState: Any + Send + 'static {
@@ -13,6 +41,25 @@ pub trait WidgetState: Any + Send + 'static {
     fn as_any_mut(&mut self) -> &mut dyn Any;
 
     fn into_any(self: Box<Self>) -> Box<dyn Any>;
+
+    /// Name of the state type, e.g. in the reports of [`crate::id_map`].
+    fn type_name(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// States that can be captured into a UI state snapshot return themselves here.
+    fn as_serializable(&self) -> Option<&dyn SerializableWidgetState> {
+        None
+    }
+
+    fn as_serializable_mut(&mut self) -> Option<&mut dyn SerializableWidgetState> {
+        None
+    }
+
+    /// Draws the leaf with the id of the state and the type `WidgetType::of::<Self>()`,
+    /// e.g. the one pushed with [`crate::BuildContext::push_leaf`] for the state of
+    /// [`crate::BuildContext::state`]. Nothing is drawn by default.
+    fn render(&self, _ctx: &mut RenderContext, _placement: &WidgetPlacement) {}
 }
 
 pub struct UiState {
@@ -21,8 +68,8 @@ pub struct UiState {
     pub layout_commands: Vec<LayoutCommand>,
     pub phase_allocator: bumpalo::Bump,
     pub(crate) layout_state: LayoutState,
-    pub current_event_queue: Vec<Arc<dyn Any + Send>>,
-    pub next_event_queue: Vec<Arc<dyn Any + Send>>,
+    pub current_event_queue: Vec<Arc<dyn Any + Send + Sync>>,
+    pub next_event_queue: Vec<Arc<dyn Any + Send + Sync>>,
     pub(crate) widgets_states: WidgetsStates,
     pub(crate) widget_placements: Vec<WidgetPlacement>,
     pub(crate) layout_items: Vec<LayoutItem>,
@@ -31,14 +78,64 @@ pub struct UiState {
     pub user_input: UserInput,
     pub backgrounds: SmallVec<[WidgetRef; 8]>,
     pub foregrounds: SmallVec<[WidgetRef; 8]>,
-    pub non_interactable: FxHashSet<WidgetId>,
+    pub non_interactable: WidgetIdSet,
+    pub(crate) focus_chain: FocusChain,
+    pub(crate) portals: Vec<Portal>,
+    /// Ids of the portals from the bottom to the top.
+    pub(crate) portal_order: Vec<WidgetId>,
     pub animations_stepped_this_frame: FxHashSet<usize>,
     // TODO(sysint64): Maybe move it to build context
     pub layout_direction: LayoutDirection,
-    pub async_tx: tokio::sync::mpsc::UnboundedSender<Box<dyn Any + Send>>,
-    pub async_rx: tokio::sync::mpsc::UnboundedReceiver<Box<dyn Any + Send>>,
+    pub system_theme: SystemTheme,
+    pub async_tx: tokio::sync::mpsc::UnboundedSender<Box<dyn Any + Send + Sync>>,
+    pub async_rx: tokio::sync::mpsc::UnboundedReceiver<Box<dyn Any + Send + Sync>>,
     pub(crate) shortcuts_manager: ShortcutsManager,
     pub(crate) shortcuts_registry: ShortcutsRegistry,
+    pub modal_dialogs: ModalDialogs,
+    pub(crate) image_captures: Vec<ImageCapture>,
+    pub(crate) phases: FramePhases,
+    pub(crate) view_config: ViewConfig,
+    /// Requests to the platform, e.g. to commit the IME composition, see
+    /// [`crate::ClewInstance::take_os_events`].
+    pub(crate) os_events: SmallVec<[OsEvent; 4]>,
+    /// For the screen readers, see [`crate::announcement`].
+    pub(crate) announcements: Announcements,
+    /// Drop targets of the external files and the files dropped onto them.
+    pub(crate) file_drops: FileDrops,
+    /// Draws a square on top of everything at the position the pointer had when the
+    /// frame was built. Its distance to the cursor on the screen is the latency of the
+    /// frame, a dragged widget should stay as close to it as possible.
+    pub latency_probe: bool,
+    /// Frames built since the view was created.
+    pub(crate) frame_index: u64,
+    /// Delta times of the frames built so far added up.
+    pub(crate) time_since_start: Duration,
+}
+
+/// Counts native modal dialogs opened from a view, the shell ignores the input of the
+/// view while any of them is up but keeps rendering it.
+#[derive(Clone, Default)]
+pub struct ModalDialogs(Arc<AtomicUsize>);
+
+impl ModalDialogs {
+    pub fn is_open(&self) -> bool {
+        self.0.load(Ordering::Acquire) > 0
+    }
+
+    /// Marks a dialog as open until the returned guard is dropped.
+    pub fn begin(&self) -> ModalDialogGuard {
+        self.0.fetch_add(1, Ordering::AcqRel);
+
+        ModalDialogGuard(self.clone())
+    }
+}
+
+pub struct ModalDialogGuard(ModalDialogs);
+
+impl Drop for ModalDialogGuard {
+    fn drop(&mut self) {
+        self.0.0.fetch_sub(1, Ordering::AcqRel);
+    }
 }
 
 #[derive(Default)]
@@ -48,50 +145,157 @@ pub(crate) struct WidgetsStates {
     pub(crate) layout_measures: TypedWidgetStates<LayoutMeasure>,
 
     pub(crate) decorated_box: TypedWidgetStates<decorated_box::State>,
+    pub(crate) decorated_box_transitions: TypedWidgetStates<decorated_box::TransitionState>,
+    pub(crate) decorator_placements: TypedWidgetStates<DecoratorPlacement>,
     pub(crate) scroll_area: TypedWidgetStates<scroll_area::State>,
+    pub(crate) pan_zoom_area: TypedWidgetStates<pan_zoom_area::State>,
+    pub(crate) virtual_list: TypedWidgetStates<virtual_list::State>,
     pub(crate) text: TypedWidgetStates<text::State>,
+    pub(crate) text_updates: text::TextUpdates,
+    /// Items of the virtual lists drawn as placeholders in this frame.
+    pub(crate) placeholder_items: u32,
+    pub(crate) rich_text: TypedWidgetStates<rich_text::State>,
+    pub(crate) silhouette: TypedWidgetStates<silhouette::State>,
     pub(crate) editable_text: TypedWidgetStates<editable_text::State>,
     pub(crate) gesture_detector: TypedWidgetStates<gesture_detector::State>,
-    pub(crate) svg: TypedWidgetStates<svg::State>,
-    pub(crate) components: TypedWidgetStates<Box<dyn Any>>,
+    pub(crate) image: TypedWidgetStates<image::State>,
+    pub(crate) async_image: TypedWidgetStates<async_image::State>,
+    pub(crate) components: TypedWidgetStates<Box<dyn Any + Send>>,
     pub(crate) custom: TypedWidgetStates<Option<Box<dyn WidgetState>>>,
+    pub(crate) snapshots: SnapshotsState,
+    /// Where the ids built in the frame come from, only recorded while an id map is
+    /// captured, see [`crate::ClewInstance::id_stability_report`].
+    pub(crate) id_infos: Option<WidgetIdMap<WidgetIdInfo>>,
 }
 
 #[derive(Default)]
 pub struct ViewConfig {
+    /// Caret of the focused text field in physical pixels, the input method puts its
+    /// candidate window next to it, see [`crate::ClewInstance::ime_cursor_rect`].
     pub ime_cursor_rect: Rect,
     pub should_use_wide_space: bool,
     pub layout_direction: LayoutDirection,
     pub should_update_cursor_each_frame: bool,
+    /// Part of the view covered by the platform, e.g. by an on-screen keyboard or an IME
+    /// candidate panel, see [`crate::ClewInstance::set_obscured_insets`].
+    pub obscured_insets: EdgeInsets,
+    /// The obscured insets the layout follows, they move to the reported ones.
+    pub(crate) obscured_insets_animation: Tween<EdgeInsets>,
+    /// Overrides the reduced motion preference of the system theme, see
+    /// [`crate::ClewInstance::set_reduced_motion`].
+    pub reduced_motion: Option<bool>,
+    /// Time the build of a frame may take, see
+    /// [`crate::ClewInstance::set_frame_budget`].
+    pub frame_budget: Option<Duration>,
+}
+
+/// What a widget looks up about itself once per build and passes through it: where its
+/// state is, how the user interacts with it and the text it edits, see
+/// [`TypedWidgetStates::entry`]. The states only move in the sweep at the end of the
+/// frame, the entry isn't kept past it.
+#[derive(Clone, Copy)]
+pub(crate) struct WidgetEntry {
+    pub(crate) id: WidgetId,
+    index: u32,
+    pub(crate) interaction: WidgetInteractionState,
+    pub(crate) text_id: Option<TextId>,
 }
 
 pub struct TypedWidgetStates<T> {
-    id_to_index: FxHashMap<WidgetId, u32>,
+    id_to_index: WidgetIdMap<u32>,
     states: Vec<T>,
     ids: Vec<WidgetId>,
-    pub accessed_this_frame: FxHashSet<WidgetId>,
+    /// Whether the state at the same index has been accessed this frame.
+    accessed: Vec<bool>,
+    /// Ids accessed this frame before their states are made, e.g. the measures are
+    /// stored by the layout after the build.
+    accessed_before_insert: WidgetIdSet,
 }
 
 impl<T> Default for TypedWidgetStates<T> {
     fn default() -> Self {
         Self {
-            id_to_index: FxHashMap::default(),
+            id_to_index: WidgetIdMap::default(),
             states: Vec::new(),
             ids: Vec::new(),
-            accessed_this_frame: FxHashSet::default(),
+            accessed: Vec::new(),
+            accessed_before_insert: WidgetIdSet::default(),
         }
     }
 }
 
 impl<T> TypedWidgetStates<T> {
-    pub fn get_or_insert(&mut self, id: WidgetId, create: impl FnOnce() -> T) -> &mut T {
-        let index = *self.id_to_index.entry(id).or_insert_with(|| {
+    fn index_or_insert(&mut self, id: WidgetId, create: impl FnOnce() -> T) -> usize {
+        *self.id_to_index.entry(id).or_insert_with(|| {
             let idx = self.states.len() as u32;
             self.states.push(create());
             self.ids.push(id);
+            self.accessed.push(false);
             idx
-        });
-        &mut self.states[index as usize]
+        }) as usize
+    }
+
+    /// Fetches what the widget built this frame reads about itself in one lookup, its
+    /// state is made if it's new and marked as accessed. The state is reached again
+    /// through the entry with [`TypedWidgetStates::at`] without hashing the id.
+    pub(crate) fn entry(
+        &mut self,
+        id: WidgetId,
+        interaction: &InteractionState,
+        create: impl FnOnce() -> T,
+    ) -> WidgetEntry {
+        let index = self.index_or_insert(id, create);
+        self.accessed[index] = true;
+
+        WidgetEntry {
+            id,
+            index: index as u32,
+            interaction: interaction.widget(&id),
+            text_id: None,
+        }
+    }
+
+    /// State of the widget the entry was fetched for in this frame.
+    pub(crate) fn at(&mut self, entry: &WidgetEntry) -> &mut T {
+        debug_assert_eq!(self.ids[entry.index as usize], entry.id);
+
+        &mut self.states[entry.index as usize]
+    }
+
+    pub fn get_or_insert(&mut self, id: WidgetId, create: impl FnOnce() -> T) -> &mut T {
+        let index = self.index_or_insert(id, create);
+
+        &mut self.states[index]
+    }
+
+    /// The state of the widget built this frame, made if it's new, and marks it as
+    /// accessed in the same lookup. The widgets fetch their states with it once per
+    /// build and pass them through.
+    pub fn access(&mut self, id: WidgetId, create: impl FnOnce() -> T) -> &mut T {
+        let index = self.index_or_insert(id, create);
+        self.accessed[index] = true;
+
+        &mut self.states[index]
+    }
+
+    /// Keeps the state of the widget past the sweep of this frame, it may be made
+    /// later in the frame.
+    pub fn mark_accessed(&mut self, id: WidgetId) {
+        match self.id_to_index.get(&id) {
+            Some(&index) => self.accessed[index as usize] = true,
+            None => {
+                self.accessed_before_insert.insert(id);
+            }
+        }
+    }
+
+    pub fn is_accessed(&self, id: WidgetId) -> bool {
+        let accessed = self
+            .id_to_index
+            .get(&id)
+            .is_some_and(|&index| self.accessed[index as usize]);
+
+        accessed || self.accessed_before_insert.contains(&id)
     }
 
     pub fn get_mut(&mut self, id: WidgetId) -> Option<&mut T> {
@@ -100,6 +304,19 @@ impl<T> TypedWidgetStates<T> {
             .map(|&idx| &mut self.states[idx as usize])
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = (WidgetId, &T)> {
+        self.ids.iter().copied().zip(self.states.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (WidgetId, &mut T)> {
+        self.ids.iter().copied().zip(self.states.iter_mut())
+    }
+
+    #[inline]
+    pub fn contains(&self, id: WidgetId) -> bool {
+        self.id_to_index.contains_key(&id)
+    }
+
     pub fn get(&self, id: WidgetId) -> Option<&T> {
         self.id_to_index
             .get(&id)
@@ -107,43 +324,34 @@ impl<T> TypedWidgetStates<T> {
     }
 
     pub fn replace(&mut self, id: WidgetId, state: T) {
-        if let Some(&idx) = self.id_to_index.get(&id) {
-            self.states[idx as usize] = state;
-        } else {
-            let idx = self.states.len() as u32;
-            self.id_to_index.insert(id, idx);
-            self.states.push(state);
-            self.ids.push(id);
-        }
+        self.set(id, state);
     }
 
     pub fn set(&mut self, id: WidgetId, state: T) -> usize {
-        if let Some(&idx) = self.id_to_index.get(&id) {
-            self.states[idx as usize] = state;
+        let mut state = Some(state);
+        let index = self.index_or_insert(id, || state.take().unwrap());
 
-            idx as usize
-        } else {
-            let idx = self.states.len() as u32;
-            self.id_to_index.insert(id, idx);
-            self.states.push(state);
-            self.ids.push(id);
-
-            idx as usize
+        if let Some(state) = state {
+            self.states[index] = state;
         }
+
+        index
     }
 
     pub fn sweep(&mut self) {
         let mut i = 0;
 
         while i < self.states.len() {
-            if self.accessed_this_frame.contains(&self.ids[i]) {
+            if self.accessed[i] || self.accessed_before_insert.contains(&self.ids[i]) {
+                self.accessed[i] = false;
                 i += 1;
             } else {
-                // Swap-remove from both parallel arrays
+                // Swap-remove from the parallel arrays
                 self.id_to_index.remove(&self.ids[i]);
 
                 self.states.swap_remove(i);
                 self.ids.swap_remove(i);
+                self.accessed.swap_remove(i);
 
                 // Update the index of the element that was swapped in
                 if i < self.ids.len() {
@@ -152,14 +360,25 @@ impl<T> TypedWidgetStates<T> {
             }
         }
 
-        self.accessed_this_frame.clear();
+        self.accessed_before_insert.clear();
+    }
+
+    /// Ids of the states accessed in this frame, in the order the states were created.
+    pub(crate) fn accessed_ids(&self) -> impl Iterator<Item = (WidgetId, &T)> {
+        self.iter()
+            .enumerate()
+            .filter(|(index, (id, _))| {
+                self.accessed[*index] || self.accessed_before_insert.contains(id)
+            })
+            .map(|(_, entry)| entry)
     }
 
     pub fn clear(&mut self) {
         self.id_to_index.clear();
         self.states.clear();
         self.ids.clear();
-        self.accessed_this_frame.clear();
+        self.accessed.clear();
+        self.accessed_before_insert.clear();
     }
 }
 
@@ -172,6 +391,30 @@ impl UiState {
         &mut self.shortcuts_registry
     }
 
+    /// The reduced motion preference of the system theme unless the view overrides it.
+    pub(crate) fn reduced_motion(&self) -> bool {
+        self.view_config
+            .reduced_motion
+            .unwrap_or(self.system_theme.reduced_motion)
+    }
+
+    /// Widgets placed in the last built frame with their rects in the logical coordinates
+    /// of the view, e.g. to find where a printed page can end without cutting one of them.
+    pub fn placed_widgets(&self) -> impl Iterator<Item = (WidgetRef, Rect)> + '_ {
+        self.layout_items.iter().filter_map(|item| match item {
+            LayoutItem::Placement(placement)
+                if placement.widget_ref.widget_type != WidgetType::of::<DebugBoundary>() =>
+            {
+                let rect = placement.transform.map_or(placement.rect, |transform| {
+                    transform.apply_rect(placement.rect)
+                });
+
+                Some((placement.widget_ref, rect))
+            }
+            _ => None,
+        })
+    }
+
     pub fn new(view: View) -> Self {
         let (async_tx, async_rx) = tokio::sync::mpsc::unbounded_channel();
 
@@ -194,30 +437,141 @@ impl UiState {
             last_interaction_state: InteractionState::default(),
             user_input: UserInput::default(),
             layout_direction: LayoutDirection::LTR,
-            non_interactable: FxHashSet::default(),
+            system_theme: SystemTheme::default(),
+            non_interactable: WidgetIdSet::default(),
+            focus_chain: FocusChain::default(),
+            portals: Vec::new(),
+            portal_order: Vec::new(),
             animations_stepped_this_frame: FxHashSet::default(),
             async_tx,
             async_rx,
             shortcuts_manager: ShortcutsManager::default(),
             shortcuts_registry: ShortcutsRegistry::default(),
+            modal_dialogs: ModalDialogs::default(),
+            image_captures: Vec::new(),
+            phases: FramePhases::default(),
+            view_config: ViewConfig::default(),
+            os_events: SmallVec::new(),
+            announcements: Announcements::default(),
+            file_drops: FileDrops::default(),
+            latency_probe: false,
+            frame_index: 0,
+            time_since_start: Duration::ZERO,
         }
     }
 }
 
 impl WidgetsStates {
+    /// Whether the widget with the id has been built in this frame.
+    pub(crate) fn is_built(&self, id: WidgetId) -> bool {
+        self.gesture_detector.is_accessed(id)
+            || self.editable_text.is_accessed(id)
+            || self.custom.is_accessed(id)
+    }
+
+    /// Whether the widget with the id has been built before but not in this frame, its
+    /// state is collected in the sweep.
+    pub(crate) fn is_dropped(&self, id: WidgetId) -> bool {
+        let has_state = self.gesture_detector.contains(id)
+            || self.editable_text.contains(id)
+            || self.custom.contains(id);
+
+        has_state && !self.is_built(id)
+    }
+
+    /// Ids of the widgets with a retained state built so far in this frame.
+    pub(crate) fn id_map(&self) -> IdMap {
+        let mut map = IdMap::default();
+
+        let info = |id: WidgetId| {
+            self.id_infos
+                .as_ref()
+                .and_then(|infos| infos.get(&id).copied())
+        };
+
+        let mut typed = |widget_type: WidgetType, ids: &mut dyn Iterator<Item = WidgetId>| {
+            for id in ids {
+                map.push(IdEntry::new(id, widget_type, info(id)));
+            }
+        };
+
+        typed(
+            WidgetType::of::<text::TextWidget>(),
+            &mut self.text.accessed_ids().map(|(id, _)| id),
+        );
+        typed(
+            WidgetType::of::<rich_text::RichTextWidget>(),
+            &mut self.rich_text.accessed_ids().map(|(id, _)| id),
+        );
+        typed(
+            WidgetType::of::<editable_text::EditableTextWidget>(),
+            &mut self.editable_text.accessed_ids().map(|(id, _)| id),
+        );
+        typed(
+            WidgetType::of::<gesture_detector::GestureDetector>(),
+            &mut self.gesture_detector.accessed_ids().map(|(id, _)| id),
+        );
+        typed(
+            WidgetType::of::<scroll_area::ScrollAreaWidget>(),
+            &mut self.scroll_area.accessed_ids().map(|(id, _)| id),
+        );
+        typed(
+            WidgetType::of::<pan_zoom_area::PanZoomAreaWidget>(),
+            &mut self.pan_zoom_area.accessed_ids().map(|(id, _)| id),
+        );
+        typed(
+            WidgetType::of::<virtual_list::State>(),
+            &mut self.virtual_list.accessed_ids().map(|(id, _)| id),
+        );
+        typed(
+            WidgetType::of::<silhouette::State>(),
+            &mut self.silhouette.accessed_ids().map(|(id, _)| id),
+        );
+        typed(
+            WidgetType::of::<async_image::AsyncImageWidget>(),
+            &mut self.async_image.accessed_ids().map(|(id, _)| id),
+        );
+
+        // The states of components are taken out while they're built.
+        for (id, state) in self.custom.accessed_ids() {
+            if let Some(state) = state {
+                map.push(IdEntry::new(
+                    id,
+                    WidgetType::of_state(state.as_ref()),
+                    info(id),
+                ));
+            }
+        }
+
+        map
+    }
+
+    /// Texts held by the states of the widgets, the others are released after the sweep.
+    pub(crate) fn live_text_ids(&self) -> FxHashSet<TextId> {
+        let mut ids = FxHashSet::default();
+
+        ids.extend(self.text.iter().map(|(_, state)| state.text_id));
+        ids.extend(self.rich_text.iter().map(|(_, state)| state.text_id));
+
+        for (_, state) in self.editable_text.iter() {
+            ids.extend(state.text_id);
+
+            if let Some(markers) = &state.invisible_markers {
+                ids.extend([markers.space, markers.tab, markers.newline]);
+            }
+        }
+
+        ids
+    }
+
     #[profiling::function]
     pub fn get_or_insert_custom<T: WidgetState, F>(&mut self, id: WidgetId, create: F) -> &mut T
     where
         F: FnOnce() -> T,
     {
-        let index = *self.custom.id_to_index.entry(id).or_insert_with(|| {
-            let idx = self.custom.states.len() as u32;
-            self.custom.states.push(Some(Box::new(create())));
-            self.custom.ids.push(id);
-            idx
-        });
+        let index = self.custom.index_or_insert(id, || Some(Box::new(create())));
 
-        self.custom.states[index as usize]
+        self.custom.states[index]
             .as_mut()
             .unwrap()
             .as_any_mut()
@@ -256,18 +610,47 @@ impl WidgetsStates {
     //     self.custom.states[index as usize] = Box::new(state);
     // }
 
+    pub(crate) fn snapshot(&self, scope: u64) -> UiStateSnapshot {
+        let mut snapshot = UiStateSnapshot::new(scope);
+
+        let typed = self
+            .scroll_area
+            .iter()
+            .map(|(id, state)| (id, state as &dyn SerializableWidgetState))
+            .chain(
+                self.pan_zoom_area
+                    .iter()
+                    .map(|(id, state)| (id, state as &dyn SerializableWidgetState)),
+            )
+            .chain(
+                self.editable_text
+                    .iter()
+                    .map(|(id, state)| (id, state as &dyn SerializableWidgetState)),
+            );
+        let custom = self.custom.iter().filter_map(|(id, state)| {
+            state
+                .as_ref()
+                .and_then(|state| state.as_serializable())
+                .map(|state| (id, state))
+        });
+
+        for (id, state) in typed.chain(custom) {
+            if self.snapshots.in_scope(id, scope) {
+                snapshot.push(id, state);
+            }
+        }
+
+        snapshot
+    }
+
     pub fn take_or_create<T: WidgetState, F>(&mut self, id: WidgetId, create: F) -> (u32, Box<T>)
     where
         F: FnOnce() -> T,
     {
-        let index = *self.custom.id_to_index.entry(id).or_insert_with(|| {
-            let idx = self.custom.states.len() as u32;
-            self.custom.states.push(Some(Box::new(create())));
-            self.custom.ids.push(id);
-            idx
-        });
+        let index = self.custom.index_or_insert(id, || Some(Box::new(create())));
+        self.custom.accessed[index] = true;
 
-        let boxed = self.custom.states[index as usize]
+        let boxed = self.custom.states[index]
             .take()
             .expect("State already taken");
 
@@ -276,7 +659,7 @@ impl WidgetsStates {
             .downcast::<T>()
             .expect("Type mismatch in widget state");
 
-        (index, concrete)
+        (index as u32, concrete)
     }
 
     pub fn restore<T: WidgetState>(&mut self, index: u32, state: Box<T>) {
@@ -302,7 +685,7 @@ impl WidgetsStates {
     //     // self.data.insert(id, Box::new(state));
 
     //     // self.data.entry(id).or_insert(|| Box::new(create()));
-    //     // self.accessed_this_frame.insert(id);
+    //     // self.mark_accessed(id);
     //     // self.data.entry(id).or_insert_with(|| Box::new(create()));
 
     //     // self.data
@@ -360,18 +743,33 @@ impl WidgetsStates {
     #[profiling::function]
     pub fn sweep(&mut self) {
         self.decorated_box.clear();
-        self.svg.clear();
+        self.decorated_box_transitions.sweep();
+        self.decorator_placements.clear();
+        self.image.clear();
+        self.async_image.sweep();
         self.gesture_detector.sweep();
         self.custom.sweep();
         self.text.sweep();
+        self.rich_text.sweep();
+        self.silhouette.sweep();
         self.scroll_area.sweep();
+        self.pan_zoom_area.sweep();
+        self.virtual_list.sweep();
+        self.editable_text.sweep();
         self.layout_measures.sweep();
 
+        self.snapshots.retain(|id| {
+            self.scroll_area.contains(*id)
+                || self.pan_zoom_area.contains(*id)
+                || self.editable_text.contains(*id)
+                || self.custom.contains(*id)
+        });
+
         // self.data
         //     .retain(|id, _| self.accessed_this_frame.contains(id));
 
         // if let Some(id) = interaction.focused {
-        //     if !self.accessed_this_frame.contains(&id) {
+        //     if !self.is_accessed(id) {
         //         interaction.focused = None;
         //     }
         // }
@@ -379,3 +777,56 @@ impl WidgetsStates {
         // self.accessed_this_frame.clear();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sweep_keeps_states_accessed_this_frame() {
+        let [a, b, c, d] = [1, 2, 3, 4].map(WidgetId::auto_with_seed);
+        let mut states = TypedWidgetStates::default();
+
+        for (id, value) in [(a, 1), (b, 2), (c, 3)] {
+            states.set(id, value);
+        }
+
+        *states.access(b, || 0) += 10;
+        // Marked during the build and stored later, like the layout measures.
+        states.mark_accessed(d);
+        states.set(d, 4);
+        states.sweep();
+
+        assert_eq!(states.get(a), None);
+        assert_eq!(states.get(b), Some(&12));
+        assert_eq!(states.get(c), None);
+        assert_eq!(states.get(d), Some(&4));
+        assert!(!states.is_accessed(b));
+
+        // Nothing is accessed in the next frame.
+        states.sweep();
+        assert_eq!(states.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_entry_reaches_the_state_it_was_fetched_for() {
+        let [a, b] = [1, 2].map(WidgetId::auto_with_seed);
+        let mut interaction = InteractionState::default();
+        interaction.focus_with_keyboard(&b);
+        interaction.hover.insert(b);
+        let mut states = TypedWidgetStates::default();
+        states.set(a, 1);
+
+        let entry = states.entry(b, &interaction, || 2);
+        // States made later in the frame don't move it.
+        states.set(WidgetId::auto(), 3);
+        *states.at(&entry) += 10;
+
+        assert!(entry.interaction.is_focused && entry.interaction.is_hover);
+        assert!(!states.entry(a, &interaction, || 0).interaction.is_focused);
+        assert_eq!(states.get(b), Some(&12));
+
+        states.sweep();
+        assert_eq!(states.iter().count(), 2);
+    }
+}
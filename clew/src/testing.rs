@@ -0,0 +1,36 @@
+//! Headless host and instance the tests drive the widgets with, shared with the tests
+//! of the other crates of the workspace.
+
+use std::sync::Arc;
+
+use crate::assets::Assets;
+use crate::text::FontResources;
+use crate::widgets::builder::NoopEventLoopProxy;
+use crate::{ClewHost, ClewInstance, EdgeInsets, PhysicalSize, Resources, View, ViewId};
+
+/// Host without assets and resources.
+pub fn host<'a>() -> ClewHost<'a> {
+    ClewHost::new(
+        Assets::new(),
+        Resources::new(),
+        Arc::new(NoopEventLoopProxy),
+    )
+}
+
+/// View of the size at scale 1, without a safe area.
+pub fn view(width: u32, height: u32) -> View {
+    View {
+        id: ViewId(0),
+        size: PhysicalSize::new(width, height),
+        scale_factor: 1.,
+        safe_area: EdgeInsets::ZERO,
+    }
+}
+
+/// [`host`] and an instance of a [`view`] of the size.
+pub fn instance<'a>(width: u32, height: u32) -> (ClewHost<'a>, ClewInstance<'a>) {
+    (
+        host(),
+        ClewInstance::new(view(width, height), FontResources::new()),
+    )
+}
@@ -1,23 +1,75 @@
-use cosmic_text::Edit;
-use slotmap::{SlotMap, new_key_type};
+use std::{borrow::Cow, fmt, ops::Range, sync::Arc, time::Instant};
+
+use cosmic_text::{Edit, fontdb};
+use rustc_hash::FxHashSet;
+use slotmap::{SecondaryMap, SlotMap, new_key_type};
+use smallvec::SmallVec;
 use string_interner;
 
-use crate::{Vec2, View};
+use crate::{LayoutDirection, TextDirection, Vec2, View};
 
 new_key_type! {
-    pub struct FontId;
+    pub struct FamilyId;
     pub struct TextId;
 }
 
+#[deprecated(note = "renamed to `FamilyId`")]
+pub type FontId = FamilyId;
+
 pub type StringId = string_interner::DefaultSymbol;
 
 pub type StringInterner = string_interner::StringInterner<string_interner::DefaultBackend>;
 
 pub struct FontResources {
     pub font_system: cosmic_text::FontSystem,
-    fonts: SlotMap<FontId, &'static str>,
+    fonts: SlotMap<FamilyId, LoadedFont>,
+    system_fonts_loaded: bool,
+}
+
+/// When the fonts installed in the system are scanned, it's the slowest part of creating
+/// the font resources.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SystemFonts {
+    /// Right away, together with the explicitly loaded fonts.
+    #[default]
+    Eager,
+    /// Only the explicitly loaded fonts are used until a family that isn't among them is
+    /// requested. The first loaded family is the default sans-serif one until then.
+    Lazy,
+}
+
+#[derive(Clone)]
+struct LoadedFont {
+    name: &'static str,
+    faces: SmallVec<[fontdb::ID; 4]>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FamilyInfo {
+    pub name: String,
+    pub weights: Vec<u16>,
+    pub styles: Vec<TextStyle>,
+    pub monospaced: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FontLoadError {
+    /// The data doesn't contain any font face that can be parsed.
+    InvalidData { name: &'static str },
+}
+
+impl fmt::Display for FontLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FontLoadError::InvalidData { name } => {
+                write!(f, "Failed to parse font data for \"{name}\"")
+            }
+        }
+    }
 }
 
+impl std::error::Error for FontLoadError {}
+
 impl Default for FontResources {
     fn default() -> Self {
         Self::new()
@@ -26,18 +78,206 @@ impl Default for FontResources {
 
 impl FontResources {
     pub fn new() -> Self {
-        let font_system = cosmic_text::FontSystem::new();
+        Self::with_system_fonts(SystemFonts::Eager)
+    }
+
+    pub fn with_system_fonts(system_fonts: SystemFonts) -> Self {
+        let started = Instant::now();
+        let font_system = match system_fonts {
+            SystemFonts::Eager => cosmic_text::FontSystem::new(),
+            SystemFonts::Lazy => cosmic_text::FontSystem::new_with_locale_and_db(
+                sys_locale::get_locale().unwrap_or_else(|| String::from("en-US")),
+                fontdb::Database::new(),
+            ),
+        };
+
+        log::debug!(
+            "Created font system ({system_fonts:?} system fonts) in {:?}",
+            started.elapsed()
+        );
 
         Self {
             font_system,
             fonts: SlotMap::default(),
+            system_fonts_loaded: system_fonts == SystemFonts::Eager,
+        }
+    }
+
+    /// Creates the resources with the given fonts loaded, the fonts that fail to load
+    /// are logged and skipped.
+    pub fn with_fonts<'f>(
+        system_fonts: SystemFonts,
+        fonts: impl IntoIterator<Item = (&'static str, &'f [u8])>,
+    ) -> Self {
+        let mut resources = Self::with_system_fonts(system_fonts);
+
+        for (name, data) in fonts {
+            log::debug!("Load font: {name}");
+
+            if let Err(err) = resources.load_font(name, data) {
+                log::error!("{err}");
+            }
+        }
+
+        resources
+    }
+
+    /// Font resources for another view with the fonts of these ones, without loading
+    /// them or scanning the system fonts again. The families keep their ids, so the ids
+    /// are valid in both views.
+    pub fn fork(&self) -> Self {
+        let font_system = cosmic_text::FontSystem::new_with_locale_and_db(
+            self.font_system.locale().to_string(),
+            self.font_system.db().clone(),
+        );
+
+        Self {
+            font_system,
+            fonts: self.fonts.clone(),
+            system_fonts_loaded: self.system_fonts_loaded,
+        }
+    }
+
+    pub fn load_font(
+        &mut self,
+        name: &'static str,
+        data: &[u8],
+    ) -> Result<FamilyId, FontLoadError> {
+        let faces = self
+            .font_system
+            .db_mut()
+            .load_font_source(fontdb::Source::Binary(Arc::new(data.to_vec())));
+
+        if faces.is_empty() {
+            return Err(FontLoadError::InvalidData { name });
+        }
+
+        if !self.system_fonts_loaded && self.fonts.is_empty() {
+            let db = self.font_system.db_mut();
+
+            let family = db
+                .face(faces[0])
+                .and_then(|face| face.families.first())
+                .map(|(family, _)| family.clone());
+
+            if let Some(family) = family {
+                db.set_sans_serif_family(family);
+            }
+        }
+
+        Ok(self.fonts.insert(LoadedFont {
+            name,
+            faces: faces.into_iter().collect(),
+        }))
+    }
+
+    /// Removes faces loaded with [`FontResources::load_font`] and reshapes texts that
+    /// were using them.
+    pub fn unload_font(&mut self, id: FamilyId, texts: &mut TextsResources) -> bool {
+        let Some(font) = self.fonts.remove(id) else {
+            return false;
+        };
+
+        log::debug!("Unload font: {}", font.name);
+
+        let db = self.font_system.db_mut();
+
+        for face in &font.faces {
+            db.remove_face(*face);
+        }
+
+        texts.invalidate_faces(&font.faces, &mut self.font_system);
+
+        true
+    }
+
+    /// Scans the fonts installed in the system if it wasn't done yet.
+    pub fn load_system_fonts(&mut self) {
+        if self.system_fonts_loaded {
+            return;
+        }
+
+        let started = Instant::now();
+        let db = self.font_system.db_mut();
+        // Back to the default in place of the first loaded font, the system may set its own.
+        let default_family = fontdb::Database::new()
+            .family_name(&fontdb::Family::SansSerif)
+            .to_string();
+        db.set_sans_serif_family(default_family);
+        db.load_system_fonts();
+
+        self.system_fonts_loaded = true;
+
+        log::debug!(
+            "Loaded system fonts in {:?}, {} faces in total",
+            started.elapsed(),
+            self.font_system.db().len()
+        );
+    }
+
+    /// Makes the family available if it's installed in the system, with
+    /// [`SystemFonts::Lazy`] the system fonts are scanned the first time a family that
+    /// isn't loaded is requested.
+    pub fn request_family(&mut self, name: &str) {
+        if !self.system_fonts_loaded && !self.contains_family(name) {
+            self.load_system_fonts();
         }
     }
 
-    pub fn load_font(&mut self, name: &'static str, data: &[u8]) -> FontId {
-        self.font_system.db_mut().load_font_data(data.to_vec());
+    pub fn contains_family(&self, name: &str) -> bool {
+        self.font_system
+            .db()
+            .faces()
+            .any(|face| face.families.iter().any(|(family, _)| family == name))
+    }
+
+    /// Returns all available families sorted by name, including system fonts once they
+    /// are loaded.
+    pub fn families(&self) -> Vec<FamilyInfo> {
+        let mut families: Vec<FamilyInfo> = Vec::new();
+
+        for face in self.font_system.db().faces() {
+            let Some((name, _)) = face.families.first() else {
+                continue;
+            };
+
+            let style = match face.style {
+                fontdb::Style::Normal => TextStyle::Normal,
+                fontdb::Style::Italic | fontdb::Style::Oblique => TextStyle::Italic,
+            };
+
+            let family = match families.iter_mut().find(|it| &it.name == name) {
+                Some(family) => family,
+                None => {
+                    families.push(FamilyInfo {
+                        name: name.clone(),
+                        weights: Vec::new(),
+                        styles: Vec::new(),
+                        monospaced: true,
+                    });
+
+                    families.last_mut().unwrap()
+                }
+            };
+
+            if !family.weights.contains(&face.weight.0) {
+                family.weights.push(face.weight.0);
+            }
+
+            if !family.styles.contains(&style) {
+                family.styles.push(style);
+            }
+
+            family.monospaced &= face.monospaced;
+        }
+
+        for family in families.iter_mut() {
+            family.weights.sort_unstable();
+        }
+
+        families.sort_by(|a, b| a.name.cmp(&b.name));
 
-        self.fonts.insert(name)
+        families
     }
 }
 
@@ -47,6 +287,8 @@ pub enum Text<'buffer> {
         attrs: cosmic_text::Attrs<'buffer>,
         font_size: f32,
         line_height: f32,
+        /// Columns of the text set with [`Text::set_simple_text`].
+        simple: Option<SimpleLayout>,
     },
     Editor {
         editor: cosmic_text::Editor<'buffer>,
@@ -56,8 +298,328 @@ pub enum Text<'buffer> {
     },
 }
 
+/// Layout of a printable ASCII text in a monospace font made without the shaping, see
+/// [`Text::set_simple_text`]. Every character is a glyph of the same advance, so the
+/// glyphs are placed by their columns and the positions in the text map to the columns
+/// linearly. The buffer of the text is left empty, it only keeps the metrics, the size
+/// and the alignment the glyphs are placed with.
+#[derive(Debug, Clone)]
+pub struct SimpleLayout {
+    text: String,
+    lines: Vec<SimpleLine>,
+    glyphs: Vec<cosmic_text::LayoutGlyph>,
+    font_id: fontdb::ID,
+    /// Advance of a column, ascent and descent of the font in em.
+    advance: f32,
+    ascent: f32,
+    descent: f32,
+}
+
+#[derive(Debug, Clone)]
+struct SimpleLine {
+    /// Byte range of the line in the text, its glyphs start at the start of the range
+    /// minus the index of the line, as the line breaks have none.
+    range: Range<usize>,
+    /// Width of the glyphs and their offset from the alignment in pixels.
+    w: f32,
+    x: f32,
+}
+
+/// Visual line of a simple layout, where the buffer would lay it out.
+#[derive(Clone, Copy)]
+struct SimpleRun {
+    index: usize,
+    columns: usize,
+    advance: f32,
+    x: f32,
+    top: f32,
+    line_height: f32,
+}
+
+impl SimpleLayout {
+    /// Glyphs of the text in the font the basic shaping picks for the attributes, `None`
+    /// if the font isn't monospace.
+    fn new(
+        font_system: &mut cosmic_text::FontSystem,
+        attrs: &cosmic_text::Attrs,
+        text: &str,
+    ) -> Option<Self> {
+        let mut probe = cosmic_text::BufferLine::new(
+            "0",
+            cosmic_text::LineEnding::None,
+            cosmic_text::AttrsList::new(attrs),
+            cosmic_text::Shaping::Basic,
+        );
+        let glyph = probe
+            .shape(font_system, 8)
+            .spans
+            .first()?
+            .words
+            .first()?
+            .glyphs
+            .first()?
+            .clone();
+
+        if !font_system.db().face(glyph.font_id)?.monospaced {
+            return None;
+        }
+
+        let font = font_system.get_font(glyph.font_id)?;
+        let charmap = font.as_swash().charmap();
+        let mut lines = Vec::new();
+        let mut glyphs = Vec::with_capacity(text.len());
+        let mut start = 0;
+
+        for line in text.split('\n') {
+            glyphs.extend(
+                line.char_indices()
+                    .map(|(index, c)| cosmic_text::LayoutGlyph {
+                        start: index,
+                        end: index + 1,
+                        font_size: 0.,
+                        line_height_opt: glyph.metrics_opt.map(|metrics| metrics.line_height),
+                        font_id: glyph.font_id,
+                        glyph_id: charmap.map(c),
+                        x: 0.,
+                        y: 0.,
+                        w: 0.,
+                        level: unicode_bidi::Level::ltr(),
+                        x_offset: 0.,
+                        y_offset: 0.,
+                        color_opt: glyph.color_opt,
+                        metadata: glyph.metadata,
+                        cache_key_flags: glyph.cache_key_flags,
+                    }),
+            );
+            lines.push(SimpleLine {
+                range: start..start + line.len(),
+                w: 0.,
+                x: 0.,
+            });
+            start += line.len() + 1;
+        }
+
+        Some(Self {
+            text: text.to_string(),
+            lines,
+            glyphs,
+            font_id: glyph.font_id,
+            advance: glyph.x_advance,
+            ascent: glyph.ascent,
+            descent: glyph.descent,
+        })
+    }
+
+    /// Places the glyphs at the font size, the width and the alignment of the buffer.
+    fn place(&mut self, buffer: &cosmic_text::Buffer) {
+        let font_size = buffer.metrics().font_size;
+        let advance = self.advance * font_size;
+        let align = buffer.lines.first().and_then(|line| line.align());
+
+        for (index, line) in self.lines.iter_mut().enumerate() {
+            let glyphs = &mut self.glyphs[line.range.start - index..line.range.end - index];
+            line.w = glyphs.iter().map(|_| advance).sum();
+            line.x = match (align, buffer.size().0) {
+                (Some(cosmic_text::Align::Right | cosmic_text::Align::End), Some(width)) => {
+                    width - line.w
+                }
+                (Some(cosmic_text::Align::Center), Some(width)) => (width - line.w) / 2.,
+                _ => 0.,
+            };
+
+            let mut x = line.x;
+
+            for glyph in glyphs {
+                glyph.font_size = font_size;
+                glyph.x = x;
+                glyph.w = advance;
+                x += advance;
+            }
+        }
+    }
+
+    /// Whether none of the lines is wrapped at the width of the buffer.
+    fn fits(&self, buffer: &cosmic_text::Buffer) -> bool {
+        let advance = self.advance * buffer.metrics().font_size;
+
+        buffer.size().0.is_none_or(|width| {
+            self.lines
+                .iter()
+                .all(|line| line.range.len() as f32 * advance <= width)
+        })
+    }
+
+    /// Visual lines from the scroll of the buffer down to its height, the same as the
+    /// buffer lays them out.
+    fn runs<'l>(&'l self, buffer: &'l cosmic_text::Buffer) -> SimpleRuns<'l> {
+        SimpleRuns {
+            layout: self,
+            metrics: buffer.metrics(),
+            scroll: buffer.scroll(),
+            height: buffer.size().1,
+            index: buffer.scroll().line,
+        }
+    }
+}
+
+struct SimpleRuns<'a> {
+    layout: &'a SimpleLayout,
+    metrics: cosmic_text::Metrics,
+    scroll: cosmic_text::Scroll,
+    height: Option<f32>,
+    index: usize,
+}
+
+impl<'a> Iterator for SimpleRuns<'a> {
+    type Item = (SimpleRun, cosmic_text::LayoutRun<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let layout = self.layout;
+        let font_size = self.metrics.font_size;
+        let line_height = self.metrics.line_height;
+
+        loop {
+            let index = self.index;
+            let line = layout.lines.get(index)?;
+            self.index += 1;
+
+            let top = (index - self.scroll.line) as f32 * line_height - self.scroll.vertical;
+            let glyphs = &layout.glyphs[line.range.start - index..line.range.end - index];
+
+            // Empty lines have no glyphs to take the ascent from.
+            let (ascent, descent) = if glyphs.is_empty() {
+                (0., 0.)
+            } else {
+                (layout.ascent * font_size, layout.descent * font_size)
+            };
+            let line_y = top + (line_height - ascent - descent) / 2. + ascent;
+
+            if self.height.is_some_and(|height| line_y > height) {
+                self.index = layout.lines.len();
+
+                return None;
+            }
+
+            if line_y < 0. {
+                continue;
+            }
+
+            let run = SimpleRun {
+                index,
+                columns: glyphs.len(),
+                advance: layout.advance * font_size,
+                x: line.x,
+                top,
+                line_height,
+            };
+            let layout_run = cosmic_text::LayoutRun {
+                line_i: index,
+                text: &layout.text[line.range.clone()],
+                rtl: false,
+                glyphs,
+                line_y,
+                line_top: top,
+                line_height,
+                line_w: line.w,
+            };
+
+            return Some((run, layout_run));
+        }
+    }
+}
+
+impl SimpleRun {
+    fn x(&self, column: usize) -> f32 {
+        self.x + column.min(self.columns) as f32 * self.advance
+    }
+
+    /// Columns between the cursors, the same as cosmic-text highlights on a laid out
+    /// line: the edges of the glyphs between them, an edge is the cursor after the
+    /// glyph on its left and the cursor before the one on its right.
+    fn highlight(
+        &self,
+        start: cosmic_text::Cursor,
+        end: cosmic_text::Cursor,
+    ) -> Option<(usize, usize)> {
+        if self.columns == 0 || self.index < start.line || self.index > end.line {
+            return None;
+        }
+
+        let from = if self.index > start.line {
+            0
+        } else if start.index < self.columns
+            || (start.index == self.columns && start.affinity == cosmic_text::Affinity::Before)
+        {
+            start.index
+        } else {
+            return None;
+        };
+
+        let to = if self.index < end.line {
+            self.columns
+        } else if end.index == 0 && end.affinity == cosmic_text::Affinity::Before {
+            return None;
+        } else {
+            end.index.min(self.columns)
+        };
+
+        (from <= to).then_some((from, to))
+    }
+}
+
+/// Visual lines of a [`Text`], see [`Text::layout_runs`].
+pub struct LayoutRuns<'a>(LayoutRunsSource<'a>);
+
+enum LayoutRunsSource<'a> {
+    Buffer(cosmic_text::LayoutRunIter<'a>),
+    Simple(SimpleRuns<'a>),
+}
+
+impl<'a> Iterator for LayoutRuns<'a> {
+    type Item = cosmic_text::LayoutRun<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match &mut self.0 {
+            LayoutRunsSource::Buffer(runs) => runs.next(),
+            LayoutRunsSource::Simple(runs) => runs.next().map(|(_, run)| run),
+        }
+    }
+}
+
+/// Number and estimated size of the texts of a view, see [`TextsResources::stats`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct TextsStats {
+    pub buffers: usize,
+    /// Buffers that are editors.
+    pub editors: usize,
+    /// Rough number of bytes taken by the buffers with their lines and shaped glyphs.
+    pub estimated_bytes: usize,
+}
+
 pub struct TextsResources<'a> {
     items: SlotMap<TextId, Text<'a>>,
+    static_texts: SecondaryMap<TextId, ()>,
+    /// Buffer the lines are shaped in by [`TextsResources::measure`].
+    measured: Option<Text<'a>>,
+}
+
+/// Font a line is measured in with [`crate::BuildContext::measure_text`], the default
+/// is the one [`crate::text`] is drawn in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MeasureTextStyle {
+    pub font_size: f32,
+    pub font_family: Option<&'static str>,
+    pub tabular_numbers: bool,
+}
+
+impl Default for MeasureTextStyle {
+    fn default() -> Self {
+        Self {
+            font_size: 12.,
+            font_family: None,
+            tabular_numbers: false,
+        }
+    }
 }
 
 impl<'a> Default for TextsResources<'a> {
@@ -70,20 +632,61 @@ impl<'a> TextsResources<'a> {
     pub fn new() -> Self {
         Self {
             items: SlotMap::default(),
+            static_texts: SecondaryMap::default(),
+            measured: None,
         }
     }
 
-    pub fn editor(&self, id: TextId) -> &cosmic_text::Editor<'a> {
-        match self.items.get(id).unwrap() {
-            Text::Buffer { .. } => panic!("Provided text id is not editor"),
-            Text::Editor { editor, .. } => editor,
+    /// Size in physical pixels a line of the text takes in the style. All the lines are
+    /// shaped in the same buffer, it's kept between the calls.
+    pub fn measure(
+        &mut self,
+        view: &View,
+        font_resources: &mut FontResources,
+        text: &str,
+        style: MeasureTextStyle,
+    ) -> Vec2 {
+        let measured = self.measured.get_or_insert_with(|| {
+            Text::new(view, font_resources, style.font_size, style.font_size)
+        });
+
+        measured.set_metrics(view, font_resources, style.font_size, style.font_size);
+        measured.set_family(match style.font_family {
+            Some(name) => cosmic_text::Family::Name(name),
+            None => cosmic_text::Family::SansSerif,
+        });
+        measured.set_font_features(if style.tabular_numbers {
+            &[("tnum", 1)]
+        } else {
+            &[]
+        });
+        measured.set_text(font_resources, text);
+        measured.with_buffer_mut(|buffer| {
+            buffer.shape_until_scroll(&mut font_resources.font_system, false);
+        });
+
+        measured.layout()
+    }
+
+    /// Returns `None` if the text with the given id is a plain buffer.
+    pub fn editor(&self, id: TextId) -> Option<&cosmic_text::Editor<'a>> {
+        match self.items.get(id)? {
+            Text::Buffer { .. } => {
+                debug_assert!(false, "Provided text id is not editor");
+                None
+            }
+            Text::Editor { editor, .. } => Some(editor),
         }
     }
 
-    pub fn editor_mut(&mut self, id: TextId) -> &mut cosmic_text::Editor<'a> {
-        match self.items.get_mut(id).unwrap() {
-            Text::Buffer { .. } => panic!("Provided text id is not editor"),
-            Text::Editor { editor, .. } => editor,
+    /// Returns `None` if the text with the given id is a plain buffer.
+    pub fn editor_mut(&mut self, id: TextId) -> Option<&mut cosmic_text::Editor<'a>> {
+        match self.items.get_mut(id)? {
+            Text::Buffer { .. } => {
+                debug_assert!(false, "Provided text id is not editor");
+                None
+            }
+            Text::Editor { editor, .. } => Some(editor),
         }
     }
 
@@ -111,8 +714,66 @@ impl<'a> TextsResources<'a> {
         self.items.get_mut(id)
     }
 
+    /// Whether the text hasn't been removed.
+    pub fn contains(&self, id: TextId) -> bool {
+        self.items.contains_key(id)
+    }
+
     pub fn clear(&mut self) {
         self.items.clear();
+        self.static_texts.clear();
+    }
+
+    /// Marks the text content as rarely changing, so lines outside of the visible part of
+    /// a clipped container can be left unshaped.
+    pub fn set_static(&mut self, id: TextId, is_static: bool) {
+        if is_static {
+            self.static_texts.insert(id, ());
+        } else {
+            self.static_texts.remove(id);
+        }
+    }
+
+    pub fn is_static(&self, id: TextId) -> bool {
+        self.static_texts.contains_key(id)
+    }
+
+    pub(crate) fn invalidate_faces(
+        &mut self,
+        faces: &[fontdb::ID],
+        font_system: &mut cosmic_text::FontSystem,
+    ) {
+        for text in self.items.values_mut() {
+            // The simple text is laid out again, the basic shaping may pick another font.
+            if let Text::Buffer {
+                simple: Some(simple),
+                ..
+            } = text
+            {
+                if faces.contains(&simple.font_id) {
+                    let content = std::mem::take(&mut simple.text);
+                    text.set_simple_text_in(font_system, &content);
+                }
+
+                continue;
+            }
+
+            text.with_buffer_mut(|buffer| {
+                let uses_faces = buffer.layout_runs().any(|run| {
+                    run.glyphs
+                        .iter()
+                        .any(|glyph| faces.contains(&glyph.font_id))
+                });
+
+                if uses_faces {
+                    for line in buffer.lines.iter_mut() {
+                        line.reset_shaping();
+                    }
+
+                    buffer.shape_until_scroll(font_system, false);
+                }
+            });
+        }
     }
 
     pub fn add_text<F>(
@@ -166,9 +827,43 @@ impl<'a> TextsResources<'a> {
 
     pub fn remove(&mut self, id: TextId) {
         self.items.remove(id);
+        self.static_texts.remove(id);
+    }
+
+    /// Drops the texts that aren't in the live ids. The instance calls it after each
+    /// frame with the texts of the widgets that still have a state, so the texts of
+    /// the widgets that are gone don't pile up.
+    pub fn retain_only(&mut self, live_ids: &FxHashSet<TextId>) {
+        self.items.retain(|id, _| live_ids.contains(&id));
+        self.static_texts.retain(|id, _| live_ids.contains(&id));
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Walks all the buffers, meant for debug overlays and leak hunting.
+    pub fn stats(&self) -> TextsStats {
+        let mut stats = TextsStats::default();
+
+        for text in self.items.values() {
+            stats.buffers += 1;
+            stats.estimated_bytes += text.estimated_bytes();
+
+            if let Text::Editor { .. } = text {
+                stats.editors += 1;
+            }
+        }
+
+        stats
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum TextStyle {
     Normal,
     Italic,
@@ -208,6 +903,7 @@ impl<'buffer> Text<'buffer> {
             attrs,
             font_size,
             line_height,
+            simple: None,
         }
     }
 
@@ -247,11 +943,12 @@ impl<'buffer> Text<'buffer> {
             buffer.set_metrics(
                 &mut font_resources.font_system,
                 cosmic_text::Metrics::new(
-                    font_size * view.scale_factor,
-                    line_height,
-                ),
+                font_size * view.scale_factor,
+                line_height,
+            ),
             );
         });
+        self.place_simple();
     }
 
     pub fn update_view(&mut self, view: &View, font_resources: &mut FontResources) {
@@ -262,11 +959,120 @@ impl<'buffer> Text<'buffer> {
             buffer.set_metrics(
                 &mut font_resources.font_system,
                 cosmic_text::Metrics::new(
-                    font_size * view.scale_factor,
-                    line_height,
-                ),
+                font_size * view.scale_factor,
+                line_height,
+            ),
             );
         });
+        self.place_simple();
+    }
+
+    pub fn set_family(&mut self, family: cosmic_text::Family<'buffer>) {
+        match self {
+            Text::Buffer { attrs, .. } => *attrs = attrs.clone().family(family),
+            Text::Editor { attrs, .. } => *attrs = attrs.clone().family(family),
+        }
+    }
+
+    /// Sets the OpenType features, e.g. `("tnum", 1)` or `("liga", 0)`, the tags
+    /// that aren't 4 bytes long are skipped.
+    pub fn set_font_features(&mut self, features: &[(&str, u32)]) {
+        let mut font_features = cosmic_text::FontFeatures::new();
+
+        for (tag, value) in features {
+            match <&[u8; 4]>::try_from(tag.as_bytes()) {
+                Ok(tag) => {
+                    font_features.set(cosmic_text::FeatureTag::new(tag), *value);
+                }
+                Err(_) => log::warn!("Skipping font feature {tag:?}, tags are 4 bytes long"),
+            }
+        }
+
+        self.with_attrs_mut(|attrs| {
+            *attrs = attrs.clone().font_features(font_features);
+        });
+    }
+
+    /// Spaces out the digits of the fonts without the `tnum` feature to the advance of
+    /// the widest digit of their font, so numbers of the same length take the same
+    /// width. The spacing is kept until the text is set again, returns whether a digit
+    /// was padded.
+    pub fn pad_numbers(&mut self, font_resources: &mut FontResources) -> bool {
+        let mut cached: SmallVec<[(fontdb::ID, Option<[f32; 10]>); 2]> = SmallVec::new();
+
+        self.pad_digits(&mut font_resources.font_system, |font_system, font_id| {
+            if let Some((_, advances)) = cached.iter().find(|(id, _)| *id == font_id) {
+                return *advances;
+            }
+
+            let advances = digit_advances(font_system, font_id);
+            cached.push((font_id, advances));
+
+            advances
+        })
+    }
+
+    fn pad_digits<F>(&mut self, font_system: &mut cosmic_text::FontSystem, mut advances: F) -> bool
+    where
+        F: FnMut(&mut cosmic_text::FontSystem, fontdb::ID) -> Option<[f32; 10]>,
+    {
+        // Simple layouts are monospace, their digits are of the same width already.
+        if let Text::Buffer {
+            simple: Some(_), ..
+        } = self
+        {
+            return false;
+        }
+
+        let mut padded = false;
+
+        self.with_buffer_mut(|buffer| {
+            let tab_width = buffer.tab_width();
+
+            for line in &mut buffer.lines {
+                let digits: SmallVec<[(Range<usize>, fontdb::ID); 8]> = line
+                    .shape(font_system, tab_width)
+                    .spans
+                    .iter()
+                    .flat_map(|span| &span.words)
+                    .flat_map(|word| &word.glyphs)
+                    .filter(|glyph| glyph.end == glyph.start + 1)
+                    .map(|glyph| (glyph.start..glyph.end, glyph.font_id))
+                    .collect();
+
+                let mut attrs_list = line.attrs_list().clone();
+
+                for (range, font_id) in digits {
+                    let digit = line.text().as_bytes()[range.start];
+
+                    if !digit.is_ascii_digit() {
+                        continue;
+                    }
+
+                    let Some(advances) = advances(font_system, font_id) else {
+                        continue;
+                    };
+                    let widest = advances.iter().copied().fold(0., f32::max);
+                    let spacing = widest - advances[(digit - b'0') as usize];
+
+                    if spacing > f32::EPSILON {
+                        let attrs = cosmic_text::AttrsOwned::new(
+                            &attrs_list.get_span(range.start).letter_spacing(spacing),
+                        );
+                        attrs_list.add_span(range, &attrs.as_attrs());
+                    }
+                }
+
+                padded |= line.set_attrs_list(attrs_list);
+            }
+
+            if padded {
+                buffer.set_redraw(true);
+                buffer.shape_until_scroll(font_system, false);
+            }
+        });
+
+        padded
     }
 
     pub fn set_style(&mut self, style: TextStyle) {
@@ -298,16 +1104,42 @@ impl<'buffer> Text<'buffer> {
         let mut max_width = 0.;
         let mut height = 0.;
 
-        self.with_buffer(|buffer| {
-            for layout in buffer.layout_runs() {
-                max_width = f32::max(max_width, layout.line_w);
-                height = layout.line_y;
-            }
-        });
+        for layout in self.layout_runs() {
+            max_width = f32::max(max_width, layout.line_w);
+            height = layout.line_y;
+        }
 
         Vec2::new(max_width, height)
     }
 
+    /// Visual lines of the text to draw, the laid out lines of the buffer or the lines
+    /// of the text set with [`Text::set_simple_text`].
+    pub fn layout_runs(&self) -> LayoutRuns<'_> {
+        match self {
+            Text::Buffer {
+                buffer,
+                simple: Some(simple),
+                ..
+            } => LayoutRuns(LayoutRunsSource::Simple(simple.runs(buffer))),
+            text => LayoutRuns(LayoutRunsSource::Buffer(text.buffer().layout_runs())),
+        }
+    }
+
+    /// Height of the visual lines clamped to `min_lines..=max_lines` lines.
+    pub fn lines_height(&self, min_lines: u32, max_lines: u32) -> f32 {
+        let lines = self.layout_runs().count() as u32;
+        let line_height = self.buffer().metrics().line_height;
+
+        lines.clamp(min_lines, max_lines.max(min_lines)) as f32 * line_height
+    }
+
+    /// Tabs advance to the next multiple of `tab_width` spaces.
+    pub fn set_tab_width(&mut self, font_resources: &mut FontResources, tab_width: u16) {
+        self.with_buffer_mut(|buffer| {
+            buffer.set_tab_width(&mut font_resources.font_system, tab_width);
+        });
+    }
+
     pub fn set_text(&mut self, font_resources: &mut FontResources, text: &str) {
         self.with_buffer_and_attrs_mut(|buffer, attrs| {
             buffer.set_text(
@@ -319,12 +1151,251 @@ impl<'buffer> Text<'buffer> {
         });
     }
 
+    /// Sets the text without the shaping when it's printable ASCII in a monospace font,
+    /// e.g. for the lines of a large log. Every character is then one glyph of the
+    /// font's advance: the text is laid out as its columns times the advance, and
+    /// [`Text::hit`] and [`Text::highlight_rects`] map x to the columns linearly. Any
+    /// other text, or a font that isn't monospace, falls back to the full shaping, so
+    /// does the text once its lines are wrapped, see [`Text::set_size`]. Returns whether
+    /// the text is laid out by its columns.
+    pub fn set_simple_text(&mut self, font_resources: &mut FontResources, text: &str) -> bool {
+        self.set_simple_text_in(&mut font_resources.font_system, text)
+    }
+
+    fn set_simple_text_in(
+        &mut self,
+        font_system: &mut cosmic_text::FontSystem,
+        text: &str,
+    ) -> bool {
+        // Tabs, combining marks and the direction marks are all outside of it, an empty
+        // last line is left out of the buffer.
+        let simple = !text.is_empty()
+            && !text.ends_with('\n')
+            && text.bytes().all(|it| matches!(it, b' '..=b'~' | b'\n'));
+
+        let layout = match self {
+            Text::Buffer { attrs, .. } if simple => SimpleLayout::new(font_system, attrs, text),
+            _ => None,
+        };
+        let simple = layout.is_some();
+
+        // The buffer only keeps the metrics, the size and the alignment then.
+        self.with_buffer_and_attrs_mut(|buffer, attrs| {
+            buffer.set_text(
+                font_system,
+                if simple { "" } else { text },
+                attrs,
+                cosmic_text::Shaping::Advanced,
+            );
+        });
+
+        if let Text::Buffer { simple, .. } = self {
+            *simple = layout;
+        }
+
+        self.place_simple();
+
+        if !self.simple_fits() {
+            self.shape_simple(font_system);
+        }
+
+        simple && self.is_simple()
+    }
+
+    /// Whether the text is laid out by its columns, see [`Text::set_simple_text`].
+    pub fn is_simple(&self) -> bool {
+        matches!(
+            self,
+            Text::Buffer {
+                simple: Some(_),
+                ..
+            }
+        )
+    }
+
+    /// Sets the width the lines are wrapped at and the height the lines below are left
+    /// out at. The text set with [`Text::set_simple_text`] is shaped once its lines are
+    /// wrapped.
+    pub fn set_size(
+        &mut self,
+        font_system: &mut cosmic_text::FontSystem,
+        width: Option<f32>,
+        height: Option<f32>,
+    ) {
+        if self.buffer().size() == (width, height) {
+            return;
+        }
+
+        self.with_buffer_mut(|buffer| {
+            buffer.set_size(font_system, width, height);
+        });
+
+        if self.simple_fits() {
+            self.place_simple();
+        } else {
+            self.shape_simple(font_system);
+        }
+    }
+
+    /// Aligns every line of the text within the width of the buffer.
+    pub fn set_align(&mut self, align: Option<cosmic_text::Align>) {
+        self.with_buffer_mut(|buffer| {
+            for line in buffer.lines.iter_mut() {
+                line.set_align(align);
+            }
+        });
+        self.place_simple();
+    }
+
+    /// Cursor closest to the point relative to the origin of the text, the lines above
+    /// and below the text hit its first and last line.
+    pub fn hit(&self, x: f32, y: f32) -> Option<cosmic_text::Cursor> {
+        let Text::Buffer {
+            buffer,
+            simple: Some(simple),
+            ..
+        } = self
+        else {
+            return crate::widgets::editable_text::cursor::hit(self.buffer(), x, y);
+        };
+
+        let mut runs = simple.runs(buffer).map(|(run, _)| run);
+        let mut hit = runs.next()?;
+
+        if y >= hit.top + hit.line_height {
+            for run in runs {
+                hit = run;
+
+                if y < run.top + run.line_height {
+                    break;
+                }
+            }
+        }
+
+        // The closer caret of the two around x, the left one at the half.
+        let column = ((x - hit.x) / hit.advance - 0.5).ceil().max(0.) as usize;
+        let column = column.min(hit.columns);
+        let affinity = if column == hit.columns && column != 0 {
+            cosmic_text::Affinity::Before
+        } else {
+            cosmic_text::Affinity::After
+        };
+
+        Some(cosmic_text::Cursor::new_with_affinity(
+            hit.index, column, affinity,
+        ))
+    }
+
+    /// Rects covering the text between the cursors relative to the origin of the text,
+    /// one per visual line, e.g. to draw a selection.
+    pub fn highlight_rects(
+        &self,
+        start: cosmic_text::Cursor,
+        end: cosmic_text::Cursor,
+    ) -> Vec<crate::Rect> {
+        let (start, end) = if end < start {
+            (end, start)
+        } else {
+            (start, end)
+        };
+
+        let Text::Buffer {
+            buffer,
+            simple: Some(simple),
+            ..
+        } = self
+        else {
+            return crate::widgets::editable_text::cursor::highlight_rects(
+                self.buffer(),
+                start,
+                end,
+            );
+        };
+
+        simple
+            .runs(buffer)
+            .filter_map(|(run, _)| match run.highlight(start, end) {
+                Some((from, to)) => Some(crate::Rect::new(
+                    run.x(from),
+                    run.top,
+                    run.x(to) - run.x(from),
+                    run.line_height,
+                )),
+                None if run.columns == 0 && run.index >= start.line && run.index < end.line => {
+                    Some(crate::Rect::new(
+                        0.,
+                        run.top,
+                        run.line_height / 4.,
+                        run.line_height,
+                    ))
+                }
+                None => None,
+            })
+            .collect()
+    }
+
+    fn place_simple(&mut self) {
+        if let Text::Buffer {
+            buffer,
+            simple: Some(simple),
+            ..
+        } = self
+        {
+            simple.place(buffer);
+        }
+    }
+
+    fn simple_fits(&self) -> bool {
+        match self {
+            Text::Buffer {
+                buffer,
+                simple: Some(simple),
+                ..
+            } => simple.fits(buffer),
+            _ => true,
+        }
+    }
+
+    /// Moves the text set with [`Text::set_simple_text`] to the buffer, shaped the basic
+    /// way, so it's wrapped.
+    fn shape_simple(&mut self, font_system: &mut cosmic_text::FontSystem) {
+        let Text::Buffer {
+            buffer,
+            attrs,
+            simple: simple @ Some(_),
+            ..
+        } = self
+        else {
+            return;
+        };
+
+        let text = simple.take().map(|it| it.text).unwrap_or_default();
+        let align = buffer.lines.first().and_then(|line| line.align());
+
+        buffer.set_text(font_system, &text, attrs, cosmic_text::Shaping::Basic);
+
+        for line in buffer.lines.iter_mut() {
+            line.set_align(align);
+        }
+
+        buffer.shape_until_scroll(font_system, false);
+    }
+
     pub fn with_buffer_and_attrs_mut<F>(&mut self, callback: F)
     where
         F: FnOnce(&mut cosmic_text::Buffer, &mut cosmic_text::Attrs),
     {
         match self {
-            Text::Buffer { buffer, attrs, .. } => callback(buffer, attrs),
+            Text::Buffer {
+                buffer,
+                attrs,
+                simple,
+                ..
+            } => {
+                // The content of the buffer is replaced.
+                *simple = None;
+                callback(buffer, attrs)
+            }
             Text::Editor { editor, attrs, .. } => callback(
                 match editor.buffer_ref_mut() {
                     cosmic_text::BufferRef::Owned(buffer) => buffer,
@@ -375,6 +1446,46 @@ impl<'buffer> Text<'buffer> {
         }
     }
 
+    fn estimated_bytes(&self) -> usize {
+        let mut bytes = size_of::<Self>();
+
+        self.with_buffer(|buffer| {
+            for line in &buffer.lines {
+                bytes += size_of::<cosmic_text::BufferLine>() + line.text().len();
+
+                if let Some(shape) = line.shape_opt() {
+                    let glyphs: usize = shape
+                        .spans
+                        .iter()
+                        .flat_map(|span| &span.words)
+                        .map(|word| word.glyphs.len())
+                        .sum();
+
+                    bytes += glyphs * size_of::<cosmic_text::ShapeGlyph>();
+                }
+
+                if let Some(layout) = line.layout_opt() {
+                    for layout_line in layout {
+                        bytes += size_of::<cosmic_text::LayoutLine>()
+                            + layout_line.glyphs.len() * size_of::<cosmic_text::LayoutGlyph>();
+                    }
+                }
+            }
+        });
+
+        if let Text::Buffer {
+            simple: Some(simple),
+            ..
+        } = self
+        {
+            bytes += simple.text.len()
+                + simple.lines.len() * size_of::<SimpleLine>()
+                + simple.glyphs.len() * size_of::<cosmic_text::LayoutGlyph>();
+        }
+
+        bytes
+    }
+
     fn font_size(&self) -> f32 {
         match self {
             Text::Buffer { font_size, .. } => *font_size,
@@ -389,3 +1500,493 @@ impl<'buffer> Text<'buffer> {
         }
     }
 }
+
+/// Whether the glyph of the cluster draws anything. Tabs, control and zero-width
+/// characters only take space, if any, so renderers skip them rather than drawing the
+/// replacement box of a font without the glyph. They stay in the text and the cursor
+/// moves over them like over any other character. So do the object replacement
+/// characters the inline boxes of [`crate::widgets::rich_text`] are shaped as.
+pub fn is_visible_cluster(cluster: &str) -> bool {
+    cluster.chars().any(|c| !is_invisible_char(c))
+}
+
+fn is_invisible_char(c: char) -> bool {
+    c.is_control()
+        || matches!(
+            c,
+            '\u{200B}'..='\u{200F}'
+                | '\u{2028}'..='\u{202E}'
+                | '\u{2060}'..='\u{206F}'
+                | '\u{FEFF}'
+                | '\u{FFFC}'
+        )
+}
+
+/// Direction of the first strong directional character of the paragraph, skipping
+/// isolates (UAX #9 P2 and P3), `None` when there is no such character.
+pub(crate) fn paragraph_direction(paragraph: &str) -> Option<LayoutDirection> {
+    match unicode_bidi::get_base_direction(paragraph) {
+        unicode_bidi::Direction::Ltr => Some(LayoutDirection::LTR),
+        unicode_bidi::Direction::Rtl => Some(LayoutDirection::RTL),
+        unicode_bidi::Direction::Mixed => None,
+    }
+}
+
+/// Text with every paragraph laid out in its resolved direction. cosmic-text detects
+/// the direction of each line itself and falls back to LTR, so the paragraphs it would
+/// get wrong start with an invisible LRM or RLM mark.
+pub(crate) fn with_text_direction(
+    text: &str,
+    direction: TextDirection,
+    layout_direction: LayoutDirection,
+) -> Cow<'_, str> {
+    let needs_mark = |paragraph: &str| {
+        let detected = paragraph_direction(paragraph).unwrap_or(LayoutDirection::LTR);
+
+        direction.resolve(paragraph, layout_direction) != detected
+    };
+
+    if !text.split('\n').any(needs_mark) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut result = String::with_capacity(text.len() + 3);
+
+    for (index, paragraph) in text.split('\n').enumerate() {
+        if index > 0 {
+            result.push('\n');
+        }
+
+        if needs_mark(paragraph) {
+            result.push(match direction.resolve(paragraph, layout_direction) {
+                LayoutDirection::LTR => '\u{200E}',
+                LayoutDirection::RTL => '\u{200F}',
+            });
+        }
+
+        result.push_str(paragraph);
+    }
+
+    Cow::Owned(result)
+}
+
+/// Advances of the digits from 0 to 9 in em, `None` when the font has the `tnum`
+/// feature.
+fn digit_advances(
+    font_system: &mut cosmic_text::FontSystem,
+    font_id: fontdb::ID,
+) -> Option<[f32; 10]> {
+    let font = font_system.get_font(font_id)?;
+    let font = font.as_swash();
+    let tnum = u32::from_be_bytes(*b"tnum");
+
+    if font.features().any(|feature| feature.tag() == tnum) {
+        return None;
+    }
+
+    let charmap = font.charmap();
+    let metrics = font.glyph_metrics(&[]).scale(1.);
+
+    Some(std::array::from_fn(|digit| {
+        metrics.advance_width(charmap.map(char::from(b'0' + digit as u8)))
+    }))
+}
+
+/// Fonts of the tests, the shipped DejaVu Sans Mono is the default sans-serif and
+/// monospace family so the text is shaped the same on every system.
+#[cfg(test)]
+pub(crate) fn test_fonts() -> FontResources {
+    let mut fonts = FontResources::with_fonts(
+        SystemFonts::Lazy,
+        [(
+            "DejaVu Sans Mono",
+            include_bytes!("../../test-fonts/DejaVuSansMono.ttf").as_slice(),
+        )],
+    );
+    fonts
+        .font_system
+        .db_mut()
+        .set_monospace_family("DejaVu Sans Mono");
+
+    fonts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn test_invisible_clusters() {
+        assert!(is_visible_cluster("a"));
+        assert!(is_visible_cluster(" "));
+        assert!(is_visible_cluster("e\u{301}"));
+        assert!(!is_visible_cluster("\t"));
+        assert!(!is_visible_cluster("\u{1}"));
+        assert!(!is_visible_cluster("\u{200B}"));
+        assert!(!is_visible_cluster("\u{FEFF}"));
+    }
+
+    #[test]
+    fn test_load_invalid_font_fails() {
+        let mut fonts = FontResources::new();
+        let result = fonts.load_font("Broken", &[0, 1, 2, 3]);
+
+        assert_eq!(
+            result.err(),
+            Some(FontLoadError::InvalidData { name: "Broken" })
+        );
+        assert!(!fonts.contains_family("Broken"));
+    }
+
+    fn shaped_glyphs(
+        fonts: &mut FontResources,
+        family: cosmic_text::Family,
+        value: &str,
+    ) -> Vec<(fontdb::ID, u16)> {
+        let mut text = Text::new(&testing::view(800, 100), fonts, 12., 12.);
+        text.set_family(family);
+        text.set_text(fonts, value);
+        text.with_buffer_mut(|buffer| buffer.shape_until_scroll(&mut fonts.font_system, false));
+
+        let mut glyphs = Vec::new();
+        text.with_buffer(|buffer| {
+            for run in buffer.layout_runs() {
+                glyphs.extend(
+                    run.glyphs
+                        .iter()
+                        .map(|glyph| (glyph.font_id, glyph.glyph_id)),
+                );
+            }
+        });
+
+        glyphs
+    }
+
+    fn glyph_xs(text: &Text) -> Vec<f32> {
+        text.layout_runs()
+            .flat_map(|run| run.glyphs.iter().map(|glyph| glyph.x))
+            .collect()
+    }
+
+    #[test]
+    fn test_narrow_digits_are_padded_to_widest_one() {
+        let mut fonts = test_fonts();
+        let mut text = Text::new(&testing::view(800, 100), &mut fonts, 10., 10.);
+        text.set_text(&mut fonts, "1+21");
+
+        let width = text.layout().x;
+        let xs = glyph_xs(&text);
+
+        // As if the 1 of the font was 0.2 em narrower than the other digits.
+        let padded = text.pad_digits(&mut fonts.font_system, |_, _| {
+            let mut advances = [0.6; 10];
+            advances[1] = 0.4;
+
+            Some(advances)
+        });
+
+        let padded_xs = glyph_xs(&text);
+        assert!(padded);
+        assert!((text.layout().x - (width + 4.)).abs() < 0.01);
+        assert!((padded_xs[1] - (xs[1] + 2.)).abs() < 0.01);
+        assert!((padded_xs[2] - (xs[2] + 2.)).abs() < 0.01);
+        assert!((padded_xs[3] - (xs[3] + 2.)).abs() < 0.01);
+
+        // Setting the text again drops the spacing.
+        text.set_text(&mut fonts, "1+2");
+        text.set_text(&mut fonts, "1+21");
+        assert_eq!(glyph_xs(&text), xs);
+    }
+
+    #[test]
+    fn test_numbers_of_same_length_take_same_width() {
+        let mut fonts = test_fonts();
+        let font_id = shaped_glyphs(&mut fonts, cosmic_text::Family::SansSerif, "0")[0].0;
+        let advances = digit_advances(&mut fonts.font_system, font_id).unwrap();
+        let mut widths = Vec::new();
+
+        for value in ["1111", "8808", "1234"] {
+            let mut text = Text::new(&testing::view(800, 100), &mut fonts, 10., 10.);
+            text.set_text(&mut fonts, value);
+            text.pad_numbers(&mut fonts);
+            widths.push(text.layout().x);
+        }
+
+        // DejaVu Sans Mono has no `tnum`, the padding keeps its digits as they are.
+        assert!(advances.iter().all(|it| *it == advances[0]));
+        assert!(widths.iter().all(|it| (it - widths[0]).abs() < 0.01));
+        assert!((widths[0] - 4. * advances[0] * 10.).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_loaded_family_does_not_load_system_fonts() {
+        let mut fonts = test_fonts();
+        fonts.request_family("DejaVu Sans Mono");
+
+        assert!(!fonts.system_fonts_loaded);
+    }
+
+    #[test]
+    fn test_missing_family_falls_back_to_default() {
+        let mut fonts = test_fonts();
+        fonts.request_family("Missing Family");
+
+        assert!(fonts.system_fonts_loaded);
+        assert!(!fonts.contains_family("Missing Family"));
+
+        let glyphs = shaped_glyphs(
+            &mut fonts,
+            cosmic_text::Family::Name("Missing Family"),
+            "ok",
+        );
+
+        assert_eq!(glyphs.len(), 2);
+        assert!(glyphs.iter().all(|(_, glyph_id)| *glyph_id != 0));
+    }
+
+    #[test]
+    fn test_missing_glyphs_fall_back_to_other_families() {
+        let mut fonts = test_fonts();
+        let sans = fonts
+            .load_font(
+                "DejaVu Sans",
+                include_bytes!("../../test-fonts/DejaVuSans.ttf"),
+            )
+            .unwrap();
+        let mono_faces = fonts.fonts.values().next().unwrap().faces.clone();
+        let sans_faces = fonts.fonts[sans].faces.clone();
+
+        // The monospace family has no hebrew, only that letter is taken from another
+        // loaded family.
+        let glyphs = shaped_glyphs(
+            &mut fonts,
+            cosmic_text::Family::Name("DejaVu Sans Mono"),
+            "a א",
+        );
+
+        assert_eq!(glyphs.len(), 3);
+        assert!(glyphs.iter().all(|(_, glyph_id)| *glyph_id != 0));
+        assert!(mono_faces.contains(&glyphs[0].0));
+        assert!(sans_faces.contains(&glyphs[2].0));
+    }
+
+    #[test]
+    fn test_forked_fonts_keep_the_family_ids() {
+        let mut fonts = test_fonts();
+        let sans = fonts
+            .load_font(
+                "DejaVu Sans",
+                include_bytes!("../../test-fonts/DejaVuSans.ttf"),
+            )
+            .unwrap();
+        let sans_faces = fonts.fonts[sans].faces.clone();
+
+        let mut fork = fonts.fork();
+
+        assert_eq!(fork.fonts[sans].faces, sans_faces);
+        assert!(fork.contains_family("DejaVu Sans"));
+        assert!(!fork.system_fonts_loaded);
+        assert_eq!(
+            fork.font_system
+                .db()
+                .family_name(&fontdb::Family::Monospace),
+            "DejaVu Sans Mono"
+        );
+
+        // The fork can unload the family by the id it was loaded under in the original.
+        assert!(fork.unload_font(sans, &mut TextsResources::new()));
+        assert!(!fork.contains_family("DejaVu Sans"));
+        assert!(fonts.contains_family("DejaVu Sans"));
+    }
+
+    #[test]
+    fn test_simple_text_advances_by_columns() {
+        let mut fonts = test_fonts();
+        let view = testing::view(800, 100);
+        let mut text = Text::new(&view, &mut fonts, 12., 12.);
+        text.set_family(cosmic_text::Family::Monospace);
+
+        assert!(text.set_simple_text(&mut fonts, "[build] ok"));
+
+        let run = text.layout_runs().next().unwrap();
+
+        assert_eq!(run.glyphs.len(), 10);
+        assert!(run.glyphs.iter().enumerate().all(|(column, glyph)| {
+            glyph.start == column && (glyph.w - run.glyphs[0].w).abs() < 0.01
+        }));
+
+        assert!(!text.set_simple_text(&mut fonts, "cafe\u{301}"));
+    }
+
+    #[test]
+    fn test_measure_follows_the_style() {
+        let mut fonts = test_fonts();
+        let mut texts = TextsResources::new();
+        let style = MeasureTextStyle {
+            font_family: Some("DejaVu Sans Mono"),
+            ..Default::default()
+        };
+        let large_style = MeasureTextStyle {
+            font_size: 24.,
+            ..style
+        };
+
+        let small = texts.measure(&testing::view(800, 100), &mut fonts, "Page 12", style);
+        let large = texts.measure(&testing::view(800, 100), &mut fonts, "Page 12", large_style);
+
+        assert!(small.x > 0.);
+        assert!((large.x - small.x * 2.).abs() < 0.5);
+        // Measured again in the kept buffer, which isn't one of the texts.
+        assert_eq!(
+            texts.measure(&testing::view(800, 100), &mut fonts, "Page 12", style),
+            small
+        );
+        assert!(texts.items.is_empty());
+    }
+
+    #[test]
+    fn test_simple_layout_matches_the_laid_out_glyphs() {
+        let mut fonts = test_fonts();
+        let value = "[build] ok\nwarning: unused  x\n\n  end";
+        let mut simple = Text::new(&testing::view(800, 100), &mut fonts, 14., 20.);
+        let mut shaped = Text::new(&testing::view(800, 100), &mut fonts, 14., 20.);
+        simple.set_family(cosmic_text::Family::Monospace);
+        shaped.set_family(cosmic_text::Family::Monospace);
+
+        assert!(simple.set_simple_text(&mut fonts, value));
+        shaped.set_text(&mut fonts, value);
+        assert!(simple.is_simple());
+        assert!(!shaped.is_simple());
+
+        let near = |a: Vec2, b: Vec2| (a.x - b.x).abs() < 0.01 && (a.y - b.y).abs() < 0.01;
+        let compare = |simple: &mut Text, shaped: &mut Text| {
+            let (simple_size, shaped_size) = (simple.layout(), shaped.layout());
+            assert!(
+                near(simple_size, shaped_size),
+                "{simple_size:?} {shaped_size:?}"
+            );
+
+            for y in [-5., 0., 19.9, 20., 45., 70., 200.] {
+                for x in (-2..200).map(|x| x as f32 * 0.7) {
+                    assert_eq!(simple.hit(x, y), shaped.hit(x, y), "({x}, {y})");
+                }
+            }
+
+            let cursors: Vec<_> = value
+                .split('\n')
+                .enumerate()
+                .flat_map(|(line, text)| (0..=text.len()).map(move |index| (line, index)))
+                .flat_map(|(line, index)| {
+                    [cosmic_text::Affinity::Before, cosmic_text::Affinity::After].map(|affinity| {
+                        cosmic_text::Cursor::new_with_affinity(line, index, affinity)
+                    })
+                })
+                .collect();
+
+            for (start, end) in cursors
+                .iter()
+                .flat_map(|a| cursors.iter().map(move |b| (*a, *b)))
+            {
+                let simple = simple.highlight_rects(start, end);
+                let shaped = shaped.highlight_rects(start, end);
+
+                assert_eq!(simple.len(), shaped.len(), "{start:?} {end:?}");
+
+                for (simple, shaped) in simple.iter().zip(&shaped) {
+                    assert!(
+                        near(simple.position(), shaped.position())
+                            && near(simple.size(), shaped.size()),
+                        "{simple:?} {shaped:?}",
+                    );
+                }
+            }
+        };
+
+        compare(&mut simple, &mut shaped);
+
+        // Only the lines above the visible height are laid out.
+        for text in [&mut simple, &mut shaped] {
+            text.set_size(&mut fonts.font_system, None, Some(30.));
+        }
+
+        compare(&mut simple, &mut shaped);
+
+        // The lines are aligned within the width.
+        for align in [cosmic_text::Align::Center, cosmic_text::Align::Right] {
+            for text in [&mut simple, &mut shaped] {
+                text.set_size(&mut fonts.font_system, Some(190.), None);
+                text.set_align(Some(align));
+            }
+
+            shaped.with_buffer_mut(|buffer| {
+                buffer.shape_until_scroll(&mut fonts.font_system, false);
+            });
+
+            compare(&mut simple, &mut shaped);
+            assert!(simple.is_simple());
+        }
+
+        // Wrapped lines are laid out by the glyphs.
+        for text in [&mut simple, &mut shaped] {
+            text.set_size(&mut fonts.font_system, Some(60.), None);
+        }
+
+        compare(&mut simple, &mut shaped);
+        assert!(!simple.is_simple());
+        assert!(simple.layout().y > 80.);
+    }
+
+    #[test]
+    fn test_paragraph_direction() {
+        assert_eq!(paragraph_direction("Hello"), Some(LayoutDirection::LTR));
+        assert_eq!(
+            paragraph_direction("مرحبا بالعالم"),
+            Some(LayoutDirection::RTL)
+        );
+        assert_eq!(paragraph_direction("שלום עולם"), Some(LayoutDirection::RTL));
+        assert_eq!(
+            paragraph_direction("123 - שלום, world"),
+            Some(LayoutDirection::RTL)
+        );
+        assert_eq!(
+            paragraph_direction("\u{2068}שלום\u{2069} world"),
+            Some(LayoutDirection::LTR)
+        );
+        assert_eq!(paragraph_direction("123, 456!"), None);
+    }
+
+    #[test]
+    fn test_direction_resolved_per_paragraph() {
+        let text = "Hello, עולם\nمرحبا world\n42";
+        let directions: Vec<_> = text
+            .split('\n')
+            .map(|paragraph| TextDirection::Auto.resolve(paragraph, LayoutDirection::RTL))
+            .collect();
+
+        assert_eq!(
+            directions,
+            [
+                LayoutDirection::LTR,
+                LayoutDirection::RTL,
+                LayoutDirection::RTL
+            ]
+        );
+        assert_eq!(
+            with_text_direction(text, TextDirection::Auto, LayoutDirection::LTR),
+            text
+        );
+        assert_eq!(
+            with_text_direction(text, TextDirection::Auto, LayoutDirection::RTL),
+            "Hello, עולם\nمرحبا world\n\u{200F}42"
+        );
+        assert_eq!(
+            with_text_direction(text, TextDirection::Rtl, LayoutDirection::LTR),
+            "\u{200F}Hello, עולם\nمرحبا world\n\u{200F}42"
+        );
+        assert_eq!(
+            with_text_direction(text, TextDirection::Ltr, LayoutDirection::LTR),
+            "Hello, עולם\n\u{200E}مرحبا world\n42"
+        );
+    }
+}
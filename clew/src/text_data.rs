@@ -1,19 +1,30 @@
-use rustc_hash::{FxHashMap, FxHashSet};
-
 use crate::{
-    WidgetId,
+    WidgetId, WidgetIdMap,
     editable_text::EditableTextDelta,
     text::{TextId, TextsResources},
+    text_history::TextEditDelta,
 };
 
 pub struct TextData {
     pub(crate) buffer: cosmic_text::Buffer,
 
-    // This potentially can bloat as we never clear these data,
-    // but realistically is should be fine as usually TextData not used by many widgets.
-    pub(crate) replace_buffer: FxHashSet<WidgetId>,
-    pub(crate) dirty: FxHashSet<WidgetId>,
-    pub(crate) text_id: FxHashMap<WidgetId, TextId>,
+    // Entries of the widgets whose texts have been released are dropped by
+    // `forget_released_texts` the next time any widget with this data is built.
+    pub(crate) widgets: WidgetIdMap<TextDataWidget>,
+}
+
+/// What the data keeps about an editable text showing it, the widget takes it all with
+/// one lookup when it's built, see [`TextData::bind`].
+#[derive(Default)]
+pub(crate) struct TextDataWidget {
+    pub(crate) text_id: TextId,
+    /// The text has been set, the widget replaces the content of its editor.
+    pub(crate) replace_buffer: bool,
+    /// Another widget has edited the text, the widget lays its content out again.
+    pub(crate) dirty: bool,
+    /// Deltas applied with [`TextData::apply_delta`] and [`TextData::undo_delta`], replayed
+    /// into the editor of the widget when it's built next.
+    pub(crate) queued_deltas: Vec<EditableTextDelta>,
 }
 
 impl Default for TextData {
@@ -32,9 +43,7 @@ impl TextData {
 
         Self {
             buffer,
-            dirty: FxHashSet::default(),
-            replace_buffer: FxHashSet::default(),
-            text_id: FxHashMap::default(),
+            widgets: WidgetIdMap::default(),
         }
     }
 
@@ -45,12 +54,51 @@ impl TextData {
         data
     }
 
-    pub(crate) fn text_id(&self, id: WidgetId) -> Option<TextId> {
-        self.text_id.get(&id).cloned()
+    /// Takes what the widget has to catch up on since it was built last, the text is
+    /// made with `create` if the widget shows the data for the first time.
+    pub(crate) fn bind(
+        &mut self,
+        id: WidgetId,
+        create: impl FnOnce(&Self) -> TextId,
+    ) -> TextDataWidget {
+        match self.widgets.get_mut(&id) {
+            Some(widget) => TextDataWidget {
+                text_id: widget.text_id,
+                replace_buffer: std::mem::take(&mut widget.replace_buffer),
+                dirty: std::mem::take(&mut widget.dirty),
+                queued_deltas: std::mem::take(&mut widget.queued_deltas),
+            },
+            None => {
+                let text_id = create(self);
+                self.widgets.insert(
+                    id,
+                    TextDataWidget {
+                        text_id,
+                        ..Default::default()
+                    },
+                );
+
+                TextDataWidget {
+                    text_id,
+                    ..Default::default()
+                }
+            }
+        }
+    }
+
+    /// Keeps the content of the editor of the widget to be replaced the next time it's
+    /// built.
+    pub(crate) fn defer_replace_buffer(&mut self, id: WidgetId) {
+        if let Some(widget) = self.widgets.get_mut(&id) {
+            widget.replace_buffer = true;
+        }
     }
 
-    pub(crate) fn set_text_id(&mut self, id: WidgetId, text_id: TextId) {
-        self.text_id.insert(id, text_id);
+    /// Forgets the widgets whose texts have been released with their states, see
+    /// [`TextsResources::retain_only`]. A widget built again gets a new text.
+    pub(crate) fn forget_released_texts(&mut self, texts: &TextsResources) {
+        self.widgets
+            .retain(|_, widget| texts.contains(widget.text_id));
     }
 
     pub fn set_text(&mut self, data: &str) {
@@ -74,8 +122,9 @@ impl TextData {
             ));
         }
 
-        for k in self.text_id.keys() {
-            self.replace_buffer.insert(*k);
+        for widget in self.widgets.values_mut() {
+            widget.replace_buffer = true;
+            widget.queued_deltas.clear();
         }
     }
 
@@ -107,8 +156,9 @@ impl TextData {
             ));
         }
 
-        for k in self.text_id.keys() {
-            self.replace_buffer.insert(*k);
+        for widget in self.widgets.values_mut() {
+            widget.replace_buffer = true;
+            widget.queued_deltas.clear();
         }
     }
 
@@ -156,7 +206,30 @@ impl TextData {
         self.set_text("");
     }
 
-    pub(crate) fn apply_delta(
+    /// Applies the delta reported by an editable text with the external history, e.g. to
+    /// redo it from the history of the app. The editors of the widgets showing the text
+    /// get it when they are built next, with the cursor put after the edit and scrolled
+    /// into view.
+    pub fn apply_delta(&mut self, delta: &TextEditDelta) {
+        delta.apply_to_buffer(&mut self.buffer);
+        self.queue_delta(EditableTextDelta::Apply(delta.clone()));
+    }
+
+    /// Reverts the delta reported by an editable text with the external history, the
+    /// counterpart of [`TextData::apply_delta`] for the undo. The cursor of the editors is
+    /// put back where it was before the edit.
+    pub fn undo_delta(&mut self, delta: &TextEditDelta) {
+        delta.undo_to_buffer(&mut self.buffer);
+        self.queue_delta(EditableTextDelta::Undo(delta.clone()));
+    }
+
+    fn queue_delta(&mut self, delta: EditableTextDelta) {
+        for widget in self.widgets.values_mut() {
+            widget.queued_deltas.push(delta.clone());
+        }
+    }
+
+    pub(crate) fn apply_widget_delta(
         &mut self,
         text_resources: &mut TextsResources,
         id: WidgetId,
@@ -167,22 +240,18 @@ impl TextData {
             EditableTextDelta::Apply(delta) => delta.apply_to_buffer(&mut self.buffer),
         }
 
-        for (key, text_id) in self.text_id.iter() {
+        for (key, widget) in self.widgets.iter_mut() {
             if *key == id {
                 continue;
             }
 
-            self.dirty.insert(*key);
+            widget.dirty = true;
             text_resources
-                .get_mut(*text_id)
+                .get_mut(widget.text_id)
                 .with_buffer_mut(|buffer| match delta {
                     EditableTextDelta::Undo(delta) => delta.undo_to_buffer(buffer),
                     EditableTextDelta::Apply(delta) => delta.apply_to_buffer(buffer),
                 });
         }
     }
-
-    pub(crate) fn mark_as_not_dirty(&mut self, id: &WidgetId) {
-        self.dirty.remove(id);
-    }
 }
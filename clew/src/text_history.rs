@@ -16,6 +16,7 @@ pub struct TextEditHistoryManager {
 }
 
 #[derive(Debug, Eq, PartialEq, Copy, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum TextDeletionDirection {
     Forward,  // Delete
     Backward, // Backspace
@@ -0,0 +1,606 @@
+//! Stable form of the [`TextEditDelta`]s, e.g. to send the edits of a text to the other
+//! clients of a shared document, and the helpers that merge the concurrent edits.
+//!
+//! An edit received from another client is [`transform`]ed against the edits made
+//! locally since the text they both started from, the text ends up the same on both
+//! clients whatever order the edits are applied in. There are no guarantees beyond two
+//! clients, e.g. a server that orders the edits is needed for more of them.
+
+use cosmic_text::{Affinity, Cursor};
+
+use crate::text_history::{TextDeletionDirection, TextEditDelta};
+
+/// Position in the text, the line and the byte index in it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StableCursor {
+    pub line: u64,
+    pub index: u64,
+    /// `0` when the cursor sticks to the text before it, `1` to the text after it.
+    pub affinity: u8,
+}
+
+impl From<Cursor> for StableCursor {
+    fn from(cursor: Cursor) -> Self {
+        Self {
+            line: cursor.line as u64,
+            index: cursor.index as u64,
+            affinity: match cursor.affinity {
+                Affinity::Before => 0,
+                Affinity::After => 1,
+            },
+        }
+    }
+}
+
+impl From<StableCursor> for Cursor {
+    fn from(cursor: StableCursor) -> Self {
+        let affinity = if cursor.affinity == 0 {
+            Affinity::Before
+        } else {
+            Affinity::After
+        };
+
+        Cursor::new_with_affinity(cursor.line as usize, cursor.index as usize, affinity)
+    }
+}
+
+/// [`TextEditDelta`] that doesn't depend on the types of the text layout, so it keeps
+/// its form across versions and can be serialized.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StableTextEditDelta {
+    Insert {
+        cursor_before: StableCursor,
+        cursor_after: StableCursor,
+        text: String,
+    },
+    Delete {
+        start: StableCursor,
+        end: StableCursor,
+        deleted_text: String,
+        direction: TextDeletionDirection,
+    },
+    Replace {
+        range_before: (StableCursor, StableCursor),
+        range_after: (StableCursor, StableCursor),
+        text_before: String,
+        text_after: String,
+    },
+}
+
+impl From<&TextEditDelta> for StableTextEditDelta {
+    fn from(delta: &TextEditDelta) -> Self {
+        match delta.clone() {
+            TextEditDelta::Insert {
+                cursor_before,
+                cursor_after,
+                text,
+            } => StableTextEditDelta::Insert {
+                cursor_before: cursor_before.into(),
+                cursor_after: cursor_after.into(),
+                text,
+            },
+            TextEditDelta::Delete {
+                start,
+                end,
+                deleted_text,
+                direction,
+            } => StableTextEditDelta::Delete {
+                start: start.into(),
+                end: end.into(),
+                deleted_text,
+                direction,
+            },
+            TextEditDelta::Replace {
+                range_before,
+                range_after,
+                text_before,
+                text_after,
+            } => StableTextEditDelta::Replace {
+                range_before: (range_before.0.into(), range_before.1.into()),
+                range_after: (range_after.0.into(), range_after.1.into()),
+                text_before,
+                text_after,
+            },
+        }
+    }
+}
+
+impl From<StableTextEditDelta> for TextEditDelta {
+    fn from(delta: StableTextEditDelta) -> Self {
+        match delta {
+            StableTextEditDelta::Insert {
+                cursor_before,
+                cursor_after,
+                text,
+            } => TextEditDelta::Insert {
+                cursor_before: cursor_before.into(),
+                cursor_after: cursor_after.into(),
+                text,
+            },
+            StableTextEditDelta::Delete {
+                start,
+                end,
+                deleted_text,
+                direction,
+            } => TextEditDelta::Delete {
+                start: start.into(),
+                end: end.into(),
+                deleted_text,
+                direction,
+            },
+            StableTextEditDelta::Replace {
+                range_before,
+                range_after,
+                text_before,
+                text_after,
+            } => TextEditDelta::Replace {
+                range_before: (range_before.0.into(), range_before.1.into()),
+                range_after: (range_after.0.into(), range_after.1.into()),
+                text_before,
+                text_after,
+            },
+        }
+    }
+}
+
+/// Any delta as the range it deletes and the text it inserts at the start of it.
+struct Edit {
+    start: Cursor,
+    end: Cursor,
+    deleted: String,
+    inserted: String,
+}
+
+impl Edit {
+    fn new(delta: &TextEditDelta) -> Self {
+        match delta.clone() {
+            TextEditDelta::Insert {
+                cursor_before,
+                text,
+                ..
+            } => Edit {
+                start: cursor_before,
+                end: cursor_before,
+                deleted: String::new(),
+                inserted: text,
+            },
+            TextEditDelta::Delete {
+                start,
+                end,
+                deleted_text,
+                ..
+            } => {
+                let (start, end) = ordered(start, end);
+
+                Edit {
+                    start,
+                    end,
+                    deleted: deleted_text,
+                    inserted: String::new(),
+                }
+            }
+            TextEditDelta::Replace {
+                range_before: (start, end),
+                text_before,
+                text_after,
+                ..
+            } => {
+                let (start, end) = ordered(start, end);
+
+                Edit {
+                    start,
+                    end,
+                    deleted: text_before,
+                    inserted: text_after,
+                }
+            }
+        }
+    }
+
+    fn inserted_end(&self) -> Cursor {
+        advance(self.start, &self.inserted)
+    }
+
+    fn insert(self) -> TextEditDelta {
+        TextEditDelta::Insert {
+            cursor_before: self.start,
+            cursor_after: self.inserted_end(),
+            text: self.inserted,
+        }
+    }
+
+    fn delete(self, direction: TextDeletionDirection) -> TextEditDelta {
+        TextEditDelta::Delete {
+            start: self.start,
+            end: self.end,
+            deleted_text: self.deleted,
+            direction,
+        }
+    }
+
+    fn replace(self) -> TextEditDelta {
+        TextEditDelta::Replace {
+            range_before: (self.start, self.end),
+            range_after: (self.start, self.inserted_end()),
+            text_before: self.deleted,
+            text_after: self.inserted,
+        }
+    }
+}
+
+/// Compares the positions without the affinities, they don't move the text.
+fn position(cursor: Cursor) -> (usize, usize) {
+    (cursor.line, cursor.index)
+}
+
+fn ordered(start: Cursor, end: Cursor) -> (Cursor, Cursor) {
+    if position(start) > position(end) {
+        (end, start)
+    } else {
+        (start, end)
+    }
+}
+
+/// Position at the end of the text inserted at the cursor.
+fn advance(cursor: Cursor, text: &str) -> Cursor {
+    match text.rfind('\n') {
+        Some(last) => Cursor::new(
+            cursor.line + text.matches('\n').count(),
+            text.len() - last - 1,
+        ),
+        None => Cursor::new(cursor.line, cursor.index + text.len()),
+    }
+}
+
+/// Moves the position at or after `from` along with it when `from` moves to `to`.
+fn rebase(cursor: Cursor, from: Cursor, to: Cursor) -> Cursor {
+    if cursor.line == from.line {
+        Cursor::new(to.line, to.index + cursor.index.saturating_sub(from.index))
+    } else {
+        Cursor::new(cursor.line - from.line + to.line, cursor.index)
+    }
+}
+
+/// Byte offset of the position in the text that starts at `start`, clamped to the text
+/// so that malformed deltas, e.g. received from the network, don't panic.
+fn offset(text: &str, start: Cursor, cursor: Cursor) -> usize {
+    let offset = if cursor.line <= start.line {
+        cursor.index.saturating_sub(start.index)
+    } else {
+        text.match_indices('\n')
+            .nth(cursor.line - start.line - 1)
+            .map_or(text.len(), |(newline, _)| newline + 1 + cursor.index)
+    };
+
+    text.floor_char_boundary(offset)
+}
+
+/// Adjusts the `delta` made concurrently with the `applied` one, both made on the same
+/// text, to apply it after the `applied` delta. Applying `a` then `transform(b, a)`
+/// leaves the same text as `b` then `transform(a, b)`:
+///
+/// - The positions after the applied edit are moved along with the text it has
+///   inserted and deleted.
+/// - The text deleted by both edits is deleted once.
+/// - Text inserted at the same position by both edits is ordered by the ends of their
+///   deleted ranges and then by the texts, the insertion of the shorter range or the
+///   smaller text goes first.
+/// - Text inserted strictly inside the range deleted by the other edit is deleted with
+///   it, while the range deleted by the edit still spans it.
+///
+/// The transformed delta keeps the kind of the `delta`, the transformed deletion is
+/// still a [`TextEditDelta::Delete`] even when there is nothing left for it to delete.
+pub fn transform(delta: &TextEditDelta, applied: &TextEditDelta) -> TextEditDelta {
+    let a = Edit::new(delta);
+    let b = Edit::new(applied);
+    let (a_start, a_end) = (position(a.start), position(a.end));
+    let (b_start, b_end) = (position(b.start), position(b.end));
+    let b_inserted_end = b.inserted_end();
+    let shift = |cursor: Cursor| rebase(cursor, b.end, b_inserted_end);
+
+    let first = a_start == b_start && (a_end, &a.inserted) <= (b_end, &b.inserted);
+    // The applied edit has inserted its text inside the range of the delta.
+    let swallowed = a_start < b_start && b_start < a_end;
+    // The delta inserts inside the range the applied edit has deleted.
+    let dropped = b_start < a_start && a_start < b_end;
+
+    let start = if a_start < b_start || first {
+        a.start
+    } else if a_start < b_end || a_start == b_start {
+        b_inserted_end
+    } else {
+        shift(a.start)
+    };
+
+    let end = if a_end > b_end {
+        shift(a.end)
+    } else if swallowed {
+        b_inserted_end
+    } else if a_end > b_start || a_start == b_start {
+        start
+    } else {
+        a.end
+    };
+
+    let mut deleted = String::new();
+
+    if a_start < b_start {
+        let end = if a_end < b_start { a.end } else { b.start };
+
+        deleted.push_str(&a.deleted[..offset(&a.deleted, a.start, end)]);
+    }
+
+    if swallowed {
+        deleted.push_str(&b.inserted);
+    }
+
+    if a_end > b_end {
+        let start = if a_start > b_end { a.start } else { b.end };
+
+        deleted.push_str(&a.deleted[offset(&a.deleted, a.start, start)..]);
+    }
+
+    let edit = Edit {
+        start,
+        end,
+        deleted,
+        inserted: if dropped { String::new() } else { a.inserted },
+    };
+
+    match delta {
+        TextEditDelta::Insert { .. } => edit.insert(),
+        TextEditDelta::Delete { direction, .. } => edit.delete(*direction),
+        TextEditDelta::Replace { .. } => edit.replace(),
+    }
+}
+
+fn deletion_direction(delta: &TextEditDelta) -> Option<TextDeletionDirection> {
+    match delta {
+        TextEditDelta::Delete { direction, .. } => Some(*direction),
+        _ => None,
+    }
+}
+
+/// Single delta with the effect of the `first` delta followed by the `second` one,
+/// e.g. to send the edits typed in a short time at once. `None` when the second edit
+/// doesn't touch the text the first one has changed, the text between them isn't known
+/// to the deltas.
+///
+/// The composed delta is an insertion when it deletes nothing, a deletion when it
+/// inserts nothing and a replacement otherwise.
+pub fn compose(first: &TextEditDelta, second: &TextEditDelta) -> Option<TextEditDelta> {
+    let a = Edit::new(first);
+    let b = Edit::new(second);
+    let a_inserted_end = a.inserted_end();
+
+    if position(b.start) > position(a_inserted_end) || position(b.end) < position(a.start) {
+        return None;
+    }
+
+    let start = if position(b.start) < position(a.start) {
+        b.start
+    } else {
+        a.start
+    };
+
+    // The text the second edit has deleted around the text of the first one.
+    let before = if position(b.start) < position(a.start) {
+        &b.deleted[..offset(&b.deleted, b.start, a.start)]
+    } else {
+        ""
+    };
+    let after = if position(b.end) > position(a_inserted_end) {
+        &b.deleted[offset(&b.deleted, b.start, a_inserted_end)..]
+    } else {
+        ""
+    };
+
+    let end = if position(b.end) > position(a_inserted_end) {
+        rebase(b.end, a_inserted_end, a.end)
+    } else {
+        a.end
+    };
+
+    // The text between the start and the end after the first edit.
+    let between = format!("{before}{}{after}", a.inserted);
+    let inserted = format!(
+        "{}{}{}",
+        &between[..offset(&between, start, b.start)],
+        b.inserted,
+        &between[offset(&between, start, b.end)..],
+    );
+    let edit = Edit {
+        start,
+        end,
+        deleted: format!("{before}{}{after}", a.deleted),
+        inserted,
+    };
+
+    Some(if edit.deleted.is_empty() {
+        edit.insert()
+    } else if edit.inserted.is_empty() {
+        let direction = deletion_direction(second)
+            .or(deletion_direction(first))
+            .unwrap_or(TextDeletionDirection::Forward);
+
+        edit.delete(direction)
+    } else {
+        edit.replace()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use cosmic_text::{Buffer, Edit as _, Editor, FontSystem, Metrics, fontdb};
+
+    use crate::StableRng;
+
+    use super::*;
+
+    fn editor(text: &str) -> Editor<'static> {
+        let mut font_system =
+            FontSystem::new_with_locale_and_db("en-US".into(), fontdb::Database::new());
+        let mut editor = Editor::new(Buffer::new(&mut font_system, Metrics::new(14., 16.)));
+
+        editor.insert_string(text, None);
+
+        editor
+    }
+
+    fn text_of(editor: &Editor) -> String {
+        editor.with_buffer(|buffer| {
+            buffer
+                .lines
+                .iter()
+                .map(|line| line.text())
+                .collect::<Vec<_>>()
+                .join("\n")
+        })
+    }
+
+    fn random_string(rng: &mut StableRng, max_len: u64) -> String {
+        (0..rng.range_u64(0..max_len + 1))
+            .map(|_| *rng.pick(&['a', 'b', 'c', '\n']).unwrap())
+            .collect()
+    }
+
+    fn random_cursor(rng: &mut StableRng, text: &str) -> Cursor {
+        let lines = text.split('\n').collect::<Vec<_>>();
+        let line = rng.range_u64(0..lines.len() as u64) as usize;
+
+        Cursor::new(
+            line,
+            rng.range_u64(0..lines[line].len() as u64 + 1) as usize,
+        )
+    }
+
+    fn byte_offset(text: &str, cursor: Cursor) -> usize {
+        offset(text, Cursor::new(0, 0), cursor)
+    }
+
+    /// Insert, deletion or replacement at random positions of the text.
+    fn random_delta(rng: &mut StableRng, text: &str) -> TextEditDelta {
+        let (start, end) = ordered(random_cursor(rng, text), random_cursor(rng, text));
+        let deleted = text[byte_offset(text, start)..byte_offset(text, end)].to_string();
+        let inserted = random_string(rng, 3);
+        let edit = Edit {
+            start,
+            end,
+            deleted,
+            inserted,
+        };
+
+        match rng.range_u64(0..3) {
+            0 => Edit {
+                end: start,
+                deleted: String::new(),
+                ..edit
+            }
+            .insert(),
+            1 => edit.delete(TextDeletionDirection::Backward),
+            _ => edit.replace(),
+        }
+    }
+
+    #[test]
+    fn test_concurrent_deltas_converge() {
+        let mut rng = StableRng::new(1500);
+
+        for _ in 0..2000 {
+            let text = random_string(&mut rng, 8);
+            let a = random_delta(&mut rng, &text);
+            let b = random_delta(&mut rng, &text);
+
+            let mut left = editor(&text);
+            let b_after_a = transform(&b, &a);
+
+            a.apply(&mut left);
+            b_after_a.apply(&mut left);
+
+            let mut right = editor(&text);
+            let a_after_b = transform(&a, &b);
+
+            b.apply(&mut right);
+            a_after_b.apply(&mut right);
+
+            assert_eq!(text_of(&left), text_of(&right), "{text:?} {a:?} {b:?}");
+
+            // Undone in the reverse order the text is back where it started.
+            b_after_a.undo(&mut left);
+            a.undo(&mut left);
+
+            assert_eq!(text_of(&left), text, "{text:?} {a:?} {b:?}");
+        }
+    }
+
+    #[test]
+    fn test_composed_deltas_apply_at_once() {
+        let mut rng = StableRng::new(1500);
+        let mut composed = 0;
+
+        for _ in 0..2000 {
+            let text = random_string(&mut rng, 8);
+            let first = random_delta(&mut rng, &text);
+            let mut expected = editor(&text);
+
+            first.apply(&mut expected);
+
+            let second = random_delta(&mut rng, &text_of(&expected));
+
+            second.apply(&mut expected);
+
+            let Some(delta) = compose(&first, &second) else {
+                continue;
+            };
+            let mut editor = editor(&text);
+
+            delta.apply(&mut editor);
+
+            assert_eq!(
+                text_of(&editor),
+                text_of(&expected),
+                "{text:?} {first:?} {second:?}"
+            );
+
+            delta.undo(&mut editor);
+
+            assert_eq!(text_of(&editor), text);
+
+            composed += 1;
+        }
+
+        assert!(composed > 100);
+    }
+
+    #[test]
+    fn test_stable_delta_round_trips() {
+        let delta = TextEditDelta::Delete {
+            start: Cursor::new_with_affinity(2, 5, Affinity::After),
+            end: Cursor::new(1, 0),
+            deleted_text: "ab\ncd".to_string(),
+            direction: TextDeletionDirection::Backward,
+        };
+        let stable = StableTextEditDelta::from(&delta);
+
+        assert_eq!(
+            stable,
+            StableTextEditDelta::Delete {
+                start: StableCursor {
+                    line: 2,
+                    index: 5,
+                    affinity: 1
+                },
+                end: StableCursor {
+                    line: 1,
+                    index: 0,
+                    affinity: 0
+                },
+                deleted_text: "ab\ncd".to_string(),
+                direction: TextDeletionDirection::Backward,
+            }
+        );
+        assert_eq!(TextEditDelta::from(stable), delta);
+    }
+}
@@ -0,0 +1,110 @@
+//! Appearance of the system the application runs on, and the default colors of the
+//! widgets derived from it.
+//!
+//! The shell reports the system theme to every view with
+//! [`crate::ClewInstance::set_system_theme`], widgets read it with
+//! [`crate::BuildContext::system_theme`]. The colors to draw with come from
+//! [`crate::BuildContext::widget_theme`]: a [`WidgetTheme`] registered as a resource
+//! (see [`crate::resources`]) wins, otherwise it's derived from the system theme, so an
+//! application that registers nothing switches between dark and light along with the
+//! system.
+
+use crate::{ColorRgba, Lerp};
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Appearance {
+    #[default]
+    Light,
+    Dark,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SystemTheme {
+    pub appearance: Appearance,
+    /// Accent color the user picked in the system settings, `None` where the platform
+    /// has none or it isn't read.
+    pub accent_color: Option<ColorRgba>,
+    /// The user asked the system to reduce the motion of the interface, see
+    /// [`crate::BuildContext::reduced_motion`].
+    pub reduced_motion: bool,
+}
+
+impl SystemTheme {
+    pub fn is_dark(&self) -> bool {
+        self.appearance == Appearance::Dark
+    }
+}
+
+/// Colors the widgets are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WidgetTheme {
+    pub appearance: Appearance,
+    /// Behind the content of the view.
+    pub background: ColorRgba,
+    /// Of the controls and the popups on top of the background.
+    pub surface: ColorRgba,
+    pub border: ColorRgba,
+    pub text: ColorRgba,
+    /// Of the hints, the placeholders and the captions.
+    pub secondary_text: ColorRgba,
+    /// Of the selection, the focus rings and the values of the controls.
+    pub accent: ColorRgba,
+}
+
+impl WidgetTheme {
+    /// Accent of the themes where the system has none.
+    pub const DEFAULT_ACCENT: u32 = 0xFF357CCE;
+
+    pub fn light(accent: ColorRgba) -> Self {
+        Self {
+            appearance: Appearance::Light,
+            background: ColorRgba::from_hex(0xFFF5F5F5),
+            surface: ColorRgba::from_hex(0xFFFFFFFF),
+            border: ColorRgba::from_hex(0xFFC8C8C8),
+            text: ColorRgba::from_hex(0xFF1C1C1C),
+            secondary_text: ColorRgba::from_hex(0xFF6B6B6B),
+            accent,
+        }
+    }
+
+    pub fn dark(accent: ColorRgba) -> Self {
+        Self {
+            appearance: Appearance::Dark,
+            background: ColorRgba::from_hex(0xFF121212),
+            surface: ColorRgba::from_hex(0xFF272727),
+            border: ColorRgba::from_hex(0xFF414141),
+            text: ColorRgba::from_hex(0xFFE0E0E0),
+            secondary_text: ColorRgba::from_hex(0xFF999999),
+            accent,
+        }
+    }
+
+    /// Theme of the appearance of the system, with its accent color when it has one.
+    pub fn from_system(system_theme: &SystemTheme) -> Self {
+        let accent = system_theme
+            .accent_color
+            .unwrap_or(ColorRgba::from_hex(Self::DEFAULT_ACCENT));
+
+        match system_theme.appearance {
+            Appearance::Light => Self::light(accent),
+            Appearance::Dark => Self::dark(accent),
+        }
+    }
+
+    /// The surface moved toward the text by `amount`, e.g. of a hovered control.
+    pub fn raised(&self, amount: f32) -> ColorRgba {
+        self.surface.lerp(self.text, amount)
+    }
+
+    /// The surface moved toward the background by `amount`, e.g. of a pressed control
+    /// or a field.
+    pub fn sunken(&self, amount: f32) -> ColorRgba {
+        self.surface.lerp(self.background, amount)
+    }
+}
+
+impl Default for WidgetTheme {
+    fn default() -> Self {
+        Self::from_system(&SystemTheme::default())
+    }
+}
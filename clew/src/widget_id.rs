@@ -1,39 +1,75 @@
-use std::hash::{Hash, Hasher};
+use std::{
+    collections::{HashMap, HashSet},
+    hash::{Hash, Hasher},
+    panic::Location,
+};
 
 use rustc_hash::FxHasher;
 
-#[derive(Clone, Copy, Debug, Eq)]
-pub struct WidgetId {
+use crate::state::WidgetState;
+
+/// Id of a built widget, a hash of the call site of its builder, its explicit key and
+/// the keys of the enclosing scopes. It's computed once when the widget is built, the
+/// maps of the states pass it through instead of hashing it again.
+#[repr(transparent)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct WidgetId(u64);
+
+impl WidgetId {
+    #[track_caller]
+    pub fn auto() -> Self {
+        WidgetKey::auto().resolve(None)
+    }
+
+    #[track_caller]
+    pub fn auto_with_seed(seed: impl Hash) -> Self {
+        WidgetKey::auto_with_seed(seed).resolve(None)
+    }
+}
+
+/// Id of a widget as its builder knows it: the call site and the explicit key. It's
+/// resolved to the [`WidgetId`] against the keys of the enclosing scopes when the widget
+/// is built, see [`crate::BuildContext::widget_id`].
+#[derive(Clone, Copy, Debug)]
+pub struct WidgetKey {
     base: u64, // hash of file/line/column
-    seed: Option<u64>,
+    key: Option<u64>,
+    location: &'static Location<'static>,
 }
 
-impl Hash for WidgetId {
+impl Hash for WidgetKey {
     fn hash<H: Hasher>(&self, state: &mut H) {
         self.base.hash(state);
-        self.seed.hash(state);
+        self.key.hash(state);
     }
 }
 
-impl PartialEq for WidgetId {
+impl PartialEq for WidgetKey {
     fn eq(&self, other: &Self) -> bool {
-        self.base == other.base && self.seed == other.seed
+        self.base == other.base && self.key == other.key
     }
 }
 
-impl WidgetId {
+impl Eq for WidgetKey {}
+
+impl WidgetKey {
     #[track_caller]
     pub fn auto() -> Self {
         let location = std::panic::Location::caller();
 
+        // The name of the file rather than its address, which moves between the runs, so
+        // the ids and the seeds derived from them are the same in every run.
         let mut hasher = FxHasher::default();
-        std::ptr::hash(location.file().as_ptr(), &mut hasher);
+        location.file().hash(&mut hasher);
         location.line().hash(&mut hasher);
         location.column().hash(&mut hasher);
 
         Self {
             base: hasher.finish(),
-            seed: None,
+            key: None,
+            location,
         }
     }
 
@@ -42,17 +78,97 @@ impl WidgetId {
         let mut hasher = FxHasher::default();
         seed.hash(&mut hasher);
 
-        Self::auto().with_seed(Some(hasher.finish()))
+        Self {
+            key: Some(hasher.finish()),
+            ..Self::auto()
+        }
     }
 
-    pub fn with_seed(mut self, seed: Option<u64>) -> Self {
-        if self.seed.is_none() {
-            self.seed = seed;
+    /// The explicit key combined with the keys of the enclosing scopes, `seed` is
+    /// [`crate::BuildContext`]'s one. The same key at the same call site gives other ids
+    /// in other subtrees, and nothing built next to the subtree changes the ids in it.
+    ///
+    /// Before the keys were combined, an explicit key replaced the keys of the scopes, so
+    /// a keyed widget built inside a scope has another id than it used to have, and an
+    /// id made with [`WidgetId::auto_with_seed`] outside of the scope no longer finds
+    /// it. Outside of any scope the key alone is the seed, as before.
+    pub fn key_path(&self, seed: Option<u64>) -> Option<u64> {
+        match (seed, self.key) {
+            (None, key) => key,
+            (Some(scope), None) => Some(scope),
+            (Some(scope), Some(key)) => {
+                let mut hasher = FxHasher::default();
+                scope.hash(&mut hasher);
+                key.hash(&mut hasher);
+
+                Some(hasher.finish())
+            }
         }
-        self
+    }
+
+    /// Id of the widget built in the scope with the `seed`.
+    pub fn resolve(&self, seed: Option<u64>) -> WidgetId {
+        let Some(key_path) = self.key_path(seed) else {
+            return WidgetId(self.base);
+        };
+
+        let mut hasher = FxHasher::default();
+        self.base.hash(&mut hasher);
+        key_path.hash(&mut hasher);
+
+        WidgetId(hasher.finish())
+    }
+
+    /// Call site of the builder the id is derived from.
+    pub fn location(&self) -> &'static Location<'static> {
+        self.location
+    }
+}
+
+/// Where the id of a widget comes from, recorded for [`crate::id_map`] while a map is
+/// captured.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct WidgetIdInfo {
+    pub(crate) key_path: Option<u64>,
+    pub(crate) location: &'static Location<'static>,
+}
+
+/// Hasher of the maps keyed by [`WidgetId`]. The id is a hash already, the hasher passes
+/// it through instead of hashing it again on every lookup.
+#[derive(Default)]
+pub struct WidgetIdHasher(u64);
+
+impl Hasher for WidgetIdHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        // Only the ids are meant to be hashed with it, other keys still get a hash.
+        let mut hasher = FxHasher::default();
+        self.0.hash(&mut hasher);
+        hasher.write(bytes);
+        self.0 = hasher.finish();
+    }
+
+    fn write_u64(&mut self, value: u64) {
+        self.0 = value;
     }
 }
 
+/// Builds the hashers of [`WidgetIdMap`] and [`WidgetIdSet`]. With the `fast-id-hash`
+/// feature, on by default, it's the pass through [`WidgetIdHasher`], otherwise the
+/// SipHash of the standard library.
+#[cfg(feature = "fast-id-hash")]
+pub type BuildWidgetIdHasher = std::hash::BuildHasherDefault<WidgetIdHasher>;
+
+#[cfg(not(feature = "fast-id-hash"))]
+pub type BuildWidgetIdHasher = std::hash::RandomState;
+
+pub type WidgetIdMap<V> = HashMap<WidgetId, V, BuildWidgetIdHasher>;
+
+pub type WidgetIdSet = HashSet<WidgetId, BuildWidgetIdHasher>;
+
 pub struct LayoutWidget;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -68,6 +184,17 @@ impl WidgetType {
             name: std::any::type_name::<T>(),
         }
     }
+
+    pub(crate) fn of_state(state: &dyn WidgetState) -> Self {
+        Self {
+            type_id: state.as_any().type_id(),
+            name: state.type_name(),
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -79,7 +206,38 @@ pub struct WidgetRef {
 pub struct DebugBoundary;
 
 impl WidgetRef {
-    pub(crate) fn new(widget_type: WidgetType, id: WidgetId) -> Self {
+    pub fn new(widget_type: WidgetType, id: WidgetId) -> Self {
         Self { widget_type, id }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_explicit_key_is_combined_with_the_scope_keys() {
+        let keyed = |key: u64| WidgetKey::auto_with_seed(key);
+
+        // Outside of any scope the key alone is the key path.
+        assert_eq!(keyed(7).key_path(None), keyed(7).key);
+        assert_ne!(keyed(7).resolve(None), keyed(8).resolve(None));
+
+        let scoped = keyed(7).resolve(Some(1));
+        assert_ne!(scoped, keyed(7).resolve(None));
+        assert_ne!(scoped, keyed(7).resolve(Some(2)));
+        assert_ne!(scoped, keyed(8).resolve(Some(1)));
+        assert_ne!(keyed(7).key_path(Some(1)), keyed(7).key_path(Some(2)));
+    }
+
+    #[test]
+    fn test_id_is_a_transparent_hash() {
+        let id = WidgetId::auto();
+
+        assert_eq!(std::mem::size_of::<WidgetId>(), std::mem::size_of::<u64>());
+
+        let mut hasher = WidgetIdHasher::default();
+        id.hash(&mut hasher);
+        assert_eq!(hasher.finish(), id.0);
+    }
+}
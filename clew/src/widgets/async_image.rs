@@ -0,0 +1,454 @@
+use std::{fmt::Display, sync::Arc, time::Duration};
+
+use clew_derive::WidgetBuilder;
+
+use crate::{
+    AlignX, AlignY, Animation, ClewError, ColorRgba, ImageFit, Tween, Value, Vec2, WidgetId,
+    WidgetRef, WidgetType,
+    assets::BitmapLoad,
+    layout::{ContainerKind, DeriveWrapSize, LayoutCommand, WidgetPlacement},
+    render::{PixelExtension, RenderCommand, RenderContext},
+};
+
+use super::{FrameBuilder, builder::BuildContext, decorated_box::decoration};
+
+pub struct AsyncImageWidget;
+
+type SlotFn<'a> = Box<dyn FnOnce(&mut BuildContext) + 'a>;
+
+type ErrorSlotFn<'a> = Box<dyn FnOnce(&mut BuildContext, &ClewError) -> bool + 'a>;
+
+#[derive(WidgetBuilder)]
+pub struct AsyncImageBuilder<'a, S> {
+    frame: FrameBuilder,
+    key: &'a str,
+    source: S,
+    placeholder: Option<SlotFn<'a>>,
+    error: Option<ErrorSlotFn<'a>>,
+    fallback_colors: &'a [ColorRgba],
+    fade_in: Duration,
+}
+
+/// Where the image of an [`async_image`] is at.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ImageLoadState {
+    Loading,
+    Loaded,
+    Failed(ClewError),
+}
+
+pub struct AsyncImageResponse {
+    pub id: WidgetId,
+    pub state: ImageLoadState,
+}
+
+impl AsyncImageResponse {
+    pub fn is_loading(&self) -> bool {
+        self.state == ImageLoadState::Loading
+    }
+
+    pub fn is_loaded(&self) -> bool {
+        self.state == ImageLoadState::Loaded
+    }
+
+    pub fn error(&self) -> Option<&ClewError> {
+        match &self.state {
+            ImageLoadState::Failed(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+pub(crate) struct State {
+    key: Arc<str>,
+    /// Keeps the load alive while the widget is built, it's canceled when the last
+    /// widget waiting for it is gone.
+    load: Option<Arc<BitmapLoad>>,
+    /// Whether the image has been waited for, it fades in when it arrives then.
+    waited: bool,
+    opacity: Tween<f32>,
+}
+
+impl State {
+    fn new(key: &str) -> Self {
+        Self {
+            key: key.into(),
+            load: None,
+            waited: false,
+            opacity: Tween::new(1.),
+        }
+    }
+}
+
+impl<'a, S, F, E> AsyncImageBuilder<'a, S>
+where
+    S: FnOnce() -> F,
+    F: Future<Output = Result<Vec<u8>, E>> + Send + 'static,
+    E: Display,
+{
+    /// Shown while the image is loading.
+    pub fn placeholder(mut self, placeholder: impl FnOnce(&mut BuildContext) + 'a) -> Self {
+        self.placeholder = Some(Box::new(placeholder));
+
+        self
+    }
+
+    /// Shown when the image has failed to load, the image is loaded again when it
+    /// returns true, e.g. when its retry button is clicked.
+    pub fn error(mut self, error: impl FnOnce(&mut BuildContext, &ClewError) -> bool + 'a) -> Self {
+        self.error = Some(Box::new(error));
+
+        self
+    }
+
+    /// Colors the widget is filled with while the image isn't there, one of them is
+    /// picked by the key with [`BuildContext::stable_hash`], e.g. so every avatar gets a
+    /// color of its own that stays the same in every frame and every run.
+    pub fn fallback_colors(mut self, colors: &'a [ColorRgba]) -> Self {
+        self.fallback_colors = colors;
+
+        self
+    }
+
+    /// Duration of the fade-in of the image that has been waited for, zero shows it
+    /// right away.
+    pub fn fade_in(mut self, duration: Duration) -> Self {
+        self.fade_in = duration;
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build(mut self, context: &mut BuildContext) -> AsyncImageResponse {
+        let id = context.widget_id(self.frame.id);
+        let reduced_motion = context.reduced_motion();
+        let animation_delta_time = context.animation_delta_time();
+
+        let state = context
+            .widgets_states
+            .async_image
+            .access(id, || State::new(self.key));
+
+        if *state.key != *self.key {
+            *state = State::new(self.key);
+        }
+
+        let bitmap_size = context
+            .assets
+            .get_bitmap(self.key)
+            .map(|bitmap| Vec2::new(bitmap.width() as f32, bitmap.height() as f32));
+
+        let load_state = if bitmap_size.is_some() {
+            state.load = None;
+
+            if std::mem::take(&mut state.waited) && !self.fade_in.is_zero() && !reduced_motion {
+                state.opacity = Tween::new(0.).duration(self.fade_in);
+                state.opacity.tween_to(1.);
+            } else if state.opacity.in_progress() {
+                state.opacity.step(animation_delta_time);
+            }
+
+            if state.opacity.in_progress() {
+                context.request_frame();
+            }
+
+            ImageLoadState::Loaded
+        } else if let Some(err) = context.assets.bitmap_error(self.key) {
+            state.load = None;
+
+            ImageLoadState::Failed(err.clone())
+        } else {
+            if state.load.is_none() {
+                state.load = Some(context.assets.load_bitmap_async(self.key, self.source));
+            }
+
+            state.waited = true;
+
+            ImageLoadState::Loading
+        };
+
+        if bitmap_size.is_none() && !self.fallback_colors.is_empty() {
+            let index = context.stable_hash(self.key) % self.fallback_colors.len() as u64;
+            let color = self.fallback_colors[index as usize];
+            let fallback = context.scope(id, |ctx| decoration().color(color).build(ctx));
+
+            self.frame.backgrounds.insert(0, fallback);
+        }
+
+        let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+
+        if let Some(size) = bitmap_size {
+            context.push_layout_command(LayoutCommand::Leaf {
+                widget_ref: WidgetRef::new(WidgetType::of::<AsyncImageWidget>(), id),
+                backgrounds,
+                foregrounds,
+                padding: self.frame.padding,
+                margin: self.frame.margin,
+                constraints: self.frame.constraints,
+                size: self.frame.size,
+                zindex: self.frame.zindex,
+                derive_wrap_size: DeriveWrapSize::Fixed(size),
+                clip: self.frame.clip,
+            });
+
+            return AsyncImageResponse {
+                id,
+                state: load_state,
+            };
+        }
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds,
+            foregrounds,
+            zindex: self.frame.zindex,
+            padding: self.frame.padding,
+            margin: self.frame.margin,
+            kind: ContainerKind::ZStack {
+                align_x: AlignX::Center,
+                align_y: AlignY::Center,
+            },
+            size: self.frame.size,
+            constraints: self.frame.constraints,
+            clip: self.frame.clip,
+        });
+
+        let mut retry = false;
+
+        context.handle_decoration_defer(|context| match &load_state {
+            ImageLoadState::Failed(err) => {
+                if let Some(error) = self.error.take() {
+                    retry = error(context, err);
+                }
+            }
+            _ => {
+                if let Some(placeholder) = self.placeholder.take() {
+                    placeholder(context);
+                }
+            }
+        });
+        context.push_layout_command(LayoutCommand::EndContainer);
+
+        if retry {
+            context.assets.forget_bitmap_error(self.key);
+            context.request_frame();
+        }
+
+        AsyncImageResponse {
+            id,
+            state: load_state,
+        }
+    }
+}
+
+/// Image loaded in the background, the source is called to start the load and returns
+/// the future that resolves to the encoded PNG or JPEG data, the widget doesn't fetch
+/// anything itself. The image is decoded off the main thread and cached in
+/// [`crate::assets::Assets`] under the key, so the widgets with the same key share it
+/// and decode it once. Wraps to the size of the image in pixels.
+///
+/// The load is canceled when no widget waiting for it is built anymore, e.g. the rows
+/// of a [`super::virtual_list`] scrolled out of view, and started again when one is.
+#[track_caller]
+pub fn async_image<S>(key: &str, source: S) -> AsyncImageBuilder<'_, S> {
+    AsyncImageBuilder {
+        frame: FrameBuilder::new(),
+        key,
+        source,
+        placeholder: None,
+        error: None,
+        fallback_colors: &[],
+        fade_in: Duration::from_millis(200),
+    }
+}
+
+pub(crate) fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &State) {
+    ctx.push_command(
+        placement.zindex,
+        RenderCommand::Image {
+            boundary: placement.rect.px(ctx),
+            asset_id: state.key.clone(),
+            tint_color: None,
+            fit: ImageFit::Fill,
+            opacity: state.opacity.value(),
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+    };
+
+    use crate::{ClewHost, Size, prelude::*, render::Fill, testing};
+
+    use super::*;
+
+    struct DropFlag(Arc<AtomicBool>);
+
+    impl Drop for DropFlag {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn png() -> Vec<u8> {
+        let mut data = Vec::new();
+
+        image::RgbaImage::from_pixel(3, 2, image::Rgba([0x2F, 0x80, 0xC4, 0xFF]))
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        data
+    }
+
+    #[tokio::test]
+    async fn test_rows_with_same_key_share_load() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let calls = Arc::new(AtomicUsize::new(0));
+
+        let mut frame = |host: &mut ClewHost<'static>| {
+            let mut states = Vec::new();
+
+            instance.frame(host, &mut |ctx| {
+                for index in 0..2 {
+                    let calls = calls.clone();
+                    let response = async_image("avatar", move || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+
+                        async { Ok::<_, String>(png()) }
+                    })
+                    .id(index)
+                    .build(ctx);
+
+                    states.push(response.state);
+                }
+            });
+
+            states
+        };
+
+        assert_eq!(
+            frame(&mut host),
+            [ImageLoadState::Loading, ImageLoadState::Loading]
+        );
+
+        while !host.assets.receive_loaded() {
+            tokio::time::sleep(Duration::from_millis(1)).await;
+        }
+
+        assert_eq!(
+            frame(&mut host),
+            [ImageLoadState::Loaded, ImageLoadState::Loaded]
+        );
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        let commands = instance.ui_state().render_state.commands();
+        let images: Vec<_> = commands
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::Image {
+                    boundary, opacity, ..
+                } => Some((boundary.width, boundary.height, *opacity)),
+                _ => None,
+            })
+            .collect();
+
+        // Waited for, so both start the fade-in.
+        assert_eq!(images, [(3., 2., 0.); 2]);
+    }
+
+    #[tokio::test]
+    async fn test_load_is_canceled_when_not_built() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let calls = Arc::new(AtomicUsize::new(0));
+        let dropped = Arc::new(AtomicBool::new(false));
+
+        let mut frame = |host: &mut ClewHost<'static>, visible: bool| {
+            instance.frame(host, &mut |ctx| {
+                if visible {
+                    let calls = calls.clone();
+                    let guard = DropFlag(dropped.clone());
+
+                    async_image("avatar", move || {
+                        calls.fetch_add(1, Ordering::SeqCst);
+
+                        async move {
+                            let _guard = guard;
+
+                            std::future::pending::<Result<Vec<u8>, String>>().await
+                        }
+                    })
+                    .build(ctx);
+                }
+            });
+        };
+
+        frame(&mut host, true);
+        frame(&mut host, true);
+        assert_eq!(calls.load(Ordering::SeqCst), 1);
+
+        frame(&mut host, false);
+
+        for _ in 0..10 {
+            tokio::task::yield_now().await;
+        }
+
+        assert!(dropped.load(Ordering::SeqCst));
+
+        frame(&mut host, true);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_fallback_color_is_picked_by_the_key() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let colors = [
+            ColorRgba::from_hex(0xFFE57373),
+            ColorRgba::from_hex(0xFF64B5F6),
+            ColorRgba::from_hex(0xFF81C784),
+        ];
+
+        let mut frame = |keys: &[&str]| {
+            let mut expected = Vec::new();
+
+            instance.frame(&mut host, &mut |ctx| {
+                for key in keys {
+                    expected.push(colors[(ctx.stable_hash(*key) % 3) as usize]);
+
+                    async_image(key, std::future::pending::<Result<Vec<u8>, String>>)
+                        .id(*key)
+                        .size(Size::square(32.))
+                        .fallback_colors(&colors)
+                        .build(ctx);
+                }
+            });
+
+            let fills: Vec<_> = instance
+                .ui_state()
+                .render_state
+                .commands()
+                .iter()
+                .filter_map(|command| match command {
+                    RenderCommand::Rect {
+                        fill: Some(Fill::Color(color)),
+                        ..
+                    } => Some(*color),
+                    _ => None,
+                })
+                .collect();
+
+            assert_eq!(fills, expected);
+
+            fills
+        };
+
+        let first = frame(&["ada", "grace", "alan", "edsger"]);
+
+        assert_eq!(frame(&["ada", "grace", "alan", "edsger"]), first);
+    }
+}
@@ -2,16 +2,31 @@ use std::{
     any::Any,
     hash::{Hash, Hasher},
     sync::Arc,
+    time::{Duration, Instant},
 };
 
 use rustc_hash::{FxHashSet, FxHasher};
 use smallvec::SmallVec;
 
 use crate::{
-    Animation, Constraints, ShortcutId, ShortcutModifierId, ShortcutsManager, ShortcutsRegistry, Size, Value, View, ViewId, WidgetId, WidgetRef, interaction::InteractionState, io::UserInput, layout::LayoutCommand, state::{UiState, WidgetsStates}, text::{FontResources, TextsResources}
+    Animation, ClewError, Constraints, EdgeInsets, StableRng, assets::Assets, LayoutDirection, Rect, capture::ImageCapture, KeyBinding, ShortcutId, ShortcutModifierId, ShortcutsManager, ShortcutsRegistry, Size, Value, Vec2, View, ViewId, WidgetId, WidgetIdSet, WidgetKey, WidgetRef, focus::FocusChain, id_map::IdMap, widget_id::WidgetIdInfo, interaction::InteractionState, io::{Keyboard, TextInputAction, UserInput}, layout::{ContainerKind, DeriveWrapSize, LayoutCommand}, phases::FramePhases, resources::Resources, state::{ModalDialogGuard, ModalDialogs, UiState, ViewConfig, WidgetState, WidgetsStates}, text::{FontResources, MeasureTextStyle, TextsResources}
+};
+use crate::snapshot::{UI_STATE_SNAPSHOT_VERSION, UiStateSnapshot};
+use crate::announcement::{Announcements, Politeness};
+use crate::size_class::{Breakpoints, ViewSizeClass};
+use crate::theme::{SystemTheme, WidgetTheme};
+
+use super::{
+    FrameBuilder,
+    decorated_box::{DecorationBuilder, Transition, apply_transition},
+    editable_text::OsEvent,
+    file_drop_target::FileDrops,
+    frame::FrameBuilderFlags,
+    gesture_detector::KeyboardAccess,
+    portal::Portal,
+    safe_area::SafeAreaPadding,
+    scope::scope_key,
 };
-
-use super::{FrameBuilder, decorated_box::DecorationBuilder, frame::FrameBuilderFlags};
 
 pub struct PositionedChildMeta {
     pub index: u32,
@@ -25,13 +40,41 @@ pub(crate) type DecorationDeferFn =
 
 #[derive(Debug)]
 pub enum ApplicationEvent {
-    Wake { view_id: ViewId },
+    Wake {
+        view_id: ViewId,
+    },
+    /// Has to be run by the shell on the main thread as soon as possible.
+    RunOnMainThread(MainThreadTask),
+    /// An asset loaded in the background is ready, see [`crate::assets::Assets::load_svg_batch`].
+    AssetLoaded,
+}
+
+pub struct MainThreadTask(Box<dyn FnOnce() + Send>);
+
+impl MainThreadTask {
+    pub fn run(self) {
+        (self.0)()
+    }
+}
+
+impl std::fmt::Debug for MainThreadTask {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("MainThreadTask")
+    }
 }
 
 pub trait ApplicationEventLoopProxy: Send + Sync {
     fn send_event(&self, event: ApplicationEvent);
 }
 
+/// Proxy of a host without an event loop, e.g. one rendering a document offscreen, the
+/// events sent to it are dropped.
+pub struct NoopEventLoopProxy;
+
+impl ApplicationEventLoopProxy for NoopEventLoopProxy {
+    fn send_event(&self, _: ApplicationEvent) {}
+}
+
 pub struct UserDataStack<'a> {
     data: &'a (dyn Any + Send),
     parent: Option<&'a UserDataStack<'a>>,
@@ -42,18 +85,24 @@ pub struct MutUserDataStack<'a> {
     parent: Option<&'a mut MutUserDataStack<'a>>,
 }
 
+pub(crate) type AsyncSender = tokio::sync::mpsc::UnboundedSender<Box<dyn Any + Send + Sync>>;
+
 pub struct BuildContext<'a, 'b> {
     pub(crate) ignore_pointer: bool,
     pub(crate) layout_commands: &'a mut Vec<LayoutCommand>,
     pub(crate) widgets_states: &'a mut WidgetsStates,
-    pub(crate) event_queue: &'a mut Vec<Arc<dyn Any + Send>>,
-    pub(crate) next_event_queue: &'a mut Vec<Arc<dyn Any + Send>>,
-    pub(crate) broadcast_event_queue: &'a mut Vec<Arc<dyn Any + Send>>,
+    pub(crate) event_queue: &'a mut Vec<Arc<dyn Any + Send + Sync>>,
+    pub(crate) next_event_queue: &'a mut Vec<Arc<dyn Any + Send + Sync>>,
+    pub(crate) broadcast_event_queue: &'a mut Vec<Arc<dyn Any + Send + Sync>>,
     pub(crate) text: &'a mut TextsResources<'b>,
     pub(crate) fonts: &'a mut FontResources,
     pub(crate) view: &'a View,
-    pub(crate) async_tx: &'a mut tokio::sync::mpsc::UnboundedSender<Box<dyn Any + Send>>,
-    pub(crate) broadcast_async_tx: &'a mut tokio::sync::mpsc::UnboundedSender<Box<dyn Any + Send>>,
+    pub(crate) obscured_insets: EdgeInsets,
+    pub(crate) layout_direction: LayoutDirection,
+    pub(crate) system_theme: SystemTheme,
+    pub(crate) reduced_motion: bool,
+    pub(crate) async_tx: &'a mut AsyncSender,
+    pub(crate) broadcast_async_tx: &'a mut AsyncSender,
     pub(crate) event_loop_proxy: Arc<dyn ApplicationEventLoopProxy>,
     pub(crate) id_seed: Option<u64>,
     // pub(crate) user_data: Vec<Box<dyn Any + Send>>,
@@ -61,18 +110,34 @@ pub struct BuildContext<'a, 'b> {
     pub(crate) scoped_user_data: Option<&'a mut MutUserDataStack<'a>>,
     pub(crate) backgrounds: &'a mut SmallVec<[WidgetRef; 8]>,
     pub(crate) foregrounds: &'a mut SmallVec<[WidgetRef; 8]>,
-    pub(crate) non_interactable: &'a mut FxHashSet<WidgetId>,
+    pub(crate) non_interactable: &'a mut WidgetIdSet,
+    pub(crate) focus_chain: &'a mut FocusChain,
+    pub(crate) portals: &'a mut Vec<Portal>,
+    pub(crate) portal_order: &'a mut Vec<WidgetId>,
     pub(crate) phase_allocator: &'a bumpalo::Bump,
     pub(crate) input: &'a mut UserInput,
     pub(crate) interaction: &'a mut InteractionState,
     pub(crate) delta_time: f32,
+    pub(crate) frame_index: u64,
+    pub(crate) time_since_start: Duration,
+    pub(crate) deadline: Option<Instant>,
     pub(crate) animations_stepped_this_frame: &'a mut FxHashSet<usize>,
     pub(crate) child_index: u32,
     pub(crate) child_index_stack: Vec<u32>,
-    pub(crate) decoration_defer: Vec<(WidgetId, u32, DecorationDeferFn)>,
+    pub(crate) decoration_defer: Vec<(WidgetId, u32, DecorationDeferFn, Option<Transition>)>,
     pub(crate) decoration_defer_start_stack: Vec<usize>,
     pub(crate) shortcuts_manager: &'a mut ShortcutsManager,
     pub(crate) shortcuts_registry: &'a mut ShortcutsRegistry,
+    pub(crate) modal_dialogs: &'a ModalDialogs,
+    pub(crate) resources: &'a mut Resources,
+    pub(crate) assets: &'a mut Assets<'b>,
+    pub(crate) invalidated_rects: &'a mut Vec<Rect>,
+    pub(crate) image_captures: &'a mut Vec<ImageCapture>,
+    pub(crate) phases: &'a mut FramePhases,
+    pub(crate) announcements: &'a mut Announcements,
+    pub(crate) file_drops: &'a mut FileDrops,
+    pub(crate) os_events: &'a mut SmallVec<[OsEvent; 4]>,
+    pub(crate) view_config: &'a mut ViewConfig,
 }
 
 pub trait Resolve<V> {
@@ -96,17 +161,20 @@ where
 }
 
 impl<'a, 'b> BuildContext<'a, 'b> {
-    // #[allow(clippy::too_many_arguments)]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         ui_state: &'a mut UiState,
         texts: &'a mut TextsResources<'b>,
         fonts: &'a mut FontResources,
-        broadcast_event_queue: &'a mut Vec<Arc<dyn Any + Send>>,
-        broadcast_async_tx: &'a mut tokio::sync::mpsc::UnboundedSender<Box<dyn Any + Send>>,
+        resources: &'a mut Resources,
+        assets: &'a mut Assets<'b>,
+        broadcast_event_queue: &'a mut Vec<Arc<dyn Any + Send + Sync>>,
+        broadcast_async_tx: &'a mut AsyncSender,
         event_loop_proxy: Arc<dyn ApplicationEventLoopProxy>,
         delta_time: f32,
     ) -> BuildContext<'a, 'b> {
         ui_state.animations_stepped_this_frame.clear();
+        let reduced_motion = ui_state.reduced_motion();
 
         BuildContext {
             child_index: 0,
@@ -118,6 +186,10 @@ impl<'a, 'b> BuildContext<'a, 'b> {
             text: texts,
             fonts,
             view: &ui_state.view,
+            obscured_insets: ui_state.view_config.obscured_insets_animation.value(),
+            layout_direction: ui_state.layout_direction,
+            system_theme: ui_state.system_theme,
+            reduced_motion,
             async_tx: &mut ui_state.async_tx,
             broadcast_event_queue,
             broadcast_async_tx,
@@ -130,14 +202,34 @@ impl<'a, 'b> BuildContext<'a, 'b> {
             input: &mut ui_state.user_input,
             interaction: &mut ui_state.interaction_state,
             delta_time,
+            frame_index: ui_state.frame_index,
+            time_since_start: ui_state.time_since_start,
+            deadline: ui_state
+                .view_config
+                .frame_budget
+                .map(|budget| Instant::now() + budget),
             animations_stepped_this_frame: &mut ui_state.animations_stepped_this_frame,
             foregrounds: &mut ui_state.foregrounds,
             non_interactable: &mut ui_state.non_interactable,
+            focus_chain: &mut ui_state.focus_chain,
+            portals: &mut ui_state.portals,
+            portal_order: &mut ui_state.portal_order,
             child_index_stack: Vec::new(),
             decoration_defer: Vec::new(),
             decoration_defer_start_stack: Vec::new(),
             shortcuts_manager: &mut ui_state.shortcuts_manager,
             shortcuts_registry: &mut ui_state.shortcuts_registry,
+            modal_dialogs: &ui_state.modal_dialogs,
+            resources,
+            assets,
+            invalidated_rects: &mut ui_state.render_state.invalidated_rects,
+            image_captures: &mut ui_state.image_captures,
+            phases: &mut ui_state.phases,
+            announcements: &mut ui_state.announcements,
+            file_drops: &mut ui_state.file_drops,
+            os_events: &mut ui_state.os_events,
+            // Goes last, the fields above read the config.
+            view_config: &mut ui_state.view_config,
         }
     }
     /// Advances an animation by the current frame's delta time.
@@ -156,11 +248,22 @@ impl<'a, 'b> BuildContext<'a, 'b> {
             let id = animation as *mut T as usize;
 
             if self.animations_stepped_this_frame.insert(id) {
-                animation.step(self.delta_time)
+                animation.step(self.animation_delta_time())
             }
         }
     }
 
+    /// Time to step the animations by this frame. Under reduced motion the durations
+    /// are scaled to zero, so it's infinite and the animations jump to their end, see
+    /// [`Animation::step`].
+    pub fn animation_delta_time(&self) -> f32 {
+        if self.reduced_motion {
+            f32::INFINITY
+        } else {
+            self.delta_time
+        }
+    }
+
     pub fn child_index(&self) -> u32 {
         self.child_index
     }
@@ -173,10 +276,223 @@ impl<'a, 'b> BuildContext<'a, 'b> {
         self.input
     }
 
+    /// Takes the text typed in this frame, so the widgets built after this one don't
+    /// see it, e.g. a focused text field doesn't insert the letter a popup has handled.
+    pub fn take_text_input(&mut self) -> String {
+        self.input
+            .text_input_actions
+            .retain(|action| !matches!(action, TextInputAction::Insert));
+
+        std::mem::take(&mut self.input.text_input)
+    }
+
     pub fn view(&self) -> &View {
         self.view
     }
 
+    /// Insets of the view the content shouldn't be under, the larger of the safe area of
+    /// the display and the part covered by the platform, e.g. by an on-screen keyboard.
+    /// What the enclosing [`super::safe_area`] widgets pad by already is left out.
+    pub fn safe_area_insets(&self) -> EdgeInsets {
+        let insets = self.view.safe_area.max(self.obscured_insets);
+        let padded = self
+            .of::<SafeAreaPadding>()
+            .map(|padding| padding.0)
+            .unwrap_or_default();
+
+        EdgeInsets {
+            top: (insets.top - padded.top).max(0.),
+            left: (insets.left - padded.left).max(0.),
+            right: (insets.right - padded.right).max(0.),
+            bottom: (insets.bottom - padded.bottom).max(0.),
+        }
+    }
+
+    pub fn fonts(&self) -> &FontResources {
+        self.fonts
+    }
+
+    /// Size in logical pixels a line of the text takes in the style, e.g. to decide
+    /// during the build what fits into the space the previous layout has given.
+    pub fn measure_text(&mut self, text: &str, style: MeasureTextStyle) -> Vec2 {
+        if let Some(name) = style.font_family {
+            self.fonts.request_family(name);
+        }
+
+        self.text.measure(self.view, self.fonts, text, style) / self.view.scale_factor
+    }
+
+    /// Time in seconds elapsed since the previous frame.
+    pub fn delta_time(&self) -> f32 {
+        self.delta_time
+    }
+
+    /// When the build of this frame should be done by, see
+    /// [`crate::ClewInstance::set_frame_budget`]. Widgets that can put work off to the
+    /// next frames check it, e.g. [`super::virtual_list::virtual_list`] draws
+    /// placeholders past it.
+    pub fn deadline(&self) -> Option<Instant> {
+        self.deadline
+    }
+
+    pub fn is_past_deadline(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
+
+    /// Number of the frame being built, the first frame of the view is 0.
+    pub fn frame_index(&self) -> u64 {
+        self.frame_index
+    }
+
+    /// Delta times of the frames up to this one added up, the time spent paused doesn't
+    /// count. For the phase of endless effects, e.g. a shimmer, without keeping an
+    /// [`Animation`] around.
+    pub fn time_since_start(&self) -> Duration {
+        self.time_since_start
+    }
+
+    /// Hash of the value combined with the keys of the enclosing [`BuildContext::scope`]s.
+    /// It's the same in every frame and every run of the program for the same value and
+    /// keys, whatever is built around it, e.g. to pick the color of an avatar by the name
+    /// of the user, see [`super::async_image::AsyncImageBuilder::fallback_colors`].
+    pub fn stable_hash(&self, value: impl Hash) -> u64 {
+        let mut hasher = FxHasher::default();
+        self.id_seed.hash(&mut hasher);
+        value.hash(&mut hasher);
+
+        hasher.finish()
+    }
+
+    /// Generator seeded with [`BuildContext::stable_hash`] of the key, it yields the same
+    /// values in every frame, e.g. for the staggered delays of the items of a list.
+    pub fn stable_rng(&self, key: impl Hash) -> StableRng {
+        StableRng::new(self.stable_hash(key))
+    }
+
+    pub fn layout_direction(&self) -> LayoutDirection {
+        self.layout_direction
+    }
+
+    /// Appearance of the system reported by the shell, see [`crate::theme`].
+    pub fn system_theme(&self) -> SystemTheme {
+        self.system_theme
+    }
+
+    /// Whether the user asked to reduce the motion of the interface, from the system
+    /// theme unless overridden with [`crate::ClewInstance::set_reduced_motion`].
+    /// Animations stepped with [`BuildContext::step_animation`] already jump to their
+    /// end, the widgets only check it for the motion they drive themselves.
+    pub fn reduced_motion(&self) -> bool {
+        self.reduced_motion
+    }
+
+    /// The [`WidgetTheme`] registered as a resource, otherwise the one of the system
+    /// theme, so it follows the switches of the system between dark and light.
+    pub fn widget_theme(&self) -> WidgetTheme {
+        self.resources
+            .get::<WidgetTheme>()
+            .copied()
+            .unwrap_or_else(|| WidgetTheme::from_system(&self.system_theme))
+    }
+
+    /// Logical size of the view this frame is laid out at and its class, from the
+    /// [`Breakpoints`] registered as a resource or the default ones, see
+    /// [`crate::size_class`].
+    pub fn size_class(&self) -> ViewSizeClass {
+        let width = self.view.size.width as f32 / self.view.scale_factor;
+        let height = self.view.size.height as f32 / self.view.scale_factor;
+        let breakpoints = self
+            .resources
+            .get::<Breakpoints>()
+            .copied()
+            .unwrap_or_default();
+
+        ViewSizeClass {
+            width,
+            height,
+            class: breakpoints.classify(width),
+        }
+    }
+
+    /// Queues the text to be read by the screen readers, see [`crate::announcement`].
+    pub fn announce(&mut self, text: impl Into<String>, politeness: Politeness) {
+        self.announcements.queue(text.into(), politeness);
+    }
+
+    /// Announces the value of the focused widget, e.g. the text of a field, once it has
+    /// stayed the same for [`crate::announcement::VALUE_ANNOUNCEMENT_DELAY`]. Each call
+    /// puts the announcement off again, it's dropped if the widget loses the focus.
+    pub fn announce_value(&mut self, id: WidgetId, text: impl Into<String>) {
+        if self.interaction.is_focused(&id) {
+            self.announcements.queue_value(id, text.into());
+        }
+    }
+
+    /// Whether the window of the view has the keyboard focus.
+    pub fn window_focused(&self) -> bool {
+        self.input.window_focused
+    }
+
+    /// Time since the last user input in the view.
+    pub fn idle_duration(&self) -> Duration {
+        self.input.idle_timer.idle_duration()
+    }
+
+    /// Ids of the widgets with a retained state built so far in this frame, see
+    /// [`crate::id_map`].
+    pub fn capture_id_map(&self) -> IdMap {
+        self.widgets_states.id_map()
+    }
+
+    /// Captures the serializable states of the widgets built inside of
+    /// [`crate::scope`] with the same key during the last frame.
+    pub fn snapshot_ui_state(&self, scope: impl Hash) -> UiStateSnapshot {
+        self.widgets_states.snapshot(scope_key(scope))
+    }
+
+    /// Re-seeds the widget states from the snapshot, the widgets pick up the values the
+    /// next time they're built. Values of the widgets that aren't built by the end of the
+    /// next frame are dropped.
+    pub fn restore_ui_state(&mut self, snapshot: UiStateSnapshot) -> Result<(), ClewError> {
+        if snapshot.version != UI_STATE_SNAPSHOT_VERSION {
+            return Err(ClewError::IncompatibleSnapshot {
+                version: snapshot.version,
+            });
+        }
+
+        self.widgets_states.snapshots.restore(snapshot);
+
+        Ok(())
+    }
+
+    /// Marks the rect, in view coordinates, to be repainted this frame. Changes of the
+    /// render commands are found automatically, this is for content that can change
+    /// without them, and lets commands appear or disappear inside the rect, e.g. a
+    /// blinking caret, without repainting the whole view.
+    pub fn invalidate_rect(&mut self, rect: Rect) {
+        self.invalidated_rects.push(rect);
+    }
+
+    /// Widget with the keyboard focus, it's moved with Tab and the arrows at the start
+    /// of the frame.
+    pub fn focused(&self) -> Option<WidgetId> {
+        self.interaction.focused
+    }
+
+    /// Moves focus to the widget as a result of keyboard navigation (e.g. a shortcut),
+    /// so the focus ring is shown and the widget is scrolled into view.
+    pub fn focus_with_keyboard(&mut self, id: WidgetId) {
+        self.interaction.focus_with_keyboard(&id);
+    }
+
+    /// Scrolls the enclosing scroll areas, nested ones included, so the widget becomes
+    /// visible. The widget is looked up in the layout of this frame and the areas
+    /// animate to it starting from the next one.
+    pub fn scroll_into_view(&mut self, id: WidgetId) {
+        self.interaction.scroll_into_view = Some(id);
+    }
+
     #[inline]
     pub fn handle_decoration_defer<F>(&mut self, callback: F)
     where
@@ -190,9 +506,10 @@ impl<'a, 'b> BuildContext<'a, 'b> {
         let start = self.decoration_defer_start_stack.pop().unwrap();
         let end = self.decoration_defer.len();
         let count = self.child_index.saturating_sub(1);
+        let animation_delta_time = self.animation_delta_time();
 
         for i in start..end {
-            let (id, child_index, defer) = &self.decoration_defer[i];
+            let (id, child_index, defer, transition) = &self.decoration_defer[i];
 
             let child_meta = PositionedChildMeta {
                 index: *child_index,
@@ -217,14 +534,27 @@ impl<'a, 'b> BuildContext<'a, 'b> {
             }
 
             state.gradients.append(&mut builder.gradients);
+            state.shadows.append(&mut builder.shadows);
 
             if builder.border.is_some() {
                 state.border = builder.border;
             }
 
+            if builder.border_fill.is_some() {
+                state.border_fill = builder.border_fill;
+            }
+
             if let Some(shape) = builder.shape {
                 state.shape = shape;
             }
+
+            if let Some(placement) = builder.placement {
+                self.widgets_states.decorator_placements.set(*id, placement);
+            }
+
+            if let Some(transition) = builder.transition.or(*transition) {
+                apply_transition(self.widgets_states, animation_delta_time, *id, transition);
+            }
         }
 
         self.decoration_defer.truncate(start);
@@ -279,6 +609,30 @@ impl<'a, 'b> BuildContext<'a, 'b> {
         None
     }
 
+    /// Application resource of type `T`, see [`crate::resources`].
+    pub fn get<T: 'static>(&self) -> Option<&T> {
+        self.resources.get::<T>()
+    }
+
+    /// Requests a change of the resource, it's applied after the view is built and
+    /// visible starting from the next frame.
+    pub fn update<T: 'static>(&mut self, update: impl FnOnce(&mut T) + 'static) {
+        self.resources.update(update);
+    }
+
+    /// Increases every time the resource changes, `None` if it isn't registered.
+    pub fn revision<T: 'static>(&self) -> Option<u64> {
+        self.resources.revision::<T>()
+    }
+
+    /// Registers the resource unless it's already registered, so it's safe to call
+    /// on every build of the window.
+    pub fn init_resource<T: 'static>(&mut self, create: impl FnOnce() -> T) {
+        if !self.resources.contains::<T>() {
+            self.resources.insert(create());
+        }
+    }
+
     pub fn is_shortcut_down<T: Into<ShortcutId>>(&self, shortcut_id: T) -> bool {
         self.shortcuts_manager.is_shortcut(shortcut_id)
     }
@@ -291,6 +645,28 @@ impl<'a, 'b> BuildContext<'a, 'b> {
         self.shortcuts_manager.has_modifier(modifier_id)
     }
 
+    /// Raw state of the keyboard for the widgets that handle the keys themselves rather
+    /// than with shortcuts. Only the widgets inside of a focused gesture detector, or of
+    /// one with [`super::gesture_detector::GestureDetectorBuilder::global_keys`], read
+    /// the keys, elsewhere no key reads as touched.
+    pub fn keyboard(&self) -> Keyboard<'_> {
+        let access = self.of::<KeyboardAccess>().copied().unwrap_or_default();
+
+        Keyboard::new(
+            self.input,
+            self.shortcuts_manager,
+            self.shortcuts_registry,
+            access.active,
+            access.shortcut_keys,
+        )
+    }
+
+    /// Chords of a shortcut sequence that waits for the rest of them, e.g. to show
+    /// in a status bar.
+    pub fn pending_chord(&self) -> Option<&[KeyBinding]> {
+        self.shortcuts_manager.pending_chord()
+    }
+
     // pub fn of_mut<T: 'static>(&mut self) -> Option<&mut T> {
     //     let mut current = self.scoped_user_data;
     //     while let Some(node) = current {
@@ -336,6 +712,30 @@ impl<'a, 'b> BuildContext<'a, 'b> {
 
     #[profiling::function]
     pub fn push_layout_command(&mut self, command: LayoutCommand) {
+        // Widgets inside of the ones that ignore the pointer don't take the hover.
+        if self.ignore_pointer {
+            match &command {
+                LayoutCommand::Leaf {
+                    widget_ref,
+                    backgrounds,
+                    foregrounds,
+                    ..
+                } => {
+                    let widget_refs = backgrounds.iter().chain(foregrounds).chain([widget_ref]);
+                    self.non_interactable.extend(widget_refs.map(|it| it.id));
+                }
+                LayoutCommand::BeginContainer {
+                    backgrounds,
+                    foregrounds,
+                    ..
+                } => {
+                    let widget_refs = backgrounds.iter().chain(foregrounds);
+                    self.non_interactable.extend(widget_refs.map(|it| it.id));
+                }
+                _ => {}
+            }
+        }
+
         match command {
             LayoutCommand::BeginContainer { .. } => {
                 self.child_index += 1;
@@ -352,6 +752,115 @@ impl<'a, 'b> BuildContext<'a, 'b> {
         self.layout_commands.push(command);
     }
 
+    /// Pushes a leaf widget with the size, constraints, padding, margin, z-index, clip,
+    /// offset and decorations of the frame. The decorations waiting on the context, e.g.
+    /// of an enclosing decorated box or gesture detector, go first.
+    pub fn push_leaf(
+        &mut self,
+        widget_ref: WidgetRef,
+        frame: &FrameBuilder,
+        derive_wrap_size: DeriveWrapSize,
+    ) {
+        let has_offset = frame.flags.contains(FrameBuilderFlags::OFFSET);
+
+        if has_offset {
+            self.push_layout_command(LayoutCommand::BeginOffset {
+                offset_x: frame.offset_x,
+                offset_y: frame.offset_y,
+            });
+        }
+
+        let mut backgrounds = std::mem::take(self.backgrounds);
+        backgrounds.extend_from_slice(&frame.backgrounds);
+
+        let mut foregrounds = std::mem::take(self.foregrounds);
+        foregrounds.extend_from_slice(&frame.foregrounds);
+
+        self.push_layout_command(LayoutCommand::Leaf {
+            widget_ref,
+            backgrounds,
+            foregrounds,
+            constraints: frame.constraints,
+            padding: frame.padding,
+            margin: frame.margin,
+            size: frame.size,
+            derive_wrap_size,
+            zindex: frame.zindex,
+            clip: frame.clip,
+        });
+
+        if has_offset {
+            self.push_layout_command(LayoutCommand::EndOffset);
+        }
+    }
+
+    /// Pushes a container of the kind with the fields of the frame around the widgets
+    /// built by the callback, the way the stacks are built.
+    pub fn push_container<F>(&mut self, frame: &mut FrameBuilder, kind: ContainerKind, callback: F)
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        let has_offset = frame.flags.contains(FrameBuilderFlags::OFFSET);
+
+        if has_offset {
+            self.push_layout_command(LayoutCommand::BeginOffset {
+                offset_x: frame.offset_x,
+                offset_y: frame.offset_y,
+            });
+        }
+
+        let (backgrounds, foregrounds) = self.resolve_decorators(frame);
+
+        self.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds,
+            foregrounds,
+            kind,
+            constraints: frame.constraints,
+            size: frame.size,
+            zindex: frame.zindex,
+            padding: frame.padding,
+            margin: frame.margin,
+            clip: frame.clip,
+        });
+
+        self.handle_decoration_defer(callback);
+        self.push_layout_command(LayoutCommand::EndContainer);
+
+        if has_offset {
+            self.push_layout_command(LayoutCommand::EndOffset);
+        }
+    }
+
+    /// Retained state of the widget with the id, it's created with `T::default()` when
+    /// the widget is built the first time and dropped after a frame the widget isn't
+    /// built in. A leaf with the id and the type `WidgetType::of::<T>()` is drawn by the
+    /// state, see [`WidgetState::render`].
+    ///
+    /// Panics if the id already holds a state of another type or one that's being built,
+    /// e.g. of the enclosing [`crate::widgets::stateful`].
+    pub fn state<T: WidgetState + Default>(&mut self, id: WidgetId) -> &mut T {
+        self.widgets_states.custom.mark_accessed(id);
+        self.widgets_states.get_or_insert_custom(id, T::default)
+    }
+
+    /// Id of the widget with the key built in the current scope.
+    #[inline]
+    pub fn widget_id(&mut self, key: WidgetKey) -> WidgetId {
+        let id = key.resolve(self.id_seed);
+
+        if let Some(infos) = &mut self.widgets_states.id_infos {
+            infos.insert(
+                id,
+                WidgetIdInfo {
+                    key_path: key.key_path(self.id_seed),
+                    location: key.location(),
+                },
+            );
+        }
+
+        id
+    }
+
     pub fn scope<F, T>(&mut self, key: impl Hash, callback: F) -> T
     where
         F: FnOnce(&mut BuildContext) -> T,
@@ -385,8 +894,10 @@ impl<'a, 'b> BuildContext<'a, 'b> {
         result
     }
 
+    /// Decorations of a container built with the frame: the ones waiting on the context
+    /// followed by the ones of the frame, which are taken out of it.
     #[inline]
-    pub(crate) fn resolve_decorators(
+    pub fn resolve_decorators(
         &mut self,
         frame: &mut FrameBuilder,
     ) -> (SmallVec<[WidgetRef; 8]>, SmallVec<[WidgetRef; 8]>) {
@@ -401,11 +912,19 @@ impl<'a, 'b> BuildContext<'a, 'b> {
         })
     }
 
-    pub fn emit<E: Any + Send + 'static>(&mut self, event: E) {
+    /// Asks the shell for another frame of the view, for widgets that change with time
+    /// while there is no input.
+    pub fn request_frame(&self) {
+        self.event_loop_proxy.send_event(ApplicationEvent::Wake {
+            view_id: self.view.id,
+        });
+    }
+
+    pub fn emit<E: Any + Send + Sync + 'static>(&mut self, event: E) {
         self.next_event_queue.push(Arc::new(event));
     }
 
-    pub fn spawn<E: Any + Send + 'static, F>(&self, future: F)
+    pub fn spawn<E: Any + Send + Sync + 'static, F>(&self, future: F)
     where
         F: Future<Output = E> + Send + 'static,
     {
@@ -420,11 +939,39 @@ impl<'a, 'b> BuildContext<'a, 'b> {
         });
     }
 
-    pub fn broadcast<E: Any + Send + 'static>(&mut self, event: E) {
+    /// Runs `f` on the main thread through the shell's event loop, resolves to `None`
+    /// if the shell dropped the task without running it.
+    pub fn run_on_main_thread<R, F>(
+        &self,
+        f: F,
+    ) -> impl Future<Output = Option<R>> + Send + 'static + use<R, F>
+    where
+        R: Send + 'static,
+        F: FnOnce() -> R + Send + 'static,
+    {
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        self.event_loop_proxy
+            .send_event(ApplicationEvent::RunOnMainThread(MainThreadTask(Box::new(
+                move || {
+                    let _ = tx.send(f());
+                },
+            ))));
+
+        async move { rx.await.ok() }
+    }
+
+    /// Suppresses the input of the view while a native modal dialog is up, until the
+    /// returned guard is dropped.
+    pub fn begin_modal_dialog(&self) -> ModalDialogGuard {
+        self.modal_dialogs.begin()
+    }
+
+    pub fn broadcast<E: Any + Send + Sync + 'static>(&mut self, event: E) {
         self.broadcast_event_queue.push(Arc::new(event));
     }
 
-    pub fn spawn_broadcast<E: Any + Send + 'static, F>(&self, future: F)
+    pub fn spawn_broadcast<E: Any + Send + Sync + 'static, F>(&self, future: F)
     where
         F: Future<Output = E> + Send + 'static,
     {
@@ -506,7 +1053,7 @@ macro_rules! impl_id {
     () => {
         #[track_caller]
         pub fn id(mut self, id: impl std::hash::Hash) -> Self {
-            self.id = WidgetId::auto_with_seed(id);
+            self.id = WidgetKey::auto_with_seed(id);
 
             self
         }
@@ -568,7 +1115,7 @@ pub trait WidgetBuilder {
     where
         Self: Sized,
     {
-        self.frame_mut().id = ::clew::WidgetId::auto_with_seed(id);
+        self.frame_mut().id = ::clew::WidgetKey::auto_with_seed(id);
         self.frame_mut().flags |= FrameBuilderFlags::ID;
         self
     }
@@ -0,0 +1,64 @@
+use clew_derive::WidgetBuilder;
+
+use crate::{
+    WidgetId,
+    layout::{ContainerKind, LayoutCommand},
+};
+
+use super::{FrameBuilder, builder::BuildContext};
+
+#[derive(WidgetBuilder)]
+pub struct CacheLayerBuilder {
+    frame: FrameBuilder,
+}
+
+impl CacheLayerBuilder {
+    /// Id the layer gets when built in the context, e.g. for
+    /// [`BuildContext::copy_subtree_as_image`].
+    pub fn resolve_id(&self, context: &BuildContext) -> WidgetId {
+        self.frame.id.resolve(context.id_seed)
+    }
+
+    #[profiling::function]
+    pub fn build<F, T>(mut self, context: &mut BuildContext, callback: F) -> T
+    where
+        F: FnOnce(&mut BuildContext) -> T,
+    {
+        let id = self.resolve_id(context);
+        let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds,
+            foregrounds,
+            zindex: self.frame.zindex,
+            padding: self.frame.padding,
+            margin: self.frame.margin,
+            kind: ContainerKind::Passthrough,
+            size: self.frame.size,
+            constraints: self.frame.constraints,
+            clip: self.frame.clip,
+        });
+
+        context.push_layout_command(LayoutCommand::BeginLayer { id });
+        let value = context.scope(self.frame.id, callback);
+        context.push_layout_command(LayoutCommand::EndLayer);
+
+        context.push_layout_command(LayoutCommand::EndContainer);
+
+        value
+    }
+}
+
+/// Draws the content into a layer the renderer keeps between frames and reuses
+/// until something inside of it changes, for subtrees that are expensive to draw
+/// and rarely change.
+///
+/// The layer records its commands in view space, so moving it, e.g. scrolling it
+/// or animating its position, changes every command and records it again. Caching
+/// only pays off for layers that stay in place.
+#[track_caller]
+pub fn cache_layer() -> CacheLayerBuilder {
+    CacheLayerBuilder {
+        frame: FrameBuilder::new(),
+    }
+}
@@ -1,11 +1,11 @@
-use crate::{WidgetId, impl_id, state::WidgetState};
+use crate::{WidgetKey, impl_id, state::WidgetState};
 use std::any::TypeId;
 
 use super::{builder::BuildContext, scope::scope};
 
 pub struct ComponentBuilder<'a, V: Component> {
     app: &'a mut V::App,
-    id: WidgetId,
+    id: WidgetKey,
 }
 
 pub struct ComponentWithStateBuilder<'a, V: Component> {
@@ -37,7 +37,7 @@ impl<'a, V: Component> ComponentBuilder<'a, V> {
 
 impl<'a, V: Component + Default + WidgetState> ComponentBuilder<'a, V> {
     pub fn build(&mut self, context: &mut BuildContext) {
-        let id = self.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.id);
         let (idx, mut state) = context.widgets_states.take_or_create(id, V::default);
 
         // Skip event processing for () type
@@ -49,7 +49,7 @@ impl<'a, V: Component + Default + WidgetState> ComponentBuilder<'a, V> {
             }
         }
 
-        context.widgets_states.custom.accessed_this_frame.insert(id);
+        context.widgets_states.custom.mark_accessed(id);
 
         scope(id).build(context, |context| {
             state.build(self.app, context);
@@ -78,6 +78,6 @@ impl<'a, V: Component> ComponentWithStateBuilder<'a, V> {
 pub fn component<'a, V: Component>(app: &'a mut V::App) -> ComponentBuilder<'a, V> {
     ComponentBuilder {
         app,
-        id: WidgetId::auto(),
+        id: WidgetKey::auto(),
     }
 }
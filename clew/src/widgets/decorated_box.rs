@@ -1,14 +1,17 @@
-use std::any::Any;
+use std::{any::Any, time::Duration};
 
 use clew_derive::WidgetBuilder;
 use smallvec::{SmallVec, smallvec};
 
 use crate::{
-    Border, BorderRadius, BorderSide, BoxShape, ColorRgba, Gradient, LinearGradient,
-    RadialGradient, WidgetId, WidgetRef, WidgetType, impl_id,
+    AlignX, AlignY, Border, BorderRadius, BorderSide, BoxShadow, BoxShape, ColorRgba,
+    DecoratorPlacement, EdgeInsets, Gradient, LinearGradient, RadialGradient, Size, Value,
+    WidgetId, WidgetKey, WidgetRef, WidgetType,
+    animation::{Animation, Lerp, Tween},
+    impl_id,
     layout::{DeriveWrapSize, LayoutCommand, WidgetPlacement},
     render::{Fill, PixelExtension, RenderCommand, RenderContext},
-    state::WidgetState,
+    state::{WidgetState, WidgetsStates},
 };
 
 use super::{
@@ -24,19 +27,38 @@ pub struct DecoratedBoxBuilder {
     frame: FrameBuilder,
     color: Option<ColorRgba>,
     gradients: SmallVec<[Gradient; 4]>,
+    shadows: SmallVec<[BoxShadow; 2]>,
     border_radius: Option<BorderRadius>,
     border: Option<Border>,
+    border_fill: Option<Fill>,
     shape: BoxShape,
+    transition: Option<Transition>,
 }
 
 pub struct DecorationBuilder {
-    pub(crate) id: WidgetId,
+    pub(crate) id: WidgetKey,
     pub(crate) color: Option<ColorRgba>,
     pub(crate) gradients: SmallVec<[Gradient; 4]>,
+    pub(crate) shadows: SmallVec<[BoxShadow; 2]>,
     pub(crate) border_radius: Option<BorderRadius>,
     pub(crate) border: Option<Border>,
+    pub(crate) border_fill: Option<Fill>,
     pub(crate) defer: Option<DecorationDeferFn>,
     pub(crate) shape: Option<BoxShape>,
+    pub(crate) transition: Option<Transition>,
+    pub(crate) placement: Option<DecoratorPlacement>,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Transition {
+    duration: Duration,
+    curve: fn(t: f32) -> f32,
+}
+
+/// Animated decoration of a widget, `target` is the decoration passed to the builder.
+pub(crate) struct TransitionState {
+    target: State,
+    tween: Tween<State>,
 }
 
 #[derive(Clone, PartialEq)]
@@ -44,8 +66,10 @@ pub struct State {
     pub(crate) shape: BoxShape,
     pub(crate) color: Option<ColorRgba>,
     pub(crate) gradients: SmallVec<[Gradient; 4]>,
+    pub(crate) shadows: SmallVec<[BoxShadow; 2]>,
     pub(crate) border_radius: Option<BorderRadius>,
     pub(crate) border: Option<Border>,
+    pub(crate) border_fill: Option<Fill>,
 }
 
 impl WidgetState for State {
@@ -65,6 +89,124 @@ impl WidgetState for State {
     }
 }
 
+impl Lerp for State {
+    /// Fills and shadows fade in and out, missing radii are treated as zero.
+    fn lerp(self, to: Self, t: f32) -> Self {
+        let color = match (self.color, to.color) {
+            (Some(from), Some(to)) => Some(from.lerp(to, t)),
+            (Some(from), None) if t < 1. => Some(from.lerp(from.with_opacity(0.), t)),
+            (None, Some(to)) => Some(to.with_opacity(0.).lerp(to, t)),
+            _ => None,
+        };
+
+        let gradients = if self.gradients.len() == to.gradients.len() {
+            self.gradients
+                .into_iter()
+                .zip(to.gradients)
+                .map(|(from, to)| from.lerp(to, t))
+                .collect()
+        } else {
+            to.gradients
+        };
+
+        let transparent = |shadow: BoxShadow| BoxShadow {
+            color: shadow.color.with_opacity(0.),
+            ..shadow
+        };
+        let shadows = if self.shadows.len() == to.shadows.len() {
+            self.shadows
+                .into_iter()
+                .zip(to.shadows)
+                .map(|(from, to)| from.lerp(to, t))
+                .collect()
+        } else if self.shadows.is_empty() {
+            to.shadows
+                .into_iter()
+                .map(|to| transparent(to).lerp(to, t))
+                .collect()
+        } else if to.shadows.is_empty() && t < 1. {
+            self.shadows
+                .into_iter()
+                .map(|from| from.lerp(transparent(from), t))
+                .collect()
+        } else {
+            to.shadows
+        };
+
+        let border_radius = match (self.border_radius, to.border_radius) {
+            (None, None) => None,
+            (from, to) => Some(
+                from.unwrap_or(BorderRadius::ZERO)
+                    .lerp(to.unwrap_or(BorderRadius::ZERO), t),
+            ),
+        };
+
+        let border = match (self.border, to.border) {
+            (None, None) => None,
+            (from, to) => Some(from.unwrap_or_default().lerp(to.unwrap_or_default(), t)),
+        };
+
+        let border_fill = match (self.border_fill, to.border_fill) {
+            (Some(Fill::Color(from)), Some(Fill::Color(to))) => Some(Fill::Color(from.lerp(to, t))),
+            (Some(Fill::Gradient(from)), Some(Fill::Gradient(to))) => {
+                Some(Fill::Gradient(from.lerp(to, t)))
+            }
+            (_, to) => to,
+        };
+
+        State {
+            shape: to.shape,
+            color,
+            gradients,
+            shadows,
+            border_radius,
+            border,
+            border_fill,
+        }
+    }
+}
+
+/// Moves the decoration of `id` towards the one set this frame, retargeting from the
+/// current value when it changes mid-flight.
+pub(crate) fn apply_transition(
+    widgets_states: &mut WidgetsStates,
+    delta_time: f32,
+    id: WidgetId,
+    transition: Transition,
+) {
+    let Some(state) = widgets_states.decorated_box.get_mut(id) else {
+        return;
+    };
+
+    let transition_state =
+        widgets_states
+            .decorated_box_transitions
+            .access(id, || TransitionState {
+                target: state.clone(),
+                tween: Tween::new(state.clone()),
+            });
+
+    if transition_state.target != *state {
+        if transition_state.target.shape == state.shape {
+            let current = transition_state.tween.value();
+
+            transition_state.tween = Tween::new(current)
+                .duration(transition.duration)
+                .curve(transition.curve);
+            transition_state.tween.tween_to(state.clone());
+        } else {
+            transition_state.tween.set(state.clone());
+        }
+
+        transition_state.target = state.clone();
+    }
+
+    if transition_state.tween.in_progress() {
+        transition_state.tween.step(delta_time);
+        *state = transition_state.tween.value();
+    }
+}
+
 impl DecorationBuilder {
     impl_id!();
 
@@ -86,6 +228,14 @@ impl DecorationBuilder {
         self
     }
 
+    /// Paints the border with the fill instead of the colors of its sides, e.g. with a
+    /// gradient. The widths still come from [`Self::border`].
+    pub fn border_fill(mut self, fill: Fill) -> Self {
+        self.border_fill = Some(fill);
+
+        self
+    }
+
     pub fn add_gradient(mut self, gradient: Gradient) -> Self {
         self.gradients.push(gradient);
 
@@ -104,12 +254,57 @@ impl DecorationBuilder {
         self
     }
 
+    /// Adds a shadow behind the decoration, the first one added is drawn on top.
+    pub fn shadow(mut self, shadow: BoxShadow) -> Self {
+        self.shadows.push(shadow);
+
+        self
+    }
+
     pub fn shape(mut self, shape: BoxShape) -> Self {
         self.shape = Some(shape);
 
         self
     }
 
+    /// Animates changes of the fill, border and radius between frames.
+    pub fn transition(mut self, duration: Duration, curve: fn(t: f32) -> f32) -> Self {
+        self.transition = Some(Transition { duration, curve });
+
+        self
+    }
+
+    /// Positions the decoration relative to the decorated rect instead of covering it.
+    pub fn placement(mut self, placement: DecoratorPlacement) -> Self {
+        self.placement = Some(placement);
+
+        self
+    }
+
+    /// Shrinks the decorated rect, negative values draw outside of the widget.
+    pub fn insets(mut self, insets: EdgeInsets) -> Self {
+        self.placement.get_or_insert_default().insets = insets;
+
+        self
+    }
+
+    /// Grows the decorated rect by `width` on every side, e.g. for a focus ring.
+    pub fn outset(self, width: f32) -> Self {
+        self.insets(EdgeInsets::all(-width))
+    }
+
+    pub fn size(mut self, size: impl Into<Size>) -> Self {
+        self.placement.get_or_insert_default().size = Some(size.into());
+
+        self
+    }
+
+    pub fn align(mut self, align_x: AlignX, align_y: AlignY) -> Self {
+        self.placement.get_or_insert_default().align = (align_x, align_y);
+
+        self
+    }
+
     pub fn when_positioned<F>(mut self, f: F) -> Self
     where
         F: Fn(&BuildContext, PositionedChildMeta) -> DecorationBuilder + 'static,
@@ -119,7 +314,7 @@ impl DecorationBuilder {
     }
 
     pub fn build(self, context: &mut BuildContext) -> WidgetRef {
-        let id = self.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.id);
 
         context.widgets_states.decorated_box.set(
             id,
@@ -127,15 +322,33 @@ impl DecorationBuilder {
                 color: self.color,
                 shape: self.shape.unwrap_or(BoxShape::Rect),
                 gradients: self.gradients,
+                shadows: self.shadows,
                 border_radius: self.border_radius,
                 border: self.border,
+                border_fill: self.border_fill,
             },
         );
 
+        if let Some(placement) = self.placement {
+            context
+                .widgets_states
+                .decorator_placements
+                .set(id, placement);
+        }
+
+        // Deferred decorations are complete only after the parent is built, the
+        // transition is applied once they are merged.
         if let Some(defer) = self.defer {
             context
                 .decoration_defer
-                .push((id, context.child_index, defer));
+                .push((id, context.child_index, defer, self.transition));
+        } else if let Some(transition) = self.transition {
+            apply_transition(
+                context.widgets_states,
+                context.animation_delta_time(),
+                id,
+                transition,
+            );
         }
 
         WidgetRef::new(WidgetType::of::<DecoratedBox>(), id)
@@ -161,6 +374,14 @@ impl DecoratedBoxBuilder {
         self
     }
 
+    /// Paints the border with the fill instead of the colors of its sides, e.g. with a
+    /// gradient. The widths still come from [`Self::border`].
+    pub fn border_fill(mut self, fill: Fill) -> Self {
+        self.border_fill = Some(fill);
+
+        self
+    }
+
     pub fn add_gradient(mut self, gradient: Gradient) -> Self {
         self.gradients.push(gradient);
 
@@ -179,15 +400,29 @@ impl DecoratedBoxBuilder {
         self
     }
 
+    /// Adds a shadow behind the decoration, the first one added is drawn on top.
+    pub fn shadow(mut self, shadow: BoxShadow) -> Self {
+        self.shadows.push(shadow);
+
+        self
+    }
+
     pub fn shape(mut self, shape: BoxShape) -> Self {
         self.shape = shape;
 
         self
     }
 
+    /// Animates changes of the fill, border and radius between frames.
+    pub fn transition(mut self, duration: Duration, curve: fn(t: f32) -> f32) -> Self {
+        self.transition = Some(Transition { duration, curve });
+
+        self
+    }
+
     #[profiling::function]
     pub fn build(self, context: &mut BuildContext) {
-        let id = self.frame.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.frame.id);
         let widget_ref = WidgetRef::new(WidgetType::of::<DecoratedBox>(), id);
         let backgrounds = std::mem::take(context.backgrounds);
         let foregrounds = std::mem::take(context.foregrounds);
@@ -226,10 +461,21 @@ impl DecoratedBoxBuilder {
                 color: self.color,
                 shape: self.shape,
                 gradients: self.gradients.clone(),
+                shadows: self.shadows.clone(),
                 border_radius: self.border_radius,
                 border: self.border,
+                border_fill: self.border_fill,
             },
         );
+
+        if let Some(transition) = self.transition {
+            apply_transition(
+                context.widgets_states,
+                context.animation_delta_time(),
+                id,
+                transition,
+            );
+        }
     }
 }
 
@@ -239,82 +485,202 @@ pub fn decorated_box() -> DecoratedBoxBuilder {
         frame: FrameBuilder::new(),
         color: None,
         gradients: smallvec![],
+        shadows: smallvec![],
         border_radius: None,
         border: None,
+        border_fill: None,
         shape: BoxShape::Rect,
+        transition: None,
     }
 }
 
 #[track_caller]
 pub fn decoration() -> DecorationBuilder {
     DecorationBuilder {
-        id: WidgetId::auto(),
+        id: WidgetKey::auto(),
         color: None,
         gradients: smallvec![],
+        shadows: smallvec![],
         border_radius: None,
         border: None,
+        border_fill: None,
         shape: None,
         defer: None,
+        transition: None,
+        placement: None,
     }
 }
 
+/// Draws the color and the gradients of the decoration over each other, the border is
+/// drawn once, with the last of them or alone when the decoration has neither. The
+/// shadows go under all of them.
 pub fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &State) {
-    match state.shape {
-        BoxShape::Rect => {
-            if let Some(color) = state.color {
-                ctx.push_command(
-                    placement.zindex,
-                    RenderCommand::Rect {
-                        boundary: placement.rect.px(ctx),
-                        fill: Some(Fill::Color(color)),
-                        border_radius: state.border_radius.map(|it| it.px(ctx)),
-                        border: state.border.map(|it| it.px(ctx)),
-                    },
-                );
-            }
-
-            for gradient in &state.gradients {
-                ctx.push_command(
-                    placement.zindex,
-                    RenderCommand::Rect {
-                        boundary: placement.rect.px(ctx),
-                        fill: Some(Fill::Gradient(gradient.clone())),
-                        border_radius: state.border_radius.map(|it| it.px(ctx)),
-                        border: state.border.map(|it| it.px(ctx)),
-                    },
-                );
-            }
+    render_shadows(ctx, placement, state);
+
+    let mut fills = state
+        .color
+        .map(Fill::Color)
+        .into_iter()
+        .chain(state.gradients.iter().cloned().map(Fill::Gradient))
+        .map(Some)
+        .collect::<SmallVec<[Option<Fill>; 4]>>();
+
+    if fills.is_empty() {
+        if state.border.is_none() {
+            return;
         }
-        BoxShape::Oval => {
-            let border = state.border.map(|it| it.px(ctx)).map(|it| {
-                it.top
-                    .or(it.bottom)
-                    .or(it.left)
-                    .or(it.right)
-                    .unwrap_or(BorderSide::default())
-            });
 
-            if let Some(color) = state.color {
-                ctx.push_command(
-                    placement.zindex,
-                    RenderCommand::Oval {
-                        boundary: placement.rect.px(ctx),
-                        fill: Some(Fill::Color(color)),
-                        border,
-                    },
-                );
-            }
+        fills.push(None);
+    }
+
+    let last = fills.len() - 1;
+
+    for (index, fill) in fills.into_iter().enumerate() {
+        let border = if index == last { state.border } else { None };
+        let border_fill = border.and(state.border_fill.clone());
+
+        let command = match state.shape {
+            BoxShape::Rect => RenderCommand::Rect {
+                boundary: placement.rect.px(ctx),
+                fill,
+                border_radius: state.border_radius.map(|it| it.px(ctx)),
+                border: border.map(|it| it.px(ctx)),
+                border_fill,
+            },
+            BoxShape::Oval => RenderCommand::Oval {
+                boundary: placement.rect.px(ctx),
+                fill,
+                border: border.map(|it| it.px(ctx)).map(|it| {
+                    it.top
+                        .or(it.bottom)
+                        .or(it.left)
+                        .or(it.right)
+                        .unwrap_or(BorderSide::default())
+                }),
+                border_fill,
+            },
+        };
+
+        ctx.push_command(placement.zindex, command);
+    }
+}
 
-            for gradient in &state.gradients {
-                ctx.push_command(
-                    placement.zindex,
-                    RenderCommand::Oval {
-                        boundary: placement.rect.px(ctx),
-                        fill: Some(Fill::Gradient(gradient.clone())),
-                        border,
-                    },
-                );
+fn render_shadows(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &State) {
+    let rect = placement.rect.px(ctx);
+    let border_radius = match state.shape {
+        BoxShape::Rect => state.border_radius.map(|it| it.px(ctx)),
+        BoxShape::Oval => Some(BorderRadius::all(rect.width.min(rect.height) / 2.)),
+    };
+
+    for shadow in state.shadows.iter().rev() {
+        let shadow = shadow.px(ctx);
+
+        // Rounded corners grow with the spread, as in CSS, sharp ones stay sharp.
+        let spread = |radius: f32| {
+            if radius > 0. {
+                (radius + shadow.spread).max(0.)
+            } else {
+                0.
             }
+        };
+
+        ctx.push_command(
+            placement.zindex,
+            RenderCommand::Shadow {
+                boundary: rect
+                    .offset(shadow.offset.x, shadow.offset.y)
+                    .expand(shadow.spread),
+                border_radius: border_radius.map(|it| BorderRadius {
+                    top_left: spread(it.top_left),
+                    top_right: spread(it.top_right),
+                    bottom_left: spread(it.bottom_left),
+                    bottom_right: spread(it.bottom_right),
+                }),
+                blur_radius: shadow.blur_radius.max(0.),
+                color: shadow.color,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Vec2, curves};
+
+    fn decoration_state(color: ColorRgba) -> State {
+        State {
+            shape: BoxShape::Rect,
+            color: Some(color),
+            gradients: smallvec![],
+            shadows: smallvec![],
+            border_radius: None,
+            border: None,
+            border_fill: None,
         }
     }
+
+    fn build_frame(states: &mut WidgetsStates, id: WidgetId, color: ColorRgba) -> State {
+        let transition = Transition {
+            duration: Duration::from_secs(1),
+            curve: curves::f32::linear,
+        };
+
+        states.decorated_box.set(id, decoration_state(color));
+        apply_transition(states, 0.5, id, transition);
+
+        states.decorated_box.get_mut(id).unwrap().clone()
+    }
+
+    #[test]
+    fn test_transition_retargets_from_current_value() {
+        let id = WidgetId::auto();
+        let mut states = WidgetsStates::default();
+        let black = ColorRgba::new(0., 0., 0., 1.);
+        let white = ColorRgba::new(1., 1., 1., 1.);
+
+        assert_eq!(build_frame(&mut states, id, black).color, Some(black));
+
+        let halfway = build_frame(&mut states, id, white).color.unwrap();
+        assert!(halfway.r > 0. && halfway.r < 1.);
+
+        let back = build_frame(&mut states, id, black).color.unwrap();
+        assert!(back.r > 0. && back.r < halfway.r);
+
+        assert_eq!(build_frame(&mut states, id, black).color, Some(black));
+    }
+
+    #[test]
+    fn test_gradients_lerp_stop_by_stop_only_when_counts_match() {
+        let black = ColorRgba::new(0., 0., 0., 1.);
+        let white = ColorRgba::new(1., 1., 1., 1.);
+
+        let from = Gradient::Linear(LinearGradient::vertical((black, black)));
+        let to = Gradient::Linear(LinearGradient::vertical((white, white)));
+        let Gradient::Linear(halfway) = from.clone().lerp(to, 0.5) else {
+            panic!("expected a linear gradient");
+        };
+        assert!(halfway.stops[0].color.r > 0. && halfway.stops[0].color.r < 1.);
+
+        let to = Gradient::Linear(LinearGradient::vertical((white, white, white)));
+        assert_eq!(from.lerp(to.clone(), 0.5), to);
+    }
+
+    #[test]
+    fn test_added_shadow_fades_in() {
+        let black = ColorRgba::new(0., 0., 0., 1.);
+        let shadow = BoxShadow::new(Vec2::new(0., 2.), 4., 0., black);
+        let from = decoration_state(black);
+        let to = State {
+            shadows: smallvec![shadow],
+            ..decoration_state(black)
+        };
+
+        let halfway = from.lerp(to.clone(), 0.5);
+        assert_eq!(halfway.shadows.len(), 1);
+        assert_eq!(halfway.shadows[0].offset, shadow.offset);
+        assert!(halfway.shadows[0].color.a > 0. && halfway.shadows[0].color.a < 1.);
+
+        assert!(halfway.lerp(to.clone(), 1.) == to);
+    }
 }
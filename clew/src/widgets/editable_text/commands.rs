@@ -0,0 +1,507 @@
+//! Line and character edits on top of the ones of the text input.
+//!
+//! Each command is a single delta, so it's undone in one step even when it touches
+//! several lines. The commands work on the lines of the selection when there is one, a
+//! selection ending at the start of a line doesn't include that line.
+
+use cosmic_text::{Cursor, Edit, Editor, Selection};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{
+    KeyBinding, ShortcutsRegistry,
+    keyboard::KeyCode,
+    text_history::{TextDeletionDirection, TextEditDelta},
+};
+
+use super::{ShortcutScopes, TextEditingShortcut, cursor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EditCommand {
+    /// Deletes from the cursor to the end of the line, at the end joins the next line.
+    DeleteToLineEnd,
+    /// Deletes from the start of the line to the cursor, at the start joins the line
+    /// with the previous one.
+    DeleteToLineStart,
+    DeleteLine,
+    /// Inserts a copy of the lines below them and moves the cursor to the copy.
+    DuplicateLine,
+    MoveLineUp,
+    MoveLineDown,
+    /// Swaps the characters around the cursor and moves it forward, at the end of the
+    /// line swaps the last two characters.
+    TransposeChars,
+}
+
+impl EditCommand {
+    pub const ALL: [EditCommand; 7] = [
+        EditCommand::DeleteToLineEnd,
+        EditCommand::DeleteToLineStart,
+        EditCommand::DeleteLine,
+        EditCommand::DuplicateLine,
+        EditCommand::MoveLineUp,
+        EditCommand::MoveLineDown,
+        EditCommand::TransposeChars,
+    ];
+
+    /// Shortcut of the text editing scope that runs the command.
+    pub fn shortcut(self) -> TextEditingShortcut {
+        match self {
+            EditCommand::DeleteToLineEnd => TextEditingShortcut::DeleteToLineEnd,
+            EditCommand::DeleteToLineStart => TextEditingShortcut::DeleteToLineStart,
+            EditCommand::DeleteLine => TextEditingShortcut::DeleteLine,
+            EditCommand::DuplicateLine => TextEditingShortcut::DuplicateLine,
+            EditCommand::MoveLineUp => TextEditingShortcut::MoveLineUp,
+            EditCommand::MoveLineDown => TextEditingShortcut::MoveLineDown,
+            EditCommand::TransposeChars => TextEditingShortcut::TransposeChars,
+        }
+    }
+}
+
+/// Binds the edit commands in the text editing scope. Adding a shortcut with the same
+/// id afterwards replaces the default binding.
+pub fn add_edit_command_shortcuts(registry: &mut ShortcutsRegistry) {
+    registry
+        .scope(ShortcutScopes::TextEditing)
+        .add(
+            TextEditingShortcut::DeleteToLineEnd,
+            KeyBinding::new(KeyCode::KeyK).with_ctrl(),
+        )
+        .add(
+            TextEditingShortcut::DeleteToLineStart,
+            KeyBinding::new(KeyCode::KeyU).with_ctrl(),
+        )
+        .add_repeat(
+            TextEditingShortcut::DeleteLine,
+            KeyBinding::new(KeyCode::KeyK).with_ctrl().with_shift(),
+        )
+        .add_repeat(
+            TextEditingShortcut::DuplicateLine,
+            KeyBinding::new(KeyCode::KeyD).with_ctrl().with_shift(),
+        )
+        .add_repeat(
+            TextEditingShortcut::MoveLineUp,
+            KeyBinding::new(KeyCode::ArrowUp).with_ctrl().with_shift(),
+        )
+        .add_repeat(
+            TextEditingShortcut::MoveLineDown,
+            KeyBinding::new(KeyCode::ArrowDown).with_ctrl().with_shift(),
+        )
+        .add(
+            TextEditingShortcut::TransposeChars,
+            KeyBinding::new(KeyCode::KeyT).with_ctrl(),
+        );
+}
+
+/// Runs the command on the editor, returns the delta of the edit or `None` when there
+/// is nothing to change, e.g. moving the first line up.
+pub(crate) fn apply_command(editor: &mut Editor, command: EditCommand) -> Option<TextEditDelta> {
+    match command {
+        EditCommand::DeleteToLineEnd => delete_to_line_end(editor),
+        EditCommand::DeleteToLineStart => delete_to_line_start(editor),
+        EditCommand::DeleteLine => delete_line(editor),
+        EditCommand::DuplicateLine => duplicate_line(editor),
+        EditCommand::MoveLineUp => move_line_up(editor),
+        EditCommand::MoveLineDown => move_line_down(editor),
+        EditCommand::TransposeChars => transpose_chars(editor),
+    }
+}
+
+fn line_count(editor: &Editor) -> usize {
+    editor.with_buffer(|buffer| buffer.lines.len())
+}
+
+fn line_text(editor: &Editor, line: usize) -> String {
+    editor.with_buffer(|buffer| {
+        buffer
+            .lines
+            .get(line)
+            .map(|line| line.text().to_string())
+            .unwrap_or_default()
+    })
+}
+
+fn line_end(editor: &Editor, line: usize) -> Cursor {
+    Cursor::new(line, line_text(editor, line).len())
+}
+
+/// First and last line of the selection, the line of the cursor without one.
+fn selected_lines(editor: &Editor) -> (usize, usize) {
+    match editor.selection_bounds() {
+        Some((start, end)) if end.line > start.line && end.index == 0 => (start.line, end.line - 1),
+        Some((start, end)) => (start.line, end.line),
+        None => (editor.cursor().line, editor.cursor().line),
+    }
+}
+
+fn delete(
+    editor: &mut Editor,
+    start: Cursor,
+    end: Cursor,
+    direction: TextDeletionDirection,
+) -> Option<TextEditDelta> {
+    if start == end {
+        return None;
+    }
+
+    let deleted_text = editor.with_buffer(|buffer| cursor::text_between(buffer, start, end));
+
+    editor.set_selection(Selection::None);
+    editor.delete_range(start, end);
+    editor.set_cursor(start);
+
+    Some(TextEditDelta::Delete {
+        start,
+        end,
+        deleted_text,
+        direction,
+    })
+}
+
+fn replace(editor: &mut Editor, start: Cursor, end: Cursor, text: String) -> TextEditDelta {
+    let text_before = editor.with_buffer(|buffer| cursor::text_between(buffer, start, end));
+
+    editor.set_selection(Selection::None);
+    editor.delete_range(start, end);
+    editor.set_cursor(start);
+    editor.insert_string(&text, None);
+
+    TextEditDelta::Replace {
+        range_before: (start, end),
+        range_after: (start, editor.cursor()),
+        text_before,
+        text_after: text,
+    }
+}
+
+/// Puts the cursor and the selection back moved by `lines` lines.
+fn restore_moved(editor: &mut Editor, cursor: Cursor, selection: Selection, lines: isize) {
+    let shift = |cursor: Cursor| {
+        Cursor::new_with_affinity(
+            cursor.line.saturating_add_signed(lines),
+            cursor.index,
+            cursor.affinity,
+        )
+    };
+
+    editor.set_cursor(shift(cursor));
+    editor.set_selection(match selection {
+        Selection::None => Selection::None,
+        Selection::Normal(anchor) => Selection::Normal(shift(anchor)),
+        Selection::Line(anchor) => Selection::Line(shift(anchor)),
+        Selection::Word(anchor) => Selection::Word(shift(anchor)),
+    });
+}
+
+fn delete_to_line_end(editor: &mut Editor) -> Option<TextEditDelta> {
+    if let Some((start, end)) = editor.selection_bounds() {
+        return delete(editor, start, end, TextDeletionDirection::Forward);
+    }
+
+    let cursor = editor.cursor();
+    let end = line_end(editor, cursor.line);
+
+    if cursor.index < end.index {
+        delete(editor, cursor, end, TextDeletionDirection::Forward)
+    } else if cursor.line + 1 < line_count(editor) {
+        delete(
+            editor,
+            cursor,
+            Cursor::new(cursor.line + 1, 0),
+            TextDeletionDirection::Forward,
+        )
+    } else {
+        None
+    }
+}
+
+fn delete_to_line_start(editor: &mut Editor) -> Option<TextEditDelta> {
+    if let Some((start, end)) = editor.selection_bounds() {
+        return delete(editor, start, end, TextDeletionDirection::Backward);
+    }
+
+    let cursor = editor.cursor();
+
+    if cursor.index > 0 {
+        delete(
+            editor,
+            Cursor::new(cursor.line, 0),
+            cursor,
+            TextDeletionDirection::Backward,
+        )
+    } else if cursor.line > 0 {
+        let start = line_end(editor, cursor.line - 1);
+
+        delete(editor, start, cursor, TextDeletionDirection::Backward)
+    } else {
+        None
+    }
+}
+
+fn delete_line(editor: &mut Editor) -> Option<TextEditDelta> {
+    let (first, last) = selected_lines(editor);
+
+    // The line break after the lines goes with them, the one before them when they
+    // are at the end of the text.
+    let (start, end, cursor_line) = if last + 1 < line_count(editor) {
+        (Cursor::new(first, 0), Cursor::new(last + 1, 0), first)
+    } else if first > 0 {
+        (
+            line_end(editor, first - 1),
+            line_end(editor, last),
+            first - 1,
+        )
+    } else {
+        (Cursor::new(0, 0), line_end(editor, last), 0)
+    };
+
+    let delta = delete(editor, start, end, TextDeletionDirection::Forward)?;
+    editor.set_cursor(Cursor::new(cursor_line, 0));
+
+    Some(delta)
+}
+
+fn duplicate_line(editor: &mut Editor) -> Option<TextEditDelta> {
+    let (first, last) = selected_lines(editor);
+    let cursor = editor.cursor();
+    let selection = editor.selection();
+    let end = line_end(editor, last);
+    let lines =
+        editor.with_buffer(|buffer| cursor::text_between(buffer, Cursor::new(first, 0), end));
+    let text = format!("\n{lines}");
+
+    editor.set_selection(Selection::None);
+    editor.set_cursor(end);
+    editor.insert_string(&text, None);
+
+    let delta = TextEditDelta::Insert {
+        cursor_before: end,
+        cursor_after: editor.cursor(),
+        text,
+    };
+
+    restore_moved(editor, cursor, selection, (last - first + 1) as isize);
+
+    Some(delta)
+}
+
+fn move_line_up(editor: &mut Editor) -> Option<TextEditDelta> {
+    let (first, last) = selected_lines(editor);
+
+    if first == 0 {
+        return None;
+    }
+
+    let cursor = editor.cursor();
+    let selection = editor.selection();
+    let end = line_end(editor, last);
+    let lines =
+        editor.with_buffer(|buffer| cursor::text_between(buffer, Cursor::new(first, 0), end));
+    let previous = line_text(editor, first - 1);
+
+    let delta = replace(
+        editor,
+        Cursor::new(first - 1, 0),
+        end,
+        format!("{lines}\n{previous}"),
+    );
+
+    restore_moved(editor, cursor, selection, -1);
+
+    Some(delta)
+}
+
+fn move_line_down(editor: &mut Editor) -> Option<TextEditDelta> {
+    let (first, last) = selected_lines(editor);
+
+    if last + 1 >= line_count(editor) {
+        return None;
+    }
+
+    let cursor = editor.cursor();
+    let selection = editor.selection();
+    let end = line_end(editor, last);
+    let lines =
+        editor.with_buffer(|buffer| cursor::text_between(buffer, Cursor::new(first, 0), end));
+    let next = line_text(editor, last + 1);
+
+    let delta = replace(
+        editor,
+        Cursor::new(first, 0),
+        line_end(editor, last + 1),
+        format!("{next}\n{lines}"),
+    );
+
+    restore_moved(editor, cursor, selection, 1);
+
+    Some(delta)
+}
+
+fn transpose_chars(editor: &mut Editor) -> Option<TextEditDelta> {
+    let cursor = editor.cursor();
+    let text = line_text(editor, cursor.line);
+    let graphemes = text.grapheme_indices(true).collect::<Vec<_>>();
+
+    // The grapheme after the cursor, the last one at the end of the line.
+    let next = graphemes
+        .iter()
+        .position(|(index, _)| *index >= cursor.index)
+        .unwrap_or(graphemes.len().saturating_sub(1));
+
+    if next == 0 || graphemes.len() < 2 {
+        return None;
+    }
+
+    let (start, previous) = graphemes[next - 1];
+    let (index, current) = graphemes[next];
+    let end = index + current.len();
+
+    let delta = replace(
+        editor,
+        Cursor::new(cursor.line, start),
+        Cursor::new(cursor.line, end),
+        format!("{current}{previous}"),
+    );
+
+    editor.set_cursor(Cursor::new(cursor.line, end));
+
+    Some(delta)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Editor with the text, `|` marks the cursor and `^` the selection anchor.
+    fn editor(text: &str) -> Editor<'static> {
+        let mut buffer = cosmic_text::Buffer::new_empty(cosmic_text::Metrics::new(12., 12.));
+        let mut cursor = Cursor::new(0, 0);
+        let mut anchor = None;
+
+        for (line, line_text) in text.split('\n').enumerate() {
+            if let Some(index) = line_text.replace('^', "").find('|') {
+                cursor = Cursor::new(line, index);
+            }
+
+            if let Some(index) = line_text.replace('|', "").find('^') {
+                anchor = Some(Cursor::new(line, index));
+            }
+
+            buffer.lines.push(cosmic_text::BufferLine::new(
+                line_text.replace(['|', '^'], ""),
+                cosmic_text::LineEnding::default(),
+                cosmic_text::AttrsList::new(&cosmic_text::Attrs::new()),
+                cosmic_text::Shaping::Advanced,
+            ));
+        }
+
+        let mut editor = Editor::new(buffer);
+        editor.set_cursor(cursor);
+
+        if let Some(anchor) = anchor {
+            editor.set_selection(Selection::Normal(anchor));
+        }
+
+        editor
+    }
+
+    fn text(editor: &Editor) -> String {
+        let cursor = editor.cursor();
+
+        editor.with_buffer(|buffer| {
+            let mut lines = buffer
+                .lines
+                .iter()
+                .map(|line| line.text().to_string())
+                .collect::<Vec<_>>();
+
+            lines[cursor.line].insert(cursor.index, '|');
+
+            lines.join("\n")
+        })
+    }
+
+    /// Runs the command, checks the result and that the delta brings the text back.
+    fn check(before: &str, command: EditCommand, after: &str) {
+        let mut editor = editor(before);
+        let delta = apply_command(&mut editor, command);
+
+        assert_eq!(text(&editor), after, "{command:?} on {before:?}");
+
+        if let Some(delta) = delta {
+            delta.undo(&mut editor);
+            assert_eq!(
+                text(&editor).replace('|', ""),
+                before.replace(['|', '^'], ""),
+                "undo of {command:?} on {before:?}"
+            );
+
+            delta.apply(&mut editor);
+            assert_eq!(
+                text(&editor).replace('|', ""),
+                after.replace('|', ""),
+                "redo of {command:?} on {before:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_delete_to_line_end() {
+        check("ab|cd\nef", EditCommand::DeleteToLineEnd, "ab|\nef");
+        check("abcd|\nef", EditCommand::DeleteToLineEnd, "abcd|ef");
+        check("ab\nef|", EditCommand::DeleteToLineEnd, "ab\nef|");
+        check("a^bc|d", EditCommand::DeleteToLineEnd, "a|d");
+        check("ab|cd", EditCommand::DeleteToLineStart, "|cd");
+        check("ab\n|cd", EditCommand::DeleteToLineStart, "ab|cd");
+        check("|ab", EditCommand::DeleteToLineStart, "|ab");
+    }
+
+    #[test]
+    fn test_delete_line() {
+        check("ab\nc|d\nef", EditCommand::DeleteLine, "ab\n|ef");
+        check("ab\ncd\ne|f", EditCommand::DeleteLine, "ab\n|cd");
+        check("a|b", EditCommand::DeleteLine, "|");
+        check("a^b\ncd\ne|f\ngh", EditCommand::DeleteLine, "|gh");
+        // The line the selection ends at the start of stays.
+        check("a^b\n|cd", EditCommand::DeleteLine, "|cd");
+    }
+
+    #[test]
+    fn test_duplicate_line() {
+        check("a|b\ncd", EditCommand::DuplicateLine, "ab\na|b\ncd");
+        check("ab\nc|d", EditCommand::DuplicateLine, "ab\ncd\nc|d");
+
+        let mut editor = editor("a^b\nc|d\nef");
+        apply_command(&mut editor, EditCommand::DuplicateLine);
+
+        assert_eq!(text(&editor), "ab\ncd\nab\nc|d\nef");
+        assert_eq!(editor.selection(), Selection::Normal(Cursor::new(2, 1)));
+    }
+
+    #[test]
+    fn test_move_line() {
+        check("ab\nc|d\nef", EditCommand::MoveLineUp, "c|d\nab\nef");
+        check("a|b\ncd", EditCommand::MoveLineUp, "a|b\ncd");
+        check("ab\nc|d\nef", EditCommand::MoveLineDown, "ab\nef\nc|d");
+        check("ab\nc|d", EditCommand::MoveLineDown, "ab\nc|d");
+
+        let mut editor = editor("ab\nc^d\ne|f\ngh");
+        apply_command(&mut editor, EditCommand::MoveLineDown);
+
+        assert_eq!(text(&editor), "ab\ngh\ncd\ne|f");
+        assert_eq!(editor.selection(), Selection::Normal(Cursor::new(2, 1)));
+
+        apply_command(&mut editor, EditCommand::MoveLineUp);
+        apply_command(&mut editor, EditCommand::MoveLineUp);
+
+        assert_eq!(text(&editor), "cd\ne|f\nab\ngh");
+    }
+
+    #[test]
+    fn test_transpose_chars() {
+        check("a|bc", EditCommand::TransposeChars, "ba|c");
+        check("abc|", EditCommand::TransposeChars, "acb|");
+        check("|abc", EditCommand::TransposeChars, "|abc");
+        check("a\n|bc", EditCommand::TransposeChars, "a\n|bc");
+        check("é|ü", EditCommand::TransposeChars, "üé|");
+    }
+}
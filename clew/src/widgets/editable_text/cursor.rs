@@ -0,0 +1,479 @@
+//! Cursor placement on soft wrapped lines.
+//!
+//! A wrap boundary is both the end of one visual line and the start of the next one,
+//! the cursor affinity decides which of them the cursor belongs to: `Before` keeps it
+//! at the end of the previous visual line, `After` moves it to the start of the next.
+//! Lines wrapped at whitespace leave the whitespace without glyphs, the indices
+//! inside of it are resolved the same way.
+//!
+//! Text between two cursors is taken from the logical lines, so wrap boundaries and
+//! the visual order of bidi runs never end up in copied text.
+
+use cosmic_text::{Affinity, Buffer, Cursor, FontSystem, LayoutGlyph, LayoutLine};
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::{Rect, Vec2};
+
+fn glyphs_range(glyphs: &[LayoutGlyph]) -> Option<(usize, usize)> {
+    let start = glyphs.iter().map(|glyph| glyph.start).min()?;
+    let end = glyphs.iter().map(|glyph| glyph.end).max()?;
+
+    Some((start, end))
+}
+
+/// Index of the visual line of `layout` the cursor is on.
+pub(crate) fn visual_line(layout: &[LayoutLine], cursor: Cursor) -> usize {
+    let index = cursor.index;
+    let ranges = layout
+        .iter()
+        .map(|line| glyphs_range(&line.glyphs))
+        .collect::<Vec<_>>();
+
+    if let Some(visual) = ranges
+        .iter()
+        .position(|range| range.is_some_and(|(start, end)| start < index && index < end))
+    {
+        return visual;
+    }
+
+    // On a boundary between visual lines. It's either shared by both of them or
+    // separated by the whitespace the line was wrapped at, which has no glyphs.
+    let previous = ranges
+        .iter()
+        .rposition(|range| range.is_some_and(|(_, end)| end <= index));
+    let next = ranges
+        .iter()
+        .position(|range| range.is_some_and(|(start, _)| start >= index));
+
+    match (previous, next) {
+        (Some(previous), Some(next)) => {
+            let ends_previous = ranges[previous].is_some_and(|(_, end)| end == index);
+            let starts_next = ranges[next].is_some_and(|(start, _)| start == index);
+
+            if (ends_previous && !starts_next) || cursor.affinity == Affinity::Before {
+                previous
+            } else {
+                next
+            }
+        }
+        (Some(visual), None) | (None, Some(visual)) => visual,
+        (None, None) => 0,
+    }
+}
+
+/// Caret x of `index` on a visual line.
+pub(crate) fn caret_x(text: &str, glyphs: &[LayoutGlyph], index: usize) -> f32 {
+    for glyph in glyphs {
+        if index == glyph.start {
+            return if glyph.level.is_rtl() {
+                glyph.x + glyph.w
+            } else {
+                glyph.x
+            };
+        }
+
+        if index > glyph.start && index < glyph.end {
+            // Split ligatures and clusters evenly between their graphemes.
+            let cluster = &text[glyph.start..glyph.end];
+            let total = cluster.graphemes(true).count();
+            let before = cluster
+                .grapheme_indices(true)
+                .take_while(|(i, _)| glyph.start + i < index)
+                .count();
+            let offset = glyph.w * before as f32 / total as f32;
+
+            return if glyph.level.is_rtl() {
+                glyph.x + glyph.w - offset
+            } else {
+                glyph.x + offset
+            };
+        }
+    }
+
+    // Past the last glyph, e.g. after the whitespace a line was wrapped at.
+    match glyphs
+        .iter()
+        .filter(|glyph| glyph.end <= index)
+        .max_by_key(|glyph| glyph.end)
+    {
+        Some(glyph) if glyph.level.is_rtl() => glyph.x,
+        Some(glyph) => glyph.x + glyph.w,
+        None => glyphs.first().map_or(0., |glyph| glyph.x),
+    }
+}
+
+/// Cursor closest to `x` on a visual line. The end of a wrapped line gets `Before`
+/// affinity so it stays on this line instead of jumping to the start of the next one.
+pub(crate) fn hit_visual_line(text: &str, line: usize, glyphs: &[LayoutGlyph], x: f32) -> Cursor {
+    let Some((start, end)) = glyphs_range(glyphs) else {
+        return Cursor::new_with_affinity(line, 0, Affinity::After);
+    };
+
+    let mut best = (start, f32::INFINITY);
+
+    for glyph in glyphs {
+        for (i, _) in text[glyph.start..glyph.end].grapheme_indices(true) {
+            let index = glyph.start + i;
+            let distance = (caret_x(text, glyphs, index) - x).abs();
+
+            if distance < best.1 {
+                best = (index, distance);
+            }
+        }
+    }
+
+    if (caret_x(text, glyphs, end) - x).abs() < best.1 {
+        best = (end, 0.);
+    }
+
+    let affinity = if best.0 == end && best.0 != start {
+        Affinity::Before
+    } else {
+        Affinity::After
+    };
+
+    Cursor::new_with_affinity(line, best.0, affinity)
+}
+
+/// Cursor under the point, relative to the buffer origin.
+pub(crate) fn hit(buffer: &Buffer, x: f32, y: f32) -> Option<Cursor> {
+    let runs = buffer.layout_runs().collect::<Vec<_>>();
+
+    let run = runs
+        .iter()
+        .find(|run| y >= run.line_top && y < run.line_top + run.line_height)
+        .or_else(|| {
+            if y < runs.first()?.line_top {
+                runs.first()
+            } else {
+                runs.last()
+            }
+        })?;
+
+    let text = buffer.lines.get(run.line_i)?.text();
+
+    Some(hit_visual_line(text, run.line_i, run.glyphs, x))
+}
+
+/// Top left corner of the caret relative to the buffer origin, `None` if the cursor
+/// line isn't laid out or is scrolled out of the view.
+pub(crate) fn caret_position(buffer: &Buffer, cursor: Cursor) -> Option<Vec2> {
+    let line = buffer.lines.get(cursor.line)?;
+    let visual = visual_line(line.layout_opt()?, cursor);
+
+    let run = buffer
+        .layout_runs()
+        .filter(|run| run.line_i == cursor.line)
+        .nth(visual)?;
+
+    Some(Vec2::new(
+        caret_x(line.text(), run.glyphs, cursor.index),
+        run.line_top,
+    ))
+}
+
+/// Rects covering the text between `start` and `end` relative to the buffer origin, one
+/// per visual line. Empty lines inside of the selection get a narrow rect so they still
+/// show up as selected.
+pub(crate) fn highlight_rects(buffer: &Buffer, start: Cursor, end: Cursor) -> Vec<Rect> {
+    let (start, end) = if end < start {
+        (end, start)
+    } else {
+        (start, end)
+    };
+
+    buffer
+        .layout_runs()
+        .filter_map(|run| {
+            let (x, width) = match run.highlight(start, end) {
+                Some(highlight) => highlight,
+                None if run.glyphs.is_empty()
+                    && run.line_i >= start.line
+                    && run.line_i < end.line =>
+                {
+                    (0., run.line_height / 4.)
+                }
+                None => return None,
+            };
+
+            Some(Rect::new(x, run.line_top, width, run.line_height))
+        })
+        .collect()
+}
+
+/// Vertical scroll of the buffer that keeps the caret inside of `visible_height`,
+/// changed as little as possible from the current `scroll`.
+pub(crate) fn scroll_to_caret(
+    buffer: &Buffer,
+    cursor: Cursor,
+    scroll: f32,
+    visible_height: f32,
+) -> f32 {
+    let line_height = buffer.metrics().line_height;
+    let content_height = buffer.layout_runs().count() as f32 * line_height;
+    let mut scroll = scroll;
+
+    if let Some(caret) = caret_position(buffer, cursor) {
+        if caret.y < scroll {
+            scroll = caret.y;
+        } else if caret.y + line_height > scroll + visible_height {
+            scroll = caret.y + line_height - visible_height;
+        }
+    }
+
+    scroll.clamp(0., (content_height - visible_height).max(0.))
+}
+
+/// Moves the cursor by `delta` visual lines keeping it as close as possible to the
+/// goal column `goal_x`, which is the current caret x if `None`. Returns the new
+/// cursor and the goal column for the next vertical motion.
+pub(crate) fn vertical_motion(
+    buffer: &mut Buffer,
+    font_system: &mut FontSystem,
+    cursor: Cursor,
+    goal_x: Option<f32>,
+    delta: isize,
+) -> Option<(Cursor, f32)> {
+    let mut line = cursor.line;
+    let layout = buffer.line_layout(font_system, line)?;
+    let mut visual = visual_line(layout, cursor);
+
+    let goal_x = match goal_x {
+        Some(goal_x) => goal_x,
+        None => {
+            let text = buffer.lines[line].text();
+            let glyphs = &buffer.lines[line].layout_opt()?.get(visual)?.glyphs;
+
+            caret_x(text, glyphs, cursor.index)
+        }
+    };
+
+    for _ in 0..delta.unsigned_abs() {
+        if delta < 0 {
+            if visual > 0 {
+                visual -= 1;
+            } else if line > 0 {
+                line -= 1;
+                visual = buffer
+                    .line_layout(font_system, line)?
+                    .len()
+                    .saturating_sub(1);
+            } else {
+                break;
+            }
+        } else {
+            let visual_lines = buffer.line_layout(font_system, line)?.len();
+
+            if visual + 1 < visual_lines {
+                visual += 1;
+            } else if line + 1 < buffer.lines.len() {
+                line += 1;
+                visual = 0;
+            } else {
+                break;
+            }
+        }
+    }
+
+    buffer.line_layout(font_system, line)?;
+
+    let line_text = &buffer.lines[line];
+    let glyphs = line_text
+        .layout_opt()
+        .and_then(|layout| layout.get(visual))
+        .map_or(&[][..], |it| &it.glyphs);
+    let cursor = hit_visual_line(line_text.text(), line, glyphs, goal_x);
+
+    Some((cursor, goal_x))
+}
+
+/// Text between two cursors in logical order, lines are joined with `\n` only at real
+/// line breaks whatever the line endings of the buffer are.
+pub(crate) fn text_between(buffer: &Buffer, start: Cursor, end: Cursor) -> String {
+    let mut text = String::new();
+
+    for (line, buffer_line) in buffer
+        .lines
+        .iter()
+        .enumerate()
+        .take(end.line + 1)
+        .skip(start.line)
+    {
+        let line_text = buffer_line.text();
+        let from = if line == start.line { start.index } else { 0 };
+        let to = if line == end.line {
+            end.index.min(line_text.len())
+        } else {
+            line_text.len()
+        };
+
+        if line > start.line {
+            text.push('\n');
+        }
+
+        text.push_str(line_text.get(from.min(to)..to).unwrap_or_default());
+    }
+
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Seven and a half monospace columns, "aaaa bbbb cccc" wraps after every word.
+    fn wrapped_buffer(font_system: &mut FontSystem) -> Buffer {
+        let metrics = cosmic_text::Metrics::new(10., 12.);
+        let mut buffer = Buffer::new(font_system, metrics);
+        let attrs = cosmic_text::Attrs::new().family(cosmic_text::Family::Monospace);
+
+        buffer.set_text(
+            font_system,
+            "aaaa bbbb cccc",
+            &attrs,
+            cosmic_text::Shaping::Advanced,
+        );
+
+        let advance = char_advance(&mut buffer, font_system);
+        buffer.set_size(font_system, Some(advance * 7.5), None);
+        buffer.shape_until_scroll(font_system, false);
+
+        buffer
+    }
+
+    fn char_advance(buffer: &mut Buffer, font_system: &mut FontSystem) -> f32 {
+        buffer.line_layout(font_system, 0).unwrap()[0].glyphs[0].w
+    }
+
+    fn font_system() -> FontSystem {
+        crate::text::test_fonts().font_system
+    }
+
+    #[test]
+    fn test_click_at_end_of_wrapped_line_stays_on_it() {
+        let mut font_system = font_system();
+        let mut buffer = wrapped_buffer(&mut font_system);
+        let advance = char_advance(&mut buffer, &mut font_system);
+        let layout = buffer.line_layout(&mut font_system, 0).unwrap();
+        assert_eq!(layout.len(), 3);
+
+        // Trailing half of the last glyph before the space the line is wrapped at.
+        let cursor = hit(&buffer, advance * 3.8, 6.).unwrap();
+        assert_eq!(cursor, Cursor::new_with_affinity(0, 4, Affinity::Before));
+        assert_eq!(
+            caret_position(&buffer, cursor).unwrap(),
+            Vec2::new(advance * 4., 0.)
+        );
+
+        let cursor = hit(&buffer, 0., 18.).unwrap();
+        assert_eq!(cursor, Cursor::new_with_affinity(0, 5, Affinity::After));
+        assert_eq!(caret_position(&buffer, cursor).unwrap(), Vec2::new(0., 12.));
+
+        // Without whitespace both visual lines share the boundary index.
+        let attrs = cosmic_text::Attrs::new().family(cosmic_text::Family::Monospace);
+        buffer.set_wrap(&mut font_system, cosmic_text::Wrap::Glyph);
+        buffer.set_text(
+            &mut font_system,
+            "aaaaaaaaaa",
+            &attrs,
+            cosmic_text::Shaping::Advanced,
+        );
+        buffer.shape_until_scroll(&mut font_system, false);
+
+        let cursor = hit(&buffer, advance * 6.8, 6.).unwrap();
+        assert_eq!(cursor, Cursor::new_with_affinity(0, 7, Affinity::Before));
+        assert_eq!(
+            caret_position(&buffer, cursor).unwrap(),
+            Vec2::new(advance * 7., 0.)
+        );
+
+        let next = Cursor::new_with_affinity(0, 7, Affinity::After);
+        assert_eq!(caret_position(&buffer, next).unwrap(), Vec2::new(0., 12.));
+    }
+
+    #[test]
+    fn test_scroll_follows_caret_past_max_lines() {
+        let mut font_system = font_system();
+        let buffer = wrapped_buffer(&mut font_system);
+        let two_lines = 24.;
+
+        let end = Cursor::new_with_affinity(0, 14, Affinity::Before);
+        assert_eq!(scroll_to_caret(&buffer, end, 0., two_lines), 12.);
+
+        // Moving up within the visible lines keeps the scroll.
+        let middle = Cursor::new_with_affinity(0, 7, Affinity::After);
+        assert_eq!(scroll_to_caret(&buffer, middle, 12., two_lines), 12.);
+
+        let start = Cursor::new_with_affinity(0, 0, Affinity::After);
+        assert_eq!(scroll_to_caret(&buffer, start, 12., two_lines), 0.);
+
+        // Everything fits, nothing to scroll.
+        assert_eq!(scroll_to_caret(&buffer, end, 12., 36.), 0.);
+    }
+
+    #[test]
+    fn test_vertical_motion_keeps_goal_column_across_wraps() {
+        let mut font_system = font_system();
+        let mut buffer = wrapped_buffer(&mut font_system);
+        let advance = char_advance(&mut buffer, &mut font_system);
+
+        let start = Cursor::new_with_affinity(0, 2, Affinity::After);
+        let (cursor, goal_x) =
+            vertical_motion(&mut buffer, &mut font_system, start, None, 1).unwrap();
+        assert_eq!(cursor, Cursor::new_with_affinity(0, 7, Affinity::After));
+        assert_eq!(goal_x, advance * 2.);
+
+        let (cursor, _) =
+            vertical_motion(&mut buffer, &mut font_system, cursor, Some(goal_x), 1).unwrap();
+        assert_eq!(cursor, Cursor::new_with_affinity(0, 12, Affinity::After));
+
+        let (cursor, _) =
+            vertical_motion(&mut buffer, &mut font_system, cursor, Some(goal_x), -2).unwrap();
+        assert_eq!(cursor, start);
+
+        // The end of a wrapped line keeps the cursor on it.
+        let end = Cursor::new_with_affinity(0, 14, Affinity::Before);
+        let (cursor, _) = vertical_motion(&mut buffer, &mut font_system, end, None, -1).unwrap();
+        assert_eq!(cursor, Cursor::new_with_affinity(0, 9, Affinity::Before));
+    }
+
+    #[test]
+    fn test_text_between_is_in_logical_order() {
+        let mut font_system = font_system();
+        let mut buffer = wrapped_buffer(&mut font_system);
+
+        // Wrap boundaries are not line breaks.
+        let text = text_between(&buffer, Cursor::new(0, 2), Cursor::new(0, 12));
+        assert_eq!(text, "aa bbbb cc");
+
+        let attrs = cosmic_text::Attrs::new();
+        buffer.set_size(&mut font_system, None, None);
+        buffer.set_text(
+            &mut font_system,
+            "abc שלום\r\nעולם def",
+            &attrs,
+            cosmic_text::Shaping::Advanced,
+        );
+        buffer.shape_until_scroll(&mut font_system, false);
+
+        let rtl_end = "abc שלום".len();
+        let text = text_between(&buffer, Cursor::new(0, 4), Cursor::new(0, rtl_end));
+        assert_eq!(text, "שלום");
+
+        let text = text_between(&buffer, Cursor::new(0, 2), Cursor::new(1, "עולם d".len()));
+        assert_eq!(text, "c שלום\nעולם d");
+    }
+
+    #[test]
+    fn test_selection_is_highlighted_per_visual_line() {
+        let mut font_system = font_system();
+        let mut buffer = wrapped_buffer(&mut font_system);
+        let advance = char_advance(&mut buffer, &mut font_system);
+
+        let rects = highlight_rects(&buffer, Cursor::new(0, 12), Cursor::new(0, 2));
+        assert_eq!(rects.len(), 3);
+        assert_eq!(rects[0].x, advance * 2.);
+        assert_eq!(rects[2].width, advance * 2.);
+        assert!(rects.windows(2).all(|pair| pair[0].y < pair[1].y));
+    }
+}
@@ -0,0 +1,658 @@
+//! Formatting of an editable text as it's typed, e.g. the parentheses and the dashes of
+//! a phone number or the separators of the thousands.
+//!
+//! The [`crate::TextData`] keeps the raw value, the characters typed into the field,
+//! and the editor shows the text the [`Formatter`] makes of it. Each edit of the
+//! displayed text is mapped back to the raw value: the typed and pasted text is
+//! filtered, deleting only the literals of the format deletes the raw character before
+//! them (after them for Delete), and the cursor is put back next to the same raw
+//! character. The deltas reported and kept in the history are the ones of the raw
+//! value.
+
+use std::{ops::Range, sync::Arc};
+
+use cosmic_text::{Cursor, Edit, Editor, Selection};
+
+use crate::{
+    text_data::TextData,
+    text_history::{TextDeletionDirection, TextEditDelta},
+};
+
+/// Format of the text of an editable text, see
+/// [`super::EditableTextBuilder::formatter`].
+pub trait Formatter: Send + Sync {
+    /// Characters of the text the raw value can have, in order, e.g. the digits of a
+    /// phone number cut to the length of the mask. The typed and pasted text goes
+    /// through it before it's added to the value, it has to keep a filtered text as is.
+    fn filter(&self, text: &str) -> String;
+
+    /// Text shown for the raw value and the position of each raw character in it.
+    fn format(&self, raw: &str) -> FormattedText;
+}
+
+/// Displayed text of a raw value.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct FormattedText {
+    pub text: String,
+    /// Byte range in the text of each character of the raw value, in the order of the
+    /// raw value. The rest of the text is made of the literals of the format.
+    pub raw_ranges: Vec<Range<usize>>,
+}
+
+impl FormattedText {
+    /// Position in the text of the cursor before the raw character with the index,
+    /// after the literals leading to it. At the end of the value it's the end of the
+    /// text.
+    pub fn display_index(&self, raw_index: usize) -> usize {
+        self.raw_ranges
+            .get(raw_index)
+            .map_or(self.text.len(), |range| range.start)
+    }
+
+    /// Index of the raw character the cursor at the position of the text is before,
+    /// the cursor in the literals is before the raw character after them.
+    pub fn raw_index(&self, display_index: usize) -> usize {
+        self.raw_ranges
+            .iter()
+            .take_while(|range| range.start < display_index)
+            .count()
+    }
+
+    /// Position the cursor at the position of the text is moved to so it's next to a
+    /// raw character, it skips the literals in the direction it's moving.
+    pub fn snap(&self, display_index: usize, backward: bool) -> usize {
+        let positions = self
+            .raw_ranges
+            .iter()
+            .flat_map(|range| [range.start, range.end])
+            .chain([self.text.len()]);
+
+        let snapped = if backward {
+            positions.filter(|it| *it <= display_index).max()
+        } else {
+            positions.filter(|it| *it >= display_index).min()
+        };
+
+        snapped.unwrap_or_else(|| self.display_index(0))
+    }
+}
+
+/// Pattern of a fixed format, `#` stands for a digit, `A` for a letter and `*` for a
+/// letter or a digit, `\` makes the next character a literal. The rest are literals,
+/// shown as the value reaches them, e.g. `(###) ###-####` for a phone number.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mask {
+    tokens: Vec<MaskToken>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum MaskToken {
+    Digit,
+    Letter,
+    Alphanumeric,
+    Literal(char),
+}
+
+impl MaskToken {
+    fn accepts(self, c: char) -> bool {
+        match self {
+            MaskToken::Digit => c.is_ascii_digit(),
+            MaskToken::Letter => c.is_alphabetic(),
+            MaskToken::Alphanumeric => c.is_alphanumeric(),
+            MaskToken::Literal(_) => false,
+        }
+    }
+}
+
+impl Mask {
+    pub fn new(pattern: &str) -> Self {
+        let mut tokens = Vec::new();
+        let mut chars = pattern.chars();
+
+        while let Some(c) = chars.next() {
+            tokens.push(match c {
+                '#' => MaskToken::Digit,
+                'A' => MaskToken::Letter,
+                '*' => MaskToken::Alphanumeric,
+                '\\' => match chars.next() {
+                    Some(c) => MaskToken::Literal(c),
+                    None => break,
+                },
+                c => MaskToken::Literal(c),
+            });
+        }
+
+        Self { tokens }
+    }
+
+    fn slots(&self) -> impl Iterator<Item = MaskToken> + '_ {
+        self.tokens
+            .iter()
+            .copied()
+            .filter(|token| !matches!(token, MaskToken::Literal(_)))
+    }
+}
+
+impl Formatter for Mask {
+    fn filter(&self, text: &str) -> String {
+        let mut slots = self.slots().peekable();
+
+        text.chars()
+            .filter(|c| {
+                if slots.peek().is_some_and(|slot| slot.accepts(*c)) {
+                    slots.next();
+                    true
+                } else {
+                    false
+                }
+            })
+            .collect()
+    }
+
+    fn format(&self, raw: &str) -> FormattedText {
+        let mut formatted = FormattedText::default();
+        let mut chars = raw.chars().peekable();
+
+        if chars.peek().is_none() {
+            return formatted;
+        }
+
+        // The literals up to the slot of the next character are shown, so they are
+        // skipped as the value is typed.
+        for token in &self.tokens {
+            match token {
+                MaskToken::Literal(c) => formatted.text.push(*c),
+                _ => {
+                    let Some(c) = chars.next() else {
+                        break;
+                    };
+                    let start = formatted.text.len();
+
+                    formatted.text.push(c);
+                    formatted.raw_ranges.push(start..formatted.text.len());
+                }
+            }
+        }
+
+        formatted
+    }
+}
+
+/// Number with its integer digits grouped by thousands, e.g. `1,234,567.89`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NumberGrouping {
+    separator: char,
+    decimal_separator: Option<char>,
+}
+
+impl NumberGrouping {
+    /// Integers grouped with the separator.
+    pub fn new(separator: char) -> Self {
+        Self {
+            separator,
+            decimal_separator: None,
+        }
+    }
+
+    /// Lets the number have a fraction after the decimal separator, it isn't grouped.
+    pub fn decimal_separator(mut self, decimal_separator: char) -> Self {
+        self.decimal_separator = Some(decimal_separator);
+
+        self
+    }
+}
+
+impl Formatter for NumberGrouping {
+    fn filter(&self, text: &str) -> String {
+        let mut has_fraction = false;
+
+        text.chars()
+            .filter(|c| {
+                if Some(*c) == self.decimal_separator && !has_fraction {
+                    has_fraction = true;
+                    true
+                } else {
+                    c.is_ascii_digit()
+                }
+            })
+            .collect()
+    }
+
+    fn format(&self, raw: &str) -> FormattedText {
+        let mut formatted = FormattedText::default();
+        let integer_digits = raw
+            .chars()
+            .take_while(|c| Some(*c) != self.decimal_separator)
+            .count();
+
+        for (index, c) in raw.chars().enumerate() {
+            if index > 0 && index < integer_digits && (integer_digits - index) % 3 == 0 {
+                formatted.text.push(self.separator);
+            }
+
+            let start = formatted.text.len();
+
+            formatted.text.push(c);
+            formatted.raw_ranges.push(start..formatted.text.len());
+        }
+
+        formatted
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DateOrder {
+    DayMonthYear,
+    MonthDayYear,
+    YearMonthDay,
+}
+
+/// Date typed as its digits, e.g. `31/12/2024`. The first digit of the day can't be
+/// above 3 and the one of the month above 1.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DateFormatter {
+    order: DateOrder,
+    mask: Mask,
+}
+
+impl DateFormatter {
+    pub fn new(order: DateOrder, separator: char) -> Self {
+        let pattern = match order {
+            DateOrder::DayMonthYear | DateOrder::MonthDayYear => {
+                format!("##{separator}##{separator}####")
+            }
+            DateOrder::YearMonthDay => format!("####{separator}##{separator}##"),
+        };
+
+        Self {
+            order,
+            mask: Mask::new(&pattern),
+        }
+    }
+
+    /// Largest first digit of the field with the digit at the index of the value.
+    fn max_leading_digit(&self, index: usize) -> Option<u32> {
+        let (day, month) = match self.order {
+            DateOrder::DayMonthYear => (0, 2),
+            DateOrder::MonthDayYear => (2, 0),
+            DateOrder::YearMonthDay => (6, 4),
+        };
+
+        if index == day {
+            Some(3)
+        } else if index == month {
+            Some(1)
+        } else {
+            None
+        }
+    }
+}
+
+impl Formatter for DateFormatter {
+    fn filter(&self, text: &str) -> String {
+        let mut raw = String::new();
+
+        for c in self.mask.filter(text).chars() {
+            let index = raw.len();
+
+            if let (Some(max), Some(digit)) = (self.max_leading_digit(index), c.to_digit(10))
+                && digit > max
+            {
+                continue;
+            }
+
+            raw.push(c);
+        }
+
+        raw
+    }
+
+    fn format(&self, raw: &str) -> FormattedText {
+        self.mask.format(raw)
+    }
+}
+
+/// Raw value of a formatted editable text and the text shown for it, kept in the
+/// widget state between the builds.
+#[derive(Clone)]
+pub(crate) struct FormatState {
+    formatter: Arc<dyn Formatter>,
+    raw: String,
+    formatted: FormattedText,
+}
+
+impl PartialEq for FormatState {
+    fn eq(&self, other: &Self) -> bool {
+        self.raw == other.raw && self.formatted == other.formatted
+    }
+}
+
+impl FormatState {
+    pub(crate) fn new(formatter: Arc<dyn Formatter>, raw: String) -> Self {
+        let formatted = formatter.format(&raw);
+
+        Self {
+            formatter,
+            raw,
+            formatted,
+        }
+    }
+
+    pub(crate) fn set_formatter(&mut self, formatter: Arc<dyn Formatter>) {
+        self.formatted = formatter.format(&self.raw);
+        self.formatter = formatter;
+    }
+
+    /// Maps the edit of the displayed text already made in the editor to the raw value
+    /// and shows the formatted result. Returns the delta of the raw value, `None` when
+    /// the edit doesn't change it, e.g. a letter typed into a number.
+    pub(crate) fn apply_edit(
+        &mut self,
+        editor: &mut Editor,
+        delta: &TextEditDelta,
+    ) -> Option<TextEditDelta> {
+        let (range, inserted, direction) = match delta {
+            TextEditDelta::Insert {
+                cursor_before,
+                text,
+                ..
+            } => (
+                cursor_before.index..cursor_before.index,
+                text.as_str(),
+                TextDeletionDirection::Backward,
+            ),
+            TextEditDelta::Delete {
+                start,
+                end,
+                direction,
+                ..
+            } => (
+                start.index.min(end.index)..start.index.max(end.index),
+                "",
+                *direction,
+            ),
+            TextEditDelta::Replace {
+                range_before: (start, end),
+                text_after,
+                ..
+            } => (
+                start.index.min(end.index)..start.index.max(end.index),
+                text_after.as_str(),
+                TextDeletionDirection::Backward,
+            ),
+        };
+
+        let raw_len = self.raw.chars().count();
+        let mut raw_start = self.formatted.raw_index(range.start);
+        let mut raw_end = self.formatted.raw_index(range.end).max(raw_start);
+
+        // Only literals were deleted, the raw character next to them goes instead.
+        if inserted.is_empty() && !range.is_empty() && raw_start == raw_end {
+            match direction {
+                TextDeletionDirection::Backward => raw_start = raw_start.saturating_sub(1),
+                TextDeletionDirection::Forward => raw_end = (raw_end + 1).min(raw_len),
+            }
+        }
+
+        let start = byte_index(&self.raw, raw_start);
+        let end = byte_index(&self.raw, raw_end);
+        let prefix = self
+            .formatter
+            .filter(&[&self.raw[..start], inserted].concat());
+        let raw = self.formatter.filter(&[&prefix, &self.raw[end..]].concat());
+        let cursor = prefix.chars().count().min(raw.chars().count());
+        let raw_delta = raw_delta(&self.raw, &raw, start, direction);
+
+        self.show(editor, raw, cursor);
+
+        raw_delta
+    }
+
+    /// Runs the edit on an editor of the raw value, e.g. an undo from the history, and
+    /// shows the result with the cursor next to the raw character the edit left it at.
+    pub(crate) fn edit_raw<R>(
+        &mut self,
+        editor: &mut Editor,
+        edit: impl FnOnce(&mut Editor) -> R,
+    ) -> R {
+        let mut data = TextData::from(&self.raw);
+        let (result, cursor) = {
+            let mut raw_editor = Editor::new(&mut data.buffer);
+            let result = edit(&mut raw_editor);
+            let cursor = raw_editor.cursor();
+
+            (result, cursor)
+        };
+        let raw = data.get_text();
+        let cursor = raw[..cursor.index.min(raw.len())].chars().count();
+
+        self.show(editor, raw, cursor);
+
+        result
+    }
+
+    /// Brings the editor in line with the raw value of the [`TextData`], it could have
+    /// been set or edited through another widget, and moves the cursor out of the
+    /// literals after a click or a motion. `previous_cursor` is the one of the last
+    /// build, the cursor skips the literals in the direction it moved from it.
+    pub(crate) fn sync(&mut self, editor: &mut Editor, raw: &str, previous_cursor: Cursor) {
+        let cursor = editor.cursor();
+        let shown = editor.with_buffer(|buffer| {
+            buffer.lines.len() == 1 && buffer.lines[0].text() == self.formatted.text
+        });
+
+        if raw != self.raw || !shown {
+            let raw_cursor = self
+                .formatted
+                .raw_index(cursor.index)
+                .min(raw.chars().count());
+
+            self.show(editor, raw.to_string(), raw_cursor);
+
+            return;
+        }
+
+        if editor.selection() == Selection::None {
+            let backward = cursor.line == previous_cursor.line && cursor < previous_cursor;
+            let index = self.formatted.snap(cursor.index, backward);
+
+            if index != cursor.index {
+                editor.set_cursor(Cursor::new(0, index));
+            }
+        }
+    }
+
+    /// Replaces the text of the editor with the formatted raw value and puts the cursor
+    /// before the raw character with the index.
+    fn show(&mut self, editor: &mut Editor, raw: String, raw_cursor: usize) {
+        self.formatted = self.formatter.format(&raw);
+        self.raw = raw;
+
+        let end = editor.with_buffer(|buffer| {
+            let line = buffer.lines.len().saturating_sub(1);
+
+            Cursor::new(line, buffer.lines.get(line).map_or(0, |it| it.text().len()))
+        });
+
+        editor.set_selection(Selection::None);
+        editor.delete_range(Cursor::new(0, 0), end);
+        editor.insert_at(Cursor::new(0, 0), &self.formatted.text, None);
+        editor.set_cursor(Cursor::new(0, self.formatted.display_index(raw_cursor)));
+    }
+}
+
+fn byte_index(text: &str, char_index: usize) -> usize {
+    text.char_indices()
+        .nth(char_index)
+        .map_or(text.len(), |(index, _)| index)
+}
+
+/// Delta from the raw value `before` to `after`, the changed range found around the
+/// start of the edit so the cursors of the undo and the redo are where it was made.
+fn raw_delta(
+    before: &str,
+    after: &str,
+    edit_start: usize,
+    direction: TextDeletionDirection,
+) -> Option<TextEditDelta> {
+    let prefix = before
+        .char_indices()
+        .zip(after.chars())
+        .take_while(|((index, a), b)| a == b && *index < edit_start)
+        .last()
+        .map_or(0, |((index, c), _)| index + c.len_utf8());
+    let suffix = before[prefix..]
+        .chars()
+        .rev()
+        .zip(after[prefix..].chars().rev())
+        .take_while(|(a, b)| a == b)
+        .map(|(c, _)| c.len_utf8())
+        .sum::<usize>();
+
+    let removed = &before[prefix..before.len() - suffix];
+    let added = &after[prefix..after.len() - suffix];
+    let at = |index| Cursor::new(0, index);
+
+    match (removed.is_empty(), added.is_empty()) {
+        (true, true) => None,
+        (true, false) => Some(TextEditDelta::Insert {
+            cursor_before: at(prefix),
+            cursor_after: at(prefix + added.len()),
+            text: added.to_string(),
+        }),
+        (false, true) => Some(TextEditDelta::Delete {
+            start: at(prefix),
+            end: at(prefix + removed.len()),
+            deleted_text: removed.to_string(),
+            direction,
+        }),
+        (false, false) => Some(TextEditDelta::Replace {
+            range_before: (at(prefix), at(prefix + removed.len())),
+            range_after: (at(prefix), at(prefix + added.len())),
+            text_before: removed.to_string(),
+            text_after: added.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_text(format: &mut FormatState, editor: &mut Editor, text: &str) {
+        let cursor_before = editor.cursor();
+        editor.insert_string(text, None);
+
+        let delta = TextEditDelta::Insert {
+            cursor_before,
+            cursor_after: editor.cursor(),
+            text: text.to_string(),
+        };
+
+        format.apply_edit(editor, &delta);
+    }
+
+    fn backspace(format: &mut FormatState, editor: &mut Editor) -> Option<TextEditDelta> {
+        let end = editor.cursor();
+        let start = Cursor::new(0, end.index - 1);
+        let deleted_text = format.formatted.text[start.index..end.index].to_string();
+
+        editor.delete_range(start, end);
+        editor.set_cursor(start);
+
+        format.apply_edit(
+            editor,
+            &TextEditDelta::Delete {
+                start,
+                end,
+                deleted_text,
+                direction: TextDeletionDirection::Backward,
+            },
+        )
+    }
+
+    fn shown(editor: &Editor) -> (String, usize) {
+        let text = editor.with_buffer(|buffer| buffer.lines[0].text().to_string());
+
+        (text, editor.cursor().index)
+    }
+
+    #[test]
+    fn test_typing_into_middle_of_mask_keeps_cursor() {
+        let mut data = TextData::new();
+        let mut editor = Editor::new(&mut data.buffer);
+        let mut format = FormatState::new(Arc::new(Mask::new("(###) ###-####")), String::new());
+
+        type_text(&mut format, &mut editor, "555");
+        assert_eq!(shown(&editor), ("(555) ".to_string(), 6));
+
+        // Pasted text goes through the mask.
+        type_text(&mut format, &mut editor, "12-34x");
+        assert_eq!(shown(&editor), ("(555) 123-4".to_string(), 11));
+
+        // After the `1`, the digits after the cursor move over the dash.
+        editor.set_cursor(Cursor::new(0, 7));
+        type_text(&mut format, &mut editor, "9");
+        assert_eq!(shown(&editor), ("(555) 192-34".to_string(), 8));
+
+        type_text(&mut format, &mut editor, "8");
+        assert_eq!(shown(&editor), ("(555) 198-234".to_string(), 10));
+        assert_eq!(format.raw, "555198234");
+
+        // Rejected text leaves the value and the cursor as they were.
+        type_text(&mut format, &mut editor, "a");
+        assert_eq!(shown(&editor), ("(555) 198-234".to_string(), 10));
+    }
+
+    #[test]
+    fn test_backspace_over_literal_deletes_previous_digit() {
+        let mut data = TextData::new();
+        let mut editor = Editor::new(&mut data.buffer);
+        let mut format = FormatState::new(Arc::new(Mask::new("(###) ###-####")), String::new());
+
+        type_text(&mut format, &mut editor, "5551234");
+        editor.set_cursor(Cursor::new(0, 6));
+
+        let delta = backspace(&mut format, &mut editor).unwrap();
+        assert_eq!(shown(&editor), ("(551) 234-".to_string(), 3));
+        assert_eq!(
+            delta,
+            TextEditDelta::Delete {
+                start: Cursor::new(0, 2),
+                end: Cursor::new(0, 3),
+                deleted_text: "5".to_string(),
+                direction: TextDeletionDirection::Backward,
+            }
+        );
+
+        // The delta is the one of the raw value, undone the digit is back before the
+        // cursor.
+        format.edit_raw(&mut editor, |raw| delta.undo(raw));
+        assert_eq!(shown(&editor), ("(555) 123-4".to_string(), 6));
+    }
+
+    #[test]
+    fn test_number_grouping_moves_separators() {
+        let grouping = NumberGrouping::new(',').decimal_separator('.');
+        let mut data = TextData::new();
+        let mut editor = Editor::new(&mut data.buffer);
+        let mut format = FormatState::new(Arc::new(grouping), String::new());
+
+        type_text(&mut format, &mut editor, "123.4.5");
+        assert_eq!(shown(&editor), ("123.45".to_string(), 6));
+
+        editor.set_cursor(Cursor::new(0, 0));
+        type_text(&mut format, &mut editor, "9");
+        assert_eq!(shown(&editor), ("9,123.45".to_string(), 2));
+
+        // Both sides of the separator are before the second digit, the cursor in the
+        // literals of the mask skips them in the direction it moves.
+        assert_eq!(format.formatted.raw_index(1), 1);
+        assert_eq!(format.formatted.raw_index(2), 1);
+
+        let phone = Mask::new("(###) ###").format("5551");
+        assert_eq!(phone.snap(5, true), 4);
+        assert_eq!(phone.snap(5, false), 6);
+        assert_eq!(phone.snap(0, true), 1);
+    }
+}
@@ -16,9 +16,17 @@ use crate::{
 };
 
 use super::{
-    CommonShortcut, EditableTextDelta, OsEvent, State, TextEditingShortcut, TextInputModifier,
+    CommonShortcut, EditCommand, EditableTextDelta, ImeComposition, OsEvent, State,
+    TextEditingShortcut, TextInputModifier, commands, cursor,
 };
 
+/// Selected text taken from the logical lines, `None` without a selection.
+fn selected_text(editor: &cosmic_text::Editor) -> Option<String> {
+    let (start, end) = editor.selection_bounds()?;
+
+    Some(editor.with_buffer(|buffer| cursor::text_between(buffer, start, end)))
+}
+
 #[derive(Copy, Clone)]
 enum ParagraphMotionDirection {
     Up,
@@ -67,6 +75,74 @@ fn move_paragraph(
     }
 }
 
+/// Moves the cursor by visual lines, so it doesn't skip the wrapped parts of a line.
+/// Returns the goal column to keep for the next vertical motion.
+fn move_vertically(
+    fonts: &mut FontResources,
+    editor: &mut cosmic_text::Editor,
+    goal_x: Option<f32>,
+    delta: isize,
+) -> Option<f32> {
+    let cursor = editor.cursor();
+    let (cursor, goal_x) = editor.with_buffer_mut(|buffer| {
+        cursor::vertical_motion(buffer, &mut fonts.font_system, cursor, goal_x, delta)
+    })?;
+
+    editor.set_cursor(cursor);
+
+    Some(goal_x)
+}
+
+/// Deletes the selection, or the character or the word next to the cursor in the
+/// direction without one. Returns `None` when there is nothing to delete.
+fn delete_at_cursor(
+    fonts: &mut FontResources,
+    editor: &mut cosmic_text::Editor,
+    has_selection: bool,
+    word_modifier: bool,
+    direction: TextDeletionDirection,
+) -> Option<TextEditDelta> {
+    if !has_selection {
+        let motion = match (direction, word_modifier) {
+            (TextDeletionDirection::Forward, false) => cosmic_text::Motion::Next,
+            (TextDeletionDirection::Forward, true) => cosmic_text::Motion::NextWord,
+            (TextDeletionDirection::Backward, false) => cosmic_text::Motion::Previous,
+            (TextDeletionDirection::Backward, true) => cosmic_text::Motion::PreviousWord,
+        };
+
+        editor.set_selection(cosmic_text::Selection::Normal(editor.cursor()));
+        editor.action(&mut fonts.font_system, cosmic_text::Action::Motion(motion));
+    }
+
+    let (start, end) = editor.selection_bounds()?;
+    let deleted_text = selected_text(editor).unwrap_or_default();
+
+    editor.delete_selection();
+    editor.set_selection(cosmic_text::Selection::None);
+
+    if start == end {
+        return None;
+    }
+
+    Some(TextEditDelta::Delete {
+        start,
+        end,
+        deleted_text,
+        direction,
+    })
+}
+
+fn set_affinity(editor: &mut cosmic_text::Editor, affinity: cosmic_text::Affinity) {
+    let cursor = editor.cursor();
+
+    editor.set_cursor(cosmic_text::Cursor::new_with_affinity(
+        cursor.line,
+        cursor.index,
+        affinity,
+    ));
+}
+
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn handle_interaction(
     id: WidgetId,
     user_input: &mut UserInput,
@@ -89,7 +165,7 @@ pub(crate) fn handle_interaction(
         if user_input.mouse_released {
             if interaction.is_hot(&id) {
                 interaction.set_inactive(&id);
-                interaction.focused = Some(id);
+                interaction.focus_with_pointer(&id);
                 os_events.push(OsEvent::FocusWindow);
             } else {
                 interaction.set_inactive(&id);
@@ -97,7 +173,7 @@ pub(crate) fn handle_interaction(
         }
     } else if user_input.mouse_left_pressed && interaction.is_hot(&id) {
         interaction.set_active(&id);
-        interaction.focused = Some(id);
+        interaction.focus_with_pointer(&id);
         os_events.push(OsEvent::FocusWindow);
     }
 
@@ -126,13 +202,17 @@ pub(crate) fn handle_interaction(
             && edit_shortcuts.contains(&shortcut)
         {
             if let Some(id) = state.text_id {
-                let editor = text.editor_mut(id);
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
                 normalize_editable_text_selection(state, view_config, editor);
             }
         }
 
         let has_selection = if let Some(id) = state.text_id {
-            let editor = text.editor_mut(id);
+            let Some(editor) = text.editor_mut(id) else {
+                return;
+            };
 
             editor.selection() != cosmic_text::Selection::None
         } else {
@@ -140,84 +220,52 @@ pub(crate) fn handle_interaction(
         };
 
         if let Some(id) = state.text_id {
-            let editor = text.editor_mut(id);
+            let Some(editor) = text.editor_mut(id) else {
+                return;
+            };
 
             if select_modifier && !has_selection {
                 editor.set_selection(cosmic_text::Selection::Normal(editor.cursor()));
             }
         };
 
-        if shortcuts_manager.is_shortcut(TextEditingShortcut::Delete) {
-            if let Some(id) = state.text_id {
-                let editor = text.editor_mut(id);
-
-                let (deleted_text, start, end) = if word_modifier && !has_selection {
-                    editor.set_selection(cosmic_text::Selection::Normal(editor.cursor()));
-                    editor.action(
-                        &mut fonts.font_system,
-                        cosmic_text::Action::Motion(cosmic_text::Motion::NextWord),
-                    );
-
-                    let (start, end) = editor
-                        .selection_bounds()
-                        .expect("Selection should be available");
-                    let text = editor
-                        .copy_selection()
-                        .expect("Selection should be available");
-
-                    editor.action(&mut fonts.font_system, cosmic_text::Action::Delete);
-                    editor.set_selection(cosmic_text::Selection::None);
-
-                    (text, start, end)
-                } else if !has_selection {
-                    editor.set_selection(cosmic_text::Selection::Normal(editor.cursor()));
-                    editor.action(
-                        &mut fonts.font_system,
-                        cosmic_text::Action::Motion(cosmic_text::Motion::Next),
-                    );
-
-                    let (start, end) = editor
-                        .selection_bounds()
-                        .expect("Selection should be available");
-                    let text = editor
-                        .copy_selection()
-                        .expect("Selection should be available");
-
-                    editor.action(&mut fonts.font_system, cosmic_text::Action::Delete);
-                    editor.set_selection(cosmic_text::Selection::None);
-
-                    (text, start, end)
-                } else {
-                    debug_assert!(has_selection);
-
-                    let (start, end) = editor
-                        .selection_bounds()
-                        .expect("Selection should be available");
-                    let text = editor
-                        .copy_selection()
-                        .expect("Selection should be available");
-
-                    editor.action(&mut fonts.font_system, cosmic_text::Action::Delete);
-                    editor.set_selection(cosmic_text::Selection::None);
+        for (shortcut, direction) in [
+            (TextEditingShortcut::Delete, TextDeletionDirection::Forward),
+            (
+                TextEditingShortcut::Backspace,
+                TextDeletionDirection::Backward,
+            ),
+        ] {
+            if shortcuts_manager.is_shortcut(shortcut)
+                && let Some(id) = state.text_id
+                && let Some(editor) = text.editor_mut(id)
+            {
+                let delta =
+                    delete_at_cursor(fonts, editor, has_selection, word_modifier, direction);
 
-                    (text, start, end)
-                };
+                if delta.is_some() {
+                    on_editable_text_updated(state, view_config, editor, delta);
+                }
+            }
+        }
 
-                on_editable_text_updated(
-                    state,
-                    view_config,
-                    editor,
-                    Some(TextEditDelta::Delete {
-                        start,
-                        end,
-                        deleted_text,
-                        direction: TextDeletionDirection::Forward,
-                    }),
-                );
+        for command in EditCommand::ALL {
+            if shortcuts_manager.is_shortcut(command.shortcut())
+                && let Some(id) = state.text_id
+                && let Some(editor) = text.editor_mut(id)
+                && let Some(delta) = commands::apply_command(editor, command)
+            {
+                on_editable_text_updated(state, view_config, editor, Some(delta));
             }
         }
 
-        if shortcuts_manager.is_shortcut(TextEditingShortcut::NextLine) && state.multi_line {
+        // Up, Down, Enter and Tab go to the open popup of the suggestions.
+        let suggestions_open = state.suggestions.open;
+
+        if shortcuts_manager.is_shortcut(TextEditingShortcut::NextLine)
+            && state.multi_line
+            && !suggestions_open
+        {
             user_input.text_input.push('\n');
             user_input.text_input_actions.push(TextInputAction::Insert);
 
@@ -229,7 +277,9 @@ pub(crate) fn handle_interaction(
             && let Some(id) = state.text_id
         {
             if state.multi_line {
-                let editor = text.editor_mut(id);
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
                 let cursor = editor.cursor();
 
                 editor.action(
@@ -245,13 +295,18 @@ pub(crate) fn handle_interaction(
                     );
                 }
 
+                // The start of a wrapped line is also the end of the previous one.
+                set_affinity(editor, cosmic_text::Affinity::After);
+
                 if !select_modifier {
                     editor.set_selection(cosmic_text::Selection::None);
                 }
 
                 on_editable_text_cursor_moved(state, view_config, editor);
             } else {
-                let editor = text.editor_mut(id);
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
                 editor.action(
                     &mut fonts.font_system,
                     cosmic_text::Action::Motion(cosmic_text::Motion::Home),
@@ -267,11 +322,14 @@ pub(crate) fn handle_interaction(
 
         if shortcuts_manager.is_shortcut(TextEditingShortcut::MoveEnd) {
             if let Some(id) = state.text_id {
-                let editor = text.editor_mut(id);
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
                 editor.action(
                     &mut fonts.font_system,
                     cosmic_text::Action::Motion(cosmic_text::Motion::ParagraphEnd),
                 );
+                set_affinity(editor, cosmic_text::Affinity::Before);
 
                 if !select_modifier {
                     editor.set_selection(cosmic_text::Selection::None);
@@ -282,18 +340,19 @@ pub(crate) fn handle_interaction(
         }
 
         if state.multi_line {
-            if shortcuts_manager.is_shortcut(TextEditingShortcut::MoveUp) {
+            if shortcuts_manager.is_shortcut(TextEditingShortcut::MoveUp) && !suggestions_open {
                 if let Some(id) = state.text_id {
-                    let editor = text.editor_mut(id);
+                    let Some(editor) = text.editor_mut(id) else {
+                        return;
+                    };
+
+                    let mut goal_x = None;
 
                     if !has_selection || select_modifier {
                         if paragraph_modifier {
                             move_paragraph(fonts, editor, ParagraphMotionDirection::Up);
                         } else {
-                            editor.action(
-                                &mut fonts.font_system,
-                                cosmic_text::Action::Motion(cosmic_text::Motion::Up),
-                            );
+                            goal_x = move_vertically(fonts, editor, state.goal_x, -1);
                         }
                     } else {
                         if let Some((start, _)) = editor.selection_bounds() {
@@ -301,28 +360,27 @@ pub(crate) fn handle_interaction(
                         }
 
                         editor.set_selection(cosmic_text::Selection::None);
-                        editor.action(
-                            &mut fonts.font_system,
-                            cosmic_text::Action::Motion(cosmic_text::Motion::Up),
-                        );
+                        goal_x = move_vertically(fonts, editor, None, -1);
                     }
 
                     on_editable_text_cursor_moved(state, view_config, editor);
+                    state.goal_x = goal_x;
                 }
             }
 
-            if shortcuts_manager.is_shortcut(TextEditingShortcut::MoveDown) {
+            if shortcuts_manager.is_shortcut(TextEditingShortcut::MoveDown) && !suggestions_open {
                 if let Some(id) = state.text_id {
-                    let editor = text.editor_mut(id);
+                    let Some(editor) = text.editor_mut(id) else {
+                        return;
+                    };
+
+                    let mut goal_x = None;
 
                     if !has_selection || select_modifier {
                         if paragraph_modifier {
                             move_paragraph(fonts, editor, ParagraphMotionDirection::Down);
                         } else {
-                            editor.action(
-                                &mut fonts.font_system,
-                                cosmic_text::Action::Motion(cosmic_text::Motion::Down),
-                            );
+                            goal_x = move_vertically(fonts, editor, state.goal_x, 1);
                         }
                     } else {
                         if let Some((_, end)) = editor.selection_bounds() {
@@ -330,19 +388,19 @@ pub(crate) fn handle_interaction(
                         }
 
                         editor.set_selection(cosmic_text::Selection::None);
-                        editor.action(
-                            &mut fonts.font_system,
-                            cosmic_text::Action::Motion(cosmic_text::Motion::Down),
-                        );
+                        goal_x = move_vertically(fonts, editor, None, 1);
                     }
 
                     on_editable_text_cursor_moved(state, view_config, editor);
+                    state.goal_x = goal_x;
                 }
             }
 
             if shortcuts_manager.is_shortcut(TextEditingShortcut::PageUp) {
                 if let Some(id) = state.text_id {
-                    let editor = text.editor_mut(id);
+                    let Some(editor) = text.editor_mut(id) else {
+                        return;
+                    };
 
                     if !select_modifier {
                         editor.set_selection(cosmic_text::Selection::None);
@@ -359,7 +417,9 @@ pub(crate) fn handle_interaction(
 
             if shortcuts_manager.is_shortcut(TextEditingShortcut::PageDown) {
                 if let Some(id) = state.text_id {
-                    let editor = text.editor_mut(id);
+                    let Some(editor) = text.editor_mut(id) else {
+                        return;
+                    };
 
                     if !select_modifier {
                         editor.set_selection(cosmic_text::Selection::None);
@@ -376,7 +436,9 @@ pub(crate) fn handle_interaction(
 
             if shortcuts_manager.is_shortcut(TextEditingShortcut::BufferStart) {
                 if let Some(id) = state.text_id {
-                    let editor = text.editor_mut(id);
+                    let Some(editor) = text.editor_mut(id) else {
+                        return;
+                    };
 
                     if !select_modifier {
                         editor.set_selection(cosmic_text::Selection::None);
@@ -393,7 +455,9 @@ pub(crate) fn handle_interaction(
 
             if shortcuts_manager.is_shortcut(TextEditingShortcut::BufferEnd) {
                 if let Some(id) = state.text_id {
-                    let editor = text.editor_mut(id);
+                    let Some(editor) = text.editor_mut(id) else {
+                        return;
+                    };
 
                     if !select_modifier {
                         editor.set_selection(cosmic_text::Selection::None);
@@ -411,7 +475,9 @@ pub(crate) fn handle_interaction(
 
         if shortcuts_manager.is_shortcut(TextEditingShortcut::MovePrev) {
             if let Some(id) = state.text_id {
-                let editor = text.editor_mut(id);
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
 
                 if !has_selection || select_modifier {
                     if has_selection && !state.direction_decided {
@@ -434,7 +500,7 @@ pub(crate) fn handle_interaction(
                     let bounds = editor.selection_bounds();
 
                     if let Some((start, end)) = bounds {
-                        match view_config.layout_direction {
+                        match cursor_paragraph_direction(state, view_config, editor) {
                             LayoutDirection::LTR => editor.set_cursor(start),
                             LayoutDirection::RTL => editor.set_cursor(end),
                         }
@@ -456,7 +522,9 @@ pub(crate) fn handle_interaction(
 
         if shortcuts_manager.is_shortcut(TextEditingShortcut::MoveNext) {
             if let Some(id) = state.text_id {
-                let editor = text.editor_mut(id);
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
 
                 if !has_selection || select_modifier {
                     if has_selection && !state.direction_decided {
@@ -479,7 +547,7 @@ pub(crate) fn handle_interaction(
                     let bounds = editor.selection_bounds();
 
                     if let Some((start, end)) = bounds {
-                        match view_config.layout_direction {
+                        match cursor_paragraph_direction(state, view_config, editor) {
                             LayoutDirection::LTR => editor.set_cursor(end),
                             LayoutDirection::RTL => editor.set_cursor(start),
                         }
@@ -501,7 +569,9 @@ pub(crate) fn handle_interaction(
 
         if shortcuts_manager.is_shortcut(TextEditingShortcut::SelectAll) {
             if let Some(id) = state.text_id {
-                let editor = text.editor_mut(id);
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
                 editor.set_selection(cosmic_text::Selection::None);
                 editor.action(
                     &mut fonts.font_system,
@@ -519,8 +589,10 @@ pub(crate) fn handle_interaction(
         if let Some(clipboard) = clipboard {
             if shortcuts_manager.is_shortcut(CommonShortcut::Copy) {
                 if let Some(id) = state.text_id {
-                    let editor = text.editor_mut(id);
-                    let text = editor.copy_selection();
+                    let Some(editor) = text.editor_mut(id) else {
+                        return;
+                    };
+                    let text = selected_text(editor);
 
                     if let Some(text) = text {
                         if let Err(err) = clipboard.set_text(text) {
@@ -534,8 +606,10 @@ pub(crate) fn handle_interaction(
                 && let Some(id) = state.text_id
                 && has_selection
             {
-                let editor = text.editor_mut(id);
-                let text = editor.copy_selection();
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
+                let text = selected_text(editor);
 
                 if let Some(text) = text {
                     match clipboard.set_text(text.clone()) {
@@ -566,12 +640,14 @@ pub(crate) fn handle_interaction(
 
             if shortcuts_manager.is_shortcut(CommonShortcut::Paste) {
                 if let Some(id) = state.text_id {
-                    let editor = text.editor_mut(id);
+                    let Some(editor) = text.editor_mut(id) else {
+                        return;
+                    };
 
                     match clipboard.get_text() {
                         Ok(text) => {
                             let bounds = editor.selection_bounds();
-                            let selected_text = editor.copy_selection();
+                            let selected_text = selected_text(editor);
 
                             let after_start = if let Some((before_start, _)) = bounds {
                                 before_start
@@ -608,10 +684,17 @@ pub(crate) fn handle_interaction(
             }
         }
 
-        if shortcuts_manager.is_shortcut(CommonShortcut::Undo) {
+        if !state.external_history && shortcuts_manager.is_shortcut(CommonShortcut::Undo) {
             if let Some(id) = state.text_id {
-                let editor = text.editor_mut(id);
-                let delta = state.history_manager.undo(editor).cloned();
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
+                let delta = match &mut state.format {
+                    Some(format) => {
+                        format.edit_raw(editor, |raw| state.history_manager.undo(raw).cloned())
+                    }
+                    None => state.history_manager.undo(editor).cloned(),
+                };
 
                 on_editable_text_updated(state, view_config, editor, None);
 
@@ -621,10 +704,17 @@ pub(crate) fn handle_interaction(
             }
         }
 
-        if shortcuts_manager.is_shortcut(CommonShortcut::Redo) {
+        if !state.external_history && shortcuts_manager.is_shortcut(CommonShortcut::Redo) {
             if let Some(id) = state.text_id {
-                let editor = text.editor_mut(id);
-                let delta = state.history_manager.redo(editor).cloned();
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
+                let delta = match &mut state.format {
+                    Some(format) => {
+                        format.edit_raw(editor, |raw| state.history_manager.redo(raw).cloned())
+                    }
+                    None => state.history_manager.redo(editor).cloned(),
+                };
 
                 on_editable_text_updated(state, view_config, editor, None);
 
@@ -634,44 +724,54 @@ pub(crate) fn handle_interaction(
             }
         }
 
-        for text_input_action in &user_input.text_input_actions {
+        for text_input_action in std::mem::take(&mut user_input.text_input_actions) {
             match text_input_action {
                 TextInputAction::None => {}
                 TextInputAction::ImePreedit => {
                     if let Some(id) = state.text_id {
-                        let editor = text.editor_mut(id);
-
-                        if editor.selection() != cosmic_text::Selection::None {
-                            editor.delete_selection();
-                        }
-
-                        editor.delete_range(editor.cursor(), state.ime_cursor_end);
-                        on_editable_text_updated(state, view_config, editor, None);
+                        let Some(editor) = text.editor_mut(id) else {
+                            return;
+                        };
 
-                        if !user_input.ime_preedit.is_empty() {
-                            state.ime_cursor_end =
-                                editor.insert_at(editor.cursor(), &user_input.ime_preedit, None);
-                        }
+                        update_ime_composition(state, view_config, editor, user_input);
                     }
                 }
                 TextInputAction::ImeEnable => {}
                 TextInputAction::ImeDisable | TextInputAction::ImeCommit => {
                     if let Some(id) = state.text_id {
-                        let editor = text.editor_mut(id);
-                        editor.delete_range(editor.cursor(), state.ime_cursor_end);
-                        on_editable_text_updated(state, view_config, editor, None);
+                        let Some(editor) = text.editor_mut(id) else {
+                            return;
+                        };
+                        end_ime_composition(
+                            state,
+                            view_config,
+                            editor,
+                            user_input,
+                            matches!(text_input_action, TextInputAction::ImeCommit),
+                        );
                     }
                 }
                 TextInputAction::Insert => {
-                    if !user_input.text_input.is_empty()
+                    // The keys like Backspace and Enter come with control characters,
+                    // they are handled by the shortcuts.
+                    let typed: String = user_input
+                        .text_input
+                        .chars()
+                        .filter(|c| !c.is_control() || matches!(c, '\t' | '\n'))
+                        .collect();
+
+                    if !typed.is_empty()
                         && shortcuts_manager.active_shortcut_id().is_none()
+                        && !(suggestions_open && matches!(typed.as_str(), "\t" | "\n"))
                     {
                         if let Some(id) = state.text_id {
-                            let editor = text.editor_mut(id);
-                            let text = user_input.text_input.clone();
+                            let Some(editor) = text.editor_mut(id) else {
+                                return;
+                            };
+                            let text = typed;
 
                             let bounds = editor.selection_bounds();
-                            let selected_text = editor.copy_selection();
+                            let selected_text = selected_text(editor);
 
                             let after_start = if let Some((before_start, _)) = bounds {
                                 before_start
@@ -708,7 +808,9 @@ pub(crate) fn handle_interaction(
             }
         }
 
-        user_input.text_input_actions.clear();
+        // The platform commits the composition a click has committed by the frame after
+        // the click, if at all, a later commit of the same text is typed.
+        state.ime_committed = None;
 
         let mouse_dx = state.last_mouse_x - user_input.mouse_x;
         let mouse_dy = state.last_mouse_y - user_input.mouse_y;
@@ -723,21 +825,26 @@ pub(crate) fn handle_interaction(
             state.mouse_path_y += mouse_dy.abs();
 
             if let Some(id) = state.text_id {
-                let editor = text.editor_mut(id);
+                let Some(editor) = text.editor_mut(id) else {
+                    return;
+                };
 
                 let relative_mouse_x = user_input.mouse_x as f32
-                    - boundary.x * view.scale_factor.ceil()
+                    - boundary.x * view.scale_factor
                     - state.text_offset.x;
                 let relative_mouse_y = user_input.mouse_y as f32
-                    - boundary.y * view.scale_factor.ceil()
+                    - boundary.y * view.scale_factor
                     - state.text_offset.y;
 
                 let relative_mouse_x = relative_mouse_x.floor() as i32;
                 let relative_mouse_y = relative_mouse_y.floor() as i32;
 
                 if user_input.mouse_left_pressed {
-                    user_input.ime_preedit.clear();
-                    os_events.push(OsEvent::CommitIme);
+                    // Before the click moves the cursor away from the composition.
+                    if state.ime_composition.is_some() {
+                        commit_ime_composition(state, view_config, editor, user_input);
+                        os_events.push(OsEvent::CommitIme);
+                    }
 
                     if user_input.mouse_left_click_count == 1 {
                         if select_modifier {
@@ -759,13 +866,20 @@ pub(crate) fn handle_interaction(
                                 &mut fonts.font_system,
                                 cosmic_text::Action::Motion(cosmic_text::Motion::Home),
                             );
-                            editor.action(
-                                &mut fonts.font_system,
-                                cosmic_text::Action::Click {
-                                    x: relative_mouse_x,
-                                    y: relative_mouse_y,
-                                },
-                            );
+
+                            // Resolve the click ourselves, at the end of a wrapped
+                            // line it has to stay on that line.
+                            let hit = editor.with_buffer(|buffer| {
+                                cursor::hit(
+                                    buffer,
+                                    relative_mouse_x as f32,
+                                    relative_mouse_y as f32,
+                                )
+                            });
+
+                            if let Some(hit) = hit {
+                                editor.set_cursor(hit);
+                            }
                         }
                     } else if user_input.mouse_left_click_count % 2 == 0 {
                         editor.action(
@@ -792,8 +906,8 @@ pub(crate) fn handle_interaction(
                     && last_click_time.elapsed().as_millis() > 17
                     && (state.mouse_path_x > drag_trigger || state.mouse_path_y > drag_trigger)
                 {
-                    let height = boundary.height * view.scale_factor.ceil();
-                    let scroll_area_size = 8.0 * view.scale_factor.ceil();
+                    let height = boundary.height * view.scale_factor;
+                    let scroll_area_size = 8.0 * view.scale_factor;
                     let relative_mouse_y_f32 = relative_mouse_y as f32;
                     let at_top = relative_mouse_y_f32 <= scroll_area_size;
                     let at_bottom = relative_mouse_y_f32 >= height - scroll_area_size;
@@ -862,32 +976,153 @@ pub(crate) fn handle_interaction(
         }
 
         if let Some(id) = state.text_id {
-            let editor = text.editor_mut(id);
+            let Some(editor) = text.editor_mut(id) else {
+                return;
+            };
             if user_input.mouse_released {
                 normalize_editable_text_selection(state, view_config, editor);
             }
         }
     } else if interaction.was_focused(&id) {
-        user_input.ime_preedit.clear();
-        os_events.push(OsEvent::CommitIme);
-        view_config.should_update_cursor_each_frame = false;
+        on_focus_lost(state, user_input, os_events, text, fonts, view_config);
+    }
+}
+
+/// Commits the IME composition and resets the editor once the field has lost the
+/// focus, also when it has gone with the focus.
+pub(crate) fn on_focus_lost(
+    state: &mut State,
+    user_input: &mut UserInput,
+    os_events: &mut SmallVec<[OsEvent; 4]>,
+    text: &mut TextsResources,
+    fonts: &mut FontResources,
+    view_config: &mut ViewConfig,
+) {
+    // The composition stays in the text, the platform is told to drop it.
+    match state.text_id.and_then(|id| text.editor_mut(id)) {
+        Some(editor) => commit_ime_composition(state, view_config, editor, user_input),
+        None => clear_ime_preedit(user_input),
+    }
 
-        os_events.push(OsEvent::DeactivateIme);
+    // The field doesn't take the text input anymore, the commit the platform may send
+    // again goes nowhere.
+    state.ime_committed = None;
+    os_events.push(OsEvent::CommitIme);
+    view_config.should_update_cursor_each_frame = false;
 
-        state.history_manager.clear();
-        state.scroll_x = 0.;
+    os_events.push(OsEvent::DeactivateIme);
 
-        if let Some(id) = state.text_id {
-            let editor = text.editor_mut(id);
-            editor.set_selection(cosmic_text::Selection::None);
-            editor.action(
-                &mut fonts.font_system,
-                cosmic_text::Action::Motion(cosmic_text::Motion::Home),
-            );
+    state.history_manager.clear();
+    state.scroll_x = 0.;
 
-            on_editable_text_cursor_moved(state, view_config, editor);
-        }
+    if let Some(id) = state.text_id {
+        let Some(editor) = text.editor_mut(id) else {
+            return;
+        };
+        editor.set_selection(cosmic_text::Selection::None);
+        editor.action(
+            &mut fonts.font_system,
+            cosmic_text::Action::Motion(cosmic_text::Motion::Home),
+        );
+
+        on_editable_text_cursor_moved(state, view_config, editor);
+    }
+}
+
+/// Replaces the composition of the input method in the text with the preedit, it's
+/// inserted at the cursor.
+fn update_ime_composition(
+    state: &mut State,
+    view_config: &mut ViewConfig,
+    editor: &mut cosmic_text::Editor,
+    user_input: &UserInput,
+) {
+    if editor.selection() != cosmic_text::Selection::None {
+        editor.delete_selection();
+    }
+
+    remove_ime_composition(state, editor);
+    on_editable_text_updated(state, view_config, editor, None);
+
+    if !user_input.ime_preedit.is_empty() {
+        state.ime_committed = None;
+        state.ime_cursor_end = editor.insert_at(editor.cursor(), &user_input.ime_preedit, None);
+        state.ime_composition = Some(ImeComposition {
+            caret: user_input.ime_cursor_range.map(|(_, end)| end),
+            clauses: user_input.ime_clauses.clone(),
+        });
+    }
+}
+
+/// Takes the composition out of the text before the committed text is typed in its
+/// place, or when the input method is turned off.
+fn end_ime_composition(
+    state: &mut State,
+    view_config: &mut ViewConfig,
+    editor: &mut cosmic_text::Editor,
+    user_input: &mut UserInput,
+    committed: bool,
+) {
+    remove_ime_composition(state, editor);
+    on_editable_text_updated(state, view_config, editor, None);
+
+    // The platform may commit the composition a click has committed already, it isn't
+    // typed twice.
+    if state
+        .ime_committed
+        .take()
+        .is_some_and(|text| committed && text == user_input.text_input)
+    {
+        user_input.text_input.clear();
+    }
+}
+
+/// Takes the composition of the input method out of the text, the cursor stays where
+/// it was inserted.
+fn remove_ime_composition(state: &mut State, editor: &mut cosmic_text::Editor) {
+    if state.ime_composition.take().is_some() {
+        editor.delete_range(editor.cursor(), state.ime_cursor_end);
+    }
+
+    state.ime_cursor_end = editor.cursor();
+}
+
+/// Makes the composition of the input method a part of the text where it was inserted,
+/// in one edit, so nothing that moves the cursor afterwards splits or moves it.
+pub(crate) fn commit_ime_composition(
+    state: &mut State,
+    view_config: &mut ViewConfig,
+    editor: &mut cosmic_text::Editor,
+    user_input: &mut UserInput,
+) {
+    let text = std::mem::take(&mut user_input.ime_preedit);
+    clear_ime_preedit(user_input);
+
+    if state.ime_composition.take().is_none() || text.is_empty() {
+        return;
     }
+
+    let cursor_before = editor.cursor();
+    let cursor_after = state.ime_cursor_end;
+
+    editor.set_cursor(cursor_after);
+    on_editable_text_updated(
+        state,
+        view_config,
+        editor,
+        Some(TextEditDelta::Insert {
+            cursor_before,
+            cursor_after,
+            text: text.clone(),
+        }),
+    );
+    state.ime_committed = Some(text);
+}
+
+fn clear_ime_preedit(user_input: &mut UserInput) {
+    user_input.ime_preedit.clear();
+    user_input.ime_cursor_range = None;
+    user_input.ime_clauses.clear();
 }
 
 pub(crate) fn on_editable_text_updated(
@@ -896,7 +1131,24 @@ pub(crate) fn on_editable_text_updated(
     editor: &mut cosmic_text::Editor,
     delta: Option<TextEditDelta>,
 ) {
+    record_text_edit(state, editor, delta);
+    update_should_use_wide_space(view_config, editor);
+}
+
+/// Queues the delta for the [`crate::TextData`] and the history, and lets the view
+/// follow the cursor. The edit of a formatted text is mapped to its raw value first.
+pub(crate) fn record_text_edit(
+    state: &mut State,
+    editor: &mut cosmic_text::Editor,
+    delta: Option<TextEditDelta>,
+) {
+    let delta = match (&mut state.format, delta) {
+        (Some(format), Some(delta)) => format.apply_edit(editor, &delta),
+        (_, delta) => delta,
+    };
+
     state.ime_cursor_end = editor.cursor();
+    state.goal_x = None;
 
     if let Some(delta) = &delta {
         state.deltas.push(EditableTextDelta::Apply(delta.clone()));
@@ -907,12 +1159,10 @@ pub(crate) fn on_editable_text_updated(
     state.auto_scroll_to_cursor = true;
 
     if let Some(delta) = delta
-        && state.save_history
+        && !state.external_history
     {
         state.history_manager.push(delta);
     }
-
-    update_should_use_wide_space(view_config, editor);
 }
 
 #[allow(clippy::collapsible_else_if)]
@@ -990,6 +1240,7 @@ pub(crate) fn on_editable_text_cursor_moved(
     editor: &mut cosmic_text::Editor,
 ) {
     state.ime_cursor_end = editor.cursor();
+    state.goal_x = None;
     state.direction_decided =
         state.direction_decided || editor.selection() == cosmic_text::Selection::None;
     state.auto_scroll_to_cursor = true;
@@ -1035,6 +1286,24 @@ fn grapheme_before_cursor(text: &str, byte_index: usize) -> Option<&str> {
     }
 }
 
+/// Base direction of the paragraph with the cursor, it decides which end of the
+/// selection the horizontal motions collapse to.
+fn cursor_paragraph_direction(
+    state: &State,
+    view_config: &ViewConfig,
+    editor: &cosmic_text::Editor,
+) -> LayoutDirection {
+    let line = editor.cursor().line;
+
+    editor.with_buffer(|buffer| {
+        let paragraph = buffer.lines.get(line).map_or("", |line| line.text());
+
+        state
+            .text_direction
+            .resolve(paragraph, view_config.layout_direction)
+    })
+}
+
 pub(crate) fn decide_editable_text_direction_next(
     state: &mut State,
     view_config: &mut ViewConfig,
@@ -1046,7 +1315,7 @@ pub(crate) fn decide_editable_text_direction_next(
         if start == end {
             editor.set_selection(cosmic_text::Selection::None);
         } else {
-            match view_config.layout_direction {
+            match cursor_paragraph_direction(state, view_config, editor) {
                 LayoutDirection::LTR => {
                     editor.set_cursor(end);
                     editor.set_selection(cosmic_text::Selection::Normal(start));
@@ -1074,7 +1343,7 @@ pub(crate) fn decide_editable_text_direction_prev(
             editor.set_selection(cosmic_text::Selection::None);
             state.direction_decided = true;
         } else {
-            match view_config.layout_direction {
+            match cursor_paragraph_direction(state, view_config, editor) {
                 LayoutDirection::LTR => {
                     editor.set_cursor(start);
                     editor.set_selection(cosmic_text::Selection::Normal(end));
@@ -1089,3 +1358,161 @@ pub(crate) fn decide_editable_text_direction_prev(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::io::ImeClause;
+
+    use super::*;
+
+    fn editor(text: &str, cursor: usize) -> cosmic_text::Editor<'static> {
+        let mut buffer = cosmic_text::Buffer::new_empty(cosmic_text::Metrics::new(12., 12.));
+        buffer.lines.push(cosmic_text::BufferLine::new(
+            text,
+            cosmic_text::LineEnding::default(),
+            cosmic_text::AttrsList::new(&cosmic_text::Attrs::new()),
+            cosmic_text::Shaping::Advanced,
+        ));
+
+        let mut editor = cosmic_text::Editor::new(buffer);
+        editor.set_cursor(cosmic_text::Cursor::new(0, cursor));
+        editor
+    }
+
+    fn line_text(editor: &cosmic_text::Editor) -> String {
+        editor.with_buffer(|buffer| buffer.lines[0].text().to_string())
+    }
+
+    #[test]
+    fn test_copied_text_is_the_selection_whichever_way_it_was_made() {
+        let mut editor = editor("abc שלום def", 2);
+        assert_eq!(selected_text(&editor), None);
+
+        let start = cosmic_text::Cursor::new(0, 2);
+        let end = cosmic_text::Cursor::new(0, "abc שלום d".len());
+        editor.set_selection(cosmic_text::Selection::Normal(end));
+        assert_eq!(selected_text(&editor).as_deref(), Some("c שלום d"));
+
+        editor.set_cursor(end);
+        editor.set_selection(cosmic_text::Selection::Normal(start));
+        assert_eq!(selected_text(&editor).as_deref(), Some("c שלום d"));
+    }
+
+    #[test]
+    fn test_deleted_selection_is_recorded_like_the_copied_one() {
+        let mut editor = editor("ab", 1);
+        editor.with_buffer_mut(|buffer| {
+            buffer.lines.push(cosmic_text::BufferLine::new(
+                "cd",
+                cosmic_text::LineEnding::CrLf,
+                cosmic_text::AttrsList::new(&cosmic_text::Attrs::new()),
+                cosmic_text::Shaping::Advanced,
+            ))
+        });
+        editor.set_selection(cosmic_text::Selection::Normal(cosmic_text::Cursor::new(
+            1, 1,
+        )));
+        let copied = selected_text(&editor);
+
+        let delta = delete_at_cursor(
+            &mut FontResources::new(),
+            &mut editor,
+            true,
+            false,
+            TextDeletionDirection::Backward,
+        );
+
+        assert_eq!(copied.as_deref(), Some("b\nc"));
+        assert!(matches!(
+            delta,
+            Some(TextEditDelta::Delete { deleted_text, .. }) if copied.as_ref() == Some(&deleted_text)
+        ));
+    }
+
+    #[test]
+    fn test_preedit_replaces_the_last_one_and_is_taken_out_when_it_ends() {
+        let mut state = State::new();
+        let mut view_config = ViewConfig::default();
+        let mut user_input = UserInput::default();
+        let mut editor = editor("ac", 1);
+
+        for preedit in ["に", "にほ", "にほん"] {
+            user_input.ime_preedit = preedit.to_string();
+            update_ime_composition(&mut state, &mut view_config, &mut editor, &user_input);
+        }
+
+        assert_eq!(line_text(&editor), "aにほんc");
+        assert_eq!(editor.cursor(), cosmic_text::Cursor::new(0, 1));
+
+        // The committed text is typed in place of the composition afterwards.
+        user_input.text_input = "日本".to_string();
+        end_ime_composition(
+            &mut state,
+            &mut view_config,
+            &mut editor,
+            &mut user_input,
+            true,
+        );
+
+        assert_eq!(line_text(&editor), "ac");
+        assert!(state.ime_composition.is_none());
+        assert_eq!(user_input.text_input, "日本");
+
+        // Turning the input method off drops the composition.
+        user_input.ime_preedit = "ほん".to_string();
+        update_ime_composition(&mut state, &mut view_config, &mut editor, &user_input);
+        end_ime_composition(
+            &mut state,
+            &mut view_config,
+            &mut editor,
+            &mut user_input,
+            false,
+        );
+
+        assert_eq!(line_text(&editor), "ac");
+        assert_eq!(editor.cursor(), cosmic_text::Cursor::new(0, 1));
+    }
+
+    #[test]
+    fn test_click_commits_the_composition_where_it_is() {
+        let mut state = State::new();
+        let mut view_config = ViewConfig::default();
+        let mut user_input = UserInput::default();
+        let mut editor = editor("ac", 1);
+
+        user_input.ime_preedit = "にほん".to_string();
+        user_input.ime_cursor_range = Some((0, 6));
+        user_input.ime_clauses = vec![ImeClause {
+            range: 0..6,
+            selected: true,
+        }];
+        update_ime_composition(&mut state, &mut view_config, &mut editor, &user_input);
+
+        assert_eq!(line_text(&editor), "aにほんc");
+        assert!(state.ime_composition.as_ref().unwrap().caret == Some(6));
+
+        // The click commits the composition before it moves the cursor.
+        commit_ime_composition(&mut state, &mut view_config, &mut editor, &mut user_input);
+        editor.set_cursor(cosmic_text::Cursor::new(0, 0));
+
+        assert!(state.ime_composition.is_none());
+        assert!(user_input.ime_preedit.is_empty());
+        assert!(matches!(
+            state.deltas.last(),
+            Some(EditableTextDelta::Apply(TextEditDelta::Insert { text, .. })) if text == "にほん"
+        ));
+
+        // The platform commits it once more, nothing is deleted or typed.
+        user_input.text_input = "にほん".to_string();
+        end_ime_composition(
+            &mut state,
+            &mut view_config,
+            &mut editor,
+            &mut user_input,
+            true,
+        );
+
+        assert_eq!(line_text(&editor), "aにほんc");
+        assert!(user_input.text_input.is_empty());
+    }
+}
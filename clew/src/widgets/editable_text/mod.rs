@@ -1,21 +1,39 @@
+pub(crate) mod commands;
+pub(crate) mod cursor;
+pub(crate) mod format;
 pub(crate) mod interaction;
 pub(crate) mod render;
+pub(crate) mod suggestions;
+
+pub use commands::{EditCommand, add_edit_command_shortcuts};
+pub use format::{DateFormatter, DateOrder, FormattedText, Formatter, Mask, NumberGrouping};
+pub use suggestions::{Suggestion, SuggestionToken};
 
 pub(crate) use render::render;
 
-use std::time::Instant;
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use clew_derive::{ShortcutId, ShortcutModifierId, ShortcutScopeId, WidgetBuilder, WidgetState};
 use cosmic_text::Edit;
 
 use crate::{
-    AlignY, ColorRgba, TextAlign, Vec2, WidgetId, WidgetInteractionState, WidgetRef, WidgetType,
-    layout::{DeriveWrapSize, LayoutCommand},
+    AlignY, Clip, ColorRgba, EdgeInsets, Rect, TextAlign, TextDirection, Vec2, WidgetId,
+    WidgetInteractionState, WidgetRef, WidgetType,
+    io::ImeClause,
+    layout::{ContainerKind, DeriveWrapSize, LayoutCommand},
+    snapshot::{SerializableWidgetState, StateValue},
+    state::WidgetEntry,
     text::{Text, TextId},
-    text_data::TextData,
+    text_data::{TextData, TextDataWidget},
     text_history::{TextEditDelta, TextEditHistoryManager},
 };
 
+use format::FormatState;
+use suggestions::{SuggestionSource, SuggestionTrigger, SuggestionsState};
+
 use super::{BuildContext, FrameBuilder};
 
 pub struct EditableTextWidget;
@@ -24,11 +42,40 @@ pub struct EditableTextWidget;
 pub struct EditableTextBuilder<'a> {
     frame: FrameBuilder,
     color: ColorRgba,
+    selection_color: ColorRgba,
+    selected_text_color: ColorRgba,
+    ime_underline_color: Option<ColorRgba>,
+    ime_highlight_color: Option<ColorRgba>,
     text_align: TextAlign,
+    text_direction: TextDirection,
     vertical_align: AlignY,
+    min_lines: Option<u32>,
+    max_lines: Option<u32>,
+    tab_width: Option<u16>,
+    show_invisibles: bool,
+    accepts_tab: bool,
+    external_history: bool,
+    suggestions: Option<SuggestionSource<'a>>,
+    suggestion_trigger: Option<SuggestionTrigger<'a>>,
+    max_suggestions: usize,
+    formatter: Option<Arc<dyn Formatter>>,
     text: &'a mut TextData,
 }
 
+/// Edits of the text applied to the [`TextData`] in a build.
+///
+/// An edit made in a frame, typed or run with [`apply_edit_command`], is applied to the
+/// [`TextData`] when the widget is built in the next frame, and that build reports both
+/// [`EditableTextResponse::changed`] and the delta of the edit. The deltas come in the
+/// order they were made, so they can be pushed to the history as is.
+pub struct EditableTextResponse {
+    pub id: WidgetId,
+    changed: bool,
+    deltas: Vec<TextEditDelta>,
+    suggestion_token: Option<SuggestionToken>,
+    accepted_suggestion: Option<Suggestion>,
+}
+
 #[derive(Clone, PartialEq)]
 pub(crate) enum EditableTextDelta {
     Undo(TextEditDelta),
@@ -44,10 +91,13 @@ pub enum OsEvent {
 }
 
 #[derive(WidgetState, Clone, PartialEq)]
+#[widget_state(serializable)]
 pub(crate) struct State {
     pub(crate) text_id: Option<TextId>,
     pub(crate) deltas: Vec<EditableTextDelta>,
-    pub(crate) save_history: bool,
+    /// The edits are left to the history of the app, see
+    /// [`EditableTextBuilder::external_history`].
+    pub(crate) external_history: bool,
     pub(crate) scroll_x: f32,
     pub(crate) auto_scroll_to_cursor: bool,
     pub(crate) reached_end: bool,
@@ -55,26 +105,68 @@ pub(crate) struct State {
     pub(crate) recompose_text_content: bool,
     pub(crate) last_boundary_size: Vec2,
     pub(crate) ime_cursor_end: cosmic_text::Cursor,
+    /// Composition of the input method inserted at the cursor, until `ime_cursor_end`.
+    pub(crate) ime_composition: Option<ImeComposition>,
+    /// Composition committed in place by a click in the last frame, the platform may
+    /// commit it again in this one.
+    pub(crate) ime_committed: Option<String>,
     pub(crate) direction_decided: bool,
     pub(crate) text_offset: Vec2,
     pub(crate) history_manager: TextEditHistoryManager,
     pub(crate) multi_line: bool,
-    pub(crate) auto_rtl: bool,
+    pub(crate) text_direction: TextDirection,
     pub(crate) visible_view_updated: bool,
     pub(crate) last_mouse_x: f32,
     pub(crate) last_mouse_y: f32,
     pub(crate) mouse_path_x: f32,
     pub(crate) mouse_path_y: f32,
     pub(crate) last_drag: Option<Instant>,
+    /// Caret x vertical motions try to keep, reset by any other cursor movement.
+    pub(crate) goal_x: Option<f32>,
     pub(crate) color: ColorRgba,
+    pub(crate) selection_color: ColorRgba,
+    pub(crate) selected_text_color: ColorRgba,
+    pub(crate) ime_underline_color: Option<ColorRgba>,
+    pub(crate) ime_highlight_color: Option<ColorRgba>,
     pub(crate) vertical_align: AlignY,
+    /// Editor cursor as of the last build, kept here so it can be saved to a snapshot.
+    pub(crate) cursor: cosmic_text::Cursor,
+    pub(crate) selection: cosmic_text::Selection,
+    pub(crate) restored_cursor: Option<(cosmic_text::Cursor, cosmic_text::Selection)>,
+    /// The caret is shown since this time in the view, it blinks from there.
+    pub(crate) caret_blink_since: Duration,
+    pub(crate) caret_shown: bool,
+    pub(crate) show_invisibles: bool,
+    pub(crate) invisible_markers: Option<InvisibleMarkers>,
+    /// Commands requested with [`apply_edit_command`], run in the next build.
+    pub(crate) edit_commands: Vec<EditCommand>,
+    pub(crate) suggestions: SuggestionsState,
+    /// Raw value and the text shown for it when the field has a formatter.
+    pub(crate) format: Option<FormatState>,
+}
+
+/// Caret and clauses of the text being composed with the input method, in bytes from
+/// the start of the composition.
+#[derive(Clone, Default, PartialEq)]
+pub(crate) struct ImeComposition {
+    /// `None` hides the caret.
+    pub(crate) caret: Option<usize>,
+    pub(crate) clauses: Vec<ImeClause>,
+}
+
+/// Texts of the symbols drawn over the whitespace when the invisibles are shown.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) struct InvisibleMarkers {
+    pub(crate) space: TextId,
+    pub(crate) tab: TextId,
+    pub(crate) newline: TextId,
 }
 
 impl State {
     pub(crate) fn new() -> Self {
         Self {
             text_id: None,
-            save_history: true,
+            external_history: false,
             auto_scroll_to_cursor: false,
             reached_end: false,
             was_relayout: false,
@@ -82,20 +174,37 @@ impl State {
             scroll_x: 0.0,
             recompose_text_content: true,
             ime_cursor_end: cosmic_text::Cursor::default(),
+            ime_composition: None,
+            ime_committed: None,
             direction_decided: false,
             text_offset: Vec2::ZERO,
             history_manager: TextEditHistoryManager::new(20, true),
             multi_line: true,
-            auto_rtl: false,
+            text_direction: TextDirection::Auto,
             last_boundary_size: Vec2::ZERO,
             last_mouse_x: 0.,
             last_mouse_y: 0.,
             mouse_path_x: 0.,
             mouse_path_y: 0.,
             last_drag: None,
+            goal_x: None,
             deltas: vec![],
             color: ColorRgba::from_hex(0xFFFFFFFF),
+            selection_color: ColorRgba::from_hex(0xFF264F78),
+            selected_text_color: ColorRgba::from_hex(0xFFFFFFFF),
+            ime_underline_color: None,
+            ime_highlight_color: None,
             vertical_align: AlignY::Top,
+            cursor: cosmic_text::Cursor::default(),
+            selection: cosmic_text::Selection::None,
+            restored_cursor: None,
+            caret_blink_since: Duration::ZERO,
+            caret_shown: false,
+            show_invisibles: false,
+            invisible_markers: None,
+            edit_commands: Vec::new(),
+            suggestions: SuggestionsState::default(),
+            format: None,
         }
     }
 }
@@ -130,6 +239,13 @@ pub enum TextEditingShortcut {
     BufferStart,
     BufferEnd,
     SelectAll,
+    DeleteToLineEnd,
+    DeleteToLineStart,
+    DeleteLine,
+    DuplicateLine,
+    MoveLineUp,
+    MoveLineDown,
+    TransposeChars,
 }
 
 #[derive(Debug, Clone, Copy, ShortcutModifierId)]
@@ -139,6 +255,32 @@ pub enum TextInputModifier {
     Paragraph,
 }
 
+impl EditableTextResponse {
+    /// The text was changed in this build by an edit or an undo of the widget, the text
+    /// replaced or replayed through the [`TextData`] isn't reported.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Edits applied to the text in this build. Undoing one is done with
+    /// [`TextData::undo_delta`] and redoing it with [`TextData::apply_delta`], the deltas
+    /// of the undos of the widget's own history aren't reported.
+    pub fn deltas(&self) -> &[TextEditDelta] {
+        &self.deltas
+    }
+
+    /// Token the open popup of the suggestions completes, `None` when it's closed.
+    pub fn suggestion_token(&self) -> Option<&SuggestionToken> {
+        self.suggestion_token.as_ref()
+    }
+
+    /// Suggestion accepted in this build, its edit is reported with the deltas of the
+    /// next one.
+    pub fn accepted_suggestion(&self) -> Option<&Suggestion> {
+        self.accepted_suggestion.as_ref()
+    }
+}
+
 impl<'a> EditableTextBuilder<'a> {
     pub fn color(mut self, color: ColorRgba) -> Self {
         self.color = color;
@@ -146,92 +288,378 @@ impl<'a> EditableTextBuilder<'a> {
         self
     }
 
+    /// Background of the selected text.
+    pub fn selection_color(mut self, color: ColorRgba) -> Self {
+        self.selection_color = color;
+
+        self
+    }
+
+    /// Color of the glyphs inside of the selection, drawn over the selection background.
+    pub fn selected_text_color(mut self, color: ColorRgba) -> Self {
+        self.selected_text_color = color;
+
+        self
+    }
+
+    /// Color of the underlines of the text composed with the input method, the color
+    /// of the text by default.
+    pub fn ime_underline_color(mut self, color: ColorRgba) -> Self {
+        self.ime_underline_color = Some(color);
+
+        self
+    }
+
+    /// Highlights the clause of the composition being converted with a background of
+    /// the color and the selected text color, instead of a thicker underline.
+    pub fn ime_highlight_color(mut self, color: ColorRgba) -> Self {
+        self.ime_highlight_color = Some(color);
+
+        self
+    }
+
     pub fn text_align(mut self, text_align: TextAlign) -> Self {
         self.text_align = text_align;
 
         self
     }
 
+    /// Base direction of the paragraphs, by default each one is detected from its first
+    /// strong directional character. The cursor motions and the selection follow the
+    /// direction of the paragraph they are in, the characters themselves are ordered by
+    /// cosmic-text, which always detects the direction of each line.
+    pub fn text_direction(mut self, direction: TextDirection) -> Self {
+        self.text_direction = direction;
+
+        self
+    }
+
     pub fn text_vertical_align(mut self, align_y: AlignY) -> Self {
         self.vertical_align = align_y;
 
         self
     }
 
-    pub fn build_with_frame<F>(mut self, context: &mut BuildContext, callback: F)
+    /// Keeps the field at least `min_lines` lines tall.
+    pub fn min_lines(mut self, min_lines: u32) -> Self {
+        self.min_lines = Some(min_lines);
+
+        self
+    }
+
+    /// Lets the field grow with the content up to `max_lines` lines, the rest is scrolled
+    /// keeping the caret visible.
+    pub fn max_lines(mut self, max_lines: u32) -> Self {
+        self.max_lines = Some(max_lines);
+
+        self
+    }
+
+    /// Tabs advance to the next multiple of `tab_width` spaces, 8 by default.
+    pub fn tab_width(mut self, tab_width: u16) -> Self {
+        self.tab_width = Some(tab_width);
+
+        self
+    }
+
+    /// Draws dimmed markers over spaces, tabs and line breaks. They don't change the
+    /// layout or the text, the cursor moves over the characters they mark.
+    pub fn show_invisibles(mut self, show_invisibles: bool) -> Self {
+        self.show_invisibles = show_invisibles;
+
+        self
+    }
+
+    /// Types a tab when Tab is pressed in the focused field, e.g. in a code editor. By
+    /// default Tab moves the focus on to the next widget, the field is reached with Tab
+    /// either way.
+    pub fn accepts_tab(mut self, accepts_tab: bool) -> Self {
+        self.accepts_tab = accepts_tab;
+
+        self
+    }
+
+    /// Keeps the edits out of the history of the widget and leaves
+    /// [`CommonShortcut::Undo`] and [`CommonShortcut::Redo`] to the shortcut scope of the
+    /// app, so the text shares one history with the rest of the app data. The edits are
+    /// reported in [`EditableTextResponse::deltas`].
+    pub fn external_history(mut self, external_history: bool) -> Self {
+        self.external_history = external_history;
+
+        self
+    }
+
+    /// Offers the suggestions matching the text in a popup below the field, the ones
+    /// with the text in their label, ignoring the case. Up and Down move the highlight
+    /// while the field keeps the focus, Enter and Tab accept the highlighted one and
+    /// Escape dismisses the popup until the text changes.
+    pub fn suggestions(mut self, suggestions: &'a [Suggestion]) -> Self {
+        self.suggestions = Some(SuggestionSource::List(suggestions));
+
+        self
+    }
+
+    /// Like [`Self::suggestions`], with the suggestions made for the query by the
+    /// closure, e.g. looked up in an index. It's only called while the popup can be
+    /// shown.
+    pub fn suggestions_with(
+        mut self,
+        suggestions: impl FnOnce(&str) -> Vec<Suggestion> + 'a,
+    ) -> Self {
+        self.suggestions = Some(SuggestionSource::With(Box::new(suggestions)));
+
+        self
+    }
+
+    /// Completes the word under the cursor instead of the whole line, and only while
+    /// the trigger returns the query for it, e.g. `|token| token.strip_prefix('@')` for
+    /// the mentions. The accepted suggestion replaces the whole word.
+    pub fn suggestion_trigger(
+        mut self,
+        trigger: impl for<'t> Fn(&'t str) -> Option<&'t str> + 'a,
+    ) -> Self {
+        self.suggestion_trigger = Some(Box::new(trigger));
+
+        self
+    }
+
+    /// Most suggestions shown at once, 8 by default.
+    pub fn max_suggestions(mut self, max_suggestions: usize) -> Self {
+        self.max_suggestions = max_suggestions;
+
+        self
+    }
+
+    /// Shows the text in the format of the mask, see [`Mask`]. The [`TextData`] keeps
+    /// only the characters typed into the slots of the mask.
+    pub fn mask(self, pattern: &str) -> Self {
+        self.formatter(Mask::new(pattern))
+    }
+
+    /// Shows the text formatted as it's typed while the [`TextData`] keeps the raw
+    /// value, e.g. [`NumberGrouping`] or [`DateFormatter`]. The typed and pasted text is
+    /// filtered, the cursor skips the literals of the format and Backspace over a
+    /// literal deletes the character before it. The deltas and the history are the
+    /// ones of the raw value. The field should be single line.
+    pub fn formatter(mut self, formatter: impl Formatter + 'static) -> Self {
+        self.formatter = Some(Arc::new(formatter));
+
+        self
+    }
+
+    fn lines_limit(&self) -> Option<(u32, u32)> {
+        if self.min_lines.is_none() && self.max_lines.is_none() {
+            return None;
+        }
+
+        Some((
+            self.min_lines.unwrap_or(1),
+            self.max_lines.unwrap_or(u32::MAX),
+        ))
+    }
+
+    pub fn build_with_frame<F>(
+        mut self,
+        context: &mut BuildContext,
+        callback: F,
+    ) -> EditableTextResponse
     where
         F: FnOnce(&mut BuildContext, WidgetInteractionState, FrameBuilder) -> FrameBuilder,
     {
-        let id = self.frame.id.with_seed(context.id_seed);
-
-        let interaction = WidgetInteractionState {
-            is_hover: context.interaction.is_hover(&id),
-            is_hot: context.interaction.is_hot(&id),
-            is_active: context.interaction.is_active(&id),
-            is_focused: context.interaction.is_focused(&id),
-            was_focused: context.interaction.was_focused(&id),
-        };
-        self.frame = callback(context, interaction, self.frame);
+        let (entry, binding) = self.fetch_entry(context);
+        self.frame = callback(context, entry.interaction, self.frame);
 
-        self.build_with_id(context, id);
+        self.build_with_entry(context, entry, binding)
     }
 
     #[profiling::function]
-    pub fn build(self, context: &mut BuildContext) {
-        let id = self.frame.id.with_seed(context.id_seed);
-        self.build_with_id(context, id);
+    pub fn build(mut self, context: &mut BuildContext) -> EditableTextResponse {
+        let (entry, binding) = self.fetch_entry(context);
+        self.build_with_entry(context, entry, binding)
+    }
+
+    /// Looks the widget up once, the entry is passed through the rest of the build.
+    fn fetch_entry(&mut self, context: &mut BuildContext) -> (WidgetEntry, TextDataWidget) {
+        let id = context.widget_id(self.frame.id);
+        let mut entry =
+            context
+                .widgets_states
+                .editable_text
+                .entry(id, context.interaction, State::new);
+
+        self.text.forget_released_texts(context.text);
+
+        let binding = self.text.bind(id, |data| {
+            context
+                .text
+                .add_editor(context.view, context.fonts, 12., 12., |fonts, text| {
+                    text.set_text(fonts, &data.get_text())
+                })
+        });
+        entry.text_id = Some(binding.text_id);
+
+        (entry, binding)
     }
 
     #[inline(always)]
-    fn build_with_id(mut self, context: &mut BuildContext, id: WidgetId) {
+    fn build_with_entry(
+        mut self,
+        context: &mut BuildContext,
+        entry: WidgetEntry,
+        mut binding: TextDataWidget,
+    ) -> EditableTextResponse {
+        let id = entry.id;
         let widget_ref = WidgetRef::new(WidgetType::of::<EditableTextWidget>(), id);
+        let text_id = binding.text_id;
+        let state = context.widgets_states.editable_text.at(&entry);
 
-        let state = context
-            .widgets_states
-            .editable_text
-            .get_or_insert(id, || State::new());
-
-        let text_id = match self.text.text_id(id) {
-            Some(text_id) => text_id,
-            None => {
-                let text_id = context.text.add_editor(
-                    context.view,
-                    context.fonts,
-                    12.,
-                    12.,
-                    |fonts, text| text.set_text(fonts, &self.text.get_text()),
-                );
-                self.text.set_text_id(id, text_id);
+        context.widgets_states.snapshots.track(id, state);
 
-                text_id
+        // The raw value only has the characters the format can show.
+        let raw = self.formatter.as_ref().map(|formatter| {
+            let raw = self.text.get_text();
+            let filtered = formatter.filter(&raw);
+
+            if filtered != raw {
+                self.text.set_text(&filtered);
             }
-        };
 
-        state.text_id = self.text.text_id(id);
+            filtered
+        });
+
+        match (self.formatter.take(), &mut state.format) {
+            (Some(formatter), Some(format)) => format.set_formatter(formatter),
+            (Some(formatter), format) => {
+                *format = Some(FormatState::new(formatter, raw.unwrap_or_default()))
+            }
+            (None, format) => *format = None,
+        }
+
+        state.text_id = entry.text_id;
         state.color = self.color;
+        state.selection_color = self.selection_color;
+        state.selected_text_color = self.selected_text_color;
+        state.ime_underline_color = self.ime_underline_color;
+        state.ime_highlight_color = self.ime_highlight_color;
         state.vertical_align = self.vertical_align;
+        state.text_direction = self.text_direction;
+        state.show_invisibles = self.show_invisibles;
+        state.external_history = self.external_history;
+
+        if self.external_history {
+            state.history_manager.clear();
+        }
+
+        if let Some(tab_width) = self.tab_width {
+            context
+                .text
+                .get_mut(text_id)
+                .set_tab_width(context.fonts, tab_width);
+        }
+
+        if self.show_invisibles && state.invisible_markers.is_none() {
+            let mut marker = |symbol: &str| {
+                context
+                    .text
+                    .add_text(context.view, context.fonts, 12., 12., |fonts, text| {
+                        text.set_text(fonts, symbol)
+                    })
+            };
+
+            state.invisible_markers = Some(InvisibleMarkers {
+                space: marker("·"),
+                tab: marker("→"),
+                newline: marker("¶"),
+            });
+        }
+
+        // The field is measured to place the pointer in the text and the popup of the
+        // suggestions under it.
+        let field_rect = context
+            .widgets_states
+            .layout_measures
+            .get(id)
+            .map(|measure| Rect::new(measure.x, measure.y, measure.width, measure.height));
+        let is_focused = entry.interaction.is_focused;
+
+        if is_focused {
+            context
+                .shortcuts_manager
+                .push_scope(ShortcutScopes::TextEditing);
+        }
+
+        let uses_clipboard = is_focused
+            && [
+                CommonShortcut::Copy,
+                CommonShortcut::Cut,
+                CommonShortcut::Paste,
+            ]
+            .into_iter()
+            .any(|shortcut| context.shortcuts_manager.is_shortcut(shortcut));
+        let mut clipboard = if uses_clipboard {
+            arboard::Clipboard::new()
+                .inspect_err(|err| log::error!("Failed to access clipboard: {err}"))
+                .ok()
+        } else {
+            None
+        };
+
+        interaction::handle_interaction(
+            id,
+            context.input,
+            context.view,
+            context.interaction,
+            state,
+            context.os_events,
+            context.text,
+            context.fonts,
+            context.view_config,
+            context.shortcuts_manager,
+            clipboard.as_mut(),
+            field_rect.unwrap_or_default(),
+        );
+
+        if is_focused {
+            context
+                .shortcuts_manager
+                .pop_scope(context.input, context.shortcuts_registry);
+        }
 
-        if !state.deltas.is_empty() {
+        let changed = !state.deltas.is_empty();
+        let mut deltas = Vec::new();
+        let mut queued_deltas = std::mem::take(&mut binding.queued_deltas);
+
+        // Replaced text already has the deltas in it.
+        if binding.replace_buffer {
+            queued_deltas.clear();
+        }
+
+        if changed {
             for delta in state.deltas.drain(..) {
-                self.text.apply_delta(context.text, id, &delta);
+                self.text.apply_widget_delta(context.text, id, &delta);
+
+                if let EditableTextDelta::Apply(delta) = delta {
+                    deltas.push(delta);
+                }
+            }
+
+            // The text is replaced once the edits of the widget are in.
+            if binding.replace_buffer {
+                self.text.defer_replace_buffer(id);
             }
-        } else if self.text.replace_buffer.contains(&id) {
+        } else if binding.replace_buffer {
             state.recompose_text_content = true;
             state.history_manager.clear();
 
-            self.text.replace_buffer.remove(&id);
-
             let text = context.text.get_mut(text_id);
             let data = self.text.get_text();
 
             text.set_text(context.fonts, &data);
 
-            let editor = match text {
-                Text::Buffer { .. } => panic!("Provided text id is not editor"),
-                Text::Editor { editor, .. } => editor,
-            };
-
-            editor.set_cursor(cosmic_text::Cursor::default());
+            match text {
+                Text::Buffer { .. } => debug_assert!(false, "Provided text id is not editor"),
+                Text::Editor { editor, .. } => editor.set_cursor(cosmic_text::Cursor::default()),
+            }
 
             // on_cursor_moved(
             //     &mut state,
@@ -240,41 +668,217 @@ impl<'a> EditableTextBuilder<'a> {
             // );
         }
 
-        if self.text.dirty.contains(&id) {
+        if binding.dirty {
             state.recompose_text_content = true;
-            self.text.mark_as_not_dirty(&id);
         }
 
-        // interaction::handle_interaction(
-        //     id,
-        //     context.input,
-        //     context.view,
-        //     context.interaction,
-        //     state,
-        //     context.text,
-        //     context.fonts,
-        // );
+        let mut popup = None;
+        let mut accepted_suggestion = None;
+        let mut caret_moved = false;
+
+        if let Some(editor) = context.text.editor_mut(text_id) {
+            if let Some((cursor, selection)) = state.restored_cursor.take() {
+                // The content might have changed since the snapshot was taken.
+                let clamp = |cursor: cosmic_text::Cursor| {
+                    editor.with_buffer(|buffer| {
+                        let line = cursor.line.min(buffer.lines.len().saturating_sub(1));
+                        let index = buffer
+                            .lines
+                            .get(line)
+                            .map_or(0, |text| cursor.index.min(text.text().len()));
+
+                        cosmic_text::Cursor::new_with_affinity(line, index, cursor.affinity)
+                    })
+                };
+                let selection = match selection {
+                    cosmic_text::Selection::None => cosmic_text::Selection::None,
+                    cosmic_text::Selection::Normal(cursor) => {
+                        cosmic_text::Selection::Normal(clamp(cursor))
+                    }
+                    cosmic_text::Selection::Line(cursor) => {
+                        cosmic_text::Selection::Line(clamp(cursor))
+                    }
+                    cosmic_text::Selection::Word(cursor) => {
+                        cosmic_text::Selection::Word(clamp(cursor))
+                    }
+                };
+                let cursor = clamp(cursor);
+
+                editor.set_cursor(cursor);
+                editor.set_selection(selection);
+            }
+
+            if !queued_deltas.is_empty() {
+                let replay = |editor: &mut cosmic_text::Editor| {
+                    for delta in &queued_deltas {
+                        match delta {
+                            EditableTextDelta::Undo(delta) => delta.undo(editor),
+                            EditableTextDelta::Apply(delta) => delta.apply(editor),
+                        }
+                    }
+                };
+
+                // The deltas are the ones of the raw value.
+                match &mut state.format {
+                    Some(format) => format.edit_raw(editor, replay),
+                    None => replay(editor),
+                }
+
+                state.ime_cursor_end = editor.cursor();
+                state.goal_x = None;
+                state.recompose_text_content = true;
+                state.auto_scroll_to_cursor = true;
+            }
+
+            for command in std::mem::take(&mut state.edit_commands) {
+                if let Some(delta) = commands::apply_command(editor, command) {
+                    interaction::record_text_edit(state, editor, Some(delta));
+                }
+            }
+
+            if let Some(source) = self.suggestions.take() {
+                (popup, accepted_suggestion) = suggestions::update(
+                    state,
+                    editor,
+                    source,
+                    self.suggestion_trigger.as_ref(),
+                    self.max_suggestions,
+                    context.interaction.is_focused(&id),
+                    context.input,
+                );
+            }
+
+            if let Some(format) = &mut state.format
+                && context.input.ime_preedit.is_empty()
+            {
+                format.sync(editor, &self.text.get_text(), state.cursor);
+            }
+
+            caret_moved = state.cursor != editor.cursor() || state.selection != editor.selection();
+            state.cursor = editor.cursor();
+            state.selection = editor.selection();
+        }
+
+        // The caret starts to blink again wherever it's moved to.
+        let caret_changed = caret_moved || state.caret_shown != is_focused;
+
+        if caret_changed {
+            state.caret_blink_since = context.time_since_start;
+            state.caret_shown = is_focused;
+        }
+
+        // Tab accepts the highlighted suggestion while the popup is open.
+        if self.accepts_tab || popup.is_some() {
+            context.focus_chain.push_stop_accepting_tab(id);
+        } else {
+            context.focus_chain.push_stop(id);
+        }
+
+        let lines_limit = self.lines_limit();
 
+        // The edits and the composition are drawn in the frame they're made in, the caret
+        // and the underlines are placed by the shaped lines. The height is derived from
+        // the same shaped buffer during layout, so the field grows, starts to scroll and
+        // shrinks back in the same frame as the text changes.
         context
-            .widgets_states
-            .editable_text
-            .accessed_this_frame
-            .insert(id);
+            .text
+            .shape_as_needed(text_id, &mut context.fonts.font_system, false);
+
+        if let Some((min_lines, max_lines)) = lines_limit {
+            let text = context.text.get_mut(text_id);
+            let visible_height = text.lines_height(min_lines, max_lines);
+
+            text.with_buffer(|buffer| {
+                state.text_offset.y = -cursor::scroll_to_caret(
+                    buffer,
+                    state.cursor,
+                    -state.text_offset.y,
+                    visible_height,
+                );
+            });
+
+            if self.frame.clip == Clip::None {
+                self.frame.clip = Clip::Rect;
+            }
+        }
 
         let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+        let mut margin = self.frame.margin;
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds: Default::default(),
+            foregrounds: Default::default(),
+            kind: ContainerKind::Measure { id },
+            constraints: self.frame.constraints,
+            size: self.frame.size,
+            zindex: 0,
+            padding: EdgeInsets::ZERO,
+            margin,
+            clip: Clip::None,
+        });
+        context.widgets_states.layout_measures.mark_accessed(id);
+        margin = EdgeInsets::ZERO;
 
         context.push_layout_command(LayoutCommand::Leaf {
             widget_ref,
             backgrounds,
             foregrounds,
             padding: self.frame.padding,
-            margin: self.frame.margin,
+            margin,
             constraints: self.frame.constraints,
             size: self.frame.size,
             zindex: self.frame.zindex,
-            derive_wrap_size: DeriveWrapSize::Text(text_id),
+            derive_wrap_size: match lines_limit {
+                Some((min_lines, max_lines)) => DeriveWrapSize::TextLines {
+                    text_id,
+                    min_lines,
+                    max_lines,
+                },
+                None => DeriveWrapSize::Text(text_id),
+            },
             clip: self.frame.clip,
         });
+
+        context.push_layout_command(LayoutCommand::EndContainer);
+
+        // The caret and the selection change only inside the field, the rest of the view
+        // isn't repainted.
+        if caret_changed && let Some(field_rect) = field_rect {
+            context.invalidate_rect(field_rect);
+        }
+
+        if let Some(popup) = &popup
+            && let Some(field_rect) = field_rect
+            && let Some(index) = suggestions::build_popup(context, id, field_rect, popup)
+            && let state = context.widgets_states.editable_text.at(&entry)
+            && let Some(editor) = context.text.editor_mut(text_id)
+        {
+            let suggestion = popup.suggestion(index).clone();
+
+            suggestions::accept(
+                state,
+                editor,
+                &popup.token,
+                &suggestion,
+                self.suggestion_trigger.as_ref(),
+            );
+            accepted_suggestion = Some(suggestion);
+        }
+
+        // Read out once the typing pauses rather than on every keystroke.
+        if changed {
+            context.announce_value(id, self.text.get_text());
+        }
+
+        EditableTextResponse {
+            id,
+            changed,
+            deltas,
+            suggestion_token: popup
+                .filter(|_| accepted_suggestion.is_none())
+                .map(|popup| popup.token),
+            accepted_suggestion,
+        }
     }
 }
 
@@ -284,7 +888,703 @@ pub fn editable_text(text: &mut TextData) -> EditableTextBuilder<'_> {
         frame: FrameBuilder::new(),
         text,
         color: ColorRgba::from_hex(0xFFFFFFFF),
+        selection_color: ColorRgba::from_hex(0xFF264F78),
+        selected_text_color: ColorRgba::from_hex(0xFFFFFFFF),
+        ime_underline_color: None,
+        ime_highlight_color: None,
         vertical_align: AlignY::Top,
-        text_align: TextAlign::Left,
+        text_align: TextAlign::Auto,
+        text_direction: TextDirection::Auto,
+        min_lines: None,
+        max_lines: None,
+        tab_width: None,
+        show_invisibles: false,
+        accepts_tab: false,
+        external_history: false,
+        suggestions: None,
+        suggestion_trigger: None,
+        max_suggestions: 8,
+        formatter: None,
+    }
+}
+
+/// Runs the command on the editable text with the id when it's built next, the edit
+/// goes to the history and the [`TextData`] the same way as the typed text.
+pub fn apply_edit_command(context: &mut BuildContext, id: WidgetId, command: EditCommand) {
+    if let Some(state) = context.widgets_states.editable_text.get_mut(id) {
+        state.edit_commands.push(command);
+    }
+}
+
+impl SerializableWidgetState for State {
+    fn state_kind(&self) -> &'static str {
+        "clew::editable_text"
+    }
+
+    /// Content is restored through [`TextData`], only the scroll and the cursor
+    /// positions are saved.
+    fn save_state(&self) -> StateValue {
+        let (selection_kind, selection_cursor) = match self.selection {
+            cosmic_text::Selection::None => ("none", self.cursor),
+            cosmic_text::Selection::Normal(cursor) => ("normal", cursor),
+            cosmic_text::Selection::Line(cursor) => ("line", cursor),
+            cosmic_text::Selection::Word(cursor) => ("word", cursor),
+        };
+
+        StateValue::List(vec![
+            StateValue::Float(self.scroll_x as f64),
+            StateValue::Float(self.text_offset.x as f64),
+            StateValue::Float(self.text_offset.y as f64),
+            save_cursor(self.cursor),
+            StateValue::String(selection_kind.to_string()),
+            save_cursor(selection_cursor),
+        ])
+    }
+
+    fn restore_state(&mut self, value: &StateValue) -> bool {
+        let Some(
+            [
+                scroll_x,
+                text_offset_x,
+                text_offset_y,
+                cursor,
+                selection_kind,
+                selection_cursor,
+            ],
+        ) = value.as_list()
+        else {
+            return false;
+        };
+        let (Some(scroll_x), Some(text_offset_x), Some(text_offset_y)) = (
+            scroll_x.as_f64(),
+            text_offset_x.as_f64(),
+            text_offset_y.as_f64(),
+        ) else {
+            return false;
+        };
+        let (Some(cursor), Some(selection_cursor)) =
+            (restore_cursor(cursor), restore_cursor(selection_cursor))
+        else {
+            return false;
+        };
+        let selection = match selection_kind.as_str() {
+            Some("none") => cosmic_text::Selection::None,
+            Some("normal") => cosmic_text::Selection::Normal(selection_cursor),
+            Some("line") => cosmic_text::Selection::Line(selection_cursor),
+            Some("word") => cosmic_text::Selection::Word(selection_cursor),
+            _ => return false,
+        };
+
+        self.scroll_x = scroll_x as f32;
+        self.text_offset = Vec2::new(text_offset_x as f32, text_offset_y as f32);
+        self.restored_cursor = Some((cursor, selection));
+
+        true
+    }
+}
+
+fn save_cursor(cursor: cosmic_text::Cursor) -> StateValue {
+    StateValue::List(vec![
+        StateValue::Int(cursor.line as i64),
+        StateValue::Int(cursor.index as i64),
+        StateValue::Bool(cursor.affinity == cosmic_text::Affinity::After),
+    ])
+}
+
+fn restore_cursor(value: &StateValue) -> Option<cosmic_text::Cursor> {
+    let [line, index, after] = value.as_list()? else {
+        return None;
+    };
+    let affinity = if after.as_bool()? {
+        cosmic_text::Affinity::After
+    } else {
+        cosmic_text::Affinity::Before
+    };
+
+    Some(cosmic_text::Cursor::new_with_affinity(
+        usize::try_from(line.as_i64()?).ok()?,
+        usize::try_from(index.as_i64()?).ok()?,
+        affinity,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ClewInstance, KeyBinding,
+        io::{ImeClause, InputEvent, MouseButton},
+        keyboard::KeyCode,
+        render::{Damage, Fill, RenderCommand},
+        testing,
+        text::test_fonts,
+        widgets::builder::WidgetBuilder,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_external_history_replays_deltas() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        let mut field = TextData::from("Hello");
+
+        let mut frame = |field: &mut TextData, command: Option<(WidgetId, EditCommand)>| {
+            let mut response = None;
+
+            instance.frame(&mut host, &mut |ctx| {
+                if let Some((id, command)) = command {
+                    apply_edit_command(ctx, id, command);
+                }
+
+                response = Some(editable_text(field).external_history(true).build(ctx));
+            });
+
+            let response = response.unwrap();
+            let state = instance
+                .ui_state()
+                .widgets_states
+                .editable_text
+                .get(response.id)
+                .cloned()
+                .unwrap();
+
+            (response, state)
+        };
+
+        let (response, before) = frame(&mut field, None);
+        let id = response.id;
+
+        // The edit is run in the build and applied to the text data in the next one.
+        let (response, _) = frame(&mut field, Some((id, EditCommand::DuplicateLine)));
+        assert!(!response.changed());
+
+        let (response, after) = frame(&mut field, None);
+        assert!(response.changed());
+        assert_eq!(response.deltas().len(), 1);
+        assert_eq!(field.get_text(), "Hello\nHello");
+        assert!(after.history_manager == TextEditHistoryManager::new(20, true));
+        assert_ne!(after.cursor, before.cursor);
+
+        let delta = response.deltas()[0].clone();
+
+        field.undo_delta(&delta);
+        assert_eq!(field.get_text(), "Hello");
+
+        // The cursor is put where the line was inserted and after it on the redo.
+        let TextEditDelta::Insert {
+            cursor_before,
+            cursor_after,
+            ..
+        } = &delta
+        else {
+            panic!("Duplicating a line inserts it");
+        };
+        let (response, state) = frame(&mut field, None);
+        assert!(!response.changed());
+        assert_eq!(state.cursor, *cursor_before);
+
+        field.apply_delta(&delta);
+        assert_eq!(field.get_text(), "Hello\nHello");
+
+        let (_, state) = frame(&mut field, None);
+        assert_eq!(state.cursor, *cursor_after);
+    }
+
+    #[test]
+    fn test_accepted_suggestion_replaces_token_in_one_edit() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let mut field = TextData::from("ap");
+        let suggestions = ["apple", "apricot", "banana"].map(Suggestion::from);
+
+        let mut frame =
+            |instance: &mut ClewInstance<'static>, field: &mut TextData, key: Option<KeyCode>| {
+                if let Some(key) = key {
+                    instance.push_event(InputEvent::Key {
+                        key_code: Some(key),
+                        pressed: true,
+                        repeat: false,
+                        text: None,
+                    });
+                }
+
+                let mut response = None;
+
+                instance.frame(&mut host, &mut |ctx| {
+                    response = Some(
+                        editable_text(field)
+                            .suggestions(&suggestions)
+                            .external_history(true)
+                            .build(ctx),
+                    );
+                });
+
+                response.unwrap()
+            };
+
+        let response = frame(&mut instance, &mut field, None);
+        assert!(response.suggestion_token().is_none());
+
+        instance
+            .ui_state()
+            .interaction_state
+            .focus_with_keyboard(&response.id);
+
+        let response = frame(&mut instance, &mut field, None);
+        let token = response.suggestion_token().unwrap();
+        assert_eq!((token.line, token.range.clone()), (0, 0..2));
+
+        frame(&mut instance, &mut field, Some(KeyCode::ArrowDown));
+        let response = frame(&mut instance, &mut field, Some(KeyCode::Enter));
+        assert_eq!(response.accepted_suggestion(), Some(&suggestions[1]));
+
+        // The popup stays closed for the completed text.
+        let response = frame(&mut instance, &mut field, None);
+        assert_eq!(field.get_text(), "apricot");
+        assert_eq!(response.deltas().len(), 1);
+        assert!(response.suggestion_token().is_none());
+
+        field.undo_delta(&response.deltas()[0]);
+        assert_eq!(field.get_text(), "ap");
+    }
+
+    #[test]
+    fn test_tab_moves_through_fields_and_code_editor_keeps_it() {
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), test_fonts());
+        let mut name = TextData::from("");
+        let mut code = TextData::from("");
+
+        let mut frame = |instance: &mut ClewInstance<'static>,
+                         name: &mut TextData,
+                         code: &mut TextData,
+                         key: Option<(KeyCode, &str)>| {
+            if let Some((key, text)) = key {
+                instance.push_event(InputEvent::Key {
+                    key_code: Some(key),
+                    pressed: true,
+                    repeat: false,
+                    text: Some(text.into()),
+                });
+            }
+
+            let mut ids = None;
+            let mut code_deltas = Vec::new();
+
+            instance.frame(&mut host, &mut |ctx| {
+                crate::vstack().build(ctx, |ctx| {
+                    let button = crate::gesture_detector()
+                        .focusable(true)
+                        .build(ctx, |ctx| crate::decorated_box().size(20.).build(ctx));
+                    let name = editable_text(name).width(150.).build(ctx);
+                    let code = editable_text(code).accepts_tab(true).width(150.).build(ctx);
+
+                    ids = Some((button.id, name.id, code.id));
+                    code_deltas = code.deltas().to_vec();
+                });
+            });
+
+            let focused = instance.ui_state().interaction_state.focused;
+
+            (ids.unwrap(), focused, code_deltas)
+        };
+
+        let ((button, name_id, code_id), ..) = frame(&mut instance, &mut name, &mut code, None);
+        let tab = Some((KeyCode::Tab, "\t"));
+
+        let (_, focused, _) = frame(&mut instance, &mut name, &mut code, tab);
+        assert_eq!(focused, Some(button));
+
+        // The field is a stop after the button, the tab isn't typed into it.
+        let (_, focused, _) = frame(&mut instance, &mut name, &mut code, tab);
+        assert_eq!(focused, Some(name_id));
+        assert_eq!(name.get_text(), "");
+
+        let a = Some((KeyCode::KeyA, "a"));
+        frame(&mut instance, &mut name, &mut code, a);
+        assert_eq!(name.get_text(), "a");
+
+        // Tab leaves the field for the code editor, which keeps it.
+        let (_, focused, deltas) = frame(&mut instance, &mut name, &mut code, tab);
+        assert_eq!(focused, Some(code_id));
+        assert_eq!(name.get_text(), "a");
+        assert!(deltas.is_empty());
+
+        let (_, focused, deltas) = frame(&mut instance, &mut name, &mut code, tab);
+        assert_eq!(focused, Some(code_id));
+        assert!(matches!(&deltas[..], [TextEditDelta::Insert { text, .. }] if text == "\t"));
+    }
+
+    #[test]
+    fn test_typed_value_is_announced_once_the_typing_pauses() {
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), test_fonts());
+        let mut field = TextData::from("");
+
+        let mut frame = |instance: &mut ClewInstance<'static>,
+                         field: &mut TextData,
+                         key: Option<(KeyCode, &str)>| {
+            if let Some((key, text)) = key {
+                instance.push_event(InputEvent::Key {
+                    key_code: Some(key),
+                    pressed: true,
+                    repeat: false,
+                    text: Some(text.into()),
+                });
+            }
+
+            let mut id = None;
+
+            instance.frame(&mut host, &mut |ctx| {
+                id = Some(editable_text(field).width(150.).build(ctx).id);
+            });
+
+            (id.unwrap(), instance.take_announcements())
+        };
+
+        let (id, _) = frame(&mut instance, &mut field, None);
+        instance
+            .ui_state()
+            .interaction_state
+            .focus_with_keyboard(&id);
+
+        for key in [(KeyCode::KeyO, "o"), (KeyCode::KeyK, "k")] {
+            let (_, announced) = frame(&mut instance, &mut field, Some(key));
+            assert_eq!(announced, []);
+        }
+
+        // The host is woken up for it.
+        assert!(instance.next_repaint().is_some());
+
+        std::thread::sleep(crate::announcement::VALUE_ANNOUNCEMENT_DELAY);
+
+        let (_, announced) = frame(&mut instance, &mut field, None);
+        assert_eq!(
+            announced,
+            [crate::Announcement {
+                text: "ok".to_string(),
+                politeness: crate::Politeness::Polite,
+            }]
+        );
+        assert_eq!(frame(&mut instance, &mut field, None).1, []);
+
+        // The value of the field that has lost the focus isn't read out.
+        frame(&mut instance, &mut field, Some((KeyCode::KeyO, "o")));
+        instance.ui_state().interaction_state.unfocus(&id);
+        std::thread::sleep(crate::announcement::VALUE_ANNOUNCEMENT_DELAY);
+
+        assert_eq!(frame(&mut instance, &mut field, None).1, []);
+    }
+
+    #[test]
+    fn test_typed_text_and_shortcuts_edit_the_focused_field() {
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), test_fonts());
+        instance
+            .ui_state()
+            .shortcuts_registry()
+            .scope(ShortcutScopes::TextEditing)
+            .add(
+                TextEditingShortcut::Backspace,
+                KeyBinding::new(KeyCode::Backspace),
+            )
+            .add(TextEditingShortcut::MoveEnd, KeyBinding::new(KeyCode::End))
+            .add(
+                TextEditingShortcut::MoveDown,
+                KeyBinding::new(KeyCode::ArrowDown),
+            );
+        let mut field = TextData::from("Hello\nworld");
+
+        let mut frame = |instance: &mut ClewInstance<'static>,
+                         field: &mut TextData,
+                         key: Option<(KeyCode, &str)>| {
+            if let Some((key, text)) = key {
+                instance.push_event(InputEvent::Key {
+                    key_code: Some(key),
+                    pressed: true,
+                    repeat: false,
+                    text: Some(text.into()),
+                });
+            }
+
+            let mut response = None;
+
+            instance.frame(&mut host, &mut |ctx| {
+                response = Some(editable_text(field).width(150.).build(ctx));
+            });
+
+            let response = response.unwrap();
+            let cursor = instance
+                .ui_state()
+                .widgets_states
+                .editable_text
+                .get(response.id)
+                .unwrap()
+                .cursor;
+
+            (response, cursor)
+        };
+
+        let (response, _) = frame(&mut instance, &mut field, None);
+        instance
+            .ui_state()
+            .interaction_state
+            .focus_with_keyboard(&response.id);
+
+        // The typed text goes in the build of the frame that has it.
+        let (response, _) = frame(&mut instance, &mut field, Some((KeyCode::Digit1, "!")));
+        assert!(response.changed());
+        assert_eq!(field.get_text(), "!Hello\nworld");
+
+        // The shortcuts are resolved in one build and run in the next one, their
+        // control characters aren't typed.
+        frame(
+            &mut instance,
+            &mut field,
+            Some((KeyCode::Backspace, "\u{8}")),
+        );
+        assert_eq!(field.get_text(), "!Hello\nworld");
+        frame(&mut instance, &mut field, None);
+        assert_eq!(field.get_text(), "Hello\nworld");
+
+        frame(&mut instance, &mut field, Some((KeyCode::End, "")));
+        let (_, cursor) = frame(&mut instance, &mut field, None);
+        assert_eq!((cursor.line, cursor.index), (0, 5));
+        assert_eq!(cursor.affinity, cosmic_text::Affinity::Before);
+
+        frame(&mut instance, &mut field, Some((KeyCode::ArrowDown, "")));
+        let (_, cursor) = frame(&mut instance, &mut field, None);
+        assert_eq!((cursor.line, cursor.index), (1, 5));
+    }
+
+    #[test]
+    fn test_composition_is_underlined_with_the_caret_in_its_clause() {
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), test_fonts());
+        let mut field = TextData::from("ac");
+        let underline = ColorRgba::from_hex(0xFF336699);
+
+        let mut frame = |instance: &mut ClewInstance<'static>| {
+            let mut response = None;
+
+            instance.frame(&mut host, &mut |ctx| {
+                response = Some(
+                    editable_text(&mut field)
+                        .width(150.)
+                        .ime_underline_color(underline)
+                        .build(ctx),
+                );
+            });
+
+            response.unwrap()
+        };
+
+        let response = frame(&mut instance);
+        instance
+            .ui_state()
+            .interaction_state
+            .focus_with_keyboard(&response.id);
+        frame(&mut instance);
+
+        // The second clause is being converted, the caret is at its start.
+        instance.push_event(InputEvent::ImePreedit {
+            text: "nihon".into(),
+            cursor: Some((2, 2)),
+            clauses: vec![
+                ImeClause {
+                    range: 0..2,
+                    selected: false,
+                },
+                ImeClause {
+                    range: 2..5,
+                    selected: true,
+                },
+            ],
+        });
+        frame(&mut instance);
+
+        let render_state = &instance.ui_state().render_state;
+        let underlines = render_state
+            .commands()
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::Rect {
+                    boundary,
+                    fill: Some(Fill::Color(color)),
+                    ..
+                } if *color == underline => Some(*boundary),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        let [composition, clause] = underlines[..] else {
+            panic!("The composition and its clause are underlined: {underlines:?}");
+        };
+        assert_eq!((composition.height, clause.height), (1., 2.));
+        assert!(clause.x > composition.x);
+        assert!((clause.right() - composition.right()).abs() < 0.01);
+        assert_eq!(clause.bottom(), composition.bottom());
+
+        // The caret is where the platform has put it in the composition, at the start
+        // of the clause rather than at the end of the composition.
+        let caret = render_state
+            .commands()
+            .iter()
+            .find_map(|command| match command {
+                RenderCommand::Rect { boundary, .. } if boundary.width == 1. => Some(*boundary),
+                _ => None,
+            })
+            .unwrap();
+        assert_eq!(caret.x, clause.x);
+        assert_eq!(instance.ime_cursor_rect(), caret);
+    }
+
+    #[test]
+    fn test_click_commits_the_composition_once() {
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), test_fonts());
+        let mut field = TextData::from("ac");
+
+        let mut frame =
+            |instance: &mut ClewInstance<'static>, field: &mut TextData, events: &[InputEvent]| {
+                for event in events {
+                    instance.push_event(event.clone());
+                }
+
+                let mut response = None;
+
+                instance.frame(&mut host, &mut |ctx| {
+                    response = Some(editable_text(field).width(150.).build(ctx));
+                });
+
+                response.unwrap()
+            };
+
+        let response = frame(&mut instance, &mut field, &[]);
+        instance
+            .ui_state()
+            .interaction_state
+            .focus_with_keyboard(&response.id);
+        frame(&mut instance, &mut field, &[]);
+
+        let preedit = InputEvent::ImePreedit {
+            text: "nihon".into(),
+            cursor: Some((5, 5)),
+            clauses: Vec::new(),
+        };
+        frame(&mut instance, &mut field, &[preedit]);
+        frame(
+            &mut instance,
+            &mut field,
+            &[InputEvent::PointerMoved { x: 120., y: 5. }],
+        );
+
+        // The click commits the composition in place, the platform commits it again
+        // while the button is held.
+        frame(
+            &mut instance,
+            &mut field,
+            &[InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: true,
+            }],
+        );
+        assert_eq!(field.get_text(), "nihonac");
+
+        frame(
+            &mut instance,
+            &mut field,
+            &[InputEvent::ImeCommit("nihon".into())],
+        );
+        frame(
+            &mut instance,
+            &mut field,
+            &[InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: false,
+            }],
+        );
+        assert_eq!(field.get_text(), "nihonac");
+
+        // The platform may not commit it again, the same text committed later on without
+        // a composition is typed.
+        let preedit = InputEvent::ImePreedit {
+            text: "ka".into(),
+            cursor: Some((2, 2)),
+            clauses: Vec::new(),
+        };
+        frame(
+            &mut instance,
+            &mut field,
+            &[preedit, InputEvent::PointerMoved { x: 140., y: 5. }],
+        );
+
+        for pressed in [Some(true), None, Some(false)] {
+            let events = pressed.map(|pressed| InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed,
+            });
+            frame(&mut instance, &mut field, events.as_slice());
+        }
+        assert_eq!(field.get_text(), "nihonacka");
+
+        frame(
+            &mut instance,
+            &mut field,
+            &[InputEvent::ImeCommit("ka".into())],
+        );
+        assert_eq!(field.get_text(), "nihonackaka");
+    }
+
+    #[test]
+    fn test_caret_blinks_without_building_the_frame() {
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), test_fonts());
+        let mut field = TextData::from("Hello");
+
+        // The time of the view only moves when it's set below.
+        instance.set_paused(true);
+
+        let mut frame = |instance: &mut ClewInstance<'static>| {
+            let mut response = None;
+
+            instance.frame(&mut host, &mut |ctx| {
+                crate::text("Title").build(ctx);
+                response = Some(editable_text(&mut field).width(150.).build(ctx));
+            });
+
+            response.unwrap()
+        };
+
+        let response = frame(&mut instance);
+        instance
+            .ui_state()
+            .interaction_state
+            .focus_with_keyboard(&response.id);
+        frame(&mut instance);
+
+        // The focused field has settled.
+        frame(&mut instance);
+        assert!(!instance.needs_build(&host));
+        assert!(instance.repaint().is_none());
+
+        let caret = instance.ui_state().render_state.blinks[0].index;
+        let caret_command = instance.ui_state().render_state.commands[caret].clone();
+        let caret_bounds = caret_command.bounds().unwrap();
+
+        instance.ui_state().time_since_start = Duration::from_millis(600);
+        let render_state = instance.repaint().unwrap();
+
+        assert!(matches!(
+            render_state.commands[caret],
+            RenderCommand::Rect { fill: None, .. }
+        ));
+        assert!(
+            matches!(render_state.damage(), Damage::Rect(rect) if rect.contains_rect(caret_bounds))
+        );
+        assert!(instance.repaint().is_none());
+
+        instance.ui_state().time_since_start = Duration::from_millis(1100);
+        let render_state = instance.repaint().unwrap();
+        assert_eq!(render_state.commands[caret], caret_command);
+        assert!(!instance.needs_build(&host));
     }
 }
@@ -1,12 +1,22 @@
+use std::{ops::Range, time::Duration};
+
+use cosmic_text::Edit;
+
 use crate::{
-    Vec2,
+    ClipShape, ColorRgba, Rect, Vec2,
     layout::WidgetPlacement,
-    render::{PixelExtension, RenderCommand, RenderContext},
+    render::{Blink, Fill, PixelExtension, RenderCommand, RenderContext, visible_band},
+    text::TextId,
 };
 
-use super::State;
+use super::{InvisibleMarkers, State, cursor};
+
+const INVISIBLES_OPACITY: f32 = 0.35;
+const CARET_BLINK_INTERVAL: Duration = Duration::from_millis(500);
 
-pub fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &State) {
+/// Draws the field, returns the caret of a focused one, where the candidate window of
+/// the input method goes.
+pub fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &State) -> Option<Rect> {
     let size = placement.rect.size().px(ctx);
     let position = placement.rect.position().px(ctx);
 
@@ -16,8 +26,59 @@ pub fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &Stat
 
     let text = ctx.text.get_mut(text_id);
     let text_size = text.layout();
-    let text_position =
-        position + Vec2::new(0., state.vertical_align.position(size.y, text_size.y));
+
+    // The scroll is kept in range during build, clamp it again in case the text was
+    // rewrapped to a different number of lines since then.
+    let content_height = text.lines_height(0, u32::MAX);
+    let scroll_y = state
+        .text_offset
+        .y
+        .clamp((size.y - content_height).min(0.), 0.);
+
+    let text_position = position
+        + Vec2::new(
+            state.text_offset.x,
+            scroll_y + state.vertical_align.position(size.y, text_size.y),
+        );
+
+    let band = visible_band(
+        placement.rect.px(ctx),
+        placement.clip_rect.map(|rect| rect.px(ctx)),
+        text_position.y,
+    );
+
+    let focused = ctx.interaction.is_focused(&placement.widget_ref.id);
+    let decorations = if focused {
+        text_decorations(ctx, text_id, state)
+    } else {
+        TextDecorations::default()
+    };
+
+    // The selected clause of the composition is highlighted the same way as the
+    // selection when it isn't underlined.
+    let mut highlights: Vec<(Rect, ColorRgba)> = decorations
+        .selection
+        .iter()
+        .map(|rect| (*rect, state.selection_color))
+        .collect();
+
+    if let Some(color) = state.ime_highlight_color {
+        highlights.extend(decorations.ime_clause.iter().map(|rect| (*rect, color)));
+    }
+
+    // Selection backgrounds go under all of the glyphs.
+    for (rect, color) in &highlights {
+        ctx.push_command(
+            placement.zindex,
+            RenderCommand::Rect {
+                boundary: rect.offset(text_position.x, text_position.y),
+                fill: Some(Fill::Color(*color)),
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            },
+        );
+    }
 
     ctx.push_command(
         placement.zindex,
@@ -26,6 +87,234 @@ pub fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &Stat
             y: text_position.y,
             text_id,
             tint_color: Some(state.color),
+            visible_band: band.clone(),
+            clip: None,
+            stroke: None,
         },
     );
+
+    // The selected glyphs are drawn once more in their own color, clipped to the
+    // selection so the glyphs crossing its edges are split between the two colors.
+    for (rect, _) in &highlights {
+        ctx.push_command(
+            placement.zindex,
+            RenderCommand::PushClip {
+                rect: rect.offset(text_position.x, text_position.y),
+                shape: ClipShape::Rect,
+            },
+        );
+        ctx.push_command(
+            placement.zindex,
+            RenderCommand::Text {
+                x: text_position.x,
+                y: text_position.y,
+                text_id,
+                tint_color: Some(state.selected_text_color),
+                visible_band: band.clone(),
+                clip: None,
+                stroke: None,
+            },
+        );
+        ctx.push_command(placement.zindex, RenderCommand::PopClip);
+    }
+
+    if state.show_invisibles
+        && let Some(markers) = state.invisible_markers
+    {
+        render_invisibles(ctx, placement, state, markers, text_position, band);
+    }
+
+    let thickness = ctx.view.scale_factor.ceil();
+    let underline_color = state.ime_underline_color.unwrap_or(state.color);
+
+    // A thin underline under the whole composition and a thick one under the clause
+    // being converted.
+    let clause_underlines = match state.ime_highlight_color {
+        Some(_) => &[][..],
+        None => &decorations.ime_clause[..],
+    };
+    let underlines = (decorations.preedit.iter().map(|rect| (rect, thickness)))
+        .chain(clause_underlines.iter().map(|rect| (rect, thickness * 2.)));
+
+    for (rect, thickness) in underlines {
+        ctx.push_command(
+            placement.zindex,
+            RenderCommand::Rect {
+                boundary: Rect::new(
+                    text_position.x + rect.x,
+                    text_position.y + rect.y + rect.height - thickness,
+                    rect.width,
+                    thickness,
+                ),
+                fill: Some(Fill::Color(underline_color)),
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            },
+        );
+    }
+
+    let caret = decorations.caret.map(|caret| {
+        Rect::new(
+            text_position.x + caret.x,
+            text_position.y + caret.y,
+            thickness,
+            caret.height,
+        )
+    });
+
+    // The caret is always on top.
+    if let Some(caret) = caret {
+        ctx.push_blinking_command(
+            placement.zindex,
+            RenderCommand::Rect {
+                boundary: caret,
+                fill: Some(Fill::Color(state.color)),
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            },
+            Blink {
+                since: state.caret_blink_since,
+                interval: CARET_BLINK_INTERVAL,
+            },
+        );
+    }
+
+    caret
+}
+
+/// Geometry of everything drawn around the glyphs of a focused field, relative to the
+/// buffer origin.
+#[derive(Default)]
+struct TextDecorations {
+    selection: Vec<Rect>,
+    preedit: Vec<Rect>,
+    /// The selected clause of the composition.
+    ime_clause: Vec<Rect>,
+    caret: Option<Rect>,
+}
+
+fn text_decorations(ctx: &RenderContext, text_id: TextId, state: &State) -> TextDecorations {
+    let Some(editor) = ctx.text.editor(text_id) else {
+        return TextDecorations::default();
+    };
+
+    // The composition is inserted at the cursor, its offsets are from there.
+    let start = editor.cursor();
+    let in_composition = |offset: usize| cosmic_text::Cursor::new(start.line, start.index + offset);
+
+    editor.with_buffer(|buffer| {
+        let selection = editor
+            .selection_bounds()
+            .map(|(start, end)| cursor::highlight_rects(buffer, start, end))
+            .unwrap_or_default();
+
+        let (preedit, ime_clause, caret) = match &state.ime_composition {
+            Some(composition) => (
+                cursor::highlight_rects(buffer, start, state.ime_cursor_end),
+                composition
+                    .clauses
+                    .iter()
+                    .find(|clause| clause.selected)
+                    .map(|clause| {
+                        cursor::highlight_rects(
+                            buffer,
+                            in_composition(clause.range.start),
+                            in_composition(clause.range.end),
+                        )
+                    })
+                    .unwrap_or_default(),
+                composition.caret.map(in_composition),
+            ),
+            None => (Vec::new(), Vec::new(), Some(start)),
+        };
+
+        let caret = caret
+            .and_then(|caret| cursor::caret_position(buffer, caret))
+            .map(|caret| Rect::new(caret.x, caret.y, 0., buffer.metrics().line_height));
+
+        TextDecorations {
+            selection,
+            preedit,
+            ime_clause,
+            caret,
+        }
+    })
+}
+
+/// Draws the markers over the whitespace as separate texts, so the layout and the
+/// content of the edited text stay the same.
+fn render_invisibles(
+    ctx: &mut RenderContext,
+    placement: &WidgetPlacement,
+    state: &State,
+    markers: InvisibleMarkers,
+    text_position: Vec2,
+    band: Option<Range<f32>>,
+) {
+    let Some(text_id) = state.text_id else {
+        return;
+    };
+
+    let mut positions = Vec::new();
+
+    ctx.text.get(text_id).with_buffer(|buffer| {
+        let mut runs = buffer.layout_runs().peekable();
+
+        while let Some(run) = runs.next() {
+            let ends_line = runs.peek().is_none_or(|next| next.line_i != run.line_i);
+
+            if let Some(band) = &band
+                && (run.line_top + run.line_height < band.start || run.line_top > band.end)
+            {
+                continue;
+            }
+
+            for glyph in run.glyphs.iter() {
+                let marker = match &run.text[glyph.start..glyph.end] {
+                    " " | "\u{00A0}" => markers.space,
+                    "\t" => markers.tab,
+                    _ => continue,
+                };
+
+                positions.push((marker, glyph.x + glyph.w / 2., run.line_top, 0.5));
+            }
+
+            // The line break marker follows the end of the line, on the left for RTL.
+            if ends_line && run.line_i + 1 < buffer.lines.len() {
+                if run.rtl {
+                    let start = run
+                        .glyphs
+                        .iter()
+                        .map(|glyph| glyph.x)
+                        .reduce(f32::min)
+                        .unwrap_or_default();
+
+                    positions.push((markers.newline, start, run.line_top, 1.));
+                } else {
+                    positions.push((markers.newline, run.line_w, run.line_top, 0.));
+                }
+            }
+        }
+    });
+
+    let tint_color = Some(state.color.with_opacity(state.color.a * INVISIBLES_OPACITY));
+
+    for (marker, x, y, anchor) in positions {
+        let width = ctx.text.get_mut(marker).layout().x;
+
+        ctx.push_command(
+            placement.zindex,
+            RenderCommand::Text {
+                x: text_position.x + x - width * anchor,
+                y: text_position.y + y,
+                text_id: marker,
+                tint_color,
+                visible_band: None,
+                clip: None,
+                stroke: None,
+            },
+        );
+    }
 }
@@ -0,0 +1,466 @@
+//! Suggestions offered in a popup below an editable text, e.g. the completions of a
+//! search box or the people of a mention.
+//!
+//! The suggestions complete a token: the line of the cursor, or with a trigger the
+//! word under the cursor. The popup is navigated with Up and Down while the field
+//! keeps the focus, Enter or Tab replace the token with the highlighted suggestion as
+//! one edit, and Escape dismisses the popup until the token changes.
+
+use std::ops::Range;
+
+use cosmic_text::{Cursor, Edit, Editor, Selection};
+
+use crate::{
+    AlignY, Border, BorderRadius, BorderSide, ColorRgba, EdgeInsets, Rect, WidgetId,
+    io::UserInput,
+    keyboard::KeyCode,
+    text_history::TextEditDelta,
+    widgets::{
+        BuildContext, builder::WidgetBuilder, decoration, gesture_detector, hstack, portal, scope,
+        text, vstack,
+    },
+};
+
+use super::{State, interaction};
+
+const ROW_HEIGHT: f32 = 24.;
+const POPUP_PADDING: f32 = 4.;
+const POPUP_SPACING: f32 = 4.;
+
+/// Completion offered by an editable text, see
+/// [`super::EditableTextBuilder::suggestions`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// Text the token is replaced with.
+    pub text: String,
+    /// Text shown in the popup, the query is matched against it.
+    pub label: String,
+}
+
+impl Suggestion {
+    pub fn new(text: impl Into<String>) -> Self {
+        let text = text.into();
+
+        Self {
+            label: text.clone(),
+            text,
+        }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = label.into();
+
+        self
+    }
+}
+
+impl From<&str> for Suggestion {
+    fn from(text: &str) -> Self {
+        Suggestion::new(text)
+    }
+}
+
+/// Part of the text the suggestions complete, replaced by the accepted one.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuggestionToken {
+    pub line: usize,
+    /// Byte range of the token in the line.
+    pub range: Range<usize>,
+    pub text: String,
+}
+
+pub(crate) type SuggestionTrigger<'a> = Box<dyn for<'t> Fn(&'t str) -> Option<&'t str> + 'a>;
+
+pub(crate) type SuggestionProvider<'a> = Box<dyn FnOnce(&str) -> Vec<Suggestion> + 'a>;
+
+pub(crate) enum SuggestionSource<'a> {
+    List(&'a [Suggestion]),
+    With(SuggestionProvider<'a>),
+}
+
+#[derive(Clone, PartialEq, Default)]
+pub(crate) struct SuggestionsState {
+    /// Text of the token as of the last build, the popup is reset when it changes.
+    token: Option<String>,
+    highlighted: usize,
+    dismissed: bool,
+    /// The popup was open when the keys of the frame were handled, the field leaves
+    /// Up, Down, Enter and Tab to it.
+    pub(crate) open: bool,
+}
+
+pub(crate) struct SuggestionMatch {
+    suggestion: Suggestion,
+    /// Byte range of the query in the label.
+    highlight: Option<Range<usize>>,
+}
+
+/// Popup to build after the field.
+pub(crate) struct SuggestionsPopup {
+    pub(crate) token: SuggestionToken,
+    matches: Vec<SuggestionMatch>,
+    highlighted: usize,
+}
+
+impl SuggestionSource<'_> {
+    fn matches(self, query: &str, max: usize) -> Vec<SuggestionMatch> {
+        let suggestions = match self {
+            SuggestionSource::List(suggestions) => suggestions
+                .iter()
+                .filter(|it| query.is_empty() || find_ignore_case(&it.label, query).is_some())
+                .take(max)
+                .cloned()
+                .collect(),
+            SuggestionSource::With(suggestions) => {
+                let mut suggestions = suggestions(query);
+                suggestions.truncate(max);
+
+                suggestions
+            }
+        };
+
+        suggestions
+            .into_iter()
+            .map(|suggestion| SuggestionMatch {
+                highlight: find_ignore_case(&suggestion.label, query),
+                suggestion,
+            })
+            .collect()
+    }
+}
+
+/// Byte range of the first occurrence of the needle in the haystack, ignoring the case.
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<Range<usize>> {
+    if needle.is_empty() {
+        return None;
+    }
+
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut chars = haystack[start..].char_indices();
+
+        for expected in needle.chars() {
+            let (_, char) = chars.next()?;
+
+            if !char.to_lowercase().eq(expected.to_lowercase()) {
+                return None;
+            }
+        }
+
+        let end = chars
+            .next()
+            .map_or(haystack.len(), |(offset, _)| start + offset);
+
+        Some(start..end)
+    })
+}
+
+/// Token of the line at the cursor index and the query of the suggestions. Without a
+/// trigger it's the whole line, as long as it isn't empty, with one it's the word
+/// under the cursor the trigger accepts.
+fn token_at(
+    line: &str,
+    index: usize,
+    trigger: Option<&SuggestionTrigger>,
+) -> Option<(Range<usize>, String)> {
+    let Some(trigger) = trigger else {
+        return (!line.is_empty()).then(|| (0..line.len(), line.to_string()));
+    };
+
+    let index = index.min(line.len());
+    let start = line[..index]
+        .char_indices()
+        .rev()
+        .find(|(_, char)| char.is_whitespace())
+        .map_or(0, |(offset, char)| offset + char.len_utf8());
+    let end = line[index..]
+        .find(char::is_whitespace)
+        .map_or(line.len(), |offset| index + offset);
+
+    trigger(&line[start..end]).map(|query| (start..end, query.to_string()))
+}
+
+fn editor_token(
+    editor: &Editor,
+    trigger: Option<&SuggestionTrigger>,
+) -> Option<(SuggestionToken, String)> {
+    if editor.selection() != Selection::None {
+        return None;
+    }
+
+    let cursor = editor.cursor();
+
+    editor.with_buffer(|buffer| {
+        let line = buffer.lines.get(cursor.line)?.text();
+        let (range, query) = token_at(line, cursor.index, trigger)?;
+
+        Some((
+            SuggestionToken {
+                line: cursor.line,
+                text: line[range.clone()].to_string(),
+                range,
+            },
+            query,
+        ))
+    })
+}
+
+fn is_key_pressed(input: &UserInput, key: KeyCode) -> bool {
+    input
+        .key_pressed
+        .iter()
+        .chain(input.key_pressed_repeat.iter())
+        .any(|(_, it)| *it == Some(key))
+}
+
+/// Finds the suggestions for the token under the cursor and handles the keys of the
+/// popup. Returns the popup to show, and the suggestion accepted with the keyboard.
+pub(crate) fn update(
+    state: &mut State,
+    editor: &mut Editor,
+    source: SuggestionSource,
+    trigger: Option<&SuggestionTrigger>,
+    max: usize,
+    focused: bool,
+    input: &UserInput,
+) -> (Option<SuggestionsPopup>, Option<Suggestion>) {
+    let token = if focused {
+        editor_token(editor, trigger)
+    } else {
+        None
+    };
+    let popup_state = &mut state.suggestions;
+    let token_text = token.as_ref().map(|(token, _)| token.text.clone());
+
+    if popup_state.token != token_text {
+        popup_state.token = token_text;
+        popup_state.highlighted = 0;
+        popup_state.dismissed = false;
+    }
+
+    popup_state.open = false;
+
+    let Some((token, query)) = token else {
+        return (None, None);
+    };
+
+    if popup_state.dismissed {
+        return (None, None);
+    }
+
+    let matches = source.matches(&query, max);
+
+    if matches.is_empty() {
+        return (None, None);
+    }
+
+    let count = matches.len();
+    let mut highlighted = popup_state.highlighted.min(count - 1);
+
+    popup_state.open = true;
+
+    if is_key_pressed(input, KeyCode::ArrowDown) {
+        highlighted = (highlighted + 1) % count;
+    }
+
+    if is_key_pressed(input, KeyCode::ArrowUp) {
+        highlighted = (highlighted + count - 1) % count;
+    }
+
+    popup_state.highlighted = highlighted;
+
+    if is_key_pressed(input, KeyCode::Escape) {
+        popup_state.dismissed = true;
+
+        return (None, None);
+    }
+
+    if [KeyCode::Enter, KeyCode::NumpadEnter, KeyCode::Tab]
+        .into_iter()
+        .any(|key| is_key_pressed(input, key))
+    {
+        let suggestion = matches[highlighted].suggestion.clone();
+        accept(state, editor, &token, &suggestion, trigger);
+
+        return (None, Some(suggestion));
+    }
+
+    (
+        Some(SuggestionsPopup {
+            token,
+            matches,
+            highlighted,
+        }),
+        None,
+    )
+}
+
+/// Replaces the token with the suggestion as one edit, so it's undone in one step. The
+/// popup stays dismissed for the completed token.
+pub(crate) fn accept(
+    state: &mut State,
+    editor: &mut Editor,
+    token: &SuggestionToken,
+    suggestion: &Suggestion,
+    trigger: Option<&SuggestionTrigger>,
+) {
+    let start = Cursor::new(token.line, token.range.start);
+    let end = Cursor::new(token.line, token.range.end);
+
+    editor.set_selection(Selection::None);
+    editor.delete_range(start, end);
+
+    let after = editor.insert_at(start, &suggestion.text, None);
+    editor.set_cursor(after);
+
+    let delta = if token.range.is_empty() {
+        TextEditDelta::Insert {
+            cursor_before: start,
+            cursor_after: after,
+            text: suggestion.text.clone(),
+        }
+    } else {
+        TextEditDelta::Replace {
+            range_before: (start, end),
+            range_after: (start, after),
+            text_before: token.text.clone(),
+            text_after: suggestion.text.clone(),
+        }
+    };
+
+    interaction::record_text_edit(state, editor, Some(delta));
+
+    state.suggestions.token = editor_token(editor, trigger).map(|(token, _)| token.text);
+    state.suggestions.dismissed = true;
+}
+
+/// Builds the popup below the field, or above it when there is more space there.
+/// Returns the suggestion clicked in it.
+pub(crate) fn build_popup(
+    context: &mut BuildContext,
+    id: WidgetId,
+    field: Rect,
+    popup: &SuggestionsPopup,
+) -> Option<usize> {
+    let height = popup.matches.len() as f32 * ROW_HEIGHT + POPUP_PADDING * 2.;
+    let view_height = context.view.size.height as f32 / context.view.scale_factor;
+    let space_below = view_height - field.bottom() - POPUP_SPACING;
+    let y = if height > space_below && field.top() - POPUP_SPACING > space_below {
+        field.top() - POPUP_SPACING - height
+    } else {
+        field.bottom() + POPUP_SPACING
+    };
+
+    let mut clicked = None;
+    let theme = context.widget_theme();
+
+    context.scope(id, |context| {
+        portal()
+            .rect(Rect::new(field.x, y, field.width, height))
+            .build(context, |context| {
+                vstack()
+                    .fill_max_size()
+                    .padding(EdgeInsets::all(POPUP_PADDING))
+                    .background(
+                        decoration()
+                            .color(theme.surface)
+                            .border_radius(BorderRadius::all(3.))
+                            .border(Border::all(BorderSide::new(1., theme.border)))
+                            .build(context),
+                    )
+                    .build(context, |context| {
+                        for (index, item) in popup.matches.iter().enumerate() {
+                            let highlighted = index == popup.highlighted;
+
+                            let response = scope(index).build(context, |context| {
+                                gesture_detector()
+                                    .clickable(true)
+                                    .build(context, |context| {
+                                        build_row(context, item, highlighted);
+                                    })
+                            });
+
+                            if response.clicked() {
+                                clicked = Some(index);
+                            }
+                        }
+                    });
+            });
+    });
+
+    clicked
+}
+
+fn build_row(context: &mut BuildContext, item: &SuggestionMatch, highlighted: bool) {
+    let theme = context.widget_theme();
+    let (background, color) = if highlighted {
+        (theme.accent, ColorRgba::from_hex(0xFFFFFFFF))
+    } else {
+        (ColorRgba::TRANSPARENT, theme.text)
+    };
+    let label = item.suggestion.label.as_str();
+    let (before, matched, after) = match &item.highlight {
+        Some(range) => (
+            &label[..range.start],
+            &label[range.clone()],
+            &label[range.end..],
+        ),
+        None => (label, "", ""),
+    };
+
+    hstack()
+        .fill_max_width()
+        .height(ROW_HEIGHT)
+        .spacing(0.)
+        .padding(EdgeInsets::symmetric(8., 0.))
+        .background(
+            decoration()
+                .color(background)
+                .border_radius(BorderRadius::all(2.))
+                .build(context),
+        )
+        .build(context, |context| {
+            // Every part is a text of its own, the matched one stands out.
+            for (part, color) in [
+                (before, color.with_opacity(0.8)),
+                (matched, color),
+                (after, color.with_opacity(0.8)),
+            ] {
+                if !part.is_empty() {
+                    text(part)
+                        .color(color)
+                        .text_vertical_align(AlignY::Center)
+                        .height(ROW_HEIGHT)
+                        .build(context);
+                }
+            }
+        });
+}
+
+impl SuggestionsPopup {
+    pub(crate) fn suggestion(&self, index: usize) -> &Suggestion {
+        &self.matches[index].suggestion
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trigger_takes_word_under_cursor() {
+        let mention: SuggestionTrigger = Box::new(|token| token.strip_prefix('@'));
+        let line = "hi @ali there";
+
+        assert_eq!(
+            token_at(line, 6, Some(&mention)),
+            Some((3..7, "ali".to_string()))
+        );
+        assert_eq!(token_at(line, 1, Some(&mention)), None);
+        assert_eq!(
+            token_at("@", 1, Some(&mention)),
+            Some((0..1, String::new()))
+        );
+        assert_eq!(token_at("", 0, None), None);
+        assert_eq!(find_ignore_case("Apricot", "RIC"), Some(2..5));
+    }
+}
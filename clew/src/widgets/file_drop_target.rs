@@ -0,0 +1,367 @@
+use std::path::PathBuf;
+
+use clew_derive::WidgetBuilder;
+
+use crate::{
+    Rect, Vec2, View, WidgetId,
+    io::UserInput,
+    layout::{ContainerKind, LayoutCommand, LayoutMeasure},
+    point_with_rect_hit_test,
+    state::TypedWidgetStates,
+};
+
+use super::{FrameBuilder, builder::BuildContext};
+
+#[derive(WidgetBuilder)]
+pub struct FileDropTargetBuilder {
+    frame: FrameBuilder,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct FileDropTargetResponse {
+    hovering: bool,
+    dragged_over_window: bool,
+    dropped: Vec<PathBuf>,
+}
+
+impl FileDropTargetResponse {
+    /// Files are dragged over the target, use it to highlight it. On the platforms that
+    /// don't report where the drag is, it's where the pointer was last, see
+    /// [`FileDropTargetResponse::files_dragged_over_window`] to highlight all the
+    /// targets there instead.
+    pub fn hovering_files(&self) -> bool {
+        self.hovering
+    }
+
+    /// Files are dragged over the window, wherever it is, e.g. to show all the targets
+    /// that take the files when the platform doesn't tell which one they are over.
+    pub fn files_dragged_over_window(&self) -> bool {
+        self.dragged_over_window
+    }
+
+    /// Files dropped onto the target in this frame.
+    pub fn dropped_files(&self) -> Vec<PathBuf> {
+        self.dropped.clone()
+    }
+}
+
+/// File dropped onto the view and the target it has landed on.
+struct DroppedFile {
+    path: PathBuf,
+    target: Option<WidgetId>,
+    taken: bool,
+}
+
+/// Drop targets of the view and which of them the dragged files are over.
+#[derive(Default)]
+pub(crate) struct FileDrops {
+    /// Targets built in the last frame from the bottom to the top, the nested ones
+    /// after the targets they are in.
+    targets: Vec<WidgetId>,
+    hovered: Option<WidgetId>,
+    dropped: Vec<DroppedFile>,
+    /// Dropped files that no target has taken, see
+    /// [`crate::ClewInstance::take_unclaimed_dropped_files`].
+    pub(crate) unclaimed: Vec<PathBuf>,
+}
+
+impl FileDrops {
+    /// Hit tests the dragged and the dropped files against the rects the targets got in
+    /// the last layout, the topmost target at the position gets them.
+    pub(crate) fn init_cycle(
+        &mut self,
+        user_input: &UserInput,
+        view: &View,
+        measures: &TypedWidgetStates<LayoutMeasure>,
+    ) {
+        let target_at = |position: Vec2| {
+            let point = Vec2::new(
+                position.x / view.scale_factor,
+                position.y / view.scale_factor,
+            );
+
+            self.targets.iter().rev().copied().find(|id| {
+                measures.get(*id).is_some_and(|measure| {
+                    let rect = Rect::new(measure.x, measure.y, measure.width, measure.height);
+
+                    point_with_rect_hit_test(point, rect)
+                })
+            })
+        };
+
+        self.hovered = user_input
+            .hovered_files
+            .iter()
+            .find_map(|file| file.position)
+            .and_then(target_at);
+
+        self.dropped = user_input
+            .dropped_files
+            .iter()
+            .map(|file| DroppedFile {
+                path: file.path.clone(),
+                target: file.position.and_then(target_at),
+                taken: false,
+            })
+            .collect();
+
+        // Not taken by the shell, there is nothing to read them.
+        self.unclaimed.clear();
+        self.targets.clear();
+    }
+
+    /// Leaves the files dropped without a position, outside of the targets or onto a
+    /// target that hasn't been built to the shell.
+    pub(crate) fn finalize_cycle(&mut self) {
+        for file in self.dropped.drain(..) {
+            if !file.taken {
+                self.unclaimed.push(file.path);
+            }
+        }
+    }
+}
+
+impl FileDropTargetBuilder {
+    /// Builds the content and returns the files dragged over and dropped onto it. The
+    /// response is provided to the content too, e.g. for a decoration that highlights
+    /// the target.
+    #[profiling::function]
+    pub fn build<F>(mut self, context: &mut BuildContext, callback: F) -> FileDropTargetResponse
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        let id = context.widget_id(self.frame.id);
+        let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+
+        context.file_drops.targets.push(id);
+
+        let mut dropped = Vec::new();
+
+        for file in &mut context.file_drops.dropped {
+            if file.target == Some(id) && !file.taken {
+                file.taken = true;
+                dropped.push(file.path.clone());
+            }
+        }
+
+        let response = FileDropTargetResponse {
+            hovering: context.file_drops.hovered == Some(id),
+            dragged_over_window: !context.input.hovered_files.is_empty(),
+            dropped,
+        };
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds,
+            foregrounds,
+            zindex: self.frame.zindex,
+            padding: self.frame.padding,
+            margin: self.frame.margin,
+            kind: ContainerKind::Measure { id },
+            size: self.frame.size,
+            constraints: self.frame.constraints,
+            clip: self.frame.clip,
+        });
+
+        context.provide(response.clone(), callback);
+
+        context.push_layout_command(LayoutCommand::EndContainer);
+
+        context.widgets_states.layout_measures.mark_accessed(id);
+
+        response
+    }
+}
+
+/// Area that takes the files dragged from other applications, e.g. from the file
+/// manager, and dropped onto it. Nested targets take the files dropped onto them
+/// rather than the targets they are in. The files dropped elsewhere are left to the
+/// shell, see [`crate::ClewInstance::take_unclaimed_dropped_files`].
+///
+/// ```ignore
+/// let response = file_drop_target().fill_max_width().build(ctx, |ctx| {
+///     let hovering = ctx
+///         .of::<FileDropTargetResponse>()
+///         .is_some_and(|response| response.hovering_files());
+///
+///     text(if hovering { "Release to open" } else { "Drop a file here" }).build(ctx);
+/// });
+///
+/// for path in response.dropped_files() {
+///     open(path);
+/// }
+/// ```
+#[track_caller]
+pub fn file_drop_target() -> FileDropTargetBuilder {
+    FileDropTargetBuilder {
+        frame: FrameBuilder::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ClewHost, ClewInstance, View, io::InputEvent, testing, text::FontResources,
+        widgets::builder::WidgetBuilder,
+    };
+
+    use super::*;
+
+    #[derive(Default, Debug)]
+    struct Frame {
+        outer: FileDropTargetResponse,
+        inner: FileDropTargetResponse,
+        unclaimed: Vec<PathBuf>,
+    }
+
+    fn instance<'a>() -> (ClewHost<'a>, ClewInstance<'a>) {
+        let host = testing::host();
+        let view = View {
+            scale_factor: 2.,
+            ..testing::view(300, 300)
+        };
+
+        (host, ClewInstance::new(view, FontResources::new()))
+    }
+
+    /// Builds a frame of a 100x100 target with a 50x50 target in its top left corner.
+    fn frame<'a>(
+        host: &mut ClewHost<'a>,
+        instance: &mut ClewInstance<'a>,
+        events: Vec<InputEvent>,
+    ) -> Frame {
+        let mut frame = Frame::default();
+
+        for event in events {
+            instance.push_event(event);
+        }
+
+        instance.frame(host, &mut |ctx| {
+            frame.outer = file_drop_target()
+                .width(100.)
+                .height(100.)
+                .build(ctx, |ctx| {
+                    frame.inner = file_drop_target().width(50.).height(50.).build(ctx, |_| {});
+                });
+        });
+        frame.unclaimed = instance.take_unclaimed_dropped_files();
+
+        frame
+    }
+
+    fn hovered(path: &str, position: Option<Vec2>) -> InputEvent {
+        InputEvent::FileHovered {
+            path: PathBuf::from(path),
+            position,
+        }
+    }
+
+    fn dropped(path: &str, position: Option<Vec2>) -> InputEvent {
+        InputEvent::FileDropped {
+            path: PathBuf::from(path),
+            position,
+        }
+    }
+
+    #[test]
+    fn test_topmost_target_at_the_position_takes_the_files() {
+        let (mut host, mut instance) = instance();
+
+        frame(&mut host, &mut instance, Vec::new());
+
+        // Physical pixels, in the inner target at the scale factor of 2.
+        let hover = frame(
+            &mut host,
+            &mut instance,
+            vec![
+                hovered("a.txt", Some(Vec2::new(40., 40.))),
+                hovered("b.txt", None),
+            ],
+        );
+
+        assert!(hover.inner.hovering_files());
+        assert!(!hover.outer.hovering_files());
+        assert!(hover.outer.files_dragged_over_window());
+
+        let drop = frame(
+            &mut host,
+            &mut instance,
+            vec![dropped("a.txt", None), dropped("b.txt", None)],
+        );
+
+        assert_eq!(
+            drop.inner.dropped_files(),
+            vec![PathBuf::from("a.txt"), PathBuf::from("b.txt")]
+        );
+        assert!(drop.outer.dropped_files().is_empty());
+        assert!(drop.unclaimed.is_empty());
+        assert!(!drop.inner.files_dragged_over_window());
+
+        let drop = frame(
+            &mut host,
+            &mut instance,
+            vec![
+                dropped("c.txt", Some(Vec2::new(150., 150.))),
+                dropped("d.txt", Some(Vec2::new(250., 250.))),
+            ],
+        );
+
+        assert_eq!(drop.outer.dropped_files(), vec![PathBuf::from("c.txt")]);
+        assert_eq!(drop.unclaimed, vec![PathBuf::from("d.txt")]);
+
+        // Dropped once.
+        let next = frame(&mut host, &mut instance, Vec::new());
+
+        assert!(next.outer.dropped_files().is_empty());
+        assert!(next.unclaimed.is_empty());
+    }
+
+    #[test]
+    fn test_drag_without_positions_is_at_the_pointer() {
+        let (mut host, mut instance) = instance();
+
+        frame(&mut host, &mut instance, Vec::new());
+
+        // In the outer target only.
+        let hover = frame(
+            &mut host,
+            &mut instance,
+            vec![
+                InputEvent::PointerMoved { x: 150., y: 150. },
+                hovered("a.txt", None),
+            ],
+        );
+
+        assert!(hover.outer.hovering_files());
+        assert!(!hover.inner.hovering_files());
+        assert!(hover.outer.files_dragged_over_window());
+
+        // The drag leaves the window.
+        let cancel = frame(
+            &mut host,
+            &mut instance,
+            vec![InputEvent::FileHoverCancelled],
+        );
+
+        assert!(!cancel.outer.files_dragged_over_window());
+        assert!(instance.ui_state().user_input.hovered_files.is_empty());
+
+        let drop = frame(&mut host, &mut instance, vec![dropped("a.txt", None)]);
+
+        assert_eq!(drop.outer.dropped_files(), vec![PathBuf::from("a.txt")]);
+        assert!(drop.unclaimed.is_empty());
+
+        // Outside of the targets.
+        let drop = frame(
+            &mut host,
+            &mut instance,
+            vec![
+                InputEvent::PointerMoved { x: 250., y: 250. },
+                hovered("b.txt", None),
+                dropped("b.txt", None),
+            ],
+        );
+
+        assert!(drop.outer.dropped_files().is_empty());
+        assert_eq!(drop.unclaimed, vec![PathBuf::from("b.txt")]);
+    }
+}
@@ -0,0 +1,86 @@
+use clew_derive::WidgetBuilder;
+
+use crate::layout::{ContainerKind, LayoutCommand};
+
+use super::{FrameBuilder, builder::BuildContext};
+
+#[derive(WidgetBuilder)]
+pub struct FlowBuilder {
+    frame: FrameBuilder,
+    rtl_aware: bool,
+    spacing: f32,
+    run_spacing: f32,
+}
+
+impl FlowBuilder {
+    pub fn rtl_aware(mut self, rtl_aware: bool) -> Self {
+        self.rtl_aware = rtl_aware;
+
+        self
+    }
+
+    /// Space between the widgets of a row.
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+
+        self
+    }
+
+    /// Space between the rows.
+    pub fn run_spacing(mut self, run_spacing: f32) -> Self {
+        self.run_spacing = run_spacing;
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build<F>(mut self, context: &mut BuildContext, callback: F)
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+
+        if self.frame.offset_x != 0. || self.frame.offset_y != 0. {
+            context.push_layout_command(LayoutCommand::BeginOffset {
+                offset_x: self.frame.offset_x,
+                offset_y: self.frame.offset_y,
+            });
+        }
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds,
+            foregrounds,
+            zindex: self.frame.zindex,
+            padding: self.frame.padding,
+            margin: self.frame.margin,
+            kind: ContainerKind::Flow {
+                spacing: self.spacing,
+                run_spacing: self.run_spacing,
+                rtl_aware: self.rtl_aware,
+            },
+            size: self.frame.size,
+            constraints: self.frame.constraints,
+            clip: self.frame.clip,
+        });
+
+        context.handle_decoration_defer(callback);
+
+        context.push_layout_command(LayoutCommand::EndContainer);
+
+        if self.frame.offset_x != 0. || self.frame.offset_y != 0. {
+            context.push_layout_command(LayoutCommand::EndOffset);
+        }
+    }
+}
+
+/// Lays out the widgets in a row like [`super::hstack`] and starts a new row below when
+/// the next one doesn't fit in the width. Without a fixed width the rows are broken at
+/// the width the flow is given by its parent.
+pub fn flow() -> FlowBuilder {
+    FlowBuilder {
+        frame: FrameBuilder::new(),
+        rtl_aware: false,
+        spacing: 5.,
+        run_spacing: 5.,
+    }
+}
@@ -0,0 +1,69 @@
+use crate::{WidgetId, WidgetKey, focus::FocusAxis, impl_id};
+
+use super::builder::BuildContext;
+
+pub struct FocusGroupBuilder {
+    id: WidgetKey,
+    primary: usize,
+    axis: FocusAxis,
+}
+
+#[derive(Clone)]
+pub struct FocusGroupResponse {
+    pub id: WidgetId,
+    /// The group itself has the focus, after Escape in one of its members.
+    pub is_focused: bool,
+    pub is_focus_visible: bool,
+}
+
+impl FocusGroupBuilder {
+    impl_id!();
+
+    /// Index of the member focused when Tab enters the group, the first one by default.
+    pub fn primary(mut self, index: usize) -> Self {
+        self.primary = index;
+
+        self
+    }
+
+    /// Arrow keys that move between the members.
+    pub fn axis(mut self, axis: FocusAxis) -> Self {
+        self.axis = axis;
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build<F>(self, context: &mut BuildContext, callback: F) -> FocusGroupResponse
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        let id = context.widget_id(self.id);
+
+        let response = FocusGroupResponse {
+            id,
+            is_focused: context.interaction.is_focused(&id),
+            is_focus_visible: context.interaction.is_focus_visible(&id),
+        };
+
+        context.focus_chain.begin_group(id, self.primary, self.axis);
+        context.provide(response.clone(), callback);
+        context.focus_chain.end_group();
+
+        response
+    }
+}
+
+/// Makes the focusable widgets inside a single Tab stop, e.g. the parts of a composite
+/// widget. Tab focuses the primary member and leaves the group entirely, the arrow
+/// keys move between the members in the build order, and Escape focuses the group,
+/// Enter brings the focus back to the primary member. Groups can be nested, a nested
+/// group is a single member of the enclosing one.
+#[track_caller]
+pub fn focus_group() -> FocusGroupBuilder {
+    FocusGroupBuilder {
+        id: WidgetKey::auto(),
+        primary: 0,
+        axis: FocusAxis::default(),
+    }
+}
@@ -1,14 +1,14 @@
 use smallvec::SmallVec;
 
 use crate::{
-    Clip, Constraints, EdgeInsets, Size, WidgetId, WidgetRef,
+    Clip, Constraints, EdgeInsets, Size, WidgetId, WidgetKey, WidgetRef,
     layout::{ContainerKind, LayoutCommand},
 };
 
 use super::{BuildContext, builder::Layout};
 
 pub struct FrameBuilder {
-    pub(crate) id: WidgetId,
+    pub(crate) id: WidgetKey,
     pub(crate) size: Size,
     pub(crate) constraints: Constraints,
     pub(crate) zindex: i32,
@@ -27,7 +27,7 @@ impl FrameBuilder {
     #[track_caller]
     pub fn new() -> Self {
         Self {
-            id: WidgetId::auto(),
+            id: WidgetKey::auto(),
             size: Default::default(),
             constraints: Default::default(),
             zindex: Default::default(),
@@ -43,6 +43,11 @@ impl FrameBuilder {
         }
     }
 
+    /// Id the widget built with the frame gets in the scope of the context.
+    pub fn widget_id(&self, context: &mut BuildContext) -> WidgetId {
+        context.widget_id(self.id)
+    }
+
     pub fn take_layout(&mut self) -> Layout {
         self.flags.remove(FrameBuilderFlags::SIZE);
         self.flags.remove(FrameBuilderFlags::CONSTRAINTS);
@@ -80,7 +85,7 @@ impl FrameBuilder {
         let value;
 
         let last_ignore_pointer = context.ignore_pointer;
-        context.ignore_pointer = self.ignore_pointer && context.ignore_pointer;
+        context.ignore_pointer = self.ignore_pointer || context.ignore_pointer;
 
         if needs_container {
             let (backgrounds, foregrounds) = context.resolve_decorators(self);
@@ -5,15 +5,33 @@ use super::builder::BuildContext;
 pub struct GapBuilder {
     size: Size,
     constraints: Constraints,
+    min: Option<f32>,
+    max: Option<f32>,
 }
 
 impl GapBuilder {
     impl_size_methods!();
 
+    /// Lets the gap compress down to `min` along the stack axis when the stack is too
+    /// tight, before the flexible siblings get less than their wrap size.
+    pub fn min(mut self, min: f32) -> Self {
+        self.min = Some(min);
+        self
+    }
+
+    /// Lets the gap grow up to `max` along the stack axis when the stack has free space
+    /// and no flexible children to take it.
+    pub fn max(mut self, max: f32) -> Self {
+        self.max = Some(max);
+        self
+    }
+
     pub fn build(&self, context: &mut BuildContext) {
         context.push_layout_command(LayoutCommand::Spacer {
             size: self.size,
             constraints: self.constraints,
+            main_axis_min: self.min,
+            main_axis_max: self.max,
         });
     }
 }
@@ -22,5 +40,39 @@ pub fn gap() -> GapBuilder {
     GapBuilder {
         size: Size::new(SizeConstraint::Fixed(0.), SizeConstraint::Fixed(0.)),
         constraints: Constraints::default(),
+        min: None,
+        max: None,
+    }
+}
+
+pub struct SpacerBuilder {
+    flex: f32,
+}
+
+impl SpacerBuilder {
+    /// Share of the free space relative to the other flexible children, `1` by default.
+    pub fn flex(mut self, flex: f32) -> Self {
+        self.flex = flex;
+        self
+    }
+
+    pub fn build(&self, context: &mut BuildContext) {
+        context.push_layout_command(LayoutCommand::Spacer {
+            size: Size::new(
+                SizeConstraint::Fill(self.flex),
+                SizeConstraint::Fill(self.flex),
+            ),
+            constraints: Constraints::default(),
+            main_axis_min: None,
+            main_axis_max: None,
+        });
     }
 }
+
+/// Flexible empty space that pushes the siblings of a stack apart, e.g. a title to the
+/// start and the buttons to the end of a toolbar. It has no minimum size, so it's gone
+/// before the gaps start to compress, and since it takes all the free space of the
+/// stack there is nothing left to distribute with the main axis alignment.
+pub fn spacer() -> SpacerBuilder {
+    SpacerBuilder { flex: 1. }
+}
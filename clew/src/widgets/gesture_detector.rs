@@ -1,24 +1,44 @@
 use crate::{
-    View, WidgetId, WidgetRef, WidgetType, impl_id, interaction::InteractionState, io::UserInput,
-    state::WidgetState,
+    View, WidgetId, WidgetKey, WidgetRef, WidgetType, impl_id, interaction::InteractionState,
+    io::UserInput, keyboard::KeyCode, state::WidgetState,
 };
-use std::any::Any;
+use std::{any::Any, time::Duration};
 
 use super::builder::BuildContext;
 
 pub struct GestureDetectorBuilder {
-    id: WidgetId,
+    id: WidgetKey,
     focusable: bool,
     clickable: bool,
     dragable: bool,
+    hover_through: bool,
+    hold_to_confirm: Option<Duration>,
+    global_keys: bool,
+    shortcut_keys: bool,
+}
+
+/// Whether the widgets built inside of a gesture detector read the keyboard, see
+/// [`BuildContext::keyboard`].
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct KeyboardAccess {
+    pub(crate) active: bool,
+    pub(crate) shortcut_keys: bool,
 }
 
+/// How many times faster an unfinished hold goes back than it fills up.
+const HOLD_RELEASE_SPEED: f32 = 4.;
+
+/// Longest time a frame moves the hold by. The first frame after the view has been
+/// idle comes with the whole idle gap, a press then would complete at once.
+const MAX_HOLD_STEP: f32 = 1. / 20.;
+
 #[derive(Debug, Clone, PartialEq, Default)]
 pub struct State {
     clicked: bool,
     is_active: bool,
     is_hot: bool,
     is_focused: bool,
+    is_focus_visible: bool,
     clickable: bool,
     dragable: bool,
     focusable: bool,
@@ -31,6 +51,11 @@ pub struct State {
     drag_delta_x: f32,
     drag_delta_y: f32,
     drag_state: DragState,
+    hold_to_confirm: Option<Duration>,
+    hold_progress: f32,
+    hold_completed: bool,
+    /// Completed and not released yet, holding on doesn't confirm again.
+    hold_confirmed: bool,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
@@ -63,10 +88,12 @@ impl WidgetState for State {
 
 #[derive(Clone)]
 pub struct GestureDetectorResponse {
+    pub id: WidgetId,
     pub clicked: bool,
     pub is_active: bool,
     pub is_hot: bool,
     pub is_focused: bool,
+    pub is_focus_visible: bool,
     pub drag_start_x: f32,
     pub drag_start_y: f32,
     pub drag_x: f32,
@@ -74,6 +101,8 @@ pub struct GestureDetectorResponse {
     pub drag_delta_x: f32,
     pub drag_delta_y: f32,
     pub drag_state: DragState,
+    pub hold_progress: f32,
+    pub hold_completed: bool,
 }
 
 impl GestureDetectorResponse {
@@ -96,6 +125,24 @@ impl GestureDetectorResponse {
     pub fn is_focused(&self) -> bool {
         self.is_focused
     }
+
+    /// Focused and the focus was reached with the keyboard, use it to show a focus ring.
+    #[inline]
+    pub fn is_focus_visible(&self) -> bool {
+        self.is_focus_visible
+    }
+
+    /// How far the hold to confirm is, from 0 to 1.
+    #[inline]
+    pub fn hold_progress(&self) -> f32 {
+        self.hold_progress
+    }
+
+    /// The hold to confirm has been completed in this frame.
+    #[inline]
+    pub fn hold_completed(&self) -> bool {
+        self.hold_completed
+    }
 }
 
 impl GestureDetectorBuilder {
@@ -119,30 +166,82 @@ impl GestureDetectorBuilder {
         self
     }
 
+    /// Lets the pointer through the detector and the widgets built inside of it, so
+    /// they never become hot and don't cover the widgets below, for purely decorative
+    /// overlays.
+    pub fn hover_through(mut self, value: bool) -> Self {
+        self.hover_through = value;
+
+        self
+    }
+
+    /// Confirms only when the detector is held with the pointer, or with Enter or Space
+    /// while focused, for the whole `duration`, instead of clicking. Releasing earlier
+    /// winds the progress back.
+    pub fn hold_to_confirm(mut self, duration: Duration) -> Self {
+        self.hold_to_confirm = Some(duration);
+
+        self
+    }
+
+    /// Lets the widgets built inside of the detector read the keyboard with
+    /// [`BuildContext::keyboard`] even when it isn't focused, e.g. a game viewport
+    /// that takes the keys of the whole window.
+    pub fn global_keys(mut self, value: bool) -> Self {
+        self.global_keys = value;
+
+        self
+    }
+
+    /// Reports the presses of the keys bound to the shortcuts of the enclosing scopes
+    /// to [`BuildContext::keyboard`] too, they go to the shortcuts only by default.
+    pub fn shortcut_keys(mut self, value: bool) -> Self {
+        self.shortcut_keys = value;
+
+        self
+    }
+
     #[profiling::function]
     pub fn build<F>(self, context: &mut BuildContext, callback: F) -> GestureDetectorResponse
     where
         F: FnOnce(&mut BuildContext),
     {
-        let id = self.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.id);
         let widget_ref = WidgetRef::new(WidgetType::of::<GestureDetector>(), id);
 
         let state = context
             .widgets_states
             .gesture_detector
-            .get_or_insert(id, State::default);
+            .access(id, State::default);
 
         state.clickable = self.clickable;
         state.dragable = self.dragable;
         state.focusable = self.focusable;
-
-        handle_interaction(id, context.input, context.view, context.interaction, state);
+        state.hold_to_confirm = self.hold_to_confirm;
+
+        handle_interaction(
+            id,
+            context.input,
+            context.view,
+            context.interaction,
+            context.delta_time,
+            state,
+        );
+
+        // The progress moves with time rather than with input.
+        let hold_in_progress = state.hold_progress > 0. && !state.hold_confirmed;
+
+        if self.focusable {
+            context.focus_chain.push_stop(id);
+        }
 
         let response = GestureDetectorResponse {
+            id,
             clicked: state.clicked,
             is_active: state.is_active,
             is_hot: state.is_hot,
             is_focused: state.is_focused,
+            is_focus_visible: state.is_focus_visible,
             drag_start_x: state.drag_start_x,
             drag_start_y: state.drag_start_y,
             drag_x: state.drag_x,
@@ -150,28 +249,56 @@ impl GestureDetectorBuilder {
             drag_delta_x: state.drag_delta_x,
             drag_delta_y: state.drag_delta_y,
             drag_state: state.drag_state,
+            hold_progress: state.hold_progress,
+            hold_completed: state.hold_completed,
         };
 
-        context.foregrounds.push(widget_ref);
-        context.provide(response.clone(), callback);
+        if hold_in_progress {
+            context.request_frame();
+        }
 
-        context
-            .widgets_states
-            .gesture_detector
-            .accessed_this_frame
-            .insert(id);
+        let last_ignore_pointer = context.ignore_pointer;
+        context.ignore_pointer = context.ignore_pointer || self.hover_through;
+
+        if context.ignore_pointer {
+            context.non_interactable.insert(id);
+        }
+
+        // The whole subtree of the focused detector reads the keyboard.
+        let parent_access = context.of::<KeyboardAccess>().copied().unwrap_or_default();
+        let keyboard_access = KeyboardAccess {
+            active: parent_access.active || response.is_focused || self.global_keys,
+            shortcut_keys: parent_access.shortcut_keys || self.shortcut_keys,
+        };
+
+        context.foregrounds.push(widget_ref);
+        context.provide(response.clone(), |context| {
+            context.provide(keyboard_access, callback);
+        });
+        context.ignore_pointer = last_ignore_pointer;
 
         response
     }
+
+    /// Detects the gestures on the next widget pushed to the context rather than on the
+    /// content of a callback, e.g. on the leaf of a custom widget, see
+    /// [`BuildContext::push_leaf`].
+    pub fn register(self, context: &mut BuildContext) -> GestureDetectorResponse {
+        self.build(context, |_| {})
+    }
 }
 
 #[track_caller]
 pub fn gesture_detector() -> GestureDetectorBuilder {
     GestureDetectorBuilder {
-        id: WidgetId::auto(),
+        id: WidgetKey::auto(),
         clickable: false,
         dragable: false,
         focusable: false,
+        hover_through: false,
+        hold_to_confirm: None,
+        global_keys: false,
+        shortcut_keys: false,
     }
 }
 
@@ -180,6 +307,7 @@ pub fn handle_interaction(
     input: &UserInput,
     view: &View,
     interaction: &mut InteractionState,
+    delta_time: f32,
     widget_state: &mut State,
 ) {
     widget_state.clicked = false;
@@ -201,7 +329,7 @@ pub fn handle_interaction(
                     widget_state.clicked = widget_state.clickable;
 
                     if widget_state.focusable {
-                        interaction.focused = Some(id);
+                        interaction.focus_with_pointer(&id);
                     }
                 } else {
                     interaction.set_inactive(&id);
@@ -220,7 +348,7 @@ pub fn handle_interaction(
             }
 
             if widget_state.focusable {
-                interaction.focused = Some(id);
+                interaction.focus_with_pointer(&id);
             }
 
             interaction.set_active(&id);
@@ -241,6 +369,8 @@ pub fn handle_interaction(
             DragState::Start => {
                 widget_state.drag_start_x = input.mouse_x / view.scale_factor;
                 widget_state.drag_start_y = input.mouse_y / view.scale_factor;
+                widget_state.drag_x = widget_state.drag_start_x;
+                widget_state.drag_y = widget_state.drag_start_y;
                 widget_state.last_x = input.mouse_x / view.scale_factor;
                 widget_state.last_y = input.mouse_y / view.scale_factor;
                 widget_state.drag_delta_x = 0.;
@@ -265,7 +395,185 @@ pub fn handle_interaction(
         }
     }
 
+    if let Some(duration) = widget_state.hold_to_confirm {
+        handle_hold(id, input, interaction, delta_time, duration, widget_state);
+    }
+
     widget_state.is_active = interaction.is_active(&id);
     widget_state.is_hot = interaction.is_hot(&id);
     widget_state.is_focused = interaction.is_focused(&id);
+    widget_state.is_focus_visible = interaction.is_focus_visible(&id);
+}
+
+fn handle_hold(
+    id: WidgetId,
+    input: &UserInput,
+    interaction: &InteractionState,
+    delta_time: f32,
+    duration: Duration,
+    widget_state: &mut State,
+) {
+    // The hold takes the place of the click.
+    widget_state.clicked = false;
+    widget_state.hold_completed = false;
+
+    let pointer_held = interaction.is_active(&id) && interaction.is_hot(&id);
+    let key_held = interaction.is_focused(&id)
+        && (input.is_key_down(KeyCode::Enter) || input.is_key_down(KeyCode::Space));
+    let step = if duration.is_zero() {
+        1.
+    } else {
+        delta_time.min(MAX_HOLD_STEP) / duration.as_secs_f32()
+    };
+
+    if pointer_held || key_held {
+        if !widget_state.hold_confirmed {
+            widget_state.hold_progress = (widget_state.hold_progress + step).min(1.);
+
+            if widget_state.hold_progress >= 1. {
+                widget_state.hold_completed = true;
+                widget_state.hold_confirmed = true;
+            }
+        }
+    } else {
+        widget_state.hold_confirmed = false;
+        widget_state.hold_progress =
+            (widget_state.hold_progress - step * HOLD_RELEASE_SPEED).max(0.);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn hold_state(duration: Duration) -> State {
+        State {
+            clickable: true,
+            focusable: true,
+            hold_to_confirm: Some(duration),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_hold_to_confirm_with_key() {
+        let id = WidgetId::auto();
+        let view = testing::view(100, 100);
+        let mut interaction = InteractionState::default();
+        let mut input = UserInput::default();
+        let mut state = hold_state(Duration::from_millis(100));
+
+        interaction.focus_with_keyboard(&id);
+        input.keys_down.push(KeyCode::Space);
+
+        let mut hold = |input: &UserInput, state: &mut State, delta_time| {
+            handle_interaction(id, input, &view, &mut interaction, delta_time, state);
+        };
+
+        hold(&input, &mut state, 0.03);
+        hold(&input, &mut state, 0.03);
+        assert!((state.hold_progress - 0.6).abs() < 1e-6);
+        assert!(!state.hold_completed);
+
+        hold(&input, &mut state, 0.03);
+        hold(&input, &mut state, 0.03);
+        assert_eq!(state.hold_progress, 1.);
+        assert!(state.hold_completed);
+
+        // Holding on past the end confirms once.
+        hold(&input, &mut state, 0.03);
+        assert!(!state.hold_completed);
+
+        input.keys_down.clear();
+        hold(&input, &mut state, 0.01);
+        assert!((state.hold_progress - 0.6).abs() < 1e-6);
+        assert!(!state.hold_completed);
+    }
+
+    #[test]
+    fn test_press_after_idle_gap_starts_the_hold_from_the_beginning() {
+        let id = WidgetId::auto();
+        let view = testing::view(100, 100);
+        let mut interaction = InteractionState::default();
+        let mut input = UserInput::default();
+        let mut state = hold_state(Duration::from_millis(800));
+
+        interaction.focus_with_keyboard(&id);
+        input.keys_down.push(KeyCode::Enter);
+
+        // The view has been idle for a minute before the key went down.
+        handle_interaction(id, &input, &view, &mut interaction, 60., &mut state);
+
+        assert!(state.hold_progress <= MAX_HOLD_STEP / 0.8 + 1e-6);
+        assert!(!state.hold_completed);
+    }
+
+    #[test]
+    fn test_keyboard_is_read_by_focused_and_global_detectors() {
+        use crate::{
+            ClewInstance, KeyBinding, SHORTCUTS_ROOT_SCOPE_ID, ShortcutId, io::InputEvent,
+            text::FontResources,
+        };
+
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(100, 100), FontResources::new());
+        let focused = WidgetKey::auto();
+
+        instance
+            .ui_state()
+            .shortcuts_registry
+            .scope(SHORTCUTS_ROOT_SCOPE_ID)
+            .add(ShortcutId("save"), KeyBinding::new(KeyCode::KeyS));
+        instance
+            .ui_state()
+            .interaction_state
+            .focus_with_keyboard(&focused.resolve(None));
+
+        for key in [KeyCode::KeyA, KeyCode::KeyS] {
+            instance.push_event(InputEvent::Key {
+                key_code: Some(key),
+                pressed: true,
+                repeat: false,
+                text: None,
+            });
+        }
+
+        let mut reads = Vec::new();
+
+        instance.frame(&mut host, &mut |ctx| {
+            let mut read = |ctx: &mut BuildContext| {
+                let keyboard = ctx.keyboard();
+
+                reads.push((
+                    keyboard.pressed(KeyCode::KeyA),
+                    keyboard.pressed(KeyCode::KeyS),
+                    keyboard.is_down(KeyCode::KeyS),
+                ));
+            };
+
+            read(ctx);
+            GestureDetectorBuilder {
+                id: focused,
+                ..gesture_detector().focusable(true)
+            }
+            .build(ctx, &mut read);
+            gesture_detector().focusable(true).build(ctx, &mut read);
+            gesture_detector()
+                .global_keys(true)
+                .shortcut_keys(true)
+                .build(ctx, &mut read);
+        });
+
+        assert_eq!(
+            reads,
+            [
+                (false, false, false),
+                // The press of S goes to the shortcut.
+                (true, false, true),
+                (false, false, false),
+                (true, true, true),
+            ]
+        );
+    }
 }
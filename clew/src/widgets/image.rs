@@ -0,0 +1,197 @@
+use std::{any::Any, sync::Arc};
+
+use clew_derive::WidgetBuilder;
+
+use crate::{
+    ColorRgba, ImageFit, Vec2, WidgetRef, WidgetType,
+    assets::MISSING_SVG_SIZE,
+    layout::{DeriveWrapSize, LayoutCommand, WidgetPlacement},
+    render::{PixelExtension, RenderCommand, RenderContext},
+    state::WidgetState,
+};
+
+use super::{FrameBuilder, builder::BuildContext};
+
+pub struct ImageWidget;
+
+#[derive(WidgetBuilder)]
+pub struct ImageBuilder<'a> {
+    frame: FrameBuilder,
+    asset_id: &'a str,
+    color: Option<ColorRgba>,
+    fit: ImageFit,
+    opacity: f32,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct State {
+    pub(crate) asset_id: Arc<str>,
+    pub(crate) color: Option<ColorRgba>,
+    pub(crate) fit: ImageFit,
+    pub(crate) opacity: f32,
+}
+
+impl WidgetState for State {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    #[inline]
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+}
+
+impl ImageBuilder<'_> {
+    /// Replaces the colors of the image keeping its alpha, e.g. for monochrome icons.
+    pub fn color(mut self, color: ColorRgba) -> Self {
+        self.color = Some(color);
+
+        self
+    }
+
+    /// How the image is fitted into the size of the widget, stretched by default.
+    pub fn fit(mut self, fit: ImageFit) -> Self {
+        self.fit = fit;
+
+        self
+    }
+
+    pub fn opacity(mut self, opacity: f32) -> Self {
+        self.opacity = opacity;
+
+        self
+    }
+
+    pub fn build(&self, context: &mut BuildContext) {
+        let id = context.widget_id(self.frame.id);
+
+        let widget_ref = WidgetRef::new(WidgetType::of::<ImageWidget>(), id);
+        let backgrounds = std::mem::take(context.backgrounds);
+        let foregrounds = std::mem::take(context.foregrounds);
+        let size = context
+            .assets
+            .get_bitmap(self.asset_id)
+            .map_or(MISSING_SVG_SIZE, |bitmap| {
+                Vec2::new(bitmap.width() as f32, bitmap.height() as f32)
+            });
+
+        context.push_layout_command(LayoutCommand::Leaf {
+            widget_ref,
+            backgrounds,
+            foregrounds,
+            padding: self.frame.padding,
+            margin: self.frame.margin,
+            constraints: self.frame.constraints,
+            size: self.frame.size,
+            zindex: self.frame.zindex,
+            derive_wrap_size: DeriveWrapSize::Fixed(size),
+            clip: self.frame.clip,
+        });
+
+        context.widgets_states.image.set(
+            id,
+            State {
+                asset_id: self.asset_id.into(),
+                color: self.color,
+                fit: self.fit,
+                opacity: self.opacity,
+            },
+        );
+    }
+}
+
+/// Bitmap loaded into [`crate::assets::Assets`] with
+/// [`crate::assets::Assets::load_bitmap`], e.g. a PNG icon or an avatar. Wraps to the
+/// size of the image in pixels, see [`super::async_image`] for the images loaded in the
+/// background.
+#[track_caller]
+pub fn image(asset_id: &str) -> ImageBuilder<'_> {
+    ImageBuilder {
+        frame: FrameBuilder::new(),
+        asset_id,
+        color: None,
+        fit: ImageFit::Fill,
+        opacity: 1.,
+    }
+}
+
+pub fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &State) {
+    ctx.push_command(
+        placement.zindex,
+        RenderCommand::Image {
+            boundary: placement.rect.px(ctx),
+            asset_id: state.asset_id.clone(),
+            tint_color: state.color,
+            fit: state.fit,
+            opacity: state.opacity,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::{
+        ClewHost, ClewInstance, Rect, Resources, assets::Assets, prelude::*, testing,
+        text::FontResources, widgets::builder::NoopEventLoopProxy,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_image_wraps_to_the_bitmap_size() {
+        let mut data = Vec::new();
+
+        image::RgbaImage::from_pixel(3, 2, image::Rgba([0x2F, 0x80, 0xC4, 0xFF]))
+            .write_to(
+                &mut std::io::Cursor::new(&mut data),
+                image::ImageFormat::Png,
+            )
+            .unwrap();
+
+        let mut assets = Assets::new();
+        assets.load_bitmap("avatar", &data).unwrap();
+
+        let mut host = ClewHost::new(assets, Resources::new(), Arc::new(NoopEventLoopProxy));
+        let mut instance = ClewInstance::new(testing::view(200, 200), FontResources::new());
+
+        instance.frame(&mut host, &mut |ctx| {
+            image("avatar").build(ctx);
+            image("avatar")
+                .fit(ImageFit::Cover)
+                .width(10.)
+                .height(10.)
+                .build(ctx);
+        });
+
+        let images: Vec<_> = instance
+            .ui_state()
+            .render_state
+            .commands()
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::Image { boundary, fit, .. } => {
+                    Some((boundary.width, boundary.height, *fit))
+                }
+                _ => None,
+            })
+            .collect();
+
+        assert_eq!(
+            images,
+            [(3., 2., ImageFit::Fill), (10., 10., ImageFit::Cover)]
+        );
+        assert_eq!(
+            ImageFit::Cover.rect(Rect::new(0., 0., 10., 10.), 3., 2.),
+            Rect::new(-2.5, 0., 15., 10.)
+        );
+    }
+}
@@ -0,0 +1,131 @@
+use clew_derive::{WidgetBuilder, WidgetState};
+
+use crate::announcement::Politeness;
+
+use super::{FrameBuilder, builder::BuildContext};
+
+#[derive(WidgetBuilder)]
+pub struct LiveRegionBuilder {
+    frame: FrameBuilder,
+    politeness: Politeness,
+}
+
+#[derive(WidgetState, Default)]
+struct State {
+    /// The region was built in the last frame, the texts it shows up with aren't news.
+    built: bool,
+}
+
+impl LiveRegionBuilder {
+    pub fn politeness(mut self, politeness: Politeness) -> Self {
+        self.politeness = politeness;
+
+        self
+    }
+
+    /// Builds the content and announces the texts of it that have changed or shown up
+    /// since the last frame, in the order they're built.
+    #[profiling::function]
+    pub fn build<F>(mut self, context: &mut BuildContext, content: F)
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        let id = self.frame.widget_id(context);
+        // The texts of the nested regions are theirs to announce.
+        let outer = context.announcements.live_texts.replace(Vec::new());
+
+        self.frame.build(context, content);
+
+        let texts = std::mem::replace(&mut context.announcements.live_texts, outer);
+        let state = context.state::<State>(id);
+        let appeared = !std::mem::replace(&mut state.built, true);
+
+        if let Some(texts) = texts
+            && !appeared
+            && !texts.is_empty()
+        {
+            context.announce(texts.join("\n"), self.politeness);
+        }
+    }
+}
+
+/// Announces the changes of the texts of its content to the screen readers, e.g. of a
+/// status line or of the container the toasts are shown in. Like the live regions of
+/// the web, the texts it's built with at first aren't announced and neither are the
+/// ones that go away, so it's meant to be kept while its content comes and goes.
+///
+/// ```ignore
+/// live_region().build(ctx, |ctx| {
+///     if let Some(error) = &form.error {
+///         text(error).color(theme.error).build(ctx);
+///     }
+/// });
+/// ```
+#[track_caller]
+pub fn live_region() -> LiveRegionBuilder {
+    LiveRegionBuilder {
+        frame: FrameBuilder::new(),
+        politeness: Politeness::Polite,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Announcement, testing,
+        widgets::{text::text, vstack::vstack},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_changed_texts_of_live_region_are_announced() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+
+        let mut announced = |status: &str, error: Option<&str>| {
+            instance.frame(&mut host, &mut |ctx| {
+                text("Title").build(ctx);
+
+                live_region().build(ctx, |ctx| {
+                    vstack().build(ctx, |ctx| {
+                        text(status).build(ctx);
+
+                        if let Some(error) = error {
+                            live_region()
+                                .politeness(Politeness::Assertive)
+                                .build(ctx, |ctx| text(error).build(ctx));
+                        }
+                    });
+                });
+            });
+
+            instance.take_announcements()
+        };
+
+        // Neither the texts it shows up with nor the ones that stay the same.
+        assert_eq!(announced("Idle", None), []);
+        assert_eq!(announced("Idle", None), []);
+
+        assert_eq!(
+            announced("Saving", None),
+            [Announcement {
+                text: "Saving".to_string(),
+                politeness: Politeness::Polite,
+            }]
+        );
+
+        // The nested region has appeared with the text, the enclosing one doesn't read
+        // it out for it.
+        assert_eq!(announced("Saving", Some("Disk is full")), []);
+        assert_eq!(
+            announced("Saving", Some("Disk is still full")),
+            [Announcement {
+                text: "Disk is still full".to_string(),
+                politeness: Politeness::Assertive,
+            }]
+        );
+
+        // The texts that go away aren't announced.
+        assert_eq!(announced("Saving", None), []);
+    }
+}
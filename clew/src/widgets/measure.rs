@@ -0,0 +1,69 @@
+use clew_derive::WidgetBuilder;
+
+use crate::{
+    Rect,
+    layout::{ContainerKind, LayoutCommand},
+};
+
+use super::{FrameBuilder, builder::BuildContext};
+
+#[derive(WidgetBuilder)]
+pub struct MeasureBuilder {
+    frame: FrameBuilder,
+}
+
+impl MeasureBuilder {
+    /// Builds the content and returns the rect it got in the previous layout, `None` on
+    /// the first frame. Useful to place something relative to the content, e.g. a popup
+    /// next to the button that opened it.
+    pub fn build<F>(self, context: &mut BuildContext, callback: F) -> Option<Rect>
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        self.build_with_rect(context, |context, _| callback(context))
+    }
+
+    /// Like [`MeasureBuilder::build`], and passes the rect to the content too, e.g. to
+    /// move the content that would get out of the view back into it.
+    #[profiling::function]
+    pub fn build_with_rect<F>(mut self, context: &mut BuildContext, callback: F) -> Option<Rect>
+    where
+        F: FnOnce(&mut BuildContext, Option<Rect>),
+    {
+        let id = context.widget_id(self.frame.id);
+        let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+
+        let rect = context
+            .widgets_states
+            .layout_measures
+            .get(id)
+            .map(|measure| Rect::new(measure.x, measure.y, measure.width, measure.height));
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds,
+            foregrounds,
+            zindex: self.frame.zindex,
+            padding: self.frame.padding,
+            margin: self.frame.margin,
+            kind: ContainerKind::Measure { id },
+            size: self.frame.size,
+            constraints: self.frame.constraints,
+            clip: self.frame.clip,
+        });
+
+        callback(context, rect);
+
+        context.push_layout_command(LayoutCommand::EndContainer);
+
+        context.widgets_states.layout_measures.mark_accessed(id);
+
+        rect
+    }
+}
+
+#[track_caller]
+pub fn measure() -> MeasureBuilder {
+    MeasureBuilder {
+        frame: FrameBuilder::new(),
+    }
+}
@@ -1,16 +1,32 @@
+pub mod async_image;
 pub mod builder;
+pub mod cache_layer;
 pub mod component;
 pub mod decorated_box;
 pub mod editable_text;
+pub mod file_drop_target;
+pub mod flow;
+pub mod focus_group;
 pub mod for_each;
 pub mod frame;
 pub mod gap;
 pub mod gesture_detector;
 pub mod hstack;
+pub mod image;
+pub mod live_region;
+pub mod measure;
+pub mod pan_zoom_area;
+pub mod portal;
+pub mod positioned;
+pub mod responsive;
+pub mod rich_text;
+pub mod safe_area;
 pub mod scope;
 pub mod scroll_area;
 pub mod shortcuts;
+pub mod silhouette;
 pub mod stateful;
+pub mod sticky_header;
 pub mod svg;
 pub mod text;
 pub mod virtual_list;
@@ -18,26 +34,48 @@ pub mod vstack;
 pub mod widget;
 pub mod zstack;
 
+pub use async_image::{AsyncImageResponse, ImageLoadState, async_image};
 pub use builder::{BuildContext, Resolve};
+pub use cache_layer::cache_layer;
 pub use component::{Component, component};
 pub use decorated_box::{DecorationBuilder, decorated_box, decoration};
 pub use editable_text::{
-    CommonShortcut, ShortcutScopes, TextEditingShortcut, TextInputModifier, editable_text,
+    CommonShortcut, DateFormatter, DateOrder, EditCommand, EditableTextResponse, FormattedText,
+    Formatter, Mask, NumberGrouping, ShortcutScopes, Suggestion, SuggestionToken,
+    TextEditingShortcut, TextInputModifier, add_edit_command_shortcuts, apply_edit_command,
+    editable_text,
 };
+pub use file_drop_target::{FileDropTargetResponse, file_drop_target};
+pub use flow::flow;
+pub use focus_group::{FocusGroupResponse, focus_group};
 pub use for_each::for_each;
 pub use frame::FrameBuilder;
-pub use gap::gap;
+pub use gap::{gap, spacer};
 pub use gesture_detector::{DragState, GestureDetectorResponse, gesture_detector};
 pub use hstack::hstack;
+pub use image::image;
+pub use live_region::live_region;
+pub use measure::measure;
+pub use pan_zoom_area::{
+    PanZoomAreaResponse, PanZoomCamera, fit_to_content, pan_zoom_area, set_pan_zoom_camera,
+};
+pub use portal::{PortalPlacement, PortalResponse, bring_portal_to_front, portal};
+pub use positioned::{Anchor, positioned};
+pub use responsive::responsive;
+pub use rich_text::{InlineSpan, RichText, rich_text};
+pub use safe_area::safe_area;
 pub use scope::scope;
 pub use scroll_area::{
-    ScrollAreaResponse, scroll_area, set_scroll_offset_x, set_scroll_offset_y,
-    set_scroll_progress_x, set_scroll_progress_y,
+    OverscrollIndicator, ScrollAreaResponse, ScrollBarMode, ScrollBarStyle, ScrollObserver,
+    scroll_area, scroll_effect, set_scroll_offset_x, set_scroll_offset_y, set_scroll_progress_x,
+    set_scroll_progress_y,
 };
 pub use shortcuts::shortcut_scope;
+pub use silhouette::{SilhouetteShape, silhouette};
 pub use stateful::stateful;
+pub use sticky_header::sticky_header;
 pub use svg::svg;
-pub use text::text;
+pub use text::{text, text_lazy};
 pub use virtual_list::virtual_list;
 pub use vstack::vstack;
 pub use widget::{Widget, widget};
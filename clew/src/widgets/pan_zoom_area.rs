@@ -0,0 +1,477 @@
+use std::any::Any;
+
+use clew_derive::WidgetBuilder;
+
+use crate::{
+    Clip, Rect, Transform, Value, Vec2, WidgetId, WidgetRef, WidgetType,
+    animation::{Animation, Lerp, Tween, curves},
+    interaction::InteractionState,
+    io::UserInput,
+    keyboard::{KeyCode, KeyModifiers},
+    layout::{ContainerKind, LayoutCommand, LayoutMeasure},
+    snapshot::{SerializableWidgetState, StateValue},
+    state::WidgetState,
+};
+
+use super::{FrameBuilder, builder::BuildContext};
+
+/// Zoom change per pixel of the mouse wheel delta.
+const WHEEL_ZOOM_SPEED: f32 = 0.005;
+
+pub struct PanZoomAreaWidget;
+
+#[derive(WidgetBuilder)]
+pub struct PanZoomAreaBuilder {
+    frame: FrameBuilder,
+    min_zoom: f32,
+    max_zoom: f32,
+    snap_to_actual_size: bool,
+}
+
+/// Maps the content of the pan zoom area to its viewport, both relative to the top
+/// left corner of the area.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PanZoomCamera {
+    pub offset: Vec2,
+    pub zoom: f32,
+}
+
+impl Default for PanZoomCamera {
+    fn default() -> Self {
+        Self {
+            offset: Vec2::ZERO,
+            zoom: 1.,
+        }
+    }
+}
+
+impl PanZoomCamera {
+    pub fn content_to_viewport(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            point.x * self.zoom + self.offset.x,
+            point.y * self.zoom + self.offset.y,
+        )
+    }
+
+    pub fn viewport_to_content(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            (point.x - self.offset.x) / self.zoom,
+            (point.y - self.offset.y) / self.zoom,
+        )
+    }
+
+    /// Changes the zoom keeping the content point under `anchor` in place, `anchor`
+    /// is in the viewport coordinates.
+    pub fn zoom_around(&mut self, anchor: Vec2, zoom: f32) {
+        let content_anchor = self.viewport_to_content(anchor);
+
+        self.zoom = zoom;
+        self.offset = Vec2::new(
+            anchor.x - content_anchor.x * zoom,
+            anchor.y - content_anchor.y * zoom,
+        );
+    }
+}
+
+impl Lerp for PanZoomCamera {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        PanZoomCamera {
+            offset: Vec2::new(
+                f32::lerp(self.offset.x, to.offset.x, t),
+                f32::lerp(self.offset.y, to.offset.y, t),
+            ),
+            zoom: f32::lerp(self.zoom, to.zoom, t),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct State {
+    pub(crate) camera: PanZoomCamera,
+    pub(crate) transition: Tween<PanZoomCamera>,
+    pub(crate) panning: bool,
+    pub(crate) last_pointer: Vec2,
+    pub(crate) min_zoom: f32,
+    pub(crate) max_zoom: f32,
+    pub(crate) snap_to_actual_size: bool,
+    pub(crate) x: f32,
+    pub(crate) y: f32,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    pub(crate) content_width: f32,
+    pub(crate) content_height: f32,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct PanZoomAreaResponse {
+    pub id: WidgetId,
+    pub camera: PanZoomCamera,
+    pub is_panning: bool,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub content_width: f32,
+    pub content_height: f32,
+}
+
+impl PanZoomAreaResponse {
+    /// Part of the content that is visible in the viewport, in the content coordinates.
+    pub fn visible_content_rect(&self) -> Rect {
+        let top_left = self.camera.viewport_to_content(Vec2::ZERO);
+
+        Rect::new(
+            top_left.x,
+            top_left.y,
+            self.width / self.camera.zoom,
+            self.height / self.camera.zoom,
+        )
+    }
+
+    #[inline]
+    pub fn content_rect(&self) -> Rect {
+        Rect::new(0., 0., self.content_width, self.content_height)
+    }
+}
+
+impl WidgetState for State {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    #[inline]
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    #[inline]
+    fn as_serializable(&self) -> Option<&dyn SerializableWidgetState> {
+        Some(self)
+    }
+
+    #[inline]
+    fn as_serializable_mut(&mut self) -> Option<&mut dyn SerializableWidgetState> {
+        Some(self)
+    }
+}
+
+impl SerializableWidgetState for State {
+    fn state_kind(&self) -> &'static str {
+        "clew::pan_zoom_area"
+    }
+
+    fn save_state(&self) -> StateValue {
+        StateValue::List(vec![
+            StateValue::Float(self.camera.offset.x as f64),
+            StateValue::Float(self.camera.offset.y as f64),
+            StateValue::Float(self.camera.zoom as f64),
+        ])
+    }
+
+    fn restore_state(&mut self, value: &StateValue) -> bool {
+        let Some([x, y, zoom]) = value.as_list() else {
+            return false;
+        };
+        let (Some(x), Some(y), Some(zoom)) = (x.as_f64(), y.as_f64(), zoom.as_f64()) else {
+            return false;
+        };
+
+        self.camera = PanZoomCamera {
+            offset: Vec2::new(x as f32, y as f32),
+            zoom: zoom as f32,
+        };
+        self.transition.set(self.camera);
+
+        true
+    }
+}
+
+impl PanZoomAreaBuilder {
+    pub fn zoom_range(mut self, min_zoom: f32, max_zoom: f32) -> Self {
+        self.min_zoom = min_zoom;
+        self.max_zoom = max_zoom;
+
+        self
+    }
+
+    /// Stop at 100% when the zoom passes through it.
+    pub fn snap_to_actual_size(mut self, value: bool) -> Self {
+        self.snap_to_actual_size = value;
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build<F>(mut self, context: &mut BuildContext, callback: F) -> PanZoomAreaResponse
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        let id = context.widget_id(self.frame.id);
+        let widget_ref = WidgetRef::new(WidgetType::of::<PanZoomAreaWidget>(), id);
+
+        let (mut backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+        backgrounds.push(widget_ref);
+
+        let delta_time = context.animation_delta_time();
+
+        let (transform, response) = {
+            let state = context.widgets_states.pan_zoom_area.access(id, || State {
+                camera: PanZoomCamera::default(),
+                transition: Tween::new(PanZoomCamera::default())
+                    .curve(curves::f32::ease_in_out_cubic),
+                panning: false,
+                last_pointer: Vec2::ZERO,
+                min_zoom: self.min_zoom,
+                max_zoom: self.max_zoom,
+                snap_to_actual_size: self.snap_to_actual_size,
+                x: 0.,
+                y: 0.,
+                width: 0.,
+                height: 0.,
+                content_width: 0.,
+                content_height: 0.,
+            });
+
+            context.widgets_states.snapshots.track(id, state);
+
+            state.min_zoom = self.min_zoom;
+            state.max_zoom = self.max_zoom;
+            state.snap_to_actual_size = self.snap_to_actual_size;
+
+            if state.transition.in_progress() {
+                state.transition.step(delta_time);
+                state.camera = state.transition.value();
+            }
+
+            let layout_measures = context.widgets_states.layout_measures.get_mut(id);
+
+            if let Some(layout_measures) = layout_measures {
+                handle_interaction(
+                    id,
+                    state,
+                    context.input,
+                    context.view.scale_factor,
+                    context.interaction,
+                    layout_measures,
+                );
+            }
+
+            let origin = Vec2::new(state.x, state.y);
+            let zoom = state.camera.zoom;
+
+            (
+                Transform::new(
+                    Vec2::new(
+                        origin.x * (1. - zoom) + state.camera.offset.x,
+                        origin.y * (1. - zoom) + state.camera.offset.y,
+                    ),
+                    zoom,
+                ),
+                PanZoomAreaResponse {
+                    id,
+                    camera: state.camera,
+                    is_panning: state.panning,
+                    x: state.x,
+                    y: state.y,
+                    width: state.width,
+                    height: state.height,
+                    content_width: state.content_width,
+                    content_height: state.content_height,
+                },
+            )
+        };
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds,
+            foregrounds,
+            zindex: self.frame.zindex,
+            padding: self.frame.padding,
+            margin: self.frame.margin,
+            kind: ContainerKind::Measure { id },
+            size: self.frame.size,
+            constraints: self.frame.constraints,
+            clip: self.frame.clip,
+        });
+
+        context.push_layout_command(LayoutCommand::BeginTransform { transform });
+        context.provide(response.clone(), callback);
+        context.push_layout_command(LayoutCommand::EndTransform);
+
+        context.push_layout_command(LayoutCommand::EndContainer);
+
+        context.widgets_states.layout_measures.mark_accessed(id);
+
+        response
+    }
+}
+
+#[track_caller]
+pub fn pan_zoom_area() -> PanZoomAreaBuilder {
+    let mut frame = FrameBuilder::new();
+    frame.clip = Clip::Rect;
+
+    PanZoomAreaBuilder {
+        frame,
+        min_zoom: 0.1,
+        max_zoom: 8.,
+        snap_to_actual_size: false,
+    }
+}
+
+pub fn set_pan_zoom_camera(context: &mut BuildContext, id: WidgetId, camera: PanZoomCamera) {
+    let state = context.widgets_states.pan_zoom_area.get_mut(id);
+
+    if let Some(state) = state {
+        state.transition.set(camera);
+        state.camera = camera;
+    }
+}
+
+/// Animates the camera so `rect`, in the content coordinates, fits the viewport and
+/// is centered in it.
+pub fn fit_to_content(context: &mut BuildContext, id: WidgetId, rect: Rect) {
+    let state = context.widgets_states.pan_zoom_area.get_mut(id);
+
+    if let Some(state) = state
+        && rect.width > 0.
+        && rect.height > 0.
+    {
+        let zoom = f32::min(state.width / rect.width, state.height / rect.height)
+            .clamp(state.min_zoom, state.max_zoom);
+        let target = PanZoomCamera {
+            offset: Vec2::new(
+                (state.width - rect.width * zoom) / 2. - rect.x * zoom,
+                (state.height - rect.height * zoom) / 2. - rect.y * zoom,
+            ),
+            zoom,
+        };
+
+        state.transition.set(state.camera);
+        state.transition.tween_to(target);
+    }
+}
+
+pub fn handle_interaction(
+    id: WidgetId,
+    widget_state: &mut State,
+    input: &UserInput,
+    scale_factor: f32,
+    interaction_state: &mut InteractionState,
+    layout_measure: &LayoutMeasure,
+) {
+    widget_state.x = layout_measure.x;
+    widget_state.y = layout_measure.y;
+    widget_state.width = layout_measure.width;
+    widget_state.height = layout_measure.height;
+    widget_state.content_width = layout_measure.wrap_width;
+    widget_state.content_height = layout_measure.wrap_height;
+
+    let pointer = Vec2::new(
+        input.mouse_x / scale_factor - widget_state.x,
+        input.mouse_y / scale_factor - widget_state.y,
+    );
+    let is_hover = interaction_state.is_hover(&id);
+    let space_down = input.is_key_down(KeyCode::Space);
+
+    if widget_state.panning {
+        if input.mouse_middle_pressed || (space_down && input.mouse_left_pressed) {
+            widget_state.camera.offset = Vec2::new(
+                widget_state.camera.offset.x + pointer.x - widget_state.last_pointer.x,
+                widget_state.camera.offset.y + pointer.y - widget_state.last_pointer.y,
+            );
+        } else {
+            widget_state.panning = false;
+            interaction_state.set_inactive(&id);
+        }
+    } else if is_hover
+        && interaction_state.active.is_none()
+        && (input.mouse_middle_pressed || (space_down && input.mouse_left_pressed))
+    {
+        // Taking the active slot keeps the children from reacting to the pan.
+        widget_state.panning = true;
+        interaction_state.set_active(&id);
+        interaction_state.block_hover = true;
+    }
+
+    widget_state.last_pointer = pointer;
+
+    if is_hover {
+        let zoom_modifier = input
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::SUPER);
+        let mut zoom_factor = 1. + input.pinch_delta;
+
+        if zoom_modifier {
+            zoom_factor *= (input.mouse_wheel_delta_y as f32 * WHEEL_ZOOM_SPEED).exp();
+        } else if input.mouse_wheel_delta_x != 0. || input.mouse_wheel_delta_y != 0. {
+            widget_state.camera.offset = Vec2::new(
+                widget_state.camera.offset.x + input.mouse_wheel_delta_x as f32,
+                widget_state.camera.offset.y + input.mouse_wheel_delta_y as f32,
+            );
+        }
+
+        if zoom_factor != 1. {
+            let zoom = next_zoom(
+                widget_state.camera.zoom,
+                widget_state.camera.zoom * zoom_factor,
+                widget_state.min_zoom,
+                widget_state.max_zoom,
+                widget_state.snap_to_actual_size,
+            );
+
+            widget_state.camera.zoom_around(pointer, zoom);
+        }
+    }
+
+    // Direct manipulation cancels a running transition.
+    if widget_state.transition.in_progress()
+        && widget_state.transition.value() != widget_state.camera
+    {
+        widget_state.transition.set(widget_state.camera);
+    }
+}
+
+fn next_zoom(current: f32, requested: f32, min_zoom: f32, max_zoom: f32, snap: bool) -> f32 {
+    let zoom = requested.clamp(min_zoom, max_zoom);
+
+    if snap && (current - 1.) * (zoom - 1.) < 0. {
+        1.
+    } else {
+        zoom
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_zoom_around_keeps_anchor_fixed() {
+        let mut camera = PanZoomCamera {
+            offset: Vec2::new(10., -20.),
+            zoom: 1.5,
+        };
+        let anchor = Vec2::new(120., 80.);
+        let content_point = camera.viewport_to_content(anchor);
+
+        camera.zoom_around(anchor, 3.);
+
+        let moved = camera.content_to_viewport(content_point);
+        assert!((moved.x - anchor.x).abs() < 1e-4);
+        assert!((moved.y - anchor.y).abs() < 1e-4);
+    }
+
+    #[test]
+    fn test_next_zoom_clamps_and_snaps() {
+        assert_eq!(next_zoom(1., 20., 0.1, 8., false), 8.);
+        assert_eq!(next_zoom(0.9, 1.1, 0.1, 8., true), 1.);
+        assert_eq!(next_zoom(0.9, 1.1, 0.1, 8., false), 1.1);
+        assert_eq!(next_zoom(1., 1.1, 0.1, 8., true), 1.1);
+    }
+}
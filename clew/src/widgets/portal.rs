@@ -0,0 +1,278 @@
+use crate::{
+    Clip, Constraints, EdgeInsets, Rect, Size, Vec2, WidgetId, WidgetKey, impl_id,
+    layout::{ContainerKind, LayoutCommand},
+};
+
+use super::builder::BuildContext;
+
+/// Z-index of the bottom portal, the ones above it go up by one in their stacking
+/// order. No content of the view goes this high.
+const PORTAL_ZINDEX: i32 = 1 << 24;
+
+/// Where the content of a portal is placed in the view.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PortalPlacement {
+    /// At the offset from the place the portal is built at, as it was laid out in the
+    /// previous frame. Nothing is shown on the first frame.
+    Anchored { offset: Vec2 },
+    /// In the rect of the view, the content gets the size of the rect.
+    Absolute(Rect),
+}
+
+pub struct PortalBuilder {
+    id: WidgetKey,
+    placement: PortalPlacement,
+}
+
+pub struct PortalResponse {
+    pub id: WidgetId,
+    /// Rect of the place the portal is built at from the previous layout, `None` on the
+    /// first frame.
+    pub anchor: Option<Rect>,
+}
+
+/// Content of a portal deferred until the end of the frame.
+pub(crate) struct Portal {
+    id: WidgetId,
+    /// Origin and size of the content, `None` while the anchor hasn't been laid out.
+    placement: Option<(Vec2, Size)>,
+    commands: Vec<LayoutCommand>,
+}
+
+impl PortalBuilder {
+    impl_id!();
+
+    /// Places the content at the offset from the place the portal is built at.
+    pub fn offset(mut self, x: f32, y: f32) -> Self {
+        self.placement = PortalPlacement::Anchored {
+            offset: Vec2::new(x, y),
+        };
+
+        self
+    }
+
+    /// Places the content in the rect of the view.
+    pub fn rect(mut self, rect: Rect) -> Self {
+        self.placement = PortalPlacement::Absolute(rect);
+
+        self
+    }
+
+    pub fn placement(mut self, placement: PortalPlacement) -> Self {
+        self.placement = placement;
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build<F>(self, context: &mut BuildContext, callback: F) -> PortalResponse
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        let id = context.widget_id(self.id);
+
+        let anchor = context
+            .widgets_states
+            .layout_measures
+            .get(id)
+            .map(|measure| Rect::new(measure.x, measure.y, measure.width, measure.height));
+
+        // Takes no space, it's only there to be measured.
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds: Default::default(),
+            foregrounds: Default::default(),
+            kind: ContainerKind::Measure { id },
+            constraints: Constraints::default(),
+            size: Size::fixed(0., 0.),
+            zindex: 0,
+            padding: EdgeInsets::ZERO,
+            margin: EdgeInsets::ZERO,
+            clip: Clip::None,
+        });
+        context.push_layout_command(LayoutCommand::EndContainer);
+        context.widgets_states.layout_measures.mark_accessed(id);
+
+        if !context.portal_order.contains(&id) {
+            context.portal_order.push(id);
+        }
+
+        let outer_commands = std::mem::take(context.layout_commands);
+        callback(context);
+        let commands = std::mem::replace(context.layout_commands, outer_commands);
+
+        let placement = match self.placement {
+            PortalPlacement::Anchored { offset } => {
+                anchor.map(|anchor| (anchor.position() + offset, Size::wrap()))
+            }
+            PortalPlacement::Absolute(rect) => {
+                Some((rect.position(), Size::fixed(rect.width, rect.height)))
+            }
+        };
+
+        context.portals.push(Portal {
+            id,
+            placement,
+            commands,
+        });
+
+        PortalResponse { id, anchor }
+    }
+}
+
+/// Builds the content out of the place it's called from, on top of the whole view: it
+/// escapes the clips, the transforms and the z-indices of its ancestors and takes the
+/// pointer before everything beneath. Use it for dropdowns, tooltips, context menus and
+/// modals.
+///
+/// The portals are stacked in the order they are first built in, see
+/// [`bring_portal_to_front`].
+#[track_caller]
+pub fn portal() -> PortalBuilder {
+    PortalBuilder {
+        id: WidgetKey::auto(),
+        placement: PortalPlacement::Anchored { offset: Vec2::ZERO },
+    }
+}
+
+/// Raises the portal with the id above the other portals.
+pub fn bring_portal_to_front(context: &mut BuildContext, id: WidgetId) {
+    if let Some(index) = context.portal_order.iter().position(|it| *it == id) {
+        let id = context.portal_order.remove(index);
+        context.portal_order.push(id);
+    }
+}
+
+/// Appends the portals built in the frame to the root of the layout in their stacking
+/// order, the portals that haven't been built are forgotten.
+pub(crate) fn append_portals(
+    commands: &mut Vec<LayoutCommand>,
+    portals: &mut Vec<Portal>,
+    order: &mut Vec<WidgetId>,
+) {
+    order.retain(|id| portals.iter().any(|portal| portal.id == *id));
+    portals.sort_by_key(|portal| order.iter().position(|id| *id == portal.id));
+
+    for (index, portal) in portals.drain(..).enumerate() {
+        let Some((origin, size)) = portal.placement else {
+            continue;
+        };
+
+        commands.push(LayoutCommand::BeginOffset {
+            offset_x: origin.x,
+            offset_y: origin.y,
+        });
+        commands.push(LayoutCommand::BeginContainer {
+            backgrounds: Default::default(),
+            foregrounds: Default::default(),
+            kind: ContainerKind::None,
+            constraints: Constraints::default(),
+            size,
+            zindex: PORTAL_ZINDEX + index as i32,
+            padding: EdgeInsets::ZERO,
+            margin: EdgeInsets::ZERO,
+            clip: Clip::None,
+        });
+        commands.extend(portal.commands);
+        commands.push(LayoutCommand::EndContainer);
+        commands.push(LayoutCommand::EndOffset);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        io::InputEvent,
+        layout::LayoutItem,
+        prelude::*,
+        testing,
+        widgets::{gesture_detector, hstack, zstack},
+    };
+
+    use super::*;
+
+    fn target(ctx: &mut BuildContext, key: usize) -> bool {
+        gesture_detector()
+            .id(key)
+            .clickable(true)
+            .build(ctx, |ctx| {
+                zstack().width(40.).height(40.).build(ctx, |_| {})
+            })
+            .is_hot()
+    }
+
+    #[test]
+    fn test_portal_escapes_ancestor_clip() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+
+        instance.frame(&mut host, &mut |ctx| {
+            zstack()
+                .width(50.)
+                .height(50.)
+                .clip(Clip::Rect)
+                .build(ctx, |ctx| {
+                    portal()
+                        .rect(Rect::new(100., 120., 40., 40.))
+                        .build(ctx, |ctx| {
+                            target(ctx, 0);
+                        });
+                });
+        });
+
+        let placement = instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .find_map(|item| match item {
+                LayoutItem::Placement(placement)
+                    if placement.widget_ref.widget_type
+                        == crate::WidgetType::of::<gesture_detector::GestureDetector>() =>
+                {
+                    Some(placement)
+                }
+                _ => None,
+            })
+            .unwrap();
+
+        assert_eq!(placement.rect, Rect::new(100., 120., 40., 40.));
+        assert_eq!(placement.clip_rect, None);
+    }
+
+    #[test]
+    fn test_portals_take_pointer_in_stacking_order() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let rect = Rect::new(0., 0., 40., 40.);
+
+        instance.push_event(InputEvent::PointerMoved { x: 10., y: 10. });
+
+        // The pointer is routed in the frame and seen in the next one.
+        let mut frame = |raise_first: bool| {
+            let mut hot = [false; 3];
+
+            instance.frame(&mut host, &mut |ctx| {
+                hstack().build(ctx, |ctx| {
+                    let first = portal()
+                        .rect(rect)
+                        .build(ctx, |ctx| hot[0] = target(ctx, 0));
+                    portal()
+                        .rect(rect)
+                        .build(ctx, |ctx| hot[1] = target(ctx, 1));
+
+                    if raise_first {
+                        bring_portal_to_front(ctx, first.id);
+                    }
+
+                    // Built last, it would take the pointer without the portals.
+                    hot[2] = target(ctx, 2);
+                });
+            });
+
+            hot
+        };
+
+        frame(false);
+        assert_eq!(frame(false), [false, true, false]);
+
+        frame(true);
+        assert_eq!(frame(false), [true, false, false]);
+    }
+}
@@ -0,0 +1,163 @@
+use clew_derive::WidgetBuilder;
+
+use crate::{AlignX, AlignY, Vec2, layout::LayoutCommand};
+
+use super::{FrameBuilder, builder::BuildContext, frame::FrameBuilderFlags};
+
+/// Corner, edge or center of a z-stack a [`positioned`] child is placed from.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Anchor {
+    #[default]
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+impl Anchor {
+    pub fn align(self) -> (AlignX, AlignY) {
+        let align_x = match self {
+            Anchor::TopLeft | Anchor::Left | Anchor::BottomLeft => AlignX::Left,
+            Anchor::Top | Anchor::Center | Anchor::Bottom => AlignX::Center,
+            Anchor::TopRight | Anchor::Right | Anchor::BottomRight => AlignX::Right,
+        };
+        let align_y = match self {
+            Anchor::TopLeft | Anchor::Top | Anchor::TopRight => AlignY::Top,
+            Anchor::Left | Anchor::Center | Anchor::Right => AlignY::Center,
+            Anchor::BottomLeft | Anchor::Bottom | Anchor::BottomRight => AlignY::Bottom,
+        };
+
+        (align_x, align_y)
+    }
+
+    /// Offset in the view of a distance from the anchor towards the center, e.g. a
+    /// distance of `(8, 8)` from the top right corner moves the child left and down.
+    fn offset(self, distance: Vec2) -> Vec2 {
+        let (align_x, align_y) = self.align();
+        let x = if align_x == AlignX::Right { -1. } else { 1. };
+        let y = if align_y == AlignY::Bottom { -1. } else { 1. };
+
+        Vec2::new(distance.x * x, distance.y * y)
+    }
+}
+
+#[derive(WidgetBuilder)]
+pub struct PositionedBuilder {
+    frame: FrameBuilder,
+    align_x: AlignX,
+    align_y: AlignY,
+}
+
+impl PositionedBuilder {
+    /// Alignment of the content in the enclosing z-stack.
+    pub fn align(mut self, align_x: AlignX, align_y: AlignY) -> Self {
+        self.align_x = align_x;
+        self.align_y = align_y;
+        self
+    }
+
+    /// Places the content at the `distance` from the `anchor` of the enclosing z-stack.
+    pub fn offset_from(mut self, anchor: Anchor, distance: Vec2) -> Self {
+        (self.align_x, self.align_y) = anchor.align();
+
+        let offset = anchor.offset(distance);
+        self.frame.offset_x = offset.x;
+        self.frame.offset_y = offset.y;
+        self.frame.flags |= FrameBuilderFlags::OFFSET;
+        self
+    }
+
+    #[profiling::function]
+    pub fn build<F>(mut self, context: &mut BuildContext, content: F)
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        context.push_layout_command(LayoutCommand::BeginAlign {
+            align_x: self.align_x,
+            align_y: self.align_y,
+        });
+        self.frame.build(context, content);
+        context.push_layout_command(LayoutCommand::EndAlign);
+    }
+}
+
+/// Child of a z-stack with an alignment of its own, so the children of one stack can
+/// be placed at different corners. The children are still drawn and hit-tested in the
+/// order they are built.
+///
+/// ```ignore
+/// zstack().fill_max_size().build(ctx, |ctx| {
+///     video(ctx);
+///     positioned().align(AlignX::Center, AlignY::Center).build(ctx, spinner);
+///     positioned()
+///         .offset_from(Anchor::TopRight, Vec2::new(8., 8.))
+///         .build(ctx, |ctx| close_button(ctx));
+///     positioned().align(AlignX::Center, AlignY::Bottom).build(ctx, controls);
+/// });
+/// ```
+#[track_caller]
+pub fn positioned() -> PositionedBuilder {
+    PositionedBuilder {
+        frame: FrameBuilder::new(),
+        align_x: AlignX::Left,
+        align_y: AlignY::Top,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        Rect,
+        layout::LayoutItem,
+        testing,
+        widgets::{builder::WidgetBuilder, decorated_box::decorated_box, zstack::zstack},
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_children_are_placed_at_their_own_corners() {
+        let (mut host, mut instance) = testing::instance(200, 100);
+
+        instance.frame(&mut host, &mut |ctx| {
+            zstack()
+                .fill_max_size()
+                .align_x(AlignX::Center)
+                .align_y(AlignY::Center)
+                .build(ctx, |ctx| {
+                    positioned()
+                        .align(AlignX::Center, AlignY::Bottom)
+                        .build(ctx, |ctx| decorated_box().width(40.).height(10.).build(ctx));
+                    positioned()
+                        .offset_from(Anchor::TopRight, Vec2::new(8., 4.))
+                        .build(ctx, |ctx| decorated_box().width(20.).height(20.).build(ctx));
+                    decorated_box().width(30.).height(30.).build(ctx);
+                });
+        });
+
+        let rects = instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .filter_map(|item| match item {
+                LayoutItem::Placement(placement) => Some(placement.rect),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // In the build order, the last child keeps the alignment of the stack.
+        assert_eq!(
+            rects,
+            [
+                Rect::new(80., 90., 40., 10.),
+                Rect::new(172., 4., 20., 20.),
+                Rect::new(85., 35., 30., 30.),
+            ]
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use crate::size_class::SizeClass;
+
+use super::builder::BuildContext;
+
+type LayoutFn<'f> = Box<dyn FnOnce(&mut BuildContext) + 'f>;
+
+pub struct ResponsiveBuilder<'f> {
+    compact: Option<LayoutFn<'f>>,
+    medium: Option<LayoutFn<'f>>,
+    expanded: Option<LayoutFn<'f>>,
+}
+
+impl<'f> ResponsiveBuilder<'f> {
+    pub fn compact(mut self, callback: impl FnOnce(&mut BuildContext) + 'f) -> Self {
+        self.compact = Some(Box::new(callback));
+        self
+    }
+
+    pub fn medium(mut self, callback: impl FnOnce(&mut BuildContext) + 'f) -> Self {
+        self.medium = Some(Box::new(callback));
+        self
+    }
+
+    pub fn expanded(mut self, callback: impl FnOnce(&mut BuildContext) + 'f) -> Self {
+        self.expanded = Some(Box::new(callback));
+        self
+    }
+
+    /// Builds the layout of the class of the view this frame, see
+    /// [`BuildContext::size_class`], and returns the class it was built for.
+    #[profiling::function]
+    pub fn build(self, context: &mut BuildContext) -> Option<SizeClass> {
+        let class = context.size_class().class;
+        let (class, callback) = self.take(class)?;
+
+        callback(context);
+
+        Some(class)
+    }
+
+    /// Layout of the class, or of the closest smaller class that has one, or of the
+    /// closest larger one.
+    fn take(self, class: SizeClass) -> Option<(SizeClass, LayoutFn<'f>)> {
+        let mut layouts = [
+            (SizeClass::Compact, self.compact),
+            (SizeClass::Medium, self.medium),
+            (SizeClass::Expanded, self.expanded),
+        ];
+        let index = class as usize;
+        let smaller = (0..=index).rev();
+        let larger = index + 1..layouts.len();
+
+        smaller
+            .chain(larger)
+            .find_map(|i| layouts[i].1.take().map(|callback| (layouts[i].0, callback)))
+    }
+}
+
+/// Builds one of the layouts depending on the size class of the view, the others are
+/// not built at all, so their widgets lose the state the same way as when removed.
+/// Widgets built in several layouts with the same explicit id keep it. A class
+/// without a layout uses the one of the closest smaller class.
+pub fn responsive<'f>() -> ResponsiveBuilder<'f> {
+    ResponsiveBuilder {
+        compact: None,
+        medium: None,
+        expanded: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, sync::Arc};
+
+    use clew_derive::WidgetState;
+
+    use crate::{
+        Breakpoints, ClewHost, ClewInstance, PhysicalSize, Resources, WidgetId,
+        assets::Assets,
+        io::{InputEvent, MouseButton},
+        prelude::*,
+        testing,
+        text::FontResources,
+        widgets::{
+            builder::NoopEventLoopProxy, gesture_detector::gesture_detector, zstack::zstack,
+        },
+    };
+
+    use super::*;
+
+    #[derive(WidgetState, Default)]
+    struct Visits(u32);
+
+    fn visit(ctx: &mut BuildContext, id: WidgetId) -> u32 {
+        let visits = ctx.state::<Visits>(id);
+        visits.0 += 1;
+
+        visits.0
+    }
+
+    /// Compact below 400 logical pixels, expanded from 800.
+    fn test_instance() -> (ClewHost<'static>, ClewInstance<'static>) {
+        let mut resources = Resources::new();
+        resources.insert(Breakpoints {
+            medium: 400.,
+            expanded: 800.,
+        });
+
+        let host = ClewHost::new(Assets::new(), resources, Arc::new(NoopEventLoopProxy));
+        let instance = ClewInstance::new(testing::view(300, 200), FontResources::new());
+
+        (host, instance)
+    }
+
+    #[test]
+    fn test_layout_follows_the_size_of_this_frame() {
+        let (mut host, mut instance) = test_instance();
+
+        let built = Cell::new(None);
+        let mut frame = |width| {
+            instance.resize(PhysicalSize::new(width, 200));
+            instance.frame(&mut host, &mut |ctx| {
+                responsive()
+                    .compact(|_| built.set(Some("compact")))
+                    .expanded(|_| built.set(Some("expanded")))
+                    .build(ctx);
+            });
+
+            built.take()
+        };
+
+        assert_eq!(frame(300), Some("compact"));
+        assert_eq!(frame(900), Some("expanded"));
+        // Medium has no layout and falls back to the compact one.
+        assert_eq!(frame(500), Some("compact"));
+    }
+
+    #[test]
+    fn test_missing_smaller_layouts_use_a_larger_one() {
+        let (class, _) = responsive()
+            .expanded(|_| {})
+            .take(SizeClass::Compact)
+            .unwrap();
+
+        assert_eq!(class, SizeClass::Expanded);
+        assert!(responsive().take(SizeClass::Medium).is_none());
+    }
+
+    #[test]
+    fn test_abandoned_layout_starts_over_and_shared_ids_keep_their_state() {
+        let (mut host, mut instance) = test_instance();
+        let shared = WidgetId::auto_with_seed("shared");
+        let compact_only = WidgetId::auto_with_seed("compact");
+
+        let mut frame = |width| {
+            let visits = Cell::new((0, None));
+
+            instance.resize(PhysicalSize::new(width, 200));
+            instance.frame(&mut host, &mut |ctx| {
+                responsive()
+                    .compact(|ctx| {
+                        visits.set((visit(ctx, shared), Some(visit(ctx, compact_only))));
+                    })
+                    .expanded(|ctx| visits.set((visit(ctx, shared), None)))
+                    .build(ctx);
+            });
+
+            visits.get()
+        };
+
+        assert_eq!(frame(300), (1, Some(1)));
+        assert_eq!(frame(300), (2, Some(2)));
+        assert_eq!(frame(900), (3, None));
+        // The state of the compact layout went away with it rather than being kept for
+        // its return.
+        assert_eq!(frame(300), (4, Some(1)));
+    }
+
+    #[test]
+    fn test_pressed_widget_of_abandoned_layout_releases_the_pointer() {
+        let (mut host, mut instance) = test_instance();
+
+        let mut frame = |instance: &mut ClewInstance<'static>, width| {
+            instance.resize(PhysicalSize::new(width, 200));
+            instance.frame(&mut host, &mut |ctx| {
+                responsive()
+                    .compact(|ctx| {
+                        gesture_detector().clickable(true).build(ctx, |ctx| {
+                            zstack().width(100.).height(100.).build(ctx, |_| {});
+                        });
+                    })
+                    .expanded(|ctx| zstack().width(100.).height(100.).build(ctx, |_| {}))
+                    .build(ctx);
+            });
+
+            instance.ui_state().interaction_state.active
+        };
+
+        frame(&mut instance, 300);
+        instance.push_event(InputEvent::PointerMoved { x: 50., y: 50. });
+        frame(&mut instance, 300);
+        instance.push_event(InputEvent::MouseButton {
+            button: MouseButton::Left,
+            pressed: true,
+        });
+        assert!(frame(&mut instance, 300).is_some());
+
+        // Resized while the button is held down, the layout the widget was in is gone.
+        assert_eq!(frame(&mut instance, 900), None);
+    }
+}
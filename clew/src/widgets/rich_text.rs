@@ -0,0 +1,700 @@
+use std::borrow::Cow;
+
+use clew_derive::WidgetBuilder;
+
+use crate::{
+    Clip, ColorRgba, EdgeInsets, Size, Vec2, WidgetId, WidgetKey, WidgetRef, WidgetType,
+    layout::{ContainerKind, DeriveWrapSize, LayoutCommand, WidgetPlacement},
+    render::{Fill, PixelExtension, RenderCommand, RenderContext, visible_band},
+    text::{FontResources, Text, TextId},
+};
+
+use super::{
+    FrameBuilder,
+    builder::BuildContext,
+    gesture_detector::{DragState, gesture_detector},
+};
+
+/// Character the inline boxes are shaped as, so line breaking and bidi treat each one
+/// as a single atomic cluster.
+const PLACEHOLDER: &str = "\u{FFFC}";
+
+pub struct RichTextWidget;
+
+type SlotFn<'a> = Box<dyn FnOnce(&mut BuildContext) + 'a>;
+
+#[derive(WidgetBuilder)]
+pub struct RichTextBuilder {
+    frame: FrameBuilder,
+    color: ColorRgba,
+    selectable: bool,
+    selection_color: ColorRgba,
+}
+
+pub struct RichTextResponse {
+    selected_text: Option<String>,
+}
+
+/// Spans of a [`rich_text`], in the order they are added.
+pub struct RichText<'a> {
+    spans: Vec<Span<'a>>,
+}
+
+enum Span<'a> {
+    Text(Cow<'a, str>),
+    Inline(InlineSpan<'a>),
+}
+
+/// Box reserved in the text for a widget, wrapped like a word.
+pub struct InlineSpan<'a> {
+    child: Option<SlotFn<'a>>,
+    size: Option<Vec2>,
+    baseline_offset: f32,
+}
+
+/// Box of an inline span the text has been shaped with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct InlineBox {
+    size: Vec2,
+    baseline_offset: f32,
+}
+
+pub(crate) struct State {
+    pub(crate) text_id: TextId,
+    /// Text with a placeholder for each box.
+    text: String,
+    boxes: Vec<InlineBox>,
+    scale_factor: f32,
+    color: ColorRgba,
+    /// Anchor and focus of the selection, the cursors of the buffer.
+    selection: Option<(cosmic_text::Cursor, cosmic_text::Cursor)>,
+    selection_color: ColorRgba,
+}
+
+impl State {
+    /// Byte range of the selection in the text, the cursors in order.
+    fn selected_range(&self) -> Option<std::ops::Range<usize>> {
+        let (anchor, focus) = self.selection?;
+        let offset = |cursor: cosmic_text::Cursor| {
+            let line_start: usize = self
+                .text
+                .split('\n')
+                .take(cursor.line)
+                .map(|line| line.len() + 1)
+                .sum();
+
+            line_start + cursor.index
+        };
+        let (start, end) = (offset(anchor), offset(focus));
+
+        (start != end).then(|| start.min(end)..start.max(end))
+    }
+}
+
+impl RichTextResponse {
+    /// Text of the selection of a [`RichTextBuilder::selectable`] text, each inline box
+    /// in it is one object replacement character.
+    pub fn selected_text(&self) -> Option<&str> {
+        self.selected_text.as_deref()
+    }
+}
+
+impl<'a> RichText<'a> {
+    pub fn text(&mut self, text: impl Into<Cow<'a, str>>) -> &mut Self {
+        self.spans.push(Span::Text(text.into()));
+
+        self
+    }
+
+    /// Reserves a box for the widget built by the child, of the size the child wraps
+    /// to unless it's given. The bottom of the box sits on the baseline of the line.
+    pub fn inline(&mut self, child: impl FnOnce(&mut BuildContext) + 'a) -> &mut InlineSpan<'a> {
+        self.spans.push(Span::Inline(InlineSpan {
+            child: Some(Box::new(child)),
+            size: None,
+            baseline_offset: 0.,
+        }));
+
+        match self.spans.last_mut() {
+            Some(Span::Inline(span)) => span,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl InlineSpan<'_> {
+    /// Size of the box, the child is built at it.
+    pub fn size(&mut self, width: f32, height: f32) -> &mut Self {
+        self.size = Some(Vec2::new(width, height));
+
+        self
+    }
+
+    /// Moves the box down from the baseline, e.g. to line up the text of a key cap
+    /// with the text around it.
+    pub fn baseline_offset(&mut self, offset: f32) -> &mut Self {
+        self.baseline_offset = offset;
+
+        self
+    }
+}
+
+impl RichTextBuilder {
+    pub fn color(mut self, color: ColorRgba) -> Self {
+        self.color = color;
+
+        self
+    }
+
+    /// The text is selected by dragging over it, an inline box is selected as a single
+    /// character. The selection is read from [`RichTextResponse::selected_text`].
+    pub fn selectable(mut self, value: bool) -> Self {
+        self.selectable = value;
+
+        self
+    }
+
+    pub fn selection_color(mut self, color: ColorRgba) -> Self {
+        self.selection_color = color;
+
+        self
+    }
+
+    #[profiling::function]
+    pub fn build<'a>(
+        mut self,
+        context: &mut BuildContext,
+        spans: impl FnOnce(&mut RichText<'a>),
+    ) -> RichTextResponse {
+        let id = context.widget_id(self.frame.id);
+
+        let mut rich_text = RichText { spans: Vec::new() };
+        spans(&mut rich_text);
+
+        let mut text = String::new();
+        let mut boxes = Vec::new();
+        let mut children = Vec::new();
+
+        for span in rich_text.spans {
+            match span {
+                Span::Text(span) => text.push_str(&span),
+                Span::Inline(mut span) => {
+                    let measure_id = inline_id(context, id, children.len());
+
+                    // Derived boxes are shaped at the size the child had in the last
+                    // layout, empty until it's been laid out once.
+                    let size = span.size.unwrap_or_else(|| {
+                        let measure = context.widgets_states.layout_measures.get(measure_id);
+
+                        if measure.is_none() {
+                            context.request_frame();
+                        }
+
+                        measure.map_or(Vec2::ZERO, |measure| {
+                            Vec2::new(measure.width, measure.height)
+                        })
+                    });
+
+                    text.push_str(PLACEHOLDER);
+                    boxes.push(InlineBox {
+                        size,
+                        baseline_offset: span.baseline_offset,
+                    });
+                    children.push((measure_id, span.size, span.child.take()));
+                }
+            }
+        }
+
+        let scale_factor = context.view.scale_factor;
+        let state = context.widgets_states.rich_text.get(id);
+        let changed = state.is_none_or(|state| {
+            state.text != text || state.boxes != boxes || state.scale_factor != scale_factor
+        });
+
+        let text_id = match state {
+            Some(state) => state.text_id,
+            None => context
+                .text
+                .add_text(context.view, context.fonts, 12., 12., |_, _| {}),
+        };
+
+        if changed {
+            let text_res = context.text.get_mut(text_id);
+            set_text(text_res, context.fonts, &text, &boxes, scale_factor);
+
+            // Reset wrap size calculation during layout.
+            if !self.frame.size.width.constrained() {
+                text_res.with_buffer_mut(|buffer| {
+                    buffer.set_size(&mut context.fonts.font_system, None, None);
+                });
+            }
+        }
+
+        // The drag is hit against the text of the last layout, the box is measured to
+        // place the pointer in it.
+        let focus = self
+            .selectable
+            .then(|| {
+                let gesture = context.scope(id, |context| {
+                    gesture_detector().dragable(true).register(context)
+                });
+                let measure = context.widgets_states.layout_measures.get(id)?;
+                let (x, y) = match gesture.drag_state {
+                    DragState::Start => (gesture.drag_start_x, gesture.drag_start_y),
+                    DragState::Update => (gesture.drag_x, gesture.drag_y),
+                    _ => return None,
+                };
+                let scale_factor = context.view.scale_factor;
+                let cursor = context.text.get(text_id).hit(
+                    (x - measure.x - self.frame.padding.left) * scale_factor,
+                    (y - measure.y - self.frame.padding.top) * scale_factor,
+                )?;
+
+                Some((gesture.drag_state, cursor))
+            })
+            .flatten();
+
+        let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds: Default::default(),
+            foregrounds: Default::default(),
+            kind: ContainerKind::Measure { id },
+            constraints: self.frame.constraints,
+            size: self.frame.size,
+            zindex: 0,
+            padding: EdgeInsets::ZERO,
+            margin: self.frame.margin,
+            clip: Clip::None,
+        });
+        context.widgets_states.layout_measures.mark_accessed(id);
+
+        context.push_layout_command(LayoutCommand::Leaf {
+            widget_ref: WidgetRef::new(WidgetType::of::<RichTextWidget>(), id),
+            backgrounds,
+            foregrounds,
+            padding: self.frame.padding,
+            margin: EdgeInsets::ZERO,
+            constraints: self.frame.constraints,
+            size: self.frame.size,
+            zindex: self.frame.zindex,
+            derive_wrap_size: DeriveWrapSize::Text(text_id),
+            clip: self.frame.clip,
+        });
+
+        for (index, (measure_id, size, child)) in children.into_iter().enumerate() {
+            let inline_box = boxes[index];
+
+            context.push_layout_command(LayoutCommand::BeginInlineOffset {
+                text_id,
+                placeholder: index + 1,
+                offset_x: self.frame.padding.left,
+                offset_y: self.frame.padding.top - inline_box.size.y + inline_box.baseline_offset,
+            });
+            context.push_layout_command(LayoutCommand::BeginContainer {
+                backgrounds: Default::default(),
+                foregrounds: Default::default(),
+                kind: ContainerKind::Measure { id: measure_id },
+                constraints: Default::default(),
+                size: size.map_or(Size::wrap(), |size| Size::fixed(size.x, size.y)),
+                zindex: self.frame.zindex,
+                padding: EdgeInsets::ZERO,
+                margin: EdgeInsets::ZERO,
+                clip: Clip::None,
+            });
+            context
+                .widgets_states
+                .layout_measures
+                .mark_accessed(measure_id);
+
+            if let Some(child) = child {
+                context.scope((id, index), child);
+            }
+
+            context.push_layout_command(LayoutCommand::EndContainer);
+            context.push_layout_command(LayoutCommand::EndOffset);
+        }
+
+        context.push_layout_command(LayoutCommand::EndContainer);
+
+        let state = context.widgets_states.rich_text.access(id, || State {
+            text_id,
+            text: String::new(),
+            boxes: Vec::new(),
+            scale_factor,
+            color: self.color,
+            selection: None,
+            selection_color: self.selection_color,
+        });
+
+        if changed {
+            state.text = text;
+            state.boxes = boxes;
+            state.scale_factor = scale_factor;
+            state.selection = None;
+        }
+
+        let last_selection = state.selection;
+
+        match focus {
+            Some((DragState::Start, cursor)) => state.selection = Some((cursor, cursor)),
+            Some((_, cursor)) => {
+                if let Some((_, focus)) = &mut state.selection {
+                    *focus = cursor;
+                }
+            }
+            None if !self.selectable => state.selection = None,
+            None => {}
+        }
+
+        state.color = self.color;
+        state.selection_color = self.selection_color;
+
+        let selected_text = state
+            .selected_range()
+            .map(|range| state.text[range].to_string());
+
+        if state.selection != last_selection {
+            context.request_frame();
+        }
+
+        RichTextResponse { selected_text }
+    }
+}
+
+/// Text with widgets inline, e.g. "Press [Ctrl] + [S] to save" with key caps. Each
+/// inline box is shaped as one object replacement character of its width, so it wraps
+/// like a word and a selection takes it as a single character.
+///
+/// ```ignore
+/// rich_text().build(ctx, |text| {
+///     text.text("Press ");
+///     text.inline(|ctx| key_cap(ctx, "Ctrl")).baseline_offset(3.);
+///     text.text(" to save");
+/// });
+/// ```
+#[track_caller]
+pub fn rich_text() -> RichTextBuilder {
+    RichTextBuilder {
+        frame: FrameBuilder::new(),
+        color: ColorRgba::from_hex(0xFFFFFFFF),
+        selectable: false,
+        selection_color: ColorRgba::from_hex(0xFF264F78),
+    }
+}
+
+/// Id of the measure of the child of the inline box.
+fn inline_id(context: &mut BuildContext, id: WidgetId, index: usize) -> WidgetId {
+    context.scope((id, index), |context| context.widget_id(WidgetKey::auto()))
+}
+
+/// Sets the text and widens the placeholders to their boxes. The advance of the
+/// placeholder glyph depends on the font it falls back to, so the text is shaped once
+/// to measure it and again with the letter spacing that makes up the difference.
+fn set_text(
+    text: &mut Text,
+    fonts: &mut FontResources,
+    content: &str,
+    boxes: &[InlineBox],
+    scale_factor: f32,
+) {
+    text.with_buffer_and_attrs_mut(|buffer, attrs| {
+        let metrics = buffer.metrics();
+        let font_system = &mut fonts.font_system;
+
+        // Advance of the placeholder and the ascent and descent of its line.
+        let spans = |shaped: &[(f32, f32, f32)]| {
+            let mut spans = Vec::new();
+
+            for (index, part) in content.split(PLACEHOLDER).enumerate() {
+                if let Some(index) = index.checked_sub(1) {
+                    let mut placeholder_attrs = attrs.clone().metadata(index + 1);
+
+                    if let Some(&(advance, ascent, descent)) = shaped.get(index) {
+                        let height = boxes[index].size.y * scale_factor;
+                        let offset = boxes[index].baseline_offset * scale_factor;
+
+                        // The glyphs are centered in the line, it's made tall enough
+                        // for the box to stay within it.
+                        let line_height = metrics
+                            .line_height
+                            .max(2. * (height - offset - ascent) + ascent + descent)
+                            .max(2. * offset + ascent - descent);
+
+                        placeholder_attrs = placeholder_attrs
+                            .metrics(cosmic_text::Metrics::new(metrics.font_size, line_height))
+                            .letter_spacing(
+                                (boxes[index].size.x * scale_factor - advance) / metrics.font_size,
+                            );
+                    }
+
+                    spans.push((PLACEHOLDER, placeholder_attrs));
+                }
+
+                if !part.is_empty() {
+                    spans.push((part, attrs.clone()));
+                }
+            }
+
+            spans
+        };
+
+        buffer.set_rich_text(
+            font_system,
+            spans(&[]),
+            attrs,
+            cosmic_text::Shaping::Advanced,
+            None,
+        );
+
+        if boxes.is_empty() {
+            return;
+        }
+
+        buffer.shape_until_scroll(font_system, false);
+
+        let mut shaped = vec![(0., 0., 0.); boxes.len()];
+
+        for line in &buffer.lines {
+            for layout_line in line.layout_opt().into_iter().flatten() {
+                for glyph in &layout_line.glyphs {
+                    if let Some(entry) = glyph
+                        .metadata
+                        .checked_sub(1)
+                        .and_then(|index| shaped.get_mut(index))
+                    {
+                        *entry = (glyph.w, layout_line.max_ascent, layout_line.max_descent);
+                    }
+                }
+            }
+        }
+
+        buffer.set_rich_text(
+            font_system,
+            spans(&shaped),
+            attrs,
+            cosmic_text::Shaping::Advanced,
+            None,
+        );
+    });
+}
+
+/// Position of the placeholder glyph on its baseline, in the pixels of the text.
+pub(crate) fn placeholder_position(text: &Text, placeholder: usize) -> Option<Vec2> {
+    let mut position = None;
+
+    text.with_buffer(|buffer| {
+        position = buffer.layout_runs().find_map(|run| {
+            run.glyphs
+                .iter()
+                .find(|glyph| glyph.metadata == placeholder)
+                .map(|glyph| Vec2::new(glyph.x, run.line_y))
+        });
+    });
+
+    position
+}
+
+pub(crate) fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &State) {
+    let rect = placement.rect.px(ctx);
+    let size = rect.size();
+
+    let text_size = ctx.text.get_mut(state.text_id).layout();
+    let visible_band = visible_band(rect, placement.clip_rect.map(|rect| rect.px(ctx)), rect.y);
+
+    if let Some(band) = &visible_band
+        && band.is_empty()
+    {
+        return;
+    }
+
+    let overflows = text_size.x > size.x + 0.5 || text_size.y > size.y + 0.5;
+
+    // Under the glyphs, the placeholder of an inline box is highlighted as a whole.
+    if let Some((anchor, focus)) = state.selection
+        && anchor != focus
+    {
+        let highlights = ctx
+            .text
+            .get(state.text_id)
+            .highlight_rects(anchor.min(focus), anchor.max(focus));
+
+        for highlight in highlights {
+            ctx.push_command(
+                placement.zindex,
+                RenderCommand::Rect {
+                    boundary: highlight.offset(rect.x, rect.y),
+                    fill: Some(Fill::Color(state.selection_color)),
+                    border_radius: None,
+                    border: None,
+                    border_fill: None,
+                },
+            );
+        }
+    }
+
+    ctx.push_command(
+        placement.zindex,
+        RenderCommand::Text {
+            x: rect.x,
+            y: rect.y,
+            text_id: state.text_id,
+            tint_color: Some(state.color),
+            visible_band,
+            clip: overflows.then_some(rect),
+            stroke: None,
+        },
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ClewHost, ClewInstance, Rect,
+        io::{InputEvent, MouseButton},
+        layout::LayoutItem,
+        prelude::*,
+        testing,
+        text::test_fonts,
+        widgets::{
+            gesture_detector::{self, gesture_detector},
+            zstack::zstack,
+        },
+    };
+
+    use super::*;
+
+    fn inline_rect(
+        host: &mut ClewHost<'static>,
+        instance: &mut ClewInstance<'static>,
+        width: f32,
+    ) -> Rect {
+        // The box is derived from the size of the child, laid out in the first frame.
+        for _ in 0..2 {
+            instance.frame(host, &mut |ctx| {
+                rich_text().width(width).build(ctx, |text| {
+                    text.text("Press ");
+                    text.inline(|ctx| {
+                        gesture_detector().build(ctx, |ctx| {
+                            zstack().width(30.).height(16.).build(ctx, |_| {})
+                        });
+                    });
+                    text.text(" to save");
+                });
+            });
+        }
+
+        instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .find_map(|item| match item {
+                LayoutItem::Placement(placement)
+                    if placement.widget_ref.widget_type
+                        == WidgetType::of::<gesture_detector::GestureDetector>() =>
+                {
+                    Some(placement.rect)
+                }
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_inline_box_wraps_like_word() {
+        let fonts = test_fonts();
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), fonts);
+
+        let inline = inline_rect(&mut host, &mut instance, 200.);
+        assert_eq!(inline.size(), Vec2::new(30., 16.));
+        assert!(inline.x > 0.);
+        assert!(inline.y >= 0.);
+
+        // Doesn't fit after "Press ", so it starts the next line.
+        let wrapped = inline_rect(&mut host, &mut instance, inline.x + 20.);
+        assert_eq!(wrapped.x, 0.);
+        assert!(wrapped.y > inline.y);
+    }
+
+    #[test]
+    fn test_selection_takes_inline_box_as_one_character() {
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), test_fonts());
+        let inline = inline_rect(&mut host, &mut instance, 200.);
+        let y = inline.y + inline.height / 2.;
+
+        let mut drag = |from: f32, to: f32| {
+            let mut selected = None;
+            let mut highlights = Vec::new();
+            let events = [
+                Some(InputEvent::PointerMoved { x: from, y }),
+                Some(InputEvent::MouseButton {
+                    button: MouseButton::Left,
+                    pressed: true,
+                }),
+                Some(InputEvent::PointerMoved { x: to, y }),
+                Some(InputEvent::MouseButton {
+                    button: MouseButton::Left,
+                    pressed: false,
+                }),
+                None,
+            ];
+
+            for event in events {
+                if let Some(event) = event {
+                    instance.push_event(event);
+                }
+
+                let render_state = instance.frame(&mut host, &mut |ctx| {
+                    let response = rich_text().width(200.).selectable(true).build(ctx, |text| {
+                        text.text("Press ");
+                        // Not interactive, the box is pressed through to the text.
+                        text.inline(|ctx| zstack().width(30.).height(16.).build(ctx, |_| {}));
+                        text.text(" to save");
+                    });
+
+                    selected = response.selected_text().map(str::to_string);
+                });
+
+                // Still frames are not rendered again.
+                let Some(render_state) = render_state else {
+                    continue;
+                };
+
+                highlights = render_state
+                    .commands()
+                    .iter()
+                    .filter_map(|command| match command {
+                        RenderCommand::Rect {
+                            boundary,
+                            fill: Some(Fill::Color(color)),
+                            ..
+                        } if *color == ColorRgba::from_hex(0xFF264F78) => Some(*boundary),
+                        _ => None,
+                    })
+                    .collect();
+            }
+
+            (selected, highlights)
+        };
+
+        // Either half of the box puts the cursor on one of its sides.
+        let (selected, _) = drag(1., inline.right() - 5.);
+        assert_eq!(selected.as_deref(), Some("Press \u{FFFC}"));
+
+        let (selected, highlights) = drag(inline.x + 2., inline.x + 12.);
+        assert_eq!(selected, None);
+        assert!(highlights.is_empty());
+
+        let (selected, _) = drag(inline.right() - 2., inline.right() + 12.);
+        assert_eq!(selected.as_deref(), Some(" t"));
+
+        // The whole box is highlighted.
+        let (selected, highlights) = drag(inline.x + 5., inline.right() - 5.);
+        assert_eq!(selected.as_deref(), Some("\u{FFFC}"));
+        assert_eq!(highlights.len(), 1);
+        assert_eq!(highlights[0].x, inline.x);
+        assert_eq!(highlights[0].width, inline.width);
+    }
+}
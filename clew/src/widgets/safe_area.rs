@@ -0,0 +1,140 @@
+use clew_derive::WidgetBuilder;
+
+use crate::EdgeInsets;
+
+use super::{FrameBuilder, builder::BuildContext, frame::FrameBuilderFlags};
+
+#[derive(WidgetBuilder)]
+pub struct SafeAreaBuilder {
+    frame: FrameBuilder,
+}
+
+/// Insets the enclosing safe areas pad their content by.
+pub(crate) struct SafeAreaPadding(pub(crate) EdgeInsets);
+
+impl SafeAreaBuilder {
+    /// Builds the content padded by [`BuildContext::safe_area_insets`] on top of the
+    /// padding of the frame.
+    #[profiling::function]
+    pub fn build<F>(mut self, context: &mut BuildContext, content: F)
+    where
+        F: FnOnce(&mut BuildContext),
+    {
+        let insets = context.safe_area_insets();
+        let padded = context
+            .of::<SafeAreaPadding>()
+            .map(|padding| padding.0)
+            .unwrap_or_default();
+
+        self.frame.padding = self.frame.padding + insets;
+        self.frame.flags |= FrameBuilderFlags::PADDING;
+
+        self.frame.build(context, |context| {
+            context.provide(SafeAreaPadding(padded + insets), content);
+        });
+    }
+}
+
+/// Keeps the content out of the parts of the view it can't be seen or reached in, e.g.
+/// under an on-screen keyboard or the notch of a display. Meant to wrap content that
+/// reaches the edges of the view, the safe areas inside of it don't pad again.
+///
+/// ```ignore
+/// safe_area().fill_max_size().build(ctx, |ctx| {
+///     scroll_area().fill_max_size().build(ctx, |ctx| form(ctx));
+/// });
+/// ```
+#[track_caller]
+pub fn safe_area() -> SafeAreaBuilder {
+    SafeAreaBuilder {
+        frame: FrameBuilder::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        ClewHost, ClewInstance, Rect, View, layout::LayoutItem, prelude::*, testing,
+        text::FontResources, widgets::decorated_box::decorated_box,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_content_is_padded_by_obscured_insets() {
+        let view = View {
+            safe_area: EdgeInsets::ZERO.top(10.),
+            ..testing::view(200, 200)
+        };
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(view, FontResources::new());
+
+        instance.set_obscured_insets(EdgeInsets::ZERO.bottom(80.), None);
+        instance.frame(&mut host, &mut |ctx| {
+            safe_area().fill_max_size().build(ctx, |ctx| {
+                // Nested safe areas don't pad again.
+                safe_area().fill_max_size().build(ctx, |ctx| {
+                    decorated_box().fill_max_size().build(ctx);
+                });
+            });
+        });
+
+        let rect = instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .find_map(|item| match item {
+                LayoutItem::Placement(placement) => Some(placement.rect),
+                _ => None,
+            });
+
+        assert_eq!(rect, Some(Rect::new(0., 10., 200., 110.)));
+    }
+
+    /// Height of a box filling a safe area that fills the view.
+    fn content_height<'a>(instance: &mut ClewInstance<'a>, host: &mut ClewHost<'a>) -> f32 {
+        instance.frame(host, &mut |ctx| {
+            safe_area().fill_max_size().build(ctx, |ctx| {
+                decorated_box().fill_max_size().build(ctx);
+            });
+        });
+
+        instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .find_map(|item| match item {
+                LayoutItem::Placement(placement) => Some(placement.rect.height),
+                _ => None,
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_content_follows_animated_insets() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+
+        assert_eq!(content_height(&mut instance, &mut host), 200.);
+
+        instance.set_obscured_insets(
+            EdgeInsets::ZERO.bottom(80.),
+            Some(Duration::from_millis(400)),
+        );
+
+        // On the way to the reported insets.
+        std::thread::sleep(Duration::from_millis(100));
+        let height = content_height(&mut instance, &mut host);
+        assert!(height < 200. && height > 120., "{height}");
+        assert!(instance.needs_build(&host));
+
+        std::thread::sleep(Duration::from_millis(400));
+        content_height(&mut instance, &mut host);
+        assert_eq!(content_height(&mut instance, &mut host), 120.);
+
+        // Without an animation they're taken at once.
+        instance.set_obscured_insets(EdgeInsets::ZERO, None);
+        assert_eq!(content_height(&mut instance, &mut host), 200.);
+    }
+}
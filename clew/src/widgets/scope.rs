@@ -13,15 +13,25 @@ impl ScopeBuilder {
     where
         F: FnOnce(&mut BuildContext) -> T,
     {
-        context.with_id_seed(self.key, callback)
+        context.widgets_states.snapshots.push_scope(self.key);
+        let result = context.with_id_seed(self.key, callback);
+        context.widgets_states.snapshots.pop_scope();
+
+        result
     }
 }
 
-pub fn scope(key: impl Hash) -> ScopeBuilder {
+pub(crate) fn scope_key(key: impl Hash) -> u64 {
     let mut hasher = FxHasher::default();
     key.hash(&mut hasher);
 
+    hasher.finish()
+}
+
+/// Seeds the ids of the widgets built inside, the key also names the scope for
+/// [`BuildContext::snapshot_ui_state`].
+pub fn scope(key: impl Hash) -> ScopeBuilder {
     ScopeBuilder {
-        key: hasher.finish(),
+        key: scope_key(key),
     }
 }
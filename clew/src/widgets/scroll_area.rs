@@ -1,23 +1,178 @@
-use std::any::Any;
+use std::{any::Any, ops::Range};
 
 use clew_derive::WidgetBuilder;
+use smallvec::{SmallVec, smallvec};
 
 use crate::{
-    ScrollDirection, WidgetId, WidgetRef, WidgetType,
+    AlignX, AlignY, Clip, ColorRgba, Constraints, CrossAxisAlignment, EdgeInsets, LinearGradient,
+    MainAxisAlignment, Rect, ScrollDirection, Size, SizeConstraint, Transform, Vec2, WidgetId,
+    WidgetIdMap, WidgetKey, WidgetRef, WidgetType,
     interaction::InteractionState,
     io::UserInput,
-    layout::{ContainerKind, LayoutCommand, LayoutMeasure},
-    state::WidgetState,
+    layout::{
+        ContainerKind, LayoutCommand, LayoutMeasure, ScrollAnchor, ScrollGutter, ScrollIntoView,
+    },
+    phases::{LayoutAccess, RenderMutator},
+    point_with_rect_hit_test,
+    snapshot::{SerializableWidgetState, StateValue},
+    state::{TypedWidgetStates, WidgetState},
 };
 
-use super::{FrameBuilder, builder::BuildContext};
+use super::{FrameBuilder, builder::BuildContext, decorated_box::decoration};
+
+/// Space kept between a widget scrolled into view and the edge of the scroll area.
+const SCROLL_INTO_VIEW_MARGIN: f32 = 8.;
+/// Rate of the exponential approach to the offsets a scroll into view targets.
+const SCROLL_INTO_VIEW_SPEED: f64 = 20.;
+/// Height of the shadow under a fixed header while the content is scrolled beneath it.
+const HEADER_SHADOW_HEIGHT: f32 = 6.;
+/// How far an anchor can drift from rounding before the offset follows it.
+const ANCHOR_TOLERANCE: f32 = 0.01;
+/// How far the content can go past the viewport from rounding before it overflows.
+const OVERFLOW_TOLERANCE: f32 = 0.01;
+/// Scroll delta past an edge at which the overscroll indicator is at its full intensity.
+const OVERSCROLL_MAX: f64 = 160.;
+/// Rate of the exponential decay of the overscroll once the scrolling stops.
+const OVERSCROLL_DECAY: f64 = 8.;
+/// Depth of the overscroll glow from the edge into the viewport.
+const OVERSCROLL_GLOW_EXTENT: f32 = 24.;
 
 pub struct ScrollAreaWidget;
 
+type SlotFn<'a> = Box<dyn FnOnce(&mut BuildContext) + 'a>;
+
 #[derive(WidgetBuilder)]
-pub struct ScrollAreaBuilder {
+pub struct ScrollAreaBuilder<'a> {
     frame: FrameBuilder,
     scroll_direction: ScrollDirection,
+    fixed_header: Option<SlotFn<'a>>,
+    fixed_footer: Option<SlotFn<'a>>,
+    header_shadow: Option<ColorRgba>,
+    preserve_anchor: bool,
+    scroll_bars: bool,
+    scroll_bar_style: Option<ScrollBarStyle>,
+    overscroll_indicator: OverscrollIndicator,
+    drag_to_scroll: bool,
+}
+
+/// Feedback shown while the content is scrolled further than its edge, the scroll offset
+/// itself stays clamped. Its intensity follows the scroll delta the edge has stopped and
+/// fades out when the scrolling stops, see [`ScrollAreaBuilder::overscroll_indicator`].
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OverscrollIndicator {
+    #[default]
+    None,
+    /// Gradient from the edge into the viewport, drawn over the content, like Android.
+    Glow { color: ColorRgba },
+    /// The content is stretched along the overscrolled axis from the opposite edge, up
+    /// to the factor at the full intensity, e.g. 1.05. The content is only drawn
+    /// stretched, the widgets are hit where they are laid out.
+    Stretch { max_factor: f32 },
+}
+
+/// How the scroll bars share the space with the content of a scroll area.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScrollBarMode {
+    /// The bars are drawn over the content, like the overlay bars of macOS.
+    Overlay,
+    /// The scroll area reserves the space of a bar next to the content while it
+    /// overflows along the axis of the bar, like the bars of Windows.
+    Gutter,
+}
+
+/// Look of the scroll bars, provided over a part of the view with
+/// [`BuildContext::provide`] or registered for the whole application as a resource, see
+/// [`ScrollBarStyle::of`]. The default follows the platform, in the color of the text of
+/// [`BuildContext::widget_theme`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollBarStyle {
+    pub thumb_color: ColorRgba,
+    pub thumb_hover_color: ColorRgba,
+    pub thumb_active_color: ColorRgba,
+    pub track_color: ColorRgba,
+    pub track_hover_color: ColorRgba,
+    pub thickness: f32,
+    /// Thickness of the thumb while it's dragged.
+    pub expanded_thickness: f32,
+    pub min_thumb_length: f32,
+    /// Space between the thumb and the edge of the scroll area.
+    pub margin: f32,
+    /// Space between the ends of the track and the edges of the scroll area.
+    pub end_margin: f32,
+    pub mode: ScrollBarMode,
+}
+
+impl ScrollBarStyle {
+    /// Thin bars drawn over the content that show the track while hovered.
+    pub fn overlay() -> Self {
+        Self {
+            thumb_color: ColorRgba::from_hex(0xFFFFFFFF).with_opacity(0.4),
+            thumb_hover_color: ColorRgba::from_hex(0xFFFFFFFF).with_opacity(0.5),
+            thumb_active_color: ColorRgba::from_hex(0xFFFFFFFF).with_opacity(0.5),
+            track_color: ColorRgba::TRANSPARENT,
+            track_hover_color: ColorRgba::from_hex(0xFFFFFFFF).with_opacity(0.05),
+            thickness: 4.,
+            expanded_thickness: 8.,
+            min_thumb_length: 16.,
+            margin: 8.,
+            end_margin: 8.,
+            mode: ScrollBarMode::Overlay,
+        }
+    }
+
+    /// Bars with an always visible track in the space reserved next to the content.
+    pub fn gutter() -> Self {
+        Self {
+            thumb_color: ColorRgba::from_hex(0xFFFFFFFF).with_opacity(0.3),
+            thumb_hover_color: ColorRgba::from_hex(0xFFFFFFFF).with_opacity(0.45),
+            thumb_active_color: ColorRgba::from_hex(0xFFFFFFFF).with_opacity(0.6),
+            track_color: ColorRgba::from_hex(0xFFFFFFFF).with_opacity(0.03),
+            track_hover_color: ColorRgba::from_hex(0xFFFFFFFF).with_opacity(0.06),
+            thickness: 8.,
+            expanded_thickness: 10.,
+            min_thumb_length: 24.,
+            margin: 2.,
+            end_margin: 2.,
+            mode: ScrollBarMode::Gutter,
+        }
+    }
+
+    /// Style provided with [`BuildContext::provide`], then the one registered as a
+    /// resource, then the platform default.
+    pub fn of(context: &BuildContext) -> Self {
+        context
+            .of::<ScrollBarStyle>()
+            .or_else(|| context.get::<ScrollBarStyle>())
+            .copied()
+            .unwrap_or_else(|| Self::default().with_color(context.widget_theme().text))
+    }
+
+    /// The thumb and the track in `color`, each with its own opacity.
+    pub fn with_color(self, color: ColorRgba) -> Self {
+        Self {
+            thumb_color: color.with_opacity(self.thumb_color.a),
+            thumb_hover_color: color.with_opacity(self.thumb_hover_color.a),
+            thumb_active_color: color.with_opacity(self.thumb_active_color.a),
+            track_color: color.with_opacity(self.track_color.a),
+            track_hover_color: color.with_opacity(self.track_hover_color.a),
+            ..self
+        }
+    }
+
+    /// Space a bar takes across its axis, reserved in the gutter mode.
+    pub fn extent(&self) -> f32 {
+        self.thickness + self.margin * 2.
+    }
+}
+
+impl Default for ScrollBarStyle {
+    fn default() -> Self {
+        if cfg!(target_os = "windows") {
+            Self::gutter()
+        } else {
+            Self::overlay()
+        }
+    }
 }
 
 #[derive(Clone, PartialEq)]
@@ -30,6 +185,8 @@ pub struct State {
     pub(crate) fraction_y: f64,
     pub(crate) progress_x: f64,
     pub(crate) progress_y: f64,
+    pub(crate) x: f64,
+    pub(crate) y: f64,
     pub(crate) width: f64,
     pub(crate) height: f64,
     pub(crate) content_width: f64,
@@ -37,6 +194,28 @@ pub struct State {
     pub(crate) overflow_x: bool,
     pub(crate) overflow_y: bool,
     pub(crate) scroll_direction: ScrollDirection,
+    /// Offsets the area animates to after a scroll into view.
+    pub(crate) target_offset_x: Option<f64>,
+    pub(crate) target_offset_y: Option<f64>,
+    pub(crate) preserve_anchor: bool,
+    /// Widget of the content kept in place and its position in the content.
+    pub(crate) anchor: Option<(WidgetId, Vec2)>,
+    /// Space reserved for the scroll bars in the gutter mode.
+    pub(crate) gutter: Option<f32>,
+    pub(crate) gutter_x: bool,
+    pub(crate) gutter_y: bool,
+    /// Position the observers read in this build.
+    pub(crate) observed: ScrollObserver,
+    /// Scroll delta the edges have stopped, positive past the start of the content and
+    /// decaying over time, drives the [`OverscrollIndicator`].
+    pub(crate) overscroll_x: f64,
+    pub(crate) overscroll_y: f64,
+    /// The content follows the drags of the mouse too, the touches always drag it.
+    pub(crate) drag_to_scroll: bool,
+    /// Pointer position of the last frame in logical pixels while the content is dragged.
+    pub(crate) drag_pointer: Option<Vec2>,
+    /// The left button was held in the last frame, a drag starts only with a press.
+    pub(crate) drag_pressed: bool,
 }
 
 #[derive(Clone, PartialEq)]
@@ -48,6 +227,8 @@ pub struct ScrollAreaResponse {
     pub fraction_y: f64,
     pub progress_x: f64,
     pub progress_y: f64,
+    pub x: f64,
+    pub y: f64,
     pub width: f64,
     pub height: f64,
     pub content_width: f64,
@@ -56,6 +237,97 @@ pub struct ScrollAreaResponse {
     pub overflow_y: bool,
 }
 
+/// Scroll position of a scroll area to drive other widgets with, e.g. a header that
+/// collapses as the content scrolls up. It's the position at the end of the previous
+/// frame, taken before the build, so every widget reads the same one wherever it's
+/// built, before the scroll area or inside of it. See [`ScrollAreaBuilder::observer`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScrollObserver {
+    id: WidgetId,
+    offset_x: f64,
+    offset_y: f64,
+    progress_x: f64,
+    progress_y: f64,
+    velocity_x: f64,
+    velocity_y: f64,
+}
+
+impl ScrollObserver {
+    pub(crate) fn new(id: WidgetId) -> Self {
+        Self {
+            id,
+            offset_x: 0.,
+            offset_y: 0.,
+            progress_x: 0.,
+            progress_y: 0.,
+            velocity_x: 0.,
+            velocity_y: 0.,
+        }
+    }
+
+    pub fn id(&self) -> WidgetId {
+        self.id
+    }
+
+    /// How far the content is scrolled to the left, in logical pixels.
+    pub fn offset_x(&self) -> f64 {
+        self.offset_x
+    }
+
+    /// How far the content is scrolled up, in logical pixels.
+    pub fn offset_y(&self) -> f64 {
+        self.offset_y
+    }
+
+    /// From 0 at the start of the content to 1 at its end.
+    pub fn progress_x(&self) -> f64 {
+        self.progress_x
+    }
+
+    /// From 0 at the top of the content to 1 at its bottom.
+    pub fn progress_y(&self) -> f64 {
+        self.progress_y
+    }
+
+    /// Change of [`ScrollObserver::offset_x`] over the last frame, in logical pixels per
+    /// second.
+    pub fn velocity_x(&self) -> f64 {
+        self.velocity_x
+    }
+
+    /// Change of [`ScrollObserver::offset_y`] over the last frame, in logical pixels per
+    /// second.
+    pub fn velocity_y(&self) -> f64 {
+        self.velocity_y
+    }
+}
+
+/// Where the vertical offset of the observed scroll area is in the `range`, from 0 at
+/// its start to 1 at its end and clamped, through the `curve`. E.g. the opacity of a
+/// header fading out over the first 120 pixels of scrolling:
+///
+/// ```ignore
+/// let fade = scroll_effect(&observer, 0.0..120.0, curves::f32::ease_out_cubic);
+/// header().opacity(1. - fade).build(ctx);
+/// ```
+pub fn scroll_effect(
+    observer: &ScrollObserver,
+    range: Range<f32>,
+    curve: impl Fn(f32) -> f32,
+) -> f32 {
+    let length = range.end - range.start;
+
+    if length <= 0. {
+        return if observer.offset_y as f32 >= range.end {
+            curve(1.)
+        } else {
+            curve(0.)
+        };
+    }
+
+    curve(((observer.offset_y as f32 - range.start) / length).clamp(0., 1.))
+}
+
 impl WidgetState for State {
     #[inline]
     fn as_any(&self) -> &dyn Any {
@@ -71,49 +343,186 @@ impl WidgetState for State {
     fn into_any(self: Box<Self>) -> Box<dyn Any> {
         self
     }
+
+    #[inline]
+    fn as_serializable(&self) -> Option<&dyn SerializableWidgetState> {
+        Some(self)
+    }
+
+    #[inline]
+    fn as_serializable_mut(&mut self) -> Option<&mut dyn SerializableWidgetState> {
+        Some(self)
+    }
+}
+
+impl SerializableWidgetState for State {
+    fn state_kind(&self) -> &'static str {
+        "clew::scroll_area"
+    }
+
+    fn save_state(&self) -> StateValue {
+        StateValue::List(vec![
+            StateValue::Float(self.offset_x),
+            StateValue::Float(self.offset_y),
+        ])
+    }
+
+    fn restore_state(&mut self, value: &StateValue) -> bool {
+        let Some([offset_x, offset_y]) = value.as_list() else {
+            return false;
+        };
+        let (Some(offset_x), Some(offset_y)) = (offset_x.as_f64(), offset_y.as_f64()) else {
+            return false;
+        };
+
+        // Clamped to the content size by the next interaction pass.
+        self.offset_x = offset_x;
+        self.offset_y = offset_y;
+
+        true
+    }
 }
 
-impl ScrollAreaBuilder {
+impl<'a> ScrollAreaBuilder<'a> {
     pub fn scroll_direction(mut self, scroll_direction: ScrollDirection) -> Self {
         self.scroll_direction = scroll_direction;
 
         self
     }
 
+    /// Children above the scrollable region that stay in place, the viewport and the
+    /// response only cover what is between the header and the footer.
+    pub fn fixed_header(mut self, header: impl FnOnce(&mut BuildContext) + 'a) -> Self {
+        self.fixed_header = Some(Box::new(header));
+
+        self
+    }
+
+    /// Children below the scrollable region that stay in place.
+    pub fn fixed_footer(mut self, footer: impl FnOnce(&mut BuildContext) + 'a) -> Self {
+        self.fixed_footer = Some(Box::new(footer));
+
+        self
+    }
+
+    /// Draws a shadow of `color` fading out under the fixed header while the content is
+    /// scrolled beneath it.
+    pub fn header_shadow(mut self, color: ColorRgba) -> Self {
+        self.header_shadow = Some(color);
+
+        self
+    }
+
+    /// Keeps the first widget fully visible in the viewport in place when the content
+    /// around it changes size, e.g. a section above it is collapsed. The widgets of the
+    /// content need distinct ids for the anchor to be found again.
+    pub fn preserve_anchor(mut self, value: bool) -> Self {
+        self.preserve_anchor = value;
+
+        self
+    }
+
+    /// The scroll area is shown with scroll bars, in the [`ScrollBarMode::Gutter`] mode
+    /// the space of a bar is taken from the content while the content overflows along
+    /// the axis of the bar.
+    pub fn scroll_bars(mut self, value: bool) -> Self {
+        self.scroll_bars = value;
+
+        self
+    }
+
+    /// Position of the scroll area as it was at the end of the previous frame, to be read
+    /// anywhere in the build, e.g. by the widgets built before the scroll area. The
+    /// builder has to be built in the same scope, so the ids match.
+    pub fn observer(&self, context: &BuildContext) -> ScrollObserver {
+        observer(context, self.frame.id.resolve(context.id_seed))
+    }
+
+    /// Style of the scroll bars used instead of [`ScrollBarStyle::of`], the bars have
+    /// to be built with the same style.
+    pub fn scroll_bar_style(mut self, style: ScrollBarStyle) -> Self {
+        self.scroll_bar_style = Some(style);
+
+        self
+    }
+
+    /// Shows the scrolling past the edges of the content, the areas that don't overflow
+    /// along an axis show nothing.
+    pub fn overscroll_indicator(mut self, indicator: OverscrollIndicator) -> Self {
+        self.overscroll_indicator = indicator;
+
+        self
+    }
+
+    /// The content follows the pointer while it's dragged with the left mouse button,
+    /// the touches drag it either way. Off by default, the mouse selects and drags the
+    /// widgets of the content.
+    pub fn drag_to_scroll(mut self, value: bool) -> Self {
+        self.drag_to_scroll = value;
+
+        self
+    }
+
     #[profiling::function]
     pub fn build<F>(mut self, context: &mut BuildContext, callback: F) -> ScrollAreaResponse
     where
         F: FnOnce(&mut BuildContext),
     {
-        let id = self.frame.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.frame.id);
         let widget_ref = WidgetRef::new(WidgetType::of::<ScrollAreaWidget>(), id);
 
-        let (mut backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
-        backgrounds.push(widget_ref);
+        let (mut backgrounds, mut foregrounds) = context.resolve_decorators(&mut self.frame);
+        let scroll_bar_style = self
+            .scroll_bar_style
+            .unwrap_or_else(|| ScrollBarStyle::of(context));
+        let gutter = (self.scroll_bars && scroll_bar_style.mode == ScrollBarMode::Gutter)
+            .then(|| scroll_bar_style.extent());
+        let animation_delta_time = context.animation_delta_time();
 
-        let (offset_x, offset_y, response) = {
-            let state = context
-                .widgets_states
-                .scroll_area
-                .get_or_insert(id, || State {
-                    last_offset_x: 0.,
-                    last_offset_y: 0.,
-                    offset_x: 0.,
-                    offset_y: 0.,
-                    overflow_x: false,
-                    overflow_y: false,
-                    scroll_direction: self.scroll_direction,
-                    fraction_x: 0.,
-                    fraction_y: 0.,
-                    progress_x: 0.,
-                    progress_y: 0.,
-                    width: 0.,
-                    height: 0.,
-                    content_width: 0.,
-                    content_height: 0.,
-                });
+        let (observed_behind, offset_x, offset_y, overscroll, response) = {
+            let state = context.widgets_states.scroll_area.access(id, || State {
+                last_offset_x: 0.,
+                last_offset_y: 0.,
+                offset_x: 0.,
+                offset_y: 0.,
+                overflow_x: false,
+                overflow_y: false,
+                scroll_direction: self.scroll_direction,
+                fraction_x: 0.,
+                fraction_y: 0.,
+                progress_x: 0.,
+                progress_y: 0.,
+                x: 0.,
+                y: 0.,
+                width: 0.,
+                height: 0.,
+                content_width: 0.,
+                content_height: 0.,
+                target_offset_x: None,
+                target_offset_y: None,
+                preserve_anchor: self.preserve_anchor,
+                anchor: None,
+                gutter,
+                gutter_x: false,
+                gutter_y: false,
+                observed: ScrollObserver::new(id),
+                overscroll_x: 0.,
+                overscroll_y: 0.,
+                drag_to_scroll: self.drag_to_scroll,
+                drag_pointer: None,
+                drag_pressed: false,
+            });
+
+            context.widgets_states.snapshots.track(id, state);
+            animate_to_target(state, animation_delta_time);
+            decay_overscroll(state, animation_delta_time);
+
+            state.drag_to_scroll = self.drag_to_scroll;
 
             let layout_measures = context.widgets_states.layout_measures.get_mut(id);
+            let viewport = layout_measures.as_ref().map_or(Rect::ZERO, |measure| {
+                Rect::new(measure.x, measure.y, measure.width, measure.height)
+            });
 
             if let Some(layout_measures) = layout_measures {
                 handle_interaction(
@@ -121,6 +530,7 @@ impl ScrollAreaBuilder {
                     state,
                     context.input,
                     context.interaction,
+                    context.view.scale_factor,
                     layout_measures,
                     layout_measures.wrap_width as f64,
                     layout_measures.wrap_height as f64,
@@ -128,10 +538,35 @@ impl ScrollAreaBuilder {
             }
 
             state.scroll_direction = self.scroll_direction;
+            state.preserve_anchor = self.preserve_anchor;
+
+            if !self.preserve_anchor {
+                state.anchor = None;
+            }
+
+            state.gutter = gutter;
+
+            if gutter.is_none() {
+                state.gutter_x = false;
+                state.gutter_y = false;
+            }
+
+            if self.overscroll_indicator == OverscrollIndicator::None {
+                state.overscroll_x = 0.;
+                state.overscroll_y = 0.;
+            }
+
+            let overscroll = Overscroll {
+                x: (state.overscroll_x / OVERSCROLL_MAX) as f32,
+                y: (state.overscroll_y / OVERSCROLL_MAX) as f32,
+                viewport,
+            };
 
             (
+                is_observed_behind(state),
                 state.offset_x,
                 state.offset_y,
+                overscroll,
                 ScrollAreaResponse {
                     id,
                     offset_x: state.offset_x,
@@ -142,6 +577,8 @@ impl ScrollAreaBuilder {
                     fraction_y: state.fraction_y,
                     progress_x: state.progress_x,
                     progress_y: state.progress_y,
+                    x: state.x,
+                    y: state.y,
                     width: state.width,
                     height: state.height,
                     content_width: state.content_width,
@@ -150,47 +587,140 @@ impl ScrollAreaBuilder {
             )
         };
 
-        context.push_layout_command(LayoutCommand::BeginContainer {
-            backgrounds,
-            foregrounds,
-            zindex: self.frame.zindex,
-            padding: self.frame.padding,
-            margin: self.frame.margin,
-            kind: ContainerKind::Measure { id },
-            size: self.frame.size,
-            constraints: self.frame.constraints,
-            clip: self.frame.clip,
-        });
+        // The observers read the offsets of the previous frame, they catch up in the next
+        // one. The overscroll fades out over the next ones.
+        if observed_behind || overscroll.is_visible() {
+            context.request_frame();
+        }
+
+        let glows = match self.overscroll_indicator {
+            OverscrollIndicator::Glow { color } => overscroll.glows(context, id, color),
+            _ => SmallVec::new(),
+        };
+        let stretch = match self.overscroll_indicator {
+            OverscrollIndicator::Stretch { max_factor } => overscroll.stretch(max_factor),
+            _ => None,
+        };
+
+        let has_slots = self.fixed_header.is_some() || self.fixed_footer.is_some();
+
+        if has_slots {
+            // The frame belongs to a column of the header, the scrollable region and
+            // the footer, only the region in the middle is measured.
+            context.push_layout_command(LayoutCommand::BeginContainer {
+                backgrounds,
+                foregrounds,
+                zindex: self.frame.zindex,
+                padding: self.frame.padding,
+                margin: self.frame.margin,
+                kind: ContainerKind::VStack {
+                    spacing: 0.,
+                    main_axis_alignment: MainAxisAlignment::Start,
+                    cross_axis_alignment: CrossAxisAlignment::Stretch,
+                    rtl_aware: false,
+                },
+                size: self.frame.size,
+                constraints: self.frame.constraints,
+                clip: self.frame.clip,
+            });
+
+            if let Some(header) = self.fixed_header.take() {
+                header(context);
+            }
 
-        context.push_layout_command(LayoutCommand::BeginOffset {
+            let mut foregrounds = SmallVec::new();
+
+            if let Some(color) = self.header_shadow
+                && offset_y < 0.
+            {
+                let mut shadow = decoration()
+                    .add_linear_gradient(LinearGradient::vertical((color, color.with_opacity(0.))))
+                    .size(Size::new(
+                        SizeConstraint::Wrap,
+                        SizeConstraint::Fixed(HEADER_SHADOW_HEIGHT),
+                    ))
+                    .align(AlignX::Start, AlignY::Top);
+                shadow.id = WidgetKey::auto_with_seed((id, "header_shadow"));
+
+                foregrounds.push(shadow.build(context));
+            }
+
+            foregrounds.extend(glows);
+
+            context.push_layout_command(LayoutCommand::BeginContainer {
+                backgrounds: smallvec![widget_ref],
+                foregrounds,
+                zindex: self.frame.zindex,
+                padding: EdgeInsets::ZERO,
+                margin: EdgeInsets::ZERO,
+                kind: ContainerKind::Measure { id },
+                size: Size::fill(),
+                constraints: Constraints::default(),
+                // The content never goes over the header and the footer.
+                clip: Clip::Rect,
+            });
+        } else {
+            backgrounds.push(widget_ref);
+            foregrounds.extend(glows);
+
+            context.push_layout_command(LayoutCommand::BeginContainer {
+                backgrounds,
+                foregrounds,
+                zindex: self.frame.zindex,
+                padding: self.frame.padding,
+                margin: self.frame.margin,
+                kind: ContainerKind::Measure { id },
+                size: self.frame.size,
+                constraints: self.frame.constraints,
+                clip: self.frame.clip,
+            });
+        }
+
+        if let Some(transform) = stretch {
+            context.push_layout_command(LayoutCommand::BeginRenderTransform { transform });
+        }
+
+        context.push_layout_command(LayoutCommand::BeginScrollOffset {
+            id,
             offset_x: offset_x as f32,
             offset_y: offset_y as f32,
         });
         context.provide(response.clone(), callback);
         context.push_layout_command(LayoutCommand::EndOffset);
 
+        if stretch.is_some() {
+            context.push_layout_command(LayoutCommand::EndRenderTransform);
+        }
+
         context.push_layout_command(LayoutCommand::EndContainer);
 
-        context
-            .widgets_states
-            .scroll_area
-            .accessed_this_frame
-            .insert(id);
-        context
-            .widgets_states
-            .layout_measures
-            .accessed_this_frame
-            .insert(id);
+        if has_slots {
+            if let Some(footer) = self.fixed_footer.take() {
+                footer(context);
+            }
+
+            context.push_layout_command(LayoutCommand::EndContainer);
+        }
+
+        context.widgets_states.layout_measures.mark_accessed(id);
 
         response
     }
 }
 
 #[track_caller]
-pub fn scroll_area() -> ScrollAreaBuilder {
+pub fn scroll_area<'a>() -> ScrollAreaBuilder<'a> {
     ScrollAreaBuilder {
         frame: FrameBuilder::new(),
         scroll_direction: ScrollDirection::Vertical,
+        fixed_header: None,
+        fixed_footer: None,
+        header_shadow: None,
+        preserve_anchor: false,
+        scroll_bars: false,
+        scroll_bar_style: None,
+        overscroll_indicator: OverscrollIndicator::None,
+        drag_to_scroll: false,
     }
 }
 
@@ -199,6 +729,7 @@ pub fn set_scroll_offset_x(context: &mut BuildContext, id: WidgetId, value: f64)
 
     if let Some(state) = state {
         state.offset_x = -value;
+        state.target_offset_x = None;
     }
 }
 
@@ -207,6 +738,7 @@ pub fn set_scroll_offset_y(context: &mut BuildContext, id: WidgetId, value: f64)
 
     if let Some(state) = state {
         state.offset_y = -value;
+        state.target_offset_y = None;
     }
 }
 
@@ -215,6 +747,7 @@ pub fn set_scroll_progress_x(context: &mut BuildContext, id: WidgetId, value: f6
 
     if let Some(state) = state {
         state.offset_x = -(state.content_width - state.width) * value;
+        state.target_offset_x = None;
     }
 }
 
@@ -223,29 +756,57 @@ pub fn set_scroll_progress_y(context: &mut BuildContext, id: WidgetId, value: f6
 
     if let Some(state) = state {
         state.offset_y = -(state.content_height - state.height) * value;
+        state.target_offset_y = None;
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn handle_interaction(
     id: WidgetId,
     widget_state: &mut State,
     input: &UserInput,
     interaction_state: &InteractionState,
+    scale_factor: f32,
     layout_measure: &LayoutMeasure,
     wrap_width: f64,
     wrap_height: f64,
 ) {
+    widget_state.x = layout_measure.x as f64;
+    widget_state.y = layout_measure.y as f64;
+
+    let hovered = interaction_state.is_hover(&id);
+    let viewport = Rect::new(
+        layout_measure.x,
+        layout_measure.y,
+        layout_measure.width,
+        layout_measure.height,
+    );
+    let drag = drag_delta(widget_state, input, hovered, viewport, scale_factor);
+
     if widget_state.scroll_direction == ScrollDirection::Vertical
         || widget_state.scroll_direction == ScrollDirection::Both
     {
-        if input.mouse_wheel_delta_y != 0. && interaction_state.is_hover(&id) {
-            widget_state.offset_y += input.mouse_wheel_delta_y as f64;
+        let min_offset_y = f64::min(0., -(wrap_height - layout_measure.height as f64));
+
+        let wheel = if hovered {
+            input.mouse_wheel_delta_y
+        } else {
+            0.
+        };
+        let delta = wheel + drag.y as f64;
+
+        if delta != 0. {
+            widget_state.overscroll_y = add_overscroll(
+                widget_state.overscroll_y,
+                widget_state.offset_y,
+                delta,
+                min_offset_y,
+            );
+            widget_state.offset_y += delta;
+            widget_state.target_offset_y = None;
         }
 
-        widget_state.offset_y = widget_state.offset_y.clamp(
-            f64::min(0., -(wrap_height - layout_measure.height as f64)),
-            0.,
-        );
+        widget_state.offset_y = widget_state.offset_y.clamp(min_offset_y, 0.);
 
         widget_state.overflow_y = layout_measure.height as f64 - wrap_height <= 0.;
         widget_state.fraction_y = layout_measure.height as f64 / wrap_height;
@@ -259,14 +820,27 @@ pub fn handle_interaction(
     if widget_state.scroll_direction == ScrollDirection::Horizontal
         || widget_state.scroll_direction == ScrollDirection::Both
     {
-        if input.mouse_wheel_delta_x != 0. && interaction_state.is_hover(&id) {
-            widget_state.offset_x += input.mouse_wheel_delta_x as f64;
+        let min_offset_x = f64::min(0., -(wrap_width - layout_measure.width as f64));
+
+        let wheel = if hovered {
+            input.mouse_wheel_delta_x
+        } else {
+            0.
+        };
+        let delta = wheel + drag.x as f64;
+
+        if delta != 0. {
+            widget_state.overscroll_x = add_overscroll(
+                widget_state.overscroll_x,
+                widget_state.offset_x,
+                delta,
+                min_offset_x,
+            );
+            widget_state.offset_x += delta;
+            widget_state.target_offset_x = None;
         }
 
-        widget_state.offset_x = widget_state.offset_x.clamp(
-            f64::min(0., -(wrap_width - layout_measure.width as f64)),
-            0.,
-        );
+        widget_state.offset_x = widget_state.offset_x.clamp(min_offset_x, 0.);
 
         widget_state.overflow_x = layout_measure.width as f64 - wrap_width <= 0.;
         widget_state.fraction_x = layout_measure.width as f64 / wrap_width;
@@ -277,3 +851,1446 @@ pub fn handle_interaction(
         widget_state.progress_x = widget_state.progress_x.clamp(0., 1.);
     }
 }
+
+/// Movement of the pointer since the last frame while it drags the content. A drag
+/// starts with a touch of the area, or a press of the left button over it when the area
+/// scrolls with the mouse drags, and ends with the release.
+fn drag_delta(
+    state: &mut State,
+    input: &UserInput,
+    hovered: bool,
+    viewport: Rect,
+    scale_factor: f32,
+) -> Vec2 {
+    let pressed = input.mouse_left_pressed;
+    let was_pressed = std::mem::replace(&mut state.drag_pressed, pressed);
+    let last = state.drag_pointer.take();
+
+    // The pointer is at -1 once it has left the view.
+    if input.mouse_x < 0. || input.mouse_y < 0. {
+        return Vec2::ZERO;
+    }
+
+    let pointer = Vec2::new(input.mouse_x, input.mouse_y) / scale_factor;
+
+    if pressed && !was_pressed {
+        // The hover is of the pointer of the last frame, a finger lands without one.
+        let starts = if input.touch {
+            point_with_rect_hit_test(pointer, viewport)
+        } else {
+            hovered && state.drag_to_scroll
+        };
+
+        if starts {
+            state.drag_pointer = Some(pointer);
+        }
+
+        return Vec2::ZERO;
+    }
+
+    let Some(last) = last else {
+        return Vec2::ZERO;
+    };
+
+    if pressed {
+        state.drag_pointer = Some(pointer);
+    }
+
+    pointer - last
+}
+
+/// Adds the part of the scroll delta the edges of the content stop to the overscroll.
+/// The content that fits doesn't scroll, so it doesn't overscroll either.
+fn add_overscroll(overscroll: f64, offset: f64, delta: f64, min_offset: f64) -> f64 {
+    if min_offset >= 0. {
+        return 0.;
+    }
+
+    let offset = offset.clamp(min_offset, 0.);
+    let consumed = (offset + delta).clamp(min_offset, 0.) - offset;
+
+    (overscroll + delta - consumed).clamp(-OVERSCROLL_MAX, OVERSCROLL_MAX)
+}
+
+fn decay_overscroll(state: &mut State, delta_time: f32) {
+    // The content stays stretched while it's held past the edge.
+    if state.drag_pointer.is_some() {
+        return;
+    }
+
+    let decay = (-OVERSCROLL_DECAY * delta_time as f64).exp();
+
+    for overscroll in [&mut state.overscroll_x, &mut state.overscroll_y] {
+        *overscroll *= decay;
+
+        // Below a pixel the indicator isn't visible anymore.
+        if overscroll.abs() < 1. {
+            *overscroll = 0.;
+        }
+    }
+}
+
+/// Intensity of the overscroll at each axis, from -1 past the end to 1 past the start,
+/// and the viewport of the previous frame it's shown in.
+#[derive(Clone, Copy)]
+struct Overscroll {
+    x: f32,
+    y: f32,
+    viewport: Rect,
+}
+
+impl Overscroll {
+    fn is_visible(&self) -> bool {
+        self.x != 0. || self.y != 0.
+    }
+
+    /// Foreground decorations of the measured container at the overscrolled edges.
+    fn glows(
+        &self,
+        context: &mut BuildContext,
+        id: WidgetId,
+        color: ColorRgba,
+    ) -> SmallVec<[WidgetRef; 8]> {
+        let mut glows = SmallVec::new();
+        let transparent = color.with_opacity(0.);
+
+        if self.y != 0. {
+            let color = color.with_opacity(color.a * self.y.abs());
+            let (gradient, align_y) = if self.y > 0. {
+                ((color, transparent), AlignY::Top)
+            } else {
+                ((transparent, color), AlignY::Bottom)
+            };
+
+            let mut glow = decoration()
+                .add_linear_gradient(LinearGradient::vertical(gradient))
+                .size(Size::new(
+                    SizeConstraint::Wrap,
+                    SizeConstraint::Fixed(OVERSCROLL_GLOW_EXTENT),
+                ))
+                .align(AlignX::Start, align_y);
+            glow.id = WidgetKey::auto_with_seed((id, "overscroll_glow_y"));
+
+            glows.push(glow.build(context));
+        }
+
+        if self.x != 0. {
+            let color = color.with_opacity(color.a * self.x.abs());
+            let (gradient, align_x) = if self.x > 0. {
+                ((color, transparent), AlignX::Left)
+            } else {
+                ((transparent, color), AlignX::Right)
+            };
+
+            let mut glow = decoration()
+                .add_linear_gradient(LinearGradient::horizontal(gradient))
+                .size(Size::new(
+                    SizeConstraint::Fixed(OVERSCROLL_GLOW_EXTENT),
+                    SizeConstraint::Wrap,
+                ))
+                .align(align_x, AlignY::Top);
+            glow.id = WidgetKey::auto_with_seed((id, "overscroll_glow_x"));
+
+            glows.push(glow.build(context));
+        }
+
+        glows
+    }
+
+    /// Scale of the content along the overscrolled axes from the edge opposite to the
+    /// overscrolled one, `None` while there is no overscroll.
+    fn stretch(&self, max_factor: f32) -> Option<Transform> {
+        if !self.is_visible() {
+            return None;
+        }
+
+        let viewport = self.viewport;
+        let scale = |intensity: f32| 1. + (max_factor - 1.) * intensity.abs();
+        // Past the start the end stays in place, past the end the start does.
+        let anchor = |intensity: f32, start: f32, length: f32| {
+            if intensity > 0. {
+                start + length
+            } else {
+                start
+            }
+        };
+        let scale = Vec2::new(scale(self.x), scale(self.y));
+        let anchor = Vec2::new(
+            anchor(self.x, viewport.x, viewport.width),
+            anchor(self.y, viewport.y, viewport.height),
+        );
+
+        Some(Transform::non_uniform(
+            Vec2::new(anchor.x * (1. - scale.x), anchor.y * (1. - scale.y)),
+            scale,
+        ))
+    }
+}
+
+/// Takes the positions the observers read in the next build, with the velocities since
+/// the previous ones.
+pub(crate) fn observe(states: &mut TypedWidgetStates<State>, delta_time: f32) {
+    let velocity = |offset: f64, last: f64| {
+        if delta_time > 0. {
+            (offset - last) / delta_time as f64
+        } else {
+            0.
+        }
+    };
+
+    // The offsets can have been set after the interaction has updated the progress.
+    let progress = |offset: f64, content: f64, viewport: f64| {
+        if content > viewport {
+            (offset / (content - viewport)).clamp(0., 1.)
+        } else {
+            0.
+        }
+    };
+
+    for (id, state) in states.iter_mut() {
+        let last = state.observed;
+        let (offset_x, offset_y) = (-state.offset_x, -state.offset_y);
+
+        state.observed = ScrollObserver {
+            id,
+            offset_x,
+            offset_y,
+            progress_x: progress(offset_x, state.content_width, state.width),
+            progress_y: progress(offset_y, state.content_height, state.height),
+            velocity_x: velocity(offset_x, last.offset_x),
+            velocity_y: velocity(offset_y, last.offset_y),
+        };
+    }
+}
+
+/// Observed position of the scroll area, or of the virtual list, with the id. It's at
+/// the start before the first build.
+pub(crate) fn observer(context: &BuildContext, id: WidgetId) -> ScrollObserver {
+    context
+        .widgets_states
+        .scroll_area
+        .get(id)
+        .map_or(ScrollObserver::new(id), |state| state.observed)
+}
+
+/// Whether the observed position is behind the current one, or still moving.
+pub(crate) fn is_observed_behind(state: &State) -> bool {
+    let observed = &state.observed;
+
+    -state.offset_x != observed.offset_x
+        || -state.offset_y != observed.offset_y
+        || observed.velocity_x != 0.
+        || observed.velocity_y != 0.
+}
+
+/// Looks up the anchors of the scroll areas that preserve them in the next layout.
+pub(crate) fn track_anchors(
+    states: &TypedWidgetStates<State>,
+    anchors: &mut WidgetIdMap<ScrollAnchor>,
+) {
+    anchors.clear();
+
+    for (id, state) in states.iter() {
+        if state.preserve_anchor {
+            anchors.insert(
+                id,
+                ScrollAnchor::new(state.anchor.map(|(widget, _)| widget)),
+            );
+        }
+    }
+}
+
+/// Moves the offsets so the anchors are back where they were in the content, returns
+/// whether the content has to be laid out again.
+pub(crate) fn keep_anchors(
+    states: &mut TypedWidgetStates<State>,
+    layout_measures: &TypedWidgetStates<LayoutMeasure>,
+    anchors: &mut WidgetIdMap<ScrollAnchor>,
+) -> bool {
+    let mut moved = false;
+
+    for (id, anchor) in anchors.iter_mut() {
+        let (Some(state), Some(measure)) = (states.get_mut(*id), layout_measures.get(*id)) else {
+            continue;
+        };
+        let (Some((_, position)), Some(rect)) = (state.anchor, anchor.placed) else {
+            continue;
+        };
+
+        let delta = content_position(anchor, measure, rect) - position;
+        let mut offset = anchor.applied_offset;
+
+        if scrolls_x(state) && delta.x.abs() > ANCHOR_TOLERANCE {
+            offset.x -= delta.x;
+            state.offset_x -= delta.x as f64;
+            state.target_offset_x = None;
+        }
+
+        if scrolls_y(state) && delta.y.abs() > ANCHOR_TOLERANCE {
+            offset.y -= delta.y;
+            state.offset_y -= delta.y as f64;
+            state.target_offset_y = None;
+        }
+
+        // The layout clamps the offset of the content the same way.
+        clamp_offsets(state, measure);
+
+        if offset != anchor.applied_offset {
+            anchor.offset = Some(offset);
+            moved = true;
+        }
+    }
+
+    moved
+}
+
+/// Picks the widgets kept in place in the next frame from the final layout.
+pub(crate) fn update_anchors(
+    states: &mut TypedWidgetStates<State>,
+    layout_measures: &TypedWidgetStates<LayoutMeasure>,
+    anchors: &WidgetIdMap<ScrollAnchor>,
+) {
+    for (id, anchor) in anchors {
+        let (Some(state), Some(measure)) = (states.get_mut(*id), layout_measures.get(*id)) else {
+            continue;
+        };
+
+        state.anchor = anchor
+            .first_visible
+            .map(|(widget, rect)| (widget, content_position(anchor, measure, rect)));
+    }
+}
+
+/// Looks up the gutters the scroll areas reserve in the next layout.
+pub(crate) fn track_gutters(
+    states: &TypedWidgetStates<State>,
+    gutters: &mut WidgetIdMap<ScrollGutter>,
+) {
+    gutters.clear();
+
+    for (id, state) in states.iter() {
+        if let Some(size) = state.gutter {
+            gutters.insert(
+                id,
+                ScrollGutter {
+                    size,
+                    x: state.gutter_x,
+                    y: state.gutter_y,
+                },
+            );
+        }
+    }
+}
+
+/// Reserves the gutters along the axes the content overflows and frees the others,
+/// returns whether the content has to be laid out again.
+///
+/// Taking a gutter can make the content fit and freeing it can make the content
+/// overflow again, so the first call after a layout moves the gutters both ways and
+/// the second one only reserves them. A gutter kept that way stays until the next
+/// frame rather than have the layout flip between the two.
+pub(crate) fn settle_gutters(
+    states: &mut TypedWidgetStates<State>,
+    layout_measures: &TypedWidgetStates<LayoutMeasure>,
+    gutters: &mut WidgetIdMap<ScrollGutter>,
+    free: bool,
+) -> bool {
+    let mut changed = false;
+
+    for (id, gutter) in gutters.iter_mut() {
+        let (Some(state), Some(measure)) = (states.get_mut(*id), layout_measures.get(*id)) else {
+            continue;
+        };
+
+        // The wrap size includes the gutters, the content overflows once it and the
+        // space of the other bar don't fit.
+        let overflow_x =
+            scrolls_x(state) && measure.wrap_width > measure.width + OVERFLOW_TOLERANCE;
+        let overflow_y =
+            scrolls_y(state) && measure.wrap_height > measure.height + OVERFLOW_TOLERANCE;
+        let settled = ScrollGutter {
+            size: gutter.size,
+            x: overflow_x || (gutter.x && !free),
+            y: overflow_y || (gutter.y && !free),
+        };
+
+        if settled != *gutter {
+            *gutter = settled;
+            state.gutter_x = settled.x;
+            state.gutter_y = settled.y;
+            changed = true;
+        }
+    }
+
+    changed
+}
+
+/// Position of the placed rect relative to the scrolled content.
+fn content_position(anchor: &ScrollAnchor, measure: &LayoutMeasure, rect: Rect) -> Vec2 {
+    rect.position() - Vec2::new(measure.x, measure.y) - anchor.applied_offset
+}
+
+fn scrolls_x(state: &State) -> bool {
+    state.scroll_direction == ScrollDirection::Horizontal
+        || state.scroll_direction == ScrollDirection::Both
+}
+
+fn scrolls_y(state: &State) -> bool {
+    state.scroll_direction == ScrollDirection::Vertical
+        || state.scroll_direction == ScrollDirection::Both
+}
+
+/// Clamps the offsets the same way the layout does.
+fn clamp_offsets(state: &mut State, measure: &LayoutMeasure) {
+    let min_x = f32::min(0., -(measure.wrap_width - measure.width));
+    let min_y = f32::min(0., -(measure.wrap_height - measure.height));
+
+    state.offset_x = state.offset_x.clamp(min_x as f64, 0.);
+    state.offset_y = state.offset_y.clamp(min_y as f64, 0.);
+}
+
+/// Scrolls to the widget requested to be scrolled into view from the next build, it's
+/// dropped if it hasn't been placed. Runs after the other callbacks of
+/// [`crate::phases`] have moved their content, and the pending callback makes
+/// [`crate::ClewInstance::needs_build`] build the next frame.
+pub(crate) fn scroll_into_view_after_layout(
+    layout: &LayoutAccess,
+    mutator: &mut RenderMutator,
+    obscured_insets: EdgeInsets,
+) {
+    let view = layout.view();
+    let size = view.size.to_vec2() / view.scale_factor;
+    // The part of the view covered by the platform is as good as outside of it.
+    let visible = Rect::new(
+        obscured_insets.left,
+        obscured_insets.top,
+        size.x - obscured_insets.horizontal(),
+        size.y - obscured_insets.vertical(),
+    );
+
+    if let Some(request) = layout.scroll_into_view.cloned() {
+        mutator.before_next_build(move |context| {
+            scroll_into_view(
+                &mut context.widgets_states.scroll_area,
+                &context.widgets_states.layout_measures,
+                &request,
+                visible,
+            );
+        });
+    }
+}
+
+/// Sets the offsets the enclosing scroll areas animate to, so the placed widget and a
+/// margin around it become visible with the least movement. The innermost area moves
+/// first, then every outer one brings in where the widget ends up. The parts of the
+/// areas outside of the visible rect of the view don't count as visible.
+pub(crate) fn scroll_into_view(
+    states: &mut TypedWidgetStates<State>,
+    layout_measures: &TypedWidgetStates<LayoutMeasure>,
+    request: &ScrollIntoView,
+    visible: Rect,
+) {
+    let mut rect = request.rect;
+
+    for id in request.scroll_areas.iter().rev() {
+        let (Some(state), Some(measure)) = (states.get_mut(*id), layout_measures.get(*id)) else {
+            continue;
+        };
+
+        if state.scroll_direction == ScrollDirection::Vertical
+            || state.scroll_direction == ScrollDirection::Both
+        {
+            let (view_start, view_end) = visible_range(
+                measure.y,
+                measure.y + measure.height,
+                visible.top(),
+                visible.bottom(),
+            );
+            let delta = scroll_delta(
+                view_start,
+                view_end,
+                rect.top() - SCROLL_INTO_VIEW_MARGIN,
+                rect.bottom() + SCROLL_INTO_VIEW_MARGIN,
+            );
+            let min = f64::min(0., -(state.content_height - measure.height as f64));
+            let target = (state.offset_y + delta as f64).clamp(min, 0.);
+
+            rect.y += (target - state.offset_y) as f32;
+            state.target_offset_y = Some(target);
+        }
+
+        if state.scroll_direction == ScrollDirection::Horizontal
+            || state.scroll_direction == ScrollDirection::Both
+        {
+            let (view_start, view_end) = visible_range(
+                measure.x,
+                measure.x + measure.width,
+                visible.left(),
+                visible.right(),
+            );
+            let delta = scroll_delta(
+                view_start,
+                view_end,
+                rect.left() - SCROLL_INTO_VIEW_MARGIN,
+                rect.right() + SCROLL_INTO_VIEW_MARGIN,
+            );
+            let min = f64::min(0., -(state.content_width - measure.width as f64));
+            let target = (state.offset_x + delta as f64).clamp(min, 0.);
+
+            rect.x += (target - state.offset_x) as f32;
+            state.target_offset_x = Some(target);
+        }
+    }
+}
+
+/// Part of the area along an axis inside of the visible part of the view, the whole
+/// area if none of it is.
+fn visible_range(start: f32, end: f32, visible_start: f32, visible_end: f32) -> (f32, f32) {
+    let (clipped_start, clipped_end) = (start.max(visible_start), end.min(visible_end));
+
+    if clipped_start < clipped_end {
+        (clipped_start, clipped_end)
+    } else {
+        (start, end)
+    }
+}
+
+/// How far the content has to move along an axis so `start..end` is inside
+/// `view_start..view_end`, the start wins when the range doesn't fit.
+fn scroll_delta(view_start: f32, view_end: f32, start: f32, end: f32) -> f32 {
+    if start < view_start || end - start > view_end - view_start {
+        view_start - start
+    } else if end > view_end {
+        view_end - end
+    } else {
+        0.
+    }
+}
+
+/// Moves the offsets toward the scroll into view targets.
+pub(crate) fn animate_to_target(state: &mut State, delta_time: f32) {
+    let step = 1. - (-SCROLL_INTO_VIEW_SPEED * delta_time as f64).exp();
+
+    approach_target(&mut state.offset_x, &mut state.target_offset_x, step);
+    approach_target(&mut state.offset_y, &mut state.target_offset_y, step);
+}
+
+fn approach_target(offset: &mut f64, target: &mut Option<f64>, step: f64) {
+    let Some(value) = *target else {
+        return;
+    };
+
+    *offset += (value - *offset) * step;
+
+    if (value - *offset).abs() < 0.5 {
+        *offset = value;
+        *target = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ops::Range;
+
+    use crate::{
+        ClewHost, ClewInstance, View,
+        io::{InputEvent, MouseButton, TouchPhase},
+        layout::LayoutItem,
+        prelude::*,
+        render::{Fill, RenderCommand},
+        testing,
+        text::FontResources,
+        widgets::{
+            gesture_detector::{self, gesture_detector},
+            hstack, vstack, zstack,
+        },
+    };
+
+    use super::*;
+
+    /// Rows of 20 in an area filling the view of 200, a row is told apart by its width.
+    fn build_rows<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        rows: Range<usize>,
+        preserve_anchor: bool,
+        scroll_to: Option<f64>,
+    ) {
+        instance.frame(host, &mut |ctx| {
+            let response = scroll_area()
+                .fill_max_size()
+                .preserve_anchor(preserve_anchor)
+                .build(ctx, |ctx| {
+                    vstack().spacing(0.).build(ctx, |ctx| {
+                        for i in rows.clone() {
+                            gesture_detector().id(i).build(ctx, |ctx| {
+                                zstack()
+                                    .width(100. + i as f32)
+                                    .height(20.)
+                                    .build(ctx, |_| {})
+                            });
+                        }
+                    });
+                });
+
+            if let Some(offset) = scroll_to {
+                set_scroll_offset_y(ctx, response.id, offset);
+            }
+        });
+    }
+
+    fn row_y(instance: &mut ClewInstance, row: usize) -> Option<f32> {
+        instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .find_map(|item| match item {
+                LayoutItem::Placement(placement)
+                    if placement.widget_ref.widget_type
+                        == WidgetType::of::<gesture_detector::GestureDetector>()
+                        && placement.rect.width == 100. + row as f32 =>
+                {
+                    Some(placement.rect.y)
+                }
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn test_anchor_stays_in_place_when_content_above_collapses() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+
+        build_rows(&mut instance, &mut host, 0..40, true, Some(400.));
+        build_rows(&mut instance, &mut host, 0..40, true, None);
+        assert_eq!(row_y(&mut instance, 20), Some(0.));
+
+        // The rows above the viewport are gone, the row at the top stays there.
+        build_rows(&mut instance, &mut host, 10..40, true, None);
+        assert_eq!(row_y(&mut instance, 20), Some(0.));
+
+        // Without the anchor the content moves by the removed height.
+        let mut instance = ClewInstance::new(testing::view(200, 200), FontResources::new());
+
+        build_rows(&mut instance, &mut host, 0..40, false, Some(400.));
+        build_rows(&mut instance, &mut host, 0..40, false, None);
+        build_rows(&mut instance, &mut host, 10..40, false, None);
+        assert_eq!(row_y(&mut instance, 30), Some(0.));
+    }
+
+    #[test]
+    fn test_anchor_stays_in_place_when_content_below_collapses() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+
+        build_rows(&mut instance, &mut host, 0..40, true, Some(400.));
+        build_rows(&mut instance, &mut host, 0..40, true, None);
+        build_rows(&mut instance, &mut host, 0..30, true, None);
+        assert_eq!(row_y(&mut instance, 20), Some(0.));
+        assert_eq!(row_y(&mut instance, 29), Some(180.));
+    }
+
+    #[test]
+    fn test_offset_is_clamped_when_content_shrinks_to_fit() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+
+        build_rows(&mut instance, &mut host, 0..40, false, Some(400.));
+        build_rows(&mut instance, &mut host, 0..40, false, None);
+        assert_eq!(row_y(&mut instance, 0), Some(-400.));
+
+        // Clamped in the same frame, rather than showing nothing until the next one.
+        build_rows(&mut instance, &mut host, 0..3, false, None);
+        assert_eq!(row_y(&mut instance, 0), Some(0.));
+        assert_eq!(row_y(&mut instance, 2), Some(40.));
+    }
+
+    #[test]
+    fn test_observer_follows_the_offset_of_the_previous_frame() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let mut observed = Vec::new();
+
+        // The area is measured in the first frame.
+        for scroll_to in [None, Some(100.), None, None] {
+            instance.frame(&mut host, &mut |ctx| {
+                let area = scroll_area().fill_max_size();
+                let observer = area.observer(ctx);
+                let response = area.build(ctx, |ctx| {
+                    zstack().width(100.).height(400.).build(ctx, |_| {});
+                });
+
+                if let Some(offset) = scroll_to {
+                    set_scroll_offset_y(ctx, response.id, offset);
+                }
+
+                observed.push(observer);
+            });
+        }
+
+        assert_eq!(observed[1].offset_y(), 0.);
+        assert_eq!(observed[2].offset_y(), 100.);
+        assert_eq!(observed[2].progress_y(), 0.5);
+        assert!(observed[2].velocity_y() > 0.);
+        assert_eq!(observed[3].offset_y(), 100.);
+        assert_eq!(observed[3].velocity_y(), 0.);
+        assert_eq!(scroll_effect(&observed[3], 50.0..150.0, |t| t), 0.5);
+        assert_eq!(scroll_effect(&observed[3], 0.0..50.0, |t| t), 1.);
+    }
+
+    #[test]
+    fn test_scroll_into_view_is_targeted_by_the_build_it_wakes() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let mut row = None;
+
+        // The row is found in the first frame and requested in the second, the third one
+        // is built for the callback that scrolls to it.
+        for frame in 0..3 {
+            instance.frame(&mut host, &mut |ctx| {
+                if let Some(row) = row
+                    && frame == 1
+                {
+                    ctx.scroll_into_view(row);
+                }
+
+                scroll_area().fill_max_size().build(ctx, |ctx| {
+                    vstack().spacing(0.).build(ctx, |ctx| {
+                        for i in 0..40 {
+                            gesture_detector().id(i).build(ctx, |ctx| {
+                                zstack()
+                                    .width(100. + i as f32)
+                                    .height(20.)
+                                    .build(ctx, |_| {})
+                            });
+                        }
+                    });
+                });
+            });
+
+            if frame == 1 {
+                assert!(instance.needs_build(&host));
+            }
+
+            row = instance
+                .ui_state()
+                .layout_items
+                .iter()
+                .find_map(|item| match item {
+                    LayoutItem::Placement(placement) if placement.rect.width == 130. => {
+                        Some(placement.widget_ref.id)
+                    }
+                    _ => None,
+                });
+        }
+
+        // The row at 600 and the margin below it end at the bottom of the area.
+        let targets = instance
+            .ui_state()
+            .widgets_states
+            .scroll_area
+            .iter()
+            .filter_map(|(_, state)| state.target_offset_y)
+            .collect::<Vec<_>>();
+        assert_eq!(targets.len(), 1);
+        assert!(targets[0] < -420.);
+    }
+
+    #[test]
+    fn test_focused_widget_is_scrolled_out_from_under_obscured_insets() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let target_offsets = |instance: &mut ClewInstance| {
+            instance
+                .ui_state()
+                .widgets_states
+                .scroll_area
+                .iter()
+                .filter_map(|(_, state)| state.target_offset_y)
+                .collect::<Vec<_>>()
+        };
+
+        build_rows(&mut instance, &mut host, 0..40, false, None);
+
+        // The row from 140 to 160 is inside of the view.
+        let row = instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .find_map(|item| match item {
+                LayoutItem::Placement(placement) if placement.rect.width == 107. => {
+                    Some(placement.widget_ref.id)
+                }
+                _ => None,
+            });
+        instance.ui_state().interaction_state.focused = row;
+
+        // Something that doesn't cover it doesn't scroll.
+        instance.set_obscured_insets(EdgeInsets::ZERO.bottom(30.), None);
+        build_rows(&mut instance, &mut host, 0..40, false, None);
+        build_rows(&mut instance, &mut host, 0..40, false, None);
+        assert!(target_offsets(&mut instance).is_empty());
+
+        // A keyboard over the lower 80 of the view does, the row and the margin below it
+        // end where the keyboard starts.
+        instance.set_obscured_insets(EdgeInsets::ZERO.bottom(80.), None);
+        build_rows(&mut instance, &mut host, 0..40, false, None);
+        build_rows(&mut instance, &mut host, 0..40, false, None);
+        assert_eq!(
+            target_offsets(&mut instance),
+            [-(160. + SCROLL_INTO_VIEW_MARGIN as f64 - 120.)]
+        );
+    }
+
+    #[test]
+    fn test_fractional_wheel_deltas_add_up_exactly() {
+        let mut host = testing::host();
+        // At 1.5 a physical pixel is two thirds of a logical one.
+        let mut instance = ClewInstance::new(
+            View {
+                scale_factor: 1.5,
+                ..testing::view(300, 300)
+            },
+            FontResources::new(),
+        );
+        let mut offset = 0.;
+        let mut rows_y = Vec::new();
+
+        instance.push_event(InputEvent::PointerMoved { x: 15., y: 15. });
+
+        // The pointer is routed in the frame, the area is hovered from the next one.
+        for frame in 0..31 {
+            if frame > 0 {
+                instance.push_event(InputEvent::MouseWheel {
+                    delta_x: 0.,
+                    delta_y: -0.3,
+                });
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                offset = scroll_area()
+                    .fill_max_size()
+                    .build(ctx, |ctx| {
+                        vstack().spacing(0.).build(ctx, |ctx| {
+                            for i in 0..40 {
+                                gesture_detector().id(i).build(ctx, |ctx| {
+                                    zstack()
+                                        .width(100. + i as f32)
+                                        .height(20.)
+                                        .build(ctx, |_| {})
+                                });
+                            }
+                        });
+                    })
+                    .offset_y;
+            });
+
+            // Only the translation of the content is rounded, to the nearest physical
+            // pixel.
+            let y = row_y(&mut instance, 0).unwrap();
+            let physical_y = y * 1.5;
+
+            assert!((physical_y - physical_y.round()).abs() < 1e-4, "{y}");
+            assert!(
+                (y - offset as f32).abs() <= 1. / 3. + 1e-4,
+                "{y} at {offset}"
+            );
+
+            rows_y.push(y);
+        }
+
+        // Nothing of the 30 deltas is lost, and the content moves by a physical pixel
+        // every other frame or so.
+        assert!((offset + 9.).abs() < 1e-12);
+
+        rows_y.dedup();
+        assert!(rows_y.len() > 10);
+    }
+
+    #[test]
+    fn test_overscroll_stretch_is_only_drawn() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let mut offset = None;
+
+        instance.push_event(InputEvent::PointerMoved { x: 10., y: 10. });
+
+        for frame in 0..2 {
+            if frame > 0 {
+                // Past the top of the content.
+                instance.push_event(InputEvent::MouseWheel {
+                    delta_x: 0.,
+                    delta_y: 40.,
+                });
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                offset = Some(
+                    scroll_area()
+                        .fill_max_size()
+                        .overscroll_indicator(OverscrollIndicator::Stretch { max_factor: 1.1 })
+                        .build(ctx, |ctx| {
+                            vstack().spacing(0.).build(ctx, |ctx| {
+                                for i in 0..40 {
+                                    gesture_detector().id(i).build(ctx, |ctx| {
+                                        zstack()
+                                            .width(100. + i as f32)
+                                            .height(20.)
+                                            .build(ctx, |_| {})
+                                    });
+                                }
+                            });
+                        })
+                        .offset_y,
+                );
+            });
+
+            let commands = instance.ui_state().render_state.commands();
+            let stretch = commands.iter().find_map(|command| match command {
+                RenderCommand::PushTransform { transform } => Some(*transform),
+                _ => None,
+            });
+
+            if frame == 0 {
+                assert_eq!(stretch, None);
+            } else {
+                // Stretched along the scroll axis from the bottom edge of the area.
+                let stretch = stretch.unwrap();
+                assert!(stretch.scale.y > 1. && stretch.scale.y <= 1.1);
+                assert_eq!(stretch.scale.x, 1.);
+                assert_eq!(stretch.apply(Vec2::new(100., 200.)), Vec2::new(100., 200.));
+            }
+        }
+
+        // The offset and the hit-tested rows stay where they are.
+        assert_eq!(offset, Some(0.));
+        assert_eq!(row_y(&mut instance, 0), Some(0.));
+
+        let (_, state) = instance
+            .ui_state()
+            .widgets_states
+            .scroll_area
+            .iter_mut()
+            .next()
+            .unwrap();
+        assert_eq!(state.overscroll_y, 40.);
+
+        decay_overscroll(state, 1.);
+        assert_eq!(state.overscroll_y, 0.);
+    }
+
+    /// The 40 rows of 20 between a header of 30 and a footer of 20 in an area filling the
+    /// view of 200, the header and the footer are stretched to its width.
+    fn build_with_slots<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        scroll_to: Option<f64>,
+    ) -> ScrollAreaResponse {
+        let mut response = None;
+
+        instance.frame(host, &mut |ctx| {
+            let area = scroll_area()
+                .fill_max_size()
+                .fixed_header(|ctx| {
+                    gesture_detector()
+                        .id("header")
+                        .build(ctx, |ctx| zstack().height(30.).build(ctx, |_| {}));
+                })
+                .fixed_footer(|ctx| {
+                    gesture_detector()
+                        .id("footer")
+                        .build(ctx, |ctx| zstack().height(20.).build(ctx, |_| {}));
+                })
+                .header_shadow(ColorRgba::from_hex(0x40102030))
+                .build(ctx, |ctx| {
+                    vstack().spacing(0.).build(ctx, |ctx| {
+                        for i in 0..40 {
+                            gesture_detector().id(i).build(ctx, |ctx| {
+                                zstack()
+                                    .width(100. + i as f32)
+                                    .height(20.)
+                                    .build(ctx, |_| {})
+                            });
+                        }
+                    });
+                });
+
+            if let Some(offset) = scroll_to {
+                set_scroll_offset_y(ctx, area.id, offset);
+            }
+
+            response = Some(area);
+        });
+
+        response.unwrap()
+    }
+
+    #[test]
+    fn test_fixed_slots_stay_in_place_around_the_scrolled_content() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let shadows = |instance: &mut ClewInstance| -> Vec<Rect> {
+            instance
+                .ui_state()
+                .render_state
+                .commands()
+                .iter()
+                .filter_map(|command| match command {
+                    RenderCommand::Rect {
+                        boundary,
+                        fill: Some(Fill::Gradient(_)),
+                        ..
+                    } => Some(*boundary),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        build_with_slots(&mut instance, &mut host, None);
+        let response = build_with_slots(&mut instance, &mut host, Some(100.));
+        assert!(shadows(&mut instance).is_empty());
+
+        let response_scrolled = build_with_slots(&mut instance, &mut host, None);
+
+        // Only the region between the slots is measured.
+        for response in [response, response_scrolled] {
+            assert_eq!(response.y, 30.);
+            assert_eq!(response.height, 150.);
+            assert_eq!(response.content_height, 800.);
+            assert_eq!(response.fraction_y, 150. / 800.);
+        }
+
+        let slots_y = instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .filter_map(|item| match item {
+                LayoutItem::Placement(placement)
+                    if placement.widget_ref.widget_type
+                        == WidgetType::of::<gesture_detector::GestureDetector>()
+                        && placement.rect.width == 200. =>
+                {
+                    Some(placement.rect.y)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(slots_y, [0., 180.]);
+        assert_eq!(row_y(&mut instance, 0), Some(30. - 100.));
+        assert_eq!(row_y(&mut instance, 5), Some(30.));
+
+        // The content is clipped below the header and the shadow is drawn there.
+        let clips = instance
+            .ui_state()
+            .render_state
+            .commands()
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::PushClip { rect, .. } => Some(*rect),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(clips.contains(&Rect::new(0., 30., 200., 150.)));
+        assert_eq!(
+            shadows(&mut instance),
+            [Rect::new(0., 30., 200., HEADER_SHADOW_HEIGHT)]
+        );
+    }
+
+    /// The 40 rows of 20 in an area filling the view of 200 with the indicator, returns
+    /// the offset of the area.
+    fn build_overscrolled<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        indicator: OverscrollIndicator,
+    ) -> f64 {
+        let mut offset = 0.;
+
+        instance.frame(host, &mut |ctx| {
+            offset = scroll_area()
+                .fill_max_size()
+                .overscroll_indicator(indicator)
+                .build(ctx, |ctx| {
+                    vstack().spacing(0.).build(ctx, |ctx| {
+                        for _ in 0..40 {
+                            zstack().width(100.).height(20.).build(ctx, |_| {});
+                        }
+                    });
+                })
+                .offset_y;
+        });
+
+        offset
+    }
+
+    fn overscroll_y(instance: &mut ClewInstance) -> f64 {
+        let (_, state) = instance
+            .ui_state()
+            .widgets_states
+            .scroll_area
+            .iter_mut()
+            .next()
+            .unwrap();
+
+        state.overscroll_y
+    }
+
+    #[test]
+    fn test_overscroll_glow_is_drawn_at_the_overscrolled_edge() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let indicator = OverscrollIndicator::Glow {
+            color: ColorRgba::from_hex(0xFF357CCE),
+        };
+        let glows = |instance: &mut ClewInstance| -> Vec<Rect> {
+            instance
+                .ui_state()
+                .render_state
+                .commands()
+                .iter()
+                .filter_map(|command| match command {
+                    RenderCommand::Rect {
+                        boundary,
+                        fill: Some(Fill::Gradient(_)),
+                        ..
+                    } => Some(*boundary),
+                    _ => None,
+                })
+                .collect()
+        };
+
+        instance.push_event(InputEvent::PointerMoved { x: 10., y: 10. });
+        build_overscrolled(&mut instance, &mut host, indicator);
+        assert!(glows(&mut instance).is_empty());
+
+        // Past the top of the content.
+        instance.push_event(InputEvent::MouseWheel {
+            delta_x: 0.,
+            delta_y: 40.,
+        });
+        build_overscrolled(&mut instance, &mut host, indicator);
+        assert_eq!(
+            glows(&mut instance),
+            [Rect::new(0., 0., 200., OVERSCROLL_GLOW_EXTENT)]
+        );
+
+        // Past the bottom, once the top one has faded.
+        let (_, state) = instance
+            .ui_state()
+            .widgets_states
+            .scroll_area
+            .iter_mut()
+            .next()
+            .unwrap();
+        decay_overscroll(state, 1.);
+
+        instance.push_event(InputEvent::MouseWheel {
+            delta_x: 0.,
+            delta_y: -640.,
+        });
+        assert_eq!(
+            build_overscrolled(&mut instance, &mut host, indicator),
+            -600.
+        );
+        assert_eq!(
+            glows(&mut instance),
+            [Rect::new(
+                0.,
+                200. - OVERSCROLL_GLOW_EXTENT,
+                200.,
+                OVERSCROLL_GLOW_EXTENT
+            )]
+        );
+    }
+
+    #[test]
+    fn test_touch_drag_scrolls_and_overscrolls() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let indicator = OverscrollIndicator::Stretch { max_factor: 1.1 };
+        let touch = |phase, y| InputEvent::Touch {
+            id: 1,
+            phase,
+            x: 10.,
+            y,
+        };
+
+        build_overscrolled(&mut instance, &mut host, indicator);
+
+        // Up by 60 scrolls the content.
+        instance.push_event(touch(TouchPhase::Started, 100.));
+        build_overscrolled(&mut instance, &mut host, indicator);
+        instance.push_event(touch(TouchPhase::Moved, 40.));
+        assert_eq!(
+            build_overscrolled(&mut instance, &mut host, indicator),
+            -60.
+        );
+        assert_eq!(overscroll_y(&mut instance), 0.);
+
+        // Down by 100 goes past the top, the rest of the drag overscrolls.
+        instance.push_event(touch(TouchPhase::Moved, 140.));
+        assert_eq!(build_overscrolled(&mut instance, &mut host, indicator), 0.);
+        assert_eq!(overscroll_y(&mut instance), 40.);
+
+        // Another finger doesn't drag.
+        instance.push_event(InputEvent::Touch {
+            id: 2,
+            phase: TouchPhase::Moved,
+            x: 10.,
+            y: 10.,
+        });
+        build_overscrolled(&mut instance, &mut host, indicator);
+        assert_eq!(overscroll_y(&mut instance), 40.);
+
+        // Held past the edge it stays, lifted it fades out.
+        let (_, state) = instance
+            .ui_state()
+            .widgets_states
+            .scroll_area
+            .iter_mut()
+            .next()
+            .unwrap();
+        decay_overscroll(state, 1.);
+        assert_eq!(state.overscroll_y, 40.);
+
+        instance.push_event(touch(TouchPhase::Ended, 140.));
+        build_overscrolled(&mut instance, &mut host, indicator);
+
+        let (_, state) = instance
+            .ui_state()
+            .widgets_states
+            .scroll_area
+            .iter_mut()
+            .next()
+            .unwrap();
+        decay_overscroll(state, 1.);
+        assert_eq!(state.overscroll_y, 0.);
+    }
+
+    #[test]
+    fn test_mouse_drags_scroll_only_when_enabled() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let mut drag = |instance: &mut ClewInstance<'static>, drag_to_scroll: bool| {
+            let mut offset = 0.;
+            let mut frame = |instance: &mut ClewInstance<'static>, event: Option<InputEvent>| {
+                if let Some(event) = event {
+                    instance.push_event(event);
+                }
+
+                instance.frame(&mut host, &mut |ctx| {
+                    offset = scroll_area()
+                        .fill_max_size()
+                        .drag_to_scroll(drag_to_scroll)
+                        .build(ctx, |ctx| {
+                            zstack().width(100.).height(800.).build(ctx, |_| {});
+                        })
+                        .offset_y;
+                });
+            };
+
+            frame(instance, Some(InputEvent::PointerMoved { x: 10., y: 100. }));
+            frame(
+                instance,
+                Some(InputEvent::MouseButton {
+                    button: MouseButton::Left,
+                    pressed: true,
+                }),
+            );
+            frame(instance, Some(InputEvent::PointerMoved { x: 10., y: 70. }));
+            frame(
+                instance,
+                Some(InputEvent::MouseButton {
+                    button: MouseButton::Left,
+                    pressed: false,
+                }),
+            );
+            frame(instance, Some(InputEvent::PointerMoved { x: 10., y: 10. }));
+
+            offset
+        };
+
+        assert_eq!(drag(&mut instance, false), 0.);
+        assert_eq!(drag(&mut instance, true), -30.);
+    }
+
+    /// A block in an area filling the view of 200 with the bars in the gutter mode,
+    /// returns the rect of the block and the gutters taken.
+    fn build_block<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        direction: ScrollDirection,
+        width: Option<f32>,
+        height: f32,
+    ) -> (Rect, bool, bool) {
+        let mut id = None;
+
+        instance.frame(host, &mut |ctx| {
+            let response = scroll_area()
+                .fill_max_size()
+                .scroll_direction(direction)
+                .scroll_bars(true)
+                .scroll_bar_style(ScrollBarStyle::gutter())
+                .build(ctx, |ctx| {
+                    gesture_detector().build(ctx, |ctx| {
+                        let block = zstack().height(height);
+
+                        match width {
+                            Some(width) => block.width(width),
+                            None => block.fill_max_width(),
+                        }
+                        .build(ctx, |_| {})
+                    });
+                });
+            id = Some(response.id);
+        });
+
+        let state = instance
+            .ui_state()
+            .widgets_states
+            .scroll_area
+            .get(id.unwrap())
+            .unwrap()
+            .clone();
+        let rect = instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .find_map(|item| match item {
+                LayoutItem::Placement(placement)
+                    if placement.widget_ref.widget_type
+                        == WidgetType::of::<gesture_detector::GestureDetector>() =>
+                {
+                    Some(placement.rect)
+                }
+                _ => None,
+            })
+            .unwrap();
+
+        (rect, state.gutter_x, state.gutter_y)
+    }
+
+    #[test]
+    fn test_gutter_is_taken_while_content_overflows() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let gutter = ScrollBarStyle::gutter().extent();
+        let vertical = ScrollDirection::Vertical;
+
+        let (rect, _, y) = build_block(&mut instance, &mut host, vertical, None, 100.);
+        assert_eq!((rect.width, y), (200., false));
+
+        // Taken in the frame the content starts to overflow, and kept in the next ones.
+        for _ in 0..3 {
+            let (rect, _, y) = build_block(&mut instance, &mut host, vertical, None, 300.);
+            assert_eq!((rect.width, y), (200. - gutter, true));
+        }
+
+        let (rect, _, y) = build_block(&mut instance, &mut host, vertical, None, 100.);
+        assert_eq!((rect.width, y), (200., false));
+    }
+
+    #[test]
+    fn test_gutter_of_one_axis_makes_the_other_overflow() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let both = ScrollDirection::Both;
+
+        let (_, x, y) = build_block(&mut instance, &mut host, both, Some(195.), 195.);
+        assert_eq!((x, y), (false, false));
+
+        // Only the width overflows, the horizontal bar leaves too little height.
+        for _ in 0..3 {
+            let (_, x, y) = build_block(&mut instance, &mut host, both, Some(250.), 195.);
+            assert_eq!((x, y), (true, true));
+        }
+
+        let (_, x, y) = build_block(&mut instance, &mut host, both, Some(250.), 150.);
+        assert_eq!((x, y), (true, false));
+    }
+
+    #[test]
+    fn test_scroll_delta_moves_the_least() {
+        // Visible already.
+        assert_eq!(scroll_delta(0., 100., 10., 50.), 0.);
+        // Below the view, the end is aligned with the end of the view.
+        assert_eq!(scroll_delta(0., 100., 150., 180.), -80.);
+        // Above the view, the start is aligned with the start of the view.
+        assert_eq!(scroll_delta(0., 100., -40., -10.), 40.);
+        // Taller than the view, the start wins.
+        assert_eq!(scroll_delta(0., 100., 150., 300.), -150.);
+    }
+
+    /// Row of the master list and of the lists of the detail pane alike.
+    fn list_row(ctx: &mut BuildContext, i: usize) -> WidgetId {
+        gesture_detector()
+            .id(i)
+            .build(ctx, |ctx| {
+                zstack().width(80.).height(20.).build(ctx, |_| {});
+            })
+            .id
+    }
+
+    /// Ids of the area and the rows of the master list, with the offset of the area.
+    fn master_list(
+        ctx: &mut BuildContext,
+        scroll_to: Option<f64>,
+    ) -> (WidgetId, Vec<WidgetId>, f64) {
+        let mut rows = Vec::new();
+        let response = scroll_area()
+            .width(100.)
+            .fill_max_height()
+            .build(ctx, |ctx| {
+                vstack().spacing(0.).build(ctx, |ctx| {
+                    for i in 0..20 {
+                        rows.push(list_row(ctx, i));
+                    }
+                });
+            });
+
+        if let Some(offset) = scroll_to {
+            set_scroll_offset_y(ctx, response.id, offset);
+        }
+
+        (response.id, rows, response.offset_y)
+    }
+
+    #[test]
+    fn test_master_list_keeps_its_ids_when_the_detail_pane_changes_shape() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+        let mut frames = Vec::new();
+
+        for (frame, detail) in [0, 0, 1, 0, 1, 1, 0].into_iter().enumerate() {
+            let mut master = None;
+            let mut detail_rows = Vec::new();
+
+            instance.frame(&mut host, &mut |ctx| {
+                hstack().fill_max_size().build(ctx, |ctx| {
+                    let scroll_to = (frame == 1).then_some(60.);
+
+                    master = Some(ctx.scope("master", |ctx| master_list(ctx, scroll_to)));
+
+                    ctx.scope("detail", |ctx| {
+                        if detail == 0 {
+                            vstack().build(ctx, |ctx| {
+                                for i in 0..3 {
+                                    detail_rows.push(list_row(ctx, i));
+                                }
+                            });
+                        } else {
+                            zstack().build(ctx, |ctx| {
+                                hstack().build(ctx, |ctx| {
+                                    for i in (0..6).rev() {
+                                        detail_rows.push(list_row(ctx, i));
+                                    }
+                                });
+                            });
+                        }
+                    });
+                });
+            });
+
+            let master = master.unwrap();
+
+            // The same keys at the same call site are other widgets in the other pane.
+            assert!(detail_rows.iter().all(|id| !master.1.contains(id)));
+            frames.push(master);
+        }
+
+        for master in &frames[1..] {
+            assert_eq!(master.0, frames[0].0);
+            assert_eq!(master.1, frames[0].1);
+        }
+
+        // The offset set in the second frame is kept while the detail pane changes.
+        assert_ne!(frames[2].2, 0.);
+        assert!(frames[2..].iter().all(|master| master.2 == frames[2].2));
+    }
+}
@@ -4,6 +4,7 @@ use super::BuildContext;
 
 pub struct ShortcutsBuilder {
     active: bool,
+    exclusive: bool,
     scope_id: ShortcutScopeId,
 }
 
@@ -14,12 +15,24 @@ impl ShortcutsBuilder {
         self
     }
 
+    /// While built, only the shortcuts of this scope and of the scopes nested in it
+    /// fire, e.g. of an open menu over the editor it was opened from.
+    pub fn exclusive(mut self, value: bool) -> Self {
+        self.exclusive = value;
+
+        self
+    }
+
     pub fn build<F>(self, ctx: &mut BuildContext, callback: F)
     where
         F: FnOnce(&mut BuildContext),
     {
         if self.active {
             ctx.shortcuts_manager.push_scope(self.scope_id);
+
+            if self.exclusive {
+                ctx.shortcuts_manager.set_exclusive();
+            }
         }
 
         callback(ctx);
@@ -34,6 +47,7 @@ impl ShortcutsBuilder {
 pub fn shortcut_scope<T: Into<ShortcutScopeId>>(scope_id: T) -> ShortcutsBuilder {
     ShortcutsBuilder {
         active: true,
+        exclusive: false,
         scope_id: scope_id.into(),
     }
 }
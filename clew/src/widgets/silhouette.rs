@@ -0,0 +1,329 @@
+use clew_derive::WidgetBuilder;
+
+use crate::{
+    BoxShape, DebugBoundary, EdgeInsets, Rect, Vec2, WidgetId, WidgetIdMap, WidgetType,
+    layout::{ContainerKind, LayoutCommand, LayoutMeasure, WidgetPlacement},
+    state::{TypedWidgetStates, WidgetsStates},
+    text::{Text, TextsResources},
+};
+
+use super::{
+    FrameBuilder, builder::BuildContext, decorated_box::DecoratedBox, rich_text::RichTextWidget,
+    text::TextWidget,
+};
+
+#[derive(WidgetBuilder)]
+pub struct SilhouetteBuilder {
+    frame: FrameBuilder,
+}
+
+/// Shape of a widget of the content of a [`silhouette`], relative to the top left
+/// corner of the content.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SilhouetteShape {
+    /// Visual line of a text, as wide as its glyphs.
+    Line(Rect),
+    Block(Rect),
+    /// Box drawn as an oval, e.g. an avatar.
+    Oval(Rect),
+}
+
+pub(crate) struct State {
+    padding: EdgeInsets,
+    shapes: Vec<SilhouetteShape>,
+}
+
+impl SilhouetteShape {
+    pub fn rect(&self) -> Rect {
+        match self {
+            SilhouetteShape::Line(rect)
+            | SilhouetteShape::Block(rect)
+            | SilhouetteShape::Oval(rect) => *rect,
+        }
+    }
+}
+
+impl SilhouetteBuilder {
+    /// Lays the content out without drawing it or letting it take the input, and builds
+    /// the overlay over it from the shapes of its widgets. The shapes are from the last
+    /// layout, none until the content has been laid out once.
+    #[profiling::function]
+    pub fn build<F, O>(mut self, context: &mut BuildContext, content: F, overlay: O)
+    where
+        F: FnOnce(&mut BuildContext),
+        O: FnOnce(&mut BuildContext, &[SilhouetteShape]),
+    {
+        let id = context.widget_id(self.frame.id);
+        let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds,
+            foregrounds,
+            zindex: self.frame.zindex,
+            padding: self.frame.padding,
+            margin: self.frame.margin,
+            kind: ContainerKind::Measure { id },
+            size: self.frame.size,
+            constraints: self.frame.constraints,
+            clip: self.frame.clip,
+        });
+
+        context.push_layout_command(LayoutCommand::BeginHidden { id });
+        context.scope(self.frame.id, content);
+        context.push_layout_command(LayoutCommand::EndHidden);
+
+        context.widgets_states.layout_measures.mark_accessed(id);
+        if !context.widgets_states.silhouette.contains(id) {
+            context.request_frame();
+        }
+
+        let state = context.widgets_states.silhouette.access(id, || State {
+            padding: self.frame.padding,
+            shapes: Vec::new(),
+        });
+        state.padding = self.frame.padding;
+
+        let shapes = state.shapes.clone();
+        overlay(context, &shapes);
+
+        context.push_layout_command(LayoutCommand::EndContainer);
+    }
+}
+
+/// Outline of the content made of the lines of its texts and the boxes of its other
+/// widgets, e.g. to draw a loading placeholder of the same layout as the content it
+/// stands for. Boxes with other widgets on them, e.g. the background of a card, are
+/// left out.
+///
+/// ```ignore
+/// silhouette().build(
+///     ctx,
+///     |ctx| profile_card(ctx, &placeholder_profile),
+///     |ctx, shapes| {
+///         for shape in shapes {
+///             let rect = shape.rect();
+///
+///             decorated_box()
+///                 .offset(rect.x, rect.y)
+///                 .width(rect.width)
+///                 .height(rect.height)
+///                 .color(ColorRgba::from_hex(0xFF3A3A3A))
+///                 .build(ctx);
+///         }
+///     },
+/// );
+/// ```
+#[track_caller]
+pub fn silhouette() -> SilhouetteBuilder {
+    SilhouetteBuilder {
+        frame: FrameBuilder::new(),
+    }
+}
+
+/// Takes the shapes of the silhouettes from the placements of their hidden content.
+pub(crate) fn update_shapes(
+    widgets_states: &mut WidgetsStates,
+    hidden: &WidgetIdMap<Vec<WidgetPlacement>>,
+    text: &mut TextsResources,
+    scale_factor: f32,
+) {
+    for (id, placements) in hidden {
+        let Some(origin) = content_origin(
+            &widgets_states.layout_measures,
+            &widgets_states.silhouette,
+            *id,
+        ) else {
+            continue;
+        };
+
+        let mut shapes = Vec::new();
+
+        for placement in placements {
+            push_shapes(&mut shapes, placement, widgets_states, text, scale_factor);
+        }
+
+        // Blocks behind other shapes are backgrounds, the shapes on them would be lost
+        // if they were drawn alike.
+        let covers_other = |index: usize, rect: Rect| {
+            shapes.iter().enumerate().any(|(other, shape)| {
+                other != index && rect.contains_rect(shape.rect()) && shape.rect() != rect
+            })
+        };
+        let shapes = shapes
+            .iter()
+            .enumerate()
+            .filter(|(index, shape)| match shape {
+                SilhouetteShape::Line(_) => true,
+                SilhouetteShape::Block(rect) | SilhouetteShape::Oval(rect) => {
+                    !covers_other(*index, *rect)
+                }
+            })
+            .map(|(_, shape)| offset_shape(*shape, -origin))
+            .collect();
+
+        if let Some(state) = widgets_states.silhouette.get_mut(*id) {
+            state.shapes = shapes;
+        }
+    }
+}
+
+fn content_origin(
+    layout_measures: &TypedWidgetStates<LayoutMeasure>,
+    silhouettes: &TypedWidgetStates<State>,
+    id: WidgetId,
+) -> Option<Vec2> {
+    let measure = layout_measures.get(id)?;
+    let padding = silhouettes.get(id)?.padding;
+
+    Some(Vec2::new(measure.x + padding.left, measure.y + padding.top))
+}
+
+fn push_shapes(
+    shapes: &mut Vec<SilhouetteShape>,
+    placement: &WidgetPlacement,
+    widgets_states: &WidgetsStates,
+    text: &mut TextsResources,
+    scale_factor: f32,
+) {
+    let widget_type = placement.widget_ref.widget_type;
+    let id = placement.widget_ref.id;
+    let rect = placement.rect;
+
+    if widget_type == WidgetType::of::<TextWidget>()
+        && let Some(state) = widgets_states.text.get(id)
+    {
+        let text = text.get_mut(state.text_id);
+        let size = text.layout() / scale_factor;
+        let origin = rect.position()
+            + Vec2::new(
+                state
+                    .text_align
+                    .to_align_x()
+                    .position(state.direction, rect.width, size.x),
+                state.vertical_align.position(rect.height, size.y),
+            );
+
+        push_lines(shapes, text, origin, scale_factor);
+    } else if widget_type == WidgetType::of::<RichTextWidget>()
+        && let Some(state) = widgets_states.rich_text.get(id)
+    {
+        push_lines(
+            shapes,
+            text.get(state.text_id),
+            rect.position(),
+            scale_factor,
+        );
+    } else if widget_type == WidgetType::of::<DecoratedBox>()
+        && let Some(state) = widgets_states.decorated_box.get(id)
+        && state.shape == BoxShape::Oval
+    {
+        shapes.push(SilhouetteShape::Oval(rect));
+    } else if widget_type != WidgetType::of::<DebugBoundary>() {
+        shapes.push(SilhouetteShape::Block(rect));
+    }
+}
+
+fn push_lines(shapes: &mut Vec<SilhouetteShape>, text: &Text, origin: Vec2, scale_factor: f32) {
+    for run in text.layout_runs() {
+        let left = run.glyphs.iter().map(|glyph| glyph.x).reduce(f32::min);
+        let right = run
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.x + glyph.w)
+            .reduce(f32::max);
+
+        if let (Some(left), Some(right)) = (left, right) {
+            shapes.push(SilhouetteShape::Line(Rect::new(
+                origin.x + left / scale_factor,
+                origin.y + run.line_top / scale_factor,
+                (right - left) / scale_factor,
+                run.line_height / scale_factor,
+            )));
+        }
+    }
+}
+
+fn offset_shape(shape: SilhouetteShape, offset: Vec2) -> SilhouetteShape {
+    match shape {
+        SilhouetteShape::Line(rect) => SilhouetteShape::Line(rect.offset(offset.x, offset.y)),
+        SilhouetteShape::Block(rect) => SilhouetteShape::Block(rect.offset(offset.x, offset.y)),
+        SilhouetteShape::Oval(rect) => SilhouetteShape::Oval(rect.offset(offset.x, offset.y)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        BoxShape, ClewInstance, ColorRgba,
+        layout::LayoutItem,
+        prelude::*,
+        testing,
+        text::test_fonts,
+        widgets::{
+            decorated_box::{decorated_box, decoration},
+            text::text,
+            vstack::vstack,
+        },
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_shapes_follow_hidden_content() {
+        let fonts = test_fonts();
+        let mut host = testing::host();
+        let mut instance = ClewInstance::new(testing::view(200, 200), fonts);
+        let mut shapes = Vec::new();
+
+        // The shapes are taken from the layout of the first frame.
+        for _ in 0..2 {
+            instance.frame(&mut host, &mut |ctx| {
+                silhouette().padding(EdgeInsets::all(4.)).build(
+                    ctx,
+                    |ctx| {
+                        vstack()
+                            .padding(EdgeInsets::all(8.))
+                            .background(
+                                decoration()
+                                    .color(ColorRgba::from_hex(0xFF202020))
+                                    .build(ctx),
+                            )
+                            .build(ctx, |ctx| {
+                                decorated_box()
+                                    .width(40.)
+                                    .height(40.)
+                                    .shape(BoxShape::Oval)
+                                    .build(ctx);
+                                text("Lorem ipsum dolor").width(60.).build(ctx);
+                            });
+                    },
+                    |_, overlay_shapes| shapes = overlay_shapes.to_vec(),
+                );
+            });
+        }
+
+        assert_eq!(
+            shapes[0],
+            SilhouetteShape::Oval(Rect::new(8., 8., 40., 40.))
+        );
+
+        // The text wraps into lines, the background behind them is left out.
+        let lines = &shapes[1..];
+        assert!(lines.len() >= 2);
+        assert!(
+            lines
+                .iter()
+                .all(|shape| matches!(shape, SilhouetteShape::Line(rect)
+            if rect.x >= 8. && rect.width <= 60. && rect.y >= 48.))
+        );
+
+        // None of the content is drawn.
+        assert!(
+            !instance.ui_state().layout_items.iter().any(|item| matches!(
+                item,
+                LayoutItem::Placement(placement)
+                    if placement.widget_ref.widget_type == WidgetType::of::<TextWidget>()
+            ))
+        );
+    }
+}
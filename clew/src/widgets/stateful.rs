@@ -57,9 +57,13 @@ impl<T: WidgetState + StatefulWidget + Default> StatefulWidgetBuilder
     for StatefulWidgetAutoStateBuilder<T>
 {
     fn build(self, context: &mut BuildContext) {
-        let id = self.frame.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.frame.id);
         let (idx, mut state) = context.widgets_states.take_or_create(id, T::default);
 
+        if let Some(state) = state.as_serializable_mut() {
+            context.widgets_states.snapshots.track(id, state);
+        }
+
         // Skip event processing for () type
         if TypeId::of::<T::Event>() != TypeId::of::<()>() {
             for event_box in context.event_queue.iter() {
@@ -69,7 +73,6 @@ impl<T: WidgetState + StatefulWidget + Default> StatefulWidgetBuilder
             }
         }
 
-        context.widgets_states.custom.accessed_this_frame.insert(id);
         state.build(context, self.frame);
 
         context.widgets_states.restore(idx, state);
@@ -81,9 +84,13 @@ impl<T: WidgetState + StatefulWidget + Default> StatefulWidgetAutoStateBuilder<T
     where
         F: FnOnce(&mut T),
     {
-        let id = self.frame.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.frame.id);
         let (idx, mut state) = context.widgets_states.take_or_create(id, T::default);
 
+        if let Some(state) = state.as_serializable_mut() {
+            context.widgets_states.snapshots.track(id, state);
+        }
+
         update_state(&mut state);
 
         // Skip event processing for () type
@@ -95,7 +102,6 @@ impl<T: WidgetState + StatefulWidget + Default> StatefulWidgetAutoStateBuilder<T
             }
         }
 
-        context.widgets_states.custom.accessed_this_frame.insert(id);
         state.build(context, self.frame);
 
         context.widgets_states.restore(idx, state);
@@ -106,7 +112,7 @@ impl<'a, T: WidgetState + StatefulWidget + Default> StatefulWidgetBuilder
     for StatefulWidgetWithStateBuilder<'a, T>
 {
     fn build(self, context: &mut BuildContext) {
-        let id = self.frame.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.frame.id);
 
         // Skip event processing for () type
         if TypeId::of::<T::Event>() != TypeId::of::<()>() {
@@ -117,7 +123,7 @@ impl<'a, T: WidgetState + StatefulWidget + Default> StatefulWidgetBuilder
             }
         }
 
-        context.widgets_states.custom.accessed_this_frame.insert(id);
+        context.widgets_states.custom.mark_accessed(id);
         self.state.build(context, self.frame);
     }
 }
@@ -0,0 +1,213 @@
+use clew_derive::WidgetBuilder;
+
+use crate::{
+    Clip, CrossAxisAlignment, MainAxisAlignment, Size, SizeConstraint, WidgetId,
+    layout::{ContainerKind, LayoutCommand},
+};
+
+use super::{
+    FrameBuilder,
+    builder::{BuildContext, WidgetBuilder},
+    scroll_area::ScrollAreaResponse,
+};
+
+#[derive(WidgetBuilder)]
+pub struct StickyHeaderBuilder {
+    frame: FrameBuilder,
+}
+
+impl StickyHeaderBuilder {
+    /// Builds the header above the content. Inside of a scroll area the header sticks
+    /// to the top of the viewport while the section is scrolled under it, and is pushed
+    /// out by the end of the section. The header is moved after the layout of the frame,
+    /// so it never lags behind the content.
+    #[profiling::function]
+    pub fn build<H, F>(mut self, context: &mut BuildContext, header: H, content: F)
+    where
+        H: FnOnce(&mut BuildContext),
+        F: FnOnce(&mut BuildContext),
+    {
+        let id = context.widget_id(self.frame.id);
+        let header_id = WidgetId::auto_with_seed((id, "header"));
+        let scroll_area = context
+            .of::<ScrollAreaResponse>()
+            .map(|response| response.id);
+        let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds,
+            foregrounds,
+            zindex: self.frame.zindex,
+            padding: self.frame.padding,
+            margin: self.frame.margin,
+            kind: ContainerKind::Measure { id },
+            size: self.frame.size,
+            constraints: self.frame.constraints,
+            clip: self.frame.clip,
+        });
+
+        context.push_layout_command(LayoutCommand::BeginContainer {
+            backgrounds: Default::default(),
+            foregrounds: Default::default(),
+            zindex: self.frame.zindex,
+            padding: Default::default(),
+            margin: Default::default(),
+            kind: ContainerKind::VStack {
+                spacing: 0.,
+                main_axis_alignment: MainAxisAlignment::Start,
+                cross_axis_alignment: CrossAxisAlignment::Stretch,
+                rtl_aware: false,
+            },
+            size: Size::new(SizeConstraint::Fill(1.), SizeConstraint::Wrap),
+            constraints: Default::default(),
+            clip: Clip::None,
+        });
+
+        context.scope(self.frame.id, |context| {
+            // The header is drawn over the sections below it when it sticks.
+            context.push_layout_command(LayoutCommand::BeginContainer {
+                backgrounds: Default::default(),
+                foregrounds: Default::default(),
+                zindex: self.frame.zindex + 1,
+                padding: Default::default(),
+                margin: Default::default(),
+                kind: ContainerKind::Measure { id: header_id },
+                size: Size::new(SizeConstraint::Fill(1.), SizeConstraint::Wrap),
+                constraints: Default::default(),
+                clip: Clip::None,
+            });
+            header(context);
+            context.push_layout_command(LayoutCommand::EndContainer);
+
+            content(context);
+        });
+
+        context.push_layout_command(LayoutCommand::EndContainer);
+        context.push_layout_command(LayoutCommand::EndContainer);
+
+        let measures = &mut context.widgets_states.layout_measures;
+        measures.mark_accessed(id);
+        measures.mark_accessed(header_id);
+
+        if let Some(scroll_area) = scroll_area {
+            context.after_layout(header_id, move |layout, mutator| {
+                let (Some(viewport), Some(section), Some(header)) = (
+                    layout.rect(scroll_area),
+                    layout.rect(id),
+                    layout.rect(header_id),
+                ) else {
+                    return;
+                };
+
+                let offset = (viewport.y - header.y)
+                    .min(section.y + section.height - header.y - header.height)
+                    .max(0.);
+
+                mutator.offset(0., offset);
+            });
+        }
+    }
+}
+
+/// Section of a scroll area with a header that sticks to the top of the viewport
+/// while the section is scrolled, e.g. the letter of a group of contacts.
+///
+/// ```ignore
+/// scroll_area().build(ctx, |ctx| {
+///     vstack().build(ctx, |ctx| {
+///         for (letter, contacts) in &groups {
+///             sticky_header().id(letter).build(
+///                 ctx,
+///                 |ctx| text(letter).build(ctx),
+///                 |ctx| contacts_list(ctx, contacts),
+///             );
+///         }
+///     });
+/// });
+/// ```
+#[track_caller]
+pub fn sticky_header() -> StickyHeaderBuilder {
+    StickyHeaderBuilder {
+        frame: FrameBuilder::new().fill_max_width(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        ClewHost, ClewInstance,
+        layout::LayoutItem,
+        testing,
+        widgets::{
+            decorated_box::decorated_box,
+            scroll_area::{scroll_area, set_scroll_offset_y},
+            vstack::vstack,
+        },
+    };
+
+    use super::*;
+
+    /// Sections of a header of 20 and content of 200 in an area filling the view of 200.
+    fn build_sections<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        scroll_to: Option<f64>,
+    ) {
+        instance.frame(host, &mut |ctx| {
+            let response = scroll_area().fill_max_size().build(ctx, |ctx| {
+                vstack().spacing(0.).build(ctx, |ctx| {
+                    for i in 0..3 {
+                        sticky_header().id(i).build(
+                            ctx,
+                            |ctx| {
+                                decorated_box()
+                                    .width(100. + i as f32)
+                                    .height(20.)
+                                    .build(ctx)
+                            },
+                            |ctx| decorated_box().width(10.).height(200.).build(ctx),
+                        );
+                    }
+                });
+            });
+
+            if let Some(offset) = scroll_to {
+                set_scroll_offset_y(ctx, response.id, offset);
+            }
+        });
+    }
+
+    /// Y of the header of the section, told apart by its width.
+    fn header_y(instance: &mut ClewInstance, section: usize) -> Option<f32> {
+        instance
+            .ui_state()
+            .layout_items
+            .iter()
+            .find_map(|item| match item {
+                LayoutItem::Placement(placement)
+                    if placement.rect.width == 100. + section as f32
+                        && placement.rect.height == 20. =>
+                {
+                    Some(placement.rect.y)
+                }
+                _ => None,
+            })
+    }
+
+    #[test]
+    fn test_header_sticks_in_the_same_frame() {
+        let (mut host, mut instance) = testing::instance(200, 200);
+
+        // The offset set in a frame is laid out in the next one.
+        build_sections(&mut instance, &mut host, Some(50.));
+        build_sections(&mut instance, &mut host, Some(230.));
+        // The header sticks to the top in the frame the content has moved in.
+        assert_eq!(header_y(&mut instance, 0), Some(0.));
+        assert_eq!(header_y(&mut instance, 1), Some(170.));
+
+        // Pushed out by the end of its section by the next one.
+        build_sections(&mut instance, &mut host, None);
+        assert_eq!(header_y(&mut instance, 0), Some(-30.));
+        assert_eq!(header_y(&mut instance, 1), Some(0.));
+    }
+}
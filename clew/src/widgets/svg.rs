@@ -2,28 +2,26 @@ use std::any::Any;
 
 use clew_derive::WidgetBuilder;
 
-use crate::{
-    ColorRgba, WidgetRef, WidgetType,
-    layout::{DeriveWrapSize, LayoutCommand, WidgetPlacement},
-    render::{PixelExtension, RenderCommand, RenderContext},
-    state::WidgetState,
+// Only the widget authoring surface is used here, the same as a widget crate would.
+use crate::authoring::{
+    BuildContext, DeriveWrapSize, FrameBuilder, PixelExtension, RenderCommand, RenderContext,
+    WidgetPlacement, WidgetState,
 };
-
-use super::{FrameBuilder, builder::BuildContext};
-
-pub struct SvgWidget;
+use crate::{ColorRgba, TintMode, WidgetRef, WidgetType};
 
 #[derive(WidgetBuilder)]
 pub struct SvgBuilder {
     frame: FrameBuilder,
     asset_id: &'static str,
     color: Option<ColorRgba>,
+    tint_mode: TintMode,
 }
 
-#[derive(Clone, PartialEq)]
+#[derive(Clone, PartialEq, Default)]
 pub struct State {
     pub(crate) asset_id: &'static str,
     pub(crate) color: Option<ColorRgba>,
+    pub(crate) tint_mode: TintMode,
 }
 
 impl WidgetState for State {
@@ -41,41 +39,52 @@ impl WidgetState for State {
     fn into_any(self: Box<Self>) -> Box<dyn Any> {
         self
     }
+
+    fn render(&self, ctx: &mut RenderContext, placement: &WidgetPlacement) {
+        ctx.push_command(
+            placement.zindex,
+            RenderCommand::Svg {
+                boundary: placement.rect.px(ctx),
+                asset_id: self.asset_id,
+                tint_color: self.color,
+                tint_mode: self.tint_mode,
+            },
+        );
+    }
 }
 
 impl SvgBuilder {
-    pub fn color(mut self, color: ColorRgba) -> Self {
+    /// Same as [`SvgBuilder::tint`].
+    pub fn color(self, color: ColorRgba) -> Self {
+        self.tint(color)
+    }
+
+    /// Tints the pixels of the SVG, see [`TintMode`] for how.
+    pub fn tint(mut self, color: ColorRgba) -> Self {
         self.color = Some(color);
 
         self
     }
 
-    pub fn build(&self, context: &mut BuildContext) {
-        let id = self.frame.id.with_seed(context.id_seed);
-
-        let widget_ref = WidgetRef::new(WidgetType::of::<SvgWidget>(), id);
-        let backgrounds = std::mem::take(context.backgrounds);
-        let foregrounds = std::mem::take(context.foregrounds);
-
-        context.push_layout_command(LayoutCommand::Leaf {
-            widget_ref,
-            backgrounds,
-            foregrounds,
-            padding: self.frame.padding,
-            margin: self.frame.margin,
-            constraints: self.frame.constraints,
-            size: self.frame.size,
-            zindex: self.frame.zindex,
-            derive_wrap_size: DeriveWrapSize::Svg(self.asset_id),
-            clip: self.frame.clip,
-        });
+    pub fn tint_mode(mut self, tint_mode: TintMode) -> Self {
+        self.tint_mode = tint_mode;
 
-        context.widgets_states.svg.set(
-            id,
-            State {
-                asset_id: self.asset_id,
-                color: self.color,
-            },
+        self
+    }
+
+    pub fn build(&self, context: &mut BuildContext) {
+        let id = self.frame.widget_id(context);
+
+        *context.state::<State>(id) = State {
+            asset_id: self.asset_id,
+            color: self.color,
+            tint_mode: self.tint_mode,
+        };
+
+        context.push_leaf(
+            WidgetRef::new(WidgetType::of::<State>(), id),
+            &self.frame,
+            DeriveWrapSize::Svg(self.asset_id),
         );
     }
 }
@@ -86,16 +95,41 @@ pub fn svg(asset_id: &'static str) -> SvgBuilder {
         frame: FrameBuilder::new(),
         asset_id,
         color: None,
+        tint_mode: TintMode::Replace,
     }
 }
 
-pub fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &State) {
-    ctx.push_command(
-        placement.zindex,
-        RenderCommand::Svg {
-            boundary: placement.rect.px(ctx),
-            asset_id: state.asset_id,
-            tint_color: state.color,
-        },
-    );
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, testing};
+
+    use super::*;
+
+    #[test]
+    fn test_svg_is_drawn_by_its_state_while_built() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        let tint = ColorRgba::from_hex(0xFF336699);
+
+        instance.frame(&mut host, &mut |ctx| {
+            svg("icon")
+                .id("icon")
+                .size(16.)
+                .tint(tint)
+                .tint_mode(TintMode::Multiply)
+                .build(ctx);
+        });
+
+        let commands = instance.ui_state().render_state.commands();
+        assert!(commands.iter().any(|command| *command
+            == RenderCommand::Svg {
+                boundary: crate::Rect::new(0., 0., 16., 16.),
+                asset_id: "icon",
+                tint_color: Some(tint),
+                tint_mode: TintMode::Multiply,
+            }));
+
+        // The state goes away with the widget.
+        instance.frame(&mut host, &mut |_| {});
+        assert_eq!(instance.ui_state().widgets_states.custom.iter().count(), 0);
+    }
 }
@@ -1,12 +1,23 @@
 use clew_derive::WidgetBuilder;
-use std::any::Any;
+use rustc_hash::FxHasher;
+use smallvec::SmallVec;
+use std::{
+    any::Any,
+    borrow::Cow,
+    hash::{Hash, Hasher},
+    ops::Range,
+};
 
 use crate::{
-    AlignY, ColorRgba, TextAlign, Vec2, WidgetRef, WidgetType,
+    AlignY, BorderRadius, ColorRgba, EdgeInsets, LayoutDirection, Rect, TextAlign, TextDirection,
+    Vec2, WidgetRef, WidgetType,
     layout::{DeriveWrapSize, LayoutCommand, WidgetPlacement},
-    render::{PixelExtension, RenderCommand, RenderContext},
+    render::{
+        Fill, PixelExtension, RenderCommand, RenderContext, TextStroke, is_run_visible,
+        visible_band,
+    },
     state::WidgetState,
-    text::TextId,
+    text::{Text, TextId, with_text_direction},
 };
 
 use super::{FrameBuilder, builder::BuildContext};
@@ -16,20 +27,74 @@ pub struct TextWidget;
 #[derive(WidgetBuilder)]
 pub struct TextBuilder<'a> {
     frame: FrameBuilder,
-    text: &'a str,
+    text: TextSource<'a>,
     color: ColorRgba,
     text_align: TextAlign,
+    text_direction: TextDirection,
     font_size: f32,
     vertical_align: AlignY,
+    is_static: bool,
+    clip_text: bool,
+    font_family: Option<&'static str>,
+    tabular_numbers: bool,
+    font_features: &'a [(&'a str, u32)],
+    simple_monospace: bool,
+    stroke: Option<TextStroke>,
+    stroke_in_size: bool,
+    background: Option<TextBackground>,
+    underline: Option<Range<usize>>,
+}
+
+/// Box drawn behind every visual line of a text, see [`TextBuilder::text_background`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct TextBackground {
+    color: ColorRgba,
+    padding: EdgeInsets,
+    corner_radius: f32,
+}
+
+/// Underlined part of a text in the coordinates of the shaped text, which may start its
+/// paragraphs with a direction mark, see [`TextBuilder::underline`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct TextUnderline {
+    /// Byte range of the text it was requested for.
+    requested: Range<usize>,
+    /// Paragraph, i.e. the line of the buffer, the range is in.
+    line: usize,
+    /// Byte range in the paragraph.
+    range: Range<usize>,
+}
+
+enum TextSource<'a> {
+    Str(&'a str),
+    /// Text made only when the key has changed, see [`text_lazy`].
+    Lazy(u64, Box<dyn FnOnce() -> String + 'a>),
+}
+
+/// How many texts were set again in a frame and how many were kept as they were, see
+/// [`crate::render::RenderStats`].
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct TextUpdates {
+    pub(crate) updated: u32,
+    pub(crate) skipped: u32,
 }
 
 #[derive(Clone, PartialEq)]
 pub struct State {
     pub(crate) text_id: TextId,
-    pub(crate) text_data: String,
+    /// Hash of the text and of what it's shaped with, the text is set again only when
+    /// it changes.
+    pub(crate) content_hash: u64,
     pub(crate) color: ColorRgba,
     pub(crate) text_align: TextAlign,
+    /// Resolved direction of the first paragraph, the text is aligned by it.
+    pub(crate) direction: LayoutDirection,
     pub(crate) vertical_align: AlignY,
+    pub(crate) clip_text: bool,
+    /// Outline in logical pixels.
+    pub(crate) stroke: Option<TextStroke>,
+    pub(crate) background: Option<TextBackground>,
+    pub(crate) underline: Option<TextUnderline>,
 }
 
 impl WidgetState for State {
@@ -68,73 +133,275 @@ impl<'a> TextBuilder<'a> {
         self
     }
 
+    /// Base direction of the paragraphs, by default each one is detected from its first
+    /// strong directional character. `TextAlign::Auto` aligns the text to the start of
+    /// the resolved direction.
+    pub fn text_direction(mut self, direction: TextDirection) -> Self {
+        self.text_direction = direction;
+
+        self
+    }
+
     pub fn text_vertical_align(mut self, align_y: AlignY) -> Self {
         self.vertical_align = align_y;
 
         self
     }
 
+    pub fn font_family(mut self, family: &'static str) -> Self {
+        self.font_family = Some(family);
+
+        self
+    }
+
+    /// Digits of the same width, so live values like timers don't move the text around.
+    /// Enables the `tnum` font feature, the digits of the fonts that lack it are spaced
+    /// out to the advance of the widest one.
+    pub fn tabular_numbers(mut self, value: bool) -> Self {
+        self.tabular_numbers = value;
+
+        self
+    }
+
+    /// Lays out an ASCII text from the advances of its characters rather than shaping
+    /// it, e.g. for the lines of a large log in a virtual list. The text is in the
+    /// monospace family unless another one is set, so its columns map linearly to x.
+    /// Any other text falls back to the full shaping, a wrapped one to the basic one.
+    pub fn simple_monospace(mut self, value: bool) -> Self {
+        self.simple_monospace = value;
+
+        self
+    }
+
+    /// OpenType features, e.g. `&[("smcp", 1), ("liga", 0)]`.
+    pub fn font_features(mut self, features: &'a [(&'a str, u32)]) -> Self {
+        self.font_features = features;
+
+        self
+    }
+
+    /// Hint that the text rarely changes. Inside clipped containers only the visible
+    /// lines of static text with a constrained height get shaped.
+    pub fn static_text(mut self, is_static: bool) -> Self {
+        self.is_static = is_static;
+
+        self
+    }
+
+    /// Clips the glyphs to the content rect of the widget, inside of its padding, on by
+    /// default. Turned off the text that doesn't fit paints past the rect, e.g. a
+    /// decorative headline.
+    pub fn clip_text(mut self, clip_text: bool) -> Self {
+        self.clip_text = clip_text;
+
+        self
+    }
+
+    /// Outline of the given width around the glyphs, drawn under their fill, e.g. for a
+    /// caption over an image.
+    pub fn text_stroke(mut self, width: f32, color: ColorRgba) -> Self {
+        self.stroke = Some(TextStroke { width, color });
+
+        self
+    }
+
+    /// Pads the text by the width of its stroke, so the outline of the glyphs at the
+    /// edges isn't clipped by the rect of the widget.
+    pub fn stroke_in_size(mut self, value: bool) -> Self {
+        self.stroke_in_size = value;
+
+        self
+    }
+
+    /// Rounded box behind each line of the text, padded around the glyphs. The boxes of
+    /// lines of the same width that touch are drawn as one.
+    pub fn text_background(
+        mut self,
+        color: ColorRgba,
+        padding: EdgeInsets,
+        corner_radius: f32,
+    ) -> Self {
+        self.background = Some(TextBackground {
+            color,
+            padding,
+            corner_radius,
+        });
+
+        self
+    }
+
+    /// Underlines the glyphs of the byte `range` of the text in its color, e.g. the
+    /// letter of a mnemonic. The glyphs stay one run, so the shaping across the range,
+    /// e.g. kerning and ligatures, is kept. A range within one line only.
+    pub fn underline(mut self, range: Range<usize>) -> Self {
+        self.underline = Some(range);
+
+        self
+    }
+
     #[profiling::function]
     pub fn build(mut self, context: &mut BuildContext) {
-        let id = self.frame.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.frame.id);
 
         let widget_ref = WidgetRef::new(WidgetType::of::<TextWidget>(), id);
         let state = context.widgets_states.text.get(id);
         let mut last_text_align = state.map(|it| it.text_align).unwrap_or(TextAlign::Auto);
 
-        let (text_data, text_id) = if let Some(state) = state {
-            if state.text_data != self.text {
-                context.text.update_text(state.text_id, |text| {
-                    text.set_text(context.fonts, self.text);
-                });
+        let family = match self.font_family {
+            Some(name) => cosmic_text::Family::Name(name),
+            None if self.simple_monospace => cosmic_text::Family::Monospace,
+            None => cosmic_text::Family::SansSerif,
+        };
 
-                last_text_align = TextAlign::Auto;
+        let mut font_features: SmallVec<[(&str, u32); 4]> =
+            self.font_features.iter().copied().collect();
 
-                // Reset wrap size calculation during layout.
-                if !self.frame.size.width.constrained() {
-                    let text = context.text.get_mut(state.text_id);
-                    text.with_buffer_mut(|buffer| {
-                        buffer.set_size(&mut context.fonts.font_system, None, None);
+        if self.tabular_numbers {
+            font_features.push(("tnum", 1));
+        }
 
-                        for line in buffer.lines.iter_mut() {
-                            line.set_align(None);
-                        }
-                    });
-                }
+        let content_hash = {
+            let mut hasher = FxHasher::default();
+
+            match &self.text {
+                TextSource::Str(text) => (0u8, text).hash(&mut hasher),
+                TextSource::Lazy(key, _) => (1u8, key).hash(&mut hasher),
+            }
+
+            self.font_family.hash(&mut hasher);
+            self.text_direction.hash(&mut hasher);
+            context.layout_direction.hash(&mut hasher);
+            font_features.hash(&mut hasher);
+            self.simple_monospace.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        let last = state.map(|state| (state.text_id, state.content_hash, state.direction));
+        let last_underline = state.and_then(|state| state.underline.clone());
+        let text_direction = self.text_direction;
+        let layout_direction = context.layout_direction;
+        let underline_of = |text: &str, requested: &Option<Range<usize>>| {
+            requested.clone().and_then(|requested| {
+                resolve_underline(text, requested, text_direction, layout_direction)
+            })
+        };
+
+        // The lazy text is only at hand when it's made, otherwise the underline resolved
+        // for it last time is kept while the range stays the same.
+        let mut underline = match &self.text {
+            TextSource::Str(text) => underline_of(text, &self.underline),
+            TextSource::Lazy(..) => {
+                last_underline.filter(|last| Some(&last.requested) == self.underline.as_ref())
+            }
+        };
 
-                (Some(self.text.to_string()), state.text_id)
-            } else {
-                (None, state.text_id)
+        let (changed, text_id, direction) = match last {
+            Some((text_id, last_hash, direction)) if last_hash == content_hash => {
+                context.widgets_states.text_updates.skipped += 1;
+
+                (false, text_id, direction)
             }
-        } else {
-            let text_id =
-                context
-                    .text
-                    .add_text(context.view, context.fonts, 12., 12., |fonts, text_res| {
-                        text_res.set_text(fonts, self.text)
+            last => {
+                context.widgets_states.text_updates.updated += 1;
+
+                let lazy_text;
+                let text = match self.text {
+                    TextSource::Str(text) => text,
+                    TextSource::Lazy(_, make) => {
+                        lazy_text = make();
+                        underline = underline_of(&lazy_text, &self.underline);
+                        lazy_text.as_str()
+                    }
+                };
+                let shaped_text =
+                    with_text_direction(text, self.text_direction, context.layout_direction);
+
+                if let Some(live_texts) = &mut context.announcements.live_texts {
+                    live_texts.push(text.to_string());
+                }
+
+                if let Some(name) = self.font_family {
+                    context.fonts.request_family(name);
+                }
+
+                let text_id = if let Some((text_id, ..)) = last {
+                    context.text.update_text(text_id, |text| {
+                        text.set_family(family);
+                        text.set_font_features(&font_features);
+
+                        if self.simple_monospace {
+                            text.set_simple_text(context.fonts, &shaped_text);
+                        } else {
+                            text.set_text(context.fonts, &shaped_text);
+                        }
                     });
 
-            (Some(self.text.to_string()), text_id)
+                    last_text_align = TextAlign::Auto;
+
+                    // Reset wrap size calculation during layout.
+                    if !self.frame.size.width.constrained() {
+                        let text = context.text.get_mut(text_id);
+                        text.set_size(&mut context.fonts.font_system, None, None);
+                        text.set_align(None);
+                    }
+
+                    text_id
+                } else {
+                    context.text.add_text(
+                        context.view,
+                        context.fonts,
+                        12.,
+                        12.,
+                        |fonts, text_res| {
+                            text_res.set_family(family);
+                            text_res.set_font_features(&font_features);
+
+                            if self.simple_monospace {
+                                text_res.set_simple_text(fonts, &shaped_text);
+                            } else {
+                                text_res.set_text(fonts, &shaped_text);
+                            }
+                        },
+                    )
+                };
+
+                let first_paragraph = text.split('\n').next().unwrap_or_default();
+                let direction = self
+                    .text_direction
+                    .resolve(first_paragraph, context.layout_direction);
+
+                (true, text_id, direction)
+            }
         };
 
         if last_text_align != self.text_align {
             let text = context.text.get_mut(text_id);
             if self.frame.size.width.constrained() {
-                text.with_buffer_mut(|buffer| {
-                    for line in buffer.lines.iter_mut() {
-                        line.set_align(match self.text_align {
-                            TextAlign::Auto => None,
-                            TextAlign::Left => Some(cosmic_text::Align::Left),
-                            TextAlign::Right => Some(cosmic_text::Align::Right),
-                            TextAlign::End => Some(cosmic_text::Align::End),
-                            TextAlign::Center => Some(cosmic_text::Align::Center),
-                            TextAlign::Justified => Some(cosmic_text::Align::Justified),
-                        });
-                    }
+                text.set_align(match self.text_align {
+                    TextAlign::Auto => None,
+                    TextAlign::Left => Some(cosmic_text::Align::Left),
+                    TextAlign::Right => Some(cosmic_text::Align::Right),
+                    TextAlign::End => Some(cosmic_text::Align::End),
+                    TextAlign::Center => Some(cosmic_text::Align::Center),
+                    TextAlign::Justified => Some(cosmic_text::Align::Justified),
                 });
             }
         }
 
+        if changed && self.tabular_numbers {
+            context.text.get_mut(text_id).pad_numbers(context.fonts);
+        }
+
+        if context.text.is_static(text_id) != self.is_static {
+            context.text.set_static(text_id, self.is_static);
+        }
+
+        if self.stroke_in_size
+            && let Some(stroke) = self.stroke
+        {
+            self.frame.padding = self.frame.padding + EdgeInsets::all(stroke.width);
+        }
+
         let (backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
 
         context.push_layout_command(LayoutCommand::Leaf {
@@ -150,34 +417,101 @@ impl<'a> TextBuilder<'a> {
             clip: self.frame.clip,
         });
 
-        context.widgets_states.text.accessed_this_frame.insert(id);
-
-        let state = context.widgets_states.text.get_or_insert(id, || State {
+        let state = context.widgets_states.text.access(id, || State {
             text_id,
-            text_data: text_data.clone().unwrap(),
+            content_hash,
             color: self.color,
             text_align: self.text_align,
+            direction,
             vertical_align: self.vertical_align,
+            clip_text: self.clip_text,
+            stroke: self.stroke,
+            background: self.background,
+            underline: underline.clone(),
         });
 
-        if let Some(text_data) = text_data {
-            state.text_data = text_data;
-        }
-
+        state.content_hash = content_hash;
         state.color = self.color;
+        state.clip_text = self.clip_text;
         state.text_align = self.text_align;
+        state.direction = direction;
+        state.stroke = self.stroke;
+        state.background = self.background;
+        state.underline = underline;
+    }
+}
+
+/// Maps the byte `requested` range of the text to its paragraph of the shaped text,
+/// past the direction mark [`with_text_direction`] may start the paragraph with.
+fn resolve_underline(
+    text: &str,
+    requested: Range<usize>,
+    direction: TextDirection,
+    layout_direction: LayoutDirection,
+) -> Option<TextUnderline> {
+    if requested.start >= requested.end
+        || requested.end > text.len()
+        || !text.is_char_boundary(requested.start)
+        || !text.is_char_boundary(requested.end)
+    {
+        return None;
     }
+
+    let paragraph_start = text[..requested.start].rfind('\n').map_or(0, |it| it + 1);
+    let paragraph_end = text[requested.start..]
+        .find('\n')
+        .map_or(text.len(), |it| requested.start + it);
+    let paragraph = &text[paragraph_start..paragraph_end];
+    let mark = match with_text_direction(paragraph, direction, layout_direction) {
+        Cow::Borrowed(_) => 0,
+        Cow::Owned(_) => '\u{200E}'.len_utf8(),
+    };
+    let end = requested.end.min(paragraph_end);
+
+    Some(TextUnderline {
+        line: text[..requested.start].matches('\n').count(),
+        range: requested.start - paragraph_start + mark..end - paragraph_start + mark,
+        requested,
+    })
 }
 
 #[track_caller]
 pub fn text(text: &str) -> TextBuilder<'_> {
     TextBuilder {
         frame: FrameBuilder::new(),
-        text,
+        text: TextSource::Str(text),
         color: ColorRgba::from_hex(0xFFFFFFFF),
         vertical_align: AlignY::Top,
         font_size: 12.,
-        text_align: TextAlign::Left,
+        text_align: TextAlign::Auto,
+        text_direction: TextDirection::Auto,
+        is_static: false,
+        clip_text: true,
+        font_family: None,
+        tabular_numbers: false,
+        font_features: &[],
+        simple_monospace: false,
+        stroke: None,
+        stroke_in_size: false,
+        background: None,
+        underline: None,
+    }
+}
+
+/// Text made by `make` only when the `key` has changed since the previous frame, so the
+/// formatting is skipped too while the value it's made from stays the same.
+///
+/// ```ignore
+/// text_lazy(counter, || format!("Clicked {counter} times")).build(ctx);
+/// ```
+#[track_caller]
+pub fn text_lazy<'a, K: Hash>(key: K, make: impl FnOnce() -> String + 'a) -> TextBuilder<'a> {
+    let mut hasher = FxHasher::default();
+    key.hash(&mut hasher);
+
+    TextBuilder {
+        text: TextSource::Lazy(hasher.finish(), Box::new(make)),
+        ..text("")
     }
 }
 
@@ -192,10 +526,61 @@ pub fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &Stat
             state
                 .text_align
                 .to_align_x()
-                .position(ctx.layout_direction, size.x, text_size.x),
+                .position(state.direction, size.x, text_size.x),
             state.vertical_align.position(size.y, text_size.y),
         );
 
+    let rect = placement.rect.px(ctx);
+    let visible_band = visible_band(
+        rect,
+        placement.clip_rect.map(|rect| rect.px(ctx)),
+        text_position.y,
+    );
+
+    if let Some(band) = &visible_band
+        && band.is_empty()
+    {
+        return;
+    }
+
+    // Only the text that doesn't fit is clipped, so the overhangs of the glyphs that
+    // do, e.g. of italics, are kept.
+    let overflows = text_size.x > size.x + 0.5 || text_size.y > size.y + 0.5;
+    let scale_factor = ctx.view.scale_factor;
+
+    if let Some(background) = state.background {
+        let padding = background.padding;
+        let padding = EdgeInsets {
+            top: padding.top * scale_factor,
+            left: padding.left * scale_factor,
+            right: padding.right * scale_factor,
+            bottom: padding.bottom * scale_factor,
+        };
+        let boxes = line_boxes(ctx.text.get(state.text_id), padding, visible_band.as_ref());
+
+        for line_box in boxes {
+            ctx.push_command(
+                placement.zindex,
+                RenderCommand::Rect {
+                    boundary: line_box.offset(text_position.x, text_position.y),
+                    fill: Some(Fill::Color(background.color)),
+                    border_radius: Some(BorderRadius::all(background.corner_radius).px(ctx)),
+                    border: None,
+                    border_fill: None,
+                },
+            );
+        }
+    }
+
+    let underline = state.underline.as_ref().and_then(|underline| {
+        underline_rect(
+            ctx.text.get(state.text_id),
+            underline,
+            scale_factor,
+            visible_band.as_ref(),
+        )
+    });
+
     ctx.push_command(
         placement.zindex,
         RenderCommand::Text {
@@ -203,6 +588,277 @@ pub fn render(ctx: &mut RenderContext, placement: &WidgetPlacement, state: &Stat
             y: text_position.y,
             text_id: state.text_id,
             tint_color: Some(state.color),
+            visible_band,
+            clip: (state.clip_text && overflows).then_some(rect),
+            stroke: state.stroke.map(|stroke| TextStroke {
+                width: stroke.width * scale_factor,
+                color: stroke.color,
+            }),
         },
     );
+
+    if let Some(boundary) = underline {
+        ctx.push_command(
+            placement.zindex,
+            RenderCommand::Rect {
+                boundary: boundary.offset(text_position.x, text_position.y),
+                fill: Some(Fill::Color(state.color)),
+                border_radius: None,
+                border: None,
+                border_fill: None,
+            },
+        );
+    }
+}
+
+/// Line under the glyphs of the underlined range a pixel below the baseline, relative
+/// to the origin of the text. The glyphs of the range wrapped onto the next visual line
+/// are left out.
+fn underline_rect(
+    text: &Text,
+    underline: &TextUnderline,
+    scale_factor: f32,
+    visible_band: Option<&std::ops::Range<f32>>,
+) -> Option<Rect> {
+    let thickness = scale_factor.round().max(1.);
+
+    text.layout_runs()
+        .filter(|run| run.line_i == underline.line && is_run_visible(visible_band, run))
+        .find_map(|run| {
+            let mut glyphs = run.glyphs.iter().filter(|glyph| {
+                glyph.start < underline.range.end && glyph.end > underline.range.start
+            });
+            let first = glyphs.next()?;
+            let (left, right) = glyphs.fold((first.x, first.x + first.w), |(left, right), it| {
+                (left.min(it.x), right.max(it.x + it.w))
+            });
+
+            Some(Rect::new(
+                left,
+                run.line_y + thickness,
+                right - left,
+                thickness,
+            ))
+        })
+}
+
+/// Padded boxes around the glyphs of the visual lines, relative to the origin of the
+/// text. Boxes that overlap meet halfway, so translucent ones aren't darker where they
+/// do, and the ones of the same width are joined.
+fn line_boxes(
+    text: &Text,
+    padding: EdgeInsets,
+    visible_band: Option<&std::ops::Range<f32>>,
+) -> Vec<Rect> {
+    let mut boxes: Vec<Rect> = Vec::new();
+
+    for run in text.layout_runs() {
+        if !is_run_visible(visible_band, &run) {
+            continue;
+        }
+
+        let left = run.glyphs.iter().map(|glyph| glyph.x).reduce(f32::min);
+        let right = run
+            .glyphs
+            .iter()
+            .map(|glyph| glyph.x + glyph.w)
+            .reduce(f32::max);
+
+        let (Some(left), Some(right)) = (left, right) else {
+            continue;
+        };
+
+        let mut line_box = Rect::new(
+            left - padding.left,
+            run.line_top - padding.top,
+            right - left + padding.left + padding.right,
+            run.line_height + padding.top + padding.bottom,
+        );
+
+        if let Some(last) = boxes.last_mut()
+            && last.bottom() >= line_box.y
+        {
+            let same_width =
+                (last.x - line_box.x).abs() < 0.5 && (last.right() - line_box.right()).abs() < 0.5;
+
+            if same_width {
+                *last = last.union(line_box);
+                continue;
+            }
+
+            let middle = (last.bottom() + line_box.y) / 2.;
+            last.height = middle - last.y;
+            line_box.height = line_box.bottom() - middle;
+            line_box.y = middle;
+        }
+
+        boxes.push(line_box);
+    }
+
+    boxes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use crate::{
+        Border, BorderSide, ClewHost, ClewInstance, EdgeInsets,
+        render::RenderStats,
+        testing,
+        widgets::{builder::WidgetBuilder, decorated_box::decoration, vstack::vstack},
+    };
+
+    use super::*;
+
+    /// 500 formatted labels that stay the same and a lazy one made from the counter.
+    fn build_labels<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        counter: i32,
+        made: &Cell<i32>,
+    ) -> RenderStats {
+        instance.frame(host, &mut |ctx| {
+            vstack().build(ctx, |ctx| {
+                for i in 0..500 {
+                    text(&format!("Label {i}")).id(i).build(ctx);
+                }
+
+                text_lazy(counter, || {
+                    made.set(made.get() + 1);
+                    format!("Clicked {counter} times")
+                })
+                .build(ctx);
+            });
+        });
+
+        instance.ui_state().render_state.stats()
+    }
+
+    #[test]
+    fn test_unchanged_texts_are_not_set_again() {
+        let (mut host, mut instance) = testing::instance(400, 400);
+        let made = Cell::new(0);
+
+        let stats = build_labels(&mut instance, &mut host, 0, &made);
+        assert_eq!((stats.texts_updated, stats.texts_skipped), (501, 0));
+
+        // Neither reshaped nor formatted again while the values stay the same.
+        let stats = build_labels(&mut instance, &mut host, 0, &made);
+        assert_eq!((stats.texts_updated, stats.texts_skipped), (0, 501));
+        assert_eq!(made.get(), 1);
+
+        let stats = build_labels(&mut instance, &mut host, 1, &made);
+        assert_eq!((stats.texts_updated, stats.texts_skipped), (1, 500));
+        assert_eq!(made.get(), 2);
+    }
+
+    #[test]
+    fn test_underline_is_drawn_under_the_glyphs_of_the_range() {
+        let (mut host, mut instance) = testing::instance(400, 400);
+
+        let mut underline_of = |range: Range<usize>| {
+            instance.frame(&mut host, &mut |ctx| {
+                text("Save & Exit").underline(range.clone()).build(ctx);
+            });
+
+            let commands = instance.ui_state().render_state.commands();
+            let texts = commands
+                .iter()
+                .filter(|it| matches!(it, RenderCommand::Text { .. }))
+                .count();
+            let underlines = commands
+                .iter()
+                .filter_map(|it| match it {
+                    RenderCommand::Rect { boundary, .. } => Some(*boundary),
+                    _ => None,
+                })
+                .collect::<Vec<_>>();
+
+            // The text stays one run, the underline is drawn over it.
+            assert_eq!(texts, 1);
+            assert_eq!(underlines.len(), 1);
+
+            underlines[0]
+        };
+
+        let all = underline_of(0.."Save & Exit".len());
+        let x = underline_of(8..9);
+
+        assert!(x.width > 0. && x.width < all.width / 4.);
+        assert!(x.left() > all.left() && x.right() < all.right());
+        assert_eq!((x.y, x.height), (all.y, all.height));
+    }
+
+    #[test]
+    fn test_overflowing_text_is_clipped_to_its_content_rect() {
+        let (mut host, mut instance) = testing::instance(400, 400);
+
+        let mut clip_of = |label: &str, clip_text: bool| {
+            instance.frame(&mut host, &mut |ctx| {
+                text(label)
+                    .width(60.)
+                    .height(40.)
+                    .padding(EdgeInsets::symmetric(12., 8.))
+                    .clip_text(clip_text)
+                    .background(
+                        decoration()
+                            .border(Border::all(BorderSide::new(
+                                2.,
+                                ColorRgba::from_hex(0xFFFFFFFF),
+                            )))
+                            .build(ctx),
+                    )
+                    .build(ctx);
+            });
+
+            instance
+                .ui_state()
+                .render_state
+                .commands()
+                .iter()
+                .find_map(|it| match it {
+                    RenderCommand::Text { clip, .. } => Some(*clip),
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        // The border is drawn around the padding, the glyphs are laid out inside of it.
+        assert_eq!(
+            clip_of("A line much longer than the rect", true),
+            Some(Rect::new(12., 8., 60., 40.))
+        );
+        assert_eq!(clip_of("A line much longer than the rect", false), None);
+        assert_eq!(clip_of("A", true), None);
+    }
+
+    #[test]
+    fn test_line_backgrounds_of_the_same_width_are_joined() {
+        let (mut host, mut instance) = testing::instance(400, 400);
+
+        instance.frame(&mut host, &mut |ctx| {
+            text("Caption\nCaption\nA")
+                .text_background(ColorRgba::from_hex(0x00000080), EdgeInsets::all(4.), 2.)
+                .build(ctx);
+        });
+
+        let boxes = instance
+            .ui_state()
+            .render_state
+            .commands()
+            .iter()
+            .filter_map(|it| match it {
+                RenderCommand::Rect { boundary, .. } => Some(*boundary),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        // The two lines of the same width are one box, the narrower last line has its
+        // own and the padding between them is split halfway.
+        assert_eq!(boxes.len(), 2);
+        assert!(boxes[1].width < boxes[0].width);
+        assert!(boxes[0].height > boxes[1].height);
+        assert_eq!(boxes[0].bottom(), boxes[1].y);
+    }
 }
@@ -2,7 +2,7 @@ use clew_derive::WidgetBuilder;
 
 use crate::{
     Axis, Clip, WidgetRef, WidgetType,
-    layout::{ContainerKind, LayoutCommand},
+    layout::{ContainerKind, LayoutCommand, snap_to_pixels},
     scroll_area::ScrollAreaWidget,
     widgets::{scope::scope, scroll_area},
 };
@@ -10,18 +10,38 @@ use crate::{
 use super::{
     FrameBuilder,
     builder::{BuildContext, WidgetBuilder},
-    scroll_area::ScrollAreaResponse,
+    scroll_area::{ScrollAreaResponse, ScrollObserver},
 };
 
+/// Items scrolled into the view within this time are built ahead.
+const PREFETCH_LOOKAHEAD: f64 = 0.25;
+
+type PlaceholderFn<'a> = Box<dyn Fn(&mut BuildContext, u64) + 'a>;
+
+#[derive(Default)]
+pub(crate) struct State {
+    /// Index of the item with the focused widget, it's kept built while scrolled out.
+    pinned_item: Option<u64>,
+    last_scroll_offset: Option<f64>,
+    /// Smoothed scroll velocity in pixels per second, positive toward the end.
+    velocity: f64,
+    /// Items built in the last frame, they aren't put off again.
+    built: Vec<u64>,
+    /// Items drawn as placeholders in the last frame, they're built in this one.
+    deferred: Vec<u64>,
+}
+
 #[derive(WidgetBuilder)]
-pub struct VirtualListBuilder {
+pub struct VirtualListBuilder<'a> {
     frame: FrameBuilder,
     item_size: f32,
     items_count: u64,
     axis: Axis,
+    max_prefetch: u64,
+    placeholder: Option<PlaceholderFn<'a>>,
 }
 
-impl VirtualListBuilder {
+impl<'a> VirtualListBuilder<'a> {
     pub fn item_size(mut self, size: f32) -> Self {
         self.item_size = size;
 
@@ -40,39 +60,78 @@ impl VirtualListBuilder {
         self
     }
 
+    /// Most items built ahead of the view in the direction it's scrolled in, there are
+    /// more of them the faster it's scrolled and none once it stops.
+    pub fn max_prefetch(mut self, items: u64) -> Self {
+        self.max_prefetch = items;
+
+        self
+    }
+
+    /// Cheap stand-in for the items that come into view after the deadline of the frame,
+    /// see [`BuildContext::deadline`]. They're built for real in the next frame.
+    pub fn placeholder(mut self, placeholder: impl Fn(&mut BuildContext, u64) + 'a) -> Self {
+        self.placeholder = Some(Box::new(placeholder));
+
+        self
+    }
+
+    /// Position of the list as it was at the end of the previous frame, see
+    /// [`super::scroll_area::ScrollAreaBuilder::observer`].
+    pub fn observer(&self, context: &BuildContext) -> ScrollObserver {
+        scroll_area::observer(context, self.frame.id.resolve(context.id_seed))
+    }
+
     #[profiling::function]
     pub fn build<F>(mut self, context: &mut BuildContext, item_build: F) -> ScrollAreaResponse
     where
         F: Fn(&mut BuildContext, u64),
     {
-        let id = self.frame.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.frame.id);
         let widget_ref = WidgetRef::new(WidgetType::of::<ScrollAreaWidget>(), id);
 
         let (mut backgrounds, foregrounds) = context.resolve_decorators(&mut self.frame);
         backgrounds.push(widget_ref);
-
-        let (offset_x, offset_y, response) = {
-            let state =
-                context
-                    .widgets_states
-                    .scroll_area
-                    .get_or_insert(id, || scroll_area::State {
-                        last_offset_x: 0.,
-                        last_offset_y: 0.,
-                        offset_x: 0.,
-                        offset_y: 0.,
-                        overflow_x: false,
-                        overflow_y: false,
-                        scroll_direction: self.axis.to_scroll_direction(),
-                        fraction_x: 0.,
-                        fraction_y: 0.,
-                        progress_x: 0.,
-                        progress_y: 0.,
-                        width: 0.,
-                        height: 0.,
-                        content_width: 0.,
-                        content_height: 0.,
-                    });
+        let animation_delta_time = context.animation_delta_time();
+
+        let (observed_behind, offset_x, offset_y, response) = {
+            let state = context
+                .widgets_states
+                .scroll_area
+                .access(id, || scroll_area::State {
+                    last_offset_x: 0.,
+                    last_offset_y: 0.,
+                    offset_x: 0.,
+                    offset_y: 0.,
+                    overflow_x: false,
+                    overflow_y: false,
+                    scroll_direction: self.axis.to_scroll_direction(),
+                    fraction_x: 0.,
+                    fraction_y: 0.,
+                    progress_x: 0.,
+                    progress_y: 0.,
+                    x: 0.,
+                    y: 0.,
+                    width: 0.,
+                    height: 0.,
+                    content_width: 0.,
+                    content_height: 0.,
+                    target_offset_x: None,
+                    target_offset_y: None,
+                    preserve_anchor: false,
+                    anchor: None,
+                    gutter: None,
+                    gutter_x: false,
+                    gutter_y: false,
+                    observed: scroll_area::ScrollObserver::new(id),
+                    overscroll_x: 0.,
+                    overscroll_y: 0.,
+                    drag_to_scroll: false,
+                    drag_pointer: None,
+                    drag_pressed: false,
+                });
+
+            scroll_area::animate_to_target(state, animation_delta_time);
 
             let layout_measures = context.widgets_states.layout_measures.get_mut(id);
             let wrap_size = self.item_size as f64 * (self.items_count as f64);
@@ -83,6 +142,7 @@ impl VirtualListBuilder {
                     state,
                     context.input,
                     context.interaction,
+                    context.view.scale_factor,
                     layout_measures,
                     match self.axis {
                         Axis::Horizontal => wrap_size,
@@ -98,6 +158,7 @@ impl VirtualListBuilder {
             state.scroll_direction = self.axis.to_scroll_direction();
 
             (
+                scroll_area::is_observed_behind(state),
                 state.offset_x,
                 state.offset_y,
                 ScrollAreaResponse {
@@ -110,6 +171,8 @@ impl VirtualListBuilder {
                     fraction_y: state.fraction_y,
                     progress_x: state.progress_x,
                     progress_y: state.progress_y,
+                    x: state.x,
+                    y: state.y,
                     width: state.width,
                     height: state.height,
                     content_width: state.content_width,
@@ -118,6 +181,11 @@ impl VirtualListBuilder {
             )
         };
 
+        // The observers catch up with the offset in the next frame.
+        if observed_behind {
+            context.request_frame();
+        }
+
         context.push_layout_command(LayoutCommand::BeginContainer {
             backgrounds,
             foregrounds,
@@ -130,92 +198,387 @@ impl VirtualListBuilder {
             clip: self.frame.clip,
         });
 
-        match self.axis {
-            Axis::Horizontal => {
-                let viewport_width = if response.width == 0. {
-                    context.view.size.width as f32
-                } else {
-                    response.width as f32
-                };
-
-                let scroll_offset = -offset_x;
-
-                let first_visible = (scroll_offset / self.item_size as f64).floor() as u64;
-                let visible_count = (viewport_width / self.item_size).ceil() as u64 + 1;
-                let last_visible = (first_visible + visible_count).min(self.items_count);
-                let item_size = self.item_size as f64;
-
-                for i in first_visible..last_visible {
-                    // Position relative to viewport top
-                    let relative_x = ((i - first_visible) as f64) * item_size;
-
-                    // Adjust for partial scroll (how much of first item is scrolled off)
-                    let first_item_offset = scroll_offset % item_size;
-                    let final_x = relative_x - first_item_offset;
-
-                    context.push_layout_command(LayoutCommand::BeginOffset {
-                        offset_x: final_x as f32,
-                        offset_y: 0.,
-                    });
-                    scope(i).build(context, |ctx| item_build(ctx, i));
-                    context.push_layout_command(LayoutCommand::EndOffset);
-                }
+        let (viewport_length, scroll_offset) = match self.axis {
+            Axis::Horizontal if response.width == 0. => (context.view.size.width as f32, -offset_x),
+            Axis::Horizontal => (response.width as f32, -offset_x),
+            Axis::Vertical if response.height == 0. => (context.view.size.height as f32, -offset_y),
+            Axis::Vertical => (response.height as f32, -offset_y),
+        };
+
+        let item_size = self.item_size as f64;
+        let first_visible = (scroll_offset / item_size).floor() as u64;
+        let visible_count = (viewport_length / self.item_size).ceil() as u64 + 1;
+        let last_visible = (first_visible + visible_count).min(self.items_count);
+
+        // Adjust for partial scroll (how much of first item is scrolled off)
+        let first_item_offset = scroll_offset % item_size;
+
+        let delta_time = context.delta_time as f64;
+        let state = context
+            .widgets_states
+            .virtual_list
+            .access(id, State::default);
+        let pinned_item = state.pinned_item;
+        let built_before = std::mem::take(&mut state.built);
+        let deferred = std::mem::take(&mut state.deferred);
+
+        let measured = match state.last_scroll_offset {
+            Some(last) if delta_time > 0. => (scroll_offset - last) / delta_time,
+            _ => 0.,
+        };
+
+        // Averaged with the last frames, so a single uneven frame doesn't throw the
+        // window around, and settled once it's under an item a second.
+        state.velocity = (state.velocity + measured) / 2.;
+
+        if state.velocity.abs() < item_size {
+            state.velocity = 0.;
+        }
+
+        state.last_scroll_offset = Some(scroll_offset);
+
+        let prefetch = ((state.velocity.abs() * PREFETCH_LOOKAHEAD / item_size).ceil() as u64)
+            .min(self.max_prefetch);
+        let (window_start, window_end) = if state.velocity > 0. {
+            (
+                first_visible,
+                (last_visible + prefetch).min(self.items_count),
+            )
+        } else {
+            (first_visible.saturating_sub(prefetch), last_visible)
+        };
+
+        let mut focused_item = None;
+        let mut built_items = Vec::new();
+        let mut placeholders = Vec::new();
+
+        // The visible items go first so they get the time of the frame, then the ones
+        // ahead from the nearest on. The window only grows on one side.
+        let ahead = (last_visible..window_end).chain((window_start..first_visible).rev());
+
+        for i in (first_visible..last_visible).chain(ahead) {
+            let visible = (first_visible..last_visible).contains(&i);
+            let past_deadline = context.is_past_deadline();
+
+            // The items ahead are only built while there's time left.
+            if !visible && past_deadline && pinned_item != Some(i) {
+                continue;
             }
-            Axis::Vertical => {
-                let viewport_height = if response.height == 0. {
-                    context.view.size.height as f32
-                } else {
-                    response.height as f32
-                };
-
-                let scroll_offset = -offset_y;
-
-                let first_visible = (scroll_offset / self.item_size as f64).floor() as u64;
-                let visible_count = (viewport_height / self.item_size).ceil() as u64 + 1;
-                let last_visible = (first_visible + visible_count).min(self.items_count);
-                let item_size = self.item_size as f64;
-
-                for i in first_visible..last_visible {
-                    // Position relative to viewport top
-                    let relative_y = ((i - first_visible) as f64) * item_size;
-
-                    // Adjust for partial scroll (how much of first item is scrolled off)
-                    let first_item_offset = scroll_offset % item_size;
-                    let final_y = relative_y - first_item_offset;
-
-                    context.push_layout_command(LayoutCommand::BeginOffset {
-                        offset_x: 0.,
-                        offset_y: final_y as f32,
-                    });
-                    scope(i).build(context, |ctx| item_build(ctx, i));
-                    context.push_layout_command(LayoutCommand::EndOffset);
+
+            // Position relative to viewport start
+            let offset = (i as f64 - first_visible as f64) * item_size - first_item_offset;
+
+            // Only the items coming into view are put off, and only for a frame.
+            let placeholder = self.placeholder.as_deref().filter(|_| {
+                visible
+                    && past_deadline
+                    && pinned_item != Some(i)
+                    && !built_before.contains(&i)
+                    && !deferred.contains(&i)
+            });
+
+            if let Some(placeholder) = placeholder {
+                build_item(context, self.axis, i, offset, placeholder);
+                placeholders.push(i);
+            } else {
+                if build_item(context, self.axis, i, offset, &item_build) {
+                    focused_item = Some(i);
                 }
+
+                built_items.push(i);
             }
         }
 
+        if !placeholders.is_empty() {
+            context.widgets_states.placeholder_items += placeholders.len() as u32;
+            context.request_frame();
+        }
+
+        // The item with the focused widget is still built once it's scrolled out, at its
+        // position outside of the clip, so the widget keeps the focus and its state,
+        // e.g. the IME composition.
+        if let Some(pinned_item) = pinned_item
+            && focused_item.is_none()
+            && pinned_item < self.items_count
+            && !(window_start..window_end).contains(&pinned_item)
+        {
+            let offset = pinned_item as f64 * item_size - scroll_offset;
+
+            if build_item(context, self.axis, pinned_item, offset, &item_build) {
+                focused_item = Some(pinned_item);
+            }
+        }
+
+        if let Some(state) = context.widgets_states.virtual_list.get_mut(id) {
+            state.pinned_item = focused_item;
+            state.built = built_items;
+            state.deferred = placeholders;
+        }
+
         context.push_layout_command(LayoutCommand::EndContainer);
 
-        context
-            .widgets_states
-            .scroll_area
-            .accessed_this_frame
-            .insert(id);
-        context
-            .widgets_states
-            .layout_measures
-            .accessed_this_frame
-            .insert(id);
+        context.widgets_states.layout_measures.mark_accessed(id);
 
         response
     }
 }
 
+/// Builds the item at the offset from the start of the viewport, returns whether the
+/// focused widget is inside of it.
+fn build_item<F>(
+    context: &mut BuildContext,
+    axis: Axis,
+    index: u64,
+    offset: f64,
+    item_build: &F,
+) -> bool
+where
+    F: Fn(&mut BuildContext, u64) + ?Sized,
+{
+    let focused = context.interaction.focused;
+    let is_built = |context: &BuildContext| {
+        focused.is_some_and(|focused| context.widgets_states.is_built(focused))
+    };
+    let built_before = is_built(context);
+
+    // Picked from the unrounded offset, only moved by whole pixels like the content of
+    // a scroll area.
+    let offset = snap_to_pixels(offset as f32, context.view.scale_factor);
+    let (offset_x, offset_y) = match axis {
+        Axis::Horizontal => (offset, 0.),
+        Axis::Vertical => (0., offset),
+    };
+
+    context.push_layout_command(LayoutCommand::BeginOffset { offset_x, offset_y });
+    scope(index).build(context, |ctx| item_build(ctx, index));
+    context.push_layout_command(LayoutCommand::EndOffset);
+
+    !built_before && is_built(context)
+}
+
 #[track_caller]
-pub fn virtual_list() -> VirtualListBuilder {
+pub fn virtual_list<'a>() -> VirtualListBuilder<'a> {
     VirtualListBuilder {
         frame: FrameBuilder::new().clip(Clip::Rect),
         axis: Axis::Vertical,
         item_size: 32.,
         items_count: 0,
+        max_prefetch: 8,
+        placeholder: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::RefCell, time::Duration};
+
+    use crate::{
+        ClewHost, ClewInstance, WidgetId,
+        editable_text::OsEvent,
+        testing,
+        text_data::TextData,
+        widgets::{editable_text, scroll_area, text},
+    };
+
+    use super::*;
+
+    /// A list of a hundred items, the first is a text field.
+    fn build_list<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        field: &RefCell<TextData>,
+        scroll_to: Option<(WidgetId, f64)>,
+    ) -> WidgetId {
+        let mut list_id = None;
+
+        instance.frame(host, &mut |ctx| {
+            if let Some((id, offset)) = scroll_to {
+                scroll_area::set_scroll_offset_y(ctx, id, offset);
+            }
+
+            let response = virtual_list()
+                .item_size(20.)
+                .items_count(100)
+                .build(ctx, |ctx, i| {
+                    if i == 0 {
+                        editable_text(&mut field.borrow_mut()).build(ctx);
+                    } else {
+                        text(&format!("Item {i}")).build(ctx);
+                    }
+                });
+            list_id = Some(response.id);
+        });
+
+        list_id.unwrap()
+    }
+
+    #[test]
+    fn test_focused_field_keeps_composition_while_scrolled_out() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        let field = RefCell::new(TextData::from("Hello"));
+
+        let list_id = build_list(&mut instance, &mut host, &field, None);
+
+        let (field_id, _) = instance
+            .ui_state()
+            .widgets_states
+            .editable_text
+            .iter()
+            .next()
+            .unwrap();
+        let ui_state = instance.ui_state();
+        ui_state.interaction_state.focus_with_pointer(&field_id);
+        ui_state.user_input.ime_preedit = "に".to_string();
+
+        build_list(&mut instance, &mut host, &field, None);
+        build_list(&mut instance, &mut host, &field, Some((list_id, 1000.)));
+        build_list(&mut instance, &mut host, &field, Some((list_id, 0.)));
+
+        let ui_state = instance.ui_state();
+        assert!(ui_state.interaction_state.is_focused(&field_id));
+        assert_eq!(ui_state.user_input.ime_preedit, "に");
+        assert!(ui_state.widgets_states.editable_text.contains(field_id));
+        assert!(instance.take_os_events().is_empty());
+
+        // Without the list the field is gone, the composition is committed first.
+        instance.frame(&mut host, &mut |_| {});
+
+        let ui_state = instance.ui_state();
+        assert!(!ui_state.interaction_state.is_focused(&field_id));
+        assert!(ui_state.user_input.ime_preedit.is_empty());
+        assert!(!ui_state.widgets_states.editable_text.contains(field_id));
+        assert!(matches!(
+            instance.take_os_events().as_slice(),
+            [OsEvent::CommitIme, OsEvent::DeactivateIme]
+        ));
+    }
+
+    /// Builds a list of 20 pixels high items that fills the view of 100 pixels, returns
+    /// its id, the items built and the items drawn as placeholders.
+    fn build_recorded_list<'a>(
+        instance: &mut ClewInstance<'a>,
+        host: &mut ClewHost<'a>,
+        scroll_to: Option<(WidgetId, f64)>,
+    ) -> (WidgetId, Vec<u64>, Vec<u64>) {
+        let built = RefCell::new(Vec::new());
+        let placeholders = RefCell::new(Vec::new());
+        let mut list_id = None;
+
+        instance.frame(host, &mut |ctx| {
+            if let Some((id, offset)) = scroll_to {
+                scroll_area::set_scroll_offset_y(ctx, id, offset);
+            }
+
+            let response = virtual_list()
+                .fill_max_size()
+                .item_size(20.)
+                .items_count(50_000)
+                .max_prefetch(4)
+                .placeholder(|_, i| placeholders.borrow_mut().push(i))
+                .build(ctx, |ctx, i| {
+                    built.borrow_mut().push(i);
+                    text(&format!("Item {i}")).build(ctx);
+                });
+            list_id = Some(response.id);
+        });
+
+        (
+            list_id.unwrap(),
+            built.into_inner(),
+            placeholders.into_inner(),
+        )
+    }
+
+    #[test]
+    fn test_items_ahead_are_prefetched_while_scrolling() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        let (list_id, built, _) = build_recorded_list(&mut instance, &mut host, None);
+        assert_eq!(built, (0..6).collect::<Vec<_>>());
+
+        // Toward the end, the window grows past the view by at most 4 items.
+        let mut offset = 0.;
+
+        for _ in 0..3 {
+            offset += 200.;
+            build_recorded_list(&mut instance, &mut host, Some((list_id, offset)));
+        }
+
+        let (_, built, _) = build_recorded_list(&mut instance, &mut host, Some((list_id, 800.)));
+        assert_eq!(built, (40..50).collect::<Vec<_>>());
+
+        // And back toward the start, the visible items are built before the ones ahead.
+        let (_, built, _) = build_recorded_list(&mut instance, &mut host, Some((list_id, 400.)));
+        assert_eq!(built, [20, 21, 22, 23, 24, 25, 19, 18, 17, 16]);
+
+        // Once the scrolling stops, only the visible items are built again.
+        let mut built = Vec::new();
+
+        for _ in 0..100 {
+            (_, built, _) = build_recorded_list(&mut instance, &mut host, None);
+
+            if built.len() == 6 {
+                break;
+            }
+        }
+
+        assert_eq!(built, (20..26).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_observer_follows_the_offset_of_the_previous_frame() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        let mut observed = Vec::new();
+
+        // The list is measured in the first frame.
+        for scroll_to in [None, Some(950.), None, None] {
+            instance.frame(&mut host, &mut |ctx| {
+                let list = virtual_list()
+                    .fill_max_size()
+                    .item_size(20.)
+                    .items_count(100);
+                let observer = list.observer(ctx);
+                let response = list.build(ctx, |ctx, i| text(&format!("Item {i}")).build(ctx));
+
+                if let Some(offset) = scroll_to {
+                    scroll_area::set_scroll_offset_y(ctx, response.id, offset);
+                }
+
+                observed.push(observer);
+            });
+        }
+
+        assert_eq!(observed[1].offset_y(), 0.);
+        assert_eq!(observed[2].offset_y(), 950.);
+        assert_eq!(observed[2].progress_y(), 0.5);
+        assert!(observed[2].velocity_y() > 0.);
+        assert_eq!(observed[3].velocity_y(), 0.);
+    }
+
+    #[test]
+    fn test_items_coming_into_view_past_deadline_are_put_off_for_a_frame() {
+        let (mut host, mut instance) = testing::instance(100, 100);
+        instance.set_frame_budget(Some(Duration::ZERO));
+
+        let (list_id, built, placeholders) = build_recorded_list(&mut instance, &mut host, None);
+        assert!(built.is_empty());
+        assert_eq!(placeholders, (0..6).collect::<Vec<_>>());
+        assert_eq!(
+            instance.ui_state().render_state.stats().placeholder_items,
+            6
+        );
+
+        let (_, built, placeholders) = build_recorded_list(&mut instance, &mut host, None);
+        assert_eq!(built, (0..6).collect::<Vec<_>>());
+        assert!(placeholders.is_empty());
+        assert_eq!(
+            instance.ui_state().render_state.stats().placeholder_items,
+            0
+        );
+
+        // Only the item scrolled into view is put off, nothing is prefetched.
+        let (_, built, placeholders) =
+            build_recorded_list(&mut instance, &mut host, Some((list_id, 20.)));
+        assert_eq!(built, (1..6).collect::<Vec<_>>());
+        assert_eq!(placeholders, [6]);
     }
 }
@@ -36,7 +36,7 @@ impl<T: WidgetState + Widget + Default> WidgetBuilder<T> {
     }
 
     pub fn build(&mut self, context: &mut BuildContext) {
-        let id = self.frame.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.frame.id);
         let (idx, mut state) = context.widgets_states.take_or_create(id, T::default);
 
         // Skip event processing for () type
@@ -48,7 +48,7 @@ impl<T: WidgetState + Widget + Default> WidgetBuilder<T> {
             }
         }
 
-        context.widgets_states.custom.accessed_this_frame.insert(id);
+        context.widgets_states.custom.mark_accessed(id);
         self.frame.build(context, |ctx| state.build(ctx));
 
         context.widgets_states.restore(idx, state);
@@ -57,7 +57,7 @@ impl<T: WidgetState + Widget + Default> WidgetBuilder<T> {
 
 impl<'a, T: WidgetState + Widget + Default> WidgetWithStateBuilder<'a, T> {
     pub fn build(&mut self, context: &mut BuildContext) {
-        let id = self.frame.id.with_seed(context.id_seed);
+        let id = context.widget_id(self.frame.id);
 
         // Skip event processing for () type
         if TypeId::of::<T::Event>() != TypeId::of::<()>() {
@@ -68,7 +68,7 @@ impl<'a, T: WidgetState + Widget + Default> WidgetWithStateBuilder<'a, T> {
             }
         }
 
-        context.widgets_states.custom.accessed_this_frame.insert(id);
+        context.widgets_states.custom.mark_accessed(id);
         self.frame.build(context, |ctx| self.state.build(ctx));
     }
 }
@@ -0,0 +1,300 @@
+//! Star rating control, e.g. for a review form, written against [`clew::authoring`]
+//! only, the way a widget crate outside of clew would be.
+//!
+//! The star is an SVG, [`add_rating_stars_assets`] loads it.
+//!
+//! ```ignore
+//! if rating_stars(&mut rating).count(5).build(ctx).changed() {
+//!     save_rating(rating);
+//! }
+//! ```
+
+use std::any::Any;
+
+use clew::assets::Assets;
+use clew::authoring::{
+    BuildContext, ContainerKind, DeriveWrapSize, FrameBuilder, GestureDetectorResponse,
+    PixelExtension, RenderCommand, RenderContext, SmallVec, WidgetBuilder, WidgetId,
+    WidgetPlacement, WidgetRef, WidgetState, WidgetType, gesture_detector,
+};
+use clew::{ColorRgba, CrossAxisAlignment, MainAxisAlignment, TintMode};
+use clew_derive::WidgetBuilder;
+
+/// Asset id of the star, see [`add_rating_stars_assets`].
+pub const STAR_SVG: &str = "rating_stars::star";
+
+/// Five-pointed star with the inner corners of a regular pentagram, it's tinted with
+/// the color of the star when drawn.
+const STAR: &[u8] = br##"<svg xmlns="http://www.w3.org/2000/svg" width="24" height="24" viewBox="0 0 24 24">
+<path d="M12 0.6 L14.69 8.89 L23.41 8.89 L16.36 14.02 L19.05 22.31 L12 17.18 L4.95 22.31 L7.64 14.02 L0.59 8.89 L9.31 8.89 Z" fill="#FFFFFF"/>
+</svg>"##;
+
+/// Loads the star drawn by [`rating_stars`], once before the first frame.
+pub fn add_rating_stars_assets(assets: &mut Assets) {
+    assets
+        .load_svg(STAR_SVG, STAR)
+        .expect("The star SVG is valid");
+}
+
+#[derive(WidgetBuilder)]
+pub struct RatingStarsBuilder<'a> {
+    frame: FrameBuilder,
+    value: &'a mut u32,
+    count: u32,
+    star_size: f32,
+    spacing: f32,
+    color: ColorRgba,
+    empty_color: ColorRgba,
+}
+
+pub struct RatingResponse {
+    changed: bool,
+    hover_value: Option<u32>,
+}
+
+impl RatingResponse {
+    /// The value was changed in this frame by a click on a star.
+    pub fn changed(&self) -> bool {
+        self.changed
+    }
+
+    /// Value a click would set while the pointer is over a star, the stars up to it are
+    /// shown filled meanwhile.
+    pub fn hover_value(&self) -> Option<u32> {
+        self.hover_value
+    }
+}
+
+impl<'a> RatingStarsBuilder<'a> {
+    /// Number of stars, the highest value.
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn star_size(mut self, star_size: f32) -> Self {
+        self.star_size = star_size;
+        self
+    }
+
+    pub fn spacing(mut self, spacing: f32) -> Self {
+        self.spacing = spacing;
+        self
+    }
+
+    /// Color of the stars up to the value.
+    pub fn color(mut self, color: ColorRgba) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Color of the stars past the value.
+    pub fn empty_color(mut self, color: ColorRgba) -> Self {
+        self.empty_color = color;
+        self
+    }
+
+    pub fn build(self, ctx: &mut BuildContext) -> RatingResponse {
+        let RatingStarsBuilder {
+            mut frame,
+            value,
+            count,
+            star_size,
+            spacing,
+            color,
+            empty_color,
+        } = self;
+        let mut stars = SmallVec::<[(WidgetId, GestureDetectorResponse); 8]>::new();
+
+        ctx.push_container(
+            &mut frame,
+            ContainerKind::HStack {
+                spacing,
+                main_axis_alignment: MainAxisAlignment::Start,
+                cross_axis_alignment: CrossAxisAlignment::Center,
+                rtl_aware: true,
+            },
+            |ctx| {
+                for index in 0..count {
+                    ctx.scope(index, |ctx| {
+                        let response = gesture_detector().clickable(true).register(ctx);
+                        let star = FrameBuilder::new().size(star_size);
+                        let id = star.widget_id(ctx);
+
+                        ctx.push_leaf(
+                            WidgetRef::new(WidgetType::of::<StarState>(), id),
+                            &star,
+                            DeriveWrapSize::Constraints,
+                        );
+                        stars.push((id, response));
+                    });
+                }
+            },
+        );
+
+        let mut changed = false;
+        let mut hover_value = None;
+
+        for (index, (_, response)) in stars.iter().enumerate() {
+            let star_value = index as u32 + 1;
+
+            if response.is_hot() {
+                hover_value = Some(star_value);
+            }
+
+            // A click on the star of the value clears it.
+            if response.clicked() {
+                *value = if *value == star_value { 0 } else { star_value };
+                changed = true;
+            }
+        }
+
+        let shown = hover_value.unwrap_or(*value);
+
+        for (index, (id, _)) in stars.iter().enumerate() {
+            ctx.state::<StarState>(*id).color = if (index as u32) < shown {
+                color
+            } else {
+                empty_color
+            };
+        }
+
+        RatingResponse {
+            changed,
+            hover_value,
+        }
+    }
+}
+
+#[track_caller]
+pub fn rating_stars(value: &mut u32) -> RatingStarsBuilder<'_> {
+    RatingStarsBuilder {
+        frame: FrameBuilder::new(),
+        value,
+        count: 5,
+        star_size: 20.,
+        spacing: 4.,
+        color: ColorRgba::from_hex(0xFFFFB400),
+        empty_color: ColorRgba::from_hex(0xFFC8C8C8),
+    }
+}
+
+#[derive(Clone, PartialEq, Default)]
+struct StarState {
+    color: ColorRgba,
+}
+
+impl WidgetState for StarState {
+    #[inline]
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+
+    #[inline]
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+
+    #[inline]
+    fn into_any(self: Box<Self>) -> Box<dyn Any> {
+        self
+    }
+
+    fn render(&self, ctx: &mut RenderContext, placement: &WidgetPlacement) {
+        ctx.push_command(
+            placement.zindex,
+            RenderCommand::Svg {
+                boundary: placement.rect.px(ctx),
+                asset_id: STAR_SVG,
+                tint_color: Some(self.color),
+                tint_mode: TintMode::Replace,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use clew::{
+        ClewHost, ClewInstance, EdgeInsets, PhysicalSize, Resources, View, ViewId,
+        io::{InputEvent, MouseButton},
+        text::FontResources,
+        widgets::builder::NoopEventLoopProxy,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_click_on_a_star_sets_the_rating() {
+        let mut assets = Assets::new();
+        add_rating_stars_assets(&mut assets);
+        let mut host = ClewHost::new(assets, Resources::new(), Arc::new(NoopEventLoopProxy));
+        let view = View {
+            id: ViewId(0),
+            size: PhysicalSize::new(200, 100),
+            scale_factor: 1.,
+            safe_area: EdgeInsets::ZERO,
+        };
+        let mut instance = ClewInstance::new(view, FontResources::new());
+        let color = ColorRgba::from_hex(0xFFFF0000);
+        let mut rating = 0;
+        let mut changed = false;
+
+        let events = [
+            None,
+            // The middle of the third star.
+            Some(InputEvent::PointerMoved { x: 58., y: 10. }),
+            Some(InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: true,
+            }),
+            Some(InputEvent::MouseButton {
+                button: MouseButton::Left,
+                pressed: false,
+            }),
+            None,
+        ];
+
+        for event in events {
+            if let Some(event) = event {
+                instance.push_event(event);
+            }
+
+            instance.frame(&mut host, &mut |ctx| {
+                changed |= rating_stars(&mut rating)
+                    .star_size(20.)
+                    .spacing(4.)
+                    .color(color)
+                    .build(ctx)
+                    .changed();
+            });
+        }
+
+        assert!(changed);
+        assert_eq!(rating, 3);
+
+        // The first three stars are filled, the other two are empty.
+        let stars = instance
+            .ui_state()
+            .render_state
+            .commands()
+            .iter()
+            .filter_map(|command| match command {
+                RenderCommand::Svg {
+                    boundary,
+                    asset_id: STAR_SVG,
+                    tint_color: Some(tint),
+                    ..
+                } => Some((boundary.x, *tint)),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(stars.len(), 5);
+        assert!(stars.iter().take(3).all(|(_, tint)| *tint == color));
+        assert!(stars.iter().skip(3).all(|(_, tint)| *tint != color));
+        assert_eq!(stars[2].0, 48.);
+    }
+}